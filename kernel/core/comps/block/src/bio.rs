@@ -22,7 +22,12 @@ use ostd::{
 use spin::Once;
 
 use super::{BlockDevice, id::Sid};
-use crate::{BLOCK_SIZE, SECTOR_SIZE, impl_block_device::general_complete_fn, prelude::*};
+use crate::{
+    BLOCK_SIZE, SECTOR_SIZE,
+    impl_block_device::general_complete_fn,
+    io_priority::{self, IoPriority},
+    prelude::*,
+};
 
 /// The unit for block I/O.
 ///
@@ -92,6 +97,11 @@ impl Bio {
         &self.segments
     }
 
+    /// Returns the number of bytes to transfer.
+    pub fn nbytes(&self) -> usize {
+        self.segments.iter().map(BioSegment::nbytes).sum()
+    }
+
     /// Returns the status.
     pub fn status(&self) -> BioStatus {
         self.metadata.status()
@@ -102,6 +112,10 @@ impl Bio {
     /// This method consumes `self` and transfers its segments and completion
     /// callback to the submitted request.
     ///
+    /// Before the submission, this method calls the handler injected by
+    /// [`inject_bio_submit_handler`], which may sleep to throttle the caller.
+    /// The submitted request carries the I/O priority of the current task.
+    ///
     /// Pushes the completion record into `io_batch`.
     ///
     /// # Panics
@@ -112,6 +126,10 @@ impl Bio {
         block_device: &dyn BlockDevice,
         io_batch: &mut IoBatch,
     ) -> Result<(), BioEnqueueError> {
+        if let Some(handler) = BIO_SUBMIT_HANDLER.get() {
+            handler(block_device, &self);
+        }
+
         let Self {
             metadata,
             complete_fn,
@@ -131,6 +149,7 @@ impl Bio {
         let submitted_bio = SubmittedBio {
             metadata,
             sid_offset: 0,
+            io_priority: io_priority::current_io_priority(),
             complete_fn,
             segments,
         };
@@ -178,6 +197,21 @@ impl Debug for Bio {
     }
 }
 
+/// The handler that is called before a `Bio` is submitted to a block device.
+///
+/// The handler is called with the target block device and the `Bio`. It can
+/// account the I/O and may sleep to throttle the submitting task.
+pub type BioSubmitHandler = fn(&dyn BlockDevice, &Bio);
+
+static BIO_SUBMIT_HANDLER: Once<BioSubmitHandler> = Once::new();
+
+/// Injects a handler that is called before a `Bio` is submitted to a block device.
+///
+/// The function may be called only once; subsequent calls take no effect.
+pub fn inject_bio_submit_handler(handler: BioSubmitHandler) {
+    BIO_SUBMIT_HANDLER.call_once(|| handler);
+}
+
 /// The error type returned when enqueueing the `Bio`.
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub enum BioEnqueueError {
@@ -201,6 +235,7 @@ impl From<BioEnqueueError> for Error {
 pub struct SubmittedBio {
     metadata: Arc<BioMetadata>,
     sid_offset: u64,
+    io_priority: IoPriority,
     complete_fn: Option<BioCompleteFn>,
     segments: Vec<BioSegment>,
 }
@@ -226,6 +261,11 @@ impl SubmittedBio {
         self.sid_offset = offset;
     }
 
+    /// Returns the I/O priority of the task that submitted the `Bio`.
+    pub fn io_priority(&self) -> IoPriority {
+        self.io_priority
+    }
+
     /// Returns the slice to the memory segments.
     pub fn segments(&self) -> &[BioSegment] {
         &self.segments
@@ -275,6 +315,7 @@ impl Debug for SubmittedBio {
         f.debug_struct("SubmittedBio")
             .field("metadata", &self.metadata)
             .field("sid_offset", &self.sid_offset)
+            .field("io_priority", &self.io_priority)
             .field("segments", &self.segments)
             .finish()
     }
@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! I/O priorities of block I/O requests.
+//!
+//! An I/O priority consists of a scheduling class and a priority level within
+//! the class. It is encoded in the same way as the value of the `ioprio_set`
+//! system call in Linux.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/ioprio.h>
+
+use int_to_c_enum::TryFromInt;
+use spin::Once;
+
+/// The scheduling class of an I/O priority.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd, TryFromInt)]
+pub enum IoPriorityClass {
+    /// No class is set. The priority is derived from the CPU nice value.
+    None = 0,
+    /// The real-time class, which is always served first.
+    RealTime = 1,
+    /// The best-effort class, which shares the disk time by levels.
+    BestEffort = 2,
+    /// The idle class, which is served only when there is no other I/O.
+    Idle = 3,
+}
+
+/// The I/O priority of a block I/O request.
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Ord, PartialOrd)]
+pub struct IoPriority {
+    class: IoPriorityClass,
+    level: u8,
+}
+
+impl IoPriority {
+    /// The number of priority levels in the real-time and best-effort classes.
+    pub const NR_LEVELS: u8 = 8;
+
+    /// The default I/O priority, which is the best-effort class with the middle level.
+    pub const DEFAULT: Self = Self {
+        class: IoPriorityClass::BestEffort,
+        level: 4,
+    };
+
+    const CLASS_SHIFT: u32 = 13;
+    const CLASS_MASK: u32 = 0x7;
+    const LEVEL_MASK: u32 = Self::NR_LEVELS as u32 - 1;
+
+    /// Creates an I/O priority.
+    ///
+    /// Returns `None` if the level is out of range, or if the class is
+    /// [`IoPriorityClass::None`] but the level is not zero.
+    pub fn new(class: IoPriorityClass, level: u8) -> Option<Self> {
+        if level >= Self::NR_LEVELS || (class == IoPriorityClass::None && level != 0) {
+            return None;
+        }
+
+        Some(Self { class, level })
+    }
+
+    /// Decodes an I/O priority from the value used by `ioprio_set`.
+    ///
+    /// The priority hints in the value are ignored.
+    pub fn from_raw(raw: u32) -> Option<Self> {
+        let class = u8::try_from((raw >> Self::CLASS_SHIFT) & Self::CLASS_MASK).unwrap();
+        let class = IoPriorityClass::try_from(class).ok()?;
+        Self::new(class, (raw & Self::LEVEL_MASK) as u8)
+    }
+
+    /// Encodes the I/O priority as the value used by `ioprio_get`.
+    pub fn to_raw(self) -> u32 {
+        ((self.class as u32) << Self::CLASS_SHIFT) | u32::from(self.level)
+    }
+
+    /// Returns the class.
+    pub fn class(&self) -> IoPriorityClass {
+        self.class
+    }
+
+    /// Returns the level within the class, where a smaller level means a higher priority.
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+}
+
+impl Default for IoPriority {
+    fn default() -> Self {
+        Self::DEFAULT
+    }
+}
+
+/// The function that returns the I/O priority of the current task.
+pub type IoPriorityGetter = fn() -> IoPriority;
+
+static IO_PRIORITY_GETTER: Once<IoPriorityGetter> = Once::new();
+
+/// Injects the function that returns the I/O priority of the current task.
+///
+/// The I/O priority is recorded when a `Bio` is submitted. Before the function
+/// is injected, all `Bio`s have the default I/O priority.
+///
+/// The function may be called only once; subsequent calls take no effect.
+pub fn inject_io_priority_getter(getter: IoPriorityGetter) {
+    IO_PRIORITY_GETTER.call_once(|| getter);
+}
+
+/// Returns the I/O priority of the current task.
+pub(crate) fn current_io_priority() -> IoPriority {
+    IO_PRIORITY_GETTER
+        .get()
+        .map_or(IoPriority::DEFAULT, |getter| getter())
+}
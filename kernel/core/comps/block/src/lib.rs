@@ -43,9 +43,11 @@ pub mod bio;
 mod device_id;
 pub mod id;
 mod impl_block_device;
+pub mod io_priority;
 mod partition;
 mod prelude;
 pub mod request_queue;
+pub mod scheduler;
 
 use ::device_id::DeviceId;
 use component::{ComponentInitError, init_component};
@@ -56,6 +58,7 @@ pub use partition::{PartitionInfo, PartitionNode};
 use self::{
     bio::{BioEnqueueError, SubmittedBio},
     prelude::*,
+    request_queue::BioRequestSingleQueue,
 };
 
 pub const BLOCK_SIZE: usize = ostd::mm::PAGE_SIZE;
@@ -86,6 +89,14 @@ pub trait BlockDevice: Send + Sync + Any + Debug {
     fn partitions(&self) -> Option<Vec<Arc<dyn BlockDevice>>> {
         None
     }
+
+    /// Returns the request queue of the block device.
+    ///
+    /// Returns `None` if the block device does not use a [`BioRequestSingleQueue`],
+    /// in which case its I/O scheduler cannot be changed.
+    fn request_queue(&self) -> Option<&BioRequestSingleQueue> {
+        None
+    }
 }
 
 /// Metadata for a block device.
@@ -288,4 +288,9 @@ impl PartitionNode {
             info,
         }
     }
+
+    /// Returns the whole-disk device that contains this partition.
+    pub fn disk(&self) -> &Arc<dyn BlockDevice> {
+        &self.device
+    }
 }
@@ -1,23 +1,29 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use alloc::boxed::Box;
+
 use ostd::sync::{Mutex, WaitQueue};
 
 use super::{
     bio::{BioEnqueueError, BioType, SubmittedBio},
     id::Sid,
+    io_priority::IoPriority,
+    scheduler::{IoScheduler, IoSchedulerKind},
 };
 use crate::prelude::*;
 
-/// A simple block I/O request queue backed by one internal FIFO queue.
+/// A simple block I/O request queue backed by a pluggable I/O scheduler.
 ///
-/// It is a FIFO producer-consumer queue, where the producer (e.g., filesystem)
+/// It is a producer-consumer queue, where the producer (e.g., filesystem)
 /// submits requests to the queue, and the consumer (e.g., block device driver)
 /// continuously consumes and processes these requests from the queue.
 ///
-/// It supports merging the new request with the front request if if the type
-/// is same and the sector range is contiguous.
+/// The order in which the requests are consumed, and how a new request is
+/// merged with the queued ones, are decided by the [`IoScheduler`] of the
+/// queue. The default scheduler is [`IoSchedulerKind::None`], which consumes
+/// the requests in FIFO order.
 pub struct BioRequestSingleQueue {
-    queue: Mutex<VecDeque<BioRequest>>,
+    scheduler: Mutex<Box<dyn IoScheduler>>,
     num_requests: AtomicUsize,
     wait_queue: WaitQueue,
     max_nr_segments_per_bio: usize,
@@ -32,7 +38,7 @@ impl BioRequestSingleQueue {
     /// Creates an empty queue with the upper bound for the number of segments in a bio.
     pub fn with_max_nr_segments_per_bio(max_nr_segments_per_bio: usize) -> Self {
         Self {
-            queue: Mutex::new(VecDeque::new()),
+            scheduler: Mutex::new(IoSchedulerKind::None.new_scheduler()),
             num_requests: AtomicUsize::new(0),
             wait_queue: WaitQueue::new(),
             max_nr_segments_per_bio,
@@ -49,10 +55,31 @@ impl BioRequestSingleQueue {
         self.num_requests.load(Ordering::Relaxed)
     }
 
+    /// Returns the kind of the current I/O scheduler.
+    pub fn scheduler(&self) -> IoSchedulerKind {
+        self.scheduler.lock().kind()
+    }
+
+    /// Switches to a new I/O scheduler of the given kind.
+    ///
+    /// The requests in the queue are moved to the new scheduler.
+    pub fn set_scheduler(&self, kind: IoSchedulerKind) {
+        let mut scheduler = self.scheduler.lock();
+        if scheduler.kind() == kind {
+            return;
+        }
+
+        let mut new_scheduler = kind.new_scheduler();
+        for request in scheduler.drain() {
+            new_scheduler.insert(request);
+        }
+        *scheduler = new_scheduler;
+    }
+
     /// Enqueues a `SubmittedBio` to this queue.
     ///
-    /// When enqueueing the `SubmittedBio`, try to insert it into the last request if the
-    /// type is same and the sector range is contiguous.
+    /// When enqueueing the `SubmittedBio`, try to merge it into a queued request
+    /// chosen by the scheduler, if the type is same and the sector range is contiguous.
     /// Otherwise, creates and inserts a new request for the `SubmittedBio`.
     ///
     /// This method will wake up the waiter if a new `BioRequest` is enqueued.
@@ -61,19 +88,14 @@ impl BioRequestSingleQueue {
             return Err(BioEnqueueError::TooBig);
         }
 
-        let mut queue = self.queue.lock();
-        if let Some(request) = queue.front_mut()
-            && request.can_merge(&bio)
-            && request.num_segments() + bio.segments().len() <= self.max_nr_segments_per_bio
-        {
-            request.merge_bio(bio);
+        let mut scheduler = self.scheduler.lock();
+        let Err(bio) = scheduler.try_merge(bio, self.max_nr_segments_per_bio) else {
             return Ok(());
-        }
+        };
 
-        let new_request = BioRequest::from(bio);
-        queue.push_front(new_request);
+        scheduler.insert(BioRequest::from(bio));
         self.inc_num_requests();
-        drop(queue);
+        drop(scheduler);
 
         self.wait_queue.wake_all();
         Ok(())
@@ -87,8 +109,8 @@ impl BioRequestSingleQueue {
 
         loop {
             if num_requests > 0 {
-                let mut queue = self.queue.lock();
-                if let Some(request) = queue.pop_back() {
+                let mut scheduler = self.scheduler.lock();
+                if let Some(request) = scheduler.dispatch() {
                     self.dec_num_requests();
                     return request;
                 }
@@ -124,7 +146,7 @@ impl Debug for BioRequestSingleQueue {
     fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
         f.debug_struct("BioRequestSingleQueue")
             .field("num_requests", &self.num_requests())
-            .field("queue", &self.queue.lock())
+            .field("scheduler", &self.scheduler.lock())
             .finish()
     }
 }
@@ -150,6 +172,8 @@ pub struct BioRequest {
     sid_range: Range<Sid>,
     /// The number of segments
     num_segments: usize,
+    /// The I/O priority of the first submitted bio
+    io_priority: IoPriority,
     /// The submitted bios
     bios: VecDeque<SubmittedBio>,
 }
@@ -165,6 +189,13 @@ impl BioRequest {
         &self.sid_range
     }
 
+    /// Returns the I/O priority of the request.
+    ///
+    /// The I/O priority of a merged request is that of its first `SubmittedBio`.
+    pub fn io_priority(&self) -> IoPriority {
+        self.io_priority
+    }
+
     /// Returns an iterator to the `SubmittedBio`s.
     pub fn bios(&self) -> impl Iterator<Item = &SubmittedBio> {
         self.bios.iter()
@@ -234,6 +265,7 @@ impl From<SubmittedBio> for BioRequest {
             type_: bio.type_(),
             sid_range,
             num_segments: bio.segments().len(),
+            io_priority: bio.io_priority(),
             bios: {
                 let mut bios = VecDeque::with_capacity(1);
                 bios.push_front(bio);
@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{IoScheduler, IoSchedulerKind};
+use crate::{
+    bio::SubmittedBio,
+    io_priority::{IoPriority, IoPriorityClass},
+    prelude::*,
+    request_queue::BioRequest,
+};
+
+/// The number of priority levels in each class.
+const NR_LEVELS: usize = IoPriority::NR_LEVELS as usize;
+
+/// The factor to convert a best-effort level to a weight.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/block/bfq-iosched.h> (`BFQ_WEIGHT_CONVERSION_COEFF`)
+const WEIGHT_CONVERSION_COEFF: u64 = 10;
+/// The weight of the highest best-effort level.
+const MAX_WEIGHT: u64 = NR_LEVELS as u64 * WEIGHT_CONVERSION_COEFF;
+
+/// The `bfq` I/O scheduler.
+///
+/// It shares the disk among the I/O priorities set by `ioprio_set`. Each
+/// priority has its own FIFO queue, and the queues are served as follows:
+///
+/// - The real-time queues are always served first, from the highest level to
+///   the lowest level.
+/// - The best-effort queues share the disk in proportion to their weights,
+///   where the weight of level `l` is `(8 - l) * 10`. The service is measured
+///   in sectors, and the queue with the least weighted service (i.e., virtual
+///   time) is served next.
+/// - The idle queues are served only if there are no other requests.
+///
+/// Unlike BFQ in Linux, this scheduler never idles the disk to wait for the
+/// next request of the queue in service.
+///
+/// Reference: <https://docs.kernel.org/block/bfq-iosched.html>
+#[derive(Debug)]
+pub struct BfqScheduler {
+    real_time: [VecDeque<BioRequest>; NR_LEVELS],
+    best_effort: [BestEffortQueue; NR_LEVELS],
+    idle: [VecDeque<BioRequest>; NR_LEVELS],
+    /// The virtual time of the best-effort queue served last.
+    vtime: u64,
+}
+
+/// A best-effort queue with its virtual time.
+#[derive(Debug, Default)]
+struct BestEffortQueue {
+    requests: VecDeque<BioRequest>,
+    /// The weighted number of sectors that have been dispatched.
+    vtime: u64,
+}
+
+impl BfqScheduler {
+    /// Creates an empty scheduler.
+    pub fn new() -> Self {
+        Self {
+            real_time: Default::default(),
+            best_effort: Default::default(),
+            idle: Default::default(),
+            vtime: 0,
+        }
+    }
+
+    /// Returns the FIFO queue of the I/O priority.
+    fn queue_mut(&mut self, io_priority: IoPriority) -> &mut VecDeque<BioRequest> {
+        let level = usize::from(io_priority.level());
+        match io_priority.class() {
+            IoPriorityClass::RealTime => &mut self.real_time[level],
+            IoPriorityClass::Idle => &mut self.idle[level],
+            IoPriorityClass::BestEffort | IoPriorityClass::None => {
+                &mut self.best_effort[best_effort_level(io_priority)].requests
+            }
+        }
+    }
+
+    /// Dispatches a request from the best-effort queue with the least virtual time.
+    fn dispatch_best_effort(&mut self) -> Option<BioRequest> {
+        let (level, queue) = self
+            .best_effort
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, queue)| !queue.requests.is_empty())
+            .min_by_key(|(_, queue)| queue.vtime)?;
+
+        let request = queue.requests.pop_front().unwrap();
+        let weight = (NR_LEVELS - level) as u64 * WEIGHT_CONVERSION_COEFF;
+        // Charge a flush as one sector, so that it is also accounted.
+        let cost = (request.num_sectors() as u64).max(1);
+        self.vtime = queue.vtime;
+        queue.vtime += cost * MAX_WEIGHT / weight;
+
+        Some(request)
+    }
+}
+
+impl Default for BfqScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IoScheduler for BfqScheduler {
+    fn kind(&self) -> IoSchedulerKind {
+        IoSchedulerKind::Bfq
+    }
+
+    fn try_merge(&mut self, bio: SubmittedBio, max_nr_segments: usize) -> Result<(), SubmittedBio> {
+        let queue = self.queue_mut(bio.io_priority());
+        if let Some(request) = queue.back_mut()
+            && request.can_merge(&bio)
+            && request.num_segments() + bio.segments().len() <= max_nr_segments
+        {
+            request.merge_bio(bio);
+            return Ok(());
+        }
+
+        Err(bio)
+    }
+
+    fn insert(&mut self, request: BioRequest) {
+        let io_priority = request.io_priority();
+        if matches!(
+            io_priority.class(),
+            IoPriorityClass::BestEffort | IoPriorityClass::None
+        ) {
+            // A queue that becomes busy starts from the current virtual time,
+            // so that it cannot claim the service that it missed while idle.
+            let vtime = self.vtime;
+            let queue = &mut self.best_effort[best_effort_level(io_priority)];
+            if queue.requests.is_empty() {
+                queue.vtime = queue.vtime.max(vtime);
+            }
+        }
+
+        self.queue_mut(io_priority).push_back(request);
+    }
+
+    fn dispatch(&mut self) -> Option<BioRequest> {
+        if let Some(queue) = self.real_time.iter_mut().find(|queue| !queue.is_empty()) {
+            return queue.pop_front();
+        }
+
+        if let Some(request) = self.dispatch_best_effort() {
+            return Some(request);
+        }
+
+        self.idle
+            .iter_mut()
+            .find(|queue| !queue.is_empty())
+            .and_then(VecDeque::pop_front)
+    }
+
+    fn drain(&mut self) -> Vec<BioRequest> {
+        let best_effort = self.best_effort.iter_mut().map(|queue| &mut queue.requests);
+        self.real_time
+            .iter_mut()
+            .chain(best_effort)
+            .chain(self.idle.iter_mut())
+            .flat_map(|queue| queue.drain(..))
+            .collect()
+    }
+}
+
+/// Returns the level of the best-effort queue for the I/O priority.
+///
+/// Requests without a class are treated as of the default priority.
+fn best_effort_level(io_priority: IoPriority) -> usize {
+    if io_priority.class() == IoPriorityClass::None {
+        usize::from(IoPriority::DEFAULT.level())
+    } else {
+        usize::from(io_priority.level())
+    }
+}
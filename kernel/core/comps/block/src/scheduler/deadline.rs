@@ -0,0 +1,247 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use ostd::timer::Jiffies;
+
+use super::{IoScheduler, IoSchedulerKind};
+use crate::{
+    bio::{BioType, SubmittedBio},
+    id::Sid,
+    prelude::*,
+    request_queue::BioRequest,
+};
+
+/// The expiry time of read requests.
+const READ_EXPIRE: Duration = Duration::from_millis(500);
+/// The expiry time of write requests.
+const WRITE_EXPIRE: Duration = Duration::from_secs(5);
+/// The maximum number of requests dispatched in sector order in one batch.
+const FIFO_BATCH: usize = 16;
+/// The maximum number of times that reads can be preferred over writes.
+const WRITES_STARVED: usize = 2;
+
+/// The `mq-deadline` I/O scheduler.
+///
+/// Reads and writes are kept in separate queues. Each queue sorts the
+/// requests by their sectors, and also tracks their arrival order in a FIFO
+/// with an expiry time for each request.
+///
+/// The requests are dispatched in batches. A batch starts from the oldest
+/// request in the FIFO if it has expired, and otherwise continues from where
+/// the previous batch of the same direction ends. The rest of the batch
+/// follows the sector order. Reads are preferred over writes when starting a
+/// batch, but writes cannot be starved for more than [`WRITES_STARVED`] batches.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/block/mq-deadline.c>
+#[derive(Debug)]
+pub struct DeadlineScheduler {
+    /// The queues of reads and writes, indexed by [`Direction`].
+    queues: [DirQueue; 2],
+    /// The direction of the current batch.
+    batch_dir: Option<Direction>,
+    /// The number of requests dispatched in the current batch.
+    batch_len: usize,
+    /// The number of batches of reads started while there are pending writes.
+    starved: usize,
+    /// The sequence number of the next inserted request.
+    next_seq: u64,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum Direction {
+    Read = 0,
+    Write = 1,
+}
+
+impl Direction {
+    fn of(type_: BioType) -> Self {
+        match type_ {
+            BioType::Read => Self::Read,
+            // Flushes are queued along with writes.
+            BioType::Write | BioType::Flush => Self::Write,
+        }
+    }
+
+    fn expire(self) -> Duration {
+        match self {
+            Self::Read => READ_EXPIRE,
+            Self::Write => WRITE_EXPIRE,
+        }
+    }
+}
+
+/// The requests of one direction.
+#[derive(Debug, Default)]
+struct DirQueue {
+    /// The requests sorted by the start sector and the sequence number.
+    sorted: BTreeMap<(Sid, u64), BioRequest>,
+    /// The start sector and the deadline of each request, in arrival order.
+    fifo: BTreeMap<u64, (Sid, Duration)>,
+    /// The sector following the last dispatched request.
+    next_sid: Option<Sid>,
+}
+
+impl DirQueue {
+    fn is_empty(&self) -> bool {
+        self.sorted.is_empty()
+    }
+
+    fn insert(&mut self, seq: u64, request: BioRequest, deadline: Duration) {
+        let start = request.sid_range().start;
+        self.fifo.insert(seq, (start, deadline));
+        self.sorted.insert((start, seq), request);
+    }
+
+    fn try_merge(&mut self, bio: SubmittedBio, max_nr_segments: usize) -> Result<(), SubmittedBio> {
+        let can_merge = |request: &BioRequest, bio: &SubmittedBio| {
+            request.can_merge(bio)
+                && request.num_segments() + bio.segments().len() <= max_nr_segments
+        };
+
+        let bio_start = bio.sid_range().start + bio.sid_offset();
+        let bio_end = bio.sid_range().end + bio.sid_offset();
+
+        // Back merge: the request ending at the start of the bio.
+        if let Some((_, request)) = self.sorted.range_mut(..(bio_start, 0)).next_back()
+            && request.sid_range().end == bio_start
+            && can_merge(request, &bio)
+        {
+            request.merge_bio(bio);
+            return Ok(());
+        }
+
+        // Front merge: the request starting at the end of the bio. The
+        // request is re-keyed since its start sector changes.
+        let key = self
+            .sorted
+            .range((bio_end, 0)..=(bio_end, u64::MAX))
+            .find(|(_, request)| can_merge(request, &bio))
+            .map(|(key, _)| *key);
+        if let Some((start, seq)) = key {
+            let mut request = self.sorted.remove(&(start, seq)).unwrap();
+            request.merge_bio(bio);
+            self.sorted.insert((bio_start, seq), request);
+            self.fifo.get_mut(&seq).unwrap().0 = bio_start;
+            return Ok(());
+        }
+
+        Err(bio)
+    }
+
+    /// Returns whether the oldest request has expired.
+    fn is_fifo_expired(&self, now: Duration) -> bool {
+        self.fifo
+            .first_key_value()
+            .is_some_and(|(_, (_, deadline))| *deadline <= now)
+    }
+
+    /// Removes the oldest request.
+    fn pop_fifo(&mut self) -> Option<BioRequest> {
+        let (seq, (start, _)) = self.fifo.pop_first()?;
+        let request = self.sorted.remove(&(start, seq)).unwrap();
+        self.next_sid = Some(request.sid_range().end);
+        Some(request)
+    }
+
+    /// Removes the request following the last dispatched one in sector order.
+    fn pop_sorted(&mut self) -> Option<BioRequest> {
+        let next_sid = self.next_sid?;
+        let (start, seq) = *self.sorted.range((next_sid, 0)..).next()?.0;
+        self.fifo.remove(&seq);
+        let request = self.sorted.remove(&(start, seq)).unwrap();
+        self.next_sid = Some(request.sid_range().end);
+        Some(request)
+    }
+}
+
+impl DeadlineScheduler {
+    /// Creates an empty scheduler.
+    pub fn new() -> Self {
+        Self {
+            queues: [DirQueue::default(), DirQueue::default()],
+            batch_dir: None,
+            batch_len: 0,
+            starved: 0,
+            next_seq: 0,
+        }
+    }
+}
+
+impl Default for DeadlineScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IoScheduler for DeadlineScheduler {
+    fn kind(&self) -> IoSchedulerKind {
+        IoSchedulerKind::MqDeadline
+    }
+
+    fn try_merge(&mut self, bio: SubmittedBio, max_nr_segments: usize) -> Result<(), SubmittedBio> {
+        let dir = Direction::of(bio.type_());
+        self.queues[dir as usize].try_merge(bio, max_nr_segments)
+    }
+
+    fn insert(&mut self, request: BioRequest) {
+        let dir = Direction::of(request.type_());
+        let deadline = Jiffies::elapsed().as_duration() + dir.expire();
+        let seq = self.next_seq;
+        self.next_seq += 1;
+
+        self.queues[dir as usize].insert(seq, request, deadline);
+    }
+
+    fn dispatch(&mut self) -> Option<BioRequest> {
+        // Continue the current batch in sector order.
+        if let Some(dir) = self.batch_dir
+            && self.batch_len < FIFO_BATCH
+            && let Some(request) = self.queues[dir as usize].pop_sorted()
+        {
+            self.batch_len += 1;
+            return Some(request);
+        }
+
+        let has_reads = !self.queues[Direction::Read as usize].is_empty();
+        let has_writes = !self.queues[Direction::Write as usize].is_empty();
+        let dir = if has_reads && (!has_writes || self.starved < WRITES_STARVED) {
+            if has_writes {
+                self.starved += 1;
+            }
+            Direction::Read
+        } else if has_writes {
+            self.starved = 0;
+            Direction::Write
+        } else {
+            self.batch_dir = None;
+            return None;
+        };
+
+        // Start a new batch.
+        let queue = &mut self.queues[dir as usize];
+        let now = Jiffies::elapsed().as_duration();
+        let request = if queue.is_fifo_expired(now) {
+            queue.pop_fifo()
+        } else {
+            queue.pop_sorted().or_else(|| queue.pop_fifo())
+        };
+        self.batch_dir = Some(dir);
+        self.batch_len = 1;
+
+        request
+    }
+
+    fn drain(&mut self) -> Vec<BioRequest> {
+        let mut requests = Vec::new();
+        for queue in self.queues.iter_mut() {
+            while let Some(request) = queue.pop_fifo() {
+                requests.push(request);
+            }
+            queue.next_sid = None;
+        }
+        self.batch_dir = None;
+
+        requests
+    }
+}
@@ -0,0 +1,93 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Block I/O schedulers.
+//!
+//! An I/O scheduler decides the order in which the requests in a
+//! [`BioRequestSingleQueue`] are dispatched to the device driver. The
+//! scheduler of a queue can be switched at runtime with
+//! [`BioRequestSingleQueue::set_scheduler`].
+//!
+//! The following schedulers are provided, named after their Linux counterparts:
+//!
+//! - `none`: dispatches the requests in FIFO order;
+//! - `mq-deadline`: dispatches the requests in sector order in batches, while
+//!   guaranteeing an expiry time for each request;
+//! - `bfq`: shares the disk among the I/O priorities set by `ioprio_set`.
+//!
+//! [`BioRequestSingleQueue`]: crate::request_queue::BioRequestSingleQueue
+//! [`BioRequestSingleQueue::set_scheduler`]: crate::request_queue::BioRequestSingleQueue::set_scheduler
+
+mod bfq;
+mod deadline;
+mod none;
+
+use alloc::boxed::Box;
+
+pub use self::{bfq::BfqScheduler, deadline::DeadlineScheduler, none::NoneScheduler};
+use crate::{bio::SubmittedBio, prelude::*, request_queue::BioRequest};
+
+/// A block I/O scheduler.
+///
+/// The scheduler holds the requests that have been enqueued but not yet
+/// dispatched. It is always accessed with the lock of the request queue held.
+pub trait IoScheduler: Send + Debug {
+    /// Returns the kind of the scheduler.
+    fn kind(&self) -> IoSchedulerKind;
+
+    /// Tries to merge the `SubmittedBio` into a queued request.
+    ///
+    /// The number of segments of the merged request must not exceed
+    /// `max_nr_segments`. If the `SubmittedBio` cannot be merged, it is
+    /// returned back to the caller.
+    fn try_merge(&mut self, bio: SubmittedBio, max_nr_segments: usize) -> Result<(), SubmittedBio>;
+
+    /// Inserts a new request.
+    fn insert(&mut self, request: BioRequest);
+
+    /// Removes and returns the next request to be dispatched to the device.
+    ///
+    /// Returns `None` only if there are no queued requests.
+    fn dispatch(&mut self) -> Option<BioRequest>;
+
+    /// Removes and returns all the queued requests.
+    fn drain(&mut self) -> Vec<BioRequest>;
+}
+
+/// The kinds of the block I/O schedulers.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum IoSchedulerKind {
+    /// The [`NoneScheduler`].
+    None,
+    /// The [`DeadlineScheduler`].
+    MqDeadline,
+    /// The [`BfqScheduler`].
+    Bfq,
+}
+
+impl IoSchedulerKind {
+    /// All kinds of the schedulers.
+    pub const ALL: [Self; 3] = [Self::None, Self::MqDeadline, Self::Bfq];
+
+    /// Returns the name of the scheduler as shown in `/sys/block/<dev>/queue/scheduler`.
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::None => "none",
+            Self::MqDeadline => "mq-deadline",
+            Self::Bfq => "bfq",
+        }
+    }
+
+    /// Looks up the kind of the scheduler by its name.
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Creates an empty scheduler of this kind.
+    pub fn new_scheduler(self) -> Box<dyn IoScheduler> {
+        match self {
+            Self::None => Box::new(NoneScheduler::new()),
+            Self::MqDeadline => Box::new(DeadlineScheduler::new()),
+            Self::Bfq => Box::new(BfqScheduler::new()),
+        }
+    }
+}
@@ -0,0 +1,59 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{IoScheduler, IoSchedulerKind};
+use crate::{bio::SubmittedBio, prelude::*, request_queue::BioRequest};
+
+/// The `none` I/O scheduler.
+///
+/// It dispatches the requests in FIFO order. A new `SubmittedBio` is only
+/// merged into the last request, if the type is same and the sector range is
+/// contiguous.
+#[derive(Debug)]
+pub struct NoneScheduler {
+    queue: VecDeque<BioRequest>,
+}
+
+impl NoneScheduler {
+    /// Creates an empty scheduler.
+    pub fn new() -> Self {
+        Self {
+            queue: VecDeque::new(),
+        }
+    }
+}
+
+impl Default for NoneScheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IoScheduler for NoneScheduler {
+    fn kind(&self) -> IoSchedulerKind {
+        IoSchedulerKind::None
+    }
+
+    fn try_merge(&mut self, bio: SubmittedBio, max_nr_segments: usize) -> Result<(), SubmittedBio> {
+        if let Some(request) = self.queue.back_mut()
+            && request.can_merge(&bio)
+            && request.num_segments() + bio.segments().len() <= max_nr_segments
+        {
+            request.merge_bio(bio);
+            return Ok(());
+        }
+
+        Err(bio)
+    }
+
+    fn insert(&mut self, request: BioRequest) {
+        self.queue.push_back(request);
+    }
+
+    fn dispatch(&mut self) -> Option<BioRequest> {
+        self.queue.pop_front()
+    }
+
+    fn drain(&mut self) -> Vec<BioRequest> {
+        self.queue.drain(..).collect()
+    }
+}
@@ -91,6 +91,10 @@ impl aster_block::BlockDevice for NvmeBlockDevice {
     fn id(&self) -> DeviceId {
         self.id
     }
+
+    fn request_queue(&self) -> Option<&BioRequestSingleQueue> {
+        Some(&self.queue)
+    }
 }
 
 static NR_NVME_DEVICE: AtomicU32 = AtomicU32::new(0);
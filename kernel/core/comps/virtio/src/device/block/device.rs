@@ -149,6 +149,10 @@ impl aster_block::BlockDevice for BlockDevice {
         self.id
     }
 
+    fn request_queue(&self) -> Option<&BioRequestSingleQueue> {
+        Some(&self.queue)
+    }
+
     fn set_partitions(&self, infos: Vec<Option<PartitionInfo>>) {
         let mut partitions = self.partitions.lock();
         if let Some(old_partitions) = partitions.take() {
@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! LoongArch core dump ABI.
+
+use ostd::{
+    arch::cpu::context::{FpuContext, UserContext},
+    user::UserContextApi,
+};
+
+use crate::{prelude::*, process::posix_thread::ThreadLocal};
+
+/// The `e_machine` value of core dumps (`EM_LOONGARCH`).
+pub(crate) const ELF_MACHINE: u16 = 258;
+
+/// Mirror of Linux's `elf_gregset_t` for LoongArch.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/arch/loongarch/include/uapi/asm/ptrace.h>
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod)]
+pub(crate) struct ElfGregset {
+    regs: [usize; 32],
+    orig_a0: usize,
+    csr_era: usize,
+    csr_badv: usize,
+    reserved: [usize; 10],
+}
+
+/// Builds the general-purpose registers to be written to the `NT_PRSTATUS` note.
+pub(crate) fn elf_gregset(user_ctx: &UserContext, thread_local: &ThreadLocal) -> ElfGregset {
+    let gp_regs = user_ctx.general_regs();
+    let regs = [
+        gp_regs.zero,
+        gp_regs.ra,
+        gp_regs.tp,
+        gp_regs.sp,
+        gp_regs.a0,
+        gp_regs.a1,
+        gp_regs.a2,
+        gp_regs.a3,
+        gp_regs.a4,
+        gp_regs.a5,
+        gp_regs.a6,
+        gp_regs.a7,
+        gp_regs.t0,
+        gp_regs.t1,
+        gp_regs.t2,
+        gp_regs.t3,
+        gp_regs.t4,
+        gp_regs.t5,
+        gp_regs.t6,
+        gp_regs.t7,
+        gp_regs.t8,
+        gp_regs.r21,
+        gp_regs.fp,
+        gp_regs.s0,
+        gp_regs.s1,
+        gp_regs.s2,
+        gp_regs.s3,
+        gp_regs.s4,
+        gp_regs.s5,
+        gp_regs.s6,
+        gp_regs.s7,
+        gp_regs.s8,
+    ];
+
+    // FIXME: `csr_badv` should be the faulting address of the last exception.
+    ElfGregset {
+        regs,
+        // The system call return value is in `a0`, so the original value of `a0` is saved when
+        // entering a system call.
+        orig_a0: thread_local.orig_syscall_ret().unwrap_or(0),
+        csr_era: user_ctx.instruction_pointer(),
+        ..Default::default()
+    }
+}
+
+/// Returns the floating-point registers to be written to the `NT_PRFPREG` note.
+//
+// FIXME: Dump the floating-point registers after the FPU context is saved on LoongArch.
+pub(crate) fn elf_fpregset(_fpu_context: &FpuContext) -> Option<&[u8]> {
+    None
+}
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
+pub(crate) mod coredump;
 pub(crate) mod cpu;
 mod power;
 pub(crate) mod signal;
@@ -0,0 +1,35 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! RISC-V core dump ABI.
+
+use ostd::arch::cpu::context::{FpuContext, UserContext};
+
+use super::cpu::SigContext;
+use crate::process::posix_thread::ThreadLocal;
+
+/// The `e_machine` value of core dumps (`EM_RISCV`).
+pub(crate) const ELF_MACHINE: u16 = 243;
+
+/// Mirror of Linux's `elf_gregset_t` for RISC-V.
+///
+/// Like Linux, it has the same layout as `struct user_regs_struct`, which is also the layout of
+/// the general-purpose registers in `struct sigcontext`.
+pub(crate) type ElfGregset = SigContext;
+
+/// Builds the general-purpose registers to be written to the `NT_PRSTATUS` note.
+pub(crate) fn elf_gregset(user_ctx: &UserContext, _thread_local: &ThreadLocal) -> ElfGregset {
+    let mut regs = SigContext::default();
+    regs.copy_user_regs_from(user_ctx);
+    regs
+}
+
+/// Returns the floating-point registers to be written to the `NT_PRFPREG` note.
+///
+/// Like Linux, only the FPU context of the D extension (i.e., `struct __riscv_d_ext_state`) is
+/// dumped.
+pub(crate) fn elf_fpregset(fpu_context: &FpuContext) -> Option<&[u8]> {
+    match fpu_context {
+        FpuContext::D(_) => Some(fpu_context.as_bytes()),
+        _ => None,
+    }
+}
@@ -0,0 +1,35 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! x86-64 core dump ABI.
+
+use ostd::arch::cpu::context::{FpuContext, UserContext};
+
+use super::ptrace::CUserRegsStruct;
+use crate::process::posix_thread::ThreadLocal;
+
+/// The `e_machine` value of core dumps (`EM_X86_64`).
+pub(crate) const ELF_MACHINE: u16 = 62;
+
+/// Mirror of Linux's `elf_gregset_t` for x86-64.
+pub(crate) type ElfGregset = CUserRegsStruct;
+
+/// Size of Linux's `struct user_i387_struct`, i.e., the legacy region of the XSAVE area.
+const USER_I387_STRUCT_SIZE: usize = 512;
+
+/// Builds the general-purpose registers to be written to the `NT_PRSTATUS` note.
+pub(crate) fn elf_gregset(user_ctx: &UserContext, thread_local: &ThreadLocal) -> ElfGregset {
+    let supp = thread_local.supp_user_context();
+    let mut regs = CUserRegsStruct::from_regs(
+        user_ctx.general_regs(),
+        supp.fs_base().get(),
+        supp.gs_base().get(),
+    );
+    // Like Linux, `orig_rax` is -1 if the thread is not in a system call.
+    regs.orig_rax = thread_local.orig_syscall_ret().unwrap_or(usize::MAX);
+    regs
+}
+
+/// Returns the floating-point registers to be written to the `NT_PRFPREG` note.
+pub(crate) fn elf_fpregset(fpu_context: &FpuContext) -> Option<&[u8]> {
+    fpu_context.as_bytes().get(..USER_I387_STRUCT_SIZE)
+}
@@ -0,0 +1,340 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! TUN/TAP device support.
+//!
+//! This module registers the `/dev/net/tun` character device. Each file opened from the device
+//! can be attached to a TUN/TAP device with `TUNSETIFF`, after which the packets transmitted by
+//! the interface of the device can be read from the file and the packets written to the file are
+//! received by the interface.
+//!
+//! Reference: <https://docs.kernel.org/networking/tuntap.html>
+
+use aster_bigtcp::iface::InterfaceName;
+use device_id::{DeviceId, MinorId};
+
+use crate::{
+    context::current_userspace,
+    device::{Device, DeviceType, DevtmpfsInodeMeta, registry::char},
+    events::IoEvents,
+    fs::{
+        file::{PerOpenFileOps, StatusFlags, mkmod},
+        vfs::{inode::FileOps, path::Path},
+    },
+    net::{
+        iface::{TunFlags, TunQueue},
+        net_ns::NetNamespace,
+    },
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable},
+    },
+    security::lsm::hooks as lsm_hooks,
+    util::ioctl::{RawIoctl, dispatch_ioctl},
+};
+
+const TUN_MINOR: u32 = 200;
+
+/// The maximum size of a packet that can be written to a TUN/TAP file.
+const MAX_PACKET_LEN: usize = 65535;
+
+/// The size of the Ethernet header.
+const ETHER_HEADER_LEN: usize = 14;
+
+/// The `/dev/net/tun` device.
+#[derive(Debug)]
+struct TunDevice {
+    id: DeviceId,
+}
+
+impl TunDevice {
+    fn new() -> Arc<Self> {
+        let major = super::MISC_MAJOR.get().unwrap().get();
+        let minor = MinorId::new(TUN_MINOR);
+
+        let id = DeviceId::new(major, minor);
+        Arc::new(Self { id })
+    }
+}
+
+impl Device for TunDevice {
+    fn type_(&self) -> DeviceType {
+        DeviceType::Char
+    }
+
+    fn id(&self) -> DeviceId {
+        self.id
+    }
+
+    fn devtmpfs_meta(&self) -> Option<DevtmpfsInodeMeta<'_>> {
+        Some(DevtmpfsInodeMeta::with_mode("net/tun", mkmod!(a+rw)))
+    }
+
+    fn open(&self) -> Result<Box<dyn PerOpenFileOps>> {
+        // Like Linux, the device is created in the network namespace where the file is opened.
+        Ok(Box::new(TunFile {
+            queue: TunQueue::new(),
+            net_ns: NetNamespace::current(),
+        }))
+    }
+}
+
+/// A file handle opened from `/dev/net/tun`.
+struct TunFile {
+    queue: Arc<TunQueue>,
+    net_ns: Arc<NetNamespace>,
+}
+
+mod ioctl_defs {
+    use crate::util::ioctl::{InData, OutData, PassByVal, ioc};
+
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/if_tun.h>
+
+    // Note that the argument of `TUNSETIFF`, `TUNGETIFF`, and `TUNSETQUEUE` is a pointer to
+    // `struct ifreq`, which does not match the argument type encoded in the ioctl commands.
+    pub(super) type SetIff      = ioc!(TUNSETIFF,      b'T', 202, InData<i32>);
+    pub(super) type SetPersist  = ioc!(TUNSETPERSIST,  b'T', 203, InData<i32, PassByVal>);
+    pub(super) type GetFeatures = ioc!(TUNGETFEATURES, b'T', 207, OutData<u32>);
+    pub(super) type GetIff      = ioc!(TUNGETIFF,      b'T', 210, OutData<u32>);
+    pub(super) type SetQueue    = ioc!(TUNSETQUEUE,    b'T', 217, InData<i32>);
+}
+
+/// `struct ifreq` in Linux, of which the TUN/TAP ioctls only use the name and the flags.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/if.h#L234>
+#[repr(C)]
+#[derive(Clone, Copy, Pod)]
+struct CIfReq {
+    name: [u8; InterfaceName::MAX_BYTES_WITH_NUL],
+    flags: u16,
+    _pad: [u8; 22],
+}
+
+impl CIfReq {
+    fn name(&self) -> Option<InterfaceName> {
+        let name = InterfaceName::from_bytes_until_nul(&self.name);
+        (!name.is_empty()).then_some(name)
+    }
+}
+
+/// `struct tun_pi` in Linux, which is the packet information header.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/if_tun.h#L88>
+#[repr(C)]
+#[derive(Clone, Copy, Pod)]
+struct CTunPi {
+    flags: u16,
+    /// The protocol of the packet in network byte order.
+    proto: u16,
+}
+
+/// The packet is truncated because the buffer is too small.
+const TUN_PKT_STRIP: u16 = 0x0001;
+
+impl CTunPi {
+    fn new(packet: &[u8], is_tap: bool) -> Self {
+        const ETH_P_IP: u16 = 0x0800;
+        const ETH_P_IPV6: u16 = 0x86DD;
+
+        let proto = if is_tap {
+            packet
+                .get(12..ETHER_HEADER_LEN)
+                .map_or(0, |bytes| u16::from_be_bytes([bytes[0], bytes[1]]))
+        } else {
+            match packet.first().map(|byte| byte >> 4) {
+                Some(4) => ETH_P_IP,
+                Some(6) => ETH_P_IPV6,
+                _ => 0,
+            }
+        };
+
+        Self {
+            flags: 0,
+            proto: proto.to_be(),
+        }
+    }
+}
+
+impl TunFile {
+    fn try_read(&self, writer: &mut VmWriter) -> Result<usize> {
+        let flags = self.queue.flags()?;
+        if writer.avail() == 0 {
+            return Ok(0);
+        }
+
+        let has_pi = !flags.contains(TunFlags::NO_PI);
+        if has_pi && writer.avail() < size_of::<CTunPi>() {
+            return_errno_with_message!(Errno::EINVAL, "the buffer is too small");
+        }
+
+        let packet = self.queue.try_recv()?;
+
+        let mut header_len = 0;
+        if has_pi {
+            let mut pi = CTunPi::new(&packet, flags.contains(TunFlags::TAP));
+            if writer.avail() < size_of::<CTunPi>() + packet.len() {
+                pi.flags |= TUN_PKT_STRIP;
+            }
+            writer.write_val(&pi)?;
+            header_len = size_of::<CTunPi>();
+        }
+
+        let len = packet.len().min(writer.avail());
+        writer.write_fallible(&mut VmReader::from(&packet[..len]))?;
+
+        // Like Linux, the full length is returned even if the packet is truncated.
+        Ok(header_len + packet.len())
+    }
+
+    fn set_iff(&self, mut ifreq: CIfReq) -> Result<CIfReq> {
+        check_net_admin(&self.net_ns)?;
+
+        let flags = TunFlags::from_bits_truncate(ifreq.flags);
+        let name = self.queue.attach(&self.net_ns, ifreq.name(), flags)?;
+        ifreq.name = *name.as_array();
+
+        Ok(ifreq)
+    }
+
+    fn set_queue(&self, ifreq: CIfReq) -> Result<()> {
+        let flags = TunFlags::from_bits_truncate(ifreq.flags);
+
+        if flags.contains(TunFlags::ATTACH_QUEUE) {
+            check_net_admin(&self.net_ns)?;
+            self.queue.set_enabled(true)
+        } else if flags.contains(TunFlags::DETACH_QUEUE) {
+            self.queue.set_enabled(false)
+        } else {
+            return_errno_with_message!(Errno::EINVAL, "the queue operation is not specified");
+        }
+    }
+}
+
+impl Drop for TunFile {
+    fn drop(&mut self) {
+        self.queue.detach();
+    }
+}
+
+impl Pollable for TunFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.queue.poll(mask, poller)
+    }
+}
+
+impl FileOps for TunFile {
+    fn read_at(
+        &self,
+        _offset: usize,
+        writer: &mut VmWriter,
+        status_flags: StatusFlags,
+    ) -> Result<usize> {
+        if status_flags.contains(StatusFlags::O_NONBLOCK) {
+            self.try_read(writer)
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_read(writer))
+        }
+    }
+
+    fn write_at(
+        &self,
+        _offset: usize,
+        reader: &mut VmReader,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        let flags = self.queue.flags()?;
+        let total_len = reader.remain();
+
+        if !flags.contains(TunFlags::NO_PI) {
+            if reader.remain() < size_of::<CTunPi>() {
+                return_errno_with_message!(Errno::EINVAL, "the packet information is incomplete");
+            }
+            // The protocol is always determined from the packet itself.
+            reader.read_val::<CTunPi>()?;
+        }
+
+        let min_len = if flags.contains(TunFlags::TAP) {
+            ETHER_HEADER_LEN
+        } else {
+            1
+        };
+        let len = reader.remain();
+        if len < min_len {
+            return_errno_with_message!(Errno::EINVAL, "the packet is too short");
+        }
+        if len > MAX_PACKET_LEN {
+            return_errno_with_message!(Errno::EINVAL, "the packet is too long");
+        }
+
+        let mut packet = vec![0u8; len];
+        reader.read_fallible(&mut VmWriter::from(packet.as_mut_slice()))?;
+        self.queue.send(packet)?;
+
+        Ok(total_len)
+    }
+}
+
+impl PerOpenFileOps for TunFile {
+    fn check_seekable(&self) -> Result<()> {
+        return_errno_with_message!(Errno::ESPIPE, "the inode is a TUN/TAP file");
+    }
+
+    fn is_offset_aware(&self) -> bool {
+        false
+    }
+
+    fn ioctl(&self, _path: &Path, raw_ioctl: RawIoctl) -> Result<i32> {
+        use ioctl_defs::*;
+
+        dispatch_ioctl!(match raw_ioctl {
+            _cmd @ SetIff => {
+                let ifreq = current_userspace!().read_val::<CIfReq>(raw_ioctl.arg())?;
+                let ifreq = self.set_iff(ifreq)?;
+                current_userspace!().write_val(raw_ioctl.arg(), &ifreq)?;
+                Ok(0)
+            }
+            cmd @ SetPersist => {
+                self.queue.set_persist(cmd.get() != 0)?;
+                Ok(0)
+            }
+            cmd @ GetFeatures => {
+                let features = TunFlags::TUN | TunFlags::TAP | TunFlags::FEATURES;
+                cmd.write(&(features.bits() as u32))?;
+                Ok(0)
+            }
+            _cmd @ GetIff => {
+                let (name, flags) = self.queue.info()?;
+                let ifreq = CIfReq {
+                    name: *name.as_array(),
+                    flags: flags.bits(),
+                    _pad: [0; 22],
+                };
+                current_userspace!().write_val(raw_ioctl.arg(), &ifreq)?;
+                Ok(0)
+            }
+            _cmd @ SetQueue => {
+                let ifreq = current_userspace!().read_val::<CIfReq>(raw_ioctl.arg())?;
+                self.set_queue(ifreq)?;
+                Ok(0)
+            }
+            _ => return_errno_with_message!(
+                Errno::ENOTTY,
+                "the ioctl command is not supported by TUN/TAP files"
+            ),
+        })
+    }
+}
+
+/// Checks whether the current thread can configure the devices in the network namespace.
+fn check_net_admin(net_ns: &NetNamespace) -> Result<()> {
+    lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+        net_ns.owner().as_ref(),
+        current_thread!().as_posix_thread().unwrap(),
+        CapSet::NET_ADMIN,
+    ))
+}
+
+pub(super) fn init_in_first_kthread() {
+    char::register(TunDevice::new()).unwrap();
+}
@@ -0,0 +1,137 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The `/dev/userfaultfd` device.
+//!
+//! Userfaultfds can be created by issuing `USERFAULTFD_IOC_NEW` to the files opened from the
+//! device. Unlike the `userfaultfd` system call, this does not require `CAP_SYS_PTRACE` to handle
+//! page faults from kernel mode, since the access is controlled by the permissions of the device.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/mm/userfaultfd.html>
+
+use device_id::{DeviceId, MinorId};
+
+use crate::{
+    device::{Device, DeviceType, DevtmpfsInodeMeta, registry::char},
+    events::IoEvents,
+    fs::{
+        file::{PerOpenFileOps, StatusFlags},
+        vfs::{inode::FileOps, path::Path},
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable},
+    util::ioctl::{RawIoctl, dispatch_ioctl},
+    vm::userfaultfd::create_userfaultfd,
+};
+
+// Linux allocates the minor number dynamically. We use a fixed one that is not taken by other
+// misc devices.
+const USERFAULTFD_MINOR: u32 = 124;
+
+/// The `/dev/userfaultfd` device.
+#[derive(Debug)]
+struct UserfaultfdDevice {
+    id: DeviceId,
+}
+
+impl UserfaultfdDevice {
+    fn new() -> Arc<Self> {
+        let major = super::MISC_MAJOR.get().unwrap().get();
+        let minor = MinorId::new(USERFAULTFD_MINOR);
+
+        let id = DeviceId::new(major, minor);
+        Arc::new(Self { id })
+    }
+}
+
+impl Device for UserfaultfdDevice {
+    fn type_(&self) -> DeviceType {
+        DeviceType::Char
+    }
+
+    fn id(&self) -> DeviceId {
+        self.id
+    }
+
+    fn devtmpfs_meta(&self) -> Option<DevtmpfsInodeMeta<'_>> {
+        Some(DevtmpfsInodeMeta::new("userfaultfd"))
+    }
+
+    fn open(&self) -> Result<Box<dyn PerOpenFileOps>> {
+        Ok(Box::new(UserfaultfdDevFile))
+    }
+}
+
+/// A file handle opened from `/dev/userfaultfd`.
+struct UserfaultfdDevFile;
+
+mod ioctl_defs {
+    use crate::util::ioctl::{NoData, ioc};
+
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/userfaultfd.h>
+
+    pub(super) type NewUserfaultfd = ioc!(USERFAULTFD_IOC_NEW, 0xAA, 0x00, NoData);
+}
+
+impl Pollable for UserfaultfdDevFile {
+    fn poll(&self, mask: IoEvents, _poller: Option<&mut PollHandle>) -> IoEvents {
+        // Linux's `/dev/userfaultfd` does not implement `.poll`, so userspace sees the VFS
+        // default ("always ready").
+        mask & (IoEvents::IN | IoEvents::OUT)
+    }
+}
+
+impl FileOps for UserfaultfdDevFile {
+    fn read_at(
+        &self,
+        _offset: usize,
+        _writer: &mut VmWriter,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the userfaultfd device does not support reading"
+        );
+    }
+
+    fn write_at(
+        &self,
+        _offset: usize,
+        _reader: &mut VmReader,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the userfaultfd device does not support writing"
+        );
+    }
+}
+
+impl PerOpenFileOps for UserfaultfdDevFile {
+    fn check_seekable(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_offset_aware(&self) -> bool {
+        false
+    }
+
+    fn ioctl(&self, _path: &Path, raw_ioctl: RawIoctl) -> Result<i32> {
+        use ioctl_defs::*;
+
+        dispatch_ioctl!(match raw_ioctl {
+            _cmd @ NewUserfaultfd => {
+                let fd = create_userfaultfd(raw_ioctl.arg() as u32)?;
+                Ok(fd.into())
+            }
+            // Linux reports `EINVAL` instead of `ENOTTY` for unknown commands.
+            _ => return_errno_with_message!(
+                Errno::EINVAL,
+                "the ioctl command is not supported by the userfaultfd device"
+            ),
+        })
+    }
+}
+
+pub(super) fn init_in_first_kthread() {
+    char::register(UserfaultfdDevice::new()).unwrap();
+}
@@ -4,6 +4,7 @@ mod evdev;
 mod fb;
 mod mem;
 pub(crate) mod misc;
+mod mqueue;
 mod pty;
 mod registry;
 mod shm;
@@ -187,6 +188,7 @@ pub(crate) fn init_in_first_process(ctx: &Context) -> Result<()> {
     tty::init_in_first_process()?;
     pty::init_in_first_process(&path_resolver, ctx)?;
     shm::init_in_first_process(&path_resolver, ctx)?;
+    mqueue::init_in_first_process(&path_resolver, ctx)?;
     registry::init_in_first_process(&path_resolver)?;
 
     Ok(())
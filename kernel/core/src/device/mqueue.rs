@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    fs::{
+        file::{InodeType, chmod},
+        vfs::{
+            path::{FsPath, PathResolver, PerMountFlags},
+            registry::FsAndRoot,
+        },
+    },
+    prelude::*,
+};
+
+/// Initializes "/dev/mqueue" for POSIX message queue usage.
+pub(crate) fn init_in_first_process(path_resolver: &PathResolver, ctx: &Context) -> Result<()> {
+    use crate::fs::file::InodeMode;
+
+    let dev_path = path_resolver.lookup(&FsPath::try_from("/dev")?)?;
+
+    // Create the "mqueue" directory under "/dev" and mount the mqueue file system of the current
+    // IPC namespace on it.
+    let mqueue_fs = {
+        let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+        let ipc_ns = ns_proxy.unwrap().ipc_ns();
+        ipc_ns.mqueue_mount().fs().clone()
+    };
+    let mqueue_path =
+        dev_path.new_fs_child("mqueue", InodeType::Dir, chmod!(InodeMode::S_ISVTX, a+rwx))?;
+    mqueue_path.mount(
+        FsAndRoot::new(mqueue_fs),
+        PerMountFlags::default(),
+        Some("mqueue".to_string()),
+        ctx,
+    )?;
+    ostd::debug!("Mount mqueue at \"/dev/mqueue\"");
+    Ok(())
+}
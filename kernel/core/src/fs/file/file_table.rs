@@ -193,6 +193,19 @@ impl FileTable {
         (self.table.put(entry) as RawFileDesc).try_into().unwrap()
     }
 
+    /// Inserts `item` at the exact descriptor number `fd`, closing the file previously there.
+    pub(crate) fn insert_at(
+        &mut self,
+        fd: FileDesc,
+        item: Arc<dyn FileLike>,
+        flags: FdFlags,
+    ) -> Option<ClosedFile> {
+        let closed_file = self.close_file(fd);
+        self.table
+            .put_at(fd.into(), FileTableEntry::new(item, flags));
+        closed_file
+    }
+
     pub(crate) fn close_file(&mut self, fd: FileDesc) -> Option<ClosedFile> {
         let removed_entry = self.table.remove(fd.into())?;
         // POSIX record locks are process-associated and Linux drops them when any fd for the inode is
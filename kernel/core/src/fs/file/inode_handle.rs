@@ -22,6 +22,7 @@ use crate::{
     },
     prelude::*,
     process::signal::{PollHandle, Pollable},
+    security::lsm::{FileLsm, hooks as lsm_hooks},
     util::ioctl::RawIoctl,
 };
 
@@ -32,6 +33,7 @@ pub(crate) struct InodeHandle {
     /// and `ioctl` will be provided by the per-open file object instead of `path`.
     open_file: Option<Box<dyn PerOpenFileOps>>,
     offset: Mutex<usize>,
+    lsm: FileLsm,
 }
 
 impl InodeHandle {
@@ -72,9 +74,16 @@ impl InodeHandle {
             common: FileCommon::new(path, access_mode, status_flags),
             open_file,
             offset: Mutex::new(0),
+            lsm: FileLsm::new(),
         })
     }
 
+    /// Replaces the LSM state with the one prepared when the file is opened.
+    pub(in crate::fs) fn with_lsm(mut self, lsm: FileLsm) -> Self {
+        self.lsm = lsm;
+        self
+    }
+
     pub(crate) fn path(&self) -> &Path {
         self.common.path()
     }
@@ -398,6 +407,7 @@ impl FileLike for InodeHandle {
         }
         // `ftruncate` uses the descriptor's write mode and must not recheck current inode
         // permissions. See <https://man7.org/linux/man-pages/man2/truncate.2.html>.
+        lsm_hooks::on_file_truncate(lsm_hooks::FileContext::new(&self.lsm))?;
         self.path().resize_unchecked_access(new_size)
     }
 
@@ -1,22 +1,21 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::sync::Arc;
-use core::sync::atomic::{AtomicU32, Ordering};
+use alloc::{sync::Arc, vec::Vec};
 
 use aster_systree::{Error, MAX_ATTR_SIZE, Result, SysAttrSetBuilder, SysPerms, SysStr};
 use aster_util::{per_cpu_counter::PerCpuCounter, printer::VmPrinter};
 use ostd::{
     cpu::CpuId,
     mm::{VmReader, VmWriter},
-    sync::SpinLock,
     task::atomic_mode::AsAtomicModeGuard,
     timer::Jiffies,
-    warn,
 };
 
 use crate::{
     fs::cgroupfs::systree_node::{CgroupSysNode, CgroupSystem},
-    process::Process,
+    process::{Process, posix_thread::AsPosixThread},
+    sched::TaskGroup,
+    thread::{AsThread, Thread},
     util::ReadCString,
 };
 
@@ -46,19 +45,8 @@ struct CpuStats {
 
 /// CPU resource controls that are reset whenever `+cpu` is re-enabled.
 struct CpuControl {
-    /// A value stores the configured relative CPU share for scheduler integration.
-    weight: AtomicU32,
-    /// A value stores the configured CPU bandwidth limit for scheduler integration.
-    max: SpinLock<CpuMax>,
-}
-
-/// A CPU bandwidth limit.
-#[derive(Clone, Copy)]
-struct CpuMax {
-    /// The configured CPU runtime budget for bandwidth enforcement.
-    quota_usec: u64,
-    /// The window over which the CPU runtime budget is intended to apply.
-    period_usec: u64,
+    /// The task group that enforces `cpu.weight` and `cpu.max` in the fair scheduler.
+    task_group: Arc<TaskGroup>,
 }
 
 /// Specifies the cgroup CPU sub-controller receives one [`Jiffies`] of charge.
@@ -125,13 +113,13 @@ impl CpuController {
         writeln!(printer, "user_usec {}", user_usec)?;
         writeln!(printer, "system_usec {}", system_usec)?;
 
-        if self.control.is_some() {
-            // TODO: Support CPU bandwidth control statistics. These fields are reported as `0`
-            // for now because the cgroup CPU sub-controller does not yet implement throttling or
-            // burst accounting.
-            writeln!(printer, "nr_periods 0")?;
-            writeln!(printer, "nr_throttled 0")?;
-            writeln!(printer, "throttled_usec 0")?;
+        if let Some(control) = self.control.as_ref() {
+            let stats = control.task_group.bandwidth_stats();
+            writeln!(printer, "nr_periods {}", stats.nr_periods)?;
+            writeln!(printer, "nr_throttled {}", stats.nr_throttled)?;
+            writeln!(printer, "throttled_usec {}", stats.throttled_usec)?;
+            // TODO: Support CPU bandwidth bursts. These fields are reported as `0` for now
+            // because `cpu.max.burst` is not supported.
             writeln!(printer, "nr_bursts 0")?;
             writeln!(printer, "burst_usec 0")?;
         }
@@ -150,20 +138,8 @@ impl CpuController {
 
 impl CpuControl {
     fn new() -> Self {
-        // The default CPU weight is 100 and the default CPU bandwidth limit is
-        // unlimited quota with a 100ms period.
-        //
-        // Reference: <https://docs.kernel.org/admin-guide/cgroup-v2.html#cpu-interface-files>
-        const DEFAULT_WEIGHT: u32 = 100;
-        const DEFAULT_QUOTA_USEC: u64 = u64::MAX;
-        const DEFAULT_PERIOD_USEC: u64 = 100_000;
-
         Self {
-            weight: AtomicU32::new(DEFAULT_WEIGHT),
-            max: SpinLock::new(CpuMax {
-                quota_usec: DEFAULT_QUOTA_USEC,
-                period_usec: DEFAULT_PERIOD_USEC,
-            }),
+            task_group: Arc::new(TaskGroup::new()),
         }
     }
 }
@@ -188,15 +164,15 @@ impl super::SubControl for CpuController {
         match name {
             "cpu.stat" => return self.read_cpu_stat(&mut printer),
             "cpu.weight" => {
-                let weight = self.control()?.weight.load(Ordering::Relaxed);
+                let weight = self.control()?.task_group.weight();
                 writeln!(printer, "{}", weight)?;
             }
             "cpu.max" => {
-                let max = *self.control()?.max.lock();
-                if max.quota_usec == u64::MAX {
-                    writeln!(printer, "max {}", max.period_usec)?;
+                let (quota_usec, period_usec) = self.control()?.task_group.max();
+                if quota_usec == u64::MAX {
+                    writeln!(printer, "max {}", period_usec)?;
                 } else {
-                    writeln!(printer, "{} {}", max.quota_usec, max.period_usec)?;
+                    writeln!(printer, "{} {}", quota_usec, period_usec)?;
                 }
             }
             _ => return Err(Error::AttributeError),
@@ -229,12 +205,7 @@ impl super::SubControl for CpuController {
                     return Err(Error::InvalidOperation);
                 }
 
-                // TODO: Enforce cgroup CPU weight for scheduling and remove this warning.
-                warn!(
-                    "`cpu.weight` is accepted but not enforced yet; weight = {}",
-                    weight
-                );
-                control.weight.store(weight, Ordering::Relaxed);
+                control.task_group.set_weight(weight);
 
                 Ok(len)
             }
@@ -287,16 +258,7 @@ impl super::SubControl for CpuController {
                     None
                 };
 
-                // TODO: Enforce CPU bandwidth throttling and remove this warning.
-                warn!(
-                    "`cpu.max` is accepted but not enforced yet; quota = {}, period = {:?}",
-                    quota_usec, period_usec
-                );
-                let mut max = control.max.lock();
-                max.quota_usec = quota_usec;
-                if let Some(period_usec) = period_usec {
-                    max.period_usec = period_usec;
-                }
+                control.task_group.set_max(quota_usec, period_usec);
 
                 Ok(len)
             }
@@ -334,6 +296,31 @@ impl super::SubController<CpuController> {
     }
 }
 
+impl super::SubController<CpuController> {
+    /// Returns the task groups of this cgroup and its ancestors, excluding the
+    /// root cgroup, from the innermost to the outermost.
+    ///
+    /// A cgroup without `+cpu` enabled by its parent has no task group, so its
+    /// threads are scheduled in the task group of the nearest ancestor.
+    fn task_groups(&self) -> Vec<Arc<TaskGroup>> {
+        let mut task_groups = Vec::new();
+
+        let mut current = Some(self);
+        while let Some(node) = current {
+            // The root cgroup has no task group.
+            if node.parent.is_none() {
+                break;
+            }
+            if let Some(control) = node.inner.as_ref().unwrap().control.as_ref() {
+                task_groups.push(control.task_group.clone());
+            }
+            current = node.parent.as_deref();
+        }
+
+        task_groups
+    }
+}
+
 impl super::Controller {
     /// Charges one [`Jiffies`] in the CPU sub-controller hierarchy.
     fn charge_cpu_time<G: AsAtomicModeGuard + ?Sized>(&self, guard: &G, stat_kind: CpuStatKind) {
@@ -357,3 +344,40 @@ pub(crate) fn charge_cpu_time(process: &Process, stat_kind: CpuStatKind) {
             .charge_cpu_time(&cgroup_guard, stat_kind);
     }
 }
+
+/// Returns the task groups of `thread` for the fair scheduler, from the
+/// innermost to the outermost.
+///
+/// Threads in the root cgroup and kernel threads do not belong to any task group.
+pub(crate) fn task_groups_of(thread: &Thread) -> Vec<Arc<TaskGroup>> {
+    let Some(process) = thread
+        .as_posix_thread()
+        .and_then(|posix_thread| posix_thread.weak_process().upgrade())
+    else {
+        return Vec::new();
+    };
+
+    let cgroup_guard = process.cgroup();
+    let Some(cgroup) = cgroup_guard.get() else {
+        return Vec::new();
+    };
+
+    cgroup
+        .controller()
+        .cpu
+        .read_with(&cgroup_guard)
+        .task_groups()
+}
+
+/// Updates the task groups of the threads in `process` after its cgroup or the
+/// CPU sub-controller of its cgroup changes.
+///
+/// The scheduler does not look up the task groups by itself, so this must be
+/// called whenever they may change.
+pub(crate) fn update_task_groups(process: &Process) {
+    for task in process.tasks().lock().as_slice() {
+        if let Some(thread) = task.as_thread() {
+            thread.sched_attr().set_task_groups(task_groups_of(thread));
+        }
+    }
+}
@@ -0,0 +1,601 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{collections::btree_map::BTreeMap, sync::Arc};
+use core::{
+    sync::atomic::{AtomicU16, Ordering},
+    time::Duration,
+};
+
+use aster_block::{
+    BlockDevice, PartitionNode,
+    bio::{Bio, BioType},
+};
+use aster_systree::{Error, MAX_ATTR_SIZE, Result, SysAttrSetBuilder, SysPerms, SysStr};
+use aster_util::printer::VmPrinter;
+use device_id::{DeviceId, MajorId, MinorId};
+use ostd::{
+    mm::{VmReader, VmWriter},
+    sync::{SpinLock, WaitQueue},
+    task::atomic_mode::might_sleep,
+};
+
+use crate::{
+    fs::cgroupfs::systree_node::{CgroupSysNode, CgroupSystem},
+    process::Process,
+    time::{clocks::MonotonicClock, wait::WaitTimeout},
+    util::ReadCString,
+};
+
+/// A sub-controller responsible for block I/O resource management in the cgroup subsystem.
+///
+/// The bios submitted by the processes in a cgroup are accounted in `io.stat`
+/// of the cgroup and its ancestors, and are throttled according to `io.weight`
+/// and `io.max` of the cgroup and its ancestors.
+///
+/// Reference: <https://docs.kernel.org/admin-guide/cgroup-v2.html#io-interface-files>
+pub(crate) struct IoController {
+    /// The weight of the devices that have no per-device weights.
+    default_weight: AtomicU16,
+    /// The per-device states of the I/O.
+    devices: SpinLock<BTreeMap<DeviceId, IoDevice>>,
+}
+
+/// The I/O states of a cgroup on a block device.
+#[derive(Default)]
+struct IoDevice {
+    stat: IoStat,
+    /// The per-device weight, as shown in `io.weight`.
+    weight: Option<u16>,
+    /// The limits and their throttling states, indexed by [`IoLimitKind`].
+    limits: [IoLimit; IoLimitKind::ALL.len()],
+    /// The shares of the active child cgroups, keyed by the addresses of
+    /// their sub-controllers.
+    child_shares: BTreeMap<usize, IoShare>,
+}
+
+/// The I/O statistics of a cgroup on a block device, as shown in `io.stat`.
+#[derive(Default)]
+struct IoStat {
+    rbytes: u64,
+    wbytes: u64,
+    rios: u64,
+    wios: u64,
+}
+
+/// The share of a child cgroup in the I/O of its parent on a block device.
+///
+/// The child cgroups are served in the order of their virtual time, which
+/// advances by the bytes of each dispatched bio divided by the weight.
+struct IoShare {
+    vtime: u64,
+    /// The time when the child cgroup tried to dispatch the last bio.
+    last_active_ns: u64,
+}
+
+/// An I/O limit of a cgroup on a block device.
+struct IoLimit {
+    /// The maximum number of bytes or I/Os per second, or `u64::MAX` if unlimited.
+    max: u64,
+    /// The time when the next I/O can be dispatched without exceeding the limit.
+    next_dispatch_ns: u64,
+}
+
+impl Default for IoLimit {
+    fn default() -> Self {
+        Self {
+            max: u64::MAX,
+            next_dispatch_ns: 0,
+        }
+    }
+}
+
+/// The kind of an I/O limit in `io.max`.
+#[derive(Clone, Copy, Debug)]
+enum IoLimitKind {
+    ReadBps,
+    WriteBps,
+    ReadIops,
+    WriteIops,
+}
+
+impl IoLimitKind {
+    const ALL: [Self; 4] = [
+        Self::ReadBps,
+        Self::WriteBps,
+        Self::ReadIops,
+        Self::WriteIops,
+    ];
+
+    const fn as_str(self) -> &'static str {
+        match self {
+            Self::ReadBps => "rbps",
+            Self::WriteBps => "wbps",
+            Self::ReadIops => "riops",
+            Self::WriteIops => "wiops",
+        }
+    }
+
+    fn from_str(s: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|kind| kind.as_str() == s)
+    }
+}
+
+// The cgroup v2 I/O weight is in the range 1..=10000, and the default is 100.
+//
+// Reference: <https://docs.kernel.org/admin-guide/cgroup-v2.html#io-interface-files>
+const MIN_WEIGHT: u16 = 1;
+const MAX_WEIGHT: u16 = 10_000;
+const DEFAULT_WEIGHT: u16 = 100;
+
+/// How long a child cgroup stays active after it tries to dispatch a bio.
+///
+/// Only the active child cgroups compete for the I/O, so an idle child cgroup
+/// does not hold back its siblings.
+const ACTIVE_PERIOD_NS: u64 = 50_000_000;
+
+/// How far a child cgroup can run ahead of its active siblings in virtual
+/// time, in bytes at the default weight.
+const MAX_VTIME_LEAD: u64 = 256 * 1024;
+
+impl IoController {
+    pub(super) fn init_attr_set(builder: &mut SysAttrSetBuilder, is_root: bool) {
+        builder.add(SysStr::from("io.stat"), SysPerms::DEFAULT_RO_ATTR_PERMS);
+
+        if !is_root {
+            builder.add(SysStr::from("io.max"), SysPerms::DEFAULT_RW_ATTR_PERMS);
+            builder.add(SysStr::from("io.weight"), SysPerms::DEFAULT_RW_ATTR_PERMS);
+        }
+    }
+
+    /// Accounts a bio of `nbytes` bytes on the device.
+    fn charge(&self, device_id: DeviceId, type_: BioType, nbytes: usize) {
+        let mut devices = self.devices.lock();
+        let stat = &mut devices.entry(device_id).or_default().stat;
+        match type_ {
+            BioType::Read => {
+                stat.rbytes += nbytes as u64;
+                stat.rios += 1;
+            }
+            BioType::Write => {
+                stat.wbytes += nbytes as u64;
+                stat.wios += 1;
+            }
+            BioType::Flush => (),
+        }
+    }
+
+    /// Reserves the dispatch of a bio of `nbytes` bytes on the device.
+    ///
+    /// Returns how long the bio should be delayed so that the limits in
+    /// `io.max` are not exceeded.
+    fn throttle(&self, device_id: DeviceId, type_: BioType, nbytes: usize, now_ns: u64) -> u64 {
+        let (bps_kind, iops_kind) = match type_ {
+            BioType::Read => (IoLimitKind::ReadBps, IoLimitKind::ReadIops),
+            BioType::Write => (IoLimitKind::WriteBps, IoLimitKind::WriteIops),
+            BioType::Flush => return 0,
+        };
+
+        let mut devices = self.devices.lock();
+        let Some(device) = devices.get_mut(&device_id) else {
+            return 0;
+        };
+
+        let bps_delay_ns = device.limits[bps_kind as usize].reserve(nbytes as u64, now_ns);
+        let iops_delay_ns = device.limits[iops_kind as usize].reserve(1, now_ns);
+        bps_delay_ns.max(iops_delay_ns)
+    }
+
+    /// Returns the weight of the cgroup on the device.
+    fn weight(&self, device_id: DeviceId) -> u16 {
+        let devices = self.devices.lock();
+        devices
+            .get(&device_id)
+            .and_then(|device| device.weight)
+            .unwrap_or_else(|| self.default_weight.load(Ordering::Relaxed))
+    }
+
+    /// Tries to dispatch a bio of `nbytes` bytes from the child cgroup
+    /// identified by `child_key`.
+    ///
+    /// The bio can be dispatched only if the child cgroup is not too far ahead
+    /// of its active siblings in virtual time. Returns whether the bio is
+    /// dispatched.
+    fn try_dispatch_child(
+        &self,
+        device_id: DeviceId,
+        child_key: usize,
+        child_weight: u16,
+        nbytes: usize,
+        now_ns: u64,
+    ) -> bool {
+        let mut devices = self.devices.lock();
+        let shares = &mut devices.entry(device_id).or_default().child_shares;
+
+        shares.retain(|_, share| share.last_active_ns + ACTIVE_PERIOD_NS > now_ns);
+        let min_sibling_vtime = shares
+            .iter()
+            .filter(|(key, _)| **key != child_key)
+            .map(|(_, share)| share.vtime)
+            .min();
+
+        // A child cgroup that becomes active starts from the virtual time of
+        // its siblings, so it cannot make up for the time when it was idle.
+        let share = shares.entry(child_key).or_insert_with(|| IoShare {
+            vtime: min_sibling_vtime.unwrap_or(0),
+            last_active_ns: now_ns,
+        });
+        share.last_active_ns = now_ns;
+
+        if min_sibling_vtime.is_some_and(|min_vtime| share.vtime > min_vtime + MAX_VTIME_LEAD) {
+            return false;
+        }
+
+        share.vtime += nbytes as u64 * u64::from(DEFAULT_WEIGHT) / u64::from(child_weight);
+        true
+    }
+
+    fn read_stat(&self, printer: &mut VmPrinter) -> Result<usize> {
+        let devices = self.devices.lock();
+        for (device_id, device) in devices.iter() {
+            let stat = &device.stat;
+            if stat.rios == 0 && stat.wios == 0 {
+                continue;
+            }
+
+            // TODO: Support discarding sectors and account them in `dbytes` and `dios`.
+            writeln!(
+                printer,
+                "{}:{} rbytes={} wbytes={} rios={} wios={} dbytes=0 dios=0",
+                device_id.major().get(),
+                device_id.minor().get(),
+                stat.rbytes,
+                stat.wbytes,
+                stat.rios,
+                stat.wios,
+            )?;
+        }
+
+        Ok(printer.bytes_written())
+    }
+
+    fn read_max(&self, printer: &mut VmPrinter) -> Result<usize> {
+        let devices = self.devices.lock();
+        for (device_id, device) in devices.iter() {
+            if device.limits.iter().all(|limit| limit.max == u64::MAX) {
+                continue;
+            }
+
+            write!(
+                printer,
+                "{}:{}",
+                device_id.major().get(),
+                device_id.minor().get()
+            )?;
+            for kind in IoLimitKind::ALL {
+                let max = device.limits[kind as usize].max;
+                if max == u64::MAX {
+                    write!(printer, " {}=max", kind.as_str())?;
+                } else {
+                    write!(printer, " {}={}", kind.as_str(), max)?;
+                }
+            }
+            writeln!(printer)?;
+        }
+
+        Ok(printer.bytes_written())
+    }
+
+    fn read_weight(&self, printer: &mut VmPrinter) -> Result<usize> {
+        writeln!(
+            printer,
+            "default {}",
+            self.default_weight.load(Ordering::Relaxed)
+        )?;
+
+        let devices = self.devices.lock();
+        for (device_id, device) in devices.iter() {
+            if let Some(weight) = device.weight {
+                writeln!(
+                    printer,
+                    "{}:{} {}",
+                    device_id.major().get(),
+                    device_id.minor().get(),
+                    weight
+                )?;
+            }
+        }
+
+        Ok(printer.bytes_written())
+    }
+
+    /// Writes `io.max`, which is in the format of
+    /// `$MAJ:$MIN [rbps=$N] [wbps=$N] [riops=$N] [wiops=$N]`.
+    ///
+    /// Each limit is either a number or `max`. The limits that are not
+    /// specified are kept unchanged.
+    fn write_max(&self, reader: &mut VmReader) -> Result<usize> {
+        let (content, len) = reader
+            .read_cstring_until_end(MAX_ATTR_SIZE)
+            .map_err(|_| Error::PageFault)?;
+        let content = content.to_str().map_err(|_| Error::InvalidOperation)?;
+        let mut tokens = content.split_whitespace();
+
+        let device_id = parse_disk_id(tokens.next().ok_or(Error::InvalidOperation)?)?;
+
+        let mut new_limits = [None; IoLimitKind::ALL.len()];
+        for token in tokens {
+            let (key, value) = token.split_once('=').ok_or(Error::InvalidOperation)?;
+            let kind = IoLimitKind::from_str(key).ok_or(Error::InvalidOperation)?;
+            let max = if value == "max" {
+                u64::MAX
+            } else {
+                match value.parse::<u64>() {
+                    Ok(max) if max > 0 => max,
+                    _ => return Err(Error::InvalidOperation),
+                }
+            };
+            new_limits[kind as usize] = Some(max);
+        }
+
+        let mut devices = self.devices.lock();
+        let device = devices.entry(device_id).or_default();
+        for (limit, new_max) in device.limits.iter_mut().zip(new_limits) {
+            if let Some(new_max) = new_max {
+                *limit = IoLimit {
+                    max: new_max,
+                    ..IoLimit::default()
+                };
+            }
+        }
+
+        Ok(len)
+    }
+
+    /// Writes `io.weight`, which is in the format of `[default] $WEIGHT` or
+    /// `$MAJ:$MIN $WEIGHT`.
+    ///
+    /// The per-device weight can be removed by writing `$MAJ:$MIN default`.
+    fn write_weight(&self, reader: &mut VmReader) -> Result<usize> {
+        let (content, len) = reader
+            .read_cstring_until_end(MAX_ATTR_SIZE)
+            .map_err(|_| Error::PageFault)?;
+        let content = content.to_str().map_err(|_| Error::InvalidOperation)?;
+        let mut tokens = content.split_whitespace();
+
+        let first_token = tokens.next().ok_or(Error::InvalidOperation)?;
+        let (device_id, weight_token) = if first_token.contains(':') {
+            let device_id = parse_disk_id(first_token)?;
+            (Some(device_id), tokens.next())
+        } else if first_token == "default" {
+            (None, tokens.next())
+        } else {
+            (None, Some(first_token))
+        };
+        let weight_token = weight_token.ok_or(Error::InvalidOperation)?;
+        if tokens.next().is_some() {
+            return Err(Error::InvalidOperation);
+        }
+
+        let weight = if weight_token == "default" && device_id.is_some() {
+            None
+        } else {
+            let weight = weight_token
+                .parse::<u16>()
+                .map_err(|_| Error::InvalidOperation)?;
+            if !(MIN_WEIGHT..=MAX_WEIGHT).contains(&weight) {
+                return Err(Error::InvalidOperation);
+            }
+            Some(weight)
+        };
+
+        match device_id {
+            Some(device_id) => {
+                let mut devices = self.devices.lock();
+                devices.entry(device_id).or_default().weight = weight;
+            }
+            None => self
+                .default_weight
+                .store(weight.unwrap(), Ordering::Relaxed),
+        }
+
+        Ok(len)
+    }
+}
+
+impl IoLimit {
+    /// Reserves the dispatch of an I/O that costs `cost` bytes or I/Os.
+    ///
+    /// The I/Os are dispatched one by one at the rate of the limit. Returns
+    /// how long the I/O should wait before it can be dispatched.
+    fn reserve(&mut self, cost: u64, now_ns: u64) -> u64 {
+        if self.max == u64::MAX {
+            return 0;
+        }
+
+        let dispatch_ns = self.next_dispatch_ns.max(now_ns);
+        let cost_ns = u64::try_from(u128::from(cost) * 1_000_000_000 / u128::from(self.max))
+            .unwrap_or(u64::MAX);
+        self.next_dispatch_ns = dispatch_ns.saturating_add(cost_ns);
+
+        dispatch_ns - now_ns
+    }
+}
+
+/// Parses `$MAJ:$MIN` that refers to a whole-disk block device.
+fn parse_disk_id(token: &str) -> Result<DeviceId> {
+    let (major, minor) = token.split_once(':').ok_or(Error::InvalidOperation)?;
+    let major = major
+        .parse::<u16>()
+        .ok()
+        .and_then(|major| MajorId::try_from(major).ok())
+        .ok_or(Error::InvalidOperation)?;
+    let minor = minor
+        .parse::<u32>()
+        .ok()
+        .and_then(|minor| MinorId::try_from(minor).ok())
+        .ok_or(Error::InvalidOperation)?;
+    let device_id = DeviceId::new(major, minor);
+
+    // Like Linux, the I/O of partitions is controlled on their whole disks.
+    match aster_block::lookup(device_id) {
+        Some(device) if !device.is_partition() => Ok(device_id),
+        _ => Err(Error::NotFound),
+    }
+}
+
+impl super::SubControl for IoController {
+    fn read_attr_at(&self, name: &str, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let mut printer = VmPrinter::new_skip(writer, offset);
+        match name {
+            "io.stat" => self.read_stat(&mut printer),
+            "io.max" => self.read_max(&mut printer),
+            "io.weight" => self.read_weight(&mut printer),
+            _ => Err(Error::AttributeError),
+        }
+    }
+
+    fn write_attr(&self, name: &str, reader: &mut VmReader) -> Result<usize> {
+        match name {
+            "io.max" => self.write_max(reader),
+            "io.weight" => self.write_weight(reader),
+            _ => Err(Error::AttributeError),
+        }
+    }
+}
+
+impl super::SubControlStatic for IoController {
+    fn new(_is_root: bool, _is_active: bool) -> Self {
+        Self {
+            default_weight: AtomicU16::new(DEFAULT_WEIGHT),
+            devices: SpinLock::new(BTreeMap::new()),
+        }
+    }
+
+    fn type_() -> super::SubCtrlType {
+        super::SubCtrlType::Io
+    }
+
+    fn read_from(controller: &super::Controller) -> Arc<super::SubController<Self>> {
+        controller.io.read().get().clone()
+    }
+}
+
+/// The wait queue to delay the throttled bios. It is never woken up.
+static THROTTLE_WAIT_QUEUE: WaitQueue = WaitQueue::new();
+
+/// The wait queue for the bios that wait for their sibling cgroups to catch up
+/// in virtual time. It is woken up whenever a bio is dispatched.
+static WEIGHT_WAIT_QUEUE: WaitQueue = WaitQueue::new();
+
+/// Accounts and throttles a bio before it is submitted to the block device.
+///
+/// The bio is charged to the cgroup of the current process and its ancestors.
+/// The current task sleeps until the bio can be dispatched according to
+/// `io.weight` and without exceeding `io.max` of any of these cgroups.
+///
+/// The bios submitted by kernel threads are charged to the root cgroup, which
+/// has no limits.
+///
+/// This function is injected into the block layer with
+/// [`aster_block::bio::inject_bio_submit_handler`].
+pub(crate) fn throttle_bio(block_device: &dyn BlockDevice, bio: &Bio) {
+    might_sleep();
+
+    let type_ = bio.type_();
+    if type_ == BioType::Flush {
+        return;
+    }
+
+    let device_id = match block_device.downcast_ref::<PartitionNode>() {
+        Some(partition) => partition.disk().id(),
+        None => block_device.id(),
+    };
+    let nbytes = bio.nbytes();
+
+    let io_controller = if let Some(process) = Process::current() {
+        let cgroup_guard = process.cgroup();
+        let controller = match cgroup_guard.get() {
+            Some(cgroup) => cgroup.controller(),
+            None => CgroupSystem::singleton().controller(),
+        };
+        controller.io.read_with(&cgroup_guard).clone()
+    } else {
+        CgroupSystem::singleton()
+            .controller()
+            .io
+            .read()
+            .get()
+            .clone()
+    };
+
+    io_controller.wait_for_share(device_id, nbytes);
+
+    let now_ns = MonotonicClock::get().read_time().as_nanos() as u64;
+    let delay_ns = io_controller.charge_hierarchy(device_id, type_, nbytes, now_ns);
+    if delay_ns == 0 {
+        return;
+    }
+
+    let _ =
+        THROTTLE_WAIT_QUEUE.wait_until_or_timeout(|| None::<()>, &Duration::from_nanos(delay_ns));
+}
+
+impl super::SubController<IoController> {
+    /// Waits until a bio can be dispatched in proportion to the weights at
+    /// each level of the hierarchy.
+    ///
+    /// A cgroup can only run ahead of its active siblings by a bounded amount
+    /// of I/O, which is scaled by the inverse of the weights. So the siblings
+    /// that compete for a device get the I/O in proportion to their weights,
+    /// while a cgroup can use up the device if its siblings are idle.
+    fn wait_for_share(&self, device_id: DeviceId, nbytes: usize) {
+        let mut current = self;
+        while let Some(parent) = current.parent.as_deref() {
+            if let (Some(child), Some(parent_inner)) = (&current.inner, &parent.inner) {
+                let child_key = current as *const Self as usize;
+                let child_weight = child.weight(device_id);
+                let mut try_dispatch = || {
+                    let now_ns = MonotonicClock::get().read_time().as_nanos() as u64;
+                    parent_inner
+                        .try_dispatch_child(device_id, child_key, child_weight, nbytes, now_ns)
+                        .then_some(())
+                };
+
+                // Retry periodically so that the task stays active and its
+                // idle siblings are no longer waited for.
+                let timeout = Duration::from_nanos(ACTIVE_PERIOD_NS / 2);
+                while WEIGHT_WAIT_QUEUE
+                    .wait_until_or_timeout(&mut try_dispatch, &timeout)
+                    .is_err()
+                {}
+                WEIGHT_WAIT_QUEUE.wake_all();
+            }
+            current = parent;
+        }
+    }
+
+    /// Charges a bio across the hierarchy.
+    ///
+    /// Returns how long the bio should be delayed to satisfy the limits at
+    /// each level.
+    fn charge_hierarchy(
+        &self,
+        device_id: DeviceId,
+        type_: BioType,
+        nbytes: usize,
+        now_ns: u64,
+    ) -> u64 {
+        let mut delay_ns = 0;
+
+        let mut current = Some(self);
+        while let Some(node) = current {
+            if let Some(ref inner) = node.inner {
+                inner.charge(device_id, type_, nbytes);
+                delay_ns = delay_ns.max(inner.throttle(device_id, type_, nbytes, now_ns));
+            }
+            current = node.parent.as_deref();
+        }
+
+        delay_ns
+    }
+}
@@ -1,16 +1,92 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use alloc::sync::Arc;
+use core::{
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+    time::Duration,
+};
 
-use aster_systree::{Error, Result, SysAttrSetBuilder, SysPerms, SysStr};
-use ostd::mm::{VmReader, VmWriter};
+use aster_systree::{Error, MAX_ATTR_SIZE, Result, SysAttrSetBuilder, SysPerms, SysStr};
+use aster_util::printer::VmPrinter;
+use ostd::{
+    mm::{PAGE_SIZE, VmReader, VmWriter},
+    sync::Waiter,
+    task::Task,
+};
+
+use super::TryChargeError;
+use crate::{
+    context::Context,
+    fs::cgroupfs::CgroupSysNode,
+    process::{Process, posix_thread::AsThreadLocal, signal::Pause},
+    util::ReadCString,
+    vm::reclaim,
+};
 
 /// A sub-controller responsible for memory resource management in the cgroup subsystem.
 ///
-/// Note that even if the controller is inactive, it still provides some interfaces
-/// like "memory.pressure" for usage.
+/// Pages stay charged to the sub-controller until they are freed. So, unlike
+/// most other sub-controllers, this sub-controller always exists and is kept
+/// when `+memory` is toggled. While it is inactive, its limits are not
+/// enforced and its attributes are hidden.
 pub(crate) struct MemoryController {
-    _private: (),
+    /// Whether the memory sub-control is enabled in the parent cgroup.
+    is_active: AtomicBool,
+    /// The memory usage of this cgroup's subtree.
+    usage: MemoryUsage,
+    /// The memory limits that are reset whenever `+memory` is re-enabled.
+    limits: MemoryLimits,
+    /// The numbers of memory events that occurred in this cgroup's subtree.
+    events: MemoryEvents,
+}
+
+/// The memory usage of a cgroup in pages.
+struct MemoryUsage {
+    current: AtomicUsize,
+    peak: AtomicUsize,
+    anon: AtomicUsize,
+    file: AtomicUsize,
+}
+
+/// The memory limits of a cgroup in pages.
+///
+/// `usize::MAX` means that there is no limit.
+struct MemoryLimits {
+    /// The hard limit. Exceeding it triggers reclaim and, if that fails, the OOM killer.
+    max: AtomicUsize,
+    /// The throttle limit. Exceeding it triggers reclaim and throttles the allocating tasks.
+    high: AtomicUsize,
+}
+
+struct MemoryEvents {
+    high: AtomicUsize,
+    max: AtomicUsize,
+    oom: AtomicUsize,
+    oom_kill: AtomicUsize,
+}
+
+/// The kind of memory that a page is charged as.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum MemoryKind {
+    /// Anonymous pages, including the pages in the swap cache.
+    Anon,
+    /// Pages in the page caches, including those of tmpfs and shared memory.
+    File,
+}
+
+/// A memory event that is counted in `memory.events`.
+///
+/// Reference: <https://docs.kernel.org/admin-guide/cgroup-v2.html#memory-interface-files>
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum MemoryEvent {
+    /// The usage exceeded `memory.high`, so the allocating tasks were throttled.
+    High,
+    /// The usage was about to exceed `memory.max`.
+    Max,
+    /// The usage reached `memory.max` and memory reclaim failed.
+    Oom,
+    /// A process was killed by the OOM killer.
+    OomKill,
 }
 
 impl MemoryController {
@@ -21,31 +97,203 @@ impl MemoryController {
         //
         // Reference: <https://www.kernel.org/doc/html/latest/admin-guide/cgroup-v2.html#memory-interface-files>
         if !is_root {
+            builder.add(
+                SysStr::from("memory.current"),
+                SysPerms::DEFAULT_RO_ATTR_PERMS,
+            );
             builder.add(
                 SysStr::from("memory.events"),
                 SysPerms::DEFAULT_RO_ATTR_PERMS,
             );
-            builder.add(SysStr::from("memory.max"), SysPerms::DEFAULT_RO_ATTR_PERMS);
+            builder.add(SysStr::from("memory.high"), SysPerms::DEFAULT_RW_ATTR_PERMS);
+            builder.add(SysStr::from("memory.max"), SysPerms::DEFAULT_RW_ATTR_PERMS);
+            builder.add(SysStr::from("memory.peak"), SysPerms::DEFAULT_RO_ATTR_PERMS);
             builder.add(SysStr::from("memory.stat"), SysPerms::DEFAULT_RO_ATTR_PERMS);
         }
     }
+
+    fn new(is_active: bool) -> Self {
+        Self {
+            is_active: AtomicBool::new(is_active),
+            usage: MemoryUsage {
+                current: AtomicUsize::new(0),
+                peak: AtomicUsize::new(0),
+                anon: AtomicUsize::new(0),
+                file: AtomicUsize::new(0),
+            },
+            limits: MemoryLimits {
+                max: AtomicUsize::new(usize::MAX),
+                high: AtomicUsize::new(usize::MAX),
+            },
+            events: MemoryEvents {
+                high: AtomicUsize::new(0),
+                max: AtomicUsize::new(0),
+                oom: AtomicUsize::new(0),
+                oom_kill: AtomicUsize::new(0),
+            },
+        }
+    }
+
+    /// Activates or deactivates the sub-controller.
+    ///
+    /// When `+memory` is re-enabled, the limits are reset to their defaults,
+    /// but the usage and the events are kept.
+    pub(super) fn set_active(&self, is_active: bool) {
+        if self.is_active.swap(is_active, Ordering::Relaxed) == is_active {
+            return;
+        }
+
+        if is_active {
+            self.limits.max.store(usize::MAX, Ordering::Relaxed);
+            self.limits.high.store(usize::MAX, Ordering::Relaxed);
+        }
+    }
+
+    fn is_active(&self) -> bool {
+        self.is_active.load(Ordering::Relaxed)
+    }
+
+    fn counter_of(&self, kind: MemoryKind) -> &AtomicUsize {
+        match kind {
+            MemoryKind::Anon => &self.usage.anon,
+            MemoryKind::File => &self.usage.file,
+        }
+    }
+
+    /// Tries to charge `nr_pages` pages, enforcing the `memory.max` limit.
+    ///
+    /// Returns `false` if the limit would be exceeded; the charge is rolled back.
+    fn try_charge(&self, nr_pages: usize, kind: MemoryKind) -> bool {
+        let new_current = self.usage.current.fetch_add(nr_pages, Ordering::Relaxed) + nr_pages;
+        if self.is_active() && new_current > self.limits.max.load(Ordering::Relaxed) {
+            self.usage.current.fetch_sub(nr_pages, Ordering::Relaxed);
+            return false;
+        }
+        self.usage.peak.fetch_max(new_current, Ordering::Relaxed);
+        self.counter_of(kind).fetch_add(nr_pages, Ordering::Relaxed);
+        true
+    }
+
+    fn uncharge(&self, nr_pages: usize, kind: MemoryKind) {
+        self.counter_of(kind).fetch_sub(nr_pages, Ordering::Relaxed);
+        let old_current = self.usage.current.fetch_sub(nr_pages, Ordering::Relaxed);
+        debug_assert!(old_current >= nr_pages, "memory current usage underflow");
+    }
+
+    /// Returns the number of pages over `memory.high`.
+    fn nr_pages_over_high(&self) -> usize {
+        if !self.is_active() {
+            return 0;
+        }
+        let current = self.usage.current.load(Ordering::Relaxed);
+        current.saturating_sub(self.limits.high.load(Ordering::Relaxed))
+    }
+
+    /// Returns the number of pages to free so that `nr_pages` more pages can be
+    /// charged without exceeding `memory.max`.
+    fn nr_pages_over_max(&self, nr_pages: usize) -> usize {
+        if !self.is_active() {
+            return 0;
+        }
+        let current = self.usage.current.load(Ordering::Relaxed);
+        (current + nr_pages).saturating_sub(self.limits.max.load(Ordering::Relaxed))
+    }
+
+    fn read_limit(&self, limit: &AtomicUsize, printer: &mut VmPrinter) -> Result<()> {
+        let limit = limit.load(Ordering::Relaxed);
+        if limit == usize::MAX {
+            writeln!(printer, "max")?;
+        } else {
+            writeln!(printer, "{}", limit * PAGE_SIZE)?;
+        }
+        Ok(())
+    }
+
+    fn write_limit(&self, limit: &AtomicUsize, reader: &mut VmReader) -> Result<usize> {
+        let (content, len) = reader
+            .read_cstring_until_end(MAX_ATTR_SIZE)
+            .map_err(|_| Error::PageFault)?;
+        let value = content
+            .to_str()
+            .map_err(|_| Error::InvalidOperation)?
+            .trim();
+        let nr_pages = if value == "max" {
+            usize::MAX
+        } else {
+            parse_memory_size(value).ok_or(Error::InvalidOperation)? / PAGE_SIZE
+        };
+
+        // If the usage is over the new limit, memory will be reclaimed by the caller. See
+        // `MemCgroup::reclaim_over_limits`.
+        limit.store(nr_pages, Ordering::Relaxed);
+
+        Ok(len)
+    }
 }
 
 impl super::SubControl for MemoryController {
-    fn read_attr_at(&self, _name: &str, _offset: usize, _writer: &mut VmWriter) -> Result<usize> {
-        // TODO: Add support for reading attributes.
-        Err(Error::AttributeError)
+    fn is_attr_absent(&self, name: &str) -> bool {
+        !self.is_active() && name.starts_with("memory.")
+    }
+
+    fn read_attr_at(&self, name: &str, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        if !self.is_active() {
+            return Err(Error::AttributeError);
+        }
+
+        let mut printer = VmPrinter::new_skip(writer, offset);
+        match name {
+            "memory.current" => {
+                let current = self.usage.current.load(Ordering::Relaxed);
+                writeln!(printer, "{}", current * PAGE_SIZE)?;
+            }
+            "memory.peak" => {
+                let peak = self.usage.peak.load(Ordering::Relaxed);
+                writeln!(printer, "{}", peak * PAGE_SIZE)?;
+            }
+            "memory.max" => self.read_limit(&self.limits.max, &mut printer)?,
+            "memory.high" => self.read_limit(&self.limits.high, &mut printer)?,
+            "memory.stat" => {
+                let anon = self.usage.anon.load(Ordering::Relaxed);
+                let file = self.usage.file.load(Ordering::Relaxed);
+                writeln!(printer, "anon {}", anon * PAGE_SIZE)?;
+                writeln!(printer, "file {}", file * PAGE_SIZE)?;
+            }
+            "memory.events" => {
+                // TODO: Support `memory.low` and `memory.oom.group`.
+                writeln!(printer, "low 0")?;
+                writeln!(printer, "high {}", self.events.high.load(Ordering::Relaxed))?;
+                writeln!(printer, "max {}", self.events.max.load(Ordering::Relaxed))?;
+                writeln!(printer, "oom {}", self.events.oom.load(Ordering::Relaxed))?;
+                writeln!(
+                    printer,
+                    "oom_kill {}",
+                    self.events.oom_kill.load(Ordering::Relaxed)
+                )?;
+                writeln!(printer, "oom_group_kill 0")?;
+            }
+            _ => return Err(Error::AttributeError),
+        }
+
+        Ok(printer.bytes_written())
     }
 
-    fn write_attr(&self, _name: &str, _reader: &mut VmReader) -> Result<usize> {
-        // TODO: Add support for writing attributes.
-        Err(Error::AttributeError)
+    fn write_attr(&self, name: &str, reader: &mut VmReader) -> Result<usize> {
+        if !self.is_active() {
+            return Err(Error::AttributeError);
+        }
+
+        match name {
+            "memory.max" => self.write_limit(&self.limits.max, reader),
+            "memory.high" => self.write_limit(&self.limits.high, reader),
+            _ => Err(Error::AttributeError),
+        }
     }
 }
 
 impl super::SubControlStatic for MemoryController {
-    fn new(_is_root: bool, _is_active: bool) -> Self {
-        Self { _private: () }
+    fn new(is_root: bool, is_active: bool) -> Self {
+        Self::new(is_root || is_active)
     }
 
     fn type_() -> super::SubCtrlType {
@@ -56,3 +304,299 @@ impl super::SubControlStatic for MemoryController {
         controller.memory.read().get().clone()
     }
 }
+
+/// Parses a memory size in bytes with an optional `K`, `M`, `G`, or `T` suffix.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/lib/cmdline.c#L152>
+fn parse_memory_size(value: &str) -> Option<usize> {
+    let (digits, shift) = match value.as_bytes().last()? {
+        b'k' | b'K' => (&value[..value.len() - 1], 10),
+        b'm' | b'M' => (&value[..value.len() - 1], 20),
+        b'g' | b'G' => (&value[..value.len() - 1], 30),
+        b't' | b'T' => (&value[..value.len() - 1], 40),
+        _ => (value, 0),
+    };
+
+    digits.parse::<usize>().ok()?.checked_mul(1 << shift)
+}
+
+/// A handle to the memory sub-controller of a non-root cgroup.
+#[derive(Clone)]
+pub(crate) struct MemCgroup(Arc<super::SubController<MemoryController>>);
+
+impl MemCgroup {
+    /// Returns the memory cgroup of the process.
+    ///
+    /// Returns `None` if the process is in the root cgroup.
+    pub(crate) fn of_process(process: &Process) -> Option<Self> {
+        let cgroup_guard = process.cgroup();
+        let cgroup = cgroup_guard.get()?;
+        Some(Self::of_controller(cgroup.controller()))
+    }
+
+    /// Returns the memory cgroup of the controller.
+    pub(super) fn of_controller(controller: &super::Controller) -> Self {
+        Self(controller.memory.read().get().clone())
+    }
+
+    fn inner(&self) -> &MemoryController {
+        self.0.inner.as_ref().unwrap()
+    }
+
+    /// Returns an iterator over this cgroup and its ancestors.
+    fn iter_ancestors(&self) -> impl Iterator<Item = &Arc<super::SubController<MemoryController>>> {
+        core::iter::successors(Some(&self.0), |node| node.parent.as_ref())
+    }
+
+    /// Returns whether this cgroup is `other` or one of its ancestors.
+    pub(crate) fn is_ancestor_of(&self, other: &MemCgroup) -> bool {
+        other
+            .iter_ancestors()
+            .any(|node| Arc::ptr_eq(node, &self.0))
+    }
+
+    /// Returns whether the process is in this cgroup's subtree.
+    pub(crate) fn contains_process(&self, process: &Process) -> bool {
+        MemCgroup::of_process(process).is_some_and(|memcg| self.is_ancestor_of(&memcg))
+    }
+
+    /// Returns the current memory usage in pages.
+    pub(crate) fn nr_used_pages(&self) -> usize {
+        self.inner().usage.current.load(Ordering::Relaxed)
+    }
+
+    /// Returns the `memory.max` limit in pages.
+    pub(crate) fn nr_max_pages(&self) -> usize {
+        self.inner().limits.max.load(Ordering::Relaxed)
+    }
+
+    /// Returns the outermost cgroup among this cgroup and its ancestors that
+    /// does not allow `nr_pages` more pages to be charged.
+    ///
+    /// Memory should be reclaimed from the returned cgroup so that the charge
+    /// can succeed.
+    pub(crate) fn find_over_max(&self, nr_pages: usize) -> Option<MemCgroup> {
+        self.iter_ancestors()
+            .filter(|node| node.inner.as_ref().unwrap().nr_pages_over_max(nr_pages) > 0)
+            .last()
+            .map(|node| MemCgroup(node.clone()))
+    }
+
+    /// Reclaims memory from this cgroup until its usage is within the `memory.max` and
+    /// `memory.high` limits.
+    ///
+    /// This method should be called after the limits are lowered. If the usage cannot be reduced
+    /// to `memory.max` by reclaiming memory, processes in this cgroup will be killed by the OOM
+    /// killer.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/mm/memcontrol.c>
+    pub(super) fn reclaim_over_limits(&self) {
+        /// The maximum number of retries to reclaim memory.
+        ///
+        /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/mm/internal.h>
+        const MAX_RECLAIM_RETRIES: usize = 16;
+
+        for _ in 0..MAX_RECLAIM_RETRIES {
+            let nr_pages_over_max = self.inner().nr_pages_over_max(0);
+            let nr_pages_over_high = self.inner().nr_pages_over_high();
+            if nr_pages_over_max == 0 && nr_pages_over_high == 0 {
+                return;
+            }
+
+            let nr_pages = nr_pages_over_max.max(nr_pages_over_high);
+            if !reclaim::reclaim_cgroup_memory(self, nr_pages, nr_pages_over_max > 0) {
+                return;
+            }
+        }
+    }
+
+    /// Records a memory event in this cgroup and its ancestors.
+    pub(crate) fn record_event(&self, event: MemoryEvent) {
+        // Like Linux, the events are not recorded in the root cgroup.
+        for node in self.iter_ancestors().filter(|node| node.parent.is_some()) {
+            let events = &node.inner.as_ref().unwrap().events;
+            let counter = match event {
+                MemoryEvent::High => &events.high,
+                MemoryEvent::Max => &events.max,
+                MemoryEvent::Oom => &events.oom,
+                MemoryEvent::OomKill => &events.oom_kill,
+            };
+            counter.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Tries to charge `nr_pages` pages across the hierarchy, enforcing
+    /// `memory.max` at each level.
+    ///
+    /// If any level exceeds its limit, all previously charged levels are
+    /// rolled back and the level is returned.
+    fn try_charge_hierarchy(&self, nr_pages: usize, kind: MemoryKind) -> Option<MemCgroup> {
+        for node in self.iter_ancestors() {
+            if node.inner.as_ref().unwrap().try_charge(nr_pages, kind) {
+                continue;
+            }
+
+            for charged_node in self
+                .iter_ancestors()
+                .take_while(|charged_node| !Arc::ptr_eq(charged_node, node))
+            {
+                charged_node
+                    .inner
+                    .as_ref()
+                    .unwrap()
+                    .uncharge(nr_pages, kind);
+            }
+            return Some(MemCgroup(node.clone()));
+        }
+
+        None
+    }
+
+    fn uncharge_hierarchy(&self, nr_pages: usize, kind: MemoryKind) {
+        for node in self.iter_ancestors() {
+            node.inner.as_ref().unwrap().uncharge(nr_pages, kind);
+        }
+    }
+
+    /// Returns the outermost cgroup among this cgroup and its ancestors that is
+    /// over `memory.high`, together with the number of pages over the limit.
+    fn find_over_high(&self) -> Option<(MemCgroup, usize)> {
+        self.iter_ancestors()
+            .filter_map(|node| {
+                let nr_pages = node.inner.as_ref().unwrap().nr_pages_over_high();
+                (nr_pages > 0).then(|| (MemCgroup(node.clone()), nr_pages))
+            })
+            .last()
+    }
+
+    /// Calculates how long the tasks that charged `nr_pages` pages over
+    /// `memory.high` should be throttled.
+    ///
+    /// The delay grows quadratically with the ratio of the usage over the limit.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/mm/memcontrol.c#L2184>
+    fn high_delay(&self, nr_pages: usize) -> Duration {
+        const PRECISION_SHIFT: u32 = 20;
+        const SCALING_SHIFT: u32 = 14;
+        const CHARGE_BATCH: u64 = 64;
+        const MAX_DELAY_MS: u64 = 2000;
+        const MIN_DELAY_MS: u64 = 10;
+
+        let max_overage = self
+            .iter_ancestors()
+            .map(|node| {
+                let memcg = node.inner.as_ref().unwrap();
+                let over_pages = memcg.nr_pages_over_high() as u64;
+                let high = memcg.limits.high.load(Ordering::Relaxed).max(1) as u64;
+                (over_pages << PRECISION_SHIFT) / high
+            })
+            .max()
+            .unwrap_or(0);
+
+        let delay_ms = (max_overage.saturating_mul(max_overage).saturating_mul(1000)
+            >> (PRECISION_SHIFT + SCALING_SHIFT))
+            .saturating_mul(nr_pages as u64)
+            / CHARGE_BATCH;
+        if delay_ms <= MIN_DELAY_MS {
+            return Duration::ZERO;
+        }
+
+        Duration::from_millis(delay_ms.min(MAX_DELAY_MS))
+    }
+}
+
+/// A page charged to a memory cgroup.
+///
+/// The page is uncharged when this object is dropped, which happens when the
+/// page is freed.
+pub(crate) struct MemoryCharge {
+    memcg: MemCgroup,
+    kind: MemoryKind,
+}
+
+impl MemoryCharge {
+    /// Returns whether the page is charged to the cgroup or one of its descendants.
+    pub(crate) fn is_charged_to(&self, memcg: &MemCgroup) -> bool {
+        memcg.is_ancestor_of(&self.memcg)
+    }
+}
+
+impl Drop for MemoryCharge {
+    fn drop(&mut self) {
+        self.memcg.uncharge_hierarchy(1, self.kind);
+    }
+}
+
+impl core::fmt::Debug for MemoryCharge {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("MemoryCharge")
+            .field("kind", &self.kind)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Tries to charge a page to the memory cgroup of the current process.
+///
+/// Returns `None` if the page is not charged to any cgroup, which happens if
+/// the current task is a kernel thread or the current process is in the root
+/// cgroup.
+///
+/// This function never blocks, so it can be called in the atomic mode. If the
+/// charge fails because `memory.max` is reached, the caller should reclaim
+/// memory with [`reclaim::reclaim_memory_for_current`] and retry.
+pub(crate) fn try_charge_memory(kind: MemoryKind) -> Result<Option<MemoryCharge>, TryChargeError> {
+    let Some(process) = Process::current() else {
+        return Ok(None);
+    };
+    let Some(memcg) = MemCgroup::of_process(&process) else {
+        return Ok(None);
+    };
+
+    if let Some(over_max_memcg) = memcg.try_charge_hierarchy(1, kind) {
+        over_max_memcg.record_event(MemoryEvent::Max);
+        return Err(TryChargeError);
+    }
+
+    // Throttle the current task later when it returns to the user space. It
+    // may hold arbitrary locks now.
+    if memcg.find_over_high().is_some()
+        && let Some(thread_local) = Task::current()
+            .as_ref()
+            .and_then(|task| task.as_thread_local())
+    {
+        let nr_pages_over_high = thread_local.memcg_nr_pages_over_high();
+        nr_pages_over_high.set(nr_pages_over_high.get() + 1);
+    }
+
+    Ok(Some(MemoryCharge { memcg, kind }))
+}
+
+/// Reclaims memory from and throttles the current thread if its memory cgroup
+/// is over `memory.high`.
+///
+/// This function should be called before returning to the user space.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/mm/memcontrol.c#L2263>
+pub(crate) fn throttle_memory_over_high(ctx: &Context) {
+    let nr_pages = ctx.thread_local.memcg_nr_pages_over_high().replace(0);
+    if nr_pages == 0 {
+        return;
+    }
+    let Some(memcg) = MemCgroup::of_process(ctx.process) else {
+        return;
+    };
+
+    if let Some((over_high_memcg, nr_pages_over_high)) = memcg.find_over_high() {
+        over_high_memcg.record_event(MemoryEvent::High);
+        reclaim::reclaim_cgroup_memory(&over_high_memcg, nr_pages_over_high, false);
+    }
+
+    let delay = memcg.high_delay(nr_pages);
+    if delay.is_zero() {
+        return;
+    }
+
+    // Like Linux, the throttling can be interrupted by signals.
+    let (waiter, _waker) = Waiter::new_pair();
+    let _ = waiter.pause_until_or_timeout(|| None::<()>, &delay);
+}
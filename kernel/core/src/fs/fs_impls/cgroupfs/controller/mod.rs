@@ -18,7 +18,10 @@ use ostd::{
 use crate::fs::cgroupfs::{
     CgroupMembership, CgroupNode,
     controller::{
-        cpu::CpuController, cpuset::CpuSetController, memory::MemoryController,
+        cpu::CpuController,
+        cpuset::CpuSetController,
+        io::IoController,
+        memory::{MemCgroup, MemoryController},
         pids::PidsController,
     },
     systree_node::CgroupSysNode,
@@ -26,7 +29,8 @@ use crate::fs::cgroupfs::{
 
 pub(super) mod cpu;
 mod cpuset;
-mod memory;
+pub(super) mod io;
+pub(super) mod memory;
 mod pids;
 
 /// A trait to abstract all individual cgroup sub-controllers.
@@ -57,18 +61,20 @@ trait SubControlStatic: SubControl + Sized + 'static {
 pub(super) enum SubCtrlType {
     CpuSet,
     Cpu,
+    Io,
     Memory,
     Pids,
 }
 
 impl SubCtrlType {
     // Keep this in the Linux-visible controller order used by `Display`.
-    const ALL: [Self; 4] = [Self::CpuSet, Self::Cpu, Self::Memory, Self::Pids];
+    const ALL: [Self; 5] = [Self::CpuSet, Self::Cpu, Self::Io, Self::Memory, Self::Pids];
 
     const fn as_str(self) -> &'static str {
         match self {
             Self::CpuSet => "cpuset",
             Self::Cpu => "cpu",
+            Self::Io => "io",
             Self::Memory => "memory",
             Self::Pids => "pids",
         }
@@ -82,6 +88,7 @@ impl FromStr for SubCtrlType {
         match s {
             "cpuset" => Ok(SubCtrlType::CpuSet),
             "cpu" => Ok(SubCtrlType::Cpu),
+            "io" => Ok(SubCtrlType::Io),
             "memory" => Ok(SubCtrlType::Memory),
             "pids" => Ok(SubCtrlType::Pids),
             _ => Err(Error::NotFound),
@@ -96,6 +103,7 @@ bitflags! {
         const CPU = 1 << 1;
         const MEMORY = 1 << 2;
         const PIDS = 1 << 3;
+        const IO = 1 << 4;
     }
 }
 
@@ -142,6 +150,7 @@ impl From<SubCtrlType> for SubCtrlSet {
         match ctrl_type {
             SubCtrlType::CpuSet => Self::CPUSET,
             SubCtrlType::Cpu => Self::CPU,
+            SubCtrlType::Io => Self::IO,
             SubCtrlType::Memory => Self::MEMORY,
             SubCtrlType::Pids => Self::PIDS,
         }
@@ -186,9 +195,11 @@ impl<T: SubControlStatic> SubController<T> {
             true
         };
 
-        let inner = if is_active || T::type_() == SubCtrlType::Cpu {
+        let inner = if is_active || matches!(T::type_(), SubCtrlType::Cpu | SubCtrlType::Memory) {
             // `cpu.stat` exists regardless of whether `+cpu` has been enabled, so the
-            // CPU sub-controller must remain instantiated even while inactive.
+            // CPU sub-controller must remain instantiated even while inactive. Pages are
+            // charged to the memory sub-controller regardless of whether `+memory` has
+            // been enabled, so it must remain instantiated as well.
             Some(T::new(is_root, is_active))
         } else {
             None
@@ -231,6 +242,7 @@ pub(crate) struct Controller {
 
     cpuset: Rcu<Arc<SubController<CpuSetController>>>,
     cpu: Rcu<Arc<SubController<CpuController>>>,
+    io: Rcu<Arc<SubController<IoController>>>,
     memory: Rcu<Arc<SubController<MemoryController>>>,
     pids: Rcu<Arc<SubController<PidsController>>>,
 }
@@ -240,6 +252,7 @@ impl Controller {
     pub(super) fn new(parent_controller: Option<&Controller>) -> Self {
         let cpuset_controller = Arc::new(SubController::new(parent_controller));
         let cpu_controller = Arc::new(SubController::new(parent_controller));
+        let io_controller = Arc::new(SubController::new(parent_controller));
         let memory_controller = Arc::new(SubController::new(parent_controller));
         let pids_controller = Arc::new(SubController::new(parent_controller));
 
@@ -247,6 +260,7 @@ impl Controller {
             active_set: AtomicSubCtrlSet::new(SubCtrlSet::empty()),
             cpuset: Rcu::new(cpuset_controller),
             cpu: Rcu::new(cpu_controller),
+            io: Rcu::new(io_controller),
             memory: Rcu::new(memory_controller),
             pids: Rcu::new(pids_controller),
         }
@@ -255,6 +269,7 @@ impl Controller {
     pub(super) fn init_attr_set(builder: &mut SysAttrSetBuilder, is_root: bool) {
         CpuSetController::init_attr_set(builder, is_root);
         CpuController::init_attr_set(builder, is_root);
+        IoController::init_attr_set(builder, is_root);
         MemoryController::init_attr_set(builder, is_root);
         PidsController::init_attr_set(builder, is_root);
     }
@@ -263,6 +278,7 @@ impl Controller {
         match ctrl_type {
             SubCtrlType::CpuSet => CpuSetController::read_from(self),
             SubCtrlType::Cpu => CpuController::read_from(self),
+            SubCtrlType::Io => IoController::read_from(self),
             SubCtrlType::Memory => MemoryController::read_from(self),
             SubCtrlType::Pids => PidsController::read_from(self),
         }
@@ -316,7 +332,14 @@ impl Controller {
             return Err(Error::IsDead);
         };
 
-        controller.write_attr(name, reader)
+        let len = controller.write_attr(name, reader)?;
+
+        // The usage may exceed the new memory limits, which should be enforced immediately.
+        if ctrl_type == SubCtrlType::Memory {
+            MemCgroup::of_controller(self).reclaim_over_limits();
+        }
+
+        Ok(len)
     }
 
     /// Activates a sub-control of the specified type.
@@ -412,10 +435,20 @@ impl Controller {
                             .init_stats(previous_controller.inner.as_ref().unwrap());
                     }
                     child_node.controller().cpu.update(Arc::new(new_controller));
+                    cgroup_membership.update_task_groups_in_node(child_node);
                 }
-                SubCtrlType::Memory => {
+                SubCtrlType::Io => {
                     let new_controller = Arc::new(SubController::new(Some(parent_controller)));
-                    child_node.controller().memory.update(new_controller);
+                    child_node.controller().io.update(new_controller);
+                }
+                SubCtrlType::Memory => {
+                    // Charged pages refer to the memory sub-controller, so it is kept
+                    // and only (de)activated here.
+                    let is_active = parent_controller
+                        .active_set()
+                        .contains_type(SubCtrlType::Memory);
+                    let guard = child_node.controller().memory.read();
+                    guard.get().inner.as_ref().unwrap().set_active(is_active);
                 }
                 SubCtrlType::Pids => {
                     let mut new_controller: SubController<PidsController> =
@@ -1,7 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
 pub(crate) use cgroup_ns::CgroupNamespace;
-pub(crate) use controller::cpu::{CpuStatKind, charge_cpu_time};
+pub(crate) use controller::{
+    cpu::{CpuStatKind, charge_cpu_time, task_groups_of},
+    memory::{
+        MemCgroup, MemoryCharge, MemoryEvent, MemoryKind, throttle_memory_over_high,
+        try_charge_memory,
+    },
+};
 use fs::CgroupFsType;
 pub(in crate::fs) use systree_node::CgroupSystem;
 pub(crate) use systree_node::{CgroupMembership, CgroupNode, CgroupSysNode};
@@ -23,4 +29,5 @@ mod systree_node;
 // _after_ `aster_systree::init`.
 pub(super) fn init() {
     crate::fs::vfs::registry::register(&CgroupFsType).unwrap();
+    aster_block::bio::inject_bio_submit_handler(controller::io::throttle_bio);
 }
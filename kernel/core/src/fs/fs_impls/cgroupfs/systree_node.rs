@@ -64,7 +64,9 @@ use ostd::sync::{RwMutexReadGuard, RwMutexWriteGuard};
 use spin::Once;
 
 use crate::{
-    fs::cgroupfs::controller::{Controller, PidsPreCharge, SubCtrlSet, SubCtrlType},
+    fs::cgroupfs::controller::{
+        Controller, PidsPreCharge, SubCtrlSet, SubCtrlType, cpu::update_task_groups,
+    },
     prelude::*,
     process::{Pid, Process, pid_table},
 };
@@ -144,6 +146,23 @@ impl CgroupMembership {
         total
     }
 
+    /// Updates the task groups of the processes in a cgroup after its CPU
+    /// sub-controller is replaced.
+    pub(super) fn update_task_groups_in_node(&mut self, cgroup_node: &CgroupNode) {
+        let processes = cgroup_node
+            .with_inner(|processes| {
+                processes
+                    .values()
+                    .filter_map(Weak::upgrade)
+                    .collect::<Vec<_>>()
+            })
+            .unwrap_or_default();
+
+        for process in processes {
+            update_task_groups(&process);
+        }
+    }
+
     /// Moves a process to the new cgroup node via explicit migration.
     ///
     /// A process can only belong to one cgroup at a time.
@@ -187,6 +206,8 @@ impl CgroupMembership {
             old_cgroup.controller.uncharge_pids();
         }
 
+        update_task_groups(&process);
+
         Ok(())
     }
 
@@ -205,6 +226,8 @@ impl CgroupMembership {
 
         pids_charge.apply();
         self.add_process_to_node(&process, new_cgroup).unwrap();
+
+        update_task_groups(&process);
     }
 
     fn add_process_to_node(&self, process: &Arc<Process>, new_cgroup: &CgroupNode) -> Result<()> {
@@ -257,6 +280,8 @@ impl CgroupMembership {
 
         // Uncharge the pids sub-controller for the old cgroup.
         old_cgroup.controller.uncharge_pids();
+
+        update_task_groups(process);
     }
 }
 
@@ -9,10 +9,12 @@ pub(crate) mod configfs;
 pub(crate) mod devpts;
 pub(crate) mod exfat;
 pub(crate) mod ext2;
+pub(crate) mod mqueuefs;
 pub(crate) mod overlayfs;
 pub(crate) mod procfs;
 pub(crate) mod pseudofs;
 pub(crate) mod ramfs;
+pub(crate) mod securityfs;
 pub(crate) mod sysfs;
 pub(crate) mod tmpfs;
 pub(crate) mod virtiofs;
@@ -22,9 +24,11 @@ pub(super) fn init() {
     procfs::init();
     cgroupfs::init();
     configfs::init();
+    securityfs::init();
     ramfs::init();
     tmpfs::init();
     devpts::init();
+    mqueuefs::init();
     pseudofs::init();
 
     ext2::init();
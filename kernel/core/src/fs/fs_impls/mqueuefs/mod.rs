@@ -0,0 +1,497 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The mqueue file system.
+//!
+//! Every regular file in the file system is a POSIX message queue. Each IPC namespace owns an
+//! instance of the file system, which is accessed by the `mq_*` system calls through an internal
+//! mount. User space can mount the instance of its IPC namespace (normally at "/dev/mqueue") to
+//! list, inspect, and remove the message queues with ordinary file operations.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man7/mq_overview.7.html>.
+
+#![expect(unused_variables)]
+
+use core::{
+    sync::atomic::{AtomicU64, AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use aster_util::slot_vec::SlotVec;
+use ostd::task::Task;
+
+use self::queue::MqueueInode;
+pub(crate) use self::queue::{MQ_PRIO_MAX, MqAttr, MqNotify, MqueueFile};
+use crate::{
+    fs::{
+        file::{InodeMode, InodeType, StatusFlags, chmod},
+        pseudofs::AnonDeviceId,
+        utils::{DirEntryVecExt, DirentVisitor, NAME_MAX},
+        vfs::{
+            file_system::{FileSystem, FsEventSubscriberStats, SuperBlock},
+            inode::{
+                Extension, FileOps, Inode, Metadata, MknodType, RenameMode, RevalidationPolicy,
+            },
+            path::Mount,
+            registry::{FsCreationCtx, FsProperties, FsType},
+        },
+    },
+    prelude::*,
+    process::{
+        Gid, Uid, UserNamespace, credentials::capabilities::CapSet, posix_thread::AsPosixThread,
+    },
+    security::lsm::hooks as lsm_hooks,
+    thread::Thread,
+    time::clocks::RealTimeCoarseClock,
+};
+
+mod queue;
+
+const MQUEUE_MAGIC: u64 = 0x19800202;
+const BLOCK_SIZE: usize = PAGE_SIZE;
+
+const ROOT_INO: u64 = 1;
+const FIRST_QUEUE_INO: u64 = 2;
+
+/// The maximum number of message queues in an IPC namespace.
+///
+/// The limit does not apply to privileged processes.
+const QUEUES_MAX: usize = 256;
+/// The maximum value of `mq_maxmsg` that can be specified by unprivileged processes.
+const MSG_MAX: usize = 10;
+/// The maximum value of `mq_msgsize` that can be specified by unprivileged processes.
+const MSGSIZE_MAX: usize = 8192;
+/// The default value of `mq_maxmsg` if no attributes are specified.
+const MSG_DEFAULT: usize = 10;
+/// The default value of `mq_msgsize` if no attributes are specified.
+const MSGSIZE_DEFAULT: usize = 8192;
+/// The maximum value of `mq_maxmsg` that can be specified by privileged processes.
+const HARD_MSGMAX: usize = 65536;
+/// The maximum value of `mq_msgsize` that can be specified by privileged processes.
+const HARD_MSGSIZEMAX: usize = 16 * 1024 * 1024;
+
+/// An instance of the mqueue file system.
+pub(crate) struct MqueueFs {
+    _anon_device_id: AnonDeviceId,
+    sb: SuperBlock,
+    root: Arc<RootInode>,
+    next_ino: AtomicU64,
+    nr_queues: AtomicUsize,
+    fs_event_subscriber_stats: FsEventSubscriberStats,
+}
+
+impl MqueueFs {
+    /// Creates a new instance of the file system and returns its internal mount.
+    ///
+    /// The internal mount does not belong to any mount namespace. It allows the `mq_*` system
+    /// calls to access the message queues even if the file system is not mounted anywhere.
+    pub(crate) fn new_internal_mount() -> Result<Arc<Mount>> {
+        let anon_device_id = AnonDeviceId::acquire().ok_or_else(|| {
+            Error::with_message(Errno::ENOMEM, "no device ID is available for mqueue")
+        })?;
+        let sb = SuperBlock::new(MQUEUE_MAGIC, BLOCK_SIZE, NAME_MAX, anon_device_id.id());
+        let fs = Arc::new_cyclic(|weak_self| Self {
+            _anon_device_id: anon_device_id,
+            sb: sb.clone(),
+            root: RootInode::new(weak_self.clone(), &sb),
+            next_ino: AtomicU64::new(FIRST_QUEUE_INO),
+            nr_queues: AtomicUsize::new(0),
+            fs_event_subscriber_stats: FsEventSubscriberStats::new(),
+        });
+
+        Mount::new_pseudo(fs)
+    }
+
+    /// Creates a message queue named `name`.
+    ///
+    /// If `attr` is `None`, the message queue will be created with the default attributes.
+    pub(crate) fn create_queue(
+        &self,
+        name: &str,
+        mode: InodeMode,
+        attr: Option<&MqAttr>,
+    ) -> Result<()> {
+        if name.len() > NAME_MAX {
+            return_errno_with_message!(Errno::ENAMETOOLONG, "the queue name is too long");
+        }
+
+        let is_privileged = has_sys_resource_capability();
+        let (max_msgs, max_msg_size) = match attr {
+            Some(attr) => check_attr(attr, is_privileged)?,
+            None => (MSG_DEFAULT.min(MSG_MAX), MSGSIZE_DEFAULT.min(MSGSIZE_MAX)),
+        };
+
+        let mut queues = self.root.queues.write();
+        if queues.find_entry_by_name(name).is_some() {
+            return_errno_with_message!(Errno::EEXIST, "the queue already exists");
+        }
+        if self.nr_queues.load(Ordering::Relaxed) >= QUEUES_MAX && !is_privileged {
+            return_errno_with_message!(Errno::ENOSPC, "too many message queues");
+        }
+
+        let ino = self.next_ino.fetch_add(1, Ordering::Relaxed);
+        let queue = MqueueInode::new(
+            ino,
+            mode,
+            max_msgs,
+            max_msg_size,
+            self.root.fs.clone(),
+            &self.sb,
+        );
+        self.nr_queues.fetch_add(1, Ordering::Relaxed);
+        queues.put((String::from(name), queue));
+        drop(queues);
+
+        let now = now();
+        let mut root_metadata = self.root.metadata.write();
+        root_metadata.last_modify_at = now;
+        root_metadata.last_meta_change_at = now;
+
+        Ok(())
+    }
+}
+
+impl FileSystem for MqueueFs {
+    fn name(&self) -> &'static str {
+        "mqueue"
+    }
+
+    fn sync(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn Inode> {
+        self.root.clone()
+    }
+
+    fn sb(&self) -> SuperBlock {
+        self.sb.clone()
+    }
+
+    fn fs_event_subscriber_stats(&self) -> &FsEventSubscriberStats {
+        &self.fs_event_subscriber_stats
+    }
+}
+
+struct MqueueFsType;
+
+impl FsType for MqueueFsType {
+    type Key = ();
+
+    fn name(&self) -> &'static str {
+        "mqueue"
+    }
+
+    fn properties(&self) -> FsProperties {
+        FsProperties::empty()
+    }
+
+    fn create(&self, _fs_creation_ctx: &mut FsCreationCtx) -> Result<Arc<dyn FileSystem>> {
+        // Mounting the file system exposes the instance of the current IPC namespace.
+        let current_task = Task::current().unwrap();
+        let thread_local = current_task.as_thread_local().unwrap();
+        let ns_proxy = thread_local.borrow_ns_proxy();
+        let ipc_ns = ns_proxy.unwrap().ipc_ns();
+
+        Ok(ipc_ns.mqueue_mount().fs().clone())
+    }
+
+    fn sysnode(&self) -> Option<Arc<dyn aster_systree::SysNode>> {
+        None
+    }
+}
+
+pub(super) fn init() {
+    crate::fs::vfs::registry::register(&MqueueFsType).unwrap();
+}
+
+/// Checks the attributes specified to create a message queue.
+///
+/// Returns the maximum number of messages and the maximum message size on success.
+fn check_attr(attr: &MqAttr, is_privileged: bool) -> Result<(usize, usize)> {
+    if attr.mq_maxmsg <= 0 || attr.mq_msgsize <= 0 {
+        return_errno_with_message!(Errno::EINVAL, "the queue attributes are not positive");
+    }
+
+    let (max_msgs, max_msg_size) = (attr.mq_maxmsg as usize, attr.mq_msgsize as usize);
+    let (msg_limit, msgsize_limit) = if is_privileged {
+        (HARD_MSGMAX, HARD_MSGSIZEMAX)
+    } else {
+        (MSG_MAX, MSGSIZE_MAX)
+    };
+    if max_msgs > msg_limit || max_msg_size > msgsize_limit {
+        return_errno_with_message!(Errno::EINVAL, "the queue attributes exceed the limits");
+    }
+
+    // TODO: Charge the queue against `RLIMIT_MSGQUEUE` of the creator.
+
+    Ok((max_msgs, max_msg_size))
+}
+
+fn has_sys_resource_capability() -> bool {
+    let Some(current_thread) = Thread::current() else {
+        return false;
+    };
+    let Some(posix_thread) = current_thread.as_posix_thread() else {
+        return false;
+    };
+
+    lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+        UserNamespace::get_init_singleton(),
+        posix_thread,
+        CapSet::SYS_RESOURCE,
+    ))
+    .is_ok()
+}
+
+fn current_fs_ids() -> (Uid, Gid) {
+    Thread::current()
+        .and_then(|thread| {
+            let posix_thread = thread.as_posix_thread()?;
+            let credentials = posix_thread.credentials();
+            Some((credentials.fsuid(), credentials.fsgid()))
+        })
+        .unwrap_or((Uid::new_root(), Gid::new_root()))
+}
+
+fn now() -> Duration {
+    RealTimeCoarseClock::get().read_time()
+}
+
+struct RootInode {
+    queues: RwLock<SlotVec<(String, Arc<dyn Inode>)>>,
+    metadata: RwLock<Metadata>,
+    extension: Extension,
+    fs: Weak<MqueueFs>,
+}
+
+impl RootInode {
+    fn new(fs: Weak<MqueueFs>, sb: &SuperBlock) -> Arc<Self> {
+        Arc::new(Self {
+            queues: RwLock::new(SlotVec::new()),
+            metadata: RwLock::new(Metadata::new_dir(
+                ROOT_INO,
+                chmod!(InodeMode::S_ISVTX, a+rwx),
+                BLOCK_SIZE,
+                sb.container_dev_id,
+            )),
+            extension: Extension::new(),
+            fs,
+        })
+    }
+}
+
+impl FileOps for RootInode {
+    fn read_at(
+        &self,
+        _offset: usize,
+        _writer: &mut VmWriter,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        Err(Error::new(Errno::EISDIR))
+    }
+
+    fn write_at(
+        &self,
+        _offset: usize,
+        _reader: &mut VmReader,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        Err(Error::new(Errno::EISDIR))
+    }
+
+    fn readdir_at(&self, offset: usize, visitor: &mut dyn DirentVisitor) -> Result<usize> {
+        let try_readdir = |offset: &mut usize, visitor: &mut dyn DirentVisitor| -> Result<()> {
+            // Read the 2 special entries.
+            if *offset == 0 {
+                visitor.visit(".", self.ino(), self.type_(), *offset)?;
+                *offset += 1;
+            }
+            if *offset == 1 {
+                visitor.visit("..", self.ino(), self.type_(), *offset)?;
+                *offset += 1;
+            }
+
+            // Read the queues.
+            let queues = self.queues.read();
+            let start_offset = *offset;
+            for (idx, (name, node)) in queues
+                .idxes_and_items()
+                .map(|(idx, (name, node))| (idx + 2, (name, node)))
+                .skip_while(|(idx, _)| idx < &start_offset)
+            {
+                visitor.visit(name.as_ref(), node.ino(), node.type_(), idx)?;
+                *offset = idx + 1;
+            }
+            Ok(())
+        };
+
+        let mut iterate_offset = offset;
+        match try_readdir(&mut iterate_offset, visitor) {
+            Err(e) if offset == iterate_offset => Err(e),
+            _ => Ok(iterate_offset - offset),
+        }
+    }
+}
+
+impl Inode for RootInode {
+    fn size(&self) -> usize {
+        self.metadata.read().size
+    }
+
+    fn resize(&self, new_size: usize) -> Result<()> {
+        Err(Error::new(Errno::EISDIR))
+    }
+
+    fn metadata(&self) -> Result<Metadata> {
+        Ok(*self.metadata.read())
+    }
+
+    fn extension(&self) -> &Extension {
+        &self.extension
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino as _
+    }
+
+    fn type_(&self) -> InodeType {
+        self.metadata.read().type_
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().last_access_at
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().last_access_at = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().last_modify_at
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().last_modify_at = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().last_meta_change_at
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().last_meta_change_at = time;
+    }
+
+    fn create(&self, name: &str, type_: InodeType, mode: InodeMode) -> Result<Arc<dyn Inode>> {
+        if type_ != InodeType::File {
+            return_errno_with_message!(
+                Errno::EPERM,
+                "only message queues can be created in mqueue"
+            );
+        }
+
+        let fs = self.fs.upgrade().unwrap();
+        fs.create_queue(name, mode, None)?;
+        self.lookup(name)
+    }
+
+    fn mknod(&self, name: &str, mode: InodeMode, type_: MknodType) -> Result<Arc<dyn Inode>> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn link(&self, old: &Arc<dyn Inode>, name: &str) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn unlink(&self, name: &str) -> Result<()> {
+        let (_, queue) = self
+            .queues
+            .write()
+            .remove_entry_by_name(name)
+            .ok_or_else(|| Error::with_message(Errno::ENOENT, "the queue does not exist"))?;
+        // The queue remains usable through the descriptors that are still open.
+        queue.downcast_ref::<MqueueInode>().unwrap().mark_unlinked();
+
+        let now = now();
+        let mut metadata = self.metadata.write();
+        metadata.last_modify_at = now;
+        metadata.last_meta_change_at = now;
+
+        Ok(())
+    }
+
+    fn rmdir(&self, name: &str) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn lookup(&self, name: &str) -> Result<Arc<dyn Inode>> {
+        let inode = match name {
+            "." | ".." => self.fs().root_inode(),
+            name => self
+                .queues
+                .read()
+                .find_entry_by_name(name)
+                .cloned()
+                .ok_or_else(|| Error::with_message(Errno::ENOENT, "the queue does not exist"))?,
+        };
+        Ok(inode)
+    }
+
+    fn rename(
+        &self,
+        _old_name: &str,
+        _old_inode: &Arc<dyn Inode>,
+        _new_dir_inode: &Arc<dyn Inode>,
+        _new_name: &str,
+        _replaced_inode: Option<&Arc<dyn Inode>>,
+        _mode: RenameMode,
+    ) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+
+    fn revalidation_policy(&self) -> RevalidationPolicy {
+        RevalidationPolicy::REVALIDATE_EXISTS | RevalidationPolicy::REVALIDATE_ABSENT
+    }
+
+    fn revalidate_exists(&self, _name: &str, _child: &dyn Inode) -> bool {
+        // Message queues are created by `mq_open` without going through the dentries of the
+        // mounts that are visible to user space. Always retry lookup so that no mount caches a
+        // stale entry.
+        false
+    }
+
+    fn revalidate_absent(&self, _name: &str) -> bool {
+        false
+    }
+}
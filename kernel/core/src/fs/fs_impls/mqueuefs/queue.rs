@@ -0,0 +1,623 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#![expect(unused_variables)]
+
+use core::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use aster_util::printer::VmPrinter;
+use ostd::sync::WaitQueue;
+
+use super::{BLOCK_SIZE, MqueueFs, current_fs_ids, now};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file::{
+            AccessMode, FileLike, InodeHandle, InodeMode, InodeType, PerOpenFileOps, StatusFlags,
+        },
+        vfs::{
+            file_system::{FileSystem, SuperBlock},
+            inode::{Extension, FileOps, Inode, Metadata},
+        },
+    },
+    prelude::*,
+    process::{
+        Gid, Pid, Process, Uid,
+        posix_thread::AsPosixThread,
+        signal::{
+            Pause, PollHandle, Pollable, Pollee,
+            c_types::{SigNotify, siginfo_t, sigval_t},
+            constants::SI_MESGQ,
+            sig_num::SigNum,
+            signals::raw::RawSignal,
+        },
+    },
+    thread::Thread,
+    time::wait::ManagedTimeout,
+};
+
+/// The maximum priority of messages, exclusive.
+pub(crate) const MQ_PRIO_MAX: u32 = 32768;
+
+/// The size of message queue files.
+///
+/// This is the maximum length of the status line that can be read from a message queue file.
+const FILENT_SIZE: usize = 80;
+
+/// The attributes of a message queue (`struct mq_attr`).
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod)]
+pub(crate) struct MqAttr {
+    pub(crate) mq_flags: i64,
+    pub(crate) mq_maxmsg: i64,
+    pub(crate) mq_msgsize: i64,
+    pub(crate) mq_curmsgs: i64,
+    _reserved: [i64; 4],
+}
+
+/// The notification requested by `mq_notify`.
+#[derive(Clone, Copy)]
+pub(crate) enum MqNotify {
+    /// Nothing is delivered (`SIGEV_NONE`).
+    None,
+    /// A signal is delivered (`SIGEV_SIGNAL`).
+    Signal { signo: SigNum, value: sigval_t },
+}
+
+/// A registered notification.
+struct Notification {
+    owner: Weak<Process>,
+    owner_pid: Pid,
+    notify: MqNotify,
+}
+
+impl Notification {
+    /// Returns whether the notification is registered by `process`.
+    fn is_owned_by(&self, process: &Arc<Process>) -> bool {
+        self.owner.as_ptr() == Arc::as_ptr(process)
+    }
+
+    /// Returns whether the notification is still in effect.
+    ///
+    /// A notification no longer takes effect after its owner has been reaped.
+    fn is_active(&self) -> bool {
+        self.owner.strong_count() > 0
+    }
+
+    /// Delivers the notification on behalf of the message sender.
+    fn deliver(self, ctx: &Context) {
+        let MqNotify::Signal { signo, value } = self.notify else {
+            return;
+        };
+        let Some(owner) = self.owner.upgrade() else {
+            return;
+        };
+
+        let mut info = siginfo_t::new(signo, SI_MESGQ);
+        info.set_pid_uid_by(ctx);
+        info.set_value(value);
+        owner.enqueue_signal(Box::new(RawSignal::new(info)));
+    }
+}
+
+/// A POSIX message queue, which is a regular file in the mqueue file system.
+pub(crate) struct MqueueInode {
+    /// Maximum number of messages
+    max_msgs: usize,
+    /// Maximum number of bytes of a message
+    max_msg_size: usize,
+    /// Inner
+    inner: SpinLock<MqueueInner>,
+    /// Wait queue for the senders that wait for space and the receivers that wait for messages
+    wait_queue: WaitQueue,
+    /// Number of the receivers that wait for messages
+    nr_waiting_receivers: AtomicUsize,
+    pollee: Pollee,
+    metadata: RwLock<Metadata>,
+    extension: Extension,
+    fs: Weak<MqueueFs>,
+    this: Weak<Self>,
+}
+
+struct MqueueInner {
+    /// Messages grouped by priority, each group in the order they are sent
+    messages: BTreeMap<u32, VecDeque<Box<[u8]>>>,
+    /// Total number of messages
+    nr_messages: usize,
+    /// Total number of bytes of the messages
+    nr_bytes: usize,
+    /// Notification registered by `mq_notify`
+    notification: Option<Notification>,
+}
+
+impl MqueueInner {
+    fn push(&mut self, message: Box<[u8]>, priority: u32) {
+        self.nr_messages += 1;
+        self.nr_bytes += message.len();
+        self.messages
+            .entry(priority)
+            .or_default()
+            .push_back(message);
+    }
+
+    /// Pops the oldest message of the highest priority.
+    fn pop(&mut self) -> Option<(Box<[u8]>, u32)> {
+        let mut entry = self.messages.last_entry()?;
+        let priority = *entry.key();
+        let message = entry.get_mut().pop_front().unwrap();
+        if entry.get().is_empty() {
+            entry.remove();
+        }
+
+        self.nr_messages -= 1;
+        self.nr_bytes -= message.len();
+        Some((message, priority))
+    }
+
+    fn active_notification(&self) -> Option<&Notification> {
+        self.notification
+            .as_ref()
+            .filter(|notification| notification.is_active())
+    }
+}
+
+impl MqueueInode {
+    pub(super) fn new(
+        ino: u64,
+        mode: InodeMode,
+        max_msgs: usize,
+        max_msg_size: usize,
+        fs: Weak<MqueueFs>,
+        sb: &SuperBlock,
+    ) -> Arc<Self> {
+        let (uid, gid) = current_fs_ids();
+        let mut metadata = Metadata::new_file(ino, mode, BLOCK_SIZE, sb.container_dev_id);
+        metadata.size = FILENT_SIZE;
+        metadata.uid = uid;
+        metadata.gid = gid;
+
+        Arc::new_cyclic(|weak_self| Self {
+            max_msgs,
+            max_msg_size,
+            inner: SpinLock::new(MqueueInner {
+                messages: BTreeMap::new(),
+                nr_messages: 0,
+                nr_bytes: 0,
+                notification: None,
+            }),
+            wait_queue: WaitQueue::new(),
+            nr_waiting_receivers: AtomicUsize::new(0),
+            pollee: Pollee::new(),
+            metadata: RwLock::new(metadata),
+            extension: Extension::new(),
+            fs,
+            this: weak_self.clone(),
+        })
+    }
+
+    /// Marks the queue as unlinked from the file system.
+    pub(super) fn mark_unlinked(&self) {
+        let mut metadata = self.metadata.write();
+        metadata.nr_hard_links = 0;
+        metadata.last_meta_change_at = now();
+    }
+
+    /// Returns the maximum number of bytes of a message.
+    pub(crate) fn max_msg_size(&self) -> usize {
+        self.max_msg_size
+    }
+
+    /// Returns the attributes of the queue.
+    ///
+    /// The returned `mq_flags` is always zero because the flags belong to the open file
+    /// descriptions of the queue.
+    pub(crate) fn attr(&self) -> MqAttr {
+        let nr_messages = self.inner.lock().nr_messages;
+
+        MqAttr {
+            mq_flags: 0,
+            mq_maxmsg: self.max_msgs as i64,
+            mq_msgsize: self.max_msg_size as i64,
+            mq_curmsgs: nr_messages as i64,
+            _reserved: [0; 4],
+        }
+    }
+
+    /// Sends a message of `priority` to the queue.
+    ///
+    /// The caller must ensure that the message is not longer than [`Self::max_msg_size`].
+    ///
+    /// If the queue is full, the method waits for space until `timeout` expires, unless
+    /// `is_nonblocking` is true.
+    pub(crate) fn send(
+        &self,
+        message: Box<[u8]>,
+        priority: u32,
+        is_nonblocking: bool,
+        timeout: Option<ManagedTimeout<'static>>,
+        ctx: &Context,
+    ) -> Result<()> {
+        debug_assert!(priority < MQ_PRIO_MAX);
+        debug_assert!(message.len() <= self.max_msg_size);
+
+        let mut message = Some(message);
+        let mut try_send = || {
+            let mut inner = self.inner.lock();
+            if inner.nr_messages >= self.max_msgs {
+                return None;
+            }
+
+            let was_empty = inner.nr_messages == 0;
+            inner.push(message.take().unwrap(), priority);
+
+            // The notification is delivered only if the queue becomes non-empty and no receivers
+            // are waiting for the message.
+            if was_empty && self.nr_waiting_receivers.load(Ordering::Relaxed) == 0 {
+                Some(inner.notification.take())
+            } else {
+                Some(None)
+            }
+        };
+
+        let notification = if let Some(notification) = try_send() {
+            notification
+        } else if is_nonblocking {
+            return_errno_with_message!(Errno::EAGAIN, "the queue is full");
+        } else {
+            self.wait_queue
+                .pause_until_or_timeout(&mut try_send, timeout)
+                .map_err(map_timeout_error)?
+        };
+
+        self.update_times(true);
+        self.wait_queue.wake_all();
+        self.pollee.notify(IoEvents::IN | IoEvents::RDNORM);
+
+        if let Some(notification) = notification {
+            notification.deliver(ctx);
+        }
+
+        Ok(())
+    }
+
+    /// Receives the oldest message of the highest priority from the queue.
+    ///
+    /// If the queue is empty, the method waits for messages until `timeout` expires, unless
+    /// `is_nonblocking` is true.
+    pub(crate) fn receive(
+        &self,
+        max_len: usize,
+        is_nonblocking: bool,
+        timeout: Option<ManagedTimeout<'static>>,
+    ) -> Result<(Box<[u8]>, u32)> {
+        if max_len < self.max_msg_size {
+            return_errno_with_message!(
+                Errno::EMSGSIZE,
+                "the buffer is smaller than the maximum message size"
+            );
+        }
+
+        let try_receive = || self.inner.lock().pop();
+
+        let received = if let Some(received) = try_receive() {
+            received
+        } else if is_nonblocking {
+            return_errno_with_message!(Errno::EAGAIN, "the queue is empty");
+        } else {
+            self.nr_waiting_receivers.fetch_add(1, Ordering::Relaxed);
+            let res = self.wait_queue.pause_until_or_timeout(try_receive, timeout);
+            self.nr_waiting_receivers.fetch_sub(1, Ordering::Relaxed);
+            res.map_err(map_timeout_error)?
+        };
+
+        self.update_times(false);
+        self.wait_queue.wake_all();
+        self.pollee.notify(IoEvents::OUT);
+
+        Ok(received)
+    }
+
+    /// Registers the notification on behalf of `process`.
+    ///
+    /// If `notify` is `None`, the notification previously registered by `process` is removed.
+    pub(crate) fn set_notification(
+        &self,
+        notify: Option<MqNotify>,
+        process: &Arc<Process>,
+    ) -> Result<()> {
+        let mut inner = self.inner.lock();
+
+        let Some(notify) = notify else {
+            if inner
+                .notification
+                .as_ref()
+                .is_some_and(|notification| notification.is_owned_by(process))
+            {
+                inner.notification = None;
+            }
+            return Ok(());
+        };
+
+        if inner.active_notification().is_some() {
+            return_errno_with_message!(
+                Errno::EBUSY,
+                "another process has registered for notification"
+            );
+        }
+
+        inner.notification = Some(Notification {
+            owner: Arc::downgrade(process),
+            owner_pid: process.pid(),
+            notify,
+        });
+
+        Ok(())
+    }
+
+    fn update_times(&self, is_modified: bool) {
+        let now = now();
+        let mut metadata = self.metadata.write();
+        metadata.last_access_at = now;
+        if is_modified {
+            metadata.last_modify_at = now;
+        }
+        metadata.last_meta_change_at = now;
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let nr_messages = self.inner.lock().nr_messages;
+
+        let mut events = IoEvents::empty();
+        if nr_messages > 0 {
+            events |= IoEvents::IN | IoEvents::RDNORM;
+        }
+        if nr_messages < self.max_msgs {
+            events |= IoEvents::OUT;
+        }
+        events
+    }
+
+    /// Writes the status line of the queue.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.18/source/ipc/mqueue.c#L614>.
+    fn read_status(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let (nr_bytes, notify, signo, notify_pid) = {
+            let inner = self.inner.lock();
+            let (notify, signo, notify_pid) = match inner.active_notification() {
+                None => (0, 0, 0),
+                Some(Notification {
+                    owner_pid,
+                    notify: MqNotify::None,
+                    ..
+                }) => (SigNotify::SIGEV_NONE as i32, 0, *owner_pid),
+                Some(Notification {
+                    owner_pid,
+                    notify: MqNotify::Signal { signo, .. },
+                    ..
+                }) => (
+                    SigNotify::SIGEV_SIGNAL as i32,
+                    signo.as_u8() as i32,
+                    *owner_pid,
+                ),
+            };
+            (inner.nr_bytes, notify, signo, notify_pid)
+        };
+
+        let mut printer = VmPrinter::new_skip(writer, offset);
+        writeln!(
+            printer,
+            "QSIZE:{:<10} NOTIFY:{:<5} SIGNO:{:<5} NOTIFY_PID:{:<6}",
+            nr_bytes, notify, signo, notify_pid
+        )?;
+
+        Ok(printer.bytes_written())
+    }
+}
+
+fn map_timeout_error(err: Error) -> Error {
+    if err.error() == Errno::ETIME {
+        Error::with_message(Errno::ETIMEDOUT, "the timeout expired")
+    } else {
+        err
+    }
+}
+
+impl Pollable for MqueueInode {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl Drop for MqueueInode {
+    fn drop(&mut self) {
+        if let Some(fs) = self.fs.upgrade() {
+            fs.nr_queues.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+impl FileOps for MqueueInode {
+    fn read_at(
+        &self,
+        offset: usize,
+        writer: &mut VmWriter,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        self.read_status(offset, writer)
+    }
+
+    fn write_at(
+        &self,
+        _offset: usize,
+        _reader: &mut VmReader,
+        _status_flags: StatusFlags,
+    ) -> Result<usize> {
+        return_errno_with_message!(Errno::EINVAL, "message queue files cannot be written");
+    }
+}
+
+impl Inode for MqueueInode {
+    fn size(&self) -> usize {
+        self.metadata.read().size
+    }
+
+    fn resize(&self, new_size: usize) -> Result<()> {
+        Err(Error::new(Errno::EPERM))
+    }
+
+    fn metadata(&self) -> Result<Metadata> {
+        Ok(*self.metadata.read())
+    }
+
+    fn extension(&self) -> &Extension {
+        &self.extension
+    }
+
+    fn ino(&self) -> u64 {
+        self.metadata.read().ino as _
+    }
+
+    fn type_(&self) -> InodeType {
+        self.metadata.read().type_
+    }
+
+    fn mode(&self) -> Result<InodeMode> {
+        Ok(self.metadata.read().mode)
+    }
+
+    fn set_mode(&self, mode: InodeMode) -> Result<()> {
+        self.metadata.write().mode = mode;
+        Ok(())
+    }
+
+    fn owner(&self) -> Result<Uid> {
+        Ok(self.metadata.read().uid)
+    }
+
+    fn set_owner(&self, uid: Uid) -> Result<()> {
+        self.metadata.write().uid = uid;
+        Ok(())
+    }
+
+    fn group(&self) -> Result<Gid> {
+        Ok(self.metadata.read().gid)
+    }
+
+    fn set_group(&self, gid: Gid) -> Result<()> {
+        self.metadata.write().gid = gid;
+        Ok(())
+    }
+
+    fn atime(&self) -> Duration {
+        self.metadata.read().last_access_at
+    }
+
+    fn set_atime(&self, time: Duration) {
+        self.metadata.write().last_access_at = time;
+    }
+
+    fn mtime(&self) -> Duration {
+        self.metadata.read().last_modify_at
+    }
+
+    fn set_mtime(&self, time: Duration) {
+        self.metadata.write().last_modify_at = time;
+    }
+
+    fn ctime(&self) -> Duration {
+        self.metadata.read().last_meta_change_at
+    }
+
+    fn set_ctime(&self, time: Duration) {
+        self.metadata.write().last_meta_change_at = time;
+    }
+
+    fn fs(&self) -> Arc<dyn FileSystem> {
+        self.fs.upgrade().unwrap()
+    }
+
+    fn open(
+        &self,
+        access_mode: AccessMode,
+        status_flags: StatusFlags,
+    ) -> Option<Result<Box<dyn PerOpenFileOps>>> {
+        let queue = self.this.upgrade().unwrap();
+        Some(Ok(Box::new(MqueueFile { queue })))
+    }
+}
+
+/// An open file description of a message queue.
+pub(crate) struct MqueueFile {
+    queue: Arc<MqueueInode>,
+}
+
+impl MqueueFile {
+    /// Returns the open message queue if `file` is a message queue descriptor.
+    pub(crate) fn from_file(file: &dyn FileLike) -> Result<&Self> {
+        file.downcast_ref::<InodeHandle>()
+            .map(|inode_handle| inode_handle.downcast_open_file::<Self>())
+            .transpose()?
+            .flatten()
+            .ok_or_else(|| Error::with_message(Errno::EBADF, "the file is not a message queue"))
+    }
+
+    /// Returns the message queue.
+    pub(crate) fn queue(&self) -> &Arc<MqueueInode> {
+        &self.queue
+    }
+}
+
+impl PerOpenFileOps for MqueueFile {
+    fn check_seekable(&self) -> Result<()> {
+        Ok(())
+    }
+
+    fn is_offset_aware(&self) -> bool {
+        true
+    }
+}
+
+impl Pollable for MqueueFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.queue.poll(mask, poller)
+    }
+}
+
+impl FileOps for MqueueFile {
+    fn read_at(
+        &self,
+        offset: usize,
+        writer: &mut VmWriter,
+        status_flags: StatusFlags,
+    ) -> Result<usize> {
+        self.queue.read_at(offset, writer, status_flags)
+    }
+
+    fn write_at(
+        &self,
+        offset: usize,
+        reader: &mut VmReader,
+        status_flags: StatusFlags,
+    ) -> Result<usize> {
+        self.queue.write_at(offset, reader, status_flags)
+    }
+}
+
+impl Drop for MqueueFile {
+    fn drop(&mut self) {
+        // Closing the queue removes the notification registered by the closing process.
+        //
+        // FIXME: Linux removes the notification whenever a descriptor of the queue is closed by
+        // the owner, but we can only do this when the open file description is dropped.
+        let Some(current_thread) = Thread::current() else {
+            return;
+        };
+        let Some(posix_thread) = current_thread.as_posix_thread() else {
+            return;
+        };
+        let _ = self.queue.set_notification(None, &posix_thread.process());
+    }
+}
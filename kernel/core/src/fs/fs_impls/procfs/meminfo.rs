@@ -15,6 +15,7 @@ use crate::{
         vfs::inode::Inode,
     },
     prelude::*,
+    vm::{page_cache, swap},
 };
 
 /// Represents the inode at `/proc/meminfo`.
@@ -35,22 +36,35 @@ impl ProcFileOps for MemInfoFileOps {
 
         // The total amount of physical memory available to the system.
         let total = crate::vm::mem_total();
+        // The amount of physical memory that is not used by the system.
+        let free = osdk_frame_allocator::load_total_free_size();
+        // The amount of physical memory used by the page caches.
+        let cached = page_cache::nr_cached_pages() * PAGE_SIZE;
         // An estimation of how much memory is available for starting new
-        // applications, without disk operations.
-        let available = osdk_frame_allocator::load_total_free_size();
+        // applications, without swapping. Cached pages can be evicted when
+        // memory is allocated (dirty ones after being written back).
+        let available = free + cached;
 
         // Convert the values to KiB.
         let total = total / 1024;
+        let free = free / 1024;
+        let cached = cached / 1024;
         let available = available / 1024;
 
-        // Available memory should include both free memory and cached pages that can be
-        // immediately evicted from main memory. Currently, no pages can be evicted when memory is
-        // allocated, resulting in the two values being reported as the same.
         writeln!(printer, "MemTotal:\t{} kB", total)?;
-        writeln!(printer, "MemFree:\t{} kB", available)?;
+        writeln!(printer, "MemFree:\t{} kB", free)?;
         writeln!(printer, "MemAvailable:\t{} kB", available)?;
-        writeln!(printer, "SwapTotal:\t0 kB")?;
-        writeln!(printer, "SwapFree:\t0 kB")?;
+        writeln!(printer, "Cached:\t{} kB", cached)?;
+
+        let (swap_total, swap_used) = swap::area_stats()
+            .iter()
+            .fold((0, 0), |(total, used), stat| {
+                (total + stat.nr_pages, used + stat.nr_used)
+            });
+        let swap_total = swap_total * (PAGE_SIZE / 1024);
+        let swap_free = swap_total - swap_used * (PAGE_SIZE / 1024);
+        writeln!(printer, "SwapTotal:\t{} kB", swap_total)?;
+        writeln!(printer, "SwapFree:\t{} kB", swap_free)?;
 
         let slab = crate::vm::mem_slab() / 1024;
         writeln!(printer, "Slab:\t{} kB", slab)?;
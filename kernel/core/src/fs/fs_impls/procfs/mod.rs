@@ -16,7 +16,9 @@ use self::{
     mounts::MountsSymOps,
     pid::{PidDirOps, TidDirOps},
     self_::SelfSymOps,
+    swaps::SwapsFileOps,
     sys::SysDirOps,
+    sysvipc::SysvIpcDirOps,
     thread_self::ThreadSelfSymOps,
     uptime::UptimeFileOps,
     version::VersionFileOps,
@@ -35,7 +37,7 @@ use crate::{
     },
     prelude::*,
     process::{
-        Pid,
+        Pid, PidNamespace,
         pid_table::{self, PidEntryType},
     },
 };
@@ -49,7 +51,9 @@ mod mounts;
 mod pid;
 mod self_;
 mod stat;
+mod swaps;
 mod sys;
+mod sysvipc;
 mod template;
 mod thread_self;
 mod uptime;
@@ -73,19 +77,24 @@ const BLOCK_SIZE: usize = 1024;
 struct ProcFs {
     _anon_device_id: AnonDeviceId,
     sb: SuperBlock,
+    /// The PID namespace of the task that mounts the file system.
+    ///
+    /// The PIDs in the file system are always shown in this namespace, regardless of the reader.
+    pid_ns: Arc<PidNamespace>,
     root: Arc<dyn Inode>,
     inode_allocator: AtomicU64,
     fs_event_subscriber_stats: FsEventSubscriberStats,
 }
 
 impl ProcFs {
-    pub(self) fn new() -> Arc<Self> {
+    pub(self) fn new(pid_ns: Arc<PidNamespace>) -> Arc<Self> {
         let anon_device_id = AnonDeviceId::acquire().expect("no device ID is available for procfs");
         let sb = SuperBlock::new(PROC_MAGIC, BLOCK_SIZE, NAME_MAX, anon_device_id.id());
         Arc::new_cyclic(|weak_fs| Self {
             _anon_device_id: anon_device_id,
             sb: sb.clone(),
-            root: RootDirOps::new_inode(weak_fs.clone(), &sb),
+            pid_ns: pid_ns.clone(),
+            root: RootDirOps::new_inode(weak_fs.clone(), &sb, pid_ns),
             inode_allocator: AtomicU64::new(PROC_ROOT_INO + 1),
             fs_event_subscriber_stats: FsEventSubscriberStats::new(),
         })
@@ -96,6 +105,12 @@ impl ProcFs {
     }
 }
 
+/// Returns the PID namespace of the procfs instance that the inode belongs to.
+fn pid_ns_of(inode: &Weak<dyn Inode>) -> Arc<PidNamespace> {
+    let fs = inode.upgrade().unwrap().fs();
+    fs.downcast_ref::<ProcFs>().unwrap().pid_ns.clone()
+}
+
 impl FileSystem for ProcFs {
     fn name(&self) -> &'static str {
         "proc"
@@ -132,7 +147,8 @@ impl FsType for ProcFsType {
     }
 
     fn create(&self, _fs_creation_ctx: &mut FsCreationCtx) -> Result<Arc<dyn FileSystem>> {
-        Ok(ProcFs::new())
+        // Like Linux, the file system shows the PIDs in the PID namespace of the mounter.
+        Ok(ProcFs::new(PidNamespace::current()))
     }
 
     fn sysnode(&self) -> Option<Arc<dyn aster_systree::SysNode>> {
@@ -141,13 +157,19 @@ impl FsType for ProcFsType {
 }
 
 /// Represents the inode at `/proc`.
-struct RootDirOps;
+struct RootDirOps {
+    pid_ns: Arc<PidNamespace>,
+}
 
 impl RootDirOps {
-    pub(crate) fn new_inode(fs: Weak<ProcFs>, sb: &SuperBlock) -> Arc<dyn Inode> {
+    pub(crate) fn new_inode(
+        fs: Weak<ProcFs>,
+        sb: &SuperBlock,
+        pid_ns: Arc<PidNamespace>,
+    ) -> Arc<dyn Inode> {
         // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/proc/root.c#L368>
         let fs: Weak<dyn FileSystem> = fs;
-        ProcDir::new_root(Self, fs, PROC_ROOT_INO, sb, mkmod!(a+rx))
+        ProcDir::new_root(Self { pid_ns }, fs, PROC_ROOT_INO, sb, mkmod!(a+rx))
     }
 
     const STATIC_ENTRIES: &'static [StaticEntry] = &[
@@ -163,7 +185,9 @@ impl RootDirOps {
         ("mounts", InodeType::SymLink, MountsSymOps::new_inode),
         ("self", InodeType::SymLink, SelfSymOps::new_inode),
         ("stat", InodeType::File, StatFileOps::new_inode),
+        ("swaps", InodeType::File, SwapsFileOps::new_inode),
         ("sys", InodeType::Dir, SysDirOps::new_inode),
+        ("sysvipc", InodeType::Dir, SysvIpcDirOps::new_inode),
         (
             "thread-self",
             InodeType::SymLink,
@@ -176,7 +200,7 @@ impl RootDirOps {
 
 impl ProcDirOps for RootDirOps {
     fn lookup_child(&self, this_dir: &ProcDir<Self>, name: &str) -> Result<Arc<dyn Inode>> {
-        if let Ok(pid) = name.parse::<Pid>() {
+        if let Some(pid) = parse_global_pid(&self.pid_ns, name) {
             let pid_entry = {
                 let pid_table = pid_table::pid_table_mut();
                 pid_table.get_entry(pid)
@@ -185,12 +209,16 @@ impl ProcDirOps for RootDirOps {
                 && let Some(type_) = pid_entry.type_()
             {
                 return Ok(match type_ {
-                    PidEntryType::Process => {
-                        PidDirOps::new_inode(pid_entry, this_dir.this_weak().clone())
-                    }
-                    PidEntryType::Thread => {
-                        TidDirOps::new_inode(pid_entry, this_dir.this_weak().clone())
-                    }
+                    PidEntryType::Process => PidDirOps::new_inode(
+                        pid_entry,
+                        self.pid_ns.clone(),
+                        this_dir.this_weak().clone(),
+                    ),
+                    PidEntryType::Thread => TidDirOps::new_inode(
+                        pid_entry,
+                        self.pid_ns.clone(),
+                        this_dir.this_weak().clone(),
+                    ),
                 });
             }
         }
@@ -215,13 +243,17 @@ impl ProcDirOps for RootDirOps {
         )?;
 
         // Collect PIDs before visiting entries, as `visit_fn` may copy data to user memory.
-        let process_pids = {
+        let mut process_pids = {
             let pid_table = pid_table::pid_table_mut();
             pid_table
                 .iter_processes()
-                .filter_map(|process| usize::try_from(process.pid()).ok())
+                .filter_map(|process| self.pid_ns.ns_id_of(process.pid()))
+                .filter_map(|pid| usize::try_from(pid).ok())
                 .collect::<Vec<_>>()
         };
+        // The PIDs in a non-initial PID namespace are not necessarily in the same order as the
+        // global PIDs.
+        process_pids.sort_unstable();
 
         visit_readdir_entries(
             keyed_readdir_entries(offset, FIRST_PID_OFFSET, process_pids, |process_pid| {
@@ -242,7 +274,16 @@ impl ProcDirOps for RootDirOps {
             return true;
         };
 
+        // The process may have exited, and its PID may have been reused by another process.
+        let Some(pid) = parse_global_pid(&self.pid_ns, name) else {
+            return false;
+        };
+
         if let Some(child) = child.downcast_ref::<ProcDir<PidDirOps>>() {
+            if child.inner().pid_entry().id() != pid {
+                return false;
+            }
+
             // If the child's `PidEntry` still has the associated process, it will not be removed
             // from the `PidTable` and remains alive.
             matches!(
@@ -250,6 +291,9 @@ impl ProcDirOps for RootDirOps {
                 Some(PidEntryType::Process)
             )
         } else if let Some(child) = child.downcast_ref::<ProcDir<TidDirOps>>() {
+            if child.inner().pid_entry().id() != pid {
+                return false;
+            }
             // If the child's `PidEntry` still has the associated thread, it will not be removed
             // from the `PidTable` and remains alive.
             matches!(
@@ -262,7 +306,7 @@ impl ProcDirOps for RootDirOps {
     }
 
     fn revalidate_absent(&self, name: &str) -> bool {
-        let Ok(pid) = name.parse::<Pid>() else {
+        let Some(pid) = parse_global_pid(&self.pid_ns, name) else {
             return true;
         };
 
@@ -275,5 +319,11 @@ impl ProcDirOps for RootDirOps {
     }
 }
 
+/// Parses a PID in the PID namespace and translates it to the global PID.
+fn parse_global_pid(pid_ns: &PidNamespace, name: &str) -> Option<Pid> {
+    let pid = name.parse::<Pid>().ok()?;
+    pid_ns.global_id_of(pid)
+}
+
 type StaticEntry = StaticDirEntry<fn(Weak<dyn Inode>) -> Arc<dyn Inode>>;
 type StaticEntryWithOps<T> = StaticDirEntry<fn(&T, Weak<dyn Inode>) -> Arc<dyn Inode>>;
@@ -0,0 +1,97 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_util::printer::VmPrinter;
+
+use super::{PidDirOps, TidDirOps};
+use crate::{
+    fs::{
+        file::mkmod,
+        procfs::template::{ProcFile, ProcFileOps},
+        vfs::inode::Inode,
+    },
+    prelude::*,
+    process::coredump::CoreDumpFilter,
+    thread::Thread,
+};
+
+/// Represents the inode at `/proc/[pid]/coredump_filter`.
+pub(super) struct CoredumpFilterFileOps(TidDirOps);
+
+impl CoredumpFilterFileOps {
+    pub(super) fn new_inode(dir: &PidDirOps, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/proc/base.c>
+        ProcFile::new(Self(dir.tid_dir_ops().clone()), parent, mkmod!(a+r, u+w))
+    }
+}
+
+impl ProcFileOps for CoredumpFilterFileOps {
+    fn owner_thread(&self) -> Option<Arc<Thread>> {
+        self.0.thread()
+    }
+
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let mut printer = VmPrinter::new_skip(writer, offset);
+
+        let Some(process) = self.0.process() else {
+            return_errno_with_message!(Errno::ESRCH, "the process does not exist");
+        };
+
+        // Like Linux, the file is empty if the process has exited.
+        let vmar_guard = process.lock_vmar();
+        if let Some(vmar) = vmar_guard.as_ref() {
+            let filter = vmar.process_vm().coredump_filter();
+            writeln!(printer, "{:08x}", filter.bits())?;
+        }
+
+        Ok(printer.bytes_written())
+    }
+
+    fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
+        /// Worst case buffer size needed for holding a `u32` in any base.
+        ///
+        /// The longest possible string is `"037777777777\n\0"`, whose length is 14 bytes.
+        const BUF_SIZE_U32: usize = 14;
+
+        let (cstr, read_bytes) = reader.read_cstring_until_end(BUF_SIZE_U32 - 1)?;
+        let val = cstr
+            .to_str()
+            .ok()
+            .and_then(|str| parse_u32_auto_radix(str.trim_end_matches('\n')))
+            .ok_or_else(|| {
+                Error::with_message(Errno::EINVAL, "the value is not a valid integer")
+            })?;
+
+        let Some(process) = self.0.process() else {
+            return_errno_with_message!(Errno::ESRCH, "the process does not exist");
+        };
+        let vmar_guard = process.lock_vmar();
+        let Some(vmar) = vmar_guard.as_ref() else {
+            return_errno_with_message!(Errno::ESRCH, "the process has exited");
+        };
+        // Unknown bits are silently ignored, just like Linux.
+        vmar.process_vm()
+            .set_coredump_filter(CoreDumpFilter::from_bits_truncate(val));
+
+        Ok(read_bytes)
+    }
+}
+
+/// Parses a `u32` whose radix is determined by its prefix, like `kstrtouint(s, 0, ...)` in Linux.
+///
+/// A `0x` prefix indicates hexadecimal, a leading `0` indicates octal, and decimal is assumed
+/// otherwise.
+fn parse_u32_auto_radix(s: &str) -> Option<u32> {
+    let (digits, radix) = if let Some(hex) = s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        (hex, 16)
+    } else if s.len() > 1 && s.starts_with('0') {
+        (&s[1..], 8)
+    } else {
+        (s, 10)
+    };
+
+    // `from_str_radix` accepts a leading `+`, which Linux rejects.
+    if !digits.starts_with(|ch: char| ch.is_digit(radix)) {
+        return None;
+    }
+    u32::from_str_radix(digits, radix).ok()
+}
@@ -14,11 +14,16 @@ use crate::{
         vfs::inode::{Inode, RevalidationPolicy},
     },
     prelude::*,
-    process::pid_table::{PidEntry, PidEntryType},
+    process::{
+        PidNamespace,
+        pid_table::{PidEntry, PidEntryType},
+    },
     thread::Thread,
 };
 
+mod coredump_filter;
 mod task;
+mod timens_offsets;
 pub(super) use task::TidDirOps;
 
 /// Represents the inode at `/proc/[pid]`.
@@ -30,8 +35,12 @@ pub(super) struct PidDirOps(
 );
 
 impl PidDirOps {
-    pub(super) fn new_inode(pid_entry: Arc<PidEntry>, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
-        let this = Self(TidDirOps::new(pid_entry));
+    pub(super) fn new_inode(
+        pid_entry: Arc<PidEntry>,
+        pid_ns: Arc<PidNamespace>,
+        parent: Weak<dyn Inode>,
+    ) -> Arc<dyn Inode> {
+        let this = Self(TidDirOps::new(pid_entry, pid_ns));
         // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/proc/base.c#L3493>
         ProcDir::new(this, parent, mkmod!(a+rx))
     }
@@ -46,11 +55,21 @@ impl PidDirOps {
 
     const STATIC_ENTRIES: &[StaticEntryWithOps<PidDirOps>] = &[
         ("task", InodeType::Dir, TaskDirOps::new_inode),
+        (
+            "coredump_filter",
+            InodeType::File,
+            coredump_filter::CoredumpFilterFileOps::new_inode,
+        ),
         (
             "stat",
             InodeType::File,
             task::stat::StatFileOps::new_process_inode,
         ),
+        (
+            "timens_offsets",
+            InodeType::File,
+            timens_offsets::TimensOffsetsFileOps::new_inode,
+        ),
     ];
 }
 
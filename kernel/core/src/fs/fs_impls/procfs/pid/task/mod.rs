@@ -13,8 +13,8 @@ use crate::{
                 comm::CommFileOps, environ::EnvironFileOps, exe::ExeSymOps, fd::FdDirOps,
                 gid_map::GidMapFileOps, maps::MapsFileOps, mem::MemFileOps,
                 mountinfo::MountInfoFileOps, mounts::MountsFileOps, mountstats::MountStatsFileOps,
-                ns::NsDirOps, oom_score_adj::OomScoreAdjFileOps, stat::StatFileOps,
-                status::StatusFileOps, uid_map::UidMapFileOps,
+                ns::NsDirOps, oom_score::OomScoreFileOps, oom_score_adj::OomScoreAdjFileOps,
+                stat::StatFileOps, status::StatusFileOps, uid_map::UidMapFileOps,
             },
             template::{
                 ListedEntry, ProcDir, ProcDirOps, ReaddirEntry, keyed_readdir_entries,
@@ -25,7 +25,7 @@ use crate::{
         vfs::inode::{Inode, RevalidationPolicy},
     },
     prelude::*,
-    process::{Process, pid_table, pid_table::PidEntry, posix_thread::AsPosixThread},
+    process::{PidNamespace, Process, pid_table, pid_table::PidEntry, posix_thread::AsPosixThread},
     thread::{Thread, Tid},
 };
 
@@ -43,22 +43,23 @@ mod mountinfo;
 mod mounts;
 mod mountstats;
 mod ns;
+mod oom_score;
 mod oom_score_adj;
 pub(super) mod stat;
 mod status;
 mod uid_map;
 
 /// Represents the inode at `/proc/[pid]/task`.
-pub(super) struct TaskDirOps(Arc<PidEntry>);
+pub(super) struct TaskDirOps(TidDirOps);
 
 impl TaskDirOps {
     pub(super) fn new_inode(dir: &PidDirOps, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
         // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/proc/base.c#L3316>
-        ProcDir::new(Self(dir.pid_entry().clone()), parent, mkmod!(a+rx))
+        ProcDir::new(Self(dir.tid_dir_ops().clone()), parent, mkmod!(a+rx))
     }
 
     fn process(&self) -> Option<Arc<Process>> {
-        self.0.process_of_thread()
+        self.0.process()
     }
 }
 
@@ -66,19 +67,22 @@ impl TaskDirOps {
 #[derive(Clone)]
 pub(in procfs) struct TidDirOps {
     pid_entry: Arc<PidEntry>,
+    /// The PID namespace of the procfs instance, in which the IDs are shown.
+    pid_ns: Arc<PidNamespace>,
 }
 
 impl TidDirOps {
-    pub(super) fn new(pid_entry: Arc<PidEntry>) -> Self {
-        Self { pid_entry }
+    pub(super) fn new(pid_entry: Arc<PidEntry>, pid_ns: Arc<PidNamespace>) -> Self {
+        Self { pid_entry, pid_ns }
     }
 
     pub(in procfs) fn new_inode(
         pid_entry: Arc<PidEntry>,
+        pid_ns: Arc<PidNamespace>,
         parent: Weak<dyn Inode>,
     ) -> Arc<dyn Inode> {
         ProcDir::new(
-            Self { pid_entry },
+            Self { pid_entry, pid_ns },
             parent,
             // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/proc/base.c#L3796>
             mkmod!(a+rx),
@@ -89,6 +93,10 @@ impl TidDirOps {
         &self.pid_entry
     }
 
+    pub(super) fn pid_ns(&self) -> &Arc<PidNamespace> {
+        &self.pid_ns
+    }
+
     pub(super) fn process(&self) -> Option<Arc<Process>> {
         self.pid_entry.process_of_thread()
     }
@@ -122,6 +130,7 @@ impl TidDirOps {
         ("mountinfo", InodeType::File, MountInfoFileOps::new_inode),
         ("mountstats", InodeType::File, MountStatsFileOps::new_inode),
         ("ns", InodeType::Dir, NsDirOps::new_inode),
+        ("oom_score", InodeType::File, OomScoreFileOps::new_inode),
         (
             "oom_score_adj",
             InodeType::File,
@@ -194,7 +203,7 @@ impl ProcDirOps for TaskDirOps {
     }
 
     fn lookup_child(&self, this_dir: &ProcDir<Self>, name: &str) -> Result<Arc<dyn Inode>> {
-        let Ok(tid) = name.parse::<Tid>() else {
+        let Some(tid) = parse_global_tid(self.0.pid_ns(), name) else {
             return_errno_with_message!(Errno::ENOENT, "the name is not a valid TID");
         };
 
@@ -232,6 +241,7 @@ impl ProcDirOps for TaskDirOps {
 
         Ok(TidDirOps::new_inode(
             pid_entry,
+            self.0.pid_ns().clone(),
             this_dir.this_weak().clone(),
         ))
     }
@@ -245,13 +255,16 @@ impl ProcDirOps for TaskDirOps {
         };
 
         // Collect TIDs before visiting entries, as `visit_fn` may copy data to user memory.
-        let tids = process
+        let pid_ns = self.0.pid_ns();
+        let mut tids = process
             .tasks()
             .lock()
             .as_slice()
             .iter()
-            .filter_map(|task| usize::try_from(task.as_posix_thread().unwrap().tid()).ok())
+            .filter_map(|task| pid_ns.ns_id_of(task.as_posix_thread().unwrap().tid()))
+            .filter_map(|tid| usize::try_from(tid).ok())
             .collect::<Vec<_>>();
+        tids.sort_unstable();
 
         visit_readdir_entries(
             keyed_readdir_entries(offset, 2, tids, |tid| {
@@ -272,17 +285,22 @@ impl ProcDirOps for TaskDirOps {
             return true;
         }
 
+        // The thread may have exited, and its TID may have been reused by another thread.
+        let Some(tid) = parse_global_tid(self.0.pid_ns(), name) else {
+            return false;
+        };
+
         if let Some(child) = child.downcast_ref::<ProcDir<TidDirOps>>() {
             // If the child's `PidEntry` still has the associated thread, it will not be removed
             // from the `PidTable` and remains alive.
-            child.inner().pid_entry().type_().is_some()
+            child.inner().pid_entry().id() == tid && child.inner().pid_entry().type_().is_some()
         } else {
             false
         }
     }
 
     fn revalidate_absent(&self, name: &str) -> bool {
-        let Ok(tid) = name.parse::<Tid>() else {
+        let Some(tid) = parse_global_tid(self.0.pid_ns(), name) else {
             return true;
         };
 
@@ -298,3 +316,9 @@ impl ProcDirOps for TaskDirOps {
             .all(|task| task.as_posix_thread().unwrap().tid() != tid)
     }
 }
+
+/// Parses a TID in the PID namespace and translates it to the global TID.
+fn parse_global_tid(pid_ns: &PidNamespace, name: &str) -> Option<Tid> {
+    let tid = name.parse::<Tid>().ok()?;
+    pid_ns.global_id_of(tid)
+}
@@ -13,6 +13,7 @@ use crate::{
     },
     prelude::*,
     thread::Thread,
+    vm::oom::{OOM_SCORE_ADJ_MAX, OOM_SCORE_ADJ_MIN},
 };
 
 /// Represents the inode at `/proc/[pid]/task/[tid]/oom_score_adj` (and also `/proc/[pid]/oom_score_adj`).
@@ -45,7 +46,7 @@ impl ProcFileOps for OomScoreAdjFileOps {
     fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
         let (val, read_bytes) = read_i32_from(reader)?;
 
-        if !(OOM_SCORE_ADJ_MIN..=OOM_SCORE_ADJ_MAX).contains(&val) {
+        if !(OOM_SCORE_ADJ_MIN as i32..=OOM_SCORE_ADJ_MAX as i32).contains(&val) {
             return_errno_with_message!(Errno::EINVAL, "the OOM score adjustment is out of range");
         }
 
@@ -62,7 +63,3 @@ impl ProcFileOps for OomScoreAdjFileOps {
         Ok(read_bytes)
     }
 }
-
-// FIXME: Support OOM killer and move these constants to a more appropriate place.
-const OOM_SCORE_ADJ_MIN: i32 = -1000;
-const OOM_SCORE_ADJ_MAX: i32 = 1000;
@@ -133,7 +133,11 @@ impl ProcFileOps for StatFileOps {
         //
         // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/proc/array.c#L467-L681>
 
-        let pid = posix_thread.tid();
+        // The IDs are shown in the PID namespace of the procfs instance.
+        let pid_ns = self.dir.pid_ns();
+        let ns_id_of = |id| pid_ns.ns_id_of(id).unwrap_or(0);
+
+        let pid = ns_id_of(posix_thread.tid());
 
         let comm = posix_thread
             .thread_name()
@@ -152,9 +156,9 @@ impl ProcFileOps for StatFileOps {
                 SleepingState::StopByPtrace => 't',
             }
         };
-        let ppid = process.parent().pid();
-        let pgrp = process.pgid();
-        let session = process.sid();
+        let ppid = ns_id_of(process.parent().pid());
+        let pgrp = ns_id_of(process.pgid());
+        let session = ns_id_of(process.sid());
 
         let (tty_nr, tpgid) = if let Some(terminal) = process.terminal() {
             (
@@ -162,7 +166,7 @@ impl ProcFileOps for StatFileOps {
                 terminal
                     .job_control()
                     .foreground()
-                    .map(|pgrp| pgrp.pgid() as i64)
+                    .map(|pgrp| ns_id_of(pgrp.pgid()) as i64)
                     .unwrap_or(-1),
             )
         } else {
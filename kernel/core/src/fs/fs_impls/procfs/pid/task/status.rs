@@ -54,7 +54,9 @@ use crate::{
 /// - CapEff: Effective capabilities.
 /// - CapBnd: Bounding set.
 /// - CapAmb: Ambient capabilities.
+/// - NoNewPrivs: Whether the no-new-privileges flag is set.
 /// - Seccomp: Seccomp mode.
+/// - Seccomp_filters: Number of seccomp filters.
 /// - Cpus_allowed: CPUs allowed for this process.
 /// - Cpus_allowed_list: List of CPUs allowed for this process.
 /// - Mems_allowed: Memory nodes allowed for this process.
@@ -114,15 +116,19 @@ impl ProcFileOps for StatusFileOps {
         };
         writeln!(printer, "State:\t{}", state)?;
 
-        writeln!(printer, "Tgid:\t{}", process.pid())?;
-        writeln!(printer, "Pid:\t{}", posix_thread.tid())?;
-        writeln!(printer, "PPid:\t{}", process.parent().pid())?;
+        // The IDs are shown in the PID namespace of the procfs instance.
+        let pid_ns = self.0.pid_ns();
+        let ns_id_of = |id| pid_ns.ns_id_of(id).unwrap_or(0);
+
+        writeln!(printer, "Tgid:\t{}", ns_id_of(process.pid()))?;
+        writeln!(printer, "Pid:\t{}", ns_id_of(posix_thread.tid()))?;
+        writeln!(printer, "PPid:\t{}", ns_id_of(process.parent().pid()))?;
         writeln!(
             printer,
             "TracerPid:\t{}",
             posix_thread
                 .tracer()
-                .map(|tracer| tracer.as_posix_thread().unwrap().tid())
+                .map(|tracer| ns_id_of(tracer.as_posix_thread().unwrap().tid()))
                 .unwrap_or(0)
         )?;
 
@@ -159,10 +165,11 @@ impl ProcFileOps for StatusFileOps {
             let anon = vmar_ref.get_rss_counter(RssType::Anon) * (PAGE_SIZE / 1024);
             let file = vmar_ref.get_rss_counter(RssType::File) * (PAGE_SIZE / 1024);
             let rss = anon + file;
+            let swap = vmar_ref.get_rss_counter(RssType::Swap) * (PAGE_SIZE / 1024);
             writeln!(
                 printer,
-                "VmSize:\t{} kB\nVmRSS:\t{} kB\nRssAnon:\t{} kB\nRssFile:\t{} kB",
-                vsize, rss, anon, file
+                "VmSize:\t{} kB\nVmRSS:\t{} kB\nRssAnon:\t{} kB\nRssFile:\t{} kB\nVmSwap:\t{} kB",
+                vsize, rss, anon, file, swap
             )?;
         }
 
@@ -197,6 +204,15 @@ impl ProcFileOps for StatusFileOps {
             "CapAmb:\t{:016x}",
             credentials.ambient_capset().bits()
         )?;
+        writeln!(
+            printer,
+            "NoNewPrivs:\t{}",
+            credentials.no_new_privs() as u32
+        )?;
+
+        let seccomp = posix_thread.seccomp();
+        writeln!(printer, "Seccomp:\t{}", seccomp.mode() as u32)?;
+        writeln!(printer, "Seccomp_filters:\t{}", seccomp.num_filters())?;
 
         Ok(printer.bytes_written())
     }
@@ -0,0 +1,126 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_util::printer::VmPrinter;
+
+use super::{PidDirOps, TidDirOps};
+use crate::{
+    fs::{
+        file::mkmod,
+        procfs::template::{ProcFile, ProcFileOps},
+        vfs::inode::Inode,
+    },
+    prelude::*,
+    process::credentials::capabilities::CapSet,
+    security::lsm::hooks as lsm_hooks,
+    syscall::ClockId,
+    thread::Thread,
+    time::time_ns::{TimeNamespace, TimeNsOffset},
+};
+
+/// Represents the inode at `/proc/[pid]/timens_offsets`.
+pub(super) struct TimensOffsetsFileOps(TidDirOps);
+
+impl TimensOffsetsFileOps {
+    pub(super) fn new_inode(dir: &PidDirOps, parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/proc/base.c>
+        ProcFile::new(Self(dir.tid_dir_ops().clone()), parent, mkmod!(a+r, u+w))
+    }
+
+    /// Returns the time namespace for the children of the process.
+    ///
+    /// Like Linux, the offsets in this file are those of the namespace that the children of the
+    /// process will enter, since the offsets can only be set before any process enters the
+    /// namespace.
+    fn time_ns_for_children(&self) -> Result<Arc<TimeNamespace>> {
+        let Some(thread) = self.0.thread() else {
+            return_errno_with_message!(Errno::ESRCH, "the process does not exist");
+        };
+
+        let ns_proxy = thread.as_posix_thread().unwrap().ns_proxy().lock();
+        let ns_proxy = ns_proxy
+            .as_ref()
+            .ok_or_else(|| Error::with_message(Errno::ESRCH, "the process has exited"))?;
+        Ok(ns_proxy.time_ns_for_children().clone())
+    }
+}
+
+impl ProcFileOps for TimensOffsetsFileOps {
+    fn owner_thread(&self) -> Option<Arc<Thread>> {
+        self.0.thread()
+    }
+
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let mut printer = VmPrinter::new_skip(writer, offset);
+
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/kernel/time/namespace.c>
+        let offsets = self.time_ns_for_children()?.offsets();
+        for (name, offset) in [
+            ("monotonic", offsets.monotonic()),
+            ("boottime", offsets.boottime()),
+        ] {
+            writeln!(
+                printer,
+                "{:<10} {:>10} {:>9}",
+                name,
+                offset.secs(),
+                offset.nanos()
+            )?;
+        }
+
+        Ok(printer.bytes_written())
+    }
+
+    fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
+        let (cstr, read_bytes) = reader.read_cstring_until_end(PAGE_SIZE - 1)?;
+        let content = cstr
+            .to_str()
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the offsets are not valid UTF-8"))?;
+
+        let mut offsets = Vec::new();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            // Only `CLOCK_MONOTONIC` and `CLOCK_BOOTTIME` have offsets.
+            if offsets.len() == 2 {
+                return_errno_with_message!(Errno::EINVAL, "too many offsets are specified");
+            }
+            offsets.push(parse_offset(line)?);
+        }
+
+        let time_ns = self.time_ns_for_children()?;
+        lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+            time_ns.owner().as_ref(),
+            current_thread!().as_posix_thread().unwrap(),
+            CapSet::SYS_TIME,
+        ))?;
+        time_ns.set_offsets(&offsets)?;
+
+        Ok(read_bytes)
+    }
+}
+
+/// Parses a line in the format of `<clock> <secs> <nanos>`.
+///
+/// The clock can be specified either by its name or by its ID.
+//
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/proc/base.c>
+fn parse_offset(line: &str) -> Result<(ClockId, TimeNsOffset)> {
+    let invalid_line = || Error::with_message(Errno::EINVAL, "the offset line is invalid");
+
+    let mut fields = line.split_whitespace();
+    let (Some(clock), Some(secs), Some(nanos)) = (fields.next(), fields.next(), fields.next())
+    else {
+        return Err(invalid_line());
+    };
+
+    let clock_id = match clock {
+        "monotonic" => ClockId::CLOCK_MONOTONIC,
+        "boottime" => ClockId::CLOCK_BOOTTIME,
+        _ => match clock.parse::<i32>().ok().map(ClockId::try_from) {
+            Some(Ok(clock_id @ (ClockId::CLOCK_MONOTONIC | ClockId::CLOCK_BOOTTIME))) => clock_id,
+            _ => return Err(invalid_line()),
+        },
+    };
+    let secs = secs.parse::<i64>().map_err(|_| invalid_line())?;
+    let nanos = nanos.parse::<u64>().map_err(|_| invalid_line())?;
+
+    Ok((clock_id, TimeNsOffset::new(secs, nanos)?))
+}
@@ -7,20 +7,30 @@ use crate::{
         vfs::inode::{Inode, SymbolicLink},
     },
     prelude::*,
+    process::PidNamespace,
 };
 
 /// Represents the inode at `/proc/self`.
-pub(super) struct SelfSymOps;
+pub(super) struct SelfSymOps {
+    pid_ns: Arc<PidNamespace>,
+}
 
 impl SelfSymOps {
     pub(super) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        let pid_ns = super::pid_ns_of(&parent);
         // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/proc/self.c#L50>
-        ProcSym::new(Self, parent, mkmod!(a+rwx))
+        ProcSym::new(Self { pid_ns }, parent, mkmod!(a+rwx))
     }
 }
 
 impl ProcSymOps for SelfSymOps {
     fn read_link(&self) -> Result<SymbolicLink> {
-        Ok(SymbolicLink::Plain(current!().pid().to_string()))
+        let Some(pid) = self.pid_ns.ns_id_of(current!().pid()) else {
+            return_errno_with_message!(
+                Errno::ENOENT,
+                "the current process is not visible in the PID namespace"
+            );
+        };
+        Ok(SymbolicLink::Plain(pid.to_string()))
     }
 }
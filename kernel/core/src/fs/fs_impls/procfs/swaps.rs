@@ -0,0 +1,58 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module offers `/proc/swaps` file support, which tells the user space
+//! about the swap areas in use.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man5/proc_swaps.5.html>
+
+use aster_util::printer::VmPrinter;
+
+use crate::{
+    fs::{
+        file::mkmod,
+        procfs::template::{ProcFile, ProcFileOps},
+        vfs::inode::Inode,
+    },
+    prelude::*,
+    vm::swap,
+};
+
+/// Represents the inode at `/proc/swaps`.
+pub(super) struct SwapsFileOps;
+
+impl SwapsFileOps {
+    pub(super) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference:
+        // <https://elixir.bootlin.com/linux/v6.16.5/source/mm/swapfile.c#L2982>
+        // <https://elixir.bootlin.com/linux/v6.16.5/source/fs/proc/generic.c#L549-L550>
+        ProcFile::new(Self, parent, mkmod!(a+r))
+    }
+}
+
+impl ProcFileOps for SwapsFileOps {
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/mm/swapfile.c#L2898-L2934>
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let mut printer = VmPrinter::new_skip(writer, offset);
+
+        writeln!(printer, "Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority")?;
+
+        for stat in swap::area_stats() {
+            let size = stat.nr_pages * (PAGE_SIZE / 1024);
+            let used = stat.nr_used * (PAGE_SIZE / 1024);
+            let padding = 40usize.saturating_sub(stat.name.len()).max(1);
+            writeln!(
+                printer,
+                "{}{:padding$}partition\t{}\t{}{}\t{}{}",
+                stat.name,
+                "",
+                size,
+                if size < 10000000 { "\t" } else { "" },
+                used,
+                if used < 10000000 { "\t" } else { "" },
+                stat.priority,
+            )?;
+        }
+
+        Ok(printer.bytes_written())
+    }
+}
@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_util::printer::VmPrinter;
+
+use crate::{
+    fs::{
+        file::mkmod,
+        procfs::template::{ProcFile, ProcFileOps},
+        vfs::inode::Inode,
+    },
+    prelude::*,
+    process::coredump::{CorePattern, core_pattern, set_core_pattern},
+};
+
+/// Represents the inode at `/proc/sys/kernel/core_pattern`.
+pub(super) struct CorePatternFileOps;
+
+impl CorePatternFileOps {
+    pub(super) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/coredump.c>
+        ProcFile::new(Self, parent, mkmod!(a+r, u+w))
+    }
+}
+
+impl ProcFileOps for CorePatternFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let mut printer = VmPrinter::new_skip(writer, offset);
+
+        printer.write_bytes(core_pattern().as_bytes())?;
+        printer.write_bytes(b"\n")?;
+
+        Ok(printer.bytes_written())
+    }
+
+    fn write_at(&self, offset: usize, reader: &mut VmReader) -> Result<usize> {
+        let len = reader.remain();
+        if offset >= CorePattern::MAX_BYTES || len == 0 {
+            reader.skip(len);
+            return Ok(len);
+        }
+
+        // Like `/proc/sys/kernel/hostname`, writing at a non-zero offset edits the old pattern.
+        let mut value = if offset != 0 {
+            *core_pattern().as_array()
+        } else {
+            [0u8; CorePattern::MAX_BYTES_WITH_NUL]
+        };
+
+        let mut writer = VmWriter::from(&mut value[offset..]).to_fallible();
+        let copied_len = reader.read_fallible(&mut writer)?;
+
+        // Truncate at the first newline. Any bytes after the first nul byte will be zeroed by
+        // `CorePattern::from_bytes_until_nul`.
+        for byte in &mut value[offset..offset + copied_len] {
+            if *byte == b'\n' {
+                *byte = 0;
+                break;
+            }
+        }
+
+        set_core_pattern(CorePattern::from_bytes_until_nul(&value));
+
+        reader.skip(reader.remain());
+        Ok(len)
+    }
+}
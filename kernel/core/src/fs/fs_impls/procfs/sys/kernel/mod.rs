@@ -9,6 +9,8 @@ use crate::{
             ProcDir, StaticEntry,
             sys::kernel::{
                 cap_last_cap::CapLastCapFileOps,
+                core_pattern::CorePatternFileOps,
+                msg::{MsgmaxFileOps, MsgmnbFileOps, MsgmniFileOps},
                 pid_max::PidMaxFileOps,
                 tainted::TaintedFileOps,
                 uts::{DomainnameFileOps, HostnameFileOps, OsReleaseFileOps, VersionFileOps},
@@ -26,6 +28,8 @@ use crate::{
 };
 
 mod cap_last_cap;
+mod core_pattern;
+mod msg;
 mod pid_max;
 mod tainted;
 mod uts;
@@ -48,8 +52,16 @@ impl KernelDirOps {
             InodeType::File,
             CapLastCapFileOps::new_inode,
         ),
+        (
+            "core_pattern",
+            InodeType::File,
+            CorePatternFileOps::new_inode,
+        ),
         ("domainname", InodeType::File, DomainnameFileOps::new_inode),
         ("hostname", InodeType::File, HostnameFileOps::new_inode),
+        ("msgmax", InodeType::File, MsgmaxFileOps::new_inode),
+        ("msgmnb", InodeType::File, MsgmnbFileOps::new_inode),
+        ("msgmni", InodeType::File, MsgmniFileOps::new_inode),
         ("osrelease", InodeType::File, OsReleaseFileOps::new_inode),
         ("pid_max", InodeType::File, PidMaxFileOps::new_inode),
         ("tainted", InodeType::File, TaintedFileOps::new_inode),
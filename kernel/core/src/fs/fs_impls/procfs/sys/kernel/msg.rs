@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_util::printer::VmPrinter;
+use ostd::task::Task;
+
+use crate::{
+    fs::{
+        file::mkmod,
+        procfs::template::{ProcFile, ProcFileOps, read_i32_from},
+        vfs::inode::Inode,
+    },
+    ipc::msg::MsgLimits,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sys/kernel/msgmax`.
+pub(super) struct MsgmaxFileOps;
+
+impl MsgmaxFileOps {
+    pub(super) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/ipc_sysctl.c#L197-L203>
+        ProcFile::new(Self, parent, mkmod!(a+r, u+w))
+    }
+}
+
+impl ProcFileOps for MsgmaxFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        read_current_msg_limit(offset, writer, MsgLimits::msgmax)
+    }
+
+    fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
+        write_current_msg_limit(reader, MsgLimits::set_msgmax)
+    }
+}
+
+/// Represents the inode at `/proc/sys/kernel/msgmnb`.
+pub(super) struct MsgmnbFileOps;
+
+impl MsgmnbFileOps {
+    pub(super) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/ipc_sysctl.c#L220-L226>
+        ProcFile::new(Self, parent, mkmod!(a+r, u+w))
+    }
+}
+
+impl ProcFileOps for MsgmnbFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        read_current_msg_limit(offset, writer, MsgLimits::msgmnb)
+    }
+
+    fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
+        write_current_msg_limit(reader, MsgLimits::set_msgmnb)
+    }
+}
+
+/// Represents the inode at `/proc/sys/kernel/msgmni`.
+pub(super) struct MsgmniFileOps;
+
+impl MsgmniFileOps {
+    pub(super) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/ipc_sysctl.c#L204-L210>
+        ProcFile::new(Self, parent, mkmod!(a+r, u+w))
+    }
+}
+
+impl ProcFileOps for MsgmniFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        read_current_msg_limit(offset, writer, MsgLimits::msgmni)
+    }
+
+    fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
+        write_current_msg_limit(reader, MsgLimits::set_msgmni)
+    }
+}
+
+fn read_current_msg_limit(
+    offset: usize,
+    writer: &mut VmWriter,
+    get_value: fn(&MsgLimits) -> usize,
+) -> Result<usize> {
+    let value = {
+        let current_task = Task::current().unwrap();
+        let thread_local = current_task.as_thread_local().unwrap();
+        let ns_proxy = thread_local.borrow_ns_proxy();
+        get_value(ns_proxy.unwrap().ipc_ns().msg_limits())
+    };
+
+    let mut printer = VmPrinter::new_skip(writer, offset);
+    writeln!(printer, "{}", value)?;
+
+    Ok(printer.bytes_written())
+}
+
+fn write_current_msg_limit(
+    reader: &mut VmReader,
+    set_value: fn(&MsgLimits, i32) -> Result<()>,
+) -> Result<usize> {
+    let (value, read_bytes) = read_i32_from(reader)?;
+
+    let current_task = Task::current().unwrap();
+    let thread_local = current_task.as_thread_local().unwrap();
+    let ns_proxy = thread_local.borrow_ns_proxy();
+    set_value(ns_proxy.unwrap().ipc_ns().msg_limits(), value)?;
+
+    Ok(read_bytes)
+}
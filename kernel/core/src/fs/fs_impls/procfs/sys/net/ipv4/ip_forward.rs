@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_util::printer::VmPrinter;
+
+use crate::{
+    fs::{
+        file::mkmod,
+        procfs::template::{ProcFile, ProcFileOps},
+        vfs::inode::Inode,
+    },
+    net::net_ns::NetNamespace,
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sys/net/ipv4/ip_forward`.
+pub(super) struct IpForwardFileOps;
+
+impl IpForwardFileOps {
+    pub(super) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/net/ipv4/devinet.c>
+        ProcFile::new(Self, parent, mkmod!(a+r, u+w))
+    }
+}
+
+impl ProcFileOps for IpForwardFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let is_enabled = NetNamespace::current().netfilter().is_ip_forward_enabled();
+
+        let mut printer = VmPrinter::new_skip(writer, offset);
+        writeln!(printer, "{}", is_enabled as u8)?;
+
+        Ok(printer.bytes_written())
+    }
+
+    fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
+        /// The maximum length of the value and the separators.
+        const MAX_LEN: usize = 16;
+
+        let (cstr, read_bytes) = reader.read_cstring_until_end(MAX_LEN)?;
+        let is_enabled = match cstr.to_str().map(str::trim) {
+            Ok("0") => false,
+            Ok("1") => true,
+            _ => return_errno_with_message!(Errno::EINVAL, "the value must be 0 or 1"),
+        };
+
+        NetNamespace::current()
+            .netfilter()
+            .set_ip_forward_enabled(is_enabled);
+
+        Ok(read_bytes)
+    }
+}
@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_util::printer::VmPrinter;
+
+use crate::{
+    fs::{
+        file::mkmod,
+        procfs::template::{ProcFile, ProcFileOps},
+        vfs::inode::Inode,
+    },
+    net::net_ns::NetNamespace,
+    prelude::*,
+    process::Gid,
+};
+
+/// Represents the inode at `/proc/sys/net/ipv4/ping_group_range`.
+pub(super) struct PingGroupRangeFileOps;
+
+impl PingGroupRangeFileOps {
+    pub(super) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/net/ipv4/sysctl_net_ipv4.c>
+        ProcFile::new(Self, parent, mkmod!(a+r, u+w))
+    }
+}
+
+impl ProcFileOps for PingGroupRangeFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let (low, high) = NetNamespace::current().ping_group_range();
+
+        let mut printer = VmPrinter::new_skip(writer, offset);
+        writeln!(printer, "{}\t{}", u32::from(low), u32::from(high))?;
+
+        Ok(printer.bytes_written())
+    }
+
+    fn write_at(&self, _offset: usize, reader: &mut VmReader) -> Result<usize> {
+        /// The maximum length of the two groups and the separators.
+        const MAX_LEN: usize = 32;
+        /// The maximum group that is valid in user namespaces.
+        const MAX_GID: u32 = i32::MAX as u32;
+
+        let (cstr, read_bytes) = reader.read_cstring_until_end(MAX_LEN)?;
+        let invalid_range = || Error::with_message(Errno::EINVAL, "the group range is invalid");
+
+        let mut groups = cstr
+            .to_str()
+            .map_err(|_| invalid_range())?
+            .split_whitespace()
+            .map(|group| group.parse::<u32>().ok().filter(|gid| *gid <= MAX_GID));
+        let (Some(Some(low)), Some(Some(high)), None) =
+            (groups.next(), groups.next(), groups.next())
+        else {
+            return Err(invalid_range());
+        };
+
+        NetNamespace::current().set_ping_group_range(Gid::new(low), Gid::new(high));
+
+        Ok(read_bytes)
+    }
+}
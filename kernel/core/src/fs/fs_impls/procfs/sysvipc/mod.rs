@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#![short_vis_path::add(procfs)]
+
+use self::{msg::MsgFileOps, shm::ShmFileOps};
+use super::{
+    StaticEntry,
+    template::{ReaddirEntry, listed_entries_from_table, visit_listed_entries},
+};
+use crate::{
+    fs::{
+        file::{InodeType, mkmod},
+        procfs::template::{ProcDir, ProcDirOps, lookup_child_from_table},
+        vfs::inode::Inode,
+    },
+    prelude::*,
+};
+
+mod msg;
+mod shm;
+
+/// Represents the inode at `/proc/sysvipc`.
+pub(in procfs) struct SysvIpcDirOps;
+
+impl SysvIpcDirOps {
+    pub(in procfs) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference:
+        // <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/util.c#L104>
+        // <https://elixir.bootlin.com/linux/v6.16.5/source/fs/proc/generic.c#L488-L489>
+        ProcDir::new(Self, parent, mkmod!(a+rx))
+    }
+
+    const STATIC_ENTRIES: &'static [StaticEntry] = &[
+        ("msg", InodeType::File, MsgFileOps::new_inode),
+        ("shm", InodeType::File, ShmFileOps::new_inode),
+    ];
+}
+
+impl ProcDirOps for SysvIpcDirOps {
+    fn lookup_child(&self, this_dir: &ProcDir<Self>, name: &str) -> Result<Arc<dyn Inode>> {
+        if let Some(child) = lookup_child_from_table(name, Self::STATIC_ENTRIES, |f| {
+            (f)(this_dir.this_weak().clone())
+        }) {
+            return Ok(child);
+        }
+
+        return_errno_with_message!(Errno::ENOENT, "the file does not exist");
+    }
+
+    fn visit_entries_from_offset<'a, F>(&'a self, offset: usize, visit_fn: F) -> Result<()>
+    where
+        F: FnMut(ReaddirEntry<'a>) -> Result<()>,
+    {
+        visit_listed_entries(
+            offset,
+            listed_entries_from_table(Self::STATIC_ENTRIES),
+            visit_fn,
+        )
+    }
+}
@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_util::printer::VmPrinter;
+use ostd::task::Task;
+
+use crate::{
+    fs::{
+        file::mkmod,
+        procfs::template::{ProcFile, ProcFileOps},
+        vfs::inode::Inode,
+    },
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sysvipc/msg`.
+pub(super) struct MsgFileOps;
+
+impl MsgFileOps {
+    pub(super) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/msg.c#L1373-L1376>
+        ProcFile::new(Self, parent, mkmod!(a+r))
+    }
+}
+
+impl ProcFileOps for MsgFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let current_task = Task::current().unwrap();
+        let thread_local = current_task.as_thread_local().unwrap();
+        let ns_proxy = thread_local.borrow_ns_proxy();
+
+        // Collect the queues first, as printing may copy data to user memory.
+        let mut msg_queues = Vec::new();
+        ns_proxy
+            .unwrap()
+            .ipc_ns()
+            .for_each_msg_queue(|id, msg_queue| msg_queues.push((id, msg_queue.clone())));
+
+        let mut printer = VmPrinter::new_skip(writer, offset);
+        writeln!(
+            printer,
+            "       key      msqid perms      cbytes       qnum lspid lrpid   uid   gid  cuid  cgid      stime      rtime      ctime"
+        )?;
+        for (id, msg_queue) in msg_queues {
+            msg_queue.print_to_sysvipc(id, &mut printer)?;
+        }
+
+        Ok(printer.bytes_written())
+    }
+}
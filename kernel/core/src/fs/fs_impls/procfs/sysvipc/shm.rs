@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_util::printer::VmPrinter;
+use ostd::task::Task;
+
+use crate::{
+    fs::{
+        file::mkmod,
+        procfs::template::{ProcFile, ProcFileOps},
+        vfs::inode::Inode,
+    },
+    prelude::*,
+};
+
+/// Represents the inode at `/proc/sysvipc/shm`.
+pub(super) struct ShmFileOps;
+
+impl ShmFileOps {
+    pub(super) fn new_inode(parent: Weak<dyn Inode>) -> Arc<dyn Inode> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/shm.c#L158-L171>
+        ProcFile::new(Self, parent, mkmod!(a+r))
+    }
+}
+
+impl ProcFileOps for ShmFileOps {
+    fn read_at(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let current_task = Task::current().unwrap();
+        let thread_local = current_task.as_thread_local().unwrap();
+        let ns_proxy = thread_local.borrow_ns_proxy();
+
+        // Collect the segments first, as printing may copy data to user memory.
+        let mut segments = Vec::new();
+        ns_proxy
+            .unwrap()
+            .ipc_ns()
+            .for_each_shm(|id, segment| segments.push((id, segment.clone())));
+
+        let mut printer = VmPrinter::new_skip(writer, offset);
+        writeln!(
+            printer,
+            "       key      shmid perms                  size  cpid  lpid nattch   uid   gid  cuid  cgid      atime      dtime      ctime                   rss                  swap"
+        )?;
+        for (id, segment) in segments {
+            segment.print_to_sysvipc(id, &mut printer)?;
+        }
+
+        Ok(printer.bytes_written())
+    }
+}
@@ -16,7 +16,8 @@ use crate::{
         vfs::inode::Inode,
     },
     prelude::*,
-    time::{NSEC_PER_SEC, cpu_time_stats::CpuTimeStatsManager},
+    syscall::ClockId,
+    time::{NSEC_PER_SEC, cpu_time_stats::CpuTimeStatsManager, time_ns::TimeNamespace},
 };
 
 /// Represents the inode at `/proc/uptime`.
@@ -31,8 +32,10 @@ impl UptimeFileOps {
     }
 
     fn print_uptime(printer: &mut VmPrinter) -> Result<()> {
-        let (uptime_secs, uptime_centis) =
-            duration_to_seconds_and_centiseconds(aster_time::read_monotonic_time());
+        // The uptime is shifted by the boot-time offset of the time namespace.
+        let uptime = TimeNamespace::current()
+            .to_ns_time(ClockId::CLOCK_BOOTTIME, aster_time::read_monotonic_time());
+        let (uptime_secs, uptime_centis) = duration_to_seconds_and_centiseconds(uptime);
 
         let cpustat = CpuTimeStatsManager::singleton();
         let (idle_secs, idle_centis) = duration_to_seconds_and_centiseconds(
@@ -388,11 +388,8 @@ pub(crate) enum NsType {
     Cgroup,
     Ipc,
     Mnt,
-    #[expect(unused)]
     Net,
-    #[expect(unused)]
     Pid,
-    #[expect(unused)]
     Time,
     User,
     Uts,
@@ -0,0 +1,103 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::sync::Arc;
+
+use aster_systree::SysNode;
+use spin::Once;
+
+use super::inode::SecurityInode;
+use crate::fs::{
+    Result,
+    pseudofs::AnonDeviceId,
+    securityfs::systree_node::SecurityRootNode,
+    utils::systree_inode::SysTreeInodeTy,
+    vfs::{
+        file_system::{FileSystem, FsEventSubscriberStats, SuperBlock},
+        inode::Inode,
+        registry::{FsCreationCtx, FsProperties, FsType},
+    },
+};
+
+/// A file system that exposes the interfaces of LSM modules.
+///
+/// Each LSM module that needs a user-space interface registers a subsystem
+/// directory under the root of `SecurityFs`, e.g., `apparmor`.
+pub(crate) struct SecurityFs {
+    _anon_device_id: AnonDeviceId,
+    sb: SuperBlock,
+    root: Arc<dyn Inode>,
+    fs_event_subscriber_stats: FsEventSubscriberStats,
+}
+
+// Magic number for `SecurityFs` (taken from Linux).
+const MAGIC_NUMBER: u64 = 0x73636673;
+const BLOCK_SIZE: usize = 4096;
+const NAME_MAX: usize = 255;
+
+impl SecurityFs {
+    /// Returns the `SecurityFs` singleton.
+    pub(super) fn singleton() -> &'static Arc<SecurityFs> {
+        static SINGLETON: Once<Arc<SecurityFs>> = Once::new();
+
+        SINGLETON.call_once(|| Self::new(SecurityRootNode::singleton().clone()))
+    }
+
+    fn new(root_node: Arc<SecurityRootNode>) -> Arc<Self> {
+        let anon_device_id =
+            AnonDeviceId::acquire().expect("no device ID is available for securityfs");
+        let sb = SuperBlock::new(MAGIC_NUMBER, BLOCK_SIZE, NAME_MAX, anon_device_id.id());
+        let root_inode = SecurityInode::new_root(root_node, &sb);
+
+        Arc::new(Self {
+            _anon_device_id: anon_device_id,
+            sb,
+            root: root_inode,
+            fs_event_subscriber_stats: FsEventSubscriberStats::new(),
+        })
+    }
+}
+
+impl FileSystem for SecurityFs {
+    fn name(&self) -> &'static str {
+        "securityfs"
+    }
+
+    fn sync(&self) -> Result<()> {
+        // `SecurityFs` is volatile, sync is a no-op
+        Ok(())
+    }
+
+    fn root_inode(&self) -> Arc<dyn Inode> {
+        self.root.clone()
+    }
+
+    fn sb(&self) -> SuperBlock {
+        self.sb.clone()
+    }
+
+    fn fs_event_subscriber_stats(&self) -> &FsEventSubscriberStats {
+        &self.fs_event_subscriber_stats
+    }
+}
+
+pub(super) struct SecurityFsType;
+
+impl FsType for SecurityFsType {
+    type Key = ();
+
+    fn name(&self) -> &'static str {
+        "securityfs"
+    }
+
+    fn properties(&self) -> FsProperties {
+        FsProperties::empty()
+    }
+
+    fn create(&self, _fs_creation_ctx: &mut FsCreationCtx) -> Result<Arc<dyn FileSystem>> {
+        Ok(SecurityFs::singleton().clone())
+    }
+
+    fn sysnode(&self) -> Option<Arc<dyn SysNode>> {
+        None
+    }
+}
@@ -0,0 +1,37 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The security file system.
+//!
+//! `SecurityFs` is the interface through which LSM modules expose their
+//! policy-management files to user space. It is conventionally mounted at
+//! `/sys/kernel/security`.
+
+use aster_systree::{EmptyNode, SysBranchNode};
+use systree_node::SecurityRootNode;
+
+use crate::{fs::securityfs::fs::SecurityFsType, prelude::*};
+
+mod fs;
+mod inode;
+mod systree_node;
+
+// This method should be called during kernel file system initialization,
+// _after_ `aster_systree::init`.
+pub(super) fn init() {
+    let security_kernel_sysnode = EmptyNode::new("security".into());
+    super::sysfs::register_kernel_sysnode(security_kernel_sysnode).unwrap();
+
+    crate::fs::vfs::registry::register(&SecurityFsType).unwrap();
+}
+
+/// Registers a subsystem `SysTree` node under the root node of [`SecurityFs`].
+///
+/// If a subsystem with the same name has already been registered,
+/// this function returns an error.
+///
+/// [`SecurityFs`]: fs::SecurityFs
+pub(crate) fn register_subsystem(subsystem: Arc<dyn SysBranchNode>) -> Result<()> {
+    SecurityRootNode::singleton().add_child(subsystem)?;
+
+    Ok(())
+}
@@ -0,0 +1,182 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Implementation of the `/sys/block` sysfs directory.
+//!
+//! This module provides a directory for each whole-disk block device in
+//! `/sys/block`. Currently implemented attributes:
+//!
+//! - `queue/scheduler`: The I/O schedulers of the device. Reading it lists the
+//!   available schedulers with the active one enclosed in square brackets, and
+//!   writing the name of a scheduler to it switches to that scheduler.
+//!
+//! Reference: <https://docs.kernel.org/block/switching-sched.html>
+
+use alloc::{string::String, sync::Arc};
+
+use aster_block::{BlockDevice, scheduler::IoSchedulerKind};
+use aster_systree::{
+    BranchNodeFields, Error, NormalNodeFields, Result, SysAttrSetBuilder, SysBranchNode, SysObj,
+    SysPerms, SysStr, inherit_sys_branch_node, inherit_sys_leaf_node,
+};
+use aster_util::printer::VmPrinter;
+use inherit_methods_macro::inherit_methods;
+use ostd::mm::{VmReader, VmWriter};
+
+use crate::util::ReadCString;
+
+pub(super) fn init() {
+    let block_node = BlockSysNodeRoot::new();
+    for device in aster_block::collect_all() {
+        if device.is_partition() {
+            continue;
+        }
+
+        let disk_node = DiskNode::new(SysStr::from(String::from(device.name())));
+        disk_node.add_child(QueueNode::new(device)).unwrap();
+        block_node.add_child(disk_node).unwrap();
+    }
+
+    super::systree_singleton()
+        .root()
+        .add_child(block_node)
+        .unwrap();
+}
+
+/// A systree node representing the `/sys/block` directory.
+#[derive(Debug)]
+struct BlockSysNodeRoot {
+    fields: BranchNodeFields<dyn SysObj, Self>,
+}
+
+#[inherit_methods(from = "self.fields")]
+impl BlockSysNodeRoot {
+    fn new() -> Arc<Self> {
+        let name = SysStr::from("block");
+        let attrs = SysAttrSetBuilder::new().build().unwrap();
+        Arc::new_cyclic(|weak_self| {
+            let fields = BranchNodeFields::new(name, attrs, weak_self.clone());
+            BlockSysNodeRoot { fields }
+        })
+    }
+
+    fn add_child(&self, new_child: Arc<dyn SysObj>) -> Result<()>;
+}
+
+inherit_sys_branch_node!(BlockSysNodeRoot, fields, {
+    fn perms(&self) -> SysPerms {
+        SysPerms::DEFAULT_RO_PERMS
+    }
+});
+
+/// A systree node representing the `/sys/block/<dev>` directory.
+#[derive(Debug)]
+struct DiskNode {
+    fields: BranchNodeFields<dyn SysObj, Self>,
+}
+
+#[inherit_methods(from = "self.fields")]
+impl DiskNode {
+    fn new(name: SysStr) -> Arc<Self> {
+        // TODO: Add the attributes of the device, such as `dev` and `size`.
+        let attrs = SysAttrSetBuilder::new().build().unwrap();
+        Arc::new_cyclic(|weak_self| {
+            let fields = BranchNodeFields::new(name, attrs, weak_self.clone());
+            DiskNode { fields }
+        })
+    }
+
+    fn add_child(&self, new_child: Arc<dyn SysObj>) -> Result<()>;
+}
+
+inherit_sys_branch_node!(DiskNode, fields, {
+    fn perms(&self) -> SysPerms {
+        SysPerms::DEFAULT_RO_PERMS
+    }
+});
+
+/// A systree node representing the `/sys/block/<dev>/queue` directory.
+#[derive(Debug)]
+struct QueueNode {
+    fields: NormalNodeFields<Self>,
+    device: Arc<dyn BlockDevice>,
+}
+
+impl QueueNode {
+    fn new(device: Arc<dyn BlockDevice>) -> Arc<Self> {
+        let name = SysStr::from("queue");
+
+        let mut builder = SysAttrSetBuilder::new();
+        builder.add(SysStr::from("scheduler"), SysPerms::DEFAULT_RW_ATTR_PERMS);
+        let attrs = builder.build().unwrap();
+
+        Arc::new_cyclic(|weak_self| {
+            let fields = NormalNodeFields::new(name, attrs, weak_self.clone());
+            QueueNode { fields, device }
+        })
+    }
+
+    fn read_scheduler(&self, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        let mut printer = VmPrinter::new_skip(writer, offset);
+
+        let Some(queue) = self.device.request_queue() else {
+            writeln!(printer, "[{}]", IoSchedulerKind::None.name())?;
+            return Ok(printer.bytes_written());
+        };
+
+        let active_kind = queue.scheduler();
+        for (index, kind) in IoSchedulerKind::ALL.into_iter().enumerate() {
+            if index > 0 {
+                write!(printer, " ")?;
+            }
+            if kind == active_kind {
+                write!(printer, "[{}]", kind.name())?;
+            } else {
+                write!(printer, "{}", kind.name())?;
+            }
+        }
+        writeln!(printer)?;
+
+        Ok(printer.bytes_written())
+    }
+
+    fn write_scheduler(&self, reader: &mut VmReader) -> Result<usize> {
+        const MAX_NAME_LEN: usize = 32;
+
+        let (content, len) = reader
+            .read_cstring_until_end(MAX_NAME_LEN)
+            .map_err(|_| Error::PageFault)?;
+        let name = content
+            .to_str()
+            .map_err(|_| Error::InvalidOperation)?
+            .trim();
+        let kind = IoSchedulerKind::from_name(name).ok_or(Error::InvalidOperation)?;
+
+        match self.device.request_queue() {
+            Some(queue) => queue.set_scheduler(kind),
+            None if kind == IoSchedulerKind::None => (),
+            None => return Err(Error::InvalidOperation),
+        }
+
+        Ok(len)
+    }
+}
+
+inherit_sys_leaf_node!(QueueNode, fields, {
+    fn read_attr_at(&self, name: &str, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        match name {
+            "scheduler" => self.read_scheduler(offset, writer),
+            _ => Err(Error::AttributeError),
+        }
+    }
+
+    fn write_attr(&self, name: &str, reader: &mut VmReader) -> Result<usize> {
+        match name {
+            "scheduler" => self.write_scheduler(reader),
+            _ => Err(Error::AttributeError),
+        }
+    }
+
+    fn perms(&self) -> SysPerms {
+        SysPerms::DEFAULT_RW_PERMS
+    }
+});
@@ -0,0 +1,697 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{fmt::Display, time::Duration};
+
+use ostd::sync::LocalIrqDisabled;
+
+use super::{
+    IO_URING_OP_SUPPORTED, IORING_MAX_CQ_ENTRIES, IORING_MAX_ENTRIES, IoUringCqe,
+    IoUringEnterFlags, IoUringFeatures, IoUringFilesUpdate, IoUringOp, IoUringParams, IoUringProbe,
+    IoUringProbeOp, IoUringRegisterOp, IoUringSetupFlags, IoUringSqe, registry::Registry,
+    request::Request, ring::SharedRings,
+};
+use crate::{
+    events::{IoEvents, Observer},
+    fs::{
+        file::{
+            AccessMode, CreationFlags, FileCommon, FileLike, Mappable, StatusFlags,
+            file_table::FdFlags,
+        },
+        pseudofs::AnonInodeFs,
+    },
+    prelude::*,
+    process::signal::{PollAdaptor, PollHandle, Pollable, Pollee, Poller},
+    time::{
+        Timer, TimerManager,
+        clocks::{BootTimeClock, MonotonicClock, RealTimeClock},
+        timer::Timeout,
+        timespec_t,
+    },
+};
+
+/// An io_uring instance.
+///
+/// The requests are issued without holding the lock of [`State`], so a request that performs
+/// blocking I/O does not stall the completions, the waiters, or the registration. The lock is
+/// only taken to parse SQEs, to park requests, and to post CQEs.
+pub(crate) struct IoUringFile {
+    rings: SharedRings,
+    setup_flags: IoUringSetupFlags,
+    /// The lock that serializes the consumers of the SQ.
+    submit_lock: Mutex<()>,
+    state: Mutex<State>,
+    ready: Arc<ReadyQueue>,
+    common: FileCommon,
+}
+
+/// The state of the requests and the registered resources.
+struct State {
+    registry: Registry,
+    next_id: u64,
+    /// Requests that are waiting for their files to become ready.
+    parked: BTreeMap<u64, ParkedRequest>,
+    /// Timeouts that have not fired.
+    timeouts: BTreeMap<u64, PendingTimeout>,
+    /// CQEs that cannot be posted because the CQ is full.
+    overflow: VecDeque<IoUringCqe>,
+    /// The number of completions, excluding those of timeouts.
+    ///
+    /// This is used to implement timeouts that fire after a number of completions.
+    nr_completed: u64,
+}
+
+struct ParkedRequest {
+    request: Request,
+    // Keep the adaptor alive to receive the events of the file.
+    _poller: PollAdaptor<ReadyObserver>,
+}
+
+struct PendingTimeout {
+    user_data: u64,
+    // Dropping the timer cancels the timeout.
+    _timer: Arc<Timer>,
+    /// The value of [`State::nr_completed`] at which the timeout completes successfully.
+    target: Option<u64>,
+}
+
+/// A queue of the IDs of the parked requests and timeouts that become ready.
+///
+/// The queue is filled in the observer callbacks and the timer callbacks, and is drained in the
+/// context of the submitting task when it enters the ring.
+//
+// Keep this in a separate `Arc` to avoid dropping the io_uring instance in the callbacks.
+struct ReadyQueue {
+    ids: SpinLock<VecDeque<u64>, LocalIrqDisabled>,
+    pollee: Pollee,
+}
+
+impl ReadyQueue {
+    fn push(&self, id: u64) {
+        self.ids.lock().push_back(id);
+        self.pollee.notify(IoEvents::IN);
+    }
+
+    fn take_all(&self) -> VecDeque<u64> {
+        core::mem::take(&mut *self.ids.lock())
+    }
+
+    fn is_empty(&self) -> bool {
+        self.ids.lock().is_empty()
+    }
+}
+
+struct ReadyObserver {
+    ready: Arc<ReadyQueue>,
+    id: u64,
+}
+
+impl Observer<IoEvents> for ReadyObserver {
+    fn on_events(&self, _events: &IoEvents) {
+        self.ready.push(self.id);
+    }
+}
+
+impl IoUringFile {
+    /// Creates an io_uring instance with at least `entries` SQ entries.
+    ///
+    /// The fields of `params` that are written by the kernel (e.g., the ring offsets) are filled
+    /// in upon success.
+    pub(crate) fn new(entries: u32, params: &mut IoUringParams) -> Result<Self> {
+        let setup_flags = IoUringSetupFlags::from_bits(params.flags)
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid setup flags"))?;
+        if !IoUringSetupFlags::SUPPORTED.contains(setup_flags) {
+            return_errno_with_message!(Errno::EINVAL, "the setup flags are not supported");
+        }
+        if params.resv.iter().any(|resv| *resv != 0) {
+            return_errno_with_message!(Errno::EINVAL, "the reserved fields are not zero");
+        }
+
+        let is_clamped = setup_flags.contains(IoUringSetupFlags::IORING_SETUP_CLAMP);
+
+        if entries == 0 {
+            return_errno_with_message!(Errno::EINVAL, "the number of entries is zero");
+        }
+        let sq_entries = if entries <= IORING_MAX_ENTRIES {
+            entries.next_power_of_two()
+        } else if is_clamped {
+            IORING_MAX_ENTRIES
+        } else {
+            return_errno_with_message!(Errno::EINVAL, "the number of entries is too large");
+        };
+
+        let cq_entries = if setup_flags.contains(IoUringSetupFlags::IORING_SETUP_CQSIZE) {
+            let cq_entries = match params.cq_entries {
+                0 => return_errno_with_message!(Errno::EINVAL, "the number of CQ entries is zero"),
+                n if n <= IORING_MAX_CQ_ENTRIES => n.next_power_of_two(),
+                _ if is_clamped => IORING_MAX_CQ_ENTRIES,
+                _ => {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "the number of CQ entries is too large"
+                    )
+                }
+            };
+            if cq_entries < sq_entries {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the number of CQ entries is less than that of SQ entries"
+                );
+            }
+            cq_entries
+        } else {
+            2 * sq_entries
+        };
+
+        let rings = SharedRings::new(sq_entries, cq_entries)?;
+
+        params.sq_entries = sq_entries;
+        params.cq_entries = cq_entries;
+        params.features = (IoUringFeatures::IORING_FEAT_SINGLE_MMAP
+            | IoUringFeatures::IORING_FEAT_NODROP
+            | IoUringFeatures::IORING_FEAT_SUBMIT_STABLE
+            | IoUringFeatures::IORING_FEAT_RW_CUR_POS)
+            .bits();
+        params.sq_off = rings.sq_offsets();
+        params.cq_off = rings.cq_offsets();
+
+        let state = State {
+            registry: Registry::default(),
+            next_id: 0,
+            parked: BTreeMap::new(),
+            timeouts: BTreeMap::new(),
+            overflow: VecDeque::new(),
+            nr_completed: 0,
+        };
+        let ready = Arc::new(ReadyQueue {
+            ids: SpinLock::new(VecDeque::new()),
+            pollee: Pollee::new(),
+        });
+
+        let pseudo_path = AnonInodeFs::new_path(|_| "anon_inode:[io_uring]".to_string());
+        Ok(Self {
+            rings,
+            setup_flags,
+            submit_lock: Mutex::new(()),
+            state: Mutex::new(state),
+            ready,
+            // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/io_uring/io_uring.c#L3836>.
+            common: FileCommon::new(pseudo_path, AccessMode::O_RDWR, StatusFlags::empty()),
+        })
+    }
+
+    /// Submits at most `to_submit` SQEs and waits for at least `min_complete` completions if
+    /// `IORING_ENTER_GETEVENTS` is specified.
+    ///
+    /// This method returns the number of consumed SQEs.
+    pub(crate) fn enter(
+        &self,
+        to_submit: u32,
+        min_complete: u32,
+        flags: IoUringEnterFlags,
+        ctx: &Context,
+    ) -> Result<u32> {
+        if flags.intersects(
+            IoUringEnterFlags::IORING_ENTER_EXT_ARG
+                | IoUringEnterFlags::IORING_ENTER_REGISTERED_RING,
+        ) {
+            return_errno_with_message!(Errno::EINVAL, "the enter flags are not supported");
+        }
+
+        let submitted = if to_submit > 0 {
+            self.submit(to_submit, ctx)?
+        } else {
+            0
+        };
+
+        if !flags.contains(IoUringEnterFlags::IORING_ENTER_GETEVENTS) {
+            self.run_ready(ctx)?;
+            return Ok(submitted);
+        }
+
+        // Like Linux, errors that occur while waiting are only reported if no SQEs are consumed.
+        match self.wait_completions(min_complete.min(self.rings.cq_entries()), ctx) {
+            Ok(()) => Ok(submitted),
+            Err(_) if submitted > 0 => Ok(submitted),
+            Err(err) => Err(err),
+        }
+    }
+
+    fn submit(&self, to_submit: u32, ctx: &Context) -> Result<u32> {
+        let _submit_guard = self.submit_lock.lock();
+
+        let nr_pending = self.rings.sq_pending()?.min(to_submit);
+        let mut submitted = 0;
+
+        while submitted < nr_pending {
+            submitted += 1;
+
+            let Some(sqe) = self.rings.pop_sqe()? else {
+                continue;
+            };
+            if let Err(err) = self.submit_sqe(sqe, ctx) {
+                self.post_error(&mut self.state.lock(), sqe.user_data, err)?;
+
+                if !self
+                    .setup_flags
+                    .contains(IoUringSetupFlags::IORING_SETUP_SUBMIT_ALL)
+                {
+                    break;
+                }
+            }
+        }
+
+        Ok(submitted)
+    }
+
+    /// Parses and issues an SQE.
+    ///
+    /// Errors are returned only if the SQE is invalid. Otherwise, the errors of the operation are
+    /// reported in the CQE.
+    fn submit_sqe(&self, sqe: IoUringSqe, ctx: &Context) -> Result<()> {
+        let mut state = self.state.lock();
+
+        let request = Request::new(sqe, &state.registry, ctx)?;
+        // A parked request holds its file, so polling the ring itself would keep the ring alive
+        // through a reference cycle.
+        if request.op() == IoUringOp::PollAdd
+            && core::ptr::addr_eq(Arc::as_ptr(request.file()), self as *const Self)
+        {
+            return_errno_with_message!(Errno::EBADF, "the ring cannot poll itself");
+        }
+        if request.op() == IoUringOp::Timeout {
+            return self.arm_timeout(&mut state, request, ctx);
+        }
+        drop(state);
+
+        self.issue(request, ctx)
+    }
+
+    /// Issues a request and posts its CQE, or parks it if it should wait for its file.
+    fn issue(&self, request: Request, ctx: &Context) -> Result<()> {
+        let (mut state, res) = match request.op() {
+            IoUringOp::Nop => (self.state.lock(), Ok(0)),
+            IoUringOp::PollRemove => {
+                let mut state = self.state.lock();
+                let res = self.cancel_parked(&mut state, request.sqe().addr, IoUringOp::PollAdd);
+                (state, res)
+            }
+            IoUringOp::TimeoutRemove => {
+                let mut state = self.state.lock();
+                let res = self.cancel_timeout(&mut state, request.sqe());
+                (state, res)
+            }
+            _ => {
+                // The operation may block, so it is performed without holding the lock.
+                let res = request.issue(ctx);
+                (self.state.lock(), res)
+            }
+        };
+
+        match res {
+            Ok(_) if request.skips_success() => {
+                state.nr_completed += 1;
+                self.flush_counted_timeouts(&mut state)
+            }
+            Ok(res) => self.post(&mut state, IoUringCqe::new(request.user_data(), res), true),
+            Err(err) if err.error() == Errno::EAGAIN && request.can_wait() => {
+                self.park(&mut state, request);
+                Ok(())
+            }
+            Err(err) => self.post_error(&mut state, request.user_data(), err),
+        }
+    }
+
+    /// Parks the request until its file becomes ready.
+    fn park(&self, state: &mut State, request: Request) {
+        let id = state.alloc_id();
+
+        let mut poller = PollAdaptor::with_observer(ReadyObserver {
+            ready: self.ready.clone(),
+            id,
+        });
+        let events = request
+            .file()
+            .poll(request.wait_mask().unwrap(), Some(poller.as_handle_mut()));
+
+        state.parked.insert(
+            id,
+            ParkedRequest {
+                request,
+                _poller: poller,
+            },
+        );
+
+        // The file may become ready before the observer is registered.
+        if !events.is_empty() {
+            self.ready.push(id);
+        }
+    }
+
+    fn arm_timeout(&self, state: &mut State, request: Request, ctx: &Context) -> Result<()> {
+        const IORING_TIMEOUT_ABS: u32 = 1 << 0;
+        const IORING_TIMEOUT_BOOTTIME: u32 = 1 << 2;
+        const IORING_TIMEOUT_REALTIME: u32 = 1 << 3;
+
+        let sqe = request.sqe();
+        if sqe.len != 1 {
+            return_errno_with_message!(Errno::EINVAL, "the timeout length is not one");
+        }
+
+        let flags = sqe.op_flags;
+        if flags & !(IORING_TIMEOUT_ABS | IORING_TIMEOUT_BOOTTIME | IORING_TIMEOUT_REALTIME) != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the timeout flags are not supported");
+        }
+        let timer_manager: &Arc<TimerManager> =
+            match flags & (IORING_TIMEOUT_BOOTTIME | IORING_TIMEOUT_REALTIME) {
+                0 => MonotonicClock::timer_manager(),
+                IORING_TIMEOUT_BOOTTIME => BootTimeClock::timer_manager(),
+                IORING_TIMEOUT_REALTIME => RealTimeClock::timer_manager(),
+                _ => return_errno_with_message!(Errno::EINVAL, "multiple clocks are specified"),
+            };
+
+        let timespec = ctx.user_space().read_val::<timespec_t>(sqe.addr as Vaddr)?;
+        let duration = Duration::try_from(timespec)?;
+        let timeout = if flags & IORING_TIMEOUT_ABS != 0 {
+            Timeout::When(duration)
+        } else {
+            Timeout::After(duration)
+        };
+
+        let id = state.alloc_id();
+        let ready = self.ready.clone();
+        let timer = timer_manager.create_timer(move |_| ready.push(id));
+        timer.lock().set_timeout(timeout);
+
+        let target = (sqe.off != 0).then(|| state.nr_completed + sqe.off);
+        state.timeouts.insert(
+            id,
+            PendingTimeout {
+                user_data: request.user_data(),
+                _timer: timer,
+                target,
+            },
+        );
+
+        Ok(())
+    }
+
+    /// Cancels the parked request of `op` whose user data is `user_data`.
+    fn cancel_parked(&self, state: &mut State, user_data: u64, op: IoUringOp) -> Result<i32> {
+        let Some(id) = state.parked.iter().find_map(|(id, parked)| {
+            (parked.request.user_data() == user_data && parked.request.op() == op).then_some(*id)
+        }) else {
+            return_errno_with_message!(Errno::ENOENT, "the request is not found");
+        };
+
+        let parked = state.parked.remove(&id).unwrap();
+        let cqe = IoUringCqe::new(parked.request.user_data(), -(Errno::ECANCELED as i32));
+        self.post(state, cqe, true)?;
+
+        Ok(0)
+    }
+
+    /// Cancels the timeout specified by the `IORING_OP_TIMEOUT_REMOVE` SQE.
+    fn cancel_timeout(&self, state: &mut State, sqe: &IoUringSqe) -> Result<i32> {
+        if sqe.op_flags != 0 {
+            return_errno_with_message!(Errno::EINVAL, "updating timeouts is not supported");
+        }
+
+        let Some(id) = state
+            .timeouts
+            .iter()
+            .find_map(|(id, timeout)| (timeout.user_data == sqe.addr).then_some(*id))
+        else {
+            return_errno_with_message!(Errno::ENOENT, "the timeout is not found");
+        };
+
+        let timeout = state.timeouts.remove(&id).unwrap();
+        let cqe = IoUringCqe::new(timeout.user_data, -(Errno::ECANCELED as i32));
+        self.post(state, cqe, false)?;
+
+        Ok(0)
+    }
+
+    /// Retries the parked requests and completes the timeouts that become ready.
+    fn run_ready(&self, ctx: &Context) -> Result<()> {
+        let mut ready_requests = Vec::new();
+
+        {
+            let mut state = self.state.lock();
+
+            self.flush_overflow(&mut state)?;
+
+            // Only handle the IDs that are ready now, so a request that keeps becoming ready
+            // spuriously will not trap us here.
+            for id in self.ready.take_all() {
+                if let Some(parked) = state.parked.remove(&id) {
+                    ready_requests.push(parked.request);
+                } else if let Some(timeout) = state.timeouts.remove(&id) {
+                    let cqe = IoUringCqe::new(timeout.user_data, -(Errno::ETIME as i32));
+                    self.post(&mut state, cqe, false)?;
+                }
+            }
+        }
+
+        for request in ready_requests {
+            self.issue(request, ctx)?;
+        }
+
+        Ok(())
+    }
+
+    /// Waits until there are at least `min_complete` CQEs in the CQ.
+    fn wait_completions(&self, min_complete: u32, ctx: &Context) -> Result<()> {
+        let mut poller = Poller::new(None);
+        self.ready
+            .pollee
+            .register_poller(poller.as_handle_mut(), IoEvents::IN);
+
+        loop {
+            self.run_ready(ctx)?;
+            if self.rings.cq_ready()? >= min_complete {
+                return Ok(());
+            }
+
+            poller.wait()?;
+        }
+    }
+
+    fn post_error(&self, state: &mut State, user_data: u64, err: Error) -> Result<()> {
+        let errno = match err.error() {
+            // Requests are not restarted by the syscall, so report `EINTR` to userspace.
+            Errno::ERESTARTSYS => Errno::EINTR,
+            errno => errno,
+        };
+
+        self.post(state, IoUringCqe::new(user_data, -(errno as i32)), true)
+    }
+
+    /// Posts a CQE.
+    ///
+    /// If `is_counted` is true, the CQE will be counted in [`State::nr_completed`].
+    fn post(&self, state: &mut State, cqe: IoUringCqe, is_counted: bool) -> Result<()> {
+        if !state.overflow.is_empty() || !self.rings.push_cqe(&cqe)? {
+            state.overflow.push_back(cqe);
+            self.rings.set_cq_overflow_flag(true)?;
+        }
+        self.ready.pollee.notify(IoEvents::IN);
+
+        if is_counted {
+            state.nr_completed += 1;
+            self.flush_counted_timeouts(state)?;
+        }
+
+        Ok(())
+    }
+
+    /// Completes the timeouts whose completion counts are reached.
+    fn flush_counted_timeouts(&self, state: &mut State) -> Result<()> {
+        let nr_completed = state.nr_completed;
+        let reached_ids: Vec<u64> = state
+            .timeouts
+            .iter()
+            .filter(|(_, timeout)| timeout.target.is_some_and(|target| target <= nr_completed))
+            .map(|(id, _)| *id)
+            .collect();
+
+        for id in reached_ids {
+            let timeout = state.timeouts.remove(&id).unwrap();
+            self.post(state, IoUringCqe::new(timeout.user_data, 0), false)?;
+        }
+
+        Ok(())
+    }
+
+    /// Moves the overflowed CQEs to the CQ if there is space.
+    fn flush_overflow(&self, state: &mut State) -> Result<()> {
+        while let Some(cqe) = state.overflow.front() {
+            if !self.rings.push_cqe(cqe)? {
+                return Ok(());
+            }
+            state.overflow.pop_front();
+        }
+
+        self.rings.set_cq_overflow_flag(false)
+    }
+
+    /// Performs an `io_uring_register` operation.
+    pub(crate) fn register(
+        &self,
+        op: IoUringRegisterOp,
+        arg: Vaddr,
+        nr_args: u32,
+        ctx: &Context,
+    ) -> Result<i32> {
+        let mut state = self.state.lock();
+        let registry = &mut state.registry;
+
+        match op {
+            IoUringRegisterOp::RegisterBuffers => registry.register_buffers(arg, nr_args, ctx)?,
+            IoUringRegisterOp::UnregisterBuffers => {
+                check_no_args(arg, nr_args)?;
+                registry.unregister_buffers()?;
+            }
+            IoUringRegisterOp::RegisterFiles => registry.register_files(arg, nr_args, ctx)?,
+            IoUringRegisterOp::UnregisterFiles => {
+                check_no_args(arg, nr_args)?;
+                registry.unregister_files()?;
+            }
+            IoUringRegisterOp::RegisterFilesUpdate => {
+                let update = ctx.user_space().read_val::<IoUringFilesUpdate>(arg)?;
+                if update.resv != 0 {
+                    return_errno_with_message!(Errno::EINVAL, "the reserved field is not zero");
+                }
+                let nr_updated =
+                    registry.update_files(update.offset, update.fds as Vaddr, nr_args, ctx)?;
+                return Ok(nr_updated as i32);
+            }
+            IoUringRegisterOp::RegisterProbe => write_probe(arg, nr_args, ctx)?,
+        }
+
+        Ok(0)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let mut events = IoEvents::empty();
+
+        if self
+            .rings
+            .sq_pending()
+            .is_ok_and(|n| n < self.rings.sq_entries())
+        {
+            events |= IoEvents::OUT;
+        }
+        if self.rings.cq_ready().is_ok_and(|n| n > 0) || !self.ready.is_empty() {
+            events |= IoEvents::IN;
+        }
+
+        events
+    }
+}
+
+impl State {
+    fn alloc_id(&mut self) -> u64 {
+        let id = self.next_id;
+        self.next_id += 1;
+        id
+    }
+}
+
+fn check_no_args(arg: Vaddr, nr_args: u32) -> Result<()> {
+    if arg != 0 || nr_args != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the arguments should be empty");
+    }
+
+    Ok(())
+}
+
+/// Writes the supported opcodes to the `io_uring_probe` structure at `addr`.
+fn write_probe(addr: Vaddr, nr_args: u32, ctx: &Context) -> Result<()> {
+    let ops_len = nr_args.min(IoUringOp::LAST as u32 + 1) as usize;
+
+    let user_space = ctx.user_space();
+    let probe = user_space.read_val::<IoUringProbe>(addr)?;
+    if probe.last_op != 0 || probe.ops_len != 0 || probe.resv != 0 || probe.resv2 != [0; 3] {
+        return_errno_with_message!(Errno::EINVAL, "the probe structure is not zeroed");
+    }
+
+    let ops_addr = addr + size_of::<IoUringProbe>();
+    for op in IoUringOp::ALL {
+        if (op as usize) < ops_len {
+            let probe_op = IoUringProbeOp {
+                op: op as u8,
+                resv: 0,
+                flags: IO_URING_OP_SUPPORTED,
+                resv2: 0,
+            };
+            user_space.write_val(
+                ops_addr + op as usize * size_of::<IoUringProbeOp>(),
+                &probe_op,
+            )?;
+        }
+    }
+
+    let probe = IoUringProbe {
+        last_op: IoUringOp::LAST,
+        ops_len: ops_len as u8,
+        resv: 0,
+        resv2: [0; 3],
+    };
+    user_space.write_val(addr, &probe)?;
+
+    Ok(())
+}
+
+impl Pollable for IoUringFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.ready
+            .pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl FileLike for IoUringFile {
+    fn mappable(&self) -> Result<Mappable> {
+        Ok(Mappable::Vmo(self.rings.vmo().clone()))
+    }
+
+    fn common(&self) -> &FileCommon {
+        &self.common
+    }
+
+    fn dump_proc_fdinfo(self: Arc<Self>, fd_flags: FdFlags) -> Box<dyn Display> {
+        struct FdInfo {
+            flags: u32,
+            sq_mask: u32,
+            cq_mask: u32,
+            sq_entries: u32,
+            cq_entries: u32,
+        }
+
+        impl Display for FdInfo {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                writeln!(f, "pos:\t{}", 0)?;
+                writeln!(f, "flags:\t0{:o}", self.flags)?;
+                writeln!(f, "mnt_id:\t{}", AnonInodeFs::mount_node().id())?;
+                writeln!(f, "ino:\t{}", AnonInodeFs::shared_inode().ino())?;
+                writeln!(f, "SqMask:\t0x{:x}", self.sq_mask)?;
+                writeln!(f, "CqMask:\t0x{:x}", self.cq_mask)?;
+                writeln!(f, "SqEntries:\t{}", self.sq_entries)?;
+                writeln!(f, "CqEntries:\t{}", self.cq_entries)
+            }
+        }
+
+        let mut flags = self.common.status_flags().bits() | self.common.access_mode() as u32;
+        if fd_flags.contains(FdFlags::CLOEXEC) {
+            flags |= CreationFlags::O_CLOEXEC.bits();
+        }
+
+        Box::new(FdInfo {
+            flags,
+            sq_mask: self.rings.sq_entries() - 1,
+            cq_mask: self.rings.cq_entries() - 1,
+            sq_entries: self.rings.sq_entries(),
+            cq_entries: self.rings.cq_entries(),
+        })
+    }
+}
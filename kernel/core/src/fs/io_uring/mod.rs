@@ -0,0 +1,293 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The io_uring asynchronous I/O interface.
+//!
+//! An io_uring instance consists of a submission queue (SQ) and a completion queue (CQ) that are
+//! shared between the kernel and userspace. Userspace places submission queue entries (SQEs) in
+//! the SQ and notifies the kernel via `io_uring_enter`, and the kernel places completion queue
+//! entries (CQEs) in the CQ.
+//!
+//! Requests are issued in the context of the submitting task when it enters the ring. Requests
+//! that cannot complete immediately (e.g., reading from an empty socket) are parked until the
+//! target file becomes ready, and are retried the next time the task enters the ring to wait for
+//! completions. This is similar to the semantics of `IORING_SETUP_DEFER_TASKRUN` in Linux.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/io_uring.h>
+
+use int_to_c_enum::TryFromInt;
+
+use crate::prelude::*;
+
+mod file;
+mod registry;
+mod request;
+mod ring;
+
+pub(crate) use file::IoUringFile;
+
+/// The mmap offset of the SQ ring.
+pub(crate) const IORING_OFF_SQ_RING: usize = 0;
+/// The mmap offset of the CQ ring.
+pub(crate) const IORING_OFF_CQ_RING: usize = 0x8000000;
+/// The mmap offset of the SQE array.
+pub(crate) const IORING_OFF_SQES: usize = 0x10000000;
+
+/// The maximum number of SQ entries.
+pub(crate) const IORING_MAX_ENTRIES: u32 = 32768;
+/// The maximum number of CQ entries.
+pub(crate) const IORING_MAX_CQ_ENTRIES: u32 = 2 * IORING_MAX_ENTRIES;
+
+bitflags! {
+    /// Flags for `io_uring_setup`.
+    pub(crate) struct IoUringSetupFlags: u32 {
+        const IORING_SETUP_IOPOLL         = 1 << 0;
+        const IORING_SETUP_SQPOLL         = 1 << 1;
+        const IORING_SETUP_SQ_AFF         = 1 << 2;
+        const IORING_SETUP_CQSIZE         = 1 << 3;
+        const IORING_SETUP_CLAMP          = 1 << 4;
+        const IORING_SETUP_ATTACH_WQ      = 1 << 5;
+        const IORING_SETUP_R_DISABLED     = 1 << 6;
+        const IORING_SETUP_SUBMIT_ALL     = 1 << 7;
+        const IORING_SETUP_COOP_TASKRUN   = 1 << 8;
+        const IORING_SETUP_TASKRUN_FLAG   = 1 << 9;
+        const IORING_SETUP_SQE128         = 1 << 10;
+        const IORING_SETUP_CQE32          = 1 << 11;
+        const IORING_SETUP_SINGLE_ISSUER  = 1 << 12;
+        const IORING_SETUP_DEFER_TASKRUN  = 1 << 13;
+    }
+}
+
+impl IoUringSetupFlags {
+    /// The flags that are supported.
+    ///
+    /// The remaining flags either require kernel worker threads (e.g., `IORING_SETUP_SQPOLL`) or
+    /// change the layout of the shared memory (e.g., `IORING_SETUP_SQE128`).
+    pub(crate) const SUPPORTED: Self = Self::IORING_SETUP_CQSIZE
+        .union(Self::IORING_SETUP_CLAMP)
+        .union(Self::IORING_SETUP_SUBMIT_ALL)
+        .union(Self::IORING_SETUP_COOP_TASKRUN)
+        .union(Self::IORING_SETUP_SINGLE_ISSUER)
+        .union(Self::IORING_SETUP_DEFER_TASKRUN);
+}
+
+bitflags! {
+    /// Flags for `io_uring_enter`.
+    pub(crate) struct IoUringEnterFlags: u32 {
+        const IORING_ENTER_GETEVENTS       = 1 << 0;
+        const IORING_ENTER_SQ_WAKEUP       = 1 << 1;
+        const IORING_ENTER_SQ_WAIT         = 1 << 2;
+        const IORING_ENTER_EXT_ARG         = 1 << 3;
+        const IORING_ENTER_REGISTERED_RING = 1 << 4;
+    }
+}
+
+bitflags! {
+    /// Features reported to userspace in [`IoUringParams::features`].
+    pub(crate) struct IoUringFeatures: u32 {
+        const IORING_FEAT_SINGLE_MMAP     = 1 << 0;
+        const IORING_FEAT_NODROP          = 1 << 1;
+        const IORING_FEAT_SUBMIT_STABLE   = 1 << 2;
+        const IORING_FEAT_RW_CUR_POS      = 1 << 3;
+    }
+}
+
+bitflags! {
+    /// Per-SQE flags.
+    pub(super) struct SqeFlags: u8 {
+        const IOSQE_FIXED_FILE       = 1 << 0;
+        const IOSQE_IO_DRAIN         = 1 << 1;
+        const IOSQE_IO_LINK          = 1 << 2;
+        const IOSQE_IO_HARDLINK      = 1 << 3;
+        const IOSQE_ASYNC            = 1 << 4;
+        const IOSQE_BUFFER_SELECT    = 1 << 5;
+        const IOSQE_CQE_SKIP_SUCCESS = 1 << 6;
+    }
+}
+
+impl SqeFlags {
+    /// The flags that are supported.
+    //
+    // `IOSQE_IO_DRAIN` and `IOSQE_ASYNC` are accepted since requests are issued in order and
+    // offloading them to workers is only an optimization.
+    pub(super) const SUPPORTED: Self = Self::IOSQE_FIXED_FILE
+        .union(Self::IOSQE_IO_DRAIN)
+        .union(Self::IOSQE_ASYNC)
+        .union(Self::IOSQE_CQE_SKIP_SUCCESS);
+}
+
+/// The io_uring opcodes.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
+pub(super) enum IoUringOp {
+    Nop = 0,
+    Readv = 1,
+    Writev = 2,
+    Fsync = 3,
+    ReadFixed = 4,
+    WriteFixed = 5,
+    PollAdd = 6,
+    PollRemove = 7,
+    Timeout = 11,
+    TimeoutRemove = 12,
+    Accept = 13,
+    Read = 22,
+    Write = 23,
+    Send = 26,
+    Recv = 27,
+}
+
+impl IoUringOp {
+    /// The number of opcodes known to Linux, which is reported by `IORING_REGISTER_PROBE`.
+    pub(super) const LAST: u8 = 58;
+
+    /// All the supported opcodes.
+    pub(super) const ALL: [Self; 15] = [
+        Self::Nop,
+        Self::Readv,
+        Self::Writev,
+        Self::Fsync,
+        Self::ReadFixed,
+        Self::WriteFixed,
+        Self::PollAdd,
+        Self::PollRemove,
+        Self::Timeout,
+        Self::TimeoutRemove,
+        Self::Accept,
+        Self::Read,
+        Self::Write,
+        Self::Send,
+        Self::Recv,
+    ];
+}
+
+/// The io_uring registration opcodes.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
+pub(crate) enum IoUringRegisterOp {
+    RegisterBuffers = 0,
+    UnregisterBuffers = 1,
+    RegisterFiles = 2,
+    UnregisterFiles = 3,
+    RegisterFilesUpdate = 6,
+    RegisterProbe = 8,
+}
+
+/// The parameters of `io_uring_setup`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(crate) struct IoUringParams {
+    pub(crate) sq_entries: u32,
+    pub(crate) cq_entries: u32,
+    pub(crate) flags: u32,
+    pub(crate) sq_thread_cpu: u32,
+    pub(crate) sq_thread_idle: u32,
+    pub(crate) features: u32,
+    pub(crate) wq_fd: u32,
+    pub(crate) resv: [u32; 3],
+    pub(crate) sq_off: IoSqringOffsets,
+    pub(crate) cq_off: IoCqringOffsets,
+}
+
+/// The offsets of the SQ ring fields, relative to [`IORING_OFF_SQ_RING`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod)]
+pub(crate) struct IoSqringOffsets {
+    pub(crate) head: u32,
+    pub(crate) tail: u32,
+    pub(crate) ring_mask: u32,
+    pub(crate) ring_entries: u32,
+    pub(crate) flags: u32,
+    pub(crate) dropped: u32,
+    pub(crate) array: u32,
+    pub(crate) resv1: u32,
+    pub(crate) user_addr: u64,
+}
+
+/// The offsets of the CQ ring fields, relative to [`IORING_OFF_CQ_RING`].
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod)]
+pub(crate) struct IoCqringOffsets {
+    pub(crate) head: u32,
+    pub(crate) tail: u32,
+    pub(crate) ring_mask: u32,
+    pub(crate) ring_entries: u32,
+    pub(crate) overflow: u32,
+    pub(crate) cqes: u32,
+    pub(crate) flags: u32,
+    pub(crate) resv1: u32,
+    pub(crate) user_addr: u64,
+}
+
+/// A submission queue entry.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(super) struct IoUringSqe {
+    pub(super) opcode: u8,
+    pub(super) flags: u8,
+    pub(super) ioprio: u16,
+    pub(super) fd: i32,
+    /// The file offset, or `addr2` for some opcodes.
+    pub(super) off: u64,
+    /// The buffer address, or the address of other arguments for some opcodes.
+    pub(super) addr: u64,
+    pub(super) len: u32,
+    /// The opcode-specific flags (e.g., `rw_flags`, `poll32_events`, and `msg_flags`).
+    pub(super) op_flags: u32,
+    pub(super) user_data: u64,
+    pub(super) buf_index: u16,
+    pub(super) personality: u16,
+    pub(super) file_index: i32,
+    pub(super) addr3: u64,
+    pub(super) pad2: u64,
+}
+
+/// A completion queue entry.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(super) struct IoUringCqe {
+    pub(super) user_data: u64,
+    pub(super) res: i32,
+    pub(super) flags: u32,
+}
+
+impl IoUringCqe {
+    pub(super) fn new(user_data: u64, res: i32) -> Self {
+        Self {
+            user_data,
+            res,
+            flags: 0,
+        }
+    }
+}
+
+/// The argument of `IORING_REGISTER_FILES_UPDATE`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(crate) struct IoUringFilesUpdate {
+    pub(crate) offset: u32,
+    pub(crate) resv: u32,
+    pub(crate) fds: u64,
+}
+
+/// The header of the argument of `IORING_REGISTER_PROBE`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(crate) struct IoUringProbe {
+    pub(crate) last_op: u8,
+    pub(crate) ops_len: u8,
+    pub(crate) resv: u16,
+    pub(crate) resv2: [u32; 3],
+}
+
+/// An entry of the argument of `IORING_REGISTER_PROBE`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(crate) struct IoUringProbeOp {
+    pub(crate) op: u8,
+    pub(crate) resv: u8,
+    pub(crate) flags: u16,
+    pub(crate) resv2: u32,
+}
+
+/// The flag in [`IoUringProbeOp::flags`] indicating that the opcode is supported.
+pub(crate) const IO_URING_OP_SUPPORTED: u16 = 1 << 0;
@@ -0,0 +1,231 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::IoUringFile;
+use crate::{
+    fs::file::{FileLike, file_table::RawFileDesc},
+    prelude::*,
+    util::UserIoVec,
+};
+
+/// The maximum number of registered files.
+const IORING_MAX_FIXED_FILES: usize = 1 << 15;
+/// The maximum number of registered buffers.
+const IORING_MAX_REG_BUFFERS: usize = 1 << 14;
+/// The maximum length of a registered buffer.
+const IORING_MAX_REG_BUFFER_LEN: usize = 1 << 30;
+
+/// The files and buffers registered via `io_uring_register`.
+///
+/// Registered files are referenced by SQEs with `IOSQE_FIXED_FILE`, which avoids looking up the
+/// file table for each request. Registered buffers are referenced by `IORING_OP_READ_FIXED` and
+/// `IORING_OP_WRITE_FIXED`.
+#[derive(Default)]
+pub(super) struct Registry {
+    files: Option<Vec<Option<Arc<dyn FileLike>>>>,
+    buffers: Option<Vec<RegisteredBuffer>>,
+}
+
+/// A registered buffer.
+///
+/// Unlike Linux, which pins the user pages at registration time, the buffer is accessed through
+/// the user space of the submitting task when a request is issued.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct RegisteredBuffer {
+    addr: Vaddr,
+    len: usize,
+}
+
+impl RegisteredBuffer {
+    /// Checks that `addr..addr + len` lies in the buffer and returns the range.
+    pub(super) fn check_range(&self, addr: Vaddr, len: usize) -> Result<(Vaddr, usize)> {
+        let end = addr
+            .checked_add(len)
+            .ok_or_else(|| Error::with_message(Errno::EFAULT, "the buffer range overflows"))?;
+        if addr < self.addr || end > self.addr + self.len {
+            return_errno_with_message!(Errno::EFAULT, "the range is outside the registered buffer");
+        }
+
+        Ok((addr, len))
+    }
+}
+
+impl Registry {
+    /// Registers the files referenced by the array of `nr_args` file descriptors at `fds_addr`.
+    ///
+    /// A file descriptor of `-1` leaves the slot empty, which can be updated later with
+    /// [`Self::update_files`]. io_uring instances cannot be registered.
+    pub(super) fn register_files(
+        &mut self,
+        fds_addr: Vaddr,
+        nr_args: u32,
+        ctx: &Context,
+    ) -> Result<()> {
+        if self.files.is_some() {
+            return_errno_with_message!(Errno::EBUSY, "the files are already registered");
+        }
+        let nr_args = nr_args as usize;
+        if nr_args == 0 {
+            return_errno_with_message!(Errno::EINVAL, "no files are specified");
+        }
+        if nr_args > IORING_MAX_FIXED_FILES {
+            return_errno_with_message!(Errno::EMFILE, "too many files are specified");
+        }
+
+        let mut files = Vec::with_capacity(nr_args);
+        files.resize_with(nr_args, || None);
+        fill_files(&mut files, 0, fds_addr, nr_args, ctx)?;
+        self.files = Some(files);
+
+        Ok(())
+    }
+
+    pub(super) fn unregister_files(&mut self) -> Result<()> {
+        if self.files.take().is_none() {
+            return_errno_with_message!(Errno::ENXIO, "no files are registered");
+        }
+
+        Ok(())
+    }
+
+    /// Replaces the registered files starting from `offset` with the files referenced by the
+    /// array of `nr_args` file descriptors at `fds_addr`.
+    ///
+    /// A file descriptor of `-1` clears the slot.
+    pub(super) fn update_files(
+        &mut self,
+        offset: u32,
+        fds_addr: Vaddr,
+        nr_args: u32,
+        ctx: &Context,
+    ) -> Result<u32> {
+        let Some(files) = self.files.as_mut() else {
+            return_errno_with_message!(Errno::ENXIO, "no files are registered");
+        };
+        let offset = offset as usize;
+        let nr_args = nr_args as usize;
+        if offset
+            .checked_add(nr_args)
+            .is_none_or(|end| end > files.len())
+        {
+            return_errno_with_message!(Errno::EINVAL, "the update is outside the registered files");
+        }
+
+        fill_files(files, offset, fds_addr, nr_args, ctx)?;
+
+        Ok(nr_args as u32)
+    }
+
+    /// Returns the registered file at `index`.
+    pub(super) fn file(&self, index: i32) -> Result<Arc<dyn FileLike>> {
+        let file = self
+            .files
+            .as_ref()
+            .and_then(|files| files.get(usize::try_from(index).ok()?))
+            .and_then(|file| file.clone());
+
+        file.ok_or_else(|| Error::with_message(Errno::EBADF, "the registered file does not exist"))
+    }
+
+    /// Registers the buffers described by the array of `nr_args` I/O vectors at `iovecs_addr`.
+    pub(super) fn register_buffers(
+        &mut self,
+        iovecs_addr: Vaddr,
+        nr_args: u32,
+        ctx: &Context,
+    ) -> Result<()> {
+        if self.buffers.is_some() {
+            return_errno_with_message!(Errno::EBUSY, "the buffers are already registered");
+        }
+        let nr_args = nr_args as usize;
+        if nr_args == 0 || nr_args > IORING_MAX_REG_BUFFERS {
+            return_errno_with_message!(Errno::EINVAL, "the number of buffers is invalid");
+        }
+
+        let user_space = ctx.user_space();
+        let mut buffers = Vec::with_capacity(nr_args);
+        for i in 0..nr_args {
+            let io_vec =
+                user_space.read_val::<UserIoVec>(iovecs_addr + i * size_of::<UserIoVec>())?;
+            if io_vec.len < 0 || io_vec.len as usize > IORING_MAX_REG_BUFFER_LEN {
+                return_errno_with_message!(Errno::EFAULT, "the buffer length is invalid");
+            }
+            if io_vec.base == 0 && io_vec.len != 0 {
+                return_errno_with_message!(Errno::EFAULT, "the buffer address is null");
+            }
+
+            let len = io_vec.len as usize;
+            // Check that the buffer lies in the user space.
+            let _ = user_space.reader(io_vec.base, len)?;
+
+            buffers.push(RegisteredBuffer {
+                addr: io_vec.base,
+                len,
+            });
+        }
+        self.buffers = Some(buffers);
+
+        Ok(())
+    }
+
+    pub(super) fn unregister_buffers(&mut self) -> Result<()> {
+        if self.buffers.take().is_none() {
+            return_errno_with_message!(Errno::ENXIO, "no buffers are registered");
+        }
+
+        Ok(())
+    }
+
+    /// Returns the registered buffer at `index`.
+    pub(super) fn buffer(&self, index: u16) -> Result<RegisteredBuffer> {
+        self.buffers
+            .as_ref()
+            .and_then(|buffers| buffers.get(index as usize))
+            .copied()
+            .ok_or_else(|| {
+                Error::with_message(Errno::EFAULT, "the registered buffer does not exist")
+            })
+    }
+}
+
+fn fill_files(
+    files: &mut [Option<Arc<dyn FileLike>>],
+    offset: usize,
+    fds_addr: Vaddr,
+    nr_args: usize,
+    ctx: &Context,
+) -> Result<()> {
+    let user_space = ctx.user_space();
+    let raw_fds = (0..nr_args)
+        .map(|i| user_space.read_val::<RawFileDesc>(fds_addr + i * size_of::<RawFileDesc>()))
+        .collect::<Result<Vec<_>>>()?;
+
+    // Look up all the files first so that the registered files are untouched upon errors.
+    let new_files = {
+        let file_table = ctx.thread_local.borrow_file_table();
+        let file_table_locked = file_table.unwrap().read();
+        raw_fds
+            .into_iter()
+            .map(|raw_fd| {
+                if raw_fd == -1 {
+                    return Ok(None);
+                }
+                let file = file_table_locked.get_file(raw_fd.try_into()?)?;
+                // A registered io_uring instance (including the ring itself) would keep the ring
+                // alive through a reference cycle.
+                if file.downcast_ref::<IoUringFile>().is_some() {
+                    return_errno_with_message!(
+                        Errno::EBADF,
+                        "io_uring instances cannot be registered"
+                    );
+                }
+                Ok(Some(file.clone()))
+            })
+            .collect::<Result<Vec<_>>>()?
+    };
+
+    for (slot, file) in files[offset..offset + nr_args].iter_mut().zip(new_files) {
+        *slot = file;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,367 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{
+    IoUringOp, IoUringSqe, SqeFlags,
+    registry::{RegisteredBuffer, Registry},
+};
+use crate::{
+    events::IoEvents,
+    fs::file::{CreationFlags, FileLike, StatusFlags, file_table::FdFlags},
+    net::socket::util::{MessageHeader, RecvFlags, SendFlags},
+    prelude::*,
+    util::{VmReaderArray, VmWriterArray, net::write_socket_addr_to_user},
+};
+
+/// A request that is parsed from an SQE.
+pub(super) struct Request {
+    op: IoUringOp,
+    sqe: IoUringSqe,
+    flags: SqeFlags,
+    file: Option<Arc<dyn FileLike>>,
+    /// The registered buffer of `IORING_OP_READ_FIXED` and `IORING_OP_WRITE_FIXED`.
+    buffer: Option<RegisteredBuffer>,
+}
+
+impl Request {
+    /// Parses the SQE and looks up the target file and the registered buffer.
+    pub(super) fn new(sqe: IoUringSqe, registry: &Registry, ctx: &Context) -> Result<Self> {
+        let op = IoUringOp::try_from(sqe.opcode)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the opcode is not supported"))?;
+
+        let flags = SqeFlags::from_bits(sqe.flags)
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the SQE flags are invalid"))?;
+        if !SqeFlags::SUPPORTED.contains(flags) {
+            return_errno_with_message!(Errno::EINVAL, "the SQE flags are not supported");
+        }
+        if sqe.personality != 0 {
+            return_errno_with_message!(Errno::EINVAL, "personalities are not supported");
+        }
+
+        let file = if op.needs_file() {
+            let file = if flags.contains(SqeFlags::IOSQE_FIXED_FILE) {
+                registry.file(sqe.fd)?
+            } else {
+                let file_table = ctx.thread_local.borrow_file_table();
+                let file_table_locked = file_table.unwrap().read();
+                file_table_locked.get_file(sqe.fd.try_into()?)?.clone()
+            };
+            Some(file)
+        } else {
+            None
+        };
+
+        let buffer = if matches!(op, IoUringOp::ReadFixed | IoUringOp::WriteFixed) {
+            Some(registry.buffer(sqe.buf_index)?)
+        } else {
+            None
+        };
+
+        Ok(Self {
+            op,
+            sqe,
+            flags,
+            file,
+            buffer,
+        })
+    }
+
+    pub(super) fn op(&self) -> IoUringOp {
+        self.op
+    }
+
+    pub(super) fn sqe(&self) -> &IoUringSqe {
+        &self.sqe
+    }
+
+    pub(super) fn user_data(&self) -> u64 {
+        self.sqe.user_data
+    }
+
+    /// Returns whether the CQE should be omitted if the request succeeds.
+    pub(super) fn skips_success(&self) -> bool {
+        self.flags.contains(SqeFlags::IOSQE_CQE_SKIP_SUCCESS)
+    }
+
+    /// Returns the target file.
+    ///
+    /// # Panics
+    ///
+    /// This method panics if the opcode does not operate on a file.
+    pub(super) fn file(&self) -> &Arc<dyn FileLike> {
+        self.file.as_ref().unwrap()
+    }
+
+    /// Returns the events to wait for if the request cannot complete immediately.
+    ///
+    /// This method returns `None` if the request never needs to wait for the file.
+    pub(super) fn wait_mask(&self) -> Option<IoEvents> {
+        let mask = match self.op {
+            IoUringOp::Readv
+            | IoUringOp::ReadFixed
+            | IoUringOp::Read
+            | IoUringOp::Recv
+            | IoUringOp::Accept => IoEvents::IN,
+            IoUringOp::Writev | IoUringOp::WriteFixed | IoUringOp::Write | IoUringOp::Send => {
+                IoEvents::OUT
+            }
+            IoUringOp::PollAdd => self.poll_events(),
+            IoUringOp::Nop
+            | IoUringOp::Fsync
+            | IoUringOp::PollRemove
+            | IoUringOp::Timeout
+            | IoUringOp::TimeoutRemove => return None,
+        };
+
+        // Errors and hangups should also wake up the request so that it can fail.
+        Some(mask | IoEvents::ERR | IoEvents::HUP)
+    }
+
+    /// Returns whether the request should wait for the file if it cannot complete immediately.
+    ///
+    /// Otherwise, the request completes with `EAGAIN`, which follows the semantics of
+    /// nonblocking files.
+    pub(super) fn can_wait(&self) -> bool {
+        match self.op {
+            IoUringOp::PollAdd => true,
+            _ => {
+                self.wait_mask().is_some()
+                    && !self.file().status_flags().contains(StatusFlags::O_NONBLOCK)
+            }
+        }
+    }
+
+    fn poll_events(&self) -> IoEvents {
+        IoEvents::from_bits_truncate(self.sqe.op_flags)
+    }
+
+    /// Issues the request on the target file.
+    ///
+    /// If the request should wait for the file (see [`Self::can_wait`]), this method fails with
+    /// `EAGAIN` before performing any operation that may block.
+    pub(super) fn issue(&self, ctx: &Context) -> Result<i32> {
+        let file = self.file();
+
+        if let Some(mask) = self.wait_mask()
+            && self.can_wait()
+        {
+            let events = file.poll(mask, None);
+            if events.is_empty() {
+                return_errno_with_message!(Errno::EAGAIN, "the file is not ready");
+            }
+            if self.op == IoUringOp::PollAdd {
+                return Ok(events.bits() as i32);
+            }
+        }
+
+        let res = match self.op {
+            IoUringOp::Read => {
+                let user_space = ctx.user_space();
+                let mut writer =
+                    user_space.writer(self.sqe.addr as Vaddr, self.sqe.len as usize)?;
+                self.read(file, &mut writer)?
+            }
+            IoUringOp::Write => {
+                let user_space = ctx.user_space();
+                let mut reader =
+                    user_space.reader(self.sqe.addr as Vaddr, self.sqe.len as usize)?;
+                self.write(file, &mut reader)?
+            }
+            IoUringOp::ReadFixed => {
+                let (addr, len) = self.fixed_buffer()?;
+                let user_space = ctx.user_space();
+                let mut writer = user_space.writer(addr, len)?;
+                self.read(file, &mut writer)?
+            }
+            IoUringOp::WriteFixed => {
+                let (addr, len) = self.fixed_buffer()?;
+                let user_space = ctx.user_space();
+                let mut reader = user_space.reader(addr, len)?;
+                self.write(file, &mut reader)?
+            }
+            IoUringOp::Readv => {
+                let user_space = ctx.user_space();
+                let mut writer_array = VmWriterArray::from_user_io_vecs(
+                    &user_space,
+                    self.sqe.addr as Vaddr,
+                    self.sqe.len as usize,
+                )?;
+                self.readv(file, writer_array.writers_mut())?
+            }
+            IoUringOp::Writev => {
+                let user_space = ctx.user_space();
+                let mut reader_array = VmReaderArray::from_user_io_vecs(
+                    &user_space,
+                    self.sqe.addr as Vaddr,
+                    self.sqe.len as usize,
+                )?;
+                self.writev(file, reader_array.readers_mut())?
+            }
+            IoUringOp::Fsync => {
+                const IORING_FSYNC_DATASYNC: u32 = 1 << 0;
+
+                let path = file.as_inode_handle_or_err()?.path();
+                if self.sqe.op_flags & IORING_FSYNC_DATASYNC != 0 {
+                    path.sync_data()?;
+                } else {
+                    path.sync_all()?;
+                }
+                0
+            }
+            IoUringOp::Accept => self.accept(file, ctx)?,
+            IoUringOp::Send => {
+                let socket = file.as_socket_or_err()?;
+                let flags = SendFlags::from_bits_truncate(self.sqe.op_flags as i32);
+                let user_space = ctx.user_space();
+                let mut reader =
+                    user_space.reader(self.sqe.addr as Vaddr, self.sqe.len as usize)?;
+                socket.sendmsg(&mut reader, MessageHeader::new(None, Vec::new()), flags)?
+            }
+            IoUringOp::Recv => {
+                let socket = file.as_socket_or_err()?;
+                let flags = RecvFlags::from_bits_truncate(self.sqe.op_flags as i32);
+                let user_space = ctx.user_space();
+                let mut writer =
+                    user_space.writer(self.sqe.addr as Vaddr, self.sqe.len as usize)?;
+                let (output, _) = socket.recvmsg(&mut writer, flags)?;
+                output.len()
+            }
+            IoUringOp::PollAdd => {
+                // Nonblocking polls are handled above.
+                unreachable!()
+            }
+            IoUringOp::Nop
+            | IoUringOp::PollRemove
+            | IoUringOp::Timeout
+            | IoUringOp::TimeoutRemove => {
+                // These requests are handled by the ring itself.
+                unreachable!()
+            }
+        };
+
+        Ok(res.try_into().unwrap_or(i32::MAX))
+    }
+
+    /// Returns the file offset, or `None` to use (and update) the current file position.
+    fn offset(&self) -> Result<Option<usize>> {
+        match self.sqe.off.cast_signed() {
+            -1 => Ok(None),
+            offset if offset < 0 => {
+                return_errno_with_message!(Errno::EINVAL, "the offset cannot be negative")
+            }
+            offset => Ok(Some(offset as usize)),
+        }
+    }
+
+    fn fixed_buffer(&self) -> Result<(Vaddr, usize)> {
+        let buffer = self.buffer.as_ref().unwrap();
+        buffer.check_range(self.sqe.addr as Vaddr, self.sqe.len as usize)
+    }
+
+    fn read(&self, file: &Arc<dyn FileLike>, writer: &mut VmWriter) -> Result<usize> {
+        match self.offset()? {
+            Some(offset) => file.read_at(offset, writer),
+            None => file.read(writer),
+        }
+    }
+
+    fn write(&self, file: &Arc<dyn FileLike>, reader: &mut VmReader) -> Result<usize> {
+        match self.offset()? {
+            Some(offset) => file.write_at(offset, reader),
+            None => file.write(reader),
+        }
+    }
+
+    fn readv(&self, file: &Arc<dyn FileLike>, writers: &mut [VmWriter]) -> Result<usize> {
+        let mut offset = self.offset()?;
+        let mut total_len = 0;
+
+        for writer in writers {
+            let expected_len = writer.avail();
+            let res = match offset {
+                Some(offset) => file.read_at(offset, writer),
+                None => file.read(writer),
+            };
+            match res {
+                Ok(read_len) => {
+                    total_len += read_len;
+                    offset = offset.map(|offset| offset + read_len);
+                    if read_len < expected_len {
+                        break;
+                    }
+                }
+                Err(_) if total_len > 0 => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(total_len)
+    }
+
+    fn writev(&self, file: &Arc<dyn FileLike>, readers: &mut [VmReader]) -> Result<usize> {
+        let mut offset = self.offset()?;
+        let mut total_len = 0;
+
+        for reader in readers {
+            let expected_len = reader.remain();
+            let res = match offset {
+                Some(offset) => file.write_at(offset, reader),
+                None => file.write(reader),
+            };
+            match res {
+                Ok(write_len) => {
+                    total_len += write_len;
+                    offset = offset.map(|offset| offset + write_len);
+                    if write_len < expected_len {
+                        break;
+                    }
+                }
+                Err(_) if total_len > 0 => break,
+                Err(err) => return Err(err),
+            }
+        }
+
+        Ok(total_len)
+    }
+
+    fn accept(&self, file: &Arc<dyn FileLike>, ctx: &Context) -> Result<usize> {
+        const SOCK_NONBLOCK: u32 = StatusFlags::O_NONBLOCK.bits();
+        const SOCK_CLOEXEC: u32 = CreationFlags::O_CLOEXEC.bits();
+
+        let flags = self.sqe.op_flags;
+        if flags & !(SOCK_NONBLOCK | SOCK_CLOEXEC) != 0 {
+            return_errno_with_message!(Errno::EINVAL, "invalid accept flags");
+        }
+
+        let socket = file.as_socket_or_err()?;
+        let (connected_socket, socket_addr) = socket.accept(flags & SOCK_NONBLOCK != 0)?;
+
+        let sockaddr_ptr = self.sqe.addr as Vaddr;
+        let addrlen_ptr = self.sqe.off as Vaddr;
+        if sockaddr_ptr != 0 {
+            write_socket_addr_to_user(&socket_addr, sockaddr_ptr, addrlen_ptr)?;
+        }
+
+        let fd_flags = if flags & SOCK_CLOEXEC != 0 {
+            FdFlags::CLOEXEC
+        } else {
+            FdFlags::empty()
+        };
+        let fd = {
+            let file_table = ctx.thread_local.borrow_file_table();
+            let mut file_table_locked = file_table.unwrap().write();
+            file_table_locked.insert(connected_socket, fd_flags)
+        };
+
+        Ok(fd.into())
+    }
+}
+
+impl IoUringOp {
+    /// Returns whether the opcode operates on a file specified by [`IoUringSqe::fd`].
+    fn needs_file(self) -> bool {
+        !matches!(
+            self,
+            Self::Nop | Self::PollRemove | Self::Timeout | Self::TimeoutRemove
+        )
+    }
+}
@@ -0,0 +1,311 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::mem::offset_of;
+
+use align_ext::AlignExt;
+use ostd::const_assert;
+
+use super::{
+    IORING_OFF_CQ_RING, IORING_OFF_SQ_RING, IORING_OFF_SQES, IoCqringOffsets, IoSqringOffsets,
+    IoUringCqe, IoUringSqe,
+};
+use crate::{
+    prelude::*,
+    vm::page_cache::{Vmo, VmoOptions},
+};
+
+/// The memory regions that are shared between the kernel and userspace.
+///
+/// All the regions are backed by a single VMO:
+///  - The rings, which start with [`RingHeader`], followed by the CQE array and the SQ index
+///    array, are at [`IORING_OFF_SQ_RING`] and are aliased at [`IORING_OFF_CQ_RING`].
+///  - The SQE array is at [`IORING_OFF_SQES`].
+///
+/// Since the VMO pages are committed lazily, the unused gaps between the regions do not consume
+/// any memory.
+pub(super) struct SharedRings {
+    vmo: Arc<Vmo>,
+    sq_entries: u32,
+    cq_entries: u32,
+}
+
+/// The header of the rings.
+///
+/// Userspace locates each field by the offsets reported in [`IoSqringOffsets`] and
+/// [`IoCqringOffsets`], so the layout here is not a part of the ABI.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct RingHeader {
+    sq_head: u32,
+    sq_tail: u32,
+    cq_head: u32,
+    cq_tail: u32,
+    sq_ring_mask: u32,
+    cq_ring_mask: u32,
+    sq_ring_entries: u32,
+    cq_ring_entries: u32,
+    sq_dropped: u32,
+    sq_flags: u32,
+    cq_flags: u32,
+    cq_overflow: u32,
+}
+
+/// The offset of the CQE array in the rings.
+const CQES_OFFSET: usize = 64;
+const_assert!(size_of::<RingHeader>() <= CQES_OFFSET);
+
+impl SharedRings {
+    /// Allocates the shared memory for the rings.
+    ///
+    /// Both `sq_entries` and `cq_entries` must be powers of two.
+    pub(super) fn new(sq_entries: u32, cq_entries: u32) -> Result<Self> {
+        debug_assert!(sq_entries.is_power_of_two() && cq_entries.is_power_of_two());
+
+        let sq_array_offset = CQES_OFFSET + cq_entries as usize * size_of::<IoUringCqe>();
+        let rings_size =
+            (sq_array_offset + sq_entries as usize * size_of::<u32>()).align_up(PAGE_SIZE);
+        debug_assert!(rings_size <= IORING_OFF_CQ_RING);
+
+        let sqes_size = (sq_entries as usize * size_of::<IoUringSqe>()).align_up(PAGE_SIZE);
+        let vmo = VmoOptions::new(IORING_OFF_SQES + sqes_size).alloc()?;
+        vmo.alias_anon_pages(IORING_OFF_SQ_RING, IORING_OFF_CQ_RING, rings_size)?;
+
+        let rings = Self {
+            vmo,
+            sq_entries,
+            cq_entries,
+        };
+
+        let header = RingHeader {
+            sq_head: 0,
+            sq_tail: 0,
+            cq_head: 0,
+            cq_tail: 0,
+            sq_ring_mask: sq_entries - 1,
+            cq_ring_mask: cq_entries - 1,
+            sq_ring_entries: sq_entries,
+            cq_ring_entries: cq_entries,
+            sq_dropped: 0,
+            sq_flags: 0,
+            cq_flags: 0,
+            cq_overflow: 0,
+        };
+        rings.write_val(IORING_OFF_SQ_RING, &header)?;
+
+        Ok(rings)
+    }
+
+    /// Returns the VMO that backs the shared memory.
+    pub(super) fn vmo(&self) -> &Arc<Vmo> {
+        &self.vmo
+    }
+
+    pub(super) fn sq_entries(&self) -> u32 {
+        self.sq_entries
+    }
+
+    pub(super) fn cq_entries(&self) -> u32 {
+        self.cq_entries
+    }
+
+    /// Returns the offsets of the SQ ring fields.
+    pub(super) fn sq_offsets(&self) -> IoSqringOffsets {
+        IoSqringOffsets {
+            head: offset_of!(RingHeader, sq_head) as u32,
+            tail: offset_of!(RingHeader, sq_tail) as u32,
+            ring_mask: offset_of!(RingHeader, sq_ring_mask) as u32,
+            ring_entries: offset_of!(RingHeader, sq_ring_entries) as u32,
+            flags: offset_of!(RingHeader, sq_flags) as u32,
+            dropped: offset_of!(RingHeader, sq_dropped) as u32,
+            array: self.sq_array_offset() as u32,
+            resv1: 0,
+            user_addr: 0,
+        }
+    }
+
+    /// Returns the offsets of the CQ ring fields.
+    pub(super) fn cq_offsets(&self) -> IoCqringOffsets {
+        IoCqringOffsets {
+            head: offset_of!(RingHeader, cq_head) as u32,
+            tail: offset_of!(RingHeader, cq_tail) as u32,
+            ring_mask: offset_of!(RingHeader, cq_ring_mask) as u32,
+            ring_entries: offset_of!(RingHeader, cq_ring_entries) as u32,
+            overflow: offset_of!(RingHeader, cq_overflow) as u32,
+            cqes: CQES_OFFSET as u32,
+            flags: offset_of!(RingHeader, cq_flags) as u32,
+            resv1: 0,
+            user_addr: 0,
+        }
+    }
+
+    fn sq_array_offset(&self) -> usize {
+        CQES_OFFSET + self.cq_entries as usize * size_of::<IoUringCqe>()
+    }
+
+    /// Returns the number of SQEs that are submitted by userspace but not consumed by the kernel.
+    pub(super) fn sq_pending(&self) -> Result<u32> {
+        let head = self.read_val::<u32>(offset_of!(RingHeader, sq_head))?;
+        let tail = self.read_val::<u32>(offset_of!(RingHeader, sq_tail))?;
+
+        // A malicious userspace program may set the tail arbitrarily. Clamp the value to avoid
+        // looping for too long.
+        Ok(tail.wrapping_sub(head).min(self.sq_entries))
+    }
+
+    /// Consumes the SQE at the SQ head.
+    ///
+    /// This method returns `None` if the SQ index array refers to an invalid SQE, in which case
+    /// the SQE is dropped and the `dropped` counter is increased.
+    ///
+    /// The caller must ensure that there are pending SQEs (see [`Self::sq_pending`]).
+    pub(super) fn pop_sqe(&self) -> Result<Option<IoUringSqe>> {
+        let head_offset = offset_of!(RingHeader, sq_head);
+        let head = self.read_val::<u32>(head_offset)?;
+
+        let array_idx = (head & (self.sq_entries - 1)) as usize;
+        let sqe_idx =
+            self.read_val::<u32>(self.sq_array_offset() + array_idx * size_of::<u32>())?;
+
+        let sqe = if sqe_idx < self.sq_entries {
+            Some(self.read_val::<IoUringSqe>(
+                IORING_OFF_SQES + sqe_idx as usize * size_of::<IoUringSqe>(),
+            )?)
+        } else {
+            let dropped_offset = offset_of!(RingHeader, sq_dropped);
+            let dropped = self.read_val::<u32>(dropped_offset)?;
+            self.write_val(dropped_offset, &dropped.wrapping_add(1))?;
+            None
+        };
+
+        self.write_val(head_offset, &head.wrapping_add(1))?;
+
+        Ok(sqe)
+    }
+
+    /// Returns the number of CQEs that are posted by the kernel but not consumed by userspace.
+    pub(super) fn cq_ready(&self) -> Result<u32> {
+        let head = self.read_val::<u32>(offset_of!(RingHeader, cq_head))?;
+        let tail = self.read_val::<u32>(offset_of!(RingHeader, cq_tail))?;
+
+        Ok(tail.wrapping_sub(head).min(self.cq_entries))
+    }
+
+    /// Posts a CQE at the CQ tail.
+    ///
+    /// This method returns `false` if the CQ is full.
+    pub(super) fn push_cqe(&self, cqe: &IoUringCqe) -> Result<bool> {
+        let head = self.read_val::<u32>(offset_of!(RingHeader, cq_head))?;
+        let tail_offset = offset_of!(RingHeader, cq_tail);
+        let tail = self.read_val::<u32>(tail_offset)?;
+
+        if tail.wrapping_sub(head) >= self.cq_entries {
+            return Ok(false);
+        }
+
+        let cqe_idx = (tail & (self.cq_entries - 1)) as usize;
+        self.write_val(CQES_OFFSET + cqe_idx * size_of::<IoUringCqe>(), cqe)?;
+        self.write_val(tail_offset, &tail.wrapping_add(1))?;
+
+        Ok(true)
+    }
+
+    /// Sets or clears the `IORING_SQ_CQ_OVERFLOW` flag, which tells userspace that there are
+    /// CQEs that cannot be posted because the CQ is full.
+    pub(super) fn set_cq_overflow_flag(&self, is_overflow: bool) -> Result<()> {
+        const IORING_SQ_CQ_OVERFLOW: u32 = 1 << 1;
+
+        let flags_offset = offset_of!(RingHeader, sq_flags);
+        let flags = self.read_val::<u32>(flags_offset)?;
+        let new_flags = if is_overflow {
+            flags | IORING_SQ_CQ_OVERFLOW
+        } else {
+            flags & !IORING_SQ_CQ_OVERFLOW
+        };
+        if new_flags != flags {
+            self.write_val(flags_offset, &new_flags)?;
+        }
+
+        Ok(())
+    }
+
+    fn read_val<T: Pod>(&self, offset: usize) -> Result<T> {
+        let mut val = T::new_zeroed();
+        self.vmo.read(
+            offset,
+            &mut VmWriter::from(val.as_mut_bytes()).to_fallible(),
+        )?;
+        Ok(val)
+    }
+
+    fn write_val<T: Pod>(&self, offset: usize, val: &T) -> Result<()> {
+        self.vmo
+            .write(offset, &mut VmReader::from(val.as_bytes()).to_fallible())
+    }
+}
+
+#[cfg(ktest)]
+mod tests {
+    use ostd::prelude::ktest;
+
+    use super::*;
+
+    #[ktest]
+    fn cq_ring_is_aliased() {
+        let rings = SharedRings::new(4, 8).unwrap();
+
+        let cq_ring_entries = rings
+            .read_val::<u32>(IORING_OFF_CQ_RING + rings.cq_offsets().ring_entries as usize)
+            .unwrap();
+        assert_eq!(cq_ring_entries, 8);
+
+        assert!(rings.push_cqe(&IoUringCqe::new(42, 0)).unwrap());
+        let cq_tail = rings
+            .read_val::<u32>(IORING_OFF_CQ_RING + rings.cq_offsets().tail as usize)
+            .unwrap();
+        assert_eq!(cq_tail, 1);
+    }
+
+    #[ktest]
+    fn push_cqe_until_full() {
+        let rings = SharedRings::new(2, 4).unwrap();
+
+        for i in 0..4 {
+            assert!(rings.push_cqe(&IoUringCqe::new(i, 0)).unwrap());
+        }
+        assert!(!rings.push_cqe(&IoUringCqe::new(4, 0)).unwrap());
+        assert_eq!(rings.cq_ready().unwrap(), 4);
+
+        // Consume one CQE as userspace does.
+        rings
+            .write_val(rings.cq_offsets().head as usize, &1u32)
+            .unwrap();
+        assert_eq!(rings.cq_ready().unwrap(), 3);
+        assert!(rings.push_cqe(&IoUringCqe::new(4, 0)).unwrap());
+    }
+
+    #[ktest]
+    fn pop_sqe_drops_invalid_index() {
+        let rings = SharedRings::new(2, 4).unwrap();
+        let sq_off = rings.sq_offsets();
+
+        // Submit two SQEs, where the second one refers to an invalid index.
+        let sqe = IoUringSqe {
+            user_data: 7,
+            ..IoUringSqe::new_zeroed()
+        };
+        rings.write_val(IORING_OFF_SQES, &sqe).unwrap();
+        rings.write_val(sq_off.array as usize, &0u32).unwrap();
+        rings
+            .write_val(sq_off.array as usize + size_of::<u32>(), &2u32)
+            .unwrap();
+        rings.write_val(sq_off.tail as usize, &2u32).unwrap();
+
+        assert_eq!(rings.sq_pending().unwrap(), 2);
+        assert_eq!(rings.pop_sqe().unwrap().unwrap().user_data, 7);
+        assert!(rings.pop_sqe().unwrap().is_none());
+        assert_eq!(rings.sq_pending().unwrap(), 0);
+
+        let dropped = rings.read_val::<u32>(sq_off.dropped as usize).unwrap();
+        assert_eq!(dropped, 1);
+    }
+}
@@ -3,6 +3,7 @@
 pub(crate) mod file;
 mod fs_impls;
 pub(crate) mod initramfs;
+pub(crate) mod io_uring;
 pub(crate) mod pipe;
 pub(crate) mod rootfs;
 pub(crate) mod thread_info;
@@ -10,7 +11,8 @@ pub(crate) mod utils;
 pub(crate) mod vfs;
 
 pub(crate) use fs_impls::{
-    cgroupfs, configfs, devpts, exfat, ext2, procfs, pseudofs, ramfs, sysfs, tmpfs,
+    cgroupfs, configfs, devpts, exfat, ext2, mqueuefs, procfs, pseudofs, ramfs, securityfs, sysfs,
+    tmpfs,
 };
 
 use crate::{
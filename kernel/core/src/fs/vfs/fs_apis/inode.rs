@@ -265,6 +265,14 @@ impl MknodType {
             MknodType::BlockDevice(_) => Some(DeviceType::Block),
         }
     }
+
+    pub(crate) fn inode_type(&self) -> InodeType {
+        match self {
+            MknodType::NamedPipe => InodeType::NamedPipe,
+            MknodType::CharDevice(_) => InodeType::CharDevice,
+            MknodType::BlockDevice(_) => InodeType::BlockDevice,
+        }
+    }
 }
 
 bitflags! {
@@ -29,9 +29,12 @@ use crate::{
     },
     prelude::*,
     process::{
-        Gid, Uid, UserNamespace, credentials::capabilities::CapSet, posix_thread::AsPosixThread,
+        Gid, Uid, UserNamespace,
+        credentials::capabilities::CapSet,
+        posix_thread::{AsPosixThread, PosixThread},
     },
-    security::lsm::hooks as lsm_hooks,
+    security::lsm::{FileLsm, hooks as lsm_hooks},
+    thread::Thread,
 };
 
 mod dentry;
@@ -73,6 +76,14 @@ impl Path {
     ) -> Result<Self> {
         let dir_dentry = self.dentry.as_dir_dentry_or_err()?;
         self.check_dir_entry_mutation()?;
+        run_lsm_hook(|posix_thread| {
+            lsm_hooks::on_path_create(lsm_hooks::PathCreateContext::new(
+                posix_thread,
+                self,
+                name,
+                type_,
+            ))
+        })?;
         let new_child_dentry = dir_dentry.create(name, type_, mode)?;
         Ok(Self::new(self.mount.clone(), new_child_dentry))
     }
@@ -166,6 +177,17 @@ impl Path {
             );
         }
 
+        let file_lsm = FileLsm::new();
+        run_lsm_hook(|posix_thread| {
+            lsm_hooks::on_path_open(lsm_hooks::PathOpenContext::new(
+                posix_thread,
+                self,
+                open_args.access_mode,
+                *status_flags,
+                &file_lsm,
+            ))
+        })?;
+
         if inode_type.is_regular_file()
             && creation_flags.contains(CreationFlags::O_TRUNC)
             && !status_flags.contains(StatusFlags::O_PATH)
@@ -173,7 +195,8 @@ impl Path {
             self.resize(0)?;
         }
 
-        InodeHandle::new(self.clone(), open_args.access_mode, *status_flags)
+        let inode_handle = InodeHandle::new(self.clone(), open_args.access_mode, *status_flags)?;
+        Ok(inode_handle.with_lsm(file_lsm))
     }
 
     /// Gets the parent `Path` within the same mount.
@@ -188,6 +211,28 @@ impl Path {
         Some(Self::new(self.mount.clone(), parent))
     }
 
+    /// Gets the parent `Path`, crossing mount boundaries.
+    ///
+    /// If the current path is the root of a mount, the parent is the parent of
+    /// the mount point. Unlike resolving `..`, this ignores the root directory
+    /// of the process, so walking up always ends at the root of the mount tree,
+    /// where `None` is returned.
+    pub(crate) fn effective_parent(&self) -> Option<Self> {
+        let mut mount = self.mount.clone();
+        let mut dentry = self.dentry.clone();
+
+        // Mount roots are skipped, along with the mount points they hide.
+        while Arc::ptr_eq(&dentry, mount.root_dentry()) {
+            // `upgrade()` may fail if the mount namespace has been dropped. Such
+            // a path is disconnected from any mount tree, so there is no parent.
+            let parent_mount = mount.parent()?.upgrade()?;
+            dentry = mount.mountpoint()?;
+            mount = parent_mount;
+        }
+
+        Some(Self::new(mount, dentry.parent()?))
+    }
+
     /// Gets the top `Path` of the current.
     ///
     /// Used when different file systems are mounted on the same mount point.
@@ -256,10 +301,23 @@ impl Path {
     }
 
     /// Returns true if the `Path` represents a pseudo file.
-    fn is_pseudo(&self) -> bool {
+    pub(crate) fn is_pseudo(&self) -> bool {
         self.dentry.is_pseudo()
     }
 
+    /// Returns the absolute path name of the `Path` from the root of its mount namespace.
+    ///
+    /// Unlike the path names seen by a thread, the result is not affected by `chroot`.
+    /// Returns `None` if the `Path` is a pseudo path, cannot be traced back to the root of
+    /// the mount namespace, or belongs to a mount that is not in any mount namespace.
+    pub(crate) fn abs_path_in_mnt_ns(&self) -> Option<String> {
+        let mnt_ns = self.mount.mnt_ns().upgrade()?;
+        match mnt_ns.new_path_resolver().make_abs_path(self) {
+            AbsPathResult::Reachable(path_name) => Some(path_name),
+            AbsPathResult::Unreachable(_) => None,
+        }
+    }
+
     fn this(&self) -> Self {
         self.clone()
     }
@@ -666,6 +724,14 @@ impl Path {
     pub(crate) fn mknod(&self, name: &str, mode: InodeMode, type_: MknodType) -> Result<Self> {
         let dir_dentry = self.dentry.as_dir_dentry_or_err()?;
         self.check_dir_entry_mutation()?;
+        run_lsm_hook(|posix_thread| {
+            lsm_hooks::on_path_create(lsm_hooks::PathCreateContext::new(
+                posix_thread,
+                self,
+                name,
+                type_.inode_type(),
+            ))
+        })?;
         let inner = dir_dentry.mknod(name, mode, type_)?;
         Ok(Self::new(self.mount.clone(), inner))
     }
@@ -679,6 +745,14 @@ impl Path {
         let dir_dentry = self.dentry.as_dir_dentry_or_err()?;
         old.check_hardlink_source()?;
         self.check_dir_entry_mutation()?;
+        run_lsm_hook(|posix_thread| {
+            lsm_hooks::on_path_link(lsm_hooks::PathLinkContext::new(
+                posix_thread,
+                old,
+                self,
+                name,
+            ))
+        })?;
         dir_dentry.link(old.inode(), name)
     }
 
@@ -686,6 +760,9 @@ impl Path {
     pub(crate) fn unlink(&self, name: &str) -> Result<()> {
         let dir_dentry = self.dentry.as_dir_dentry_or_err()?;
         self.check_dir_entry_mutation()?;
+        run_lsm_hook(|posix_thread| {
+            lsm_hooks::on_path_unlink(lsm_hooks::DirEntryContext::new(posix_thread, self, name))
+        })?;
         dir_dentry.unlink(name)
     }
 
@@ -693,6 +770,9 @@ impl Path {
     pub(crate) fn rmdir(&self, name: &str) -> Result<()> {
         let dir_dentry = self.dentry.as_dir_dentry_or_err()?;
         self.check_dir_entry_mutation()?;
+        run_lsm_hook(|posix_thread| {
+            lsm_hooks::on_path_rmdir(lsm_hooks::DirEntryContext::new(posix_thread, self, name))
+        })?;
         dir_dentry.rmdir(name)
     }
 
@@ -716,6 +796,17 @@ impl Path {
             new_dir.check_dir_entry_mutation()?;
         }
 
+        run_lsm_hook(|posix_thread| {
+            lsm_hooks::on_path_rename(lsm_hooks::PathRenameContext::new(
+                posix_thread,
+                self,
+                old_name,
+                new_dir,
+                new_name,
+                mode,
+            ))
+        })?;
+
         old_dir_dentry.rename(old_name, &new_dir_dentry, new_name, mode)
     }
 }
@@ -749,6 +840,9 @@ impl Path {
     /// Resizes the file.
     pub(crate) fn resize(&self, size: usize) -> Result<()> {
         self.inode().check_permission(Permission::MAY_WRITE)?;
+        run_lsm_hook(|posix_thread| {
+            lsm_hooks::on_path_truncate(lsm_hooks::PathContext::new(posix_thread, self))
+        })?;
         self.resize_unchecked_access(size)
     }
 
@@ -795,6 +889,21 @@ impl Path {
     }
 }
 
+/// Runs an LSM hook on behalf of the current thread.
+///
+/// Operations performed by kernel threads, e.g., when populating the initial
+/// file system, are not subject to LSM checks.
+fn run_lsm_hook(hook: impl FnOnce(&PosixThread) -> Result<()>) -> Result<()> {
+    let Some(current_thread) = Thread::current() else {
+        return Ok(());
+    };
+    let Some(posix_thread) = current_thread.as_posix_thread() else {
+        return Ok(());
+    };
+
+    hook(posix_thread)
+}
+
 fn capable_modify_file_cap(inode: &dyn Inode, ctx: &Context) -> Result<()> {
     lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
         ctx.thread_local.borrow_user_ns().as_ref(),
@@ -49,6 +49,7 @@ fn init() {
     crate::util::random::init();
     crate::driver::init();
     crate::time::init();
+    crate::vm::init();
     crate::net::init();
     crate::sched::init();
     crate::process::init();
@@ -253,6 +254,7 @@ fn init_in_first_kthread(path_resolver: &PathResolver) {
     // Work queue should be initialized before interrupt is enabled,
     // in case any irq handler uses work queue as bottom half
     crate::thread::work_queue::init_in_first_kthread();
+    crate::vm::init_in_first_kthread();
     crate::device::init_in_first_kthread();
     crate::net::init_in_first_kthread();
     crate::fs::init_in_first_kthread(path_resolver);
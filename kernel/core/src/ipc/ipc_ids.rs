@@ -3,6 +3,7 @@
 use aster_util::ranged_integer::RangedU32;
 use id_alloc::IdAlloc;
 
+use super::{IPC_PRIVATE, IpcKey};
 use crate::prelude::*;
 
 /// An IPC ID.
@@ -11,12 +12,20 @@ pub(crate) type IpcId = RangedU32<1, { i32::MAX as u32 }>;
 /// Maps IPC IDs to objects and manages ID allocation.
 ///
 /// Lock ordering:
-/// `objects` -> `id_allocator`.
+/// `inner` -> `id_allocator`.
 pub(super) struct IpcIds<T> {
-    objects: RwMutex<BTreeMap<IpcId, T>>,
+    inner: RwMutex<IpcIdsInner<T>>,
     id_allocator: SpinLock<IdAlloc>,
 }
 
+struct IpcIdsInner<T> {
+    objects: BTreeMap<IpcId, T>,
+    /// The IDs of the objects that can be looked up by their keys.
+    ///
+    /// Objects inserted by [`IpcIds::insert_at`] do not appear here.
+    keys: BTreeMap<IpcKey, IpcId>,
+}
+
 impl<T> IpcIds<T> {
     /// Creates an IPC ID table with IDs in `1..=max_id`.
     pub(super) fn new(max_id: IpcId) -> Self {
@@ -25,7 +34,10 @@ impl<T> IpcIds<T> {
         id_allocator.alloc_specific(0).unwrap();
 
         Self {
-            objects: RwMutex::new(BTreeMap::new()),
+            inner: RwMutex::new(IpcIdsInner {
+                objects: BTreeMap::new(),
+                keys: BTreeMap::new(),
+            }),
             id_allocator: SpinLock::new(id_allocator),
         }
     }
@@ -35,9 +47,9 @@ impl<T> IpcIds<T> {
     where
         F: FnOnce(&T) -> R,
     {
-        let objects = self.objects.read();
+        let inner = self.inner.read();
 
-        let Some(object) = objects.get(&id) else {
+        let Some(object) = inner.objects.get(&id) else {
             return Err(IdNotExistError);
         };
 
@@ -51,27 +63,102 @@ impl<T> IpcIds<T> {
     {
         use alloc::collections::btree_map::Entry;
 
-        let mut objects = self.objects.write();
+        let mut inner = self.inner.write();
 
-        let Entry::Occupied(entry) = objects.entry(id) else {
+        let Entry::Occupied(entry) = inner.objects.entry(id) else {
             return_errno_with_message!(Errno::EINVAL, "the ID does not exist");
         };
 
         may_remove(entry.get())?;
         entry.remove();
+        inner.keys.retain(|_, key_id| *key_id != id);
 
         self.id_allocator.lock().free(id.get() as usize);
 
         Ok(())
     }
 
+    /// Makes the object identified by `id` no longer able to be looked up by its key.
+    ///
+    /// The object itself remains accessible by its ID.
+    pub(super) fn forget_key(&self, id: IpcId) {
+        let mut inner = self.inner.write();
+        inner.keys.retain(|_, key_id| *key_id != id);
+    }
+
+    /// Calls `op` with the object identified by `key`, or inserts a new object with an
+    /// automatically allocated ID if no object is identified by `key`.
+    ///
+    /// The new object is created by `new_object_fn`. It can be looked up by `key` later unless
+    /// `key` is [`IPC_PRIVATE`]. No new object will be inserted if there are already `max_len`
+    /// objects.
+    pub(super) fn with_key_or_insert<F, G>(
+        &self,
+        key: IpcKey,
+        max_len: usize,
+        op: F,
+        new_object_fn: G,
+    ) -> Result<IpcId>
+    where
+        F: FnOnce(IpcId, &T) -> Result<()>,
+        G: FnOnce(IpcId) -> Result<T>,
+    {
+        let mut inner = self.inner.write();
+
+        if key != IPC_PRIVATE
+            && let Some(&id) = inner.keys.get(&key)
+        {
+            op(id, inner.objects.get(&id).unwrap())?;
+            return Ok(id);
+        }
+
+        if inner.objects.len() >= max_len {
+            return_errno_with_message!(Errno::ENOSPC, "too many IPC objects");
+        }
+
+        let id = self.insert_auto_locked(&mut inner, new_object_fn)?;
+        if key != IPC_PRIVATE {
+            inner.keys.insert(key, id);
+        }
+
+        Ok(id)
+    }
+
+    /// Calls `op` with each object and its ID in ascending order of IDs.
+    pub(super) fn for_each<F>(&self, mut op: F)
+    where
+        F: FnMut(IpcId, &T),
+    {
+        let inner = self.inner.read();
+
+        for (id, object) in inner.objects.iter() {
+            op(*id, object);
+        }
+    }
+
+    /// Returns the number of objects.
+    pub(super) fn len(&self) -> usize {
+        self.inner.read().objects.len()
+    }
+
+    /// Returns the maximum ID in use, or `None` if there are no objects.
+    pub(super) fn max_id(&self) -> Option<IpcId> {
+        self.inner.read().objects.keys().next_back().copied()
+    }
+
     /// Inserts a new object with an automatically allocated ID.
     pub(super) fn insert_auto<F>(&self, new_object_fn: F) -> Result<IpcId>
     where
         F: FnOnce(IpcId) -> Result<T>,
     {
-        let mut objects = self.objects.write();
+        let mut inner = self.inner.write();
+        self.insert_auto_locked(&mut inner, new_object_fn)
+    }
 
+    fn insert_auto_locked<F>(&self, inner: &mut IpcIdsInner<T>, new_object_fn: F) -> Result<IpcId>
+    where
+        F: FnOnce(IpcId) -> Result<T>,
+    {
         let Some(id) = self
             .id_allocator
             .lock()
@@ -88,7 +175,7 @@ impl<T> IpcIds<T> {
                 return Err(err);
             }
         };
-        objects.insert(id, object);
+        inner.objects.insert(id, object);
 
         Ok(id)
     }
@@ -98,7 +185,7 @@ impl<T> IpcIds<T> {
     where
         F: FnOnce(IpcId) -> Result<T>,
     {
-        let mut objects = self.objects.write();
+        let mut inner = self.inner.write();
 
         if self
             .id_allocator
@@ -116,7 +203,7 @@ impl<T> IpcIds<T> {
                 return Err(err);
             }
         };
-        objects.insert(id, object);
+        inner.objects.insert(id, object);
 
         Ok(())
     }
@@ -2,29 +2,35 @@
 
 //! Defines the IPC namespace abstraction.
 //!
-//! An IPC namespace isolates System V IPC resources from other namespaces.
-//! It currently manages semaphore sets only, while message queues and shared
-//! memory remain to be added.
+//! An IPC namespace isolates System V IPC resources and POSIX message queues
+//! from other namespaces. It manages semaphore sets, message queues, and shared
+//! memory segments, and owns an instance of the mqueue file system.
 //!
-//! Each namespace stores its semaphore sets in a per-namespace map keyed by
-//! IPC key and uses a dedicated ID allocator to assign semaphore identifiers.
+//! Each namespace stores each type of IPC objects in a per-namespace map keyed
+//! by IPC ID and uses a dedicated ID allocator to assign the identifiers.
 
+use align_ext::AlignExt;
 use aster_rights::ReadOp;
 use spin::Once;
 
 use super::{
-    IPC_PRIVATE, IpcFlags, IpcId, IpcKey,
+    IPC_PRIVATE, IpcFlags, IpcId, IpcKey, IpcPermission, PermissionMode,
     ipc_ids::IpcIds,
-    semaphore::system_v::{
-        PermissionMode,
-        sem_set::{SEMMNI, SemaphoreSet},
-    },
+    msg::{IPCMNI, MsgLimits, MsgQueue, MsqidDs},
+    semaphore::system_v::sem_set::{SEMMNI, SemaphoreSet},
+    shm::{SHMMNI, ShmAtFlags, ShmFile, ShmSegment, ShmUsageInfo},
 };
 use crate::{
-    fs::pseudofs::{NsCommonOps, NsType, StashedDentry},
+    fs::{
+        file::AccessMode,
+        mqueuefs::MqueueFs,
+        pseudofs::{NsCommonOps, NsType, StashedDentry},
+        vfs::path::Mount,
+    },
     prelude::*,
     process::{
-        Credentials, UserNamespace, credentials::capabilities::CapSet, posix_thread::PosixThread,
+        Credentials, ResourceType, UserNamespace, credentials::capabilities::CapSet,
+        posix_thread::PosixThread,
     },
     security::lsm::hooks as lsm_hooks,
 };
@@ -32,15 +38,26 @@ use crate::{
 /// The IPC namespace.
 ///
 /// An IPC namespace isolates System V IPC objects
-/// (semaphores, message queues, shared memory).
+/// (semaphores, message queues, shared memory)
+/// and POSIX message queues.
 /// Each namespace maintains its own independent set
 /// of IPC resources and identifier allocator.
 ///
 /// Lock ordering:
-/// `sem_ids` -> `SemaphoreSet::inner`.
+/// `sem_ids` -> `SemaphoreSet::inner`,
+/// `msg_ids` -> `MsgQueue::inner`,
+/// `shm_ids` -> `ShmSegment::inner`.
 pub(crate) struct IpcNamespace {
     /// Semaphore sets within this namespace.
     sem_ids: IpcIds<SemaphoreSet>,
+    /// Message queues within this namespace.
+    msg_ids: IpcIds<Arc<MsgQueue>>,
+    /// Limits of message queues within this namespace.
+    msg_limits: MsgLimits,
+    /// Shared memory segments within this namespace.
+    shm_ids: IpcIds<Arc<ShmSegment>>,
+    /// Internal mount of the mqueue file system that contains POSIX message queues.
+    mqueue_mount: Arc<Mount>,
     /// Owner user namespace.
     owner: Arc<UserNamespace>,
     /// Stashed dentry for nsfs.
@@ -54,24 +71,41 @@ impl IpcNamespace {
 
         INIT.call_once(|| {
             let owner = UserNamespace::get_init_singleton().clone();
-            Self::new(owner)
+            Self::new(owner).unwrap()
         })
     }
 
-    fn new(owner: Arc<UserNamespace>) -> Arc<Self> {
+    fn new(owner: Arc<UserNamespace>) -> Result<Arc<Self>> {
         const MAX_SEM_ID: IpcId = {
             assert!(SEMMNI <= u32::MAX as usize);
             IpcId::new(SEMMNI as u32)
         };
 
+        const MAX_MSG_ID: IpcId = {
+            assert!(IPCMNI <= u32::MAX as usize);
+            IpcId::new(IPCMNI as u32)
+        };
+
+        const MAX_SHM_ID: IpcId = {
+            assert!(SHMMNI <= u32::MAX as usize);
+            IpcId::new(SHMMNI as u32)
+        };
+
         let sem_ids = IpcIds::new(MAX_SEM_ID);
+        let msg_ids = IpcIds::new(MAX_MSG_ID);
+        let shm_ids = IpcIds::new(MAX_SHM_ID);
+        let mqueue_mount = MqueueFs::new_internal_mount()?;
         let stashed_dentry = StashedDentry::new();
 
-        Arc::new(Self {
+        Ok(Arc::new(Self {
             sem_ids,
+            msg_ids,
+            msg_limits: MsgLimits::new(),
+            shm_ids,
+            mqueue_mount,
             owner,
             stashed_dentry,
-        })
+        }))
     }
 
     /// Clones a new IPC namespace from `self`.
@@ -87,7 +121,7 @@ impl IpcNamespace {
             posix_thread,
             CapSet::SYS_ADMIN,
         ))?;
-        Ok(Self::new(owner))
+        Self::new(owner)
     }
 
     /// Calls `op` with the semaphore set identified by `semid`.
@@ -193,6 +227,404 @@ impl IpcNamespace {
         self.sem_ids
             .insert_auto(|_| SemaphoreSet::new(IPC_PRIVATE, num_sems, mode, &credentials))
     }
+
+    /// Returns the existing message queue or creates a new one.
+    pub(crate) fn get_or_create_msg_queue(
+        &self,
+        key: IpcKey,
+        flags: IpcFlags,
+        mode: u16,
+        posix_thread: &PosixThread,
+    ) -> Result<IpcId> {
+        let credentials = posix_thread.credentials();
+
+        self.msg_ids.with_key_or_insert(
+            key,
+            self.msg_limits.msgmni(),
+            |_, msg_queue| {
+                if flags.contains(IpcFlags::IPC_CREAT | IpcFlags::IPC_EXCL) {
+                    return_errno_with_message!(
+                        Errno::EEXIST,
+                        "the message queue already exists with IPC_EXCL"
+                    );
+                }
+
+                let required_perm =
+                    PermissionMode::from_bits_truncate((mode >> 6) | (mode >> 3) | mode);
+                self.check_perm(msg_queue.permission(), required_perm, posix_thread)
+            },
+            |_| {
+                if key != IPC_PRIVATE && !flags.contains(IpcFlags::IPC_CREAT) {
+                    return_errno_with_message!(Errno::ENOENT, "the key does not exist");
+                }
+
+                let msg_queue = MsgQueue::new(key, mode, &credentials, self.msg_limits.msgmnb());
+                Ok(Arc::new(msg_queue))
+            },
+        )
+    }
+
+    /// Returns the message queue identified by `msqid`.
+    ///
+    /// The queue is returned instead of being accessed in a closure, so that the caller can wait
+    /// on the queue without blocking other operations on the IPC namespace.
+    pub(crate) fn get_msg_queue(
+        &self,
+        msqid: IpcId,
+        required_perm: PermissionMode,
+        posix_thread: &PosixThread,
+    ) -> Result<Arc<MsgQueue>> {
+        self.msg_ids.with(msqid, |msg_queue| {
+            self.check_perm(msg_queue.permission(), required_perm, posix_thread)?;
+            Ok(msg_queue.clone())
+        })?
+    }
+
+    /// Removes the message queue identified by `msqid`.
+    ///
+    /// All the waiting senders and receivers will fail with [`EIDRM`].
+    ///
+    /// [`EIDRM`]: crate::error::Errno::EIDRM
+    pub(crate) fn remove_msg_queue<F>(&self, msqid: IpcId, may_remove: F) -> Result<()>
+    where
+        F: FnOnce(&MsgQueue) -> Result<()>,
+    {
+        self.msg_ids.remove(msqid, |msg_queue| {
+            may_remove(msg_queue)?;
+            msg_queue.mark_removed();
+            Ok(())
+        })
+    }
+
+    /// Changes the message queue identified by `msqid` as specified in `msqid_ds` with `IPC_SET`.
+    pub(crate) fn set_msg_queue(
+        &self,
+        msqid: IpcId,
+        msqid_ds: &MsqidDs,
+        posix_thread: &PosixThread,
+    ) -> Result<()> {
+        self.msg_ids.with(msqid, |msg_queue| {
+            self.check_setter(msg_queue.permission(), posix_thread)?;
+
+            let is_privileged = || {
+                lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+                    UserNamespace::get_init_singleton(),
+                    posix_thread,
+                    CapSet::SYS_RESOURCE,
+                ))
+                .is_ok()
+            };
+            msg_queue.set(msqid_ds, self.msg_limits.msgmnb(), is_privileged)
+        })?
+    }
+
+    /// Returns the limits of message queues.
+    pub(crate) fn msg_limits(&self) -> &MsgLimits {
+        &self.msg_limits
+    }
+
+    /// Calls `op` with each message queue and its ID.
+    pub(crate) fn for_each_msg_queue<F>(&self, op: F)
+    where
+        F: FnMut(IpcId, &Arc<MsgQueue>),
+    {
+        self.msg_ids.for_each(op);
+    }
+
+    /// Returns the maximum ID of message queues, or `None` if there are no queues.
+    pub(crate) fn max_msg_id(&self) -> Option<IpcId> {
+        self.msg_ids.max_id()
+    }
+
+    /// Returns the resource usage of all message queues.
+    ///
+    /// The results are the number of queues, the total number of messages, and the total number
+    /// of bytes of messages.
+    pub(crate) fn msg_usage(&self) -> (usize, usize, usize) {
+        let mut nr_queues = 0;
+        let mut nr_messages = 0;
+        let mut nr_bytes = 0;
+
+        self.msg_ids.for_each(|_, msg_queue| {
+            let (queue_messages, queue_bytes) = msg_queue.usage();
+            nr_queues += 1;
+            nr_messages += queue_messages;
+            nr_bytes += queue_bytes;
+        });
+
+        (nr_queues, nr_messages, nr_bytes)
+    }
+
+    /// Returns the existing shared memory segment or creates a new one.
+    pub(crate) fn get_or_create_shm(
+        &self,
+        key: IpcKey,
+        size: usize,
+        flags: IpcFlags,
+        mode: u16,
+        posix_thread: &PosixThread,
+    ) -> Result<IpcId> {
+        let credentials = posix_thread.credentials();
+
+        self.shm_ids.with_key_or_insert(
+            key,
+            SHMMNI,
+            |_, shm| {
+                if flags.contains(IpcFlags::IPC_CREAT | IpcFlags::IPC_EXCL) {
+                    return_errno_with_message!(
+                        Errno::EEXIST,
+                        "the shared memory segment already exists with IPC_EXCL"
+                    );
+                }
+
+                let required_perm =
+                    PermissionMode::from_bits_truncate((mode >> 6) | (mode >> 3) | mode);
+                self.check_perm(shm.permission(), required_perm, posix_thread)?;
+
+                if shm.size() < size {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "the shared memory segment is too small"
+                    );
+                }
+
+                Ok(())
+            },
+            |_| {
+                if key != IPC_PRIVATE && !flags.contains(IpcFlags::IPC_CREAT) {
+                    return_errno_with_message!(Errno::ENOENT, "the key does not exist");
+                }
+
+                let segment =
+                    ShmSegment::new(key, size, mode, &credentials, posix_thread.process().pid())?;
+                Ok(Arc::new(segment))
+            },
+        )
+    }
+
+    /// Calls `op` with the shared memory segment identified by `shmid`.
+    pub(crate) fn with_shm<T, F>(
+        &self,
+        shmid: IpcId,
+        required_perm: PermissionMode,
+        posix_thread: &PosixThread,
+        op: F,
+    ) -> Result<T>
+    where
+        F: FnOnce(&Arc<ShmSegment>) -> Result<T>,
+    {
+        self.shm_ids.with(shmid, |shm| {
+            self.check_perm(shm.permission(), required_perm, posix_thread)?;
+            op(shm)
+        })?
+    }
+
+    /// Attaches the shared memory segment identified by `shmid`.
+    ///
+    /// The returned file should be bound to the mapping of the segment. Each mapping of the file
+    /// counts as an attach of the segment.
+    pub(crate) fn attach_shm(
+        self: &Arc<Self>,
+        shmid: IpcId,
+        flags: ShmAtFlags,
+        posix_thread: &PosixThread,
+    ) -> Result<Arc<ShmFile>> {
+        let (required_perm, access_mode) = if flags.contains(ShmAtFlags::SHM_RDONLY) {
+            (PermissionMode::READ, AccessMode::O_RDONLY)
+        } else {
+            (
+                PermissionMode::READ | PermissionMode::WRITE,
+                AccessMode::O_RDWR,
+            )
+        };
+
+        self.shm_ids.with(shmid, |shm| {
+            self.check_perm(shm.permission(), required_perm, posix_thread)?;
+
+            // Note that Linux allows attaching a segment that is marked to be destroyed.
+            Ok(Arc::new(ShmFile::new(
+                shmid,
+                shm.clone(),
+                Arc::downgrade(self),
+                access_mode,
+            )))
+        })?
+    }
+
+    /// Marks the shared memory segment identified by `shmid` to be destroyed.
+    ///
+    /// The segment can no longer be looked up by its key. It will be removed after the last
+    /// detach.
+    pub(crate) fn destroy_shm<F>(&self, shmid: IpcId, may_destroy: F) -> Result<()>
+    where
+        F: FnOnce(&ShmSegment) -> Result<()>,
+    {
+        let segment = self.shm_ids.with(shmid, |shm| {
+            may_destroy(shm)?;
+            shm.mark_destroyed();
+            Ok::<_, Error>(shm.clone())
+        })??;
+
+        self.shm_ids.forget_key(shmid);
+        self.remove_destroyed_shm(shmid, &segment);
+
+        Ok(())
+    }
+
+    /// Removes the shared memory segment identified by `shmid` if it is `segment` and it is
+    /// marked to be destroyed and is no longer attached.
+    pub(super) fn remove_destroyed_shm(&self, shmid: IpcId, segment: &Arc<ShmSegment>) {
+        let _ = self.shm_ids.remove(shmid, |shm| {
+            // The ID may have been reused if the segment has already been removed.
+            if !Arc::ptr_eq(shm, segment) || !shm.is_removable() {
+                return_errno_with_message!(Errno::EBUSY, "the segment cannot be removed");
+            }
+
+            Ok(())
+        });
+    }
+
+    /// Locks or unlocks the shared memory segment identified by `shmid` in memory.
+    pub(crate) fn lock_shm(
+        &self,
+        shmid: IpcId,
+        is_locked: bool,
+        posix_thread: &PosixThread,
+    ) -> Result<()> {
+        self.shm_ids.with(shmid, |shm| {
+            // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/shm.c#L1231-L1245>.
+            if lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+                self.owner.as_ref(),
+                posix_thread,
+                CapSet::IPC_LOCK,
+            ))
+            .is_err()
+            {
+                if !shm
+                    .permission()
+                    .is_owner_or_creator(&posix_thread.credentials())
+                {
+                    return_errno_with_message!(
+                        Errno::EPERM,
+                        "the shared memory segment is not owned by the thread"
+                    );
+                }
+
+                let memlock_limit = posix_thread
+                    .process()
+                    .resource_limits()
+                    .get_rlimit(ResourceType::RLIMIT_MEMLOCK)
+                    .get_cur();
+                if is_locked && memlock_limit == 0 {
+                    return_errno_with_message!(Errno::EPERM, "the RLIMIT_MEMLOCK limit is zero");
+                }
+                if is_locked
+                    && !shm.is_locked()
+                    && (shm.size().align_up(PAGE_SIZE) as u64) > memlock_limit
+                {
+                    return_errno_with_message!(
+                        Errno::ENOMEM,
+                        "the segment size exceeds the RLIMIT_MEMLOCK limit"
+                    );
+                }
+            }
+
+            shm.set_locked(is_locked);
+            Ok(())
+        })?
+    }
+
+    /// Calls `op` with each shared memory segment and its ID.
+    pub(crate) fn for_each_shm<F>(&self, op: F)
+    where
+        F: FnMut(IpcId, &Arc<ShmSegment>),
+    {
+        self.shm_ids.for_each(op);
+    }
+
+    /// Returns the maximum ID of shared memory segments, or `None` if there are no segments.
+    pub(crate) fn max_shm_id(&self) -> Option<IpcId> {
+        self.shm_ids.max_id()
+    }
+
+    /// Returns the resource usage of all shared memory segments.
+    pub(crate) fn shm_usage_info(&self) -> ShmUsageInfo {
+        let mut usage_info = ShmUsageInfo {
+            used_ids: self.shm_ids.len() as i32,
+            ..ShmUsageInfo::default()
+        };
+
+        self.shm_ids.for_each(|_, shm| {
+            usage_info.shm_tot += shm.size().div_ceil(PAGE_SIZE) as u64;
+            usage_info.shm_rss += shm.vmo().nr_committed_pages() as u64;
+        });
+
+        usage_info
+    }
+
+    /// Returns the internal mount of the mqueue file system.
+    pub(crate) fn mqueue_mount(&self) -> &Arc<Mount> {
+        &self.mqueue_mount
+    }
+
+    /// Checks whether the thread has `required_perm` on the IPC object.
+    fn check_perm(
+        &self,
+        permission: &IpcPermission,
+        required_perm: PermissionMode,
+        posix_thread: &PosixThread,
+    ) -> Result<()> {
+        if permission.is_granted(required_perm, &posix_thread.credentials()) {
+            return Ok(());
+        }
+
+        lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+            self.owner.as_ref(),
+            posix_thread,
+            CapSet::IPC_OWNER,
+        ))
+        .map_err(|_| Error::with_message(Errno::EACCES, "the IPC object cannot be accessed"))
+    }
+
+    /// Checks whether the thread is the owner or the creator of the IPC object, or is privileged.
+    pub(crate) fn check_owner(
+        &self,
+        permission: &IpcPermission,
+        posix_thread: &PosixThread,
+    ) -> Result<()> {
+        if permission.is_owner_or_creator(&posix_thread.credentials()) {
+            return Ok(());
+        }
+
+        lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+            self.owner.as_ref(),
+            posix_thread,
+            CapSet::SYS_ADMIN,
+        ))
+        .map_err(|_| Error::with_message(Errno::EPERM, "the IPC object is not owned by the thread"))
+    }
+
+    /// Checks whether the thread can change the owner and the permission mode of the IPC object
+    /// with `IPC_SET`.
+    ///
+    /// Besides the threads that pass [`Self::check_owner`], threads with `CAP_IPC_OWNER` can
+    /// change them.
+    pub(crate) fn check_setter(
+        &self,
+        permission: &IpcPermission,
+        posix_thread: &PosixThread,
+    ) -> Result<()> {
+        if self.check_owner(permission, posix_thread).is_ok() {
+            return Ok(());
+        }
+
+        lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+            self.owner.as_ref(),
+            posix_thread,
+            CapSet::IPC_OWNER,
+        ))
+        .map_err(|_| Error::with_message(Errno::EPERM, "the IPC object is not owned by the thread"))
+    }
 }
 
 impl NsCommonOps for IpcNamespace {
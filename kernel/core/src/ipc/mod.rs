@@ -1,13 +1,17 @@
 // SPDX-License-Identifier: MPL-2.0
 
+use aster_rights::ReadOp;
+
 use crate::{
     prelude::*,
-    process::{Gid, Uid},
+    process::{Credentials, Gid, Uid},
 };
 
 mod ipc_ids;
 mod ipc_ns;
+pub(crate) mod msg;
 pub(crate) mod semaphore;
+pub(crate) mod shm;
 
 pub(crate) use ipc_ids::IpcId;
 pub(crate) use ipc_ns::IpcNamespace;
@@ -36,6 +40,17 @@ bitflags! {
     }
 }
 
+/// The bits of the permission mode that can be changed with `IPC_SET`.
+const IPC_MODE_MASK: u16 = 0o777;
+
+bitflags! {
+    pub(crate) struct PermissionMode: u16{
+        const ALTER  = 0o002;
+        const WRITE  = 0o002;
+        const READ   = 0o004;
+    }
+}
+
 // TODO: Add support for the commented-out commands below
 #[expect(non_camel_case_types)]
 #[repr(i32)]
@@ -57,15 +72,18 @@ pub(crate) enum IpcControlCmd {
 #[derive(Debug)]
 pub(crate) struct IpcPermission {
     key: IpcKey,
-    /// Owner's UID
-    uid: Uid,
-    /// Owner's GID
-    gid: Gid,
     /// Creator's UID
     cuid: Uid,
     /// Creator's GID
     cguid: Gid,
-    /// Permission mode
+    /// Owner's UID and GID, and permission mode, which can be changed with `IPC_SET`
+    owner: SpinLock<IpcOwner>,
+}
+
+#[derive(Clone, Copy, Debug)]
+struct IpcOwner {
+    uid: Uid,
+    gid: Gid,
     mode: u16,
 }
 
@@ -76,12 +94,12 @@ impl IpcPermission {
 
     /// Returns owner's UID
     pub(crate) fn uid(&self) -> Uid {
-        self.uid
+        self.owner.lock().uid
     }
 
     /// Returns owner's GID
     pub(crate) fn gid(&self) -> Gid {
-        self.gid
+        self.owner.lock().gid
     }
 
     /// Returns creator's UID
@@ -96,17 +114,103 @@ impl IpcPermission {
 
     /// Returns permission mode
     pub(crate) fn mode(&self) -> u16 {
-        self.mode
+        self.owner.lock().mode
     }
 
-    pub(self) fn new_sem_perm(key: IpcKey, uid: Uid, gid: Gid, mode: u16) -> Self {
+    pub(self) fn new(key: IpcKey, uid: Uid, gid: Gid, mode: u16) -> Self {
         Self {
             key,
-            uid,
-            gid,
             cuid: uid,
             cguid: gid,
-            mode,
+            owner: SpinLock::new(IpcOwner { uid, gid, mode }),
         }
     }
+
+    /// Changes the owner and the permission mode as specified in the `ipc64_perm` layout.
+    ///
+    /// Only the permission bits of the mode can be changed.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/util.c>
+    pub(self) fn set(&self, perm: &IpcPerm) -> Result<()> {
+        let uid = Uid::new(perm.uid);
+        let gid = Gid::new(perm.gid);
+        if uid == Uid::INVALID || gid == Gid::INVALID {
+            return_errno_with_message!(Errno::EINVAL, "the owner is invalid");
+        }
+
+        let mut owner = self.owner.lock();
+        owner.uid = uid;
+        owner.gid = gid;
+        owner.mode = (owner.mode & !IPC_MODE_MASK) | (perm.mode & IPC_MODE_MASK);
+
+        Ok(())
+    }
+
+    /// Returns whether the permission mode grants `required_perm` to `credentials`.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/util.c#L541>.
+    pub(self) fn is_granted(
+        &self,
+        required_perm: PermissionMode,
+        credentials: &Credentials<ReadOp>,
+    ) -> bool {
+        let euid = credentials.euid();
+        let egid = credentials.egid();
+        let owner = *self.owner.lock();
+
+        let granted_perm = if euid == owner.uid || euid == self.cuid {
+            owner.mode >> 6
+        } else if egid == owner.gid
+            || egid == self.cguid
+            || credentials
+                .groups()
+                .iter()
+                .any(|gid| *gid == owner.gid || *gid == self.cguid)
+        {
+            owner.mode >> 3
+        } else {
+            owner.mode
+        };
+
+        required_perm.bits() & !granted_perm & 0o7 == 0
+    }
+
+    /// Returns whether `credentials` belong to the owner or the creator.
+    pub(self) fn is_owner_or_creator(&self, credentials: &Credentials<ReadOp>) -> bool {
+        let euid = credentials.euid();
+        euid == self.uid() || euid == self.cuid
+    }
+
+    /// Returns the permission in the `ipc64_perm` layout.
+    pub(self) fn to_ipc_perm(&self) -> IpcPerm {
+        let owner = *self.owner.lock();
+
+        IpcPerm {
+            key: self.key.cast_unsigned(),
+            uid: owner.uid.into(),
+            gid: owner.gid.into(),
+            cuid: self.cuid.into(),
+            cgid: self.cguid.into(),
+            mode: owner.mode,
+            ..IpcPerm::default()
+        }
+    }
+}
+
+// Reference: <https://elixir.bootlin.com/linux/v6.18/source/include/uapi/asm-generic/ipcbuf.h#L22>.
+#[padding_struct]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod)]
+struct IpcPerm {
+    key: u32,
+    uid: u32,
+    gid: u32,
+    cuid: u32,
+    cgid: u32,
+    mode: u16,
+    _pad1: u16,
+    seq: u16,
+    _pad2: u16,
+    _unused1: u64,
+    _unused2: u64,
 }
@@ -0,0 +1,228 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! System V message queues.
+//!
+//! A message queue holds typed messages sent by `msgsnd` until they are received by `msgrcv`.
+//! Senders wait if the queue is full and receivers wait if there is no matching message, unless
+//! `IPC_NOWAIT` is specified.
+
+mod queue;
+
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+pub(crate) use queue::{Message, MsgQueue};
+
+use super::IpcPerm;
+use crate::prelude::*;
+
+// The following constant values are derived from the default values in Linux.
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/msg.h#L77-L92>.
+
+/// Default maximum number of message queues.
+const MSGMNI: usize = 32000;
+/// Default maximum size of a message in bytes.
+const MSGMAX: usize = 8192;
+/// Default maximum number of bytes in a message queue.
+const MSGMNB: usize = 16384;
+/// Size of a message segment in bytes.
+const MSGSSZ: i32 = 16;
+/// Maximum number of message segments.
+const MSGSEG: u16 = 0xffff;
+
+/// The upper bound of the maximum number of message queues.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/linux/ipc.h#L16>.
+pub(crate) const IPCMNI: usize = 32768;
+
+bitflags! {
+    /// Flags for `msgsnd` and `msgrcv`, in addition to `IPC_NOWAIT`.
+    pub(crate) struct MsgFlags: u32 {
+        /// Truncate the message text if it is longer than the buffer.
+        const MSG_NOERROR = 0o10000;
+        /// Receive the first message whose type is not equal to the given type.
+        const MSG_EXCEPT  = 0o20000;
+        /// Copy the message at the given position without removing it.
+        const MSG_COPY    = 0o40000;
+    }
+}
+
+#[expect(non_camel_case_types)]
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, TryFromInt)]
+pub(crate) enum MsgControlCmd {
+    IPC_RMID = 0,
+    IPC_SET = 1,
+    IPC_STAT = 2,
+    IPC_INFO = 3,
+
+    MSG_STAT = 11,
+    MSG_INFO = 12,
+    MSG_STAT_ANY = 13,
+}
+
+/// The rule to select a message in `msgrcv`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum MsgSelector {
+    /// Selects the first message.
+    Any,
+    /// Selects the first message of the type.
+    Equal(i64),
+    /// Selects the first message whose type is not the type.
+    NotEqual(i64),
+    /// Selects the first message of the lowest type that is less than or equal to the type.
+    LessEqual(i64),
+}
+
+impl MsgSelector {
+    /// Creates the selector from the `msgtyp` argument and flags of `msgrcv`.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/msg.c#L976-L987>.
+    pub(crate) fn new(msgtyp: i64, flags: MsgFlags) -> Self {
+        if msgtyp == 0 {
+            Self::Any
+        } else if msgtyp < 0 {
+            // Note that `i64::MIN` cannot be negated.
+            Self::LessEqual(msgtyp.checked_neg().unwrap_or(i64::MAX))
+        } else if flags.contains(MsgFlags::MSG_EXCEPT) {
+            Self::NotEqual(msgtyp)
+        } else {
+            Self::Equal(msgtyp)
+        }
+    }
+}
+
+/// The tunable limits of message queues in an IPC namespace.
+///
+/// The limits are exposed in `/proc/sys/kernel/msg{mni,max,mnb}`.
+pub(crate) struct MsgLimits {
+    /// Maximum number of message queues.
+    msgmni: AtomicUsize,
+    /// Maximum size of a message in bytes.
+    msgmax: AtomicUsize,
+    /// Default maximum number of bytes in a new message queue.
+    msgmnb: AtomicUsize,
+}
+
+impl MsgLimits {
+    pub(super) const fn new() -> Self {
+        Self {
+            msgmni: AtomicUsize::new(MSGMNI),
+            msgmax: AtomicUsize::new(MSGMAX),
+            msgmnb: AtomicUsize::new(MSGMNB),
+        }
+    }
+
+    pub(crate) fn msgmni(&self) -> usize {
+        self.msgmni.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn msgmax(&self) -> usize {
+        self.msgmax.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn msgmnb(&self) -> usize {
+        self.msgmnb.load(Ordering::Relaxed)
+    }
+
+    /// Sets the maximum number of message queues, which must be within `0..=IPCMNI`.
+    pub(crate) fn set_msgmni(&self, val: i32) -> Result<()> {
+        let val = usize::try_from(val)
+            .ok()
+            .filter(|val| *val <= IPCMNI)
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the msgmni value is invalid"))?;
+        self.msgmni.store(val, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Sets the maximum size of a message, which must not be negative.
+    pub(crate) fn set_msgmax(&self, val: i32) -> Result<()> {
+        let val = usize::try_from(val)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the msgmax value is negative"))?;
+        self.msgmax.store(val, Ordering::Relaxed);
+        Ok(())
+    }
+
+    /// Sets the default maximum number of bytes in a new message queue, which must not be
+    /// negative.
+    pub(crate) fn set_msgmnb(&self, val: i32) -> Result<()> {
+        let val = usize::try_from(val)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the msgmnb value is negative"))?;
+        self.msgmnb.store(val, Ordering::Relaxed);
+        Ok(())
+    }
+}
+
+// In Linux, all 64-bit architectures adopt the same layout of `msqid64_ds`.
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/asm-generic/msgbuf.h#L28>.
+#[padding_struct]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod)]
+pub(crate) struct MsqidDs {
+    msg_perm: IpcPerm,
+    msg_stime: u64,
+    msg_rtime: u64,
+    msg_ctime: u64,
+    msg_cbytes: u64,
+    msg_qnum: u64,
+    msg_qbytes: u64,
+    msg_lspid: i32,
+    msg_lrpid: i32,
+    _unused4: u64,
+    _unused5: u64,
+}
+
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/msg.h#L65>.
+#[padding_struct]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod)]
+pub(crate) struct MsgInfo {
+    msgpool: i32,
+    msgmap: i32,
+    msgmax: i32,
+    msgmnb: i32,
+    msgmni: i32,
+    msgssz: i32,
+    msgtql: i32,
+    msgseg: u16,
+}
+
+impl MsgInfo {
+    /// Returns the limits of message queues, which is the result of `IPC_INFO`.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/msg.c#L490-L512>.
+    pub(crate) fn new_limits(limits: &MsgLimits) -> Self {
+        let clamp = |val: usize| val.min(i32::MAX as usize) as i32;
+
+        Self {
+            msgpool: clamp(MSGMNI * MSGMNB / 1024),
+            msgmap: clamp(MSGMNB),
+            msgmax: clamp(limits.msgmax()),
+            msgmnb: clamp(limits.msgmnb()),
+            msgmni: clamp(limits.msgmni()),
+            msgssz: MSGSSZ,
+            msgtql: clamp(MSGMNB),
+            msgseg: MSGSEG,
+            ..Self::default()
+        }
+    }
+
+    /// Returns the usage of message queues, which is the result of `MSG_INFO`.
+    ///
+    /// The fields that report the usage are `msgpool` (the number of message queues), `msgmap`
+    /// (the total number of messages), and `msgtql` (the total number of bytes of messages).
+    pub(crate) fn new_usage(
+        limits: &MsgLimits,
+        nr_queues: usize,
+        nr_messages: usize,
+        nr_bytes: usize,
+    ) -> Self {
+        let clamp = |val: usize| val.min(i32::MAX as usize) as i32;
+
+        Self {
+            msgpool: clamp(nr_queues),
+            msgmap: clamp(nr_messages),
+            msgtql: clamp(nr_bytes),
+            ..Self::new_limits(limits)
+        }
+    }
+}
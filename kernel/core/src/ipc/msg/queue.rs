@@ -0,0 +1,327 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_rights::ReadOp;
+use aster_util::printer::VmPrinter;
+use ostd::sync::WaitQueue;
+
+use super::{MsgSelector, MsqidDs};
+use crate::{
+    ipc::{IpcId, IpcKey, IpcPermission},
+    prelude::*,
+    process::{Credentials, Pid, signal::Pause},
+    time::clocks::RealTimeCoarseClock,
+};
+
+/// A message in a message queue.
+pub(crate) struct Message {
+    mtype: i64,
+    text: Box<[u8]>,
+}
+
+impl Message {
+    /// Creates a message of `mtype`, which must be positive.
+    pub(crate) fn new(mtype: i64, text: Box<[u8]>) -> Self {
+        debug_assert!(mtype > 0);
+        Self { mtype, text }
+    }
+
+    pub(crate) fn mtype(&self) -> i64 {
+        self.mtype
+    }
+
+    pub(crate) fn text(&self) -> &[u8] {
+        &self.text
+    }
+}
+
+/// A System V message queue.
+pub(crate) struct MsgQueue {
+    /// Queue permission
+    permission: IpcPermission,
+    /// Inner
+    inner: SpinLock<MsgQueueInner>,
+    /// Wait queue for the senders that wait for space and the receivers that wait for messages
+    wait_queue: WaitQueue,
+}
+
+struct MsgQueueInner {
+    /// Messages in the order they are sent
+    messages: VecDeque<Message>,
+    /// Total number of bytes of the messages
+    nr_bytes: usize,
+    /// Maximum number of bytes of the messages
+    max_bytes: usize,
+    /// Whether the queue has been removed with `IPC_RMID`
+    is_removed: bool,
+    /// PID of the last `msgsnd`
+    last_send_pid: Pid,
+    /// PID of the last `msgrcv`
+    last_recv_pid: Pid,
+    /// Last `msgsnd` time
+    stime: u64,
+    /// Last `msgrcv` time
+    rtime: u64,
+    /// Creation time or last modification via `msgctl`
+    ctime: u64,
+}
+
+impl MsgQueueInner {
+    /// Returns whether a message of `len` bytes can be sent without exceeding the limit.
+    ///
+    /// Note that the limit also applies to the number of messages, so that it is impossible to
+    /// fill the queue with an unlimited number of empty messages.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/msg.c#L843-L848>.
+    fn can_send(&self, len: usize) -> bool {
+        self.nr_bytes + len <= self.max_bytes && self.messages.len() < self.max_bytes
+    }
+
+    /// Returns the index of the message selected by `selector`.
+    fn find(&self, selector: MsgSelector) -> Option<usize> {
+        let mut messages = self.messages.iter().enumerate();
+
+        match selector {
+            MsgSelector::Any => messages.next().map(|(index, _)| index),
+            MsgSelector::Equal(mtype) => messages
+                .find(|(_, message)| message.mtype == mtype)
+                .map(|(index, _)| index),
+            MsgSelector::NotEqual(mtype) => messages
+                .find(|(_, message)| message.mtype != mtype)
+                .map(|(index, _)| index),
+            MsgSelector::LessEqual(mtype) => messages
+                .filter(|(_, message)| message.mtype <= mtype)
+                .min_by_key(|(_, message)| message.mtype)
+                .map(|(index, _)| index),
+        }
+    }
+}
+
+impl MsgQueue {
+    pub(in crate::ipc) fn new(
+        key: IpcKey,
+        mode: u16,
+        credentials: &Credentials<ReadOp>,
+        max_bytes: usize,
+    ) -> Self {
+        let permission = IpcPermission::new(key, credentials.euid(), credentials.egid(), mode);
+
+        Self {
+            permission,
+            inner: SpinLock::new(MsgQueueInner {
+                messages: VecDeque::new(),
+                nr_bytes: 0,
+                max_bytes,
+                is_removed: false,
+                last_send_pid: 0,
+                last_recv_pid: 0,
+                stime: 0,
+                rtime: 0,
+                ctime: now(),
+            }),
+            wait_queue: WaitQueue::new(),
+        }
+    }
+
+    pub(crate) fn permission(&self) -> &IpcPermission {
+        &self.permission
+    }
+
+    /// Sends `message` to the queue on behalf of the process identified by `pid`.
+    ///
+    /// If the queue is full, this method waits until there is enough space, unless
+    /// `is_nonblocking` is true.
+    pub(crate) fn send(&self, message: Message, pid: Pid, is_nonblocking: bool) -> Result<()> {
+        let mut message = Some(message);
+
+        self.wait_queue.pause_until(|| {
+            let mut inner = self.inner.lock();
+
+            if inner.is_removed {
+                return Some(Err(Error::with_message(
+                    Errno::EIDRM,
+                    "the message queue has been removed",
+                )));
+            }
+
+            let len = message.as_ref().unwrap().text.len();
+            if !inner.can_send(len) {
+                return is_nonblocking.then(|| {
+                    Err(Error::with_message(
+                        Errno::EAGAIN,
+                        "the message queue is full",
+                    ))
+                });
+            }
+
+            inner.messages.push_back(message.take().unwrap());
+            inner.nr_bytes += len;
+            inner.last_send_pid = pid;
+            inner.stime = now();
+
+            Some(Ok(()))
+        })??;
+
+        self.wait_queue.wake_all();
+
+        Ok(())
+    }
+
+    /// Receives a message selected by `selector` from the queue on behalf of the process
+    /// identified by `pid`.
+    ///
+    /// If there is no such message, this method waits until one arrives, unless `is_nonblocking`
+    /// is true. If the message text is longer than `max_len`, this method fails with [`E2BIG`]
+    /// unless `is_truncatable` is true.
+    ///
+    /// [`E2BIG`]: crate::error::Errno::E2BIG
+    pub(crate) fn receive(
+        &self,
+        selector: MsgSelector,
+        max_len: usize,
+        is_truncatable: bool,
+        pid: Pid,
+        is_nonblocking: bool,
+    ) -> Result<Message> {
+        let message = self.wait_queue.pause_until(|| {
+            let mut inner = self.inner.lock();
+
+            if inner.is_removed {
+                return Some(Err(Error::with_message(
+                    Errno::EIDRM,
+                    "the message queue has been removed",
+                )));
+            }
+
+            let Some(index) = inner.find(selector) else {
+                return is_nonblocking.then(|| {
+                    Err(Error::with_message(
+                        Errno::ENOMSG,
+                        "there is no message of the requested type",
+                    ))
+                });
+            };
+
+            if inner.messages[index].text.len() > max_len && !is_truncatable {
+                return Some(Err(Error::with_message(
+                    Errno::E2BIG,
+                    "the message text is longer than the buffer",
+                )));
+            }
+
+            let message = inner.messages.remove(index).unwrap();
+            inner.nr_bytes -= message.text.len();
+            inner.last_recv_pid = pid;
+            inner.rtime = now();
+
+            Some(Ok(message))
+        })??;
+
+        self.wait_queue.wake_all();
+
+        Ok(message)
+    }
+
+    /// Changes the owner, the permission mode, and the maximum number of bytes of the messages
+    /// with `IPC_SET`.
+    ///
+    /// Raising the maximum number of bytes above `max_bytes_limit` requires `is_privileged` to
+    /// return true.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/msg.c>.
+    pub(in crate::ipc) fn set<F>(
+        &self,
+        msqid_ds: &MsqidDs,
+        max_bytes_limit: usize,
+        is_privileged: F,
+    ) -> Result<()>
+    where
+        F: FnOnce() -> bool,
+    {
+        let max_bytes = msqid_ds.msg_qbytes as usize;
+        if max_bytes > max_bytes_limit && !is_privileged() {
+            return_errno_with_message!(
+                Errno::EPERM,
+                "raising the queue size above MSGMNB requires CAP_SYS_RESOURCE"
+            );
+        }
+
+        self.permission.set(&msqid_ds.msg_perm)?;
+
+        let mut inner = self.inner.lock();
+        inner.max_bytes = max_bytes;
+        inner.ctime = now();
+        drop(inner);
+
+        // The waiting senders may be able to send with a larger limit.
+        self.wait_queue.wake_all();
+
+        Ok(())
+    }
+
+    /// Marks the queue as removed and wakes up all the waiting senders and receivers.
+    pub(in crate::ipc) fn mark_removed(&self) {
+        let mut inner = self.inner.lock();
+        inner.is_removed = true;
+        inner.messages.clear();
+        inner.nr_bytes = 0;
+        drop(inner);
+
+        self.wait_queue.wake_all();
+    }
+
+    /// Returns the number of messages and the total number of bytes of the messages.
+    pub(crate) fn usage(&self) -> (usize, usize) {
+        let inner = self.inner.lock();
+        (inner.messages.len(), inner.nr_bytes)
+    }
+
+    pub(crate) fn msqid_ds(&self) -> MsqidDs {
+        let inner = self.inner.lock();
+
+        MsqidDs {
+            msg_perm: self.permission.to_ipc_perm(),
+            msg_stime: inner.stime,
+            msg_rtime: inner.rtime,
+            msg_ctime: inner.ctime,
+            msg_cbytes: inner.nr_bytes as u64,
+            msg_qnum: inner.messages.len() as u64,
+            msg_qbytes: inner.max_bytes as u64,
+            msg_lspid: inner.last_send_pid.cast_signed(),
+            msg_lrpid: inner.last_recv_pid.cast_signed(),
+            ..MsqidDs::default()
+        }
+    }
+
+    /// Prints the queue information in the format of `/proc/sysvipc/msg`.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/msg.c#L1315-L1340>
+    pub(crate) fn print_to_sysvipc(&self, msqid: IpcId, printer: &mut VmPrinter) -> Result<()> {
+        let msqid_ds = self.msqid_ds();
+        let msg_perm = &msqid_ds.msg_perm;
+
+        writeln!(
+            printer,
+            "{:>10} {:>10}  {:>4o}  {:>10} {:>10} {:>5} {:>5} {:>5} {:>5} {:>5} {:>5} {:>10} {:>10} {:>10}",
+            msg_perm.key.cast_signed(),
+            msqid.get(),
+            msg_perm.mode,
+            msqid_ds.msg_cbytes,
+            msqid_ds.msg_qnum,
+            msqid_ds.msg_lspid,
+            msqid_ds.msg_lrpid,
+            msg_perm.uid,
+            msg_perm.gid,
+            msg_perm.cuid,
+            msg_perm.cgid,
+            msqid_ds.msg_stime,
+            msqid_ds.msg_rtime,
+            msqid_ds.msg_ctime,
+        )?;
+
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    RealTimeCoarseClock::get().read_time().as_secs()
+}
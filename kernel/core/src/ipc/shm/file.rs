@@ -0,0 +1,151 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::fmt::{Debug, Display};
+
+use super::ShmSegment;
+use crate::{
+    events::IoEvents,
+    fs::{
+        file::{
+            AccessMode, CreationFlags, FileCommon, FileLike, Mappable, StatusFlags,
+            file_table::FdFlags,
+        },
+        pseudofs::AnonInodeFs,
+    },
+    ipc::{IpcId, IpcNamespace},
+    prelude::*,
+    process::{
+        Pid, Process,
+        signal::{PollHandle, Pollable},
+    },
+};
+
+/// A file that represents a `shmat` of a shared memory segment.
+///
+/// Each `shmat` creates a new file, which is bound to the new mapping. The file lives as long as
+/// any part of the mapping exists. Each mapping of the file holds a [`ShmAttach`], so the number
+/// of attaches follows the mappings as they are duplicated, split, and unmapped.
+pub(crate) struct ShmFile {
+    id: IpcId,
+    segment: Arc<ShmSegment>,
+    ipc_ns: Weak<IpcNamespace>,
+    common: FileCommon,
+}
+
+impl ShmFile {
+    pub(in crate::ipc) fn new(
+        id: IpcId,
+        segment: Arc<ShmSegment>,
+        ipc_ns: Weak<IpcNamespace>,
+        access_mode: AccessMode,
+    ) -> Self {
+        let pseudo_path = AnonInodeFs::new_path(|_| "anon_inode:[sysv_shm]".to_string());
+
+        Self {
+            id,
+            segment,
+            ipc_ns,
+            common: FileCommon::new(pseudo_path, access_mode, StatusFlags::empty()),
+        }
+    }
+
+    /// Returns the attached segment.
+    pub(crate) fn segment(&self) -> &Arc<ShmSegment> {
+        &self.segment
+    }
+
+    /// Attaches the segment for a new mapping of the file.
+    pub(crate) fn attach(&self) -> ShmAttach {
+        ShmAttach::new(self.id, self.segment.clone(), self.ipc_ns.clone())
+    }
+}
+
+/// An attach of a shared memory segment, which is held by a mapping of the segment.
+///
+/// Similar to the `open` and `close` operations of the VMAs in Linux, the attach is duplicated
+/// when the mapping is duplicated (e.g., on `fork` or when the mapping is split) and is detached
+/// when the mapping is unmapped.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/shm.c>
+pub(crate) struct ShmAttach {
+    id: IpcId,
+    segment: Arc<ShmSegment>,
+    ipc_ns: Weak<IpcNamespace>,
+}
+
+impl ShmAttach {
+    fn new(id: IpcId, segment: Arc<ShmSegment>, ipc_ns: Weak<IpcNamespace>) -> Self {
+        segment.attach(current_pid());
+
+        Self {
+            id,
+            segment,
+            ipc_ns,
+        }
+    }
+
+    /// Duplicates the attach for a new mapping.
+    pub(crate) fn dup(&self) -> Self {
+        Self::new(self.id, self.segment.clone(), self.ipc_ns.clone())
+    }
+}
+
+impl Debug for ShmAttach {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("ShmAttach").field("id", &self.id).finish()
+    }
+}
+
+impl Drop for ShmAttach {
+    fn drop(&mut self) {
+        if !self.segment.detach(current_pid()) {
+            return;
+        }
+
+        if let Some(ipc_ns) = self.ipc_ns.upgrade() {
+            ipc_ns.remove_destroyed_shm(self.id, &self.segment);
+        }
+    }
+}
+
+fn current_pid() -> Pid {
+    Process::current().map_or(0, |process| process.pid())
+}
+
+impl Pollable for ShmFile {
+    fn poll(&self, _mask: IoEvents, _poller: Option<&mut PollHandle>) -> IoEvents {
+        IoEvents::empty()
+    }
+}
+
+impl FileLike for ShmFile {
+    fn mappable(&self) -> Result<Mappable> {
+        Ok(Mappable::Vmo(self.segment.vmo().clone()))
+    }
+
+    fn common(&self) -> &FileCommon {
+        &self.common
+    }
+
+    fn dump_proc_fdinfo(self: Arc<Self>, fd_flags: FdFlags) -> Box<dyn Display> {
+        struct FdInfo {
+            flags: u32,
+        }
+
+        impl Display for FdInfo {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                writeln!(f, "pos:\t{}", 0)?;
+                writeln!(f, "flags:\t0{:o}", self.flags)?;
+                writeln!(f, "mnt_id:\t{}", AnonInodeFs::mount_node().id())?;
+                writeln!(f, "ino:\t{}", AnonInodeFs::shared_inode().ino())
+            }
+        }
+
+        let mut flags = self.common.status_flags().bits() | self.common.access_mode() as u32;
+        if fd_flags.contains(FdFlags::CLOEXEC) {
+            flags |= CreationFlags::O_CLOEXEC.bits();
+        }
+
+        Box::new(FdInfo { flags })
+    }
+}
@@ -0,0 +1,135 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! System V shared memory.
+//!
+//! A shared memory segment is backed by an anonymous [`Vmo`]. Each `shmat` creates a
+//! [`ShmFile`] that is bound to the new mapping, so `shmdt` can locate the mapping. Each mapping
+//! of the file holds a [`ShmAttach`], so the segment knows when the last attachment is gone.
+//!
+//! [`Vmo`]: crate::vm::page_cache::Vmo
+
+mod file;
+mod segment;
+
+pub(crate) use file::{ShmAttach, ShmFile};
+pub(crate) use segment::ShmSegment;
+
+use super::IpcPerm;
+use crate::prelude::*;
+
+// The following constant values are derived from the default values in Linux.
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/shm.h#L19-L25>.
+
+/// Minimum size of a shared memory segment.
+pub(crate) const SHMMIN: usize = 1;
+/// Maximum size of a shared memory segment.
+pub(crate) const SHMMAX: usize = usize::MAX - (1 << 24);
+/// Maximum number of shared memory segments.
+pub(crate) const SHMMNI: usize = 4096;
+/// Maximum number of pages of all shared memory segments.
+pub(crate) const SHMALL: usize = usize::MAX - (1 << 24);
+/// Maximum number of shared memory segments that a process can attach.
+pub(crate) const SHMSEG: usize = SHMMNI;
+
+/// The alignment of the address to attach a shared memory segment with `SHM_RND`.
+pub(crate) const SHMLBA: usize = PAGE_SIZE;
+
+bitflags! {
+    /// Flags for `shmat`.
+    pub(crate) struct ShmAtFlags: u32 {
+        /// Attach the segment for read-only access.
+        const SHM_RDONLY = 0o10000;
+        /// Round the attach address down to a multiple of `SHMLBA`.
+        const SHM_RND    = 0o20000;
+        /// Replace any existing mapping at the attach address.
+        const SHM_REMAP  = 0o40000;
+        /// Allow the contents of the segment to be executed.
+        const SHM_EXEC   = 0o100000;
+    }
+}
+
+/// The mode bit indicating that the segment will be destroyed after the last detach.
+const SHM_DEST: u16 = 0o1000;
+/// The mode bit indicating that the segment is locked in memory.
+const SHM_LOCKED: u16 = 0o2000;
+
+#[expect(non_camel_case_types)]
+#[repr(i32)]
+#[derive(Clone, Copy, Debug, TryFromInt)]
+pub(crate) enum ShmControlCmd {
+    IPC_RMID = 0,
+    IPC_SET = 1,
+    IPC_STAT = 2,
+    IPC_INFO = 3,
+
+    SHM_LOCK = 11,
+    SHM_UNLOCK = 12,
+    SHM_STAT = 13,
+    SHM_INFO = 14,
+    SHM_STAT_ANY = 15,
+}
+
+// In Linux, all 64-bit architectures adopt the same layout of `shmid64_ds`.
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/asm-generic/shmbuf.h#L27>.
+#[padding_struct]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod)]
+pub(crate) struct ShmidDs {
+    shm_perm: IpcPerm,
+    shm_segsz: u64,
+    shm_atime: u64,
+    shm_dtime: u64,
+    shm_ctime: u64,
+    shm_cpid: i32,
+    shm_lpid: i32,
+    shm_nattch: u64,
+    _unused4: u64,
+    _unused5: u64,
+}
+
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/asm-generic/shmbuf.h#L44>.
+#[padding_struct]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod)]
+pub(crate) struct ShmInfo64 {
+    shmmax: u64,
+    shmmin: u64,
+    shmmni: u64,
+    shmseg: u64,
+    shmall: u64,
+    _unused1: u64,
+    _unused2: u64,
+    _unused3: u64,
+    _unused4: u64,
+}
+
+impl ShmInfo64 {
+    /// Returns the system-wide limits of shared memory segments.
+    pub(crate) fn new() -> Self {
+        Self {
+            shmmax: SHMMAX as u64,
+            shmmin: SHMMIN as u64,
+            shmmni: SHMMNI as u64,
+            shmseg: SHMSEG as u64,
+            shmall: SHMALL as u64,
+            ..Self::default()
+        }
+    }
+}
+
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/shm.h#L95>.
+#[padding_struct]
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod)]
+pub(crate) struct ShmUsageInfo {
+    /// Number of existing segments.
+    pub(crate) used_ids: i32,
+    /// Total number of pages of all segments.
+    pub(crate) shm_tot: u64,
+    /// Number of resident pages of all segments.
+    pub(crate) shm_rss: u64,
+    /// Number of swapped pages of all segments.
+    pub(crate) shm_swp: u64,
+    _swap_attempts: u64,
+    _swap_successes: u64,
+}
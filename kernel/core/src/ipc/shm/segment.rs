@@ -0,0 +1,223 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use align_ext::AlignExt;
+use aster_rights::ReadOp;
+use aster_util::printer::VmPrinter;
+
+use super::{SHM_DEST, SHM_LOCKED, SHMMAX, SHMMIN, ShmidDs};
+use crate::{
+    ipc::{IpcId, IpcKey, IpcPermission},
+    prelude::*,
+    process::{Credentials, Pid},
+    time::clocks::RealTimeCoarseClock,
+    vm::page_cache::{Vmo, VmoOptions},
+};
+
+/// A System V shared memory segment.
+pub(crate) struct ShmSegment {
+    /// Segment permission
+    permission: IpcPermission,
+    /// Size in bytes, which is not necessarily page-aligned
+    size: usize,
+    /// The memory of the segment
+    vmo: Arc<Vmo>,
+    /// PID of the creator
+    creator_pid: Pid,
+    /// Inner
+    inner: SpinLock<ShmInner>,
+}
+
+struct ShmInner {
+    /// Number of current attaches
+    nattch: usize,
+    /// Whether the segment has been marked to be destroyed with `IPC_RMID`
+    is_destroyed: bool,
+    /// Whether the segment has been locked with `SHM_LOCK`
+    is_locked: bool,
+    /// PID of the last `shmat` or `shmdt`
+    last_pid: Pid,
+    /// Last `shmat` time
+    atime: u64,
+    /// Last `shmdt` time
+    dtime: u64,
+    /// Creation time or last modification via `shmctl`
+    ctime: u64,
+}
+
+impl ShmSegment {
+    pub(in crate::ipc) fn new(
+        key: IpcKey,
+        size: usize,
+        mode: u16,
+        credentials: &Credentials<ReadOp>,
+        creator_pid: Pid,
+    ) -> Result<Self> {
+        if !(SHMMIN..=SHMMAX).contains(&size) {
+            return_errno_with_message!(Errno::EINVAL, "the segment size is out of range");
+        }
+
+        let vmo = VmoOptions::new(size.align_up(PAGE_SIZE)).alloc()?;
+        let permission = IpcPermission::new(key, credentials.euid(), credentials.egid(), mode);
+
+        Ok(Self {
+            permission,
+            size,
+            vmo,
+            creator_pid,
+            inner: SpinLock::new(ShmInner {
+                nattch: 0,
+                is_destroyed: false,
+                is_locked: false,
+                last_pid: 0,
+                atime: 0,
+                dtime: 0,
+                ctime: now(),
+            }),
+        })
+    }
+
+    pub(crate) fn permission(&self) -> &IpcPermission {
+        &self.permission
+    }
+
+    /// Returns the size in bytes.
+    pub(crate) fn size(&self) -> usize {
+        self.size
+    }
+
+    pub(crate) fn vmo(&self) -> &Arc<Vmo> {
+        &self.vmo
+    }
+
+    /// Returns the number of current attaches.
+    pub(crate) fn nattch(&self) -> usize {
+        self.inner.lock().nattch
+    }
+
+    /// Returns whether the segment is locked in memory.
+    pub(crate) fn is_locked(&self) -> bool {
+        self.inner.lock().is_locked
+    }
+
+    pub(crate) fn set_locked(&self, is_locked: bool) {
+        let mut inner = self.inner.lock();
+        inner.is_locked = is_locked;
+        inner.ctime = now();
+    }
+
+    /// Changes the owner and the permission mode with `IPC_SET`.
+    pub(crate) fn set_permission(&self, shmid_ds: &ShmidDs) -> Result<()> {
+        self.permission.set(&shmid_ds.shm_perm)?;
+        self.inner.lock().ctime = now();
+        Ok(())
+    }
+
+    /// Records a new attach by the process identified by `pid`.
+    pub(super) fn attach(&self, pid: Pid) {
+        let mut inner = self.inner.lock();
+        inner.nattch += 1;
+        inner.last_pid = pid;
+        inner.atime = now();
+    }
+
+    /// Records a detach by the process identified by `pid`.
+    ///
+    /// This method returns whether the segment should be removed.
+    pub(super) fn detach(&self, pid: Pid) -> bool {
+        let mut inner = self.inner.lock();
+        inner.nattch -= 1;
+        inner.last_pid = pid;
+        inner.dtime = now();
+
+        inner.is_destroyed && inner.nattch == 0
+    }
+
+    /// Marks the segment to be destroyed after the last detach.
+    ///
+    /// This method returns whether the segment should be removed now.
+    pub(in crate::ipc) fn mark_destroyed(&self) -> bool {
+        let mut inner = self.inner.lock();
+        inner.is_destroyed = true;
+
+        inner.nattch == 0
+    }
+
+    /// Returns whether the segment is marked to be destroyed and is no longer attached.
+    pub(in crate::ipc) fn is_removable(&self) -> bool {
+        let inner = self.inner.lock();
+        inner.is_destroyed && inner.nattch == 0
+    }
+
+    /// Returns the permission mode, including the `SHM_DEST` and `SHM_LOCKED` bits.
+    pub(crate) fn mode(&self) -> u16 {
+        let inner = self.inner.lock();
+
+        let mut mode = self.permission.mode();
+        if inner.is_destroyed {
+            mode |= SHM_DEST;
+        }
+        if inner.is_locked {
+            mode |= SHM_LOCKED;
+        }
+        mode
+    }
+
+    pub(crate) fn shmid_ds(&self) -> ShmidDs {
+        let mut shm_perm = self.permission.to_ipc_perm();
+        shm_perm.mode = self.mode();
+
+        let inner = self.inner.lock();
+        if inner.is_destroyed {
+            // Linux hides the key of a destroyed segment, which can no longer be looked up.
+            shm_perm.key = 0;
+        }
+
+        ShmidDs {
+            shm_perm,
+            shm_segsz: self.size as u64,
+            shm_atime: inner.atime,
+            shm_dtime: inner.dtime,
+            shm_ctime: inner.ctime,
+            shm_cpid: self.creator_pid.cast_signed(),
+            shm_lpid: inner.last_pid.cast_signed(),
+            shm_nattch: inner.nattch as u64,
+            ..ShmidDs::default()
+        }
+    }
+
+    /// Prints the segment information in the format of `/proc/sysvipc/shm`.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/shm.c#L1845-L1883>
+    pub(crate) fn print_to_sysvipc(&self, shmid: IpcId, printer: &mut VmPrinter) -> Result<()> {
+        let shmid_ds = self.shmid_ds();
+        let shm_perm = &shmid_ds.shm_perm;
+        let rss = self.vmo.nr_committed_pages() * PAGE_SIZE;
+
+        writeln!(
+            printer,
+            "{:>10} {:>10}  {:>4o} {:>21} {:>5} {:>5}  {:>5} {:>5} {:>5} {:>5} {:>5} {:>10} {:>10} {:>10} {:>21} {:>21}",
+            shm_perm.key.cast_signed(),
+            shmid.get(),
+            shm_perm.mode,
+            shmid_ds.shm_segsz,
+            shmid_ds.shm_cpid,
+            shmid_ds.shm_lpid,
+            shmid_ds.shm_nattch,
+            shm_perm.uid,
+            shm_perm.gid,
+            shm_perm.cuid,
+            shm_perm.cgid,
+            shmid_ds.shm_atime,
+            shmid_ds.shm_dtime,
+            shmid_ds.shm_ctime,
+            rss,
+            0,
+        )?;
+
+        Ok(())
+    }
+}
+
+fn now() -> u64 {
+    RealTimeCoarseClock::get().read_time().as_secs()
+}
@@ -5,7 +5,7 @@ use core::net::Ipv4Addr;
 use aster_bigtcp::wire::{IpAddress, IpEndpoint};
 use spin::Once;
 
-use crate::{net::iface::iter_all_ifaces, prelude::*};
+use crate::{net::iface::Iface, prelude::*};
 
 /// All known broadcast addresses.
 // FIXME: This information should be maintained in the routing table,
@@ -13,13 +13,13 @@ use crate::{net::iface::iter_all_ifaces, prelude::*};
 // or netmask changes, or if an interface is added/removed.
 static BROADCAST_ADDRS: Once<BTreeSet<Ipv4Addr>> = Once::new();
 
-pub(super) fn init() {
+pub(super) fn init(ifaces: &[Arc<Iface>]) {
     BROADCAST_ADDRS.call_once(|| {
         let mut broadcast_addrs = BTreeSet::new();
         // 255.255.255.255 is always included.
         broadcast_addrs.insert(Ipv4Addr::BROADCAST);
 
-        for iface in iter_all_ifaces() {
+        for iface in ifaces {
             let Some(broadcast_addr) = iface.broadcast_addr() else {
                 continue;
             };
@@ -1,7 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use super::sched::PollScheduler;
-use crate::net::socket::ip::{DatagramObserver, StreamObserver};
+use crate::net::{
+    netfilter::NetfilterHook,
+    socket::{
+        ip::{DatagramObserver, StreamObserver},
+        packet::PacketTap,
+    },
+};
 
 pub(crate) struct BigtcpExt;
 
@@ -10,4 +16,9 @@ impl aster_bigtcp::ext::Ext for BigtcpExt {
 
     type TcpEventObserver = StreamObserver;
     type UdpEventObserver = DatagramObserver;
+    type RawEventObserver = DatagramObserver;
+
+    type FrameTap = PacketTap;
+
+    type PacketFilter = NetfilterHook;
 }
@@ -1,61 +1,88 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use core::slice::Iter;
-
 use aster_bigtcp::{
     device::WithDevice,
     iface::{InterfaceFlags, InterfaceName, InterfaceType},
 };
 use aster_softirq::BottomHalfDisabled;
-use spin::Once;
 
-use super::{Iface, poll::poll_ifaces};
+use super::{
+    Iface,
+    poll::{poll_ifaces, spawn_background_poll_thread},
+};
 use crate::{
-    net::iface::{broadcast, sched::PollScheduler},
+    net::{
+        iface::{broadcast, sched::PollScheduler},
+        net_ns::NetNamespace,
+        route::{RTPROT_BOOT, Route},
+    },
     prelude::*,
 };
 
-static IFACES: Once<Vec<Arc<Iface>>> = Once::new();
-
-pub(crate) fn loopback_iface() -> &'static Arc<Iface> {
-    &IFACES.get().unwrap()[0]
-}
-
-pub(crate) fn virtio_iface() -> Option<&'static Arc<Iface>> {
-    IFACES.get().unwrap().get(1)
-}
-
-pub(crate) fn iter_all_ifaces() -> Iter<'static, Arc<Iface>> {
-    IFACES.get().unwrap().iter()
-}
-
 // TODO: Support multiple network devices and avoid the hardcoded device name.
 const VIRTIO_DEVICE_NAME: &str = aster_virtio::device::network::DEVICE_NAME;
 
 pub(crate) fn init() {
-    IFACES.call_once(|| {
-        let mut ifaces = Vec::with_capacity(2);
+    let ifaces = NetNamespace::get_init_singleton().ifaces();
 
-        // Initialize loopback before virtio
-        // to ensure the loopback interface index is ahead of virtio.
-        ifaces.push(new_loopback());
+    broadcast::init(&ifaces);
 
-        if let Some(iface_virtio) = new_virtio() {
-            ifaces.push(iface_virtio);
-        }
+    poll_ifaces(&ifaces);
+}
+
+/// Creates the interfaces of the initial network namespace.
+pub(in crate::net) fn new_init_ifaces() -> Vec<Arc<Iface>> {
+    let mut ifaces = Vec::with_capacity(2);
 
-        ifaces
-    });
+    // Initialize loopback before virtio
+    // to ensure the loopback interface index is ahead of virtio.
+    ifaces.push(new_loopback());
 
-    if let Some(iface_virtio) = virtio_iface() {
-        let callback = || iface_virtio.poll();
-        aster_network::register_recv_callback(VIRTIO_DEVICE_NAME, callback);
+    if let Some(iface_virtio) = new_virtio() {
+        let callback = {
+            let iface_virtio = iface_virtio.clone();
+            move || iface_virtio.poll()
+        };
+        aster_network::register_recv_callback(VIRTIO_DEVICE_NAME, callback.clone());
         aster_network::register_send_callback(VIRTIO_DEVICE_NAME, callback);
+
+        ifaces.push(iface_virtio);
     }
 
-    broadcast::init();
+    ifaces
+}
+
+/// Creates the routes of the initial network namespace.
+///
+/// `ifaces` should be the interfaces created by [`new_init_ifaces`].
+pub(in crate::net) fn new_init_routes(ifaces: &[Arc<Iface>]) -> Vec<Route> {
+    use aster_bigtcp::wire::{IpAddress, IpCidr, Ipv4Address};
+
+    const VIRTIO_GATEWAY: Ipv4Address = Ipv4Address::new(10, 0, 2, 2);
+
+    // The virtio interface, if any, follows the loopback interface.
+    let Some(iface_virtio) = ifaces.get(1) else {
+        return Vec::new();
+    };
+
+    let default_route = Route {
+        dst: IpCidr::new(IpAddress::Ipv4(Ipv4Address::UNSPECIFIED), 0),
+        gateway: Some(IpAddress::Ipv4(VIRTIO_GATEWAY)),
+        iface: iface_virtio.clone(),
+        priority: 0,
+        protocol: RTPROT_BOOT,
+    };
+    vec![default_route]
+}
 
-    poll_ifaces();
+/// Creates the loopback interface of a new network namespace.
+///
+/// The background polling thread of the interface is spawned immediately, so this should only be
+/// called after the first kernel thread starts.
+pub(in crate::net) fn new_ns_loopback() -> Arc<Iface> {
+    let iface = new_loopback();
+    spawn_background_poll_thread(iface.clone());
+    iface
 }
 
 fn new_loopback() -> Arc<Iface> {
@@ -93,7 +120,7 @@ fn new_loopback() -> Arc<Iface> {
 
     IpIface::new(
         Wrapper(Mutex::new(Loopback::new(Medium::Ip))),
-        Ipv4Cidr::new(LOOPBACK_ADDRESS, LOOPBACK_ADDRESS_PREFIX_LEN),
+        Some(Ipv4Cidr::new(LOOPBACK_ADDRESS, LOOPBACK_ADDRESS_PREFIX_LEN)),
         Some(Ipv6Cidr::new(
             LOOPBACK_IPV6_ADDRESS,
             LOOPBACK_IPV6_PREFIX_LEN,
@@ -114,7 +141,6 @@ fn new_virtio() -> Option<Arc<Iface>> {
 
     const VIRTIO_ADDRESS: Ipv4Address = Ipv4Address::new(10, 0, 2, 15);
     const VIRTIO_ADDRESS_PREFIX_LEN: u8 = 24; // mask: 255.255.255.0
-    const VIRTIO_GATEWAY: Ipv4Address = Ipv4Address::new(10, 0, 2, 2);
 
     let virtio_net = aster_network::get_device(VIRTIO_DEVICE_NAME)?;
 
@@ -142,13 +168,17 @@ fn new_virtio() -> Option<Arc<Iface>> {
         | InterfaceFlags::MULTICAST
         | InterfaceFlags::LOWER_UP;
 
-    Some(EtherIface::new(
+    let iface = EtherIface::new(
         Wrapper(virtio_net),
         EthernetAddress(ether_addr),
-        Ipv4Cidr::new(VIRTIO_ADDRESS, VIRTIO_ADDRESS_PREFIX_LEN),
-        VIRTIO_GATEWAY,
+        Some(Ipv4Cidr::new(VIRTIO_ADDRESS, VIRTIO_ADDRESS_PREFIX_LEN)),
         InterfaceName::from_str_truncated("eth0"),
         PollScheduler::new(),
         flags,
-    ))
+    );
+    // Like Linux, the IPv6 addresses are configured automatically. QEMU's user-mode network
+    // advertises the `fec0::/64` prefix, from which a global address will be configured.
+    iface.enable_ipv6_autoconf();
+
+    Some(iface)
 }
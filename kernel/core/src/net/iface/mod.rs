@@ -5,20 +5,29 @@ mod ext;
 mod init;
 mod poll;
 mod sched;
+mod virt;
 
 pub(crate) use broadcast::is_broadcast_endpoint;
-pub(crate) use init::{init, iter_all_ifaces, loopback_iface, virtio_iface};
+pub(crate) use init::init;
+pub(super) use init::{new_init_ifaces, new_init_routes, new_ns_loopback};
 pub(super) use poll::init_in_first_kthread;
+pub(super) use virt::delete_ns_links;
+pub(crate) use virt::{
+    TunFlags, TunQueue, delete_link, link_master_index, link_peer_index, move_link, new_bridge,
+    new_veth_pair, set_link_master,
+};
 
 pub(crate) type Iface = dyn aster_bigtcp::iface::Iface<ext::BigtcpExt>;
 pub(crate) type BoundTcpPort = aster_bigtcp::iface::BoundTcpPort<ext::BigtcpExt>;
 pub(crate) type BoundUdpPort = aster_bigtcp::iface::BoundUdpPort<ext::BigtcpExt>;
+pub(crate) type BoundRawPort = aster_bigtcp::iface::BoundRawPort<ext::BigtcpExt>;
 
 pub(crate) type RawTcpSocketExt = aster_bigtcp::socket::RawTcpSocketExt<ext::BigtcpExt>;
 
 pub(crate) type TcpConnection = aster_bigtcp::socket::TcpConnection<ext::BigtcpExt>;
 pub(crate) type TcpListener = aster_bigtcp::socket::TcpListener<ext::BigtcpExt>;
 pub(crate) type UdpSocket = aster_bigtcp::socket::UdpSocket<ext::BigtcpExt>;
+pub(crate) type RawSocket = aster_bigtcp::socket::RawSocket<ext::BigtcpExt>;
 
 /// The default transmit queue length.
 ///
@@ -0,0 +1,124 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::{
+    iface::InterfaceName,
+    wire::{EthernetAddress, EthernetFrame},
+};
+use aster_softirq::BottomHalfDisabled;
+
+use super::{VIRT_LINKS, VirtLink, VirtLinkKind, resolve_name};
+use crate::{
+    net::{iface::Iface, net_ns::NetNamespace},
+    prelude::*,
+};
+
+/// A learning Ethernet bridge.
+///
+/// The bridge learns the ports that the Ethernet addresses are behind from the source addresses
+/// of the received frames. A frame is forwarded to the port behind its destination address if
+/// the port is known, and is flooded to all the other ports otherwise.
+pub(super) struct Bridge {
+    ports: SpinLock<Vec<Arc<VirtLink>>, BottomHalfDisabled>,
+    /// The forwarding database, which maps the Ethernet addresses to the ports behind them.
+    //
+    // TODO: Remove the entries if they expire.
+    fdb: SpinLock<BTreeMap<EthernetAddress, Weak<VirtLink>>, BottomHalfDisabled>,
+}
+
+impl Bridge {
+    fn new() -> Self {
+        Self {
+            ports: SpinLock::new(Vec::new()),
+            fdb: SpinLock::new(BTreeMap::new()),
+        }
+    }
+
+    pub(super) fn add_port(&self, port: Arc<VirtLink>) {
+        self.ports.lock().push(port);
+    }
+
+    pub(super) fn remove_port(&self, port: &VirtLink) {
+        self.ports
+            .lock()
+            .retain(|other| !core::ptr::eq(other.as_ref(), port));
+        self.fdb
+            .lock()
+            .retain(|_, other| !core::ptr::eq(other.as_ptr(), port));
+    }
+
+    pub(super) fn release_all_ports(&self) {
+        let ports = core::mem::take(&mut *self.ports.lock());
+        for port in ports {
+            *port.master.lock() = Weak::new();
+        }
+        self.fdb.lock().clear();
+    }
+
+    /// Forwards a frame that is received from the `ingress` port, or is transmitted by the
+    /// interface of the bridge if `ingress` is `None`.
+    ///
+    /// `bridge_link` is the link that owns the bridge. Frames that are sent to the Ethernet
+    /// address of the bridge, as well as broadcast and multicast frames, are also delivered to the
+    /// interface of the bridge.
+    pub(super) fn forward(
+        &self,
+        bridge_link: &VirtLink,
+        ingress: Option<&Arc<VirtLink>>,
+        frame: Vec<u8>,
+    ) {
+        let Ok(ether_frame) = EthernetFrame::new_checked(frame.as_slice()) else {
+            return;
+        };
+        let src_addr = ether_frame.src_addr();
+        let dst_addr = ether_frame.dst_addr();
+
+        let is_from_ingress =
+            |port: &Arc<VirtLink>| ingress.is_some_and(|ingress| Arc::ptr_eq(ingress, port));
+
+        if let Some(ingress) = ingress {
+            if src_addr.is_unicast() && src_addr != bridge_link.ether_addr {
+                self.fdb.lock().insert(src_addr, Arc::downgrade(ingress));
+            }
+
+            if dst_addr == bridge_link.ether_addr {
+                bridge_link.enqueue(frame);
+                return;
+            }
+            if !dst_addr.is_unicast() {
+                bridge_link.enqueue(frame.clone());
+            }
+        }
+
+        let egress = if dst_addr.is_unicast() {
+            self.fdb.lock().get(&dst_addr).and_then(Weak::upgrade)
+        } else {
+            None
+        };
+        if let Some(egress) = egress {
+            if !is_from_ingress(&egress) {
+                egress.transmit(frame);
+            }
+            return;
+        }
+
+        let ports = self.ports.lock().clone();
+        for port in ports.iter().filter(|port| !is_from_ingress(port)) {
+            port.transmit(frame.clone());
+        }
+    }
+}
+
+/// Creates a bridge and returns its interface.
+pub(crate) fn new_bridge(
+    net_ns: &Arc<NetNamespace>,
+    name: Option<InterfaceName>,
+) -> Result<Arc<Iface>> {
+    let mut links = VIRT_LINKS.lock();
+
+    let name = resolve_name(net_ns, name, "bridge%d", &[])?;
+
+    let link = VirtLink::new(VirtLinkKind::Bridge(Bridge::new()));
+    let iface = link.register(&mut links, name, net_ns);
+
+    Ok(iface)
+}
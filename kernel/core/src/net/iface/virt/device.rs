@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::{
+    device::{self, DeviceCapabilities, Medium, NotifyDevice, WithDevice},
+    time::Instant,
+};
+
+use super::VirtLink;
+use crate::prelude::*;
+
+/// The maximum size of an Ethernet frame, excluding the frame check sequence.
+const MAX_FRAME_LEN: usize = 1514;
+
+/// The maximum size of an IP packet, which is the MTU of an Ethernet link.
+const MAX_PACKET_LEN: usize = 1500;
+
+/// The driver of the interface of a virtual link.
+pub(super) struct VirtDriver(Arc<VirtLink>);
+
+impl VirtDriver {
+    pub(super) fn new(link: Arc<VirtLink>) -> Self {
+        Self(link)
+    }
+}
+
+impl WithDevice for VirtDriver {
+    type Device = VirtDevice;
+
+    fn with<F, R>(&self, f: F) -> R
+    where
+        F: FnOnce(&mut Self::Device) -> R,
+    {
+        // The polling of the interface is already serialized by the interface itself, so the
+        // device needs no lock.
+        let res = f(&mut VirtDevice(self.0.clone()));
+
+        // A frame may be enqueued after the interface stops receiving frames but before the
+        // interface schedules the next poll, which may cancel the poll requested for the frame.
+        if self.0.has_pending_frames() {
+            self.0.schedule_poll();
+        }
+
+        res
+    }
+}
+
+pub(super) struct VirtDevice(Arc<VirtLink>);
+
+impl device::Device for VirtDevice {
+    type RxToken<'a> = RxToken;
+    type TxToken<'a> = TxToken<'a>;
+
+    fn receive(&mut self, _timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let frame = self.0.dequeue()?;
+        Some((RxToken(frame), TxToken(&self.0)))
+    }
+
+    fn transmit(&mut self, _timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        Some(TxToken(&self.0))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        let mut caps = DeviceCapabilities::default();
+        caps.medium = self.0.medium();
+        caps.max_transmission_unit = match caps.medium {
+            Medium::Ethernet => MAX_FRAME_LEN,
+            Medium::Ip => MAX_PACKET_LEN,
+        };
+        caps
+    }
+}
+
+impl NotifyDevice for VirtDevice {
+    fn notify_poll_end(&mut self) {}
+}
+
+pub(super) struct RxToken(Vec<u8>);
+
+impl device::RxToken for RxToken {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        f(&self.0)
+    }
+}
+
+pub(super) struct TxToken<'a>(&'a Arc<VirtLink>);
+
+impl device::TxToken for TxToken<'_> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        let mut frame = vec![0u8; len];
+        let res = f(&mut frame);
+        self.0.transmit(frame);
+        res
+    }
+}
@@ -0,0 +1,484 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Virtual links created from user space.
+//!
+//! A virtual link is the device below an [`EtherIface`], or an [`IpIface`] for a TUN device.
+//! Frames transmitted by the interface are handed to the link, which delivers them elsewhere:
+//! - A veth link delivers the frames to its peer, so a veth pair behaves like a cable.
+//! - A bridge link forwards the frames to its ports, which are Ethernet links attached to the
+//!   bridge. Frames received by a port are forwarded by the bridge instead of being processed by
+//!   the interface of the port.
+//! - A TUN/TAP link delivers the frames to the files opened from `/dev/net/tun`, from which the
+//!   frames are read. Frames written to the files are received by the link.
+//!
+//! The delivery is asynchronous: each receive queue is drained by the background polling thread
+//! of the receiving interface. Therefore, a link never calls into the network stack of another
+//! interface while its own interface is being polled.
+//!
+//! [`EtherIface`]: aster_bigtcp::iface::EtherIface
+//! [`IpIface`]: aster_bigtcp::iface::IpIface
+
+mod bridge;
+mod device;
+mod tun;
+mod veth;
+
+use alloc::collections::vec_deque::VecDeque;
+
+use aster_bigtcp::{
+    device::Medium,
+    iface::{EtherIface, InterfaceFlags, InterfaceName, InterfaceType, IpIface, ScheduleNextPoll},
+    wire::EthernetAddress,
+};
+use aster_softirq::BottomHalfDisabled;
+use bridge::Bridge;
+pub(crate) use bridge::new_bridge;
+use device::VirtDriver;
+use ostd::timer::Jiffies;
+use spin::Once;
+use tun::Tun;
+pub(crate) use tun::{TunFlags, TunQueue};
+use veth::Veth;
+pub(crate) use veth::new_veth_pair;
+
+use super::{
+    DEFAULT_TX_QUEUE_LEN, Iface, poll::spawn_background_poll_thread, sched::PollScheduler,
+};
+use crate::{net::net_ns::NetNamespace, prelude::*, util::random::getrandom};
+
+/// A virtual link.
+struct VirtLink {
+    kind: VirtLinkKind,
+    ether_addr: EthernetAddress,
+    id: Once<u32>,
+    iface: Once<Weak<Iface>>,
+    /// The frames that are waiting to be received by the interface.
+    rx_queue: SpinLock<VecDeque<Vec<u8>>, BottomHalfDisabled>,
+    /// The bridge that the link is attached to.
+    master: SpinLock<Weak<VirtLink>, BottomHalfDisabled>,
+    /// The network namespace that the interface belongs to.
+    net_ns: Mutex<Weak<NetNamespace>>,
+}
+
+enum VirtLinkKind {
+    Veth(Veth),
+    Bridge(Bridge),
+    Tun(Tun),
+}
+
+/// The virtual links, indexed by the IDs of their interfaces.
+///
+/// The lock also serializes the configuration changes of the virtual links, much like
+/// `rtnl_lock` in Linux.
+static VIRT_LINKS: Mutex<BTreeMap<u32, Arc<VirtLink>>> = Mutex::new(BTreeMap::new());
+
+impl VirtLink {
+    fn new(kind: VirtLinkKind) -> Arc<Self> {
+        Arc::new(Self {
+            kind,
+            ether_addr: random_ether_addr(),
+            id: Once::new(),
+            iface: Once::new(),
+            rx_queue: SpinLock::new(VecDeque::new()),
+            master: SpinLock::new(Weak::new()),
+            net_ns: Mutex::new(Weak::new()),
+        })
+    }
+
+    /// Creates the interface of the link and adds it to the network namespace.
+    ///
+    /// The name must have been resolved by [`resolve_name`] with the lock of [`VIRT_LINKS`] held.
+    fn register(
+        self: &Arc<Self>,
+        links: &mut BTreeMap<u32, Arc<VirtLink>>,
+        name: InterfaceName,
+        net_ns: &Arc<NetNamespace>,
+    ) -> Arc<Iface> {
+        // FIXME: These flags, except `UP`, are currently hardcoded. They should change when the
+        // peer of a veth link is set up or down.
+        let flags = InterfaceFlags::UP
+            | InterfaceFlags::RUNNING
+            | InterfaceFlags::MULTICAST
+            | InterfaceFlags::LOWER_UP;
+
+        let iface = match self.medium() {
+            Medium::Ethernet => EtherIface::new(
+                VirtDriver::new(self.clone()),
+                self.ether_addr,
+                None,
+                name,
+                PollScheduler::new(),
+                flags | InterfaceFlags::BROADCAST,
+            ) as Arc<Iface>,
+            Medium::Ip => IpIface::new(
+                VirtDriver::new(self.clone()),
+                None,
+                None,
+                name,
+                PollScheduler::new(),
+                InterfaceType::NONE,
+                flags | InterfaceFlags::POINTOPOINT | InterfaceFlags::NOARP,
+            ) as Arc<Iface>,
+        };
+        self.id.call_once(|| iface.id());
+        self.iface.call_once(|| Arc::downgrade(&iface));
+
+        links.insert(iface.id(), self.clone());
+        *self.net_ns.lock() = Arc::downgrade(net_ns);
+        net_ns.add_iface(iface.clone());
+        spawn_background_poll_thread(iface.clone());
+
+        iface
+    }
+
+    /// Removes the link from [`VIRT_LINKS`].
+    ///
+    /// This method returns the links that are unregistered, which include the peer of a veth
+    /// link. The caller should call [`Self::detach_from_ns`] on them after releasing the lock of
+    /// [`VIRT_LINKS`].
+    fn unregister(self: &Arc<Self>, links: &mut BTreeMap<u32, Arc<VirtLink>>) -> Vec<Arc<Self>> {
+        let mut unregistered = Vec::with_capacity(2);
+
+        links.remove(&self.id());
+        self.release_from_master();
+        match &self.kind {
+            VirtLinkKind::Veth(veth) => {
+                if let Some(peer) = veth.take_peer() {
+                    links.remove(&peer.id());
+                    peer.release_from_master();
+                    unregistered.push(peer);
+                }
+            }
+            VirtLinkKind::Bridge(bridge) => bridge.release_all_ports(),
+            VirtLinkKind::Tun(tun) => tun.detach_all_queues(),
+        }
+        unregistered.push(self.clone());
+
+        unregistered
+    }
+
+    /// Removes the interface of an unregistered link from its network namespace.
+    fn detach_from_ns(&self) {
+        let net_ns = core::mem::take(&mut *self.net_ns.lock()).upgrade();
+        if let Some(net_ns) = net_ns {
+            net_ns.remove_iface(self.id());
+        }
+
+        if let Some(iface) = self.iface() {
+            iface.sched_poll().stop();
+        }
+        self.rx_queue.lock().clear();
+    }
+
+    fn id(&self) -> u32 {
+        *self.id.get().unwrap()
+    }
+
+    fn iface(&self) -> Option<Arc<Iface>> {
+        self.iface.get().and_then(Weak::upgrade)
+    }
+
+    /// Returns the medium of the link, which is IP for a TUN link and Ethernet otherwise.
+    fn medium(&self) -> Medium {
+        match &self.kind {
+            VirtLinkKind::Tun(tun) if !tun.is_tap() => Medium::Ip,
+            _ => Medium::Ethernet,
+        }
+    }
+
+    fn master(&self) -> Option<Arc<VirtLink>> {
+        self.master.lock().upgrade()
+    }
+
+    /// Attaches the link to a bridge, or detaches the link from its bridge if `master` is `None`.
+    fn set_master(self: &Arc<Self>, master: Option<&Arc<VirtLink>>) -> Result<()> {
+        let Some(master) = master else {
+            self.release_from_master();
+            return Ok(());
+        };
+
+        let VirtLinkKind::Bridge(bridge) = &master.kind else {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "the master link is not a bridge");
+        };
+        if matches!(self.kind, VirtLinkKind::Bridge(_)) {
+            return_errno_with_message!(Errno::ELOOP, "a bridge cannot be attached to a bridge");
+        }
+        if self.medium() != Medium::Ethernet {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "only Ethernet links can be attached to a bridge"
+            );
+        }
+
+        if self
+            .master()
+            .is_some_and(|old_master| Arc::ptr_eq(&old_master, master))
+        {
+            return Ok(());
+        }
+        self.release_from_master();
+
+        bridge.add_port(self.clone());
+        *self.master.lock() = Arc::downgrade(master);
+
+        Ok(())
+    }
+
+    fn release_from_master(&self) {
+        let master = core::mem::take(&mut *self.master.lock()).upgrade();
+        if let Some(master) = master
+            && let VirtLinkKind::Bridge(bridge) = &master.kind
+        {
+            bridge.remove_port(self);
+        }
+    }
+
+    /// Transmits a frame from the interface of the link.
+    fn transmit(self: &Arc<Self>, frame: Vec<u8>) {
+        match &self.kind {
+            VirtLinkKind::Veth(veth) => {
+                if let Some(peer) = veth.peer() {
+                    peer.enqueue(frame);
+                }
+            }
+            VirtLinkKind::Bridge(bridge) => bridge.forward(self, None, frame),
+            VirtLinkKind::Tun(tun) => tun.transmit(frame),
+        }
+    }
+
+    /// Enqueues a frame to be received by the interface of the link.
+    ///
+    /// The frame is dropped if the receive queue is full.
+    fn enqueue(&self, frame: Vec<u8>) {
+        {
+            let mut rx_queue = self.rx_queue.lock();
+            if rx_queue.len() >= DEFAULT_TX_QUEUE_LEN as usize {
+                return;
+            }
+            rx_queue.push_back(frame);
+        }
+
+        self.schedule_poll();
+    }
+
+    /// Dequeues a frame to be processed by the interface of the link.
+    ///
+    /// If the link is attached to a bridge, the frames are forwarded by the bridge instead.
+    fn dequeue(self: &Arc<Self>) -> Option<Vec<u8>> {
+        loop {
+            let frame = self.rx_queue.lock().pop_front()?;
+
+            let Some(master) = self.master() else {
+                return Some(frame);
+            };
+            if let VirtLinkKind::Bridge(bridge) = &master.kind {
+                bridge.forward(&master, Some(self), frame);
+            }
+        }
+    }
+
+    fn has_pending_frames(&self) -> bool {
+        !self.rx_queue.lock().is_empty()
+    }
+
+    fn schedule_poll(&self) {
+        if let Some(iface) = self.iface() {
+            let now_as_ms = Jiffies::elapsed().as_duration().as_millis() as u64;
+            iface.sched_poll().schedule_next_poll(Some(now_as_ms));
+        }
+    }
+}
+
+/// Returns the virtual link of the interface.
+fn virt_link_of<'a>(
+    links: &'a BTreeMap<u32, Arc<VirtLink>>,
+    iface: &Iface,
+) -> Option<&'a Arc<VirtLink>> {
+    links.get(&iface.id())
+}
+
+/// Deletes the virtual link of the interface.
+///
+/// Deleting a veth link also deletes its peer. Deleting a TUN/TAP link detaches all its files.
+pub(crate) fn delete_link(iface: &Iface) -> Result<()> {
+    let unregistered = {
+        let mut links = VIRT_LINKS.lock();
+        let Some(link) = virt_link_of(&links, iface).cloned() else {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "the link cannot be deleted");
+        };
+        link.unregister(&mut links)
+    };
+
+    for link in unregistered {
+        link.detach_from_ns();
+    }
+
+    Ok(())
+}
+
+/// Deletes the virtual links among the interfaces of a network namespace that is being
+/// destroyed.
+///
+/// Like Linux, the peers of the veth links are deleted even if they are in other network
+/// namespaces.
+pub(in crate::net) fn delete_ns_links(ifaces: &[Arc<Iface>]) {
+    let unregistered = {
+        let mut links = VIRT_LINKS.lock();
+        let mut unregistered = Vec::new();
+        for iface in ifaces {
+            if let Some(link) = virt_link_of(&links, iface).cloned() {
+                unregistered.extend(link.unregister(&mut links));
+            }
+        }
+        unregistered
+    };
+
+    for link in unregistered {
+        link.detach_from_ns();
+    }
+}
+
+/// Attaches the link of the interface to a bridge, or detaches it from its bridge if `master`
+/// is `None`.
+pub(crate) fn set_link_master(iface: &Iface, master: Option<&Iface>) -> Result<()> {
+    let links = VIRT_LINKS.lock();
+
+    let master = match master {
+        Some(master) => Some(virt_link_of(&links, master).ok_or_else(|| {
+            Error::with_message(Errno::EOPNOTSUPP, "the master link is not a bridge")
+        })?),
+        None => None,
+    };
+
+    match virt_link_of(&links, iface) {
+        Some(link) => link.set_master(master),
+        None if master.is_none() => Ok(()),
+        None => return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "only virtual links can be attached to a bridge"
+        ),
+    }
+}
+
+/// Returns the interface index of the bridge that the link of the interface is attached to.
+pub(crate) fn link_master_index(iface: &Iface) -> Option<u32> {
+    let links = VIRT_LINKS.lock();
+    let master = virt_link_of(&links, iface)?.master()?;
+    Some(master.iface()?.index())
+}
+
+/// Returns the interface index of the peer if the link of the interface is a veth link.
+pub(crate) fn link_peer_index(iface: &Iface) -> Option<u32> {
+    let links = VIRT_LINKS.lock();
+    let VirtLinkKind::Veth(veth) = &virt_link_of(&links, iface)?.kind else {
+        return None;
+    };
+    Some(veth.peer()?.iface()?.index())
+}
+
+/// Moves the link of the interface from the network namespace to another one.
+///
+/// Like Linux, only veth links can be moved, and moving a link detaches it from its bridge.
+pub(crate) fn move_link(
+    iface: &Arc<Iface>,
+    from_ns: &NetNamespace,
+    to_ns: &Arc<NetNamespace>,
+) -> Result<()> {
+    let links = VIRT_LINKS.lock();
+
+    let link = match virt_link_of(&links, iface) {
+        Some(link) if matches!(link.kind, VirtLinkKind::Veth(_)) => link.clone(),
+        _ => return_errno_with_message!(
+            Errno::EINVAL,
+            "the link cannot be moved to another network namespace"
+        ),
+    };
+    if core::ptr::eq(from_ns, to_ns.as_ref()) {
+        return Ok(());
+    }
+
+    if to_ns
+        .find_iface(|other| other.name() == iface.name())
+        .is_some()
+    {
+        return_errno_with_message!(Errno::EEXIST, "the interface name is already in use");
+    }
+
+    link.release_from_master();
+    from_ns.remove_iface(iface.id());
+    *link.net_ns.lock() = Arc::downgrade(to_ns);
+    to_ns.add_iface(iface.clone());
+
+    Ok(())
+}
+
+/// Resolves the name of a new interface in the network namespace.
+///
+/// If `name` is `None`, `template` is used instead. If the name contains `%d`, it is replaced
+/// with the smallest number that makes the name unique. The names in `reserved` are considered
+/// to be in use.
+///
+/// This method must be called with the lock of [`VIRT_LINKS`] held, so that the name remains
+/// unique until the interface is added to the network namespace.
+//
+// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/net/core/dev.c#L1171>
+fn resolve_name(
+    net_ns: &NetNamespace,
+    name: Option<InterfaceName>,
+    template: &str,
+    reserved: &[InterfaceName],
+) -> Result<InterfaceName> {
+    let name = name.unwrap_or_else(|| InterfaceName::from_str_truncated(template));
+    let is_used = |name: &InterfaceName| {
+        reserved.contains(name) || net_ns.find_iface(|iface| iface.name() == name).is_some()
+    };
+
+    let bytes = name.as_bytes();
+    if bytes.is_empty()
+        || bytes == b"."
+        || bytes == b".."
+        || bytes
+            .iter()
+            .any(|byte| *byte == b'/' || *byte == b':' || byte.is_ascii_whitespace())
+    {
+        return_errno_with_message!(Errno::EINVAL, "the interface name is not valid");
+    }
+
+    let Some(pos) = bytes.iter().position(|byte| *byte == b'%') else {
+        if is_used(&name) {
+            return_errno_with_message!(Errno::EEXIST, "the interface name is already in use");
+        }
+        return Ok(name);
+    };
+    if bytes.get(pos + 1) != Some(&b'd') || bytes[pos + 2..].contains(&b'%') {
+        return_errno_with_message!(Errno::EINVAL, "the interface name template is not valid");
+    }
+
+    let (prefix, suffix) = (&bytes[..pos], &bytes[pos + 2..]);
+    for number in 0..MAX_NAME_NUMBER {
+        let number = number.to_string();
+        if prefix.len() + number.len() + suffix.len() > InterfaceName::MAX_BYTES {
+            break;
+        }
+
+        let candidate = [prefix, number.as_bytes(), suffix].concat();
+        let candidate = InterfaceName::from_bytes_until_nul(&candidate);
+        if !is_used(&candidate) {
+            return Ok(candidate);
+        }
+    }
+
+    return_errno_with_message!(Errno::ENFILE, "no interface name is available");
+}
+
+/// The maximum number of interfaces that can be named from the same template.
+const MAX_NAME_NUMBER: usize = 32768;
+
+/// Generates a random unicast Ethernet address that is locally administered.
+fn random_ether_addr() -> EthernetAddress {
+    let mut addr = [0u8; 6];
+    getrandom(&mut addr);
+    addr[0] &= !0x01; // Clear the multicast bit.
+    addr[0] |= 0x02; // Set the locally administered bit.
+    EthernetAddress(addr)
+}
@@ -0,0 +1,422 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{AtomicBool, AtomicU16, Ordering};
+
+use aster_bigtcp::iface::InterfaceName;
+use aster_softirq::BottomHalfDisabled;
+
+use super::{DEFAULT_TX_QUEUE_LEN, VIRT_LINKS, VirtLink, VirtLinkKind, resolve_name, virt_link_of};
+use crate::{
+    events::IoEvents,
+    net::net_ns::NetNamespace,
+    prelude::*,
+    process::signal::{PollHandle, Pollee},
+};
+
+bitflags! {
+    /// The flags of a TUN/TAP device.
+    ///
+    /// These are the `ifr_flags` of the `struct ifreq` used by the TUN/TAP ioctls.
+    //
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/if_tun.h#L64>
+    pub(crate) struct TunFlags: u16 {
+        /// The device exchanges IP packets.
+        const TUN          = 0x0001;
+        /// The device exchanges Ethernet frames.
+        const TAP          = 0x0002;
+        const NAPI         = 0x0010;
+        const NAPI_FRAGS   = 0x0020;
+        const NO_CARRIER   = 0x0040;
+        /// Multiple files can be attached to the device as its queues.
+        const MULTI_QUEUE  = 0x0100;
+        /// Enables a queue with `TUNSETQUEUE`.
+        const ATTACH_QUEUE = 0x0200;
+        /// Disables a queue with `TUNSETQUEUE`.
+        const DETACH_QUEUE = 0x0400;
+        /// The device is kept after all its files are closed.
+        const PERSIST      = 0x0800;
+        /// The packets are not prefixed with the packet information header.
+        const NO_PI        = 0x1000;
+        /// Ignored. This flag only exists for backward compatibility.
+        const ONE_QUEUE    = 0x2000;
+        const VNET_HDR     = 0x4000;
+        /// Fails if the device already exists.
+        const TUN_EXCL     = 0x8000;
+
+        /// The flags that can be changed whenever a file is attached to the device.
+        const FEATURES     = Self::NO_PI.bits | Self::ONE_QUEUE.bits | Self::MULTI_QUEUE.bits;
+        /// The flags that are not supported yet.
+        const UNSUPPORTED  = Self::NAPI.bits | Self::NAPI_FRAGS.bits | Self::VNET_HDR.bits;
+    }
+}
+
+/// The maximum number of queues of a TUN/TAP device.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/if_tun.h#L23>
+const MAX_QUEUES: usize = 256;
+
+/// A TUN/TAP device.
+///
+/// A TUN device exchanges IP packets and a TAP device exchanges Ethernet frames with the files
+/// attached to it, each of which serves as a queue. Packets written to a file are received by the
+/// interface of the device, while packets transmitted by the interface are delivered to one of
+/// the enabled queues and can be read from the file.
+pub(super) struct Tun {
+    flags: AtomicU16,
+    /// The queues that are attached to the device, including the disabled ones.
+    queues: SpinLock<Vec<Arc<TunQueue>>, BottomHalfDisabled>,
+}
+
+impl Tun {
+    fn new(flags: TunFlags) -> Self {
+        Self {
+            flags: AtomicU16::new(flags.bits()),
+            queues: SpinLock::new(Vec::new()),
+        }
+    }
+
+    fn flags(&self) -> TunFlags {
+        TunFlags::from_bits_truncate(self.flags.load(Ordering::Relaxed))
+    }
+
+    /// Updates the flags.
+    ///
+    /// This method must be called with the lock of [`VIRT_LINKS`] held.
+    fn set_flags(&self, flags: TunFlags) {
+        self.flags.store(flags.bits(), Ordering::Relaxed);
+    }
+
+    pub(super) fn is_tap(&self) -> bool {
+        self.flags().contains(TunFlags::TAP)
+    }
+
+    fn add_queue(&self, queue: &Arc<TunQueue>) -> Result<()> {
+        let mut queues = self.queues.lock();
+        if !queues.is_empty() && !self.flags().contains(TunFlags::MULTI_QUEUE) {
+            return_errno_with_message!(Errno::EBUSY, "the device already has a queue");
+        }
+        if queues.len() >= MAX_QUEUES {
+            return_errno_with_message!(Errno::E2BIG, "the device has too many queues");
+        }
+        queues.push(queue.clone());
+
+        Ok(())
+    }
+
+    /// Removes the queue and returns whether the device still has queues.
+    fn remove_queue(&self, queue: &TunQueue) -> bool {
+        let mut queues = self.queues.lock();
+        queues.retain(|other| !core::ptr::eq(other.as_ref(), queue));
+        !queues.is_empty()
+    }
+
+    /// Detaches all the queues because the device is being deleted.
+    pub(super) fn detach_all_queues(&self) {
+        let queues = core::mem::take(&mut *self.queues.lock());
+        for queue in queues {
+            *queue.link.lock() = None;
+            queue.packets.lock().clear();
+            queue.pollee.notify(IoEvents::ERR);
+        }
+    }
+
+    /// Delivers a packet transmitted by the interface to one of the enabled queues.
+    ///
+    /// The packet is dropped if there are no enabled queues.
+    pub(super) fn transmit(&self, packet: Vec<u8>) {
+        let queue = {
+            let queues = self.queues.lock();
+            let mut enabled_queues = queues.iter().filter(|queue| queue.is_enabled());
+            let num_enabled = enabled_queues.clone().count();
+            if num_enabled == 0 {
+                return;
+            }
+            enabled_queues
+                .nth(self.flow_hash(&packet) % num_enabled)
+                .unwrap()
+                .clone()
+        };
+
+        queue.push(packet);
+    }
+
+    /// Hashes the addresses in the packet, so that the packets of the same flow are delivered to
+    /// the same queue.
+    fn flow_hash(&self, packet: &[u8]) -> usize {
+        let addrs = if self.is_tap() {
+            packet.get(..12)
+        } else {
+            match packet.first().map(|byte| byte >> 4) {
+                Some(4) => packet.get(12..20),
+                Some(6) => packet.get(8..40),
+                _ => None,
+            }
+        };
+
+        addrs.unwrap_or_default().iter().fold(0usize, |hash, byte| {
+            hash.wrapping_mul(31).wrapping_add(*byte as usize)
+        })
+    }
+}
+
+/// A queue of a TUN/TAP device.
+///
+/// Each file opened from `/dev/net/tun` owns a queue, which is attached to a device by
+/// `TUNSETIFF`.
+pub(crate) struct TunQueue {
+    /// The link of the device that the queue is attached to.
+    link: Mutex<Option<Arc<VirtLink>>>,
+    /// Whether the queue receives the packets transmitted by the interface.
+    ///
+    /// A queue of a multiqueue device can be disabled by `TUNSETQUEUE` while staying attached.
+    is_enabled: AtomicBool,
+    /// The packets that are transmitted by the interface and wait to be read.
+    packets: SpinLock<VecDeque<Vec<u8>>, BottomHalfDisabled>,
+    pollee: Pollee,
+}
+
+impl TunQueue {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            link: Mutex::new(None),
+            is_enabled: AtomicBool::new(false),
+            packets: SpinLock::new(VecDeque::new()),
+            pollee: Pollee::new(),
+        })
+    }
+
+    /// Attaches the queue to the device with the name, creating the device if it does not exist.
+    ///
+    /// If `name` is `None` or contains `%d`, a new device is created with a generated name. This
+    /// method returns the name of the device.
+    pub(crate) fn attach(
+        self: &Arc<Self>,
+        net_ns: &Arc<NetNamespace>,
+        name: Option<InterfaceName>,
+        flags: TunFlags,
+    ) -> Result<InterfaceName> {
+        let mut links = VIRT_LINKS.lock();
+        let mut queue_link = self.link.lock();
+
+        if queue_link.is_some() {
+            if self.is_enabled() {
+                return_errno_with_message!(
+                    Errno::EEXIST,
+                    "the file is already attached to a device"
+                );
+            }
+            return_errno_with_message!(Errno::EINVAL, "the queue of the file is disabled");
+        }
+
+        // Like Linux, `IFF_TUN` takes precedence if both `IFF_TUN` and `IFF_TAP` are set.
+        let type_flag = if flags.contains(TunFlags::TUN) {
+            TunFlags::TUN
+        } else if flags.contains(TunFlags::TAP) {
+            TunFlags::TAP
+        } else {
+            return_errno_with_message!(Errno::EINVAL, "the device type is not specified");
+        };
+        if flags.intersects(TunFlags::UNSUPPORTED) {
+            return_errno_with_message!(Errno::EINVAL, "the flags are not supported");
+        }
+        let features = flags & TunFlags::FEATURES;
+
+        let existing = name.and_then(|name| net_ns.find_iface(|iface| iface.name() == &name));
+        let link = if let Some(iface) = existing {
+            if flags.contains(TunFlags::TUN_EXCL) {
+                return_errno_with_message!(Errno::EBUSY, "the device already exists");
+            }
+
+            let link = virt_link_of(&links, &iface)
+                .filter(|link| {
+                    matches!(&link.kind, VirtLinkKind::Tun(tun) if tun.flags().contains(type_flag))
+                })
+                .ok_or_else(|| {
+                    Error::with_message(
+                        Errno::EINVAL,
+                        "the interface is not a TUN/TAP device of the type",
+                    )
+                })?;
+            let tun = link.tun();
+
+            let old_flags = tun.flags();
+            if old_flags.contains(TunFlags::MULTI_QUEUE) != flags.contains(TunFlags::MULTI_QUEUE) {
+                return_errno_with_message!(Errno::EINVAL, "the multiqueue flag does not match");
+            }
+
+            tun.add_queue(self)?;
+            tun.set_flags((old_flags - TunFlags::FEATURES) | features);
+            link.clone()
+        } else {
+            let template = if type_flag == TunFlags::TUN {
+                "tun%d"
+            } else {
+                "tap%d"
+            };
+            let name = resolve_name(net_ns, name, template, &[])?;
+
+            let link = VirtLink::new(VirtLinkKind::Tun(Tun::new(type_flag | features)));
+            link.tun().add_queue(self).unwrap();
+            link.register(&mut links, name, net_ns);
+            link
+        };
+
+        let name = *link.iface().unwrap().name();
+        self.is_enabled.store(true, Ordering::Relaxed);
+        *queue_link = Some(link);
+
+        Ok(name)
+    }
+
+    /// Detaches the queue from its device because the file is closed.
+    ///
+    /// The device is deleted if this is its last queue and it is not persistent.
+    pub(crate) fn detach(self: &Arc<Self>) {
+        let unregistered = {
+            let mut links = VIRT_LINKS.lock();
+            let Some(link) = self.link.lock().take() else {
+                return;
+            };
+            self.is_enabled.store(false, Ordering::Relaxed);
+
+            let tun = link.tun();
+            if tun.remove_queue(self) || tun.flags().contains(TunFlags::PERSIST) {
+                return;
+            }
+            link.unregister(&mut links)
+        };
+
+        for link in unregistered {
+            link.detach_from_ns();
+        }
+    }
+
+    /// Enables or disables the queue.
+    pub(crate) fn set_enabled(&self, is_enabled: bool) -> Result<()> {
+        let _links = VIRT_LINKS.lock();
+
+        let Some(link) = self.link.lock().clone() else {
+            return_errno_with_message!(Errno::EINVAL, "the file is not attached to a device");
+        };
+        if !link.tun().flags().contains(TunFlags::MULTI_QUEUE) {
+            return_errno_with_message!(Errno::EINVAL, "the device is not a multiqueue device");
+        }
+        if self.is_enabled() == is_enabled {
+            return_errno_with_message!(Errno::EINVAL, "the queue is already enabled or disabled");
+        }
+
+        self.is_enabled.store(is_enabled, Ordering::Relaxed);
+        if is_enabled {
+            self.pollee.notify(IoEvents::OUT);
+        } else {
+            self.packets.lock().clear();
+            self.pollee.notify(IoEvents::ERR);
+        }
+
+        Ok(())
+    }
+
+    /// Sets whether the device is kept after all its files are closed.
+    pub(crate) fn set_persist(&self, is_persistent: bool) -> Result<()> {
+        let _links = VIRT_LINKS.lock();
+
+        let link = self.enabled_link()?;
+        let tun = link.tun();
+        let mut flags = tun.flags();
+        flags.set(TunFlags::PERSIST, is_persistent);
+        tun.set_flags(flags);
+
+        Ok(())
+    }
+
+    /// Returns the name and the flags of the device.
+    pub(crate) fn info(&self) -> Result<(InterfaceName, TunFlags)> {
+        let link = self.enabled_link()?;
+        let Some(iface) = link.iface() else {
+            return_errno_with_message!(Errno::EBADFD, "the device has been deleted");
+        };
+        Ok((*iface.name(), link.tun().flags()))
+    }
+
+    /// Returns the flags of the device.
+    pub(crate) fn flags(&self) -> Result<TunFlags> {
+        Ok(self.enabled_link()?.tun().flags())
+    }
+
+    /// Takes a packet that is transmitted by the interface.
+    ///
+    /// This method returns [`Errno::EAGAIN`] if there are no packets.
+    pub(crate) fn try_recv(&self) -> Result<Vec<u8>> {
+        self.enabled_link()?;
+
+        let mut packets = self.packets.lock();
+        let Some(packet) = packets.pop_front() else {
+            return_errno_with_message!(Errno::EAGAIN, "no packets are available");
+        };
+        if packets.is_empty() {
+            self.pollee.invalidate();
+        }
+
+        Ok(packet)
+    }
+
+    /// Injects a packet to be received by the interface.
+    ///
+    /// Like a physical device, the packet is silently dropped if the receive queue is full.
+    pub(crate) fn send(&self, packet: Vec<u8>) -> Result<()> {
+        self.enabled_link()?.enqueue(packet);
+        Ok(())
+    }
+
+    pub(crate) fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        if self.enabled_link().is_err() {
+            return IoEvents::ERR;
+        }
+
+        let mut events = IoEvents::OUT;
+        if !self.packets.lock().is_empty() {
+            events |= IoEvents::IN;
+        }
+        events
+    }
+
+    fn is_enabled(&self) -> bool {
+        self.is_enabled.load(Ordering::Relaxed)
+    }
+
+    fn enabled_link(&self) -> Result<Arc<VirtLink>> {
+        match self.link.lock().as_ref() {
+            Some(link) if self.is_enabled() => Ok(link.clone()),
+            _ => return_errno_with_message!(
+                Errno::EBADFD,
+                "the file is not attached to an enabled queue"
+            ),
+        }
+    }
+
+    fn push(&self, packet: Vec<u8>) {
+        {
+            let mut packets = self.packets.lock();
+            if packets.len() >= DEFAULT_TX_QUEUE_LEN as usize {
+                return;
+            }
+            packets.push_back(packet);
+        }
+
+        self.pollee.notify(IoEvents::IN);
+    }
+}
+
+impl VirtLink {
+    fn tun(&self) -> &Tun {
+        let VirtLinkKind::Tun(tun) = &self.kind else {
+            unreachable!("the link is not a TUN/TAP device");
+        };
+        tun
+    }
+}
@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::iface::InterfaceName;
+use aster_softirq::BottomHalfDisabled;
+
+use super::{VIRT_LINKS, VirtLink, VirtLinkKind, resolve_name};
+use crate::{
+    net::{iface::Iface, net_ns::NetNamespace},
+    prelude::*,
+};
+
+/// A virtual Ethernet link, which is always created in pairs.
+///
+/// Frames transmitted by one link of the pair are received by the other link, i.e., the peer.
+pub(super) struct Veth {
+    peer: SpinLock<Weak<VirtLink>, BottomHalfDisabled>,
+}
+
+impl Veth {
+    fn new() -> Self {
+        Self {
+            peer: SpinLock::new(Weak::new()),
+        }
+    }
+
+    pub(super) fn peer(&self) -> Option<Arc<VirtLink>> {
+        self.peer.lock().upgrade()
+    }
+
+    /// Breaks the pair and returns the peer.
+    pub(super) fn take_peer(&self) -> Option<Arc<VirtLink>> {
+        let peer = core::mem::take(&mut *self.peer.lock()).upgrade()?;
+        if let VirtLinkKind::Veth(peer_veth) = &peer.kind {
+            *peer_veth.peer.lock() = Weak::new();
+        }
+        Some(peer)
+    }
+}
+
+/// Creates a veth pair and returns the interface of the first link.
+///
+/// The interface of the peer is created in `peer_ns`, which may differ from `net_ns`.
+pub(crate) fn new_veth_pair(
+    net_ns: &Arc<NetNamespace>,
+    name: Option<InterfaceName>,
+    peer_ns: &Arc<NetNamespace>,
+    peer_name: Option<InterfaceName>,
+) -> Result<Arc<Iface>> {
+    let mut links = VIRT_LINKS.lock();
+
+    // Like Linux, the peer is registered first, so it takes the smaller number if both names are
+    // templates.
+    let peer_name = resolve_name(peer_ns, peer_name, "veth%d", &[])?;
+    let reserved = if Arc::ptr_eq(net_ns, peer_ns) {
+        core::slice::from_ref(&peer_name)
+    } else {
+        &[]
+    };
+    let name = resolve_name(net_ns, name, "veth%d", reserved)?;
+
+    let link = VirtLink::new(VirtLinkKind::Veth(Veth::new()));
+    let peer = VirtLink::new(VirtLinkKind::Veth(Veth::new()));
+    for (this, that) in [(&link, &peer), (&peer, &link)] {
+        let VirtLinkKind::Veth(veth) = &this.kind else {
+            unreachable!();
+        };
+        *veth.peer.lock() = Arc::downgrade(that);
+    }
+
+    peer.register(&mut links, peer_name, peer_ns);
+    let iface = link.register(&mut links, name, net_ns);
+
+    Ok(iface)
+}
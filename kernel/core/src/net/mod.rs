@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: MPL-2.0
 
 pub(crate) mod iface;
+pub(crate) mod net_ns;
+pub(crate) mod netfilter;
+pub(crate) mod route;
 pub(crate) mod socket;
 pub(crate) mod uts_ns;
 
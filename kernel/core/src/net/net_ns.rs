@@ -0,0 +1,296 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use aster_bigtcp::wire::IpAddress;
+use aster_softirq::BottomHalfDisabled;
+use ostd::{sync::PreemptDisabled, task::Task};
+use spin::Once;
+
+use crate::{
+    fs::pseudofs::{NsCommonOps, NsType, StashedDentry},
+    net::{
+        iface::{self, Iface},
+        netfilter::{self, Netfilter},
+        route::{Route, RouteTable},
+        socket::unix::AbstractNameTable,
+    },
+    prelude::*,
+    process::{Gid, UserNamespace, credentials::capabilities::CapSet, posix_thread::PosixThread},
+    security::lsm::hooks as lsm_hooks,
+};
+
+/// The network namespace.
+///
+/// A network namespace owns a set of network interfaces, with the loopback interface always
+/// being the first one. Since the port tables of `aster-bigtcp` are maintained per interface,
+/// sockets in different network namespaces never contend for the same ports. The namespace also
+/// owns the routing table, the netfilter ruleset, the abstract names of UNIX domain sockets, and
+/// the per-namespace network sysctls.
+pub(crate) struct NetNamespace {
+    // Packet sockets look up the ifaces while the ifaces are polled, which may happen in the
+    // softirq context.
+    ifaces: RwLock<Vec<Arc<Iface>>, BottomHalfDisabled>,
+    /// The last allocated interface index, after which the next index is searched for.
+    last_iface_index: AtomicU32,
+    route_table: SpinLock<RouteTable>,
+    netfilter: Arc<Netfilter>,
+    unix_abstract_names: Arc<AbstractNameTable>,
+    /// The inclusive range of the groups that can create ICMP echo sockets.
+    ping_group_range: SpinLock<(Gid, Gid)>,
+    owner: Arc<UserNamespace>,
+    stashed_dentry: StashedDentry,
+}
+
+impl NetNamespace {
+    /// Returns a reference to the singleton initial network namespace.
+    pub(crate) fn get_init_singleton() -> &'static Arc<NetNamespace> {
+        static INIT: Once<Arc<NetNamespace>> = Once::new();
+
+        INIT.call_once(|| {
+            let owner = UserNamespace::get_init_singleton().clone();
+            let ifaces = iface::new_init_ifaces();
+            let routes = iface::new_init_routes(&ifaces);
+            Self::new(ifaces, routes, owner)
+        })
+    }
+
+    fn new(ifaces: Vec<Arc<Iface>>, routes: Vec<Route>, owner: Arc<UserNamespace>) -> Arc<Self> {
+        let mut route_table = RouteTable::new();
+        for route in routes {
+            route_table.insert(route).unwrap();
+        }
+
+        let net_ns = Arc::new(Self {
+            ifaces: RwLock::new(Vec::with_capacity(ifaces.len())),
+            last_iface_index: AtomicU32::new(0),
+            route_table: SpinLock::new(route_table),
+            netfilter: Netfilter::new(),
+            unix_abstract_names: AbstractNameTable::new(),
+            ping_group_range: SpinLock::new(DEFAULT_PING_GROUP_RANGE),
+            owner,
+            stashed_dentry: StashedDentry::new(),
+        });
+        for iface in ifaces {
+            net_ns.add_iface(iface);
+        }
+
+        net_ns
+    }
+
+    /// Creates a new network namespace.
+    ///
+    /// Like Linux, the new network namespace does not inherit any interfaces from `self`. It
+    /// only contains a new loopback interface.
+    pub(crate) fn new_clone(
+        &self,
+        owner: Arc<UserNamespace>,
+        posix_thread: &PosixThread,
+    ) -> Result<Arc<Self>> {
+        lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+            owner.as_ref(),
+            posix_thread,
+            CapSet::SYS_ADMIN,
+        ))?;
+        Ok(Self::new(vec![iface::new_ns_loopback()], Vec::new(), owner))
+    }
+
+    /// Returns the network namespace of the current thread.
+    pub(crate) fn current() -> Arc<NetNamespace> {
+        let current_task = Task::current().unwrap();
+        let thread_local = current_task.as_thread_local().unwrap();
+        let ns_proxy = thread_local.borrow_ns_proxy();
+        ns_proxy.unwrap().net_ns().clone()
+    }
+
+    /// Returns the owner user namespace of the network namespace.
+    pub(crate) fn owner(&self) -> &Arc<UserNamespace> {
+        &self.owner
+    }
+
+    /// Returns all the interfaces in the network namespace.
+    pub(crate) fn ifaces(&self) -> Vec<Arc<Iface>> {
+        self.ifaces.read().clone()
+    }
+
+    /// Returns the first interface that satisfies the predicate.
+    pub(crate) fn find_iface(
+        &self,
+        mut predicate: impl FnMut(&Iface) -> bool,
+    ) -> Option<Arc<Iface>> {
+        self.ifaces
+            .read()
+            .iter()
+            .find(|iface| predicate(iface.as_ref()))
+            .cloned()
+    }
+
+    /// Adds an interface to the network namespace.
+    ///
+    /// The interface keeps its index if the index is not used in the namespace (e.g., when the
+    /// interface is moved from another namespace). Otherwise, a new index is allocated.
+    ///
+    /// The caller must ensure that the name of the interface is unique in the namespace.
+    pub(super) fn add_iface(self: &Arc<Self>, iface: Arc<Iface>) {
+        let mut ifaces = self.ifaces.write();
+        debug_assert!(ifaces.iter().all(|other| other.name() != iface.name()));
+
+        let index = iface.index();
+        if index == 0 || ifaces.iter().any(|other| other.index() == index) {
+            iface.set_index(self.alloc_iface_index(&ifaces));
+        }
+
+        netfilter::register_iface(self, &iface);
+        ifaces.push(iface);
+    }
+
+    /// Allocates an unused interface index.
+    ///
+    /// Like Linux, the indexes are allocated cyclically, so that the index of a removed interface
+    /// is not reused soon.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/net/core/dev.c>
+    fn alloc_iface_index(&self, ifaces: &[Arc<Iface>]) -> u32 {
+        /// The maximum interface index, which must fit in the `int` type of C.
+        const MAX_IFACE_INDEX: u32 = i32::MAX as u32;
+
+        let mut index = self.last_iface_index.load(Ordering::Relaxed);
+        loop {
+            index = if index >= MAX_IFACE_INDEX {
+                1
+            } else {
+                index + 1
+            };
+            if ifaces.iter().all(|iface| iface.index() != index) {
+                break;
+            }
+        }
+        self.last_iface_index.store(index, Ordering::Relaxed);
+
+        index
+    }
+
+    /// Removes the interface with the ID from the network namespace.
+    ///
+    /// The routes via the interface are also removed from the routing table.
+    pub(super) fn remove_iface(&self, id: u32) -> Option<Arc<Iface>> {
+        let iface = {
+            let mut ifaces = self.ifaces.write();
+            let pos = ifaces.iter().position(|iface| iface.id() == id)?;
+            ifaces.remove(pos)
+        };
+        netfilter::unregister_iface(id);
+        self.route_table.lock().remove_iface_routes(&iface);
+        Some(iface)
+    }
+
+    /// Returns the routing table of the network namespace.
+    pub(crate) fn route_table(&self) -> SpinLockGuard<'_, RouteTable, PreemptDisabled> {
+        self.route_table.lock()
+    }
+
+    /// Returns the interface to reach remote addresses that do not belong to any interface.
+    ///
+    /// The interface is chosen according to the routing table. If no route matches, the first
+    /// non-loopback interface with an address of the same family is returned, or the loopback
+    /// interface if there is no such interface.
+    //
+    // FIXME: Linux fails with `ENETUNREACH` if no route matches. We keep the fallback because
+    // the IPv6 default routers learned from router advertisements are not in the routing table.
+    pub(crate) fn route_iface(&self, remote_addr: &IpAddress) -> Arc<Iface> {
+        let route_table = self.route_table.lock();
+        if let Some(iface) = route_table.lookup(remote_addr, &self.ifaces.read()) {
+            return iface;
+        }
+        drop(route_table);
+
+        self.default_iface(matches!(remote_addr, IpAddress::Ipv6(_)))
+    }
+
+    /// Returns the first non-loopback interface with an address of the family.
+    ///
+    /// `needs_ipv6` specifies whether the interface must have an IPv6 address instead of an IPv4
+    /// address. The loopback interface is returned if no other interface has such an address.
+    fn default_iface(&self, needs_ipv6: bool) -> Arc<Iface> {
+        let ifaces = self.ifaces.read();
+        ifaces
+            .iter()
+            .skip(1)
+            .find(|iface| {
+                if needs_ipv6 {
+                    iface.ipv6_cidr().is_some()
+                } else {
+                    iface.ipv4_cidr().is_some()
+                }
+            })
+            .unwrap_or(&ifaces[0])
+            .clone()
+    }
+
+    /// Returns the netfilter state of the network namespace.
+    pub(crate) fn netfilter(&self) -> &Arc<Netfilter> {
+        &self.netfilter
+    }
+
+    /// Returns the table of the abstract names of UNIX domain sockets.
+    pub(crate) fn unix_abstract_names(&self) -> &Arc<AbstractNameTable> {
+        &self.unix_abstract_names
+    }
+
+    /// Returns the inclusive range of the groups that can create ICMP echo sockets.
+    ///
+    /// This is `net.ipv4.ping_group_range` in the sysctl interface.
+    pub(crate) fn ping_group_range(&self) -> (Gid, Gid) {
+        *self.ping_group_range.lock()
+    }
+
+    /// Sets the inclusive range of the groups that can create ICMP echo sockets.
+    ///
+    /// Like Linux, if `high` is less than `low`, the range is reset to the default empty range.
+    pub(crate) fn set_ping_group_range(&self, low: Gid, high: Gid) {
+        *self.ping_group_range.lock() = if high < low {
+            DEFAULT_PING_GROUP_RANGE
+        } else {
+            (low, high)
+        };
+    }
+}
+
+/// The default range of `net.ipv4.ping_group_range`, which contains no groups.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/net/ipv4/af_inet.c>
+const DEFAULT_PING_GROUP_RANGE: (Gid, Gid) = (Gid::new(1), Gid::new(0));
+
+impl Drop for NetNamespace {
+    fn drop(&mut self) {
+        let ifaces = self.ifaces.get_mut();
+        for iface in ifaces.iter() {
+            netfilter::unregister_iface(iface.id());
+        }
+        iface::delete_ns_links(ifaces);
+
+        // The interfaces are no longer reachable, so their polling threads can exit.
+        for iface in ifaces.iter() {
+            iface.sched_poll().stop();
+        }
+    }
+}
+
+impl NsCommonOps for NetNamespace {
+    const TYPE: NsType = NsType::Net;
+
+    fn owner_user_ns(&self) -> Option<&Arc<UserNamespace>> {
+        Some(&self.owner)
+    }
+
+    fn parent(&self) -> Result<&Arc<Self>> {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "a network namespace does not have a parent namespace"
+        );
+    }
+
+    fn stashed_dentry(&self) -> &StashedDentry {
+        &self.stashed_dentry
+    }
+}
@@ -0,0 +1,401 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Connection tracking.
+//!
+//! Each connection is identified by three tuples:
+//!  - The _original_ tuple is the tuple of the first packet when it reaches the network stack.
+//!  - The _mapped_ tuple is the original tuple after destination NAT.
+//!  - The _reply_ tuple is the tuple of the reply packets when they reach the network stack,
+//!    which is the inverse of the mapped tuple after source NAT.
+//!
+//! Packets in the original direction are translated from the original tuple to the inverse of the
+//! reply tuple, and packets in the reply direction are translated from the reply tuple to the
+//! inverse of the original tuple. Between the hook where a packet enters the network stack
+//! (`PreRouting` or `LocalOut`) and the hook where the packet leaves (`LocalIn` or
+//! `PostRouting`), the packet has the mapped tuple or its inverse.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/netfilter/nf_conntrack_core.c>
+
+use core::net::IpAddr;
+
+use super::packet::{IPPROTO_ICMP, IPPROTO_ICMPV6, IPPROTO_TCP, IPPROTO_UDP, Tuple};
+use crate::prelude::*;
+
+/// The maximum number of tracked connections (`nf_conntrack_max` in Linux).
+const MAX_CONNS: usize = 65536;
+/// The interval between garbage collections of expired connections, in milliseconds.
+const GC_INTERVAL_MS: u64 = 1000;
+
+const TCP_FIN: u8 = 0x01;
+const TCP_SYN: u8 = 0x02;
+const TCP_RST: u8 = 0x04;
+
+/// The direction of a packet in a connection.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum Direction {
+    Original,
+    Reply,
+}
+
+impl Direction {
+    pub(super) fn opposite(self) -> Self {
+        match self {
+            Self::Original => Self::Reply,
+            Self::Reply => Self::Original,
+        }
+    }
+}
+
+/// Where a packet is in the network stack, which determines the tuple that it has.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum TuplePoint {
+    /// The packet has just entered the network stack, so it has the original or the reply tuple.
+    Entry,
+    /// The packet has been translated at the entry, so it has the mapped tuple or its inverse.
+    Mapped,
+}
+
+/// A tracked connection.
+#[derive(Debug)]
+pub(super) struct Conn {
+    orig: Tuple,
+    mapped: Tuple,
+    reply: Tuple,
+    is_confirmed: bool,
+    has_seen_reply: bool,
+    has_src_nat: bool,
+    has_dst_nat: bool,
+    tcp_state: TcpState,
+    expires_at: u64,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+enum TcpState {
+    Open,
+    Closing,
+    Reset,
+}
+
+impl Conn {
+    pub(super) fn orig(&self) -> &Tuple {
+        &self.orig
+    }
+
+    pub(super) fn mapped(&self) -> &Tuple {
+        &self.mapped
+    }
+
+    pub(super) fn reply(&self) -> &Tuple {
+        &self.reply
+    }
+
+    pub(super) fn is_confirmed(&self) -> bool {
+        self.is_confirmed
+    }
+
+    pub(super) fn has_seen_reply(&self) -> bool {
+        self.has_seen_reply
+    }
+
+    pub(super) fn has_src_nat(&self) -> bool {
+        self.has_src_nat
+    }
+
+    pub(super) fn has_dst_nat(&self) -> bool {
+        self.has_dst_nat
+    }
+
+    /// Returns the `IPS_*` status bits in Linux.
+    pub(super) fn status(&self) -> u32 {
+        const IPS_SEEN_REPLY: u32 = 1 << 1;
+        const IPS_ASSURED: u32 = 1 << 2;
+        const IPS_CONFIRMED: u32 = 1 << 3;
+        const IPS_SRC_NAT: u32 = 1 << 4;
+        const IPS_DST_NAT: u32 = 1 << 5;
+
+        let mut status = 0;
+        if self.has_seen_reply {
+            status |= IPS_SEEN_REPLY | IPS_ASSURED;
+        }
+        if self.is_confirmed {
+            status |= IPS_CONFIRMED;
+        }
+        if self.has_src_nat {
+            status |= IPS_SRC_NAT;
+        }
+        if self.has_dst_nat {
+            status |= IPS_DST_NAT;
+        }
+        status
+    }
+
+    /// Returns the timeout after the last packet, in milliseconds.
+    ///
+    /// Reference: <https://docs.kernel.org/networking/nf_conntrack-sysctl.html>
+    fn timeout_ms(&self) -> u64 {
+        const SECOND: u64 = 1000;
+
+        let seconds = match self.orig.proto {
+            IPPROTO_TCP => match self.tcp_state {
+                TcpState::Reset => 10,
+                TcpState::Closing => 120,
+                TcpState::Open if self.has_seen_reply => 5 * 24 * 3600,
+                TcpState::Open => 120,
+            },
+            IPPROTO_UDP if self.has_seen_reply => 120,
+            IPPROTO_UDP => 30,
+            IPPROTO_ICMP | IPPROTO_ICMPV6 => 30,
+            _ => 600,
+        };
+        seconds * SECOND
+    }
+}
+
+/// The connection tracking table.
+pub(super) struct Conntrack {
+    conns: BTreeMap<u64, Conn>,
+    /// The original and the reply tuples of the connections.
+    entry_tuples: BTreeMap<Tuple, (u64, Direction)>,
+    /// The mapped tuples and their inverses of the connections.
+    mapped_tuples: BTreeMap<Tuple, (u64, Direction)>,
+    next_id: u64,
+    last_gc: u64,
+}
+
+impl Conntrack {
+    pub(super) const fn new() -> Self {
+        Self {
+            conns: BTreeMap::new(),
+            entry_tuples: BTreeMap::new(),
+            mapped_tuples: BTreeMap::new(),
+            next_id: 0,
+            last_gc: 0,
+        }
+    }
+
+    pub(super) fn get(&self, id: u64) -> &Conn {
+        &self.conns[&id]
+    }
+
+    /// Looks up the connection of a packet with the tuple at the point.
+    pub(super) fn lookup(
+        &mut self,
+        tuple: &Tuple,
+        point: TuplePoint,
+        now: u64,
+    ) -> Option<(u64, Direction)> {
+        let tuples = match point {
+            TuplePoint::Entry => &self.entry_tuples,
+            TuplePoint::Mapped => &self.mapped_tuples,
+        };
+        let (id, dir) = *tuples.get(tuple)?;
+
+        if self.conns[&id].expires_at <= now {
+            self.remove(id);
+            return None;
+        }
+        Some((id, dir))
+    }
+
+    /// Creates an unconfirmed connection for the first packet with the tuple.
+    ///
+    /// `None` is returned if the table is full.
+    pub(super) fn create(&mut self, tuple: &Tuple, now: u64) -> Option<u64> {
+        if now >= self.last_gc + GC_INTERVAL_MS {
+            self.gc(now);
+        }
+        if self.conns.len() >= MAX_CONNS {
+            return None;
+        }
+
+        // Clear the stale entries if an unconfirmed connection is being restarted.
+        if let Some(&(id, _)) = self.entry_tuples.get(tuple) {
+            self.remove(id);
+        }
+        if let Some(&(id, _)) = self.mapped_tuples.get(tuple) {
+            self.remove(id);
+        }
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let mut conn = Conn {
+            orig: *tuple,
+            mapped: *tuple,
+            reply: tuple.inverse(),
+            is_confirmed: false,
+            has_seen_reply: false,
+            has_src_nat: false,
+            has_dst_nat: false,
+            tcp_state: TcpState::Open,
+            expires_at: 0,
+        };
+        conn.expires_at = now + conn.timeout_ms();
+        self.conns.insert(id, conn);
+        self.entry_tuples.insert(*tuple, (id, Direction::Original));
+        self.mapped_tuples.insert(*tuple, (id, Direction::Original));
+
+        Some(id)
+    }
+
+    /// Removes a connection.
+    pub(super) fn remove(&mut self, id: u64) {
+        let Some(conn) = self.conns.remove(&id) else {
+            return;
+        };
+
+        let mut remove_if_owned = |tuples: &mut BTreeMap<Tuple, (u64, Direction)>, tuple| {
+            if tuples.get(&tuple).is_some_and(|(owner, _)| *owner == id) {
+                tuples.remove(&tuple);
+            }
+        };
+        remove_if_owned(&mut self.entry_tuples, conn.orig);
+        remove_if_owned(&mut self.entry_tuples, conn.reply);
+        remove_if_owned(&mut self.mapped_tuples, conn.mapped);
+        remove_if_owned(&mut self.mapped_tuples, conn.mapped.inverse());
+    }
+
+    /// Updates the state of a connection when a packet passes through it.
+    pub(super) fn on_packet(&mut self, id: u64, dir: Direction, tcp_flags: Option<u8>, now: u64) {
+        let conn = self.conns.get_mut(&id).unwrap();
+
+        if dir == Direction::Reply {
+            conn.has_seen_reply = true;
+        }
+        if let Some(flags) = tcp_flags {
+            if flags & TCP_RST != 0 {
+                conn.tcp_state = TcpState::Reset;
+            } else if flags & TCP_FIN != 0 {
+                conn.tcp_state = TcpState::Closing;
+            } else if flags & TCP_SYN != 0 && dir == Direction::Original {
+                // A new connection may reuse the tuple of a closed connection.
+                conn.tcp_state = TcpState::Open;
+            }
+        }
+
+        conn.expires_at = now + conn.timeout_ms();
+    }
+
+    /// Sets up the destination NAT of an unconfirmed connection.
+    pub(super) fn set_dst_nat(&mut self, id: u64, addr: IpAddr, port: u16) {
+        let conn = self.conns.get_mut(&id).unwrap();
+        debug_assert!(!conn.is_confirmed);
+
+        if self
+            .mapped_tuples
+            .get(&conn.mapped)
+            .is_some_and(|(owner, _)| *owner == id)
+        {
+            self.mapped_tuples.remove(&conn.mapped);
+        }
+        conn.mapped.dst = addr;
+        conn.mapped.dst_port = port;
+        conn.has_dst_nat = true;
+        self.mapped_tuples
+            .insert(conn.mapped, (id, Direction::Original));
+    }
+
+    /// Sets up the source NAT of an unconfirmed connection.
+    ///
+    /// The port is chosen from the inclusive range, preferring the original source port, so that
+    /// the reply tuple is unique. If `ports` is `None`, the original source port is kept if
+    /// possible, or else an unprivileged port is chosen. This method returns `false` if no port is
+    /// available.
+    pub(super) fn set_src_nat(&mut self, id: u64, addr: IpAddr, ports: Option<(u16, u16)>) -> bool {
+        let conn = &self.conns[&id];
+        debug_assert!(!conn.is_confirmed);
+
+        let mapped = conn.mapped;
+        let reply_with_port = |port: u16| {
+            let mut reply = mapped.inverse();
+            reply.dst = addr;
+            match mapped.proto {
+                // The identifier of an ICMP echo reply is at the both ports.
+                IPPROTO_ICMP | IPPROTO_ICMPV6 => {
+                    reply.src_port = port;
+                    reply.dst_port = port;
+                }
+                _ => reply.dst_port = port,
+            }
+            reply
+        };
+        let is_available = |reply: &Tuple| {
+            self.entry_tuples
+                .get(reply)
+                .is_none_or(|(owner, _)| *owner == id)
+        };
+
+        let has_ports = matches!(
+            mapped.proto,
+            IPPROTO_TCP | IPPROTO_UDP | IPPROTO_ICMP | IPPROTO_ICMPV6
+        );
+        let (min, max) = match ports {
+            Some(range) if has_ports => range,
+            _ if has_ports && mapped.proto != IPPROTO_ICMP && mapped.proto != IPPROTO_ICMPV6 => {
+                (1024, u16::MAX)
+            }
+            _ if has_ports => (0, u16::MAX),
+            _ => (mapped.src_port, mapped.src_port),
+        };
+
+        // Like Linux, the original port is kept if possible.
+        let preferred = mapped.src_port;
+        let can_keep = ports.is_none() || (min..=max).contains(&preferred);
+        let reply = if can_keep && is_available(&reply_with_port(preferred)) {
+            reply_with_port(preferred)
+        } else {
+            let len = (max - min) as u32 + 1;
+            let start = (preferred as u32) % len;
+            let Some(reply) = (0..len)
+                .map(|i| reply_with_port(min + ((start + i) % len) as u16))
+                .find(is_available)
+            else {
+                return false;
+            };
+            reply
+        };
+
+        let conn = self.conns.get_mut(&id).unwrap();
+        conn.reply = reply;
+        conn.has_src_nat = true;
+        true
+    }
+
+    /// Confirms a connection when its first packet leaves the network stack.
+    ///
+    /// This method returns `false` if the reply tuple clashes with another connection, in which
+    /// case the connection is removed and the packet should be dropped.
+    pub(super) fn confirm(&mut self, id: u64) -> bool {
+        let conn = &self.conns[&id];
+        if conn.is_confirmed {
+            return true;
+        }
+
+        let reply = conn.reply;
+        let mapped_inv = conn.mapped.inverse();
+        if self.entry_tuples.contains_key(&reply) || self.mapped_tuples.contains_key(&mapped_inv) {
+            self.remove(id);
+            return false;
+        }
+
+        self.entry_tuples.insert(reply, (id, Direction::Reply));
+        self.mapped_tuples
+            .insert(mapped_inv, (id, Direction::Reply));
+        self.conns.get_mut(&id).unwrap().is_confirmed = true;
+        true
+    }
+
+    /// Removes the expired connections.
+    fn gc(&mut self, now: u64) {
+        let expired: Vec<u64> = self
+            .conns
+            .iter()
+            .filter(|(_, conn)| conn.expires_at <= now)
+            .map(|(id, _)| *id)
+            .collect();
+        for id in expired {
+            self.remove(id);
+        }
+        self.last_gc = now;
+    }
+}
@@ -0,0 +1,700 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The expressions of nftables rules.
+//!
+//! Rules are programs of a register-based virtual machine. Each expression reads its operands from
+//! the packet or the registers, and writes its results to the registers. The verdict register
+//! determines whether the next expression is evaluated.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/netfilter/nf_tables_core.c>
+
+use core::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+use aster_bigtcp::iface::{FilterHook, RejectWith};
+
+use super::{
+    IfaceMeta, NFPROTO_INET, NFPROTO_IPV4, NFPROTO_IPV6,
+    conntrack::Direction,
+    packet::{PacketInfo, Tuple},
+    ruleset::{SetData, Table, Verdict},
+};
+use crate::prelude::*;
+
+/// The size of the data registers, in bytes.
+const REGS_LEN: usize = 80;
+/// The offset of the first data register, since the first 16 bytes are for the verdict.
+const DATA_REGS_START: usize = 16;
+
+/// The registers of the virtual machine.
+pub(super) struct Regs {
+    pub(super) verdict: Verdict,
+    data: [u8; REGS_LEN],
+}
+
+impl Regs {
+    pub(super) fn new() -> Self {
+        Self {
+            verdict: Verdict::Continue,
+            data: [0; REGS_LEN],
+        }
+    }
+
+    fn get(&self, reg: u32, len: usize) -> &[u8] {
+        let offset = reg_offset(reg, len).unwrap();
+        &self.data[offset..offset + len]
+    }
+
+    /// Stores the data into the register.
+    ///
+    /// Like Linux, if the length is not a multiple of 4, the last 32-bit word is zero-padded.
+    fn set(&mut self, reg: u32, data: &[u8]) {
+        let offset = reg_offset(reg, data.len()).unwrap();
+        let padded_len = data.len().next_multiple_of(4);
+        self.data[offset..offset + padded_len].fill(0);
+        self.data[offset..offset + data.len()].copy_from_slice(data);
+    }
+}
+
+/// `NFT_REG_VERDICT` in Linux.
+pub(crate) const NFT_REG_VERDICT: u32 = 0;
+
+/// Returns the byte offset of a data register, if the data of the length fits in the registers.
+///
+/// The registers are numbered as `NFT_REG_1` to `NFT_REG_4` (16 bytes each) or as
+/// `NFT_REG32_00` to `NFT_REG32_15` (4 bytes each), which alias the same storage.
+pub(crate) fn reg_offset(reg: u32, len: usize) -> Option<usize> {
+    let offset = match reg {
+        1..=4 => reg as usize * 16,
+        8..=23 => DATA_REGS_START + (reg as usize - 8) * 4,
+        _ => return None,
+    };
+    if len == 0 || offset + len > REGS_LEN {
+        return None;
+    }
+    Some(offset)
+}
+
+/// The packet and its metadata that are visible to the expressions.
+pub(super) struct PacketContext<'a> {
+    pub(super) packet: &'a [u8],
+    pub(super) info: &'a PacketInfo,
+    pub(super) iif: Option<&'a IfaceMeta>,
+    pub(super) oif: Option<&'a IfaceMeta>,
+    pub(super) ct: CtInfo,
+}
+
+/// The connection tracking information of a packet.
+#[derive(Clone, Copy, Debug)]
+pub(super) enum CtInfo {
+    Tracked {
+        dir: Direction,
+        is_related: bool,
+        is_new: bool,
+        status: u32,
+        orig: Tuple,
+        reply: Tuple,
+    },
+    Untracked,
+    Invalid,
+}
+
+impl CtInfo {
+    /// Returns the `NF_CT_STATE_*` bits in Linux.
+    fn state(&self) -> u32 {
+        const STATE_INVALID: u32 = 1 << 0;
+        const STATE_ESTABLISHED: u32 = 1 << 1;
+        const STATE_RELATED: u32 = 1 << 2;
+        const STATE_NEW: u32 = 1 << 3;
+        const STATE_UNTRACKED: u32 = 1 << 6;
+
+        match self {
+            Self::Tracked {
+                is_related: true, ..
+            } => STATE_RELATED,
+            Self::Tracked { is_new: true, .. } => STATE_NEW,
+            Self::Tracked { .. } => STATE_ESTABLISHED,
+            Self::Untracked => STATE_UNTRACKED,
+            Self::Invalid => STATE_INVALID,
+        }
+    }
+}
+
+/// The side effects of a rule that are carried out by the caller.
+#[derive(Clone, Copy, Debug)]
+pub(super) enum Action {
+    Nat(NatAction),
+    Reject(RejectWith),
+}
+
+/// The network address translation requested by a rule.
+#[derive(Clone, Copy, Debug)]
+pub(super) enum NatAction {
+    /// Translates the source address and optionally the source port.
+    Src {
+        addr: IpAddr,
+        ports: Option<(u16, u16)>,
+    },
+    /// Translates the destination address and optionally the destination port.
+    Dst { addr: IpAddr, port: Option<u16> },
+    /// Translates the source address to the address of the output iface.
+    Masquerade { ports: Option<(u16, u16)> },
+    /// Translates the destination address to the address of the input iface.
+    Redirect { port: Option<u16> },
+}
+
+/// An expression of an nftables rule.
+#[derive(Clone, Debug)]
+pub(crate) enum Expr {
+    /// Loads data from the packet headers.
+    Payload {
+        base: PayloadBase,
+        offset: u32,
+        len: u32,
+        dreg: u32,
+    },
+    /// Compares a register with the data, and breaks if the comparison fails.
+    Cmp { sreg: u32, op: CmpOp, data: Vec<u8> },
+    /// Loads the packet metadata.
+    Meta { key: MetaKey, dreg: u32 },
+    /// Loads the data or sets the verdict.
+    Immediate { dreg: u32, data: SetData },
+    /// Computes `(sreg & mask) ^ xor`.
+    Bitwise {
+        sreg: u32,
+        dreg: u32,
+        mask: Vec<u8>,
+        xor: Vec<u8>,
+    },
+    /// Loads the connection tracking information.
+    Ct {
+        key: CtKey,
+        dreg: u32,
+        dir: Option<u8>,
+    },
+    /// Translates the addresses and ports in the registers.
+    Nat {
+        type_: NatType,
+        family: u8,
+        reg_addr_min: Option<u32>,
+        reg_addr_max: Option<u32>,
+        reg_proto_min: Option<u32>,
+        reg_proto_max: Option<u32>,
+        flags: u32,
+    },
+    /// Translates the source address to the address of the output iface.
+    Masq {
+        flags: u32,
+        reg_proto_min: Option<u32>,
+        reg_proto_max: Option<u32>,
+    },
+    /// Translates the destination address to the address of the input iface.
+    Redir {
+        flags: u32,
+        reg_proto_min: Option<u32>,
+        reg_proto_max: Option<u32>,
+    },
+    /// Drops the packet and replies with an error.
+    Reject { type_: RejectType, icmp_code: u8 },
+    /// Counts the packets and bytes.
+    Counter(Arc<Counter>),
+    /// Looks up a register in a set, and breaks if the lookup fails.
+    Lookup {
+        set: String,
+        sreg: u32,
+        dreg: Option<u32>,
+        flags: u32,
+    },
+}
+
+/// `NFT_PAYLOAD_*_HEADER` in Linux.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
+pub(crate) enum PayloadBase {
+    LinkLayer = 0,
+    Network = 1,
+    Transport = 2,
+}
+
+/// `NFT_CMP_*` in Linux.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
+pub(crate) enum CmpOp {
+    Eq = 0,
+    Neq = 1,
+    Lt = 2,
+    Lte = 3,
+    Gt = 4,
+    Gte = 5,
+}
+
+/// `NFT_META_*` in Linux, which is limited to the supported keys.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
+pub(crate) enum MetaKey {
+    Len = 0,
+    Protocol = 1,
+    Mark = 3,
+    Iif = 4,
+    Oif = 5,
+    IifName = 6,
+    OifName = 7,
+    IifType = 8,
+    OifType = 9,
+    NfProto = 15,
+    L4Proto = 16,
+}
+
+/// `NFT_CT_*` in Linux, which is limited to the supported keys.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
+pub(crate) enum CtKey {
+    State = 0,
+    Direction = 1,
+    Status = 2,
+    Mark = 3,
+    L3Protocol = 7,
+    Src = 8,
+    Dst = 9,
+    Protocol = 10,
+    ProtoSrc = 11,
+    ProtoDst = 12,
+    SrcIp = 19,
+    DstIp = 20,
+    SrcIp6 = 21,
+    DstIp6 = 22,
+}
+
+impl CtKey {
+    /// Returns the length of the loaded data, which may depend on the family of the table.
+    pub(crate) fn len(&self, family: u8) -> usize {
+        match self {
+            Self::State | Self::Status | Self::Mark => 4,
+            Self::Direction | Self::L3Protocol | Self::Protocol => 1,
+            Self::ProtoSrc | Self::ProtoDst => 2,
+            Self::SrcIp | Self::DstIp => 4,
+            Self::SrcIp6 | Self::DstIp6 => 16,
+            Self::Src | Self::Dst if family == NFPROTO_IPV4 => 4,
+            Self::Src | Self::Dst => 16,
+        }
+    }
+}
+
+impl MetaKey {
+    /// Returns the length of the loaded data.
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Self::Len | Self::Mark | Self::Iif | Self::Oif => 4,
+            Self::Protocol | Self::IifType | Self::OifType => 2,
+            Self::IifName | Self::OifName => IFNAMSIZ,
+            Self::NfProto | Self::L4Proto => 1,
+        }
+    }
+}
+
+/// `NFT_NAT_*` in Linux.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
+pub(crate) enum NatType {
+    Snat = 0,
+    Dnat = 1,
+}
+
+/// `NFT_REJECT_*` in Linux.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
+pub(crate) enum RejectType {
+    IcmpUnreach = 0,
+    TcpRst = 1,
+    IcmpxUnreach = 2,
+}
+
+/// `NF_NAT_RANGE_MAP_IPS` in Linux.
+pub(crate) const NF_NAT_RANGE_MAP_IPS: u32 = 1 << 0;
+/// `NF_NAT_RANGE_PROTO_SPECIFIED` in Linux.
+pub(crate) const NF_NAT_RANGE_PROTO_SPECIFIED: u32 = 1 << 1;
+/// `NFT_LOOKUP_F_INV` in Linux.
+pub(crate) const NFT_LOOKUP_F_INV: u32 = 1 << 0;
+
+const IFNAMSIZ: usize = 16;
+
+/// The packet and byte counts of a counter expression.
+///
+/// The counts are kept when the ruleset is copied for a transaction.
+#[derive(Debug, Default)]
+pub(crate) struct Counter {
+    packets: AtomicU64,
+    bytes: AtomicU64,
+}
+
+impl Counter {
+    pub(crate) fn new(packets: u64, bytes: u64) -> Self {
+        Self {
+            packets: AtomicU64::new(packets),
+            bytes: AtomicU64::new(bytes),
+        }
+    }
+
+    pub(crate) fn packets(&self) -> u64 {
+        self.packets.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn bytes(&self) -> u64 {
+        self.bytes.load(Ordering::Relaxed)
+    }
+}
+
+impl Expr {
+    /// Evaluates the expression.
+    ///
+    /// If the expression cannot be evaluated (e.g., the loaded data is not present in the
+    /// packet), the verdict is set to [`Verdict::Break`] like Linux.
+    pub(super) fn eval(
+        &self,
+        regs: &mut Regs,
+        ctx: &PacketContext,
+        table: &Table,
+        action: &mut Option<Action>,
+    ) {
+        let is_done = match self {
+            Self::Payload {
+                base,
+                offset,
+                len,
+                dreg,
+            } => eval_payload(regs, ctx, *base, *offset as usize, *len as usize, *dreg),
+            Self::Cmp { sreg, op, data } => {
+                let ordering = regs.get(*sreg, data.len()).cmp(data.as_slice());
+                match op {
+                    CmpOp::Eq => ordering.is_eq(),
+                    CmpOp::Neq => ordering.is_ne(),
+                    CmpOp::Lt => ordering.is_lt(),
+                    CmpOp::Lte => ordering.is_le(),
+                    CmpOp::Gt => ordering.is_gt(),
+                    CmpOp::Gte => ordering.is_ge(),
+                }
+            }
+            Self::Meta { key, dreg } => eval_meta(regs, ctx, *key, *dreg),
+            Self::Immediate { dreg, data } => {
+                match data {
+                    SetData::Value(value) => regs.set(*dreg, value),
+                    SetData::Verdict(verdict) => regs.verdict = verdict.clone(),
+                }
+                true
+            }
+            Self::Bitwise {
+                sreg,
+                dreg,
+                mask,
+                xor,
+            } => {
+                let result: Vec<u8> = regs
+                    .get(*sreg, mask.len())
+                    .iter()
+                    .zip(mask.iter().zip(xor.iter()))
+                    .map(|(value, (mask, xor))| (value & mask) ^ xor)
+                    .collect();
+                regs.set(*dreg, &result);
+                true
+            }
+            Self::Ct { key, dreg, dir } => eval_ct(regs, ctx, *key, *dreg, *dir),
+            Self::Nat {
+                type_,
+                family,
+                reg_addr_min,
+                reg_proto_min,
+                reg_proto_max,
+                ..
+            } => {
+                // NAT expressions of other families are ignored in `inet` tables.
+                if *family != ctx.info.nfproto && *family != NFPROTO_INET {
+                    return;
+                }
+                let addr = match reg_addr_min {
+                    Some(reg) if ctx.info.nfproto == NFPROTO_IPV4 => {
+                        let octets: [u8; 4] = regs.get(*reg, 4).try_into().unwrap();
+                        Some(IpAddr::V4(Ipv4Addr::from(octets)))
+                    }
+                    Some(reg) => {
+                        let octets: [u8; 16] = regs.get(*reg, 16).try_into().unwrap();
+                        Some(IpAddr::V6(Ipv6Addr::from(octets)))
+                    }
+                    None => None,
+                };
+                let ports = read_port_range(regs, *reg_proto_min, *reg_proto_max);
+                let nat = match (type_, addr) {
+                    (NatType::Snat, Some(addr)) => NatAction::Src { addr, ports },
+                    (NatType::Dnat, Some(addr)) => NatAction::Dst {
+                        addr,
+                        port: ports.map(|(min, _)| min),
+                    },
+                    // Only the ports are translated.
+                    (NatType::Snat, None) => NatAction::Src {
+                        addr: ctx.info.src,
+                        ports,
+                    },
+                    (NatType::Dnat, None) => NatAction::Dst {
+                        addr: ctx.info.dst,
+                        port: ports.map(|(min, _)| min),
+                    },
+                };
+                *action = Some(Action::Nat(nat));
+                regs.verdict = Verdict::Accept;
+                true
+            }
+            Self::Masq {
+                reg_proto_min,
+                reg_proto_max,
+                ..
+            } => {
+                let ports = read_port_range(regs, *reg_proto_min, *reg_proto_max);
+                *action = Some(Action::Nat(NatAction::Masquerade { ports }));
+                regs.verdict = Verdict::Accept;
+                true
+            }
+            Self::Redir {
+                reg_proto_min,
+                reg_proto_max,
+                ..
+            } => {
+                let port =
+                    read_port_range(regs, *reg_proto_min, *reg_proto_max).map(|(min, _)| min);
+                *action = Some(Action::Nat(NatAction::Redirect { port }));
+                regs.verdict = Verdict::Accept;
+                true
+            }
+            Self::Reject { type_, icmp_code } => {
+                let reject_with = match type_ {
+                    RejectType::IcmpUnreach => RejectWith::IcmpUnreachable(*icmp_code),
+                    RejectType::TcpRst => RejectWith::TcpReset,
+                    RejectType::IcmpxUnreach => {
+                        RejectWith::IcmpUnreachable(icmpx_to_icmp_code(*icmp_code))
+                    }
+                };
+                *action = Some(Action::Reject(reject_with));
+                regs.verdict = Verdict::Drop;
+                true
+            }
+            Self::Counter(counter) => {
+                counter.packets.fetch_add(1, Ordering::Relaxed);
+                counter
+                    .bytes
+                    .fetch_add(ctx.info.len as u64, Ordering::Relaxed);
+                true
+            }
+            Self::Lookup {
+                set,
+                sreg,
+                dreg,
+                flags,
+            } => {
+                let Some(set) = table.find_set(set) else {
+                    regs.verdict = Verdict::Break;
+                    return;
+                };
+                let key = regs.get(*sreg, set.key_len as usize);
+                let found = set.lookup(key);
+                let is_inverted = flags & NFT_LOOKUP_F_INV != 0;
+                match (found, dreg) {
+                    (Some(elem), Some(dreg)) => match &elem.data {
+                        Some(SetData::Value(value)) => {
+                            let value = value.clone();
+                            regs.set(*dreg, &value);
+                            true
+                        }
+                        Some(SetData::Verdict(verdict)) => {
+                            regs.verdict = verdict.clone();
+                            true
+                        }
+                        None => false,
+                    },
+                    (found, _) => found.is_some() != is_inverted,
+                }
+            }
+        };
+
+        if !is_done {
+            regs.verdict = Verdict::Break;
+        }
+    }
+
+    /// Returns whether the expression may only be used at the hooks.
+    ///
+    /// NAT expressions can only be used in NAT chains, which is checked by the caller.
+    pub(crate) fn is_valid_at(&self, hook: FilterHook) -> bool {
+        match self {
+            Self::Nat {
+                type_: NatType::Snat,
+                ..
+            }
+            | Self::Masq { .. } => matches!(hook, FilterHook::PostRouting | FilterHook::LocalIn),
+            Self::Nat {
+                type_: NatType::Dnat,
+                ..
+            }
+            | Self::Redir { .. } => matches!(hook, FilterHook::PreRouting | FilterHook::LocalOut),
+            Self::Reject { .. } => !matches!(hook, FilterHook::PostRouting),
+            _ => true,
+        }
+    }
+
+    /// Returns whether the expression translates addresses.
+    pub(crate) fn is_nat(&self) -> bool {
+        matches!(
+            self,
+            Self::Nat { .. } | Self::Masq { .. } | Self::Redir { .. }
+        )
+    }
+}
+
+fn eval_payload(
+    regs: &mut Regs,
+    ctx: &PacketContext,
+    base: PayloadBase,
+    offset: usize,
+    len: usize,
+    dreg: u32,
+) -> bool {
+    let data = match base {
+        // The link-layer headers are not visible at the IP hooks.
+        PayloadBase::LinkLayer => return false,
+        PayloadBase::Network => &ctx.packet[..ctx.info.len],
+        PayloadBase::Transport => ctx.info.l4_data(ctx.packet),
+    };
+    let Some(data) = data.get(offset..offset + len) else {
+        return false;
+    };
+    regs.set(dreg, data);
+    true
+}
+
+fn eval_meta(regs: &mut Regs, ctx: &PacketContext, key: MetaKey, dreg: u32) -> bool {
+    const ETH_P_IP: u16 = 0x0800;
+    const ETH_P_IPV6: u16 = 0x86dd;
+
+    match key {
+        MetaKey::Len => regs.set(dreg, &(ctx.info.len as u32).to_ne_bytes()),
+        MetaKey::Protocol => {
+            let protocol = if ctx.info.nfproto == NFPROTO_IPV4 {
+                ETH_P_IP
+            } else {
+                ETH_P_IPV6
+            };
+            regs.set(dreg, &protocol.to_be_bytes());
+        }
+        // TODO: Support packet marks.
+        MetaKey::Mark => regs.set(dreg, &0u32.to_ne_bytes()),
+        MetaKey::Iif | MetaKey::IifName | MetaKey::IifType => {
+            let Some(iif) = ctx.iif else {
+                return false;
+            };
+            store_iface_meta(regs, iif, key, dreg);
+        }
+        MetaKey::Oif | MetaKey::OifName | MetaKey::OifType => {
+            let Some(oif) = ctx.oif else {
+                return false;
+            };
+            store_iface_meta(regs, oif, key, dreg);
+        }
+        MetaKey::NfProto => regs.set(dreg, &[ctx.info.nfproto]),
+        MetaKey::L4Proto => regs.set(dreg, &[ctx.info.l4proto]),
+    }
+    true
+}
+
+fn store_iface_meta(regs: &mut Regs, iface: &IfaceMeta, key: MetaKey, dreg: u32) {
+    match key {
+        MetaKey::Iif | MetaKey::Oif => regs.set(dreg, &iface.index.to_ne_bytes()),
+        MetaKey::IifName | MetaKey::OifName => regs.set(dreg, &iface.name),
+        _ => regs.set(dreg, &iface.type_.to_ne_bytes()),
+    }
+}
+
+fn eval_ct(regs: &mut Regs, ctx: &PacketContext, key: CtKey, dreg: u32, dir: Option<u8>) -> bool {
+    if key == CtKey::State {
+        regs.set(dreg, &ctx.ct.state().to_ne_bytes());
+        return true;
+    }
+
+    let CtInfo::Tracked {
+        dir: pkt_dir,
+        status,
+        orig,
+        reply,
+        ..
+    } = &ctx.ct
+    else {
+        return false;
+    };
+
+    let tuple = match dir {
+        Some(1) => reply,
+        _ => orig,
+    };
+    match key {
+        CtKey::State => unreachable!(),
+        CtKey::Direction => regs.set(dreg, &[(*pkt_dir == Direction::Reply) as u8]),
+        CtKey::Status => regs.set(dreg, &status.to_ne_bytes()),
+        // TODO: Support connection marks.
+        CtKey::Mark => regs.set(dreg, &0u32.to_ne_bytes()),
+        CtKey::L3Protocol => regs.set(dreg, &[ctx.info.nfproto]),
+        CtKey::Protocol => regs.set(dreg, &[tuple.proto]),
+        CtKey::ProtoSrc => regs.set(dreg, &tuple.src_port.to_be_bytes()),
+        CtKey::ProtoDst => regs.set(dreg, &tuple.dst_port.to_be_bytes()),
+        CtKey::Src | CtKey::SrcIp | CtKey::SrcIp6 => {
+            return store_addr(regs, dreg, key, &tuple.src);
+        }
+        CtKey::Dst | CtKey::DstIp | CtKey::DstIp6 => {
+            return store_addr(regs, dreg, key, &tuple.dst);
+        }
+    }
+    true
+}
+
+fn store_addr(regs: &mut Regs, dreg: u32, key: CtKey, addr: &IpAddr) -> bool {
+    match (key, addr) {
+        (CtKey::Src | CtKey::Dst | CtKey::SrcIp | CtKey::DstIp, IpAddr::V4(addr)) => {
+            regs.set(dreg, &addr.octets())
+        }
+        (CtKey::Src | CtKey::Dst | CtKey::SrcIp6 | CtKey::DstIp6, IpAddr::V6(addr)) => {
+            regs.set(dreg, &addr.octets())
+        }
+        _ => return false,
+    }
+    true
+}
+
+fn read_port_range(regs: &Regs, min: Option<u32>, max: Option<u32>) -> Option<(u16, u16)> {
+    let read_port = |reg| {
+        let bytes = regs.get(reg, 2);
+        u16::from_be_bytes([bytes[0], bytes[1]])
+    };
+
+    let min = read_port(min?);
+    let max = max.map(read_port).unwrap_or(min);
+    Some((min.min(max), min.max(max)))
+}
+
+/// Converts an `NFT_REJECT_ICMPX_*` code to the ICMP code.
+fn icmpx_to_icmp_code(code: u8) -> u8 {
+    const ICMP_NET_UNREACH: u8 = 0;
+    const ICMP_HOST_UNREACH: u8 = 1;
+    const ICMP_PORT_UNREACH: u8 = 3;
+    const ICMP_PKT_FILTERED: u8 = 13;
+
+    match code {
+        0 => ICMP_NET_UNREACH,
+        1 => ICMP_PORT_UNREACH,
+        2 => ICMP_HOST_UNREACH,
+        _ => ICMP_PKT_FILTERED,
+    }
+}
+
+/// Returns whether the family of a table matches the packet.
+pub(super) fn is_family_matched(table_family: u8, nfproto: u8) -> bool {
+    table_family == NFPROTO_INET
+        || (table_family == NFPROTO_IPV4 && nfproto == NFPROTO_IPV4)
+        || (table_family == NFPROTO_IPV6 && nfproto == NFPROTO_IPV6)
+}
@@ -0,0 +1,599 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Packet filtering, network address translation, and packet forwarding.
+//!
+//! Each network namespace has an nftables ruleset, which is configured via `NETLINK_NETFILTER`
+//! sockets, and a connection tracking table. The ifaces pass the IP packets to the rulesets at
+//! the hooks via [`NetfilterHook`], which implements [`PacketFilter`].
+//!
+//! The packets that are not destined for the receiving iface are forwarded if
+//! `net.ipv4.ip_forward` is enabled. Since routing needs to lock the ifaces, the packets are
+//! routed in a work item rather than in the polling context.
+
+mod conntrack;
+mod expr;
+mod packet;
+mod ruleset;
+
+use core::{
+    net::{IpAddr, Ipv4Addr, Ipv6Addr},
+    sync::atomic::{AtomicBool, AtomicU32, AtomicUsize, Ordering},
+};
+
+use aster_bigtcp::{
+    iface::{FilterHook, FilterVerdict, HookIface, InterfaceFlags, PacketFilter, RejectWith},
+    wire::IpAddress,
+};
+use aster_softirq::BottomHalfDisabled;
+use conntrack::{Conntrack, Direction, TuplePoint};
+use expr::{Action, CtInfo, NatAction, PacketContext};
+pub(crate) use expr::{
+    CmpOp, Counter, CtKey, Expr, MetaKey, NF_NAT_RANGE_MAP_IPS, NF_NAT_RANGE_PROTO_SPECIFIED,
+    NFT_LOOKUP_F_INV, NFT_REG_VERDICT, NatType, PayloadBase, RejectType, reg_offset,
+};
+use ostd::timer::Jiffies;
+use packet::PacketInfo;
+pub(crate) use ruleset::{
+    BaseChain, Chain, ChainType, MAX_JUMP_DEPTH, NFT_SET_ANONYMOUS, NFT_SET_ELEM_INTERVAL_END,
+    NFT_SET_INTERVAL, NFT_SET_MAP, NFT_TABLE_F_DORMANT, Rule, Ruleset, Set, SetData, SetElem,
+    Table, Verdict,
+};
+use spin::Once;
+
+use crate::{
+    net::{iface::Iface, net_ns::NetNamespace},
+    prelude::*,
+    thread::work_queue::{WorkPriority, submit_work_item, work_item::WorkItem},
+};
+
+/// `NFPROTO_INET` in Linux, which is the family of the tables for both IPv4 and IPv6.
+pub(crate) const NFPROTO_INET: u8 = 1;
+/// `NFPROTO_IPV4` in Linux.
+pub(crate) const NFPROTO_IPV4: u8 = 2;
+/// `NFPROTO_IPV6` in Linux.
+pub(crate) const NFPROTO_IPV6: u8 = 10;
+
+/// The netfilter state of a network namespace.
+pub(crate) struct Netfilter {
+    // The ruleset is replaced as a whole when a transaction is committed, so the packets being
+    // filtered always see a consistent ruleset.
+    ruleset: SpinLock<Arc<Ruleset>, BottomHalfDisabled>,
+    conntrack: SpinLock<Conntrack, BottomHalfDisabled>,
+    /// Whether the ruleset has any base chains.
+    is_active: AtomicBool,
+    /// The generation ID of the ruleset, which is increased by each transaction.
+    generation: AtomicU32,
+    /// Whether IPv4 packets are forwarded (`net.ipv4.ip_forward`).
+    ip_forward: AtomicBool,
+    /// The lock that serializes the transactions.
+    transaction: Mutex<()>,
+}
+
+/// The number of network namespaces whose rulesets have base chains.
+static NUM_ACTIVE: AtomicUsize = AtomicUsize::new(0);
+
+impl Netfilter {
+    pub(super) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            ruleset: SpinLock::new(Arc::new(Ruleset::default())),
+            conntrack: SpinLock::new(Conntrack::new()),
+            is_active: AtomicBool::new(false),
+            generation: AtomicU32::new(1),
+            ip_forward: AtomicBool::new(false),
+            transaction: Mutex::new(()),
+        })
+    }
+
+    /// Returns the current ruleset.
+    pub(crate) fn ruleset(&self) -> Arc<Ruleset> {
+        self.ruleset.lock().clone()
+    }
+
+    /// Returns the generation ID of the ruleset.
+    pub(crate) fn generation(&self) -> u32 {
+        self.generation.load(Ordering::Relaxed)
+    }
+
+    /// Locks the ruleset for a transaction.
+    ///
+    /// The ruleset can only be replaced by [`Self::commit`] while the lock is held.
+    pub(crate) fn lock_transaction(&self) -> MutexGuard<'_, ()> {
+        self.transaction.lock()
+    }
+
+    /// Replaces the ruleset and increases the generation ID.
+    pub(crate) fn commit(&self, ruleset: Ruleset, _guard: &MutexGuard<'_, ()>) {
+        let is_active = ruleset.has_base_chains();
+        *self.ruleset.lock() = Arc::new(ruleset);
+        self.generation.fetch_add(1, Ordering::Relaxed);
+
+        if self.is_active.swap(is_active, Ordering::Relaxed) != is_active {
+            if is_active {
+                NUM_ACTIVE.fetch_add(1, Ordering::Relaxed);
+            } else {
+                NUM_ACTIVE.fetch_sub(1, Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Returns whether IPv4 packets are forwarded between ifaces.
+    pub(crate) fn is_ip_forward_enabled(&self) -> bool {
+        self.ip_forward.load(Ordering::Relaxed)
+    }
+
+    /// Sets whether IPv4 packets are forwarded between ifaces.
+    pub(crate) fn set_ip_forward_enabled(&self, is_enabled: bool) {
+        self.ip_forward.store(is_enabled, Ordering::Relaxed);
+    }
+
+    /// Passes a packet through the base chains at the hook.
+    ///
+    /// `iface_addr` is the IPv4 address of the input iface at the entry hooks (for redirection) or
+    /// the output iface at the exit hooks (for masquerading).
+    fn run_hook(
+        &self,
+        hook: FilterHook,
+        iif: Option<&IfaceMeta>,
+        oif: Option<&IfaceMeta>,
+        iface_addr: Option<Ipv4Addr>,
+        packet: &mut [u8],
+    ) -> FilterVerdict {
+        if !self.is_active.load(Ordering::Relaxed) {
+            return FilterVerdict::Accept;
+        }
+        // Malformed packets are dropped by the ifaces later.
+        let Some(mut info) = PacketInfo::parse(packet) else {
+            return FilterVerdict::Accept;
+        };
+
+        let ruleset = self.ruleset();
+        let now = Jiffies::elapsed().as_duration().as_millis() as u64;
+        let mut conntrack = self.conntrack.lock();
+
+        let is_entry = matches!(hook, FilterHook::PreRouting | FilterHook::LocalOut);
+        let is_exit = matches!(hook, FilterHook::LocalIn | FilterHook::PostRouting);
+        let point = if is_entry {
+            TuplePoint::Entry
+        } else {
+            TuplePoint::Mapped
+        };
+
+        // The packets received by the loopback iface were tracked and translated when they were
+        // sent. Linux keeps the conntrack entries attached to such packets, so here they are
+        // looked up by the tuples that they were sent with, and are not tracked again.
+        let looped_back = if iif.is_some_and(|iif| iif.is_loopback)
+            && let Some(tuple) = info.tuple(packet)
+        {
+            conntrack
+                .lookup(&tuple.inverse(), TuplePoint::Entry, now)
+                .map(|(id, dir)| (id, dir.opposite()))
+        } else {
+            None
+        };
+
+        let mut tracked = None;
+        let mut ct = if let Some((id, dir)) = looped_back {
+            tracked = Some((id, dir));
+            ct_info(&conntrack, id, dir, false)
+        } else if let Some(tuple) = info.tuple(packet) {
+            let found = conntrack.lookup(&tuple, point, now).filter(|(id, _)| {
+                // An unconfirmed connection at the entry means that the previous packet did not
+                // leave the network stack, so the connection is started over.
+                !is_entry || conntrack.get(*id).is_confirmed()
+            });
+            let (id, dir) = match found {
+                Some(found) => found,
+                None => match conntrack.create(&tuple, now) {
+                    Some(id) => (id, Direction::Original),
+                    None => return FilterVerdict::Drop,
+                },
+            };
+            conntrack.on_packet(id, dir, info.tcp_flags(packet), now);
+            tracked = Some((id, dir));
+            ct_info(&conntrack, id, dir, false)
+        } else if let Some(inner) = info.icmp_error_tuple(packet) {
+            // The embedded packet was sent in the opposite direction of the ICMP error.
+            match conntrack.lookup(&inner.inverse(), point, now) {
+                // TODO: Translate the packets embedded in the ICMP errors of NATed connections.
+                Some((id, dir)) => ct_info(&conntrack, id, dir, true),
+                None => CtInfo::Invalid,
+            }
+        } else if info.is_fragment || info.is_icmp() {
+            // Like Linux, fragments and ICMP messages other than echo messages and errors (e.g.,
+            // neighbor discovery messages) are not tracked.
+            CtInfo::Untracked
+        } else {
+            CtInfo::Invalid
+        };
+
+        // Translate the packets of the established connections at the entry.
+        if is_entry
+            && looped_back.is_none()
+            && let Some((id, dir)) = tracked
+            && conntrack.get(id).is_confirmed()
+        {
+            let conn = conntrack.get(id);
+            let mapped = *conn.mapped();
+            match dir {
+                Direction::Original if conn.has_dst_nat() => {
+                    packet::rewrite_dst(packet, &info, mapped.dst, mapped.dst_port);
+                }
+                Direction::Reply if conn.has_src_nat() => {
+                    packet::rewrite_dst(packet, &info, mapped.src, mapped.src_port);
+                }
+                _ => (),
+            }
+            info = PacketInfo::parse(packet).unwrap();
+        }
+
+        let is_new = tracked.is_some_and(|(id, dir)| {
+            dir == Direction::Original && !conntrack.get(id).is_confirmed()
+        });
+        let mut has_nat = false;
+        let mut verdict = FilterVerdict::Accept;
+
+        for (table, chain) in ruleset.base_chains(hook, info.nfproto) {
+            // NAT chains only see the first packet of each connection.
+            let is_nat_chain = chain.base.as_ref().unwrap().type_ == ChainType::Nat;
+            if is_nat_chain && (!is_new || has_nat) {
+                continue;
+            }
+
+            let ctx = PacketContext {
+                packet,
+                info: &info,
+                iif,
+                oif,
+                ct,
+            };
+            match table.eval_chain(chain, &ctx) {
+                (Verdict::Drop, Some(Action::Reject(reject_with))) => {
+                    verdict = FilterVerdict::Reject(reject_with);
+                    break;
+                }
+                (Verdict::Drop, _) => {
+                    verdict = FilterVerdict::Drop;
+                    break;
+                }
+                (_, Some(Action::Nat(nat))) => {
+                    // NAT expressions are only allowed in NAT chains, which only see new
+                    // connections.
+                    let Some((id, _)) = tracked.filter(|_| is_nat_chain) else {
+                        verdict = FilterVerdict::Drop;
+                        break;
+                    };
+                    if !apply_nat(&mut conntrack, id, nat, hook, &info, packet, iface_addr) {
+                        verdict = FilterVerdict::Drop;
+                        break;
+                    }
+                    has_nat = true;
+                    info = PacketInfo::parse(packet).unwrap();
+                    ct = ct_info(&conntrack, id, Direction::Original, false);
+                }
+                _ => (),
+            }
+        }
+
+        let Some((id, dir)) = tracked else {
+            return verdict;
+        };
+        if verdict != FilterVerdict::Accept {
+            if !conntrack.get(id).is_confirmed() {
+                conntrack.remove(id);
+            }
+            return verdict;
+        }
+
+        // Translate the packets of the established connections and confirm the new connections
+        // at the exit.
+        if is_exit && looped_back.is_none() {
+            let conn = conntrack.get(id);
+            if conn.is_confirmed() {
+                match dir {
+                    Direction::Original if conn.has_src_nat() => {
+                        let reply = *conn.reply();
+                        packet::rewrite_src(packet, &info, reply.dst, reply.dst_port);
+                    }
+                    Direction::Reply if conn.has_dst_nat() => {
+                        let orig = *conn.orig();
+                        packet::rewrite_src(packet, &info, orig.dst, orig.dst_port);
+                    }
+                    _ => (),
+                }
+            } else if !conntrack.confirm(id) {
+                return FilterVerdict::Drop;
+            }
+        }
+
+        FilterVerdict::Accept
+    }
+}
+
+impl Drop for Netfilter {
+    fn drop(&mut self) {
+        if *self.is_active.get_mut() {
+            NUM_ACTIVE.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+}
+
+/// Returns the connection tracking information of a packet in the connection.
+fn ct_info(conntrack: &Conntrack, id: u64, dir: Direction, is_related: bool) -> CtInfo {
+    let conn = conntrack.get(id);
+    CtInfo::Tracked {
+        dir,
+        is_related,
+        is_new: dir == Direction::Original && !conn.has_seen_reply(),
+        status: conn.status(),
+        orig: *conn.orig(),
+        reply: *conn.reply(),
+    }
+}
+
+/// Sets up the NAT of a new connection and translates its first packet.
+///
+/// This method returns `false` if the translation is not possible, in which case the packet
+/// should be dropped.
+fn apply_nat(
+    conntrack: &mut Conntrack,
+    id: u64,
+    nat: NatAction,
+    hook: FilterHook,
+    info: &PacketInfo,
+    packet: &mut [u8],
+    iface_addr: Option<Ipv4Addr>,
+) -> bool {
+    let is_entry = matches!(hook, FilterHook::PreRouting | FilterHook::LocalOut);
+    let mapped = *conntrack.get(id).mapped();
+
+    let (dst_addr, dst_port) = match nat {
+        NatAction::Dst { addr, port } if is_entry => (addr, port),
+        NatAction::Redirect { port } if is_entry => {
+            let addr = match (hook, info.nfproto, iface_addr) {
+                (FilterHook::LocalOut, NFPROTO_IPV4, _) => IpAddr::V4(Ipv4Addr::LOCALHOST),
+                (FilterHook::LocalOut, _, _) => IpAddr::V6(Ipv6Addr::LOCALHOST),
+                (_, NFPROTO_IPV4, Some(addr)) => IpAddr::V4(addr),
+                _ => return false,
+            };
+            (addr, port)
+        }
+        NatAction::Src { addr, ports } if !is_entry => {
+            return apply_src_nat(conntrack, id, addr, ports, info, packet);
+        }
+        NatAction::Masquerade { ports } if !is_entry => {
+            let (NFPROTO_IPV4, Some(addr)) = (info.nfproto, iface_addr) else {
+                return false;
+            };
+            return apply_src_nat(conntrack, id, IpAddr::V4(addr), ports, info, packet);
+        }
+        _ => return false,
+    };
+
+    if dst_addr.is_ipv4() != mapped.dst.is_ipv4() {
+        return false;
+    }
+    // The ICMP identifiers are only translated by source NAT.
+    let dst_port = match dst_port {
+        Some(port) if matches!(mapped.proto, packet::IPPROTO_TCP | packet::IPPROTO_UDP) => port,
+        _ => mapped.dst_port,
+    };
+    conntrack.set_dst_nat(id, dst_addr, dst_port);
+    packet::rewrite_dst(packet, info, dst_addr, dst_port);
+    true
+}
+
+fn apply_src_nat(
+    conntrack: &mut Conntrack,
+    id: u64,
+    addr: IpAddr,
+    ports: Option<(u16, u16)>,
+    info: &PacketInfo,
+    packet: &mut [u8],
+) -> bool {
+    if addr.is_ipv4() != info.src.is_ipv4() || !conntrack.set_src_nat(id, addr, ports) {
+        return false;
+    }
+    let reply = *conntrack.get(id).reply();
+    packet::rewrite_src(packet, info, reply.dst, reply.dst_port);
+    true
+}
+
+/// The information about an iface that is visible to the rules.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct IfaceMeta {
+    index: u32,
+    name: [u8; 16],
+    /// The hardware type (`ARPHRD_*` in Linux).
+    type_: u16,
+    is_loopback: bool,
+}
+
+impl IfaceMeta {
+    fn new(iface: &Iface) -> Self {
+        Self {
+            index: iface.index(),
+            name: *iface.name().as_array(),
+            type_: iface.type_() as u16,
+            is_loopback: iface.flags().contains(InterfaceFlags::LOOPBACK),
+        }
+    }
+}
+
+/// The ifaces of all network namespaces, indexed by their IDs.
+///
+/// The ifaces are looked up while they are polled, which may happen in the softirq context.
+static IFACES: RwLock<BTreeMap<u32, IfaceEntry>, BottomHalfDisabled> = RwLock::new(BTreeMap::new());
+
+struct IfaceEntry {
+    meta: IfaceMeta,
+    netfilter: Arc<Netfilter>,
+    iface: Weak<Iface>,
+    net_ns: Weak<NetNamespace>,
+}
+
+/// Registers an iface that is added to a network namespace.
+pub(super) fn register_iface(net_ns: &Arc<NetNamespace>, iface: &Arc<Iface>) {
+    let entry = IfaceEntry {
+        meta: IfaceMeta::new(iface),
+        netfilter: net_ns.netfilter().clone(),
+        iface: Arc::downgrade(iface),
+        net_ns: Arc::downgrade(net_ns),
+    };
+    IFACES.write().insert(iface.id(), entry);
+}
+
+/// Unregisters an iface that is removed from its network namespace.
+pub(super) fn unregister_iface(id: u32) {
+    IFACES.write().remove(&id);
+}
+
+/// The hook that passes the packets of the ifaces to the netfilter of their network namespaces.
+pub(crate) struct NetfilterHook;
+
+impl PacketFilter for NetfilterHook {
+    fn is_active() -> bool {
+        NUM_ACTIVE.load(Ordering::Relaxed) != 0
+    }
+
+    fn filter(hook: FilterHook, iface: &HookIface, packet: &mut [u8]) -> FilterVerdict {
+        let (meta, netfilter) = {
+            let ifaces = IFACES.read();
+            let Some(entry) = ifaces.get(&iface.id) else {
+                return FilterVerdict::Accept;
+            };
+            (entry.meta, entry.netfilter.clone())
+        };
+
+        let (iif, oif) = match hook {
+            FilterHook::PreRouting | FilterHook::LocalIn => (Some(&meta), None),
+            _ => (None, Some(&meta)),
+        };
+        netfilter.run_hook(hook, iif, oif, iface.ipv4_addr.map(Into::into), packet)
+    }
+
+    fn forward(iface_id: u32, packet: &[u8]) -> bool {
+        let is_enabled = IFACES
+            .read()
+            .get(&iface_id)
+            .is_some_and(|entry| entry.netfilter.is_ip_forward_enabled());
+        if !is_enabled {
+            return false;
+        }
+
+        // TODO: Support IPv6 forwarding (`net.ipv6.conf.all.forwarding`).
+        let Some(info) = PacketInfo::parse(packet) else {
+            return false;
+        };
+        let IpAddr::V4(dst) = info.dst else {
+            return false;
+        };
+        if dst.is_multicast() || dst.is_unspecified() || dst.is_loopback() {
+            return false;
+        }
+
+        let mut queue = FORWARD_QUEUE.lock();
+        if queue.len() < FORWARD_QUEUE_LEN {
+            queue.push_back((iface_id, packet.to_vec()));
+        }
+        drop(queue);
+
+        let work_item = FORWARD_WORK_ITEM.call_once(|| WorkItem::new(Box::new(forward_packets)));
+        submit_work_item(work_item.clone(), WorkPriority::High);
+        true
+    }
+}
+
+/// The packets to forward, with the IDs of their receiving ifaces.
+static FORWARD_QUEUE: SpinLock<VecDeque<(u32, Vec<u8>)>, BottomHalfDisabled> =
+    SpinLock::new(VecDeque::new());
+/// The maximum number of packets in [`FORWARD_QUEUE`], beyond which packets are dropped.
+const FORWARD_QUEUE_LEN: usize = 1000;
+static FORWARD_WORK_ITEM: Once<Arc<WorkItem>> = Once::new();
+
+fn forward_packets() {
+    loop {
+        let Some((iface_id, packet)) = FORWARD_QUEUE.lock().pop_front() else {
+            break;
+        };
+        forward_packet(iface_id, packet);
+    }
+}
+
+/// Routes a packet received by the iface and sends it through the output iface.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/ipv4/ip_forward.c>
+fn forward_packet(iface_id: u32, mut packet: Vec<u8>) {
+    /// `ICMP_NET_UNREACH` in Linux.
+    const ICMP_NET_UNREACH: u8 = 0;
+
+    let (in_meta, netfilter, in_iface, net_ns) = {
+        let ifaces = IFACES.read();
+        let Some(entry) = ifaces.get(&iface_id) else {
+            return;
+        };
+        (
+            entry.meta,
+            entry.netfilter.clone(),
+            entry.iface.upgrade(),
+            entry.net_ns.upgrade(),
+        )
+    };
+    let (Some(in_iface), Some(net_ns)) = (in_iface, net_ns) else {
+        return;
+    };
+    let Some(info) = PacketInfo::parse(&packet) else {
+        return;
+    };
+
+    let dst_addr = match info.dst {
+        IpAddr::V4(addr) => IpAddress::Ipv4(addr),
+        IpAddr::V6(addr) => IpAddress::Ipv6(addr),
+    };
+    let out_iface = {
+        let route_table = net_ns.route_table();
+        route_table.lookup(&dst_addr, &net_ns.ifaces())
+    };
+    let Some(out_iface) = out_iface else {
+        in_iface.reject_forwarded(packet, RejectWith::IcmpUnreachable(ICMP_NET_UNREACH));
+        return;
+    };
+
+    // TODO: Reply with ICMP time exceeded messages.
+    if !packet::decrement_ttl(&mut packet, &info) {
+        return;
+    }
+    // TODO: Fragment the packets or reply with ICMP fragmentation needed messages.
+    if packet.len() > out_iface.mtu() {
+        return;
+    }
+
+    let out_meta = IfaceMeta::new(&out_iface);
+    match netfilter.run_hook(
+        FilterHook::Forward,
+        Some(&in_meta),
+        Some(&out_meta),
+        None,
+        &mut packet,
+    ) {
+        FilterVerdict::Accept => (),
+        FilterVerdict::Drop => return,
+        FilterVerdict::Reject(reject_with) => {
+            in_iface.reject_forwarded(packet, reject_with);
+            return;
+        }
+    }
+
+    let out_addr = out_iface.ipv4_cidr().map(|cidr| cidr.address());
+    if netfilter.run_hook(
+        FilterHook::PostRouting,
+        Some(&in_meta),
+        Some(&out_meta),
+        out_addr,
+        &mut packet,
+    ) != FilterVerdict::Accept
+    {
+        return;
+    }
+
+    out_iface.send_forwarded(packet);
+}
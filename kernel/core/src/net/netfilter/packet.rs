@@ -0,0 +1,432 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Parsing and rewriting of IP packets.
+
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use super::{NFPROTO_IPV4, NFPROTO_IPV6};
+
+pub(super) const IPPROTO_ICMP: u8 = 1;
+pub(super) const IPPROTO_TCP: u8 = 6;
+pub(super) const IPPROTO_UDP: u8 = 17;
+pub(super) const IPPROTO_ICMPV6: u8 = 58;
+
+const IPV4_HEADER_LEN: usize = 20;
+const IPV6_HEADER_LEN: usize = 40;
+
+/// The IPv6 extension headers that can be skipped to find the transport header.
+const IPV6_EXT_HOP_BY_HOP: u8 = 0;
+const IPV6_EXT_ROUTING: u8 = 43;
+const IPV6_EXT_FRAGMENT: u8 = 44;
+const IPV6_EXT_DEST_OPTS: u8 = 60;
+
+/// The information parsed from the IP header of a packet.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct PacketInfo {
+    /// The netfilter protocol family (`NFPROTO_IPV4` or `NFPROTO_IPV6`).
+    pub(super) nfproto: u8,
+    pub(super) src: IpAddr,
+    pub(super) dst: IpAddr,
+    /// The transport protocol.
+    pub(super) l4proto: u8,
+    /// The offset of the transport header.
+    pub(super) l4_offset: usize,
+    /// The length of the packet, including the IP header.
+    pub(super) len: usize,
+    /// Whether the packet is a fragment of a larger packet.
+    pub(super) is_fragment: bool,
+    /// Whether the transport header is present (i.e., the packet is not a non-first fragment).
+    pub(super) has_l4_header: bool,
+}
+
+impl PacketInfo {
+    /// Parses the IP header of a packet.
+    ///
+    /// Packets with malformed IP headers are rejected.
+    pub(super) fn parse(packet: &[u8]) -> Option<Self> {
+        Self::parse_inner(packet, true)
+    }
+
+    /// Parses the IP header of a packet, which may be truncated if `is_strict` is `false`.
+    ///
+    /// ICMP error messages contain truncated packets, which need to be parsed leniently.
+    fn parse_inner(packet: &[u8], is_strict: bool) -> Option<Self> {
+        match packet.first()? >> 4 {
+            4 => Self::parse_ipv4(packet, is_strict),
+            6 => Self::parse_ipv6(packet, is_strict),
+            _ => None,
+        }
+    }
+
+    fn parse_ipv4(packet: &[u8], is_strict: bool) -> Option<Self> {
+        if packet.len() < IPV4_HEADER_LEN {
+            return None;
+        }
+
+        let header_len = ((packet[0] & 0xf) as usize) * 4;
+        let total_len = u16::from_be_bytes([packet[2], packet[3]]) as usize;
+        if header_len < IPV4_HEADER_LEN || total_len < header_len || packet.len() < header_len {
+            return None;
+        }
+        let len = if total_len <= packet.len() {
+            total_len
+        } else if !is_strict {
+            packet.len()
+        } else {
+            return None;
+        };
+
+        let frag = u16::from_be_bytes([packet[6], packet[7]]);
+        let more_frags = frag & 0x2000 != 0;
+        let frag_offset = frag & 0x1fff;
+
+        Some(Self {
+            nfproto: NFPROTO_IPV4,
+            src: IpAddr::V4(Ipv4Addr::from(read_array::<4>(packet, 12))),
+            dst: IpAddr::V4(Ipv4Addr::from(read_array::<4>(packet, 16))),
+            l4proto: packet[9],
+            l4_offset: header_len,
+            len,
+            is_fragment: more_frags || frag_offset != 0,
+            has_l4_header: frag_offset == 0,
+        })
+    }
+
+    fn parse_ipv6(packet: &[u8], is_strict: bool) -> Option<Self> {
+        if packet.len() < IPV6_HEADER_LEN {
+            return None;
+        }
+
+        let payload_len = u16::from_be_bytes([packet[4], packet[5]]) as usize;
+        let len = if IPV6_HEADER_LEN + payload_len <= packet.len() {
+            IPV6_HEADER_LEN + payload_len
+        } else if !is_strict {
+            packet.len()
+        } else {
+            return None;
+        };
+
+        let mut next_header = packet[6];
+        let mut offset = IPV6_HEADER_LEN;
+        let mut is_fragment = false;
+        let mut has_l4_header = true;
+        loop {
+            match next_header {
+                IPV6_EXT_HOP_BY_HOP | IPV6_EXT_ROUTING | IPV6_EXT_DEST_OPTS => {
+                    let ext = packet.get(offset..offset + 2)?;
+                    next_header = ext[0];
+                    offset += (ext[1] as usize + 1) * 8;
+                }
+                IPV6_EXT_FRAGMENT => {
+                    let ext = packet.get(offset..offset + 8)?;
+                    let frag = u16::from_be_bytes([ext[2], ext[3]]);
+                    next_header = ext[0];
+                    offset += 8;
+                    is_fragment = true;
+                    has_l4_header = frag >> 3 == 0;
+                }
+                _ => break,
+            }
+            if offset > len {
+                return None;
+            }
+        }
+
+        Some(Self {
+            nfproto: NFPROTO_IPV6,
+            src: IpAddr::V6(Ipv6Addr::from(read_array::<16>(packet, 8))),
+            dst: IpAddr::V6(Ipv6Addr::from(read_array::<16>(packet, 24))),
+            l4proto: next_header,
+            l4_offset: offset,
+            len,
+            is_fragment,
+            has_l4_header,
+        })
+    }
+
+    /// Returns the transport header and payload.
+    ///
+    /// The result is empty if the transport header is not present.
+    pub(super) fn l4_data<'a>(&self, packet: &'a [u8]) -> &'a [u8] {
+        if !self.has_l4_header {
+            return &[];
+        }
+        &packet[self.l4_offset..self.len]
+    }
+
+    pub(super) fn is_icmp(&self) -> bool {
+        match self.nfproto {
+            NFPROTO_IPV4 => self.l4proto == IPPROTO_ICMP,
+            _ => self.l4proto == IPPROTO_ICMPV6,
+        }
+    }
+
+    /// Returns the connection tuple of the packet.
+    ///
+    /// For ICMP echo messages, the identifier is used as both the source and destination ports.
+    /// Protocols other than TCP, UDP, and ICMP are tracked by their addresses only. `None` is
+    /// returned if the packet cannot be tracked (e.g., it is a fragment or an ICMP error).
+    pub(super) fn tuple(&self, packet: &[u8]) -> Option<Tuple> {
+        if self.is_fragment {
+            return None;
+        }
+
+        let l4_data = self.l4_data(packet);
+        let (src_port, dst_port) = match self.l4proto {
+            IPPROTO_TCP | IPPROTO_UDP => {
+                let ports = l4_data.get(..4)?;
+                (
+                    u16::from_be_bytes([ports[0], ports[1]]),
+                    u16::from_be_bytes([ports[2], ports[3]]),
+                )
+            }
+            _ if self.is_icmp() => {
+                if !is_icmp_echo(self.nfproto, *l4_data.first()?) {
+                    return None;
+                }
+                let ident = l4_data.get(4..6)?;
+                let ident = u16::from_be_bytes([ident[0], ident[1]]);
+                (ident, ident)
+            }
+            _ => (0, 0),
+        };
+
+        Some(Tuple {
+            proto: self.l4proto,
+            src: self.src,
+            src_port,
+            dst: self.dst,
+            dst_port,
+        })
+    }
+
+    /// Returns the tuple of the packet embedded in an ICMP error message.
+    pub(super) fn icmp_error_tuple(&self, packet: &[u8]) -> Option<Tuple> {
+        if self.is_fragment || !self.is_icmp() {
+            return None;
+        }
+
+        let l4_data = self.l4_data(packet);
+        if !is_icmp_error(self.nfproto, *l4_data.first()?) {
+            return None;
+        }
+
+        let inner_packet = l4_data.get(8..)?;
+        let inner_info = Self::parse_inner(inner_packet, false)?;
+        if inner_info.nfproto != self.nfproto {
+            return None;
+        }
+        inner_info.tuple(inner_packet)
+    }
+
+    /// Returns the TCP flags if the packet is a TCP segment.
+    pub(super) fn tcp_flags(&self, packet: &[u8]) -> Option<u8> {
+        if self.l4proto != IPPROTO_TCP {
+            return None;
+        }
+        self.l4_data(packet).get(13).copied()
+    }
+}
+
+fn is_icmp_echo(nfproto: u8, type_: u8) -> bool {
+    match nfproto {
+        // Echo reply and echo request
+        NFPROTO_IPV4 => type_ == 0 || type_ == 8,
+        _ => type_ == 128 || type_ == 129,
+    }
+}
+
+fn is_icmp_error(nfproto: u8, type_: u8) -> bool {
+    match nfproto {
+        // Destination unreachable, source quench, time exceeded, and parameter problem
+        NFPROTO_IPV4 => matches!(type_, 3 | 4 | 11 | 12),
+        // Destination unreachable, packet too big, time exceeded, and parameter problem
+        _ => matches!(type_, 1..=4),
+    }
+}
+
+/// The tuple that identifies the packets of a connection in one direction.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub(super) struct Tuple {
+    pub(super) proto: u8,
+    pub(super) src: IpAddr,
+    pub(super) src_port: u16,
+    pub(super) dst: IpAddr,
+    pub(super) dst_port: u16,
+}
+
+impl Tuple {
+    /// Returns the tuple of the packets in the opposite direction.
+    pub(super) fn inverse(&self) -> Self {
+        Self {
+            proto: self.proto,
+            src: self.dst,
+            src_port: self.dst_port,
+            dst: self.src,
+            dst_port: self.src_port,
+        }
+    }
+}
+
+/// Rewrites the source address and port of a packet, and updates the checksums.
+///
+/// For ICMP echo messages, the port is the identifier.
+pub(super) fn rewrite_src(packet: &mut [u8], info: &PacketInfo, addr: IpAddr, port: u16) {
+    rewrite(packet, info, true, addr, port);
+}
+
+/// Rewrites the destination address and port of a packet, and updates the checksums.
+///
+/// For ICMP echo messages, the port is the identifier.
+pub(super) fn rewrite_dst(packet: &mut [u8], info: &PacketInfo, addr: IpAddr, port: u16) {
+    rewrite(packet, info, false, addr, port);
+}
+
+fn rewrite(packet: &mut [u8], info: &PacketInfo, is_src: bool, addr: IpAddr, port: u16) {
+    match (info.nfproto, addr) {
+        (NFPROTO_IPV4, IpAddr::V4(addr)) => {
+            let offset = if is_src { 12 } else { 16 };
+            packet[offset..offset + 4].copy_from_slice(&addr.octets());
+        }
+        (NFPROTO_IPV6, IpAddr::V6(addr)) => {
+            let offset = if is_src { 8 } else { 24 };
+            packet[offset..offset + 16].copy_from_slice(&addr.octets());
+        }
+        _ => return,
+    }
+
+    if info.has_l4_header {
+        let l4_data = &mut packet[info.l4_offset..info.len];
+        match info.l4proto {
+            IPPROTO_TCP | IPPROTO_UDP if l4_data.len() >= 4 => {
+                let offset = if is_src { 0 } else { 2 };
+                l4_data[offset..offset + 2].copy_from_slice(&port.to_be_bytes());
+            }
+            _ if info.is_icmp() && l4_data.len() >= 8 && is_icmp_echo(info.nfproto, l4_data[0]) => {
+                l4_data[4..6].copy_from_slice(&port.to_be_bytes());
+            }
+            _ => (),
+        }
+    }
+
+    let info = PacketInfo {
+        src: if is_src { addr } else { info.src },
+        dst: if is_src { info.dst } else { addr },
+        ..*info
+    };
+    update_checksums(packet, &info);
+}
+
+/// Recomputes the IPv4 header checksum and the transport checksum of a packet.
+fn update_checksums(packet: &mut [u8], info: &PacketInfo) {
+    if info.nfproto == NFPROTO_IPV4 {
+        let header = &mut packet[..info.l4_offset];
+        header[10..12].fill(0);
+        let checksum = finish_checksum(sum_words(header, 0));
+        header[10..12].copy_from_slice(&checksum.to_be_bytes());
+    }
+
+    // The checksum of a fragment cannot be computed without the other fragments.
+    if info.is_fragment {
+        return;
+    }
+
+    let checksum_offset = match info.l4proto {
+        IPPROTO_TCP => 16,
+        IPPROTO_UDP => 6,
+        IPPROTO_ICMP if info.nfproto == NFPROTO_IPV4 => 2,
+        IPPROTO_ICMPV6 if info.nfproto == NFPROTO_IPV6 => 2,
+        _ => return,
+    };
+
+    let l4_data = &mut packet[info.l4_offset..info.len];
+    if l4_data.len() < checksum_offset + 2 {
+        return;
+    }
+    // A zero checksum means that the checksum is not computed for UDP over IPv4.
+    if info.l4proto == IPPROTO_UDP
+        && info.nfproto == NFPROTO_IPV4
+        && l4_data[checksum_offset..checksum_offset + 2] == [0, 0]
+    {
+        return;
+    }
+
+    l4_data[checksum_offset..checksum_offset + 2].fill(0);
+    let mut sum = sum_words(l4_data, 0);
+    // ICMP over IPv4 does not use the pseudo header.
+    if info.l4proto != IPPROTO_ICMP {
+        sum = sum_pseudo_header(info, l4_data.len(), sum);
+    }
+    let mut checksum = finish_checksum(sum);
+    if checksum == 0 && info.l4proto == IPPROTO_UDP {
+        checksum = 0xffff;
+    }
+    l4_data[checksum_offset..checksum_offset + 2].copy_from_slice(&checksum.to_be_bytes());
+}
+
+fn sum_pseudo_header(info: &PacketInfo, l4_len: usize, mut sum: u32) -> u32 {
+    match (info.src, info.dst) {
+        (IpAddr::V4(src), IpAddr::V4(dst)) => {
+            sum = sum_words(&src.octets(), sum);
+            sum = sum_words(&dst.octets(), sum);
+        }
+        (IpAddr::V6(src), IpAddr::V6(dst)) => {
+            sum = sum_words(&src.octets(), sum);
+            sum = sum_words(&dst.octets(), sum);
+        }
+        _ => unreachable!("the addresses of a packet must be in the same family"),
+    }
+    sum += info.l4proto as u32;
+    sum += (l4_len as u32) >> 16;
+    sum += l4_len as u32 & 0xffff;
+    sum
+}
+
+/// Adds the 16-bit big-endian words of the data to the one's complement sum.
+///
+/// If the data has an odd length, it is padded with a zero byte.
+fn sum_words(data: &[u8], mut sum: u32) -> u32 {
+    let mut chunks = data.chunks_exact(2);
+    for chunk in &mut chunks {
+        sum += u16::from_be_bytes([chunk[0], chunk[1]]) as u32;
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    if let [last] = chunks.remainder() {
+        sum += (*last as u32) << 8;
+    }
+    sum
+}
+
+fn finish_checksum(mut sum: u32) -> u16 {
+    while sum >> 16 != 0 {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// Decrements the TTL (or the hop limit) of a packet that is being forwarded.
+///
+/// This method returns `false` if the TTL reaches zero, in which case the packet must be dropped.
+pub(super) fn decrement_ttl(packet: &mut [u8], info: &PacketInfo) -> bool {
+    let offset = match info.nfproto {
+        NFPROTO_IPV4 => 8,
+        _ => 7,
+    };
+    if packet[offset] <= 1 {
+        return false;
+    }
+    packet[offset] -= 1;
+
+    if info.nfproto == NFPROTO_IPV4 {
+        let header = &mut packet[..info.l4_offset];
+        header[10..12].fill(0);
+        let checksum = finish_checksum(sum_words(header, 0));
+        header[10..12].copy_from_slice(&checksum.to_be_bytes());
+    }
+
+    true
+}
+
+fn read_array<const N: usize>(packet: &[u8], offset: usize) -> [u8; N] {
+    packet[offset..offset + N].try_into().unwrap()
+}
@@ -0,0 +1,369 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The nftables ruleset.
+//!
+//! A ruleset consists of tables, each of which belongs to a family and contains chains and sets.
+//! Base chains are attached to the hooks, while regular chains can only be reached by jumping
+//! from other chains.
+
+use aster_bigtcp::iface::FilterHook;
+
+use super::expr::{Action, Expr, PacketContext, Regs};
+use crate::prelude::*;
+
+/// The maximum depth of the jump stack (`NFT_JUMP_STACK_SIZE` in Linux).
+pub(crate) const MAX_JUMP_DEPTH: usize = 16;
+
+/// The set of tables in a network namespace.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Ruleset {
+    pub(crate) tables: Vec<Table>,
+    /// The handle of the next table, which is unique in the network namespace.
+    pub(crate) next_table_handle: u64,
+}
+
+impl Ruleset {
+    /// Returns whether any base chain is attached to the hooks.
+    pub(crate) fn has_base_chains(&self) -> bool {
+        self.tables
+            .iter()
+            .filter(|table| !table.is_dormant())
+            .any(|table| table.chains.iter().any(|chain| chain.base.is_some()))
+    }
+
+    /// Returns the base chains at the hook for the family, in the order of their priorities.
+    pub(super) fn base_chains(&self, hook: FilterHook, nfproto: u8) -> Vec<(&Table, &Chain)> {
+        let mut chains: Vec<(&Table, &Chain)> = self
+            .tables
+            .iter()
+            .filter(|table| {
+                !table.is_dormant() && super::expr::is_family_matched(table.family, nfproto)
+            })
+            .flat_map(|table| {
+                table
+                    .chains
+                    .iter()
+                    .filter(move |chain| chain.base.as_ref().is_some_and(|base| base.hook == hook))
+                    .map(move |chain| (table, chain))
+            })
+            .collect();
+        chains.sort_by_key(|(_, chain)| chain.base.as_ref().unwrap().priority);
+        chains
+    }
+}
+
+/// `NFT_TABLE_F_DORMANT` in Linux.
+pub(crate) const NFT_TABLE_F_DORMANT: u32 = 1 << 0;
+
+/// A table.
+#[derive(Clone, Debug)]
+pub(crate) struct Table {
+    pub(crate) name: String,
+    /// The family (`NFPROTO_*` in Linux).
+    pub(crate) family: u8,
+    pub(crate) flags: u32,
+    pub(crate) handle: u64,
+    pub(crate) userdata: Option<Vec<u8>>,
+    pub(crate) chains: Vec<Chain>,
+    pub(crate) sets: Vec<Set>,
+    /// The handle of the next chain, rule, or set, which is unique in the table.
+    pub(crate) next_handle: u64,
+}
+
+impl Table {
+    pub(crate) fn is_dormant(&self) -> bool {
+        self.flags & NFT_TABLE_F_DORMANT != 0
+    }
+
+    pub(crate) fn find_chain(&self, name: &str) -> Option<&Chain> {
+        self.chains.iter().find(|chain| chain.name == name)
+    }
+
+    pub(crate) fn find_set(&self, name: &str) -> Option<&Set> {
+        self.sets.iter().find(|set| set.name == name)
+    }
+
+    /// Allocates a handle for a chain, a rule, or a set.
+    pub(crate) fn alloc_handle(&mut self) -> u64 {
+        self.next_handle += 1;
+        self.next_handle
+    }
+
+    /// Evaluates a base chain.
+    ///
+    /// The result is [`Verdict::Accept`] or [`Verdict::Drop`], with the side effect of the rule
+    /// that made the verdict.
+    pub(super) fn eval_chain(
+        &self,
+        chain: &Chain,
+        ctx: &PacketContext,
+    ) -> (Verdict, Option<Action>) {
+        let mut stack: Vec<(&Chain, usize)> = Vec::new();
+        let mut chain = chain;
+        let mut rule_index = 0;
+
+        loop {
+            let Some(rule) = chain.rules.get(rule_index) else {
+                // The end of a regular chain returns to the calling chain.
+                if let Some((caller, next_index)) = stack.pop() {
+                    chain = caller;
+                    rule_index = next_index;
+                    continue;
+                }
+                let policy = chain
+                    .base
+                    .as_ref()
+                    .map_or(Verdict::Accept, |base| base.policy.clone());
+                return (policy, None);
+            };
+            rule_index += 1;
+
+            let mut regs = Regs::new();
+            let mut action = None;
+            for expr in rule.exprs.iter() {
+                expr.eval(&mut regs, ctx, self, &mut action);
+                if regs.verdict != Verdict::Continue {
+                    break;
+                }
+            }
+
+            match regs.verdict {
+                Verdict::Continue | Verdict::Break => (),
+                Verdict::Accept | Verdict::Drop => return (regs.verdict, action),
+                Verdict::Jump(ref target) | Verdict::Goto(ref target) => {
+                    let Some(target) = self.find_chain(target) else {
+                        return (Verdict::Drop, None);
+                    };
+                    if matches!(regs.verdict, Verdict::Jump(_)) {
+                        // Loops are rejected when rules are added, so this is just a safeguard.
+                        if stack.len() >= MAX_JUMP_DEPTH {
+                            return (Verdict::Drop, None);
+                        }
+                        stack.push((chain, rule_index));
+                    }
+                    chain = target;
+                    rule_index = 0;
+                }
+                Verdict::Return => {
+                    if let Some((caller, next_index)) = stack.pop() {
+                        chain = caller;
+                        rule_index = next_index;
+                    } else {
+                        rule_index = chain.rules.len();
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// A chain.
+#[derive(Clone, Debug)]
+pub(crate) struct Chain {
+    pub(crate) name: String,
+    pub(crate) handle: u64,
+    /// The hook information, if the chain is a base chain.
+    pub(crate) base: Option<BaseChain>,
+    pub(crate) rules: Vec<Rule>,
+    pub(crate) userdata: Option<Vec<u8>>,
+}
+
+impl Chain {
+    /// Returns the names of the chains that this chain jumps to or goes to.
+    pub(crate) fn targets(&self) -> impl Iterator<Item = &str> {
+        self.rules.iter().flat_map(|rule| rule.targets())
+    }
+}
+
+/// The hook information of a base chain.
+#[derive(Clone, Debug)]
+pub(crate) struct BaseChain {
+    pub(crate) hook: FilterHook,
+    pub(crate) priority: i32,
+    /// The verdict when no rule makes a verdict, which is either accept or drop.
+    pub(crate) policy: Verdict,
+    pub(crate) type_: ChainType,
+}
+
+/// The type of a base chain.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum ChainType {
+    Filter,
+    Nat,
+    Route,
+}
+
+impl ChainType {
+    pub(crate) fn name(&self) -> &'static str {
+        match self {
+            Self::Filter => "filter",
+            Self::Nat => "nat",
+            Self::Route => "route",
+        }
+    }
+
+    pub(crate) fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "filter" => Some(Self::Filter),
+            "nat" => Some(Self::Nat),
+            "route" => Some(Self::Route),
+            _ => None,
+        }
+    }
+
+    /// Returns whether the chain type can be attached to the hook.
+    pub(crate) fn supports_hook(&self, hook: FilterHook) -> bool {
+        match self {
+            Self::Filter => true,
+            Self::Nat => hook != FilterHook::Forward,
+            Self::Route => hook == FilterHook::LocalOut,
+        }
+    }
+}
+
+/// A rule.
+#[derive(Clone, Debug)]
+pub(crate) struct Rule {
+    pub(crate) handle: u64,
+    pub(crate) exprs: Vec<Expr>,
+    pub(crate) userdata: Option<Vec<u8>>,
+}
+
+impl Rule {
+    /// Returns the names of the chains that this rule jumps to or goes to.
+    pub(crate) fn targets(&self) -> impl Iterator<Item = &str> {
+        self.exprs.iter().filter_map(|expr| match expr {
+            Expr::Immediate {
+                data: SetData::Verdict(Verdict::Jump(target) | Verdict::Goto(target)),
+                ..
+            } => Some(target.as_str()),
+            _ => None,
+        })
+    }
+
+    /// Returns the names of the sets that this rule looks up.
+    pub(crate) fn sets(&self) -> impl Iterator<Item = &str> {
+        self.exprs.iter().filter_map(|expr| match expr {
+            Expr::Lookup { set, .. } => Some(set.as_str()),
+            _ => None,
+        })
+    }
+}
+
+/// A verdict.
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub(crate) enum Verdict {
+    Accept,
+    Drop,
+    Continue,
+    Break,
+    Jump(String),
+    Goto(String),
+    Return,
+}
+
+/// The data of an immediate expression or a map element.
+#[derive(Clone, Debug)]
+pub(crate) enum SetData {
+    Value(Vec<u8>),
+    Verdict(Verdict),
+}
+
+/// `NFT_SET_*` flags in Linux.
+pub(crate) const NFT_SET_ANONYMOUS: u32 = 1 << 0;
+pub(crate) const NFT_SET_INTERVAL: u32 = 1 << 2;
+pub(crate) const NFT_SET_MAP: u32 = 1 << 3;
+
+/// `NFT_SET_ELEM_INTERVAL_END` in Linux.
+pub(crate) const NFT_SET_ELEM_INTERVAL_END: u32 = 1 << 0;
+
+/// A set or a map.
+#[derive(Clone, Debug)]
+pub(crate) struct Set {
+    pub(crate) name: String,
+    pub(crate) handle: u64,
+    pub(crate) flags: u32,
+    pub(crate) key_type: u32,
+    pub(crate) key_len: u32,
+    pub(crate) data_type: Option<u32>,
+    pub(crate) data_len: Option<u32>,
+    pub(crate) policy: Option<u32>,
+    pub(crate) desc: Option<Vec<u8>>,
+    pub(crate) userdata: Option<Vec<u8>>,
+    /// The elements, which are sorted by their keys.
+    pub(crate) elems: Vec<SetElem>,
+}
+
+impl Set {
+    pub(crate) fn is_anonymous(&self) -> bool {
+        self.flags & NFT_SET_ANONYMOUS != 0
+    }
+
+    /// Inserts an element, or returns `false` if an element with the same key exists.
+    pub(crate) fn insert(&mut self, elem: SetElem) -> bool {
+        match self
+            .elems
+            .binary_search_by(|other| (&other.key, other.flags).cmp(&(&elem.key, elem.flags)))
+        {
+            Ok(_) => false,
+            Err(pos) => {
+                self.elems.insert(pos, elem);
+                true
+            }
+        }
+    }
+
+    /// Removes the element with the key and the flags.
+    pub(crate) fn remove(&mut self, key: &[u8], flags: u32) -> Option<SetElem> {
+        let pos = self
+            .elems
+            .iter()
+            .position(|elem| elem.key == key && elem.flags == flags)?;
+        Some(self.elems.remove(pos))
+    }
+
+    /// Looks up the element that matches the key.
+    ///
+    /// In an interval set, each range is represented by an element at its start and an element
+    /// with [`NFT_SET_ELEM_INTERVAL_END`] at its (exclusive) end, unless the element has an
+    /// explicit end key. The keys are compared as big-endian integers, like Linux.
+    pub(super) fn lookup(&self, key: &[u8]) -> Option<&SetElem> {
+        if self.flags & NFT_SET_INTERVAL == 0 {
+            return self.elems.iter().find(|elem| elem.key == key);
+        }
+
+        if let Some(elem) = self.elems.iter().find(|elem| {
+            elem.key_end
+                .as_ref()
+                .is_some_and(|end| elem.key.as_slice() <= key && key <= end.as_slice())
+        }) {
+            return Some(elem);
+        }
+
+        // Find the last element whose key is not greater than the key. Among the elements with
+        // the same key, the start of a range precedes the end of the previous range.
+        let elem = self
+            .elems
+            .iter()
+            .filter(|elem| elem.key_end.is_none() && elem.key.as_slice() <= key)
+            .max_by(|a, b| {
+                a.key.cmp(&b.key).then_with(|| {
+                    (b.flags & NFT_SET_ELEM_INTERVAL_END)
+                        .cmp(&(a.flags & NFT_SET_ELEM_INTERVAL_END))
+                })
+            })?;
+        (elem.flags & NFT_SET_ELEM_INTERVAL_END == 0).then_some(elem)
+    }
+}
+
+/// An element of a set or a map.
+#[derive(Clone, Debug)]
+pub(crate) struct SetElem {
+    pub(crate) key: Vec<u8>,
+    /// The inclusive end of the range, if the element is a range.
+    pub(crate) key_end: Option<Vec<u8>>,
+    /// The data that the key maps to, if the set is a map.
+    pub(crate) data: Option<SetData>,
+    pub(crate) flags: u32,
+    pub(crate) userdata: Option<Vec<u8>>,
+}
@@ -0,0 +1,145 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The routing table.
+//!
+//! Each network namespace has a routing table (the main table in Linux), which determines the
+//! output interface and the next hop for each destination address. The networks of the interface
+//! addresses are always reachable directly, so they are not stored in the table. Instead, they are
+//! treated as implicit routes without gateways during lookups.
+
+use core::cmp::Reverse;
+
+use aster_bigtcp::{
+    iface::InterfaceFlags,
+    wire::{IpAddress, IpCidr},
+};
+
+use crate::{net::iface::Iface, prelude::*};
+
+/// `RTPROT_KERNEL` in Linux, which indicates a route installed by the kernel.
+pub(crate) const RTPROT_KERNEL: u8 = 2;
+/// `RTPROT_BOOT` in Linux, which indicates a route installed during boot.
+pub(crate) const RTPROT_BOOT: u8 = 3;
+
+/// A route in the routing table.
+#[derive(Clone)]
+pub(crate) struct Route {
+    /// The destination network.
+    pub(crate) dst: IpCidr,
+    /// The router to forward packets to, or `None` if the network is directly reachable.
+    pub(crate) gateway: Option<IpAddress>,
+    /// The output interface.
+    pub(crate) iface: Arc<Iface>,
+    /// The priority (also known as the metric). Lower values are preferred.
+    pub(crate) priority: u32,
+    /// The origin of the route (`RTPROT_*` in Linux).
+    pub(crate) protocol: u8,
+}
+
+impl Debug for Route {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Route")
+            .field("dst", &self.dst)
+            .field("gateway", &self.gateway)
+            .field("iface", &self.iface.name())
+            .field("priority", &self.priority)
+            .field("protocol", &self.protocol)
+            .finish()
+    }
+}
+
+pub(crate) struct RouteTable {
+    routes: Vec<Route>,
+}
+
+impl RouteTable {
+    pub(super) const fn new() -> Self {
+        Self { routes: Vec::new() }
+    }
+
+    /// Returns all the routes in the order of insertion.
+    pub(crate) fn routes(&self) -> &[Route] {
+        &self.routes
+    }
+
+    /// Returns the first route that satisfies the predicate.
+    pub(crate) fn find(&self, mut predicate: impl FnMut(&Route) -> bool) -> Option<&Route> {
+        self.routes.iter().find(|route| predicate(route))
+    }
+
+    /// Inserts a route into the table.
+    ///
+    /// The caller is responsible for checking whether a conflicting route exists.
+    pub(crate) fn insert(&mut self, route: Route) -> Result<()> {
+        // The interfaces deliver packets to the networks of their own addresses directly, so they
+        // only need to know about the routes via gateways.
+        if let Some(gateway) = route.gateway
+            && !route.iface.add_route(route.dst, gateway)
+        {
+            return_errno_with_message!(
+                Errno::ENOSPC,
+                "the interface has too many routes via gateways"
+            );
+        }
+
+        self.routes.push(route);
+        Ok(())
+    }
+
+    /// Removes the first route that satisfies the predicate.
+    pub(crate) fn remove(&mut self, mut predicate: impl FnMut(&Route) -> bool) -> Option<Route> {
+        let pos = self.routes.iter().position(|route| predicate(route))?;
+        let route = self.routes.remove(pos);
+        Self::unregister(&route);
+        Some(route)
+    }
+
+    /// Removes all the routes whose output interface is `iface`.
+    pub(super) fn remove_iface_routes(&mut self, iface: &Iface) {
+        self.routes.retain(|route| {
+            if route.iface.id() != iface.id() {
+                return true;
+            }
+            Self::unregister(route);
+            false
+        });
+    }
+
+    fn unregister(route: &Route) {
+        if let Some(gateway) = route.gateway {
+            let is_removed = route.iface.remove_route(route.dst, gateway);
+            debug_assert!(is_removed);
+        }
+    }
+
+    /// Looks up the output interface to reach the destination address.
+    ///
+    /// Like Linux, the route with the longest matching prefix is chosen, and routes with lower
+    /// priority values are preferred among the routes with the same prefix length. Interfaces
+    /// that are down are ignored.
+    pub(super) fn lookup(&self, dst_addr: &IpAddress, ifaces: &[Arc<Iface>]) -> Option<Arc<Iface>> {
+        let is_up = |iface: &Iface| iface.flags().contains(InterfaceFlags::UP);
+
+        let direct_routes = ifaces
+            .iter()
+            .filter(|iface| is_up(iface.as_ref()))
+            .flat_map(|iface| {
+                let cidrs: Vec<IpCidr> = match dst_addr {
+                    IpAddress::Ipv4(_) => iface.ipv4_cidrs().into_iter().map(Into::into).collect(),
+                    IpAddress::Ipv6(_) => iface.ipv6_cidrs().into_iter().map(Into::into).collect(),
+                };
+                cidrs.into_iter().map(move |cidr| (cidr, 0, iface))
+            });
+        let table_routes = self
+            .routes
+            .iter()
+            .filter(|route| is_up(route.iface.as_ref()))
+            .map(|route| (route.dst, route.priority, &route.iface));
+
+        direct_routes
+            .chain(table_routes)
+            .filter(|(cidr, _, _)| cidr.contains_addr(dst_addr))
+            .min_by_key(|(cidr, priority, _)| (Reverse(cidr.prefix_len()), *priority))
+            .map(|(_, _, iface)| iface.clone())
+    }
+}
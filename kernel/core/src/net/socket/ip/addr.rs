@@ -56,11 +56,103 @@ impl IpAddressFamily {
             IpAddressFamily::IPv6 => UNSPECIFIED_LOCAL_ENDPOINT_V6,
         }
     }
+
+    /// Converts the socket address to a local endpoint for a socket in this address family.
+    ///
+    /// For IPv6 sockets, IPv4-mapped IPv6 addresses are converted to IPv4 endpoints, unless
+    /// `is_v6only` is true (i.e., `IPV6_V6ONLY` is set), in which case they are rejected.
+    pub(super) fn local_endpoint(
+        &self,
+        socket_addr: SocketAddr,
+        is_v6only: bool,
+    ) -> Result<IpEndpoint> {
+        self.endpoint_from(socket_addr, is_v6only)?.ok_or_else(|| {
+            Error::with_message(
+                Errno::EINVAL,
+                "IPv4-mapped IPv6 addresses cannot be bound with IPV6_V6ONLY",
+            )
+        })
+    }
+
+    /// Converts the socket address to a remote endpoint for a socket in this address family.
+    ///
+    /// This is similar to [`Self::local_endpoint`], but rejected IPv4-mapped IPv6 addresses are
+    /// reported as unreachable.
+    pub(super) fn remote_endpoint(
+        &self,
+        socket_addr: SocketAddr,
+        is_v6only: bool,
+    ) -> Result<IpEndpoint> {
+        self.endpoint_from(socket_addr, is_v6only)?.ok_or_else(|| {
+            Error::with_message(
+                Errno::ENETUNREACH,
+                "IPv4-mapped IPv6 addresses are unreachable with IPV6_V6ONLY",
+            )
+        })
+    }
+
+    /// Converts the socket address to an endpoint.
+    ///
+    /// This method returns `None` if the socket address is an IPv4-mapped IPv6 address but
+    /// `is_v6only` is true.
+    fn endpoint_from(
+        &self,
+        socket_addr: SocketAddr,
+        is_v6only: bool,
+    ) -> Result<Option<IpEndpoint>> {
+        let endpoint = IpEndpoint::try_from(socket_addr)?;
+
+        match (self, endpoint.addr) {
+            (IpAddressFamily::IPv4, IpAddress::Ipv4(_)) => Ok(Some(endpoint)),
+            (IpAddressFamily::IPv6, IpAddress::Ipv6(addr)) => match addr.to_ipv4_mapped() {
+                None => Ok(Some(endpoint)),
+                Some(_) if is_v6only => Ok(None),
+                Some(ipv4_addr) => Ok(Some(IpEndpoint::new(
+                    IpAddress::Ipv4(ipv4_addr),
+                    endpoint.port,
+                ))),
+            },
+            _ => return_errno_with_message!(
+                Errno::EAFNOSUPPORT,
+                "the protocol family does not match the address family"
+            ),
+        }
+    }
+
+    /// Converts the endpoint to a socket address for a socket in this address family.
+    ///
+    /// For IPv6 sockets, IPv4 endpoints are reported as IPv4-mapped IPv6 addresses.
+    pub(super) fn socket_addr(&self, endpoint: IpEndpoint) -> SocketAddr {
+        match (self, endpoint.addr) {
+            (IpAddressFamily::IPv6, IpAddress::Ipv4(addr)) => {
+                SocketAddr::IPv6(addr.to_ipv6_mapped(), endpoint.port)
+            }
+            _ => endpoint.into(),
+        }
+    }
+}
+
+/// Checks whether the remote endpoint can be reached from the local endpoint.
+///
+/// An IPv6 socket bound to an IPv4-mapped IPv6 address can only communicate with IPv4 peers, and
+/// vice versa.
+pub(super) fn check_same_family(
+    local_endpoint: &IpEndpoint,
+    remote_endpoint: &IpEndpoint,
+) -> Result<()> {
+    if IpAddressFamily::from(local_endpoint.addr) != IpAddressFamily::from(remote_endpoint.addr) {
+        return_errno_with_message!(
+            Errno::ENETUNREACH,
+            "the remote address is not in the same family as the local address"
+        );
+    }
+
+    Ok(())
 }
 
-// Note: This does not handle IPv4-mapped IPv6 addresses. When `IPV6_V6ONLY` is set,
-// IPv4-mapped addresses are not permitted, so this function cannot be used to determine
-// whether such an address is acceptable.
+// Note: This does not handle IPv4-mapped IPv6 addresses. Use
+// `IpAddressFamily::{local_endpoint, remote_endpoint}` to convert socket addresses, which map
+// such addresses to IPv4 endpoints.
 impl From<IpAddress> for IpAddressFamily {
     fn from(addr: IpAddress) -> Self {
         match addr {
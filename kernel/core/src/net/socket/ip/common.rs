@@ -3,99 +3,69 @@
 use aster_bigtcp::{
     errors::BindError,
     iface::BindPortConfig,
-    wire::{IpAddress, IpEndpoint},
+    wire::{IpAddress, IpEndpoint, Ipv6Address, Ipv6Cidr},
 };
 
 use crate::{
-    net::{
-        iface::{Iface, iter_all_ifaces, loopback_iface, virtio_iface},
-        socket::util::check_port_privilege,
-    },
+    net::{iface::Iface, net_ns::NetNamespace, socket::util::check_port_privilege},
     prelude::*,
 };
 
-fn get_iface_to_bind(ip_addr: &IpAddress) -> Option<Arc<Iface>> {
+fn get_iface_to_bind(net_ns: &NetNamespace, ip_addr: &IpAddress) -> Option<Arc<Iface>> {
     match *ip_addr {
-        IpAddress::Ipv4(ipv4_addr) => iter_all_ifaces()
-            .find(|iface| {
-                iface
-                    .ipv4_cidr()
-                    .is_some_and(|cidr| cidr.address() == ipv4_addr)
-            })
-            .map(Clone::clone),
-        IpAddress::Ipv6(ipv6_addr) => iter_all_ifaces()
-            .find(|iface| {
-                iface
-                    .ipv6_cidr()
-                    .is_some_and(|cidr| cidr.address() == ipv6_addr)
-            })
-            .map(Clone::clone),
+        IpAddress::Ipv4(ipv4_addr) => net_ns.find_iface(|iface| {
+            iface
+                .ipv4_cidrs()
+                .iter()
+                .any(|cidr| cidr.address() == ipv4_addr)
+        }),
+        IpAddress::Ipv6(ipv6_addr) => net_ns.find_iface(|iface| {
+            iface
+                .ipv6_cidrs()
+                .iter()
+                .any(|cidr| cidr.address() == ipv6_addr)
+        }),
     }
 }
 
 /// Get a suitable iface to deal with sendto/connect request if the socket is not bound to an iface.
 /// If the remote address is the same as that of some iface, we will use the iface.
-/// Otherwise, we will use a default interface.
-fn get_ephemeral_iface(remote_ip_addr: &IpAddress) -> Arc<Iface> {
-    match remote_ip_addr {
-        IpAddress::Ipv4(remote_ipv4_addr) => {
-            if let Some(iface) = iter_all_ifaces().find(|iface| {
-                iface
-                    .ipv4_cidr()
-                    .is_some_and(|cidr| cidr.address() == *remote_ipv4_addr)
-            }) {
-                return iface.clone();
-            }
-
-            // FIXME: Instead of hardcoding the rules here, we should choose the
-            // default interface according to the routing table.
-            if let Some(virtio_iface) = virtio_iface() {
-                virtio_iface.clone()
-            } else {
-                loopback_iface().clone()
-            }
-        }
-        IpAddress::Ipv6(remote_ipv6_addr) => {
-            if let Some(iface) = iter_all_ifaces().find(|iface| {
-                iface
-                    .ipv6_cidr()
-                    .is_some_and(|cidr| cidr.address() == *remote_ipv6_addr)
-            }) {
-                return iface.clone();
-            }
-
-            // Fall back to an interface with an IPv6 address.
-            // Prefer virtio over loopback for external traffic.
-            if let Some(virtio_iface) = virtio_iface()
-                && virtio_iface.ipv6_cidr().is_some()
-            {
-                return virtio_iface.clone();
-            }
-
-            loopback_iface().clone()
-        }
+/// Otherwise, we will use the iface chosen by the routing table.
+fn get_ephemeral_iface(net_ns: &NetNamespace, remote_ip_addr: &IpAddress) -> Arc<Iface> {
+    if let Some(iface) = get_iface_to_bind(net_ns, remote_ip_addr) {
+        return iface;
     }
+
+    net_ns.route_iface(remote_ip_addr)
 }
 
 pub(super) fn resolve_bind_iface_and_config(
+    net_ns: &NetNamespace,
     endpoint: &IpEndpoint,
     can_reuse: bool,
 ) -> Result<(Arc<Iface>, BindPortConfig)> {
     check_port_privilege(endpoint.port)?;
 
-    let iface = match get_iface_to_bind(&endpoint.addr) {
-        Some(iface) => iface,
+    let iface = resolve_bind_iface(net_ns, &endpoint.addr)?;
+    let bind_port_config = BindPortConfig::new(*endpoint, can_reuse);
+
+    Ok((iface, bind_port_config))
+}
+
+/// Resolves the iface to bind to according to the local IP address.
+///
+/// Unlike [`resolve_bind_iface_and_config`], this function does not check the port privilege,
+/// which is useful for sockets whose ports are not transport-layer ports.
+pub(super) fn resolve_bind_iface(net_ns: &NetNamespace, ip_addr: &IpAddress) -> Result<Arc<Iface>> {
+    match get_iface_to_bind(net_ns, ip_addr) {
+        Some(iface) => Ok(iface),
         None => {
             return_errno_with_message!(
                 Errno::EADDRNOTAVAIL,
                 "the address is not available from the local machine"
             );
         }
-    };
-
-    let bind_port_config = BindPortConfig::new(*endpoint, can_reuse);
-
-    Ok((iface, bind_port_config))
+    }
 }
 
 impl From<BindError> for Error {
@@ -111,16 +81,44 @@ impl From<BindError> for Error {
     }
 }
 
-pub(super) fn get_ephemeral_endpoint(remote_endpoint: &IpEndpoint) -> Option<IpEndpoint> {
-    let iface = get_ephemeral_iface(&remote_endpoint.addr);
+pub(super) fn get_ephemeral_endpoint(
+    net_ns: &NetNamespace,
+    remote_endpoint: &IpEndpoint,
+) -> Option<IpEndpoint> {
+    let iface = get_ephemeral_iface(net_ns, &remote_endpoint.addr);
     match remote_endpoint.addr {
-        IpAddress::Ipv4(_) => {
-            let ipv4_cidr = iface.ipv4_cidr()?;
+        IpAddress::Ipv4(remote_addr) => {
+            // Prefer an address in the same network as the remote address.
+            let cidrs = iface.ipv4_cidrs();
+            let ipv4_cidr = cidrs
+                .iter()
+                .find(|cidr| cidr.contains_addr(&remote_addr))
+                .or(cidrs.first())?;
             Some(IpEndpoint::new(IpAddress::Ipv4(ipv4_cidr.address()), 0))
         }
-        IpAddress::Ipv6(_) => {
-            let ipv6_cidr = iface.ipv6_cidr()?;
-            Some(IpEndpoint::new(IpAddress::Ipv6(ipv6_cidr.address()), 0))
+        IpAddress::Ipv6(remote_addr) => {
+            let ipv6_addr = select_ipv6_source_addr(&iface.ipv6_cidrs(), &remote_addr)?;
+            Some(IpEndpoint::new(IpAddress::Ipv6(ipv6_addr), 0))
         }
     }
 }
+
+/// Selects the source address to reach the remote IPv6 address.
+///
+/// This is a simplified version of the source address selection algorithm in RFC 6724, which
+/// prefers an address in the same prefix as the remote address, and then an address in the same
+/// scope.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc6724#section-5>.
+fn select_ipv6_source_addr(cidrs: &[Ipv6Cidr], remote_addr: &Ipv6Address) -> Option<Ipv6Address> {
+    if let Some(cidr) = cidrs.iter().find(|cidr| cidr.contains_addr(remote_addr)) {
+        return Some(cidr.address());
+    }
+
+    let is_link_local = remote_addr.is_unicast_link_local();
+    cidrs
+        .iter()
+        .find(|cidr| cidr.address().is_unicast_link_local() == is_link_local)
+        .or(cidrs.first())
+        .map(|cidr| cidr.address())
+}
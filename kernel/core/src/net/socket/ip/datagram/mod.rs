@@ -4,15 +4,19 @@ use aster_bigtcp::wire::IpEndpoint;
 use bound::BoundDatagram;
 use unbound::{BindOptions, UnboundDatagram};
 
-use super::{addr::UNSPECIFIED_LOCAL_ENDPOINT, ioctl::ipv4_ioctl};
+use super::{
+    addr::{IpAddressFamily, check_same_family},
+    ioctl::ipv4_ioctl,
+};
 use crate::{
     events::IoEvents,
     fs::file::FileCommon,
     net::{
         iface::is_broadcast_endpoint,
+        net_ns::NetNamespace,
         socket::{
             Socket,
-            ip::options::{IpOptionSet, SetIpLevelOption},
+            ip::options::{IpOptionSet, Ipv6OptionSet, SetIpLevelOption, SetIpv6LevelOption},
             new_socket_common,
             options::{Error as SocketError, SocketOption, macros::sock_option_mut},
             private::SocketPrivate,
@@ -37,6 +41,9 @@ mod unbound;
 pub(crate) struct DatagramSocket {
     // Lock order: `inner` first, `options` second
     inner: RwMutex<Inner<UnboundDatagram, BoundDatagram>>,
+    family: IpAddressFamily,
+    /// The network namespace in which the socket was created.
+    net_ns: Arc<NetNamespace>,
     options: RwLock<OptionSet>,
     timeouts: SocketTimeouts,
 
@@ -48,6 +55,7 @@ pub(crate) struct DatagramSocket {
 struct OptionSet {
     socket: SocketOptionSet,
     ip: IpOptionSet,
+    ipv6: Ipv6OptionSet,
     // TODO: UDP option set
 }
 
@@ -55,15 +63,22 @@ impl OptionSet {
     fn new() -> Self {
         let socket = SocketOptionSet::new_udp();
         let ip = IpOptionSet::new_udp();
-        OptionSet { socket, ip }
+        let ipv6 = Ipv6OptionSet::new();
+        OptionSet { socket, ip, ipv6 }
     }
 }
 
 impl DatagramSocket {
-    pub(crate) fn new(is_nonblocking: bool) -> Arc<Self> {
-        let unbound_datagram = UnboundDatagram::new();
+    pub(crate) fn new(
+        is_nonblocking: bool,
+        family: IpAddressFamily,
+        net_ns: Arc<NetNamespace>,
+    ) -> Arc<Self> {
+        let unbound_datagram = UnboundDatagram::new(net_ns.clone());
         Arc::new(Self {
             inner: RwMutex::new(Inner::Unbound(unbound_datagram)),
+            family,
+            net_ns,
             options: RwLock::new(OptionSet::new()),
             timeouts: SocketTimeouts::new(),
             pollee: Pollee::new(),
@@ -80,7 +95,7 @@ impl DatagramSocket {
             .inner
             .read()
             .try_recv(writer, flags)
-            .map(|(output, remote_endpoint)| (output, remote_endpoint.into()))?;
+            .map(|(output, remote_endpoint)| (output, self.family.socket_addr(remote_endpoint)))?;
         self.pollee.invalidate();
 
         Ok(result)
@@ -107,6 +122,7 @@ impl DatagramSocket {
                     .bind_ephemeral(remote_endpoint, &self.pollee)
             },
             |bound_datagram, remote_endpoint| {
+                check_same_family(&bound_datagram.local_endpoint(), remote_endpoint)?;
                 let sent_bytes = bound_datagram.try_send(reader, remote_endpoint, flags)?;
                 let iface_to_poll = bound_datagram.iface().clone();
                 Ok((sent_bytes, iface_to_poll))
@@ -132,10 +148,19 @@ impl SocketPrivate for DatagramSocket {
         self.common.is_nonblocking()
     }
 
+    fn net_ns(&self) -> &Arc<NetNamespace> {
+        &self.net_ns
+    }
+
     fn protocol_ioctl(&self, raw_ioctl: RawIoctl) -> Result<i32> {
         // Handle common IPv4/IPv6 ioctl commands.
-        // TODO: Handle IPv6 ioctls once UDP sockets support IPv6.
-        ipv4_ioctl(raw_ioctl)
+        match self.family {
+            IpAddressFamily::IPv4 => ipv4_ioctl(&self.net_ns, raw_ioctl),
+            IpAddressFamily::IPv6 => {
+                // TODO: Add support for IPv6 ioctl commands.
+                return_errno_with_message!(Errno::ENOTTY, "the socket ioctl command is unknown")
+            }
+        }
 
         // Handle ioctl commands that require UDP-specific handling.
     }
@@ -143,8 +168,13 @@ impl SocketPrivate for DatagramSocket {
 
 impl Socket for DatagramSocket {
     fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
-        let can_reuse = self.options.read().socket.reuse_addr();
+        let (endpoint, can_reuse) = {
+            let options = self.options.read();
+            let endpoint = self
+                .family
+                .local_endpoint(socket_addr, options.ipv6.v6only())?;
+            (endpoint, options.socket.reuse_addr())
+        };
 
         self.inner
             .write()
@@ -152,8 +182,13 @@ impl Socket for DatagramSocket {
     }
 
     fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
-        let can_broadcast = self.options.read().socket.broadcast();
+        let (endpoint, can_broadcast) = {
+            let options = self.options.read();
+            let endpoint = self
+                .family
+                .remote_endpoint(socket_addr, options.ipv6.v6only())?;
+            (endpoint, options.socket.broadcast())
+        };
         if !can_broadcast && is_broadcast_endpoint(&endpoint) {
             return_errno_with_message!(
                 Errno::EACCES,
@@ -161,7 +196,11 @@ impl Socket for DatagramSocket {
             );
         }
 
-        self.inner.write().connect(&endpoint, &self.pollee)
+        let mut inner = self.inner.write();
+        if let Inner::Bound(bound_datagram) = &*inner {
+            check_same_family(&bound_datagram.local_endpoint(), &endpoint)?;
+        }
+        inner.connect(&endpoint, &self.pollee)
     }
 
     fn addr(&self) -> Result<SocketAddr> {
@@ -169,9 +208,9 @@ impl Socket for DatagramSocket {
             .inner
             .read()
             .addr()
-            .unwrap_or(UNSPECIFIED_LOCAL_ENDPOINT);
+            .unwrap_or(self.family.unspecified_endpoint());
 
-        Ok(endpoint.into())
+        Ok(self.family.socket_addr(endpoint))
     }
 
     fn peer_addr(&self) -> Result<SocketAddr> {
@@ -180,7 +219,7 @@ impl Socket for DatagramSocket {
                 Error::with_message(Errno::ENOTCONN, "the socket is not connected")
             })?;
 
-        Ok(endpoint.into())
+        Ok(self.family.socket_addr(endpoint))
     }
 
     fn sendmsg(
@@ -200,7 +239,10 @@ impl Socket for DatagramSocket {
         } = message_header;
 
         let endpoint = match addr {
-            Some(addr) => Some(addr.try_into()?),
+            Some(addr) => {
+                let is_v6only = self.options.read().ipv6.v6only();
+                Some(self.family.remote_endpoint(addr, is_v6only)?)
+            }
             None => None,
         };
 
@@ -268,7 +310,18 @@ impl Socket for DatagramSocket {
         }
 
         // Deal with IP-level options
-        options.ip.get_option(option)
+        match options.ip.get_option(option) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        // Deal with IPv6-level options
+        match self.family {
+            IpAddressFamily::IPv4 => {
+                return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option is unknown")
+            }
+            IpAddressFamily::IPv6 => options.ipv6.get_option(option),
+        }
     }
 
     fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
@@ -282,7 +335,17 @@ impl Socket for DatagramSocket {
         {
             Err(err) if err.error() == Errno::ENOPROTOOPT => {
                 // Deal with IP-level options
-                options.ip.set_option(option, &*inner)?
+                match options.ip.set_option(option, &*inner) {
+                    Err(err)
+                        if err.error() == Errno::ENOPROTOOPT
+                            && self.family == IpAddressFamily::IPv6 =>
+                    {
+                        // Deal with IPv6-level options
+                        options.ipv6.set_option(option, &*inner)?
+                    }
+                    Err(err) => return Err(err),
+                    Ok(need_iface_poll) => need_iface_poll,
+                }
             }
             Err(err) => return Err(err),
             Ok(need_iface_poll) => need_iface_poll,
@@ -346,3 +409,16 @@ impl SetIpLevelOption for Inner<UnboundDatagram, BoundDatagram> {
         );
     }
 }
+
+impl SetIpv6LevelOption for Inner<UnboundDatagram, BoundDatagram> {
+    fn set_v6only(&self, _v6only: bool) -> Result<()> {
+        if let Inner::Bound(_) = self {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "IPV6_V6ONLY cannot be set after the socket is bound"
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -3,7 +3,7 @@
 use aster_bigtcp::{iface::InterfaceFlags, wire::Ipv4Address};
 
 use crate::{
-    net::socket::util::ioctl::CIfReq,
+    net::{net_ns::NetNamespace, socket::util::ioctl::CIfReq},
     prelude::*,
     util::ioctl::{RawIoctl, dispatch_ioctl},
 };
@@ -19,13 +19,13 @@ mod ioctl_defs {
     pub(super) type GetIfNetmask = ioc!(SIOCGIFNETMASK, 0x891B, InOutData<CIfReq>);
 }
 
-pub(super) fn ipv4_ioctl(raw_ioctl: RawIoctl) -> Result<i32> {
+pub(super) fn ipv4_ioctl(net_ns: &NetNamespace, raw_ioctl: RawIoctl) -> Result<i32> {
     use ioctl_defs::*;
 
     dispatch_ioctl!(match raw_ioctl {
         cmd @ GetIfAddr => {
             let mut ifreq = cmd.read()?;
-            let iface = ifreq.get_iface_by_name()?;
+            let iface = ifreq.get_iface_by_name(net_ns)?;
             let ipv4_addr = iface
                 .ipv4_cidr()
                 .ok_or_else(|| Error::with_message(Errno::EADDRNOTAVAIL, "no IPv4 address found"))?
@@ -36,7 +36,7 @@ pub(super) fn ipv4_ioctl(raw_ioctl: RawIoctl) -> Result<i32> {
         }
         cmd @ GetIfDstAddr => {
             let mut ifreq = cmd.read()?;
-            let iface = ifreq.get_iface_by_name()?;
+            let iface = ifreq.get_iface_by_name(net_ns)?;
             // Asterinas does not yet support point-to-point interfaces,
             // so we report the local IPv4 address instead, consistent with Linux's behavior.
             let ipv4_addr = iface
@@ -49,7 +49,7 @@ pub(super) fn ipv4_ioctl(raw_ioctl: RawIoctl) -> Result<i32> {
         }
         cmd @ GetIfBrdAddr => {
             let mut ifreq = cmd.read()?;
-            let iface = ifreq.get_iface_by_name()?;
+            let iface = ifreq.get_iface_by_name(net_ns)?;
             let broadcast_addr = if iface.flags().contains(InterfaceFlags::BROADCAST)
                 && let Some(broadcast_addr) = iface.broadcast_addr()
             {
@@ -63,7 +63,7 @@ pub(super) fn ipv4_ioctl(raw_ioctl: RawIoctl) -> Result<i32> {
         }
         cmd @ GetIfNetmask => {
             let mut ifreq = cmd.read()?;
-            let iface = ifreq.get_iface_by_name()?;
+            let iface = ifreq.get_iface_by_name(net_ns)?;
             let netmask = iface
                 .ipv4_cidr()
                 .ok_or_else(|| Error::with_message(Errno::EADDRNOTAVAIL, "no IPv4 address found"))?
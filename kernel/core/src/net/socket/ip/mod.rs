@@ -5,10 +5,12 @@ mod common;
 mod datagram;
 mod ioctl;
 pub(crate) mod options;
+mod raw;
 mod stream;
 
 pub(crate) use addr::IpAddressFamily;
 pub(crate) use datagram::DatagramSocket;
 pub(in crate::net) use datagram::observer::DatagramObserver;
+pub(crate) use raw::RawSocket;
 pub(in crate::net) use stream::observer::StreamObserver;
 pub(crate) use stream::{StreamSocket, options as stream_options};
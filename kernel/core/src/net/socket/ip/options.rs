@@ -45,6 +45,15 @@ impl IpOptionSet {
         }
     }
 
+    pub(super) const fn new_raw() -> Self {
+        Self {
+            tos: 0,
+            ttl: IpTtl(None),
+            hdrincl: false,
+            recverr: false,
+        }
+    }
+
     pub(super) fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
         sock_option_mut!(match option {
             ip_tos @ Tos => {
@@ -132,3 +141,59 @@ impl IpTtl {
 pub(super) trait SetIpLevelOption {
     fn set_hdrincl(&self, _hdrincl: bool) -> Result<()>;
 }
+
+/// IPv6-level socket options.
+#[derive(Clone, Copy, CopyGetters, Debug, Setters)]
+#[get_copy = "pub"]
+#[set = "pub"]
+pub(super) struct Ipv6OptionSet {
+    v6only: bool,
+}
+
+impl Ipv6OptionSet {
+    pub(super) const fn new() -> Self {
+        // Linux defaults to `net.ipv6.bindv6only = 0`, so IPv6 sockets can also be used to
+        // communicate with IPv4 peers via IPv4-mapped IPv6 addresses.
+        Self { v6only: false }
+    }
+
+    pub(super) fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        sock_option_mut!(match option {
+            ipv6_v6only @ V6only => {
+                let v6only = self.v6only();
+                ipv6_v6only.set(v6only);
+            }
+            _ => return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option is unknown"),
+        });
+
+        Ok(())
+    }
+
+    pub(super) fn set_option(
+        &mut self,
+        option: &dyn SocketOption,
+        socket: &dyn SetIpv6LevelOption,
+    ) -> Result<NeedIfacePoll> {
+        sock_option_ref!(match option {
+            ipv6_v6only @ V6only => {
+                let v6only = ipv6_v6only.get().unwrap();
+                socket.set_v6only(*v6only)?;
+                self.set_v6only(*v6only);
+            }
+            _ => return_errno_with_message!(
+                Errno::ENOPROTOOPT,
+                "the socket option to be set is unknown"
+            ),
+        });
+
+        Ok(NeedIfacePoll::FALSE)
+    }
+}
+
+impl_socket_options!(
+    pub(crate) struct V6only(bool);
+);
+
+pub(super) trait SetIpv6LevelOption {
+    fn set_v6only(&self, _v6only: bool) -> Result<()>;
+}
@@ -0,0 +1,121 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::{
+    errors::raw::{RecvError, SendError},
+    wire::IpEndpoint,
+};
+
+use crate::{
+    events::IoEvents,
+    net::{
+        iface::{Iface, RawSocket},
+        socket::{
+            ip::options::IpOptionSet,
+            util::{RecvFlags, RecvOutput},
+        },
+    },
+    prelude::*,
+    util::{MultiRead, MultiWrite},
+};
+
+pub(super) struct BoundRaw {
+    bound_socket: RawSocket,
+    remote_endpoint: Option<IpEndpoint>,
+}
+
+impl BoundRaw {
+    pub(super) fn new(bound_socket: RawSocket) -> Self {
+        Self {
+            bound_socket,
+            remote_endpoint: None,
+        }
+    }
+
+    pub(super) fn iface(&self) -> &Arc<Iface> {
+        self.bound_socket.iface()
+    }
+
+    pub(super) fn local_endpoint(&self) -> IpEndpoint {
+        self.bound_socket.local_endpoint().unwrap()
+    }
+
+    pub(super) fn remote_endpoint(&self) -> Option<&IpEndpoint> {
+        self.remote_endpoint.as_ref()
+    }
+
+    pub(super) fn set_remote_endpoint(&mut self, endpoint: &IpEndpoint) {
+        self.remote_endpoint = Some(*endpoint);
+    }
+
+    pub(super) fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: RecvFlags,
+    ) -> Result<(RecvOutput, IpEndpoint)> {
+        let (data, src_addr) = match self.bound_socket.recv(flags.receive_behavior()) {
+            Ok(packet) => packet,
+            Err(RecvError::Exhausted) => {
+                return_errno_with_message!(Errno::EAGAIN, "the receive buffer is empty")
+            }
+        };
+
+        let copied_len = data.len().min(writer.sum_lens());
+        writer.write(&mut VmReader::from(&data[..copied_len]))?;
+
+        let output = RecvOutput::new_for_packet(flags, copied_len, data.len());
+        // Like Linux, the port of the source address is always zero.
+        Ok((output, IpEndpoint::new(src_addr, 0)))
+    }
+
+    pub(super) fn try_send(
+        &self,
+        reader: &mut dyn MultiRead,
+        remote: &IpEndpoint,
+        ip_options: &IpOptionSet,
+    ) -> Result<usize> {
+        let len = reader.sum_lens();
+        if len > u16::MAX as usize {
+            return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+        }
+
+        let mut data = vec![0; len];
+        reader.read(&mut VmWriter::from(data.as_mut_slice()))?;
+
+        let result = self.bound_socket.send(
+            &data,
+            remote.addr,
+            ip_options.ttl().get(),
+            ip_options.hdrincl(),
+        );
+
+        match result {
+            Ok(()) => Ok(len),
+            Err(SendError::Unaddressable) => {
+                return_errno_with_message!(Errno::EINVAL, "the destination address is invalid");
+            }
+            Err(SendError::InvalidPacket) => {
+                return_errno_with_message!(Errno::EINVAL, "the packet is invalid");
+            }
+            Err(SendError::BufferFull) => {
+                return_errno_with_message!(Errno::EAGAIN, "the send buffer is full");
+            }
+            Err(SendError::TooLarge) => {
+                return_errno_with_message!(Errno::EMSGSIZE, "the message is too large");
+            }
+        }
+    }
+
+    pub(super) fn check_io_events(&self) -> IoEvents {
+        let mut events = IoEvents::empty();
+
+        if self.bound_socket.can_recv() {
+            events |= IoEvents::IN;
+        }
+
+        if self.bound_socket.can_send() {
+            events |= IoEvents::OUT;
+        }
+
+        events
+    }
+}
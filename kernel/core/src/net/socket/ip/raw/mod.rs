@@ -0,0 +1,513 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module defines raw IP sockets and ICMP echo sockets.
+//!
+//! Raw IP sockets (i.e., `SOCK_RAW` sockets in the `AF_INET` or `AF_INET6` domains) send and
+//! receive the packets of a single IP protocol. IPv4 raw sockets receive the packets with their
+//! IP headers, and can send packets with user-provided IP headers if `IP_HDRINCL` is set. IPv6
+//! raw sockets never see the IP headers.
+//!
+//! ICMP echo sockets (i.e., `SOCK_DGRAM` sockets with `IPPROTO_ICMP` or `IPPROTO_ICMPV6`, a.k.a.
+//! ping sockets) send ICMP echo requests and receive the matching echo replies. Unlike raw
+//! sockets, they do not require `CAP_NET_RAW`. Instead, only the groups in the
+//! `net.ipv4.ping_group_range` sysctl can create them.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man7/raw.7.html>,
+//! <https://man7.org/linux/man-pages/man7/icmp.7.html>
+
+use aster_bigtcp::{iface::BindPortConfig, socket::RawSocketKind, wire::IpEndpoint};
+use bound::BoundRaw;
+
+use super::{
+    DatagramObserver, IpAddressFamily,
+    common::{get_ephemeral_endpoint, resolve_bind_iface},
+    ioctl::ipv4_ioctl,
+    options::{IpOptionSet, SetIpLevelOption},
+};
+use crate::{
+    events::IoEvents,
+    fs::file::FileCommon,
+    net::{
+        iface::{RawSocket as BoundRawSocket, is_broadcast_endpoint},
+        net_ns::NetNamespace,
+        socket::{
+            Socket, new_socket_common,
+            options::{Error as SocketError, SocketOption, macros::sock_option_mut},
+            private::SocketPrivate,
+            util::{
+                MessageHeader, RecvFlags, RecvOutput, SendFlags, SocketAddr,
+                options::{
+                    GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet, SocketTimeouts,
+                },
+            },
+        },
+    },
+    prelude::*,
+    process::{
+        Gid,
+        credentials::capabilities::CapSet,
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable, Pollee},
+    },
+    security::lsm::hooks as lsm_hooks,
+    util::{
+        MultiRead, MultiWrite,
+        ioctl::RawIoctl,
+        net::{Protocol, SockType},
+    },
+};
+
+mod bound;
+
+/// A raw IP socket or an ICMP echo socket.
+pub(crate) struct RawSocket {
+    // Lock order: `bound` first, `options` second
+    bound: RwMutex<Option<BoundRaw>>,
+    options: RwLock<OptionSet>,
+    kind: RawSocketKind,
+    family: IpAddressFamily,
+    /// The IP protocol of a raw socket, which is unused for ICMP echo sockets.
+    protocol: u8,
+    /// The network namespace in which the socket was created.
+    net_ns: Arc<NetNamespace>,
+    timeouts: SocketTimeouts,
+
+    pollee: Pollee,
+    common: FileCommon,
+}
+
+#[derive(Clone, Debug)]
+struct OptionSet {
+    socket: SocketOptionSet,
+    ip: IpOptionSet,
+}
+
+impl RawSocket {
+    /// Creates a raw socket for the IP protocol.
+    pub(crate) fn new_raw(
+        is_nonblocking: bool,
+        family: IpAddressFamily,
+        protocol: u8,
+        net_ns: Arc<NetNamespace>,
+    ) -> Result<Arc<Self>> {
+        // Linux does not support raw sockets that receive all IP protocols, except for packet
+        // sockets.
+        if protocol == 0 {
+            return_errno_with_message!(
+                Errno::EPROTONOSUPPORT,
+                "raw sockets require a specific IP protocol"
+            );
+        }
+
+        lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+            net_ns.owner().as_ref(),
+            current_thread!().as_posix_thread().unwrap(),
+            CapSet::NET_RAW,
+        ))?;
+
+        // Like Linux, `IPPROTO_RAW` implies `IP_HDRINCL` for IPv4 raw sockets.
+        let hdrincl = family == IpAddressFamily::IPv4 && protocol == Protocol::IPPROTO_RAW as u8;
+
+        Ok(Self::new(
+            is_nonblocking,
+            RawSocketKind::Raw,
+            family,
+            protocol,
+            hdrincl,
+            net_ns,
+        ))
+    }
+
+    /// Creates an ICMP echo socket.
+    pub(crate) fn new_ping(
+        is_nonblocking: bool,
+        family: IpAddressFamily,
+        net_ns: Arc<NetNamespace>,
+    ) -> Result<Arc<Self>> {
+        check_ping_group(&net_ns)?;
+
+        Ok(Self::new(
+            is_nonblocking,
+            RawSocketKind::Ping,
+            family,
+            0,
+            false,
+            net_ns,
+        ))
+    }
+
+    fn new(
+        is_nonblocking: bool,
+        kind: RawSocketKind,
+        family: IpAddressFamily,
+        protocol: u8,
+        hdrincl: bool,
+        net_ns: Arc<NetNamespace>,
+    ) -> Arc<Self> {
+        let mut ip = IpOptionSet::new_raw();
+        ip.set_hdrincl(hdrincl);
+
+        Arc::new(Self {
+            bound: RwMutex::new(None),
+            options: RwLock::new(OptionSet {
+                socket: SocketOptionSet::new_raw(),
+                ip,
+            }),
+            kind,
+            family,
+            protocol,
+            net_ns,
+            timeouts: SocketTimeouts::new(),
+            pollee: Pollee::new(),
+            common: new_socket_common(is_nonblocking),
+        })
+    }
+
+    fn check_family(&self, endpoint: &IpEndpoint) -> Result<()> {
+        if IpAddressFamily::from(endpoint.addr) != self.family {
+            return_errno_with_message!(
+                Errno::EAFNOSUPPORT,
+                "the protocol family does not match the address family"
+            );
+        }
+
+        Ok(())
+    }
+
+    fn new_bound(&self, endpoint: &IpEndpoint) -> Result<BoundRaw> {
+        let iface = resolve_bind_iface(&self.net_ns, &endpoint.addr)?;
+
+        // Raw sockets are bound to their IP protocols, while ICMP echo sockets are bound to their
+        // echo identifiers, which are specified as the ports.
+        let bound_port = match self.kind {
+            RawSocketKind::Raw => iface.bind_raw(endpoint.addr, self.protocol)?,
+            RawSocketKind::Ping => iface.bind_icmp(BindPortConfig::new(*endpoint, false))?,
+        };
+        let bound_socket = BoundRawSocket::new_bind(
+            bound_port,
+            self.kind,
+            DatagramObserver::new(self.pollee.clone()),
+        );
+
+        Ok(BoundRaw::new(bound_socket))
+    }
+
+    fn bind_ephemeral(&self, remote_endpoint: &IpEndpoint) -> Result<()> {
+        let mut bound = self.bound.write();
+        if bound.is_some() {
+            return Ok(());
+        }
+
+        let endpoint = get_ephemeral_endpoint(&self.net_ns, remote_endpoint).ok_or_else(|| {
+            Error::with_message(
+                Errno::EADDRNOTAVAIL,
+                "no interface has an address for the specified family",
+            )
+        })?;
+        *bound = Some(self.new_bound(&endpoint)?);
+
+        Ok(())
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: RecvFlags,
+    ) -> Result<(RecvOutput, SocketAddr)> {
+        // TODO: Receive packets even if the socket is not bound. Currently, the socket is not
+        // registered to any interfaces before it is bound.
+        let result = match self.bound.read().as_ref() {
+            Some(bound_raw) => bound_raw.try_recv(writer, flags)?,
+            None => return_errno_with_message!(Errno::EAGAIN, "the socket is not bound"),
+        };
+        self.pollee.invalidate();
+
+        Ok((result.0, result.1.into()))
+    }
+
+    fn try_send(&self, reader: &mut dyn MultiRead, remote: Option<&IpEndpoint>) -> Result<usize> {
+        if self.bound.read().is_none() {
+            let remote_endpoint = remote.ok_or_else(|| {
+                Error::with_message(
+                    Errno::EDESTADDRREQ,
+                    "the destination address is not specified",
+                )
+            })?;
+            self.bind_ephemeral(remote_endpoint)?;
+        }
+
+        let bound = self.bound.read();
+        // The socket cannot be unbound once it has been bound.
+        let bound_raw = bound.as_ref().unwrap();
+
+        let remote_endpoint = remote
+            .or_else(|| bound_raw.remote_endpoint())
+            .ok_or_else(|| {
+                Error::with_message(
+                    Errno::EDESTADDRREQ,
+                    "the destination address is not specified",
+                )
+            })?;
+
+        let ip_options = self.options.read().ip;
+        let sent_bytes = bound_raw.try_send(reader, remote_endpoint, &ip_options)?;
+        let iface_to_poll = bound_raw.iface().clone();
+        drop(bound);
+
+        self.pollee.invalidate();
+        iface_to_poll.poll();
+
+        Ok(sent_bytes)
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        match self.bound.read().as_ref() {
+            Some(bound_raw) => bound_raw.check_io_events(),
+            None => IoEvents::OUT,
+        }
+    }
+}
+
+/// Checks whether the current thread can create ICMP echo sockets.
+fn check_ping_group(net_ns: &NetNamespace) -> Result<()> {
+    let (low, high) = net_ns.ping_group_range();
+    let is_in_range = |gid: &Gid| low <= *gid && *gid <= high;
+
+    let credentials = current_thread!().as_posix_thread().unwrap().credentials();
+    if is_in_range(&credentials.egid()) || credentials.groups().iter().any(is_in_range) {
+        return Ok(());
+    }
+
+    return_errno_with_message!(
+        Errno::EACCES,
+        "the groups are not allowed to create ICMP echo sockets"
+    );
+}
+
+impl Pollable for RawSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl SocketPrivate for RawSocket {
+    fn is_nonblocking(&self) -> bool {
+        self.common.is_nonblocking()
+    }
+
+    fn net_ns(&self) -> &Arc<NetNamespace> {
+        &self.net_ns
+    }
+
+    fn protocol_ioctl(&self, raw_ioctl: RawIoctl) -> Result<i32> {
+        // Handle common IPv4/IPv6 ioctl commands.
+        match self.family {
+            IpAddressFamily::IPv4 => ipv4_ioctl(&self.net_ns, raw_ioctl),
+            IpAddressFamily::IPv6 => {
+                // TODO: Add support for IPv6 ioctl commands.
+                return_errno_with_message!(Errno::ENOTTY, "the socket ioctl command is unknown")
+            }
+        }
+    }
+}
+
+impl Socket for RawSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let endpoint = socket_addr.try_into()?;
+        self.check_family(&endpoint)?;
+
+        let mut bound = self.bound.write();
+        if bound.is_some() {
+            return_errno_with_message!(Errno::EINVAL, "the socket is already bound to an address");
+        }
+        *bound = Some(self.new_bound(&endpoint)?);
+
+        Ok(())
+    }
+
+    fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
+        let endpoint = socket_addr.try_into()?;
+        self.check_family(&endpoint)?;
+
+        let can_broadcast = self.options.read().socket.broadcast();
+        if !can_broadcast && is_broadcast_endpoint(&endpoint) {
+            return_errno_with_message!(
+                Errno::EACCES,
+                "connecting to a broadcast address without SO_BROADCAST is not allowed"
+            );
+        }
+
+        self.bind_ephemeral(&endpoint)?;
+        self.bound
+            .write()
+            .as_mut()
+            .unwrap()
+            .set_remote_endpoint(&endpoint);
+
+        Ok(())
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        let endpoint = self
+            .bound
+            .read()
+            .as_ref()
+            .map(BoundRaw::local_endpoint)
+            .unwrap_or_else(|| self.family.unspecified_endpoint());
+
+        Ok(endpoint.into())
+    }
+
+    fn peer_addr(&self) -> Result<SocketAddr> {
+        let bound = self.bound.read();
+        let endpoint = bound
+            .as_ref()
+            .and_then(BoundRaw::remote_endpoint)
+            .ok_or_else(|| Error::with_message(Errno::ENOTCONN, "the socket is not connected"))?;
+
+        Ok((*endpoint).into())
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let endpoint = match addr {
+            Some(addr) => Some(addr.try_into()?),
+            None => None,
+        };
+
+        if let Some(endpoint) = endpoint.as_ref() {
+            self.check_family(endpoint)?;
+
+            let can_broadcast = self.options.read().socket.broadcast();
+            if !can_broadcast && is_broadcast_endpoint(endpoint) {
+                return_errno_with_message!(
+                    Errno::EACCES,
+                    "sending to a broadcast address without SO_BROADCAST is not allowed"
+                );
+            }
+        }
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        // TODO: Block if the send buffer is full
+        self.try_send(reader, endpoint.as_ref())
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: RecvFlags,
+    ) -> Result<(RecvOutput, MessageHeader)> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let (output, peer_addr) =
+            self.block_on(IoEvents::IN, self.timeouts.recv_timeout(), || {
+                self.try_recv(writer, flags)
+            })?;
+
+        // TODO: Receive control message
+
+        let message_header = MessageHeader::new(Some(peer_addr), Vec::new());
+
+        Ok((output, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        sock_option_mut!(match option {
+            socket_errors @ SocketError => {
+                // TODO: Support socket errors for raw sockets
+                socket_errors.set(None);
+                return Ok(());
+            }
+            _ => (),
+        });
+
+        let options = self.options.read();
+
+        // Deal with socket-level options
+        match options.socket.get_option(option, self) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res,
+        }
+
+        // Deal with IP-level options
+        options.ip.get_option(option)
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        let mut options = self.options.write();
+
+        // Deal with socket-level options
+        match options.socket.set_option(option, self) {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res.map(|_need_iface_poll| ()),
+        }
+
+        // Deal with IP-level options
+        options
+            .ip
+            .set_option(option, self)
+            .map(|_need_iface_poll| ())
+    }
+
+    fn common(&self) -> &FileCommon {
+        &self.common
+    }
+}
+
+impl GetSocketLevelOption for RawSocket {
+    fn socket_type(&self) -> SockType {
+        match self.kind {
+            RawSocketKind::Raw => SockType::SOCK_RAW,
+            RawSocketKind::Ping => SockType::SOCK_DGRAM,
+        }
+    }
+
+    fn is_listening(&self) -> bool {
+        false
+    }
+
+    fn socket_timeouts(&self) -> Option<&SocketTimeouts> {
+        Some(&self.timeouts)
+    }
+}
+
+impl SetSocketLevelOption for RawSocket {
+    fn socket_timeouts(&self) -> Option<&SocketTimeouts> {
+        Some(&self.timeouts)
+    }
+}
+
+impl SetIpLevelOption for RawSocket {
+    fn set_hdrincl(&self, _hdrincl: bool) -> Result<()> {
+        if self.kind != RawSocketKind::Raw || self.family != IpAddressFamily::IPv4 {
+            return_errno_with_message!(
+                Errno::ENOPROTOOPT,
+                "IP_HDRINCL can only be set on IPv4 raw sockets"
+            );
+        }
+
+        Ok(())
+    }
+}
@@ -12,9 +12,10 @@ use crate::{
     events::IoEvents,
     net::{
         iface::BoundTcpPort,
+        net_ns::NetNamespace,
         socket::{
             ip::{
-                addr::IpAddressFamily,
+                addr::check_same_family,
                 common::{get_ephemeral_endpoint, resolve_bind_iface_and_config},
             },
             util::SocketAddr,
@@ -74,24 +75,15 @@ impl InitStream {
 
     pub(super) fn bind(
         &mut self,
+        net_ns: &NetNamespace,
         endpoint: &IpEndpoint,
-        family: IpAddressFamily,
         can_reuse: bool,
     ) -> Result<()> {
         if self.bound_port.is_some() {
             return_errno_with_message!(Errno::EINVAL, "the socket is already bound to an address");
         }
 
-        // When we support `IPV6_V6ONLY` and if it is set, we should also reject IPv4-mapped
-        // IPv6 addresses.
-        if IpAddressFamily::from(endpoint.addr) != family {
-            return_errno_with_message!(
-                Errno::EAFNOSUPPORT,
-                "the protocol family does not match the address family"
-            );
-        }
-
-        self.bound_port = Some(bind_port(endpoint, can_reuse)?);
+        self.bound_port = Some(bind_port(net_ns, endpoint, can_reuse)?);
 
         Ok(())
     }
@@ -102,8 +94,8 @@ impl InitStream {
 
     pub(super) fn connect(
         self,
+        net_ns: &NetNamespace,
         remote_endpoint: &IpEndpoint,
-        family: IpAddressFamily,
         option: &RawTcpOption,
         can_reuse: bool,
         observer: StreamObserver,
@@ -113,22 +105,18 @@ impl InitStream {
             "`finish_last_connect()` should be called before calling `connect()`"
         );
 
-        // When we support `IPV6_V6ONLY` and if it is set, we should also reject IPv4-mapped
-        // IPv6 addresses.
-        if IpAddressFamily::from(remote_endpoint.addr) != family {
-            return Err((
-                Error::with_message(
-                    Errno::EAFNOSUPPORT,
-                    "the protocol family does not match the address family",
-                ),
-                self,
-            ));
+        // An IPv6 socket may be bound to an IPv4-mapped IPv6 address, in which case it cannot
+        // connect to IPv6 peers, and vice versa.
+        if let Some(bound_port) = self.bound_port.as_ref()
+            && let Err(err) = check_same_family(&bound_port.endpoint(), remote_endpoint)
+        {
+            return Err((err, self));
         }
 
         let bound_port = if let Some(bound_port) = self.bound_port {
             bound_port
         } else {
-            let endpoint = match get_ephemeral_endpoint(remote_endpoint) {
+            let endpoint = match get_ephemeral_endpoint(net_ns, remote_endpoint) {
                 Some(ep) => ep,
                 None => {
                     return Err((
@@ -140,7 +128,7 @@ impl InitStream {
                     ));
                 }
             };
-            match bind_port(&endpoint, can_reuse) {
+            match bind_port(net_ns, &endpoint, can_reuse) {
                 Ok(bound_port) => bound_port,
                 Err(err) => return Err((err, self)),
             }
@@ -266,7 +254,11 @@ impl InitStream {
     }
 }
 
-fn bind_port(endpoint: &IpEndpoint, can_reuse: bool) -> Result<BoundTcpPort> {
-    let (iface, config) = resolve_bind_iface_and_config(endpoint, can_reuse)?;
+fn bind_port(
+    net_ns: &NetNamespace,
+    endpoint: &IpEndpoint,
+    can_reuse: bool,
+) -> Result<BoundTcpPort> {
+    let (iface, config) = resolve_bind_iface_and_config(net_ns, endpoint, can_reuse)?;
     Ok(iface.bind_tcp(config)?)
 }
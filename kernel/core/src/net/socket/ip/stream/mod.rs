@@ -21,13 +21,14 @@ use util::{Retrans, TcpOptionSet};
 use super::{
     addr::IpAddressFamily,
     ioctl::ipv4_ioctl,
-    options::{IpOptionSet, SetIpLevelOption},
+    options::{IpOptionSet, Ipv6OptionSet, SetIpLevelOption, SetIpv6LevelOption},
 };
 use crate::{
     events::IoEvents,
     fs::file::{FileCommon, FileLike},
     net::{
         iface::Iface,
+        net_ns::NetNamespace,
         socket::{
             Socket, new_socket_common,
             options::{
@@ -44,7 +45,11 @@ use crate::{
         },
     },
     prelude::*,
-    process::signal::{PollHandle, Pollable, Pollee},
+    process::{
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable, Pollee},
+    },
+    security::lsm::hooks::{self as lsm_hooks, SocketAddrContext},
     util::{MultiRead, MultiWrite, ioctl::RawIoctl, net::SockType},
 };
 
@@ -62,6 +67,8 @@ pub(crate) struct StreamSocket {
     // and other locks in `aster-bigtcp`), which will break the atomic mode.
     state: RwLock<Takeable<State>>,
     family: IpAddressFamily,
+    /// The network namespace in which the socket was created.
+    net_ns: Arc<NetNamespace>,
     options: RwLock<OptionSet>,
     timeouts: SocketTimeouts,
 
@@ -84,6 +91,7 @@ enum State {
 struct OptionSet {
     socket: SocketOptionSet,
     ip: IpOptionSet,
+    ipv6: Ipv6OptionSet,
     tcp: TcpOptionSet,
 }
 
@@ -91,8 +99,14 @@ impl OptionSet {
     fn new() -> Self {
         let socket = SocketOptionSet::new_tcp();
         let ip = IpOptionSet::new_tcp();
+        let ipv6 = Ipv6OptionSet::new();
         let tcp = TcpOptionSet::new();
-        OptionSet { socket, ip, tcp }
+        OptionSet {
+            socket,
+            ip,
+            ipv6,
+            tcp,
+        }
     }
 
     fn raw(&self) -> RawTcpOption {
@@ -106,11 +120,16 @@ impl OptionSet {
 }
 
 impl StreamSocket {
-    pub(crate) fn new(is_nonblocking: bool, family: IpAddressFamily) -> Arc<Self> {
+    pub(crate) fn new(
+        is_nonblocking: bool,
+        family: IpAddressFamily,
+        net_ns: Arc<NetNamespace>,
+    ) -> Arc<Self> {
         let init_stream = InitStream::new();
         Arc::new(Self {
             state: RwLock::new(Takeable::new(State::Init(init_stream))),
             family,
+            net_ns,
             options: RwLock::new(OptionSet::new()),
             timeouts: SocketTimeouts::new(),
             pollee: Pollee::new(),
@@ -121,6 +140,7 @@ impl StreamSocket {
     fn new_accepted(
         connected_stream: ConnectedStream,
         family: IpAddressFamily,
+        net_ns: Arc<NetNamespace>,
         listener_options: &OptionSet,
         listener_timeouts: &SocketTimeouts,
         is_nonblocking: bool,
@@ -151,6 +171,8 @@ impl StreamSocket {
                 options.tcp.set_no_delay(true);
             }
 
+            options.ipv6 = listener_options.ipv6;
+
             // TODO: Update other options for a newly-accepted socket
 
             options
@@ -162,6 +184,7 @@ impl StreamSocket {
         Arc::new(Self {
             state: RwLock::new(Takeable::new(State::Connected(connected_stream))),
             family,
+            net_ns,
             options: RwLock::new(options),
             timeouts: listener_timeouts.clone(),
             pollee,
@@ -266,8 +289,8 @@ impl StreamSocket {
             }
 
             let (target_state, iface_to_poll) = match init_stream.connect(
+                &self.net_ns,
                 remote_endpoint,
-                self.family,
                 &raw_option,
                 options.socket.reuse_addr(),
                 StreamObserver::new(self.pollee.clone()),
@@ -336,11 +359,15 @@ impl StreamSocket {
             let accepted_socket = Self::new_accepted(
                 connected_stream,
                 self.family,
+                self.net_ns.clone(),
                 &listener_options,
                 &self.timeouts,
                 is_nonblocking,
             );
-            (accepted_socket as _, remote_endpoint.into())
+            (
+                accepted_socket as _,
+                self.family.socket_addr(remote_endpoint),
+            )
         });
         let iface_to_poll = listen_stream.iface().clone();
 
@@ -385,7 +412,7 @@ impl StreamSocket {
             iface.poll();
         }
 
-        Ok((recv_bytes, remote_endpoint.into()))
+        Ok((recv_bytes, self.family.socket_addr(remote_endpoint)))
     }
 
     fn try_send(&self, reader: &mut dyn MultiRead, flags: SendFlags) -> Result<usize> {
@@ -458,10 +485,14 @@ impl SocketPrivate for StreamSocket {
         self.common.is_nonblocking()
     }
 
+    fn net_ns(&self) -> &Arc<NetNamespace> {
+        &self.net_ns
+    }
+
     fn protocol_ioctl(&self, raw_ioctl: RawIoctl) -> Result<i32> {
         // Handle common IPv4/IPv6 ioctl commands.
         match self.family {
-            IpAddressFamily::IPv4 => ipv4_ioctl(raw_ioctl),
+            IpAddressFamily::IPv4 => ipv4_ioctl(&self.net_ns, raw_ioctl),
             IpAddressFamily::IPv6 => {
                 // TODO: Add support for IPv6 ioctl commands.
                 return_errno_with_message!(Errno::ENOTTY, "the socket ioctl command is unknown")
@@ -474,19 +505,25 @@ impl SocketPrivate for StreamSocket {
 
 impl Socket for StreamSocket {
     fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
-        let endpoint = socket_addr.try_into()?;
+        run_lsm_socket_hook(lsm_hooks::on_socket_bind, &socket_addr)?;
 
         let mut state = self.write_updated_state();
         let State::Init(init_stream) = state.as_mut() else {
             return_errno_with_message!(Errno::EINVAL, "the socket is already bound to an address");
         };
 
-        let can_reuse = self.options.read().socket.reuse_addr();
-        init_stream.bind(&endpoint, self.family, can_reuse)
+        let options = self.options.read();
+        let endpoint = self
+            .family
+            .local_endpoint(socket_addr, options.ipv6.v6only())?;
+        init_stream.bind(&self.net_ns, &endpoint, options.socket.reuse_addr())
     }
 
     fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
-        let remote_endpoint = socket_addr.try_into()?;
+        run_lsm_socket_hook(lsm_hooks::on_socket_connect, &socket_addr)?;
+
+        let is_v6only = self.options.read().ipv6.v6only();
+        let remote_endpoint = self.family.remote_endpoint(socket_addr, is_v6only)?;
 
         if let Some(result) = self.start_connect(&remote_endpoint) {
             return result;
@@ -577,7 +614,7 @@ impl Socket for StreamSocket {
             State::Listen(listen_stream) => listen_stream.local_endpoint(),
             State::Connected(connected_stream) => connected_stream.local_endpoint(),
         };
-        Ok(local_endpoint.into())
+        Ok(self.family.socket_addr(local_endpoint))
     }
 
     fn peer_addr(&self) -> Result<SocketAddr> {
@@ -589,7 +626,7 @@ impl Socket for StreamSocket {
             State::Connecting(connecting_stream) => connecting_stream.remote_endpoint(),
             State::Connected(connected_stream) => connected_stream.remote_endpoint(),
         };
-        Ok(remote_endpoint.into())
+        Ok(self.family.socket_addr(remote_endpoint))
     }
 
     fn sendmsg(
@@ -674,6 +711,14 @@ impl Socket for StreamSocket {
             res => return res,
         }
 
+        // Deal with IPv6-level options
+        if self.family == IpAddressFamily::IPv6 {
+            match options.ipv6.get_option(option) {
+                Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+                res => return res,
+            }
+        }
+
         // Deal with TCP-level options
         // FIXME: Here we only return the previously set values, without actually
         // asking the underlying sockets for the real, effective values.
@@ -756,7 +801,18 @@ impl Socket for StreamSocket {
         let need_iface_poll = match socket_option_result {
             Err(err) if err.error() == Errno::ENOPROTOOPT => {
                 // Deal with IP-level options
-                match options.ip.set_option(option, state.as_ref()) {
+                let ip_option_result = match options.ip.set_option(option, state.as_ref()) {
+                    Err(err)
+                        if err.error() == Errno::ENOPROTOOPT
+                            && self.family == IpAddressFamily::IPv6 =>
+                    {
+                        // Deal with IPv6-level options
+                        options.ipv6.set_option(option, state.as_ref())
+                    }
+                    res => res,
+                };
+
+                match ip_option_result {
                     Err(err) if err.error() == Errno::ENOPROTOOPT => {
                         // Deal with TCP-level options
                         do_tcp_setsockopt(option, &mut options, state.as_mut())?
@@ -786,6 +842,23 @@ impl Socket for StreamSocket {
     }
 }
 
+/// Runs an LSM hook for binding or connecting the socket to an address.
+fn run_lsm_socket_hook(
+    hook: fn(SocketAddrContext) -> Result<()>,
+    socket_addr: &SocketAddr,
+) -> Result<()> {
+    let current_thread = current_thread!();
+    let Some(posix_thread) = current_thread.as_posix_thread() else {
+        return Ok(());
+    };
+
+    hook(SocketAddrContext::new(
+        posix_thread,
+        SockType::SOCK_STREAM,
+        socket_addr,
+    ))
+}
+
 fn do_tcp_setsockopt(
     option: &dyn SocketOption,
     options: &mut OptionSet,
@@ -992,6 +1065,23 @@ impl SetIpLevelOption for State {
     }
 }
 
+impl SetIpv6LevelOption for State {
+    fn set_v6only(&self, _v6only: bool) -> Result<()> {
+        let is_bound = match self {
+            State::Init(init_stream) => init_stream.bound_port().is_some(),
+            State::Connecting(_) | State::Connected(_) | State::Listen(_) => true,
+        };
+        if is_bound {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "IPV6_V6ONLY cannot be set after the socket is bound"
+            );
+        }
+
+        Ok(())
+    }
+}
+
 impl Drop for StreamSocket {
     fn drop(&mut self) {
         let state = self.state.get_mut().take();
@@ -21,6 +21,7 @@ use crate::{
 pub(crate) mod ip;
 pub(crate) mod netlink;
 pub(crate) mod options;
+pub(crate) mod packet;
 pub(crate) mod unix;
 pub(crate) mod util;
 pub(crate) mod vsock;
@@ -28,7 +29,10 @@ pub(crate) mod vsock;
 mod private {
     use core::time::Duration;
 
-    use crate::{events::IoEvents, prelude::*, process::signal::Pollable, util::ioctl::RawIoctl};
+    use crate::{
+        events::IoEvents, net::net_ns::NetNamespace, prelude::*, process::signal::Pollable,
+        util::ioctl::RawIoctl,
+    };
 
     /// Common methods for sockets, but private to the network module.
     ///
@@ -38,6 +42,9 @@ mod private {
         /// Returns whether the socket is in non-blocking mode.
         fn is_nonblocking(&self) -> bool;
 
+        /// Returns the network namespace in which the socket was created.
+        fn net_ns(&self) -> &Arc<NetNamespace>;
+
         /// Blocks until some events occur to complete I/O operations.
         ///
         /// If the socket is in non-blocking mode and the I/O operations cannot be completed
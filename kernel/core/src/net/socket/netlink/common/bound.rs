@@ -4,8 +4,11 @@
 
 use crate::{
     events::IoEvents,
-    net::socket::netlink::{
-        GroupIdSet, NetlinkSocketAddr, receiver::MessageQueue, table::BoundHandle,
+    net::{
+        net_ns::NetNamespace,
+        socket::netlink::{
+            GroupIdSet, NetlinkSocketAddr, receiver::MessageQueue, table::BoundHandle,
+        },
     },
     prelude::*,
 };
@@ -14,17 +17,23 @@ pub(crate) struct BoundNetlink<Message: 'static> {
     pub(in netlink) handle: BoundHandle<Message>,
     pub(in netlink) remote_addr: NetlinkSocketAddr,
     pub(in netlink) receive_queue: Arc<Mutex<MessageQueue<Message>>>,
+    /// The network namespace where the socket is created.
+    ///
+    /// Like Linux, the requests sent via the socket are handled in this network namespace.
+    pub(in netlink) net_ns: Arc<NetNamespace>,
 }
 
 impl<Message: 'static> BoundNetlink<Message> {
     pub(super) fn new(
         handle: BoundHandle<Message>,
         message_queue: Arc<Mutex<MessageQueue<Message>>>,
+        net_ns: Arc<NetNamespace>,
     ) -> Self {
         Self {
             handle,
             remote_addr: NetlinkSocketAddr::new_unspecified(),
             receive_queue: message_queue,
+            net_ns,
         }
     }
 
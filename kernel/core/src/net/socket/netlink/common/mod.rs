@@ -7,20 +7,23 @@ use super::{GroupIdSet, NetlinkSocketAddr};
 use crate::{
     events::IoEvents,
     fs::file::FileCommon,
-    net::socket::{
-        Socket,
-        netlink::{AddMembership, DropMembership, table::SupportedNetlinkProtocol},
-        new_socket_common,
-        options::{
-            Error as SocketError, SocketOption,
-            macros::{sock_option_mut, sock_option_ref},
-        },
-        private::SocketPrivate,
-        util::{
-            MessageHeader, RecvFlags, RecvOutput, SendFlags, SocketAddr,
-            datagram_common::{Bound, Inner, select_remote_and_bind},
+    net::{
+        net_ns::NetNamespace,
+        socket::{
+            Socket,
+            netlink::{AddMembership, DropMembership, table::SupportedNetlinkProtocol},
+            new_socket_common,
             options::{
-                GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet, SocketTimeouts,
+                Error as SocketError, SocketOption,
+                macros::{sock_option_mut, sock_option_ref},
+            },
+            private::SocketPrivate,
+            util::{
+                MessageHeader, RecvFlags, RecvOutput, SendFlags, SocketAddr,
+                datagram_common::{Bound, Inner, select_remote_and_bind},
+                options::{
+                    GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet, SocketTimeouts,
+                },
             },
         },
     },
@@ -36,6 +39,8 @@ pub(crate) struct NetlinkSocket<P: SupportedNetlinkProtocol> {
     inner: RwMutex<Inner<UnboundNetlink<P>, BoundNetlink<P::Message>>>,
     options: RwLock<OptionSet>,
     socket_type: SockType,
+    /// The network namespace in which the socket was created.
+    net_ns: Arc<NetNamespace>,
     timeouts: SocketTimeouts,
 
     pollee: Pollee,
@@ -59,14 +64,19 @@ impl<P: SupportedNetlinkProtocol> NetlinkSocket<P>
 where
     BoundNetlink<P::Message>: Bound<Endpoint = NetlinkSocketAddr>,
 {
-    pub(crate) fn new(is_nonblocking: bool, socket_type: SockType) -> Arc<Self> {
+    pub(crate) fn new(
+        is_nonblocking: bool,
+        socket_type: SockType,
+        net_ns: Arc<NetNamespace>,
+    ) -> Arc<Self> {
         debug_assert!(socket_type == SockType::SOCK_RAW || socket_type == SockType::SOCK_DGRAM);
 
-        let unbound = UnboundNetlink::new();
+        let unbound = UnboundNetlink::new(net_ns.clone());
         Arc::new(Self {
             inner: RwMutex::new(Inner::Unbound(unbound)),
             options: RwLock::new(OptionSet::new()),
             socket_type,
+            net_ns,
             timeouts: SocketTimeouts::new(),
             pollee: Pollee::new(),
             common: new_socket_common(is_nonblocking),
@@ -245,6 +255,10 @@ where
     fn is_nonblocking(&self) -> bool {
         self.common.is_nonblocking()
     }
+
+    fn net_ns(&self) -> &Arc<NetNamespace> {
+        &self.net_ns
+    }
 }
 
 impl<P: SupportedNetlinkProtocol> Pollable for NetlinkSocket<P>
@@ -6,20 +6,23 @@ use core::str::FromStr;
 use ostd::prelude::*;
 
 use crate::{
-    net::socket::{
-        Socket,
-        netlink::{
-            GroupIdSet, NetlinkSocketAddr, NetlinkUeventSocket,
-            kobject_uevent::{
-                UeventMessage,
-                message::{
-                    syn_uevent::{SyntheticUevent, Uuid},
-                    uevent::Uevent,
+    net::{
+        net_ns::NetNamespace,
+        socket::{
+            Socket,
+            netlink::{
+                GroupIdSet, NetlinkSocketAddr, NetlinkUeventSocket,
+                kobject_uevent::{
+                    UeventMessage,
+                    message::{
+                        syn_uevent::{SyntheticUevent, Uuid},
+                        uevent::Uevent,
+                    },
                 },
+                table::{NetlinkUeventProtocol, SupportedNetlinkProtocol},
             },
-            table::{NetlinkUeventProtocol, SupportedNetlinkProtocol},
+            util::{RecvFlags, SocketAddr},
         },
-        util::{RecvFlags, SocketAddr},
     },
     prelude::*,
     util::net::SockType,
@@ -54,7 +57,11 @@ fn multicast_synthetic_uevent() {
     crate::net::socket::netlink::init();
 
     // Creates a new netlink uevent socket and joins the group for kobject uevents.
-    let socket = NetlinkUeventSocket::new(true, SockType::SOCK_DGRAM);
+    let socket = NetlinkUeventSocket::new(
+        true,
+        SockType::SOCK_DGRAM,
+        NetNamespace::get_init_singleton().clone(),
+    );
     let socket_addr = SocketAddr::Netlink(NetlinkSocketAddr::new(100, GroupIdSet::new(0x1)));
     socket.bind(socket_addr).unwrap();
 
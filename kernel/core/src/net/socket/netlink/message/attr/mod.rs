@@ -186,6 +186,19 @@ pub(crate) trait Attribute: Debug + Send + Sync {
         Ok(ContinueRead::Parsed(res))
     }
 
+    /// Reads all nested attributes from the payload of another attribute.
+    fn read_all_from_nested(payload: &[u8]) -> Result<Vec<Self>>
+    where
+        Self: Sized,
+    {
+        let mut reader = VmReader::from(payload).to_fallible();
+        match Self::read_all_from(&mut reader, payload.len())? {
+            ContinueRead::Parsed(attrs) => Ok(attrs),
+            ContinueRead::Skipped => Ok(Vec::new()),
+            ContinueRead::SkippedErr(err) => Err(err),
+        }
+    }
+
     /// Writes the attribute to the `writer`.
     fn write_to(&self, writer: &mut dyn MultiWrite) -> Result<()> {
         let type_ = self.type_();
@@ -15,7 +15,7 @@ pub(super) use segment::{
     CSegmentType, SegmentBody,
     ack::{DoneSegment, ErrorSegment},
     common::SegmentCommon,
-    header::{CMsgSegHdr, GetRequestFlags, SegHdrCommonFlags},
+    header::{CMsgSegHdr, DeleteRequestFlags, GetRequestFlags, NewRequestFlags, SegHdrCommonFlags},
 };
 
 use super::receiver::QueueableMessage;
@@ -28,7 +28,7 @@ use crate::{
 ///
 /// A netlink message can be transmitted to and from user space using a single send/receive syscall.
 /// It consists of one or more [`ProtocolSegment`]s.
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub(crate) struct Message<T> {
     segments: Vec<T>,
 }
@@ -41,6 +41,7 @@ mod addr;
 mod common;
 mod kobject_uevent;
 mod message;
+mod netfilter;
 mod options;
 mod receiver;
 mod route;
@@ -48,6 +49,7 @@ mod table;
 
 pub(crate) use addr::{GroupIdSet, NetlinkSocketAddr};
 pub(crate) use kobject_uevent::NetlinkUeventSocket;
+pub(crate) use netfilter::NetlinkNetfilterSocket;
 pub(crate) use options::{AddMembership, DropMembership};
 pub(super) use receiver::NETLINK_DEFAULT_BUF_SIZE;
 pub(crate) use route::NetlinkRouteSocket;
@@ -0,0 +1,261 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Handle batches of modifications.
+//!
+//! The modifications in a batch are applied to a copy of the ruleset. If all of them succeed,
+//! the copy replaces the ruleset as a whole, so the ruleset is updated atomically. Otherwise,
+//! the copy is discarded.
+
+use super::{
+    chain, rule, set, table, unicast,
+    util::{ack_response, find_attr, new_segment, notify},
+};
+use crate::{
+    net::{
+        net_ns::NetNamespace,
+        netfilter::{Ruleset, Table},
+        socket::netlink::{
+            addr::PortNum,
+            message::{CMsgSegHdr, ErrorSegment, ProtocolSegment, SegHdrCommonFlags},
+            netfilter::message::{
+                NFNL_SUBSYS_NFTABLES, NfAttr, NfnlSegment, NftMsgType, NftSegment,
+            },
+        },
+    },
+    prelude::*,
+    process::posix_thread::AsPosixThread,
+};
+
+/// `NFNL_BATCH_GENID` in Linux.
+const NFNL_BATCH_GENID: u16 = 1;
+
+/// `NFTA_GEN_*` in Linux.
+const NFTA_GEN_ID: u16 = 1;
+const NFTA_GEN_PROC_PID: u16 = 2;
+const NFTA_GEN_PROC_NAME: u16 = 3;
+
+/// A transaction, which contains the modified copy of the ruleset.
+pub(super) struct Transaction {
+    pub(super) ruleset: Ruleset,
+    /// The generation ID of the ruleset if the transaction is committed.
+    pub(super) generation: u32,
+    /// The chains added in the transaction, indexed by the table handles and `NFTA_CHAIN_ID`.
+    chain_ids: BTreeMap<(u64, u32), String>,
+    /// The sets added in the transaction, indexed by the table handles and `NFTA_SET_ID`.
+    set_ids: BTreeMap<(u64, u32), String>,
+    /// The notifications that will be sent if the transaction is committed.
+    notifications: Vec<NfnlSegment>,
+    /// The notifications that will be echoed to the sender if the transaction is committed.
+    echoes: Vec<NfnlSegment>,
+}
+
+impl Transaction {
+    fn new(ruleset: Ruleset, generation: u32) -> Self {
+        Self {
+            ruleset,
+            generation,
+            chain_ids: BTreeMap::new(),
+            set_ids: BTreeMap::new(),
+            notifications: Vec::new(),
+            echoes: Vec::new(),
+        }
+    }
+
+    pub(super) fn add_chain_id(&mut self, table_handle: u64, id: u32, name: &str) {
+        self.chain_ids.insert((table_handle, id), name.to_string());
+    }
+
+    /// Returns the name of the chain added in the transaction with the ID.
+    pub(super) fn chain_by_id(&self, table: &Table, id: u32) -> Option<&str> {
+        self.chain_ids
+            .get(&(table.handle, id))
+            .map(|name| name.as_str())
+    }
+
+    pub(super) fn add_set_id(&mut self, table_handle: u64, id: u32, name: &str) {
+        self.set_ids.insert((table_handle, id), name.to_string());
+    }
+
+    /// Returns the name of the set added in the transaction with the ID.
+    pub(super) fn set_by_id(&self, table: &Table, id: u32) -> Option<&str> {
+        self.set_ids
+            .get(&(table.handle, id))
+            .map(|name| name.as_str())
+    }
+
+    /// Records a notification of a change made by the request.
+    ///
+    /// The notification is also echoed to the sender if the request has the `ECHO` flag.
+    pub(super) fn notify(&mut self, request_header: &CMsgSegHdr, segment: NfnlSegment) {
+        let flags = SegHdrCommonFlags::from_bits_truncate(request_header.flags);
+        if flags.contains(SegHdrCommonFlags::ECHO) {
+            self.echoes.push(segment.clone());
+        }
+        self.notifications.push(segment);
+    }
+}
+
+/// Handles the requests in a batch.
+///
+/// `requests` are the requests after `begin`. The requests after `NFNL_MSG_BATCH_END` are
+/// ignored, like Linux.
+pub(super) fn handle_batch(
+    net_ns: &NetNamespace,
+    begin: &NftSegment,
+    requests: impl Iterator<Item = Result<NfnlSegment, ErrorSegment>>,
+    dst_port: PortNum,
+) {
+    let netfilter = net_ns.netfilter();
+    let begin_header = begin.header();
+
+    if begin.body().res_id != NFNL_SUBSYS_NFTABLES {
+        let error = Error::with_message(Errno::EINVAL, "the netfilter subsystem is not supported");
+        report_error(
+            ErrorSegment::new_from_request(begin_header, Some(error)),
+            dst_port,
+        );
+        return;
+    }
+
+    let guard = netfilter.lock_transaction();
+
+    // User space can ask the kernel to abort the batch if the ruleset has been changed after
+    // the ruleset is read by user space.
+    if let Some(attr) = find_attr(begin.attrs(), NFNL_BATCH_GENID)
+        && attr.as_be32().ok() != Some(netfilter.generation())
+    {
+        let error = Error::with_message(Errno::ERESTART, "the generation ID does not match");
+        report_error(
+            ErrorSegment::new_from_request(begin_header, Some(error)),
+            dst_port,
+        );
+        return;
+    }
+
+    let mut transaction = Transaction::new(
+        (*netfilter.ruleset()).clone(),
+        netfilter.generation().wrapping_add(1),
+    );
+    let mut responses = Vec::new();
+    let mut is_failed = false;
+    let mut is_ended = false;
+
+    for request in requests {
+        let result = match request {
+            Ok(NfnlSegment::Nftables(msg_type, segment)) => {
+                apply(&mut transaction, msg_type, &segment)
+                    .map(|_| ack_response(segment.header()))
+                    .map_err(|error| ErrorSegment::new_from_request(segment.header(), Some(error)))
+            }
+            Ok(NfnlSegment::BatchEnd(_)) => {
+                is_ended = true;
+                break;
+            }
+            Ok(NfnlSegment::BatchBegin(_)) => {
+                // Nested batches are malformed. Like Linux, the errors are discarded.
+                responses.clear();
+                is_failed = true;
+                break;
+            }
+            Ok(segment @ (NfnlSegment::Done(_) | NfnlSegment::Error(_))) => {
+                let error = Error::with_message(Errno::EINVAL, "the segment is not a request");
+                Err(ErrorSegment::new_from_request(
+                    segment.header(),
+                    Some(error),
+                ))
+            }
+            Err(err_segment) => Err(err_segment),
+        };
+
+        // Like Linux, the remaining requests are still processed after an error occurs, so
+        // user space can see the errors for all requests.
+        match result {
+            Ok(ack_segments) => responses.extend(ack_segments),
+            Err(err_segment) => {
+                responses.push(NfnlSegment::Error(err_segment));
+                is_failed = true;
+            }
+        }
+    }
+
+    if is_ended && !is_failed {
+        netfilter.commit(transaction.ruleset, &guard);
+        let generation = netfilter.generation();
+
+        let mut notifications = transaction.notifications;
+        notifications.push(new_gen_segment(begin_header, generation));
+        notify(net_ns, notifications);
+
+        if !transaction.echoes.is_empty() {
+            unicast(transaction.echoes, dst_port);
+        }
+    }
+    drop(guard);
+
+    for response in responses {
+        unicast(vec![response], dst_port);
+    }
+}
+
+/// Applies a modification to the transaction.
+fn apply(transaction: &mut Transaction, msg_type: NftMsgType, segment: &NftSegment) -> Result<()> {
+    debug!("netlink netfilter request: {:?} {:?}", msg_type, segment);
+
+    match msg_type {
+        NftMsgType::NEWTABLE => table::do_new_table(transaction, segment),
+        NftMsgType::DELTABLE => table::do_del_table(transaction, segment, false),
+        NftMsgType::DESTROYTABLE => table::do_del_table(transaction, segment, true),
+        NftMsgType::NEWCHAIN => chain::do_new_chain(transaction, segment),
+        NftMsgType::DELCHAIN => chain::do_del_chain(transaction, segment, false),
+        NftMsgType::DESTROYCHAIN => chain::do_del_chain(transaction, segment, true),
+        NftMsgType::NEWRULE => rule::do_new_rule(transaction, segment),
+        NftMsgType::DELRULE => rule::do_del_rule(transaction, segment, false),
+        NftMsgType::DESTROYRULE => rule::do_del_rule(transaction, segment, true),
+        NftMsgType::NEWSET => set::do_new_set(transaction, segment),
+        NftMsgType::DELSET => set::do_del_set(transaction, segment, false),
+        NftMsgType::DESTROYSET => set::do_del_set(transaction, segment, true),
+        NftMsgType::NEWSETELEM => set::do_new_set_elem(transaction, segment),
+        NftMsgType::DELSETELEM => set::do_del_set_elem(transaction, segment, false),
+        NftMsgType::DESTROYSETELEM => set::do_del_set_elem(transaction, segment, true),
+        NftMsgType::NEWOBJ
+        | NftMsgType::DELOBJ
+        | NftMsgType::DESTROYOBJ
+        | NftMsgType::NEWFLOWTABLE
+        | NftMsgType::DELFLOWTABLE
+        | NftMsgType::DESTROYFLOWTABLE => {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "stateful objects and flowtables are not supported"
+            )
+        }
+        // Get requests cannot be in a batch.
+        _ => return_errno_with_message!(Errno::EINVAL, "the request cannot be in a batch"),
+    }
+}
+
+/// Handles `NFT_MSG_GETGEN` requests.
+pub(super) fn do_get_gen(net_ns: &NetNamespace, segment: &NftSegment) -> Result<Vec<NfnlSegment>> {
+    let generation = net_ns.netfilter().generation();
+    Ok(vec![new_gen_segment(segment.header(), generation)])
+}
+
+/// Creates an `NFT_MSG_NEWGEN` segment.
+fn new_gen_segment(request_header: &CMsgSegHdr, generation: u32) -> NfnlSegment {
+    let thread_name = *current_thread!()
+        .as_posix_thread()
+        .unwrap()
+        .thread_name()
+        .lock();
+    let attrs = vec![
+        NfAttr::new_be32(NFTA_GEN_ID, generation),
+        NfAttr::new_be32(NFTA_GEN_PROC_PID, current!().pid()),
+        NfAttr::new(NFTA_GEN_PROC_NAME, thread_name.as_bytes_with_nul().to_vec()),
+    ];
+
+    // The family of `NFT_MSG_NEWGEN` segments is `AF_UNSPEC`.
+    new_segment(NftMsgType::NEWGEN, 0, request_header, generation, attrs)
+}
+
+fn report_error(err_segment: ErrorSegment, dst_port: PortNum) {
+    unicast(vec![NfnlSegment::Error(err_segment)], dst_port);
+}
@@ -0,0 +1,471 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Handle chain-related requests.
+
+use aster_bigtcp::iface::FilterHook;
+
+use super::{
+    batch::Transaction,
+    util::{
+        find_attr, find_table, find_table_mut, finish_response, is_dump, new_segment, parse_name,
+        parse_userdata, required_attr,
+    },
+};
+use crate::{
+    net::{
+        net_ns::NetNamespace,
+        netfilter::{BaseChain, Chain, ChainType, MAX_JUMP_DEPTH, SetData, Table, Verdict},
+        socket::netlink::{
+            message::{CMsgSegHdr, DeleteRequestFlags, NewRequestFlags},
+            netfilter::message::{NfAttr, NfnlSegment, NftMsgType, NftSegment},
+        },
+    },
+    prelude::*,
+};
+
+const NFTA_CHAIN_TABLE: u16 = 1;
+const NFTA_CHAIN_HANDLE: u16 = 2;
+const NFTA_CHAIN_NAME: u16 = 3;
+const NFTA_CHAIN_HOOK: u16 = 4;
+const NFTA_CHAIN_POLICY: u16 = 5;
+const NFTA_CHAIN_USE: u16 = 6;
+const NFTA_CHAIN_TYPE: u16 = 7;
+const NFTA_CHAIN_FLAGS: u16 = 10;
+const NFTA_CHAIN_ID: u16 = 11;
+const NFTA_CHAIN_USERDATA: u16 = 12;
+
+const NFTA_HOOK_HOOKNUM: u16 = 1;
+const NFTA_HOOK_PRIORITY: u16 = 2;
+
+/// `NFT_CHAIN_BASE` in Linux.
+const NFT_CHAIN_BASE: u32 = 1 << 0;
+
+/// The policies of base chains (`NF_DROP` and `NF_ACCEPT` in Linux).
+const NF_DROP: u32 = 0;
+const NF_ACCEPT: u32 = 1;
+
+pub(super) fn do_get_chain(
+    net_ns: &NetNamespace,
+    segment: &NftSegment,
+) -> Result<Vec<NfnlSegment>> {
+    let netfilter = net_ns.netfilter();
+    let ruleset = netfilter.ruleset();
+    let generation = netfilter.generation();
+
+    let request_header = segment.header();
+    let family = segment.body().family;
+    let dump_all = is_dump(request_header);
+
+    let mut response_segments = if dump_all {
+        ruleset
+            .tables
+            .iter()
+            .filter(|table| family == 0 || table.family == family)
+            .flat_map(|table| {
+                table.chains.iter().map(move |chain| {
+                    chain_to_segment(
+                        NftMsgType::NEWCHAIN,
+                        request_header,
+                        generation,
+                        table,
+                        chain,
+                    )
+                })
+            })
+            .collect()
+    } else {
+        let attrs = segment.attrs();
+        let table_name = required_attr(attrs, NFTA_CHAIN_TABLE)?.as_str()?;
+        let table = find_table(&ruleset, family, table_name)?;
+        let name = required_attr(attrs, NFTA_CHAIN_NAME)?.as_str()?;
+        let chain = table
+            .find_chain(name)
+            .ok_or_else(|| Error::with_message(Errno::ENOENT, "the chain does not exist"))?;
+        vec![chain_to_segment(
+            NftMsgType::NEWCHAIN,
+            request_header,
+            generation,
+            table,
+            chain,
+        )]
+    };
+
+    finish_response(request_header, dump_all, &mut response_segments);
+
+    Ok(response_segments)
+}
+
+pub(super) fn do_new_chain(transaction: &mut Transaction, segment: &NftSegment) -> Result<()> {
+    let family = segment.body().family;
+    let attrs = segment.attrs();
+    let request_header = segment.header();
+    let request_flags = NewRequestFlags::from_bits_truncate(request_header.flags);
+
+    let flags = find_attr(attrs, NFTA_CHAIN_FLAGS)
+        .map(|attr| attr.as_be32())
+        .transpose()?
+        .unwrap_or(0);
+    if flags & !NFT_CHAIN_BASE != 0 {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "the chain flags are not supported");
+    }
+    let hook = find_attr(attrs, NFTA_CHAIN_HOOK)
+        .map(|attr| parse_hook(attr, attrs))
+        .transpose()?;
+    let policy = find_attr(attrs, NFTA_CHAIN_POLICY)
+        .map(|attr| parse_policy(attr))
+        .transpose()?;
+    let id = find_attr(attrs, NFTA_CHAIN_ID)
+        .map(|attr| attr.as_be32())
+        .transpose()?;
+
+    let generation = transaction.generation;
+    let table_name = required_attr(attrs, NFTA_CHAIN_TABLE)?.as_str()?;
+    let table = find_table_mut(&mut transaction.ruleset, family, table_name)?;
+    let table_handle = table.handle;
+
+    let index = if let Some(attr) = find_attr(attrs, NFTA_CHAIN_HANDLE) {
+        let handle = attr.as_be64()?;
+        let Some(index) = table.chains.iter().position(|chain| chain.handle == handle) else {
+            return_errno_with_message!(Errno::ENOENT, "the chain does not exist");
+        };
+        Some(index)
+    } else {
+        let name = required_attr(attrs, NFTA_CHAIN_NAME)?.as_str()?;
+        table.chains.iter().position(|chain| chain.name == name)
+    };
+
+    let index = if let Some(index) = index {
+        if request_flags.contains(NewRequestFlags::EXCL) {
+            return_errno_with_message!(Errno::EEXIST, "the chain already exists");
+        }
+        if request_flags.contains(NewRequestFlags::REPLACE) {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "chains cannot be replaced");
+        }
+
+        let chain = &mut table.chains[index];
+        if find_attr(attrs, NFTA_CHAIN_NAME)
+            .is_some_and(|attr| attr.as_str().is_ok_and(|name| name != chain.name))
+        {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "renaming chains is not supported");
+        }
+        // Like Linux, the hook of a chain cannot be changed.
+        if let Some(hook) = hook.as_ref() {
+            let Some(base) = chain.base.as_ref() else {
+                return_errno_with_message!(Errno::EEXIST, "the chain is not a base chain");
+            };
+            if base.hook != hook.hook || base.priority != hook.priority || base.type_ != hook.type_
+            {
+                return_errno_with_message!(
+                    Errno::EEXIST,
+                    "the hook of the chain cannot be changed"
+                );
+            }
+        }
+        if let Some(policy) = policy {
+            let Some(base) = chain.base.as_mut() else {
+                return_errno_with_message!(Errno::EOPNOTSUPP, "the chain is not a base chain");
+            };
+            base.policy = policy;
+        }
+
+        index
+    } else {
+        let name = parse_name(required_attr(attrs, NFTA_CHAIN_NAME)?)?;
+        let base = match hook {
+            Some(mut hook) => {
+                if let Some(policy) = policy {
+                    hook.policy = policy;
+                }
+                Some(hook)
+            }
+            None if policy.is_some() => {
+                return_errno_with_message!(Errno::EOPNOTSUPP, "the chain is not a base chain");
+            }
+            None => None,
+        };
+
+        let handle = table.alloc_handle();
+        table.chains.push(Chain {
+            name,
+            handle,
+            base,
+            rules: Vec::new(),
+            userdata: parse_userdata(attrs, NFTA_CHAIN_USERDATA),
+        });
+        table.chains.len() - 1
+    };
+
+    let chain = &table.chains[index];
+    let chain_name = chain.name.clone();
+    let segment = chain_to_segment(
+        NftMsgType::NEWCHAIN,
+        request_header,
+        generation,
+        table,
+        chain,
+    );
+
+    if let Some(id) = id {
+        transaction.add_chain_id(table_handle, id, &chain_name);
+    }
+    transaction.notify(request_header, segment);
+
+    Ok(())
+}
+
+/// Deletes a chain.
+///
+/// If `is_destroy` is true (`NFT_MSG_DESTROYCHAIN`), it is not an error if the chain does not
+/// exist.
+pub(super) fn do_del_chain(
+    transaction: &mut Transaction,
+    segment: &NftSegment,
+    is_destroy: bool,
+) -> Result<()> {
+    let family = segment.body().family;
+    let attrs = segment.attrs();
+    let request_header = segment.header();
+
+    let generation = transaction.generation;
+    let table_name = required_attr(attrs, NFTA_CHAIN_TABLE)?.as_str()?;
+    let table = find_table_mut(&mut transaction.ruleset, family, table_name)?;
+
+    let index = if let Some(attr) = find_attr(attrs, NFTA_CHAIN_HANDLE) {
+        let handle = attr.as_be64()?;
+        table.chains.iter().position(|chain| chain.handle == handle)
+    } else {
+        let name = required_attr(attrs, NFTA_CHAIN_NAME)?.as_str()?;
+        table.chains.iter().position(|chain| chain.name == name)
+    };
+    let Some(index) = index else {
+        if is_destroy {
+            return Ok(());
+        }
+        return_errno_with_message!(Errno::ENOENT, "the chain does not exist");
+    };
+
+    let chain = &table.chains[index];
+    let delete_flags = DeleteRequestFlags::from_bits_truncate(request_header.flags);
+    if delete_flags.contains(DeleteRequestFlags::NONREC) && !chain.rules.is_empty() {
+        return_errno_with_message!(Errno::EBUSY, "the chain is not empty");
+    }
+    // The rules in the chain are deleted with the chain, so only the references from other
+    // chains matter.
+    if table.chains.iter().enumerate().any(|(other_index, other)| {
+        other_index != index && chain_targets(table, other).any(|target| target == chain.name)
+    }) {
+        return_errno_with_message!(Errno::EBUSY, "the chain is referenced by other chains");
+    }
+
+    let segment = chain_to_segment(
+        NftMsgType::DELCHAIN,
+        request_header,
+        generation,
+        table,
+        chain,
+    );
+
+    let chain = table.chains.remove(index);
+    let anonymous_sets: Vec<&str> = chain.rules.iter().flat_map(|rule| rule.sets()).collect();
+    table
+        .sets
+        .retain(|set| !(set.is_anonymous() && anonymous_sets.contains(&set.name.as_str())));
+
+    transaction.notify(request_header, segment);
+
+    Ok(())
+}
+
+/// Checks whether the chains in the table are valid after a modification.
+///
+/// Like `nf_tables_check_loops` and `nft_table_validate` in Linux, this checks that the chains
+/// do not jump to each other in loops or too deeply, and that the expressions reachable from each
+/// base chain can be used at the hook of the base chain.
+pub(super) fn validate_table(table: &Table) -> Result<()> {
+    // Check for loops with a depth-first search.
+    let mut finished: BTreeSet<&str> = BTreeSet::new();
+    for chain in table.chains.iter() {
+        let mut path = Vec::new();
+        check_loops(table, chain, &mut path, &mut finished)?;
+    }
+
+    for chain in table.chains.iter() {
+        let Some(base) = chain.base.as_ref() else {
+            continue;
+        };
+        validate_chain(table, chain, base, 0)?;
+    }
+
+    Ok(())
+}
+
+fn check_loops<'a>(
+    table: &'a Table,
+    chain: &'a Chain,
+    path: &mut Vec<&'a str>,
+    finished: &mut BTreeSet<&'a str>,
+) -> Result<()> {
+    if finished.contains(chain.name.as_str()) {
+        return Ok(());
+    }
+    if path.contains(&chain.name.as_str()) {
+        return_errno_with_message!(Errno::ELOOP, "the chains jump to each other in a loop");
+    }
+
+    path.push(&chain.name);
+    for target in chain_targets(table, chain) {
+        let Some(target) = table.find_chain(target) else {
+            return_errno_with_message!(Errno::ENOENT, "the chain does not exist");
+        };
+        check_loops(table, target, path, finished)?;
+    }
+    path.pop();
+
+    finished.insert(&chain.name);
+    Ok(())
+}
+
+fn validate_chain(table: &Table, chain: &Chain, base: &BaseChain, depth: usize) -> Result<()> {
+    if depth > MAX_JUMP_DEPTH {
+        return_errno_with_message!(Errno::EMLINK, "the chains jump too deeply");
+    }
+
+    for expr in chain.rules.iter().flat_map(|rule| rule.exprs.iter()) {
+        if expr.is_nat() && base.type_ != ChainType::Nat {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "NAT expressions can only be used in NAT chains"
+            );
+        }
+        if !expr.is_valid_at(base.hook) {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "the expression cannot be used at the hook"
+            );
+        }
+    }
+
+    for target in chain_targets(table, chain) {
+        // Missing chains have been checked by `check_loops`.
+        let target = table.find_chain(target).unwrap();
+        validate_chain(table, target, base, depth + 1)?;
+    }
+
+    Ok(())
+}
+
+/// Returns the names of the chains that a chain jumps to or goes to, including the verdicts in
+/// the maps that the chain looks up.
+fn chain_targets<'a>(table: &'a Table, chain: &'a Chain) -> impl Iterator<Item = &'a str> {
+    let map_targets = chain
+        .rules
+        .iter()
+        .flat_map(|rule| rule.sets())
+        .filter_map(|name| table.find_set(name))
+        .flat_map(|set| set.elems.iter())
+        .filter_map(|elem| match elem.data.as_ref() {
+            Some(SetData::Verdict(Verdict::Jump(target) | Verdict::Goto(target))) => {
+                Some(target.as_str())
+            }
+            _ => None,
+        });
+
+    chain.targets().chain(map_targets)
+}
+
+/// Parses `NFTA_CHAIN_HOOK` and `NFTA_CHAIN_TYPE`.
+fn parse_hook(attr: &NfAttr, chain_attrs: &[NfAttr]) -> Result<BaseChain> {
+    let attrs = attr.as_nested()?;
+    let hook = match required_attr(&attrs, NFTA_HOOK_HOOKNUM)?.as_be32()? {
+        0 => FilterHook::PreRouting,
+        1 => FilterHook::LocalIn,
+        2 => FilterHook::Forward,
+        3 => FilterHook::LocalOut,
+        4 => FilterHook::PostRouting,
+        _ => return_errno_with_message!(Errno::EOPNOTSUPP, "the hook is not supported"),
+    };
+    let priority = required_attr(&attrs, NFTA_HOOK_PRIORITY)?.as_be32()? as i32;
+
+    let type_ = match find_attr(chain_attrs, NFTA_CHAIN_TYPE) {
+        Some(attr) => ChainType::from_name(attr.as_str()?)
+            .ok_or_else(|| Error::with_message(Errno::ENOENT, "the chain type does not exist"))?,
+        None => ChainType::Filter,
+    };
+    if !type_.supports_hook(hook) {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "the chain type does not support the hook"
+        );
+    }
+
+    Ok(BaseChain {
+        hook,
+        priority,
+        policy: Verdict::Accept,
+        type_,
+    })
+}
+
+fn parse_policy(attr: &NfAttr) -> Result<Verdict> {
+    match attr.as_be32()? {
+        NF_DROP => Ok(Verdict::Drop),
+        NF_ACCEPT => Ok(Verdict::Accept),
+        _ => return_errno_with_message!(Errno::EINVAL, "the chain policy is not valid"),
+    }
+}
+
+fn hook_to_num(hook: FilterHook) -> u32 {
+    match hook {
+        FilterHook::PreRouting => 0,
+        FilterHook::LocalIn => 1,
+        FilterHook::Forward => 2,
+        FilterHook::LocalOut => 3,
+        FilterHook::PostRouting => 4,
+    }
+}
+
+fn chain_to_segment(
+    msg_type: NftMsgType,
+    request_header: &CMsgSegHdr,
+    generation: u32,
+    table: &Table,
+    chain: &Chain,
+) -> NfnlSegment {
+    let mut attrs = vec![
+        NfAttr::new_str(NFTA_CHAIN_TABLE, &table.name),
+        NfAttr::new_str(NFTA_CHAIN_NAME, &chain.name),
+        NfAttr::new_be64(NFTA_CHAIN_HANDLE, chain.handle),
+    ];
+
+    if let Some(base) = chain.base.as_ref() {
+        attrs.push(NfAttr::new_nested(
+            NFTA_CHAIN_HOOK,
+            vec![
+                NfAttr::new_be32(NFTA_HOOK_HOOKNUM, hook_to_num(base.hook)),
+                NfAttr::new_be32(NFTA_HOOK_PRIORITY, base.priority as u32),
+            ],
+        ));
+        let policy = if base.policy == Verdict::Drop {
+            NF_DROP
+        } else {
+            NF_ACCEPT
+        };
+        attrs.push(NfAttr::new_be32(NFTA_CHAIN_POLICY, policy));
+        attrs.push(NfAttr::new_str(NFTA_CHAIN_TYPE, base.type_.name()));
+        attrs.push(NfAttr::new_be32(NFTA_CHAIN_FLAGS, NFT_CHAIN_BASE));
+    }
+
+    let use_count = table
+        .chains
+        .iter()
+        .flat_map(|other| chain_targets(table, other))
+        .filter(|target| *target == chain.name)
+        .count();
+    attrs.push(NfAttr::new_be32(NFTA_CHAIN_USE, use_count as u32));
+
+    if let Some(userdata) = chain.userdata.as_ref() {
+        attrs.push(NfAttr::new(NFTA_CHAIN_USERDATA, userdata.clone()));
+    }
+
+    new_segment(msg_type, table.family, request_header, generation, attrs)
+}
@@ -0,0 +1,634 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Parse and dump the expressions of rules.
+//!
+//! Each expression is an `NFTA_LIST_ELEM` attribute that contains the name of the expression
+//! (`NFTA_EXPR_NAME`) and its nested attributes (`NFTA_EXPR_DATA`).
+//!
+//! Like Linux, the registers are checked when the expressions are parsed, so that they never
+//! access the registers out of bounds when the expressions are evaluated.
+
+use super::{
+    batch::Transaction,
+    util::{
+        NFT_DATA_VERDICT, NFTA_LIST_ELEM, data_to_attr, find_attr, parse_data, parse_data_value,
+        required_attr, value_to_attr,
+    },
+};
+use crate::{
+    net::{
+        netfilter::{
+            CmpOp, Counter, CtKey, Expr, MetaKey, NF_NAT_RANGE_MAP_IPS,
+            NF_NAT_RANGE_PROTO_SPECIFIED, NFPROTO_IPV4, NFPROTO_IPV6, NFT_LOOKUP_F_INV,
+            NFT_REG_VERDICT, NFT_SET_MAP, NatType, PayloadBase, RejectType, SetData, Table,
+            reg_offset,
+        },
+        socket::netlink::{message::Attribute, netfilter::message::NfAttr},
+    },
+    prelude::*,
+};
+
+const NFTA_EXPR_NAME: u16 = 1;
+const NFTA_EXPR_DATA: u16 = 2;
+
+const NFTA_PAYLOAD_DREG: u16 = 1;
+const NFTA_PAYLOAD_BASE: u16 = 2;
+const NFTA_PAYLOAD_OFFSET: u16 = 3;
+const NFTA_PAYLOAD_LEN: u16 = 4;
+const NFTA_PAYLOAD_SREG: u16 = 5;
+
+const NFTA_CMP_SREG: u16 = 1;
+const NFTA_CMP_OP: u16 = 2;
+const NFTA_CMP_DATA: u16 = 3;
+
+const NFTA_META_DREG: u16 = 1;
+const NFTA_META_KEY: u16 = 2;
+const NFTA_META_SREG: u16 = 3;
+
+const NFTA_IMMEDIATE_DREG: u16 = 1;
+const NFTA_IMMEDIATE_DATA: u16 = 2;
+
+const NFTA_BITWISE_SREG: u16 = 1;
+const NFTA_BITWISE_DREG: u16 = 2;
+const NFTA_BITWISE_LEN: u16 = 3;
+const NFTA_BITWISE_MASK: u16 = 4;
+const NFTA_BITWISE_XOR: u16 = 5;
+const NFTA_BITWISE_OP: u16 = 6;
+const NFTA_BITWISE_DATA: u16 = 7;
+
+/// `NFT_BITWISE_BOOL` in Linux, which is the only supported bitwise operation.
+const NFT_BITWISE_BOOL: u32 = 0;
+
+const NFTA_CT_DREG: u16 = 1;
+const NFTA_CT_KEY: u16 = 2;
+const NFTA_CT_DIRECTION: u16 = 3;
+const NFTA_CT_SREG: u16 = 4;
+
+const NFTA_NAT_TYPE: u16 = 1;
+const NFTA_NAT_FAMILY: u16 = 2;
+const NFTA_NAT_REG_ADDR_MIN: u16 = 3;
+const NFTA_NAT_REG_ADDR_MAX: u16 = 4;
+const NFTA_NAT_REG_PROTO_MIN: u16 = 5;
+const NFTA_NAT_REG_PROTO_MAX: u16 = 6;
+const NFTA_NAT_FLAGS: u16 = 7;
+
+/// `NF_NAT_RANGE_NETMAP` in Linux.
+const NF_NAT_RANGE_NETMAP: u32 = 1 << 6;
+
+const NFTA_MASQ_FLAGS: u16 = 1;
+const NFTA_MASQ_REG_PROTO_MIN: u16 = 2;
+const NFTA_MASQ_REG_PROTO_MAX: u16 = 3;
+
+const NFTA_REDIR_REG_PROTO_MIN: u16 = 1;
+const NFTA_REDIR_REG_PROTO_MAX: u16 = 2;
+const NFTA_REDIR_FLAGS: u16 = 3;
+
+const NFTA_REJECT_TYPE: u16 = 1;
+const NFTA_REJECT_ICMP_CODE: u16 = 2;
+
+/// `NFT_REJECT_ICMPX_MAX` in Linux.
+const NFT_REJECT_ICMPX_MAX: u8 = 3;
+
+const NFTA_COUNTER_BYTES: u16 = 1;
+const NFTA_COUNTER_PACKETS: u16 = 2;
+
+const NFTA_LOOKUP_SET: u16 = 1;
+const NFTA_LOOKUP_SREG: u16 = 2;
+const NFTA_LOOKUP_DREG: u16 = 3;
+const NFTA_LOOKUP_SET_ID: u16 = 4;
+const NFTA_LOOKUP_FLAGS: u16 = 5;
+
+/// Parses an expression in an `NFTA_LIST_ELEM` attribute.
+///
+/// The expression is added to `table`, whose chains and sets can be referred to.
+pub(super) fn parse_expr(attr: &NfAttr, table: &Table, transaction: &Transaction) -> Result<Expr> {
+    if attr.type_() != NFTA_LIST_ELEM {
+        return_errno_with_message!(Errno::EINVAL, "the expression is not a list element");
+    }
+
+    let expr_attrs = attr.as_nested()?;
+    let name = required_attr(&expr_attrs, NFTA_EXPR_NAME)?.as_str()?;
+    let attrs = match find_attr(&expr_attrs, NFTA_EXPR_DATA) {
+        Some(attr) => attr.as_nested()?,
+        None => Vec::new(),
+    };
+
+    match name {
+        "payload" => parse_payload(&attrs),
+        "cmp" => parse_cmp(&attrs),
+        "meta" => parse_meta(&attrs),
+        "immediate" => parse_immediate(&attrs, table, transaction),
+        "bitwise" => parse_bitwise(&attrs),
+        "ct" => parse_ct(&attrs, table),
+        "nat" => parse_nat(&attrs),
+        "masq" => parse_masq(&attrs),
+        "redir" => parse_redir(&attrs),
+        "reject" => parse_reject(&attrs),
+        "counter" => parse_counter(&attrs),
+        "lookup" => parse_lookup(&attrs, table, transaction),
+        _ => return_errno_with_message!(Errno::ENOENT, "the expression type does not exist"),
+    }
+}
+
+fn parse_payload(attrs: &[NfAttr]) -> Result<Expr> {
+    if find_attr(attrs, NFTA_PAYLOAD_SREG).is_some() {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "modifying the packets is not supported");
+    }
+
+    let base = required_attr(attrs, NFTA_PAYLOAD_BASE)?.as_be32()?;
+    let base = PayloadBase::try_from(base)
+        .map_err(|_| Error::with_message(Errno::EOPNOTSUPP, "the payload base is not supported"))?;
+    let offset = required_attr(attrs, NFTA_PAYLOAD_OFFSET)?.as_be32()?;
+    let len = required_attr(attrs, NFTA_PAYLOAD_LEN)?.as_be32()?;
+    let dreg = parse_store_reg(attrs, NFTA_PAYLOAD_DREG, len as usize)?;
+
+    Ok(Expr::Payload {
+        base,
+        offset,
+        len,
+        dreg,
+    })
+}
+
+fn parse_cmp(attrs: &[NfAttr]) -> Result<Expr> {
+    let op = CmpOp::try_from(required_attr(attrs, NFTA_CMP_OP)?.as_be32()?)?;
+    let data = parse_data_value(required_attr(attrs, NFTA_CMP_DATA)?)?;
+    let sreg = parse_load_reg(attrs, NFTA_CMP_SREG, data.len())?;
+
+    Ok(Expr::Cmp { sreg, op, data })
+}
+
+fn parse_meta(attrs: &[NfAttr]) -> Result<Expr> {
+    if find_attr(attrs, NFTA_META_SREG).is_some() {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "setting the metadata is not supported");
+    }
+
+    let key = required_attr(attrs, NFTA_META_KEY)?.as_be32()?;
+    let key = MetaKey::try_from(key)
+        .map_err(|_| Error::with_message(Errno::EOPNOTSUPP, "the meta key is not supported"))?;
+    let dreg = parse_store_reg(attrs, NFTA_META_DREG, key.len())?;
+
+    Ok(Expr::Meta { key, dreg })
+}
+
+fn parse_immediate(attrs: &[NfAttr], table: &Table, transaction: &Transaction) -> Result<Expr> {
+    let data = parse_data(required_attr(attrs, NFTA_IMMEDIATE_DATA)?, table, |id| {
+        transaction.chain_by_id(table, id)
+    })?;
+
+    let dreg = required_attr(attrs, NFTA_IMMEDIATE_DREG)?.as_be32()?;
+    match &data {
+        SetData::Verdict(_) if dreg == NFT_REG_VERDICT => (),
+        SetData::Verdict(_) => {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "verdicts must be stored in the verdict register"
+            )
+        }
+        SetData::Value(value) => {
+            check_store_reg(dreg, value.len())?;
+        }
+    }
+
+    Ok(Expr::Immediate { dreg, data })
+}
+
+fn parse_bitwise(attrs: &[NfAttr]) -> Result<Expr> {
+    if find_attr(attrs, NFTA_BITWISE_OP)
+        .is_some_and(|attr| attr.as_be32().ok() != Some(NFT_BITWISE_BOOL))
+        || find_attr(attrs, NFTA_BITWISE_DATA).is_some()
+    {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "the bitwise operation is not supported");
+    }
+
+    let len = required_attr(attrs, NFTA_BITWISE_LEN)?.as_be32()? as usize;
+    let mask = parse_data_value(required_attr(attrs, NFTA_BITWISE_MASK)?)?;
+    let xor = parse_data_value(required_attr(attrs, NFTA_BITWISE_XOR)?)?;
+    if mask.len() != len || xor.len() != len {
+        return_errno_with_message!(Errno::EINVAL, "the data length does not match");
+    }
+    let sreg = parse_load_reg(attrs, NFTA_BITWISE_SREG, len)?;
+    let dreg = parse_store_reg(attrs, NFTA_BITWISE_DREG, len)?;
+
+    Ok(Expr::Bitwise {
+        sreg,
+        dreg,
+        mask,
+        xor,
+    })
+}
+
+fn parse_ct(attrs: &[NfAttr], table: &Table) -> Result<Expr> {
+    if find_attr(attrs, NFTA_CT_SREG).is_some() {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "setting the connection tracking information is not supported"
+        );
+    }
+
+    let key = required_attr(attrs, NFTA_CT_KEY)?.as_be32()?;
+    let key = CtKey::try_from(key)
+        .map_err(|_| Error::with_message(Errno::EOPNOTSUPP, "the ct key is not supported"))?;
+    let dir = find_attr(attrs, NFTA_CT_DIRECTION)
+        .map(|attr| attr.as_u8())
+        .transpose()?;
+
+    // Like Linux, the keys of the tuples require a direction, and the other keys do not accept
+    // one.
+    let is_tuple_key = !matches!(
+        key,
+        CtKey::State | CtKey::Direction | CtKey::Status | CtKey::Mark
+    );
+    match dir {
+        Some(0 | 1) if is_tuple_key => (),
+        None if !is_tuple_key => (),
+        _ => return_errno_with_message!(Errno::EINVAL, "the ct direction is not valid"),
+    }
+
+    let dreg = parse_store_reg(attrs, NFTA_CT_DREG, key.len(table.family))?;
+
+    Ok(Expr::Ct { key, dreg, dir })
+}
+
+fn parse_nat(attrs: &[NfAttr]) -> Result<Expr> {
+    let type_ = NatType::try_from(required_attr(attrs, NFTA_NAT_TYPE)?.as_be32()?)?;
+    let family = required_attr(attrs, NFTA_NAT_FAMILY)?.as_be32()?;
+    let addr_len = match u8::try_from(family) {
+        Ok(NFPROTO_IPV4) => 4,
+        Ok(NFPROTO_IPV6) => 16,
+        _ => return_errno_with_message!(Errno::EINVAL, "the NAT family is not valid"),
+    };
+
+    let mut flags = find_attr(attrs, NFTA_NAT_FLAGS)
+        .map(|attr| attr.as_be32())
+        .transpose()?
+        .unwrap_or(0);
+    if flags & NF_NAT_RANGE_NETMAP != 0 {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "mapping the prefixes is not supported");
+    }
+
+    let reg_addr_min = parse_optional_load_reg(attrs, NFTA_NAT_REG_ADDR_MIN, addr_len)?;
+    let reg_addr_max = parse_optional_load_reg(attrs, NFTA_NAT_REG_ADDR_MAX, addr_len)?;
+    let (reg_proto_min, reg_proto_max) =
+        parse_port_regs(attrs, NFTA_NAT_REG_PROTO_MIN, NFTA_NAT_REG_PROTO_MAX)?;
+    if reg_addr_min.is_some() {
+        flags |= NF_NAT_RANGE_MAP_IPS;
+    }
+    if reg_proto_min.is_some() {
+        flags |= NF_NAT_RANGE_PROTO_SPECIFIED;
+    }
+
+    Ok(Expr::Nat {
+        type_,
+        family: family as u8,
+        reg_addr_min,
+        reg_addr_max: reg_addr_max.or(reg_addr_min),
+        reg_proto_min,
+        reg_proto_max,
+        flags,
+    })
+}
+
+fn parse_masq(attrs: &[NfAttr]) -> Result<Expr> {
+    let flags = find_attr(attrs, NFTA_MASQ_FLAGS)
+        .map(|attr| attr.as_be32())
+        .transpose()?
+        .unwrap_or(0);
+    let (reg_proto_min, reg_proto_max) =
+        parse_port_regs(attrs, NFTA_MASQ_REG_PROTO_MIN, NFTA_MASQ_REG_PROTO_MAX)?;
+
+    Ok(Expr::Masq {
+        flags,
+        reg_proto_min,
+        reg_proto_max,
+    })
+}
+
+fn parse_redir(attrs: &[NfAttr]) -> Result<Expr> {
+    let flags = find_attr(attrs, NFTA_REDIR_FLAGS)
+        .map(|attr| attr.as_be32())
+        .transpose()?
+        .unwrap_or(0);
+    let (reg_proto_min, reg_proto_max) =
+        parse_port_regs(attrs, NFTA_REDIR_REG_PROTO_MIN, NFTA_REDIR_REG_PROTO_MAX)?;
+
+    Ok(Expr::Redir {
+        flags,
+        reg_proto_min,
+        reg_proto_max,
+    })
+}
+
+fn parse_reject(attrs: &[NfAttr]) -> Result<Expr> {
+    let type_ = RejectType::try_from(required_attr(attrs, NFTA_REJECT_TYPE)?.as_be32()?)?;
+    let icmp_code = find_attr(attrs, NFTA_REJECT_ICMP_CODE)
+        .map(|attr| attr.as_u8())
+        .transpose()?;
+
+    let icmp_code = match type_ {
+        RejectType::TcpRst => 0,
+        RejectType::IcmpUnreach => icmp_code
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the ICMP code is missing"))?,
+        RejectType::IcmpxUnreach => match icmp_code {
+            Some(code) if code <= NFT_REJECT_ICMPX_MAX => code,
+            _ => return_errno_with_message!(Errno::EINVAL, "the ICMPX code is not valid"),
+        },
+    };
+
+    Ok(Expr::Reject { type_, icmp_code })
+}
+
+fn parse_counter(attrs: &[NfAttr]) -> Result<Expr> {
+    let parse_count = |type_| {
+        find_attr(attrs, type_)
+            .map(|attr| attr.as_be64())
+            .transpose()
+            .map(|count| count.unwrap_or(0))
+    };
+    let bytes = parse_count(NFTA_COUNTER_BYTES)?;
+    let packets = parse_count(NFTA_COUNTER_PACKETS)?;
+
+    Ok(Expr::Counter(Arc::new(Counter::new(packets, bytes))))
+}
+
+fn parse_lookup(attrs: &[NfAttr], table: &Table, transaction: &Transaction) -> Result<Expr> {
+    let set_name = required_attr(attrs, NFTA_LOOKUP_SET)?.as_str()?;
+    let set = match table.find_set(set_name) {
+        Some(set) => set,
+        None => find_attr(attrs, NFTA_LOOKUP_SET_ID)
+            .map(|attr| attr.as_be32())
+            .transpose()?
+            .and_then(|id| transaction.set_by_id(table, id))
+            .and_then(|name| table.find_set(name))
+            .ok_or_else(|| Error::with_message(Errno::ENOENT, "the set does not exist"))?,
+    };
+
+    let flags = find_attr(attrs, NFTA_LOOKUP_FLAGS)
+        .map(|attr| attr.as_be32())
+        .transpose()?
+        .unwrap_or(0);
+    if flags & !NFT_LOOKUP_F_INV != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the lookup flags are not valid");
+    }
+
+    let sreg = parse_load_reg(attrs, NFTA_LOOKUP_SREG, set.key_len as usize)?;
+
+    let dreg = match find_attr(attrs, NFTA_LOOKUP_DREG) {
+        Some(attr) => {
+            if flags & NFT_LOOKUP_F_INV != 0 {
+                return_errno_with_message!(Errno::EINVAL, "inverted lookups cannot load data");
+            }
+            if set.flags & NFT_SET_MAP == 0 {
+                return_errno_with_message!(Errno::EINVAL, "the set is not a map");
+            }
+
+            let dreg = attr.as_be32()?;
+            if set.data_type == Some(NFT_DATA_VERDICT) {
+                if dreg != NFT_REG_VERDICT {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "verdicts must be stored in the verdict register"
+                    );
+                }
+            } else {
+                check_store_reg(dreg, set.data_len.unwrap_or(0) as usize)?;
+            }
+            Some(dreg)
+        }
+        None => None,
+    };
+
+    // Like Linux, an anonymous set can only be bound to one rule.
+    if set.is_anonymous()
+        && table
+            .chains
+            .iter()
+            .flat_map(|chain| chain.rules.iter())
+            .flat_map(|rule| rule.sets())
+            .any(|name| name == set.name)
+    {
+        return_errno_with_message!(Errno::EBUSY, "the anonymous set is already bound");
+    }
+
+    Ok(Expr::Lookup {
+        set: set.name.clone(),
+        sreg,
+        dreg,
+        flags,
+    })
+}
+
+/// Parses the source register that loads data of the length.
+fn parse_load_reg(attrs: &[NfAttr], type_: u16, len: usize) -> Result<u32> {
+    let reg = required_attr(attrs, type_)?.as_be32()?;
+    if reg == NFT_REG_VERDICT {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "data cannot be loaded from the verdict register"
+        );
+    }
+    if reg_offset(reg, len).is_none() {
+        return_errno_with_message!(Errno::ERANGE, "the register is out of range");
+    }
+    Ok(reg)
+}
+
+fn parse_optional_load_reg(attrs: &[NfAttr], type_: u16, len: usize) -> Result<Option<u32>> {
+    if find_attr(attrs, type_).is_none() {
+        return Ok(None);
+    }
+    parse_load_reg(attrs, type_, len).map(Some)
+}
+
+/// Parses the destination register that stores data of the length.
+fn parse_store_reg(attrs: &[NfAttr], type_: u16, len: usize) -> Result<u32> {
+    let reg = required_attr(attrs, type_)?.as_be32()?;
+    check_store_reg(reg, len)?;
+    Ok(reg)
+}
+
+fn check_store_reg(reg: u32, len: usize) -> Result<()> {
+    if reg == NFT_REG_VERDICT {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "data cannot be stored in the verdict register"
+        );
+    }
+    if reg_offset(reg, len).is_none() {
+        return_errno_with_message!(Errno::ERANGE, "the register is out of range");
+    }
+    Ok(())
+}
+
+/// Parses the registers of the port range, where the maximum defaults to the minimum.
+fn parse_port_regs(
+    attrs: &[NfAttr],
+    min_type: u16,
+    max_type: u16,
+) -> Result<(Option<u32>, Option<u32>)> {
+    const PORT_LEN: usize = 2;
+
+    let Some(min) = parse_optional_load_reg(attrs, min_type, PORT_LEN)? else {
+        return Ok((None, None));
+    };
+    let max = parse_optional_load_reg(attrs, max_type, PORT_LEN)?.unwrap_or(min);
+    Ok((Some(min), Some(max)))
+}
+
+/// Creates an `NFTA_LIST_ELEM` attribute that contains the expression.
+pub(super) fn expr_to_attr(expr: &Expr) -> NfAttr {
+    let (name, attrs) = match expr {
+        Expr::Payload {
+            base,
+            offset,
+            len,
+            dreg,
+        } => (
+            "payload",
+            vec![
+                NfAttr::new_be32(NFTA_PAYLOAD_DREG, *dreg),
+                NfAttr::new_be32(NFTA_PAYLOAD_BASE, *base as u32),
+                NfAttr::new_be32(NFTA_PAYLOAD_OFFSET, *offset),
+                NfAttr::new_be32(NFTA_PAYLOAD_LEN, *len),
+            ],
+        ),
+        Expr::Cmp { sreg, op, data } => (
+            "cmp",
+            vec![
+                NfAttr::new_be32(NFTA_CMP_SREG, *sreg),
+                NfAttr::new_be32(NFTA_CMP_OP, *op as u32),
+                value_to_attr(NFTA_CMP_DATA, data),
+            ],
+        ),
+        Expr::Meta { key, dreg } => (
+            "meta",
+            vec![
+                NfAttr::new_be32(NFTA_META_KEY, *key as u32),
+                NfAttr::new_be32(NFTA_META_DREG, *dreg),
+            ],
+        ),
+        Expr::Immediate { dreg, data } => (
+            "immediate",
+            vec![
+                NfAttr::new_be32(NFTA_IMMEDIATE_DREG, *dreg),
+                data_to_attr(NFTA_IMMEDIATE_DATA, data),
+            ],
+        ),
+        Expr::Bitwise {
+            sreg,
+            dreg,
+            mask,
+            xor,
+        } => (
+            "bitwise",
+            vec![
+                NfAttr::new_be32(NFTA_BITWISE_SREG, *sreg),
+                NfAttr::new_be32(NFTA_BITWISE_DREG, *dreg),
+                NfAttr::new_be32(NFTA_BITWISE_LEN, mask.len() as u32),
+                NfAttr::new_be32(NFTA_BITWISE_OP, NFT_BITWISE_BOOL),
+                value_to_attr(NFTA_BITWISE_MASK, mask),
+                value_to_attr(NFTA_BITWISE_XOR, xor),
+            ],
+        ),
+        Expr::Ct { key, dreg, dir } => {
+            let mut attrs = vec![
+                NfAttr::new_be32(NFTA_CT_DREG, *dreg),
+                NfAttr::new_be32(NFTA_CT_KEY, *key as u32),
+            ];
+            if let Some(dir) = dir {
+                attrs.push(NfAttr::new_u8(NFTA_CT_DIRECTION, *dir));
+            }
+            ("ct", attrs)
+        }
+        Expr::Nat {
+            type_,
+            family,
+            reg_addr_min,
+            reg_addr_max,
+            reg_proto_min,
+            reg_proto_max,
+            flags,
+        } => {
+            let mut attrs = vec![
+                NfAttr::new_be32(NFTA_NAT_TYPE, *type_ as u32),
+                NfAttr::new_be32(NFTA_NAT_FAMILY, *family as u32),
+            ];
+            push_reg_attr(&mut attrs, NFTA_NAT_REG_ADDR_MIN, *reg_addr_min);
+            push_reg_attr(&mut attrs, NFTA_NAT_REG_ADDR_MAX, *reg_addr_max);
+            push_reg_attr(&mut attrs, NFTA_NAT_REG_PROTO_MIN, *reg_proto_min);
+            push_reg_attr(&mut attrs, NFTA_NAT_REG_PROTO_MAX, *reg_proto_max);
+            if *flags != 0 {
+                attrs.push(NfAttr::new_be32(NFTA_NAT_FLAGS, *flags));
+            }
+            ("nat", attrs)
+        }
+        Expr::Masq {
+            flags,
+            reg_proto_min,
+            reg_proto_max,
+        } => {
+            let mut attrs = Vec::new();
+            if *flags != 0 {
+                attrs.push(NfAttr::new_be32(NFTA_MASQ_FLAGS, *flags));
+            }
+            push_reg_attr(&mut attrs, NFTA_MASQ_REG_PROTO_MIN, *reg_proto_min);
+            push_reg_attr(&mut attrs, NFTA_MASQ_REG_PROTO_MAX, *reg_proto_max);
+            ("masq", attrs)
+        }
+        Expr::Redir {
+            flags,
+            reg_proto_min,
+            reg_proto_max,
+        } => {
+            let mut attrs = Vec::new();
+            push_reg_attr(&mut attrs, NFTA_REDIR_REG_PROTO_MIN, *reg_proto_min);
+            push_reg_attr(&mut attrs, NFTA_REDIR_REG_PROTO_MAX, *reg_proto_max);
+            if *flags != 0 {
+                attrs.push(NfAttr::new_be32(NFTA_REDIR_FLAGS, *flags));
+            }
+            ("redir", attrs)
+        }
+        Expr::Reject { type_, icmp_code } => {
+            let mut attrs = vec![NfAttr::new_be32(NFTA_REJECT_TYPE, *type_ as u32)];
+            if *type_ != RejectType::TcpRst {
+                attrs.push(NfAttr::new_u8(NFTA_REJECT_ICMP_CODE, *icmp_code));
+            }
+            ("reject", attrs)
+        }
+        Expr::Counter(counter) => (
+            "counter",
+            vec![
+                NfAttr::new_be64(NFTA_COUNTER_BYTES, counter.bytes()),
+                NfAttr::new_be64(NFTA_COUNTER_PACKETS, counter.packets()),
+            ],
+        ),
+        Expr::Lookup {
+            set,
+            sreg,
+            dreg,
+            flags,
+        } => {
+            let mut attrs = vec![
+                NfAttr::new_str(NFTA_LOOKUP_SET, set),
+                NfAttr::new_be32(NFTA_LOOKUP_SREG, *sreg),
+            ];
+            push_reg_attr(&mut attrs, NFTA_LOOKUP_DREG, *dreg);
+            attrs.push(NfAttr::new_be32(NFTA_LOOKUP_FLAGS, *flags));
+            ("lookup", attrs)
+        }
+    };
+
+    NfAttr::new_nested(
+        NFTA_LIST_ELEM,
+        vec![
+            NfAttr::new_str(NFTA_EXPR_NAME, name),
+            NfAttr::new_nested(NFTA_EXPR_DATA, attrs),
+        ],
+    )
+}
+
+fn push_reg_attr(attrs: &mut Vec<NfAttr>, type_: u16, reg: Option<u32>) {
+    if let Some(reg) = reg {
+        attrs.push(NfAttr::new_be32(type_, reg));
+    }
+}
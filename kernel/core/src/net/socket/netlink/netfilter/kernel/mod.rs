@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module defines the kernel socket,
+//! which is responsible for handling requests from user space.
+
+use core::marker::PhantomData;
+
+use super::message::{NfnlMessage, NfnlSegment, NftMsgType};
+use crate::{
+    net::{
+        net_ns::NetNamespace,
+        socket::netlink::{
+            addr::PortNum,
+            message::{ErrorSegment, ProtocolSegment},
+            table::{NetlinkNetfilterProtocol, SupportedNetlinkProtocol},
+        },
+    },
+    prelude::*,
+};
+
+mod batch;
+mod chain;
+mod expr;
+mod rule;
+mod set;
+mod table;
+mod util;
+
+/// The maximum length of a message that contains dumped segments.
+const MAX_DUMP_MESSAGE_LEN: usize = PAGE_SIZE;
+
+pub(super) struct NetlinkNetfilterKernelSocket {
+    _private: PhantomData<()>,
+}
+
+impl NetlinkNetfilterKernelSocket {
+    const fn new() -> Self {
+        Self {
+            _private: PhantomData,
+        }
+    }
+
+    /// Handles the requests sent in a single message.
+    ///
+    /// If the first request is `NFNL_MSG_BATCH_BEGIN`, the requests form a batch. Otherwise,
+    /// the requests are handled one by one, and only get requests are allowed.
+    pub(super) fn handle_requests(
+        &self,
+        net_ns: &NetNamespace,
+        requests: Vec<Result<NfnlSegment, ErrorSegment>>,
+        dst_port: PortNum,
+    ) {
+        let mut requests = requests.into_iter();
+        let Some(first_request) = requests.next() else {
+            return;
+        };
+
+        // Like Linux, all requests require `CAP_NET_ADMIN`, including the get requests.
+        if let Err(error) = util::check_net_admin(net_ns) {
+            let err_segment = match first_request {
+                Ok(segment) => ErrorSegment::new_from_request(segment.header(), Some(error)),
+                Err(err_segment) => err_segment,
+            };
+            self.report_error(err_segment, dst_port);
+            return;
+        }
+
+        if let Ok(NfnlSegment::BatchBegin(begin)) = &first_request {
+            batch::handle_batch(net_ns, begin, requests, dst_port);
+            return;
+        }
+
+        for request in core::iter::once(first_request).chain(requests) {
+            match request {
+                Ok(segment) => self.handle_request(net_ns, &segment, dst_port),
+                Err(err_segment) => self.report_error(err_segment, dst_port),
+            }
+        }
+    }
+
+    fn handle_request(&self, net_ns: &NetNamespace, request: &NfnlSegment, dst_port: PortNum) {
+        debug!("netlink netfilter request: {:?}", request);
+
+        let request_header = request.header();
+
+        let response_segments = match request {
+            NfnlSegment::Nftables(msg_type, segment) => match msg_type {
+                NftMsgType::GETGEN => batch::do_get_gen(net_ns, segment),
+                NftMsgType::GETTABLE => table::do_get_table(net_ns, segment),
+                NftMsgType::GETCHAIN => chain::do_get_chain(net_ns, segment),
+                NftMsgType::GETRULE => rule::do_get_rule(net_ns, segment),
+                NftMsgType::GETSET => set::do_get_set(net_ns, segment),
+                NftMsgType::GETSETELEM => set::do_get_set_elem(net_ns, segment),
+                // Stateful objects and flowtables are not supported, so there is nothing to dump.
+                NftMsgType::GETOBJ | NftMsgType::GETFLOWTABLE if util::is_dump(request_header) => {
+                    let mut segments = Vec::new();
+                    util::finish_response(request_header, true, &mut segments);
+                    Ok(segments)
+                }
+                NftMsgType::GETOBJ | NftMsgType::GETFLOWTABLE => Err(Error::with_message(
+                    Errno::ENOENT,
+                    "stateful objects and flowtables are not supported",
+                )),
+                NftMsgType::GETRULE_RESET | NftMsgType::GETOBJ_RESET => Err(Error::with_message(
+                    Errno::EOPNOTSUPP,
+                    "resetting the counters is not supported",
+                )),
+                // Like Linux, modifications must be sent in batches.
+                _ => Err(Error::with_message(
+                    Errno::EINVAL,
+                    "the request must be sent in a batch",
+                )),
+            },
+            NfnlSegment::BatchBegin(_)
+            | NfnlSegment::BatchEnd(_)
+            | NfnlSegment::Done(_)
+            | NfnlSegment::Error(_) => Err(Error::with_message(
+                Errno::EINVAL,
+                "the segment is not a valid request",
+            )),
+        };
+
+        let mut response_segments = match response_segments {
+            Ok(segments) => segments,
+            Err(error) => {
+                let err_segment = ErrorSegment::new_from_request(request_header, Some(error));
+                self.report_error(err_segment, dst_port);
+                return;
+            }
+        };
+
+        // Like the netlink route protocol, the `NLMSG_DONE` segment is delivered in a separate
+        // message.
+        let done_segments = if matches!(response_segments.last(), Some(NfnlSegment::Done(_))) {
+            response_segments.split_off(response_segments.len() - 1)
+        } else {
+            Vec::new()
+        };
+
+        // A ruleset can be large, so the dumped segments are split into multiple messages like
+        // Linux. Otherwise, the messages may not fit in the buffers of user space (e.g.,
+        // `MNL_SOCKET_BUFFER_SIZE` in libmnl).
+        let mut segments = Vec::new();
+        let mut message_len = 0;
+        for segment in response_segments {
+            let segment_len = segment.header().len as usize;
+            if !segments.is_empty() && message_len + segment_len > MAX_DUMP_MESSAGE_LEN {
+                unicast(core::mem::take(&mut segments), dst_port);
+                message_len = 0;
+            }
+            message_len += segment_len;
+            segments.push(segment);
+        }
+
+        for segments in [segments, done_segments] {
+            if segments.is_empty() {
+                continue;
+            }
+            unicast(segments, dst_port);
+        }
+    }
+
+    fn report_error(&self, err_segment: ErrorSegment, dst_port: PortNum) {
+        unicast(vec![NfnlSegment::Error(err_segment)], dst_port);
+    }
+}
+
+/// Sends the segments to the socket bound to the port in a message.
+fn unicast(segments: Vec<NfnlSegment>, dst_port: PortNum) {
+    let response = NfnlMessage::new(segments);
+
+    debug!("netlink netfilter response: {:?}", response);
+
+    NetlinkNetfilterProtocol::unicast(dst_port, response).unwrap();
+}
+
+/// The kernel socket of `NETLINK_NETFILTER`.
+///
+/// A single socket serves all network namespaces, since it is stateless: the nftables state is
+/// kept in the network namespace of each requesting socket. However, the port numbers to which the
+/// replies are sent are still global, while they are per network namespace in Linux.
+static NETLINK_NETFILTER_KERNEL: NetlinkNetfilterKernelSocket = NetlinkNetfilterKernelSocket::new();
+
+pub(super) fn get_netlink_netfilter_kernel() -> &'static NetlinkNetfilterKernelSocket {
+    &NETLINK_NETFILTER_KERNEL
+}
@@ -0,0 +1,373 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Handle rule-related requests.
+
+use super::{
+    batch::Transaction,
+    chain::validate_table,
+    expr::{expr_to_attr, parse_expr},
+    util::{
+        find_attr, find_table, find_table_mut, finish_response, is_dump, new_segment,
+        parse_userdata, required_attr,
+    },
+};
+use crate::{
+    net::{
+        net_ns::NetNamespace,
+        netfilter::{Chain, Rule, Table},
+        socket::netlink::{
+            message::{CMsgSegHdr, NewRequestFlags},
+            netfilter::message::{NfAttr, NfnlSegment, NftMsgType, NftSegment},
+        },
+    },
+    prelude::*,
+};
+
+const NFTA_RULE_TABLE: u16 = 1;
+const NFTA_RULE_CHAIN: u16 = 2;
+const NFTA_RULE_HANDLE: u16 = 3;
+const NFTA_RULE_EXPRESSIONS: u16 = 4;
+const NFTA_RULE_COMPAT: u16 = 5;
+const NFTA_RULE_POSITION: u16 = 6;
+const NFTA_RULE_USERDATA: u16 = 7;
+const NFTA_RULE_POSITION_ID: u16 = 10;
+const NFTA_RULE_CHAIN_ID: u16 = 11;
+
+/// `NFT_RULE_MAXEXPRS` in Linux.
+const NFT_RULE_MAXEXPRS: usize = 128;
+
+pub(super) fn do_get_rule(net_ns: &NetNamespace, segment: &NftSegment) -> Result<Vec<NfnlSegment>> {
+    let netfilter = net_ns.netfilter();
+    let ruleset = netfilter.ruleset();
+    let generation = netfilter.generation();
+
+    let request_header = segment.header();
+    let family = segment.body().family;
+    let attrs = segment.attrs();
+    let dump_all = is_dump(request_header);
+
+    let mut response_segments = if dump_all {
+        let table_name = find_attr(attrs, NFTA_RULE_TABLE)
+            .map(|attr| attr.as_str())
+            .transpose()?;
+        let chain_name = find_attr(attrs, NFTA_RULE_CHAIN)
+            .map(|attr| attr.as_str())
+            .transpose()?;
+
+        let mut segments = Vec::new();
+        for table in ruleset.tables.iter().filter(|table| {
+            (family == 0 || table.family == family)
+                && table_name.is_none_or(|name| table.name == name)
+        }) {
+            for chain in table
+                .chains
+                .iter()
+                .filter(|chain| chain_name.is_none_or(|name| chain.name == name))
+            {
+                segments.extend(chain.rules.iter().enumerate().map(|(index, rule)| {
+                    rule_to_segment(
+                        NftMsgType::NEWRULE,
+                        request_header,
+                        generation,
+                        table,
+                        chain,
+                        index,
+                        rule,
+                    )
+                }));
+            }
+        }
+        segments
+    } else {
+        let table_name = required_attr(attrs, NFTA_RULE_TABLE)?.as_str()?;
+        let table = find_table(&ruleset, family, table_name)?;
+        let chain = find_chain(table, required_attr(attrs, NFTA_RULE_CHAIN)?.as_str()?)?;
+        let handle = required_attr(attrs, NFTA_RULE_HANDLE)?.as_be64()?;
+        let index = find_rule(chain, handle)?;
+        vec![rule_to_segment(
+            NftMsgType::NEWRULE,
+            request_header,
+            generation,
+            table,
+            chain,
+            index,
+            &chain.rules[index],
+        )]
+    };
+
+    finish_response(request_header, dump_all, &mut response_segments);
+
+    Ok(response_segments)
+}
+
+pub(super) fn do_new_rule(transaction: &mut Transaction, segment: &NftSegment) -> Result<()> {
+    let family = segment.body().family;
+    let attrs = segment.attrs();
+    let request_header = segment.header();
+    let request_flags = NewRequestFlags::from_bits_truncate(request_header.flags);
+
+    if find_attr(attrs, NFTA_RULE_COMPAT).is_some() {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "the xtables compatibility is not supported"
+        );
+    }
+    if find_attr(attrs, NFTA_RULE_POSITION_ID).is_some() {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "referring to the rules in the same batch is not supported"
+        );
+    }
+
+    let table_name = required_attr(attrs, NFTA_RULE_TABLE)?.as_str()?;
+    let table = find_table(&transaction.ruleset, family, table_name)?;
+    let chain_index = find_chain_index(transaction, table, attrs)?;
+
+    let exprs = match find_attr(attrs, NFTA_RULE_EXPRESSIONS) {
+        Some(attr) => attr.as_nested()?,
+        None => Vec::new(),
+    };
+    if exprs.len() > NFT_RULE_MAXEXPRS {
+        return_errno_with_message!(Errno::EINVAL, "the rule has too many expressions");
+    }
+    let exprs = exprs
+        .iter()
+        .map(|attr| parse_expr(attr, table, transaction))
+        .collect::<Result<Vec<_>>>()?;
+
+    let handle = find_attr(attrs, NFTA_RULE_HANDLE)
+        .map(|attr| attr.as_be64())
+        .transpose()?;
+    let position = find_attr(attrs, NFTA_RULE_POSITION)
+        .map(|attr| attr.as_be64())
+        .transpose()?;
+    let userdata = parse_userdata(attrs, NFTA_RULE_USERDATA);
+
+    let generation = transaction.generation;
+    let table = find_table_mut(&mut transaction.ruleset, family, table_name)?;
+
+    let index = if let Some(handle) = handle {
+        let index = find_rule(&table.chains[chain_index], handle)?;
+        if request_flags.contains(NewRequestFlags::EXCL) {
+            return_errno_with_message!(Errno::EEXIST, "the rule already exists");
+        }
+        if !request_flags.contains(NewRequestFlags::REPLACE) {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "the rule cannot be updated");
+        }
+
+        let rule = Rule {
+            handle: table.alloc_handle(),
+            exprs,
+            userdata,
+        };
+        let old_rule = core::mem::replace(&mut table.chains[chain_index].rules[index], rule);
+        remove_anonymous_sets(table, &[old_rule]);
+        index
+    } else {
+        if !request_flags.contains(NewRequestFlags::CREATE)
+            || request_flags.contains(NewRequestFlags::REPLACE)
+        {
+            return_errno_with_message!(Errno::EINVAL, "the rule must be created");
+        }
+
+        let is_append = request_flags.contains(NewRequestFlags::APPEND);
+        let chain = &table.chains[chain_index];
+        let index = match position {
+            Some(position) if is_append => find_rule(chain, position)? + 1,
+            Some(position) => find_rule(chain, position)?,
+            None if is_append => chain.rules.len(),
+            None => 0,
+        };
+
+        let rule = Rule {
+            handle: table.alloc_handle(),
+            exprs,
+            userdata,
+        };
+        table.chains[chain_index].rules.insert(index, rule);
+        index
+    };
+
+    validate_table(table)?;
+
+    let chain = &table.chains[chain_index];
+    let segment = rule_to_segment(
+        NftMsgType::NEWRULE,
+        request_header,
+        generation,
+        table,
+        chain,
+        index,
+        &chain.rules[index],
+    );
+    transaction.notify(request_header, segment);
+
+    Ok(())
+}
+
+/// Deletes a rule, or all rules in a chain or a table if no rule is specified.
+///
+/// If `is_destroy` is true (`NFT_MSG_DESTROYRULE`), it is not an error if the rule does not
+/// exist.
+pub(super) fn do_del_rule(
+    transaction: &mut Transaction,
+    segment: &NftSegment,
+    is_destroy: bool,
+) -> Result<()> {
+    let family = segment.body().family;
+    let attrs = segment.attrs();
+    let request_header = segment.header();
+
+    let generation = transaction.generation;
+    let table_name = required_attr(attrs, NFTA_RULE_TABLE)?.as_str()?;
+    let table = find_table(&transaction.ruleset, family, table_name)?;
+
+    let chain_index = if find_attr(attrs, NFTA_RULE_CHAIN).is_some()
+        || find_attr(attrs, NFTA_RULE_CHAIN_ID).is_some()
+    {
+        Some(find_chain_index(transaction, table, attrs)?)
+    } else {
+        None
+    };
+    let handle = find_attr(attrs, NFTA_RULE_HANDLE)
+        .map(|attr| attr.as_be64())
+        .transpose()?;
+
+    let table = find_table_mut(&mut transaction.ruleset, family, table_name)?;
+
+    // Collect the deleted rules with their chains and positions, so that the notifications can be
+    // generated.
+    let mut deleted_rules = Vec::new();
+    match (chain_index, handle) {
+        (Some(chain_index), Some(handle)) => {
+            let chain = &mut table.chains[chain_index];
+            match chain.rules.iter().position(|rule| rule.handle == handle) {
+                Some(index) => deleted_rules.push((chain_index, index, chain.rules.remove(index))),
+                None if is_destroy => return Ok(()),
+                None => return_errno_with_message!(Errno::ENOENT, "the rule does not exist"),
+            }
+        }
+        (Some(chain_index), None) => {
+            let rules = core::mem::take(&mut table.chains[chain_index].rules);
+            deleted_rules.extend(
+                rules
+                    .into_iter()
+                    .enumerate()
+                    .map(|(index, rule)| (chain_index, index, rule)),
+            );
+        }
+        // Like Linux, the handle is ignored if the chain is not specified.
+        (None, _) => {
+            for (chain_index, chain) in table.chains.iter_mut().enumerate() {
+                let rules = core::mem::take(&mut chain.rules);
+                deleted_rules.extend(
+                    rules
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, rule)| (chain_index, index, rule)),
+                );
+            }
+        }
+    }
+
+    let segments: Vec<_> = deleted_rules
+        .iter()
+        .map(|(chain_index, index, rule)| {
+            rule_to_segment(
+                NftMsgType::DELRULE,
+                request_header,
+                generation,
+                table,
+                &table.chains[*chain_index],
+                *index,
+                rule,
+            )
+        })
+        .collect();
+
+    let deleted_rules: Vec<_> = deleted_rules.into_iter().map(|(_, _, rule)| rule).collect();
+    remove_anonymous_sets(table, &deleted_rules);
+
+    for segment in segments {
+        transaction.notify(request_header, segment);
+    }
+
+    Ok(())
+}
+
+/// Finds the chain of a rule by `NFTA_RULE_CHAIN` or `NFTA_RULE_CHAIN_ID`.
+fn find_chain_index(transaction: &Transaction, table: &Table, attrs: &[NfAttr]) -> Result<usize> {
+    let name = if let Some(attr) = find_attr(attrs, NFTA_RULE_CHAIN) {
+        attr.as_str()?
+    } else {
+        let id = required_attr(attrs, NFTA_RULE_CHAIN_ID)?.as_be32()?;
+        transaction
+            .chain_by_id(table, id)
+            .ok_or_else(|| Error::with_message(Errno::ENOENT, "the chain does not exist"))?
+    };
+
+    table
+        .chains
+        .iter()
+        .position(|chain| chain.name == name)
+        .ok_or_else(|| Error::with_message(Errno::ENOENT, "the chain does not exist"))
+}
+
+fn find_chain<'a>(table: &'a Table, name: &str) -> Result<&'a Chain> {
+    table
+        .find_chain(name)
+        .ok_or_else(|| Error::with_message(Errno::ENOENT, "the chain does not exist"))
+}
+
+fn find_rule(chain: &Chain, handle: u64) -> Result<usize> {
+    chain
+        .rules
+        .iter()
+        .position(|rule| rule.handle == handle)
+        .ok_or_else(|| Error::with_message(Errno::ENOENT, "the rule does not exist"))
+}
+
+/// Removes the anonymous sets that are bound to the removed rules.
+///
+/// Like Linux, an anonymous set is owned by the rule that it is bound to.
+fn remove_anonymous_sets(table: &mut Table, removed_rules: &[Rule]) {
+    let removed_sets: Vec<&str> = removed_rules.iter().flat_map(|rule| rule.sets()).collect();
+    if removed_sets.is_empty() {
+        return;
+    }
+
+    table
+        .sets
+        .retain(|set| !(set.is_anonymous() && removed_sets.contains(&set.name.as_str())));
+}
+
+fn rule_to_segment(
+    msg_type: NftMsgType,
+    request_header: &CMsgSegHdr,
+    generation: u32,
+    table: &Table,
+    chain: &Chain,
+    index: usize,
+    rule: &Rule,
+) -> NfnlSegment {
+    let mut attrs = vec![
+        NfAttr::new_str(NFTA_RULE_TABLE, &table.name),
+        NfAttr::new_str(NFTA_RULE_CHAIN, &chain.name),
+        NfAttr::new_be64(NFTA_RULE_HANDLE, rule.handle),
+    ];
+    // Like Linux, the position is the handle of the previous rule.
+    if msg_type == NftMsgType::NEWRULE
+        && let Some(prev) = index.checked_sub(1).and_then(|prev| chain.rules.get(prev))
+    {
+        attrs.push(NfAttr::new_be64(NFTA_RULE_POSITION, prev.handle));
+    }
+    attrs.push(NfAttr::new_nested(
+        NFTA_RULE_EXPRESSIONS,
+        rule.exprs.iter().map(expr_to_attr).collect(),
+    ));
+    if let Some(userdata) = rule.userdata.as_ref() {
+        attrs.push(NfAttr::new(NFTA_RULE_USERDATA, userdata.clone()));
+    }
+
+    new_segment(msg_type, table.family, request_header, generation, attrs)
+}
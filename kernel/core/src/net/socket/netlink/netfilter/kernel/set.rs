@@ -0,0 +1,648 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Handle requests related to sets and their elements.
+
+use super::{
+    batch::Transaction,
+    chain::validate_table,
+    util::{
+        NFT_DATA_VALUE_MAXLEN, NFT_DATA_VERDICT, NFTA_LIST_ELEM, data_to_attr, find_attr,
+        find_table, find_table_mut, finish_response, is_dump, new_segment, parse_data,
+        parse_data_value, parse_name, parse_userdata, required_attr, value_to_attr,
+    },
+};
+use crate::{
+    net::{
+        net_ns::NetNamespace,
+        netfilter::{
+            NFT_SET_ANONYMOUS, NFT_SET_ELEM_INTERVAL_END, NFT_SET_INTERVAL, NFT_SET_MAP, Set,
+            SetData, SetElem, Table,
+        },
+        socket::netlink::{
+            message::{CMsgSegHdr, NewRequestFlags},
+            netfilter::message::{NfAttr, NfnlSegment, NftMsgType, NftSegment},
+        },
+    },
+    prelude::*,
+};
+
+const NFTA_SET_TABLE: u16 = 1;
+const NFTA_SET_NAME: u16 = 2;
+const NFTA_SET_FLAGS: u16 = 3;
+const NFTA_SET_KEY_TYPE: u16 = 4;
+const NFTA_SET_KEY_LEN: u16 = 5;
+const NFTA_SET_DATA_TYPE: u16 = 6;
+const NFTA_SET_DATA_LEN: u16 = 7;
+const NFTA_SET_POLICY: u16 = 8;
+const NFTA_SET_DESC: u16 = 9;
+const NFTA_SET_ID: u16 = 10;
+const NFTA_SET_TIMEOUT: u16 = 11;
+const NFTA_SET_GC_INTERVAL: u16 = 12;
+const NFTA_SET_USERDATA: u16 = 13;
+const NFTA_SET_OBJ_TYPE: u16 = 15;
+const NFTA_SET_HANDLE: u16 = 16;
+const NFTA_SET_EXPR: u16 = 17;
+const NFTA_SET_EXPRESSIONS: u16 = 18;
+
+/// `NFT_SET_CONSTANT` in Linux.
+const NFT_SET_CONSTANT: u32 = 1 << 1;
+
+const NFTA_SET_ELEM_LIST_TABLE: u16 = 1;
+const NFTA_SET_ELEM_LIST_SET: u16 = 2;
+const NFTA_SET_ELEM_LIST_ELEMENTS: u16 = 3;
+const NFTA_SET_ELEM_LIST_SET_ID: u16 = 4;
+
+const NFTA_SET_ELEM_KEY: u16 = 1;
+const NFTA_SET_ELEM_DATA: u16 = 2;
+const NFTA_SET_ELEM_FLAGS: u16 = 3;
+const NFTA_SET_ELEM_TIMEOUT: u16 = 4;
+const NFTA_SET_ELEM_USERDATA: u16 = 6;
+const NFTA_SET_ELEM_EXPR: u16 = 7;
+const NFTA_SET_ELEM_OBJREF: u16 = 9;
+const NFTA_SET_ELEM_KEY_END: u16 = 10;
+const NFTA_SET_ELEM_EXPRESSIONS: u16 = 11;
+
+/// The maximum number of elements that are dumped in a segment.
+const MAX_DUMPED_ELEMS: usize = 16;
+
+pub(super) fn do_get_set(net_ns: &NetNamespace, segment: &NftSegment) -> Result<Vec<NfnlSegment>> {
+    let netfilter = net_ns.netfilter();
+    let ruleset = netfilter.ruleset();
+    let generation = netfilter.generation();
+
+    let request_header = segment.header();
+    let family = segment.body().family;
+    let attrs = segment.attrs();
+    let dump_all = is_dump(request_header);
+
+    let mut response_segments = if dump_all {
+        let table_name = find_attr(attrs, NFTA_SET_TABLE)
+            .map(|attr| attr.as_str())
+            .transpose()?;
+        ruleset
+            .tables
+            .iter()
+            .filter(|table| {
+                (family == 0 || table.family == family)
+                    && table_name.is_none_or(|name| table.name == name)
+            })
+            .flat_map(|table| {
+                table.sets.iter().map(move |set| {
+                    set_to_segment(NftMsgType::NEWSET, request_header, generation, table, set)
+                })
+            })
+            .collect()
+    } else {
+        let table_name = required_attr(attrs, NFTA_SET_TABLE)?.as_str()?;
+        let table = find_table(&ruleset, family, table_name)?;
+        let name = required_attr(attrs, NFTA_SET_NAME)?.as_str()?;
+        let set = table
+            .find_set(name)
+            .ok_or_else(|| Error::with_message(Errno::ENOENT, "the set does not exist"))?;
+        vec![set_to_segment(
+            NftMsgType::NEWSET,
+            request_header,
+            generation,
+            table,
+            set,
+        )]
+    };
+
+    finish_response(request_header, dump_all, &mut response_segments);
+
+    Ok(response_segments)
+}
+
+pub(super) fn do_new_set(transaction: &mut Transaction, segment: &NftSegment) -> Result<()> {
+    let family = segment.body().family;
+    let attrs = segment.attrs();
+    let request_header = segment.header();
+    let request_flags = NewRequestFlags::from_bits_truncate(request_header.flags);
+
+    if [
+        NFTA_SET_TIMEOUT,
+        NFTA_SET_GC_INTERVAL,
+        NFTA_SET_OBJ_TYPE,
+        NFTA_SET_EXPR,
+        NFTA_SET_EXPRESSIONS,
+    ]
+    .into_iter()
+    .any(|type_| find_attr(attrs, type_).is_some())
+    {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "the set attributes are not supported");
+    }
+
+    let flags = find_attr(attrs, NFTA_SET_FLAGS)
+        .map(|attr| attr.as_be32())
+        .transpose()?
+        .unwrap_or(0);
+    if flags & !(NFT_SET_ANONYMOUS | NFT_SET_CONSTANT | NFT_SET_INTERVAL | NFT_SET_MAP) != 0 {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "the set flags are not supported");
+    }
+
+    let key_type = find_attr(attrs, NFTA_SET_KEY_TYPE)
+        .map(|attr| attr.as_be32())
+        .transpose()?
+        .unwrap_or(0);
+    let key_len = required_attr(attrs, NFTA_SET_KEY_LEN)?.as_be32()?;
+    if key_len == 0 || key_len as usize > NFT_DATA_VALUE_MAXLEN {
+        return_errno_with_message!(Errno::EINVAL, "the key length is not valid");
+    }
+
+    let (data_type, data_len) = if flags & NFT_SET_MAP != 0 {
+        let data_type = required_attr(attrs, NFTA_SET_DATA_TYPE)?.as_be32()?;
+        let data_len = if data_type == NFT_DATA_VERDICT {
+            None
+        } else {
+            let data_len = required_attr(attrs, NFTA_SET_DATA_LEN)?.as_be32()?;
+            if data_len == 0 || data_len as usize > NFT_DATA_VALUE_MAXLEN {
+                return_errno_with_message!(Errno::EINVAL, "the data length is not valid");
+            }
+            Some(data_len)
+        };
+        (Some(data_type), data_len)
+    } else {
+        if find_attr(attrs, NFTA_SET_DATA_TYPE).is_some() {
+            return_errno_with_message!(Errno::EINVAL, "the set is not a map");
+        }
+        (None, None)
+    };
+
+    let policy = find_attr(attrs, NFTA_SET_POLICY)
+        .map(|attr| attr.as_be32())
+        .transpose()?;
+    let id = find_attr(attrs, NFTA_SET_ID)
+        .map(|attr| attr.as_be32())
+        .transpose()?;
+
+    let generation = transaction.generation;
+    let table_name = required_attr(attrs, NFTA_SET_TABLE)?.as_str()?;
+    let table = find_table_mut(&mut transaction.ruleset, family, table_name)?;
+    let table_handle = table.handle;
+
+    let name = parse_name(required_attr(attrs, NFTA_SET_NAME)?)?;
+    let index = if let Some(index) = table.sets.iter().position(|set| set.name == name) {
+        if request_flags.contains(NewRequestFlags::EXCL) {
+            return_errno_with_message!(Errno::EEXIST, "the set already exists");
+        }
+        if request_flags.contains(NewRequestFlags::REPLACE) {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "sets cannot be replaced");
+        }
+
+        // Like Linux, the existing set must have the same type.
+        let set = &table.sets[index];
+        if set.flags != flags
+            || set.key_type != key_type
+            || set.key_len != key_len
+            || set.data_type != data_type
+            || set.data_len != data_len
+        {
+            return_errno_with_message!(Errno::EEXIST, "the set exists with a different type");
+        }
+        index
+    } else {
+        let name = alloc_set_name(table, name)?;
+        let handle = table.alloc_handle();
+        table.sets.push(Set {
+            name,
+            handle,
+            flags,
+            key_type,
+            key_len,
+            data_type,
+            data_len,
+            policy,
+            desc: find_attr(attrs, NFTA_SET_DESC).map(|attr| attr.payload().to_vec()),
+            userdata: parse_userdata(attrs, NFTA_SET_USERDATA),
+            elems: Vec::new(),
+        });
+        table.sets.len() - 1
+    };
+
+    let set = &table.sets[index];
+    let set_name = set.name.clone();
+    let segment = set_to_segment(NftMsgType::NEWSET, request_header, generation, table, set);
+
+    if let Some(id) = id {
+        transaction.add_set_id(table_handle, id, &set_name);
+    }
+    transaction.notify(request_header, segment);
+
+    Ok(())
+}
+
+/// Deletes a set.
+///
+/// If `is_destroy` is true (`NFT_MSG_DESTROYSET`), it is not an error if the set does not exist.
+pub(super) fn do_del_set(
+    transaction: &mut Transaction,
+    segment: &NftSegment,
+    is_destroy: bool,
+) -> Result<()> {
+    let family = segment.body().family;
+    let attrs = segment.attrs();
+    let request_header = segment.header();
+
+    let generation = transaction.generation;
+    let table_name = required_attr(attrs, NFTA_SET_TABLE)?.as_str()?;
+    let table = find_table_mut(&mut transaction.ruleset, family, table_name)?;
+
+    let index = if let Some(attr) = find_attr(attrs, NFTA_SET_HANDLE) {
+        let handle = attr.as_be64()?;
+        table.sets.iter().position(|set| set.handle == handle)
+    } else {
+        let name = required_attr(attrs, NFTA_SET_NAME)?.as_str()?;
+        table.sets.iter().position(|set| set.name == name)
+    };
+    let Some(index) = index else {
+        if is_destroy {
+            return Ok(());
+        }
+        return_errno_with_message!(Errno::ENOENT, "the set does not exist");
+    };
+
+    let set = &table.sets[index];
+    if is_set_bound(table, &set.name) {
+        return_errno_with_message!(Errno::EBUSY, "the set is used by rules");
+    }
+
+    let segment = set_to_segment(NftMsgType::DELSET, request_header, generation, table, set);
+    table.sets.remove(index);
+    transaction.notify(request_header, segment);
+
+    Ok(())
+}
+
+pub(super) fn do_get_set_elem(
+    net_ns: &NetNamespace,
+    segment: &NftSegment,
+) -> Result<Vec<NfnlSegment>> {
+    let request_header = segment.header();
+    if !is_dump(request_header) {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "getting a single element is not supported"
+        );
+    }
+
+    let netfilter = net_ns.netfilter();
+    let ruleset = netfilter.ruleset();
+    let generation = netfilter.generation();
+
+    let family = segment.body().family;
+    let attrs = segment.attrs();
+    let table_name = required_attr(attrs, NFTA_SET_ELEM_LIST_TABLE)?.as_str()?;
+    let table = find_table(&ruleset, family, table_name)?;
+    let set_name = required_attr(attrs, NFTA_SET_ELEM_LIST_SET)?.as_str()?;
+    let set = table
+        .find_set(set_name)
+        .ok_or_else(|| Error::with_message(Errno::ENOENT, "the set does not exist"))?;
+
+    let mut response_segments = set
+        .elems
+        .chunks(MAX_DUMPED_ELEMS)
+        .map(|elems| {
+            elems_to_segment(
+                NftMsgType::NEWSETELEM,
+                request_header,
+                generation,
+                table,
+                set,
+                elems,
+            )
+        })
+        .collect();
+
+    finish_response(request_header, true, &mut response_segments);
+
+    Ok(response_segments)
+}
+
+pub(super) fn do_new_set_elem(transaction: &mut Transaction, segment: &NftSegment) -> Result<()> {
+    let family = segment.body().family;
+    let attrs = segment.attrs();
+    let request_header = segment.header();
+    let request_flags = NewRequestFlags::from_bits_truncate(request_header.flags);
+
+    let generation = transaction.generation;
+    let table_name = required_attr(attrs, NFTA_SET_ELEM_LIST_TABLE)?.as_str()?;
+    let table = find_table(&transaction.ruleset, family, table_name)?;
+    let set_index = find_set_index(transaction, table, attrs)?;
+
+    let set = &table.sets[set_index];
+    if set.flags & NFT_SET_CONSTANT != 0 && is_set_bound(table, &set.name) {
+        return_errno_with_message!(Errno::EBUSY, "the constant set is used by rules");
+    }
+
+    let elems = match find_attr(attrs, NFTA_SET_ELEM_LIST_ELEMENTS) {
+        Some(attr) => attr.as_nested()?,
+        None => Vec::new(),
+    };
+    let elems = elems
+        .iter()
+        .map(|attr| parse_elem(attr, table, set, transaction))
+        .collect::<Result<Vec<_>>>()?;
+    let has_verdicts = set.data_type == Some(NFT_DATA_VERDICT);
+
+    let table = find_table_mut(&mut transaction.ruleset, family, table_name)?;
+
+    let mut segments = Vec::new();
+    for elem in elems {
+        let segment = elems_to_segment(
+            NftMsgType::NEWSETELEM,
+            request_header,
+            generation,
+            table,
+            &table.sets[set_index],
+            core::slice::from_ref(&elem),
+        );
+        if !table.sets[set_index].insert(elem) && request_flags.contains(NewRequestFlags::EXCL) {
+            return_errno_with_message!(Errno::EEXIST, "the element already exists");
+        }
+        segments.push(segment);
+    }
+
+    // The verdicts in the map may jump to other chains.
+    if has_verdicts {
+        validate_table(table)?;
+    }
+
+    for segment in segments {
+        transaction.notify(request_header, segment);
+    }
+
+    Ok(())
+}
+
+/// Deletes elements, or all elements if no element is specified.
+///
+/// If `is_destroy` is true (`NFT_MSG_DESTROYSETELEM`), it is not an error if the elements do not
+/// exist.
+pub(super) fn do_del_set_elem(
+    transaction: &mut Transaction,
+    segment: &NftSegment,
+    is_destroy: bool,
+) -> Result<()> {
+    let family = segment.body().family;
+    let attrs = segment.attrs();
+    let request_header = segment.header();
+
+    let generation = transaction.generation;
+    let table_name = required_attr(attrs, NFTA_SET_ELEM_LIST_TABLE)?.as_str()?;
+    let table = find_table(&transaction.ruleset, family, table_name)?;
+    let set_index = find_set_index(transaction, table, attrs)?;
+
+    let set = &table.sets[set_index];
+    if set.flags & NFT_SET_CONSTANT != 0 && is_set_bound(table, &set.name) {
+        return_errno_with_message!(Errno::EBUSY, "the constant set is used by rules");
+    }
+
+    let elems = match find_attr(attrs, NFTA_SET_ELEM_LIST_ELEMENTS) {
+        Some(attr) => Some(
+            attr.as_nested()?
+                .iter()
+                .map(|attr| parse_elem(attr, table, set, transaction))
+                .collect::<Result<Vec<_>>>()?,
+        ),
+        None => None,
+    };
+
+    let table = find_table_mut(&mut transaction.ruleset, family, table_name)?;
+    let set = &mut table.sets[set_index];
+
+    let deleted_elems = match elems {
+        Some(elems) => {
+            let mut deleted_elems = Vec::new();
+            for elem in elems {
+                match set.remove(&elem.key, elem.flags) {
+                    Some(elem) => deleted_elems.push(elem),
+                    None if is_destroy => (),
+                    None => {
+                        return_errno_with_message!(Errno::ENOENT, "the element does not exist")
+                    }
+                }
+            }
+            deleted_elems
+        }
+        // Like Linux, the set is flushed if no element is specified.
+        None => core::mem::take(&mut set.elems),
+    };
+
+    let set = &table.sets[set_index];
+    let segments: Vec<_> = deleted_elems
+        .iter()
+        .map(|elem| {
+            elems_to_segment(
+                NftMsgType::DELSETELEM,
+                request_header,
+                generation,
+                table,
+                set,
+                core::slice::from_ref(elem),
+            )
+        })
+        .collect();
+    for segment in segments {
+        transaction.notify(request_header, segment);
+    }
+
+    Ok(())
+}
+
+/// Returns whether the set is used by any rule in the table.
+pub(super) fn is_set_bound(table: &Table, name: &str) -> bool {
+    table
+        .chains
+        .iter()
+        .flat_map(|chain| chain.rules.iter())
+        .flat_map(|rule| rule.sets())
+        .any(|set| set == name)
+}
+
+/// Finds the set of the elements by `NFTA_SET_ELEM_LIST_SET` or `NFTA_SET_ELEM_LIST_SET_ID`.
+///
+/// Like Linux, the ID is only used if the set cannot be found by the name, which may be a
+/// template (e.g., `__set%d`) of a set added in the same batch.
+fn find_set_index(transaction: &Transaction, table: &Table, attrs: &[NfAttr]) -> Result<usize> {
+    let name = required_attr(attrs, NFTA_SET_ELEM_LIST_SET)?.as_str()?;
+    if let Some(index) = table.sets.iter().position(|set| set.name == name) {
+        return Ok(index);
+    }
+
+    let id = find_attr(attrs, NFTA_SET_ELEM_LIST_SET_ID)
+        .map(|attr| attr.as_be32())
+        .transpose()?;
+    id.and_then(|id| transaction.set_by_id(table, id))
+        .and_then(|name| table.sets.iter().position(|set| set.name == name))
+        .ok_or_else(|| Error::with_message(Errno::ENOENT, "the set does not exist"))
+}
+
+/// Allocates the name of a new set.
+///
+/// Like Linux, `%d` in the name is replaced by the smallest number that makes the name unique.
+fn alloc_set_name(table: &Table, name: String) -> Result<String> {
+    if !name.contains('%') {
+        return Ok(name);
+    }
+    if name.matches('%').count() > 1 || !name.contains("%d") {
+        return_errno_with_message!(Errno::EINVAL, "the set name is not a valid template");
+    }
+
+    (0..u32::MAX)
+        .map(|n| name.replacen("%d", &n.to_string(), 1))
+        .find(|name| table.find_set(name).is_none())
+        .ok_or_else(|| Error::with_message(Errno::ENFILE, "no set names are available"))
+}
+
+/// Parses an element in an `NFTA_LIST_ELEM` attribute.
+fn parse_elem(
+    attr: &NfAttr,
+    table: &Table,
+    set: &Set,
+    transaction: &Transaction,
+) -> Result<SetElem> {
+    let attrs = attr.as_nested()?;
+    if [
+        NFTA_SET_ELEM_TIMEOUT,
+        NFTA_SET_ELEM_EXPR,
+        NFTA_SET_ELEM_OBJREF,
+        NFTA_SET_ELEM_EXPRESSIONS,
+    ]
+    .into_iter()
+    .any(|type_| find_attr(&attrs, type_).is_some())
+    {
+        return_errno_with_message!(
+            Errno::EOPNOTSUPP,
+            "the element attributes are not supported"
+        );
+    }
+
+    let flags = find_attr(&attrs, NFTA_SET_ELEM_FLAGS)
+        .map(|attr| attr.as_be32())
+        .transpose()?
+        .unwrap_or(0);
+    let is_interval = set.flags & NFT_SET_INTERVAL != 0;
+    if flags & !NFT_SET_ELEM_INTERVAL_END != 0 {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "the element flags are not supported");
+    }
+    if flags & NFT_SET_ELEM_INTERVAL_END != 0 && !is_interval {
+        return_errno_with_message!(Errno::EINVAL, "the set is not an interval set");
+    }
+
+    let parse_key = |attr: &NfAttr| -> Result<Vec<u8>> {
+        let key = parse_data_value(attr)?;
+        if key.len() != set.key_len as usize {
+            return_errno_with_message!(Errno::EINVAL, "the key length does not match");
+        }
+        Ok(key)
+    };
+    let key = parse_key(required_attr(&attrs, NFTA_SET_ELEM_KEY)?)?;
+    let key_end = match find_attr(&attrs, NFTA_SET_ELEM_KEY_END) {
+        Some(_) if !is_interval => {
+            return_errno_with_message!(Errno::EINVAL, "the set is not an interval set")
+        }
+        Some(attr) => Some(parse_key(attr)?),
+        None => None,
+    };
+
+    let data = match (find_attr(&attrs, NFTA_SET_ELEM_DATA), set.data_type) {
+        (Some(attr), Some(data_type)) => {
+            let data = parse_data(attr, table, |id| transaction.chain_by_id(table, id))?;
+            let is_valid = match &data {
+                SetData::Verdict(_) => data_type == NFT_DATA_VERDICT,
+                SetData::Value(value) => set.data_len == Some(value.len() as u32),
+            };
+            if !is_valid {
+                return_errno_with_message!(Errno::EINVAL, "the data does not match the map");
+            }
+            Some(data)
+        }
+        (Some(_), None) => {
+            return_errno_with_message!(Errno::EINVAL, "the set is not a map")
+        }
+        // The end of an interval does not map to any data.
+        (None, Some(_)) if flags & NFT_SET_ELEM_INTERVAL_END == 0 => {
+            return_errno_with_message!(Errno::EINVAL, "the data of the element is missing")
+        }
+        (None, _) => None,
+    };
+
+    Ok(SetElem {
+        key,
+        key_end,
+        data,
+        flags,
+        userdata: parse_userdata(&attrs, NFTA_SET_ELEM_USERDATA),
+    })
+}
+
+fn set_to_segment(
+    msg_type: NftMsgType,
+    request_header: &CMsgSegHdr,
+    generation: u32,
+    table: &Table,
+    set: &Set,
+) -> NfnlSegment {
+    let mut attrs = vec![
+        NfAttr::new_str(NFTA_SET_TABLE, &table.name),
+        NfAttr::new_str(NFTA_SET_NAME, &set.name),
+        NfAttr::new_be64(NFTA_SET_HANDLE, set.handle),
+        NfAttr::new_be32(NFTA_SET_FLAGS, set.flags),
+        NfAttr::new_be32(NFTA_SET_KEY_TYPE, set.key_type),
+        NfAttr::new_be32(NFTA_SET_KEY_LEN, set.key_len),
+    ];
+    if let Some(data_type) = set.data_type {
+        attrs.push(NfAttr::new_be32(NFTA_SET_DATA_TYPE, data_type));
+    }
+    if let Some(data_len) = set.data_len {
+        attrs.push(NfAttr::new_be32(NFTA_SET_DATA_LEN, data_len));
+    }
+    if let Some(policy) = set.policy {
+        attrs.push(NfAttr::new_be32(NFTA_SET_POLICY, policy));
+    }
+    if let Some(userdata) = set.userdata.as_ref() {
+        attrs.push(NfAttr::new(NFTA_SET_USERDATA, userdata.clone()));
+    }
+    if let Some(desc) = set.desc.as_ref() {
+        attrs.push(NfAttr::new(NFTA_SET_DESC, desc.clone()));
+    }
+
+    new_segment(msg_type, table.family, request_header, generation, attrs)
+}
+
+fn elems_to_segment(
+    msg_type: NftMsgType,
+    request_header: &CMsgSegHdr,
+    generation: u32,
+    table: &Table,
+    set: &Set,
+    elems: &[SetElem],
+) -> NfnlSegment {
+    let elem_attrs = elems
+        .iter()
+        .map(|elem| {
+            let mut attrs = vec![value_to_attr(NFTA_SET_ELEM_KEY, &elem.key)];
+            if let Some(key_end) = elem.key_end.as_ref() {
+                attrs.push(value_to_attr(NFTA_SET_ELEM_KEY_END, key_end));
+            }
+            if let Some(data) = elem.data.as_ref() {
+                attrs.push(data_to_attr(NFTA_SET_ELEM_DATA, data));
+            }
+            if elem.flags != 0 {
+                attrs.push(NfAttr::new_be32(NFTA_SET_ELEM_FLAGS, elem.flags));
+            }
+            if let Some(userdata) = elem.userdata.as_ref() {
+                attrs.push(NfAttr::new(NFTA_SET_ELEM_USERDATA, userdata.clone()));
+            }
+            NfAttr::new_nested(NFTA_LIST_ELEM, attrs)
+        })
+        .collect();
+
+    let attrs = vec![
+        NfAttr::new_str(NFTA_SET_ELEM_LIST_TABLE, &table.name),
+        NfAttr::new_str(NFTA_SET_ELEM_LIST_SET, &set.name),
+        NfAttr::new_nested(NFTA_SET_ELEM_LIST_ELEMENTS, elem_attrs),
+    ];
+
+    new_segment(msg_type, table.family, request_header, generation, attrs)
+}
@@ -0,0 +1,192 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Handle table-related requests.
+
+use super::{
+    batch::Transaction,
+    util::{
+        check_family, find_attr, find_table, finish_response, is_dump, new_segment, parse_name,
+        parse_userdata, required_attr,
+    },
+};
+use crate::{
+    net::{
+        net_ns::NetNamespace,
+        netfilter::{NFT_TABLE_F_DORMANT, Table},
+        socket::netlink::{
+            message::{CMsgSegHdr, NewRequestFlags},
+            netfilter::message::{NfAttr, NfnlSegment, NftMsgType, NftSegment},
+        },
+    },
+    prelude::*,
+};
+
+const NFTA_TABLE_NAME: u16 = 1;
+const NFTA_TABLE_FLAGS: u16 = 2;
+const NFTA_TABLE_USE: u16 = 3;
+const NFTA_TABLE_HANDLE: u16 = 4;
+const NFTA_TABLE_USERDATA: u16 = 6;
+
+pub(super) fn do_get_table(
+    net_ns: &NetNamespace,
+    segment: &NftSegment,
+) -> Result<Vec<NfnlSegment>> {
+    let netfilter = net_ns.netfilter();
+    let ruleset = netfilter.ruleset();
+    let generation = netfilter.generation();
+
+    let request_header = segment.header();
+    let family = segment.body().family;
+    let dump_all = is_dump(request_header);
+
+    let mut response_segments = if dump_all {
+        ruleset
+            .tables
+            .iter()
+            .filter(|table| family == 0 || table.family == family)
+            .map(|table| table_to_segment(NftMsgType::NEWTABLE, request_header, generation, table))
+            .collect()
+    } else {
+        let name = required_attr(segment.attrs(), NFTA_TABLE_NAME)?.as_str()?;
+        let table = find_table(&ruleset, family, name)?;
+        vec![table_to_segment(
+            NftMsgType::NEWTABLE,
+            request_header,
+            generation,
+            table,
+        )]
+    };
+
+    finish_response(request_header, dump_all, &mut response_segments);
+
+    Ok(response_segments)
+}
+
+pub(super) fn do_new_table(transaction: &mut Transaction, segment: &NftSegment) -> Result<()> {
+    let family = segment.body().family;
+    check_family(family)?;
+
+    let attrs = segment.attrs();
+    let name = parse_name(required_attr(attrs, NFTA_TABLE_NAME)?)?;
+    let flags = find_attr(attrs, NFTA_TABLE_FLAGS)
+        .map(|attr| attr.as_be32())
+        .transpose()?;
+    if flags.is_some_and(|flags| flags & !NFT_TABLE_F_DORMANT != 0) {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "the table flags are not supported");
+    }
+
+    let request_header = segment.header();
+    let request_flags = NewRequestFlags::from_bits_truncate(request_header.flags);
+    let generation = transaction.generation;
+    let ruleset = &mut transaction.ruleset;
+
+    let index = ruleset
+        .tables
+        .iter()
+        .position(|table| table.family == family && table.name == name);
+    let table = if let Some(index) = index {
+        if request_flags.contains(NewRequestFlags::EXCL) {
+            return_errno_with_message!(Errno::EEXIST, "the table already exists");
+        }
+        if request_flags.contains(NewRequestFlags::REPLACE) {
+            return_errno_with_message!(Errno::EOPNOTSUPP, "tables cannot be replaced");
+        }
+        // Only the flags can be updated.
+        let Some(flags) = flags else {
+            return Ok(());
+        };
+        let table = &mut ruleset.tables[index];
+        table.flags = flags;
+        table
+    } else {
+        ruleset.next_table_handle += 1;
+        ruleset.tables.push(Table {
+            name,
+            family,
+            flags: flags.unwrap_or(0),
+            handle: ruleset.next_table_handle,
+            userdata: parse_userdata(attrs, NFTA_TABLE_USERDATA),
+            chains: Vec::new(),
+            sets: Vec::new(),
+            next_handle: 0,
+        });
+        ruleset.tables.last_mut().unwrap()
+    };
+
+    let segment = table_to_segment(NftMsgType::NEWTABLE, request_header, generation, table);
+    transaction.notify(request_header, segment);
+
+    Ok(())
+}
+
+/// Deletes a table, or all tables if no table is specified.
+///
+/// If `is_destroy` is true (`NFT_MSG_DESTROYTABLE`), it is not an error if the table does not
+/// exist.
+pub(super) fn do_del_table(
+    transaction: &mut Transaction,
+    segment: &NftSegment,
+    is_destroy: bool,
+) -> Result<()> {
+    let family = segment.body().family;
+    let attrs = segment.attrs();
+    let name = find_attr(attrs, NFTA_TABLE_NAME)
+        .map(|attr| attr.as_str())
+        .transpose()?;
+    let handle = find_attr(attrs, NFTA_TABLE_HANDLE)
+        .map(|attr| attr.as_be64())
+        .transpose()?;
+
+    // Like Linux, the tables are flushed if the family or the table is not specified.
+    let is_flush = family == 0 || (name.is_none() && handle.is_none());
+    let is_deleted = |table: &Table| {
+        if is_flush {
+            (family == 0 || table.family == family) && name.is_none_or(|name| table.name == name)
+        } else {
+            table.family == family
+                && name.is_none_or(|name| table.name == name)
+                && handle.is_none_or(|handle| table.handle == handle)
+        }
+    };
+
+    let (deleted_tables, remaining_tables) = core::mem::take(&mut transaction.ruleset.tables)
+        .into_iter()
+        .partition::<Vec<_>, _>(is_deleted);
+    transaction.ruleset.tables = remaining_tables;
+
+    if deleted_tables.is_empty() && !is_flush && !is_destroy {
+        return_errno_with_message!(Errno::ENOENT, "the table does not exist");
+    }
+
+    let request_header = segment.header();
+    for table in deleted_tables.iter() {
+        let segment = table_to_segment(
+            NftMsgType::DELTABLE,
+            request_header,
+            transaction.generation,
+            table,
+        );
+        transaction.notify(request_header, segment);
+    }
+
+    Ok(())
+}
+
+fn table_to_segment(
+    msg_type: NftMsgType,
+    request_header: &CMsgSegHdr,
+    generation: u32,
+    table: &Table,
+) -> NfnlSegment {
+    let mut attrs = vec![
+        NfAttr::new_str(NFTA_TABLE_NAME, &table.name),
+        NfAttr::new_be32(NFTA_TABLE_FLAGS, table.flags),
+        NfAttr::new_be32(NFTA_TABLE_USE, table.chains.len() as u32),
+        NfAttr::new_be64(NFTA_TABLE_HANDLE, table.handle),
+    ];
+    if let Some(userdata) = table.userdata.as_ref() {
+        attrs.push(NfAttr::new(NFTA_TABLE_USERDATA, userdata.clone()));
+    }
+
+    new_segment(msg_type, table.family, request_header, generation, attrs)
+}
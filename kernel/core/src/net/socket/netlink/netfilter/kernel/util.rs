@@ -0,0 +1,297 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    net::{
+        net_ns::NetNamespace,
+        netfilter::{NFPROTO_INET, NFPROTO_IPV4, NFPROTO_IPV6, Ruleset, SetData, Table, Verdict},
+        socket::netlink::{
+            GroupIdSet,
+            message::{
+                Attribute, CMsgSegHdr, DoneSegment, ErrorSegment, GetRequestFlags, ProtocolSegment,
+                SegHdrCommonFlags,
+            },
+            netfilter::message::{
+                NfAttr, NfGenMsg, NfnlMessage, NfnlSegment, NftMsgType, NftSegment,
+            },
+            table::{NetlinkNetfilterProtocol, SupportedNetlinkProtocol},
+        },
+    },
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+    security::lsm::hooks as lsm_hooks,
+};
+
+/// `NFNETLINK_V0` in Linux.
+const NFNETLINK_V0: u8 = 0;
+
+/// `NFT_NAME_MAXLEN` in Linux, which includes the terminating null byte.
+const NFT_NAME_MAXLEN: usize = 256;
+/// `NFT_DATA_VALUE_MAXLEN` in Linux.
+pub(super) const NFT_DATA_VALUE_MAXLEN: usize = 64;
+/// `NFT_DATA_VERDICT` in Linux, which is the data type of verdict maps.
+pub(super) const NFT_DATA_VERDICT: u32 = 0xffffff00;
+
+/// `NFTA_LIST_ELEM` in Linux.
+pub(super) const NFTA_LIST_ELEM: u16 = 1;
+
+const NFTA_DATA_VALUE: u16 = 1;
+const NFTA_DATA_VERDICT: u16 = 2;
+
+const NFTA_VERDICT_CODE: u16 = 1;
+const NFTA_VERDICT_CHAIN: u16 = 2;
+const NFTA_VERDICT_CHAIN_ID: u16 = 3;
+
+/// The verdict codes (`NF_*` and `NFT_*` in Linux).
+const NF_DROP: i32 = 0;
+const NF_ACCEPT: i32 = 1;
+const NFT_CONTINUE: i32 = -1;
+const NFT_BREAK: i32 = -2;
+const NFT_JUMP: i32 = -3;
+const NFT_GOTO: i32 = -4;
+const NFT_RETURN: i32 = -5;
+
+/// `NFNLGRP_NFTABLES` in Linux.
+const NFNLGRP_NFTABLES: u32 = 7;
+
+/// Finishes a response message.
+pub(super) fn finish_response(
+    request_header: &CMsgSegHdr,
+    dump_all: bool,
+    response_segments: &mut Vec<NfnlSegment>,
+) {
+    if !dump_all {
+        assert_eq!(response_segments.len(), 1);
+        return;
+    }
+    append_done_segment(request_header, response_segments);
+    add_multi_flag(response_segments);
+}
+
+/// Returns the response for a successful request that is not a get request.
+///
+/// The response is an acknowledgment if the request asks for one, and is empty otherwise.
+pub(super) fn ack_response(request_header: &CMsgSegHdr) -> Vec<NfnlSegment> {
+    let flags = SegHdrCommonFlags::from_bits_truncate(request_header.flags);
+    if !flags.contains(SegHdrCommonFlags::ACK) {
+        return Vec::new();
+    }
+
+    let ack_segment = ErrorSegment::new_from_request(request_header, None);
+    vec![NfnlSegment::Error(ack_segment)]
+}
+
+/// Checks whether the current thread can administer the network namespace.
+pub(super) fn check_net_admin(net_ns: &NetNamespace) -> Result<()> {
+    lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+        net_ns.owner().as_ref(),
+        current_thread!().as_posix_thread().unwrap(),
+        CapSet::NET_ADMIN,
+    ))
+}
+
+/// Notifies the sockets in the `NFNLGRP_NFTABLES` group of a committed change in the network
+/// namespace.
+///
+/// Only the sockets created in `net_ns` will receive the notification.
+pub(super) fn notify(net_ns: &NetNamespace, segments: Vec<NfnlSegment>) {
+    // Group `n` is represented by bit `n - 1` in `GroupIdSet`.
+    let groups = GroupIdSet::new(1 << (NFNLGRP_NFTABLES - 1));
+    let message = NfnlMessage::new(segments);
+
+    debug!("netlink netfilter notification: {:?}", message);
+
+    NetlinkNetfilterProtocol::multicast_in_ns(net_ns, groups, message).unwrap();
+}
+
+/// Creates a segment of the nftables subsystem.
+///
+/// Like Linux, the lower 16 bits of the generation ID are reported in the resource ID.
+pub(super) fn new_segment(
+    msg_type: NftMsgType,
+    family: u8,
+    request_header: &CMsgSegHdr,
+    generation: u32,
+    attrs: Vec<NfAttr>,
+) -> NfnlSegment {
+    let header = CMsgSegHdr {
+        len: 0,
+        type_: msg_type.segment_type(),
+        flags: 0,
+        seq: request_header.seq,
+        pid: request_header.pid,
+    };
+    let body = NfGenMsg {
+        family,
+        version: NFNETLINK_V0,
+        res_id: generation as u16,
+    };
+
+    NfnlSegment::Nftables(msg_type, NftSegment::new(header, body, attrs))
+}
+
+/// Returns whether the request is a dump request.
+pub(super) fn is_dump(request_header: &CMsgSegHdr) -> bool {
+    let flags = GetRequestFlags::from_bits_truncate(request_header.flags);
+    flags.contains(GetRequestFlags::DUMP)
+}
+
+/// Finds the attribute of the type.
+pub(super) fn find_attr(attrs: &[NfAttr], type_: u16) -> Option<&NfAttr> {
+    attrs.iter().find(|attr| attr.type_() == type_)
+}
+
+/// Finds the attribute of the type, which is required.
+pub(super) fn required_attr(attrs: &[NfAttr], type_: u16) -> Result<&NfAttr> {
+    find_attr(attrs, type_)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "a required attribute is missing"))
+}
+
+/// Parses the name of a table, a chain, or a set.
+pub(super) fn parse_name(attr: &NfAttr) -> Result<String> {
+    let name = attr.as_str()?;
+    if name.is_empty() {
+        return_errno_with_message!(Errno::EINVAL, "the name is empty");
+    }
+    if name.len() >= NFT_NAME_MAXLEN {
+        return_errno_with_message!(Errno::ENAMETOOLONG, "the name is too long");
+    }
+    Ok(name.to_string())
+}
+
+/// Parses the optional user data, which is opaque to the kernel.
+pub(super) fn parse_userdata(attrs: &[NfAttr], type_: u16) -> Option<Vec<u8>> {
+    find_attr(attrs, type_).map(|attr| attr.payload().to_vec())
+}
+
+/// Checks whether the family of the tables is supported.
+pub(super) fn check_family(family: u8) -> Result<()> {
+    if !matches!(family, NFPROTO_INET | NFPROTO_IPV4 | NFPROTO_IPV6) {
+        return_errno_with_message!(Errno::EAFNOSUPPORT, "the nftables family is not supported");
+    }
+    Ok(())
+}
+
+/// Finds a table in the ruleset.
+pub(super) fn find_table<'a>(ruleset: &'a Ruleset, family: u8, name: &str) -> Result<&'a Table> {
+    ruleset
+        .tables
+        .iter()
+        .find(|table| table.family == family && table.name == name)
+        .ok_or_else(|| Error::with_message(Errno::ENOENT, "the table does not exist"))
+}
+
+/// Finds a table in the ruleset for modification.
+pub(super) fn find_table_mut<'a>(
+    ruleset: &'a mut Ruleset,
+    family: u8,
+    name: &str,
+) -> Result<&'a mut Table> {
+    ruleset
+        .tables
+        .iter_mut()
+        .find(|table| table.family == family && table.name == name)
+        .ok_or_else(|| Error::with_message(Errno::ENOENT, "the table does not exist"))
+}
+
+/// Parses the value in an `NFTA_DATA_*` attribute.
+pub(super) fn parse_data_value(attr: &NfAttr) -> Result<Vec<u8>> {
+    let attrs = attr.as_nested()?;
+    let value = required_attr(&attrs, NFTA_DATA_VALUE)?.payload();
+    if value.is_empty() || value.len() > NFT_DATA_VALUE_MAXLEN {
+        return_errno_with_message!(Errno::EINVAL, "the data length is not valid");
+    }
+    Ok(value.to_vec())
+}
+
+/// Parses the value or the verdict in an `NFTA_DATA_*` attribute.
+///
+/// `chain_by_id` resolves the chains that are referred to by `NFTA_VERDICT_CHAIN_ID`.
+pub(super) fn parse_data<'a>(
+    attr: &NfAttr,
+    table: &Table,
+    chain_by_id: impl Fn(u32) -> Option<&'a str>,
+) -> Result<SetData> {
+    let attrs = attr.as_nested()?;
+    if find_attr(&attrs, NFTA_DATA_VALUE).is_some() {
+        return parse_data_value(attr).map(SetData::Value);
+    }
+
+    let verdict_attrs = required_attr(&attrs, NFTA_DATA_VERDICT)?.as_nested()?;
+    let code = required_attr(&verdict_attrs, NFTA_VERDICT_CODE)?.as_be32()? as i32;
+    let verdict = match code {
+        NF_DROP => Verdict::Drop,
+        NF_ACCEPT => Verdict::Accept,
+        NFT_CONTINUE => Verdict::Continue,
+        NFT_BREAK => Verdict::Break,
+        NFT_RETURN => Verdict::Return,
+        NFT_JUMP | NFT_GOTO => {
+            let target = if let Some(attr) = find_attr(&verdict_attrs, NFTA_VERDICT_CHAIN) {
+                attr.as_str()?.to_string()
+            } else {
+                let id = required_attr(&verdict_attrs, NFTA_VERDICT_CHAIN_ID)?.as_be32()?;
+                chain_by_id(id)
+                    .ok_or_else(|| Error::with_message(Errno::ENOENT, "the chain does not exist"))?
+                    .to_string()
+            };
+            let Some(chain) = table.find_chain(&target) else {
+                return_errno_with_message!(Errno::ENOENT, "the chain does not exist");
+            };
+            if chain.base.is_some() {
+                return_errno_with_message!(Errno::EOPNOTSUPP, "jumping to base chains is invalid");
+            }
+            if code == NFT_JUMP {
+                Verdict::Jump(target)
+            } else {
+                Verdict::Goto(target)
+            }
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "the verdict code is not valid"),
+    };
+
+    Ok(SetData::Verdict(verdict))
+}
+
+/// Creates an `NFTA_DATA_*` attribute that contains the value or the verdict.
+pub(super) fn data_to_attr(type_: u16, data: &SetData) -> NfAttr {
+    let attr = match data {
+        SetData::Value(value) => NfAttr::new(NFTA_DATA_VALUE, value.clone()),
+        SetData::Verdict(verdict) => {
+            let (code, target) = match verdict {
+                Verdict::Drop => (NF_DROP, None),
+                Verdict::Accept => (NF_ACCEPT, None),
+                Verdict::Continue => (NFT_CONTINUE, None),
+                Verdict::Break => (NFT_BREAK, None),
+                Verdict::Return => (NFT_RETURN, None),
+                Verdict::Jump(target) => (NFT_JUMP, Some(target)),
+                Verdict::Goto(target) => (NFT_GOTO, Some(target)),
+            };
+            let mut verdict_attrs = vec![NfAttr::new_be32(NFTA_VERDICT_CODE, code as u32)];
+            if let Some(target) = target {
+                verdict_attrs.push(NfAttr::new_str(NFTA_VERDICT_CHAIN, target));
+            }
+            NfAttr::new_nested(NFTA_DATA_VERDICT, verdict_attrs)
+        }
+    };
+    NfAttr::new_nested(type_, vec![attr])
+}
+
+/// Creates an `NFTA_DATA_*` attribute that contains the value.
+pub(super) fn value_to_attr(type_: u16, value: &[u8]) -> NfAttr {
+    NfAttr::new_nested(type_, vec![NfAttr::new(NFTA_DATA_VALUE, value.to_vec())])
+}
+
+/// Appends a done segment as the last segment of the provided segments.
+fn append_done_segment(request_header: &CMsgSegHdr, response_segments: &mut Vec<NfnlSegment>) {
+    let done_segment = DoneSegment::new_from_request(request_header, None);
+    response_segments.push(NfnlSegment::Done(done_segment));
+}
+
+/// Adds the `MULTI` flag to all segments in `segments`.
+fn add_multi_flag(response_segments: &mut [NfnlSegment]) {
+    for segment in response_segments.iter_mut() {
+        let header = segment.header_mut();
+        let mut flags = SegHdrCommonFlags::from_bits_truncate(header.flags);
+        flags |= SegHdrCommonFlags::MULTI;
+        header.flags = flags.bits();
+    }
+}
@@ -0,0 +1,120 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{
+    net::socket::netlink::message::{Attribute, CAttrHeader, ContinueRead},
+    prelude::*,
+    util::MultiRead,
+};
+
+/// A netfilter attribute.
+///
+/// Unlike the attributes of the netlink route protocol, nftables attributes are deeply nested and
+/// the meaning of a nested attribute depends on its context (e.g., the attributes of an
+/// expression depend on the expression name). So the attributes are kept in the raw form, and
+/// the handlers interpret them with the `NFTA_*` types.
+///
+/// Integers in nftables attributes are in the network byte order.
+#[derive(Clone, Debug)]
+pub(crate) struct NfAttr {
+    type_: u16,
+    payload: Vec<u8>,
+}
+
+impl NfAttr {
+    pub(crate) fn new(type_: u16, payload: Vec<u8>) -> Self {
+        Self { type_, payload }
+    }
+
+    /// Creates an attribute whose payload is a C string.
+    pub(crate) fn new_str(type_: u16, value: &str) -> Self {
+        let mut payload = Vec::with_capacity(value.len() + 1);
+        payload.extend_from_slice(value.as_bytes());
+        payload.push(0);
+        Self::new(type_, payload)
+    }
+
+    pub(crate) fn new_u8(type_: u16, value: u8) -> Self {
+        Self::new(type_, vec![value])
+    }
+
+    pub(crate) fn new_be32(type_: u16, value: u32) -> Self {
+        Self::new(type_, value.to_be_bytes().to_vec())
+    }
+
+    pub(crate) fn new_be64(type_: u16, value: u64) -> Self {
+        Self::new(type_, value.to_be_bytes().to_vec())
+    }
+
+    /// Creates an attribute whose payload consists of the nested attributes.
+    pub(crate) fn new_nested(type_: u16, attrs: Vec<NfAttr>) -> Self {
+        let payload_len = attrs.iter().map(|attr| attr.total_len_with_padding()).sum();
+        let mut payload = vec![0u8; payload_len];
+
+        let mut writer = VmWriter::from(payload.as_mut_slice()).to_fallible();
+        for attr in attrs.iter() {
+            // Writing to kernel memory never fails.
+            attr.write_to(&mut writer).unwrap();
+        }
+
+        Self::new(type_, payload)
+    }
+
+    pub(crate) fn payload(&self) -> &[u8] {
+        &self.payload
+    }
+
+    /// Interprets the payload as a string.
+    ///
+    /// Like `nla_strscpy` in Linux, the terminating null byte is optional.
+    pub(crate) fn as_str(&self) -> Result<&str> {
+        let bytes = match self.payload.iter().position(|byte| *byte == 0) {
+            Some(len) => &self.payload[..len],
+            None => &self.payload[..],
+        };
+        Ok(core::str::from_utf8(bytes)?)
+    }
+
+    pub(crate) fn as_u8(&self) -> Result<u8> {
+        let [value] = self.payload[..] else {
+            return_errno_with_message!(Errno::EINVAL, "the attribute length is not valid");
+        };
+        Ok(value)
+    }
+
+    pub(crate) fn as_be32(&self) -> Result<u32> {
+        let bytes = <[u8; 4]>::try_from(self.payload.as_slice())
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the attribute length is not valid"))?;
+        Ok(u32::from_be_bytes(bytes))
+    }
+
+    pub(crate) fn as_be64(&self) -> Result<u64> {
+        let bytes = <[u8; 8]>::try_from(self.payload.as_slice())
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the attribute length is not valid"))?;
+        Ok(u64::from_be_bytes(bytes))
+    }
+
+    /// Parses the payload as nested attributes.
+    pub(crate) fn as_nested(&self) -> Result<Vec<NfAttr>> {
+        Self::read_all_from_nested(&self.payload)
+    }
+}
+
+impl Attribute for NfAttr {
+    fn type_(&self) -> u16 {
+        self.type_
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        &self.payload
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<ContinueRead<Self>>
+    where
+        Self: Sized,
+    {
+        let mut payload = vec![0u8; header.payload_len()];
+        reader.read(&mut VmWriter::from(payload.as_mut_slice()))?;
+
+        Ok(ContinueRead::Parsed(Self::new(header.type_(), payload)))
+    }
+}
@@ -0,0 +1,21 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Netlink message types for the netlink netfilter protocol.
+//!
+//! This module defines how to interpret messages sent from user space and how to write
+//! kernel messages back to user space.
+
+#![short_vis_path::add(netlink)]
+
+mod attr;
+mod segment;
+
+pub(super) use attr::NfAttr;
+pub(super) use segment::{NFNL_SUBSYS_NFTABLES, NfGenMsg, NfnlSegment, NftMsgType, NftSegment};
+
+use crate::net::socket::netlink::{message::Message, table::MulticastMessage};
+
+/// A netlink netfilter message.
+pub(in netlink) type NfnlMessage = Message<NfnlSegment>;
+
+impl MulticastMessage for NfnlMessage {}
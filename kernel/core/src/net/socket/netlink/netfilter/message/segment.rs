@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::attr::NfAttr;
+use crate::{
+    net::socket::netlink::message::{
+        CMsgSegHdr, ContinueRead, DoneSegment, ErrorSegment, ProtocolSegment, SegmentBody,
+        SegmentCommon,
+    },
+    prelude::*,
+    util::{MultiRead, MultiWrite},
+};
+
+/// The netlink netfilter segment, which is the basic unit of a netlink netfilter message.
+#[derive(Clone, Debug)]
+pub(crate) enum NfnlSegment {
+    /// The beginning of a batch of modifications (`NFNL_MSG_BATCH_BEGIN`).
+    BatchBegin(NftSegment),
+    /// The end of a batch of modifications (`NFNL_MSG_BATCH_END`).
+    BatchEnd(NftSegment),
+    /// A segment of the nftables subsystem.
+    Nftables(NftMsgType, NftSegment),
+    Done(DoneSegment),
+    Error(ErrorSegment),
+}
+
+pub(crate) type NftSegment = SegmentCommon<NfGenMsg, NfAttr>;
+
+/// `NFNL_MSG_BATCH_BEGIN` in Linux.
+const NFNL_MSG_BATCH_BEGIN: u16 = 0x10;
+/// `NFNL_MSG_BATCH_END` in Linux.
+const NFNL_MSG_BATCH_END: u16 = 0x11;
+/// `NFNL_SUBSYS_NFTABLES` in Linux.
+pub(crate) const NFNL_SUBSYS_NFTABLES: u16 = 10;
+
+/// The message types of the nftables subsystem.
+///
+/// The segment type is `(NFNL_SUBSYS_NFTABLES << 8) | msg_type`.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/netfilter/nf_tables.h>.
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
+pub(crate) enum NftMsgType {
+    NEWTABLE = 0,
+    GETTABLE = 1,
+    DELTABLE = 2,
+    NEWCHAIN = 3,
+    GETCHAIN = 4,
+    DELCHAIN = 5,
+    NEWRULE = 6,
+    GETRULE = 7,
+    DELRULE = 8,
+    NEWSET = 9,
+    GETSET = 10,
+    DELSET = 11,
+    NEWSETELEM = 12,
+    GETSETELEM = 13,
+    DELSETELEM = 14,
+    NEWGEN = 15,
+    GETGEN = 16,
+    TRACE = 17,
+    NEWOBJ = 18,
+    GETOBJ = 19,
+    DELOBJ = 20,
+    GETOBJ_RESET = 21,
+    NEWFLOWTABLE = 22,
+    GETFLOWTABLE = 23,
+    DELFLOWTABLE = 24,
+    GETRULE_RESET = 25,
+    DESTROYTABLE = 26,
+    DESTROYCHAIN = 27,
+    DESTROYRULE = 28,
+    DESTROYSET = 29,
+    DESTROYSETELEM = 30,
+    DESTROYOBJ = 31,
+    DESTROYFLOWTABLE = 32,
+}
+
+impl NftMsgType {
+    /// Returns the segment type of the message type.
+    pub(crate) fn segment_type(self) -> u16 {
+        (NFNL_SUBSYS_NFTABLES << 8) | self as u16
+    }
+}
+
+/// `nfgenmsg` in Linux.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/netfilter/nfnetlink.h>.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(crate) struct CNfGenMsg {
+    /// The family (`NFPROTO_*`)
+    pub family: u8,
+    /// The version (`NFNETLINK_V0`)
+    pub version: u8,
+    /// The resource ID, in the network byte order
+    pub res_id: u16,
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct NfGenMsg {
+    pub family: u8,
+    pub version: u8,
+    pub res_id: u16,
+}
+
+impl SegmentBody for NfGenMsg {
+    type CType = CNfGenMsg;
+}
+
+impl TryFrom<CNfGenMsg> for NfGenMsg {
+    type Error = Error;
+
+    fn try_from(value: CNfGenMsg) -> Result<Self> {
+        Ok(Self {
+            family: value.family,
+            version: value.version,
+            res_id: u16::from_be(value.res_id),
+        })
+    }
+}
+
+impl From<NfGenMsg> for CNfGenMsg {
+    fn from(value: NfGenMsg) -> Self {
+        Self {
+            family: value.family,
+            version: value.version,
+            res_id: value.res_id.to_be(),
+        }
+    }
+}
+
+impl ProtocolSegment for NfnlSegment {
+    fn header(&self) -> &CMsgSegHdr {
+        match self {
+            NfnlSegment::BatchBegin(segment)
+            | NfnlSegment::BatchEnd(segment)
+            | NfnlSegment::Nftables(_, segment) => segment.header(),
+            NfnlSegment::Done(done_segment) => done_segment.header(),
+            NfnlSegment::Error(error_segment) => error_segment.header(),
+        }
+    }
+
+    fn header_mut(&mut self) -> &mut CMsgSegHdr {
+        match self {
+            NfnlSegment::BatchBegin(segment)
+            | NfnlSegment::BatchEnd(segment)
+            | NfnlSegment::Nftables(_, segment) => segment.header_mut(),
+            NfnlSegment::Done(done_segment) => done_segment.header_mut(),
+            NfnlSegment::Error(error_segment) => error_segment.header_mut(),
+        }
+    }
+
+    fn read_from(reader: &mut dyn MultiRead) -> Result<ContinueRead<Self, ErrorSegment>> {
+        let header = reader
+            .read_val_opt::<CMsgSegHdr>()?
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the reader length is too small"))?;
+
+        let segment = match header.type_ {
+            NFNL_MSG_BATCH_BEGIN => {
+                NftSegment::read_from(&header, reader)?.map(NfnlSegment::BatchBegin)
+            }
+            NFNL_MSG_BATCH_END => {
+                NftSegment::read_from(&header, reader)?.map(NfnlSegment::BatchEnd)
+            }
+            type_ if type_ >> 8 == NFNL_SUBSYS_NFTABLES => {
+                match NftMsgType::try_from(type_ & 0xff) {
+                    Ok(msg_type) => NftSegment::read_from(&header, reader)?
+                        .map(|segment| NfnlSegment::Nftables(msg_type, segment)),
+                    Err(_) => {
+                        let payload_len = header.calc_payload_len_with_padding(reader)?;
+                        reader.skip_some(payload_len);
+                        ContinueRead::skipped_with_error(
+                            Errno::EOPNOTSUPP,
+                            "the nftables message type is not supported",
+                        )
+                    }
+                }
+            }
+            _ => {
+                let payload_len = header.calc_payload_len_with_padding(reader)?;
+                reader.skip_some(payload_len);
+                ContinueRead::skipped_with_error(
+                    Errno::EINVAL,
+                    "the netfilter subsystem is not supported",
+                )
+            }
+        };
+
+        Ok(segment.map_err(|error| ErrorSegment::new_from_request(&header, Some(error))))
+    }
+
+    fn write_to(&self, writer: &mut dyn MultiWrite) -> Result<()> {
+        match self {
+            NfnlSegment::Nftables(_, segment) => segment.write_to(writer)?,
+            NfnlSegment::Done(done_segment) => done_segment.write_to(writer)?,
+            NfnlSegment::Error(error_segment) => error_segment.write_to(writer)?,
+            NfnlSegment::BatchBegin(_) | NfnlSegment::BatchEnd(_) => {
+                unreachable!("kernel should not write batch delimiters to user space");
+            }
+        }
+        Ok(())
+    }
+}
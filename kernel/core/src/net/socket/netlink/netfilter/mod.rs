@@ -0,0 +1,18 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Netlink Netfilter Socket.
+//!
+//! Only the nftables subsystem is supported, which configures the [netfilter ruleset] of the
+//! network namespace.
+//!
+//! [netfilter ruleset]: crate::net::netfilter
+
+pub(super) use message::NfnlMessage;
+
+use crate::net::socket::netlink::{common::NetlinkSocket, table::NetlinkNetfilterProtocol};
+
+mod bound;
+mod kernel;
+mod message;
+
+pub(crate) type NetlinkNetfilterSocket = NetlinkSocket<NetlinkNetfilterProtocol>;
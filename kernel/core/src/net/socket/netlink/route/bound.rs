@@ -89,7 +89,7 @@ impl datagram_common::Bound for BoundNetlinkRoute {
                 header.pid = local_port;
             }
 
-            rtnl_kernel.handle_request(&segment, local_port);
+            rtnl_kernel.handle_request(&self.net_ns, &segment, local_port);
         }
 
         Ok(sum_lens)
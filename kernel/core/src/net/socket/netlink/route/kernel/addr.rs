@@ -5,14 +5,20 @@
 use alloc::borrow::ToOwned;
 use core::{net::IpAddr, num::NonZeroU32};
 
-use aster_bigtcp::{iface::InterfaceFlags, wire::IpCidr};
+use aster_bigtcp::{
+    iface::InterfaceFlags,
+    wire::{IpCidr, Ipv4Cidr, Ipv6Cidr},
+};
 
-use super::util::finish_response;
+use super::util::{RtnlGroup, ack_response, check_net_admin, finish_response, notify};
 use crate::{
     net::{
-        iface::{Iface, iter_all_ifaces},
+        iface::Iface,
+        net_ns::NetNamespace,
         socket::netlink::{
-            message::{CMsgSegHdr, CSegmentType, GetRequestFlags, SegHdrCommonFlags},
+            message::{
+                CMsgSegHdr, CSegmentType, GetRequestFlags, NewRequestFlags, SegHdrCommonFlags,
+            },
             route::message::{
                 AddrAttr, AddrMessageFlags, AddrProtocol, AddrSegment, AddrSegmentBody, RtScope,
                 RtnlSegment,
@@ -23,7 +29,10 @@ use crate::{
     util::net::CSocketAddrFamily,
 };
 
-pub(super) fn do_get_addr(request_segment: &AddrSegment) -> Result<Vec<RtnlSegment>> {
+pub(super) fn do_get_addr(
+    net_ns: &Arc<NetNamespace>,
+    request_segment: &AddrSegment,
+) -> Result<Vec<RtnlSegment>> {
     let dump_all = {
         let flags = GetRequestFlags::from_bits_truncate(request_segment.header().flags);
         flags.contains(GetRequestFlags::DUMP)
@@ -33,7 +42,9 @@ pub(super) fn do_get_addr(request_segment: &AddrSegment) -> Result<Vec<RtnlSegme
     }
 
     let requested_family = request_segment.body().family;
-    let mut addr_segments: Vec<AddrSegment> = iter_all_ifaces()
+    let mut addr_segments: Vec<AddrSegment> = net_ns
+        .ifaces()
+        .iter()
         .flat_map(|iface| iface_to_new_addrs(request_segment.header(), requested_family, iface))
         .collect();
 
@@ -51,30 +62,185 @@ pub(super) fn do_get_addr(request_segment: &AddrSegment) -> Result<Vec<RtnlSegme
     Ok(response_segments)
 }
 
+pub(super) fn do_new_addr(
+    net_ns: &Arc<NetNamespace>,
+    request_segment: &AddrSegment,
+) -> Result<Vec<RtnlSegment>> {
+    check_net_admin(net_ns)?;
+
+    let iface = find_requested_iface(net_ns, request_segment)?;
+    let cidr = requested_cidr(request_segment)?;
+    let flags = NewRequestFlags::from_bits_truncate(request_segment.header().flags);
+
+    // Like `inet_rtm_newaddr` in Linux, an existing address is only changed with `NLM_F_REPLACE`.
+    if let Some(old_cidr) = iface_cidrs(&iface, &cidr)
+        .into_iter()
+        .find(|old_cidr| old_cidr.address() == cidr.address())
+    {
+        if flags.contains(NewRequestFlags::EXCL) || !flags.contains(NewRequestFlags::REPLACE) {
+            return_errno_with_message!(Errno::EEXIST, "the address already exists");
+        }
+        if old_cidr == cidr {
+            return Ok(ack_response(request_segment.header()));
+        }
+        // The prefix length is changed. The address is replaced below.
+        iface.remove_ip_cidr(old_cidr);
+    }
+
+    if !iface.add_ip_cidr(cidr) {
+        return_errno_with_message!(Errno::ENOSPC, "the link has too many addresses");
+    }
+
+    notify_addr(
+        net_ns,
+        CSegmentType::NEWADDR,
+        request_segment.header(),
+        &iface,
+        cidr,
+    );
+
+    Ok(ack_response(request_segment.header()))
+}
+
+pub(super) fn do_del_addr(
+    net_ns: &Arc<NetNamespace>,
+    request_segment: &AddrSegment,
+) -> Result<Vec<RtnlSegment>> {
+    check_net_admin(net_ns)?;
+
+    let iface = find_requested_iface(net_ns, request_segment)?;
+    let cidr = requested_cidr(request_segment)?;
+
+    if !iface.remove_ip_cidr(cidr) {
+        return_errno_with_message!(Errno::EADDRNOTAVAIL, "the address does not exist");
+    }
+
+    notify_addr(
+        net_ns,
+        CSegmentType::DELADDR,
+        request_segment.header(),
+        &iface,
+        cidr,
+    );
+
+    Ok(ack_response(request_segment.header()))
+}
+
+fn find_requested_iface(
+    net_ns: &NetNamespace,
+    request_segment: &AddrSegment,
+) -> Result<Arc<Iface>> {
+    request_segment
+        .body()
+        .index
+        .and_then(|index| net_ns.find_iface(|iface| iface.index() == index.get()))
+        .ok_or_else(|| Error::with_message(Errno::ENODEV, "the link does not exist"))
+}
+
+/// Returns the address and the prefix length specified by the request.
+fn requested_cidr(request_segment: &AddrSegment) -> Result<IpCidr> {
+    let body = request_segment.body();
+    let find_addr = |is_local: bool| {
+        request_segment.attrs().iter().find_map(|attr| match attr {
+            AddrAttr::Local(addr) if is_local => Some(*addr),
+            AddrAttr::Address(addr) if !is_local => Some(*addr),
+            _ => None,
+        })
+    };
+
+    // On a point-to-point link, `IFA_ADDRESS` is the peer address, so `IFA_LOCAL` is preferred.
+    let Some(addr) = find_addr(true).or_else(|| find_addr(false)) else {
+        return_errno_with_message!(Errno::EINVAL, "the address is not specified");
+    };
+
+    match (body.family, addr) {
+        (family, IpAddr::V4(addr))
+            if family == CSocketAddrFamily::AF_INET as i32 && body.prefix_len <= 32 =>
+        {
+            Ok(IpCidr::Ipv4(Ipv4Cidr::new(addr, body.prefix_len)))
+        }
+        (family, IpAddr::V6(addr))
+            if family == CSocketAddrFamily::AF_INET6 as i32 && body.prefix_len <= 128 =>
+        {
+            Ok(IpCidr::Ipv6(Ipv6Cidr::new(addr, body.prefix_len)))
+        }
+        _ => return_errno_with_message!(
+            Errno::EINVAL,
+            "the address does not match the family or the prefix length"
+        ),
+    }
+}
+
+/// Returns the addresses of the interface in the same family as `cidr`.
+fn iface_cidrs(iface: &Iface, cidr: &IpCidr) -> Vec<IpCidr> {
+    match cidr {
+        IpCidr::Ipv4(_) => iface.ipv4_cidrs().into_iter().map(IpCidr::Ipv4).collect(),
+        IpCidr::Ipv6(_) => iface.ipv6_cidrs().into_iter().map(IpCidr::Ipv6).collect(),
+    }
+}
+
+/// Notifies the subscribers of `RTNLGRP_IPV4_IFADDR` or `RTNLGRP_IPV6_IFADDR` that the address
+/// is added or deleted.
+fn notify_addr(
+    net_ns: &Arc<NetNamespace>,
+    type_: CSegmentType,
+    request_header: &CMsgSegHdr,
+    iface: &Arc<Iface>,
+    cidr: IpCidr,
+) {
+    let group = match cidr {
+        IpCidr::Ipv4(_) => RtnlGroup::IPV4_IFADDR,
+        IpCidr::Ipv6(_) => RtnlGroup::IPV6_IFADDR,
+    };
+
+    let segment = iface_to_addr(type_, request_header, iface, cidr);
+    let segment = match type_ {
+        CSegmentType::DELADDR => RtnlSegment::DelAddr(segment),
+        _ => RtnlSegment::NewAddr(segment),
+    };
+    notify(net_ns, group, segment);
+}
+
 fn iface_to_new_addrs(
     request_header: &CMsgSegHdr,
     requested_family: i32,
     iface: &Arc<Iface>,
 ) -> impl IntoIterator<Item = AddrSegment> {
-    let mut addr_segments = [None, None];
+    let mut addr_segments = Vec::new();
 
     // Linux dumps addresses for all families
     // when the requested family is neither AF_INET nor AF_INET6.
     let dump_ipv4 = requested_family != CSocketAddrFamily::AF_INET6 as i32;
     let dump_ipv6 = requested_family != CSocketAddrFamily::AF_INET as i32;
 
-    if dump_ipv4 && let Some(cidr) = iface.ipv4_cidr() {
-        addr_segments[0] = Some(iface_to_new_addr(request_header, iface, IpCidr::Ipv4(cidr)));
-    };
-
-    if dump_ipv6 && let Some(cidr) = iface.ipv6_cidr() {
-        addr_segments[1] = Some(iface_to_new_addr(request_header, iface, IpCidr::Ipv6(cidr)));
+    // An interface may have multiple addresses (e.g., a link-local IPv6 address and a global IPv6
+    // address configured by SLAAC, or the addresses added by `RTM_NEWADDR`).
+    if dump_ipv4 {
+        for cidr in iface.ipv4_cidrs() {
+            addr_segments.push(iface_to_addr(
+                CSegmentType::NEWADDR,
+                request_header,
+                iface,
+                IpCidr::Ipv4(cidr),
+            ));
+        }
+    }
+    if dump_ipv6 {
+        for cidr in iface.ipv6_cidrs() {
+            addr_segments.push(iface_to_addr(
+                CSegmentType::NEWADDR,
+                request_header,
+                iface,
+                IpCidr::Ipv6(cidr),
+            ));
+        }
     }
 
-    addr_segments.into_iter().flatten()
+    addr_segments
 }
 
-fn iface_to_new_addr(
+fn iface_to_addr(
+    type_: CSegmentType,
     request_header: &CMsgSegHdr,
     iface: &Arc<Iface>,
     ip_cidr: IpCidr,
@@ -114,7 +280,7 @@ fn iface_to_new_addr(
 
     let header = CMsgSegHdr {
         len: 0,
-        type_: CSegmentType::NEWADDR as _,
+        type_: type_ as _,
         flags: SegHdrCommonFlags::empty().bits(),
         seq: request_header.seq,
         pid: request_header.pid,
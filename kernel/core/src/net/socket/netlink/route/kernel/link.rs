@@ -4,35 +4,62 @@
 
 use core::num::NonZero;
 
-use aster_bigtcp::{iface::InterfaceType, wire::EthernetAddress};
+use aster_bigtcp::{
+    iface::{InterfaceFlags, InterfaceName, InterfaceType},
+    wire::EthernetAddress,
+};
+use ostd::task::Task;
 
-use super::util::finish_response;
+use super::util::{RtnlGroup, ack_response, check_net_admin, finish_response, notify};
 use crate::{
+    fs::{
+        file::{InodeHandle, file_table::get_file_fast},
+        pseudofs::NsFile,
+    },
     net::{
-        iface::{DEFAULT_TX_QUEUE_LEN, Iface, iter_all_ifaces},
+        iface::{self, DEFAULT_TX_QUEUE_LEN, Iface, link_master_index, link_peer_index},
+        net_ns::NetNamespace,
         socket::netlink::{
-            message::{CMsgSegHdr, CSegmentType, GetRequestFlags, SegHdrCommonFlags},
-            route::message::{LinkAttr, LinkSegment, LinkSegmentBody, RtnlSegment},
+            message::{
+                Attribute, CMsgSegHdr, CSegmentType, GetRequestFlags, NewRequestFlags,
+                SegHdrCommonFlags,
+            },
+            route::message::{
+                LinkAttr, LinkInfoAttr, LinkSegment, LinkSegmentBody, RtnlSegment, VethInfoAttr,
+            },
         },
     },
     prelude::*,
+    process::{pid_table, posix_thread::AsPosixThread},
     util::net::CSocketAddrFamily,
 };
 
 /// The unspecified link-layer address.
 const UNSPECIFIED_LINK_ADDR: EthernetAddress = EthernetAddress([0; 6]);
 
-pub(super) fn do_get_link(request_segment: &LinkSegment) -> Result<Vec<RtnlSegment>> {
+pub(super) fn do_get_link(
+    net_ns: &Arc<NetNamespace>,
+    request_segment: &LinkSegment,
+) -> Result<Vec<RtnlSegment>> {
     let filter_by = FilterBy::from_request(request_segment)?;
 
-    let mut response_segments: Vec<RtnlSegment> = iter_all_ifaces()
+    let mut response_segments: Vec<RtnlSegment> = net_ns
+        .ifaces()
+        .iter()
         // Filter to include only requested links.
         .filter(|iface| match &filter_by {
             FilterBy::Index(index) => *index == iface.index(),
             FilterBy::Name(name) => *name == iface.name().as_cstr(),
             FilterBy::Dump => true,
         })
-        .map(|iface| iface_to_new_link(request_segment.header(), iface))
+        .map(|iface| {
+            iface_to_link(
+                CSegmentType::NEWLINK,
+                request_segment.header(),
+                iface,
+                InterfaceFlags::empty(),
+            )
+        })
         .map(RtnlSegment::NewLink)
         .collect();
 
@@ -47,6 +74,348 @@ pub(super) fn do_get_link(request_segment: &LinkSegment) -> Result<Vec<RtnlSegme
     Ok(response_segments)
 }
 
+pub(super) fn do_new_link(
+    net_ns: &Arc<NetNamespace>,
+    request_segment: &LinkSegment,
+) -> Result<Vec<RtnlSegment>> {
+    check_net_admin(net_ns)?;
+
+    let attrs = request_segment.attrs();
+    let flags = NewRequestFlags::from_bits_truncate(request_segment.header().flags);
+    let target_ns = net_ns_from_attrs(attrs)?;
+    let master = attrs.iter().find_map(|attr| match attr {
+        LinkAttr::Master(index) => Some(*index),
+        _ => None,
+    });
+
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/net/core/rtnetlink.c#L3942>
+    if let Some(iface) = find_requested_iface(net_ns, request_segment).flatten() {
+        if flags.contains(NewRequestFlags::EXCL) {
+            return_errno_with_message!(Errno::EEXIST, "the link already exists");
+        }
+
+        let dst_ns = match &target_ns {
+            Some(target_ns) => {
+                iface::move_link(&iface, net_ns, target_ns)?;
+                // Like Linux, the link disappears from the old network namespace and appears in
+                // the new one.
+                if !Arc::ptr_eq(net_ns, target_ns) {
+                    notify_link(net_ns, CSegmentType::DELLINK, &iface, InterfaceFlags::all());
+                    notify_link(
+                        target_ns,
+                        CSegmentType::NEWLINK,
+                        &iface,
+                        InterfaceFlags::all(),
+                    );
+                }
+                target_ns
+            }
+            None => net_ns,
+        };
+        change_link(dst_ns, &iface, request_segment)?;
+    } else {
+        if !flags.contains(NewRequestFlags::CREATE) {
+            return_errno_with_message!(Errno::ENODEV, "the link does not exist");
+        }
+        if request_segment.body().index.is_some() {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "specifying the index of a new link is not supported"
+            );
+        }
+
+        let dst_ns = target_ns.as_ref().unwrap_or(net_ns);
+        let iface = new_link(dst_ns, net_ns, attrs)?;
+        if let Some(master) = master
+            && let Err(err) = set_master(dst_ns, &iface, master)
+        {
+            // The link may have been deleted by another request, in which case there is nothing
+            // to roll back.
+            let _ = iface::delete_link(&iface);
+            return Err(err);
+        }
+        set_flags(&iface, request_segment.body());
+
+        notify_link(dst_ns, CSegmentType::NEWLINK, &iface, InterfaceFlags::all());
+    }
+
+    Ok(ack_response(request_segment.header()))
+}
+
+pub(super) fn do_set_link(
+    net_ns: &Arc<NetNamespace>,
+    request_segment: &LinkSegment,
+) -> Result<Vec<RtnlSegment>> {
+    check_net_admin(net_ns)?;
+
+    let Some(iface) = find_requested_iface(net_ns, request_segment) else {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "either interface name or index should be specified"
+        );
+    };
+    let Some(iface) = iface else {
+        return_errno_with_message!(Errno::ENODEV, "the link does not exist");
+    };
+    change_link(net_ns, &iface, request_segment)?;
+
+    Ok(ack_response(request_segment.header()))
+}
+
+pub(super) fn do_del_link(
+    net_ns: &Arc<NetNamespace>,
+    request_segment: &LinkSegment,
+) -> Result<Vec<RtnlSegment>> {
+    check_net_admin(net_ns)?;
+
+    let Some(iface) = find_requested_iface(net_ns, request_segment) else {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "either interface name or index should be specified"
+        );
+    };
+    let Some(iface) = iface else {
+        return_errno_with_message!(Errno::ENODEV, "the link does not exist");
+    };
+    iface::delete_link(&iface)?;
+
+    notify_link(net_ns, CSegmentType::DELLINK, &iface, InterfaceFlags::all());
+
+    Ok(ack_response(request_segment.header()))
+}
+
+/// Finds the interface specified by the index or, if the index is absent, by the name.
+///
+/// This method returns `None` if neither the index nor the name is specified.
+fn find_requested_iface(
+    net_ns: &NetNamespace,
+    request_segment: &LinkSegment,
+) -> Option<Option<Arc<Iface>>> {
+    if let Some(index) = request_segment.body().index {
+        return Some(net_ns.find_iface(|iface| iface.index() == index.get()));
+    }
+
+    let name = find_name(request_segment.attrs())?;
+    Some(net_ns.find_iface(|iface| iface.name() == &name))
+}
+
+/// Creates a link of the kind specified by `IFLA_LINKINFO` in `net_ns`.
+///
+/// `src_ns` is the network namespace of the requesting netlink socket.
+fn new_link(
+    net_ns: &Arc<NetNamespace>,
+    src_ns: &Arc<NetNamespace>,
+    attrs: &[LinkAttr],
+) -> Result<Arc<Iface>> {
+    let link_info = attrs
+        .iter()
+        .find_map(|attr| match attr {
+            LinkAttr::LinkInfo(payload) => Some(payload.as_slice()),
+            _ => None,
+        })
+        .map(LinkInfoAttr::read_all_from_nested)
+        .transpose()?
+        .unwrap_or_default();
+
+    let kind = link_info.iter().find_map(|attr| match attr {
+        LinkInfoAttr::Kind(kind) => Some(kind.as_c_str()),
+        _ => None,
+    });
+    let data = link_info.iter().find_map(|attr| match attr {
+        LinkInfoAttr::Data(payload) => Some(payload.as_slice()),
+        _ => None,
+    });
+    let name = find_name(attrs);
+
+    match kind.map(CStr::to_bytes) {
+        Some(b"veth") => {
+            let peer = data
+                .map(VethInfoAttr::read_all_from_nested)
+                .transpose()?
+                .unwrap_or_default()
+                .into_iter()
+                .next()
+                .map(|VethInfoAttr::Peer(payload)| payload);
+            let (peer_ns, peer_name) = match peer {
+                Some(payload) => {
+                    let (_, peer_attrs) = VethInfoAttr::parse_peer(&payload)?;
+                    let peer_ns = net_ns_from_attrs(&peer_attrs)?;
+                    (peer_ns, find_name(&peer_attrs))
+                }
+                None => (None, None),
+            };
+            // Like Linux, the peer is created in the network namespace of the requesting socket
+            // unless another one is specified.
+            let peer_ns = peer_ns.unwrap_or_else(|| src_ns.clone());
+
+            iface::new_veth_pair(net_ns, name, &peer_ns, peer_name)
+        }
+        Some(b"bridge") => iface::new_bridge(net_ns, name),
+        _ => return_errno_with_message!(Errno::EOPNOTSUPP, "the link kind is not supported"),
+    }
+}
+
+/// Applies the changes requested by `RTM_NEWLINK` or `RTM_SETLINK` to an existing link.
+///
+/// This corresponds to `do_setlink` in Linux, but only a few changes are supported.
+fn change_link(
+    net_ns: &Arc<NetNamespace>,
+    iface: &Arc<Iface>,
+    request_segment: &LinkSegment,
+) -> Result<()> {
+    let old_flags = iface.flags();
+
+    for attr in request_segment.attrs() {
+        match attr {
+            LinkAttr::Name(name) if name != iface.name() => {
+                return_errno_with_message!(Errno::EOPNOTSUPP, "renaming links is not supported");
+            }
+            LinkAttr::Mtu(mtu) if *mtu != iface.mtu() as u32 => {
+                return_errno_with_message!(
+                    Errno::EOPNOTSUPP,
+                    "changing the MTU of links is not supported"
+                );
+            }
+            _ => (),
+        }
+    }
+
+    let master = request_segment.attrs().iter().find_map(|attr| match attr {
+        LinkAttr::Master(index) => Some(*index),
+        _ => None,
+    });
+    if let Some(master) = master {
+        set_master(net_ns, iface, master)?;
+    }
+
+    set_flags(iface, request_segment.body());
+
+    let changed_flags = old_flags ^ iface.flags();
+    if master.is_some() || !changed_flags.is_empty() {
+        notify_link(net_ns, CSegmentType::NEWLINK, iface, changed_flags);
+    }
+
+    Ok(())
+}
+
+/// Changes the flags of the interface as specified by `ifi_flags` and `ifi_change`.
+///
+/// Only [`InterfaceFlags::UP`] can be changed. Other flags are ignored.
+fn set_flags(iface: &Iface, body: &LinkSegmentBody) {
+    // This follows `rtnl_dev_combine_flags` in Linux.
+    if body.flags.is_empty() && body.change.is_empty() {
+        return;
+    }
+    let flags = if body.change.is_empty() {
+        body.flags
+    } else {
+        (body.flags & body.change) | (iface.flags() & !body.change)
+    };
+
+    iface.set_up(flags.contains(InterfaceFlags::UP));
+}
+
+/// Notifies the subscribers of `RTNLGRP_LINK` that the link is changed.
+fn notify_link(
+    net_ns: &Arc<NetNamespace>,
+    type_: CSegmentType,
+    iface: &Arc<Iface>,
+    change: InterfaceFlags,
+) {
+    // Linux does not associate link notifications with the requests that cause them, so the
+    // sequence number and the port ID are zero.
+    let header = CMsgSegHdr {
+        len: 0,
+        type_: 0,
+        flags: 0,
+        seq: 0,
+        pid: 0,
+    };
+
+    let segment = iface_to_link(type_, &header, iface, change);
+    let segment = match type_ {
+        CSegmentType::DELLINK => RtnlSegment::DelLink(segment),
+        _ => RtnlSegment::NewLink(segment),
+    };
+    notify(net_ns, RtnlGroup::LINK, segment);
+}
+
+/// Attaches the interface to the bridge with the index, or detaches it if the index is zero.
+fn set_master(net_ns: &NetNamespace, iface: &Iface, master: u32) -> Result<()> {
+    if master == 0 {
+        return iface::set_link_master(iface, None);
+    }
+
+    let Some(master) = net_ns.find_iface(|iface| iface.index() == master) else {
+        return_errno_with_message!(Errno::EINVAL, "the master link does not exist");
+    };
+    iface::set_link_master(iface, Some(&master))
+}
+
+fn find_name(attrs: &[LinkAttr]) -> Option<InterfaceName> {
+    attrs.iter().find_map(|attr| match attr {
+        LinkAttr::Name(name) => Some(*name),
+        _ => None,
+    })
+}
+
+/// Returns the network namespace specified by `IFLA_NET_NS_PID` or `IFLA_NET_NS_FD`.
+fn net_ns_from_attrs(attrs: &[LinkAttr]) -> Result<Option<Arc<NetNamespace>>> {
+    let pid = attrs.iter().find_map(|attr| match attr {
+        LinkAttr::NetNsPid(pid) => Some(*pid),
+        _ => None,
+    });
+    let fd = attrs.iter().find_map(|attr| match attr {
+        LinkAttr::NetNsFd(fd) => Some(*fd),
+        _ => None,
+    });
+
+    let net_ns = match (pid, fd) {
+        (None, None) => return Ok(None),
+        (Some(pid), None) => net_ns_of_pid(pid)?,
+        (None, Some(fd)) => net_ns_of_fd(fd)?,
+        (Some(_), Some(_)) => {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the network namespace is specified by both a PID and a file descriptor"
+            );
+        }
+    };
+    check_net_admin(&net_ns)?;
+
+    Ok(Some(net_ns))
+}
+
+fn net_ns_of_pid(pid: u32) -> Result<Arc<NetNamespace>> {
+    let process = current!()
+        .pid_ns()
+        .global_id_of(pid)
+        .and_then(|pid| pid_table::pid_table_mut().get_process(pid))
+        .ok_or_else(|| Error::with_message(Errno::ESRCH, "the process does not exist"))?;
+
+    let main_thread = process.main_thread();
+    let ns_proxy = main_thread.as_posix_thread().unwrap().ns_proxy().lock();
+    let ns_proxy = ns_proxy
+        .as_ref()
+        .ok_or_else(|| Error::with_message(Errno::ESRCH, "the process has exited"))?;
+    Ok(ns_proxy.net_ns().clone())
+}
+
+fn net_ns_of_fd(fd: u32) -> Result<Arc<NetNamespace>> {
+    let current = Task::current().unwrap();
+    let mut file_table = current.as_thread_local().unwrap().borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, (fd as i32).try_into()?);
+
+    let ns_file = match file.downcast_ref::<InodeHandle>() {
+        Some(inode_handle) => inode_handle.downcast_open_file::<NsFile<NetNamespace>>()?,
+        None => None,
+    };
+    let Some(ns_file) = ns_file else {
+        return_errno_with_message!(Errno::EINVAL, "the file is not a network namespace file");
+    };
+    Ok(ns_file.ns().clone())
+}
+
 enum FilterBy<'a> {
     Index(u32),
     Name(&'a CStr),
@@ -94,11 +463,10 @@ impl<'a> FilterBy<'a> {
 // Reference: <https://docs.kernel.org/userspace-api/netlink/intro.html#strict-checking>.
 
 fn validate_getlink_request(body: &LinkSegmentBody) -> Result<()> {
-    // FIXME: The Linux implementation also checks the `padding` and `change` fields,
-    // but these fields are lost during the conversion of a `CIfInfoMsg` to `LinkSegmentBody`.
-    // We should consider including the `change` field in `LinkSegmentBody`.
+    // FIXME: The Linux implementation also checks the `padding` field,
+    // but this field is lost during the conversion of a `CIfInfoMsg` to `LinkSegmentBody`.
     // Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/core/rtnetlink.c#L4043>.
-    if !body.flags.is_empty() || body.type_ != InterfaceType::NETROM {
+    if !body.flags.is_empty() || !body.change.is_empty() || body.type_ != InterfaceType::NETROM {
         return_errno_with_message!(Errno::EINVAL, "the flags or the type is not valid");
     }
 
@@ -106,9 +474,9 @@ fn validate_getlink_request(body: &LinkSegmentBody) -> Result<()> {
 }
 
 fn validate_dumplink_request(body: &LinkSegmentBody) -> Result<()> {
-    // FIXME: The Linux implementation also checks the `padding` and `change` fields.
+    // FIXME: The Linux implementation also checks the `padding` field.
     // Reference: <https://elixir.bootlin.com/linux/v6.13/source/net/core/rtnetlink.c#L2378>.
-    if !body.flags.is_empty() || body.type_ != InterfaceType::NETROM {
+    if !body.flags.is_empty() || !body.change.is_empty() || body.type_ != InterfaceType::NETROM {
         return_errno_with_message!(Errno::EINVAL, "the flags or the type is not valid");
     }
 
@@ -123,10 +491,15 @@ fn validate_dumplink_request(body: &LinkSegmentBody) -> Result<()> {
     Ok(())
 }
 
-fn iface_to_new_link(request_header: &CMsgSegHdr, iface: &Arc<Iface>) -> LinkSegment {
+fn iface_to_link(
+    type_: CSegmentType,
+    request_header: &CMsgSegHdr,
+    iface: &Arc<Iface>,
+    change: InterfaceFlags,
+) -> LinkSegment {
     let header = CMsgSegHdr {
         len: 0,
-        type_: CSegmentType::NEWLINK as _,
+        type_: type_ as _,
         flags: SegHdrCommonFlags::empty().bits(),
         seq: request_header.seq,
         pid: request_header.pid,
@@ -137,18 +510,22 @@ fn iface_to_new_link(request_header: &CMsgSegHdr, iface: &Arc<Iface>) -> LinkSeg
         type_: iface.type_(),
         index: NonZero::new(iface.index()),
         flags: iface.flags(),
+        change,
     };
 
     // Linux may report dozens of attributes in a fixed order.
     // See the reference below for the complete attribute list and ordering.
     // TODO: Asterinas currently reports only a subset of these attributes.
     // Reference: <https://elixir.bootlin.com/linux/v7.1/source/net/core/rtnetlink.c#L2050>.
-    let mut attrs = Vec::with_capacity(5);
+    let mut attrs = Vec::with_capacity(7);
     attrs.extend([
         LinkAttr::Name(*iface.name()),
         LinkAttr::TxqLen(DEFAULT_TX_QUEUE_LEN),
         LinkAttr::Mtu(iface.mtu() as u32),
     ]);
+    if let Some(master) = link_master_index(iface) {
+        attrs.push(LinkAttr::Master(master));
+    }
 
     let (link_addr, link_broadcast_addr) = match iface.ethernet_addr() {
         Some(ethernet_addr) => (ethernet_addr, EthernetAddress::BROADCAST),
@@ -158,6 +535,9 @@ fn iface_to_new_link(request_header: &CMsgSegHdr, iface: &Arc<Iface>) -> LinkSeg
         LinkAttr::Address(link_addr),
         LinkAttr::Broadcast(link_broadcast_addr),
     ]);
+    if let Some(peer) = link_peer_index(iface) {
+        attrs.push(LinkAttr::Link(peer));
+    }
 
     LinkSegment::new(header, link_message, attrs)
 }
@@ -7,16 +7,20 @@ use core::marker::PhantomData;
 
 use super::message::{RtnlMessage, RtnlSegment};
 use crate::{
-    net::socket::netlink::{
-        addr::PortNum,
-        message::{ErrorSegment, ProtocolSegment},
-        table::{NetlinkRouteProtocol, SupportedNetlinkProtocol},
+    net::{
+        net_ns::NetNamespace,
+        socket::netlink::{
+            addr::PortNum,
+            message::{ErrorSegment, ProtocolSegment},
+            table::{NetlinkRouteProtocol, SupportedNetlinkProtocol},
+        },
     },
     prelude::*,
 };
 
 mod addr;
 mod link;
+mod route;
 mod util;
 
 pub(super) struct NetlinkRouteKernelSocket {
@@ -30,14 +34,27 @@ impl NetlinkRouteKernelSocket {
         }
     }
 
-    pub(super) fn handle_request(&self, request: &RtnlSegment, dst_port: PortNum) {
+    pub(super) fn handle_request(
+        &self,
+        net_ns: &Arc<NetNamespace>,
+        request: &RtnlSegment,
+        dst_port: PortNum,
+    ) {
         debug!("netlink route request: {:?}", request);
 
         let request_header = request.header();
 
         let response_segments = match request {
-            RtnlSegment::GetLink(request_segment) => link::do_get_link(request_segment),
-            RtnlSegment::GetAddr(request_segment) => addr::do_get_addr(request_segment),
+            RtnlSegment::NewLink(request_segment) => link::do_new_link(net_ns, request_segment),
+            RtnlSegment::DelLink(request_segment) => link::do_del_link(net_ns, request_segment),
+            RtnlSegment::GetLink(request_segment) => link::do_get_link(net_ns, request_segment),
+            RtnlSegment::SetLink(request_segment) => link::do_set_link(net_ns, request_segment),
+            RtnlSegment::NewAddr(request_segment) => addr::do_new_addr(net_ns, request_segment),
+            RtnlSegment::DelAddr(request_segment) => addr::do_del_addr(net_ns, request_segment),
+            RtnlSegment::GetAddr(request_segment) => addr::do_get_addr(net_ns, request_segment),
+            RtnlSegment::NewRoute(request_segment) => route::do_new_route(net_ns, request_segment),
+            RtnlSegment::DelRoute(request_segment) => route::do_del_route(net_ns, request_segment),
+            RtnlSegment::GetRoute(request_segment) => route::do_get_route(net_ns, request_segment),
             _ => Err(Error::with_message(
                 Errno::EOPNOTSUPP,
                 "the netlink route request is not supported",
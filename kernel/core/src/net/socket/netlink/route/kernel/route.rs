@@ -0,0 +1,481 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Handle route-related requests.
+
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
+
+use aster_bigtcp::{
+    iface::InterfaceFlags,
+    wire::{IpAddress, IpCidr, Ipv4Cidr, Ipv6Cidr},
+};
+
+use super::util::{RtnlGroup, ack_response, check_net_admin, finish_response, notify};
+use crate::{
+    net::{
+        iface::Iface,
+        net_ns::NetNamespace,
+        route::{RTPROT_KERNEL, Route},
+        socket::netlink::{
+            message::{
+                CMsgSegHdr, CSegmentType, GetRequestFlags, NewRequestFlags, SegHdrCommonFlags,
+            },
+            route::message::{
+                RouteAttr, RouteSegment, RouteSegmentBody, RouteType, RtScope, RtnlSegment,
+            },
+        },
+    },
+    prelude::*,
+    util::net::CSocketAddrFamily,
+};
+
+/// `RT_TABLE_UNSPEC` in Linux.
+const RT_TABLE_UNSPEC: u32 = 0;
+/// `RT_TABLE_MAIN` in Linux, which is the only supported routing table.
+const RT_TABLE_MAIN: u32 = 254;
+
+/// `IP6_RT_PRIO_USER` in Linux, which is the default priority of IPv6 routes added by user space.
+const IP6_RT_PRIO_USER: u32 = 1024;
+/// `IP6_RT_PRIO_ADDRCONF` in Linux, which is the priority of IPv6 routes to the networks of the
+/// interface addresses.
+const IP6_RT_PRIO_ADDRCONF: u32 = 256;
+
+pub(super) fn do_get_route(
+    net_ns: &Arc<NetNamespace>,
+    request_segment: &RouteSegment,
+) -> Result<Vec<RtnlSegment>> {
+    let dump_all = {
+        let flags = GetRequestFlags::from_bits_truncate(request_segment.header().flags);
+        flags.contains(GetRequestFlags::DUMP)
+    };
+    if !dump_all {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "GETROUTE only supports dump requests");
+    }
+
+    let requested_family = request_segment.body().family;
+    let request_header = request_segment.header();
+
+    let mut route_segments: Vec<RouteSegment> = net_ns
+        .ifaces()
+        .iter()
+        .filter(|iface| iface.flags().contains(InterfaceFlags::UP))
+        .flat_map(|iface| iface_to_new_routes(request_header, iface))
+        .collect();
+    route_segments.extend(
+        net_ns
+            .route_table()
+            .routes()
+            .iter()
+            .map(|route| route_to_segment(CSegmentType::NEWROUTE, request_header, route, None)),
+    );
+
+    // Linux dumps IPv4 routes before IPv6 routes, and dumps routes for all families when the
+    // requested family is neither AF_INET nor AF_INET6.
+    if requested_family == CSocketAddrFamily::AF_INET as i32
+        || requested_family == CSocketAddrFamily::AF_INET6 as i32
+    {
+        route_segments.retain(|segment| segment.body().family == requested_family);
+    }
+    route_segments.sort_by_key(|segment| segment.body().family);
+
+    let mut response_segments: Vec<RtnlSegment> = route_segments
+        .into_iter()
+        .map(RtnlSegment::NewRoute)
+        .collect();
+
+    finish_response(request_header, dump_all, &mut response_segments);
+
+    Ok(response_segments)
+}
+
+pub(super) fn do_new_route(
+    net_ns: &Arc<NetNamespace>,
+    request_segment: &RouteSegment,
+) -> Result<Vec<RtnlSegment>> {
+    check_net_admin(net_ns)?;
+
+    let request = RouteRequest::parse(request_segment)?;
+    if request_segment.body().type_ != RouteType::UNICAST {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "only unicast routes are supported");
+    }
+
+    let iface = output_iface(net_ns, &request)?;
+    let priority = request.priority.unwrap_or(match request.dst {
+        IpCidr::Ipv4(_) => 0,
+        IpCidr::Ipv6(_) => IP6_RT_PRIO_USER,
+    });
+    let route = Route {
+        dst: request.dst,
+        gateway: request.gateway,
+        iface,
+        priority,
+        protocol: request_segment.body().protocol,
+    };
+
+    let flags = NewRequestFlags::from_bits_truncate(request_segment.header().flags);
+    let is_same = |old: &Route| old.dst == route.dst && old.priority == route.priority;
+
+    let mut route_table = net_ns.route_table();
+    if route_table.find(is_same).is_some() {
+        if flags.contains(NewRequestFlags::EXCL) || !flags.contains(NewRequestFlags::REPLACE) {
+            return_errno_with_message!(Errno::EEXIST, "the route already exists");
+        }
+        let old_route = route_table.remove(is_same).unwrap();
+        if let Err(err) = route_table.insert(route.clone()) {
+            // Removing the old route has freed the space for it.
+            route_table.insert(old_route).unwrap();
+            return Err(err);
+        }
+    } else {
+        if !flags.contains(NewRequestFlags::CREATE) {
+            return_errno_with_message!(Errno::ENOENT, "the route does not exist");
+        }
+        route_table.insert(route.clone())?;
+    }
+    drop(route_table);
+
+    notify_route(
+        net_ns,
+        CSegmentType::NEWROUTE,
+        request_segment.header(),
+        &route,
+    );
+
+    Ok(ack_response(request_segment.header()))
+}
+
+pub(super) fn do_del_route(
+    net_ns: &Arc<NetNamespace>,
+    request_segment: &RouteSegment,
+) -> Result<Vec<RtnlSegment>> {
+    check_net_admin(net_ns)?;
+
+    let request = RouteRequest::parse(request_segment)?;
+    if !matches!(
+        request_segment.body().type_,
+        RouteType::UNSPEC | RouteType::UNICAST
+    ) {
+        return_errno_with_message!(Errno::ESRCH, "only unicast routes can exist");
+    }
+
+    // Like Linux, the unspecified fields match any route.
+    let removed = net_ns.route_table().remove(|route| {
+        route.dst == request.dst
+            && request
+                .gateway
+                .is_none_or(|gateway| route.gateway == Some(gateway))
+            && request.oif.is_none_or(|oif| route.iface.index() == oif)
+            && request
+                .priority
+                .is_none_or(|priority| route.priority == priority)
+    });
+    let Some(route) = removed else {
+        return_errno_with_message!(Errno::ESRCH, "the route does not exist");
+    };
+
+    notify_route(
+        net_ns,
+        CSegmentType::DELROUTE,
+        request_segment.header(),
+        &route,
+    );
+
+    Ok(ack_response(request_segment.header()))
+}
+
+/// The fields of an `RTM_NEWROUTE` or `RTM_DELROUTE` request.
+struct RouteRequest {
+    dst: IpCidr,
+    gateway: Option<IpAddress>,
+    oif: Option<u32>,
+    priority: Option<u32>,
+}
+
+impl RouteRequest {
+    fn parse(request_segment: &RouteSegment) -> Result<Self> {
+        let body = request_segment.body();
+
+        let mut dst = None;
+        let mut gateway = None;
+        let mut oif = None;
+        let mut priority = None;
+        let mut table = None;
+        for attr in request_segment.attrs() {
+            match attr {
+                RouteAttr::Dst(addr) => dst = Some(*addr),
+                RouteAttr::Gateway(addr) => gateway = Some(*addr),
+                RouteAttr::Oif(index) => oif = Some(*index),
+                RouteAttr::Priority(value) => priority = Some(*value),
+                RouteAttr::Table(value) => table = Some(*value),
+                RouteAttr::PrefSrc(_) => (),
+            }
+        }
+
+        // `RTA_TABLE` takes precedence over the table ID in the header, which cannot hold table
+        // IDs larger than 255.
+        let table = table.unwrap_or(body.table as u32);
+        if table != RT_TABLE_UNSPEC && table != RT_TABLE_MAIN {
+            return_errno_with_message!(
+                Errno::EOPNOTSUPP,
+                "only the main routing table is supported"
+            );
+        }
+
+        let dst = parse_dst(body, dst)?;
+        if gateway.is_some_and(|gateway| !is_same_family(&dst, &gateway)) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the gateway does not match the address family"
+            );
+        }
+
+        Ok(Self {
+            dst,
+            gateway: gateway.map(IpAddress::from),
+            oif,
+            priority,
+        })
+    }
+}
+
+/// Returns the destination network specified by the header and `RTA_DST`.
+fn parse_dst(body: &RouteSegmentBody, dst: Option<IpAddr>) -> Result<IpCidr> {
+    let family = body.family;
+    let dst_len = body.dst_len;
+
+    if family == CSocketAddrFamily::AF_INET as i32 {
+        let addr = match dst {
+            None => Ipv4Addr::UNSPECIFIED,
+            Some(IpAddr::V4(addr)) => addr,
+            Some(IpAddr::V6(_)) => {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the destination does not match the address family"
+                )
+            }
+        };
+        if dst_len > 32 {
+            return_errno_with_message!(Errno::EINVAL, "the prefix length is invalid");
+        }
+        let cidr = IpCidr::Ipv4(Ipv4Cidr::new(addr, dst_len));
+        // Unlike IPv6, Linux rejects IPv4 destinations with host bits.
+        if network_of(&cidr) != cidr {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the prefix is invalid for the prefix length"
+            );
+        }
+        Ok(cidr)
+    } else if family == CSocketAddrFamily::AF_INET6 as i32 {
+        let addr = match dst {
+            None => Ipv6Addr::UNSPECIFIED,
+            Some(IpAddr::V6(addr)) => addr,
+            Some(IpAddr::V4(_)) => {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the destination does not match the address family"
+                )
+            }
+        };
+        if dst_len > 128 {
+            return_errno_with_message!(Errno::EINVAL, "the prefix length is invalid");
+        }
+        Ok(network_of(&IpCidr::Ipv6(Ipv6Cidr::new(addr, dst_len))))
+    } else {
+        return_errno_with_message!(
+            Errno::EAFNOSUPPORT,
+            "the address family is not supported for routes"
+        );
+    }
+}
+
+/// Returns the output interface of the new route.
+///
+/// If a gateway is specified, it must be directly reachable from the output interface.
+fn output_iface(net_ns: &NetNamespace, request: &RouteRequest) -> Result<Arc<Iface>> {
+    let iface = match request.oif {
+        Some(oif) => Some(
+            net_ns
+                .find_iface(|iface| iface.index() == oif)
+                .ok_or_else(|| Error::with_message(Errno::ENODEV, "the link does not exist"))?,
+        ),
+        None => None,
+    };
+
+    let Some(gateway) = request.gateway else {
+        return iface.ok_or_else(|| {
+            Error::with_message(
+                Errno::EINVAL,
+                "either the gateway or the output link should be specified",
+            )
+        });
+    };
+
+    let is_on_link = |iface: &Iface| {
+        iface_cidrs(iface)
+            .iter()
+            .any(|cidr| cidr.contains_addr(&gateway))
+    };
+    let iface = match iface {
+        Some(iface) => is_on_link(iface.as_ref()).then_some(iface),
+        None => net_ns.find_iface(is_on_link),
+    };
+    iface.ok_or_else(|| Error::with_message(Errno::ENETUNREACH, "the gateway is not reachable"))
+}
+
+/// Returns the routes to the networks of the interface addresses.
+///
+/// Linux adds these routes when the addresses are added, and removes them when the addresses are
+/// removed. In our routing table, they are implicit, so they are generated here for dumps.
+fn iface_to_new_routes(request_header: &CMsgSegHdr, iface: &Arc<Iface>) -> Vec<RouteSegment> {
+    let mut route_segments = Vec::new();
+
+    // The routes to IPv4 loopback addresses are in the local table, not the main table.
+    for cidr in iface.ipv4_cidrs() {
+        if cidr.address().is_loopback() {
+            continue;
+        }
+        let route = Route {
+            dst: network_of(&IpCidr::Ipv4(cidr)),
+            gateway: None,
+            iface: iface.clone(),
+            priority: 0,
+            protocol: RTPROT_KERNEL,
+        };
+        let pref_src = IpAddr::V4(cidr.address());
+        route_segments.push(route_to_segment(
+            CSegmentType::NEWROUTE,
+            request_header,
+            &route,
+            Some(pref_src),
+        ));
+    }
+
+    for cidr in iface.ipv6_cidrs() {
+        let route = Route {
+            dst: network_of(&IpCidr::Ipv6(cidr)),
+            gateway: None,
+            iface: iface.clone(),
+            priority: IP6_RT_PRIO_ADDRCONF,
+            protocol: RTPROT_KERNEL,
+        };
+        route_segments.push(route_to_segment(
+            CSegmentType::NEWROUTE,
+            request_header,
+            &route,
+            None,
+        ));
+    }
+
+    route_segments
+}
+
+/// Notifies the subscribers of `RTNLGRP_IPV4_ROUTE` or `RTNLGRP_IPV6_ROUTE` that the route is
+/// added or deleted.
+fn notify_route(
+    net_ns: &Arc<NetNamespace>,
+    type_: CSegmentType,
+    request_header: &CMsgSegHdr,
+    route: &Route,
+) {
+    let group = match route.dst {
+        IpCidr::Ipv4(_) => RtnlGroup::IPV4_ROUTE,
+        IpCidr::Ipv6(_) => RtnlGroup::IPV6_ROUTE,
+    };
+
+    let segment = route_to_segment(type_, request_header, route, None);
+    let segment = match type_ {
+        CSegmentType::DELROUTE => RtnlSegment::DelRoute(segment),
+        _ => RtnlSegment::NewRoute(segment),
+    };
+    notify(net_ns, group, segment);
+}
+
+fn route_to_segment(
+    type_: CSegmentType,
+    request_header: &CMsgSegHdr,
+    route: &Route,
+    pref_src: Option<IpAddr>,
+) -> RouteSegment {
+    let header = CMsgSegHdr {
+        len: 0,
+        type_: type_ as _,
+        flags: SegHdrCommonFlags::empty().bits(),
+        seq: request_header.seq,
+        pid: request_header.pid,
+    };
+
+    let (family, is_ipv4) = match route.dst {
+        IpCidr::Ipv4(_) => (CSocketAddrFamily::AF_INET, true),
+        IpCidr::Ipv6(_) => (CSocketAddrFamily::AF_INET6, false),
+    };
+    // Linux reports IPv6 routes with `RT_SCOPE_UNIVERSE` regardless of gateways.
+    let scope = if route.gateway.is_some() || !is_ipv4 {
+        RtScope::UNIVERSE
+    } else {
+        RtScope::LINK
+    };
+
+    let route_message = RouteSegmentBody {
+        family: family as _,
+        dst_len: route.dst.prefix_len(),
+        src_len: 0,
+        tos: 0,
+        table: RT_TABLE_MAIN as u8,
+        protocol: route.protocol,
+        scope,
+        type_: RouteType::UNICAST,
+        flags: 0,
+    };
+
+    // Linux may report many other attributes, such as `RTA_METRICS`, `RTA_CACHEINFO`, and
+    // `RTA_PREF`. See `fib_dump_info` and `rt6_fill_node` in Linux for the complete lists.
+    // TODO: Support more attributes.
+    let mut attrs = vec![RouteAttr::Table(RT_TABLE_MAIN)];
+    if route.dst.prefix_len() > 0 {
+        attrs.push(RouteAttr::Dst(route.dst.address().into()));
+    }
+    // Linux always reports the priority of IPv6 routes.
+    if route.priority != 0 || !is_ipv4 {
+        attrs.push(RouteAttr::Priority(route.priority));
+    }
+    if let Some(pref_src) = pref_src {
+        attrs.push(RouteAttr::PrefSrc(pref_src));
+    }
+    if let Some(gateway) = route.gateway {
+        attrs.push(RouteAttr::Gateway(gateway.into()));
+    }
+    attrs.push(RouteAttr::Oif(route.iface.index()));
+
+    RouteSegment::new(header, route_message, attrs)
+}
+
+/// Returns the addresses of the interface in all families.
+fn iface_cidrs(iface: &Iface) -> Vec<IpCidr> {
+    let ipv4_cidrs = iface.ipv4_cidrs().into_iter().map(IpCidr::Ipv4);
+    let ipv6_cidrs = iface.ipv6_cidrs().into_iter().map(IpCidr::Ipv6);
+    ipv4_cidrs.chain(ipv6_cidrs).collect()
+}
+
+/// Returns the network of the CIDR, i.e., the CIDR with the host bits cleared.
+fn network_of(cidr: &IpCidr) -> IpCidr {
+    let prefix_len = cidr.prefix_len();
+    match cidr {
+        IpCidr::Ipv4(cidr) => {
+            let mask = u32::MAX.checked_shl(32 - prefix_len as u32).unwrap_or(0);
+            let addr = Ipv4Addr::from(u32::from(cidr.address()) & mask);
+            IpCidr::Ipv4(Ipv4Cidr::new(addr, prefix_len))
+        }
+        IpCidr::Ipv6(cidr) => {
+            let mask = u128::MAX.checked_shl(128 - prefix_len as u32).unwrap_or(0);
+            let addr = Ipv6Addr::from(u128::from(cidr.address()) & mask);
+            IpCidr::Ipv6(Ipv6Cidr::new(addr, prefix_len))
+        }
+    }
+}
+
+fn is_same_family(cidr: &IpCidr, addr: &IpAddr) -> bool {
+    matches!(
+        (cidr, addr),
+        (IpCidr::Ipv4(_), IpAddr::V4(_)) | (IpCidr::Ipv6(_), IpAddr::V6(_))
+    )
+}
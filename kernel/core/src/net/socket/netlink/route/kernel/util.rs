@@ -1,11 +1,18 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use crate::{
-    net::socket::netlink::{
-        message::{CMsgSegHdr, DoneSegment, ProtocolSegment, SegHdrCommonFlags},
-        route::message::RtnlSegment,
+    net::{
+        net_ns::NetNamespace,
+        socket::netlink::{
+            GroupIdSet,
+            message::{CMsgSegHdr, DoneSegment, ErrorSegment, ProtocolSegment, SegHdrCommonFlags},
+            route::message::{RtnlMessage, RtnlSegment},
+            table::{NetlinkRouteProtocol, SupportedNetlinkProtocol},
+        },
     },
     prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+    security::lsm::hooks as lsm_hooks,
 };
 
 /// Finishes a response message.
@@ -22,6 +29,59 @@ pub(crate) fn finish_response(
     add_multi_flag(response_segments);
 }
 
+/// Returns the response for a successful request that is not a get request.
+///
+/// The response is an acknowledgment if the request asks for one, and is empty otherwise.
+pub(crate) fn ack_response(request_header: &CMsgSegHdr) -> Vec<RtnlSegment> {
+    let flags = SegHdrCommonFlags::from_bits_truncate(request_header.flags);
+    if !flags.contains(SegHdrCommonFlags::ACK) {
+        return Vec::new();
+    }
+
+    let ack_segment = ErrorSegment::new_from_request(request_header, None);
+    vec![RtnlSegment::Error(ack_segment)]
+}
+
+/// Checks whether the current thread can administer the network namespace.
+pub(super) fn check_net_admin(net_ns: &NetNamespace) -> Result<()> {
+    lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+        net_ns.owner().as_ref(),
+        current_thread!().as_posix_thread().unwrap(),
+        CapSet::NET_ADMIN,
+    ))
+}
+
+/// Multicast groups of the netlink route protocol.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/rtnetlink.h>.
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+#[repr(u32)]
+#[derive(Clone, Copy, Debug)]
+pub(super) enum RtnlGroup {
+    LINK = 1,
+    IPV4_IFADDR = 5,
+    IPV4_ROUTE = 7,
+    IPV6_IFADDR = 9,
+    IPV6_ROUTE = 11,
+}
+
+/// Notifies the sockets in the multicast group of a change in the network namespace.
+///
+/// Only the sockets created in `net_ns` will receive the notification.
+///
+/// Like Linux, the notification should be sent before the response to the request that causes
+/// the change.
+pub(super) fn notify(net_ns: &NetNamespace, group: RtnlGroup, segment: RtnlSegment) {
+    // Group `n` is represented by bit `n - 1` in `GroupIdSet`.
+    let groups = GroupIdSet::new(1 << (group as u32 - 1));
+    let message = RtnlMessage::new(vec![segment]);
+
+    debug!("netlink route notification: {:?}", message);
+
+    NetlinkRouteProtocol::multicast_in_ns(net_ns, groups, message).unwrap();
+}
+
 /// Appends a done segment as the last segment of the provided segments.
 fn append_done_segment(request_header: &CMsgSegHdr, response_segments: &mut Vec<RtnlSegment>) {
     let done_segment = DoneSegment::new_from_request(request_header, None);
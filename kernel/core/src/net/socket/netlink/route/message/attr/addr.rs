@@ -1,7 +1,8 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use core::net::{IpAddr, Ipv4Addr};
+use core::net::{IpAddr, Ipv4Addr, Ipv6Addr};
 
+use aster_bigtcp::iface::InterfaceName;
 use zerocopy::Immutable;
 
 use crate::{
@@ -54,7 +55,7 @@ pub(crate) enum AddrProtocol {
     KernelLinkLocal = 3,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub(crate) enum AddrAttr {
     Address(IpAddr),
     Broadcast(Ipv4Addr),
@@ -98,26 +99,73 @@ impl Attribute for AddrAttr {
         Self: Sized,
     {
         let payload_len = header.payload_len();
-        reader.skip_some(payload_len);
 
-        // GETADDR only supports dump requests. These requests do not have any attributes.
-        // According to the Linux behavior, we should just ignore all the attributes.
+        // TODO: Currently, `IS_NET_BYTEORDER_MASK` and `IS_NESTED_MASK` are ignored.
+        let Ok(class) = AddrAttrClass::try_from(header.type_()) else {
+            // Unknown attributes should be ignored.
+            // Reference: <https://docs.kernel.org/userspace-api/netlink/intro.html#unknown-attributes>.
+            reader.skip_some(payload_len);
+            return Ok(ContinueRead::Skipped);
+        };
 
-        Ok(ContinueRead::Skipped)
-    }
+        let res = match (class, payload_len) {
+            (AddrAttrClass::ADDRESS, 4 | 16) => Self::Address(read_ip_addr(reader, payload_len)?),
+            (AddrAttrClass::LOCAL, 4 | 16) => Self::Local(read_ip_addr(reader, payload_len)?),
+            (AddrAttrClass::BROADCAST, 4) => {
+                Self::Broadcast(Ipv4Addr::from(reader.read_val_opt::<[u8; 4]>()?.unwrap()))
+            }
+            (AddrAttrClass::LABEL, 1..=InterfaceName::MAX_BYTES_WITH_NUL) => {
+                let mut label_bytes = [0u8; InterfaceName::MAX_BYTES_WITH_NUL];
 
-    fn read_all_from(
-        reader: &mut dyn MultiRead,
-        total_len: usize,
-    ) -> Result<ContinueRead<Vec<Self>>>
-    where
-        Self: Sized,
-    {
-        reader.skip_some(total_len);
+                let mut writer = VmWriter::from(&mut label_bytes[..payload_len]);
+                reader.read(&mut writer)?;
 
-        // GETADDR only supports dump requests. These requests do not have any attributes.
-        // According to the Linux behavior, we should just ignore all the attributes.
+                // The label may not be null-terminated if it fills the entire buffer.
+                let Ok(label) = CStr::from_bytes_until_nul(&label_bytes) else {
+                    return Ok(ContinueRead::skipped_with_error(
+                        Errno::EINVAL,
+                        "the address label is too long",
+                    ));
+                };
+                Self::Label(CString::from(label))
+            }
+            (AddrAttrClass::FLAGS, 4) => Self::Flags(AddrMessageFlags::from_bits_truncate(
+                reader.read_val_opt::<u32>()?.unwrap(),
+            )),
 
-        Ok(ContinueRead::Skipped)
+            (
+                AddrAttrClass::ADDRESS
+                | AddrAttrClass::LOCAL
+                | AddrAttrClass::BROADCAST
+                | AddrAttrClass::LABEL
+                | AddrAttrClass::FLAGS,
+                _,
+            ) => {
+                warn!("address attribute `{:?}` contains invalid payload", class);
+                reader.skip_some(payload_len);
+                return Ok(ContinueRead::skipped_with_error(
+                    Errno::EINVAL,
+                    "the address attribute is invalid",
+                ));
+            }
+
+            (_, _) => {
+                warn!("address attribute `{:?}` is not supported", class);
+                reader.skip_some(payload_len);
+                return Ok(ContinueRead::Skipped);
+            }
+        };
+
+        Ok(ContinueRead::Parsed(res))
     }
 }
+
+/// Reads an IPv4 or IPv6 address, depending on the payload length.
+fn read_ip_addr(reader: &mut dyn MultiRead, payload_len: usize) -> Result<IpAddr> {
+    let addr = if payload_len == 4 {
+        IpAddr::V4(Ipv4Addr::from(reader.read_val_opt::<[u8; 4]>()?.unwrap()))
+    } else {
+        IpAddr::V6(Ipv6Addr::from(reader.read_val_opt::<[u8; 16]>()?.unwrap()))
+    };
+    Ok(addr)
+}
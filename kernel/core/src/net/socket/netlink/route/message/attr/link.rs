@@ -83,7 +83,7 @@ enum LinkAttrClass {
     PARENT_DEV_BUS_NAME = 57,
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub(crate) enum LinkAttr {
     // FIXME: Linux link-layer addresses have device-specific lengths.
     // Using `EthernetAddress` may be inappropriate
@@ -92,8 +92,16 @@ pub(crate) enum LinkAttr {
     Broadcast(EthernetAddress),
     Name(InterfaceName),
     Mtu(u32),
+    Link(u32),
+    Master(u32),
     TxqLen(u32),
     LinkMode(u8),
+    /// The raw payload, which contains nested [`LinkInfoAttr`]s.
+    ///
+    /// [`LinkInfoAttr`]: super::link_info::LinkInfoAttr
+    LinkInfo(Vec<u8>),
+    NetNsPid(u32),
+    NetNsFd(u32),
     ExtMask(RtExtFilter),
 }
 
@@ -104,8 +112,13 @@ impl LinkAttr {
             LinkAttr::Broadcast(_) => LinkAttrClass::BROADCAST,
             LinkAttr::Name(_) => LinkAttrClass::IFNAME,
             LinkAttr::Mtu(_) => LinkAttrClass::MTU,
+            LinkAttr::Link(_) => LinkAttrClass::LINK,
+            LinkAttr::Master(_) => LinkAttrClass::MASTER,
             LinkAttr::TxqLen(_) => LinkAttrClass::TXQLEN,
             LinkAttr::LinkMode(_) => LinkAttrClass::LINKMODE,
+            LinkAttr::LinkInfo(_) => LinkAttrClass::LINKINFO,
+            LinkAttr::NetNsPid(_) => LinkAttrClass::NET_NS_PID,
+            LinkAttr::NetNsFd(_) => LinkAttrClass::NET_NS_FD,
             LinkAttr::ExtMask(_) => LinkAttrClass::EXT_MASK,
         }
     }
@@ -122,8 +135,13 @@ impl Attribute for LinkAttr {
             LinkAttr::Broadcast(address) => &address.0,
             LinkAttr::Name(name) => name.as_bytes_with_nul(),
             LinkAttr::Mtu(mtu) => mtu.as_bytes(),
+            LinkAttr::Link(index) => index.as_bytes(),
+            LinkAttr::Master(index) => index.as_bytes(),
             LinkAttr::TxqLen(txq_len) => txq_len.as_bytes(),
             LinkAttr::LinkMode(link_mode) => link_mode.as_bytes(),
+            LinkAttr::LinkInfo(payload) => payload,
+            LinkAttr::NetNsPid(pid) => pid.as_bytes(),
+            LinkAttr::NetNsFd(fd) => fd.as_bytes(),
             LinkAttr::ExtMask(ext_filter) => ext_filter.as_bytes(),
         }
     }
@@ -160,8 +178,19 @@ impl Attribute for LinkAttr {
                 Self::Name(name)
             }
             (LinkAttrClass::MTU, 4) => Self::Mtu(reader.read_val_opt::<u32>()?.unwrap()),
+            (LinkAttrClass::LINK, 4) => Self::Link(reader.read_val_opt::<u32>()?.unwrap()),
+            (LinkAttrClass::MASTER, 4) => Self::Master(reader.read_val_opt::<u32>()?.unwrap()),
             (LinkAttrClass::TXQLEN, 4) => Self::TxqLen(reader.read_val_opt::<u32>()?.unwrap()),
             (LinkAttrClass::LINKMODE, 1) => Self::LinkMode(reader.read_val_opt::<u8>()?.unwrap()),
+            (LinkAttrClass::LINKINFO, _) => {
+                let mut payload = vec![0u8; payload_len];
+                reader.read(&mut VmWriter::from(payload.as_mut_slice()))?;
+                Self::LinkInfo(payload)
+            }
+            (LinkAttrClass::NET_NS_PID, 4) => {
+                Self::NetNsPid(reader.read_val_opt::<u32>()?.unwrap())
+            }
+            (LinkAttrClass::NET_NS_FD, 4) => Self::NetNsFd(reader.read_val_opt::<u32>()?.unwrap()),
             (LinkAttrClass::EXT_MASK, 4) => {
                 const { assert!(size_of::<RtExtFilter>() == 4) };
                 Self::ExtMask(reader.read_val_opt::<RtExtFilter>()?.unwrap())
@@ -170,8 +199,12 @@ impl Attribute for LinkAttr {
             (
                 LinkAttrClass::IFNAME
                 | LinkAttrClass::MTU
+                | LinkAttrClass::LINK
+                | LinkAttrClass::MASTER
                 | LinkAttrClass::TXQLEN
                 | LinkAttrClass::LINKMODE
+                | LinkAttrClass::NET_NS_PID
+                | LinkAttrClass::NET_NS_FD
                 | LinkAttrClass::EXT_MASK,
                 _,
             ) => {
@@ -0,0 +1,165 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Attributes nested in [`LinkAttr::LinkInfo`], which describe the kind of a link and the
+//! kind-specific data.
+//!
+//! [`LinkAttr::LinkInfo`]: super::link::LinkAttr::LinkInfo
+
+use crate::{
+    net::socket::netlink::{
+        message::{Attribute, CAttrHeader, ContinueRead},
+        route::message::{
+            attr::link::LinkAttr,
+            segment::link::{CIfinfoMsg, LinkSegmentBody},
+        },
+    },
+    prelude::*,
+    util::MultiRead,
+};
+
+/// Link information attributes.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/if_link.h#L1211>.
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
+enum LinkInfoAttrClass {
+    UNSPEC = 0,
+    KIND = 1,
+    DATA = 2,
+    XSTATS = 3,
+    SLAVE_KIND = 4,
+    SLAVE_DATA = 5,
+}
+
+#[derive(Debug)]
+pub(crate) enum LinkInfoAttr {
+    Kind(CString),
+    /// The raw payload, whose format depends on the kind of the link.
+    Data(Vec<u8>),
+}
+
+impl LinkInfoAttr {
+    fn class(&self) -> LinkInfoAttrClass {
+        match self {
+            LinkInfoAttr::Kind(_) => LinkInfoAttrClass::KIND,
+            LinkInfoAttr::Data(_) => LinkInfoAttrClass::DATA,
+        }
+    }
+}
+
+impl Attribute for LinkInfoAttr {
+    fn type_(&self) -> u16 {
+        self.class() as u16
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        match self {
+            LinkInfoAttr::Kind(kind) => kind.as_bytes_with_nul(),
+            LinkInfoAttr::Data(payload) => payload,
+        }
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<ContinueRead<Self>>
+    where
+        Self: Sized,
+    {
+        let payload_len = header.payload_len();
+
+        let Ok(class) = LinkInfoAttrClass::try_from(header.type_()) else {
+            reader.skip_some(payload_len);
+            return Ok(ContinueRead::Skipped);
+        };
+
+        let res = match class {
+            LinkInfoAttrClass::KIND => {
+                let payload = read_payload(reader, payload_len)?;
+                let len = payload
+                    .iter()
+                    .position(|byte| *byte == 0)
+                    .unwrap_or(payload.len());
+                Self::Kind(CString::new(&payload[..len]).unwrap())
+            }
+            LinkInfoAttrClass::DATA => Self::Data(read_payload(reader, payload_len)?),
+            _ => {
+                warn!("link info attribute `{:?}` is not supported", class);
+                reader.skip_some(payload_len);
+                return Ok(ContinueRead::Skipped);
+            }
+        };
+
+        Ok(ContinueRead::Parsed(res))
+    }
+}
+
+/// Veth-specific attributes, which are nested in [`LinkInfoAttr::Data`].
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.13/source/include/uapi/linux/veth.h#L5>.
+#[expect(non_camel_case_types)]
+#[expect(clippy::upper_case_acronyms)]
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
+enum VethInfoAttrClass {
+    UNSPEC = 0,
+    PEER = 1,
+}
+
+#[derive(Debug)]
+pub(crate) enum VethInfoAttr {
+    /// The raw payload, which contains the body and the attributes of the peer link.
+    Peer(Vec<u8>),
+}
+
+impl VethInfoAttr {
+    /// Parses the body and the attributes of the peer link from the payload of
+    /// [`VethInfoAttr::Peer`].
+    pub(crate) fn parse_peer(payload: &[u8]) -> Result<(LinkSegmentBody, Vec<LinkAttr>)> {
+        let Some((body, attrs)) = payload.split_at_checked(size_of::<CIfinfoMsg>()) else {
+            return_errno_with_message!(Errno::EINVAL, "the peer information is too short");
+        };
+
+        let body = LinkSegmentBody::try_from(CIfinfoMsg::from_bytes(body))?;
+        let attrs = LinkAttr::read_all_from_nested(attrs)?;
+
+        Ok((body, attrs))
+    }
+}
+
+impl Attribute for VethInfoAttr {
+    fn type_(&self) -> u16 {
+        match self {
+            VethInfoAttr::Peer(_) => VethInfoAttrClass::PEER as u16,
+        }
+    }
+
+    fn payload_as_bytes(&self) -> &[u8] {
+        match self {
+            VethInfoAttr::Peer(payload) => payload,
+        }
+    }
+
+    fn read_from(header: &CAttrHeader, reader: &mut dyn MultiRead) -> Result<ContinueRead<Self>>
+    where
+        Self: Sized,
+    {
+        let payload_len = header.payload_len();
+
+        match VethInfoAttrClass::try_from(header.type_()) {
+            Ok(VethInfoAttrClass::PEER) => Ok(ContinueRead::Parsed(Self::Peer(read_payload(
+                reader,
+                payload_len,
+            )?))),
+            _ => {
+                reader.skip_some(payload_len);
+                Ok(ContinueRead::Skipped)
+            }
+        }
+    }
+}
+
+fn read_payload(reader: &mut dyn MultiRead, payload_len: usize) -> Result<Vec<u8>> {
+    let mut payload = vec![0u8; payload_len];
+    reader.read(&mut VmWriter::from(payload.as_mut_slice()))?;
+    Ok(payload)
+}
@@ -2,3 +2,5 @@
 
 pub(crate) mod addr;
 pub(crate) mod link;
+pub(crate) mod link_info;
+pub(crate) mod route;
@@ -13,14 +13,19 @@ mod segment;
 pub(super) use attr::{
     addr::{AddrAttr, AddrProtocol},
     link::LinkAttr,
+    link_info::{LinkInfoAttr, VethInfoAttr},
+    route::RouteAttr,
 };
 pub(super) use segment::{
     RtnlSegment,
     addr::{AddrMessageFlags, AddrSegment, AddrSegmentBody, RtScope},
     link::{LinkSegment, LinkSegmentBody},
+    route::{RouteSegment, RouteSegmentBody, RouteType},
 };
 
-use crate::net::socket::netlink::message::Message;
+use crate::net::socket::netlink::{message::Message, table::MulticastMessage};
 
 /// A netlink route message.
 pub(in netlink) type RtnlMessage = Message<RtnlSegment>;
+
+impl MulticastMessage for RtnlMessage {}
@@ -9,9 +9,12 @@ use super::{
     receiver::QueueableMessage,
 };
 use crate::{
-    net::socket::netlink::{
-        addr::UNSPECIFIED_PORT, kobject_uevent::UeventMessage, receiver::MessageReceiver,
-        route::RtnlMessage,
+    net::{
+        net_ns::NetNamespace,
+        socket::netlink::{
+            addr::UNSPECIFIED_PORT, kobject_uevent::UeventMessage, netfilter::NfnlMessage,
+            receiver::MessageReceiver, route::RtnlMessage,
+        },
     },
     prelude::*,
     util::random::getrandom,
@@ -25,6 +28,7 @@ static NETLINK_SOCKET_TABLE: Once<NetlinkSocketTable> = Once::new();
 struct NetlinkSocketTable {
     route: RwMutex<ProtocolSocketTable<RtnlMessage>>,
     uevent: RwMutex<ProtocolSocketTable<UeventMessage>>,
+    netfilter: RwMutex<ProtocolSocketTable<NfnlMessage>>,
 }
 
 impl NetlinkSocketTable {
@@ -32,6 +36,7 @@ impl NetlinkSocketTable {
         Self {
             route: RwMutex::new(ProtocolSocketTable::new()),
             uevent: RwMutex::new(ProtocolSocketTable::new()),
+            netfilter: RwMutex::new(ProtocolSocketTable::new()),
         }
     }
 }
@@ -44,9 +49,10 @@ pub(crate) trait SupportedNetlinkProtocol {
     fn bind(
         addr: &NetlinkSocketAddr,
         receiver: MessageReceiver<Self::Message>,
+        net_ns: Arc<NetNamespace>,
     ) -> Result<BoundHandle<Self::Message>> {
         let mut socket_table = Self::socket_table().write();
-        socket_table.bind(Self::socket_table(), addr, receiver)
+        socket_table.bind(Self::socket_table(), addr, receiver, net_ns)
     }
 
     fn unicast(dst_port: PortNum, message: Self::Message) -> Result<()>
@@ -57,13 +63,28 @@ pub(crate) trait SupportedNetlinkProtocol {
         socket_table.unicast(dst_port, message)
     }
 
-    #[cfg_attr(not(ktest), expect(dead_code))]
+    /// Multicasts the message to the sockets in the groups in all network namespaces.
     fn multicast(dst_groups: GroupIdSet, message: Self::Message) -> Result<()>
     where
         Self::Message: MulticastMessage,
     {
         let socket_table = Self::socket_table().read();
-        socket_table.multicast(dst_groups, message)
+        socket_table.multicast(dst_groups, message, |_| true)
+    }
+
+    /// Multicasts the message to the sockets in the groups that are created in `net_ns`.
+    fn multicast_in_ns(
+        net_ns: &NetNamespace,
+        dst_groups: GroupIdSet,
+        message: Self::Message,
+    ) -> Result<()>
+    where
+        Self::Message: MulticastMessage,
+    {
+        let socket_table = Self::socket_table().read();
+        socket_table.multicast(dst_groups, message, |socket_ns| {
+            core::ptr::eq(socket_ns.as_ref(), net_ns)
+        })
     }
 }
 
@@ -87,15 +108,32 @@ impl SupportedNetlinkProtocol for NetlinkUeventProtocol {
     }
 }
 
+pub(crate) enum NetlinkNetfilterProtocol {}
+
+impl SupportedNetlinkProtocol for NetlinkNetfilterProtocol {
+    type Message = NfnlMessage;
+
+    fn socket_table() -> &'static RwMutex<ProtocolSocketTable<Self::Message>> {
+        &NETLINK_SOCKET_TABLE.get().unwrap().netfilter
+    }
+}
+
 /// Bound socket table of a single netlink protocol.
 ///
 /// Each table can have bound sockets for unicast
 /// and at most 32 groups for multicast.
 pub(crate) struct ProtocolSocketTable<Message> {
-    unicast_sockets: BTreeMap<PortNum, MessageReceiver<Message>>,
+    unicast_sockets: BTreeMap<PortNum, BoundSocket<Message>>,
     multicast_groups: Box<[MulticastGroup]>,
 }
 
+/// A socket in the [`ProtocolSocketTable`].
+struct BoundSocket<Message> {
+    receiver: MessageReceiver<Message>,
+    /// The network namespace where the socket is created.
+    net_ns: Arc<NetNamespace>,
+}
+
 impl<Message: 'static> ProtocolSocketTable<Message> {
     /// Creates a new table.
     fn new() -> Self {
@@ -122,6 +160,7 @@ impl<Message: 'static> ProtocolSocketTable<Message> {
         socket_table: &'static RwMutex<ProtocolSocketTable<Message>>,
         addr: &NetlinkSocketAddr,
         receiver: MessageReceiver<Message>,
+        net_ns: Arc<NetNamespace>,
     ) -> Result<BoundHandle<Message>> {
         let port = if addr.port() != UNSPECIFIED_PORT {
             addr.port()
@@ -138,7 +177,8 @@ impl<Message: 'static> ProtocolSocketTable<Message> {
             return_errno_with_message!(Errno::EADDRINUSE, "the netlink port is already in use");
         }
 
-        self.unicast_sockets.insert(port, receiver);
+        self.unicast_sockets
+            .insert(port, BoundSocket { receiver, net_ns });
 
         for group_id in addr.groups().ids_iter() {
             let group = &mut self.multicast_groups[group_id as usize];
@@ -152,18 +192,21 @@ impl<Message: 'static> ProtocolSocketTable<Message> {
     where
         Message: QueueableMessage,
     {
-        let Some(receiver) = self.unicast_sockets.get(&dst_port) else {
+        let Some(socket) = self.unicast_sockets.get(&dst_port) else {
             // FIXME: Should we return error here?
             return Ok(());
         };
-        receiver.enqueue_message(message);
+        socket.receiver.enqueue_message(message);
 
         Ok(())
     }
 
-    fn multicast(&self, dst_groups: GroupIdSet, message: Message) -> Result<()>
+    /// Multicasts the message to the sockets in the groups
+    /// whose network namespaces satisfy `ns_filter`.
+    fn multicast<F>(&self, dst_groups: GroupIdSet, message: Message, ns_filter: F) -> Result<()>
     where
         Message: MulticastMessage,
+        F: Fn(&Arc<NetNamespace>) -> bool,
     {
         for group in dst_groups.ids_iter() {
             let Some(group) = self.multicast_groups.get(group as usize) else {
@@ -171,10 +214,13 @@ impl<Message: 'static> ProtocolSocketTable<Message> {
             };
 
             for port_num in group.members() {
-                let Some(receiver) = self.unicast_sockets.get(port_num) else {
+                let Some(socket) = self.unicast_sockets.get(port_num) else {
                     continue;
                 };
-                receiver.enqueue_message(message.clone());
+                if !ns_filter(&socket.net_ns) {
+                    continue;
+                }
+                socket.receiver.enqueue_message(message.clone());
             }
         }
 
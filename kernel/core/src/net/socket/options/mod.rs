@@ -3,7 +3,12 @@
 use macros::impl_socket_options;
 
 use super::util::{LingerOption, SocketTimeout};
-use crate::{net::socket::unix::CUserCred, prelude::*, process::Gid, util::net::SockType};
+use crate::{
+    net::socket::unix::CUserCred,
+    prelude::*,
+    process::Gid,
+    util::{bpf::BpfProgram, net::SockType},
+};
 
 pub(in crate::net) mod macros;
 
@@ -33,4 +38,6 @@ impl_socket_options!(
     pub(crate) struct SendBufForce(u32);
     pub(crate) struct RecvBufForce(u32);
     pub(crate) struct PeerGroups(Arc<[Gid]>);
+    pub(crate) struct AttachFilter(Arc<BpfProgram>);
+    pub(crate) struct DetachFilter(());
 );
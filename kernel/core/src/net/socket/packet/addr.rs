@@ -0,0 +1,71 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use crate::{net::socket::util::SocketAddr, prelude::*};
+
+/// The maximum length of a link-layer address in [`PacketSocketAddr`].
+pub(crate) const MAX_HARDWARE_ADDR_LEN: usize = 8;
+
+/// A packet socket address, i.e., `struct sockaddr_ll` in Linux.
+///
+/// Reference: <https://man7.org/linux/man-pages/man7/packet.7.html>
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) struct PacketSocketAddr {
+    /// The link-layer protocol in host byte order.
+    pub(crate) protocol: u16,
+    /// The interface index, or zero for any interface.
+    pub(crate) ifindex: u32,
+    /// The ARP hardware type of the interface.
+    pub(crate) hatype: u16,
+    /// The packet type.
+    pub(crate) pkttype: PacketType,
+    /// The length of the valid bytes in `addr`.
+    pub(crate) halen: u8,
+    /// The link-layer address.
+    pub(crate) addr: [u8; MAX_HARDWARE_ADDR_LEN],
+}
+
+impl PacketSocketAddr {
+    /// Returns the valid bytes of the link-layer address.
+    pub(crate) fn hardware_addr(&self) -> &[u8] {
+        &self.addr[..(self.halen as usize).min(MAX_HARDWARE_ADDR_LEN)]
+    }
+}
+
+/// The type of a packet received by a packet socket.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/if_packet.h#L26>
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq, TryFromInt)]
+pub(crate) enum PacketType {
+    /// The packet is addressed to the local host.
+    #[default]
+    Host = 0,
+    /// The packet is a link-layer broadcast packet.
+    Broadcast = 1,
+    /// The packet is a link-layer multicast packet.
+    Multicast = 2,
+    /// The packet is addressed to another host.
+    OtherHost = 3,
+    /// The packet is transmitted by the local host.
+    Outgoing = 4,
+}
+
+impl TryFrom<SocketAddr> for PacketSocketAddr {
+    type Error = Error;
+
+    fn try_from(value: SocketAddr) -> Result<Self> {
+        match value {
+            SocketAddr::Packet(addr) => Ok(addr),
+            _ => return_errno_with_message!(
+                Errno::EINVAL,
+                "the address is in an unsupported address family"
+            ),
+        }
+    }
+}
+
+impl From<PacketSocketAddr> for SocketAddr {
+    fn from(value: PacketSocketAddr) -> Self {
+        SocketAddr::Packet(value)
+    }
+}
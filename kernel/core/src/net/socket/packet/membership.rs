@@ -0,0 +1,154 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{addr::MAX_HARDWARE_ADDR_LEN, options::PacketMreq};
+use crate::{
+    net::{iface::Iface, net_ns::NetNamespace},
+    prelude::*,
+};
+
+/// The link-layer memberships that a packet socket adds to the interfaces.
+///
+/// The memberships are reverted when the set is dropped, i.e., when the socket is closed.
+pub(super) struct MembershipSet {
+    memberships: Vec<Membership>,
+}
+
+struct Membership {
+    ifindex: u32,
+    type_: MembershipType,
+    addr: [u8; MAX_HARDWARE_ADDR_LEN],
+    alen: u16,
+    count: usize,
+    iface: Weak<Iface>,
+}
+
+/// The type of a link-layer membership.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/if_packet.h#L285>
+#[repr(u16)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
+enum MembershipType {
+    Multicast = 0,
+    Promisc = 1,
+    AllMulti = 2,
+    Unicast = 3,
+}
+
+impl MembershipSet {
+    pub(super) const fn new() -> Self {
+        Self {
+            memberships: Vec::new(),
+        }
+    }
+
+    /// Adds a membership to the interface specified by `mreq`.
+    pub(super) fn add(&mut self, mreq: &PacketMreq, net_ns: &NetNamespace) -> Result<()> {
+        let type_ = MembershipType::try_from(mreq.type_)
+            .map_err(|_| Error::with_message(Errno::EINVAL, "the membership type is invalid"))?;
+        let ifindex = mreq.ifindex as u32;
+
+        let Some(iface) = net_ns.find_iface(|iface| iface.index() == ifindex) else {
+            return_errno_with_message!(Errno::ENODEV, "the interface does not exist");
+        };
+        let iface_addr_len = iface
+            .ethernet_addr()
+            .map_or(0, |addr| addr.as_bytes().len());
+        if mreq.alen as usize > iface_addr_len {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the address is longer than the hardware address of the interface"
+            );
+        }
+
+        let addr = masked_addr(mreq);
+        if let Some(membership) = self
+            .memberships
+            .iter_mut()
+            .find(|membership| membership.matches(ifindex, type_, mreq.alen, &addr))
+        {
+            membership.count += 1;
+            return Ok(());
+        }
+
+        apply_membership(&iface, type_, true);
+        self.memberships.push(Membership {
+            ifindex,
+            type_,
+            addr,
+            alen: mreq.alen,
+            count: 1,
+            iface: Arc::downgrade(&iface),
+        });
+
+        Ok(())
+    }
+
+    /// Removes a membership that has been added by [`Self::add`].
+    pub(super) fn remove(&mut self, mreq: &PacketMreq) -> Result<()> {
+        let Ok(type_) = MembershipType::try_from(mreq.type_) else {
+            return_errno_with_message!(Errno::EADDRNOTAVAIL, "the membership does not exist");
+        };
+        let ifindex = mreq.ifindex as u32;
+        let addr = masked_addr(mreq);
+
+        let Some(pos) = self
+            .memberships
+            .iter()
+            .position(|membership| membership.matches(ifindex, type_, mreq.alen, &addr))
+        else {
+            return_errno_with_message!(Errno::EADDRNOTAVAIL, "the membership does not exist");
+        };
+
+        let membership = &mut self.memberships[pos];
+        membership.count -= 1;
+        if membership.count == 0 {
+            self.memberships.swap_remove(pos).revert();
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for MembershipSet {
+    fn drop(&mut self) {
+        for membership in self.memberships.drain(..) {
+            membership.revert();
+        }
+    }
+}
+
+impl Membership {
+    fn matches(
+        &self,
+        ifindex: u32,
+        type_: MembershipType,
+        alen: u16,
+        addr: &[u8; MAX_HARDWARE_ADDR_LEN],
+    ) -> bool {
+        self.ifindex == ifindex && self.type_ == type_ && self.alen == alen && &self.addr == addr
+    }
+
+    fn revert(self) {
+        // The interface may have been deleted. In that case, there is nothing to revert.
+        if let Some(iface) = self.iface.upgrade() {
+            apply_membership(&iface, self.type_, false);
+        }
+    }
+}
+
+fn masked_addr(mreq: &PacketMreq) -> [u8; MAX_HARDWARE_ADDR_LEN] {
+    let mut addr = [0; MAX_HARDWARE_ADDR_LEN];
+    let len = (mreq.alen as usize).min(MAX_HARDWARE_ADDR_LEN);
+    addr[..len].copy_from_slice(&mreq.address[..len]);
+    addr
+}
+
+fn apply_membership(iface: &Iface, type_: MembershipType, is_add: bool) {
+    match type_ {
+        MembershipType::Promisc => iface.update_promiscuity(is_add),
+        MembershipType::AllMulti => iface.update_allmulti(is_add),
+        // TODO: Support link-layer address filters. Currently, the devices do not filter frames by
+        // their destination addresses, so these memberships have no effects.
+        MembershipType::Multicast | MembershipType::Unicast => (),
+    }
+}
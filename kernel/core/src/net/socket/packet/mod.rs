@@ -0,0 +1,421 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module defines packet sockets.
+//!
+//! Packet sockets (i.e., `AF_PACKET` sockets in Linux) send and receive raw frames at the device
+//! driver level. A packet socket sees a copy of every frame that the interfaces in its network
+//! namespace receive, and can optionally be bound to a single interface and a single link-layer
+//! protocol. `SOCK_RAW` sockets work with the complete frames, including the link-layer headers,
+//! while `SOCK_DGRAM` sockets work with the frames whose link-layer headers are removed.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man7/packet.7.html>
+
+use aster_bigtcp::iface::FrameDirection;
+use membership::MembershipSet;
+use receiver::{Binding, ETHER_HEADER_LEN, PacketReceiver, dispatch_frame};
+
+use crate::{
+    events::IoEvents,
+    fs::file::FileCommon,
+    net::{
+        iface::Iface,
+        net_ns::NetNamespace,
+        socket::{
+            Socket, new_socket_common,
+            options::{
+                AttachFilter, DetachFilter, Error as SocketError, SocketOption,
+                macros::{sock_option_mut, sock_option_ref},
+            },
+            private::SocketPrivate,
+            util::{
+                MessageHeader, RecvFlags, RecvOutput, SendFlags, SocketAddr,
+                options::{
+                    GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet, SocketTimeouts,
+                },
+            },
+        },
+    },
+    prelude::*,
+    process::{
+        credentials::capabilities::CapSet,
+        posix_thread::AsPosixThread,
+        signal::{PollHandle, Pollable, Pollee},
+    },
+    security::lsm::hooks as lsm_hooks,
+    util::{MultiRead, MultiWrite, net::SockType},
+};
+
+mod addr;
+mod membership;
+mod options;
+mod receiver;
+
+pub(crate) use addr::{MAX_HARDWARE_ADDR_LEN, PacketSocketAddr, PacketType};
+pub(crate) use options::{AddMembership, DropMembership, PacketMreq, PacketStats, Statistics};
+pub(super) use receiver::PACKET_DEFAULT_BUF_SIZE;
+pub(in crate::net) use receiver::PacketTap;
+
+/// The protocol that matches all link-layer protocols.
+const ETH_P_ALL: u16 = 0x0003;
+
+pub(crate) struct PacketSocket {
+    receiver: Arc<PacketReceiver>,
+    memberships: Mutex<MembershipSet>,
+    options: RwLock<SocketOptionSet>,
+    socket_type: SockType,
+    timeouts: SocketTimeouts,
+
+    pollee: Pollee,
+    common: FileCommon,
+}
+
+impl PacketSocket {
+    /// Creates a packet socket.
+    ///
+    /// The protocol is the link-layer protocol in network byte order, as given to the `socket`
+    /// system call. If it is zero, the socket will not receive any frames until it is bound to a
+    /// protocol.
+    pub(crate) fn new(
+        is_nonblocking: bool,
+        socket_type: SockType,
+        protocol: u16,
+        net_ns: Arc<NetNamespace>,
+    ) -> Result<Arc<Self>> {
+        debug_assert!(socket_type == SockType::SOCK_RAW || socket_type == SockType::SOCK_DGRAM);
+
+        lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+            net_ns.owner().as_ref(),
+            current_thread!().as_posix_thread().unwrap(),
+            CapSet::NET_RAW,
+        ))?;
+
+        let pollee = Pollee::new();
+        let receiver =
+            PacketReceiver::new(net_ns, socket_type, u16::from_be(protocol), pollee.clone());
+
+        Ok(Arc::new(Self {
+            receiver,
+            memberships: Mutex::new(MembershipSet::new()),
+            options: RwLock::new(SocketOptionSet::new_packet()),
+            socket_type,
+            timeouts: SocketTimeouts::new(),
+            pollee,
+            common: new_socket_common(is_nonblocking),
+        }))
+    }
+
+    fn find_iface(&self, ifindex: u32) -> Option<Arc<Iface>> {
+        self.receiver
+            .net_ns()
+            .find_iface(|iface| iface.index() == ifindex)
+    }
+
+    fn try_send(
+        &self,
+        reader: &mut dyn MultiRead,
+        remote: Option<&PacketSocketAddr>,
+    ) -> Result<usize> {
+        let binding = self.receiver.binding();
+        let (ifindex, protocol) = match remote {
+            Some(remote) => (remote.ifindex, remote.protocol),
+            None => (binding.ifindex, binding.protocol),
+        };
+
+        if ifindex == 0 {
+            return_errno_with_message!(Errno::ENXIO, "the interface is not specified");
+        }
+        let Some(iface) = self.find_iface(ifindex) else {
+            return_errno_with_message!(Errno::ENXIO, "the interface does not exist");
+        };
+
+        let payload_len = reader.sum_lens();
+        let mut frame = match (self.socket_type, iface.ethernet_addr()) {
+            (SockType::SOCK_DGRAM, Some(ether_addr)) => {
+                let Some(remote) = remote else {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "the destination address is not specified"
+                    );
+                };
+                let Some(dst_addr) = remote.hardware_addr().get(..6) else {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "the destination address is too short"
+                    );
+                };
+
+                let mut frame = Vec::with_capacity(ETHER_HEADER_LEN + payload_len);
+                frame.extend_from_slice(dst_addr);
+                frame.extend_from_slice(ether_addr.as_bytes());
+                frame.extend_from_slice(&protocol.to_be_bytes());
+                frame
+            }
+            _ => Vec::with_capacity(payload_len),
+        };
+        let header_len = frame.len();
+
+        if header_len + payload_len > iface.mtu() {
+            return_errno_with_message!(Errno::EMSGSIZE, "the frame is too large");
+        }
+        if self.socket_type == SockType::SOCK_RAW
+            && iface.ethernet_addr().is_some()
+            && payload_len < ETHER_HEADER_LEN
+        {
+            return_errno_with_message!(Errno::EINVAL, "the frame is too short");
+        }
+
+        frame.resize(header_len + payload_len, 0);
+        reader.read(&mut VmWriter::from(&mut frame[header_len..]))?;
+
+        if !iface.transmit_frame(&frame) {
+            return_errno_with_message!(Errno::ENOBUFS, "the device cannot transmit frames now");
+        }
+        dispatch_frame(
+            iface.id(),
+            &frame,
+            FrameDirection::Outgoing,
+            Some(&self.receiver),
+        );
+
+        // The loopback device queues the frame for itself, so poll the interface to receive it as
+        // soon as possible.
+        iface.poll();
+
+        Ok(payload_len)
+    }
+
+    fn try_recv(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: RecvFlags,
+    ) -> Result<(RecvOutput, PacketSocketAddr)> {
+        let should_dequeue = flags.receive_behavior().will_consume_data();
+        let (data, addr) = self.receiver.recv(should_dequeue)?;
+        self.pollee.invalidate();
+
+        let copied_len = data.len().min(writer.sum_lens());
+        writer.write(&mut VmReader::from(&data[..copied_len]))?;
+
+        let output = RecvOutput::new_for_packet(flags, copied_len, data.len());
+        Ok((output, addr))
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let mut events = IoEvents::OUT;
+
+        if self.receiver.has_frames() {
+            events |= IoEvents::IN;
+        }
+
+        events
+    }
+}
+
+impl Socket for PacketSocket {
+    fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
+        let addr = PacketSocketAddr::try_from(socket_addr)?;
+
+        if addr.ifindex != 0 && self.find_iface(addr.ifindex).is_none() {
+            return_errno_with_message!(Errno::ENODEV, "the interface does not exist");
+        }
+
+        let old_binding = self.receiver.binding();
+        self.receiver.set_binding(Binding {
+            // Like Linux, a zero protocol keeps the protocol that the socket is receiving.
+            protocol: if addr.protocol == 0 {
+                old_binding.protocol
+            } else {
+                addr.protocol
+            },
+            ifindex: addr.ifindex,
+        });
+
+        Ok(())
+    }
+
+    fn addr(&self) -> Result<SocketAddr> {
+        let binding = self.receiver.binding();
+
+        let mut addr = PacketSocketAddr {
+            protocol: binding.protocol,
+            ifindex: binding.ifindex,
+            hatype: 0,
+            pkttype: PacketType::Host,
+            halen: 0,
+            addr: [0; MAX_HARDWARE_ADDR_LEN],
+        };
+        if let Some(iface) = self.find_iface(binding.ifindex) {
+            addr.hatype = iface.type_() as u16;
+            if let Some(ether_addr) = iface.ethernet_addr() {
+                let bytes = ether_addr.as_bytes();
+                addr.halen = bytes.len() as u8;
+                addr.addr[..bytes.len()].copy_from_slice(bytes);
+            }
+        }
+
+        Ok(addr.into())
+    }
+
+    fn sendmsg(
+        &self,
+        reader: &mut dyn MultiRead,
+        message_header: MessageHeader,
+        flags: SendFlags,
+    ) -> Result<usize> {
+        // TODO: Deal with flags
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let MessageHeader {
+            addr,
+            control_messages,
+        } = message_header;
+
+        let remote = match addr {
+            None => None,
+            Some(addr) => Some(addr.try_into()?),
+        };
+
+        if !control_messages.is_empty() {
+            // TODO: Support sending control message
+            warn!("sending control message is not supported");
+        }
+
+        self.try_send(reader, remote.as_ref())
+    }
+
+    fn recvmsg(
+        &self,
+        writer: &mut dyn MultiWrite,
+        flags: RecvFlags,
+    ) -> Result<(RecvOutput, MessageHeader)> {
+        // TODO: Deal with other flags.
+        if !flags.is_all_supported() {
+            warn!("unsupported flags: {:?}", flags);
+        }
+
+        let (output, addr) = self.block_on(IoEvents::IN, self.timeouts.recv_timeout(), || {
+            self.try_recv(writer, flags)
+        })?;
+
+        // TODO: Receive control message
+
+        let message_header = MessageHeader::new(Some(addr.into()), Vec::new());
+
+        Ok((output, message_header))
+    }
+
+    fn get_option(&self, option: &mut dyn SocketOption) -> Result<()> {
+        sock_option_mut!(match option {
+            socket_errors @ SocketError => {
+                // TODO: Support socket errors for packet sockets
+                socket_errors.set(None);
+                return Ok(());
+            }
+            statistics @ Statistics => {
+                let stats = self.receiver.take_stats();
+                statistics.set(stats);
+                return Ok(());
+            }
+            _ => (),
+        });
+
+        // Deal with socket-level options
+        self.options.read().get_option(
+            option,
+            &(self.receiver.as_ref(), self.socket_type, &self.timeouts),
+        )
+    }
+
+    fn set_option(&self, option: &dyn SocketOption) -> Result<()> {
+        // Deal with socket-level options
+        match self
+            .options
+            .write()
+            .set_option(option, &(self.receiver.as_ref(), &self.timeouts))
+        {
+            Err(err) if err.error() == Errno::ENOPROTOOPT => (),
+            res => return res.map(|_need_iface_poll| ()),
+        }
+
+        // Deal with packet-level options and socket filters
+        do_packet_setsockopt(option, self)
+    }
+
+    fn common(&self) -> &FileCommon {
+        &self.common
+    }
+}
+
+impl SocketPrivate for PacketSocket {
+    fn is_nonblocking(&self) -> bool {
+        self.common.is_nonblocking()
+    }
+
+    fn net_ns(&self) -> &Arc<NetNamespace> {
+        self.receiver.net_ns()
+    }
+}
+
+impl Pollable for PacketSocket {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.pollee
+            .poll_with(mask, poller, || self.check_io_events())
+    }
+}
+
+impl Drop for PacketSocket {
+    fn drop(&mut self) {
+        self.receiver.unregister();
+    }
+}
+
+fn do_packet_setsockopt(option: &dyn SocketOption, socket: &PacketSocket) -> Result<()> {
+    sock_option_ref!(match option {
+        add_membership @ AddMembership => {
+            let mreq = add_membership.get().unwrap();
+            socket
+                .memberships
+                .lock()
+                .add(mreq, socket.receiver.net_ns())?;
+        }
+        drop_membership @ DropMembership => {
+            let mreq = drop_membership.get().unwrap();
+            socket.memberships.lock().remove(mreq)?;
+        }
+        attach_filter @ AttachFilter => {
+            let filter = attach_filter.get().unwrap();
+            socket.receiver.set_filter(Some(filter.clone()));
+        }
+        _detach_filter @ DetachFilter => {
+            if socket.receiver.set_filter(None).is_none() {
+                return_errno_with_message!(Errno::ENOENT, "no socket filter is attached");
+            }
+        }
+        _ =>
+            return_errno_with_message!(Errno::ENOPROTOOPT, "the socket option to be set is unknown"),
+    });
+
+    Ok(())
+}
+
+impl GetSocketLevelOption for (&PacketReceiver, SockType, &SocketTimeouts) {
+    fn socket_type(&self) -> SockType {
+        self.1
+    }
+
+    fn is_listening(&self) -> bool {
+        false
+    }
+
+    fn socket_timeouts(&self) -> Option<&SocketTimeouts> {
+        Some(self.2)
+    }
+}
+
+impl SetSocketLevelOption for (&PacketReceiver, &SocketTimeouts) {
+    fn socket_timeouts(&self) -> Option<&SocketTimeouts> {
+        Some(self.1)
+    }
+}
@@ -0,0 +1,34 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::addr::MAX_HARDWARE_ADDR_LEN;
+use crate::{net::socket::options::macros::impl_socket_options, prelude::*};
+
+impl_socket_options!(
+    pub(crate) struct AddMembership(PacketMreq);
+    pub(crate) struct DropMembership(PacketMreq);
+    pub(crate) struct Statistics(PacketStats);
+);
+
+/// `struct packet_mreq` in Linux, which specifies a link-layer membership of an interface.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/if_packet.h#L278>
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(crate) struct PacketMreq {
+    pub(crate) ifindex: i32,
+    pub(crate) type_: u16,
+    pub(crate) alen: u16,
+    pub(crate) address: [u8; MAX_HARDWARE_ADDR_LEN],
+}
+
+/// `struct tpacket_stats` in Linux, which counts the packets received by a packet socket.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/if_packet.h#L54>
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Default, Pod)]
+pub(crate) struct PacketStats {
+    /// The number of packets, including the dropped ones.
+    pub(crate) packets: u32,
+    /// The number of packets dropped because the receive buffer is full.
+    pub(crate) drops: u32,
+}
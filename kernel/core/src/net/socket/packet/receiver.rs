@@ -0,0 +1,370 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_bigtcp::iface::{FrameDirection, FrameTap};
+use aster_softirq::BottomHalfDisabled;
+
+use super::{
+    ETH_P_ALL,
+    addr::{MAX_HARDWARE_ADDR_LEN, PacketSocketAddr, PacketType},
+    options::PacketStats,
+};
+use crate::{
+    events::IoEvents,
+    net::{iface::Iface, net_ns::NetNamespace},
+    prelude::*,
+    process::signal::Pollee,
+    util::{
+        bpf::{BpfData, BpfProgram},
+        net::SockType,
+    },
+};
+
+/// The receiving part of a packet socket.
+///
+/// All receivers are registered in [`RECEIVERS`], so that they can see every frame that passes
+/// through the interfaces in their network namespaces.
+pub(super) struct PacketReceiver {
+    net_ns: Arc<NetNamespace>,
+    socket_type: SockType,
+    binding: SpinLock<Binding, BottomHalfDisabled>,
+    filter: SpinLock<Option<Arc<BpfProgram>>, BottomHalfDisabled>,
+    queue: SpinLock<FrameQueue, BottomHalfDisabled>,
+    pollee: Pollee,
+}
+
+/// The protocol and the interface that a packet socket is bound to.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct Binding {
+    /// The link-layer protocol in host byte order, or zero for no protocol.
+    pub(super) protocol: u16,
+    /// The interface index, or zero for any interface.
+    pub(super) ifindex: u32,
+}
+
+struct FrameQueue {
+    frames: VecDeque<ReceivedFrame>,
+    total_len: usize,
+    stats: PacketStats,
+}
+
+struct ReceivedFrame {
+    data: Vec<u8>,
+    addr: PacketSocketAddr,
+}
+
+static RECEIVERS: RwLock<Vec<Arc<PacketReceiver>>, BottomHalfDisabled> = RwLock::new(Vec::new());
+
+impl PacketReceiver {
+    /// Creates a receiver and registers it in [`RECEIVERS`].
+    pub(super) fn new(
+        net_ns: Arc<NetNamespace>,
+        socket_type: SockType,
+        protocol: u16,
+        pollee: Pollee,
+    ) -> Arc<Self> {
+        let receiver = Arc::new(Self {
+            net_ns,
+            socket_type,
+            binding: SpinLock::new(Binding {
+                protocol,
+                ifindex: 0,
+            }),
+            filter: SpinLock::new(None),
+            queue: SpinLock::new(FrameQueue {
+                frames: VecDeque::new(),
+                total_len: 0,
+                stats: PacketStats::default(),
+            }),
+            pollee,
+        });
+        RECEIVERS.write().push(receiver.clone());
+        receiver
+    }
+
+    /// Unregisters the receiver from [`RECEIVERS`].
+    pub(super) fn unregister(self: &Arc<Self>) {
+        RECEIVERS
+            .write()
+            .retain(|receiver| !Arc::ptr_eq(receiver, self));
+    }
+
+    pub(super) fn net_ns(&self) -> &Arc<NetNamespace> {
+        &self.net_ns
+    }
+
+    pub(super) fn binding(&self) -> Binding {
+        *self.binding.lock()
+    }
+
+    pub(super) fn set_binding(&self, binding: Binding) {
+        *self.binding.lock() = binding;
+    }
+
+    pub(super) fn set_filter(&self, filter: Option<Arc<BpfProgram>>) -> Option<Arc<BpfProgram>> {
+        core::mem::replace(&mut *self.filter.lock(), filter)
+    }
+
+    /// Takes the statistics and resets them.
+    pub(super) fn take_stats(&self) -> PacketStats {
+        core::mem::take(&mut self.queue.lock().stats)
+    }
+
+    pub(super) fn has_frames(&self) -> bool {
+        !self.queue.lock().frames.is_empty()
+    }
+
+    /// Receives a frame and its source address.
+    ///
+    /// If `should_dequeue` is false, the frame will be kept in the receive queue.
+    pub(super) fn recv(&self, should_dequeue: bool) -> Result<(Vec<u8>, PacketSocketAddr)> {
+        // The frame is moved out of the queue before being copied to the user space, since the
+        // queue is protected by a spin lock.
+        let mut queue = self.queue.lock();
+
+        let frame = if should_dequeue {
+            queue.frames.pop_front().inspect(|frame| {
+                queue.total_len -= frame.data.len();
+            })
+        } else {
+            queue.frames.front().map(|frame| ReceivedFrame {
+                data: frame.data.clone(),
+                addr: frame.addr,
+            })
+        };
+        let Some(frame) = frame else {
+            return_errno_with_message!(Errno::EAGAIN, "the receive buffer is empty");
+        };
+
+        Ok((frame.data, frame.addr))
+    }
+
+    fn deliver(&self, frame: &[u8], info: &FrameInfo) {
+        let binding = self.binding();
+        if binding.ifindex != 0 && binding.ifindex != info.addr.ifindex {
+            return;
+        }
+        // Like Linux, frames transmitted by the interfaces are only passed to the sockets that
+        // receive all protocols.
+        let is_wanted = match info.addr.pkttype {
+            PacketType::Outgoing => binding.protocol == ETH_P_ALL,
+            _ => binding.protocol == ETH_P_ALL || binding.protocol == info.addr.protocol,
+        };
+        if !is_wanted {
+            return;
+        }
+
+        let data = match self.socket_type {
+            SockType::SOCK_DGRAM => &frame[info.header_len..],
+            _ => frame,
+        };
+
+        let filter = self.filter.lock().clone();
+        let snap_len = match filter {
+            Some(filter) => {
+                let filter_data = FilterData {
+                    frame,
+                    data_offset: frame.len() - data.len(),
+                    info,
+                };
+                filter.run(&filter_data) as usize
+            }
+            None => data.len(),
+        };
+        if snap_len == 0 {
+            return;
+        }
+        let data = &data[..snap_len.min(data.len())];
+
+        let mut queue = self.queue.lock();
+        queue.stats.packets = queue.stats.packets.wrapping_add(1);
+        if PACKET_DEFAULT_BUF_SIZE - queue.total_len < data.len() {
+            queue.stats.drops = queue.stats.drops.wrapping_add(1);
+            return;
+        }
+        queue.frames.push_back(ReceivedFrame {
+            data: data.to_vec(),
+            addr: info.addr,
+        });
+        queue.total_len += data.len();
+        drop(queue);
+
+        self.pollee.notify(IoEvents::IN);
+    }
+}
+
+/// The information about a frame that passes through an interface.
+struct FrameInfo {
+    /// The address that describes the source of the frame.
+    addr: PacketSocketAddr,
+    /// The length of the link-layer header.
+    header_len: usize,
+}
+
+/// The size of the Ethernet header.
+pub(super) const ETHER_HEADER_LEN: usize = 14;
+
+impl FrameInfo {
+    fn new(iface: &Iface, frame: &[u8], direction: FrameDirection) -> Option<Self> {
+        const ETH_P_802_3: u16 = 0x0001;
+        const ETH_P_802_2: u16 = 0x0004;
+        const ETH_P_802_3_MIN: u16 = 0x0600;
+        const ETH_P_IP: u16 = 0x0800;
+        const ETH_P_IPV6: u16 = 0x86DD;
+
+        let mut addr = PacketSocketAddr {
+            protocol: 0,
+            ifindex: iface.index(),
+            hatype: iface.type_() as u16,
+            pkttype: PacketType::Host,
+            halen: 0,
+            addr: [0; MAX_HARDWARE_ADDR_LEN],
+        };
+
+        let Some(ether_addr) = iface.ethernet_addr() else {
+            addr.protocol = match frame.first().map(|byte| byte >> 4) {
+                Some(4) => ETH_P_IP,
+                Some(6) => ETH_P_IPV6,
+                _ => 0,
+            };
+            if direction == FrameDirection::Outgoing {
+                addr.pkttype = PacketType::Outgoing;
+            }
+            return Some(Self {
+                addr,
+                header_len: 0,
+            });
+        };
+
+        if frame.len() < ETHER_HEADER_LEN {
+            return None;
+        }
+
+        let dst_addr = &frame[0..6];
+        let src_addr = &frame[6..12];
+        let ethertype = u16::from_be_bytes([frame[12], frame[13]]);
+
+        addr.protocol = if ethertype >= ETH_P_802_3_MIN {
+            ethertype
+        } else if frame[ETHER_HEADER_LEN..].starts_with(&[0xFF, 0xFF]) {
+            // Novell raw 802.3 frames start with an IPX header whose checksum is always 0xFFFF.
+            ETH_P_802_3
+        } else {
+            ETH_P_802_2
+        };
+        addr.pkttype = if direction == FrameDirection::Outgoing {
+            PacketType::Outgoing
+        } else if dst_addr == ether_addr.as_bytes() {
+            PacketType::Host
+        } else if dst_addr == [0xFF; 6] {
+            PacketType::Broadcast
+        } else if dst_addr[0] & 1 != 0 {
+            PacketType::Multicast
+        } else {
+            PacketType::OtherHost
+        };
+        addr.halen = src_addr.len() as u8;
+        addr.addr[..src_addr.len()].copy_from_slice(src_addr);
+
+        Some(Self {
+            addr,
+            header_len: ETHER_HEADER_LEN,
+        })
+    }
+}
+
+/// The data that socket filters run on.
+struct FilterData<'a> {
+    frame: &'a [u8],
+    /// The offset of the data received by the socket in the frame.
+    data_offset: usize,
+    info: &'a FrameInfo,
+}
+
+impl BpfData for FilterData<'_> {
+    fn len(&self) -> u32 {
+        (self.frame.len() - self.data_offset) as u32
+    }
+
+    fn load(&self, offset: u32, size: usize) -> Option<u32> {
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/filter.h#L60>
+        const SKF_AD_OFF: u32 = -0x1000i32 as u32;
+        const SKF_AD_PROTOCOL: u32 = 0;
+        const SKF_AD_PKTTYPE: u32 = 4;
+        const SKF_AD_IFINDEX: u32 = 8;
+        const SKF_AD_HATYPE: u32 = 28;
+        const SKF_NET_OFF: u32 = -0x100000i32 as u32;
+        const SKF_LL_OFF: u32 = -0x200000i32 as u32;
+
+        let start = if offset >= SKF_AD_OFF {
+            let addr = &self.info.addr;
+            return match offset - SKF_AD_OFF {
+                SKF_AD_PROTOCOL => Some(addr.protocol as u32),
+                SKF_AD_PKTTYPE => Some(addr.pkttype as u32),
+                SKF_AD_IFINDEX => Some(addr.ifindex),
+                SKF_AD_HATYPE => Some(addr.hatype as u32),
+                _ => None,
+            };
+        } else if offset >= SKF_NET_OFF {
+            self.info.header_len + (offset - SKF_NET_OFF) as usize
+        } else if offset >= SKF_LL_OFF {
+            (offset - SKF_LL_OFF) as usize
+        } else {
+            self.data_offset.checked_add(offset as usize)?
+        };
+
+        let bytes = self.frame.get(start..start.checked_add(size)?)?;
+        Some(
+            bytes
+                .iter()
+                .fold(0u32, |val, byte| (val << 8) | *byte as u32),
+        )
+    }
+}
+
+/// The frame tap that passes frames to packet sockets.
+pub(in crate::net) struct PacketTap;
+
+impl FrameTap for PacketTap {
+    fn on_frame(iface_id: u32, frame: &[u8], direction: FrameDirection) {
+        dispatch_frame(iface_id, frame, direction, None);
+    }
+}
+
+/// Passes the frame to the packet sockets, except for the one whose receiver is `exclude`.
+pub(super) fn dispatch_frame(
+    iface_id: u32,
+    frame: &[u8],
+    direction: FrameDirection,
+    exclude: Option<&Arc<PacketReceiver>>,
+) {
+    let receivers = RECEIVERS.read();
+
+    // The interface is looked up lazily, since there are usually no packet sockets at all. Since
+    // interface IDs are globally unique, only the sockets in the network namespace that owns the
+    // interface can receive the frame.
+    let mut found: Option<(&Arc<NetNamespace>, Option<FrameInfo>)> = None;
+
+    for receiver in receivers.iter() {
+        if exclude.is_some_and(|exclude| Arc::ptr_eq(exclude, receiver)) {
+            continue;
+        }
+
+        let info = match &found {
+            Some((net_ns, info)) if Arc::ptr_eq(net_ns, &receiver.net_ns) => info.as_ref(),
+            Some(_) => continue,
+            None => {
+                let Some(iface) = receiver.net_ns.find_iface(|iface| iface.id() == iface_id) else {
+                    continue;
+                };
+                let info = FrameInfo::new(&iface, frame, direction);
+                found.insert((&receiver.net_ns, info)).1.as_ref()
+            }
+        };
+
+        if let Some(info) = info {
+            receiver.deliver(frame, info);
+        }
+    }
+}
+
+pub(in crate::net) const PACKET_DEFAULT_BUF_SIZE: usize = 212992;
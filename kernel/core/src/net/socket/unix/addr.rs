@@ -5,7 +5,7 @@ use keyable_arc::KeyableArc;
 use super::ns::{self, AbstractHandle};
 use crate::{
     fs::vfs::{inode::Inode, path::Path},
-    net::socket::util::SocketAddr,
+    net::{net_ns::NetNamespace, socket::util::SocketAddr},
     prelude::*,
 };
 
@@ -17,14 +17,21 @@ pub(crate) enum UnixSocketAddr {
 }
 
 impl UnixSocketAddr {
-    pub(super) fn bind(self) -> Result<UnixSocketAddrBound> {
+    /// Binds the address.
+    ///
+    /// Abstract names are allocated in `net_ns`, which is the network namespace of the socket.
+    pub(super) fn bind(self, net_ns: &NetNamespace) -> Result<UnixSocketAddrBound> {
         let bound = match self {
-            Self::Unnamed => UnixSocketAddrBound::Abstract(ns::alloc_ephemeral_abstract_name()?),
+            Self::Unnamed => {
+                UnixSocketAddrBound::Abstract(ns::alloc_ephemeral_abstract_name(net_ns)?)
+            }
             Self::Path(path_name) => {
                 let path = ns::create_socket_file(&path_name)?;
                 UnixSocketAddrBound::Path(path_name, path)
             }
-            Self::Abstract(name) => UnixSocketAddrBound::Abstract(ns::create_abstract_name(name)?),
+            Self::Abstract(name) => {
+                UnixSocketAddrBound::Abstract(ns::create_abstract_name(net_ns, name)?)
+            }
         };
 
         Ok(bound)
@@ -38,7 +45,10 @@ impl UnixSocketAddr {
         }
     }
 
-    pub(super) fn connect(&self) -> Result<UnixSocketAddrKey> {
+    /// Looks up the address to connect to.
+    ///
+    /// Abstract names are looked up in `net_ns`, which is the network namespace of the socket.
+    pub(super) fn connect(&self, net_ns: &NetNamespace) -> Result<UnixSocketAddrKey> {
         let bound = match self {
             Self::Unnamed => return_errno_with_message!(
                 Errno::EINVAL,
@@ -47,9 +57,9 @@ impl UnixSocketAddr {
             Self::Path(path) => UnixSocketAddrKey::Path(KeyableArc::from(
                 ns::lookup_socket_file(path)?.inode().clone(),
             )),
-            Self::Abstract(name) => {
-                UnixSocketAddrKey::Abstract(KeyableArc::from(ns::lookup_abstract_name(name)?))
-            }
+            Self::Abstract(name) => UnixSocketAddrKey::Abstract(KeyableArc::from(
+                ns::lookup_abstract_name(net_ns, name)?,
+            )),
         };
 
         Ok(bound)
@@ -10,13 +10,16 @@ use spin::Once;
 
 use crate::{
     events::IoEvents,
-    net::socket::{
-        unix::{
-            UnixSocketAddr,
-            addr::{UnixSocketAddrBound, UnixSocketAddrKey},
-            ctrl_msg::AuxiliaryData,
+    net::{
+        net_ns::NetNamespace,
+        socket::{
+            unix::{
+                UnixSocketAddr,
+                addr::{UnixSocketAddrBound, UnixSocketAddrKey},
+                ctrl_msg::AuxiliaryData,
+            },
+            util::{ControlMessage, RecvFlags, RecvOutput},
         },
-        util::{ControlMessage, RecvFlags, RecvOutput},
     },
     prelude::*,
     process::signal::Pollee,
@@ -156,14 +159,14 @@ impl MessageReceiver {
         }
     }
 
-    pub(super) fn bind(&self, addr_to_bind: UnixSocketAddr) -> Result<()> {
+    pub(super) fn bind(&self, addr_to_bind: UnixSocketAddr, net_ns: &NetNamespace) -> Result<()> {
         let mut addr = self.addr.lock();
 
         if addr.is_some() {
             return addr_to_bind.bind_unnamed();
         }
 
-        let bound_addr = addr_to_bind.bind()?;
+        let bound_addr = addr_to_bind.bind(net_ns)?;
         QUEUE_TABLE.add_queue(bound_addr.to_key(), self.queue.clone());
         self.queue.addr.call_once(|| bound_addr.clone().into());
         *addr = Some(bound_addr);
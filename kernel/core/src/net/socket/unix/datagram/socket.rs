@@ -11,15 +11,18 @@ use super::message::{MessageQueue, MessageReceiver};
 use crate::{
     events::IoEvents,
     fs::file::FileCommon,
-    net::socket::{
-        Socket, new_socket_common,
-        options::{Error as SocketError, PeerCred, SocketOption, macros::sock_option_mut},
-        private::SocketPrivate,
-        unix::{CUserCred, UnixSocketAddr, cred::SocketCred, ctrl_msg::AuxiliaryData},
-        util::{
-            MessageHeader, RecvFlags, RecvOutput, SendFlags, SockShutdownCmd, SocketAddr,
-            options::{
-                GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet, SocketTimeouts,
+    net::{
+        net_ns::NetNamespace,
+        socket::{
+            Socket, new_socket_common,
+            options::{Error as SocketError, PeerCred, SocketOption, macros::sock_option_mut},
+            private::SocketPrivate,
+            unix::{CUserCred, UnixSocketAddr, cred::SocketCred, ctrl_msg::AuxiliaryData},
+            util::{
+                MessageHeader, RecvFlags, RecvOutput, SendFlags, SockShutdownCmd, SocketAddr,
+                options::{
+                    GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet, SocketTimeouts,
+                },
             },
         },
     },
@@ -37,6 +40,10 @@ pub(crate) struct UnixDatagramSocket {
     // credentials. According to the Linux implementation, however, peer credentials are recorded
     // when a socket pair is created using the `socketpair` system call.
     peer_cred: Option<SocketCred>,
+    /// The network namespace where the socket is created.
+    ///
+    /// Abstract names are bound and looked up in this network namespace.
+    net_ns: Arc<NetNamespace>,
 
     is_write_shutdown: AtomicBool,
     common: FileCommon,
@@ -56,13 +63,16 @@ impl OptionSet {
 }
 
 impl UnixDatagramSocket {
-    pub(crate) fn new(is_nonblocking: bool) -> Arc<Self> {
-        Arc::new(Self::new_raw(is_nonblocking))
+    pub(crate) fn new(is_nonblocking: bool, net_ns: Arc<NetNamespace>) -> Arc<Self> {
+        Arc::new(Self::new_raw(is_nonblocking, net_ns))
     }
 
-    pub(crate) fn new_pair(is_nonblocking: bool) -> (Arc<Self>, Arc<Self>) {
-        let mut socket_a = Self::new_raw(is_nonblocking);
-        let mut socket_b = Self::new_raw(is_nonblocking);
+    pub(crate) fn new_pair(
+        is_nonblocking: bool,
+        net_ns: Arc<NetNamespace>,
+    ) -> (Arc<Self>, Arc<Self>) {
+        let mut socket_a = Self::new_raw(is_nonblocking, net_ns.clone());
+        let mut socket_b = Self::new_raw(is_nonblocking, net_ns);
 
         let cred = SocketCred::<ReadDupOp>::new_current();
         socket_a.peer_cred = Some(cred.dup().restrict());
@@ -77,13 +87,14 @@ impl UnixDatagramSocket {
         (Arc::new(socket_a), Arc::new(socket_b))
     }
 
-    fn new_raw(is_nonblocking: bool) -> Self {
+    fn new_raw(is_nonblocking: bool, net_ns: Arc<NetNamespace>) -> Self {
         Self {
             local_receiver: MessageReceiver::new(),
             remote_queue: RwLock::new(None),
             options: RwLock::new(OptionSet::new()),
             timeouts: SocketTimeouts::new(),
             peer_cred: None,
+            net_ns,
             is_write_shutdown: AtomicBool::new(false),
             common: new_socket_common(is_nonblocking),
         }
@@ -102,7 +113,7 @@ impl UnixDatagramSocket {
         }
 
         let queue = if let Some(remote_addr) = remote.as_ref() {
-            let connected_addr = remote_addr.connect()?;
+            let connected_addr = remote_addr.connect(&self.net_ns)?;
             MessageQueue::lookup_bound(&connected_addr)?
         } else {
             let remote_queue = self.remote_queue.read();
@@ -162,18 +173,22 @@ impl SocketPrivate for UnixDatagramSocket {
     fn is_nonblocking(&self) -> bool {
         self.common.is_nonblocking()
     }
+
+    fn net_ns(&self) -> &Arc<NetNamespace> {
+        &self.net_ns
+    }
 }
 
 impl Socket for UnixDatagramSocket {
     fn bind(&self, socket_addr: SocketAddr) -> Result<()> {
         let addr = UnixSocketAddr::try_from(socket_addr)?;
-        self.local_receiver.bind(addr)
+        self.local_receiver.bind(addr, &self.net_ns)
     }
 
     fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
         let remote_addr = UnixSocketAddr::try_from(socket_addr)?;
 
-        let connected_addr = remote_addr.connect()?;
+        let connected_addr = remote_addr.connect(&self.net_ns)?;
         let queue = MessageQueue::lookup_bound(&connected_addr)?;
 
         let mut remote_queue = self.remote_queue.write();
@@ -12,5 +12,6 @@ pub(crate) use cred::CUserCred;
 pub(super) use ctrl_msg::UnixControlMessage;
 pub(super) use datagram::UNIX_DATAGRAM_DEFAULT_BUF_SIZE;
 pub(crate) use datagram::UnixDatagramSocket;
+pub(crate) use ns::AbstractNameTable;
 pub(super) use stream::UNIX_STREAM_DEFAULT_BUF_SIZE;
 pub(crate) use stream::UnixStreamSocket;
@@ -1,44 +1,55 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use alloc::{collections::btree_map::Entry, format};
+use core::fmt;
 
-use keyable_arc::KeyableArc;
+use crate::{net::net_ns::NetNamespace, prelude::*};
 
-use crate::prelude::*;
-
-#[derive(Debug, Eq, Ord, PartialEq, PartialOrd)]
-pub(crate) struct AbstractHandle(KeyableArc<[u8]>);
+pub(crate) struct AbstractHandle {
+    name: Arc<[u8]>,
+    table: Arc<AbstractNameTable>,
+}
 
 impl AbstractHandle {
-    fn new(name: Arc<[u8]>) -> Self {
-        Self(KeyableArc::from(name))
+    fn new(name: Arc<[u8]>, table: Arc<AbstractNameTable>) -> Self {
+        Self { name, table }
     }
 
     pub(crate) fn name(&self) -> Arc<[u8]> {
-        self.0.clone().into()
+        self.name.clone()
+    }
+}
+
+impl fmt::Debug for AbstractHandle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("AbstractHandle")
+            .field("name", &self.name)
+            .finish_non_exhaustive()
     }
 }
 
 impl Drop for AbstractHandle {
     fn drop(&mut self) {
-        HANDLE_TABLE.remove(self.name());
+        self.table.remove(self.name());
     }
 }
 
-static HANDLE_TABLE: HandleTable = HandleTable::new();
-
-struct HandleTable {
+/// The table of the abstract names of UNIX domain sockets.
+///
+/// Each network namespace has its own table, so abstract names are only visible within the
+/// network namespace.
+pub(crate) struct AbstractNameTable {
     handles: RwLock<BTreeMap<Arc<[u8]>, Weak<AbstractHandle>>>,
 }
 
-impl HandleTable {
-    const fn new() -> Self {
-        Self {
+impl AbstractNameTable {
+    pub(crate) fn new() -> Arc<Self> {
+        Arc::new(Self {
             handles: RwLock::new(BTreeMap::new()),
-        }
+        })
     }
 
-    fn create(&self, name: Arc<[u8]>) -> Option<Arc<AbstractHandle>> {
+    fn create(self: &Arc<Self>, name: Arc<[u8]>) -> Option<Arc<AbstractHandle>> {
         let mut handles = self.handles.write();
 
         let mut entry = handles.entry(name.clone());
@@ -50,7 +61,7 @@ impl HandleTable {
             }
         }
 
-        let new_handle = Arc::new(AbstractHandle::new(name));
+        let new_handle = Arc::new(AbstractHandle::new(name, self.clone()));
         let weak_handle = Arc::downgrade(&new_handle);
 
         match entry {
@@ -72,8 +83,8 @@ impl HandleTable {
             return;
         };
 
-        // Due to race conditions between `AbstractHandle::drop` and `HandleTable::create`, the
-        // entry may be occupied by another handle.
+        // Due to race conditions between `AbstractHandle::drop` and `AbstractNameTable::create`,
+        // the entry may be occupied by another handle.
         //
         // Therefore, before removing the entry, we must check again if the entry should be removed.
         if occupied.get().strong_count() == 0 {
@@ -87,7 +98,7 @@ impl HandleTable {
         handles.get(name).and_then(Weak::upgrade)
     }
 
-    fn alloc_ephemeral(&self) -> Option<Arc<AbstractHandle>> {
+    fn alloc_ephemeral(self: &Arc<Self>) -> Option<Arc<AbstractHandle>> {
         // See "Autobind feature" in the man pages:
         // <https://man7.org/linux/man-pages/man7/unix.7.html>.
         //
@@ -103,20 +114,30 @@ impl HandleTable {
     }
 }
 
-pub(crate) fn create_abstract_name(name: Arc<[u8]>) -> Result<Arc<AbstractHandle>> {
-    HANDLE_TABLE.create(name).ok_or_else(|| {
+pub(crate) fn create_abstract_name(
+    net_ns: &NetNamespace,
+    name: Arc<[u8]>,
+) -> Result<Arc<AbstractHandle>> {
+    net_ns.unix_abstract_names().create(name).ok_or_else(|| {
         Error::with_message(Errno::EADDRINUSE, "the abstract name is already in use")
     })
 }
 
-pub(crate) fn alloc_ephemeral_abstract_name() -> Result<Arc<AbstractHandle>> {
-    HANDLE_TABLE.alloc_ephemeral().ok_or_else(|| {
-        Error::with_message(Errno::ENOSPC, "no ephemeral abstract name is available")
-    })
+pub(crate) fn alloc_ephemeral_abstract_name(net_ns: &NetNamespace) -> Result<Arc<AbstractHandle>> {
+    net_ns
+        .unix_abstract_names()
+        .alloc_ephemeral()
+        .ok_or_else(|| {
+            Error::with_message(Errno::ENOSPC, "no ephemeral abstract name is available")
+        })
 }
 
-pub(crate) fn lookup_abstract_name(name: &[u8]) -> Result<Arc<AbstractHandle>> {
-    HANDLE_TABLE
+pub(crate) fn lookup_abstract_name(
+    net_ns: &NetNamespace,
+    name: &[u8],
+) -> Result<Arc<AbstractHandle>> {
+    net_ns
+        .unix_abstract_names()
         .lookup(name)
         .ok_or_else(|| Error::with_message(Errno::ECONNREFUSED, "the abstract name does not exist"))
 }
@@ -1,7 +1,8 @@
 // SPDX-License-Identifier: MPL-2.0
 
 pub(super) use abs::{
-    AbstractHandle, alloc_ephemeral_abstract_name, create_abstract_name, lookup_abstract_name,
+    AbstractHandle, AbstractNameTable, alloc_ephemeral_abstract_name, create_abstract_name,
+    lookup_abstract_name,
 };
 pub(super) use path::{create_socket_file, lookup_socket_file};
 
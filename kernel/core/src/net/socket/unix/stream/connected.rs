@@ -10,11 +10,15 @@ use spin::Once;
 use crate::{
     events::IoEvents,
     fs::utils::{Endpoint, EndpointState},
-    net::socket::{
-        unix::{
-            UnixSocketAddr, addr::UnixSocketAddrBound, cred::SocketCred, ctrl_msg::AuxiliaryData,
+    net::{
+        net_ns::NetNamespace,
+        socket::{
+            unix::{
+                UnixSocketAddr, addr::UnixSocketAddrBound, cred::SocketCred,
+                ctrl_msg::AuxiliaryData,
+            },
+            util::{ControlMessage, RecvFlags, RecvOutput, SockShutdownCmd},
         },
-        util::{ControlMessage, RecvFlags, RecvOutput, SockShutdownCmd},
     },
     prelude::*,
     process::signal::Pollee,
@@ -98,12 +102,16 @@ impl Connected {
             .unwrap_or(UnixSocketAddr::Unnamed)
     }
 
-    pub(super) fn bind(&mut self, addr_to_bind: UnixSocketAddr) -> Result<()> {
+    pub(super) fn bind(
+        &mut self,
+        addr_to_bind: UnixSocketAddr,
+        net_ns: &NetNamespace,
+    ) -> Result<()> {
         if self.addr.is_some() {
             return addr_to_bind.bind_unnamed();
         }
 
-        let bound_addr = addr_to_bind.bind()?;
+        let bound_addr = addr_to_bind.bind(net_ns)?;
         self.inner
             .this_end()
             .addr
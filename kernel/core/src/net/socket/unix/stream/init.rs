@@ -12,12 +12,15 @@ use super::{
 use crate::{
     events::IoEvents,
     fs::utils::EndpointState,
-    net::socket::{
-        unix::{
-            addr::{UnixSocketAddr, UnixSocketAddrBound},
-            cred::SocketCred,
+    net::{
+        net_ns::NetNamespace,
+        socket::{
+            unix::{
+                addr::{UnixSocketAddr, UnixSocketAddrBound},
+                cred::SocketCred,
+            },
+            util::SockShutdownCmd,
         },
-        util::SockShutdownCmd,
     },
     prelude::*,
     process::signal::Pollee,
@@ -38,12 +41,16 @@ impl Init {
         }
     }
 
-    pub(super) fn bind(&mut self, addr_to_bind: UnixSocketAddr) -> Result<()> {
+    pub(super) fn bind(
+        &mut self,
+        addr_to_bind: UnixSocketAddr,
+        net_ns: &NetNamespace,
+    ) -> Result<()> {
         if self.addr.is_some() {
             return addr_to_bind.bind_unnamed();
         }
 
-        let bound_addr = addr_to_bind.bind()?;
+        let bound_addr = addr_to_bind.bind(net_ns)?;
         self.addr = Some(bound_addr);
 
         Ok(())
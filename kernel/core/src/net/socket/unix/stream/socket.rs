@@ -14,18 +14,21 @@ use crate::{
         file::{FileCommon, FileLike},
         utils::EndpointState,
     },
-    net::socket::{
-        Socket, new_socket_common,
-        options::{
-            Error as SocketError, PeerCred, PeerGroups, SocketOption, macros::sock_option_mut,
-        },
-        private::SocketPrivate,
-        unix::{CUserCred, UnixSocketAddr, cred::SocketCred, ctrl_msg::AuxiliaryData},
-        util::{
-            ControlMessage, MessageHeader, RecvFlags, RecvOutput, SendFlags, SockShutdownCmd,
-            SocketAddr,
+    net::{
+        net_ns::NetNamespace,
+        socket::{
+            Socket, new_socket_common,
             options::{
-                GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet, SocketTimeouts,
+                Error as SocketError, PeerCred, PeerGroups, SocketOption, macros::sock_option_mut,
+            },
+            private::SocketPrivate,
+            unix::{CUserCred, UnixSocketAddr, cred::SocketCred, ctrl_msg::AuxiliaryData},
+            util::{
+                ControlMessage, MessageHeader, RecvFlags, RecvOutput, SendFlags, SockShutdownCmd,
+                SocketAddr,
+                options::{
+                    GetSocketLevelOption, SetSocketLevelOption, SocketOptionSet, SocketTimeouts,
+                },
             },
         },
     },
@@ -44,6 +47,10 @@ pub(crate) struct UnixStreamSocket {
     timeouts: SocketTimeouts,
 
     socket_type: SockType,
+    /// The network namespace where the socket is created.
+    ///
+    /// Abstract names are bound and looked up in this network namespace.
+    net_ns: Arc<NetNamespace>,
     pollee: Pollee,
     common: FileCommon,
 }
@@ -166,26 +173,40 @@ impl OptionSet {
 }
 
 impl UnixStreamSocket {
-    pub(crate) fn new(is_nonblocking: bool, socket_type: SockType) -> Arc<Self> {
+    pub(crate) fn new(
+        is_nonblocking: bool,
+        socket_type: SockType,
+        net_ns: Arc<NetNamespace>,
+    ) -> Arc<Self> {
         debug_assert!(
             socket_type == SockType::SOCK_STREAM || socket_type == SockType::SOCK_SEQPACKET
         );
 
-        Self::new_init(Init::new(), is_nonblocking, socket_type)
+        Self::new_init(Init::new(), is_nonblocking, socket_type, net_ns)
     }
 
-    fn new_init(init: Init, is_nonblocking: bool, socket_type: SockType) -> Arc<Self> {
+    fn new_init(
+        init: Init,
+        is_nonblocking: bool,
+        socket_type: SockType,
+        net_ns: Arc<NetNamespace>,
+    ) -> Arc<Self> {
         Arc::new(Self {
             state: RwMutex::new(Takeable::new(State::Init(init))),
             options: RwLock::new(OptionSet::new()),
             timeouts: SocketTimeouts::new(),
             socket_type,
+            net_ns,
             pollee: Pollee::new(),
             common: new_socket_common(is_nonblocking),
         })
     }
 
-    pub(crate) fn new_pair(is_nonblocking: bool, socket_type: SockType) -> (Arc<Self>, Arc<Self>) {
+    pub(crate) fn new_pair(
+        is_nonblocking: bool,
+        socket_type: SockType,
+        net_ns: Arc<NetNamespace>,
+    ) -> (Arc<Self>, Arc<Self>) {
         debug_assert!(
             socket_type == SockType::SOCK_STREAM || socket_type == SockType::SOCK_SEQPACKET
         );
@@ -201,8 +222,20 @@ impl UnixStreamSocket {
             cred.restrict(),
         );
         (
-            Self::new_connected(conn_a, OptionSet::new(), is_nonblocking, socket_type),
-            Self::new_connected(conn_b, OptionSet::new(), is_nonblocking, socket_type),
+            Self::new_connected(
+                conn_a,
+                OptionSet::new(),
+                is_nonblocking,
+                socket_type,
+                net_ns.clone(),
+            ),
+            Self::new_connected(
+                conn_b,
+                OptionSet::new(),
+                is_nonblocking,
+                socket_type,
+                net_ns,
+            ),
         )
     }
 
@@ -211,6 +244,7 @@ impl UnixStreamSocket {
         options: OptionSet,
         is_nonblocking: bool,
         socket_type: SockType,
+        net_ns: Arc<NetNamespace>,
     ) -> Arc<Self> {
         let cloned_pollee = connected.cloned_pollee();
         Arc::new(Self {
@@ -218,6 +252,7 @@ impl UnixStreamSocket {
             options: RwLock::new(options),
             timeouts: SocketTimeouts::new(),
             socket_type,
+            net_ns,
             pollee: cloned_pollee,
             common: new_socket_common(is_nonblocking),
         })
@@ -292,7 +327,9 @@ impl UnixStreamSocket {
 
     fn try_accept(&self, is_nonblocking: bool) -> Result<(Arc<dyn FileLike>, SocketAddr)> {
         match self.state.read().as_ref() {
-            State::Listen(listen) => listen.try_accept(self.socket_type, is_nonblocking) as _,
+            State::Listen(listen) => {
+                listen.try_accept(self.socket_type, is_nonblocking, &self.net_ns) as _
+            }
             State::Init(_) | State::Connected(_) => {
                 return_errno_with_message!(Errno::EINVAL, "the socket is not listening")
             }
@@ -319,6 +356,10 @@ impl SocketPrivate for UnixStreamSocket {
     fn is_nonblocking(&self) -> bool {
         self.common.is_nonblocking()
     }
+
+    fn net_ns(&self) -> &Arc<NetNamespace> {
+        &self.net_ns
+    }
 }
 
 impl Socket for UnixStreamSocket {
@@ -326,8 +367,8 @@ impl Socket for UnixStreamSocket {
         let addr = UnixSocketAddr::try_from(socket_addr)?;
 
         match self.state.write().as_mut() {
-            State::Init(init) => init.bind(addr),
-            State::Connected(connected) => connected.bind(addr),
+            State::Init(init) => init.bind(addr, &self.net_ns),
+            State::Connected(connected) => connected.bind(addr, &self.net_ns),
             State::Listen(_) => {
                 // Listening sockets are always already bound.
                 addr.bind_unnamed()
@@ -336,7 +377,7 @@ impl Socket for UnixStreamSocket {
     }
 
     fn connect(&self, socket_addr: SocketAddr) -> Result<()> {
-        let remote_addr = UnixSocketAddr::try_from(socket_addr)?.connect()?;
+        let remote_addr = UnixSocketAddr::try_from(socket_addr)?.connect(&self.net_ns)?;
         let backlog = get_backlog(&remote_addr)?;
 
         if self.is_nonblocking() {
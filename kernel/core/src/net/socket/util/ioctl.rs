@@ -8,7 +8,8 @@ use ostd::task::Task;
 
 use crate::{
     net::{
-        iface::{DEFAULT_TX_QUEUE_LEN, Iface, iter_all_ifaces},
+        iface::{DEFAULT_TX_QUEUE_LEN, Iface},
+        net_ns::NetNamespace,
         socket::Socket,
     },
     prelude::*,
@@ -34,8 +35,10 @@ mod ioctl_defs {
 }
 
 pub(crate) fn socket_ioctl<T: Socket>(socket: &T, raw_ioctl: RawIoctl) -> Result<i32> {
+    let net_ns = socket.net_ns();
+
     // Linux always handles `SIOCGIFCONF` first.
-    match handle_get_ifconf(raw_ioctl) {
+    match handle_get_ifconf(net_ns, raw_ioctl) {
         Err(err) if err.error() == Errno::ENOTTY => (),
         res => return res,
     }
@@ -47,16 +50,16 @@ pub(crate) fn socket_ioctl<T: Socket>(socket: &T, raw_ioctl: RawIoctl) -> Result
     }
 
     // Handle network device commands.
-    network_device_ioctl(raw_ioctl)
+    network_device_ioctl(net_ns, raw_ioctl)
 }
 
-fn handle_get_ifconf(raw_ioctl: RawIoctl) -> Result<i32> {
+fn handle_get_ifconf(net_ns: &NetNamespace, raw_ioctl: RawIoctl) -> Result<i32> {
     use ioctl_defs::*;
 
     dispatch_ioctl!(match raw_ioctl {
         cmd @ GetIfConf => {
             let mut ifconf = cmd.read()?;
-            ifconf.write_ifreqs()?;
+            ifconf.write_ifreqs(net_ns)?;
             cmd.write(&ifconf)?;
             Ok(0)
         }
@@ -64,27 +67,27 @@ fn handle_get_ifconf(raw_ioctl: RawIoctl) -> Result<i32> {
     })
 }
 
-fn network_device_ioctl(raw_ioctl: RawIoctl) -> Result<i32> {
+fn network_device_ioctl(net_ns: &NetNamespace, raw_ioctl: RawIoctl) -> Result<i32> {
     use ioctl_defs::*;
 
     dispatch_ioctl!(match raw_ioctl {
         cmd @ GetIfName => {
             let mut ifreq = cmd.read()?;
-            let iface = ifreq.get_iface_by_index()?;
+            let iface = ifreq.get_iface_by_index(net_ns)?;
             ifreq.name = *iface.name();
             cmd.write(&ifreq)?;
             Ok(0)
         }
         cmd @ GetIfFlags => {
             let mut ifreq = cmd.read()?;
-            let iface = ifreq.get_iface_by_name()?;
+            let iface = ifreq.get_iface_by_name(net_ns)?;
             ifreq.data = CIfReqData::new_flags(iface.flags().bits() as i16);
             cmd.write(&ifreq)?;
             Ok(0)
         }
         cmd @ GetIfMetric => {
             let mut ifreq = cmd.read()?;
-            ifreq.get_iface_by_name()?;
+            ifreq.get_iface_by_name(net_ns)?;
             // Linux's per-interface metric is currently unused and always reported as zero.
             ifreq.data = CIfReqData::new_value(0);
             cmd.write(&ifreq)?;
@@ -92,14 +95,14 @@ fn network_device_ioctl(raw_ioctl: RawIoctl) -> Result<i32> {
         }
         cmd @ GetIfMtu => {
             let mut ifreq = cmd.read()?;
-            let iface = ifreq.get_iface_by_name()?;
+            let iface = ifreq.get_iface_by_name(net_ns)?;
             ifreq.data = CIfReqData::new_mtu(iface.mtu() as i32);
             cmd.write(&ifreq)?;
             Ok(0)
         }
         cmd @ GetIfHwAddr => {
             let mut ifreq = cmd.read()?;
-            let iface = ifreq.get_iface_by_name()?;
+            let iface = ifreq.get_iface_by_name(net_ns)?;
             let socket_addr = CSocketAddr::new_ethernet(iface.type_(), iface.ethernet_addr());
             ifreq.data = CIfReqData::new_hardware_addr(socket_addr);
             cmd.write(&ifreq)?;
@@ -107,7 +110,7 @@ fn network_device_ioctl(raw_ioctl: RawIoctl) -> Result<i32> {
         }
         cmd @ GetIfIndex => {
             let mut ifreq = cmd.read()?;
-            let iface = ifreq.get_iface_by_name()?;
+            let iface = ifreq.get_iface_by_name(net_ns)?;
             let index = iface.index();
             ifreq.data = CIfReqData::new_value(index.cast_signed());
             cmd.write(&ifreq)?;
@@ -115,7 +118,7 @@ fn network_device_ioctl(raw_ioctl: RawIoctl) -> Result<i32> {
         }
         cmd @ GetIfTxQueueLen => {
             let mut ifreq = cmd.read()?;
-            ifreq.get_iface_by_name()?;
+            ifreq.get_iface_by_name(net_ns)?;
             ifreq.data = CIfReqData::new_value(DEFAULT_TX_QUEUE_LEN.cast_signed());
             cmd.write(&ifreq)?;
             Ok(0)
@@ -136,8 +139,9 @@ struct CIfConf {
 }
 
 impl CIfConf {
-    fn write_ifreqs(&mut self) -> Result<()> {
-        let ifreqs = iter_all_ifaces().filter_map(|iface| {
+    fn write_ifreqs(&mut self, net_ns: &NetNamespace) -> Result<()> {
+        let ifaces = net_ns.ifaces();
+        let ifreqs = ifaces.iter().filter_map(|iface| {
             let address = {
                 let ipv4_addr = iface.ipv4_cidr()?.address();
                 CSocketAddr::new_ipv4(ipv4_addr)
@@ -267,16 +271,19 @@ impl CIfReq {
         self.data = CIfReqData::new_addr(socket_addr);
     }
 
-    pub(in crate::net::socket) fn get_iface_by_name(&mut self) -> Result<&'static Arc<Iface>> {
-        iter_all_ifaces()
-            .find(|iface| iface.name() == &self.name)
+    pub(in crate::net::socket) fn get_iface_by_name(
+        &mut self,
+        net_ns: &NetNamespace,
+    ) -> Result<Arc<Iface>> {
+        net_ns
+            .find_iface(|iface| iface.name() == &self.name)
             .ok_or_else(|| Error::with_message(Errno::ENODEV, "no interface found"))
     }
 
-    fn get_iface_by_index(&self) -> Result<&'static Arc<Iface>> {
+    fn get_iface_by_index(&self, net_ns: &NetNamespace) -> Result<Arc<Iface>> {
         let index = *self.data.value();
-        iter_all_ifaces()
-            .find(|iface| iface.index().cast_signed() == index)
+        net_ns
+            .find_iface(|iface| iface.index().cast_signed() == index)
             .ok_or_else(|| Error::with_message(Errno::ENODEV, "no interface found"))
     }
 }
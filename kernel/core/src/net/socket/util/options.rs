@@ -7,7 +7,8 @@ use core::{
 };
 
 use aster_bigtcp::socket::{
-    NeedIfacePoll, TCP_RECV_BUF_LEN, TCP_SEND_BUF_LEN, UDP_RECV_PAYLOAD_LEN, UDP_SEND_PAYLOAD_LEN,
+    NeedIfacePoll, RAW_RECV_BUF_LEN, RAW_SEND_BUF_LEN, TCP_RECV_BUF_LEN, TCP_SEND_BUF_LEN,
+    UDP_RECV_PAYLOAD_LEN, UDP_SEND_PAYLOAD_LEN,
 };
 
 use super::{LingerOption, SocketTimeout};
@@ -20,6 +21,7 @@ use crate::{
             SendTimeout, SocketOption, SocketType,
             macros::{sock_option_mut, sock_option_ref},
         },
+        packet::PACKET_DEFAULT_BUF_SIZE,
         unix::{CUserCred, UNIX_DATAGRAM_DEFAULT_BUF_SIZE, UNIX_STREAM_DEFAULT_BUF_SIZE},
     },
     prelude::*,
@@ -78,6 +80,15 @@ impl SocketOptionSet {
         }
     }
 
+    /// Returns the default socket level options for raw socket.
+    pub(in crate::net) fn new_raw() -> Self {
+        Self {
+            send_buf: RAW_SEND_BUF_LEN as u32,
+            recv_buf: RAW_RECV_BUF_LEN as u32,
+            ..Default::default()
+        }
+    }
+
     /// Returns the default socket level options for unix stream socket.
     pub(in crate::net) fn new_unix_stream() -> Self {
         Self {
@@ -105,6 +116,15 @@ impl SocketOptionSet {
         }
     }
 
+    /// Returns the default socket level options for packet socket.
+    pub(in crate::net) fn new_packet() -> Self {
+        Self {
+            send_buf: PACKET_DEFAULT_BUF_SIZE as u32,
+            recv_buf: PACKET_DEFAULT_BUF_SIZE as u32,
+            ..Default::default()
+        }
+    }
+
     /// Gets socket-level options.
     ///
     /// Note that the socket error has to be handled separately. This method does not handle it
@@ -14,12 +14,15 @@ use takeable::Takeable;
 use crate::{
     events::IoEvents,
     fs::file::{FileCommon, FileLike},
-    net::socket::{
-        Socket, new_socket_common,
-        options::{Error as SocketError, SocketOption, macros::sock_option_mut},
-        private::SocketPrivate,
-        util::{MessageHeader, RecvFlags, RecvOutput, SendFlags, SockShutdownCmd, SocketAddr},
-        vsock::addr::{UNSPECIFIED_VSOCK_ADDR, VsockSocketAddr},
+    net::{
+        net_ns::NetNamespace,
+        socket::{
+            Socket, new_socket_common,
+            options::{Error as SocketError, SocketOption, macros::sock_option_mut},
+            private::SocketPrivate,
+            util::{MessageHeader, RecvFlags, RecvOutput, SendFlags, SockShutdownCmd, SocketAddr},
+            vsock::addr::{UNSPECIFIED_VSOCK_ADDR, VsockSocketAddr},
+        },
     },
     prelude::*,
     process::signal::{PollHandle, Pollable, Pollee},
@@ -28,6 +31,8 @@ use crate::{
 
 pub(crate) struct VsockStreamSocket {
     state: Mutex<Takeable<State>>,
+    /// The network namespace in which the socket was created.
+    net_ns: Arc<NetNamespace>,
     // Note that for vsock, all pollee notifications and invalidations live in the transport module
     // (e.g., `super::transport`) rather than in this module.
     pollee: Pollee,
@@ -42,9 +47,10 @@ enum State {
 }
 
 impl VsockStreamSocket {
-    pub(crate) fn new(is_nonblocking: bool) -> Result<Arc<Self>> {
+    pub(crate) fn new(is_nonblocking: bool, net_ns: Arc<NetNamespace>) -> Result<Arc<Self>> {
         Ok(Arc::new(Self {
             state: Mutex::new(Takeable::new(State::Init(InitStream::new()))),
+            net_ns,
             pollee: Pollee::new(),
             common: new_socket_common(is_nonblocking),
         }))
@@ -145,6 +151,7 @@ impl VsockStreamSocket {
 
         let accepted = Arc::new(Self {
             state: Mutex::new(Takeable::new(State::Connected(connected))),
+            net_ns: self.net_ns.clone(),
             pollee,
             common: new_socket_common(is_nonblocking),
         });
@@ -234,6 +241,10 @@ impl SocketPrivate for VsockStreamSocket {
     fn is_nonblocking(&self) -> bool {
         self.common.is_nonblocking()
     }
+
+    fn net_ns(&self) -> &Arc<NetNamespace> {
+        &self.net_ns
+    }
 }
 
 impl Socket for VsockStreamSocket {
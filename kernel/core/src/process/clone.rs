@@ -20,15 +20,15 @@ use crate::{
     context::current_userspace,
     cpu::LinuxAbi,
     fs::{
-        cgroupfs::{CgroupMembership, CgroupSysNode},
+        cgroupfs::{CgroupMembership, CgroupSysNode, task_groups_of},
         file::file_table::{FdFlags, FileTable, RawFileDesc},
         thread_info::ThreadFsInfo,
     },
     prelude::*,
     process::{
-        NsProxy, UserNamespace,
+        NsProxy, PidNamespace, UserNamespace,
         pid_file::PidFile,
-        posix_thread::{PosixThread, ThreadLocal, allocate_posix_tid},
+        posix_thread::{PosixThread, ThreadLocal},
         stats::PROCESS_CREATION_COUNTER,
     },
     sched::Nice,
@@ -203,7 +203,7 @@ impl CloneArgs {
                 );
             }
 
-            if ctx.process.is_init_process() {
+            if ctx.process.is_init_process() || ctx.process.pid_ns().is_child_reaper(ctx.process) {
                 return_errno_with_message!(
                     Errno::EINVAL,
                     "`CLONE_PARENT` cannot be used if the process is the init process"
@@ -220,10 +220,40 @@ impl CloneArgs {
                 );
             }
 
-            if clone_flags.intersects(CloneFlags::CLONE_PIDFD | CloneFlags::CLONE_NEWUSER) {
+            if clone_flags.intersects(
+                CloneFlags::CLONE_PIDFD | CloneFlags::CLONE_NEWUSER | CloneFlags::CLONE_NEWPID,
+            ) {
                 return_errno_with_message!(
                     Errno::EINVAL,
-                    "`CLONE_THREAD` cannot be used together with `CLONE_PIDFD` or `CLONE_NEWUSER`"
+                    "`CLONE_THREAD` cannot be used together with `CLONE_PIDFD`, `CLONE_NEWUSER`, or `CLONE_NEWPID`"
+                );
+            }
+
+            // The new thread must be in the same PID namespace as the process.
+            let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+            if !Arc::ptr_eq(
+                ns_proxy.unwrap().pid_ns_for_children(),
+                ctx.process.pid_ns(),
+            ) {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "`CLONE_THREAD` cannot be used after the PID namespace for children is changed"
+                );
+            }
+        }
+
+        // Reject invalid argument combinations related to time namespaces. The new thread or
+        // process that shares the VM must be in the same time namespace, because the vDSO
+        // depends on the time namespace.
+        if clone_flags.intersects(CloneFlags::CLONE_VM | CloneFlags::CLONE_THREAD) {
+            let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+            let ns_proxy = ns_proxy.unwrap();
+            if clone_flags.contains(CloneFlags::CLONE_NEWTIME)
+                || !Arc::ptr_eq(ns_proxy.time_ns(), ns_proxy.time_ns_for_children())
+            {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "`CLONE_VM` and `CLONE_THREAD` cannot be used if the child enters a new time namespace"
                 );
             }
         }
@@ -276,6 +306,8 @@ impl CloneFlags {
             | CloneFlags::CLONE_VFORK
             | CloneFlags::CLONE_NEWCGROUP
             | CloneFlags::CLONE_NEWNS
+            | CloneFlags::CLONE_NEWPID
+            | CloneFlags::CLONE_NEWTIME
             | CloneFlags::CLONE_NEWUTS
             | CloneFlags::CLONE_PARENT;
         let unsupported_flags = *self - supported_flags;
@@ -300,9 +332,22 @@ pub(crate) fn clone_child(
     if clone_args.flags.contains(CloneFlags::CLONE_THREAD) {
         let child_task = clone_child_task(ctx, parent_context, clone_args)?;
         let child_thread = child_task.as_thread().unwrap();
-        child_thread.run();
 
+        // Hold the read lock so that the task groups cannot be changed by a
+        // concurrent migration between the lookup and the update.
+        {
+            let _cgroup_read_guard = CgroupMembership::read_lock();
+            child_thread
+                .sched_attr()
+                .set_task_groups(task_groups_of(child_thread));
+        }
+
+        // Translate the TID before the child runs, since the child may exit at any time after.
         let child_tid = child_thread.as_posix_thread().unwrap().tid();
+        let child_tid = ctx.process.pid_ns().ns_id_of(child_tid).unwrap();
+
+        child_thread.run();
+
         Ok(child_tid)
     } else {
         // Hold the read lock before charge to ensure the cgroup of current process
@@ -345,6 +390,10 @@ pub(crate) fn clone_child(
             child_process.status().set_vfork_child(true);
         }
 
+        // Translate the PID before the child runs, since the child may exit at any time after.
+        // The child is always visible in the PID namespace of the parent.
+        let child_pid = ctx.process.pid_ns().ns_id_of(child_process.pid()).unwrap();
+
         child_process.run();
 
         PROCESS_CREATION_COUNTER
@@ -359,7 +408,6 @@ pub(crate) fn clone_child(
             current.children_wait_queue().wait_until(cond);
         }
 
-        let child_pid = child_process.pid();
         Ok(child_pid)
     }
 }
@@ -406,7 +454,19 @@ fn clone_child_task(
         process,
         posix_thread,
         clone_flags,
-    )?;
+    )?
+    .enter_time_ns_for_children()?;
+
+    // Switch the vDSO if the child process enters a new time namespace.
+    #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+    if !Arc::ptr_eq(
+        child_ns_proxy.time_ns(),
+        thread_local.borrow_ns_proxy().unwrap().time_ns(),
+    ) {
+        child_vmar
+            .process_vm()
+            .switch_vdso(&child_vmar, child_ns_proxy.time_ns())?;
+    }
 
     // Clone default timer slack
     let default_timer_slack_ns = posix_thread.timer_slack_ns();
@@ -436,7 +496,7 @@ fn clone_child_task(
     // Inherit the thread name.
     let thread_name = *posix_thread.thread_name().lock();
 
-    let child_tid = allocate_posix_tid();
+    let child_tid = process.pid_ns().allocate_id()?;
     let child_task = {
         let credentials = {
             let credentials = ctx.posix_thread.credentials();
@@ -464,23 +524,33 @@ fn clone_child_task(
         }
 
         // Deal with SETTID/CLEARTID flags
-        clone_parent_settid(child_tid, clone_args.parent_tid, clone_flags)?;
+        clone_parent_settid(
+            process.pid_ns(),
+            child_tid,
+            clone_args.parent_tid,
+            clone_flags,
+        )?;
         thread_builder = clone_child_cleartid(thread_builder, clone_args.child_tid, clone_flags);
         thread_builder = clone_child_settid(thread_builder, clone_args.child_tid, clone_flags);
 
         thread_builder.build()
     };
 
-    process
-        .tasks()
-        .lock()
-        .insert(child_task.clone())
-        .map_err(|_| {
-            Error::with_message(
-                Errno::EINTR,
-                "the process has exited or has already executed a new program",
-            )
-        })?;
+    let child_posix_thread = child_task.as_posix_thread().unwrap();
+    child_posix_thread.lsm().inherit_from(posix_thread.lsm());
+
+    let mut tasks = process.tasks().lock();
+    // Inherit the seccomp state while holding the lock so that no synchronized filters are missed.
+    child_posix_thread
+        .seccomp()
+        .inherit_from(posix_thread.seccomp());
+    tasks.insert(child_task.clone()).map_err(|_| {
+        Error::with_message(
+            Errno::EINTR,
+            "the process has exited or has already executed a new program",
+        )
+    })?;
+    drop(tasks);
 
     let child_thread = child_task.as_thread().unwrap();
     pid_table::pid_table_mut().insert_thread(child_tid, child_thread);
@@ -565,7 +635,8 @@ fn clone_child_process(
     // Inherit the parent's OOM score adjustment
     let child_oom_score_adj = process.oom_score_adj().load(Ordering::Relaxed);
 
-    let child_tid = allocate_posix_tid();
+    let child_pid_ns = child_ns_proxy.pid_ns_for_children().clone();
+    let child_tid = child_pid_ns.allocate_id()?;
 
     let child = {
         let child_vmar_arc = child_vmar.clone_arc();
@@ -602,7 +673,12 @@ fn clone_child_process(
         }
 
         // Deal with SETTID/CLEARTID flags
-        clone_parent_settid(child_tid, clone_args.parent_tid, clone_flags)?;
+        clone_parent_settid(
+            process.pid_ns(),
+            child_tid,
+            clone_args.parent_tid,
+            clone_flags,
+        )?;
         child_thread_builder =
             clone_child_cleartid(child_thread_builder, clone_args.child_tid, clone_flags);
         child_thread_builder =
@@ -616,10 +692,21 @@ fn clone_child_process(
             child_oom_score_adj,
             child_sig_dispositions,
             child_user_ns,
+            child_pid_ns,
             child_thread_builder,
         )
     };
 
+    // Inherit the parent's seccomp and LSM states
+    let child_main_thread = child.main_thread();
+    let child_posix_thread = child_main_thread.as_posix_thread().unwrap();
+    child_posix_thread
+        .seccomp()
+        .inherit_from(ctx.posix_thread.seccomp());
+    child_posix_thread
+        .lsm()
+        .inherit_from(ctx.posix_thread.lsm());
+
     clone_pidfd(ctx, &child, clone_flags, clone_args.pidfd)?;
 
     if let Some(sig) = clone_args.exit_signal {
@@ -657,6 +744,7 @@ fn clone_child_settid(
 }
 
 fn clone_parent_settid(
+    parent_pid_ns: &PidNamespace,
     child_tid: Tid,
     parent_tidptr: Option<Vaddr>,
     clone_flags: CloneFlags,
@@ -664,6 +752,8 @@ fn clone_parent_settid(
     if let Some(addr) =
         parent_tidptr.filter(|_| clone_flags.contains(CloneFlags::CLONE_PARENT_SETTID))
     {
+        // The child is always visible in the PID namespace of the parent.
+        let child_tid = parent_pid_ns.ns_id_of(child_tid).unwrap();
         current_userspace!().write_val(addr, &child_tid)?;
     }
     Ok(())
@@ -839,6 +929,7 @@ fn create_child_process(
     oom_score_adj: i16,
     sig_dispositions: Arc<Mutex<SigDispositions>>,
     user_ns: Arc<UserNamespace>,
+    pid_ns: Arc<PidNamespace>,
     thread_builder: PosixThreadBuilder,
 ) -> Arc<Process> {
     let child_proc = Process::new(
@@ -849,6 +940,7 @@ fn create_child_process(
         oom_score_adj,
         sig_dispositions,
         user_ns,
+        pid_ns,
     );
 
     let child_task = thread_builder.process(Arc::downgrade(&child_proc)).build();
@@ -857,7 +949,11 @@ fn create_child_process(
     child_proc
 }
 
-fn set_parent_and_group(clone_flags: CloneFlags, parent: &Arc<Process>, child: &Arc<Process>) {
+pub(super) fn set_parent_and_group(
+    clone_flags: CloneFlags,
+    parent: &Arc<Process>,
+    child: &Arc<Process>,
+) {
     loop {
         let real_parent = clone_parent(clone_flags, parent);
 
@@ -0,0 +1,530 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The ELF format of core dumps.
+//!
+//! A core dump consists of an ELF header, program headers, notes, and memory contents:
+//! - The `PT_NOTE` segment describes the process and its threads (registers, signals, etc.).
+//! - Each `PT_LOAD` segment describes a mapping, whose dumped contents follow page-aligned.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/binfmt_elf.c>
+
+use core::{ops::Range, sync::atomic::Ordering};
+
+use ostd::mm::VmIo;
+
+use super::{CoreDumpFilter, ThreadState, output::CoreDumpWriter};
+use crate::{
+    arch::coredump::{ELF_MACHINE, ElfGregset},
+    prelude::*,
+    process::signal::{HandlePendingSignal, c_types::siginfo_t, sig_num::SigNum},
+    time::timeval_t,
+    vm::{
+        perms::VmPerms,
+        vmar::{VMAR_CAP_ADDR, VMAR_LOWEST_ADDR, VmMapping, Vmar},
+    },
+};
+
+/// Writes the core dump of the current process in the ELF format.
+pub(super) fn write_elf_core(
+    writer: &mut CoreDumpWriter,
+    sig_num: SigNum,
+    siginfo: &siginfo_t,
+    threads: &[ThreadState],
+    ctx: &Context,
+) -> Result<()> {
+    let user_space = ctx.user_space();
+    let vmar = user_space.vmar();
+
+    let segments = collect_segments(vmar, ctx);
+    let notes = build_notes(sig_num, siginfo, threads, &segments, ctx)?;
+
+    // One program header for the notes, and one for each mapping.
+    let nr_phdrs = segments.len() + 1;
+    if nr_phdrs >= PN_XNUM as usize {
+        return_errno_with_message!(Errno::EFBIG, "there are too many mappings to dump");
+    }
+
+    let notes_offset = size_of::<Elf64Ehdr>() + size_of::<Elf64Phdr>() * nr_phdrs;
+    let data_offset = (notes_offset + notes.len()).next_multiple_of(PAGE_SIZE);
+
+    writer.write(Elf64Ehdr::new_core(nr_phdrs as u16).as_bytes())?;
+
+    let notes_phdr = Elf64Phdr {
+        p_type: PT_NOTE,
+        p_offset: notes_offset as u64,
+        p_filesz: notes.len() as u64,
+        p_align: 4,
+        ..Elf64Phdr::new_zeroed()
+    };
+    writer.write(notes_phdr.as_bytes())?;
+
+    let mut offset = data_offset;
+    for segment in segments.iter() {
+        let load_phdr = Elf64Phdr {
+            p_type: PT_LOAD,
+            p_flags: segment_flags(segment.perms),
+            p_offset: offset as u64,
+            p_vaddr: segment.range.start as u64,
+            p_paddr: 0,
+            p_filesz: segment.dump_size as u64,
+            p_memsz: segment.range.len() as u64,
+            p_align: PAGE_SIZE as u64,
+        };
+        writer.write(load_phdr.as_bytes())?;
+        offset += segment.dump_size;
+    }
+
+    writer.write(&notes)?;
+    writer.skip_to_align(PAGE_SIZE);
+    debug_assert_eq!(writer.pos(), data_offset);
+
+    let mut page = vec![0u8; PAGE_SIZE];
+    for segment in segments.iter() {
+        let dump_range = segment.range.start..segment.range.start + segment.dump_size;
+        for vaddr in dump_range.step_by(PAGE_SIZE) {
+            if ctx.has_pending_sigkill() {
+                return_errno_with_message!(Errno::EINTR, "the core dump is interrupted");
+            }
+
+            // Pages that read as zeros are skipped, which leaves holes in the core file.
+            let Some(frame) = vmar.get_dump_page(vaddr) else {
+                writer.skip(PAGE_SIZE);
+                continue;
+            };
+            frame.read_bytes(0, &mut page)?;
+            writer.write(&page)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// A mapping that is described by a `PT_LOAD` segment.
+struct Segment {
+    range: Range<Vaddr>,
+    perms: VmPerms,
+    /// The number of bytes at the start of the mapping that are dumped.
+    dump_size: usize,
+    /// The absolute path and the page offset of the file that backs the mapping.
+    file: Option<(String, usize)>,
+}
+
+/// How much of a mapping is dumped.
+enum DumpPolicy {
+    Nothing,
+    Whole,
+    /// Only the first page is dumped if it contains an ELF header.
+    ElfHeader,
+}
+
+fn collect_segments(vmar: &Vmar, ctx: &Context) -> Vec<Segment> {
+    let filter = vmar.process_vm().coredump_filter();
+
+    let mut segments = Vec::new();
+    let mut policies = Vec::new();
+    {
+        let fs_ref = ctx.thread_local.borrow_fs();
+        let path_resolver = fs_ref.resolver().read();
+
+        let query_guard = vmar.query(VMAR_LOWEST_ADDR..VMAR_CAP_ADDR);
+        for vm_mapping in query_guard.iter() {
+            let file = vm_mapping.file().map(|file| {
+                let path = path_resolver.make_abs_path(file.path()).into_string();
+                let page_offset = vm_mapping.vmo_offset().unwrap_or(0) / PAGE_SIZE;
+                (path, page_offset)
+            });
+
+            segments.push(Segment {
+                range: vm_mapping.map_to_addr()..vm_mapping.map_end(),
+                perms: vm_mapping.perms(),
+                dump_size: 0,
+                file,
+            });
+            policies.push(dump_policy(vm_mapping, filter));
+        }
+    }
+
+    // User memory is read after releasing the lock, since reading it may cause page faults.
+    let user_space = ctx.user_space();
+    for (segment, policy) in segments.iter_mut().zip(policies) {
+        segment.dump_size = match policy {
+            DumpPolicy::Nothing => 0,
+            DumpPolicy::Whole => segment.range.len(),
+            DumpPolicy::ElfHeader => {
+                let is_elf = user_space
+                    .read_val::<[u8; 4]>(segment.range.start)
+                    .is_ok_and(|magic| magic == ELF_MAGIC);
+                if is_elf { PAGE_SIZE } else { 0 }
+            }
+        };
+    }
+
+    segments
+}
+
+/// Decides how much of a mapping is dumped according to the core dump filter.
+///
+/// This corresponds to `vma_dump_size` in Linux.
+fn dump_policy(vm_mapping: &VmMapping, filter: CoreDumpFilter) -> DumpPolicy {
+    if vm_mapping.is_dont_dump() {
+        return DumpPolicy::Nothing;
+    }
+
+    let is_included = |flag| {
+        if filter.contains(flag) {
+            DumpPolicy::Whole
+        } else {
+            DumpPolicy::Nothing
+        }
+    };
+
+    if vm_mapping.is_shared() {
+        // Shared anonymous mappings and shared mappings of unlinked files are treated as
+        // anonymous.
+        let is_anon = vm_mapping.inode().is_none_or(|inode| {
+            inode
+                .metadata()
+                .is_ok_and(|metadata| metadata.nr_hard_links == 0)
+        });
+        return if is_anon {
+            is_included(CoreDumpFilter::ANON_SHARED)
+        } else {
+            is_included(CoreDumpFilter::MAPPED_SHARED)
+        };
+    }
+
+    if vm_mapping.file().is_none() {
+        return is_included(CoreDumpFilter::ANON_PRIVATE);
+    }
+
+    // Writable private file mappings may contain anonymous (i.e., copied-on-write) pages.
+    if vm_mapping.perms().contains(VmPerms::WRITE) && filter.contains(CoreDumpFilter::ANON_PRIVATE)
+    {
+        return DumpPolicy::Whole;
+    }
+
+    if filter.contains(CoreDumpFilter::MAPPED_PRIVATE) {
+        return DumpPolicy::Whole;
+    }
+
+    // Dumping ELF headers helps to identify the build IDs of the mapped binaries.
+    if filter.contains(CoreDumpFilter::ELF_HEADERS)
+        && vm_mapping.vmo_offset() == Some(0)
+        && vm_mapping.perms().contains(VmPerms::READ)
+    {
+        return DumpPolicy::ElfHeader;
+    }
+
+    DumpPolicy::Nothing
+}
+
+fn segment_flags(perms: VmPerms) -> u32 {
+    let mut flags = 0;
+    if perms.contains(VmPerms::READ) {
+        flags |= PF_R;
+    }
+    if perms.contains(VmPerms::WRITE) {
+        flags |= PF_W;
+    }
+    if perms.contains(VmPerms::EXEC) {
+        flags |= PF_X;
+    }
+    flags
+}
+
+fn build_notes(
+    sig_num: SigNum,
+    siginfo: &siginfo_t,
+    threads: &[ThreadState],
+    segments: &[Segment],
+    ctx: &Context,
+) -> Result<Vec<u8>> {
+    let mut notes = Vec::new();
+
+    // Like Linux, the process-wide notes follow the `NT_PRSTATUS` note of the dumping thread, and
+    // the `NT_PRFPREG` note of each thread follows its `NT_PRSTATUS` note.
+    for (i, thread) in threads.iter().enumerate() {
+        push_note(
+            &mut notes,
+            NT_PRSTATUS,
+            prstatus(thread, sig_num, ctx).as_bytes(),
+        );
+
+        if i == 0 {
+            push_note(&mut notes, NT_PRPSINFO, prpsinfo(ctx)?.as_bytes());
+            push_note(&mut notes, NT_SIGINFO, siginfo.as_bytes());
+            push_note(&mut notes, NT_AUXV, &auxv(ctx)?);
+            push_note(&mut notes, NT_FILE, &file_note(segments));
+        }
+
+        if let Some(fpregs) = thread.fpregs.as_ref() {
+            push_note(&mut notes, NT_PRFPREG, fpregs);
+        }
+    }
+
+    Ok(notes)
+}
+
+fn push_note(notes: &mut Vec<u8>, note_type: u32, desc: &[u8]) {
+    let header = Elf64Nhdr {
+        n_namesz: NOTE_NAME.len() as u32,
+        n_descsz: desc.len() as u32,
+        n_type: note_type,
+    };
+    notes.extend_from_slice(header.as_bytes());
+
+    notes.extend_from_slice(NOTE_NAME);
+    notes.resize(notes.len().next_multiple_of(4), 0);
+
+    notes.extend_from_slice(desc);
+    notes.resize(notes.len().next_multiple_of(4), 0);
+}
+
+fn prstatus(thread: &ThreadState, sig_num: SigNum, ctx: &Context) -> ElfPrstatus {
+    let process = ctx.process.as_ref();
+    let pid_ns = process.pid_ns();
+    let (children_user_time, children_kernel_time) = process.reaped_children_stats().lock().get();
+
+    ElfPrstatus {
+        info: ElfSiginfo {
+            si_signo: sig_num.as_u8() as i32,
+            si_code: 0,
+            si_errno: 0,
+        },
+        cursig: sig_num.as_u8() as u16,
+        _pad0: 0,
+        sigpend: u64::from(thread.sig_pending),
+        sighold: u64::from(thread.sig_blocked),
+        pid: pid_ns.ns_id_of(thread.tid).unwrap_or(0) as i32,
+        ppid: pid_ns.ns_id_of(process.parent().pid()).unwrap_or(0) as i32,
+        pgrp: pid_ns.ns_id_of(process.pgid()).unwrap_or(0) as i32,
+        sid: pid_ns.ns_id_of(process.sid()).unwrap_or(0) as i32,
+        utime: timeval_t::from(thread.user_time),
+        stime: timeval_t::from(thread.kernel_time),
+        cutime: timeval_t::from(children_user_time),
+        cstime: timeval_t::from(children_kernel_time),
+        reg: thread.gregs,
+        fpvalid: thread.fpregs.is_some() as i32,
+        _pad1: 0,
+    }
+}
+
+fn prpsinfo(ctx: &Context) -> Result<ElfPrpsinfo> {
+    let process = ctx.process.as_ref();
+    let pid_ns = process.pid_ns();
+    let credentials = ctx.posix_thread.credentials();
+
+    let mut psinfo = ElfPrpsinfo {
+        // The dumping thread is running.
+        sname: b'R',
+        nice: process.nice().load(Ordering::Relaxed).value().get(),
+        uid: credentials.ruid().into(),
+        gid: credentials.rgid().into(),
+        pid: pid_ns.ns_id_of(process.pid()).unwrap_or(0) as i32,
+        ppid: pid_ns.ns_id_of(process.parent().pid()).unwrap_or(0) as i32,
+        pgrp: pid_ns.ns_id_of(process.pgid()).unwrap_or(0) as i32,
+        sid: pid_ns.ns_id_of(process.sid()).unwrap_or(0) as i32,
+        ..ElfPrpsinfo::new_zeroed()
+    };
+
+    let thread_name = *ctx.posix_thread.thread_name().lock();
+    let fname = thread_name.as_bytes();
+    psinfo.fname[..fname.len()].copy_from_slice(fname);
+
+    // The arguments are separated by spaces and truncated to fit in the buffer.
+    let psargs_len = {
+        let vmar_guard = process.lock_vmar();
+        let Some(init_stack_reader) = vmar_guard.init_stack_reader() else {
+            return_errno_with_message!(Errno::ESRCH, "the process has exited");
+        };
+        let mut writer = VmWriter::from(&mut psinfo.psargs[..ELF_PRARGSZ - 1]).to_fallible();
+        init_stack_reader.argv(0, &mut writer)?
+    };
+    for byte in psinfo.psargs[..psargs_len].iter_mut() {
+        if *byte == 0 {
+            *byte = b' ';
+        }
+    }
+
+    Ok(psinfo)
+}
+
+fn auxv(ctx: &Context) -> Result<Vec<u8>> {
+    let vmar_guard = ctx.process.lock_vmar();
+    let Some(init_stack_reader) = vmar_guard.init_stack_reader() else {
+        return_errno_with_message!(Errno::ESRCH, "the process has exited");
+    };
+
+    let mut auxv = vec![0u8; PAGE_SIZE];
+    let mut writer = VmWriter::from(auxv.as_mut_slice()).to_fallible();
+    let len = init_stack_reader.auxv(0, &mut writer)?;
+    auxv.truncate(len);
+
+    Ok(auxv)
+}
+
+/// Builds the `NT_FILE` note, which lists the file-backed mappings.
+///
+/// The note starts with the number of mappings and the page size, followed by the start address,
+/// the end address, and the page offset of each mapping, and then the null-terminated paths.
+fn file_note(segments: &[Segment]) -> Vec<u8> {
+    let files = segments
+        .iter()
+        .filter_map(|segment| Some((&segment.range, segment.file.as_ref()?)));
+
+    let mut header = vec![files.clone().count() as u64, PAGE_SIZE as u64];
+    let mut paths = Vec::new();
+    for (range, (path, page_offset)) in files {
+        header.extend([range.start as u64, range.end as u64, *page_offset as u64]);
+        paths.extend_from_slice(path.as_bytes());
+        paths.push(0);
+    }
+
+    let mut note = header.as_bytes().to_vec();
+    note.extend_from_slice(&paths);
+    note
+}
+
+const ELF_MAGIC: [u8; 4] = *b"\x7fELF";
+
+const ELFCLASS64: u8 = 2;
+const ELFDATA2LSB: u8 = 1;
+const EV_CURRENT: u8 = 1;
+const ET_CORE: u16 = 4;
+
+const PT_LOAD: u32 = 1;
+const PT_NOTE: u32 = 4;
+const PN_XNUM: u16 = 0xffff;
+
+const PF_X: u32 = 1 << 0;
+const PF_W: u32 = 1 << 1;
+const PF_R: u32 = 1 << 2;
+
+const NOTE_NAME: &[u8] = b"CORE\0";
+
+const NT_PRSTATUS: u32 = 1;
+const NT_PRFPREG: u32 = 2;
+const NT_PRPSINFO: u32 = 3;
+const NT_AUXV: u32 = 6;
+const NT_SIGINFO: u32 = 0x53494749;
+const NT_FILE: u32 = 0x46494c45;
+
+/// The size of `pr_psargs` in `struct elf_prpsinfo`.
+const ELF_PRARGSZ: usize = 80;
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct Elf64Ehdr {
+    e_ident: [u8; 16],
+    e_type: u16,
+    e_machine: u16,
+    e_version: u32,
+    e_entry: u64,
+    e_phoff: u64,
+    e_shoff: u64,
+    e_flags: u32,
+    e_ehsize: u16,
+    e_phentsize: u16,
+    e_phnum: u16,
+    e_shentsize: u16,
+    e_shnum: u16,
+    e_shstrndx: u16,
+}
+
+impl Elf64Ehdr {
+    fn new_core(nr_phdrs: u16) -> Self {
+        let mut e_ident = [0u8; 16];
+        e_ident[..4].copy_from_slice(&ELF_MAGIC);
+        e_ident[4] = ELFCLASS64;
+        e_ident[5] = ELFDATA2LSB;
+        e_ident[6] = EV_CURRENT;
+
+        Self {
+            e_ident,
+            e_type: ET_CORE,
+            e_machine: ELF_MACHINE,
+            e_version: EV_CURRENT as u32,
+            e_phoff: size_of::<Elf64Ehdr>() as u64,
+            e_ehsize: size_of::<Elf64Ehdr>() as u16,
+            e_phentsize: size_of::<Elf64Phdr>() as u16,
+            e_phnum: nr_phdrs,
+            ..Self::new_zeroed()
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct Elf64Phdr {
+    p_type: u32,
+    p_flags: u32,
+    p_offset: u64,
+    p_vaddr: u64,
+    p_paddr: u64,
+    p_filesz: u64,
+    p_memsz: u64,
+    p_align: u64,
+}
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct Elf64Nhdr {
+    n_namesz: u32,
+    n_descsz: u32,
+    n_type: u32,
+}
+
+/// Mirror of Linux's `struct elf_siginfo`.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct ElfSiginfo {
+    si_signo: i32,
+    si_code: i32,
+    si_errno: i32,
+}
+
+/// Mirror of Linux's `struct elf_prstatus`.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/linux/elfcore.h>
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct ElfPrstatus {
+    info: ElfSiginfo,
+    cursig: u16,
+    _pad0: u16,
+    sigpend: u64,
+    sighold: u64,
+    pid: i32,
+    ppid: i32,
+    pgrp: i32,
+    sid: i32,
+    utime: timeval_t,
+    stime: timeval_t,
+    cutime: timeval_t,
+    cstime: timeval_t,
+    reg: ElfGregset,
+    fpvalid: i32,
+    _pad1: u32,
+}
+
+/// Mirror of Linux's `struct elf_prpsinfo`.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/linux/elfcore.h>
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct ElfPrpsinfo {
+    state: u8,
+    sname: u8,
+    zomb: u8,
+    nice: i8,
+    _pad0: u32,
+    flag: u64,
+    uid: u32,
+    gid: u32,
+    pid: i32,
+    ppid: i32,
+    pgrp: i32,
+    sid: i32,
+    fname: [u8; 16],
+    psargs: [u8; ELF_PRARGSZ],
+}
@@ -0,0 +1,279 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Core dumps.
+//!
+//! When a process is killed by a signal whose default action is to dump core (e.g., `SIGSEGV`),
+//! the kernel writes an ELF core file that describes the memory and the threads of the process.
+//! Depending on `/proc/sys/kernel/core_pattern`, the core file is either written to a file or
+//! piped to a user-mode helper program.
+//!
+//! Reference: <https://man7.org/linux/man-pages/man5/core.5.html>
+
+use core::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use aster_rights::ReadOp;
+use ostd::{arch::cpu::context::UserContext, sync::WaitQueue};
+
+pub(crate) use self::pattern::{CorePattern, core_pattern, set_core_pattern};
+use self::{
+    output::CoreDumpWriter,
+    pattern::{CoreName, format_core_name},
+};
+use super::{
+    Credentials, ResourceType, TermStatus,
+    posix_thread::sigkill_other_threads,
+    signal::{
+        HandlePendingSignal,
+        c_types::siginfo_t,
+        sig_mask::{SigMask, SigSet},
+        sig_num::SigNum,
+    },
+};
+use crate::{
+    arch::coredump::{ElfGregset, elf_fpregset, elf_gregset},
+    prelude::*,
+    thread::{AsThread, Tid},
+};
+
+mod elf;
+mod output;
+mod pattern;
+
+/// Whether a process can produce core dumps.
+///
+/// This is also the value of `PR_GET_DUMPABLE` and `PR_SET_DUMPABLE`.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/linux/sched/coredump.h>
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
+pub(crate) enum Dumpable {
+    /// No core dumps are produced.
+    Disable = 0,
+    /// Core dumps are produced as the user of the process.
+    User = 1,
+    /// Core dumps are produced as root (only settable via `suid_dumpable`).
+    Root = 2,
+}
+
+bitflags! {
+    /// The kinds of mappings to include in core dumps.
+    ///
+    /// This is set via `/proc/[pid]/coredump_filter`.
+    ///
+    /// Reference: <https://man7.org/linux/man-pages/man5/core.5.html>
+    pub(crate) struct CoreDumpFilter: u32 {
+        /// Anonymous private mappings.
+        const ANON_PRIVATE    = 1 << 0;
+        /// Anonymous shared mappings.
+        const ANON_SHARED     = 1 << 1;
+        /// File-backed private mappings.
+        const MAPPED_PRIVATE  = 1 << 2;
+        /// File-backed shared mappings.
+        const MAPPED_SHARED   = 1 << 3;
+        /// ELF headers.
+        const ELF_HEADERS     = 1 << 4;
+        /// Private huge pages.
+        const HUGETLB_PRIVATE = 1 << 5;
+        /// Shared huge pages.
+        const HUGETLB_SHARED  = 1 << 6;
+        /// Private DAX pages.
+        const DAX_PRIVATE     = 1 << 7;
+        /// Shared DAX pages.
+        const DAX_SHARED      = 1 << 8;
+    }
+}
+
+impl Default for CoreDumpFilter {
+    fn default() -> Self {
+        Self::ANON_PRIVATE | Self::ANON_SHARED | Self::ELF_HEADERS | Self::HUGETLB_PRIVATE
+    }
+}
+
+/// Changes the user or group IDs of the current thread with `set_ids`.
+///
+/// Like Linux, the process becomes non-dumpable if the effective or filesystem IDs are changed.
+/// Since `/proc/sys/fs/suid_dumpable` is not supported, its default value (0) is always used.
+///
+/// Reference: `commit_creds` in Linux.
+pub(crate) fn set_ids_and_update_dumpable<R>(ctx: &Context, set_ids: impl FnOnce() -> R) -> R {
+    let ids_of = |credentials: Credentials<ReadOp>| {
+        (
+            credentials.euid(),
+            credentials.egid(),
+            credentials.fsuid(),
+            credentials.fsgid(),
+        )
+    };
+
+    let old_ids = ids_of(ctx.posix_thread.credentials());
+    let res = set_ids();
+    if ids_of(ctx.posix_thread.credentials()) != old_ids {
+        ctx.user_space()
+            .vmar()
+            .process_vm()
+            .set_dumpable(Dumpable::Disable);
+    }
+
+    res
+}
+
+/// The state of an ongoing core dump.
+///
+/// While a thread is producing a core dump, the other threads in the process are killed. Before
+/// exiting, each of them reports its register state here, so that the state can be included in the
+/// core dump.
+pub(in crate::process) struct CoreDumpState {
+    threads: Mutex<Vec<ThreadState>>,
+    nr_reported: AtomicUsize,
+    wait_queue: WaitQueue,
+}
+
+impl CoreDumpState {
+    fn new() -> Self {
+        Self {
+            threads: Mutex::new(Vec::new()),
+            nr_reported: AtomicUsize::new(0),
+            wait_queue: WaitQueue::new(),
+        }
+    }
+
+    /// Reports the state of the current thread, which is exiting, to the dumping thread.
+    pub(in crate::process) fn report(&self, ctx: &Context, user_ctx: &UserContext) {
+        self.threads
+            .lock()
+            .push(ThreadState::capture(ctx, user_ctx));
+        self.nr_reported.fetch_add(1, Ordering::Release);
+        self.wait_queue.wake_all();
+    }
+}
+
+/// The state of a thread to be written to the core dump.
+struct ThreadState {
+    tid: Tid,
+    gregs: ElfGregset,
+    fpregs: Option<Vec<u8>>,
+    sig_pending: SigSet,
+    sig_blocked: SigMask,
+    user_time: Duration,
+    kernel_time: Duration,
+}
+
+impl ThreadState {
+    fn capture(ctx: &Context, user_ctx: &UserContext) -> Self {
+        let fpu_context = ctx.thread_local.supp_user_context().fpu().get();
+        let prof_clock = ctx.posix_thread.prof_clock();
+
+        Self {
+            tid: ctx.posix_thread.tid(),
+            gregs: elf_gregset(user_ctx, ctx.thread_local),
+            fpregs: elf_fpregset(&fpu_context).map(<[u8]>::to_vec),
+            sig_pending: ctx.pending_signals(),
+            sig_blocked: ctx.posix_thread.sig_mask(),
+            user_time: prof_clock.user_clock().read_jiffies().as_duration(),
+            kernel_time: prof_clock.kernel_clock().read_jiffies().as_duration(),
+        }
+    }
+}
+
+/// Kills the other threads in the current process and produces a core dump.
+///
+/// Returns the termination status of the process, which indicates whether the core dump has been
+/// produced. The caller should then exit the process with the returned status.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/coredump.c>
+pub(crate) fn do_coredump(
+    sig_num: SigNum,
+    siginfo: &siginfo_t,
+    ctx: &Context,
+    user_ctx: &UserContext,
+) -> TermStatus {
+    let killed = TermStatus::Killed(sig_num);
+
+    if ctx.user_space().vmar().process_vm().dumpable() == Dumpable::Disable {
+        return killed;
+    }
+
+    let state = Arc::new(CoreDumpState::new());
+    let nr_other_threads = {
+        let mut tasks = ctx.process.tasks().lock();
+        if tasks.has_exited_group() || tasks.in_execve() {
+            // Another thread is killing the process or replacing the program.
+            return killed;
+        }
+
+        sigkill_other_threads(ctx.task, &tasks);
+        tasks.set_exited_group();
+        ctx.process.status().set_exit_code(killed.as_u32());
+        tasks.start_core_dump(state.clone());
+
+        tasks
+            .as_slice()
+            .iter()
+            .filter(|task| !core::ptr::eq(task.as_ref(), ctx.task))
+            .filter(|task| !task.as_thread().unwrap().is_exited())
+            .count()
+    };
+
+    state.wait_queue.wait_until(|| {
+        (state.nr_reported.load(Ordering::Acquire) == nr_other_threads).then_some(())
+    });
+
+    // Like Linux, the dumping thread comes first.
+    let mut threads = Vec::with_capacity(nr_other_threads + 1);
+    threads.push(ThreadState::capture(ctx, user_ctx));
+    threads.append(&mut state.threads.lock());
+
+    let res = dump(sig_num, siginfo, &threads, ctx);
+    ctx.process.tasks().lock().finish_core_dump();
+
+    if let Err(err) = res {
+        debug!("the core dump is not produced: {:?}", err);
+        return killed;
+    }
+
+    let dumped = TermStatus::CoreDumped(sig_num);
+    ctx.process.status().set_exit_code(dumped.as_u32());
+    dumped
+}
+
+fn dump(
+    sig_num: SigNum,
+    siginfo: &siginfo_t,
+    threads: &[ThreadState],
+    ctx: &Context,
+) -> Result<()> {
+    let core_limit = ctx
+        .process
+        .resource_limits()
+        .get_rlimit(ResourceType::RLIMIT_CORE)
+        .get_cur();
+
+    let mut writer = match format_core_name(sig_num, ctx)? {
+        CoreName::File(path) => {
+            if core_limit < PAGE_SIZE as u64 {
+                return_errno_with_message!(Errno::EFBIG, "the core dump size limit is too small");
+            }
+            let file = output::create_core_file(&path, ctx)?;
+            CoreDumpWriter::new_file(file, usize::try_from(core_limit).unwrap_or(usize::MAX))
+        }
+        CoreName::Pipe(args) => {
+            // The core dump helpers run with `RLIMIT_CORE` set to 1. Like Linux, their core dumps
+            // are not piped to new helpers to avoid infinite recursion.
+            if core_limit == 1 {
+                return_errno_with_message!(
+                    Errno::EPERM,
+                    "the core dump helper cannot be dumped via a pipe"
+                );
+            }
+            let pipe = output::spawn_core_dump_helper(args)?;
+            // The size limit does not apply to pipes.
+            CoreDumpWriter::new_pipe(pipe, usize::MAX)
+        }
+    };
+
+    elf::write_elf_core(&mut writer, sig_num, siginfo, threads, ctx)?;
+    writer.finish()
+}
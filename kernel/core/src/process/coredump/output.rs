@@ -0,0 +1,180 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The output of core dumps.
+
+use crate::{
+    fs::{
+        file::{AccessMode, FileLike, InodeHandle, InodeMode, InodeType, SeekFrom, StatusFlags},
+        pipe,
+        vfs::{
+            notify,
+            path::{FsPath, SplitPath, SplitPathError},
+        },
+    },
+    prelude::*,
+    process::{rlimit::new_resource_limits_for_core_dump_helper, spawn_usermode_helper},
+};
+
+/// Creates the core file at `path`, which is relative to the current working directory.
+///
+/// Like Linux, an existing file at `path` is unlinked first, and the core file is created
+/// exclusively with mode 0600 (subject to the umask).
+pub(super) fn create_core_file(path: &str, ctx: &Context) -> Result<Arc<dyn FileLike>> {
+    let (dir_name, file_name) = path
+        .split_dirname_and_basename()
+        .map_err(SplitPathError::reject_root_as_is_dir)?;
+
+    let fs_ref = ctx.thread_local.borrow_fs();
+    let mode = InodeMode::from_bits_truncate(0o600 & !fs_ref.umask().get());
+    let dir_path = fs_ref
+        .resolver()
+        .read()
+        .lookup(&FsPath::try_from(dir_name)?)?;
+
+    // If the file does not exist, that's fine. Other errors will be reported when creating it.
+    let _ = dir_path.unlink(file_name);
+
+    let file_path = dir_path.new_fs_child(file_name, InodeType::File, mode)?;
+    notify::on_create(&dir_path, || file_name.to_string());
+
+    let file =
+        InodeHandle::new_unchecked_access(file_path, AccessMode::O_WRONLY, StatusFlags::empty())?;
+    Ok(Arc::new(file))
+}
+
+/// Spawns the helper program that receives the core dump from its standard input.
+///
+/// Returns the pipe to which the core dump should be written.
+pub(super) fn spawn_core_dump_helper(args: Vec<String>) -> Result<Arc<dyn FileLike>> {
+    let path = args[0].clone();
+    let argv = args
+        .into_iter()
+        .map(CString::new)
+        .collect::<core::result::Result<Vec<_>, _>>()?;
+
+    let (reader, writer) = pipe::new_file_pair(StatusFlags::empty())?;
+    // Like Linux, the helper runs with an empty environment.
+    spawn_usermode_helper(
+        &path,
+        argv,
+        Vec::new(),
+        reader,
+        new_resource_limits_for_core_dump_helper(),
+    )?;
+
+    Ok(writer)
+}
+
+/// A writer that emits a core dump to a file or a pipe.
+///
+/// Holes in the core dump are skipped lazily. For files, holes are created by seeking; for pipes,
+/// holes are filled with zeros.
+pub(super) struct CoreDumpWriter {
+    file: Arc<dyn FileLike>,
+    is_seekable: bool,
+    /// The number of bytes that have been emitted, including holes.
+    pos: usize,
+    /// The number of bytes to be skipped before the next write.
+    to_skip: usize,
+    /// The maximum size of the core dump.
+    limit: usize,
+}
+
+impl CoreDumpWriter {
+    /// Creates a writer that writes to a regular file, which is seekable.
+    pub(super) fn new_file(file: Arc<dyn FileLike>, limit: usize) -> Self {
+        Self::new(file, true, limit)
+    }
+
+    /// Creates a writer that writes to a pipe, which is not seekable.
+    pub(super) fn new_pipe(file: Arc<dyn FileLike>, limit: usize) -> Self {
+        Self::new(file, false, limit)
+    }
+
+    fn new(file: Arc<dyn FileLike>, is_seekable: bool, limit: usize) -> Self {
+        Self {
+            file,
+            is_seekable,
+            pos: 0,
+            to_skip: 0,
+            limit,
+        }
+    }
+
+    /// Returns the current position in the core dump.
+    pub(super) fn pos(&self) -> usize {
+        self.pos + self.to_skip
+    }
+
+    /// Writes all the bytes in `buf`.
+    pub(super) fn write(&mut self, buf: &[u8]) -> Result<()> {
+        self.flush_skip()?;
+
+        if buf.len() > self.limit - self.pos {
+            return_errno_with_message!(Errno::EFBIG, "the core dump exceeds the size limit");
+        }
+
+        let mut written = 0;
+        while written < buf.len() {
+            let len = self.file.write_bytes(&buf[written..])?;
+            if len == 0 {
+                return_errno_with_message!(Errno::EIO, "the core dump cannot be written");
+            }
+            written += len;
+        }
+        self.pos += buf.len();
+
+        Ok(())
+    }
+
+    /// Skips `len` bytes, leaving a hole in the core dump.
+    pub(super) fn skip(&mut self, len: usize) {
+        self.to_skip += len;
+    }
+
+    /// Skips bytes until the position is aligned to `align`.
+    pub(super) fn skip_to_align(&mut self, align: usize) {
+        let pos = self.pos();
+        self.skip(pos.next_multiple_of(align) - pos);
+    }
+
+    /// Finishes the core dump.
+    ///
+    /// If the core dump ends with a hole, the last byte of the hole is written explicitly so that
+    /// the file size covers the hole.
+    pub(super) fn finish(mut self) -> Result<()> {
+        if self.to_skip == 0 {
+            return Ok(());
+        }
+
+        self.to_skip -= 1;
+        self.write(&[0])
+    }
+
+    fn flush_skip(&mut self) -> Result<()> {
+        if self.to_skip == 0 {
+            return Ok(());
+        }
+
+        if self.to_skip > self.limit - self.pos {
+            return_errno_with_message!(Errno::EFBIG, "the core dump exceeds the size limit");
+        }
+
+        let to_skip = core::mem::take(&mut self.to_skip);
+        if self.is_seekable {
+            self.file.seek(SeekFrom::Current(to_skip.try_into()?))?;
+            self.pos += to_skip;
+            return Ok(());
+        }
+
+        let zeros = [0u8; PAGE_SIZE];
+        let mut remaining = to_skip;
+        while remaining > 0 {
+            let len = remaining.min(PAGE_SIZE);
+            self.write(&zeros[..len])?;
+            remaining -= len;
+        }
+
+        Ok(())
+    }
+}
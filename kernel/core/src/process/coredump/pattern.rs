@@ -0,0 +1,162 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The core dump pattern in `/proc/sys/kernel/core_pattern`.
+
+use aster_util::fixed_str::FixedCStr;
+use spin::Once;
+
+use crate::{
+    prelude::*,
+    process::{ResourceType, signal::sig_num::SigNum},
+    time::SystemTime,
+};
+
+/// The maximum length of the core dump pattern, including the trailing nul.
+//
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/linux/coredump.h>
+const CORENAME_MAX_SIZE: usize = 128;
+
+/// The core dump pattern.
+pub(crate) type CorePattern = FixedCStr<CORENAME_MAX_SIZE>;
+
+fn core_pattern_lock() -> &'static Mutex<CorePattern> {
+    static CORE_PATTERN: Once<Mutex<CorePattern>> = Once::new();
+
+    CORE_PATTERN.call_once(|| Mutex::new(CorePattern::from_str_truncated("core")))
+}
+
+/// Returns the current core dump pattern.
+pub(crate) fn core_pattern() -> CorePattern {
+    *core_pattern_lock().lock()
+}
+
+/// Sets the core dump pattern.
+pub(crate) fn set_core_pattern(core_pattern: CorePattern) {
+    *core_pattern_lock().lock() = core_pattern;
+}
+
+/// The destination of a core dump, which is derived from the core dump pattern.
+pub(super) enum CoreName {
+    /// The core dump is written to the file at this path.
+    File(String),
+    /// The core dump is piped to the helper program described by these arguments.
+    Pipe(Vec<String>),
+}
+
+/// Expands the core dump pattern for the current process, which is being killed by `sig_num`.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/coredump.c>
+pub(super) fn format_core_name(sig_num: SigNum, ctx: &Context) -> Result<CoreName> {
+    let pattern = core_pattern();
+    let pattern = pattern
+        .as_str()
+        .map_err(|_| Error::with_message(Errno::EINVAL, "the core pattern is not valid UTF-8"))?;
+
+    let (is_pipe, pattern) = match pattern.strip_prefix('|') {
+        Some(pattern) => (true, pattern),
+        None => (false, pattern),
+    };
+
+    let mut args = vec![String::new()];
+    let mut was_space = false;
+    let mut chars = pattern.chars();
+    while let Some(ch) = chars.next() {
+        // Split the arguments before expanding the specifiers, so that specifiers with spaces
+        // (e.g., `%e`) are not split.
+        if is_pipe {
+            if ch.is_ascii_whitespace() {
+                if !args.last().unwrap().is_empty() {
+                    was_space = true;
+                }
+                continue;
+            } else if was_space {
+                was_space = false;
+                args.push(String::new());
+            }
+        }
+
+        let arg = args.last_mut().unwrap();
+        if ch != '%' {
+            arg.push(ch);
+            continue;
+        }
+
+        // Unknown specifiers are dropped, just like Linux.
+        let Some(specifier) = chars.next() else {
+            break;
+        };
+        expand_specifier(specifier, arg, sig_num, ctx);
+    }
+
+    if is_pipe {
+        if args[0].is_empty() {
+            return_errno_with_message!(Errno::EINVAL, "the core pattern has no helper program");
+        }
+        Ok(CoreName::Pipe(args))
+    } else {
+        Ok(CoreName::File(args.pop().unwrap()))
+    }
+}
+
+fn expand_specifier(specifier: char, out: &mut String, sig_num: SigNum, ctx: &Context) {
+    let process = ctx.process.as_ref();
+    let pid_ns = process.pid_ns();
+
+    match specifier {
+        '%' => out.push('%'),
+        'p' => out.push_str(&pid_ns.ns_id_of(process.pid()).unwrap().to_string()),
+        'P' => out.push_str(&process.pid().to_string()),
+        'i' => out.push_str(&pid_ns.ns_id_of(ctx.posix_thread.tid()).unwrap().to_string()),
+        'I' => out.push_str(&ctx.posix_thread.tid().to_string()),
+        'u' => {
+            let uid = ctx.posix_thread.credentials().ruid();
+            out.push_str(&u32::from(uid).to_string());
+        }
+        'g' => {
+            let gid = ctx.posix_thread.credentials().rgid();
+            out.push_str(&u32::from(gid).to_string());
+        }
+        'd' => {
+            let dumpable = ctx.user_space().vmar().process_vm().dumpable();
+            out.push_str(&(dumpable as u8).to_string());
+        }
+        's' => out.push_str(&sig_num.as_u8().to_string()),
+        't' => {
+            let now = SystemTime::now()
+                .duration_since(&SystemTime::UNIX_EPOCH)
+                .unwrap_or_default();
+            out.push_str(&now.as_secs().to_string());
+        }
+        'h' => {
+            let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+            let uts_name = ns_proxy.unwrap().uts_ns().uts_name();
+            out.push_str(&uts_name.nodename().as_cstr().to_string_lossy());
+        }
+        'e' => {
+            let thread_name = *ctx.posix_thread.thread_name().lock();
+            push_escaped(out, &thread_name.as_cstr().to_string_lossy());
+        }
+        'E' => {
+            let user_space = ctx.user_space();
+            let fs_ref = ctx.thread_local.borrow_fs();
+            let path_resolver = fs_ref.resolver().read();
+            let executable_path = path_resolver
+                .make_abs_path(user_space.vmar().process_vm().executable_path())
+                .into_string();
+            push_escaped(out, &executable_path);
+        }
+        'c' => {
+            let core_limit = process
+                .resource_limits()
+                .get_rlimit(ResourceType::RLIMIT_CORE)
+                .get_cur();
+            out.push_str(&core_limit.to_string());
+        }
+        _ => (),
+    }
+}
+
+/// Appends `s` to `out`, replacing `/` with `!` so that `s` cannot introduce new path components.
+fn push_escaped(out: &mut String, s: &str) {
+    out.extend(s.chars().map(|ch| if ch == '/' { '!' } else { ch }));
+}
@@ -15,7 +15,8 @@ use crate::{
     fs::vfs::path::{Path, PerMountFlags},
     prelude::*,
     process::{
-        ContextUnshareAdminApi, Credentials, Process,
+        ContextSetNsAdminApi, ContextUnshareAdminApi, Credentials, NsProxy, Process,
+        coredump::Dumpable,
         credentials::{ExecCred, FileCapabilities},
         pid_table,
         posix_thread::{
@@ -30,6 +31,7 @@ use crate::{
             signals::kernel::KernelSignal,
         },
     },
+    security::lsm::hooks as lsm_hooks,
     vm::vmar::VmarHandle,
 };
 
@@ -62,9 +64,22 @@ pub(crate) fn do_execve(
     let executable_path = executable.path().clone();
     let program_to_load = ProgramToLoad::from_executable(executable, &path_resolver, argv, envp)?;
     let exec_cred = prepare_exec_cred(program_to_load.elf_path(), ctx)?;
+    lsm_hooks::on_exec_check(lsm_hooks::PathContext::new(
+        ctx.posix_thread,
+        &executable_path,
+    ))?;
+
+    // Like Linux, the process enters the time namespace for children after `execve()`.
+    let ns_proxy = ctx
+        .thread_local
+        .borrow_ns_proxy()
+        .unwrap()
+        .clone()
+        .enter_time_ns_for_children()?;
 
     let new_vmar = VmarHandle::new(ProcessVm::new(executable_path.clone()));
-    let elf_load_info = program_to_load.load_to_vmar(&new_vmar, &path_resolver)?;
+    let elf_load_info =
+        program_to_load.load_to_vmar(&new_vmar, &path_resolver, ns_proxy.time_ns())?;
 
     // Ensure no other thread is concurrently performing exit_group or execve.
     // If such an operation is in progress, return EAGAIN.
@@ -93,6 +108,7 @@ pub(crate) fn do_execve(
         new_vmar,
         &elf_load_info,
         exec_cred,
+        ns_proxy,
     );
 
     if res.is_ok() {
@@ -191,6 +207,7 @@ fn do_execve_no_return(
     new_vmar: VmarHandle,
     elf_load_info: &ElfLoadInfo,
     exec_cred: ExecCred,
+    ns_proxy: Arc<NsProxy>,
 ) -> Result<()> {
     let Context {
         process,
@@ -210,9 +227,16 @@ fn do_execve_no_return(
     // `/proc/[pid]/mem` or `/proc/[pid]/maps`.
     let (vmar_guard, old_vmar) = activate_vmar(ctx, new_vmar);
     apply_exec_cred(process, &ctx.credentials_mut(), exec_cred)?;
+    lsm_hooks::on_exec_commit(posix_thread);
+    reset_coredump_settings(ctx, &old_vmar);
     drop(vmar_guard);
     drop(old_vmar);
 
+    // Switch the namespaces after the VMAR, which maps the vDSO of the new time namespace.
+    if !Arc::ptr_eq(thread_local.borrow_ns_proxy().unwrap(), &ns_proxy) {
+        ctx.set_ns_proxy(ns_proxy);
+    }
+
     // After the program has been successfully loaded, the virtual memory of the current process
     // is initialized. Hence, it is necessary to clear the previously recorded robust list.
     *thread_local.robust_list().borrow_mut() = None;
@@ -358,6 +382,29 @@ fn apply_exec_cred(
     Ok(())
 }
 
+/// Resets the core dump settings of the new VMAR.
+///
+/// The core dump filter is inherited from the old VMAR. The process becomes non-dumpable if the
+/// new program runs with different user or group IDs.
+///
+/// Reference: `begin_new_exec` in Linux.
+fn reset_coredump_settings(ctx: &Context, old_vmar: &VmarHandle) {
+    let old_process_vm = old_vmar.process_vm();
+    let user_space = ctx.user_space();
+    let new_process_vm = user_space.vmar().process_vm();
+
+    new_process_vm.set_coredump_filter(old_process_vm.coredump_filter());
+
+    let credentials = ctx.posix_thread.credentials();
+    let dumpable =
+        if credentials.euid() == credentials.ruid() && credentials.egid() == credentials.rgid() {
+            Dumpable::User
+        } else {
+            Dumpable::Disable
+        };
+    new_process_vm.set_dumpable(dumpable);
+}
+
 fn reset_vfork_child(process: &Process) {
     if process.status().is_vfork_child() {
         // Resumes the parent process.
@@ -2,9 +2,11 @@
 
 use core::sync::atomic::Ordering;
 
-use super::{INIT_PROCESS_PID, Pid, Process, pid_table};
+use super::{Pid, Process};
 use crate::{
-    events::IoEvents, fs::cgroupfs::CgroupMembership, prelude::*,
+    events::IoEvents,
+    fs::{cgroupfs::CgroupMembership, pseudofs::NsCommonOps},
+    prelude::*,
     process::signal::signals::kernel::KernelSignal,
 };
 
@@ -21,6 +23,12 @@ pub(super) fn exit_process(current_process: &Process) {
     // Drop fields in `Process`.
     drop_after!(current_process.lock_vmar().set_vmar(None));
 
+    // Tear down the PID namespace if the current process is its init process.
+    let pid_ns = current_process.pid_ns();
+    if !current_process.is_init_process() && pid_ns.is_child_reaper(current_process) {
+        pid_ns.kill_all_processes();
+    }
+
     // Move the children to the reaper process and send them signals. The children should see a new
     // parent when they receive the signal.
     let children = move_children_to_reaper_process(current_process);
@@ -80,13 +88,21 @@ fn move_children_to_reaper_process(current_process: &Process) -> BTreeMap<Pid, A
         }
     }
 
-    let init_process = pid_table::pid_table_mut()
-        .get_process(INIT_PROCESS_PID)
-        .unwrap();
-    let children = move_process_children(current_process, &init_process).unwrap();
-    init_process.children_wait_queue().wake_all();
+    // Fall back to the init process of the PID namespace. If the init process has exited (i.e.,
+    // the namespace is being torn down), fall back to that of the parent namespace. The init
+    // process of the initial PID namespace never exits, so this loop will terminate.
+    let mut pid_ns = current_process.pid_ns();
+    loop {
+        if let Some(init_process) = pid_ns.child_reaper()
+            && !core::ptr::eq(init_process.as_ref(), current_process)
+            && let Ok(children) = move_process_children(current_process, &init_process)
+        {
+            init_process.children_wait_queue().wake_all();
+            return children;
+        }
 
-    children
+        pid_ns = pid_ns.parent().unwrap();
+    }
 }
 
 /// Finds a reaper process for `current_process`.
@@ -98,12 +114,19 @@ fn find_reaper_process(current_process: &Process) -> Option<Arc<Process>> {
     // current process to a new reaper (updating this `Weak` reference) before becoming zombie
     // itself. Therefore, the parent is always alive and `upgrade` cannot fail.
     let mut parent = current_process.parent().lock().process().upgrade().unwrap();
+    let pid_ns = current_process.pid_ns();
 
     loop {
         if parent.is_init_process() {
             return Some(parent);
         }
 
+        // Orphaned processes never leave their PID namespace, and the init process of the
+        // namespace is handled by the caller.
+        if pid_ns.is_child_reaper(&parent) {
+            return None;
+        }
+
         if !parent.has_child_subreaper.load(Ordering::Acquire) {
             return None;
         }
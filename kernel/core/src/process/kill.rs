@@ -3,7 +3,11 @@
 use super::{
     Pgid, Pid, Process, pid_table,
     posix_thread::AsPosixThread,
-    signal::{constants::SIGCONT, sig_num::SigNum, signals::Signal},
+    signal::{
+        constants::{SIGCONT, SIGKILL, SIGSTOP},
+        sig_num::SigNum,
+        signals::Signal,
+    },
 };
 use crate::{
     prelude::*,
@@ -26,7 +30,9 @@ pub(crate) fn kill(pid: Pid, signal: Option<Box<dyn Signal>>, ctx: &Context) ->
             return Ok(());
         };
 
-        if !ctx.posix_thread.has_signal_blocked(signal.num()) {
+        if !ctx.posix_thread.has_signal_blocked(signal.num())
+            && !is_protected_ns_init(ctx.process.as_ref(), signal.num(), ctx)
+        {
             // Killing the current thread does not raise any permission issues.
             ctx.posix_thread.enqueue_signal(signal);
             return Ok(());
@@ -114,7 +120,9 @@ pub(crate) fn tgkill(
         return Ok(());
     }
 
-    if let Some(signal) = signal {
+    if let Some(signal) = signal
+        && !is_protected_ns_init(&target_posix_thread.process(), signal.num(), ctx)
+    {
         // We've checked the permission issues above.
         // FIXME: We should take some lock while checking the permission to avoid race conditions.
         target_posix_thread.enqueue_signal(signal);
@@ -126,13 +134,21 @@ pub(crate) fn tgkill(
 /// Sends a signal to all processes except current process and init process, using
 /// the current process as the sender.
 ///
+/// Only the processes in the PID namespace of the current process (including its descendant
+/// namespaces) are affected, and the init process refers to that of the namespace.
+///
 /// The credentials of the current process will be checked to determine
 /// if it is authorized to send the signal to the target group.
 pub(crate) fn kill_all<S: Signal + Clone>(signal: Option<S>, ctx: &Context) -> Result<()> {
     let mut result = Ok(());
+    let pid_ns = ctx.process.pid_ns();
 
     for process in pid_table::pid_table_mut().iter_processes() {
-        if Arc::ptr_eq(&ctx.process, &process) || process.is_init_process() {
+        if Arc::ptr_eq(&ctx.process, &process) {
+            continue;
+        }
+        // Skip the processes that are invisible and the init process.
+        if pid_ns.ns_id_of(process.pid()).is_none_or(|pid| pid <= 1) {
             continue;
         }
 
@@ -154,13 +170,29 @@ fn kill_process(process: &Process, signal: Option<Box<dyn Signal>>, ctx: &Contex
     let target_main_thread = process.main_thread();
     check_signal_perm(target_main_thread.as_posix_thread().unwrap(), ctx, signum)?;
 
-    if let Some(signal) = signal {
+    if let Some(signal) = signal
+        && !is_protected_ns_init(process, signal.num(), ctx)
+    {
         process.enqueue_signal(signal);
     }
 
     Ok(())
 }
 
+/// Returns whether the signal should be dropped because the target is the init process of the
+/// PID namespace of the current process.
+///
+/// The init process of a PID namespace ignores the signals with the default action, which is
+/// done when handling them. However, `SIGKILL` and `SIGSTOP` cannot be ignored, so they are
+/// dropped here unless they are sent from an ancestor PID namespace.
+//
+// Reference: <https://elixir.bootlin.com/linux/v6.17/source/kernel/signal.c#L80>
+fn is_protected_ns_init(target: &Process, signum: SigNum, ctx: &Context) -> bool {
+    (signum == SIGKILL || signum == SIGSTOP)
+        && Arc::ptr_eq(target.pid_ns(), ctx.process.pid_ns())
+        && target.pid_ns().is_child_reaper(target)
+}
+
 // Reference: <https://elixir.bootlin.com/linux/v6.17/source/kernel/signal.c#L799>.
 fn check_signal_perm(target: &PosixThread, ctx: &Context, signum: Option<SigNum>) -> Result<()> {
     let target_process = target.process();
@@ -169,6 +201,20 @@ fn check_signal_perm(target: &PosixThread, ctx: &Context, signum: Option<SigNum>
         return Ok(());
     }
 
+    check_signal_cred(target, &target_process, ctx, signum)?;
+    lsm_hooks::on_signal(lsm_hooks::SignalContext::new(
+        ctx.posix_thread,
+        target,
+        signum,
+    ))
+}
+
+fn check_signal_cred(
+    target: &PosixThread,
+    target_process: &Process,
+    ctx: &Context,
+    signum: Option<SigNum>,
+) -> Result<()> {
     let current_cred = ctx.posix_thread.credentials();
     let target_cred = target.credentials();
     if current_cred.euid() == target_cred.suid()
@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod clone;
+pub(crate) mod coredump;
 pub(crate) mod credentials;
 mod execve;
 mod exit;
@@ -29,24 +30,27 @@ pub(crate) use execve::do_execve;
 pub(crate) use kill::{kill, kill_all, kill_group, tgkill};
 pub(crate) use namespace::{
     nsproxy::{ContextSetNsAdminApi, NsProxy, NsProxyBuilder, check_unsupported_ns_flags},
+    pid_ns::PidNamespace,
     unshare::ContextUnshareAdminApi,
     user_ns::UserNamespace,
 };
 pub(crate) use pid_file::PidFile;
 pub(crate) use process::{
-    ExitCode, INIT_PROCESS_PID, JobControl, Pgid, Pid, Process, ProcessGroup, ReapedChildrenStats,
-    Session, Sid, Terminal, broadcast_signal_async, enqueue_signal_async, spawn_init_process,
+    ExitCode, JobControl, Pgid, Pid, Process, ProcessGroup, ReapedChildrenStats, Session, Sid,
+    Terminal, broadcast_signal_async, enqueue_signal_async, spawn_init_process,
+    spawn_usermode_helper,
 };
 pub(crate) use process_filter::ProcessFilter;
 pub(crate) use process_vm::{INIT_STACK_SIZE, LockedHeap, ProcessVm, VmarSnapshot};
 pub(crate) use program_loader::{ShebangScriptPath, UndetectedExecutable};
 pub(crate) use rlimit::ResourceType;
 pub(crate) use stats::collect_process_creation_count;
-pub(crate) use term_status::TermStatus;
+pub(crate) use term_status::{CORE_DUMP_FLAG, TermStatus};
 pub(crate) use wait::{WaitOptions, WaitStatus, do_wait};
 
 pub(super) fn init() {
     posix_thread::futex::init();
+    posix_thread::io_priority::init();
     stats::init();
 }
 
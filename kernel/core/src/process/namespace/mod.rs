@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
 pub(super) mod nsproxy;
+pub(super) mod pid_ns;
 pub(super) mod unshare;
 pub(super) mod user_ns;
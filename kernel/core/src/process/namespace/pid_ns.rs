@@ -0,0 +1,314 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use spin::Once;
+
+use crate::{
+    fs::pseudofs::{NsCommonOps, NsType, StashedDentry},
+    prelude::*,
+    process::{
+        Process, UserNamespace,
+        credentials::capabilities::CapSet,
+        pid_table,
+        posix_thread::{PID_MAX, PosixThread, allocate_posix_tid},
+        signal::{constants::SIGKILL, signals::kernel::KernelSignal},
+    },
+    security::lsm::hooks as lsm_hooks,
+    thread::Tid,
+};
+
+/// The PID namespace.
+///
+/// PID namespaces form a tree. A process belongs to a single PID namespace, but it is visible in
+/// that namespace and all the ancestor namespaces, with a different ID in each of them.
+///
+/// Inside the kernel, threads, processes, process groups, and sessions are always identified by
+/// their IDs in the initial PID namespace, which are called global IDs and are the keys of the
+/// PID table. Every other PID namespace maps the global IDs of the objects visible in it to the
+/// IDs that are local to the namespace. The IDs must be translated at the user-kernel boundary.
+pub(crate) struct PidNamespace {
+    parent: Option<Arc<PidNamespace>>,
+    level: u32,
+    ids: Mutex<PidNsIds>,
+    /// The init process of the namespace, which reaps the orphaned processes in the namespace.
+    child_reaper: Mutex<Weak<Process>>,
+    owner: Arc<UserNamespace>,
+    stashed_dentry: StashedDentry,
+}
+
+struct PidNsIds {
+    /// The local ID from which the next unused ID is searched for.
+    next_id: u32,
+    /// The mapping from the global IDs to the local IDs.
+    local_ids: BTreeMap<u32, u32>,
+    /// The mapping from the local IDs to the global IDs.
+    global_ids: BTreeMap<u32, u32>,
+    /// Whether the init process has exited, which forbids new processes in the namespace.
+    is_dying: bool,
+}
+
+/// The maximum nesting depth of PID namespaces.
+//
+// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/linux/pid_namespace.h#L16>
+const MAX_PID_NS_LEVEL: u32 = 32;
+
+/// The first ID allocated in a PID namespace, which belongs to the init process.
+const FIRST_LOCAL_ID: u32 = 1;
+
+/// The IDs below this value are not reused once the allocation wraps around.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/linux/pid_namespace.h>
+const RESERVED_LOCAL_IDS: u32 = 300;
+
+impl PidNamespace {
+    /// Returns a reference to the singleton initial PID namespace.
+    pub(crate) fn get_init_singleton() -> &'static Arc<PidNamespace> {
+        static INIT: Once<Arc<PidNamespace>> = Once::new();
+
+        INIT.call_once(|| {
+            let owner = UserNamespace::get_init_singleton().clone();
+            Self::new(None, owner)
+        })
+    }
+
+    fn new(parent: Option<Arc<PidNamespace>>, owner: Arc<UserNamespace>) -> Arc<Self> {
+        let level = parent.as_ref().map_or(0, |parent| parent.level + 1);
+
+        Arc::new(Self {
+            parent,
+            level,
+            ids: Mutex::new(PidNsIds {
+                next_id: FIRST_LOCAL_ID,
+                local_ids: BTreeMap::new(),
+                global_ids: BTreeMap::new(),
+                is_dying: false,
+            }),
+            child_reaper: Mutex::new(Weak::new()),
+            owner,
+            stashed_dentry: StashedDentry::new(),
+        })
+    }
+
+    /// Creates a new PID namespace as a child of `self`.
+    pub(in crate::process) fn new_child(
+        self: &Arc<Self>,
+        owner: Arc<UserNamespace>,
+        posix_thread: &PosixThread,
+    ) -> Result<Arc<Self>> {
+        lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+            owner.as_ref(),
+            posix_thread,
+            CapSet::SYS_ADMIN,
+        ))?;
+
+        if self.level >= MAX_PID_NS_LEVEL {
+            return_errno_with_message!(
+                Errno::ENOSPC,
+                "the maximum nesting depth of PID namespaces is reached"
+            );
+        }
+
+        Ok(Self::new(Some(self.clone()), owner))
+    }
+
+    /// Returns the PID namespace of the current process.
+    pub(crate) fn current() -> Arc<PidNamespace> {
+        current!().pid_ns().clone()
+    }
+
+    /// Returns whether this namespace is the same as, or an ancestor of, the other namespace.
+    pub(crate) fn is_same_or_ancestor_of(&self, other: &PidNamespace) -> bool {
+        let mut ns = other;
+        loop {
+            if core::ptr::eq(ns, self) {
+                return true;
+            }
+            if ns.level <= self.level {
+                return false;
+            }
+            ns = ns.parent.as_deref().unwrap();
+        }
+    }
+
+    /// Allocates a global ID for a new thread in this namespace.
+    ///
+    /// The global ID is also mapped to a new local ID in this namespace and every ancestor
+    /// namespace.
+    pub(in crate::process) fn allocate_id(&self) -> Result<Tid> {
+        let mut ids = self.ids.lock();
+        if ids.is_dying {
+            return_errno_with_message!(
+                Errno::ENOMEM,
+                "the init process of the PID namespace has exited"
+            );
+        }
+
+        let global_id = allocate_posix_tid();
+        if self.level == 0 {
+            return Ok(global_id);
+        }
+
+        ids.insert(global_id)?;
+        drop(ids);
+
+        let mut ns = self.parent.as_deref().unwrap();
+        while ns.level > 0 {
+            if let Err(err) = ns.ids.lock().insert(global_id) {
+                self.release_id(global_id);
+                return Err(err);
+            }
+            ns = ns.parent.as_deref().unwrap();
+        }
+
+        Ok(global_id)
+    }
+
+    /// Releases the local IDs that are mapped to the global ID.
+    pub(in crate::process) fn release_id(&self, global_id: u32) {
+        let mut ns = self;
+        while ns.level > 0 {
+            ns.ids.lock().remove(global_id);
+            ns = ns.parent.as_deref().unwrap();
+        }
+    }
+
+    /// Translates a global ID to the ID that is visible in this namespace.
+    ///
+    /// Returns `None` if the ID is not visible in this namespace. An ID of zero, which is used by
+    /// the bootstrap process group and session, is always translated to zero.
+    pub(crate) fn ns_id_of(&self, global_id: u32) -> Option<u32> {
+        if self.level == 0 || global_id == 0 {
+            return Some(global_id);
+        }
+
+        self.ids.lock().local_ids.get(&global_id).copied()
+    }
+
+    /// Translates an ID that is visible in this namespace to the global ID.
+    ///
+    /// Returns `None` if no object has the ID in this namespace.
+    pub(crate) fn global_id_of(&self, ns_id: u32) -> Option<u32> {
+        if self.level == 0 || ns_id == 0 {
+            return Some(ns_id);
+        }
+
+        self.ids.lock().global_ids.get(&ns_id).copied()
+    }
+
+    /// Returns the init process of the namespace.
+    ///
+    /// Returns `None` if the init process has not been created yet or has been reaped.
+    pub(crate) fn child_reaper(&self) -> Option<Arc<Process>> {
+        self.child_reaper.lock().upgrade()
+    }
+
+    /// Returns whether the process is the init process of the namespace.
+    pub(crate) fn is_child_reaper(&self, process: &Process) -> bool {
+        core::ptr::eq(self.child_reaper.lock().as_ptr(), process)
+    }
+
+    /// Records the process as the init process of the namespace if it is the first process in
+    /// the namespace.
+    pub(in crate::process) fn try_set_child_reaper(&self, process: &Arc<Process>) {
+        if self.ns_id_of(process.pid()) == Some(FIRST_LOCAL_ID) {
+            *self.child_reaper.lock() = Arc::downgrade(process);
+        }
+    }
+
+    /// Kills all the processes in the namespace, except the init process.
+    ///
+    /// This is called when the init process exits. After that, no new processes can be created
+    /// in the namespace.
+    //
+    // FIXME: A process whose ID is allocated but which is not yet in the PID table will not be
+    // killed. Linux avoids this race by holding the `tasklist_lock`.
+    pub(in crate::process) fn kill_all_processes(&self) {
+        let global_ids: Vec<u32> = {
+            let mut ids = self.ids.lock();
+            ids.is_dying = true;
+            ids.local_ids.keys().copied().collect()
+        };
+
+        let pid_table = pid_table::pid_table_mut();
+        for global_id in global_ids {
+            let Some(process) = pid_table.get_process(global_id) else {
+                continue;
+            };
+            if self.is_child_reaper(&process) {
+                continue;
+            }
+            process.enqueue_signal(Box::new(KernelSignal::new(SIGKILL)));
+        }
+    }
+}
+
+impl PidNsIds {
+    /// Maps the global ID to a new local ID.
+    ///
+    /// Like Linux, the local ID is the lowest unused ID after the last allocated one, and the
+    /// search wraps around at [`PID_MAX`], so the IDs of the exited processes are not reused soon.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/kernel/pid.c>
+    fn insert(&mut self, global_id: u32) -> Result<()> {
+        let min_id = if self.next_id > RESERVED_LOCAL_IDS {
+            RESERVED_LOCAL_IDS
+        } else {
+            FIRST_LOCAL_ID
+        };
+        let Some(local_id) = self
+            .find_unused_id(self.next_id, PID_MAX)
+            .or_else(|| self.find_unused_id(min_id, self.next_id))
+        else {
+            return_errno_with_message!(Errno::EAGAIN, "no IDs are available in the PID namespace");
+        };
+        self.next_id = local_id + 1;
+
+        self.local_ids.insert(global_id, local_id);
+        self.global_ids.insert(local_id, global_id);
+
+        Ok(())
+    }
+
+    /// Returns the lowest local ID in `start..end` that is not in use.
+    fn find_unused_id(&self, start: u32, end: u32) -> Option<u32> {
+        let mut id = start;
+        for used_id in self
+            .global_ids
+            .range(start..end)
+            .map(|(used_id, _)| *used_id)
+        {
+            if used_id != id {
+                break;
+            }
+            id += 1;
+        }
+
+        (id < end).then_some(id)
+    }
+
+    fn remove(&mut self, global_id: u32) {
+        if let Some(local_id) = self.local_ids.remove(&global_id) {
+            self.global_ids.remove(&local_id);
+        }
+    }
+}
+
+impl NsCommonOps for PidNamespace {
+    const TYPE: NsType = NsType::Pid;
+
+    fn owner_user_ns(&self) -> Option<&Arc<UserNamespace>> {
+        Some(&self.owner)
+    }
+
+    fn parent(&self) -> Result<&Arc<Self>> {
+        self.parent.as_ref().ok_or_else(|| {
+            Error::with_message(
+                Errno::EPERM,
+                "the initial PID namespace does not have a parent namespace",
+            )
+        })
+    }
+
+    fn stashed_dentry(&self) -> &StashedDentry {
+        &self.stashed_dentry
+    }
+}
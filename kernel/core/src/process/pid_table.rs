@@ -9,7 +9,7 @@
 
 use alloc::collections::btree_map::Entry;
 
-use super::{Pgid, Pid, Process, ProcessGroup, Session, Sid};
+use super::{Pgid, Pid, PidNamespace, Process, ProcessGroup, Session, Sid};
 use crate::{
     prelude::*,
     process::posix_thread::AsPosixThread,
@@ -36,10 +36,12 @@ impl PidTable {
     }
 
     /// Returns the entry for the given ID, or creates a new one if absent.
-    fn get_or_create_entry(&mut self, id: u32) -> &Arc<PidEntry> {
+    ///
+    /// `pid_ns` is the PID namespace in which the ID was allocated.
+    fn get_or_create_entry(&mut self, id: u32, pid_ns: &Arc<PidNamespace>) -> &Arc<PidEntry> {
         self.entries
             .entry(id)
-            .or_insert_with(|| Arc::new(PidEntry::new()))
+            .or_insert_with(|| Arc::new(PidEntry::new(id, pid_ns.clone())))
     }
 
     // ---- Thread operations ----
@@ -51,7 +53,8 @@ impl PidTable {
     pub(super) fn insert_thread(&mut self, tid: Tid, thread: &Arc<Thread>) {
         debug_assert_eq!(tid, thread.as_posix_thread().unwrap().tid());
 
-        let mut entry = self.get_or_create_entry(tid).lock();
+        let pid_ns = thread.as_posix_thread().unwrap().process().pid_ns().clone();
+        let mut entry = self.get_or_create_entry(tid, &pid_ns).lock();
         debug_assert!(!entry.has_live_process());
 
         entry.set_thread(thread);
@@ -109,7 +112,8 @@ impl PidTable {
     pub(super) fn replace_thread(&mut self, tid: Tid, thread: &Arc<Thread>) {
         debug_assert_eq!(tid, thread.as_posix_thread().unwrap().tid());
 
-        let entry = self.get_or_create_entry(tid);
+        let pid_ns = thread.as_posix_thread().unwrap().process().pid_ns().clone();
+        let entry = self.get_or_create_entry(tid, &pid_ns);
         entry.lock().replace_thread(thread);
     }
 
@@ -134,7 +138,8 @@ impl PidTable {
         // `set_process` will assert the process slot is empty.
         self.process_count += 1;
 
-        let entry = self.get_or_create_entry(pid);
+        let entry = self.get_or_create_entry(pid, process.pid_ns());
+        process.set_pid_entry(entry);
         let mut entry = entry.lock();
         entry.set_process(process);
         entry.set_thread(&process.main_thread());
@@ -189,8 +194,11 @@ impl PidTable {
     // ---- Process group operations ----
 
     /// Inserts a process group into the table.
+    ///
+    /// The PGID is the PID of the group leader, so the entry already exists, except for the
+    /// bootstrap process group.
     pub(super) fn insert_process_group(&mut self, pgid: Pgid, group: &Arc<ProcessGroup>) {
-        let entry = self.get_or_create_entry(pgid);
+        let entry = self.get_or_create_entry(pgid, PidNamespace::get_init_singleton());
         entry.lock().set_process_group(group);
     }
 
@@ -229,8 +237,11 @@ impl PidTable {
     // ---- Session operations ----
 
     /// Inserts a session into the table.
+    ///
+    /// The SID is the PID of the session leader, so the entry already exists, except for the
+    /// bootstrap session.
     pub(super) fn insert_session(&mut self, sid: Sid, session: &Arc<Session>) {
-        let entry = self.get_or_create_entry(sid);
+        let entry = self.get_or_create_entry(sid, PidNamespace::get_init_singleton());
         entry.lock().set_session(session);
     }
 
@@ -278,6 +289,9 @@ impl PidTable {
 /// there will never be a `PidEntry` in the [`PidTable`] that is associated with
 /// a [`Process`], but at some intermediate moment has only an associated [`Thread`].
 pub(crate) struct PidEntry {
+    id: u32,
+    /// The PID namespace in which the ID was allocated.
+    pid_ns: Arc<PidNamespace>,
     inner: Mutex<PidEntryInner>,
 }
 
@@ -303,8 +317,10 @@ pub(crate) enum PidEntryType {
 
 impl PidEntry {
     /// Creates a new empty `PidEntry`.
-    fn new() -> Self {
+    fn new(id: u32, pid_ns: Arc<PidNamespace>) -> Self {
         Self {
+            id,
+            pid_ns,
             inner: Mutex::new(PidEntryInner::new()),
         }
     }
@@ -314,6 +330,11 @@ impl PidEntry {
         self.inner.lock()
     }
 
+    /// Returns the global ID of the entry.
+    pub(crate) fn id(&self) -> u32 {
+        self.id
+    }
+
     /// Returns the thread associated with the entry, if any.
     pub(crate) fn thread(&self) -> Option<Arc<Thread>> {
         self.lock().thread()
@@ -360,6 +381,14 @@ impl PidEntry {
     }
 }
 
+impl Drop for PidEntry {
+    fn drop(&mut self) {
+        // The ID is no longer used by any object, so the IDs in the PID namespaces can be
+        // released.
+        self.pid_ns.release_id(self.id);
+    }
+}
+
 impl PidEntryInner {
     /// Creates a new empty `PidEntryInner`.
     fn new() -> Self {
@@ -474,6 +503,11 @@ pub(crate) fn pid_table_mut() -> MutexGuard<'static, PidTable> {
     PID_TABLE.lock()
 }
 
+/// Tries to acquire a mutable reference to the global PID table without blocking.
+pub(crate) fn try_pid_table_mut() -> Option<MutexGuard<'static, PidTable>> {
+    PID_TABLE.try_lock()
+}
+
 /// Extension methods for `Weak<T>` values stored in `PidEntry`.
 ///
 /// In this file, `Weak::new()` is used as a sentinel that represents an empty
@@ -60,7 +60,7 @@ fn exit_internal(
     let thread_local = current_task.as_thread_local().unwrap();
     let posix_process = posix_thread.process();
 
-    let is_last_thread = {
+    let (is_last_thread, core_dump) = {
         let mut tasks = posix_process.tasks().lock();
         let has_exited_group = tasks.has_exited_group();
         let in_evecve = tasks.in_execve();
@@ -85,9 +85,17 @@ fn exit_internal(
         }
         current_thread.exit();
 
-        tasks.remove_exited(&current_task)
+        // If another thread is producing a core dump, it is waiting for this thread to report
+        // its state.
+        let core_dump = tasks.core_dump().cloned();
+
+        (tasks.remove_exited(&current_task), core_dump)
     };
 
+    if let Some(core_dump) = core_dump {
+        core_dump.report(ctx, user_ctx);
+    }
+
     // This is put after `current_thread.exit()`,
     // so `attach_tracee` will observe that the tracer has exited while
     // holding the `tracees` lock, and can not race with `clear_tracees`.
@@ -100,7 +108,9 @@ fn exit_internal(
 
     wake_clear_ctid(thread_local);
 
-    wake_robust_list(thread_local, posix_thread.tid());
+    // The robust futexes store the TID that is visible in the PID namespace of the thread.
+    let ns_tid = posix_process.pid_ns().ns_id_of(posix_thread.tid()).unwrap();
+    wake_robust_list(thread_local, ns_tid);
 
     // According to Linux behavior, the main thread shouldn't be removed from the table until the
     // process is reaped by its parent.
@@ -111,6 +121,7 @@ fn exit_internal(
     // Drop fields in `PosixThread`.
     drop_after!(posix_thread.file_table().lock().take());
     drop_after!(posix_thread.ns_proxy().lock().take());
+    drop_after!(posix_thread.seccomp().take_filter());
 
     // Drop fields in `ThreadLocal`.
     drop_after!(thread_local.vmar().borrow_mut().take());
@@ -0,0 +1,39 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::sync::atomic::Ordering;
+
+use aster_block::io_priority::{IoPriority, IoPriorityClass};
+
+use super::AsPosixThread;
+use crate::thread::Thread;
+
+pub(in crate::process) fn init() {
+    aster_block::io_priority::inject_io_priority_getter(current_io_priority);
+}
+
+/// Returns the effective I/O priority of the current thread.
+///
+/// If the thread has no I/O priority class set by `ioprio_set`, it gets the
+/// best-effort class with a level derived from the nice value of its process.
+/// Kernel threads always get the default I/O priority.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/linux/ioprio.h> (`task_nice_ioprio`)
+fn current_io_priority() -> IoPriority {
+    let Some(thread) = Thread::current() else {
+        return IoPriority::DEFAULT;
+    };
+    let Some(posix_thread) = thread.as_posix_thread() else {
+        return IoPriority::DEFAULT;
+    };
+
+    let raw = posix_thread.io_priority().load(Ordering::Relaxed);
+    if let Some(io_priority) = IoPriority::from_raw(raw)
+        && io_priority.class() != IoPriorityClass::None
+    {
+        return io_priority;
+    }
+
+    let nice = i8::from(posix_thread.process().nice().load(Ordering::Relaxed));
+    let level = (i16::from(nice) + 20) / 5;
+    IoPriority::new(IoPriorityClass::BestEffort, level as u8).unwrap()
+}
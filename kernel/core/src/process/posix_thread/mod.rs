@@ -24,6 +24,7 @@ use crate::{
         posix_thread::ptrace::TraceeStatus,
         signal::{PauseReason, PollHandle, sig_mask::SigMask},
     },
+    security::{lsm::ThreadLsm, seccomp::ThreadSeccomp},
     thread::{Thread, Tid},
     time::{Timer, TimerManager, clocks::ProfClock, timer::TimerGuard},
 };
@@ -33,6 +34,7 @@ mod builder;
 mod cpu_sync;
 mod exit;
 pub(crate) mod futex;
+pub(super) mod io_priority;
 mod personality;
 mod posix_thread_ext;
 pub(crate) mod ptrace;
@@ -106,6 +108,12 @@ pub(crate) struct PosixThread {
 
     /// The personality value for this thread.
     personality: AtomicU32,
+
+    /// Seccomp state.
+    seccomp: ThreadSeccomp,
+
+    /// LSM state.
+    lsm: ThreadLsm,
 }
 
 impl PosixThread {
@@ -307,6 +315,15 @@ impl PosixThread {
         self.credentials.dup().restrict()
     }
 
+    /// Sets the no-new-privileges flag of the thread.
+    ///
+    /// Unlike other credentials, the flag can be set by another thread in the same process when
+    /// synchronizing seccomp filters.
+    pub(crate) fn set_no_new_privs(&self) {
+        let credentials: Credentials<ReadWriteOp> = self.credentials.dup().restrict();
+        credentials.set_no_new_privs();
+    }
+
     /// Returns the I/O priority value of the thread.
     pub(crate) fn io_priority(&self) -> &AtomicU32 {
         &self.io_priority
@@ -342,6 +359,16 @@ impl PosixThread {
     pub(crate) fn exit_code(&self) -> ExitCode {
         self.exit_code.load(Ordering::Relaxed)
     }
+
+    /// Returns the seccomp state of this thread.
+    pub(crate) fn seccomp(&self) -> &ThreadSeccomp {
+        &self.seccomp
+    }
+
+    /// Returns the LSM state of this thread.
+    pub(crate) fn lsm(&self) -> &ThreadLsm {
+        &self.lsm
+    }
 }
 
 /// Provides administrative APIs for the current POSIX thread.
@@ -27,9 +27,10 @@ use crate::{
 
 mod util;
 
-pub(in crate::process) use util::PtraceEvent;
 use util::StopDeliverySignal;
-pub(crate) use util::{PtraceContRequest, PtraceOptions, PtraceStopResult, PtraceWaitStatus};
+pub(crate) use util::{
+    PtraceContRequest, PtraceEvent, PtraceOptions, PtraceStopResult, PtraceWaitStatus,
+};
 
 impl PosixThread {
     /// Returns whether this thread is being traced.
@@ -89,14 +90,18 @@ impl PosixThread {
     ///
     /// May block in the event-stop until the tracer continues the stop,
     /// or until a `SIGKILL` interrupts it.
-    pub(in crate::process) fn ptrace_may_stop_on(
+    ///
+    /// Returns a [`PtraceStopResult`] indicating why this ptrace-stop ended.
+    pub(crate) fn ptrace_may_stop_on(
         &self,
         event: PtraceEvent,
         ctx: &Context,
         user_ctx: &mut UserContext,
-    ) {
+    ) -> PtraceStopResult {
         if let Some(status) = self.tracee_status.get() {
             status.ptrace_may_stop_on(event, ctx, user_ctx)
+        } else {
+            PtraceStopResult::NotTraced(None)
         }
     }
 
@@ -407,12 +412,17 @@ impl TraceeStatus {
         self.do_ptrace_stop(state, tracer, signal, wait_status, None, ctx, user_ctx)
     }
 
-    fn ptrace_may_stop_on(&self, event: PtraceEvent, ctx: &Context, user_ctx: &mut UserContext) {
+    fn ptrace_may_stop_on(
+        &self,
+        event: PtraceEvent,
+        ctx: &Context,
+        user_ctx: &mut UserContext,
+    ) -> PtraceStopResult {
         // Hold the lock first to avoid race conditions.
         let state = self.state.lock();
 
         let Some(tracer) = state.tracer() else {
-            return;
+            return PtraceStopResult::NotTraced(None);
         };
 
         if !state.options.contains(event.option()) {
@@ -426,7 +436,7 @@ impl TraceeStatus {
                 ctx.posix_thread
                     .enqueue_signal(Box::new(UserSignal::new_kill(SIGTRAP, ctx)));
             }
-            return;
+            return PtraceStopResult::NotTraced(None);
         }
 
         let siginfo = event.siginfo(ctx);
@@ -442,7 +452,7 @@ impl TraceeStatus {
             Some(event),
             ctx,
             user_ctx,
-        );
+        )
     }
 
     fn ptrace_may_stop_on_syscall(
@@ -142,6 +142,8 @@ bitflags! {
         const PTRACE_O_TRACEVFORKDONE = 1 << PtraceEvent::VforkDone(0).code();
         /// Stops the tracee at `exit`.
         const PTRACE_O_TRACEEXIT = 1 << PtraceEvent::Exit(0).code();
+        /// Stops the tracee when a seccomp filter returns `SECCOMP_RET_TRACE`.
+        const PTRACE_O_TRACESECCOMP = 1 << PtraceEvent::Seccomp(0).code();
         /// Send a `SIGKILL` signal to the tracee if the tracer exits.
         const PTRACE_O_EXITKILL = 1 << 20;
     }
@@ -162,6 +164,8 @@ pub(crate) enum PtraceEvent {
     VforkDone(Tid),
     /// An `exit` event with the tracee's exit code.
     Exit(ExitCode),
+    /// A seccomp event with the `SECCOMP_RET_DATA` part of the filter's return value.
+    Seccomp(u16),
 }
 
 impl PtraceEvent {
@@ -174,6 +178,7 @@ impl PtraceEvent {
             Self::Exec(_) => 4,
             Self::VforkDone(_) => 5,
             Self::Exit(_) => 6,
+            Self::Seccomp(_) => 7,
         }
     }
 
@@ -191,6 +196,7 @@ impl PtraceEvent {
             | Self::Exec(tid)
             | Self::VforkDone(tid) => *tid as usize,
             Self::Exit(exit_code) => *exit_code as usize,
+            Self::Seccomp(data) => *data as usize,
         }
     }
 
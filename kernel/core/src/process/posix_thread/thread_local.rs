@@ -56,6 +56,10 @@ pub(crate) struct ThreadLocal {
     // Namespaces.
     user_ns: RefCell<Arc<UserNamespace>>,
     ns_proxy: RefCell<Option<Arc<NsProxy>>>,
+
+    // Memory cgroup.
+    /// The number of pages charged while the memory cgroup is over `memory.high`.
+    memcg_nr_pages_over_high: Cell<usize>,
 }
 
 impl ThreadLocal {
@@ -84,6 +88,7 @@ impl ThreadLocal {
             orig_syscall_ret: Cell::new(None),
             user_ns: RefCell::new(user_ns),
             ns_proxy: RefCell::new(Some(ns_proxy)),
+            memcg_nr_pages_over_high: Cell::new(0),
         }
     }
 
@@ -178,7 +183,7 @@ impl ThreadLocal {
 
     /// Returns the original syscall-return register value
     /// for the most recent kernel entry.
-    pub(in crate::process) fn orig_syscall_ret(&self) -> Option<usize> {
+    pub(crate) fn orig_syscall_ret(&self) -> Option<usize> {
         self.orig_syscall_ret.get()
     }
 
@@ -199,6 +204,10 @@ impl ThreadLocal {
     pub(in crate::process) fn borrow_ns_proxy_mut(&self) -> NsProxyRefMut<'_> {
         ThreadLocalOptionRefMut(self.ns_proxy.borrow_mut())
     }
+
+    pub(crate) fn memcg_nr_pages_over_high(&self) -> &Cell<usize> {
+        &self.memcg_nr_pages_over_high
+    }
 }
 
 /// Supplementary userspace CPU context.
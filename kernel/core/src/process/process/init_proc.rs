@@ -2,24 +2,27 @@
 
 //! This module defines functions related to spawning the init process.
 
-use ostd::{arch::cpu::context::UserContext, task::Task, user::UserContextApi};
+use ostd::{arch::cpu::context::UserContext, sync::RwArc, task::Task, user::UserContextApi};
 
 use super::{Process, Session};
 use crate::{
     fs::{
+        file::file_table::FileTable,
         thread_info::ThreadFsInfo,
         vfs::path::{Path, PathResolver},
     },
     prelude::*,
     process::{
-        Credentials, ProcessVm, ShebangScriptPath, UndetectedExecutable, UserNamespace, pid_table,
-        posix_thread::{PosixThreadBuilder, allocate_posix_tid, derive_thread_name},
+        Credentials, PidNamespace, ProcessVm, ShebangScriptPath, UndetectedExecutable,
+        UserNamespace, pid_table,
+        posix_thread::{PosixThreadBuilder, derive_thread_name},
         program_loader::ProgramToLoad,
         rlimit::new_resource_limits_for_init,
         signal::sig_disposition::SigDispositions,
     },
     sched::Nice,
     thread::Tid,
+    time::time_ns::TimeNamespace,
     vm::vmar::VmarHandle,
 };
 
@@ -54,7 +57,8 @@ fn create_init_process(
 ) -> Result<Arc<Process>> {
     let fs = ThreadFsInfo::new(path_resolver);
 
-    let pid = allocate_posix_tid();
+    let pid_ns = PidNamespace::get_init_singleton().clone();
+    let pid = pid_ns.allocate_id()?;
     let vmar = VmarHandle::new(ProcessVm::new(executable_path.clone()));
     let resource_limits = new_resource_limits_for_init();
     let nice = Nice::default();
@@ -70,9 +74,19 @@ fn create_init_process(
         oom_score_adj,
         sig_dispositions,
         user_ns,
+        pid_ns,
     );
 
-    let init_task = create_init_task(pid, &init_proc, fs, vmar, executable_path, argv, envp)?;
+    let init_task = create_root_task(
+        pid,
+        &init_proc,
+        fs,
+        FileTable::new(),
+        vmar,
+        executable_path,
+        argv,
+        envp,
+    )?;
     init_proc.tasks().lock().insert(init_task).unwrap();
 
     Ok(init_proc)
@@ -92,11 +106,15 @@ fn set_bootstrap_session_and_group(process: &Arc<Process>) {
     pid_table.insert_process(process.pid(), process);
 }
 
-/// Creates the init task from the given executable path.
-fn create_init_task(
+/// Creates a task that runs the given executable path with root credentials.
+///
+/// This is used for processes that are spawned by the kernel, e.g., the init process.
+#[expect(clippy::too_many_arguments)]
+pub(super) fn create_root_task(
     tid: Tid,
     process: &Arc<Process>,
     fs: ThreadFsInfo,
+    file_table: RwArc<FileTable>,
     vmar: VmarHandle,
     executable_path: Path,
     argv: Vec<CString>,
@@ -114,7 +132,11 @@ fn create_init_task(
         let program_to_load =
             ProgramToLoad::from_executable(executable, &path_resolver, argv, envp)?;
         let vmar = process.lock_vmar();
-        let elf_load_info = program_to_load.load_to_vmar(vmar.unwrap(), &path_resolver)?;
+        let elf_load_info = program_to_load.load_to_vmar(
+            vmar.unwrap(),
+            &path_resolver,
+            TimeNamespace::get_init_singleton(),
+        )?;
 
         (elf_load_info, executable_abs_path)
     };
@@ -128,6 +150,7 @@ fn create_init_task(
     let thread_builder =
         PosixThreadBuilder::new(tid, thread_name, Box::new(user_ctx), credentials, vmar)
             .process(Arc::downgrade(process))
-            .fs(Arc::new(fs));
+            .fs(Arc::new(fs))
+            .file_table(file_table);
     Ok(thread_builder.build())
 }
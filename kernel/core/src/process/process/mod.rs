@@ -6,11 +6,12 @@ use core::{
 };
 
 use ostd::timer::Jiffies;
+use spin::Once;
 
 use self::timer_manager::PosixTimerManager;
 use super::{
-    pid_table::{self, PidTable},
-    posix_thread::{AsPosixThread, FIRST_POSIX_TID},
+    pid_table::{self, PidEntry, PidTable},
+    posix_thread::AsPosixThread,
     process_vm::ProcessVmarGuard,
     rlimit::ResourceLimits,
     signal::{
@@ -26,7 +27,7 @@ use crate::{
     fs::cgroupfs::CgroupNode,
     prelude::*,
     process::{
-        UserNamespace, WaitOptions,
+        PidNamespace, UserNamespace, WaitOptions,
         signal::{Pollee, sig_queues::SigQueues},
         status::StopWaitStatus,
     },
@@ -42,6 +43,7 @@ mod process_group;
 mod session;
 mod terminal;
 mod timer_manager;
+mod usermode_helper;
 
 use atomic_integer_wrapper::define_atomic_version_of_integer_like_type;
 pub(crate) use init_proc::spawn_init_process;
@@ -53,13 +55,11 @@ use ostd::{
 pub(crate) use process_group::ProcessGroup;
 pub(crate) use session::Session;
 pub(crate) use terminal::Terminal;
+pub(crate) use usermode_helper::spawn_usermode_helper;
 
 /// Process ID.
 pub(crate) type Pid = u32;
 
-/// The PID of the init process.
-pub(crate) const INIT_PROCESS_PID: Pid = FIRST_POSIX_TID;
-
 define_atomic_version_of_integer_like_type!(Pid, {
     /// Atomic [`Pid`].
     #[derive(Debug)]
@@ -154,6 +154,13 @@ pub(crate) struct Process {
     // Namespaces
     /// The user namespace
     user_ns: Mutex<Arc<UserNamespace>>,
+    /// The PID namespace
+    pid_ns: Arc<PidNamespace>,
+    /// The entry of the process in the PID table.
+    ///
+    /// Holding the entry keeps the IDs of the process in the PID namespaces valid even after the
+    /// process is reaped, so that they can still be reported to the waiting parent.
+    pid_entry: Once<Arc<PidEntry>>,
 }
 
 impl Drop for Process {
@@ -234,8 +241,9 @@ impl Process {
         oom_score_adj: i16,
         sig_dispositions: Arc<Mutex<SigDispositions>>,
         user_ns: Arc<UserNamespace>,
+        pid_ns: Arc<PidNamespace>,
     ) -> Arc<Self> {
-        Arc::new_cyclic(|process_ref: &Weak<Process>| {
+        let process = Arc::new_cyclic(|process_ref: &Weak<Process>| {
             // SIGCHID does not interrupt pauser. Child process will
             // resume paused parent when doing exit.
             let children_wait_queue = WaitQueue::new();
@@ -268,8 +276,14 @@ impl Process {
                 timer_manager,
                 start_time: Jiffies::elapsed(),
                 user_ns: Mutex::new(user_ns),
+                pid_ns,
+                pid_entry: Once::new(),
             }
-        })
+        });
+
+        process.pid_ns.try_set_child_reaper(&process);
+
+        process
     }
 
     /// Runs the process.
@@ -623,6 +637,11 @@ impl Process {
         ProcessVmarGuard::new(self.vmar.lock())
     }
 
+    /// Tries to lock the VMAR of the process without blocking.
+    pub(crate) fn try_lock_vmar(&self) -> Option<ProcessVmarGuard<'_>> {
+        self.vmar.try_lock().map(ProcessVmarGuard::new)
+    }
+
     // ****************** Signal ******************
 
     pub(crate) fn sig_dispositions(&self) -> &Mutex<Arc<Mutex<SigDispositions>>> {
@@ -786,6 +805,16 @@ impl Process {
         &self.user_ns
     }
 
+    /// Returns the PID namespace of the process.
+    pub(crate) fn pid_ns(&self) -> &Arc<PidNamespace> {
+        &self.pid_ns
+    }
+
+    /// Records the entry of the process in the PID table.
+    pub(in crate::process) fn set_pid_entry(&self, pid_entry: &Arc<PidEntry>) {
+        self.pid_entry.call_once(|| pid_entry.clone());
+    }
+
     // ******************* cgroup ********************
 
     /// Returns a RCU read guard to the cgroup of the process.
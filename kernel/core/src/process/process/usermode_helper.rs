@@ -0,0 +1,84 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! This module defines functions related to spawning user-mode helper processes.
+
+use super::{Process, init_proc::create_root_task};
+use crate::{
+    fs::{
+        file::{
+            FileLike,
+            file_table::{FdFlags, FileTable},
+        },
+        thread_info::ThreadFsInfo,
+        vfs::path::FsPath,
+    },
+    prelude::*,
+    process::{
+        CloneFlags, NsProxy, PidNamespace, ProcessVm, UserNamespace,
+        clone::set_parent_and_group,
+        rlimit::ResourceLimits,
+        signal::{constants::SIGCHLD, sig_disposition::SigDispositions},
+    },
+    sched::Nice,
+    vm::vmar::VmarHandle,
+};
+
+/// Spawns a user-mode helper process to run the program at `path`.
+///
+/// The helper process runs with root credentials in the initial namespaces, as a child of the
+/// init process. Its standard input is `stdin`, and no other file descriptors are open.
+///
+/// This corresponds to `call_usermodehelper` in Linux.
+pub(crate) fn spawn_usermode_helper(
+    path: &str,
+    argv: Vec<CString>,
+    envp: Vec<CString>,
+    stdin: Arc<dyn FileLike>,
+    resource_limits: ResourceLimits,
+) -> Result<Arc<Process>> {
+    let Some(parent) = PidNamespace::get_init_singleton().child_reaper() else {
+        return_errno_with_message!(Errno::ESRCH, "the init process does not exist");
+    };
+
+    let fs = ThreadFsInfo::new(NsProxy::get_init_singleton().mnt_ns().new_path_resolver());
+    let executable_path = fs.resolver().read().lookup(&FsPath::try_from(path)?)?;
+
+    let file_table = FileTable::new();
+    file_table.write().insert(stdin, FdFlags::empty());
+
+    let pid_ns = PidNamespace::get_init_singleton().clone();
+    let pid = pid_ns.allocate_id()?;
+    let vmar = VmarHandle::new(ProcessVm::new(executable_path.clone()));
+    let sig_dispositions = Arc::new(Mutex::new(SigDispositions::default()));
+    let user_ns = UserNamespace::get_init_singleton().clone();
+
+    let process = Process::new(
+        pid,
+        vmar.clone_arc(),
+        resource_limits,
+        Nice::default(),
+        0,
+        sig_dispositions,
+        user_ns,
+        pid_ns,
+    );
+
+    let task = create_root_task(
+        pid,
+        &process,
+        fs,
+        file_table,
+        vmar,
+        executable_path,
+        argv,
+        envp,
+    )?;
+    process.tasks().lock().insert(task).unwrap();
+
+    process.set_exit_signal(SIGCHLD);
+    set_parent_and_group(CloneFlags::empty(), &parent, &process);
+
+    process.run();
+
+    Ok(process)
+}
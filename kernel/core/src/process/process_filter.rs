@@ -27,8 +27,8 @@ impl ProcessFilter {
 
         match which {
             P_ALL => Ok(ProcessFilter::Any),
-            P_PID => Ok(ProcessFilter::WithPid(id)),
-            P_PGID => Ok(ProcessFilter::WithPgid(id)),
+            P_PID => Ok(ProcessFilter::WithPid(to_global_id(id)?)),
+            P_PGID => Ok(ProcessFilter::WithPgid(to_global_id(id)?)),
             P_PIDFD => {
                 let fd = FileDesc::try_from(id.cast_signed())
                     .map_err(|_| Error::with_message(Errno::EINVAL, "the pidfd is invalid"))?;
@@ -56,7 +56,8 @@ impl ProcessFilter {
         } else if wait_pid < -1 {
             // "wait for any child process whose process group ID is equal to the absolute value of
             // `pid`"
-            Ok(ProcessFilter::WithPgid((-wait_pid).cast_unsigned()))
+            let pgid = to_global_id((-wait_pid).cast_unsigned())?;
+            Ok(ProcessFilter::WithPgid(pgid))
         } else if wait_pid == -1 {
             // "wait for any child process"
             Ok(ProcessFilter::Any)
@@ -67,7 +68,18 @@ impl ProcessFilter {
             Ok(ProcessFilter::WithPgid(pgid))
         } else {
             // "wait for the child whose process ID is equal to the value of `pid`"
-            Ok(ProcessFilter::WithPid(wait_pid.cast_unsigned()))
+            let pid = to_global_id(wait_pid.cast_unsigned())?;
+            Ok(ProcessFilter::WithPid(pid))
         }
     }
 }
+
+/// Translates an ID in the PID namespace of the current process to the global ID.
+fn to_global_id(id: u32) -> Result<u32> {
+    current!().pid_ns().global_id_of(id).ok_or_else(|| {
+        Error::with_message(
+            Errno::ESRCH,
+            "the ID does not exist in the current PID namespace",
+        )
+    })
+}
@@ -12,9 +12,12 @@
 mod heap;
 mod init_stack;
 
-use core::ops::Range;
 #[cfg(target_arch = "riscv64")]
-use core::sync::atomic::{AtomicUsize, Ordering};
+use core::sync::atomic::AtomicUsize;
+use core::{
+    ops::Range,
+    sync::atomic::{AtomicU8, AtomicU32, Ordering},
+};
 
 use ostd::task::disable_preempt;
 
@@ -28,8 +31,11 @@ pub(crate) use self::{
 use crate::{
     fs::vfs::path::Path,
     prelude::*,
+    process::coredump::{CoreDumpFilter, Dumpable},
     vm::vmar::{Vmar, VmarHandle},
 };
+#[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+use crate::{time::time_ns::TimeNamespace, vm::page_cache::Vmo};
 
 /*
  * The user's virtual memory space layout looks like below.
@@ -77,9 +83,16 @@ pub(crate) struct ProcessVm {
     data_range: SpinLock<Range<Vaddr>>,
     /// The path of the executable file.
     executable_path: Path,
+    /// Whether the process can produce core dumps (see [`Dumpable`]).
+    dumpable: AtomicU8,
+    /// The kinds of mappings to include in core dumps.
+    coredump_filter: AtomicU32,
     /// The base address for vDSO segment
     #[cfg(target_arch = "riscv64")]
     vdso_base: AtomicUsize,
+    /// The vDSO VMO mapped by the process, which depends on the time namespace.
+    #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+    vdso_vmo: SpinLock<Option<Arc<Vmo>>>,
 }
 
 impl ProcessVm {
@@ -91,8 +104,12 @@ impl ProcessVm {
             code_range: SpinLock::new(0..0),
             data_range: SpinLock::new(0..0),
             executable_path,
+            dumpable: AtomicU8::new(Dumpable::User as u8),
+            coredump_filter: AtomicU32::new(CoreDumpFilter::default().bits()),
             #[cfg(target_arch = "riscv64")]
             vdso_base: AtomicUsize::new(0),
+            #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+            vdso_vmo: SpinLock::new(None),
         }
     }
 
@@ -104,8 +121,12 @@ impl ProcessVm {
             code_range: SpinLock::new(process_vm.code_range.lock().clone()),
             data_range: SpinLock::new(process_vm.data_range.lock().clone()),
             executable_path: process_vm.executable_path.clone(),
+            dumpable: AtomicU8::new(process_vm.dumpable.load(Ordering::Relaxed)),
+            coredump_filter: AtomicU32::new(process_vm.coredump_filter.load(Ordering::Relaxed)),
             #[cfg(target_arch = "riscv64")]
             vdso_base: AtomicUsize::new(process_vm.vdso_base.load(Ordering::Relaxed)),
+            #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+            vdso_vmo: SpinLock::new(process_vm.vdso_vmo.lock().clone()),
         }
     }
 
@@ -134,6 +155,26 @@ impl ProcessVm {
         &self.executable_path
     }
 
+    /// Returns whether the process can produce core dumps.
+    pub(crate) fn dumpable(&self) -> Dumpable {
+        Dumpable::try_from(self.dumpable.load(Ordering::Relaxed)).unwrap()
+    }
+
+    /// Sets whether the process can produce core dumps.
+    pub(crate) fn set_dumpable(&self, dumpable: Dumpable) {
+        self.dumpable.store(dumpable as u8, Ordering::Relaxed);
+    }
+
+    /// Returns the kinds of mappings to include in core dumps.
+    pub(crate) fn coredump_filter(&self) -> CoreDumpFilter {
+        CoreDumpFilter::from_bits_truncate(self.coredump_filter.load(Ordering::Relaxed))
+    }
+
+    /// Sets the kinds of mappings to include in core dumps.
+    pub(crate) fn set_coredump_filter(&self, filter: CoreDumpFilter) {
+        self.coredump_filter.store(filter.bits(), Ordering::Relaxed);
+    }
+
     /// Maps and writes the initial portion of the main stack of a process.
     pub(super) fn map_and_write_init_stack(
         &self,
@@ -177,6 +218,38 @@ impl ProcessVm {
     pub(super) fn set_vdso_base(&self, addr: Vaddr) {
         self.vdso_base.store(addr, Ordering::Relaxed);
     }
+
+    /// Returns the vDSO VMO mapped by the process.
+    #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+    pub(crate) fn vdso_vmo(&self) -> Option<Arc<Vmo>> {
+        self.vdso_vmo.lock().clone()
+    }
+
+    /// Sets the vDSO VMO mapped by the process.
+    #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+    pub(super) fn set_vdso_vmo(&self, vdso_vmo: Arc<Vmo>) {
+        *self.vdso_vmo.lock() = Some(vdso_vmo);
+    }
+
+    /// Switches the mapped vDSO to the one of the time namespace.
+    ///
+    /// This method should be called when the process enters a time namespace without calling
+    /// `execve()`, so that the vDSO reports the time in the new time namespace. The VMAR must not
+    /// be shared with other processes.
+    #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+    pub(crate) fn switch_vdso(&self, vmar: &Vmar, time_ns: &TimeNamespace) -> Result<()> {
+        let (Some(old_vdso_vmo), Some(new_vdso_vmo)) = (self.vdso_vmo(), time_ns.vdso_vmo()) else {
+            return Ok(());
+        };
+        if Arc::ptr_eq(&old_vdso_vmo, &new_vdso_vmo) {
+            return Ok(());
+        }
+
+        vmar.replace_vmo_mappings(&old_vdso_vmo, &new_vdso_vmo)?;
+        self.set_vdso_vmo(new_vdso_vmo);
+
+        Ok(())
+    }
 }
 
 /// A guard to the [`Vmar`] used by a process.
@@ -22,6 +22,7 @@ use crate::{
         process_vm::{AuxKey, AuxVec},
         program_loader::open_executable_file,
     },
+    time::time_ns::TimeNamespace,
     util::random::getrandom,
     vm::{
         perms::VmPerms,
@@ -59,6 +60,11 @@ pub(crate) fn load_elf_to_vmar(
     elf_headers: ElfHeaders,
     argv: Vec<CString>,
     envp: Vec<CString>,
+    #[cfg_attr(
+        not(any(target_arch = "x86_64", target_arch = "riscv64")),
+        expect(unused_variables)
+    )]
+    time_ns: &TimeNamespace,
 ) -> Result<ElfLoadInfo> {
     let ldso = lookup_and_parse_ldso(&elf_headers, &elf_file, path_resolver)?;
 
@@ -77,7 +83,7 @@ pub(crate) fn load_elf_to_vmar(
     // Since the vDSO does not require being mapped to any specific address,
     // the vDSO is mapped after the ELF file, heap, and stack.
     #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
-    if let Some(vdso_text_base) = map_vdso_to_vmar(vmar) {
+    if let Some(vdso_text_base) = map_vdso_to_vmar(vmar, time_ns) {
         #[cfg(target_arch = "riscv64")]
         vmar.process_vm().set_vdso_base(vdso_text_base);
         aux_vec.set(AuxKey::AT_SYSINFO_EHDR, vdso_text_base as u64);
@@ -490,20 +496,21 @@ fn init_aux_vec(
     Ok(aux_vec)
 }
 
-/// Maps the vDSO VMO to the corresponding virtual memory address.
+/// Maps the vDSO VMO of the time namespace to the corresponding virtual memory address.
 #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
-fn map_vdso_to_vmar(vmar: &Vmar) -> Option<Vaddr> {
-    use crate::vdso::{VDSO_VMO_LAYOUT, vdso_vmo};
+fn map_vdso_to_vmar(vmar: &Vmar, time_ns: &TimeNamespace) -> Option<Vaddr> {
+    use crate::vdso::VDSO_VMO_LAYOUT;
 
-    let vdso_vmo = vdso_vmo()?;
+    let vdso_vmo = time_ns.vdso_vmo()?;
 
     let options = vmar
         .new_map(VDSO_VMO_LAYOUT.size, VmPerms::empty())
         .unwrap()
-        .vmo(vdso_vmo);
+        .vmo(vdso_vmo.clone());
 
     let vdso_vmo_base = options.build().unwrap();
     let vdso_data_base = vdso_vmo_base + VDSO_VMO_LAYOUT.data_segment_offset;
+    let vdso_time_ns_data_base = vdso_vmo_base + VDSO_VMO_LAYOUT.time_ns_data_segment_offset;
     let vdso_text_base = vdso_vmo_base + VDSO_VMO_LAYOUT.text_segment_offset;
 
     let data_perms = VmPerms::READ;
@@ -513,10 +520,17 @@ fn map_vdso_to_vmar(vmar: &Vmar) -> Option<Vaddr> {
         vdso_data_base..(vdso_data_base + VDSO_VMO_LAYOUT.data_segment_size),
     )
     .unwrap();
+    vmar.protect(
+        data_perms,
+        vdso_time_ns_data_base..(vdso_time_ns_data_base + VDSO_VMO_LAYOUT.data_segment_size),
+    )
+    .unwrap();
     vmar.protect(
         text_perms,
         vdso_text_base..(vdso_text_base + VDSO_VMO_LAYOUT.text_segment_size),
     )
     .unwrap();
+
+    vmar.process_vm().set_vdso_vmo(vdso_vmo);
     Some(vdso_text_base)
 }
@@ -17,6 +17,10 @@ use crate::{
         },
     },
     prelude::*,
+    process::posix_thread::AsPosixThread,
+    security::lsm::hooks as lsm_hooks,
+    thread::Thread,
+    time::time_ns::TimeNamespace,
     vm::vmar::Vmar,
 };
 
@@ -97,6 +101,11 @@ enum DetectedExecutable {
 /// Opens a path as an executable file.
 fn open_executable_file(path: Path) -> Result<Arc<dyn FileLike>> {
     check_executable_inode(path.inode().as_ref())?;
+    if let Some(current_thread) = Thread::current()
+        && let Some(posix_thread) = current_thread.as_posix_thread()
+    {
+        lsm_hooks::on_path_exec(lsm_hooks::PathContext::new(posix_thread, &path))?;
+    }
 
     let file: Arc<dyn FileLike> = Arc::new(InodeHandle::new_unchecked_access(
         path,
@@ -194,11 +203,15 @@ impl ProgramToLoad {
 
     /// Loads the executable into the specified virtual memory space.
     ///
+    /// The vDSO of `time_ns`, which is the time namespace that the process will be in, is also
+    /// mapped.
+    ///
     /// Returns the information about the ELF loading process.
     pub(super) fn load_to_vmar(
         self,
         vmar: &Vmar,
         path_resolver: &PathResolver,
+        time_ns: &TimeNamespace,
     ) -> Result<ElfLoadInfo> {
         load_elf_to_vmar(
             vmar,
@@ -207,6 +220,7 @@ impl ProgramToLoad {
             self.elf_headers,
             self.argv,
             self.envp,
+            time_ns,
         )
     }
 }
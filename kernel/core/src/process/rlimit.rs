@@ -172,7 +172,7 @@ impl RLimit64 {
 
     /// Sets the rlimit _without_ synchronization and permission check.
     ///
-    /// Only called during init process creation.
+    /// Only called when creating processes spawned by the kernel.
     #[track_caller]
     pub(self) fn set_raw_rlimit_unchecked(&self, new: RawRLimit64) {
         assert!(
@@ -228,3 +228,15 @@ pub(super) fn new_resource_limits_for_init() -> ResourceLimits {
         .set_raw_rlimit_unchecked(raw_rlimit);
     resource_limits
 }
+
+/// Creates resource limits for the user-mode helpers that receive core dumps.
+///
+/// Like Linux, `RLIMIT_CORE` is set to 1 in the helpers. This tells the core dump logic not to
+/// pipe the core dumps of the helpers to new helpers, which may recurse infinitely.
+pub(super) fn new_resource_limits_for_core_dump_helper() -> ResourceLimits {
+    let resource_limits = new_resource_limits_for_init();
+    resource_limits
+        .get_rlimit(ResourceType::RLIMIT_CORE)
+        .set_raw_rlimit_unchecked(RawRLimit64 { cur: 1, max: 1 });
+    resource_limits
+}
@@ -68,6 +68,10 @@ impl siginfo_t {
         self.set_pid_uid(ctx.process.pid(), ctx.posix_thread.credentials().ruid());
     }
 
+    pub(crate) fn set_value(&mut self, value: sigval_t) {
+        *self.siginfo_fields.common_mut().second.value_mut() = value;
+    }
+
     pub(crate) fn set_status(&mut self, status: i32) {
         *self
             .siginfo_fields
@@ -80,6 +84,14 @@ impl siginfo_t {
     pub(crate) fn si_addr(&self) -> Vaddr {
         self.siginfo_fields.sigfault().addr
     }
+
+    pub(crate) fn set_sigsys(&mut self, call_addr: Vaddr, syscall: i32, arch: u32) {
+        *self.siginfo_fields.sigsys_mut() = siginfo_sigsys_t {
+            call_addr,
+            syscall,
+            arch,
+        };
+    }
 }
 
 #[pod_union]
@@ -89,6 +101,7 @@ union siginfo_fields_t {
     bytes: [u8; 128 - size_of::<i32>() * 4],
     common: siginfo_common_t,
     sigfault: siginfo_sigfault_t,
+    sigsys: siginfo_sigsys_t,
 }
 
 impl Default for siginfo_fields_t {
@@ -192,6 +205,14 @@ union siginfo_addr_bnd_t {
     upper: Vaddr, // *const c_void,
 }
 
+#[repr(C)]
+#[derive(Clone, Copy, Pod)]
+struct siginfo_sigsys_t {
+    call_addr: Vaddr, //*const c_void
+    syscall: i32,
+    arch: u32,
+}
+
 /// Reference: <https://elixir.bootlin.com/linux/v6.15.7/source/include/uapi/asm-generic/ucontext.h#L5>
 #[cfg(target_arch = "x86_64")]
 #[repr(C)]
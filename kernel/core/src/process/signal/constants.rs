@@ -112,3 +112,5 @@ pub(crate) const TRAP_BRANCH: i32 = 3;
 pub(crate) const TRAP_HWBKPT: i32 = 4;
 pub(crate) const TRAP_UNK: i32 = 5;
 pub(crate) const TRAP_PERF: i32 = 6;
+
+pub(crate) const SYS_SECCOMP: i32 = 1;
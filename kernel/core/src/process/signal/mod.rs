@@ -15,7 +15,7 @@ pub(crate) mod signals;
 
 use align_ext::AlignExt;
 use c_types::{siginfo_t, ucontext_t};
-use constants::SIGSEGV;
+use constants::{SIGSEGV, SIGSTOP};
 use ostd::{
     arch::cpu::context::{FpuContext, UserContext},
     mm::VmIo,
@@ -35,6 +35,7 @@ use crate::{
     prelude::*,
     process::{
         TermStatus,
+        coredump::do_coredump,
         posix_thread::{ContextPthreadAdminApi, do_exit_group, ptrace::PtraceStopResult},
         signal::{c_types::stack_t, constants::SIGKILL},
     },
@@ -183,12 +184,30 @@ pub(crate) fn handle_pending_signal(user_ctx: &mut UserContext, ctx: &Context) {
             // "The only signals that can be sent to process ID 1, the init process, are those for
             // which init has explicitly installed signal handlers."
         }
+        SigAction::Dfl
+            if ctx.process.pid_ns().is_child_reaper(ctx.process)
+                && sig_num != SIGKILL
+                && sig_num != SIGSTOP =>
+        {
+            // The same applies to the init process of a PID namespace, except that `SIGKILL` and
+            // `SIGSTOP` sent from an ancestor PID namespace still take effect. Such signals sent
+            // from inside the namespace have been dropped by the sender.
+        }
         SigAction::Dfl => {
             let sig_default_action = SigDefaultAction::from_signum(sig_num);
             debug!("sig_default_action = {:?}", sig_default_action);
 
             match sig_default_action {
-                SigDefaultAction::Core | SigDefaultAction::Term => {
+                SigDefaultAction::Core => {
+                    warn!(
+                        "PID {}: dumping core on signal {}",
+                        ctx.process.pid(),
+                        sig_num.sig_name()
+                    );
+                    let term_status = do_coredump(sig_num, &signal.to_info(), ctx, user_ctx);
+                    do_exit_group(term_status, ctx, user_ctx);
+                }
+                SigDefaultAction::Term => {
                     warn!(
                         "PID {}: terminating on signal {}",
                         ctx.process.pid(),
@@ -6,7 +6,7 @@ use super::Signal;
 use crate::{
     context::Context,
     process::{
-        Pid, Uid,
+        Pid, PidNamespace, Uid,
         signal::{
             c_types::siginfo_t,
             constants::{SI_QUEUE, SI_TKILL, SI_USER},
@@ -70,8 +70,11 @@ impl Signal for UserSignal {
             UserSignalKind::Sigqueue => SI_QUEUE,
         };
 
+        // The PID of the sender is reported in the PID namespace of the receiver.
+        let pid = PidNamespace::current().ns_id_of(self.pid).unwrap_or(0);
+
         let mut info = siginfo_t::new(self.num, code);
-        info.set_pid_uid(self.pid, self.uid);
+        info.set_pid_uid(pid, self.uid);
 
         info
     }
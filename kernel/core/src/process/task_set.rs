@@ -7,6 +7,7 @@ use ostd::{
     task::{CurrentTask, Task},
 };
 
+use super::coredump::CoreDumpState;
 use crate::prelude::*;
 
 /// A task set that maintains all tasks in a POSIX process.
@@ -16,6 +17,7 @@ pub(crate) struct TaskSet {
     has_exited_group: bool,
     in_execve: bool,
     execve_waker: Option<Arc<Waker>>,
+    core_dump: Option<Arc<CoreDumpState>>,
 }
 
 impl TaskSet {
@@ -27,6 +29,7 @@ impl TaskSet {
             has_exited_group: false,
             in_execve: false,
             execve_waker: None,
+            core_dump: None,
         }
     }
 
@@ -130,6 +133,25 @@ impl TaskSet {
     pub(super) fn clear_execve_waker(&mut self) {
         self.execve_waker = None;
     }
+
+    /// Sets the state of a core dump that has been initiated.
+    ///
+    /// Exiting threads should report their states to the dumping thread via the state.
+    pub(super) fn start_core_dump(&mut self, state: Arc<CoreDumpState>) {
+        debug_assert!(self.has_exited_group && self.core_dump.is_none());
+        self.core_dump = Some(state);
+    }
+
+    /// Clears the state set by [`Self::start_core_dump`] to indicate that the core dump has
+    /// finished.
+    pub(super) fn finish_core_dump(&mut self) {
+        self.core_dump = None;
+    }
+
+    /// Returns the state of the ongoing core dump, if any.
+    pub(super) fn core_dump(&self) -> Option<&Arc<CoreDumpState>> {
+        self.core_dump.as_ref()
+    }
 }
 
 impl TaskSet {
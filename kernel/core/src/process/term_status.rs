@@ -2,10 +2,14 @@
 
 use super::signal::sig_num::SigNum;
 
+/// The flag in the wait status indicating that a core dump was produced.
+pub(crate) const CORE_DUMP_FLAG: u32 = 0x80;
+
 #[derive(Clone, Copy, Debug, Eq, PartialEq)]
 pub(crate) enum TermStatus {
     Exited(u8),
     Killed(SigNum),
+    CoreDumped(SigNum),
 }
 
 impl TermStatus {
@@ -14,6 +18,7 @@ impl TermStatus {
         match self {
             TermStatus::Exited(status) => (*status as u32) << 8,
             TermStatus::Killed(signum) => signum.as_u8() as u32,
+            TermStatus::CoreDumped(signum) => signum.as_u8() as u32 | CORE_DUMP_FLAG,
         }
     }
 }
@@ -7,8 +7,8 @@ mod stats;
 pub(crate) use self::{
     nice::{AtomicNice, Nice},
     sched_class::{
-        LinuxSchedPolicy, RealTimePolicy, RealTimePriority, SchedAttr, SchedPolicy, init,
-        init_on_each_cpu,
+        LinuxSchedPolicy, RealTimePolicy, RealTimePriority, SchedAttr, SchedPolicy, TaskGroup,
+        init, init_on_each_cpu,
     },
     stats::{loadavg, nr_queued_and_running},
 };
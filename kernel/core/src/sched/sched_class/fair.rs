@@ -1,14 +1,19 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{collections::BinaryHeap, sync::Arc};
+use alloc::{
+    collections::{BTreeMap, BinaryHeap},
+    sync::Arc,
+    vec::Vec,
+};
 use core::{
     cmp::{self, Reverse},
     sync::atomic::{AtomicU64, Ordering},
 };
 
 use ostd::{
+    arch::read_tsc as sched_clock,
     cpu::{CpuId, num_cpus},
-    sync::SpinLock,
+    sync::{LocalIrqDisabled, SpinLock},
     task::{
         Task,
         scheduler::{EnqueueFlags, UpdateFlags},
@@ -17,14 +22,15 @@ use ostd::{
 
 use super::{
     CurrentRuntime, SchedAttr, SchedClassRq,
-    time::{base_slice_clocks, min_period_clocks},
+    group::TaskGroup,
+    time::{base_slice_clocks, clocks_to_ns, min_period_clocks},
 };
 use crate::{
     sched::nice::{Nice, NiceValue},
     thread::AsThread,
 };
 
-const WEIGHT_0: u64 = 1024;
+pub(super) const WEIGHT_0: u64 = 1024;
 
 const HAS_PENDING: u64 = 1 << (u64::BITS - 1);
 
@@ -122,12 +128,21 @@ pub(crate) const fn nice_to_weight(nice: Nice) -> u64 {
 ///
 /// Most of the time, this mechanism allows the access to the weight lock-free and
 /// ensures that only one load is needed.
+///
+/// # Task groups
+///
+/// The thread may belong to task groups (see [`TaskGroup`]), which are looked up
+/// from its cgroup each time before it is woken up, and whenever it is moved to
+/// another cgroup. The groups are stored here because the lookup cannot be done
+/// with the run queue locked.
 #[derive(Debug)]
 pub(crate) struct FairAttr {
     // Updates to the `weight` field must be serialized with the `pending_weight` lock.
     weight: AtomicU64,
     pending_weight: SpinLock<u64>,
     vruntime: AtomicU64,
+    /// The task groups of the thread, from the innermost to the outermost.
+    groups: SpinLock<Vec<Arc<TaskGroup>>, LocalIrqDisabled>,
 }
 
 impl FairAttr {
@@ -137,9 +152,17 @@ impl FairAttr {
             weight: weight.into(),
             pending_weight: SpinLock::new(weight),
             vruntime: Default::default(),
+            groups: SpinLock::new(Vec::new()),
         }
     }
 
+    /// Sets the task groups of the thread, from the innermost to the outermost.
+    ///
+    /// The new groups take effect the next time the thread is enqueued.
+    pub(crate) fn set_groups(&self, groups: Vec<Arc<TaskGroup>>) {
+        let _old_groups = core::mem::replace(&mut *self.groups.lock(), groups);
+    }
+
     pub(crate) fn update(&self, nice: Nice) {
         let mut pending_weight = self.pending_weight.lock();
         *pending_weight = nice_to_weight(nice);
@@ -177,11 +200,25 @@ impl FairAttr {
     }
 }
 
-/// The wrapper for threads in the FAIR run queue.
+/// The key of a task group in the FAIR run queues, which is the address of the
+/// [`TaskGroup`].
+type GroupKey = usize;
+
+fn group_key(group: &Arc<TaskGroup>) -> GroupKey {
+    Arc::as_ptr(group) as GroupKey
+}
+
+/// An entity in a FAIR run queue, which is either a thread or a task group.
+enum FairEntity {
+    Task(Arc<Task>),
+    Group(GroupKey),
+}
+
+/// The wrapper for entities in the FAIR run queue.
 ///
 /// This structure is used to provide the capability for keying in the
-/// run queue implemented by `BTreeSet` in the `FairClassRq`.
-struct FairQueueItem(Arc<Task>, u64);
+/// run queue implemented by `BinaryHeap` in the `FairRq`.
+struct FairQueueItem(FairEntity, u64);
 
 impl core::fmt::Debug for FairQueueItem {
     fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
@@ -215,31 +252,138 @@ impl Ord for FairQueueItem {
     }
 }
 
+/// A run queue of entities, which belongs to either the root or a task group
+/// on a CPU.
+#[derive(Debug, Default)]
+struct FairRq {
+    /// The ready-to-run entities.
+    entities: BinaryHeap<Reverse<FairQueueItem>>,
+    /// The minimum of vruntime in the run queue. Serves as the initial
+    /// value of newly-enqueued entities.
+    min_vruntime: u64,
+    total_weight: u64,
+    /// The number of ready-to-run threads in the run queue, including the ones
+    /// in the descendant groups that are not throttled.
+    nr_tasks: usize,
+}
+
+impl FairRq {
+    /// The virtual time slice for each entity in the run queue, measured in vruntime clocks.
+    fn vtime_slice(&self, period: u64) -> u64 {
+        period / (self.entities.len() + 1) as u64
+    }
+
+    /// The time slice for each entity in the run queue, measured in sched clocks.
+    fn time_slice(&self, period: u64, cur_weight: u64) -> u64 {
+        period * cur_weight / (self.total_weight + cur_weight)
+    }
+
+    fn push(&mut self, entity: FairEntity, vruntime: u64, weight: u64) {
+        self.total_weight += weight;
+        self.entities.push(Reverse(FairQueueItem(entity, vruntime)));
+    }
+
+    /// Updates the run queue with the vruntime of its running entity.
+    ///
+    /// Returns whether the running entity should be preempted by another
+    /// entity in the run queue.
+    fn update_current(
+        &mut self,
+        vruntime: u64,
+        weight: u64,
+        period_delta: u64,
+        period: u64,
+    ) -> bool {
+        let leftmost = self.entities.peek();
+        self.min_vruntime = match leftmost {
+            Some(Reverse(leftmost)) => vruntime.min(leftmost.key()),
+            None => vruntime,
+        };
+        if leftmost.is_none() {
+            return false;
+        }
+
+        period_delta > self.time_slice(period, weight)
+            || vruntime > self.min_vruntime + self.vtime_slice(period)
+    }
+}
+
+/// A task group on a CPU.
+///
+/// The group is scheduled as an entity in the run queue of its parent (or the
+/// root), and its own run queue holds the threads and the child groups.
+#[derive(Debug)]
+struct GroupEntity {
+    group: Arc<TaskGroup>,
+    /// The parent group, or `None` if the parent is the root.
+    parent: Option<GroupKey>,
+    rq: FairRq,
+    vruntime: u64,
+    /// The weight of the entity in the run queue of its parent.
+    weight: u64,
+    /// The number of threads of the group on the CPU, including the running one.
+    nr_running: usize,
+    /// Whether the entity is in the run queue of its parent.
+    is_queued: bool,
+    /// Whether a thread of the group is running on the CPU.
+    is_running: bool,
+    /// The time when the group was throttled, if it is throttled.
+    throttled_since: Option<u64>,
+}
+
+impl GroupEntity {
+    fn new(group: Arc<TaskGroup>, parent: Option<GroupKey>) -> Self {
+        Self {
+            group,
+            parent,
+            rq: FairRq::default(),
+            vruntime: 0,
+            weight: WEIGHT_0,
+            nr_running: 0,
+            is_queued: false,
+            is_running: false,
+            throttled_since: None,
+        }
+    }
+
+    fn is_throttled(&self) -> bool {
+        self.throttled_since.is_some()
+    }
+
+    fn update_vruntime(&mut self, delta: u64) -> u64 {
+        self.vruntime += delta * WEIGHT_0 / self.weight;
+        self.vruntime
+    }
+}
+
 /// The per-cpu run queue for the FAIR scheduling class.
 ///
 /// See [`FairAttr`] for the explanation of vruntimes and scheduling periods.
 ///
-/// The structure contains a `BTreeSet` to store the threads in the run queue to
-/// ensure the efficiency for finding next-to-run threads.
+/// The threads are scheduled hierarchically by their task groups (see
+/// [`TaskGroup`]). The root run queue holds the threads that do not belong to
+/// any task group and the outermost task groups. When a task group is picked,
+/// the next entity is picked from the run queue of the group, until a thread
+/// is reached. Each run queue uses a `BinaryHeap` to ensure the efficiency for
+/// finding next-to-run entities.
 #[derive(Debug)]
 pub(super) struct FairClassRq {
     #[expect(unused)]
     cpu: CpuId,
-    /// The ready-to-run threads.
-    entities: BinaryHeap<Reverse<FairQueueItem>>,
-    /// The minimum of vruntime in the run queue. Serves as the initial
-    /// value of newly-enqueued threads.
-    min_vruntime: u64,
-    total_weight: u64,
+    root: FairRq,
+    /// The task groups with threads on this CPU.
+    groups: BTreeMap<GroupKey, GroupEntity>,
+    /// The thread last picked from this run queue and its innermost group.
+    current: Option<(Arc<Task>, Option<GroupKey>)>,
 }
 
 impl FairClassRq {
     pub(crate) fn new(cpu: CpuId) -> Self {
         Self {
             cpu,
-            entities: BinaryHeap::new(),
-            min_vruntime: 0,
-            total_weight: 0,
+            root: FairRq::default(),
+            groups: BTreeMap::new(),
+            current: None,
         }
     }
 
@@ -262,27 +406,180 @@ impl FairClassRq {
 
         // `+ 1` means including the current running thread.
         let period_single_cpu =
-            (base_slice_clks * (self.entities.len() + 1) as u64).max(min_period_clks);
+            (base_slice_clks * (self.root.nr_tasks + 1) as u64).max(min_period_clks);
         period_single_cpu * u64::from((1 + num_cpus()).ilog2())
     }
 
-    /// The virtual time slice for each thread in the run queue, measured in vruntime clocks.
-    fn vtime_slice(&self) -> u64 {
-        self.period() / (self.entities.len() + 1) as u64
+    fn rq_mut(&mut self, key: Option<GroupKey>) -> &mut FairRq {
+        match key {
+            Some(key) => &mut self.group_mut(key).rq,
+            None => &mut self.root,
+        }
     }
 
-    /// The time slice for each thread in the run queue, measured in sched clocks.
-    fn time_slice(&self, cur_weight: u64) -> u64 {
-        self.period() * cur_weight / (self.total_weight + cur_weight)
+    fn group_mut(&mut self, key: GroupKey) -> &mut GroupEntity {
+        self.groups.get_mut(&key).unwrap()
+    }
+
+    /// Finds or creates the entities of the task groups, which are ordered
+    /// from the innermost to the outermost.
+    ///
+    /// Returns the key of the innermost group.
+    fn attach_groups(&mut self, groups: &[Arc<TaskGroup>]) -> Option<GroupKey> {
+        let mut parent = None;
+        for group in groups.iter().rev() {
+            let key = group_key(group);
+            self.groups
+                .entry(key)
+                .or_insert_with(|| GroupEntity::new(group.clone(), parent));
+            parent = Some(key);
+        }
+        parent
+    }
+
+    /// Adds `delta` to the number of ready-to-run threads in the run queue of
+    /// the group and its ancestors.
+    ///
+    /// The threads in a throttled group are not counted by its ancestors.
+    fn add_nr_tasks(&mut self, mut key: Option<GroupKey>, delta: isize) {
+        loop {
+            let rq = self.rq_mut(key);
+            rq.nr_tasks = rq.nr_tasks.wrapping_add_signed(delta);
+
+            let Some(group_key) = key else {
+                return;
+            };
+            let group = &self.groups[&group_key];
+            if group.is_throttled() {
+                return;
+            }
+            key = group.parent;
+        }
+    }
+
+    /// Enqueues the entities of the group and its ancestors into the run
+    /// queues of their parents if they have ready-to-run entities.
+    fn enqueue_groups(&mut self, mut key: Option<GroupKey>) {
+        while let Some(group_key) = key {
+            let group = self.group_mut(group_key);
+            key = group.parent;
+            if group.is_queued
+                || group.is_running
+                || group.is_throttled()
+                || group.rq.entities.is_empty()
+            {
+                continue;
+            }
+
+            group.weight = group.group.entity_weight(group.nr_running);
+            group.is_queued = true;
+            let (vruntime, weight) = (group.vruntime, group.weight);
+
+            let parent_rq = self.rq_mut(key);
+            let vruntime = vruntime.max(parent_rq.min_vruntime);
+            parent_rq.push(FairEntity::Group(group_key), vruntime, weight);
+            self.group_mut(group_key).vruntime = vruntime;
+        }
+    }
+
+    /// Puts back the groups of the thread last picked from this run queue,
+    /// which is no longer running.
+    fn put_prev(&mut self) {
+        let Some((_, leaf)) = self.current.take() else {
+            return;
+        };
+
+        let mut key = leaf;
+        while let Some(group_key) = key {
+            let group = self.group_mut(group_key);
+            group.is_running = false;
+            group.nr_running -= 1;
+            group.group.dec_nr_running();
+            key = group.parent;
+        }
+
+        self.enqueue_groups(leaf);
+
+        // Remove the groups that have no threads on this CPU. The ancestors of
+        // a group have at least as many threads as the group.
+        let now = now_ns();
+        let mut key = leaf;
+        while let Some(group_key) = key {
+            if self.groups[&group_key].nr_running > 0 {
+                break;
+            }
+            let group = self.groups.remove(&group_key).unwrap();
+            if let Some(throttled_since) = group.throttled_since {
+                group
+                    .group
+                    .record_unthrottled(now.saturating_sub(throttled_since));
+            }
+            key = group.parent;
+        }
+    }
+
+    fn throttle_group(&mut self, key: GroupKey, now: u64) {
+        let group = self.group_mut(key);
+        group.throttled_since = Some(now);
+        group.group.record_throttled();
+
+        let (parent, nr_tasks) = (group.parent, group.rq.nr_tasks);
+        self.add_nr_tasks(parent, -(nr_tasks as isize));
+    }
+
+    fn unthrottle_group(&mut self, key: GroupKey, now: u64) {
+        let group = self.group_mut(key);
+        let throttled_since = group.throttled_since.take().unwrap();
+        group
+            .group
+            .record_unthrottled(now.saturating_sub(throttled_since));
+
+        let (parent, nr_tasks) = (group.parent, group.rq.nr_tasks);
+        self.add_nr_tasks(parent, nr_tasks as isize);
+        self.enqueue_groups(Some(key));
+    }
+
+    /// Unthrottles the groups that have CPU time in a new period.
+    pub(super) fn unthrottle_groups(&mut self) {
+        let now = now_ns();
+        while let Some(key) = self
+            .groups
+            .iter()
+            .find(|(_, group)| group.is_throttled() && group.group.has_runtime(now))
+            .map(|(key, _)| *key)
+        {
+            self.unthrottle_group(key, now);
+        }
     }
 }
 
 impl SchedClassRq for FairClassRq {
     fn enqueue(&mut self, entity: Arc<Task>, flags: Option<EnqueueFlags>) {
+        // The thread last picked from this run queue is put back.
+        if self
+            .current
+            .as_ref()
+            .is_some_and(|(task, _)| Arc::ptr_eq(task, &entity))
+        {
+            self.put_prev();
+        }
+
         let fair_attr = &entity.as_thread().unwrap().sched_attr().fair;
+        let leaf = self.attach_groups(&fair_attr.groups.lock());
+
+        let mut key = leaf;
+        while let Some(group_key) = key {
+            let group = self.group_mut(group_key);
+            group.nr_running += 1;
+            group.group.inc_nr_running();
+            key = group.parent;
+        }
+
+        let period = self.period();
+        let rq = self.rq_mut(leaf);
         let vruntime = match flags {
-            Some(EnqueueFlags::Spawn) => self.min_vruntime + self.vtime_slice(),
-            _ => self.min_vruntime,
+            Some(EnqueueFlags::Spawn) => rq.min_vruntime + rq.vtime_slice(period),
+            _ => rq.min_vruntime,
         };
         let (_old_weight, weight) = fair_attr.fetch_weight();
 
@@ -291,30 +588,76 @@ impl SchedClassRq for FairClassRq {
             .fetch_max(vruntime, Ordering::Relaxed)
             .max(vruntime);
 
-        self.total_weight += weight;
-        self.entities.push(Reverse(FairQueueItem(entity, vruntime)));
+        rq.push(FairEntity::Task(entity), vruntime, weight);
+        self.add_nr_tasks(leaf, 1);
+        self.enqueue_groups(leaf);
     }
 
     fn len(&self) -> usize {
-        self.entities.len()
+        self.root.nr_tasks
     }
 
     fn is_empty(&self) -> bool {
-        self.entities.is_empty()
+        self.root.nr_tasks == 0
     }
 
     fn pick_next(&mut self) -> Option<Arc<Task>> {
-        let Reverse(FairQueueItem(entity, _)) = self.entities.pop()?;
-
-        let sched_attr = entity.as_thread().unwrap().sched_attr();
-        let (old_weight, _weight) = sched_attr.fair.fetch_weight();
-        // Equals to:
-        //
-        // self.total_weight = self.total_weight + weight - old_weight;
-        // self.total_weight -= weight;
-        self.total_weight -= old_weight;
+        self.put_prev();
+
+        let now = now_ns();
+        let mut key = None;
+        loop {
+            let Some(Reverse(FairQueueItem(entity, _))) = self.rq_mut(key).entities.pop() else {
+                if key.is_none() {
+                    return None;
+                }
+                // A queued group should always have ready-to-run entities.
+                // Otherwise, pick again from the root.
+                key = None;
+                continue;
+            };
 
-        Some(entity)
+            match entity {
+                FairEntity::Task(task) => {
+                    let sched_attr = task.as_thread().unwrap().sched_attr();
+                    let (old_weight, _weight) = sched_attr.fair.fetch_weight();
+                    // Equals to:
+                    //
+                    // self.total_weight = self.total_weight + weight - old_weight;
+                    // self.total_weight -= weight;
+                    self.rq_mut(key).total_weight -= old_weight;
+                    self.add_nr_tasks(key, -1);
+
+                    let mut group_key = key;
+                    while let Some(running_key) = group_key {
+                        let group = self.group_mut(running_key);
+                        group.is_running = true;
+                        group_key = group.parent;
+                    }
+
+                    self.current = Some((task.clone(), key));
+                    return Some(task);
+                }
+                FairEntity::Group(group_key) => {
+                    let group = self.group_mut(group_key);
+                    group.is_queued = false;
+                    let weight = group.weight;
+                    let has_runtime = group.group.has_runtime(now);
+                    self.rq_mut(key).total_weight -= weight;
+
+                    if has_runtime {
+                        key = Some(group_key);
+                        continue;
+                    }
+
+                    // The group has used up its quota. Throttle it and pick again
+                    // from the root.
+                    self.throttle_group(group_key, now);
+                    self.enqueue_groups(key);
+                    key = None;
+                }
+            }
+        }
     }
 
     fn update_current(
@@ -327,20 +670,50 @@ impl SchedClassRq for FairClassRq {
             UpdateFlags::Tick | UpdateFlags::Yield | UpdateFlags::Wait => {
                 let (_old_weight, weight) = attr.fair.fetch_weight();
                 let vruntime = attr.fair.update_vruntime(rt.delta, weight);
-                let leftmost = self.entities.peek();
-                self.min_vruntime = match leftmost {
-                    Some(Reverse(leftmost)) => vruntime.min(leftmost.key()),
-                    None => vruntime,
+
+                // The groups are charged only if the current thread is picked
+                // from this run queue.
+                let leaf = match &self.current {
+                    Some((task, leaf))
+                        if core::ptr::eq(task.as_thread().unwrap().sched_attr(), attr) =>
+                    {
+                        *leaf
+                    }
+                    _ => None,
                 };
-                if leftmost.is_none() {
-                    return false;
+
+                let period = self.period();
+                let mut should_preempt =
+                    self.rq_mut(leaf)
+                        .update_current(vruntime, weight, rt.period_delta, period);
+
+                let now = now_ns();
+                let delta_ns = clocks_to_ns(rt.delta);
+                let mut key = leaf;
+                while let Some(group_key) = key {
+                    let group = self.group_mut(group_key);
+                    group.weight = group.group.entity_weight(group.nr_running);
+                    let vruntime = group.update_vruntime(rt.delta);
+                    let weight = group.weight;
+                    key = group.parent;
+
+                    if !group.group.consume_runtime(now, delta_ns) && !group.is_throttled() {
+                        self.throttle_group(group_key, now);
+                        should_preempt = true;
+                    }
+                    should_preempt |=
+                        self.rq_mut(key)
+                            .update_current(vruntime, weight, rt.period_delta, period);
                 }
 
-                matches!(flags, UpdateFlags::Wait)
-                    || rt.period_delta > self.time_slice(weight)
-                    || vruntime > self.min_vruntime + self.vtime_slice()
+                should_preempt || (matches!(flags, UpdateFlags::Wait) && !self.is_empty())
             }
             UpdateFlags::Exit => !self.is_empty(),
         }
     }
 }
+
+/// Returns the current time in nanoseconds for the CPU bandwidth control.
+fn now_ns() -> u64 {
+    clocks_to_ns(sched_clock())
+}
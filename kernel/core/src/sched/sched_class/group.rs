@@ -0,0 +1,220 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Task groups for the FAIR scheduling class.
+//!
+//! A task group corresponds to a cgroup with the CPU controller enabled. The
+//! CPU time is distributed among sibling task groups in proportion to their
+//! weights, and then among the threads and child groups within each group.
+//! The CPU time of a task group can also be limited by a bandwidth quota in
+//! each period, in which case the group is throttled once the quota runs out.
+
+use core::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
+
+use ostd::sync::{LocalIrqDisabled, SpinLock};
+
+use super::fair::WEIGHT_0;
+
+/// A group of threads that share CPU time in the FAIR scheduling class.
+#[derive(Debug)]
+pub(crate) struct TaskGroup {
+    /// The weight of the group, as shown in cgroup `cpu.weight`.
+    weight: AtomicU32,
+    /// The number of threads of the group in the FAIR run queues of all CPUs,
+    /// including the running ones.
+    nr_running: AtomicUsize,
+    /// The CPU bandwidth limit and its statistics.
+    bandwidth: SpinLock<Bandwidth, LocalIrqDisabled>,
+}
+
+/// The CPU bandwidth limit of a task group.
+#[derive(Debug)]
+struct Bandwidth {
+    /// The CPU time that the group can use in each period, or `u64::MAX` if unlimited.
+    quota_usec: u64,
+    /// The length of a period.
+    period_usec: u64,
+    /// The remaining CPU time in the current period.
+    runtime_ns: i64,
+    /// The start time of the current period.
+    period_start_ns: u64,
+    /// Whether the group has been throttled in the current period.
+    is_throttled_in_period: bool,
+    nr_periods: u64,
+    nr_throttled: u64,
+    throttled_ns: u64,
+}
+
+/// The statistics of the CPU bandwidth limit of a task group.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct BandwidthStats {
+    /// The number of elapsed periods in which the group is runnable.
+    pub(crate) nr_periods: u64,
+    /// The number of periods in which the group is throttled.
+    pub(crate) nr_throttled: u64,
+    /// The total time that the threads of the group are throttled.
+    pub(crate) throttled_usec: u64,
+}
+
+/// The minimum weight of a task group in a run queue.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/kernel/sched/sched.h> (`MIN_SHARES`)
+const MIN_SHARES: u64 = 2;
+
+impl TaskGroup {
+    /// Creates a task group with the default weight and no bandwidth limit.
+    pub(crate) fn new() -> Self {
+        // The default CPU weight is 100 and the default CPU bandwidth limit is
+        // unlimited quota with a 100ms period.
+        //
+        // Reference: <https://docs.kernel.org/admin-guide/cgroup-v2.html#cpu-interface-files>
+        const DEFAULT_WEIGHT: u32 = 100;
+        const DEFAULT_PERIOD_USEC: u64 = 100_000;
+
+        Self {
+            weight: AtomicU32::new(DEFAULT_WEIGHT),
+            nr_running: AtomicUsize::new(0),
+            bandwidth: SpinLock::new(Bandwidth {
+                quota_usec: u64::MAX,
+                period_usec: DEFAULT_PERIOD_USEC,
+                runtime_ns: 0,
+                period_start_ns: 0,
+                is_throttled_in_period: false,
+                nr_periods: 0,
+                nr_throttled: 0,
+                throttled_ns: 0,
+            }),
+        }
+    }
+
+    /// Returns the weight of the group.
+    pub(crate) fn weight(&self) -> u32 {
+        self.weight.load(Ordering::Relaxed)
+    }
+
+    /// Sets the weight of the group.
+    ///
+    /// The new weight takes effect the next time the group is enqueued or
+    /// charged for running.
+    pub(crate) fn set_weight(&self, weight: u32) {
+        self.weight.store(weight, Ordering::Relaxed);
+    }
+
+    /// Returns the bandwidth limit of the group as `(quota_usec, period_usec)`.
+    ///
+    /// The quota is `u64::MAX` if the bandwidth is unlimited.
+    pub(crate) fn max(&self) -> (u64, u64) {
+        let bandwidth = self.bandwidth.lock();
+        (bandwidth.quota_usec, bandwidth.period_usec)
+    }
+
+    /// Sets the bandwidth limit of the group.
+    ///
+    /// If `period_usec` is `None`, the period is kept unchanged. A new period
+    /// starts the next time the group is charged for running.
+    pub(crate) fn set_max(&self, quota_usec: u64, period_usec: Option<u64>) {
+        let mut bandwidth = self.bandwidth.lock();
+        bandwidth.quota_usec = quota_usec;
+        if let Some(period_usec) = period_usec {
+            bandwidth.period_usec = period_usec;
+        }
+        bandwidth.period_start_ns = 0;
+        bandwidth.runtime_ns = 0;
+    }
+
+    /// Returns the statistics of the bandwidth limit.
+    pub(crate) fn bandwidth_stats(&self) -> BandwidthStats {
+        let bandwidth = self.bandwidth.lock();
+        BandwidthStats {
+            nr_periods: bandwidth.nr_periods,
+            nr_throttled: bandwidth.nr_throttled,
+            throttled_usec: bandwidth.throttled_ns / 1000,
+        }
+    }
+
+    /// Returns the weight of a group entity on a CPU with `nr_running` threads
+    /// of the group.
+    ///
+    /// The weight of the group is distributed among the CPUs in proportion to
+    /// the number of threads of the group on each CPU.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/kernel/sched/fair.c> (`calc_group_shares`)
+    pub(super) fn entity_weight(&self, nr_running: usize) -> u64 {
+        // The default weight 100 is equivalent to the weight of a thread with nice 0.
+        let shares = WEIGHT_0 * u64::from(self.weight()) / 100;
+        let nr_total = self
+            .nr_running
+            .load(Ordering::Relaxed)
+            .max(nr_running)
+            .max(1);
+
+        (shares * nr_running as u64 / nr_total as u64).clamp(MIN_SHARES, shares)
+    }
+
+    pub(super) fn inc_nr_running(&self) {
+        self.nr_running.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub(super) fn dec_nr_running(&self) {
+        self.nr_running.fetch_sub(1, Ordering::Relaxed);
+    }
+
+    /// Charges `delta_ns` of CPU time to the group.
+    ///
+    /// Returns `false` if the group has used up its quota in the current period
+    /// and should be throttled.
+    pub(super) fn consume_runtime(&self, now_ns: u64, delta_ns: u64) -> bool {
+        let mut bandwidth = self.bandwidth.lock();
+        if bandwidth.quota_usec == u64::MAX {
+            return true;
+        }
+
+        bandwidth.refresh(now_ns);
+        bandwidth.runtime_ns = bandwidth
+            .runtime_ns
+            .saturating_sub(i64::try_from(delta_ns).unwrap_or(i64::MAX));
+        bandwidth.runtime_ns > 0
+    }
+
+    /// Returns whether the group has CPU time left in the current period.
+    pub(super) fn has_runtime(&self, now_ns: u64) -> bool {
+        let mut bandwidth = self.bandwidth.lock();
+        if bandwidth.quota_usec == u64::MAX {
+            return true;
+        }
+
+        bandwidth.refresh(now_ns);
+        bandwidth.runtime_ns > 0
+    }
+
+    /// Records that the group is throttled on a CPU.
+    pub(super) fn record_throttled(&self) {
+        let mut bandwidth = self.bandwidth.lock();
+        if !bandwidth.is_throttled_in_period {
+            bandwidth.is_throttled_in_period = true;
+            bandwidth.nr_throttled += 1;
+        }
+    }
+
+    /// Records that the group has been throttled on a CPU for `throttled_ns`.
+    pub(super) fn record_unthrottled(&self, throttled_ns: u64) {
+        let mut bandwidth = self.bandwidth.lock();
+        bandwidth.throttled_ns += throttled_ns;
+    }
+}
+
+impl Bandwidth {
+    /// Starts a new period and refills the quota if the current period has ended.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/kernel/sched/fair.c> (`__refill_cfs_bandwidth_runtime`)
+    fn refresh(&mut self, now_ns: u64) {
+        let period_ns = self.period_usec * 1000;
+        if now_ns < self.period_start_ns.saturating_add(period_ns) {
+            return;
+        }
+
+        self.period_start_ns = now_ns - now_ns % period_ns;
+        self.runtime_ns = i64::try_from(self.quota_usec.saturating_mul(1000)).unwrap_or(i64::MAX);
+        self.is_throttled_in_period = false;
+        self.nr_periods += 1;
+    }
+}
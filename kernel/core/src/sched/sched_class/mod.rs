@@ -4,7 +4,7 @@
 
 #![warn(unused)]
 
-use alloc::{boxed::Box, sync::Arc};
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
 use core::{fmt, ops::Bound, sync::atomic::Ordering};
 
 use ostd::{
@@ -32,12 +32,14 @@ mod policy;
 mod time;
 
 mod fair;
+mod group;
 mod idle;
 mod real_time;
 mod stop;
 
 use self::policy::{SchedPolicyKind, SchedPolicyState};
 pub(crate) use self::{
+    group::TaskGroup,
     policy::{LinuxSchedPolicy, SchedPolicy},
     real_time::{RealTimePolicy, RealTimePriority},
 };
@@ -207,6 +209,11 @@ impl SchedAttr {
         })
     }
 
+    /// Sets the task groups of the thread for the fair scheduling class.
+    pub(crate) fn set_task_groups(&self, groups: Vec<Arc<TaskGroup>>) {
+        self.fair.set_groups(groups);
+    }
+
     pub(crate) fn last_cpu(&self) -> Option<CpuId> {
         self.last_cpu.get()
     }
@@ -382,6 +389,10 @@ impl LocalRunQueue for PerCpuClassRqSet {
     }
 
     fn update_current(&mut self, flags: UpdateFlags) -> bool {
+        if matches!(flags, UpdateFlags::Tick) {
+            self.fair.unthrottle_groups();
+        }
+
         let (should_preempt, mut lookahead) = if let Some(((_, cur), rt)) = &mut self.current {
             rt.update();
             let attr = &cur.sched_attr();
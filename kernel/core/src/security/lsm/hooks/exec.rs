@@ -0,0 +1,27 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Hooks for replacing the program of a thread with `execve`.
+//!
+//! An exec is mediated in two steps. The check runs before the point of no
+//! return, so a module can still fail the `execve`. The commit runs after the
+//! old program has been torn down, so a module can apply the security state
+//! that it has prepared for the new program.
+
+use super::{super::modules, PathContext};
+use crate::{prelude::*, process::posix_thread::PosixThread};
+
+/// Runs exec check hooks in module order.
+pub(crate) fn on_exec_check(context: PathContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_exec_check(&context)?;
+    }
+
+    Ok(())
+}
+
+/// Runs exec commit hooks in module order.
+pub(crate) fn on_exec_commit(posix_thread: &PosixThread) {
+    for module in modules::active_modules() {
+        module.on_exec_commit(posix_thread);
+    }
+}
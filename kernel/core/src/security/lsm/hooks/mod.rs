@@ -4,12 +4,29 @@
 
 mod alien_access;
 mod capability;
+mod exec;
+mod mount;
+mod path;
+mod signal;
+mod socket;
 
 pub(crate) use self::{
     alien_access::{AlienAccessContext, on_alien_access},
     capability::{CapableContext, on_capable},
+    exec::{on_exec_check, on_exec_commit},
+    mount::{MountContext, on_mount, on_umount},
+    path::{
+        DirEntryContext, FileContext, PathContext, PathCreateContext, PathLinkContext,
+        PathOpenContext, PathRenameContext, on_file_truncate, on_path_create, on_path_exec,
+        on_path_link, on_path_open, on_path_rename, on_path_rmdir, on_path_truncate,
+        on_path_unlink,
+    },
+    signal::{SignalContext, on_signal},
+    socket::{
+        SocketAddrContext, SocketCreateContext, on_socket_bind, on_socket_connect, on_socket_create,
+    },
 };
-use crate::prelude::*;
+use crate::{prelude::*, process::posix_thread::PosixThread};
 
 pub(super) trait LsmAlienAccessHook: Sync {
     /// Handles an alien access attempt.
@@ -24,3 +41,99 @@ pub(super) trait LsmCapabilityHook: Sync {
         Ok(())
     }
 }
+
+pub(super) trait LsmExecHook: Sync {
+    /// Checks whether a thread may execute a program, and prepares the security
+    /// state that the thread will have after the `execve` succeeds.
+    fn on_exec_check(&self, _context: &PathContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Applies the security state prepared by [`Self::on_exec_check`].
+    ///
+    /// This is called after the `execve` has passed the point of no return.
+    fn on_exec_commit(&self, _posix_thread: &PosixThread) {}
+}
+
+pub(super) trait LsmMountHook: Sync {
+    /// Checks whether a mount operation may be performed.
+    fn on_mount(&self, _context: &MountContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Checks whether a mount may be unmounted.
+    fn on_umount(&self, _context: &PathContext) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub(super) trait LsmPathHook: Sync {
+    /// Checks whether a file may be opened.
+    fn on_path_open(&self, _context: &PathOpenContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Checks whether a file may be opened for execution.
+    fn on_path_exec(&self, _context: &PathContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Checks whether a file may be truncated through its path.
+    fn on_path_truncate(&self, _context: &PathContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Checks whether an opened file may be truncated.
+    fn on_file_truncate(&self, _context: &FileContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Checks whether a file may be created in a directory.
+    fn on_path_create(&self, _context: &PathCreateContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Checks whether a hard link may be created.
+    fn on_path_link(&self, _context: &PathLinkContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Checks whether a non-directory entry may be removed.
+    fn on_path_unlink(&self, _context: &DirEntryContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Checks whether a directory may be removed.
+    fn on_path_rmdir(&self, _context: &DirEntryContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Checks whether a directory entry may be renamed or exchanged.
+    fn on_path_rename(&self, _context: &PathRenameContext) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub(super) trait LsmSignalHook: Sync {
+    /// Checks whether a signal may be sent to a thread of another process.
+    fn on_signal(&self, _context: &SignalContext) -> Result<()> {
+        Ok(())
+    }
+}
+
+pub(super) trait LsmSocketHook: Sync {
+    /// Checks whether a socket may be created.
+    fn on_socket_create(&self, _context: &SocketCreateContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Checks whether a socket may be bound to an address.
+    fn on_socket_bind(&self, _context: &SocketAddrContext) -> Result<()> {
+        Ok(())
+    }
+
+    /// Checks whether a socket may be connected to an address.
+    fn on_socket_connect(&self, _context: &SocketAddrContext) -> Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Hooks for mounting and unmounting file systems.
+
+use super::{super::modules, PathContext};
+use crate::{fs::vfs::path::Path, prelude::*, process::posix_thread::PosixThread};
+
+/// Runs mount hooks in module order.
+pub(crate) fn on_mount(context: MountContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_mount(&context)?;
+    }
+
+    Ok(())
+}
+
+/// Runs unmount hooks in module order.
+pub(crate) fn on_umount(context: PathContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_umount(&context)?;
+    }
+
+    Ok(())
+}
+
+/// The inputs for a `mount` operation.
+pub(crate) struct MountContext<'a> {
+    posix_thread: &'a PosixThread,
+    target: &'a Path,
+    fs_type: Option<&'a str>,
+}
+
+impl<'a> MountContext<'a> {
+    /// Creates a mount context.
+    pub(crate) const fn new(
+        posix_thread: &'a PosixThread,
+        target: &'a Path,
+        fs_type: Option<&'a str>,
+    ) -> Self {
+        Self {
+            posix_thread,
+            target,
+            fs_type,
+        }
+    }
+
+    /// Returns the thread performing the operation.
+    pub(crate) const fn posix_thread(&self) -> &PosixThread {
+        self.posix_thread
+    }
+
+    /// Returns the mount point.
+    pub(crate) const fn target(&self) -> &Path {
+        self.target
+    }
+
+    /// Returns the type of the file system to mount.
+    ///
+    /// This is `None` for operations that do not create a new mount,
+    /// such as remounts, bind mounts, and mount moves.
+    pub(crate) const fn fs_type(&self) -> Option<&str> {
+        self.fs_type
+    }
+}
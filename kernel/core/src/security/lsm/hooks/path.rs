@@ -0,0 +1,374 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Hooks for operations on paths in the VFS tree.
+//!
+//! The hooks run after the VFS has checked the ordinary file permissions, and
+//! before the operation modifies the file system.
+
+use super::super::{FileLsm, modules};
+use crate::{
+    fs::{
+        file::{AccessMode, InodeType, StatusFlags},
+        vfs::{inode::RenameMode, path::Path},
+    },
+    prelude::*,
+    process::posix_thread::PosixThread,
+};
+
+/// Runs file open hooks in module order.
+pub(crate) fn on_path_open(context: PathOpenContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_path_open(&context)?;
+    }
+
+    Ok(())
+}
+
+/// Runs executable file hooks in module order.
+pub(crate) fn on_path_exec(context: PathContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_path_exec(&context)?;
+    }
+
+    Ok(())
+}
+
+/// Runs file truncation hooks in module order.
+pub(crate) fn on_path_truncate(context: PathContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_path_truncate(&context)?;
+    }
+
+    Ok(())
+}
+
+/// Runs opened file truncation hooks in module order.
+pub(crate) fn on_file_truncate(context: FileContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_file_truncate(&context)?;
+    }
+
+    Ok(())
+}
+
+/// Runs file creation hooks in module order.
+pub(crate) fn on_path_create(context: PathCreateContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_path_create(&context)?;
+    }
+
+    Ok(())
+}
+
+/// Runs hard link hooks in module order.
+pub(crate) fn on_path_link(context: PathLinkContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_path_link(&context)?;
+    }
+
+    Ok(())
+}
+
+/// Runs non-directory removal hooks in module order.
+pub(crate) fn on_path_unlink(context: DirEntryContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_path_unlink(&context)?;
+    }
+
+    Ok(())
+}
+
+/// Runs directory removal hooks in module order.
+pub(crate) fn on_path_rmdir(context: DirEntryContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_path_rmdir(&context)?;
+    }
+
+    Ok(())
+}
+
+/// Runs rename hooks in module order.
+pub(crate) fn on_path_rename(context: PathRenameContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_path_rename(&context)?;
+    }
+
+    Ok(())
+}
+
+/// The inputs for an operation on an existing path.
+pub(crate) struct PathContext<'a> {
+    posix_thread: &'a PosixThread,
+    path: &'a Path,
+}
+
+impl<'a> PathContext<'a> {
+    /// Creates a path context.
+    pub(crate) const fn new(posix_thread: &'a PosixThread, path: &'a Path) -> Self {
+        Self { posix_thread, path }
+    }
+
+    /// Returns the thread performing the operation.
+    pub(crate) const fn posix_thread(&self) -> &PosixThread {
+        self.posix_thread
+    }
+
+    /// Returns the path being operated on.
+    pub(crate) const fn path(&self) -> &Path {
+        self.path
+    }
+}
+
+/// The inputs for opening a file.
+pub(crate) struct PathOpenContext<'a> {
+    posix_thread: &'a PosixThread,
+    path: &'a Path,
+    access_mode: AccessMode,
+    status_flags: StatusFlags,
+    file_lsm: &'a FileLsm,
+}
+
+impl<'a> PathOpenContext<'a> {
+    /// Creates a file open context.
+    pub(crate) const fn new(
+        posix_thread: &'a PosixThread,
+        path: &'a Path,
+        access_mode: AccessMode,
+        status_flags: StatusFlags,
+        file_lsm: &'a FileLsm,
+    ) -> Self {
+        Self {
+            posix_thread,
+            path,
+            access_mode,
+            status_flags,
+            file_lsm,
+        }
+    }
+
+    /// Returns the thread opening the file.
+    pub(crate) const fn posix_thread(&self) -> &PosixThread {
+        self.posix_thread
+    }
+
+    /// Returns the path being opened.
+    pub(crate) const fn path(&self) -> &Path {
+        self.path
+    }
+
+    /// Returns the requested access mode.
+    pub(crate) const fn access_mode(&self) -> AccessMode {
+        self.access_mode
+    }
+
+    /// Returns the requested status flags.
+    pub(crate) const fn status_flags(&self) -> StatusFlags {
+        self.status_flags
+    }
+
+    /// Returns the LSM state of the file being opened.
+    pub(crate) const fn file_lsm(&self) -> &FileLsm {
+        self.file_lsm
+    }
+}
+
+/// The inputs for an operation on an opened file.
+pub(crate) struct FileContext<'a> {
+    file_lsm: &'a FileLsm,
+}
+
+impl<'a> FileContext<'a> {
+    /// Creates an opened file context.
+    pub(crate) const fn new(file_lsm: &'a FileLsm) -> Self {
+        Self { file_lsm }
+    }
+
+    /// Returns the LSM state of the file.
+    pub(crate) const fn file_lsm(&self) -> &FileLsm {
+        self.file_lsm
+    }
+}
+
+/// The inputs for creating a file in a directory.
+pub(crate) struct PathCreateContext<'a> {
+    posix_thread: &'a PosixThread,
+    dir: &'a Path,
+    name: &'a str,
+    type_: InodeType,
+}
+
+impl<'a> PathCreateContext<'a> {
+    /// Creates a file creation context.
+    pub(crate) const fn new(
+        posix_thread: &'a PosixThread,
+        dir: &'a Path,
+        name: &'a str,
+        type_: InodeType,
+    ) -> Self {
+        Self {
+            posix_thread,
+            dir,
+            name,
+            type_,
+        }
+    }
+
+    /// Returns the thread creating the file.
+    pub(crate) const fn posix_thread(&self) -> &PosixThread {
+        self.posix_thread
+    }
+
+    /// Returns the directory in which the file is created.
+    pub(crate) const fn dir(&self) -> &Path {
+        self.dir
+    }
+
+    /// Returns the name of the new file.
+    pub(crate) const fn name(&self) -> &str {
+        self.name
+    }
+
+    /// Returns the type of the new file.
+    pub(crate) const fn type_(&self) -> InodeType {
+        self.type_
+    }
+}
+
+/// The inputs for creating a hard link.
+pub(crate) struct PathLinkContext<'a> {
+    posix_thread: &'a PosixThread,
+    old: &'a Path,
+    new_dir: &'a Path,
+    new_name: &'a str,
+}
+
+impl<'a> PathLinkContext<'a> {
+    /// Creates a hard link context.
+    pub(crate) const fn new(
+        posix_thread: &'a PosixThread,
+        old: &'a Path,
+        new_dir: &'a Path,
+        new_name: &'a str,
+    ) -> Self {
+        Self {
+            posix_thread,
+            old,
+            new_dir,
+            new_name,
+        }
+    }
+
+    /// Returns the thread creating the link.
+    pub(crate) const fn posix_thread(&self) -> &PosixThread {
+        self.posix_thread
+    }
+
+    /// Returns the existing path to be linked.
+    pub(crate) const fn old(&self) -> &Path {
+        self.old
+    }
+
+    /// Returns the directory in which the link is created.
+    pub(crate) const fn new_dir(&self) -> &Path {
+        self.new_dir
+    }
+
+    /// Returns the name of the new link.
+    pub(crate) const fn new_name(&self) -> &str {
+        self.new_name
+    }
+}
+
+/// The inputs for removing an entry from a directory.
+pub(crate) struct DirEntryContext<'a> {
+    posix_thread: &'a PosixThread,
+    dir: &'a Path,
+    name: &'a str,
+}
+
+impl<'a> DirEntryContext<'a> {
+    /// Creates a directory entry context.
+    pub(crate) const fn new(posix_thread: &'a PosixThread, dir: &'a Path, name: &'a str) -> Self {
+        Self {
+            posix_thread,
+            dir,
+            name,
+        }
+    }
+
+    /// Returns the thread performing the operation.
+    pub(crate) const fn posix_thread(&self) -> &PosixThread {
+        self.posix_thread
+    }
+
+    /// Returns the directory containing the entry.
+    pub(crate) const fn dir(&self) -> &Path {
+        self.dir
+    }
+
+    /// Returns the name of the entry.
+    pub(crate) const fn name(&self) -> &str {
+        self.name
+    }
+}
+
+/// The inputs for renaming or exchanging directory entries.
+pub(crate) struct PathRenameContext<'a> {
+    posix_thread: &'a PosixThread,
+    old_dir: &'a Path,
+    old_name: &'a str,
+    new_dir: &'a Path,
+    new_name: &'a str,
+    mode: RenameMode,
+}
+
+impl<'a> PathRenameContext<'a> {
+    /// Creates a rename context.
+    pub(crate) const fn new(
+        posix_thread: &'a PosixThread,
+        old_dir: &'a Path,
+        old_name: &'a str,
+        new_dir: &'a Path,
+        new_name: &'a str,
+        mode: RenameMode,
+    ) -> Self {
+        Self {
+            posix_thread,
+            old_dir,
+            old_name,
+            new_dir,
+            new_name,
+            mode,
+        }
+    }
+
+    /// Returns the thread performing the rename.
+    pub(crate) const fn posix_thread(&self) -> &PosixThread {
+        self.posix_thread
+    }
+
+    /// Returns the directory containing the source entry.
+    pub(crate) const fn old_dir(&self) -> &Path {
+        self.old_dir
+    }
+
+    /// Returns the name of the source entry.
+    pub(crate) const fn old_name(&self) -> &str {
+        self.old_name
+    }
+
+    /// Returns the directory containing the destination entry.
+    pub(crate) const fn new_dir(&self) -> &Path {
+        self.new_dir
+    }
+
+    /// Returns the name of the destination entry.
+    pub(crate) const fn new_name(&self) -> &str {
+        self.new_name
+    }
+
+    /// Returns the rename mode.
+    pub(crate) const fn mode(&self) -> RenameMode {
+        self.mode
+    }
+}
@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Hooks for sending signals to other processes.
+
+use super::super::modules;
+use crate::{
+    prelude::*,
+    process::{posix_thread::PosixThread, signal::sig_num::SigNum},
+};
+
+/// Runs signal hooks in module order.
+pub(crate) fn on_signal(context: SignalContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_signal(&context)?;
+    }
+
+    Ok(())
+}
+
+/// The inputs for sending a signal to a thread.
+pub(crate) struct SignalContext<'a> {
+    sender: &'a PosixThread,
+    target: &'a PosixThread,
+    signum: Option<SigNum>,
+}
+
+impl<'a> SignalContext<'a> {
+    /// Creates a signal context.
+    pub(crate) const fn new(
+        sender: &'a PosixThread,
+        target: &'a PosixThread,
+        signum: Option<SigNum>,
+    ) -> Self {
+        Self {
+            sender,
+            target,
+            signum,
+        }
+    }
+
+    /// Returns the thread sending the signal.
+    pub(crate) const fn sender(&self) -> &PosixThread {
+        self.sender
+    }
+
+    /// Returns the thread receiving the signal.
+    pub(crate) const fn target(&self) -> &PosixThread {
+        self.target
+    }
+
+    /// Returns the signal number.
+    ///
+    /// This is `None` if the sender only checks whether the target exists.
+    pub(crate) const fn signum(&self) -> Option<SigNum> {
+        self.signum
+    }
+}
@@ -0,0 +1,112 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Hooks for creating, binding, and connecting sockets.
+
+use super::super::modules;
+use crate::{
+    net::socket::util::SocketAddr,
+    prelude::*,
+    process::posix_thread::PosixThread,
+    util::net::{CSocketAddrFamily, SockType},
+};
+
+/// Runs socket creation hooks in module order.
+pub(crate) fn on_socket_create(context: SocketCreateContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_socket_create(&context)?;
+    }
+
+    Ok(())
+}
+
+/// Runs socket bind hooks in module order.
+pub(crate) fn on_socket_bind(context: SocketAddrContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_socket_bind(&context)?;
+    }
+
+    Ok(())
+}
+
+/// Runs socket connect hooks in module order.
+pub(crate) fn on_socket_connect(context: SocketAddrContext) -> Result<()> {
+    for module in modules::active_modules() {
+        module.on_socket_connect(&context)?;
+    }
+
+    Ok(())
+}
+
+/// The inputs for creating a socket.
+pub(crate) struct SocketCreateContext<'a> {
+    posix_thread: &'a PosixThread,
+    family: CSocketAddrFamily,
+    sock_type: SockType,
+}
+
+impl<'a> SocketCreateContext<'a> {
+    /// Creates a socket creation context.
+    pub(crate) const fn new(
+        posix_thread: &'a PosixThread,
+        family: CSocketAddrFamily,
+        sock_type: SockType,
+    ) -> Self {
+        Self {
+            posix_thread,
+            family,
+            sock_type,
+        }
+    }
+
+    /// Returns the thread creating the socket.
+    pub(crate) const fn posix_thread(&self) -> &PosixThread {
+        self.posix_thread
+    }
+
+    /// Returns the address family of the socket.
+    pub(crate) const fn family(&self) -> CSocketAddrFamily {
+        self.family
+    }
+
+    /// Returns the type of the socket.
+    pub(crate) const fn sock_type(&self) -> SockType {
+        self.sock_type
+    }
+}
+
+/// The inputs for an operation that associates a socket with an address.
+pub(crate) struct SocketAddrContext<'a> {
+    posix_thread: &'a PosixThread,
+    sock_type: SockType,
+    addr: &'a SocketAddr,
+}
+
+impl<'a> SocketAddrContext<'a> {
+    /// Creates a socket address context.
+    pub(crate) const fn new(
+        posix_thread: &'a PosixThread,
+        sock_type: SockType,
+        addr: &'a SocketAddr,
+    ) -> Self {
+        Self {
+            posix_thread,
+            sock_type,
+            addr,
+        }
+    }
+
+    /// Returns the thread performing the operation.
+    pub(crate) const fn posix_thread(&self) -> &PosixThread {
+        self.posix_thread
+    }
+
+    /// Returns the type of the socket.
+    pub(crate) const fn sock_type(&self) -> SockType {
+        self.sock_type
+    }
+
+    /// Returns the address to bind or connect to.
+    pub(crate) const fn addr(&self) -> &SocketAddr {
+        self.addr
+    }
+}
@@ -7,17 +7,31 @@
 //! inspect common hook contexts before allowing or rejecting an operation.
 //!
 //! This module defines the common LSM traits and hook contexts shared by
-//! built-in modules such as `capability` and `yama`. Module selection follows
-//! the `lsm=` and legacy `security=` kernel command-line parameters.
+//! built-in modules such as `capability`, `yama`, `landlock`, and `apparmor`. Module
+//! selection follows the `lsm=` and legacy `security=` kernel command-line
+//! parameters.
 
 pub(crate) mod hooks;
 mod modules;
 
+pub(crate) mod landlock {
+    pub(crate) use super::modules::landlock::{do_add_rule, do_create_ruleset, do_restrict_self};
+}
+
 pub(crate) mod yama {
     pub(crate) use super::modules::yama::{YamaScope, get_scope, set_scope};
 }
 
-use self::hooks::{LsmAlienAccessHook, LsmCapabilityHook};
+use self::{
+    hooks::{
+        LsmAlienAccessHook, LsmCapabilityHook, LsmExecHook, LsmMountHook, LsmPathHook,
+        LsmSignalHook, LsmSocketHook,
+    },
+    modules::{
+        apparmor::ThreadAppArmor,
+        landlock::{FileLandlock, ThreadLandlock},
+    },
+};
 use crate::prelude::*;
 
 bitflags! {
@@ -31,12 +45,62 @@ bitflags! {
 }
 
 /// The common interface for built-in LSM modules.
-trait LsmModule: LsmAlienAccessHook + LsmCapabilityHook + Sync {
+trait LsmModule:
+    LsmAlienAccessHook
+    + LsmCapabilityHook
+    + LsmExecHook
+    + LsmMountHook
+    + LsmPathHook
+    + LsmSignalHook
+    + LsmSocketHook
+    + Sync
+{
     /// Returns the module name.
     fn name(&self) -> &'static str;
 
     /// Returns the module flags.
     fn flags(&self) -> LsmFlags;
+
+    /// Initializes the module if it is enabled.
+    ///
+    /// This is called once during boot, after the file systems are initialized.
+    fn init(&self) {}
+}
+
+/// The per-thread state of LSM modules.
+pub(crate) struct ThreadLsm {
+    landlock: ThreadLandlock,
+    apparmor: ThreadAppArmor,
+}
+
+impl ThreadLsm {
+    pub(crate) fn new() -> Self {
+        Self {
+            landlock: ThreadLandlock::new(),
+            apparmor: ThreadAppArmor::new(),
+        }
+    }
+
+    /// Copies the LSM state of the parent thread to this new thread.
+    pub(crate) fn inherit_from(&self, parent: &ThreadLsm) {
+        self.landlock.inherit_from(&parent.landlock);
+        self.apparmor.inherit_from(&parent.apparmor);
+    }
+}
+
+/// The per-file state of LSM modules.
+///
+/// The state belongs to an opened file and is shared by all the file descriptors that refer to it.
+pub(crate) struct FileLsm {
+    landlock: FileLandlock,
+}
+
+impl FileLsm {
+    pub(crate) fn new() -> Self {
+        Self {
+            landlock: FileLandlock::new(),
+        }
+    }
 }
 
 /// Returns whether the Yama LSM is enabled.
@@ -49,5 +113,6 @@ pub(crate) fn is_yama_enabled() -> bool {
 pub(super) fn init() {
     for module in modules::active_modules() {
         info!("[kernel] LSM module enabled: {}", module.name());
+        module.init();
     }
 }
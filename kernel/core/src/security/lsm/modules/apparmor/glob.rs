@@ -0,0 +1,313 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Patterns that match path names and profile names.
+//!
+//! The supported syntax is a subset of the AppArmor regular expressions:
+//!
+//! - `*` matches any number of characters except `/`;
+//! - `**` matches any number of characters including `/`;
+//! - `?` matches one character except `/`;
+//! - `[abc]`, `[a-c]`, and `[^abc]` match one character in or not in a set;
+//! - `{foo,bar}` matches any of the comma-separated alternatives, which may
+//!   contain patterns themselves;
+//! - `\` makes the next character literal.
+//!
+//! Alternations are expanded when a pattern is compiled, so matching only
+//! needs to handle the remaining constructs.
+
+use core::ops::RangeInclusive;
+
+use crate::prelude::*;
+
+/// A compiled pattern.
+#[derive(Debug)]
+pub(super) struct Glob {
+    alternatives: Vec<Vec<Token>>,
+}
+
+/// The maximum number of alternatives that a pattern may expand to.
+const MAX_ALTERNATIVES: usize = 256;
+
+impl Glob {
+    /// Compiles a pattern.
+    pub(super) fn new(source: &str) -> Result<Self> {
+        let mut pos = 0;
+        let expanded = expand_alternations(source.as_bytes(), &mut pos, false)?;
+        let alternatives = expanded
+            .iter()
+            .map(|alternative| tokenize(alternative))
+            .collect::<Result<Vec<_>>>()?;
+
+        Ok(Self { alternatives })
+    }
+
+    /// Returns whether the pattern matches the whole `name`.
+    pub(super) fn matches(&self, name: &str) -> bool {
+        self.alternatives
+            .iter()
+            .any(|tokens| matches_tokens(tokens, name.as_bytes()))
+    }
+
+    /// Returns the number of literal characters in the longest alternative.
+    ///
+    /// A pattern with more literal characters is considered more specific.
+    pub(super) fn literal_len(&self) -> usize {
+        self.alternatives
+            .iter()
+            .map(|tokens| {
+                tokens
+                    .iter()
+                    .filter(|token| matches!(token, Token::Byte(_)))
+                    .count()
+            })
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+#[derive(Debug)]
+enum Token {
+    /// A literal byte.
+    Byte(u8),
+    /// `?`.
+    AnyByte,
+    /// `*`.
+    AnyBytes,
+    /// `**`.
+    AnyBytesWithSlash,
+    /// `[...]`.
+    Class {
+        negated: bool,
+        ranges: Vec<RangeInclusive<u8>>,
+    },
+}
+
+impl Token {
+    fn matches(&self, byte: u8) -> bool {
+        match self {
+            Token::Byte(literal) => *literal == byte,
+            Token::AnyByte | Token::AnyBytes => byte != b'/',
+            Token::AnyBytesWithSlash => true,
+            Token::Class { negated, ranges } => {
+                ranges.iter().any(|range| range.contains(&byte)) != *negated
+            }
+        }
+    }
+
+    fn is_repeatable(&self) -> bool {
+        matches!(self, Token::AnyBytes | Token::AnyBytesWithSlash)
+    }
+}
+
+/// Expands the alternations in `pattern[*pos..]`.
+///
+/// If `nested` is true, the expansion stops before an unmatched `,` or `}`.
+fn expand_alternations(pattern: &[u8], pos: &mut usize, nested: bool) -> Result<Vec<Vec<u8>>> {
+    let mut results = vec![Vec::new()];
+
+    while let Some(&byte) = pattern.get(*pos) {
+        match byte {
+            b',' | b'}' if nested => break,
+            b'}' => return_errno_with_message!(Errno::EINVAL, "the pattern has an unmatched `}`"),
+            b'{' => {
+                *pos += 1;
+                let mut options = Vec::new();
+                loop {
+                    options.extend(expand_alternations(pattern, pos, true)?);
+                    match pattern.get(*pos) {
+                        Some(b',') => *pos += 1,
+                        Some(b'}') => {
+                            *pos += 1;
+                            break;
+                        }
+                        _ => return_errno_with_message!(
+                            Errno::EINVAL,
+                            "the pattern has an unmatched `{`"
+                        ),
+                    }
+                }
+
+                if results.len().saturating_mul(options.len()) > MAX_ALTERNATIVES {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "the pattern has too many alternatives"
+                    );
+                }
+                results = results
+                    .iter()
+                    .flat_map(|prefix| options.iter().map(|option| [prefix, option].concat()))
+                    .collect();
+            }
+            _ => {
+                let len = match byte {
+                    b'\\' => 2,
+                    b'[' => class_len(pattern, *pos)?,
+                    _ => 1,
+                };
+                let end = (*pos + len).min(pattern.len());
+                for result in results.iter_mut() {
+                    result.extend_from_slice(&pattern[*pos..end]);
+                }
+                *pos = end;
+            }
+        }
+    }
+
+    Ok(results)
+}
+
+/// Returns the length of the character class that starts at `pattern[start]`.
+fn class_len(pattern: &[u8], start: usize) -> Result<usize> {
+    let mut pos = start + 1;
+    if pattern.get(pos) == Some(&b'^') {
+        pos += 1;
+    }
+    // A `]` at the beginning of a class is literal.
+    if pattern.get(pos) == Some(&b']') {
+        pos += 1;
+    }
+
+    loop {
+        match pattern.get(pos) {
+            None => return_errno_with_message!(Errno::EINVAL, "the pattern has an unmatched `[`"),
+            Some(b']') => return Ok(pos + 1 - start),
+            Some(b'\\') => pos += 2,
+            Some(_) => pos += 1,
+        }
+    }
+}
+
+fn tokenize(pattern: &[u8]) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+
+    while let Some(&byte) = pattern.get(pos) {
+        let token = match byte {
+            b'*' => {
+                let stars = pattern[pos..]
+                    .iter()
+                    .take_while(|&&byte| byte == b'*')
+                    .count();
+                pos += stars;
+                if stars == 1 {
+                    Token::AnyBytes
+                } else {
+                    Token::AnyBytesWithSlash
+                }
+            }
+            b'?' => {
+                pos += 1;
+                Token::AnyByte
+            }
+            b'[' => {
+                let len = class_len(pattern, pos)?;
+                let token = parse_class(&pattern[pos + 1..pos + len - 1])?;
+                pos += len;
+                token
+            }
+            b'\\' => {
+                let Some(&escaped) = pattern.get(pos + 1) else {
+                    return_errno_with_message!(Errno::EINVAL, "the pattern ends with `\\`");
+                };
+                pos += 2;
+                Token::Byte(escaped)
+            }
+            _ => {
+                pos += 1;
+                Token::Byte(byte)
+            }
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+/// Parses the content of a character class, excluding the brackets.
+fn parse_class(content: &[u8]) -> Result<Token> {
+    let (negated, content) = match content.split_first() {
+        Some((b'^', rest)) => (true, rest),
+        _ => (false, content),
+    };
+
+    let mut bytes = Vec::new();
+    let mut pos = 0;
+    while let Some(&byte) = content.get(pos) {
+        if byte == b'\\' {
+            bytes.push((content[pos + 1], true));
+            pos += 2;
+        } else {
+            bytes.push((byte, false));
+            pos += 1;
+        }
+    }
+
+    let mut ranges = Vec::new();
+    let mut index = 0;
+    while index < bytes.len() {
+        let (first, _) = bytes[index];
+        match bytes.get(index + 1..index + 3) {
+            Some(&[(b'-', false), (last, _)]) => {
+                if last < first {
+                    return_errno_with_message!(
+                        Errno::EINVAL,
+                        "the pattern has an invalid character range"
+                    );
+                }
+                ranges.push(first..=last);
+                index += 3;
+            }
+            _ => {
+                ranges.push(first..=first);
+                index += 1;
+            }
+        }
+    }
+    if ranges.is_empty() {
+        return_errno_with_message!(Errno::EINVAL, "the pattern has an empty character class");
+    }
+
+    Ok(Token::Class { negated, ranges })
+}
+
+/// Matches `name` against the tokens by simulating a nondeterministic automaton.
+///
+/// State `i` means that the first `i` tokens have matched a prefix of `name`.
+/// The simulation takes linear time in the product of the lengths, so a
+/// malicious pattern cannot cause exponential backtracking.
+fn matches_tokens(tokens: &[Token], name: &[u8]) -> bool {
+    let mut states = vec![false; tokens.len() + 1];
+    states[0] = true;
+    skip_empty_repeats(tokens, &mut states);
+
+    for &byte in name {
+        let mut next_states = vec![false; tokens.len() + 1];
+        for (index, token) in tokens.iter().enumerate() {
+            if !states[index] || !token.matches(byte) {
+                continue;
+            }
+            if token.is_repeatable() {
+                next_states[index] = true;
+            } else {
+                next_states[index + 1] = true;
+            }
+        }
+        skip_empty_repeats(tokens, &mut next_states);
+
+        if !next_states.contains(&true) {
+            return false;
+        }
+        states = next_states;
+    }
+
+    states[tokens.len()]
+}
+
+/// Adds the states that are reachable by letting `*` and `**` match nothing.
+fn skip_empty_repeats(tokens: &[Token], states: &mut [bool]) {
+    for (index, token) in tokens.iter().enumerate() {
+        if states[index] && token.is_repeatable() {
+            states[index + 1] = true;
+        }
+    }
+}
@@ -0,0 +1,574 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The AppArmor LSM.
+//!
+//! AppArmor confines programs with profiles. A profile lists the files that a
+//! program may access, as path name patterns with permissions, and the
+//! network, signal, and mount operations that the program may perform. A
+//! thread becomes confined when it executes a program that a profile attaches
+//! to, and its children inherit the confinement.
+//!
+//! Profiles are loaded at runtime through securityfs. A profile in enforce
+//! mode denies and logs the operations that it does not allow, while a profile
+//! in complain mode only logs them. The log lines are written to the kernel
+//! log in a format similar to the audit records of Linux.
+//!
+//! Path names are absolute in the mount namespace of the file, so they are not
+//! affected by `chroot`. As in AppArmor, the names of directories end with `/`.
+//!
+//! Reference: <https://docs.kernel.org/admin-guide/LSM/apparmor.html>.
+
+mod glob;
+mod parser;
+mod policy;
+mod profile;
+mod securityfs;
+
+use core::fmt;
+
+use self::{
+    policy::ProfileEntry,
+    profile::{ExecTransition, FilePerms, Profile, ProfileMode, SignalPerms},
+};
+use super::super::{
+    LsmFlags, LsmModule,
+    hooks::{
+        DirEntryContext, LsmAlienAccessHook, LsmCapabilityHook, LsmExecHook, LsmMountHook,
+        LsmPathHook, LsmSignalHook, LsmSocketHook, MountContext, PathContext, PathCreateContext,
+        PathLinkContext, PathOpenContext, PathRenameContext, SignalContext, SocketAddrContext,
+        SocketCreateContext,
+    },
+};
+use crate::{
+    fs::{
+        file::{InodeType, StatusFlags},
+        vfs::{inode::RenameMode, path::Path},
+    },
+    net::socket::util::SocketAddr,
+    prelude::*,
+    process::{posix_thread::PosixThread, signal::sig_num::SigNum},
+    util::net::{CSocketAddrFamily, SockType},
+};
+
+pub(super) static APPARMOR_LSM: AppArmorLsm = AppArmorLsm;
+
+/// The AppArmor LSM.
+pub(super) struct AppArmorLsm;
+
+impl LsmModule for AppArmorLsm {
+    fn name(&self) -> &'static str {
+        "apparmor"
+    }
+
+    fn flags(&self) -> LsmFlags {
+        LsmFlags::LEGACY_MAJOR | LsmFlags::EXCLUSIVE
+    }
+
+    fn init(&self) {
+        securityfs::init();
+    }
+}
+
+impl LsmAlienAccessHook for AppArmorLsm {}
+
+impl LsmCapabilityHook for AppArmorLsm {}
+
+impl LsmExecHook for AppArmorLsm {
+    fn on_exec_check(&self, context: &PathContext) -> Result<()> {
+        let posix_thread = context.posix_thread();
+        let exe_name = path_name(context.path());
+
+        let current_entry = entry_of(posix_thread);
+        let current = current_entry
+            .as_ref()
+            .and_then(|entry| Some((entry, entry.profile()?)));
+        let new_entry = match current {
+            // An unconfined thread is confined by the profile that attaches to the program.
+            None => exe_name.as_deref().and_then(policy::find_by_attachment),
+            Some((entry, profile)) => {
+                exec_transition(&profile, entry, posix_thread, exe_name.as_deref())?
+            }
+        };
+
+        *posix_thread.lsm().apparmor.exec_profile.lock() = Some(new_entry);
+        Ok(())
+    }
+
+    fn on_exec_commit(&self, posix_thread: &PosixThread) {
+        let apparmor = &posix_thread.lsm().apparmor;
+        if let Some(new_entry) = apparmor.exec_profile.lock().take() {
+            *apparmor.profile.lock() = new_entry;
+        }
+    }
+}
+
+/// Returns the profile that confines a confined thread after it executes a program.
+///
+/// In complain mode, a denied transition keeps the current profile.
+fn exec_transition(
+    profile: &Profile,
+    entry: &Arc<ProfileEntry>,
+    posix_thread: &PosixThread,
+    exe_name: Option<&str>,
+) -> Result<Option<Arc<ProfileEntry>>> {
+    let Some(exe_name) = exe_name else {
+        audit(
+            profile,
+            posix_thread,
+            "exec",
+            format_args!("info=\"the program has no name\""),
+        )?;
+        return Ok(Some(entry.clone()));
+    };
+
+    let target = match profile.exec_transition(exe_name) {
+        Some(ExecTransition::Inherit) => return Ok(Some(entry.clone())),
+        Some(ExecTransition::Unconfined) => return Ok(None),
+        Some(ExecTransition::Profile(Some(target_name))) => policy::find_by_name(target_name),
+        Some(ExecTransition::Profile(None)) => policy::find_by_attachment(exe_name),
+        None => {
+            audit(
+                profile,
+                posix_thread,
+                "exec",
+                format_args!(
+                    "name=\"{}\" requested_mask=\"x\" denied_mask=\"x\"",
+                    exe_name
+                ),
+            )?;
+            return Ok(Some(entry.clone()));
+        }
+    };
+    if target.is_none() {
+        audit(
+            profile,
+            posix_thread,
+            "exec",
+            format_args!(
+                "name=\"{}\" info=\"the target profile is not found\"",
+                exe_name
+            ),
+        )?;
+        return Ok(Some(entry.clone()));
+    }
+
+    Ok(target)
+}
+
+impl LsmMountHook for AppArmorLsm {
+    fn on_mount(&self, context: &MountContext) -> Result<()> {
+        let posix_thread = context.posix_thread();
+        let Some(profile) = profile_of(posix_thread) else {
+            return Ok(());
+        };
+        let Some(target) = path_name(context.target()) else {
+            return Ok(());
+        };
+        if profile.allows_mount(context.fs_type(), &target) {
+            return Ok(());
+        }
+
+        audit(
+            &profile,
+            posix_thread,
+            "mount",
+            format_args!(
+                "name=\"{}\" fstype=\"{}\"",
+                target,
+                context.fs_type().unwrap_or_default()
+            ),
+        )
+    }
+
+    fn on_umount(&self, context: &PathContext) -> Result<()> {
+        let posix_thread = context.posix_thread();
+        let Some(profile) = profile_of(posix_thread) else {
+            return Ok(());
+        };
+        let Some(target) = path_name(context.path()) else {
+            return Ok(());
+        };
+        if profile.allows_umount(&target) {
+            return Ok(());
+        }
+
+        audit(
+            &profile,
+            posix_thread,
+            "umount",
+            format_args!("name=\"{}\"", target),
+        )
+    }
+}
+
+impl LsmPathHook for AppArmorLsm {
+    fn on_path_open(&self, context: &PathOpenContext) -> Result<()> {
+        if context.status_flags().contains(StatusFlags::O_PATH) {
+            return Ok(());
+        }
+        let Some(profile) = profile_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        let mut perms = FilePerms::empty();
+        if context.access_mode().is_readable() {
+            perms |= FilePerms::READ;
+        }
+        if context.access_mode().is_writable() {
+            if context.status_flags().contains(StatusFlags::O_APPEND) {
+                perms |= FilePerms::APPEND;
+            } else {
+                perms |= FilePerms::WRITE;
+            }
+        }
+
+        check_file(
+            &profile,
+            context.posix_thread(),
+            "open",
+            path_name(context.path()),
+            perms,
+        )
+    }
+
+    fn on_path_truncate(&self, context: &PathContext) -> Result<()> {
+        let Some(profile) = profile_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        check_file(
+            &profile,
+            context.posix_thread(),
+            "truncate",
+            path_name(context.path()),
+            FilePerms::WRITE,
+        )
+    }
+
+    fn on_path_create(&self, context: &PathCreateContext) -> Result<()> {
+        let Some(profile) = profile_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        let operation = if context.type_() == InodeType::Dir {
+            "mkdir"
+        } else {
+            "mknod"
+        };
+        check_file(
+            &profile,
+            context.posix_thread(),
+            operation,
+            child_name(context.dir(), context.name(), context.type_()),
+            FilePerms::WRITE,
+        )
+    }
+
+    fn on_path_link(&self, context: &PathLinkContext) -> Result<()> {
+        let Some(profile) = profile_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        check_file(
+            &profile,
+            context.posix_thread(),
+            "link",
+            child_name(context.new_dir(), context.new_name(), context.old().type_()),
+            FilePerms::LINK,
+        )
+    }
+
+    fn on_path_unlink(&self, context: &DirEntryContext) -> Result<()> {
+        let Some(profile) = profile_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        check_file(
+            &profile,
+            context.posix_thread(),
+            "unlink",
+            child_name(context.dir(), context.name(), InodeType::File),
+            FilePerms::WRITE,
+        )
+    }
+
+    fn on_path_rmdir(&self, context: &DirEntryContext) -> Result<()> {
+        let Some(profile) = profile_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        check_file(
+            &profile,
+            context.posix_thread(),
+            "rmdir",
+            child_name(context.dir(), context.name(), InodeType::Dir),
+            FilePerms::WRITE,
+        )
+    }
+
+    fn on_path_rename(&self, context: &PathRenameContext) -> Result<()> {
+        let posix_thread = context.posix_thread();
+        let Some(profile) = profile_of(posix_thread) else {
+            return Ok(());
+        };
+
+        // If a lookup fails, the rename fails later with the proper error.
+        let Ok(old) = context.old_dir().lookup_child(context.old_name()) else {
+            return Ok(());
+        };
+        let old_type = old.type_();
+        let old_perms = FilePerms::READ | FilePerms::WRITE;
+        let mut new_perms = FilePerms::WRITE;
+        let mut new_type = old_type;
+        if context.mode() == RenameMode::Exchange {
+            let Ok(new) = context.new_dir().lookup_child(context.new_name()) else {
+                return Ok(());
+            };
+            new_type = new.type_();
+            new_perms |= FilePerms::READ;
+        }
+
+        check_file(
+            &profile,
+            posix_thread,
+            "rename_src",
+            child_name(context.old_dir(), context.old_name(), old_type),
+            old_perms,
+        )?;
+        check_file(
+            &profile,
+            posix_thread,
+            "rename_dest",
+            child_name(context.new_dir(), context.new_name(), new_type),
+            new_perms,
+        )
+    }
+}
+
+impl LsmSignalHook for AppArmorLsm {
+    fn on_signal(&self, context: &SignalContext) -> Result<()> {
+        let sender_profile = profile_of(context.sender());
+        let target_profile = profile_of(context.target());
+        let signum = context.signum();
+
+        // Both the sender and the target must allow the signal.
+        if let Some(profile) = sender_profile.as_ref()
+            && !profile.allows_signal(SignalPerms::SEND, signum, label(target_profile.as_deref()))
+        {
+            audit(
+                profile,
+                context.sender(),
+                "signal",
+                format_args!(
+                    "requested_mask=\"send\" signal={} peer=\"{}\"",
+                    signal_number(signum),
+                    label(target_profile.as_deref())
+                ),
+            )?;
+        }
+        if let Some(profile) = target_profile.as_ref()
+            && !profile.allows_signal(
+                SignalPerms::RECEIVE,
+                signum,
+                label(sender_profile.as_deref()),
+            )
+        {
+            audit(
+                profile,
+                context.target(),
+                "signal",
+                format_args!(
+                    "requested_mask=\"receive\" signal={} peer=\"{}\"",
+                    signal_number(signum),
+                    label(sender_profile.as_deref())
+                ),
+            )?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Returns the label of a thread, which is the name of its profile or `unconfined`.
+fn label(profile: Option<&Profile>) -> &str {
+    profile.map_or("unconfined", |profile| profile.name.as_str())
+}
+
+/// Returns the signal number in log lines, where 0 stands for checking whether the target exists.
+fn signal_number(signum: Option<SigNum>) -> u8 {
+    signum.map_or(0, |signum| signum.as_u8())
+}
+
+impl LsmSocketHook for AppArmorLsm {
+    fn on_socket_create(&self, context: &SocketCreateContext) -> Result<()> {
+        check_network(
+            context.posix_thread(),
+            "create",
+            context.family(),
+            context.sock_type(),
+        )
+    }
+
+    fn on_socket_bind(&self, context: &SocketAddrContext) -> Result<()> {
+        check_network(
+            context.posix_thread(),
+            "bind",
+            family_of(context.addr()),
+            context.sock_type(),
+        )
+    }
+
+    fn on_socket_connect(&self, context: &SocketAddrContext) -> Result<()> {
+        check_network(
+            context.posix_thread(),
+            "connect",
+            family_of(context.addr()),
+            context.sock_type(),
+        )
+    }
+}
+
+fn check_network(
+    posix_thread: &PosixThread,
+    operation: &str,
+    family: CSocketAddrFamily,
+    sock_type: SockType,
+) -> Result<()> {
+    let Some(profile) = profile_of(posix_thread) else {
+        return Ok(());
+    };
+    if profile.allows_network(family, sock_type) {
+        return Ok(());
+    }
+
+    audit(
+        &profile,
+        posix_thread,
+        operation,
+        format_args!("family={:?} sock_type={:?}", family, sock_type),
+    )
+}
+
+fn family_of(addr: &SocketAddr) -> CSocketAddrFamily {
+    match addr {
+        SocketAddr::Unix(_) => CSocketAddrFamily::AF_UNIX,
+        SocketAddr::IPv4(..) => CSocketAddrFamily::AF_INET,
+        SocketAddr::IPv6(..) => CSocketAddrFamily::AF_INET6,
+        SocketAddr::Netlink(_) => CSocketAddrFamily::AF_NETLINK,
+        SocketAddr::Packet(_) => CSocketAddrFamily::AF_PACKET,
+        SocketAddr::Vsock(_) => CSocketAddrFamily::AF_VSOCK,
+    }
+}
+
+/// Checks whether a profile grants the permissions on a file.
+///
+/// Files without names, such as pipes and sockets, are not mediated.
+fn check_file(
+    profile: &Profile,
+    posix_thread: &PosixThread,
+    operation: &str,
+    name: Option<String>,
+    perms: FilePerms,
+) -> Result<()> {
+    let Some(name) = name else {
+        return Ok(());
+    };
+    let denied_perms = perms - profile.file_perms(&name);
+    if denied_perms.is_empty() {
+        return Ok(());
+    }
+
+    audit(
+        profile,
+        posix_thread,
+        operation,
+        format_args!(
+            "name=\"{}\" requested_mask=\"{}\" denied_mask=\"{}\"",
+            name,
+            perms.to_letters(),
+            denied_perms.to_letters()
+        ),
+    )
+}
+
+/// Logs an operation that a profile does not allow.
+///
+/// Returns an error if the profile is in enforce mode.
+fn audit(
+    profile: &Profile,
+    posix_thread: &PosixThread,
+    operation: &str,
+    details: fmt::Arguments,
+) -> Result<()> {
+    let verdict = match profile.mode {
+        ProfileMode::Enforce => "DENIED",
+        ProfileMode::Complain => "ALLOWED",
+    };
+    info!(
+        "apparmor=\"{}\" operation=\"{}\" profile=\"{}\" {} pid={}",
+        verdict,
+        operation,
+        profile.name,
+        details,
+        posix_thread.tid()
+    );
+
+    if profile.mode == ProfileMode::Enforce {
+        return_errno_with_message!(Errno::EACCES, "the operation is denied by AppArmor");
+    }
+    Ok(())
+}
+
+/// Returns the name of a path in rules.
+fn path_name(path: &Path) -> Option<String> {
+    let mut name = path.abs_path_in_mnt_ns()?;
+    if path.type_() == InodeType::Dir && !name.ends_with('/') {
+        name.push('/');
+    }
+    Some(name)
+}
+
+/// Returns the name of an entry of a directory in rules.
+fn child_name(dir: &Path, name: &str, type_: InodeType) -> Option<String> {
+    let mut child_name = path_name(dir)?;
+    child_name.push_str(name);
+    if type_ == InodeType::Dir {
+        child_name.push('/');
+    }
+    Some(child_name)
+}
+
+/// The AppArmor state of a thread.
+pub(crate) struct ThreadAppArmor {
+    /// The profile that confines the thread, or `None` if the thread is unconfined.
+    profile: SpinLock<Option<Arc<ProfileEntry>>>,
+    /// The profile that will confine the thread after an ongoing `execve`.
+    ///
+    /// It is prepared by the exec check and applied by the exec commit.
+    exec_profile: SpinLock<Option<Option<Arc<ProfileEntry>>>>,
+}
+
+impl ThreadAppArmor {
+    pub(crate) fn new() -> Self {
+        Self {
+            profile: SpinLock::new(None),
+            exec_profile: SpinLock::new(None),
+        }
+    }
+
+    /// Copies the profile of the parent thread to this new thread.
+    pub(crate) fn inherit_from(&self, parent: &ThreadAppArmor) {
+        *self.profile.lock() = parent.profile.lock().clone();
+    }
+}
+
+fn entry_of(posix_thread: &PosixThread) -> Option<Arc<ProfileEntry>> {
+    posix_thread.lsm().apparmor.profile.lock().clone()
+}
+
+/// Returns the profile that confines a thread.
+///
+/// Returns `None` if the thread is unconfined.
+fn profile_of(posix_thread: &PosixThread) -> Option<Arc<Profile>> {
+    entry_of(posix_thread)?.profile()
+}
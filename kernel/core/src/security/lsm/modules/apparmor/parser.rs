@@ -0,0 +1,501 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The parser of the policy text.
+//!
+//! Linux loads policies that `apparmor_parser` has compiled into a binary
+//! format. Instead, the policy here is written directly to the kernel in a
+//! subset of the source syntax of AppArmor profiles:
+//!
+//! ```text
+//! abi <abi/3.0>,
+//!
+//! # A profile that attaches to `/usr/bin/foo` on `execve`.
+//! /usr/bin/foo flags=(complain) {
+//!   /etc/foo/** r,
+//!   deny /etc/foo/secret rw,
+//!   /usr/bin/bar px -> bar,
+//!   network inet stream,
+//!   signal (send) set=(term, kill) peer=bar,
+//!   mount fstype=tmpfs -> /mnt/**/,
+//!   umount /mnt/**/,
+//! }
+//!
+//! # A profile that can only be attached by a `px` transition.
+//! profile bar {
+//!   /tmp/** rw,
+//! }
+//! ```
+//!
+//! Variables, includes, child profiles, hats, and rule qualifiers other than
+//! `deny` are not supported, and a policy that uses them is rejected.
+
+use super::{
+    glob::Glob,
+    profile::{
+        ExecTransition, FileMatcher, FilePerms, MountMatcher, NetworkMatcher, Profile, ProfileMode,
+        Rule, SignalMatcher, SignalPerms, SignalSet, UmountMatcher,
+    },
+};
+use crate::{
+    prelude::*,
+    process::signal::sig_num::SigNum,
+    util::net::{CSocketAddrFamily, SockType},
+};
+
+/// Parses the profiles in a policy.
+pub(super) fn parse_policy(text: &str) -> Result<Vec<Profile>> {
+    let mut parser = Parser {
+        tokens: tokenize(text)?,
+        pos: 0,
+    };
+
+    let mut profiles = Vec::new();
+    while let Some(words) = parser.next_words() {
+        match parser.next_token() {
+            Some(Token::Comma) if words.first().map(String::as_str) == Some("abi") => {}
+            Some(Token::Open) => {
+                let mut profile = parse_profile_header(&words)?;
+                parser.parse_profile_body(&mut profile)?;
+                profiles.push(profile);
+            }
+            _ => return_errno_with_message!(Errno::EINVAL, "a profile is expected"),
+        }
+    }
+    if profiles.is_empty() {
+        return_errno_with_message!(Errno::EINVAL, "the policy has no profiles");
+    }
+
+    Ok(profiles)
+}
+
+/// Parses the names of the profiles to remove.
+///
+/// Each non-empty line contains a profile name.
+pub(super) fn parse_profile_names(text: &str) -> Result<Vec<&str>> {
+    let names: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    if names.is_empty() {
+        return_errno_with_message!(Errno::EINVAL, "no profile name is given");
+    }
+
+    Ok(names)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum Token {
+    Word(String),
+    Open,
+    Close,
+    Comma,
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+
+    for line in text.lines() {
+        let line = line.trim_start();
+        if line.starts_with("#include") {
+            return_errno_with_message!(Errno::EINVAL, "includes are not supported");
+        }
+
+        let mut chars = line.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            match c {
+                '#' => break,
+                '{' | '}' | ',' => {
+                    chars.next();
+                    tokens.push(match c {
+                        '{' => Token::Open,
+                        '}' => Token::Close,
+                        _ => Token::Comma,
+                    });
+                }
+                c if c.is_whitespace() => {
+                    chars.next();
+                }
+                _ => {
+                    // A word ends at a whitespace or a comma unless it is inside
+                    // a pair of braces, parentheses, or quotes.
+                    let mut word = String::new();
+                    let mut depth = 0usize;
+                    let mut quoted = false;
+                    while let Some(&c) = chars.peek() {
+                        match c {
+                            '"' => quoted = !quoted,
+                            _ if quoted => word.push(c),
+                            '{' | '(' => {
+                                depth += 1;
+                                word.push(c);
+                            }
+                            '}' | ')' if depth > 0 => {
+                                depth -= 1;
+                                word.push(c);
+                            }
+                            '}' | '#' => break,
+                            ',' if depth == 0 => break,
+                            _ if c.is_whitespace() && depth == 0 => break,
+                            _ => word.push(c),
+                        }
+                        chars.next();
+                    }
+                    if quoted || depth > 0 {
+                        return_errno_with_message!(Errno::EINVAL, "a word is not terminated");
+                    }
+                    tokens.push(Token::Word(word));
+                }
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn next_token(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos)?.clone();
+        self.pos += 1;
+        Some(token)
+    }
+
+    /// Collects the words before the next punctuation token.
+    ///
+    /// Returns `None` if there are no more tokens.
+    fn next_words(&mut self) -> Option<Vec<String>> {
+        if self.pos == self.tokens.len() {
+            return None;
+        }
+
+        let mut words = Vec::new();
+        while let Some(Token::Word(word)) = self.tokens.get(self.pos) {
+            words.push(word.clone());
+            self.pos += 1;
+        }
+        Some(words)
+    }
+
+    fn parse_profile_body(&mut self, profile: &mut Profile) -> Result<()> {
+        loop {
+            let Some(words) = self.next_words() else {
+                return_errno_with_message!(Errno::EINVAL, "a profile is not terminated");
+            };
+            match self.next_token() {
+                Some(Token::Close) if words.is_empty() => return Ok(()),
+                Some(Token::Comma) if !words.is_empty() => parse_rule(&words, profile)?,
+                Some(Token::Open) => {
+                    return_errno_with_message!(Errno::EINVAL, "child profiles are not supported")
+                }
+                _ => return_errno_with_message!(Errno::EINVAL, "a rule must end with a comma"),
+            }
+        }
+    }
+}
+
+fn parse_profile_header(words: &[String]) -> Result<Profile> {
+    let (name, attachment, options) = match words {
+        [keyword, name, rest @ ..] if keyword == "profile" => match rest {
+            [attachment, options @ ..] if attachment.starts_with('/') => {
+                (name, Some(attachment), options)
+            }
+            _ => (name, None, rest),
+        },
+        [name, options @ ..] if name.starts_with('/') => (name, Some(name), options),
+        _ => return_errno_with_message!(Errno::EINVAL, "the profile header is invalid"),
+    };
+    if name.starts_with('^') || name.contains("//") {
+        return_errno_with_message!(Errno::EINVAL, "hats and child profiles are not supported");
+    }
+
+    let mut mode = ProfileMode::Enforce;
+    for option in options {
+        let Some(flags) = option.strip_prefix("flags=") else {
+            return_errno_with_message!(Errno::EINVAL, "the profile header is invalid");
+        };
+        for flag in parse_list(flags) {
+            mode = match flag {
+                "enforce" => ProfileMode::Enforce,
+                "complain" => ProfileMode::Complain,
+                _ => return_errno_with_message!(Errno::EINVAL, "the profile flag is not supported"),
+            };
+        }
+    }
+
+    Ok(Profile {
+        name: name.clone(),
+        attachment: attachment.map(|glob| Glob::new(glob)).transpose()?,
+        mode,
+        file_rules: Vec::new(),
+        network_rules: Vec::new(),
+        signal_rules: Vec::new(),
+        mount_rules: Vec::new(),
+        umount_rules: Vec::new(),
+    })
+}
+
+fn parse_rule(words: &[String], profile: &mut Profile) -> Result<()> {
+    let (deny, words) = match words {
+        [qualifier, rest @ ..] if qualifier == "deny" => (true, rest),
+        [qualifier, rest @ ..] if qualifier == "allow" => (false, rest),
+        _ => (false, words),
+    };
+    let Some((keyword, args)) = words.split_first() else {
+        return_errno_with_message!(Errno::EINVAL, "the rule is empty");
+    };
+
+    match keyword.as_str() {
+        "network" => profile.network_rules.push(Rule {
+            deny,
+            matcher: parse_network(args)?,
+        }),
+        "signal" => profile.signal_rules.push(Rule {
+            deny,
+            matcher: parse_signal(args)?,
+        }),
+        "mount" => profile.mount_rules.push(Rule {
+            deny,
+            matcher: parse_mount(args)?,
+        }),
+        "umount" | "unmount" => profile.umount_rules.push(Rule {
+            deny,
+            matcher: parse_umount(args)?,
+        }),
+        "file" => profile.file_rules.push(Rule {
+            deny,
+            matcher: parse_file(args, deny)?,
+        }),
+        _ => profile.file_rules.push(Rule {
+            deny,
+            matcher: parse_file(words, deny)?,
+        }),
+    }
+
+    Ok(())
+}
+
+fn parse_file(args: &[String], deny: bool) -> Result<FileMatcher> {
+    // The permissions can be written either after or before the path.
+    let (pattern, perms, rest) = match args {
+        [pattern, perms, rest @ ..] if pattern.starts_with('/') => (pattern, perms, rest),
+        [perms, pattern, rest @ ..] if pattern.starts_with('/') => (pattern, perms, rest),
+        _ => return_errno_with_message!(Errno::EINVAL, "the rule is not supported"),
+    };
+
+    let (perms, transition) = parse_file_perms(perms, deny)?;
+    let transition = match (transition, rest) {
+        (transition, []) => transition,
+        (Some(ExecTransition::Profile(None)), [arrow, target]) if arrow == "->" => {
+            Some(ExecTransition::Profile(Some(target.clone())))
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "the file rule is invalid"),
+    };
+
+    Ok(FileMatcher {
+        pattern: Glob::new(pattern)?,
+        perms,
+        transition,
+    })
+}
+
+fn parse_file_perms(letters: &str, deny: bool) -> Result<(FilePerms, Option<ExecTransition>)> {
+    let mut perms = FilePerms::empty();
+    let mut transition = None;
+
+    let mut chars = letters.chars();
+    while let Some(letter) = chars.next() {
+        let qualified_transition = match letter {
+            'i' => ExecTransition::Inherit,
+            // The uppercase forms also scrub the environment in Linux. This is not implemented.
+            'p' | 'P' => ExecTransition::Profile(None),
+            'u' | 'U' => ExecTransition::Unconfined,
+            'x' if deny => {
+                perms |= FilePerms::EXEC;
+                continue;
+            }
+            _ => {
+                let Some(perm) =
+                    FilePerms::from_letter(letter).filter(|perm| *perm != FilePerms::EXEC)
+                else {
+                    return_errno_with_message!(Errno::EINVAL, "the file permission is invalid");
+                };
+                perms |= perm;
+                continue;
+            }
+        };
+
+        if deny || chars.next() != Some('x') || transition.is_some() {
+            return_errno_with_message!(Errno::EINVAL, "the exec permission is invalid");
+        }
+        perms |= FilePerms::EXEC;
+        transition = Some(qualified_transition);
+    }
+    if perms.is_empty() {
+        return_errno_with_message!(Errno::EINVAL, "the file rule has no permissions");
+    }
+
+    Ok((perms, transition))
+}
+
+const FAMILY_NAMES: [(&str, CSocketAddrFamily); 7] = [
+    ("unix", CSocketAddrFamily::AF_UNIX),
+    ("local", CSocketAddrFamily::AF_UNIX),
+    ("inet", CSocketAddrFamily::AF_INET),
+    ("inet6", CSocketAddrFamily::AF_INET6),
+    ("netlink", CSocketAddrFamily::AF_NETLINK),
+    ("packet", CSocketAddrFamily::AF_PACKET),
+    ("vsock", CSocketAddrFamily::AF_VSOCK),
+];
+
+const SOCK_TYPE_NAMES: [(&str, SockType); 6] = [
+    ("stream", SockType::SOCK_STREAM),
+    ("dgram", SockType::SOCK_DGRAM),
+    ("seqpacket", SockType::SOCK_SEQPACKET),
+    ("rdm", SockType::SOCK_RDM),
+    ("raw", SockType::SOCK_RAW),
+    ("packet", SockType::SOCK_PACKET),
+];
+
+fn parse_network(args: &[String]) -> Result<NetworkMatcher> {
+    let mut args = args;
+    let family = take_keyword(&mut args, &FAMILY_NAMES);
+    let sock_type = take_keyword(&mut args, &SOCK_TYPE_NAMES);
+    if !args.is_empty() {
+        return_errno_with_message!(Errno::EINVAL, "the network rule is not supported");
+    }
+
+    Ok(NetworkMatcher { family, sock_type })
+}
+
+/// Takes the first argument if it is one of the keywords.
+fn take_keyword<T: Copy>(args: &mut &[String], keywords: &[(&str, T)]) -> Option<T> {
+    let (first, rest) = args.split_first()?;
+    let value = keywords
+        .iter()
+        .find(|(keyword, _)| *keyword == first.as_str())
+        .map(|(_, value)| *value)?;
+    *args = rest;
+    Some(value)
+}
+
+fn parse_signal(args: &[String]) -> Result<SignalMatcher> {
+    let mut perms = SignalPerms::empty();
+    let mut signals = None;
+    let mut peer = None;
+
+    for arg in args {
+        if let Some(set) = arg.strip_prefix("set=") {
+            let signals = signals.get_or_insert(SignalSet::new_empty());
+            for name in parse_list(set) {
+                signals.add(parse_signal_name(name)?);
+            }
+        } else if let Some(label) = arg.strip_prefix("peer=") {
+            peer = Some(Glob::new(label)?);
+        } else {
+            for perm in parse_list(arg) {
+                perms |= match perm {
+                    "send" | "w" => SignalPerms::SEND,
+                    "receive" | "r" => SignalPerms::RECEIVE,
+                    "rw" => SignalPerms::all(),
+                    _ => return_errno_with_message!(Errno::EINVAL, "the signal rule is invalid"),
+                };
+            }
+        }
+    }
+    if perms.is_empty() {
+        perms = SignalPerms::all();
+    }
+
+    Ok(SignalMatcher {
+        perms,
+        signals,
+        peer,
+    })
+}
+
+/// Parses the name of a signal in a `signal` rule.
+///
+/// `exists` stands for checking whether the target exists.
+fn parse_signal_name(name: &str) -> Result<Option<SigNum>> {
+    /// The first real-time signal number of the kernel, which can differ from `SIGRTMIN` in libc.
+    const KERNEL_SIGRTMIN: u8 = 32;
+
+    if name == "exists" {
+        return Ok(None);
+    }
+    if let Some(offset) = name.strip_prefix("rtmin+") {
+        let signum = offset
+            .parse::<u8>()
+            .ok()
+            .and_then(|offset| KERNEL_SIGRTMIN.checked_add(offset))
+            .and_then(|signum| SigNum::try_from(signum).ok());
+        return signum
+            .map(Some)
+            .ok_or_else(|| Error::with_message(Errno::EINVAL, "the real-time signal is invalid"));
+    }
+
+    // `stp` is the AppArmor name of `SIGTSTP`.
+    let name = if name == "stp" { "tstp" } else { name };
+    (1..KERNEL_SIGRTMIN)
+        .map(SigNum::from_u8)
+        .find(|signum| {
+            signum
+                .sig_name()
+                .strip_prefix("SIG")
+                .is_some_and(|sig_name| sig_name.eq_ignore_ascii_case(name))
+        })
+        .map(Some)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the signal name is invalid"))
+}
+
+fn parse_mount(args: &[String]) -> Result<MountMatcher> {
+    let mut fs_types = None;
+    let mut target = None;
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        if let Some(types) = arg.strip_prefix("fstype=") {
+            let fs_types = fs_types.get_or_insert_with(Vec::new);
+            for fs_type in parse_list(types) {
+                fs_types.push(Glob::new(fs_type)?);
+            }
+        } else if arg == "->"
+            && let Some(pattern) = args.next()
+            && target.is_none()
+        {
+            target = Some(Glob::new(pattern)?);
+        } else {
+            return_errno_with_message!(Errno::EINVAL, "the mount rule is not supported");
+        }
+    }
+
+    Ok(MountMatcher { fs_types, target })
+}
+
+fn parse_umount(args: &[String]) -> Result<UmountMatcher> {
+    let target = match args {
+        [] => None,
+        [pattern] => Some(Glob::new(pattern)?),
+        _ => return_errno_with_message!(Errno::EINVAL, "the umount rule is not supported"),
+    };
+
+    Ok(UmountMatcher { target })
+}
+
+/// Splits a value that is either a single item or a parenthesized list of
+/// items separated by commas or whitespace.
+fn parse_list(value: &str) -> impl Iterator<Item = &str> {
+    let value = value
+        .strip_prefix('(')
+        .and_then(|value| value.strip_suffix(')'))
+        .unwrap_or(value);
+
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|item| !item.is_empty())
+}
@@ -0,0 +1,127 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The set of loaded profiles.
+
+use super::profile::{Profile, ProfileMode};
+use crate::prelude::*;
+
+/// A loaded profile.
+///
+/// Threads refer to an entry rather than to a [`Profile`] so that replacing
+/// or removing a profile takes effect on the threads that it confines.
+pub(super) struct ProfileEntry {
+    profile: RwLock<Option<Arc<Profile>>>,
+}
+
+impl ProfileEntry {
+    /// Returns the current version of the profile.
+    ///
+    /// Returns `None` if the profile has been removed, in which case the
+    /// threads that it confined become unconfined.
+    pub(super) fn profile(&self) -> Option<Arc<Profile>> {
+        self.profile.read().clone()
+    }
+}
+
+static PROFILES: RwLock<BTreeMap<String, Arc<ProfileEntry>>> = RwLock::new(BTreeMap::new());
+
+/// Loads profiles into the policy.
+///
+/// If `replace` is false, loading fails if any of the profiles exists. Otherwise,
+/// existing profiles are replaced. Either all or none of the profiles are loaded.
+pub(super) fn load(profiles: Vec<Profile>, replace: bool) -> Result<()> {
+    let mut loaded_profiles = PROFILES.write();
+
+    let mut names = BTreeSet::new();
+    for profile in profiles.iter() {
+        if !names.insert(profile.name.as_str()) {
+            return_errno_with_message!(Errno::EINVAL, "a profile is defined more than once");
+        }
+        if !replace && loaded_profiles.contains_key(&profile.name) {
+            return_errno_with_message!(Errno::EEXIST, "the profile already exists");
+        }
+    }
+
+    for profile in profiles {
+        let name = profile.name.clone();
+        let profile = Some(Arc::new(profile));
+        if let Some(entry) = loaded_profiles.get(&name) {
+            *entry.profile.write() = profile;
+        } else {
+            let entry = Arc::new(ProfileEntry {
+                profile: RwLock::new(profile),
+            });
+            loaded_profiles.insert(name, entry);
+        }
+    }
+
+    Ok(())
+}
+
+/// Removes profiles from the policy.
+///
+/// Either all or none of the profiles are removed.
+pub(super) fn remove(names: &[&str]) -> Result<()> {
+    let mut loaded_profiles = PROFILES.write();
+
+    if names
+        .iter()
+        .any(|name| !loaded_profiles.contains_key(*name))
+    {
+        return_errno_with_message!(Errno::ENOENT, "the profile does not exist");
+    }
+    for name in names {
+        if let Some(entry) = loaded_profiles.remove(*name) {
+            *entry.profile.write() = None;
+        }
+    }
+
+    Ok(())
+}
+
+/// Finds a profile by its name.
+pub(super) fn find_by_name(name: &str) -> Option<Arc<ProfileEntry>> {
+    PROFILES.read().get(name).cloned()
+}
+
+/// Finds the profile that attaches to an executable.
+///
+/// If multiple profiles attach to the executable, the one with the most
+/// specific attachment pattern is chosen.
+pub(super) fn find_by_attachment(exe_name: &str) -> Option<Arc<ProfileEntry>> {
+    let profiles = PROFILES.read();
+
+    let mut best_match = None;
+    let mut best_literal_len = 0;
+    for entry in profiles.values() {
+        let Some(profile) = entry.profile() else {
+            continue;
+        };
+        let Some(attachment) = profile.attachment.as_ref() else {
+            continue;
+        };
+        if !attachment.matches(exe_name) {
+            continue;
+        }
+
+        let literal_len = attachment.literal_len();
+        if best_match.is_none() || literal_len > best_literal_len {
+            best_match = Some(entry.clone());
+            best_literal_len = literal_len;
+        }
+    }
+
+    best_match
+}
+
+/// Returns the names and modes of the loaded profiles.
+pub(super) fn list() -> Vec<(String, ProfileMode)> {
+    PROFILES
+        .read()
+        .iter()
+        .filter_map(|(name, entry)| {
+            let profile = entry.profile()?;
+            Some((name.clone(), profile.mode))
+        })
+        .collect()
+}
@@ -0,0 +1,286 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Profiles and the rules in them.
+//!
+//! Every rule either allows or denies a set of operations. An operation is
+//! permitted by a profile only if some allow rule matches it and no deny rule
+//! does, so deny rules always take precedence.
+
+use super::glob::Glob;
+use crate::{
+    prelude::*,
+    process::signal::sig_num::SigNum,
+    util::net::{CSocketAddrFamily, SockType},
+};
+
+/// A profile that confines the threads attached to it.
+#[derive(Debug)]
+pub(super) struct Profile {
+    pub(super) name: String,
+    /// The pattern of executables that the profile attaches to on `execve`.
+    pub(super) attachment: Option<Glob>,
+    pub(super) mode: ProfileMode,
+    pub(super) file_rules: Vec<Rule<FileMatcher>>,
+    pub(super) network_rules: Vec<Rule<NetworkMatcher>>,
+    pub(super) signal_rules: Vec<Rule<SignalMatcher>>,
+    pub(super) mount_rules: Vec<Rule<MountMatcher>>,
+    pub(super) umount_rules: Vec<Rule<UmountMatcher>>,
+}
+
+/// The enforcement mode of a profile.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(super) enum ProfileMode {
+    /// Denied operations fail and are logged.
+    Enforce,
+    /// Denied operations are logged, but still allowed.
+    Complain,
+}
+
+impl ProfileMode {
+    pub(super) fn as_str(&self) -> &'static str {
+        match self {
+            ProfileMode::Enforce => "enforce",
+            ProfileMode::Complain => "complain",
+        }
+    }
+}
+
+/// A rule that allows or denies the operations selected by a matcher.
+#[derive(Debug)]
+pub(super) struct Rule<M> {
+    pub(super) deny: bool,
+    pub(super) matcher: M,
+}
+
+impl Profile {
+    /// Returns the permissions that the profile grants on a file.
+    pub(super) fn file_perms(&self, name: &str) -> FilePerms {
+        let mut allowed = FilePerms::empty();
+        let mut denied = FilePerms::empty();
+        for rule in self
+            .file_rules
+            .iter()
+            .filter(|rule| rule.matcher.pattern.matches(name))
+        {
+            if rule.deny {
+                denied |= rule.matcher.perms;
+            } else {
+                allowed |= rule.matcher.perms;
+            }
+        }
+
+        // The permission to write a file includes the permission to append to it.
+        if allowed.contains(FilePerms::WRITE) {
+            allowed |= FilePerms::APPEND;
+        }
+        allowed - denied
+    }
+
+    /// Returns how the profile transitions when a file is executed.
+    ///
+    /// Returns `None` if the profile does not allow executing the file.
+    pub(super) fn exec_transition(&self, name: &str) -> Option<&ExecTransition> {
+        if !self.file_perms(name).contains(FilePerms::EXEC) {
+            return None;
+        }
+
+        self.file_rules
+            .iter()
+            .filter(|rule| !rule.deny && rule.matcher.pattern.matches(name))
+            .find_map(|rule| rule.matcher.transition.as_ref())
+    }
+
+    /// Returns whether the profile allows creating a socket.
+    pub(super) fn allows_network(&self, family: CSocketAddrFamily, sock_type: SockType) -> bool {
+        is_allowed(&self.network_rules, |matcher| {
+            matcher
+                .family
+                .is_none_or(|rule_family| rule_family == family)
+                && matcher
+                    .sock_type
+                    .is_none_or(|rule_sock_type| rule_sock_type == sock_type)
+        })
+    }
+
+    /// Returns whether the profile allows sending or receiving a signal.
+    ///
+    /// `peer` is the name of the profile confining the other side, or
+    /// `unconfined`.
+    pub(super) fn allows_signal(
+        &self,
+        perms: SignalPerms,
+        signum: Option<SigNum>,
+        peer: &str,
+    ) -> bool {
+        is_allowed(&self.signal_rules, |matcher| {
+            matcher.perms.contains(perms)
+                && matcher
+                    .signals
+                    .is_none_or(|signals| signals.contains(signum))
+                && matcher.peer.as_ref().is_none_or(|glob| glob.matches(peer))
+        })
+    }
+
+    /// Returns whether the profile allows a mount operation.
+    pub(super) fn allows_mount(&self, fs_type: Option<&str>, target: &str) -> bool {
+        is_allowed(&self.mount_rules, |matcher| {
+            matcher.fs_types.as_ref().is_none_or(|fs_types| {
+                fs_type.is_some_and(|fs_type| fs_types.iter().any(|glob| glob.matches(fs_type)))
+            }) && matcher
+                .target
+                .as_ref()
+                .is_none_or(|glob| glob.matches(target))
+        })
+    }
+
+    /// Returns whether the profile allows unmounting a mount point.
+    pub(super) fn allows_umount(&self, target: &str) -> bool {
+        is_allowed(&self.umount_rules, |matcher| {
+            matcher
+                .target
+                .as_ref()
+                .is_none_or(|glob| glob.matches(target))
+        })
+    }
+}
+
+fn is_allowed<M>(rules: &[Rule<M>], mut matches: impl FnMut(&M) -> bool) -> bool {
+    let mut allowed = false;
+    for rule in rules.iter().filter(|rule| matches(&rule.matcher)) {
+        if rule.deny {
+            return false;
+        }
+        allowed = true;
+    }
+
+    allowed
+}
+
+bitflags! {
+    /// Permissions on files.
+    pub(super) struct FilePerms: u32 {
+        /// `r`.
+        const READ = 1 << 0;
+        /// `w`.
+        const WRITE = 1 << 1;
+        /// `a`.
+        const APPEND = 1 << 2;
+        /// `l`.
+        const LINK = 1 << 3;
+        /// `k`.
+        const LOCK = 1 << 4;
+        /// `m`.
+        const MMAP_EXEC = 1 << 5;
+        /// `x`, qualified by an exec transition in allow rules.
+        const EXEC = 1 << 6;
+    }
+}
+
+impl FilePerms {
+    const LETTERS: [(FilePerms, char); 7] = [
+        (FilePerms::READ, 'r'),
+        (FilePerms::WRITE, 'w'),
+        (FilePerms::APPEND, 'a'),
+        (FilePerms::LINK, 'l'),
+        (FilePerms::LOCK, 'k'),
+        (FilePerms::MMAP_EXEC, 'm'),
+        (FilePerms::EXEC, 'x'),
+    ];
+
+    /// Returns the permission for a letter in a file rule.
+    pub(super) fn from_letter(letter: char) -> Option<Self> {
+        Self::LETTERS
+            .iter()
+            .find(|(_, perm_letter)| *perm_letter == letter)
+            .map(|(perm, _)| *perm)
+    }
+
+    /// Formats the permissions in the same way as they are written in rules.
+    pub(super) fn to_letters(self) -> String {
+        Self::LETTERS
+            .iter()
+            .filter(|(perm, _)| self.contains(*perm))
+            .map(|(_, letter)| *letter)
+            .collect()
+    }
+}
+
+/// The matcher of a file rule.
+#[derive(Debug)]
+pub(super) struct FileMatcher {
+    pub(super) pattern: Glob,
+    pub(super) perms: FilePerms,
+    pub(super) transition: Option<ExecTransition>,
+}
+
+/// The profile that a thread switches to when it executes a file.
+#[derive(Debug)]
+pub(super) enum ExecTransition {
+    /// `ix`: Stays in the current profile.
+    Inherit,
+    /// `px`: Switches to the named profile, or the profile attached to the
+    /// file if no name is given.
+    Profile(Option<String>),
+    /// `ux`: Runs unconfined.
+    Unconfined,
+}
+
+/// The matcher of a `network` rule.
+#[derive(Debug)]
+pub(super) struct NetworkMatcher {
+    pub(super) family: Option<CSocketAddrFamily>,
+    pub(super) sock_type: Option<SockType>,
+}
+
+bitflags! {
+    /// Permissions on signals.
+    pub(super) struct SignalPerms: u8 {
+        const SEND = 1 << 0;
+        const RECEIVE = 1 << 1;
+    }
+}
+
+/// A set of signals.
+///
+/// Bit 0 stands for checking whether the target exists without sending a signal.
+#[derive(Clone, Copy, Debug)]
+pub(super) struct SignalSet(u128);
+
+impl SignalSet {
+    pub(super) const fn new_empty() -> Self {
+        Self(0)
+    }
+
+    pub(super) fn add(&mut self, signum: Option<SigNum>) {
+        self.0 |= Self::bit(signum);
+    }
+
+    pub(super) fn contains(&self, signum: Option<SigNum>) -> bool {
+        self.0 & Self::bit(signum) != 0
+    }
+
+    fn bit(signum: Option<SigNum>) -> u128 {
+        1 << signum.map_or(0, |signum| signum.as_u8())
+    }
+}
+
+/// The matcher of a `signal` rule.
+#[derive(Debug)]
+pub(super) struct SignalMatcher {
+    pub(super) perms: SignalPerms,
+    pub(super) signals: Option<SignalSet>,
+    pub(super) peer: Option<Glob>,
+}
+
+/// The matcher of a `mount` rule.
+#[derive(Debug)]
+pub(super) struct MountMatcher {
+    pub(super) fs_types: Option<Vec<Glob>>,
+    pub(super) target: Option<Glob>,
+}
+
+/// The matcher of an `umount` rule.
+#[derive(Debug)]
+pub(super) struct UmountMatcher {
+    pub(super) target: Option<Glob>,
+}
@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The `apparmor` directory in securityfs.
+//!
+//! - `.load`: Writing a policy loads its profiles, which must not exist yet.
+//! - `.replace`: Writing a policy loads its profiles, replacing existing ones.
+//! - `.remove`: Writing profile names, one per line, removes the profiles.
+//! - `profiles`: Reading lists the loaded profiles and their modes.
+//!
+//! A policy must be written with a single `write`. Managing the policy requires
+//! `CAP_MAC_ADMIN` and is not allowed to confined threads.
+
+use alloc::sync::Arc;
+
+use aster_systree::{
+    BranchNodeFields, Error, Result, SysAttrSetBuilder, SysNode, SysPerms, SysStr,
+    inherit_sys_branch_node,
+};
+use aster_util::printer::VmPrinter;
+use ostd::mm::{FallibleVmRead, VmReader, VmWriter};
+
+use super::{parser, policy};
+use crate::{
+    fs::securityfs,
+    prelude::{Errno, current_thread, vec, warn},
+    process::{UserNamespace, credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+    security::lsm::hooks as lsm_hooks,
+};
+
+/// The maximum size of a policy that can be written at once.
+const MAX_POLICY_SIZE: usize = 64 * 1024;
+
+pub(super) fn init() {
+    securityfs::register_subsystem(AppArmorNode::new()).unwrap();
+}
+
+#[derive(Debug)]
+struct AppArmorNode {
+    fields: BranchNodeFields<dyn SysNode, Self>,
+}
+
+impl AppArmorNode {
+    fn new() -> Arc<Self> {
+        let name = SysStr::from("apparmor");
+
+        let mut builder = SysAttrSetBuilder::new();
+        for control in [".load", ".replace", ".remove"] {
+            builder.add(SysStr::from(control), SysPerms::OWNER_W);
+        }
+        builder.add(SysStr::from("profiles"), SysPerms::DEFAULT_RO_ATTR_PERMS);
+        let attrs = builder
+            .build()
+            .expect("Failed to build AppArmor attribute set");
+
+        Arc::new_cyclic(|weak_self| {
+            let fields = BranchNodeFields::new(name, attrs, weak_self.clone());
+            AppArmorNode { fields }
+        })
+    }
+}
+
+inherit_sys_branch_node!(AppArmorNode, fields, {
+    fn perms(&self) -> SysPerms {
+        SysPerms::DEFAULT_RO_PERMS
+    }
+
+    fn read_attr_at(&self, name: &str, offset: usize, writer: &mut VmWriter) -> Result<usize> {
+        match name {
+            "profiles" => {
+                let mut printer = VmPrinter::new_skip(writer, offset);
+                for (name, mode) in policy::list() {
+                    writeln!(printer, "{} ({})", name, mode.as_str())?;
+                }
+                Ok(printer.bytes_written())
+            }
+            ".load" | ".replace" | ".remove" => Err(Error::InvalidOperation),
+            _ => Err(Error::AttributeError),
+        }
+    }
+
+    fn write_attr(&self, name: &str, reader: &mut VmReader) -> Result<usize> {
+        if !matches!(name, ".load" | ".replace" | ".remove") {
+            return Err(Error::AttributeError);
+        }
+        check_policy_admin()?;
+
+        if reader.remain() > MAX_POLICY_SIZE {
+            return Err(Error::InvalidOperation);
+        }
+        let mut buf = vec![0u8; reader.remain()];
+        let len = reader
+            .read_fallible(&mut VmWriter::from(&mut buf[..]))
+            .map_err(|_| Error::PageFault)?;
+        let text = core::str::from_utf8(&buf[..len]).map_err(|_| Error::InvalidOperation)?;
+
+        let result = match name {
+            ".load" => {
+                parser::parse_policy(text).and_then(|profiles| policy::load(profiles, false))
+            }
+            ".replace" => {
+                parser::parse_policy(text).and_then(|profiles| policy::load(profiles, true))
+            }
+            _ => parser::parse_profile_names(text).and_then(|names| policy::remove(&names)),
+        };
+        result.map_err(|err| {
+            warn!(
+                "apparmor: failed to update the policy through `{}`: {:?}",
+                name, err
+            );
+            match err.error() {
+                Errno::EEXIST => Error::AlreadyExists,
+                Errno::ENOENT => Error::NotFound,
+                _ => Error::InvalidOperation,
+            }
+        })?;
+
+        Ok(len)
+    }
+});
+
+fn check_policy_admin() -> Result<()> {
+    let current = current_thread!();
+    let posix_thread = current.as_posix_thread().unwrap();
+
+    if super::profile_of(posix_thread).is_some() {
+        return Err(Error::PermissionDenied);
+    }
+    lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+        UserNamespace::get_init_singleton().as_ref(),
+        posix_thread,
+        CapSet::MAC_ADMIN,
+    ))
+    .map_err(|_| Error::PermissionDenied)
+}
@@ -2,7 +2,10 @@
 
 use super::super::{
     LsmFlags, LsmModule,
-    hooks::{AlienAccessContext, CapableContext, LsmAlienAccessHook, LsmCapabilityHook},
+    hooks::{
+        AlienAccessContext, CapableContext, LsmAlienAccessHook, LsmCapabilityHook, LsmExecHook,
+        LsmMountHook, LsmPathHook, LsmSignalHook, LsmSocketHook,
+    },
 };
 use crate::{
     prelude::*,
@@ -81,3 +84,13 @@ impl LsmAlienAccessHook for CapabilityLsm {
         );
     }
 }
+
+impl LsmExecHook for CapabilityLsm {}
+
+impl LsmMountHook for CapabilityLsm {}
+
+impl LsmPathHook for CapabilityLsm {}
+
+impl LsmSignalHook for CapabilityLsm {}
+
+impl LsmSocketHook for CapabilityLsm {}
@@ -0,0 +1,136 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::{
+    access::{FsAccess, NetAccess},
+    ruleset::Ruleset,
+};
+use crate::{
+    fs::{file::InodeType, vfs::path::Path},
+    prelude::*,
+};
+
+/// The maximum number of layers in a domain.
+const MAX_LAYERS: usize = 16;
+
+/// The restrictions enforced on a thread.
+///
+/// A domain is a stack of layers, one for each ruleset enforced by the thread
+/// or its ancestors. An access is allowed only if every layer allows it, so
+/// enforcing a new ruleset can never grant more access.
+pub(super) struct Domain {
+    layers: Vec<Arc<Ruleset>>,
+}
+
+impl Domain {
+    /// Creates a domain by stacking the ruleset on top of the parent domain.
+    pub(super) fn new(parent: Option<&Domain>, ruleset: Ruleset) -> Result<Self> {
+        let mut layers = parent.map_or_else(Vec::new, |parent| parent.layers.clone());
+        if layers.len() >= MAX_LAYERS {
+            return_errno_with_message!(Errno::E2BIG, "the domain has too many layers");
+        }
+        layers.push(Arc::new(ruleset));
+
+        Ok(Self { layers })
+    }
+
+    /// Checks whether the access rights to the path are allowed.
+    ///
+    /// A rule on a directory also applies to the files beneath it, so the rules
+    /// are collected while walking from the path up to the root of the mount
+    /// tree.
+    pub(super) fn check_path(&self, path: &Path, access: FsAccess) -> Result<()> {
+        // Pseudo files, e.g., pipes and sockets, are not in the VFS tree.
+        if path.is_pseudo() {
+            return Ok(());
+        }
+
+        let mut missing = [FsAccess::empty(); MAX_LAYERS];
+        for (layer, missing) in self.layers.iter().zip(missing.iter_mut()) {
+            *missing = access & layer.handled_fs();
+        }
+
+        let mut next = Some(path.clone());
+        while !missing.iter().all(FsAccess::is_empty) {
+            let Some(current) = next else {
+                return_errno_with_message!(Errno::EACCES, "the access is denied by Landlock");
+            };
+
+            for (layer, missing) in self.layers.iter().zip(missing.iter_mut()) {
+                missing.remove(layer.fs_access_of(&current));
+            }
+            next = current.effective_parent();
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether a file of the type may be moved or linked from one
+    /// directory to another.
+    ///
+    /// The move is allowed only if both directories allow
+    /// [`FsAccess::REFER`], and the file gains no access rights by moving to
+    /// the new directory.
+    pub(super) fn check_refer(
+        &self,
+        old_dir: &Path,
+        new_dir: &Path,
+        type_: InodeType,
+    ) -> Result<()> {
+        let old_allowed = self.allowed_beneath(old_dir);
+        let new_allowed = self.allowed_beneath(new_dir);
+
+        let relevant = if type_ == InodeType::Dir {
+            FsAccess::all()
+        } else {
+            FsAccess::FILE
+        };
+
+        for (old_allowed, new_allowed) in old_allowed.iter().zip(new_allowed.iter()) {
+            if !old_allowed.contains(FsAccess::REFER) || !new_allowed.contains(FsAccess::REFER) {
+                return_errno_with_message!(
+                    Errno::EXDEV,
+                    "reparenting the file is denied by Landlock"
+                );
+            }
+            if !old_allowed.contains(*new_allowed & relevant) {
+                return_errno_with_message!(
+                    Errno::EXDEV,
+                    "reparenting the file would grant more access rights"
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Collects the access rights that each layer allows beneath the directory.
+    fn allowed_beneath(&self, dir: &Path) -> Vec<FsAccess> {
+        let mut allowed: Vec<FsAccess> = self
+            .layers
+            .iter()
+            .map(|layer| !layer.handled_fs())
+            .collect();
+
+        let mut current = Some(dir.clone());
+        while let Some(path) = current {
+            for (layer, allowed) in self.layers.iter().zip(allowed.iter_mut()) {
+                *allowed |= layer.fs_access_of(&path);
+            }
+            current = path.effective_parent();
+        }
+
+        allowed
+    }
+
+    /// Checks whether the access rights to the TCP port are allowed.
+    pub(super) fn check_port(&self, port: u16, access: NetAccess) -> Result<()> {
+        for layer in self.layers.iter() {
+            let required = access & layer.handled_net();
+            if !layer.net_access_of(port).contains(required) {
+                return_errno_with_message!(Errno::EACCES, "the access is denied by Landlock");
+            }
+        }
+
+        Ok(())
+    }
+}
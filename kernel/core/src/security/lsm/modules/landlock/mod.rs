@@ -0,0 +1,479 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The Landlock LSM.
+//!
+//! Landlock lets unprivileged threads restrict their own access rights. A
+//! thread first creates a ruleset that states which access rights it handles,
+//! then adds rules that allow some of them to file hierarchies or TCP ports,
+//! and finally enforces the ruleset on itself. Handled access rights that no
+//! rule allows are denied from then on.
+//!
+//! The enforced restrictions are inherited by new threads and processes and
+//! survive `execve`. They can only be stacked with more restrictions, but
+//! never removed.
+//!
+//! Reference: <https://docs.kernel.org/userspace-api/landlock.html>.
+
+mod access;
+mod domain;
+mod ruleset;
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use self::{
+    access::{FsAccess, NetAccess},
+    domain::Domain,
+    ruleset::{Ruleset, RulesetFile},
+};
+use super::super::{
+    LsmFlags, LsmModule,
+    hooks::{
+        DirEntryContext, FileContext, LsmAlienAccessHook, LsmCapabilityHook, LsmExecHook,
+        LsmMountHook, LsmPathHook, LsmSignalHook, LsmSocketHook, PathContext, PathCreateContext,
+        PathLinkContext, PathOpenContext, PathRenameContext, SocketAddrContext,
+    },
+};
+use crate::{
+    fs::{
+        file::{
+            FileLike, InodeType, StatusFlags,
+            file_table::{FdFlags, FileDesc},
+        },
+        vfs::inode::RenameMode,
+    },
+    net::socket::util::SocketAddr,
+    prelude::*,
+    process::{credentials::capabilities::CapSet, posix_thread::PosixThread},
+    security::lsm::hooks as lsm_hooks,
+    util::{CopyCompat, net::SockType},
+};
+
+pub(super) static LANDLOCK_LSM: LandlockLsm = LandlockLsm;
+
+/// The Landlock LSM.
+pub(super) struct LandlockLsm;
+
+impl LsmModule for LandlockLsm {
+    fn name(&self) -> &'static str {
+        "landlock"
+    }
+
+    fn flags(&self) -> LsmFlags {
+        LsmFlags::empty()
+    }
+}
+
+impl LsmCapabilityHook for LandlockLsm {}
+
+impl LsmAlienAccessHook for LandlockLsm {}
+
+impl LsmExecHook for LandlockLsm {}
+
+impl LsmMountHook for LandlockLsm {}
+
+impl LsmPathHook for LandlockLsm {
+    fn on_path_open(&self, context: &PathOpenContext) -> Result<()> {
+        if context.status_flags().contains(StatusFlags::O_PATH) {
+            return Ok(());
+        }
+        let Some(domain) = domain_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        let path = context.path();
+        let mut access = FsAccess::empty();
+        if context.access_mode().is_readable() {
+            if path.type_() == InodeType::Dir {
+                access |= FsAccess::READ_DIR;
+            } else {
+                access |= FsAccess::READ_FILE;
+            }
+        }
+        if context.access_mode().is_writable() {
+            access |= FsAccess::WRITE_FILE;
+        }
+        domain.check_path(path, access)?;
+
+        // Like Linux, whether the file can be truncated through `ftruncate` is decided when it is
+        // opened, so the file descriptor keeps the right even if it is passed to another thread.
+        if context.access_mode().is_writable()
+            && domain.check_path(path, FsAccess::TRUNCATE).is_err()
+        {
+            context
+                .file_lsm()
+                .landlock
+                .is_truncate_allowed
+                .store(false, Ordering::Relaxed);
+        }
+
+        Ok(())
+    }
+
+    fn on_path_exec(&self, context: &PathContext) -> Result<()> {
+        let Some(domain) = domain_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        // Like Linux, an executable file is opened for reading, so both rights are required.
+        domain.check_path(context.path(), FsAccess::EXECUTE | FsAccess::READ_FILE)
+    }
+
+    fn on_path_truncate(&self, context: &PathContext) -> Result<()> {
+        let Some(domain) = domain_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        domain.check_path(context.path(), FsAccess::TRUNCATE)
+    }
+
+    fn on_file_truncate(&self, context: &FileContext) -> Result<()> {
+        if !context
+            .file_lsm()
+            .landlock
+            .is_truncate_allowed
+            .load(Ordering::Relaxed)
+        {
+            return_errno_with_message!(Errno::EACCES, "truncating the file is not allowed");
+        }
+
+        Ok(())
+    }
+
+    fn on_path_create(&self, context: &PathCreateContext) -> Result<()> {
+        let Some(domain) = domain_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        domain.check_path(context.dir(), FsAccess::make(context.type_()))
+    }
+
+    fn on_path_link(&self, context: &PathLinkContext) -> Result<()> {
+        let Some(domain) = domain_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        let type_ = context.old().type_();
+        let new_dir = context.new_dir();
+        domain.check_path(new_dir, FsAccess::make(type_))?;
+
+        match context.old().effective_parent() {
+            Some(old_dir) if old_dir != *new_dir => domain.check_refer(&old_dir, new_dir, type_),
+            _ => Ok(()),
+        }
+    }
+
+    fn on_path_unlink(&self, context: &DirEntryContext) -> Result<()> {
+        let Some(domain) = domain_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        domain.check_path(context.dir(), FsAccess::REMOVE_FILE)
+    }
+
+    fn on_path_rmdir(&self, context: &DirEntryContext) -> Result<()> {
+        let Some(domain) = domain_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        domain.check_path(context.dir(), FsAccess::REMOVE_DIR)
+    }
+
+    fn on_path_rename(&self, context: &PathRenameContext) -> Result<()> {
+        let Some(domain) = domain_of(context.posix_thread()) else {
+            return Ok(());
+        };
+
+        let old_dir = context.old_dir();
+        let new_dir = context.new_dir();
+        // If a lookup fails, the rename fails later with the proper error.
+        let Ok(old) = old_dir.lookup_child(context.old_name()) else {
+            return Ok(());
+        };
+        let old_type = old.type_();
+        let new_type = match context.mode() {
+            RenameMode::NoReplace => None,
+            RenameMode::Replace | RenameMode::Exchange => new_dir
+                .lookup_child(context.new_name())
+                .ok()
+                .map(|new| new.type_()),
+        };
+
+        let mut old_dir_access = FsAccess::remove(old_type);
+        let mut new_dir_access = FsAccess::make(old_type);
+        if let Some(new_type) = new_type {
+            new_dir_access |= FsAccess::remove(new_type);
+            if context.mode() == RenameMode::Exchange {
+                old_dir_access |= FsAccess::make(new_type);
+            }
+        }
+
+        if old_dir == new_dir {
+            return domain.check_path(old_dir, old_dir_access | new_dir_access);
+        }
+
+        domain.check_path(old_dir, old_dir_access)?;
+        domain.check_path(new_dir, new_dir_access)?;
+        domain.check_refer(old_dir, new_dir, old_type)?;
+        if let Some(new_type) = new_type
+            && context.mode() == RenameMode::Exchange
+        {
+            domain.check_refer(new_dir, old_dir, new_type)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl LsmSignalHook for LandlockLsm {}
+
+impl LsmSocketHook for LandlockLsm {
+    fn on_socket_bind(&self, context: &SocketAddrContext) -> Result<()> {
+        check_tcp_port(context, NetAccess::BIND_TCP)
+    }
+
+    fn on_socket_connect(&self, context: &SocketAddrContext) -> Result<()> {
+        check_tcp_port(context, NetAccess::CONNECT_TCP)
+    }
+}
+
+fn check_tcp_port(context: &SocketAddrContext, access: NetAccess) -> Result<()> {
+    if context.sock_type() != SockType::SOCK_STREAM {
+        return Ok(());
+    }
+    let (SocketAddr::IPv4(_, port) | SocketAddr::IPv6(_, port)) = context.addr() else {
+        return Ok(());
+    };
+    let Some(domain) = domain_of(context.posix_thread()) else {
+        return Ok(());
+    };
+
+    domain.check_port(*port, access)
+}
+
+/// The Landlock state of a thread.
+pub(crate) struct ThreadLandlock {
+    domain: SpinLock<Option<Arc<Domain>>>,
+}
+
+impl ThreadLandlock {
+    pub(crate) fn new() -> Self {
+        Self {
+            domain: SpinLock::new(None),
+        }
+    }
+
+    /// Copies the domain of the parent thread to this new thread.
+    pub(crate) fn inherit_from(&self, parent: &ThreadLandlock) {
+        *self.domain.lock() = parent.domain.lock().clone();
+    }
+}
+
+/// The Landlock state of an opened file.
+pub(crate) struct FileLandlock {
+    /// Whether the file may be truncated, which is decided when the file is opened.
+    is_truncate_allowed: AtomicBool,
+}
+
+impl FileLandlock {
+    pub(crate) fn new() -> Self {
+        Self {
+            is_truncate_allowed: AtomicBool::new(true),
+        }
+    }
+}
+
+fn domain_of(posix_thread: &PosixThread) -> Option<Arc<Domain>> {
+    posix_thread.lsm().landlock.domain.lock().clone()
+}
+
+/// The ABI version of Landlock.
+///
+/// Version 4 adds TCP port rules. Later versions add access rights for device
+/// `ioctl`s, IPC scoping, and audit logging, which are not supported.
+const LANDLOCK_ABI_VERSION: isize = 4;
+
+const LANDLOCK_CREATE_RULESET_VERSION: u32 = 1 << 0;
+
+const LANDLOCK_RULE_PATH_BENEATH: u32 = 1;
+const LANDLOCK_RULE_NET_PORT: u32 = 2;
+
+/// `struct landlock_ruleset_attr` in Linux.
+///
+/// The `scoped` field is introduced in ABI version 6 and is omitted, so that
+/// requesting any IPC scoping fails as it does on Linux with ABI version 4.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct CRulesetAttr {
+    handled_access_fs: u64,
+    handled_access_net: u64,
+}
+
+/// `struct landlock_path_beneath_attr` in Linux.
+#[repr(C, packed)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct CPathBeneathAttr {
+    allowed_access: u64,
+    parent_fd: i32,
+}
+
+/// `struct landlock_net_port_attr` in Linux.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct CNetPortAttr {
+    allowed_access: u64,
+    port: u64,
+}
+
+pub(crate) fn do_create_ruleset(
+    attr_addr: Vaddr,
+    size: usize,
+    flags: u32,
+    ctx: &Context,
+) -> Result<isize> {
+    check_enabled()?;
+
+    if flags != 0 {
+        if flags == LANDLOCK_CREATE_RULESET_VERSION && attr_addr == 0 && size == 0 {
+            return Ok(LANDLOCK_ABI_VERSION);
+        }
+        return_errno_with_message!(Errno::EINVAL, "the flags are invalid");
+    }
+
+    if attr_addr == 0 {
+        return_errno_with_message!(Errno::EFAULT, "the ruleset attribute is NULL");
+    }
+    if size < size_of::<u64>() {
+        return_errno_with_message!(Errno::EINVAL, "the ruleset attribute is too small");
+    }
+    if size > PAGE_SIZE {
+        return_errno_with_message!(Errno::E2BIG, "the ruleset attribute is too large");
+    }
+    let attr = ctx
+        .user_space()
+        .read_val_compat::<CRulesetAttr>(attr_addr, size)?;
+
+    let Some(handled_fs) = FsAccess::from_bits(attr.handled_access_fs) else {
+        return_errno_with_message!(Errno::EINVAL, "the file system access rights are invalid");
+    };
+    let Some(handled_net) = NetAccess::from_bits(attr.handled_access_net) else {
+        return_errno_with_message!(Errno::EINVAL, "the network access rights are invalid");
+    };
+    if handled_fs.is_empty() && handled_net.is_empty() {
+        return_errno_with_message!(Errno::ENOMSG, "the ruleset handles no access rights");
+    }
+
+    let file = Arc::new(RulesetFile::new(Ruleset::new(handled_fs, handled_net)));
+    let file_table = ctx.thread_local.borrow_file_table();
+    let fd = file_table.unwrap().write().insert(file, FdFlags::CLOEXEC);
+    Ok(fd.into())
+}
+
+pub(crate) fn do_add_rule(
+    ruleset_fd: i32,
+    rule_type: u32,
+    rule_attr_addr: Vaddr,
+    flags: u32,
+    ctx: &Context,
+) -> Result<()> {
+    check_enabled()?;
+
+    if flags != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the flags are invalid");
+    }
+    let file = get_file(ruleset_fd, ctx)?;
+    let mut ruleset = as_ruleset_file(&file)?.ruleset().lock();
+
+    match rule_type {
+        LANDLOCK_RULE_PATH_BENEATH => {
+            let attr = ctx
+                .user_space()
+                .read_val::<CPathBeneathAttr>(rule_attr_addr)?;
+            let Some(access) = FsAccess::from_bits(attr.allowed_access) else {
+                return_errno_with_message!(Errno::EINVAL, "the access rights are invalid");
+            };
+            ruleset.check_fs_access(access)?;
+
+            let path = get_file(attr.parent_fd, ctx)?.path().clone();
+            // Pseudo files, including Landlock rulesets, cannot be the targets of rules.
+            if path.is_pseudo() {
+                return_errno_with_message!(Errno::EBADFD, "the file is not in the VFS tree");
+            }
+
+            ruleset.add_path_beneath_rule(&path, access)
+        }
+        LANDLOCK_RULE_NET_PORT => {
+            let attr = ctx.user_space().read_val::<CNetPortAttr>(rule_attr_addr)?;
+            let Some(access) = NetAccess::from_bits(attr.allowed_access) else {
+                return_errno_with_message!(Errno::EINVAL, "the access rights are invalid");
+            };
+            ruleset.check_net_access(access)?;
+
+            let Ok(port) = u16::try_from(attr.port) else {
+                return_errno_with_message!(Errno::EINVAL, "the port is invalid");
+            };
+
+            ruleset.add_net_port_rule(port, access);
+            Ok(())
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "the rule type is invalid"),
+    }
+}
+
+pub(crate) fn do_restrict_self(ruleset_fd: i32, flags: u32, ctx: &Context) -> Result<()> {
+    check_enabled()?;
+
+    // Unprivileged threads must set `no_new_privs` first, so that they cannot use the
+    // restrictions to confuse privileged programs executed later.
+    if !ctx.posix_thread.credentials().no_new_privs()
+        && lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+            ctx.thread_local.borrow_user_ns().as_ref(),
+            ctx.posix_thread,
+            CapSet::SYS_ADMIN,
+        ))
+        .is_err()
+    {
+        return_errno_with_message!(
+            Errno::EPERM,
+            "enforcing a ruleset requires no_new_privs or CAP_SYS_ADMIN"
+        );
+    }
+    if flags != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the flags are invalid");
+    }
+
+    let file = get_file(ruleset_fd, ctx)?;
+    let ruleset = as_ruleset_file(&file)?.ruleset().lock().clone();
+
+    let thread_landlock = &ctx.posix_thread.lsm().landlock;
+    // Only the current thread changes its own domain, so the domain cannot change in between.
+    let parent = thread_landlock.domain.lock().clone();
+    let domain = Arc::new(Domain::new(parent.as_deref(), ruleset)?);
+    *thread_landlock.domain.lock() = Some(domain);
+
+    Ok(())
+}
+
+fn check_enabled() -> Result<()> {
+    if !super::active_modules()
+        .iter()
+        .any(|module| module.name() == LANDLOCK_LSM.name())
+    {
+        return_errno_with_message!(Errno::EOPNOTSUPP, "Landlock is not enabled");
+    }
+
+    Ok(())
+}
+
+fn get_file(fd: i32, ctx: &Context) -> Result<Arc<dyn FileLike>> {
+    let file_table = ctx.thread_local.borrow_file_table();
+    let file_table_locked = file_table.unwrap().read();
+    Ok(file_table_locked.get_file(FileDesc::try_from(fd)?)?.clone())
+}
+
+fn as_ruleset_file(file: &Arc<dyn FileLike>) -> Result<&RulesetFile> {
+    if file.status_flags().contains(StatusFlags::O_PATH) {
+        return_errno_with_message!(Errno::EBADF, "the file is opened as a path");
+    }
+    file.downcast_ref::<RulesetFile>()
+        .ok_or_else(|| Error::with_message(Errno::EBADFD, "the file is not a Landlock ruleset"))
+}
@@ -0,0 +1,205 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::fmt::Display;
+
+use super::access::{FsAccess, NetAccess};
+use crate::{
+    events::IoEvents,
+    fs::{
+        file::{
+            AccessMode, CreationFlags, FileCommon, FileLike, InodeType, StatusFlags,
+            file_table::FdFlags,
+        },
+        pseudofs::AnonInodeFs,
+        vfs::{inode::Inode, path::Path},
+    },
+    prelude::*,
+    process::signal::{PollHandle, Pollable},
+};
+
+/// A set of rules, together with the access rights that they handle.
+///
+/// A ruleset is built through a [`RulesetFile`]. Once enforced, a snapshot of
+/// the ruleset becomes an immutable layer of a domain.
+#[derive(Clone)]
+pub(super) struct Ruleset {
+    handled_fs: FsAccess,
+    handled_net: NetAccess,
+    fs_rules: BTreeMap<InodeKey, FsRule>,
+    net_rules: BTreeMap<u16, NetAccess>,
+}
+
+/// Identifies an inode by its file system and its inode number.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+struct InodeKey {
+    fs: usize,
+    ino: u64,
+}
+
+impl InodeKey {
+    fn new(inode: &dyn Inode) -> Self {
+        Self {
+            fs: Arc::as_ptr(&inode.fs()) as *const () as usize,
+            ino: inode.ino(),
+        }
+    }
+}
+
+#[derive(Clone)]
+struct FsRule {
+    /// The inode is kept alive so that its key cannot be reused by another inode.
+    _inode: Arc<dyn Inode>,
+    access: FsAccess,
+}
+
+impl Ruleset {
+    pub(super) fn new(handled_fs: FsAccess, handled_net: NetAccess) -> Self {
+        Self {
+            handled_fs,
+            handled_net,
+            fs_rules: BTreeMap::new(),
+            net_rules: BTreeMap::new(),
+        }
+    }
+
+    /// Checks whether a rule may allow the file system access rights.
+    pub(super) fn check_fs_access(&self, access: FsAccess) -> Result<()> {
+        if access.is_empty() {
+            return_errno_with_message!(Errno::ENOMSG, "the rule allows no access");
+        }
+        if !self.handled_fs.contains(access) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the rule allows access that is not handled by the ruleset"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Checks whether a rule may allow the network access rights.
+    pub(super) fn check_net_access(&self, access: NetAccess) -> Result<()> {
+        if access.is_empty() {
+            return_errno_with_message!(Errno::ENOMSG, "the rule allows no access");
+        }
+        if !self.handled_net.contains(access) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the rule allows access that is not handled by the ruleset"
+            );
+        }
+
+        Ok(())
+    }
+
+    /// Allows the access rights to the path and the files beneath it.
+    ///
+    /// The access rights must have been checked with [`Self::check_fs_access`].
+    pub(super) fn add_path_beneath_rule(&mut self, path: &Path, access: FsAccess) -> Result<()> {
+        if path.type_() != InodeType::Dir && !FsAccess::FILE.contains(access) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the rule allows directory access to a non-directory file"
+            );
+        }
+
+        let inode = path.inode();
+        self.fs_rules
+            .entry(InodeKey::new(inode.as_ref()))
+            .and_modify(|rule| rule.access |= access)
+            .or_insert_with(|| FsRule {
+                _inode: inode.clone(),
+                access,
+            });
+
+        Ok(())
+    }
+
+    /// Allows the access rights to the TCP port.
+    ///
+    /// The access rights must have been checked with [`Self::check_net_access`].
+    pub(super) fn add_net_port_rule(&mut self, port: u16, access: NetAccess) {
+        *self.net_rules.entry(port).or_insert(NetAccess::empty()) |= access;
+    }
+
+    /// Returns the file system access rights that are denied unless allowed by a rule.
+    ///
+    /// Reparenting files is always restricted, even if the ruleset does not
+    /// handle [`FsAccess::REFER`]. In that case, no rule can allow it.
+    pub(super) fn handled_fs(&self) -> FsAccess {
+        self.handled_fs | FsAccess::REFER
+    }
+
+    /// Returns the network access rights that are denied unless allowed by a rule.
+    pub(super) fn handled_net(&self) -> NetAccess {
+        self.handled_net
+    }
+
+    /// Returns the access rights allowed by the rule on the path itself.
+    pub(super) fn fs_access_of(&self, path: &Path) -> FsAccess {
+        self.fs_rules
+            .get(&InodeKey::new(path.inode().as_ref()))
+            .map_or(FsAccess::empty(), |rule| rule.access)
+    }
+
+    /// Returns the access rights allowed by the rule on the port.
+    pub(super) fn net_access_of(&self, port: u16) -> NetAccess {
+        self.net_rules
+            .get(&port)
+            .copied()
+            .unwrap_or(NetAccess::empty())
+    }
+}
+
+/// A file that refers to a [`Ruleset`].
+pub(super) struct RulesetFile {
+    ruleset: Mutex<Ruleset>,
+    common: FileCommon,
+}
+
+impl RulesetFile {
+    pub(super) fn new(ruleset: Ruleset) -> Self {
+        let pseudo_path = AnonInodeFs::new_path(|_| "anon_inode:[landlock-ruleset]".to_string());
+        Self {
+            ruleset: Mutex::new(ruleset),
+            common: FileCommon::new(pseudo_path, AccessMode::O_RDWR, StatusFlags::empty()),
+        }
+    }
+
+    pub(super) fn ruleset(&self) -> &Mutex<Ruleset> {
+        &self.ruleset
+    }
+}
+
+impl Pollable for RulesetFile {
+    fn poll(&self, _mask: IoEvents, _poller: Option<&mut PollHandle>) -> IoEvents {
+        IoEvents::empty()
+    }
+}
+
+impl FileLike for RulesetFile {
+    fn common(&self) -> &FileCommon {
+        &self.common
+    }
+
+    fn dump_proc_fdinfo(self: Arc<Self>, fd_flags: FdFlags) -> Box<dyn Display> {
+        struct FdInfo {
+            flags: u32,
+        }
+
+        impl Display for FdInfo {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                writeln!(f, "pos:\t{}", 0)?;
+                writeln!(f, "flags:\t0{:o}", self.flags)?;
+                writeln!(f, "mnt_id:\t{}", AnonInodeFs::mount_node().id())?;
+                writeln!(f, "ino:\t{}", AnonInodeFs::shared_inode().ino())
+            }
+        }
+
+        let mut flags = self.common.status_flags().bits() | self.common.access_mode() as u32;
+        if fd_flags.contains(FdFlags::CLOEXEC) {
+            flags |= CreationFlags::O_CLOEXEC.bits();
+        }
+        Box::new(FdInfo { flags })
+    }
+}
@@ -0,0 +1,644 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Secure computing mode (seccomp).
+//!
+//! Seccomp restricts the syscalls that a thread can make. In the strict mode, only `read`,
+//! `write`, `exit`, and `rt_sigreturn` are allowed. In the filter mode, each syscall is checked by
+//! a stack of cBPF programs supplied by userspace, and the programs decide the fate of the syscall
+//! (e.g., allowing it, failing it with an error number, killing the thread, or asking a tracer or
+//! a supervisor process to handle it).
+//!
+//! Filters are inherited by child threads and processes and are preserved across `execve`. Once
+//! installed, a filter can never be removed.
+//!
+//! Reference: <https://docs.kernel.org/userspace-api/seccomp_filter.html>
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use ostd::{arch::cpu::context::UserContext, mm::VmIo, user::UserContextApi};
+
+use self::notify::{NotifyReply, SeccompNotifier, SeccompNotifyFile};
+use crate::{
+    cpu::LinuxAbi,
+    fs::file::file_table::FdFlags,
+    prelude::*,
+    process::{
+        TermStatus,
+        coredump::do_coredump,
+        credentials::capabilities::CapSet,
+        posix_thread::{
+            AsPosixThread, ContextPthreadAdminApi, do_exit, do_exit_group,
+            ptrace::{PtraceEvent, PtraceStopResult},
+        },
+        signal::{
+            c_types::siginfo_t,
+            constants::{SIGKILL, SIGSYS, SYS_SECCOMP},
+            sig_action::SigAction,
+            signals::raw::RawSignal,
+        },
+    },
+    security::lsm::hooks as lsm_hooks,
+    syscall::{SYS_EXIT, SYS_READ, SYS_RT_SIGRETURN, SYS_WRITE},
+    util::bpf::{BpfData, BpfProgram},
+};
+
+mod notify;
+
+// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/seccomp.h>
+
+/// The operations of the `seccomp` syscall (`SECCOMP_SET_MODE_STRICT`, etc.).
+const SECCOMP_SET_MODE_STRICT: u32 = 0;
+const SECCOMP_SET_MODE_FILTER: u32 = 1;
+const SECCOMP_GET_ACTION_AVAIL: u32 = 2;
+const SECCOMP_GET_NOTIF_SIZES: u32 = 3;
+
+bitflags! {
+    /// Flags accepted by `SECCOMP_SET_MODE_FILTER`.
+    struct FilterFlags: u32 {
+        const SECCOMP_FILTER_FLAG_TSYNC = 1 << 0;
+        const SECCOMP_FILTER_FLAG_LOG = 1 << 1;
+        const SECCOMP_FILTER_FLAG_SPEC_ALLOW = 1 << 2;
+        const SECCOMP_FILTER_FLAG_NEW_LISTENER = 1 << 3;
+        const SECCOMP_FILTER_FLAG_TSYNC_ESRCH = 1 << 4;
+        const SECCOMP_FILTER_FLAG_WAIT_KILLABLE_RECV = 1 << 5;
+    }
+}
+
+// The return values of filters. The values are ordered from the most restrictive to the least
+// restrictive when interpreted as signed integers.
+const SECCOMP_RET_KILL_PROCESS: u32 = 0x8000_0000;
+const SECCOMP_RET_KILL_THREAD: u32 = 0x0000_0000;
+const SECCOMP_RET_TRAP: u32 = 0x0003_0000;
+const SECCOMP_RET_ERRNO: u32 = 0x0005_0000;
+const SECCOMP_RET_USER_NOTIF: u32 = 0x7fc0_0000;
+const SECCOMP_RET_TRACE: u32 = 0x7ff0_0000;
+const SECCOMP_RET_LOG: u32 = 0x7ffc_0000;
+const SECCOMP_RET_ALLOW: u32 = 0x7fff_0000;
+
+const SECCOMP_RET_ACTION_FULL: u32 = 0xffff_0000;
+const SECCOMP_RET_DATA: u32 = 0x0000_ffff;
+
+/// The maximum error number that `SECCOMP_RET_ERRNO` can return.
+const MAX_ERRNO: u32 = 4095;
+
+/// The maximum number of instructions of all filters of a thread.
+///
+/// Each filter is charged with four extra instructions to account for its overhead.
+const MAX_INSNS_PER_PATH: usize = 1 << 15;
+const INSNS_PENALTY: usize = 4;
+
+/// The `AUDIT_ARCH_*` value of the current architecture.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/audit.h>
+const AUDIT_ARCH: u32 = cfg_select! {
+    target_arch = "x86_64" => 0xc000_003e,
+    target_arch = "riscv64" => 0xc000_00f3,
+    target_arch = "loongarch64" => 0xc000_0102,
+    _ => compile_error!("unsupported target"),
+};
+
+/// The seccomp mode of a thread.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, TryFromInt)]
+pub(crate) enum SeccompMode {
+    Disabled = 0,
+    Strict = 1,
+    Filter = 2,
+}
+
+/// The seccomp state of a thread.
+pub(crate) struct ThreadSeccomp {
+    /// Whether the mode is not [`SeccompMode::Disabled`].
+    ///
+    /// This allows syscall entries to skip seccomp without taking the lock.
+    is_enabled: AtomicBool,
+    state: SpinLock<SeccompState>,
+}
+
+#[derive(Clone)]
+struct SeccompState {
+    mode: SeccompMode,
+    filter: Option<Arc<SeccompFilter>>,
+}
+
+impl ThreadSeccomp {
+    pub(crate) fn new() -> Self {
+        Self {
+            is_enabled: AtomicBool::new(false),
+            state: SpinLock::new(SeccompState {
+                mode: SeccompMode::Disabled,
+                filter: None,
+            }),
+        }
+    }
+
+    /// Copies the seccomp state of the parent thread to this new thread.
+    ///
+    /// If the new thread belongs to the same process, this method should be called while holding
+    /// the task set lock, so that filters synchronized with `SECCOMP_FILTER_FLAG_TSYNC` will not
+    /// be missed.
+    pub(crate) fn inherit_from(&self, parent: &ThreadSeccomp) {
+        let state = parent.state.lock().clone();
+        self.is_enabled
+            .store(state.mode != SeccompMode::Disabled, Ordering::Relaxed);
+        *self.state.lock() = state;
+    }
+
+    /// Returns the seccomp mode.
+    pub(crate) fn mode(&self) -> SeccompMode {
+        self.state.lock().mode
+    }
+
+    /// Returns the number of filters.
+    pub(crate) fn num_filters(&self) -> usize {
+        let filter = self.state.lock().filter.clone();
+        core::iter::successors(filter.as_ref(), |filter| filter.prev.as_ref()).count()
+    }
+
+    /// Takes the filters away when the thread exits.
+    ///
+    /// The filters must be dropped after the lock is released, as dropping the last reference
+    /// to a filter may notify the supervisor.
+    pub(crate) fn take_filter(&self) -> Option<Arc<SeccompFilter>> {
+        self.state.lock().filter.take()
+    }
+
+    fn set_mode(&self, state: &mut SeccompState, mode: SeccompMode) {
+        state.mode = mode;
+        self.is_enabled.store(true, Ordering::Relaxed);
+    }
+}
+
+/// A seccomp filter, which is linked to the filters installed before it.
+pub(crate) struct SeccompFilter {
+    prog: BpfProgram,
+    prev: Option<Arc<SeccompFilter>>,
+    /// Whether actions other than `SECCOMP_RET_ALLOW` should be logged.
+    is_logged: bool,
+    /// The notifier for `SECCOMP_RET_USER_NOTIF`, if the filter has a listener.
+    notifier: Option<Arc<SeccompNotifier>>,
+}
+
+impl SeccompFilter {
+    /// Runs all the filters from the newest to the oldest.
+    ///
+    /// Returns the most restrictive result and the filter that returns it. If multiple filters
+    /// return results with the same action, the newest filter wins.
+    fn run(self: &Arc<Self>, data: &SeccompData) -> (u32, &Arc<Self>) {
+        let mut result = SECCOMP_RET_ALLOW;
+        let mut matched = self;
+
+        for filter in core::iter::successors(Some(self), |filter| filter.prev.as_ref()) {
+            let ret = filter.prog.run(data);
+            if action_of(ret) < action_of(result) {
+                result = ret;
+                matched = filter;
+            }
+        }
+
+        (result, matched)
+    }
+
+    fn is_ancestor_of(self: &Arc<Self>, child: Option<&Arc<Self>>) -> bool {
+        core::iter::successors(child, |filter| filter.prev.as_ref())
+            .any(|filter| Arc::ptr_eq(filter, self))
+    }
+}
+
+impl Drop for SeccompFilter {
+    fn drop(&mut self) {
+        if let Some(notifier) = self.notifier.as_ref() {
+            notifier.orphan();
+        }
+
+        // Drop the chain iteratively. Otherwise, dropping a long chain will overflow the stack.
+        let mut prev = self.prev.take();
+        while let Some(filter) = prev {
+            let Ok(mut filter) = Arc::try_unwrap(filter) else {
+                break;
+            };
+            prev = filter.prev.take();
+        }
+    }
+}
+
+/// Returns the action of a filter's return value, which can be compared for restrictiveness.
+fn action_of(ret: u32) -> i32 {
+    (ret & SECCOMP_RET_ACTION_FULL) as i32
+}
+
+/// `struct seccomp_data` in Linux, which is the data that filters run on.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct SeccompData {
+    nr: i32,
+    arch: u32,
+    instruction_pointer: u64,
+    args: [u64; 6],
+}
+
+impl SeccompData {
+    fn new(user_ctx: &UserContext) -> Self {
+        Self {
+            nr: user_ctx.syscall_num() as i32,
+            arch: AUDIT_ARCH,
+            instruction_pointer: user_ctx.instruction_pointer() as u64,
+            args: user_ctx.syscall_args().map(|arg| arg as u64),
+        }
+    }
+}
+
+impl BpfData for SeccompData {
+    fn len(&self) -> u32 {
+        size_of::<Self>() as u32
+    }
+
+    fn load(&self, offset: u32, size: usize) -> Option<u32> {
+        // The program has been checked to only perform aligned 32-bit loads in bounds.
+        let offset = offset as usize;
+        let bytes = self.as_bytes().get(offset..offset + size)?;
+        Some(u32::from_ne_bytes(bytes.try_into().ok()?))
+    }
+}
+
+/// Checks the current syscall against the seccomp state of the current thread.
+///
+/// Returns whether the syscall should be executed. If not, the return value of the syscall has
+/// already been set in `user_ctx`, or the current thread has exited.
+pub(crate) fn secure_computing(ctx: &Context, user_ctx: &mut UserContext) -> bool {
+    let seccomp = ctx.posix_thread.seccomp();
+    if !seccomp.is_enabled.load(Ordering::Relaxed) {
+        return true;
+    }
+
+    let SeccompState { mode, filter } = seccomp.state.lock().clone();
+    match mode {
+        SeccompMode::Disabled => true,
+        SeccompMode::Strict => check_strict(ctx, user_ctx),
+        SeccompMode::Filter => check_filter(filter.as_ref().unwrap(), false, ctx, user_ctx),
+    }
+}
+
+fn check_strict(ctx: &Context, user_ctx: &mut UserContext) -> bool {
+    const ALLOWED_SYSCALLS: [u64; 4] = [SYS_READ, SYS_WRITE, SYS_EXIT, SYS_RT_SIGRETURN];
+
+    let syscall_num = user_ctx.syscall_num() as u64;
+    if ALLOWED_SYSCALLS.contains(&syscall_num) {
+        return true;
+    }
+
+    log_action(ctx, syscall_num as i32, SECCOMP_RET_KILL_THREAD);
+    do_exit(TermStatus::Killed(SIGKILL), ctx, user_ctx);
+    false
+}
+
+/// Checks the current syscall with the filters.
+///
+/// `is_rechecking` is true if the filters have returned `SECCOMP_RET_TRACE` and the tracer has
+/// continued the syscall. In this case, `SECCOMP_RET_TRACE` will allow the syscall.
+fn check_filter(
+    filter: &Arc<SeccompFilter>,
+    is_rechecking: bool,
+    ctx: &Context,
+    user_ctx: &mut UserContext,
+) -> bool {
+    let data = SeccompData::new(user_ctx);
+    let (ret, matched) = filter.run(&data);
+    let action = ret & SECCOMP_RET_ACTION_FULL;
+    let ret_data = ret & SECCOMP_RET_DATA;
+
+    if action != SECCOMP_RET_ALLOW && (action == SECCOMP_RET_LOG || matched.is_logged) {
+        log_action(ctx, data.nr, action);
+    }
+
+    match action {
+        SECCOMP_RET_ALLOW | SECCOMP_RET_LOG => true,
+        SECCOMP_RET_ERRNO => {
+            set_syscall_ret(user_ctx, -(ret_data.min(MAX_ERRNO) as isize));
+            false
+        }
+        SECCOMP_RET_TRAP => {
+            let mut siginfo = siginfo_t::new(SIGSYS, SYS_SECCOMP);
+            siginfo.si_errno = ret_data as i32;
+            siginfo.set_sigsys(data.instruction_pointer as Vaddr, data.nr, data.arch);
+            force_sigsys(ctx, siginfo);
+
+            // Like Linux, the return value register is left as it was at the syscall entry.
+            false
+        }
+        SECCOMP_RET_TRACE => {
+            if is_rechecking {
+                return true;
+            }
+
+            let event = PtraceEvent::Seccomp(ret_data as u16);
+            match ctx.posix_thread.ptrace_may_stop_on(event, ctx, user_ctx) {
+                PtraceStopResult::Continued(_) => {
+                    // The tracer can skip the syscall by setting the syscall number to -1.
+                    if ctx.thread_local.orig_syscall_ret().is_none() {
+                        return false;
+                    }
+                    // The tracer may have changed the syscall, so check it again.
+                    check_filter(filter, true, ctx, user_ctx)
+                }
+                // The thread is going to be killed, so skip the syscall.
+                PtraceStopResult::Interrupted => false,
+                PtraceStopResult::NotTraced(_) => {
+                    set_syscall_ret(user_ctx, -(Errno::ENOSYS as isize));
+                    false
+                }
+            }
+        }
+        SECCOMP_RET_USER_NOTIF => {
+            let reply = match matched.notifier.as_ref() {
+                Some(notifier) => notifier.notify(&data, ctx),
+                None => Err(Error::with_message(
+                    Errno::ENOSYS,
+                    "the filter does not have a listener",
+                )),
+            };
+            match reply {
+                Ok(NotifyReply::Continue) => true,
+                Ok(NotifyReply::Return(val)) => {
+                    set_syscall_ret(user_ctx, val);
+                    false
+                }
+                Err(err) => {
+                    set_syscall_ret(user_ctx, -(err.error() as isize));
+                    false
+                }
+            }
+        }
+        _ => {
+            if !matched.is_logged {
+                log_action(ctx, data.nr, action);
+            }
+
+            // Like Linux, killing the last thread kills the whole process, and unknown actions
+            // are treated as `SECCOMP_RET_KILL_PROCESS`.
+            let is_last_thread = ctx.process.tasks().lock().as_slice().len() == 1;
+            if action == SECCOMP_RET_KILL_THREAD && !is_last_thread {
+                do_exit(TermStatus::Killed(SIGSYS), ctx, user_ctx);
+            } else {
+                // Like Linux, a core dump is produced as if the process were killed by `SIGSYS`.
+                let mut siginfo = siginfo_t::new(SIGSYS, SYS_SECCOMP);
+                siginfo.set_sigsys(data.instruction_pointer as Vaddr, data.nr, data.arch);
+                let term_status = do_coredump(SIGSYS, &siginfo, ctx, user_ctx);
+                do_exit_group(term_status, ctx, user_ctx);
+            }
+            false
+        }
+    }
+}
+
+/// Sends `SIGSYS` to the current thread, even if the signal is blocked or ignored.
+///
+/// Like Linux, `SIGSYS` is unblocked, and its disposition is reset to the default if it is
+/// ignored. Otherwise, the thread would continue to run after the disallowed syscall is skipped.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/kernel/signal.c>
+fn force_sigsys(ctx: &Context, siginfo: siginfo_t) {
+    {
+        let sig_dispositions = ctx.process.sig_dispositions().lock();
+        let mut sig_dispositions = sig_dispositions.lock();
+        if sig_dispositions.get(SIGSYS) == SigAction::Ign {
+            sig_dispositions.set_default(SIGSYS);
+        }
+    }
+
+    ctx.set_sig_mask(ctx.posix_thread.sig_mask() - SIGSYS);
+
+    ctx.posix_thread
+        .enqueue_signal(Box::new(RawSignal::new(siginfo)));
+}
+
+fn set_syscall_ret(user_ctx: &mut UserContext, ret: isize) {
+    user_ctx.set_syscall_ret(ret as usize);
+}
+
+fn log_action(ctx: &Context, syscall_num: i32, action: u32) {
+    info!(
+        "seccomp: pid={} tid={} syscall={} action={:#x}",
+        ctx.process.pid(),
+        ctx.posix_thread.tid(),
+        syscall_num,
+        action
+    );
+}
+
+/// Sets the seccomp mode for `PR_SET_SECCOMP`.
+///
+/// For [`SeccompMode::Filter`], `filter_addr` points to the filter program. Unlike the `seccomp`
+/// syscall, no filter flags can be specified.
+pub(crate) fn prctl_set_seccomp(
+    mode: SeccompMode,
+    filter_addr: Vaddr,
+    ctx: &Context,
+) -> Result<isize> {
+    match mode {
+        SeccompMode::Strict => do_seccomp(SECCOMP_SET_MODE_STRICT, 0, 0, ctx),
+        SeccompMode::Filter => do_seccomp(SECCOMP_SET_MODE_FILTER, 0, filter_addr, ctx),
+        SeccompMode::Disabled => {
+            return_errno_with_message!(Errno::EINVAL, "the seccomp mode cannot be disabled")
+        }
+    }
+}
+
+/// Performs a seccomp operation for the `seccomp` syscall.
+pub(crate) fn do_seccomp(op: u32, flags: u32, args: Vaddr, ctx: &Context) -> Result<isize> {
+    match op {
+        SECCOMP_SET_MODE_STRICT => {
+            if flags != 0 || args != 0 {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "the strict mode does not accept flags or arguments"
+                );
+            }
+            set_mode_strict(ctx)?;
+            Ok(0)
+        }
+        SECCOMP_SET_MODE_FILTER => set_mode_filter(flags, args, ctx),
+        SECCOMP_GET_ACTION_AVAIL => {
+            if flags != 0 {
+                return_errno_with_message!(Errno::EINVAL, "the flags are invalid");
+            }
+            let action = ctx.user_space().read_val::<u32>(args)?;
+            match action {
+                SECCOMP_RET_KILL_PROCESS
+                | SECCOMP_RET_KILL_THREAD
+                | SECCOMP_RET_TRAP
+                | SECCOMP_RET_ERRNO
+                | SECCOMP_RET_USER_NOTIF
+                | SECCOMP_RET_TRACE
+                | SECCOMP_RET_LOG
+                | SECCOMP_RET_ALLOW => Ok(0),
+                _ => return_errno_with_message!(Errno::EOPNOTSUPP, "the action is not available"),
+            }
+        }
+        SECCOMP_GET_NOTIF_SIZES => {
+            if flags != 0 {
+                return_errno_with_message!(Errno::EINVAL, "the flags are invalid");
+            }
+            ctx.user_space()
+                .write_val(args, &notify::CSeccompNotifSizes::new())?;
+            Ok(0)
+        }
+        _ => return_errno_with_message!(Errno::EINVAL, "the seccomp operation is invalid"),
+    }
+}
+
+fn set_mode_strict(ctx: &Context) -> Result<()> {
+    let seccomp = ctx.posix_thread.seccomp();
+    let mut state = seccomp.state.lock();
+
+    if state.mode == SeccompMode::Filter {
+        return_errno_with_message!(Errno::EINVAL, "the filter mode is already enabled");
+    }
+    seccomp.set_mode(&mut state, SeccompMode::Strict);
+
+    Ok(())
+}
+
+fn set_mode_filter(flags: u32, prog_addr: Vaddr, ctx: &Context) -> Result<isize> {
+    let Some(flags) = FilterFlags::from_bits(flags) else {
+        return_errno_with_message!(Errno::EINVAL, "the filter flags are invalid");
+    };
+    // The return value cannot be both the listener FD and the ID of the thread that fails to
+    // synchronize.
+    if flags.contains(FilterFlags::SECCOMP_FILTER_FLAG_TSYNC)
+        && flags.contains(FilterFlags::SECCOMP_FILTER_FLAG_NEW_LISTENER)
+        && !flags.contains(FilterFlags::SECCOMP_FILTER_FLAG_TSYNC_ESRCH)
+    {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "TSYNC with NEW_LISTENER requires TSYNC_ESRCH"
+        );
+    }
+    if flags.contains(FilterFlags::SECCOMP_FILTER_FLAG_WAIT_KILLABLE_RECV)
+        && !flags.contains(FilterFlags::SECCOMP_FILTER_FLAG_NEW_LISTENER)
+    {
+        return_errno_with_message!(Errno::EINVAL, "WAIT_KILLABLE_RECV requires NEW_LISTENER");
+    }
+
+    // Unprivileged threads must set `no_new_privs` first, so that they cannot use filters to
+    // confuse privileged programs executed later.
+    if !ctx.posix_thread.credentials().no_new_privs()
+        && lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+            ctx.thread_local.borrow_user_ns().as_ref(),
+            ctx.posix_thread,
+            CapSet::SYS_ADMIN,
+        ))
+        .is_err()
+    {
+        return_errno_with_message!(
+            Errno::EACCES,
+            "installing filters requires no_new_privs or CAP_SYS_ADMIN"
+        );
+    }
+
+    let prog = BpfProgram::read_from_user(prog_addr)?;
+    prog.check_word_loads(size_of::<SeccompData>() as u32)?;
+
+    // FIXME: `SECCOMP_FILTER_FLAG_WAIT_KILLABLE_RECV` is accepted, but the thread still waits
+    // interruptibly after the supervisor receives the notification.
+    let notifier = flags
+        .contains(FilterFlags::SECCOMP_FILTER_FLAG_NEW_LISTENER)
+        .then(SeccompNotifier::new);
+
+    let is_tsync = flags.contains(FilterFlags::SECCOMP_FILTER_FLAG_TSYNC);
+    let tasks = is_tsync.then(|| ctx.process.tasks().lock());
+
+    let seccomp = ctx.posix_thread.seccomp();
+    let mut state = seccomp.state.lock();
+
+    if let Some(tasks) = tasks.as_ref() {
+        // Other threads can be synchronized only if their filters are ancestors of ours.
+        for task in tasks.as_slice() {
+            let posix_thread = task.as_posix_thread().unwrap();
+            if core::ptr::eq(posix_thread, ctx.posix_thread) {
+                continue;
+            }
+
+            let other_state = posix_thread.seccomp().state.lock();
+            let can_sync = match other_state.mode {
+                SeccompMode::Disabled => true,
+                SeccompMode::Strict => false,
+                SeccompMode::Filter => other_state
+                    .filter
+                    .as_ref()
+                    .unwrap()
+                    .is_ancestor_of(state.filter.as_ref()),
+            };
+            if can_sync {
+                continue;
+            }
+
+            if flags.contains(FilterFlags::SECCOMP_FILTER_FLAG_TSYNC_ESRCH) {
+                return_errno_with_message!(Errno::ESRCH, "a thread cannot be synchronized");
+            }
+            let tid = ctx.process.pid_ns().ns_id_of(posix_thread.tid()).unwrap();
+            return Ok(tid as isize);
+        }
+    }
+
+    if state.mode == SeccompMode::Strict {
+        return_errno_with_message!(Errno::EINVAL, "the strict mode is already enabled");
+    }
+
+    let filters = core::iter::successors(state.filter.as_ref(), |filter| filter.prev.as_ref());
+    if notifier.is_some() && filters.clone().any(|filter| filter.notifier.is_some()) {
+        return_errno_with_message!(Errno::EBUSY, "another filter already has a listener");
+    }
+    let total_insns = filters
+        .map(|filter| filter.prog.num_insns() + INSNS_PENALTY)
+        .sum::<usize>()
+        + prog.num_insns();
+    if total_insns > MAX_INSNS_PER_PATH {
+        return_errno_with_message!(Errno::ENOMEM, "the filters have too many instructions");
+    }
+
+    let filter = Arc::new(SeccompFilter {
+        prog,
+        prev: state.filter.take(),
+        is_logged: flags.contains(FilterFlags::SECCOMP_FILTER_FLAG_LOG),
+        notifier: notifier.clone(),
+    });
+    state.filter = Some(filter.clone());
+    seccomp.set_mode(&mut state, SeccompMode::Filter);
+    drop(state);
+
+    if let Some(tasks) = tasks {
+        let mut old_filters = Vec::new();
+        let no_new_privs = ctx.posix_thread.credentials().no_new_privs();
+
+        for task in tasks.as_slice() {
+            let posix_thread = task.as_posix_thread().unwrap();
+            if core::ptr::eq(posix_thread, ctx.posix_thread) {
+                continue;
+            }
+
+            // Like Linux, the synchronized threads inherit `no_new_privs`. It is set before the
+            // filter is published, so that the filter never applies without the flag.
+            if no_new_privs {
+                posix_thread.set_no_new_privs();
+            }
+
+            let other_seccomp = posix_thread.seccomp();
+            let mut other_state = other_seccomp.state.lock();
+            old_filters.push(other_state.filter.replace(filter.clone()));
+            other_seccomp.set_mode(&mut other_state, SeccompMode::Filter);
+        }
+
+        drop(tasks);
+        drop(old_filters);
+    }
+
+    let Some(notifier) = notifier else {
+        return Ok(0);
+    };
+
+    let file = Arc::new(SeccompNotifyFile::new(notifier));
+    let file_table = ctx.thread_local.borrow_file_table();
+    let fd = file_table.unwrap().write().insert(file, FdFlags::CLOEXEC);
+    Ok(fd.into())
+}
@@ -0,0 +1,614 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Seccomp user notifications.
+//!
+//! A filter installed with `SECCOMP_FILTER_FLAG_NEW_LISTENER` comes with a listener file. When
+//! the filter returns `SECCOMP_RET_USER_NOTIF`, the thread making the syscall (the target) sends
+//! a notification to the listener and waits. The process holding the listener (the supervisor)
+//! receives the notification, optionally installs files into the target, and replies with the
+//! result of the syscall, or asks the target to continue the syscall.
+
+use core::fmt::Display;
+
+use ostd::{sync::WaitQueue, task::Task};
+
+use super::SeccompData;
+use crate::{
+    events::IoEvents,
+    fs::{
+        file::{
+            AccessMode, CreationFlags, FileCommon, FileLike, StatusFlags,
+            file_table::{FdFlags, FileDesc},
+        },
+        pseudofs::AnonInodeFs,
+    },
+    prelude::*,
+    process::{
+        ResourceType,
+        signal::{PollHandle, Pollable, Pollee},
+    },
+    thread::Tid,
+    util::ioctl::{RawIoctl, dispatch_ioctl},
+};
+
+/// `struct seccomp_notif` in Linux.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct CSeccompNotif {
+    id: u64,
+    pid: u32,
+    flags: u32,
+    data: SeccompData,
+}
+
+/// `struct seccomp_notif_resp` in Linux.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct CSeccompNotifResp {
+    id: u64,
+    val: i64,
+    error: i32,
+    flags: u32,
+}
+
+/// `struct seccomp_notif_addfd` in Linux.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct CSeccompNotifAddfd {
+    id: u64,
+    flags: u32,
+    srcfd: u32,
+    newfd: u32,
+    newfd_flags: u32,
+}
+
+/// `struct seccomp_notif_sizes` in Linux.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(super) struct CSeccompNotifSizes {
+    seccomp_notif: u16,
+    seccomp_notif_resp: u16,
+    seccomp_data: u16,
+}
+
+impl CSeccompNotifSizes {
+    pub(super) fn new() -> Self {
+        Self {
+            seccomp_notif: size_of::<CSeccompNotif>() as u16,
+            seccomp_notif_resp: size_of::<CSeccompNotifResp>() as u16,
+            seccomp_data: size_of::<SeccompData>() as u16,
+        }
+    }
+}
+
+/// The flag of `struct seccomp_notif_resp` that asks the target to continue the syscall.
+const SECCOMP_USER_NOTIF_FLAG_CONTINUE: u32 = 1 << 0;
+
+/// The flags of `struct seccomp_notif_addfd`.
+const SECCOMP_ADDFD_FLAG_SETFD: u32 = 1 << 0;
+const SECCOMP_ADDFD_FLAG_SEND: u32 = 1 << 1;
+
+mod ioctl_defs {
+    use super::{CSeccompNotif, CSeccompNotifAddfd, CSeccompNotifResp};
+    use crate::util::ioctl::{InData, InOutData, ioc};
+
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/seccomp.h>
+    pub(super) type Recv    = ioc!(SECCOMP_IOCTL_NOTIF_RECV,     b'!', 0, InOutData<CSeccompNotif>);
+    pub(super) type Send    = ioc!(SECCOMP_IOCTL_NOTIF_SEND,     b'!', 1, InOutData<CSeccompNotifResp>);
+    pub(super) type IdValid = ioc!(SECCOMP_IOCTL_NOTIF_ID_VALID, b'!', 2, InData<u64>);
+    pub(super) type AddFd   = ioc!(SECCOMP_IOCTL_NOTIF_ADDFD,    b'!', 3, InData<CSeccompNotifAddfd>);
+}
+
+/// The reply to a notification.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(super) enum NotifyReply {
+    /// The syscall should be executed.
+    Continue,
+    /// The syscall should be skipped and return the value.
+    Return(isize),
+}
+
+/// The shared state between the targets and the supervisor of a filter.
+pub(super) struct SeccompNotifier {
+    inner: Mutex<NotifierInner>,
+    /// The pollee of the listener.
+    pollee: Pollee,
+    /// The wait queue for targets waiting for replies and supervisors waiting for added files.
+    wait_queue: WaitQueue,
+}
+
+struct NotifierInner {
+    notifications: BTreeMap<u64, Notification>,
+    next_id: u64,
+    /// Whether the listener has been closed.
+    is_detached: bool,
+    /// Whether the filter is no longer used by any threads.
+    is_orphaned: bool,
+}
+
+struct Notification {
+    tid: Tid,
+    data: SeccompData,
+    state: NotifyState,
+    /// The pending requests to add files to the target.
+    addfd_requests: VecDeque<Arc<AddFdRequest>>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum NotifyState {
+    /// The notification has not been received by the supervisor.
+    Init,
+    /// The notification has been received by the supervisor.
+    Sent,
+    /// The supervisor has asked to add a file to the target and return its FD as the result.
+    SendingFd,
+    /// The supervisor has replied.
+    Replied(NotifyReply),
+}
+
+/// A request to add a file to the target.
+struct AddFdRequest {
+    file: Arc<dyn FileLike>,
+    /// The FD that the file should be installed at, or `None` for the lowest available FD.
+    fd: Option<FileDesc>,
+    fd_flags: FdFlags,
+    is_send: bool,
+    result: Mutex<Option<Result<FileDesc>>>,
+}
+
+impl SeccompNotifier {
+    pub(super) fn new() -> Arc<Self> {
+        Arc::new(Self {
+            inner: Mutex::new(NotifierInner {
+                notifications: BTreeMap::new(),
+                next_id: 1,
+                is_detached: false,
+                is_orphaned: false,
+            }),
+            pollee: Pollee::new(),
+            wait_queue: WaitQueue::new(),
+        })
+    }
+
+    /// Sends a notification and waits for the reply.
+    ///
+    /// # Errors
+    ///
+    /// Returns `ENOSYS` if the listener has been closed, or `ERESTARTSYS` if the wait is
+    /// interrupted by a signal.
+    pub(super) fn notify(&self, data: &SeccompData, ctx: &Context) -> Result<NotifyReply> {
+        let id = {
+            let mut inner = self.inner.lock();
+            if inner.is_detached {
+                return_errno_with_message!(Errno::ENOSYS, "the listener has been closed");
+            }
+
+            let id = inner.next_id;
+            inner.next_id += 1;
+            inner.notifications.insert(
+                id,
+                Notification {
+                    tid: ctx.posix_thread.tid(),
+                    data: *data,
+                    state: NotifyState::Init,
+                    addfd_requests: VecDeque::new(),
+                },
+            );
+            id
+        };
+        self.pollee.notify(IoEvents::IN);
+
+        let result = self.wait_for_reply(id, ctx);
+
+        let mut inner = self.inner.lock();
+        if let Some(notification) = inner.notifications.remove(&id) {
+            // The supervisor's requests cannot be handled anymore.
+            for request in notification.addfd_requests {
+                *request.result.lock() = Some(Err(Error::with_message(
+                    Errno::ESRCH,
+                    "the target is no longer waiting",
+                )));
+            }
+        }
+        drop(inner);
+        self.wait_queue.wake_all();
+
+        result
+    }
+
+    fn wait_for_reply(&self, id: u64, ctx: &Context) -> Result<NotifyReply> {
+        enum Event {
+            AddFd(Arc<AddFdRequest>),
+            Replied(NotifyReply),
+            Detached,
+        }
+
+        loop {
+            let event = self.wait_queue.pause_until(|| {
+                let mut inner = self.inner.lock();
+                if inner.is_detached {
+                    return Some(Event::Detached);
+                }
+
+                let notification = inner.notifications.get_mut(&id).unwrap();
+                if let Some(request) = notification.addfd_requests.pop_front() {
+                    return Some(Event::AddFd(request));
+                }
+                match notification.state {
+                    NotifyState::Replied(reply) => Some(Event::Replied(reply)),
+                    _ => None,
+                }
+            });
+
+            match event {
+                Ok(Event::AddFd(request)) => {
+                    let result = request.install(ctx);
+                    if request.is_send {
+                        let mut inner = self.inner.lock();
+                        let notification = inner.notifications.get_mut(&id).unwrap();
+                        notification.state = match result {
+                            Ok(fd) => NotifyState::Replied(NotifyReply::Return(fd.into())),
+                            // Let the supervisor reply again.
+                            Err(_) => NotifyState::Sent,
+                        };
+                    }
+                    *request.result.lock() = Some(result);
+                    self.wait_queue.wake_all();
+                }
+                Ok(Event::Replied(reply)) => return Ok(reply),
+                Ok(Event::Detached) => {
+                    return_errno_with_message!(Errno::ENOSYS, "the listener has been closed")
+                }
+                Err(err) if err.error() == Errno::EINTR => {
+                    return_errno_with_message!(
+                        Errno::ERESTARTSYS,
+                        "the wait for the reply is interrupted"
+                    )
+                }
+                Err(err) => return Err(err),
+            }
+        }
+    }
+
+    /// Marks the filter as no longer used by any threads.
+    pub(super) fn orphan(&self) {
+        self.inner.lock().is_orphaned = true;
+        self.pollee.notify(IoEvents::HUP);
+    }
+
+    /// Marks the listener as closed and fails all pending notifications.
+    fn detach(&self) {
+        self.inner.lock().is_detached = true;
+        self.wait_queue.wake_all();
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let inner = self.inner.lock();
+
+        let mut events = IoEvents::empty();
+        for notification in inner.notifications.values() {
+            match notification.state {
+                NotifyState::Init => events |= IoEvents::IN | IoEvents::RDNORM,
+                NotifyState::Sent => events |= IoEvents::OUT,
+                NotifyState::SendingFd | NotifyState::Replied(_) => (),
+            }
+        }
+        if inner.is_orphaned {
+            events |= IoEvents::HUP;
+        }
+
+        events
+    }
+
+    fn try_recv(&self) -> Result<(u64, Tid, SeccompData)> {
+        let mut inner = self.inner.lock();
+
+        let Some((id, notification)) = inner
+            .notifications
+            .iter_mut()
+            .find(|(_, notification)| notification.state == NotifyState::Init)
+        else {
+            return_errno_with_message!(Errno::EAGAIN, "no notifications are pending");
+        };
+        notification.state = NotifyState::Sent;
+
+        Ok((*id, notification.tid, notification.data))
+    }
+
+    /// Puts back a notification that the supervisor failed to receive.
+    fn unrecv(&self, id: u64) {
+        let mut inner = self.inner.lock();
+        if let Some(notification) = inner.notifications.get_mut(&id)
+            && notification.state == NotifyState::Sent
+        {
+            notification.state = NotifyState::Init;
+        }
+        drop(inner);
+
+        self.pollee.notify(IoEvents::IN);
+    }
+
+    fn reply(&self, resp: &CSeccompNotifResp) -> Result<()> {
+        if resp.flags & !SECCOMP_USER_NOTIF_FLAG_CONTINUE != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the response flags are invalid");
+        }
+        let reply = if resp.flags & SECCOMP_USER_NOTIF_FLAG_CONTINUE != 0 {
+            if resp.val != 0 || resp.error != 0 {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "continuing the syscall does not accept a return value"
+                );
+            }
+            NotifyReply::Continue
+        } else if resp.error != 0 {
+            NotifyReply::Return(resp.error as isize)
+        } else {
+            NotifyReply::Return(resp.val as isize)
+        };
+
+        let mut inner = self.inner.lock();
+        let notification = find_sent(&mut inner, resp.id)?;
+        notification.state = NotifyState::Replied(reply);
+        drop(inner);
+
+        self.wait_queue.wake_all();
+        Ok(())
+    }
+
+    fn is_id_valid(&self, id: u64) -> bool {
+        let inner = self.inner.lock();
+        inner
+            .notifications
+            .get(&id)
+            .is_some_and(|notification| notification.state == NotifyState::Sent)
+    }
+
+    /// Asks the target to add a file and waits for the result.
+    fn add_fd(&self, id: u64, request: AddFdRequest) -> Result<FileDesc> {
+        let request = Arc::new(request);
+
+        let mut inner = self.inner.lock();
+        let notification = find_sent(&mut inner, id)?;
+        if request.is_send {
+            if !notification.addfd_requests.is_empty() {
+                return_errno_with_message!(
+                    Errno::EBUSY,
+                    "other requests to add files are still pending"
+                );
+            }
+            notification.state = NotifyState::SendingFd;
+        }
+        notification.addfd_requests.push_back(request.clone());
+        drop(inner);
+        self.wait_queue.wake_all();
+
+        let result = self.wait_queue.pause_until(|| request.result.lock().take());
+        if let Ok(result) = result {
+            return result;
+        }
+
+        // The wait is interrupted. Withdraw the request if the target has not handled it.
+        let mut inner = self.inner.lock();
+        if let Some(notification) = inner.notifications.get_mut(&id)
+            && let Some(pos) = notification
+                .addfd_requests
+                .iter()
+                .position(|pending| Arc::ptr_eq(pending, &request))
+        {
+            notification.addfd_requests.remove(pos);
+            if request.is_send {
+                notification.state = NotifyState::Sent;
+            }
+            drop(inner);
+            return_errno_with_message!(
+                Errno::ERESTARTSYS,
+                "the wait for the target is interrupted"
+            );
+        }
+        drop(inner);
+
+        // The target has taken the request, so wait for the result without interruption.
+        self.wait_queue.wait_until(|| request.result.lock().take())
+    }
+}
+
+/// Finds a notification that has been received by the supervisor but not replied.
+fn find_sent(inner: &mut NotifierInner, id: u64) -> Result<&mut Notification> {
+    let Some(notification) = inner.notifications.get_mut(&id) else {
+        return_errno_with_message!(Errno::ENOENT, "the notification does not exist");
+    };
+    if notification.state != NotifyState::Sent {
+        return_errno_with_message!(
+            Errno::EINPROGRESS,
+            "the notification is not received or already replied"
+        );
+    }
+    Ok(notification)
+}
+
+impl AddFdRequest {
+    /// Installs the file to the file table of the target, which is the current thread.
+    fn install(&self, ctx: &Context) -> Result<FileDesc> {
+        let file_table = ctx.thread_local.borrow_file_table();
+
+        let Some(fd) = self.fd else {
+            let fd = file_table
+                .unwrap()
+                .write()
+                .insert(self.file.clone(), self.fd_flags);
+            return Ok(fd);
+        };
+
+        if u64::from(fd)
+            >= ctx
+                .process
+                .resource_limits()
+                .get_rlimit(ResourceType::RLIMIT_NOFILE)
+                .get_cur()
+        {
+            return_errno_with_message!(Errno::EBADF, "the FD exceeds the resource limit");
+        }
+        let replaced_file =
+            file_table
+                .unwrap()
+                .write()
+                .insert_at(fd, self.file.clone(), self.fd_flags);
+        drop(replaced_file);
+
+        Ok(fd)
+    }
+}
+
+/// The listener file of a filter.
+pub(super) struct SeccompNotifyFile {
+    notifier: Arc<SeccompNotifier>,
+    common: FileCommon,
+}
+
+impl SeccompNotifyFile {
+    pub(super) fn new(notifier: Arc<SeccompNotifier>) -> Self {
+        let pseudo_path = AnonInodeFs::new_path(|_| "anon_inode:seccomp notify".to_string());
+        Self {
+            notifier,
+            common: FileCommon::new(pseudo_path, AccessMode::O_RDWR, StatusFlags::empty()),
+        }
+    }
+
+    fn recv(&self, buf: &mut CSeccompNotif) -> Result<()> {
+        // Like Linux, the buffer must be zeroed so that the structure can be extended later.
+        if buf.as_bytes().iter().any(|byte| *byte != 0) {
+            return_errno_with_message!(Errno::EINVAL, "the notification buffer is not zeroed");
+        }
+
+        let (id, tid, data) = self.wait_events(IoEvents::IN, None, || self.notifier.try_recv())?;
+
+        let current = current!();
+        *buf = CSeccompNotif {
+            id,
+            pid: current.pid_ns().ns_id_of(tid).unwrap_or(0),
+            flags: 0,
+            data,
+        };
+
+        Ok(())
+    }
+
+    fn add_fd(&self, addfd: &CSeccompNotifAddfd) -> Result<FileDesc> {
+        if addfd.flags & !(SECCOMP_ADDFD_FLAG_SETFD | SECCOMP_ADDFD_FLAG_SEND) != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the flags are invalid");
+        }
+        if addfd.newfd_flags & !CreationFlags::O_CLOEXEC.bits() != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the FD flags are invalid");
+        }
+        if addfd.newfd != 0 && addfd.flags & SECCOMP_ADDFD_FLAG_SETFD == 0 {
+            return_errno_with_message!(Errno::EINVAL, "the FD is specified without SETFD");
+        }
+
+        let file = {
+            let current_task = Task::current().unwrap();
+            let thread_local = current_task.as_thread_local().unwrap();
+            let file_table = thread_local.borrow_file_table();
+            let file_table_locked = file_table.unwrap().read();
+            file_table_locked
+                .get_file(FileDesc::try_from(addfd.srcfd as i32)?)?
+                .clone()
+        };
+
+        let fd = if addfd.flags & SECCOMP_ADDFD_FLAG_SETFD != 0 {
+            Some(FileDesc::try_from(addfd.newfd as i32)?)
+        } else {
+            None
+        };
+        let fd_flags = if addfd.newfd_flags != 0 {
+            FdFlags::CLOEXEC
+        } else {
+            FdFlags::empty()
+        };
+
+        self.notifier.add_fd(
+            addfd.id,
+            AddFdRequest {
+                file,
+                fd,
+                fd_flags,
+                is_send: addfd.flags & SECCOMP_ADDFD_FLAG_SEND != 0,
+                result: Mutex::new(None),
+            },
+        )
+    }
+}
+
+impl Drop for SeccompNotifyFile {
+    fn drop(&mut self) {
+        self.notifier.detach();
+    }
+}
+
+impl Pollable for SeccompNotifyFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.notifier
+            .pollee
+            .poll_with(mask, poller, || self.notifier.check_io_events())
+    }
+}
+
+impl FileLike for SeccompNotifyFile {
+    fn ioctl(&self, raw_ioctl: RawIoctl) -> Result<i32> {
+        use ioctl_defs::*;
+
+        dispatch_ioctl!(match raw_ioctl {
+            cmd @ Recv => {
+                let mut notif = cmd.read()?;
+                self.recv(&mut notif)?;
+                if let Err(err) = cmd.write(&notif) {
+                    self.notifier.unrecv(notif.id);
+                    return Err(err);
+                }
+                Ok(0)
+            }
+            cmd @ Send => {
+                let resp = cmd.read()?;
+                self.notifier.reply(&resp)?;
+                Ok(0)
+            }
+            cmd @ IdValid => {
+                let id = cmd.read()?;
+                if !self.notifier.is_id_valid(id) {
+                    return_errno_with_message!(Errno::ENOENT, "the notification is not valid");
+                }
+                Ok(0)
+            }
+            cmd @ AddFd => {
+                let addfd = cmd.read()?;
+                let fd = self.add_fd(&addfd)?;
+                Ok(fd.into())
+            }
+            _ => return_errno_with_message!(Errno::ENOTTY, "the ioctl command is unknown"),
+        })
+    }
+
+    fn common(&self) -> &FileCommon {
+        &self.common
+    }
+
+    fn dump_proc_fdinfo(self: Arc<Self>, fd_flags: FdFlags) -> Box<dyn Display> {
+        struct FdInfo {
+            flags: u32,
+        }
+
+        impl Display for FdInfo {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                writeln!(f, "pos:\t{}", 0)?;
+                writeln!(f, "flags:\t0{:o}", self.flags)?;
+                writeln!(f, "mnt_id:\t{}", AnonInodeFs::mount_node().id())?;
+                writeln!(f, "ino:\t{}", AnonInodeFs::shared_inode().ino())
+            }
+        }
+
+        let mut flags = self.common.status_flags().bits() | self.common.access_mode() as u32;
+        if fd_flags.contains(FdFlags::CLOEXEC) {
+            flags |= CreationFlags::O_CLOEXEC.bits();
+        }
+        Box::new(FdInfo { flags })
+    }
+}
@@ -65,8 +65,12 @@ macro_rules! import_generic_syscall_entries {
             getuid::sys_getuid,
             getxattr::{sys_fgetxattr, sys_getxattr, sys_lgetxattr},
             inotify::{sys_inotify_add_watch, sys_inotify_init1, sys_inotify_rm_watch},
+            io_uring::{sys_io_uring_enter, sys_io_uring_register, sys_io_uring_setup},
             ioctl::sys_ioctl,
             kill::sys_kill,
+            landlock::{
+                sys_landlock_add_rule, sys_landlock_create_ruleset, sys_landlock_restrict_self,
+            },
             link::sys_linkat,
             listen::sys_listen,
             listmount::sys_listmount,
@@ -80,7 +84,16 @@ macro_rules! import_generic_syscall_entries {
             mount::sys_mount,
             move_mount::sys_move_mount,
             mprotect::sys_mprotect,
+            mq_getsetattr::sys_mq_getsetattr,
+            mq_notify::sys_mq_notify,
+            mq_open::{sys_mq_open, sys_mq_unlink},
+            mq_timedreceive::sys_mq_timedreceive,
+            mq_timedsend::sys_mq_timedsend,
             mremap::sys_mremap,
+            msgctl::sys_msgctl,
+            msgget::sys_msgget,
+            msgrcv::sys_msgrcv,
+            msgsnd::sys_msgsnd,
             msync::sys_msync,
             munmap::sys_munmap,
             nanosleep::{sys_clock_nanosleep, sys_nanosleep},
@@ -123,6 +136,7 @@ macro_rules! import_generic_syscall_entries {
             sched_setparam::sys_sched_setparam,
             sched_setscheduler::sys_sched_setscheduler,
             sched_yield::sys_sched_yield,
+            seccomp::sys_seccomp,
             semctl::sys_semctl,
             semget::sys_semget,
             semop::{sys_semop, sys_semtimedop},
@@ -151,6 +165,10 @@ macro_rules! import_generic_syscall_entries {
             setsockopt::sys_setsockopt,
             setuid::sys_setuid,
             setxattr::{sys_fsetxattr, sys_lsetxattr, sys_setxattr},
+            shmat::sys_shmat,
+            shmctl::sys_shmctl,
+            shmdt::sys_shmdt,
+            shmget::sys_shmget,
             shutdown::sys_shutdown,
             sigaltstack::sys_sigaltstack,
             signalfd::sys_signalfd4,
@@ -159,6 +177,7 @@ macro_rules! import_generic_syscall_entries {
             stat::{sys_fstat, sys_fstatat},
             statfs::{sys_fstatfs, sys_statfs},
             statx::sys_statx,
+            swapon::{sys_swapoff, sys_swapon},
             symlink::sys_symlinkat,
             sync::{sys_sync, sys_syncfs},
             sysinfo::sys_sysinfo,
@@ -174,6 +193,7 @@ macro_rules! import_generic_syscall_entries {
             uname::sys_uname,
             unlink::sys_unlinkat,
             unshare::sys_unshare,
+            userfaultfd::sys_userfaultfd,
             utimens::sys_utimensat,
             wait4::sys_wait4,
             waitid::sys_waitid,
@@ -359,10 +379,24 @@ macro_rules! define_syscalls_with_generic_syscall_table {
             SYS_GETEGID = 177                => sys_getegid(args[..0]);
             SYS_GETTID = 178                 => sys_gettid(args[..0]);
             SYS_SYSINFO = 179                => sys_sysinfo(args[..1]);
+            SYS_MQ_OPEN = 180                => sys_mq_open(args[..4]);
+            SYS_MQ_UNLINK = 181              => sys_mq_unlink(args[..1]);
+            SYS_MQ_TIMEDSEND = 182           => sys_mq_timedsend(args[..5]);
+            SYS_MQ_TIMEDRECEIVE = 183        => sys_mq_timedreceive(args[..5]);
+            SYS_MQ_NOTIFY = 184              => sys_mq_notify(args[..2]);
+            SYS_MQ_GETSETATTR = 185          => sys_mq_getsetattr(args[..3]);
+            SYS_MSGGET = 186                 => sys_msgget(args[..2]);
+            SYS_MSGCTL = 187                 => sys_msgctl(args[..3]);
+            SYS_MSGRCV = 188                 => sys_msgrcv(args[..5]);
+            SYS_MSGSND = 189                 => sys_msgsnd(args[..4]);
             SYS_SEMGET = 190                 => sys_semget(args[..3]);
             SYS_SEMCTL = 191                 => sys_semctl(args[..4]);
             SYS_SEMTIMEDOP = 192             => sys_semtimedop(args[..4]);
             SYS_SEMOP = 193                  => sys_semop(args[..3]);
+            SYS_SHMGET = 194                 => sys_shmget(args[..3]);
+            SYS_SHMCTL = 195                 => sys_shmctl(args[..3]);
+            SYS_SHMAT = 196                  => sys_shmat(args[..3]);
+            SYS_SHMDT = 197                  => sys_shmdt(args[..1]);
             SYS_SOCKET = 198                 => sys_socket(args[..3]);
             SYS_SOCKETPAIR = 199             => sys_socketpair(args[..4]);
             SYS_BIND = 200                   => sys_bind(args[..3]);
@@ -385,6 +419,8 @@ macro_rules! define_syscalls_with_generic_syscall_table {
             SYS_EXECVE = 221                 => sys_execve(args[..3], &mut user_ctx);
             SYS_MMAP = 222                   => sys_mmap(args[..6]);
             SYS_FADVISE64 = 223              => sys_fadvise64(args[..4]);
+            SYS_SWAPON = 224                 => sys_swapon(args[..2]);
+            SYS_SWAPOFF = 225                => sys_swapoff(args[..1]);
             SYS_MPROTECT = 226               => sys_mprotect(args[..3]);
             SYS_MSYNC = 227                  => sys_msync(args[..3]);
             SYS_MADVISE = 233                => sys_madvise(args[..3]);
@@ -397,13 +433,18 @@ macro_rules! define_syscalls_with_generic_syscall_table {
             SYS_SCHED_SETATTR = 274          => sys_sched_setattr(args[..3]);
             SYS_SCHED_GETATTR = 275          => sys_sched_getattr(args[..4]);
             SYS_RENAMEAT2 = 276              => sys_renameat2(args[..5]);
+            SYS_SECCOMP = 277                => sys_seccomp(args[..3]);
             SYS_GETRANDOM = 278              => sys_getrandom(args[..3]);
             SYS_MEMFD_CREATE = 279           => sys_memfd_create(args[..2]);
             SYS_EXECVEAT = 281               => sys_execveat(args[..5], &mut user_ctx);
+            SYS_USERFAULTFD = 282            => sys_userfaultfd(args[..1]);
             SYS_PREADV2 = 286                => sys_preadv2(args[..6]);
             SYS_PWRITEV2 = 287               => sys_pwritev2(args[..6]);
             SYS_STATX = 291                  => sys_statx(args[..5]);
             SYS_PIDFD_SEND_SIGNAL = 424      => sys_pidfd_send_signal(args[..4]);
+            SYS_IO_URING_SETUP = 425         => sys_io_uring_setup(args[..2]);
+            SYS_IO_URING_ENTER = 426         => sys_io_uring_enter(args[..6]);
+            SYS_IO_URING_REGISTER = 427      => sys_io_uring_register(args[..4]);
             SYS_MOVE_MOUNT = 429             => sys_move_mount(args[..5]);
             SYS_FSOPEN = 430                 => sys_fsopen(args[..2]);
             SYS_FSCONFIG = 431               => sys_fsconfig(args[..5]);
@@ -414,6 +455,9 @@ macro_rules! define_syscalls_with_generic_syscall_table {
             SYS_PIDFD_GETFD = 438            => sys_pidfd_getfd(args[..3]);
             SYS_FACCESSAT2 = 439             => sys_faccessat2(args[..4]);
             SYS_EPOLL_PWAIT2 = 441           => sys_epoll_pwait2(args[..6]);
+            SYS_LANDLOCK_CREATE_RULESET = 444 => sys_landlock_create_ruleset(args[..3]);
+            SYS_LANDLOCK_ADD_RULE = 445      => sys_landlock_add_rule(args[..4]);
+            SYS_LANDLOCK_RESTRICT_SELF = 446 => sys_landlock_restrict_self(args[..2]);
             SYS_FCHMODAT2 = 452              => sys_fchmodat2(args[..4]);
             SYS_LISTMOUNT = 458              => sys_listmount(args[..4]);
             // Architecture-specific syscalls
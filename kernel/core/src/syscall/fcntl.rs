@@ -174,7 +174,12 @@ fn handle_getown(fd: FileDesc, ctx: &Context) -> Result<SyscallReturn> {
     let mut file_table = ctx.thread_local.borrow_file_table_mut();
     let file = get_file_fast!(&mut file_table, fd);
 
-    let pid = file.common().owner().pid().unwrap_or(0);
+    let pid = file
+        .common()
+        .owner()
+        .pid()
+        .and_then(|pid| ctx.process.pid_ns().ns_id_of(pid))
+        .unwrap_or(0);
     Ok(SyscallReturn::Return(pid as _))
 }
 
@@ -189,12 +194,18 @@ fn handle_setown(fd: FileDesc, arg: u64, ctx: &Context) -> Result<SyscallReturn>
     let owner_process = if pid == 0 {
         None
     } else {
-        Some(pid_table::pid_table_mut().get_process(pid).ok_or_else(|| {
-            Error::with_message(
-                Errno::ESRCH,
-                "the process to be a file owner does not exist",
-            )
-        })?)
+        let process = ctx
+            .process
+            .pid_ns()
+            .global_id_of(pid)
+            .and_then(|pid| pid_table::pid_table_mut().get_process(pid))
+            .ok_or_else(|| {
+                Error::with_message(
+                    Errno::ESRCH,
+                    "the process to be a file owner does not exist",
+                )
+            })?;
+        Some(process)
     };
 
     let mut file_table = ctx.thread_local.borrow_file_table_mut();
@@ -11,9 +11,10 @@ use crate::{
         FutexFlags, FutexOp, FutexVisibility, futex_op_and_flags_from_u32, futex_requeue,
         futex_wait, futex_wait_bitset, futex_wake, futex_wake_bitset, futex_wake_op,
     },
-    syscall::SyscallReturn,
+    syscall::{ClockId, SyscallReturn},
     time::{
         clocks::{MonotonicClock, RealTimeClock},
+        time_ns::TimeNamespace,
         timer::Timeout,
         timespec_t,
         wait::ManagedTimeout,
@@ -60,8 +61,12 @@ pub(super) fn sys_futex(
             // employ FUTEX_WAIT_BITSET with val3 specified as FUTEX_BITSET_MATCH_ANY.
             if futex_op == FutexOp::FUTEX_WAIT {
                 Timeout::After(timeout)
-            } else {
+            } else if is_real_time {
                 Timeout::When(timeout)
+            } else {
+                // The absolute time of the monotonic clock is observed in the time namespace.
+                let time_ns = TimeNamespace::current();
+                Timeout::When(time_ns.from_ns_time(ClockId::CLOCK_MONOTONIC, timeout))
             }
         };
 
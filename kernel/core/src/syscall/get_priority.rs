@@ -85,7 +85,7 @@ impl PriorityTarget {
                 let pid = if who == 0 {
                     ctx.process.pid()
                 } else {
-                    who as Pid
+                    to_global_id(who, ctx)?
                 };
                 Self::Process(pid)
             }
@@ -93,7 +93,7 @@ impl PriorityTarget {
                 let pgid = if who == 0 {
                     ctx.process.pgid()
                 } else {
-                    who as Pgid
+                    to_global_id(who, ctx)?
                 };
                 Self::ProcessGroup(pgid)
             }
@@ -109,6 +109,14 @@ impl PriorityTarget {
     }
 }
 
+/// Translates an ID in the PID namespace of the current process to the global ID.
+fn to_global_id(id: u32, ctx: &Context) -> Result<u32> {
+    ctx.process
+        .pid_ns()
+        .global_id_of(id)
+        .ok_or_else(|| Error::with_message(Errno::ESRCH, "the target does not exist"))
+}
+
 #[expect(non_camel_case_types)]
 #[repr(i32)]
 #[derive(Clone, Debug, TryFromInt)]
@@ -9,22 +9,29 @@ use crate::{
 pub(super) fn sys_getpgid(pid: Pid, ctx: &Context) -> Result<SyscallReturn> {
     debug!("pid = {}", pid);
 
+    let pid_ns = ctx.process.pid_ns();
+
     // The documentation quoted below is from
     // <https://www.man7.org/linux/man-pages/man2/getpgid.2.html>.
 
     // "If `pid` is equal to 0, getpgid() shall return the process group ID of the calling
     // process."
     if pid == 0 {
-        return Ok(SyscallReturn::Return(ctx.process.pgid() as _));
+        let id = pid_ns.ns_id_of(ctx.process.pgid()).unwrap_or(0);
+        return Ok(SyscallReturn::Return(id as _));
     }
 
-    let process = pid_table::pid_table_mut().get_process(pid).ok_or_else(|| {
-        Error::with_message(Errno::ESRCH, "the process to get the PGID does not exist")
-    })?;
+    let process = pid_ns
+        .global_id_of(pid)
+        .and_then(|pid| pid_table::pid_table_mut().get_process(pid))
+        .ok_or_else(|| {
+            Error::with_message(Errno::ESRCH, "the process to get the PGID does not exist")
+        })?;
 
     // The man pages allow the implementation to return `EPERM` if `process` is in a different
     // session than the current process. Linux does not perform this check by default, but some
     // strict security policies (e.g. SELinux) may do so.
 
-    Ok(SyscallReturn::Return(process.pgid() as _))
+    let id = pid_ns.ns_id_of(process.pgid()).unwrap_or(0);
+    Ok(SyscallReturn::Return(id as _))
 }
@@ -4,5 +4,12 @@ use super::SyscallReturn;
 use crate::prelude::*;
 
 pub(super) fn sys_getppid(ctx: &Context) -> Result<SyscallReturn> {
-    Ok(SyscallReturn::Return(ctx.process.parent().pid() as _))
+    // The parent process is invisible if it is outside the PID namespace. In this case, zero is
+    // returned, just like Linux.
+    let ppid = ctx
+        .process
+        .pid_ns()
+        .ns_id_of(ctx.process.parent().pid())
+        .unwrap_or(0);
+    Ok(SyscallReturn::Return(ppid as _))
 }
@@ -0,0 +1,98 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file::file_table::{FdFlags, RawFileDesc, get_file_fast},
+        io_uring::{IoUringEnterFlags, IoUringFile, IoUringParams, IoUringRegisterOp},
+    },
+    prelude::*,
+    process::{posix_thread::ContextPthreadAdminApi, signal::sig_mask::SigMask},
+};
+
+pub(super) fn sys_io_uring_setup(
+    entries: u32,
+    params_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!("entries = {}, params_addr = 0x{:x}", entries, params_addr);
+
+    let user_space = ctx.user_space();
+    let mut params = user_space.read_val::<IoUringParams>(params_addr)?;
+    debug!("params = {:?}", params);
+
+    let io_uring_file = Arc::new(IoUringFile::new(entries, &mut params)?);
+    user_space.write_val(params_addr, &params)?;
+
+    let file_table = ctx.thread_local.borrow_file_table();
+    let mut file_table_locked = file_table.unwrap().write();
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/io_uring/io_uring.c#L3866>.
+    let fd = file_table_locked.insert(io_uring_file, FdFlags::CLOEXEC);
+
+    Ok(SyscallReturn::Return(fd.into()))
+}
+
+pub(super) fn sys_io_uring_enter(
+    fd: RawFileDesc,
+    to_submit: u32,
+    min_complete: u32,
+    flags: u32,
+    sigmask_addr: Vaddr,
+    sigmask_size: usize,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = IoUringEnterFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid enter flags"))?;
+    debug!(
+        "fd = {}, to_submit = {}, min_complete = {}, flags = {:?}, sigmask_addr = 0x{:x}, sigmask_size = {}",
+        fd, to_submit, min_complete, flags, sigmask_addr, sigmask_size
+    );
+
+    let file = {
+        let mut file_table = ctx.thread_local.borrow_file_table_mut();
+        get_file_fast!(&mut file_table, fd.try_into()?).into_owned()
+    };
+    let io_uring_file = file
+        .downcast_ref::<IoUringFile>()
+        .ok_or_else(|| Error::with_message(Errno::EOPNOTSUPP, "the file is not an io_uring"))?;
+
+    if sigmask_addr != 0 && flags.contains(IoUringEnterFlags::IORING_ENTER_GETEVENTS) {
+        if sigmask_size != size_of::<SigMask>() {
+            return_errno_with_message!(Errno::EINVAL, "invalid sigmask size");
+        }
+
+        let sigmask = ctx.user_space().read_val::<SigMask>(sigmask_addr)?;
+        ctx.save_and_set_sig_mask(sigmask);
+    }
+
+    let submitted = io_uring_file.enter(to_submit, min_complete, flags, ctx)?;
+
+    Ok(SyscallReturn::Return(submitted as _))
+}
+
+pub(super) fn sys_io_uring_register(
+    fd: RawFileDesc,
+    opcode: u32,
+    arg: Vaddr,
+    nr_args: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let op = IoUringRegisterOp::try_from(opcode)
+        .map_err(|_| Error::with_message(Errno::EINVAL, "the register opcode is not supported"))?;
+    debug!(
+        "fd = {}, op = {:?}, arg = 0x{:x}, nr_args = {}",
+        fd, op, arg, nr_args
+    );
+
+    let file = {
+        let mut file_table = ctx.thread_local.borrow_file_table_mut();
+        get_file_fast!(&mut file_table, fd.try_into()?).into_owned()
+    };
+    let io_uring_file = file
+        .downcast_ref::<IoUringFile>()
+        .ok_or_else(|| Error::with_message(Errno::EOPNOTSUPP, "the file is not an io_uring"))?;
+
+    let res = io_uring_file.register(op, arg, nr_args, ctx)?;
+
+    Ok(SyscallReturn::Return(res as _))
+}
@@ -0,0 +1,49 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    prelude::*,
+    security::lsm::landlock::{do_add_rule, do_create_ruleset, do_restrict_self},
+};
+
+pub(super) fn sys_landlock_create_ruleset(
+    attr_addr: Vaddr,
+    size: usize,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "attr_addr = {:#x}, size = {}, flags = {:#x}",
+        attr_addr, size, flags
+    );
+
+    let ret = do_create_ruleset(attr_addr, size, flags, ctx)?;
+    Ok(SyscallReturn::Return(ret))
+}
+
+pub(super) fn sys_landlock_add_rule(
+    ruleset_fd: i32,
+    rule_type: u32,
+    rule_attr_addr: Vaddr,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "ruleset_fd = {}, rule_type = {}, rule_attr_addr = {:#x}, flags = {:#x}",
+        ruleset_fd, rule_type, rule_attr_addr, flags
+    );
+
+    do_add_rule(ruleset_fd, rule_type, rule_attr_addr, flags, ctx)?;
+    Ok(SyscallReturn::Return(0))
+}
+
+pub(super) fn sys_landlock_restrict_self(
+    ruleset_fd: i32,
+    flags: u32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!("ruleset_fd = {}, flags = {:#x}", ruleset_fd, flags);
+
+    do_restrict_self(ruleset_fd, flags, ctx)?;
+    Ok(SyscallReturn::Return(0))
+}
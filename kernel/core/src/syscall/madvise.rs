@@ -47,6 +47,21 @@ pub(super) fn sys_madvise(
             }
             // For `DUMMY_MADVISE`, doing nothing is correct, though it may not be efficient.
         }
+        MadviseBehavior::MADV_PAGEOUT => {
+            if !vmar.query(addr_range.clone()).is_fully_mapped() {
+                return_errno_with_message!(
+                    Errno::ENOMEM,
+                    "the range contains pages that are not mapped"
+                );
+            }
+            vmar.page_out(addr_range)?;
+        }
+        MadviseBehavior::MADV_DONTDUMP => {
+            vmar.set_dont_dump(true, addr_range)?;
+        }
+        MadviseBehavior::MADV_DODUMP => {
+            vmar.set_dont_dump(false, addr_range)?;
+        }
         _ => return_errno_with_message!(Errno::EINVAL, "the madvise behavior is not supported yet"),
     }
 
@@ -7,6 +7,7 @@
     expect(dead_code)
 )]
 
+pub(crate) use arch::{SYS_EXIT, SYS_READ, SYS_RT_SIGRETURN, SYS_WRITE};
 pub(crate) use clock_gettime::ClockId;
 use ostd::arch::cpu::context::UserContext;
 pub(crate) use timer_create::create_timer;
@@ -78,8 +79,10 @@ mod gettimeofday;
 mod getuid;
 mod getxattr;
 mod inotify;
+mod io_uring;
 mod ioctl;
 mod kill;
+mod landlock;
 mod link;
 mod listen;
 mod listmount;
@@ -93,7 +96,16 @@ mod mmap;
 mod mount;
 mod move_mount;
 mod mprotect;
+mod mq_getsetattr;
+mod mq_notify;
+mod mq_open;
+mod mq_timedreceive;
+mod mq_timedsend;
 mod mremap;
+mod msgctl;
+mod msgget;
+mod msgrcv;
+mod msgsnd;
 mod msync;
 mod munmap;
 mod nanosleep;
@@ -139,6 +151,7 @@ mod sched_setattr;
 mod sched_setparam;
 mod sched_setscheduler;
 mod sched_yield;
+mod seccomp;
 mod select;
 mod semctl;
 mod semget;
@@ -168,6 +181,10 @@ mod setsid;
 mod setsockopt;
 mod setuid;
 mod setxattr;
+mod shmat;
+mod shmctl;
+mod shmdt;
+mod shmget;
 mod shutdown;
 mod sigaltstack;
 mod signalfd;
@@ -176,6 +193,7 @@ mod socketpair;
 mod stat;
 mod statfs;
 mod statx;
+mod swapon;
 mod symlink;
 mod sync;
 mod sysinfo;
@@ -192,6 +210,7 @@ mod umount;
 mod uname;
 mod unlink;
 mod unshare;
+mod userfaultfd;
 mod utimens;
 mod wait4;
 mod waitid;
@@ -377,6 +396,10 @@ impl SyscallArgument {
 }
 
 pub(crate) fn handle_syscall(ctx: &Context, user_ctx: &mut UserContext) {
+    if !crate::security::seccomp::secure_computing(ctx, user_ctx) {
+        return;
+    }
+
     let syscall_frame = SyscallArgument::new_from_context(user_ctx);
     let syscall_return = arch::syscall_dispatch(
         syscall_frame.syscall_number,
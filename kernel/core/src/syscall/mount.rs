@@ -11,6 +11,7 @@ use crate::{
         },
     },
     prelude::*,
+    security::lsm::hooks as lsm_hooks,
     syscall::constants::MAX_FILENAME_LEN,
 };
 
@@ -46,6 +47,24 @@ pub(super) fn sys_mount(
             .get_top_path()
     };
 
+    // The file system type is only meaningful when creating a new mount.
+    let is_new_mount = !mount_flags.intersects(
+        MountFlags::MS_REMOUNT | MountFlags::MS_BIND | MountFlags::MS_MOVE | MS_PROPAGATION,
+    );
+    let fs_type = if is_new_mount {
+        Some(
+            ctx.user_space()
+                .read_cstring(fs_type_addr, MAX_FILENAME_LEN)?,
+        )
+    } else {
+        None
+    };
+    lsm_hooks::on_mount(lsm_hooks::MountContext::new(
+        ctx.posix_thread,
+        &dst_path,
+        fs_type.as_deref().and_then(|fs_type| fs_type.to_str().ok()),
+    ))?;
+
     if mount_flags.contains(MountFlags::MS_REMOUNT) && mount_flags.contains(MountFlags::MS_BIND) {
         // If `MS_BIND` is specified, only the mount flags are changed.
         do_remount_mnt(&dst_path, mount_flags, ctx)?;
@@ -63,10 +82,12 @@ pub(super) fn sys_mount(
     } else if mount_flags.contains(MountFlags::MS_MOVE) {
         do_move_mount_old(src_name_addr, dst_path, ctx)?;
     } else {
+        // The file system type has been read above because this is a new mount.
+        let fs_type = fs_type.as_deref().unwrap();
         do_new_mount(
             src_name_addr,
             mount_flags,
-            fs_type_addr,
+            fs_type,
             dst_path,
             data_addr,
             ctx,
@@ -186,7 +207,7 @@ fn do_move_mount_old(src_name_addr: Vaddr, dst_path: Path, ctx: &Context) -> Res
 fn do_new_mount(
     src_name_addr: Vaddr,
     flags: MountFlags,
-    fs_type_addr: Vaddr,
+    fs_type: &CStr,
     target_path: Path,
     data_addr: Vaddr,
     ctx: &Context,
@@ -196,14 +217,11 @@ fn do_new_mount(
     };
 
     let fs_type = {
-        let fs_type_cstr = ctx
-            .user_space()
-            .read_cstring(fs_type_addr, MAX_FILENAME_LEN)?;
-        if fs_type_cstr.is_empty() {
+        if fs_type.is_empty() {
             return_errno_with_message!(Errno::EINVAL, "empty file system type");
         }
 
-        let fs_type_str = fs_type_cstr
+        let fs_type_str = fs_type
             .to_str()
             .map_err(|_| Error::with_message(Errno::ENODEV, "invalid file system type"))?;
         crate::fs::vfs::registry::look_up(fs_type_str).ok_or_else(|| {
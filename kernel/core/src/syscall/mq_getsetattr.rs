@@ -0,0 +1,57 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::mm::VmIo;
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file::{StatusFlags, file_table::get_file_fast},
+        mqueuefs::{MqAttr, MqueueFile},
+    },
+    prelude::*,
+};
+
+pub(super) fn sys_mq_getsetattr(
+    mqdes: i32,
+    new_attr_addr: Vaddr,
+    old_attr_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, new_attr_addr = {:#x}, old_attr_addr = {:#x}",
+        mqdes, new_attr_addr, old_attr_addr
+    );
+
+    let new_attr = if new_attr_addr != 0 {
+        let new_attr = ctx.user_space().read_val::<MqAttr>(new_attr_addr)?;
+        // Only `O_NONBLOCK` can be changed. Other attributes are ignored.
+        if new_attr.mq_flags & !(StatusFlags::O_NONBLOCK.bits() as i64) != 0 {
+            return_errno_with_message!(Errno::EINVAL, "invalid message queue flags");
+        }
+        Some(new_attr)
+    } else {
+        None
+    };
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, mqdes.try_into()?);
+    let queue = MqueueFile::from_file(file.as_ref())?.queue();
+
+    let old_attr = {
+        let mut attr = queue.attr();
+        if file.status_flags().contains(StatusFlags::O_NONBLOCK) {
+            attr.mq_flags = StatusFlags::O_NONBLOCK.bits() as i64;
+        }
+        attr
+    };
+
+    if let Some(new_attr) = new_attr {
+        file.update_status_nonblock(new_attr.mq_flags != 0);
+    }
+
+    if old_attr_addr != 0 {
+        ctx.user_space().write_val(old_attr_addr, &old_attr)?;
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::mm::VmIo;
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file::file_table::get_file_fast,
+        mqueuefs::{MqNotify, MqueueFile},
+    },
+    prelude::*,
+    process::signal::{
+        c_types::{SigNotify, sigevent_t},
+        sig_num::SigNum,
+    },
+};
+
+pub(super) fn sys_mq_notify(
+    mqdes: i32,
+    sigevent_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!("mqdes = {}, sigevent_addr = {:#x}", mqdes, sigevent_addr);
+
+    let notify = if sigevent_addr == 0 {
+        None
+    } else {
+        let sig_event = ctx.user_space().read_val::<sigevent_t>(sigevent_addr)?;
+        match SigNotify::try_from(sig_event.sigev_notify)? {
+            SigNotify::SIGEV_NONE => Some(MqNotify::None),
+            SigNotify::SIGEV_SIGNAL => {
+                let signo = u8::try_from(sig_event.sigev_signo)
+                    .map_err(|_| Error::with_message(Errno::EINVAL, "invalid signal number"))?;
+                Some(MqNotify::Signal {
+                    signo: SigNum::try_from(signo)?,
+                    value: sig_event.sigev_value,
+                })
+            }
+            // TODO: Support `SIGEV_THREAD`. The C library implements it with a netlink socket
+            // whose descriptor is passed in `sigev_signo`, which is not supported yet.
+            SigNotify::SIGEV_THREAD => {
+                return_errno_with_message!(Errno::EINVAL, "`SIGEV_THREAD` is not supported");
+            }
+            SigNotify::SIGEV_THREAD_ID => {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "`SIGEV_THREAD_ID` is not valid for message queues"
+                );
+            }
+        }
+    };
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, mqdes.try_into()?);
+    let queue = MqueueFile::from_file(file.as_ref())?.queue();
+
+    queue.set_notification(notify, &ctx.process)?;
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,133 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::mm::VmIo;
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file::{
+            AccessMode, CreationFlags, InodeHandle, InodeMode, Permission, StatusFlags,
+            file_table::FdFlags,
+        },
+        mqueuefs::{MqAttr, MqueueFs},
+        utils::NAME_MAX,
+        vfs::path::Path,
+    },
+    prelude::*,
+    syscall::constants::MAX_FILENAME_LEN,
+};
+
+pub(super) fn sys_mq_open(
+    name_addr: Vaddr,
+    oflag: u32,
+    mode: u16,
+    attr_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let name = read_queue_name(name_addr, ctx)?;
+    let access_mode = AccessMode::from_u32(oflag)?;
+    let creation_flags = CreationFlags::from_bits_truncate(oflag);
+    let status_flags = StatusFlags::from_bits_truncate(oflag) & StatusFlags::O_NONBLOCK;
+
+    debug!(
+        "mq_open: name = {:?}, access_mode = {:?}, creation_flags = {:?}, status_flags = {:?}, mode = {:#o}, attr_addr = {:#x}",
+        name, access_mode, creation_flags, status_flags, mode, attr_addr
+    );
+
+    let attr = if creation_flags.contains(CreationFlags::O_CREAT) && attr_addr != 0 {
+        Some(ctx.user_space().read_val::<MqAttr>(attr_addr)?)
+    } else {
+        None
+    };
+
+    let root = {
+        let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+        let ipc_ns = ns_proxy.unwrap().ipc_ns();
+        Path::new_fs_root(ipc_ns.mqueue_mount().clone())
+    };
+
+    let inode_handle = match root.lookup_child(&name) {
+        Ok(_) if creation_flags.contains(CreationFlags::O_CREAT | CreationFlags::O_EXCL) => {
+            return_errno_with_message!(Errno::EEXIST, "the queue already exists");
+        }
+        Ok(path) => InodeHandle::new(path, access_mode, status_flags)?,
+        Err(err)
+            if err.error() == Errno::ENOENT && creation_flags.contains(CreationFlags::O_CREAT) =>
+        {
+            let mode = {
+                let fs_ref = ctx.thread_local.borrow_fs();
+                InodeMode::from_bits_truncate(mode & !fs_ref.umask().get())
+            };
+            create_queue(&root, &name, mode, attr.as_ref(), access_mode, status_flags)?
+        }
+        Err(err) => return Err(err),
+    };
+
+    let file_table = ctx.thread_local.borrow_file_table();
+    let mut file_table_locked = file_table.unwrap().write();
+    // Message queue descriptors are always closed on `execve`.
+    let fd = file_table_locked.insert(Arc::new(inode_handle), FdFlags::CLOEXEC);
+
+    Ok(SyscallReturn::Return(fd.into()))
+}
+
+pub(super) fn sys_mq_unlink(name_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let name = read_queue_name(name_addr, ctx)?;
+
+    debug!("mq_unlink: name = {:?}", name);
+
+    let root = {
+        let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+        let ipc_ns = ns_proxy.unwrap().ipc_ns();
+        Path::new_fs_root(ipc_ns.mqueue_mount().clone())
+    };
+    root.unlink(&name)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Reads the name of a message queue from the user space.
+///
+/// The C library strips the leading slash, so the name must be a single path component.
+fn read_queue_name(name_addr: Vaddr, ctx: &Context) -> Result<String> {
+    let name = ctx
+        .user_space()
+        .read_cstring(name_addr, MAX_FILENAME_LEN)?
+        .into_string()
+        .map_err(|_| Error::with_message(Errno::EINVAL, "the queue name is not valid UTF-8"))?;
+
+    if name.is_empty() || name == "." || name == ".." || name.contains('/') {
+        return_errno_with_message!(Errno::EACCES, "the queue name is not a valid file name");
+    }
+    if name.len() > NAME_MAX {
+        return_errno_with_message!(Errno::ENAMETOOLONG, "the queue name is too long");
+    }
+
+    Ok(name)
+}
+
+fn create_queue(
+    root: &Path,
+    name: &str,
+    mode: InodeMode,
+    attr: Option<&MqAttr>,
+    access_mode: AccessMode,
+    status_flags: StatusFlags,
+) -> Result<InodeHandle> {
+    root.inode()
+        .check_permission(Permission::MAY_WRITE | Permission::MAY_EXEC)?;
+
+    let fs = root.fs();
+    let mqueue_fs = fs.downcast_ref::<MqueueFs>().unwrap();
+    match mqueue_fs.create_queue(name, mode, attr) {
+        // The creator can always open the new queue regardless of its mode.
+        Ok(()) => {
+            InodeHandle::new_unchecked_access(root.lookup_child(name)?, access_mode, status_flags)
+        }
+        // Another process has created the queue in the meantime.
+        Err(err) if err.error() == Errno::EEXIST => {
+            InodeHandle::new(root.lookup_child(name)?, access_mode, status_flags)
+        }
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,78 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use ostd::mm::VmIo;
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file::{StatusFlags, file_table::get_file_fast},
+        mqueuefs::{MQ_PRIO_MAX, MqueueFile},
+    },
+    prelude::*,
+    time::{clocks::RealTimeClock, timer::Timeout, timespec_t, wait::ManagedTimeout},
+};
+
+pub(super) fn sys_mq_timedsend(
+    mqdes: i32,
+    msg_ptr: Vaddr,
+    msg_len: usize,
+    msg_prio: u32,
+    abs_timeout_addr: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!(
+        "mqdes = {}, msg_ptr = {:#x}, msg_len = {}, msg_prio = {}, abs_timeout_addr = {:#x}",
+        mqdes, msg_ptr, msg_len, msg_prio, abs_timeout_addr
+    );
+
+    if msg_prio >= MQ_PRIO_MAX {
+        return_errno_with_message!(Errno::EINVAL, "the message priority is too large");
+    }
+    let timeout = read_abs_timeout(abs_timeout_addr, ctx)?;
+
+    let mut file_table = ctx.thread_local.borrow_file_table_mut();
+    let file = get_file_fast!(&mut file_table, mqdes.try_into()?);
+    let queue = MqueueFile::from_file(file.as_ref())?.queue();
+
+    if !file.access_mode().is_writable() {
+        return_errno_with_message!(Errno::EBADF, "the queue is not opened for writing");
+    }
+    if msg_len > queue.max_msg_size() {
+        return_errno_with_message!(Errno::EMSGSIZE, "the message is too long");
+    }
+
+    let mut message = vec![0u8; msg_len].into_boxed_slice();
+    ctx.user_space().read_bytes(msg_ptr, &mut message)?;
+
+    let is_nonblocking = file.status_flags().contains(StatusFlags::O_NONBLOCK);
+    queue
+        .send(message, msg_prio, is_nonblocking, timeout, ctx)
+        .map_err(|err| match err.error() {
+            Errno::EINTR => Error::new(Errno::ERESTARTSYS),
+            _ => err,
+        })?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+/// Reads the absolute timeout of `mq_timedsend` and `mq_timedreceive`.
+///
+/// The timeout is measured against `CLOCK_REALTIME`. A null pointer means waiting forever.
+pub(super) fn read_abs_timeout(
+    abs_timeout_addr: Vaddr,
+    ctx: &Context,
+) -> Result<Option<ManagedTimeout<'static>>> {
+    if abs_timeout_addr == 0 {
+        return Ok(None);
+    }
+
+    let time_spec: timespec_t = ctx.user_space().read_val(abs_timeout_addr)?;
+    let timeout = Duration::try_from(time_spec)?;
+
+    Ok(Some(ManagedTimeout::new_with_manager(
+        Timeout::When(timeout),
+        RealTimeClock::timer_manager(),
+    )))
+}
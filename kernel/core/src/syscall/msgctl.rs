@@ -0,0 +1,76 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::mm::VmIo;
+
+use super::SyscallReturn;
+use crate::{
+    ipc::{
+        IpcId, PermissionMode,
+        msg::{MsgControlCmd, MsgInfo, MsqidDs},
+    },
+    prelude::*,
+};
+
+pub(super) fn sys_msgctl(msqid: i32, cmd: i32, buf: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let cmd = MsgControlCmd::try_from(cmd)?;
+
+    debug!(
+        "msgctl: msqid = {}, cmd = {:?}, buf = {:#x}",
+        msqid, cmd, buf
+    );
+
+    let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+    let ipc_ns = ns_proxy.unwrap().ipc_ns();
+
+    let parse_msqid = || {
+        IpcId::try_from(msqid.cast_unsigned()).map_err(|_| {
+            Error::with_message(Errno::EINVAL, "non-positive message queue IDs are invalid")
+        })
+    };
+    let max_msqid = || ipc_ns.max_msg_id().map_or(0, |id| id.get() as isize);
+
+    match cmd {
+        MsgControlCmd::IPC_INFO => {
+            let msg_info = MsgInfo::new_limits(ipc_ns.msg_limits());
+            ctx.user_space().write_val(buf, &msg_info)?;
+            return Ok(SyscallReturn::Return(max_msqid()));
+        }
+        MsgControlCmd::MSG_INFO => {
+            let (nr_queues, nr_messages, nr_bytes) = ipc_ns.msg_usage();
+            let msg_info =
+                MsgInfo::new_usage(ipc_ns.msg_limits(), nr_queues, nr_messages, nr_bytes);
+            ctx.user_space().write_val(buf, &msg_info)?;
+            return Ok(SyscallReturn::Return(max_msqid()));
+        }
+        MsgControlCmd::IPC_STAT | MsgControlCmd::MSG_STAT | MsgControlCmd::MSG_STAT_ANY => {
+            // For `MSG_STAT` and `MSG_STAT_ANY`, `msqid` is an index of the kernel's internal
+            // array, which is the same as the ID here.
+            let msqid = parse_msqid()?;
+            let required_perm = if matches!(cmd, MsgControlCmd::MSG_STAT_ANY) {
+                PermissionMode::empty()
+            } else {
+                PermissionMode::READ
+            };
+
+            let msg_queue = ipc_ns.get_msg_queue(msqid, required_perm, ctx.posix_thread)?;
+            ctx.user_space().write_val(buf, &msg_queue.msqid_ds())?;
+
+            if !matches!(cmd, MsgControlCmd::IPC_STAT) {
+                return Ok(SyscallReturn::Return(msqid.get() as isize));
+            }
+        }
+        MsgControlCmd::IPC_RMID => {
+            let msqid = parse_msqid()?;
+            ipc_ns.remove_msg_queue(msqid, |msg_queue| {
+                ipc_ns.check_owner(msg_queue.permission(), ctx.posix_thread)
+            })?;
+        }
+        MsgControlCmd::IPC_SET => {
+            let msqid = parse_msqid()?;
+            let msqid_ds = ctx.user_space().read_val::<MsqidDs>(buf)?;
+            ipc_ns.set_msg_queue(msqid, &msqid_ds, ctx.posix_thread)?;
+        }
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,60 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::mm::VmIo;
+
+use super::SyscallReturn;
+use crate::{
+    ipc::{
+        IpcFlags, IpcId, PermissionMode,
+        msg::{MsgFlags, MsgSelector},
+    },
+    prelude::*,
+};
+
+pub(super) fn sys_msgrcv(
+    msqid: i32,
+    msgp: Vaddr,
+    msgsz: isize,
+    msgtyp: i64,
+    msgflg: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let ipc_flags = IpcFlags::from_bits_truncate(msgflg.cast_unsigned());
+    let msg_flags = MsgFlags::from_bits_truncate(msgflg.cast_unsigned());
+
+    debug!(
+        "msgrcv: msqid = {}, msgp = {:#x}, msgsz = {}, msgtyp = {}, flags = {:?} {:?}",
+        msqid, msgp, msgsz, msgtyp, ipc_flags, msg_flags
+    );
+
+    let Ok(msqid) = IpcId::try_from(msqid.cast_unsigned()) else {
+        return_errno_with_message!(Errno::EINVAL, "non-positive message queue IDs are invalid");
+    };
+    let Ok(max_len) = usize::try_from(msgsz) else {
+        return_errno_with_message!(Errno::EINVAL, "the message size is negative");
+    };
+    if msg_flags.contains(MsgFlags::MSG_COPY) {
+        // Linux supports `MSG_COPY` only if `CONFIG_CHECKPOINT_RESTORE` is enabled.
+        return_errno_with_message!(Errno::ENOSYS, "MSG_COPY is not supported");
+    }
+
+    let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+    let ipc_ns = ns_proxy.unwrap().ipc_ns();
+
+    let msg_queue = ipc_ns.get_msg_queue(msqid, PermissionMode::READ, ctx.posix_thread)?;
+    let message = msg_queue.receive(
+        MsgSelector::new(msgtyp, msg_flags),
+        max_len,
+        msg_flags.contains(MsgFlags::MSG_NOERROR),
+        ctx.process.pid(),
+        ipc_flags.contains(IpcFlags::IPC_NOWAIT),
+    )?;
+
+    // Note that the message is lost if the copy fails. Linux behaves the same way.
+    let text = &message.text()[..message.text().len().min(max_len)];
+    let user_space = ctx.user_space();
+    user_space.write_val(msgp, &message.mtype())?;
+    user_space.write_bytes(msgp + size_of::<i64>(), text)?;
+
+    Ok(SyscallReturn::Return(text.len() as isize))
+}
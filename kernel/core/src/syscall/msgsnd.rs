@@ -0,0 +1,52 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::mm::VmIo;
+
+use super::SyscallReturn;
+use crate::{
+    ipc::{IpcFlags, IpcId, PermissionMode, msg::Message},
+    prelude::*,
+};
+
+pub(super) fn sys_msgsnd(
+    msqid: i32,
+    msgp: Vaddr,
+    msgsz: usize,
+    msgflg: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let flags = IpcFlags::from_bits_truncate(msgflg.cast_unsigned());
+
+    debug!(
+        "msgsnd: msqid = {}, msgp = {:#x}, msgsz = {}, flags = {:?}",
+        msqid, msgp, msgsz, flags
+    );
+
+    let Ok(msqid) = IpcId::try_from(msqid.cast_unsigned()) else {
+        return_errno_with_message!(Errno::EINVAL, "non-positive message queue IDs are invalid");
+    };
+
+    let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+    let ipc_ns = ns_proxy.unwrap().ipc_ns();
+
+    if msgsz > ipc_ns.msg_limits().msgmax() {
+        return_errno_with_message!(Errno::EINVAL, "the message is too long");
+    }
+
+    let user_space = ctx.user_space();
+    let mtype = user_space.read_val::<i64>(msgp)?;
+    if mtype < 1 {
+        return_errno_with_message!(Errno::EINVAL, "the message type is not positive");
+    }
+    let mut text = vec![0u8; msgsz].into_boxed_slice();
+    user_space.read_bytes(msgp + size_of::<i64>(), &mut text)?;
+
+    let msg_queue = ipc_ns.get_msg_queue(msqid, PermissionMode::WRITE, ctx.posix_thread)?;
+    msg_queue.send(
+        Message::new(mtype, text),
+        ctx.process.pid(),
+        flags.contains(IpcFlags::IPC_NOWAIT),
+    )?;
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -19,8 +19,11 @@ pub(super) fn sys_pidfd_open(pid: Pid, flags: u32, ctx: &Context) -> Result<Sysc
         return_errno_with_message!(Errno::EINVAL, "non-positive PIDs are not valid");
     }
 
-    let process = pid_table::pid_table_mut()
-        .get_process(pid)
+    let process = ctx
+        .process
+        .pid_ns()
+        .global_id_of(pid)
+        .and_then(|pid| pid_table::pid_table_mut().get_process(pid))
         .ok_or_else(|| Error::with_message(Errno::ESRCH, "the process does not exist"))?;
 
     let pid_fd = {
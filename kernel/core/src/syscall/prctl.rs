@@ -6,10 +6,12 @@ use super::SyscallReturn;
 use crate::{
     prelude::*,
     process::{
+        coredump::Dumpable,
         credentials::{SecureBits, capabilities::CapSet},
         posix_thread::{ContextPthreadAdminApi, ThreadName},
         signal::sig_num::SigNum,
     },
+    security::seccomp::{self, SeccompMode},
 };
 
 pub(super) fn sys_prctl(
@@ -35,14 +37,14 @@ pub(super) fn sys_prctl(
             ctx.user_space().write_val(write_to_addr, &write_val)?;
         }
         PrctlCmd::PR_GET_DUMPABLE => {
-            // TODO: When coredump is supported, return the actual value.
-            return Ok(SyscallReturn::Return(Dumpable::Disable as _));
+            let dumpable = ctx.user_space().vmar().process_vm().dumpable();
+            return Ok(SyscallReturn::Return(dumpable as _));
         }
         PrctlCmd::PR_SET_DUMPABLE(dumpable) => {
             if dumpable != Dumpable::Disable && dumpable != Dumpable::User {
                 return_errno_with_message!(Errno::EINVAL, "invalid dumpable attribute");
             }
-            // TODO: Implement coredump.
+            ctx.user_space().vmar().process_vm().set_dumpable(dumpable);
         }
         PrctlCmd::PR_GET_KEEPCAPS => {
             let keep_cap = {
@@ -72,6 +74,13 @@ pub(super) fn sys_prctl(
             ctx.user_space()
                 .write_bytes(write_to_addr, thread_name.as_bytes_with_nul())?;
         }
+        PrctlCmd::PR_GET_SECCOMP => {
+            let mode = ctx.posix_thread.seccomp().mode();
+            return Ok(SyscallReturn::Return(mode as _));
+        }
+        PrctlCmd::PR_SET_SECCOMP(mode, filter_addr) => {
+            seccomp::prctl_set_seccomp(mode, filter_addr, ctx)?;
+        }
         PrctlCmd::PR_CAPBSET_READ(capability) => {
             let credentials = ctx.posix_thread.credentials();
             let is_in_bounding_set = credentials.bounding_capset().contains(capability);
@@ -159,6 +168,8 @@ const PR_GET_KEEPCAPS: i32 = 7;
 const PR_SET_KEEPCAPS: i32 = 8;
 const PR_SET_NAME: i32 = 15;
 const PR_GET_NAME: i32 = 16;
+const PR_GET_SECCOMP: i32 = 21;
+const PR_SET_SECCOMP: i32 = 22;
 const PR_CAPBSET_READ: i32 = 23;
 const PR_CAPBSET_DROP: i32 = 24;
 const PR_GET_SECUREBITS: i32 = 27;
@@ -182,6 +193,8 @@ enum PrctlCmd {
     PR_SET_KEEPCAPS(u32),
     PR_SET_NAME(Vaddr),
     PR_GET_NAME(Vaddr),
+    PR_GET_SECCOMP,
+    PR_SET_SECCOMP(SeccompMode, Vaddr),
     PR_CAPBSET_READ(CapSet),
     PR_CAPBSET_DROP(CapSet),
     PR_GET_SECUREBITS,
@@ -195,14 +208,6 @@ enum PrctlCmd {
     PR_CAP_AMBIENT(CapAmbientCmd),
 }
 
-#[repr(u64)]
-#[derive(Clone, Copy, Debug, Eq, PartialEq, TryFromInt)]
-enum Dumpable {
-    Disable = 0, /* No setuid dumping */
-    User = 1,    /* Dump as user of process */
-    Root = 2,    /* Dump as root */
-}
-
 #[derive(Clone, Copy, Debug)]
 enum CapAmbientCmd {
     IsSet(CapSet),
@@ -220,11 +225,29 @@ impl PrctlCmd {
             }
             PR_GET_PDEATHSIG => Ok(PrctlCmd::PR_GET_PDEATHSIG(arg2 as _)),
             PR_GET_DUMPABLE => Ok(PrctlCmd::PR_GET_DUMPABLE),
-            PR_SET_DUMPABLE => Ok(PrctlCmd::PR_SET_DUMPABLE(Dumpable::try_from(arg2)?)),
+            PR_SET_DUMPABLE => {
+                let dumpable = u8::try_from(arg2)
+                    .ok()
+                    .and_then(|dumpable| Dumpable::try_from(dumpable).ok())
+                    .ok_or_else(|| {
+                        Error::with_message(Errno::EINVAL, "invalid dumpable attribute")
+                    })?;
+                Ok(PrctlCmd::PR_SET_DUMPABLE(dumpable))
+            }
             PR_GET_KEEPCAPS => Ok(PrctlCmd::PR_GET_KEEPCAPS),
             PR_SET_KEEPCAPS => Ok(PrctlCmd::PR_SET_KEEPCAPS(arg2 as _)),
             PR_SET_NAME => Ok(PrctlCmd::PR_SET_NAME(arg2 as _)),
             PR_GET_NAME => Ok(PrctlCmd::PR_GET_NAME(arg2 as _)),
+            PR_GET_SECCOMP => Ok(PrctlCmd::PR_GET_SECCOMP),
+            PR_SET_SECCOMP => {
+                let mode = u32::try_from(arg2)
+                    .ok()
+                    .and_then(|mode| SeccompMode::try_from(mode).ok())
+                    .ok_or_else(|| {
+                        Error::with_message(Errno::EINVAL, "the seccomp mode is invalid")
+                    })?;
+                Ok(PrctlCmd::PR_SET_SECCOMP(mode, arg3 as _))
+            }
             PR_CAPBSET_READ => Ok(PrctlCmd::PR_CAPBSET_READ(parse_capability(arg2)?)),
             PR_CAPBSET_DROP => Ok(PrctlCmd::PR_CAPBSET_DROP(parse_capability(arg2)?)),
             PR_GET_SECUREBITS => Ok(PrctlCmd::PR_GET_SECUREBITS),
@@ -53,9 +53,14 @@ pub(super) fn sys_prlimit64(
     let old_raw = if pid == 0 || pid == ctx.process.pid() {
         do_prlimit64(&ctx.process, resource, new_raw, ctx)?
     } else {
-        let target_process = pid_table::pid_table_mut().get_process(pid).ok_or_else(|| {
-            Error::with_message(Errno::ESRCH, "the target process does not exist")
-        })?;
+        let target_process = ctx
+            .process
+            .pid_ns()
+            .global_id_of(pid)
+            .and_then(|pid| pid_table::pid_table_mut().get_process(pid))
+            .ok_or_else(|| {
+                Error::with_message(Errno::ESRCH, "the target process does not exist")
+            })?;
         // Check permissions
         check_rlimit_perm(&target_process, ctx)?;
         do_prlimit64(&target_process, resource, new_raw, ctx)?
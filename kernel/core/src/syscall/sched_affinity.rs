@@ -9,7 +9,11 @@ use ostd::{
 };
 
 use super::SyscallReturn;
-use crate::{prelude::*, process::pid_table, thread::Tid};
+use crate::{
+    prelude::*,
+    process::pid_table,
+    thread::{Thread, Tid},
+};
 
 pub(super) fn sys_sched_getaffinity(
     tid: Tid,
@@ -19,7 +23,7 @@ pub(super) fn sys_sched_getaffinity(
 ) -> Result<SyscallReturn> {
     let cpu_set = match tid {
         0 => ctx.thread.atomic_cpu_affinity().load(Ordering::Relaxed),
-        _ => match pid_table::pid_table_mut().get_thread(tid) {
+        _ => match get_thread(tid, ctx) {
             Some(thread) => thread.atomic_cpu_affinity().load(Ordering::Relaxed),
             None => return_errno_with_message!(Errno::ESRCH, "the target thread does not exist"),
         },
@@ -47,7 +51,7 @@ pub(super) fn sys_sched_setaffinity(
             .thread
             .atomic_cpu_affinity()
             .store(&user_cpu_set, Ordering::Relaxed),
-        _ => match pid_table::pid_table_mut().get_thread(tid) {
+        _ => match get_thread(tid, ctx) {
             Some(thread) => {
                 thread
                     .atomic_cpu_affinity()
@@ -60,6 +64,12 @@ pub(super) fn sys_sched_setaffinity(
     Ok(SyscallReturn::Return(0))
 }
 
+/// Gets the thread by its TID in the PID namespace of the current process.
+fn get_thread(tid: Tid, ctx: &Context) -> Option<Arc<Thread>> {
+    let tid = ctx.process.pid_ns().global_id_of(tid)?;
+    pid_table::pid_table_mut().get_thread(tid)
+}
+
 // Linux uses `DECLARE_BITMAP` for `cpu_set_t`, inside which each part is a
 // `long`. We use the same scheme to ensure byte endianness compatibility.
 type Part = u64;
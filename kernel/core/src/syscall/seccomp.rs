@@ -0,0 +1,16 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{prelude::*, security::seccomp::do_seccomp};
+
+pub(super) fn sys_seccomp(
+    op: u32,
+    flags: u32,
+    args: Vaddr,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    debug!("op = {}, flags = {:#x}, args = {:#x}", op, flags, args);
+
+    let ret = do_seccomp(op, flags, args, ctx)?;
+    Ok(SyscallReturn::Return(ret))
+}
@@ -2,8 +2,14 @@
 
 use core::sync::atomic::Ordering;
 
+use aster_block::io_priority::{IoPriority, IoPriorityClass};
+
 use super::{SyscallReturn, get_ioprio::IoPrioWho};
-use crate::{prelude::*, process::posix_thread::AsPosixThread};
+use crate::{
+    prelude::*,
+    process::{UserNamespace, credentials::capabilities::CapSet, posix_thread::AsPosixThread},
+    security::lsm::hooks as lsm_hooks,
+};
 
 pub(super) fn sys_ioprio_set(
     which: u32,
@@ -14,6 +20,7 @@ pub(super) fn sys_ioprio_set(
     debug!("which = {}, who = {}, ioprio = {}", which, who, ioprio);
 
     let ioprio_who = IoPrioWho::from_which_and_who(which, who, ctx)?;
+    check_io_priority(ioprio, ctx)?;
 
     match ioprio_who {
         IoPrioWho::Thread(thread) => {
@@ -34,3 +41,32 @@ pub(super) fn sys_ioprio_set(
         }
     }
 }
+
+/// Checks whether the I/O priority is valid and can be set by the current thread.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/block/ioprio.c> (`ioprio_check_cap`)
+fn check_io_priority(ioprio: u32, ctx: &Context) -> Result<()> {
+    let Some(io_priority) = IoPriority::from_raw(ioprio) else {
+        return_errno_with_message!(Errno::EINVAL, "the I/O priority is invalid");
+    };
+
+    if io_priority.class() == IoPriorityClass::RealTime {
+        let user_ns = UserNamespace::get_init_singleton();
+        let is_capable = |cap| {
+            lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+                user_ns.as_ref(),
+                ctx.posix_thread,
+                cap,
+            ))
+            .is_ok()
+        };
+        if !is_capable(CapSet::SYS_NICE) && !is_capable(CapSet::SYS_ADMIN) {
+            return_errno_with_message!(
+                Errno::EPERM,
+                "setting a real-time I/O priority requires `CAP_SYS_NICE` or `CAP_SYS_ADMIN`"
+            );
+        }
+    }
+
+    Ok(())
+}
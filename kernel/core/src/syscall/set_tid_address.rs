@@ -15,6 +15,10 @@ pub(super) fn sys_set_tid_address(tidptr: Vaddr, ctx: &Context) -> Result<Syscal
 
     ctx.thread_local.set_child_tid().set(clear_child_tid);
 
-    let tid = ctx.posix_thread.tid();
+    let tid = ctx
+        .process
+        .pid_ns()
+        .ns_id_of(ctx.posix_thread.tid())
+        .unwrap();
     Ok(SyscallReturn::Return(tid as _))
 }
@@ -3,7 +3,7 @@
 use super::SyscallReturn;
 use crate::{
     prelude::*,
-    process::{Gid, posix_thread::ContextPthreadAdminApi},
+    process::{Gid, coredump::set_ids_and_update_dumpable, posix_thread::ContextPthreadAdminApi},
 };
 
 pub(super) fn sys_setfsgid(gid: i32, ctx: &Context) -> Result<SyscallReturn> {
@@ -17,8 +17,7 @@ pub(super) fn sys_setfsgid(gid: i32, ctx: &Context) -> Result<SyscallReturn> {
 
     let old_fsgid = {
         let credentials = ctx.credentials_mut();
-        credentials
-            .set_fsgid(fsgid)
+        set_ids_and_update_dumpable(ctx, || credentials.set_fsgid(fsgid))
             .unwrap_or_else(|old_fsgid| old_fsgid)
     };
 
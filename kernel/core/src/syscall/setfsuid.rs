@@ -3,7 +3,7 @@
 use super::SyscallReturn;
 use crate::{
     prelude::*,
-    process::{Uid, posix_thread::ContextPthreadAdminApi},
+    process::{Uid, coredump::set_ids_and_update_dumpable, posix_thread::ContextPthreadAdminApi},
 };
 
 pub(super) fn sys_setfsuid(uid: i32, ctx: &Context) -> Result<SyscallReturn> {
@@ -17,8 +17,7 @@ pub(super) fn sys_setfsuid(uid: i32, ctx: &Context) -> Result<SyscallReturn> {
 
     let old_fsuid = {
         let credentials = ctx.credentials_mut();
-        credentials
-            .set_fsuid(fsuid)
+        set_ids_and_update_dumpable(ctx, || credentials.set_fsuid(fsuid))
             .unwrap_or_else(|old_fsuid| old_fsuid)
     };
 
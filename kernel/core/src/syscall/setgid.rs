@@ -3,7 +3,7 @@
 use super::SyscallReturn;
 use crate::{
     prelude::*,
-    process::{Gid, posix_thread::ContextPthreadAdminApi},
+    process::{Gid, coredump::set_ids_and_update_dumpable, posix_thread::ContextPthreadAdminApi},
 };
 
 pub(super) fn sys_setgid(gid: i32, ctx: &Context) -> Result<SyscallReturn> {
@@ -15,7 +15,7 @@ pub(super) fn sys_setgid(gid: i32, ctx: &Context) -> Result<SyscallReturn> {
     debug!("gid = {:?}", gid);
 
     let credentials = ctx.credentials_mut();
-    credentials.set_gid(gid)?;
+    set_ids_and_update_dumpable(ctx, || credentials.set_gid(gid))?;
 
     Ok(SyscallReturn::Return(0))
 }
@@ -18,14 +18,15 @@ use crate::{
         vfs::path::MountNamespace,
     },
     ipc::IpcNamespace,
-    net::uts_ns::UtsNamespace,
+    net::{net_ns::NetNamespace, uts_ns::UtsNamespace},
     prelude::*,
     process::{
-        CloneFlags, ContextSetNsAdminApi, NsProxy, NsProxyBuilder, PidFile,
+        CloneFlags, ContextSetNsAdminApi, NsProxy, NsProxyBuilder, PidFile, PidNamespace,
         check_unsupported_ns_flags, credentials::capabilities::CapSet, posix_thread::AsPosixThread,
     },
     security::lsm::hooks as lsm_hooks,
     syscall::SyscallReturn,
+    time::time_ns::TimeNamespace,
 };
 
 pub(super) fn sys_setns(fd: RawFileDesc, flags: u32, ctx: &Context) -> Result<SyscallReturn> {
@@ -71,10 +72,10 @@ fn build_proxy_from_pid_file(
 
     check_unsupported_ns_flags(flags)?;
 
-    let target_thread = pid_file
+    let target_process = pid_file
         .process_opt()
-        .ok_or_else(|| Error::with_message(Errno::ESRCH, "the target process has been reaped"))?
-        .main_thread();
+        .ok_or_else(|| Error::with_message(Errno::ESRCH, "the target process has been reaped"))?;
+    let target_thread = target_process.main_thread();
     let target_proxy = target_thread.as_posix_thread().unwrap().ns_proxy().lock();
     let Some(target_proxy) = target_proxy.as_ref() else {
         return_errno_with_message!(Errno::ESRCH, "the target process has exited");
@@ -100,6 +101,21 @@ fn build_proxy_from_pid_file(
         set_mnt_ns(&mut builder, target_ns, ctx)?;
     }
 
+    if flags.contains(CloneFlags::CLONE_NEWNET) {
+        let target_ns = target_proxy.net_ns();
+        set_net_ns(&mut builder, target_ns, ctx)?;
+    }
+
+    if flags.contains(CloneFlags::CLONE_NEWPID) {
+        let target_ns = target_process.pid_ns();
+        set_pid_ns(&mut builder, target_ns, ctx)?;
+    }
+
+    if flags.contains(CloneFlags::CLONE_NEWTIME) {
+        let target_ns = target_proxy.time_ns();
+        set_time_ns(&mut builder, target_ns, ctx)?;
+    }
+
     if flags.contains(CloneFlags::CLONE_NEWUTS) {
         let target_ns = target_proxy.uts_ns();
         set_uts_ns(&mut builder, target_ns, ctx)?;
@@ -141,6 +157,15 @@ fn build_proxy_from_ns_file(
         || try_apply_ns_from_inode::<MountNamespace>(inode_handle, flags, |ns| {
             set_mnt_ns(&mut builder, &ns, ctx)
         })?
+        || try_apply_ns_from_inode::<NetNamespace>(inode_handle, flags, |ns| {
+            set_net_ns(&mut builder, &ns, ctx)
+        })?
+        || try_apply_ns_from_inode::<PidNamespace>(inode_handle, flags, |ns| {
+            set_pid_ns(&mut builder, &ns, ctx)
+        })?
+        || try_apply_ns_from_inode::<TimeNamespace>(inode_handle, flags, |ns| {
+            set_time_ns(&mut builder, &ns, ctx)
+        })?
         || try_apply_ns_from_inode::<UtsNamespace>(inode_handle, flags, |ns| {
             set_uts_ns(&mut builder, &ns, ctx)
         })?;
@@ -218,6 +243,68 @@ fn set_mnt_ns(
     Ok(())
 }
 
+fn set_net_ns(
+    builder: &mut NsProxyBuilder,
+    target_ns: &Arc<NetNamespace>,
+    ctx: &Context,
+) -> Result<()> {
+    check_set_ns_perms(target_ns, ctx)?;
+
+    builder.net_ns(target_ns.clone());
+
+    Ok(())
+}
+
+fn set_pid_ns(
+    builder: &mut NsProxyBuilder,
+    target_ns: &Arc<PidNamespace>,
+    ctx: &Context,
+) -> Result<()> {
+    check_set_ns_perms(target_ns, ctx)?;
+
+    // Only the PID namespace for the child processes is changed. It can only be set to the PID
+    // namespace of the current process or one of its descendants.
+    if !ctx.process.pid_ns().is_same_or_ancestor_of(target_ns) {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "the target PID namespace is not a descendant of the current PID namespace"
+        );
+    }
+
+    builder.pid_ns_for_children(target_ns.clone());
+
+    Ok(())
+}
+
+fn set_time_ns(
+    builder: &mut NsProxyBuilder,
+    target_ns: &Arc<TimeNamespace>,
+    ctx: &Context,
+) -> Result<()> {
+    check_set_ns_perms(target_ns, ctx)?;
+
+    // Switching the time namespace remaps the vDSO, which must not affect other threads or
+    // processes sharing the same address space.
+    if ctx.process.tasks().lock().as_slice().len() != 1
+        || ctx.user_space().vmar().has_multiple_handles()
+    {
+        return_errno_with_message!(
+            Errno::EUSERS,
+            "setting a time namespace is not allowed with a shared address space"
+        );
+    }
+
+    target_ns.enter()?;
+
+    // Unlike `unshare`, both the time namespace of the current process and that for the child
+    // processes are changed.
+    builder
+        .time_ns(target_ns.clone())
+        .time_ns_for_children(target_ns.clone());
+
+    Ok(())
+}
+
 fn set_uts_ns(
     builder: &mut NsProxyBuilder,
     target_ns: &Arc<UtsNamespace>,
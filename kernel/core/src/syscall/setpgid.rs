@@ -16,6 +16,14 @@ pub(super) fn sys_setpgid(pid: Pid, pgid: Pgid, ctx: &Context) -> Result<Syscall
         return_errno_with_message!(Errno::EINVAL, "negative PIDs or PGIDs are not valid");
     }
 
+    let pid_ns = current.pid_ns();
+    let pid = pid_ns.global_id_of(pid).ok_or_else(|| {
+        Error::with_message(Errno::ESRCH, "the process to set the PGID does not exist")
+    })?;
+    let pgid = pid_ns
+        .global_id_of(pgid)
+        .ok_or_else(|| Error::with_message(Errno::EPERM, "the new process group does not exist"))?;
+
     // "If `pid` is zero, then the process ID of the calling process is used."
     let pid = if pid == 0 { current.pid() } else { pid };
     // "If `pgid` is zero, then the PGID of the process specified by `pid` is made the same as its
@@ -3,7 +3,7 @@
 use super::SyscallReturn;
 use crate::{
     prelude::*,
-    process::{Gid, posix_thread::ContextPthreadAdminApi},
+    process::{Gid, coredump::set_ids_and_update_dumpable, posix_thread::ContextPthreadAdminApi},
 };
 
 pub(super) fn sys_setregid(rgid: i32, egid: i32, ctx: &Context) -> Result<SyscallReturn> {
@@ -22,7 +22,7 @@ pub(super) fn sys_setregid(rgid: i32, egid: i32, ctx: &Context) -> Result<Syscal
     debug!("rgid = {:?}, egid = {:?}", rgid, egid);
 
     let credentials = ctx.credentials_mut();
-    credentials.set_regid(rgid, egid)?;
+    set_ids_and_update_dumpable(ctx, || credentials.set_regid(rgid, egid))?;
 
     Ok(SyscallReturn::Return(0))
 }
@@ -3,7 +3,7 @@
 use super::SyscallReturn;
 use crate::{
     prelude::*,
-    process::{Gid, posix_thread::ContextPthreadAdminApi},
+    process::{Gid, coredump::set_ids_and_update_dumpable, posix_thread::ContextPthreadAdminApi},
 };
 
 pub(super) fn sys_setresgid(
@@ -33,7 +33,7 @@ pub(super) fn sys_setresgid(
     debug!("rgid = {:?}, egid = {:?}, sgid = {:?}", rgid, egid, sgid);
 
     let credentials = ctx.credentials_mut();
-    credentials.set_resgid(rgid, egid, sgid)?;
+    set_ids_and_update_dumpable(ctx, || credentials.set_resgid(rgid, egid, sgid))?;
 
     Ok(SyscallReturn::Return(0))
 }
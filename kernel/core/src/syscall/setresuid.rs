@@ -3,7 +3,7 @@
 use super::SyscallReturn;
 use crate::{
     prelude::*,
-    process::{Uid, posix_thread::ContextPthreadAdminApi},
+    process::{Uid, coredump::set_ids_and_update_dumpable, posix_thread::ContextPthreadAdminApi},
 };
 
 pub(super) fn sys_setresuid(
@@ -33,7 +33,7 @@ pub(super) fn sys_setresuid(
     debug!("ruid = {:?}, euid = {:?}, suid = {:?}", ruid, euid, suid);
 
     let credentials = ctx.credentials_mut();
-    credentials.set_resuid(ruid, euid, suid)?;
+    set_ids_and_update_dumpable(ctx, || credentials.set_resuid(ruid, euid, suid))?;
 
     Ok(SyscallReturn::Return(0))
 }
@@ -3,7 +3,7 @@
 use super::SyscallReturn;
 use crate::{
     prelude::*,
-    process::{Uid, posix_thread::ContextPthreadAdminApi},
+    process::{Uid, coredump::set_ids_and_update_dumpable, posix_thread::ContextPthreadAdminApi},
 };
 
 pub(super) fn sys_setreuid(ruid: i32, euid: i32, ctx: &Context) -> Result<SyscallReturn> {
@@ -22,7 +22,7 @@ pub(super) fn sys_setreuid(ruid: i32, euid: i32, ctx: &Context) -> Result<Syscal
     debug!("ruid = {:?}, euid = {:?}", ruid, euid);
 
     let credentials = ctx.credentials_mut();
-    credentials.set_reuid(ruid, euid)?;
+    set_ids_and_update_dumpable(ctx, || credentials.set_reuid(ruid, euid))?;
 
     Ok(SyscallReturn::Return(0))
 }
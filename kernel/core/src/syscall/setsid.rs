@@ -3,8 +3,9 @@
 use super::SyscallReturn;
 use crate::prelude::*;
 
-pub(super) fn sys_setsid(_ctx: &Context) -> Result<SyscallReturn> {
+pub(super) fn sys_setsid(ctx: &Context) -> Result<SyscallReturn> {
     let sid = current!().to_new_session()?;
+    let sid = ctx.process.pid_ns().ns_id_of(sid).unwrap();
 
     Ok(SyscallReturn::Return(sid as _))
 }
@@ -3,7 +3,7 @@
 use super::SyscallReturn;
 use crate::{
     prelude::*,
-    process::{Uid, posix_thread::ContextPthreadAdminApi},
+    process::{Uid, coredump::set_ids_and_update_dumpable, posix_thread::ContextPthreadAdminApi},
 };
 
 pub(super) fn sys_setuid(uid: i32, ctx: &Context) -> Result<SyscallReturn> {
@@ -15,7 +15,7 @@ pub(super) fn sys_setuid(uid: i32, ctx: &Context) -> Result<SyscallReturn> {
     debug!("uid = {:?}", uid);
 
     let credentials = ctx.credentials_mut();
-    credentials.set_uid(uid)?;
+    set_ids_and_update_dumpable(ctx, || credentials.set_uid(uid))?;
 
     Ok(SyscallReturn::Return(0))
 }
@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use align_ext::AlignExt;
+
+use super::SyscallReturn;
+use crate::{
+    ipc::{
+        IpcId,
+        shm::{SHMLBA, ShmAtFlags},
+    },
+    prelude::*,
+    vm::{
+        perms::VmPerms,
+        vmar::{VMAR_CAP_ADDR, VMAR_LOWEST_ADDR, VmarMapOffset},
+    },
+};
+
+pub(super) fn sys_shmat(
+    shmid: i32,
+    shmaddr: Vaddr,
+    shmflg: i32,
+    ctx: &Context,
+) -> Result<SyscallReturn> {
+    let Ok(shmid) = IpcId::try_from(shmid.cast_unsigned()) else {
+        return_errno_with_message!(Errno::EINVAL, "non-positive shared memory IDs are invalid");
+    };
+    let flags = ShmAtFlags::from_bits_truncate(shmflg.cast_unsigned());
+
+    debug!(
+        "shmat: shmid = {:?}, shmaddr = 0x{:x}, flags = {:?}",
+        shmid, shmaddr, flags
+    );
+
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/shm.c#L1540-L1560>.
+    let addr = if shmaddr == 0 {
+        if flags.contains(ShmAtFlags::SHM_REMAP) {
+            return_errno_with_message!(Errno::EINVAL, "SHM_REMAP requires an attach address");
+        }
+        None
+    } else if shmaddr.is_multiple_of(SHMLBA) {
+        Some(shmaddr)
+    } else if flags.contains(ShmAtFlags::SHM_RND) {
+        let addr = shmaddr.align_down(SHMLBA);
+        if addr == 0 && flags.contains(ShmAtFlags::SHM_REMAP) {
+            return_errno_with_message!(Errno::EINVAL, "SHM_REMAP requires an attach address");
+        }
+        (addr != 0).then_some(addr)
+    } else {
+        return_errno_with_message!(Errno::EINVAL, "the attach address is not aligned");
+    };
+
+    let mut perms = VmPerms::READ;
+    let mut may_perms = VmPerms::ALL_MAY_PERMS;
+    if flags.contains(ShmAtFlags::SHM_RDONLY) {
+        may_perms.remove(VmPerms::MAY_WRITE);
+    } else {
+        perms |= VmPerms::WRITE;
+    }
+    if flags.contains(ShmAtFlags::SHM_EXEC) {
+        perms |= VmPerms::EXEC;
+    }
+
+    let shm_file = {
+        let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+        let ipc_ns = ns_proxy.unwrap().ipc_ns();
+        ipc_ns.attach_shm(shmid, flags, ctx.posix_thread)?
+    };
+    let size = shm_file.segment().size().align_up(PAGE_SIZE);
+
+    let user_space = ctx.user_space();
+    let vmar = user_space.vmar();
+    let mut options = vmar
+        .new_map(size, perms)?
+        .may_perms(may_perms)
+        .is_shared(true)
+        .mappable(shm_file)?;
+
+    if let Some(addr) = addr {
+        if addr < VMAR_LOWEST_ADDR || VMAR_CAP_ADDR.checked_sub(addr).is_none_or(|gap| gap < size) {
+            return_errno_with_message!(Errno::EINVAL, "the attach address is out of range");
+        }
+
+        options = if flags.contains(ShmAtFlags::SHM_REMAP) {
+            options.offset(VmarMapOffset::FixedReplace(addr))
+        } else {
+            options.offset(VmarMapOffset::FixedNoReplace(addr))
+        };
+    }
+
+    let map_addr = options.build().map_err(|err| {
+        if err.error() == Errno::EEXIST {
+            Error::with_message(
+                Errno::EINVAL,
+                "the attach address overlaps existing mappings",
+            )
+        } else {
+            err
+        }
+    })?;
+
+    Ok(SyscallReturn::Return(map_addr as _))
+}
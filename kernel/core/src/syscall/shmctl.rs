@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use ostd::mm::VmIo;
+
+use super::SyscallReturn;
+use crate::{
+    ipc::{
+        IpcId, PermissionMode,
+        shm::{ShmControlCmd, ShmInfo64, ShmidDs},
+    },
+    prelude::*,
+};
+
+pub(super) fn sys_shmctl(shmid: i32, cmd: i32, buf: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let cmd = ShmControlCmd::try_from(cmd)?;
+
+    debug!(
+        "shmctl: shmid = {}, cmd = {:?}, buf = {:#x}",
+        shmid, cmd, buf
+    );
+
+    let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+    let ipc_ns = ns_proxy.unwrap().ipc_ns();
+
+    let parse_shmid = || {
+        IpcId::try_from(shmid.cast_unsigned()).map_err(|_| {
+            Error::with_message(Errno::EINVAL, "non-positive shared memory IDs are invalid")
+        })
+    };
+    let max_shmid = || ipc_ns.max_shm_id().map_or(0, |id| id.get() as isize);
+
+    match cmd {
+        ShmControlCmd::IPC_INFO => {
+            ctx.user_space().write_val(buf, &ShmInfo64::new())?;
+            return Ok(SyscallReturn::Return(max_shmid()));
+        }
+        ShmControlCmd::SHM_INFO => {
+            ctx.user_space().write_val(buf, &ipc_ns.shm_usage_info())?;
+            return Ok(SyscallReturn::Return(max_shmid()));
+        }
+        ShmControlCmd::IPC_STAT | ShmControlCmd::SHM_STAT | ShmControlCmd::SHM_STAT_ANY => {
+            // For `SHM_STAT` and `SHM_STAT_ANY`, `shmid` is an index of the kernel's internal
+            // array, which is the same as the ID here.
+            let shmid = parse_shmid()?;
+            let required_perm = if matches!(cmd, ShmControlCmd::SHM_STAT_ANY) {
+                PermissionMode::empty()
+            } else {
+                PermissionMode::READ
+            };
+
+            let shmid_ds = ipc_ns.with_shm(shmid, required_perm, ctx.posix_thread, |shm| {
+                Ok(shm.shmid_ds())
+            })?;
+            ctx.user_space().write_val(buf, &shmid_ds)?;
+
+            if !matches!(cmd, ShmControlCmd::IPC_STAT) {
+                return Ok(SyscallReturn::Return(shmid.get() as isize));
+            }
+        }
+        ShmControlCmd::IPC_RMID => {
+            let shmid = parse_shmid()?;
+            ipc_ns.destroy_shm(shmid, |shm| {
+                ipc_ns.check_owner(shm.permission(), ctx.posix_thread)
+            })?;
+        }
+        ShmControlCmd::SHM_LOCK | ShmControlCmd::SHM_UNLOCK => {
+            let shmid = parse_shmid()?;
+            let is_locked = matches!(cmd, ShmControlCmd::SHM_LOCK);
+            ipc_ns.lock_shm(shmid, is_locked, ctx.posix_thread)?;
+        }
+        ShmControlCmd::IPC_SET => {
+            let shmid = parse_shmid()?;
+            let shmid_ds = ctx.user_space().read_val::<ShmidDs>(buf)?;
+            ipc_ns.with_shm(shmid, PermissionMode::empty(), ctx.posix_thread, |shm| {
+                ipc_ns.check_setter(shm.permission(), ctx.posix_thread)?;
+                shm.set_permission(&shmid_ds)
+            })?;
+        }
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use align_ext::AlignExt;
+
+use super::SyscallReturn;
+use crate::{ipc::shm::ShmFile, prelude::*, vm::vmar::VMAR_CAP_ADDR};
+
+pub(super) fn sys_shmdt(shmaddr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("shmdt: shmaddr = 0x{:x}", shmaddr);
+
+    if !shmaddr.is_multiple_of(PAGE_SIZE) || shmaddr >= VMAR_CAP_ADDR {
+        return_errno_with_message!(Errno::EINVAL, "the address is invalid");
+    }
+
+    let user_space = ctx.user_space();
+    let vmar = user_space.vmar();
+
+    // Find the mappings that belong to the attach at `shmaddr`. The attach may have been split
+    // into multiple mappings (e.g., by `mprotect`), so all of them are collected.
+    //
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/ipc/shm.c#L1742>.
+    let ranges = {
+        let query_guard = vmar.query(shmaddr..VMAR_CAP_ADDR);
+        let mut mappings = query_guard.iter();
+
+        let Some((first_mapping, shm_file)) = mappings.find_map(|mapping| {
+            let file = mapping.file()?;
+            file.downcast_ref::<ShmFile>()?;
+            let attach_addr = mapping.map_to_addr().checked_sub(mapping.vmo_offset()?)?;
+            (attach_addr == shmaddr).then_some((mapping, file))
+        }) else {
+            return_errno_with_message!(Errno::EINVAL, "no shared memory segment is attached");
+        };
+
+        let size = shm_file
+            .downcast_ref::<ShmFile>()
+            .unwrap()
+            .segment()
+            .size()
+            .align_up(PAGE_SIZE);
+        let end = shmaddr + size;
+
+        let mut ranges = vec![first_mapping.map_to_addr()..first_mapping.map_end()];
+        for mapping in mappings {
+            if mapping.map_end() > end {
+                break;
+            }
+            if mapping
+                .file()
+                .is_some_and(|file| Arc::ptr_eq(file, shm_file))
+            {
+                ranges.push(mapping.map_to_addr()..mapping.map_end());
+            }
+        }
+
+        ranges
+    };
+
+    for range in ranges {
+        vmar.remove_mapping(range)?;
+    }
+
+    Ok(SyscallReturn::Return(0))
+}
@@ -4,14 +4,17 @@ use super::SyscallReturn;
 use crate::{
     fs::file::{FileLike, file_table::FdFlags},
     net::socket::{
-        ip::{DatagramSocket, IpAddressFamily, StreamSocket},
+        ip::{DatagramSocket, IpAddressFamily, RawSocket, StreamSocket},
         netlink::{
-            NetlinkRouteSocket, NetlinkUeventSocket, StandardNetlinkProtocol, is_valid_protocol,
+            NetlinkNetfilterSocket, NetlinkRouteSocket, NetlinkUeventSocket,
+            StandardNetlinkProtocol, is_valid_protocol,
         },
+        packet::PacketSocket,
         unix::{UnixDatagramSocket, UnixStreamSocket},
         vsock::VsockStreamSocket,
     },
     prelude::*,
+    security::lsm::hooks as lsm_hooks,
     util::net::{CSocketAddrFamily, Protocol, SOCK_TYPE_MASK, SockFlags, SockType},
 };
 
@@ -29,16 +32,23 @@ pub(super) fn sys_socket(
         domain, sock_type, sock_flags
     );
 
+    lsm_hooks::on_socket_create(lsm_hooks::SocketCreateContext::new(
+        ctx.posix_thread,
+        domain,
+        sock_type,
+    ))?;
+
     let is_nonblocking = sock_flags.contains(SockFlags::SOCK_NONBLOCK);
+    let net_ns = ctx.thread_local.borrow_ns_proxy().unwrap().net_ns().clone();
     let file_like = match (domain, sock_type) {
         (CSocketAddrFamily::AF_UNIX, SockType::SOCK_STREAM) => {
-            UnixStreamSocket::new(is_nonblocking, sock_type) as Arc<dyn FileLike>
+            UnixStreamSocket::new(is_nonblocking, sock_type, net_ns) as Arc<dyn FileLike>
         }
         (CSocketAddrFamily::AF_UNIX, SockType::SOCK_SEQPACKET) => {
-            UnixStreamSocket::new(is_nonblocking, sock_type) as Arc<dyn FileLike>
+            UnixStreamSocket::new(is_nonblocking, sock_type, net_ns) as Arc<dyn FileLike>
         }
         (CSocketAddrFamily::AF_UNIX, SockType::SOCK_RAW | SockType::SOCK_DGRAM) => {
-            UnixDatagramSocket::new(is_nonblocking) as Arc<dyn FileLike>
+            UnixDatagramSocket::new(is_nonblocking, net_ns) as Arc<dyn FileLike>
         }
         (CSocketAddrFamily::AF_INET | CSocketAddrFamily::AF_INET6, SockType::SOCK_STREAM) => {
             let protocol = Protocol::try_from(protocol)?;
@@ -50,7 +60,7 @@ pub(super) fn sys_socket(
                         CSocketAddrFamily::AF_INET6 => IpAddressFamily::IPv6,
                         _ => unreachable!(),
                     };
-                    StreamSocket::new(is_nonblocking, family) as Arc<dyn FileLike>
+                    StreamSocket::new(is_nonblocking, family, net_ns) as Arc<dyn FileLike>
                 }
                 _ => return_errno_with_message!(Errno::EAFNOSUPPORT, "unsupported protocol"),
             }
@@ -60,20 +70,56 @@ pub(super) fn sys_socket(
             debug!("protocol = {:?}", protocol);
             match protocol {
                 Protocol::IPPROTO_IP | Protocol::IPPROTO_UDP => {
-                    DatagramSocket::new(is_nonblocking) as Arc<dyn FileLike>
+                    DatagramSocket::new(is_nonblocking, IpAddressFamily::IPv4, net_ns)
+                        as Arc<dyn FileLike>
+                }
+                Protocol::IPPROTO_ICMP => {
+                    RawSocket::new_ping(is_nonblocking, IpAddressFamily::IPv4, net_ns)?
+                        as Arc<dyn FileLike>
+                }
+                _ => return_errno_with_message!(Errno::EAFNOSUPPORT, "unsupported protocol"),
+            }
+        }
+        (CSocketAddrFamily::AF_INET6, SockType::SOCK_DGRAM) => {
+            let protocol = Protocol::try_from(protocol)?;
+            debug!("protocol = {:?}", protocol);
+            match protocol {
+                Protocol::IPPROTO_IP | Protocol::IPPROTO_UDP => {
+                    DatagramSocket::new(is_nonblocking, IpAddressFamily::IPv6, net_ns)
+                        as Arc<dyn FileLike>
+                }
+                Protocol::IPPROTO_ICMPV6 => {
+                    RawSocket::new_ping(is_nonblocking, IpAddressFamily::IPv6, net_ns)?
+                        as Arc<dyn FileLike>
                 }
                 _ => return_errno_with_message!(Errno::EAFNOSUPPORT, "unsupported protocol"),
             }
         }
+        (CSocketAddrFamily::AF_INET | CSocketAddrFamily::AF_INET6, SockType::SOCK_RAW) => {
+            let Ok(protocol) = u8::try_from(protocol) else {
+                return_errno_with_message!(Errno::EPROTONOSUPPORT, "the protocol is invalid");
+            };
+            debug!("protocol = {}", protocol);
+            let family = match domain {
+                CSocketAddrFamily::AF_INET => IpAddressFamily::IPv4,
+                CSocketAddrFamily::AF_INET6 => IpAddressFamily::IPv6,
+                _ => unreachable!(),
+            };
+            RawSocket::new_raw(is_nonblocking, family, protocol, net_ns)? as Arc<dyn FileLike>
+        }
         (CSocketAddrFamily::AF_NETLINK, SockType::SOCK_RAW | SockType::SOCK_DGRAM) => {
             let netlink_family = StandardNetlinkProtocol::try_from(protocol as u32);
             debug!("netlink family = {:?}", netlink_family);
             match netlink_family {
                 Ok(StandardNetlinkProtocol::ROUTE) => {
-                    NetlinkRouteSocket::new(is_nonblocking, sock_type) as Arc<dyn FileLike>
+                    NetlinkRouteSocket::new(is_nonblocking, sock_type, net_ns) as Arc<dyn FileLike>
                 }
                 Ok(StandardNetlinkProtocol::KOBJECT_UEVENT) => {
-                    NetlinkUeventSocket::new(is_nonblocking, sock_type) as Arc<dyn FileLike>
+                    NetlinkUeventSocket::new(is_nonblocking, sock_type, net_ns) as Arc<dyn FileLike>
+                }
+                Ok(StandardNetlinkProtocol::NETFILTER) => {
+                    NetlinkNetfilterSocket::new(is_nonblocking, sock_type, net_ns)
+                        as Arc<dyn FileLike>
                 }
                 Ok(_) => {
                     return_errno_with_message!(
@@ -92,8 +138,12 @@ pub(super) fn sys_socket(
                 }
             }
         }
+        (CSocketAddrFamily::AF_PACKET, SockType::SOCK_RAW | SockType::SOCK_DGRAM) => {
+            PacketSocket::new(is_nonblocking, sock_type, protocol as u16, net_ns)?
+                as Arc<dyn FileLike>
+        }
         (CSocketAddrFamily::AF_VSOCK, SockType::SOCK_STREAM) => {
-            VsockStreamSocket::new(is_nonblocking)? as Arc<dyn FileLike>
+            VsockStreamSocket::new(is_nonblocking, net_ns)? as Arc<dyn FileLike>
         }
         _ => return_errno_with_message!(Errno::EAFNOSUPPORT, "unsupported domain"),
     };
@@ -10,6 +10,7 @@ use crate::{
     },
     net::socket::unix::{UnixDatagramSocket, UnixStreamSocket},
     prelude::*,
+    security::lsm::hooks as lsm_hooks,
     util::net::{CSocketAddrFamily, Protocol, SOCK_TYPE_MASK, SockFlags, SockType},
 };
 
@@ -29,6 +30,12 @@ pub(super) fn sys_socketpair(
         domain, sock_type, sock_flags, protocol
     );
 
+    lsm_hooks::on_socket_create(lsm_hooks::SocketCreateContext::new(
+        ctx.posix_thread,
+        domain,
+        sock_type,
+    ))?;
+
     macro_rules! file_pair {
         ($expr:expr) => {{
             let (socket_a, socket_b) = $expr;
@@ -37,15 +44,16 @@ pub(super) fn sys_socketpair(
     }
 
     let nonblocking = sock_flags.contains(SockFlags::SOCK_NONBLOCK);
+    let net_ns = ctx.thread_local.borrow_ns_proxy().unwrap().net_ns().clone();
     let (socket_a, socket_b) = match (domain, sock_type) {
         (CSocketAddrFamily::AF_UNIX, SockType::SOCK_STREAM) => {
-            file_pair!(UnixStreamSocket::new_pair(nonblocking, sock_type))
+            file_pair!(UnixStreamSocket::new_pair(nonblocking, sock_type, net_ns))
         }
         (CSocketAddrFamily::AF_UNIX, SockType::SOCK_SEQPACKET) => {
-            file_pair!(UnixStreamSocket::new_pair(nonblocking, sock_type))
+            file_pair!(UnixStreamSocket::new_pair(nonblocking, sock_type, net_ns))
         }
         (CSocketAddrFamily::AF_UNIX, SockType::SOCK_RAW | SockType::SOCK_DGRAM) => {
-            file_pair!(UnixDatagramSocket::new_pair(nonblocking))
+            file_pair!(UnixDatagramSocket::new_pair(nonblocking, net_ns))
         }
         _ => return_errno_with_message!(
             Errno::EAFNOSUPPORT,
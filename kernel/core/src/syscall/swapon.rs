@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_block::BlockDevice;
+
+use super::SyscallReturn;
+use crate::{
+    fs::{
+        file::InodeType,
+        vfs::path::{AT_FDCWD, EmptyPathStr, FsPath},
+    },
+    prelude::*,
+    process::{UserNamespace, credentials::capabilities::CapSet},
+    security::lsm::hooks as lsm_hooks,
+    syscall::constants::MAX_FILENAME_LEN,
+    vm::swap,
+};
+
+pub(super) fn sys_swapon(path_addr: Vaddr, flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    let path_name = ctx.user_space().read_cstring(path_addr, MAX_FILENAME_LEN)?;
+    debug!("path = {:?}, flags = {:#x}", path_name, flags);
+
+    check_sys_admin(ctx)?;
+
+    if flags & !SWAP_FLAGS_VALID != 0 {
+        return_errno_with_message!(Errno::EINVAL, "the swap flags are invalid");
+    }
+    if flags & SWAP_FLAG_DISCARD_MASK != 0 {
+        warn!("discarding swap pages is not supported; the flag is ignored");
+    }
+    let priority = if flags & SWAP_FLAG_PREFER != 0 {
+        Some((flags & SWAP_FLAG_PRIO_MASK) as i16)
+    } else {
+        None
+    };
+
+    let (name, device) = lookup_block_device(&path_name.to_string_lossy(), ctx)?;
+    swap::swap_on(name, device, priority)?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+pub(super) fn sys_swapoff(path_addr: Vaddr, ctx: &Context) -> Result<SyscallReturn> {
+    let path_name = ctx.user_space().read_cstring(path_addr, MAX_FILENAME_LEN)?;
+    debug!("path = {:?}", path_name);
+
+    check_sys_admin(ctx)?;
+
+    let (_, device) = lookup_block_device(&path_name.to_string_lossy(), ctx)?;
+    swap::swap_off(device.id())?;
+
+    Ok(SyscallReturn::Return(0))
+}
+
+fn check_sys_admin(ctx: &Context) -> Result<()> {
+    lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+        UserNamespace::get_init_singleton().as_ref(),
+        ctx.posix_thread,
+        CapSet::SYS_ADMIN,
+    ))
+}
+
+/// Looks up the block device at the path, and returns the absolute path name
+/// along with the device.
+fn lookup_block_device(path_name: &str, ctx: &Context) -> Result<(String, Arc<dyn BlockDevice>)> {
+    let fs_path = FsPath::from_fd_at(AT_FDCWD, path_name, EmptyPathStr::Reject)?;
+    let fs_ref = ctx.thread_local.borrow_fs();
+    let resolver = fs_ref.resolver().read();
+    let path = resolver.lookup(&fs_path)?;
+
+    // TODO: Support swap files, which are regular files on file systems.
+    if path.type_() != InodeType::BlockDevice {
+        return_errno_with_message!(Errno::EINVAL, "the path is not a block device");
+    }
+
+    let device = path
+        .metadata()?
+        .self_dev_id
+        .and_then(aster_block::lookup)
+        .ok_or_else(|| Error::with_message(Errno::ENXIO, "the block device is not found"))?;
+    let name = resolver.make_abs_path(&path).into_string();
+
+    Ok((name, device))
+}
+
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/linux/swap.h#L23-L32>
+const SWAP_FLAG_PREFER: u32 = 0x8000;
+const SWAP_FLAG_PRIO_MASK: u32 = 0x7fff;
+const SWAP_FLAG_DISCARD: u32 = 0x10000;
+const SWAP_FLAG_DISCARD_ONCE: u32 = 0x20000;
+const SWAP_FLAG_DISCARD_PAGES: u32 = 0x40000;
+
+const SWAP_FLAG_DISCARD_MASK: u32 =
+    SWAP_FLAG_DISCARD | SWAP_FLAG_DISCARD_ONCE | SWAP_FLAG_DISCARD_PAGES;
+const SWAP_FLAGS_VALID: u32 = SWAP_FLAG_PRIO_MASK | SWAP_FLAG_PREFER | SWAP_FLAG_DISCARD_MASK;
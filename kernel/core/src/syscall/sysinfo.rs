@@ -6,7 +6,7 @@ use aster_time::read_monotonic_time;
 use aster_util::fixed_point::FixedU64;
 use ostd::mm::VmIo;
 
-use super::SyscallReturn;
+use super::{ClockId, SyscallReturn};
 use crate::{prelude::*, process::pid_table, sched::loadavg};
 
 #[padding_struct]
@@ -31,8 +31,14 @@ pub(super) fn sys_sysinfo(sysinfo_addr: Vaddr, ctx: &Context) -> Result<SyscallR
     type SysinfoLoadAvg = FixedU64<16>;
 
     let loadavg = loadavg::get_loadavg();
+    // The uptime is shifted by the boot-time offset of the time namespace.
+    let uptime = {
+        let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+        let time_ns = ns_proxy.unwrap().time_ns();
+        time_ns.to_ns_time(ClockId::CLOCK_BOOTTIME, read_monotonic_time())
+    };
     let info = SysInfo {
-        uptime: read_monotonic_time().as_secs_round_up() as i64,
+        uptime: uptime.as_secs_round_up() as i64,
         loads: [
             SysinfoLoadAvg::from(loadavg[0]).raw(),
             SysinfoLoadAvg::from(loadavg[1]).raw(),
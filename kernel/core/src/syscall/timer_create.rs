@@ -76,9 +76,13 @@ pub(super) fn sys_timer_create(
                 // Send a signal to the specified thread when the timer is expired.
                 SigNotify::SIGEV_THREAD_ID => {
                     let tid = sig_event.sigev_un.read_tid() as u32;
-                    let thread = pid_table::pid_table_mut().get_thread(tid).ok_or_else(|| {
-                        Error::with_message(Errno::EINVAL, "the target thread does not exist")
-                    })?;
+                    let thread = current_process
+                        .pid_ns()
+                        .global_id_of(tid)
+                        .and_then(|tid| pid_table::pid_table_mut().get_thread(tid))
+                        .ok_or_else(|| {
+                            Error::with_message(Errno::EINVAL, "the target thread does not exist")
+                        })?;
                     let posix_thread = thread.as_posix_thread().unwrap();
                     if posix_thread.process().pid() != current_process.pid() {
                         return_errno_with_message!(
@@ -147,8 +151,11 @@ where
         let dynamic_clockid_info = DynamicClockIdInfo::try_from(clockid)?;
         match dynamic_clockid_info {
             DynamicClockIdInfo::Pid(pid, clock_type) => {
-                let process = pid_table::pid_table_mut()
-                    .get_process(pid)
+                let process = ctx
+                    .process
+                    .pid_ns()
+                    .global_id_of(pid)
+                    .and_then(|pid| pid_table::pid_table_mut().get_process(pid))
                     .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid clock ID"))?;
                 let process_timer_manager = process.timer_manager();
                 match clock_type {
@@ -159,8 +166,11 @@ where
                 }
             }
             DynamicClockIdInfo::Tid(tid, clock_type) => {
-                let thread = pid_table::pid_table_mut()
-                    .get_thread(tid)
+                let thread = ctx
+                    .process
+                    .pid_ns()
+                    .global_id_of(tid)
+                    .and_then(|tid| pid_table::pid_table_mut().get_thread(tid))
                     .ok_or_else(|| Error::with_message(Errno::EINVAL, "invalid clock ID"))?;
                 let posix_thread = thread.as_posix_thread().unwrap();
                 match clock_type {
@@ -42,7 +42,10 @@ pub(super) fn sys_timer_settime(
         let timeout = if (flags & TIMER_ABSTIME) == 0 {
             Timeout::After(expire_time)
         } else {
-            Timeout::When(expire_time)
+            // The absolute time is observed in the time namespace.
+            let ns_proxy = ctx.thread_local.borrow_ns_proxy();
+            let time_ns = ns_proxy.unwrap().time_ns();
+            Timeout::When(time_ns.from_ns_timeout(&timer, expire_time))
         };
         timer_guard.set_timeout(timeout);
     }
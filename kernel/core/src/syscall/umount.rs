@@ -4,6 +4,7 @@ use super::SyscallReturn;
 use crate::{
     fs::vfs::path::{AT_FDCWD, EmptyPathStr, FsPath},
     prelude::*,
+    security::lsm::hooks as lsm_hooks,
     syscall::constants::MAX_FILENAME_LEN,
 };
 
@@ -35,7 +36,9 @@ pub(super) fn sys_umount(path_addr: Vaddr, flags: u64, ctx: &Context) -> Result<
     // to the topmost mount. If there is a mount stacked above the current thread's `cwd`, normal
     // path lookup through "." cannot access the upper mount, but umount through "." can operate
     // on the upper mount.
-    target_path.get_top_path().unmount(ctx)?;
+    let top_path = target_path.get_top_path();
+    lsm_hooks::on_umount(lsm_hooks::PathContext::new(ctx.posix_thread, &top_path))?;
+    top_path.unmount(ctx)?;
 
     Ok(SyscallReturn::Return(0))
 }
@@ -0,0 +1,29 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use super::SyscallReturn;
+use crate::{
+    prelude::*,
+    process::{UserNamespace, credentials::capabilities::CapSet},
+    security::lsm::hooks as lsm_hooks,
+    vm::userfaultfd::{UserfaultFlags, create_userfaultfd},
+};
+
+pub(super) fn sys_userfaultfd(flags: u32, ctx: &Context) -> Result<SyscallReturn> {
+    debug!("flags = {:#x}", flags);
+
+    // Handling page faults from kernel mode is a privileged operation, since it can be used to
+    // pause the kernel at a chosen point. Opening `/dev/userfaultfd` does not require the
+    // capability, but the device file is only accessible to the root user by default.
+    //
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/userfaultfd.c>
+    if flags & UserfaultFlags::USER_MODE_ONLY.bits() == 0 {
+        lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+            UserNamespace::get_init_singleton().as_ref(),
+            ctx.posix_thread,
+            CapSet::SYS_PTRACE,
+        ))?;
+    }
+
+    let fd = create_userfaultfd(flags)?;
+    Ok(SyscallReturn::Return(fd.into()))
+}
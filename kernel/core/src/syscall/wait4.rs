@@ -42,7 +42,12 @@ pub(super) fn sys_wait4(
         return Ok(SyscallReturn::Return(0 as _));
     };
 
-    let (return_pid, status_code) = (wait_status.pid(), calculate_status_code(&wait_status));
+    let return_pid = ctx
+        .process
+        .pid_ns()
+        .ns_id_of(wait_status.pid())
+        .unwrap_or(0);
+    let status_code = calculate_status_code(&wait_status);
     if status_ptr != 0 {
         ctx.user_space().write_val(status_ptr as _, &status_code)?;
     }
@@ -16,6 +16,7 @@ pub(super) fn handle_exception(ctx: &Context, user_ctx: &UserContext, exception:
     debug!("handle exception: {:#x?}", exception);
 
     if let Ok(page_fault_info) = PageFaultInfo::try_from(&exception) {
+        let page_fault_info = page_fault_info.in_user_mode();
         let user_space = ctx.user_space();
         let vmar = user_space.vmar();
         if handle_page_fault_from_vmar(vmar, &page_fault_info).is_ok() {
@@ -13,6 +13,7 @@ use super::{Thread, oops};
 use crate::{
     context::current_userspace,
     cpu::LinuxAbi,
+    fs::cgroupfs::throttle_memory_over_high,
     prelude::*,
     process::{
         posix_thread::{AsPosixThread, FIRST_POSIX_TID, ThreadLocal, ptrace::PtraceStopResult},
@@ -52,7 +53,11 @@ pub(crate) fn create_new_user_task(
         if is_userspace_vaddr(child_tid_ptr) {
             // At this point, we can do almost nothing if the address is not valid and the store
             // operation fails. So we ignore the error here.
-            let _ = current_userspace!().write_val(child_tid_ptr, &current_posix_thread.tid());
+            let tid = current_process
+                .pid_ns()
+                .ns_id_of(current_posix_thread.tid())
+                .unwrap();
+            let _ = current_userspace!().write_val(child_tid_ptr, &tid);
         }
 
         let ctx = Context {
@@ -101,6 +106,9 @@ pub(crate) fn create_new_user_task(
                 break;
             }
 
+            // Throttle the thread if its memory cgroup is over `memory.high`
+            throttle_memory_over_high(&ctx);
+
             // Handle signals
             handle_pending_signal(user_ctx, &ctx);
 
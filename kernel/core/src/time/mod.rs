@@ -15,6 +15,7 @@ mod core;
 pub(crate) mod cpu_time_stats;
 mod softirq;
 mod system_time;
+pub(crate) mod time_ns;
 pub(crate) mod timerfd;
 pub(crate) mod wait;
 
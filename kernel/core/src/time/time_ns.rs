@@ -0,0 +1,342 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::time::Duration;
+
+use ostd::task::Task;
+use spin::Once;
+
+#[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+use crate::vm::page_cache::Vmo;
+use crate::{
+    fs::pseudofs::{NsCommonOps, NsType, StashedDentry},
+    prelude::*,
+    process::{UserNamespace, credentials::capabilities::CapSet, posix_thread::PosixThread},
+    security::lsm::hooks as lsm_hooks,
+    syscall::ClockId,
+    time::{
+        Clock, NSEC_PER_SEC, Timer,
+        clocks::{BootTimeClock, MonotonicClock},
+    },
+};
+
+/// The time namespace.
+///
+/// A time namespace shifts the monotonic and boot-time clocks by per-namespace offsets, so that
+/// the clocks observed by the processes do not go backwards after the processes are checkpointed
+/// and restored on another machine.
+///
+/// Like Linux, `unshare()` does not move the calling process into the new time namespace.
+/// Instead, the namespace becomes the time namespace for the child processes, which enter it when
+/// they are created. The offsets can only be changed before any process enters the namespace.
+pub(crate) struct TimeNamespace {
+    /// The clock offsets, which can be changed until a process enters the namespace.
+    offsets: Mutex<TimeNsOffsets>,
+    /// The state of the namespace after a process enters it.
+    entered: Once<EnteredTimeNs>,
+    owner: Arc<UserNamespace>,
+    stashed_dentry: StashedDentry,
+}
+
+/// The state of a time namespace whose clock offsets have been frozen.
+struct EnteredTimeNs {
+    offsets: TimeNsOffsets,
+    /// The vDSO VMO that reports the time in the namespace.
+    ///
+    /// If the offsets are all zeros, this is `None` and the vDSO VMO of the initial time namespace
+    /// is used.
+    #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+    vdso_vmo: Option<Arc<Vmo>>,
+}
+
+/// The clock offsets of a time namespace.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct TimeNsOffsets {
+    monotonic: TimeNsOffset,
+    boottime: TimeNsOffset,
+}
+
+impl TimeNsOffsets {
+    /// Returns the offset of the monotonic clocks.
+    pub(crate) fn monotonic(&self) -> TimeNsOffset {
+        self.monotonic
+    }
+
+    /// Returns the offset of the boot-time clock.
+    pub(crate) fn boottime(&self) -> TimeNsOffset {
+        self.boottime
+    }
+
+    #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+    fn is_zero(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+/// The offset of a clock in a time namespace.
+///
+/// The offset may be negative, but the nanoseconds are always normalized to
+/// `0..NSEC_PER_SEC`, which matches `struct timespec64` in Linux.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct TimeNsOffset {
+    secs: i64,
+    nanos: u32,
+}
+
+impl TimeNsOffset {
+    /// Creates an offset from seconds and nanoseconds.
+    ///
+    /// The nanoseconds must be less than one second.
+    pub(crate) fn new(secs: i64, nanos: u64) -> Result<Self> {
+        if nanos >= NSEC_PER_SEC as u64 {
+            return_errno_with_message!(Errno::EINVAL, "the nanoseconds are not normalized");
+        }
+
+        Ok(Self {
+            secs,
+            nanos: nanos as u32,
+        })
+    }
+
+    /// Returns the seconds of the offset.
+    pub(crate) fn secs(&self) -> i64 {
+        self.secs
+    }
+
+    /// Returns the nanoseconds of the offset.
+    pub(crate) fn nanos(&self) -> u32 {
+        self.nanos
+    }
+
+    fn as_nanos(&self) -> i128 {
+        self.secs as i128 * NSEC_PER_SEC as i128 + self.nanos as i128
+    }
+}
+
+/// The maximum time in seconds that a clock in a time namespace can report after the offset is
+/// set.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/kernel/time/namespace.c#L452>
+const MAX_NS_TIME_SECS: i64 = i64::MAX / NSEC_PER_SEC / 2;
+
+impl TimeNamespace {
+    /// Returns a reference to the singleton initial time namespace.
+    pub(crate) fn get_init_singleton() -> &'static Arc<TimeNamespace> {
+        static INIT: Once<Arc<TimeNamespace>> = Once::new();
+
+        INIT.call_once(|| {
+            let owner = UserNamespace::get_init_singleton().clone();
+            let init_ns = Self::new(TimeNsOffsets::default(), owner);
+            init_ns.enter().unwrap();
+            init_ns
+        })
+    }
+
+    fn new(offsets: TimeNsOffsets, owner: Arc<UserNamespace>) -> Arc<Self> {
+        Arc::new(Self {
+            offsets: Mutex::new(offsets),
+            entered: Once::new(),
+            owner,
+            stashed_dentry: StashedDentry::new(),
+        })
+    }
+
+    /// Creates a new time namespace.
+    ///
+    /// Like Linux, the new time namespace inherits the clock offsets of `self`.
+    pub(crate) fn new_clone(
+        &self,
+        owner: Arc<UserNamespace>,
+        posix_thread: &PosixThread,
+    ) -> Result<Arc<Self>> {
+        lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+            owner.as_ref(),
+            posix_thread,
+            CapSet::SYS_ADMIN,
+        ))?;
+        Ok(Self::new(self.offsets(), owner))
+    }
+
+    /// Returns the time namespace of the current thread.
+    pub(crate) fn current() -> Arc<TimeNamespace> {
+        let current_task = Task::current().unwrap();
+        let thread_local = current_task.as_thread_local().unwrap();
+        let ns_proxy = thread_local.borrow_ns_proxy();
+        ns_proxy.unwrap().time_ns().clone()
+    }
+
+    /// Returns the owner user namespace of the time namespace.
+    pub(crate) fn owner(&self) -> &Arc<UserNamespace> {
+        &self.owner
+    }
+
+    /// Returns the clock offsets of the time namespace.
+    pub(crate) fn offsets(&self) -> TimeNsOffsets {
+        if let Some(entered) = self.entered.get() {
+            return entered.offsets;
+        }
+
+        *self.offsets.lock()
+    }
+
+    /// Sets the offsets of the specified clocks.
+    ///
+    /// Only `CLOCK_MONOTONIC` and `CLOCK_BOOTTIME` have offsets. The offset of `CLOCK_MONOTONIC`
+    /// also applies to `CLOCK_MONOTONIC_RAW` and `CLOCK_MONOTONIC_COARSE`.
+    ///
+    /// This method fails with [`EACCES`] if a process has entered the time namespace.
+    ///
+    /// [`EACCES`]: Errno::EACCES
+    pub(crate) fn set_offsets(&self, new_offsets: &[(ClockId, TimeNsOffset)]) -> Result<()> {
+        for (clock_id, offset) in new_offsets {
+            let now = match clock_id {
+                ClockId::CLOCK_MONOTONIC => MonotonicClock::get().read_time(),
+                ClockId::CLOCK_BOOTTIME => BootTimeClock::get().read_time(),
+                _ => return_errno_with_message!(Errno::EINVAL, "the clock cannot have an offset"),
+            };
+
+            let ns_time_secs =
+                (now.as_nanos() as i128 + offset.as_nanos()).div_euclid(NSEC_PER_SEC as i128);
+            if !(0..=MAX_NS_TIME_SECS as i128).contains(&ns_time_secs) {
+                return_errno_with_message!(
+                    Errno::ERANGE,
+                    "the time in the namespace is out of range"
+                );
+            }
+        }
+
+        let mut offsets = self.offsets.lock();
+        if self.entered.is_completed() {
+            return_errno_with_message!(
+                Errno::EACCES,
+                "the offsets cannot be changed after a process has entered the namespace"
+            );
+        }
+
+        for (clock_id, offset) in new_offsets {
+            match clock_id {
+                ClockId::CLOCK_MONOTONIC => offsets.monotonic = *offset,
+                ClockId::CLOCK_BOOTTIME => offsets.boottime = *offset,
+                _ => unreachable!(),
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Freezes the clock offsets because a process is entering the time namespace.
+    ///
+    /// This also prepares the vDSO VMO that reports the time in the namespace.
+    pub(crate) fn enter(&self) -> Result<()> {
+        if self.entered.is_completed() {
+            return Ok(());
+        }
+
+        let offsets = self.offsets.lock();
+        if self.entered.is_completed() {
+            return Ok(());
+        }
+
+        #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+        let vdso_vmo = if offsets.is_zero() {
+            None
+        } else {
+            crate::vdso::new_time_ns_vdso_vmo(&offsets)?
+        };
+
+        self.entered.call_once(|| EnteredTimeNs {
+            offsets: *offsets,
+            #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+            vdso_vmo,
+        });
+
+        Ok(())
+    }
+
+    /// Returns the vDSO VMO that should be mapped by the processes in the time namespace.
+    ///
+    /// This method returns `None` if the vDSO does not exist.
+    #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
+    pub(crate) fn vdso_vmo(&self) -> Option<Arc<Vmo>> {
+        self.entered
+            .get()
+            .and_then(|entered| entered.vdso_vmo.clone())
+            .or_else(crate::vdso::vdso_vmo)
+    }
+
+    /// Converts the time of a clock to the time observed in the time namespace.
+    pub(crate) fn to_ns_time(&self, clock_id: ClockId, time: Duration) -> Duration {
+        match self.clock_offset(clock_id) {
+            Some(offset) => shift_time(time, offset.as_nanos()),
+            None => time,
+        }
+    }
+
+    /// Converts the time observed in the time namespace to the time of a clock.
+    pub(crate) fn from_ns_time(&self, clock_id: ClockId, time: Duration) -> Duration {
+        match self.clock_offset(clock_id) {
+            Some(offset) => shift_time(time, -offset.as_nanos()),
+            None => time,
+        }
+    }
+
+    /// Converts an absolute timeout observed in the time namespace to the time of the clock on
+    /// which the timer is based.
+    pub(crate) fn from_ns_timeout(&self, timer: &Timer, timeout: Duration) -> Duration {
+        // The timer managers are per-CPU, but the clocks are singletons.
+        let clock = Arc::as_ptr(timer.timer_manager().clock()).cast::<()>();
+        let clock_id = if clock == Arc::as_ptr(MonotonicClock::get()).cast() {
+            ClockId::CLOCK_MONOTONIC
+        } else if clock == Arc::as_ptr(BootTimeClock::get()).cast() {
+            ClockId::CLOCK_BOOTTIME
+        } else {
+            return timeout;
+        };
+
+        self.from_ns_time(clock_id, timeout)
+    }
+
+    fn clock_offset(&self, clock_id: ClockId) -> Option<TimeNsOffset> {
+        // Processes only live in time namespaces that have been entered.
+        let offsets = &self.entered.get()?.offsets;
+
+        match clock_id {
+            ClockId::CLOCK_MONOTONIC
+            | ClockId::CLOCK_MONOTONIC_RAW
+            | ClockId::CLOCK_MONOTONIC_COARSE => Some(offsets.monotonic),
+            ClockId::CLOCK_BOOTTIME => Some(offsets.boottime),
+            _ => None,
+        }
+    }
+}
+
+/// Shifts the time by the nanoseconds, saturating at zero.
+fn shift_time(time: Duration, nanos: i128) -> Duration {
+    let nanos = time.as_nanos() as i128 + nanos;
+    if nanos <= 0 {
+        return Duration::ZERO;
+    }
+
+    Duration::new(
+        (nanos / NSEC_PER_SEC as i128) as u64,
+        (nanos % NSEC_PER_SEC as i128) as u32,
+    )
+}
+
+impl NsCommonOps for TimeNamespace {
+    const TYPE: NsType = NsType::Time;
+
+    fn owner_user_ns(&self) -> Option<&Arc<UserNamespace>> {
+        Some(&self.owner)
+    }
+
+    fn parent(&self) -> Result<&Arc<Self>> {
+        return_errno_with_message!(
+            Errno::EINVAL,
+            "a time namespace does not have a parent namespace"
+        );
+    }
+
+    fn stashed_dentry(&self) -> &StashedDentry {
+        &self.stashed_dentry
+    }
+}
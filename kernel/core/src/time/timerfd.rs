@@ -20,6 +20,7 @@ use crate::{
     syscall::create_timer,
     time::{
         Timer,
+        time_ns::TimeNamespace,
         timer::{Timeout, TimerGuard},
     },
 };
@@ -153,7 +154,9 @@ impl TimerfdFile {
             }
 
             let timeout = if flags.contains(TFDSetTimeFlags::TFD_TIMER_ABSTIME) {
-                Timeout::When(expire_time)
+                // The absolute time is observed in the time namespace of the caller.
+                let time_ns = TimeNamespace::current();
+                Timeout::When(time_ns.from_ns_timeout(&self.timer, expire_time))
             } else {
                 Timeout::After(expire_time)
             };
@@ -0,0 +1,360 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Classic BPF (cBPF) programs.
+//!
+//! A cBPF program is a sequence of instructions that run on a small virtual machine with an
+//! accumulator `A`, an index register `X`, and 16 words of scratch memory. The program can only
+//! jump forward, so it always terminates. Userspace supplies cBPF programs to filter packets
+//! received by sockets (i.e., `SO_ATTACH_FILTER`) and to filter syscalls (i.e., seccomp).
+//!
+//! Reference: <https://docs.kernel.org/networking/filter.html>
+
+use ostd::mm::VmIo;
+
+use crate::{context::current_userspace, prelude::*};
+
+/// `struct sock_filter` in Linux, which is a single cBPF instruction.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/filter.h#L24>
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(crate) struct CSockFilter {
+    code: u16,
+    jt: u8,
+    jf: u8,
+    k: u32,
+}
+
+/// `struct sock_fprog` in Linux, which refers to a cBPF program in userspace.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.9/source/include/uapi/linux/filter.h#L31>
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(crate) struct CSockFprog {
+    len: u16,
+    _pad: [u8; 6],
+    filter: u64,
+}
+
+/// The maximum number of instructions in a program (`BPF_MAXINSNS` in Linux).
+const MAX_INSNS: usize = 4096;
+
+/// The number of words in the scratch memory (`BPF_MEMWORDS` in Linux).
+const MEM_WORDS: usize = 16;
+
+// Instruction classes.
+const BPF_LD: u16 = 0x00;
+const BPF_LDX: u16 = 0x01;
+const BPF_ST: u16 = 0x02;
+const BPF_STX: u16 = 0x03;
+const BPF_ALU: u16 = 0x04;
+const BPF_JMP: u16 = 0x05;
+const BPF_RET: u16 = 0x06;
+const BPF_MISC: u16 = 0x07;
+
+// Sizes of `BPF_LD`.
+const BPF_W: u16 = 0x00;
+const BPF_H: u16 = 0x08;
+const BPF_B: u16 = 0x10;
+
+// Modes of `BPF_LD` and `BPF_LDX`.
+const BPF_IMM: u16 = 0x00;
+const BPF_ABS: u16 = 0x20;
+const BPF_IND: u16 = 0x40;
+const BPF_MEM: u16 = 0x60;
+const BPF_LEN: u16 = 0x80;
+const BPF_MSH: u16 = 0xa0;
+
+// Operations of `BPF_ALU`.
+const BPF_ADD: u16 = 0x00;
+const BPF_SUB: u16 = 0x10;
+const BPF_MUL: u16 = 0x20;
+const BPF_DIV: u16 = 0x30;
+const BPF_OR: u16 = 0x40;
+const BPF_AND: u16 = 0x50;
+const BPF_LSH: u16 = 0x60;
+const BPF_RSH: u16 = 0x70;
+const BPF_NEG: u16 = 0x80;
+const BPF_MOD: u16 = 0x90;
+const BPF_XOR: u16 = 0xa0;
+
+// Operations of `BPF_JMP`.
+const BPF_JA: u16 = 0x00;
+const BPF_JEQ: u16 = 0x10;
+const BPF_JGT: u16 = 0x20;
+const BPF_JGE: u16 = 0x30;
+const BPF_JSET: u16 = 0x40;
+
+// Sources of `BPF_ALU`, `BPF_JMP`, and `BPF_RET`.
+const BPF_K: u16 = 0x00;
+const BPF_X: u16 = 0x08;
+const BPF_A: u16 = 0x10;
+
+// Operations of `BPF_MISC`.
+const BPF_TAX: u16 = 0x00;
+const BPF_TXA: u16 = 0x80;
+
+/// The data that a cBPF program runs on.
+pub(crate) trait BpfData {
+    /// Returns the length of the data, which is loaded by `BPF_LEN`.
+    fn len(&self) -> u32;
+
+    /// Loads `size` (1, 2, or 4) bytes at `offset` as an integer.
+    ///
+    /// Packets are loaded in the network byte order, while structures (e.g., `struct
+    /// seccomp_data`) are loaded in the native byte order.
+    ///
+    /// The data may interpret some offsets specially (e.g., `SKF_AD_OFF` for socket filters).
+    /// If the method returns `None`, the program stops and returns zero.
+    fn load(&self, offset: u32, size: usize) -> Option<u32>;
+}
+
+/// A validated cBPF program.
+#[derive(Debug)]
+pub(crate) struct BpfProgram {
+    insns: Box<[CSockFilter]>,
+}
+
+impl BpfProgram {
+    /// Reads the program referred to by the `struct sock_fprog` at `addr` from userspace.
+    pub(crate) fn read_from_user(addr: Vaddr) -> Result<Self> {
+        let fprog = current_userspace!().read_val::<CSockFprog>(addr)?;
+
+        let len = fprog.len as usize;
+        if len == 0 || len > MAX_INSNS {
+            return_errno_with_message!(Errno::EINVAL, "the number of instructions is invalid");
+        }
+
+        let mut insns = Vec::with_capacity(len);
+        for i in 0..len {
+            let insn_addr = (fprog.filter as usize)
+                .checked_add(i * size_of::<CSockFilter>())
+                .ok_or(Errno::EFAULT)?;
+            insns.push(current_userspace!().read_val::<CSockFilter>(insn_addr)?);
+        }
+
+        Self::new(insns.into_boxed_slice())
+    }
+
+    /// Validates the instructions and creates a program.
+    ///
+    /// Like Linux, this method rejects unknown instructions, jumps that go out of the program,
+    /// and divisions or shifts that are guaranteed to be invalid. The last instruction must be a
+    /// return instruction, so that a valid program never runs out of instructions.
+    fn new(insns: Box<[CSockFilter]>) -> Result<Self> {
+        for (pc, insn) in insns.iter().enumerate() {
+            let k = insn.k as usize;
+            let remain = insns.len() - pc - 1;
+
+            let is_valid = match insn.code {
+                code if code == BPF_ALU | BPF_DIV | BPF_K || code == BPF_ALU | BPF_MOD | BPF_K => {
+                    insn.k != 0
+                }
+                code if code == BPF_ALU | BPF_LSH | BPF_K || code == BPF_ALU | BPF_RSH | BPF_K => {
+                    insn.k < 32
+                }
+                code if is_alu(code) => true,
+                code if code == BPF_LD | BPF_W | BPF_MEM
+                    || code == BPF_LDX | BPF_W | BPF_MEM
+                    || code == BPF_ST
+                    || code == BPF_STX =>
+                {
+                    k < MEM_WORDS
+                }
+                code if is_load(code) => true,
+                code if code == BPF_JMP | BPF_JA => k < remain,
+                code if is_cond_jump(code) => {
+                    (insn.jt as usize) < remain && (insn.jf as usize) < remain
+                }
+                code if code == BPF_RET | BPF_K || code == BPF_RET | BPF_A => true,
+                code if code == BPF_MISC | BPF_TAX || code == BPF_MISC | BPF_TXA => true,
+                _ => false,
+            };
+            if !is_valid {
+                return_errno_with_message!(Errno::EINVAL, "the instruction is invalid");
+            }
+        }
+
+        let last_code = insns.last().unwrap().code;
+        if last_code != BPF_RET | BPF_K && last_code != BPF_RET | BPF_A {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the program does not end with a return instruction"
+            );
+        }
+
+        Ok(Self { insns })
+    }
+
+    /// Returns the number of instructions in the program.
+    pub(crate) fn num_insns(&self) -> usize {
+        self.insns.len()
+    }
+
+    /// Checks that the program only loads the data with 32-bit absolute loads at aligned offsets
+    /// within `data_len` bytes.
+    ///
+    /// This is required if the data is a structure rather than a packet. Like Linux, indirect
+    /// loads and loads of bytes or half-words are rejected.
+    pub(crate) fn check_word_loads(&self, data_len: u32) -> Result<()> {
+        for insn in self.insns.iter() {
+            if insn.code & 0x07 != BPF_LD && insn.code & 0x07 != BPF_LDX {
+                continue;
+            }
+
+            let is_valid = match insn.code & 0xe0 {
+                BPF_ABS => {
+                    insn.code & 0x18 == BPF_W && insn.k % 4 == 0 && insn.k < data_len
+                }
+                BPF_IND | BPF_MSH => false,
+                _ => true,
+            };
+            if !is_valid {
+                return_errno_with_message!(Errno::EINVAL, "the load instruction is invalid");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Runs the program on the data and returns the result.
+    pub(crate) fn run(&self, data: &dyn BpfData) -> u32 {
+        let mut a: u32 = 0;
+        let mut x: u32 = 0;
+        let mut mem = [0u32; MEM_WORDS];
+        let mut pc = 0;
+
+        loop {
+            let insn = &self.insns[pc];
+            let k = insn.k;
+            pc += 1;
+
+            let class = insn.code & 0x07;
+            match class {
+                BPF_LD | BPF_LDX => {
+                    let size = match insn.code & 0x18 {
+                        BPF_W => 4,
+                        BPF_H => 2,
+                        _ => 1,
+                    };
+                    let val = match insn.code & 0xe0 {
+                        BPF_IMM => k,
+                        BPF_MEM => mem[k as usize],
+                        BPF_LEN => data.len(),
+                        BPF_ABS => match data.load(k, size) {
+                            Some(val) => val,
+                            None => return 0,
+                        },
+                        BPF_IND => match data.load(x.wrapping_add(k), size) {
+                            Some(val) => val,
+                            None => return 0,
+                        },
+                        BPF_MSH => match data.load(k, 1) {
+                            Some(val) => (val & 0xf) * 4,
+                            None => return 0,
+                        },
+                        _ => unreachable!(),
+                    };
+                    if class == BPF_LD {
+                        a = val;
+                    } else {
+                        x = val;
+                    }
+                }
+                BPF_ST => mem[k as usize] = a,
+                BPF_STX => mem[k as usize] = x,
+                BPF_ALU => {
+                    let src = if insn.code & BPF_X != 0 { x } else { k };
+                    a = match insn.code & 0xf0 {
+                        BPF_ADD => a.wrapping_add(src),
+                        BPF_SUB => a.wrapping_sub(src),
+                        BPF_MUL => a.wrapping_mul(src),
+                        BPF_DIV => match a.checked_div(src) {
+                            Some(val) => val,
+                            None => return 0,
+                        },
+                        BPF_MOD => match a.checked_rem(src) {
+                            Some(val) => val,
+                            None => return 0,
+                        },
+                        BPF_OR => a | src,
+                        BPF_AND => a & src,
+                        BPF_XOR => a ^ src,
+                        BPF_LSH => a.checked_shl(src).unwrap_or(0),
+                        BPF_RSH => a.checked_shr(src).unwrap_or(0),
+                        BPF_NEG => a.wrapping_neg(),
+                        _ => unreachable!(),
+                    };
+                }
+                BPF_JMP => {
+                    let src = if insn.code & BPF_X != 0 { x } else { k };
+                    let is_taken = match insn.code & 0xf0 {
+                        BPF_JA => {
+                            pc += k as usize;
+                            continue;
+                        }
+                        BPF_JEQ => a == src,
+                        BPF_JGT => a > src,
+                        BPF_JGE => a >= src,
+                        BPF_JSET => (a & src) != 0,
+                        _ => unreachable!(),
+                    };
+                    let offset = if is_taken { insn.jt } else { insn.jf };
+                    pc += offset as usize;
+                }
+                BPF_RET => {
+                    return if insn.code & BPF_A != 0 { a } else { k };
+                }
+                BPF_MISC => {
+                    if insn.code & BPF_TXA != 0 {
+                        a = x;
+                    } else {
+                        x = a;
+                    }
+                }
+                _ => unreachable!(),
+            }
+        }
+    }
+}
+
+fn is_alu(code: u16) -> bool {
+    if code & 0x07 != BPF_ALU || code & !0xff != 0 {
+        return false;
+    }
+
+    match code & 0xf0 {
+        BPF_ADD | BPF_SUB | BPF_MUL | BPF_DIV | BPF_OR | BPF_AND | BPF_LSH | BPF_RSH | BPF_MOD
+        | BPF_XOR => true,
+        BPF_NEG => code & BPF_X == 0,
+        _ => false,
+    }
+}
+
+fn is_load(code: u16) -> bool {
+    const LOADS: [u16; 13] = [
+        BPF_LD | BPF_W | BPF_IMM,
+        BPF_LD | BPF_W | BPF_ABS,
+        BPF_LD | BPF_H | BPF_ABS,
+        BPF_LD | BPF_B | BPF_ABS,
+        BPF_LD | BPF_W | BPF_IND,
+        BPF_LD | BPF_H | BPF_IND,
+        BPF_LD | BPF_B | BPF_IND,
+        BPF_LD | BPF_W | BPF_MEM,
+        BPF_LD | BPF_W | BPF_LEN,
+        BPF_LDX | BPF_W | BPF_IMM,
+        BPF_LDX | BPF_W | BPF_MEM,
+        BPF_LDX | BPF_W | BPF_LEN,
+        BPF_LDX | BPF_B | BPF_MSH,
+    ];
+
+    LOADS.contains(&code)
+}
+
+fn is_cond_jump(code: u16) -> bool {
+    if code & 0x07 != BPF_JMP || code & !0xff != 0 {
+        return false;
+    }
+
+    matches!(code & 0xf0, BPF_JEQ | BPF_JGT | BPF_JGE | BPF_JSET)
+}
@@ -1,5 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
+pub(crate) mod bpf;
 mod copy_compact;
 pub(crate) mod ioctl;
 mod iovec;
@@ -9,5 +10,5 @@ mod read_cstring;
 pub(crate) mod ring_buffer;
 
 pub(crate) use copy_compact::CopyCompat;
-pub(crate) use iovec::{MultiRead, MultiWrite, VmReaderArray, VmWriterArray};
+pub(crate) use iovec::{MultiRead, MultiWrite, UserIoVec, VmReaderArray, VmWriterArray};
 pub(crate) use read_cstring::ReadCString;
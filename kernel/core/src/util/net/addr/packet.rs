@@ -0,0 +1,65 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::mem::offset_of;
+
+use super::family::CSocketAddrFamily;
+use crate::{
+    net::socket::packet::{MAX_HARDWARE_ADDR_LEN, PacketSocketAddr, PacketType},
+    prelude::*,
+};
+
+/// Packet socket address.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+pub(super) struct CSocketAddrLinkLayer {
+    /// Address family (AF_PACKET).
+    sll_family: u16,
+    /// Link-layer protocol in network byte order.
+    sll_protocol: u16,
+    /// Interface index.
+    sll_ifindex: i32,
+    /// ARP hardware type.
+    sll_hatype: u16,
+    /// Packet type.
+    sll_pkttype: u8,
+    /// Length of the link-layer address.
+    sll_halen: u8,
+    /// Link-layer address.
+    sll_addr: [u8; MAX_HARDWARE_ADDR_LEN],
+}
+
+impl CSocketAddrLinkLayer {
+    /// Returns the minimum length of the C structure to contain the link-layer address.
+    pub(super) fn min_len(&self) -> usize {
+        offset_of!(Self, sll_addr) + self.sll_halen as usize
+    }
+}
+
+impl From<PacketSocketAddr> for CSocketAddrLinkLayer {
+    fn from(value: PacketSocketAddr) -> Self {
+        Self {
+            sll_family: CSocketAddrFamily::AF_PACKET as u16,
+            sll_protocol: value.protocol.to_be(),
+            sll_ifindex: value.ifindex as i32,
+            sll_hatype: value.hatype,
+            sll_pkttype: value.pkttype as u8,
+            sll_halen: value.halen,
+            sll_addr: value.addr,
+        }
+    }
+}
+
+impl From<CSocketAddrLinkLayer> for PacketSocketAddr {
+    fn from(value: CSocketAddrLinkLayer) -> Self {
+        debug_assert_eq!(value.sll_family, CSocketAddrFamily::AF_PACKET as u16);
+        Self {
+            protocol: u16::from_be(value.sll_protocol),
+            ifindex: value.sll_ifindex as u32,
+            hatype: value.sll_hatype,
+            // The packet type is ignored when sending packets.
+            pkttype: PacketType::try_from(value.sll_pkttype).unwrap_or_default(),
+            halen: value.sll_halen.min(MAX_HARDWARE_ADDR_LEN as u8),
+            addr: value.sll_addr,
+        }
+    }
+}
@@ -8,12 +8,16 @@ use crate::{
     context::current_userspace,
     net::socket::{
         ip::{options::IpTtl, stream_options::CongestionControl},
+        packet::{PacketMreq, PacketStats},
         unix::CUserCred,
         util::{LingerOption, SocketTimeout},
     },
     prelude::*,
     time::timeval_t,
-    util::net::SockType,
+    util::{
+        bpf::{BpfProgram, CSockFprog},
+        net::SockType,
+    },
 };
 
 /// Create an object by reading its C counterpart from the user space.
@@ -279,3 +283,37 @@ impl WriteToUser for CUserCred {
         Ok(write_len)
     }
 }
+
+impl ReadFromUser for Arc<BpfProgram> {
+    fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
+        if (max_len as usize) < size_of::<CSockFprog>() {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        Ok(Arc::new(BpfProgram::read_from_user(addr)?))
+    }
+}
+
+impl ReadFromUser for PacketMreq {
+    fn read_from_user(addr: Vaddr, max_len: u32) -> Result<Self> {
+        if (max_len as usize) < size_of::<PacketMreq>() {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        Ok(current_userspace!().read_val::<PacketMreq>(addr)?)
+    }
+}
+
+impl WriteToUser for PacketStats {
+    fn write_to_user(&self, addr: Vaddr, max_len: u32) -> Result<usize> {
+        let write_len = size_of::<PacketStats>();
+
+        if (max_len as usize) < write_len {
+            return_errno_with_message!(Errno::EINVAL, "max_len is too short");
+        }
+
+        current_userspace!().write_val(addr, self)?;
+
+        Ok(write_len)
+    }
+}
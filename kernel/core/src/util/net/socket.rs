@@ -31,6 +31,7 @@ pub(crate) enum Protocol {
     IPPROTO_GRE = 47,       /* Cisco GRE tunnels (rfc 1701,1702)	*/
     IPPROTO_ESP = 50,       /* Encapsulation Security Payload protocol */
     IPPROTO_AH = 51,        /* Authentication Header protocol	*/
+    IPPROTO_ICMPV6 = 58,    /* ICMPv6				*/
     IPPROTO_MTP = 92,       /* Multicast Transport Protocol		*/
     IPPROTO_BEETPH = 94,    /* IP option pseudo header for BEET	*/
     IPPROTO_ENCAP = 98,     /* Encapsulation Header			*/
@@ -26,10 +26,12 @@ use ostd_pod::IntoBytes;
 use spin::Once;
 
 use crate::{
+    prelude::Result,
     syscall::ClockId,
     time::{
         START_TIME, SystemTime,
         clocks::MonotonicClock,
+        time_ns::{TimeNsOffset, TimeNsOffsets},
         timer::{Timeout, TimerGuard},
     },
     vm::page_cache::{Vmo, VmoMapMode, VmoOptions},
@@ -43,9 +45,14 @@ static START_SECS_COUNT: Once<u64> = Once::new();
 static VDSO: Once<Arc<Vdso>> = Once::new();
 
 #[derive(Clone, Copy, Debug)]
+#[repr(i32)]
 enum VdsoClockMode {
     None = 0,
     Tsc = 1,
+    /// The vDSO data belongs to a time namespace and contains the clock offsets.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.2.10/source/include/vdso/clocksource.h#L20>
+    TimeNs = i32::MAX,
 }
 
 /// An instant used in [`VdsoData`]
@@ -160,6 +167,19 @@ impl VdsoData {
         self.basetime[clockid].nanos_info = nanos_info;
     }
 
+    /// Stores the clock offset of a time namespace.
+    ///
+    /// In the vDSO data of a time namespace, the instants are reinterpreted as the clock offsets.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.2.10/source/include/vdso/datapage.h#L99>.
+    fn update_time_ns_offset(&mut self, clockid: ClockId, offset: TimeNsOffset) {
+        self.update_clock_instant(
+            clockid as usize,
+            offset.secs() as u64,
+            offset.nanos() as u64,
+        );
+    }
+
     fn update_high_res_instant(&mut self, instant: Instant, instant_cycles: u64) {
         self.last_cycles = instant_cycles;
         for clock_id in HIGH_RES_CLOCK_IDS {
@@ -395,6 +415,53 @@ pub(crate) fn vdso_vmo() -> Option<Arc<Vmo>> {
     VDSO.get().map(|vdso| vdso.vmo.clone())
 }
 
+/// Creates a vDSO VMO for a time namespace with the clock offsets.
+///
+/// Like Linux, the data segment of the new VMO contains the vDSO data of the time namespace,
+/// which tells the vDSO library to read the real vDSO data at the time namespace data segment and
+/// then apply the clock offsets. The real vDSO data and the library text are shared with the vDSO
+/// VMO returned by [`vdso_vmo`].
+///
+/// This function will return `None` if vDSO does not exist (e.g., if it has not been initialized).
+pub(crate) fn new_time_ns_vdso_vmo(offsets: &TimeNsOffsets) -> Result<Option<Arc<Vmo>>> {
+    let Some(vdso) = VDSO.get() else {
+        return Ok(None);
+    };
+
+    let mut time_ns_data = VdsoData::empty();
+    // An odd sequence number makes the vDSO library check the clock mode before reading.
+    time_ns_data.seq = 1;
+    time_ns_data.set_clock_mode(VdsoClockMode::TimeNs);
+    let monotonic_clock_ids = [
+        ClockId::CLOCK_MONOTONIC,
+        ClockId::CLOCK_MONOTONIC_RAW,
+        ClockId::CLOCK_MONOTONIC_COARSE,
+    ];
+    for clock_id in monotonic_clock_ids {
+        time_ns_data.update_time_ns_offset(clock_id, offsets.monotonic());
+    }
+    time_ns_data.update_time_ns_offset(ClockId::CLOCK_BOOTTIME, offsets.boottime());
+
+    let vmo = VmoOptions::new(VDSO_VMO_LAYOUT.size).alloc()?;
+    let mut reader = VmReader::from(time_ns_data.as_bytes()).to_fallible();
+    vmo.write(VDSO_VMO_LAYOUT.data_offset, &mut reader)?;
+
+    vmo.share_anon_pages(
+        &vdso.vmo,
+        VDSO_VMO_LAYOUT.data_segment_offset,
+        VDSO_VMO_LAYOUT.time_ns_data_segment_offset,
+        VDSO_VMO_LAYOUT.data_segment_size,
+    )?;
+    vmo.share_anon_pages(
+        &vdso.vmo,
+        VDSO_VMO_LAYOUT.text_segment_offset,
+        VDSO_VMO_LAYOUT.text_segment_offset,
+        VDSO_VMO_LAYOUT.text_segment_size,
+    )?;
+
+    Ok(Some(vmo))
+}
+
 #[cfg(target_arch = "x86_64")]
 pub(crate) const VDSO_VMO_LAYOUT: VdsoVmoLayout = VdsoVmoLayout {
     // https://elixir.bootlin.com/linux/v6.2.10/source/arch/x86/entry/vdso/vdso-layout.lds.S#L20
@@ -403,6 +470,8 @@ pub(crate) const VDSO_VMO_LAYOUT: VdsoVmoLayout = VdsoVmoLayout {
     // https://elixir.bootlin.com/linux/v6.2.10/source/arch/x86/entry/vdso/vdso-layout.lds.S#L19
     text_segment_offset: 4 * PAGE_SIZE,
     text_segment_size: PAGE_SIZE,
+    // https://elixir.bootlin.com/linux/v6.2.10/source/arch/x86/entry/vdso/vdso-layout.lds.S
+    time_ns_data_segment_offset: 3 * PAGE_SIZE,
     // https://elixir.bootlin.com/linux/v6.2.10/source/arch/x86/include/asm/vvar.h#L51
     data_offset: 0x80,
 
@@ -417,6 +486,8 @@ pub(crate) const VDSO_VMO_LAYOUT: VdsoVmoLayout = VdsoVmoLayout {
     // https://elixir.bootlin.com/linux/v6.2.10/source/arch/riscv/kernel/vdso.c#L256
     text_segment_offset: 2 * PAGE_SIZE,
     text_segment_size: PAGE_SIZE,
+    // https://elixir.bootlin.com/linux/v6.2.10/source/arch/riscv/kernel/vdso.c
+    time_ns_data_segment_offset: PAGE_SIZE,
     // https://elixir.bootlin.com/linux/v6.2.10/source/arch/riscv/kernel/vdso.c#L47
     data_offset: 0,
 
@@ -428,6 +499,9 @@ pub(crate) struct VdsoVmoLayout {
     pub data_segment_size: usize,
     pub text_segment_offset: usize,
     pub text_segment_size: usize,
+    /// The offset of the segment where the real vDSO data is mapped in the vDSO VMO of a time
+    /// namespace.
+    pub time_ns_data_segment_offset: usize,
     pub data_offset: usize,
     pub size: usize,
 }
@@ -444,6 +518,11 @@ const_assert!(
         .is_multiple_of(PAGE_SIZE)
 );
 const_assert!(VDSO_VMO_LAYOUT.text_segment_size.is_multiple_of(PAGE_SIZE));
+const_assert!(
+    VDSO_VMO_LAYOUT
+        .time_ns_data_segment_offset
+        .is_multiple_of(PAGE_SIZE)
+);
 const_assert!(VDSO_VMO_LAYOUT.size.is_multiple_of(PAGE_SIZE));
 
 // Ensure that the vDSO data at `VDSO_VMO_LAYOUT.data_offset` is in the data segment.
@@ -0,0 +1,46 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Anonymous pages.
+//!
+//! Anonymous pages are the private pages of processes, which are not backed by
+//! files. They are charged to the memory cgroups of the processes that
+//! allocate them.
+
+use ostd::{
+    impl_untyped_frame_meta_for,
+    mm::{Frame, FrameAllocOptions},
+};
+
+use crate::{
+    fs::cgroupfs::{MemoryCharge, MemoryKind, try_charge_memory},
+    prelude::*,
+};
+
+/// An anonymous page.
+pub(crate) type AnonPage = Frame<AnonPageMeta>;
+
+/// Metadata for an anonymous page.
+#[derive(Debug)]
+pub(crate) struct AnonPageMeta {
+    /// The charge to the memory cgroup, which is released when the page is freed.
+    _charge: Option<MemoryCharge>,
+}
+
+impl_untyped_frame_meta_for!(AnonPageMeta);
+
+/// Allocates an anonymous page for the current process.
+///
+/// This function never blocks. It fails with [`Errno::ENOMEM`] if the frame
+/// allocator is exhausted or the memory cgroup of the current process reaches
+/// its limit. In both cases, the caller may reclaim memory with
+/// [`super::reclaim::reclaim_memory_for_current`] and retry.
+pub(crate) fn alloc_anon_page(zeroed: bool) -> Result<AnonPage> {
+    let charge = try_charge_memory(MemoryKind::Anon)
+        .map_err(|_| Error::with_message(Errno::ENOMEM, "the memory cgroup reaches its limit"))?;
+    let meta = AnonPageMeta { _charge: charge };
+
+    let page = FrameAllocOptions::new()
+        .zeroed(zeroed)
+        .alloc_frame_with(meta)?;
+    Ok(page)
+}
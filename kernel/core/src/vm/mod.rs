@@ -12,8 +12,13 @@
 use osdk_frame_allocator::FrameAllocator;
 use osdk_heap_allocator::{HeapAllocator, type_from_layout};
 
+pub(crate) mod anon_page;
+pub(crate) mod oom;
 pub(crate) mod page_cache;
 pub(crate) mod perms;
+pub(crate) mod reclaim;
+pub(crate) mod swap;
+pub(crate) mod userfaultfd;
 pub(crate) mod vmar;
 
 #[ostd::global_frame_allocator]
@@ -27,6 +32,14 @@ const fn slot_type_from_layout(layout: core::alloc::Layout) -> Option<ostd::mm::
     type_from_layout(layout)
 }
 
+pub(super) fn init() {
+    swap::init();
+}
+
+pub(super) fn init_in_first_kthread() {
+    reclaim::init_in_first_kthread();
+}
+
 /// Total physical memory in the entire system in bytes.
 pub(crate) fn mem_total() -> usize {
     use ostd::boot::{boot_info, memory_region::MemoryRegionType};
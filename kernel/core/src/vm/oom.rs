@@ -0,0 +1,252 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The out-of-memory (OOM) killer.
+//!
+//! When no memory can be reclaimed, the OOM killer kills a process to free
+//! memory. The victim is the process with the highest badness, which is
+//! roughly the number of pages that will be freed by killing the process,
+//! adjusted by the process's `oom_score_adj`.
+//!
+//! If a memory cgroup runs out of memory, the victim is chosen among the
+//! processes in the cgroup.
+
+use core::sync::atomic::Ordering;
+
+use crate::{
+    fs::cgroupfs::{MemCgroup, MemoryEvent},
+    prelude::*,
+    process::{
+        Process, pid_table,
+        posix_thread::AsPosixThread,
+        signal::{constants::SIGKILL, signals::kernel::KernelSignal},
+    },
+    vm::{
+        swap,
+        vmar::{RssType, Vmar},
+    },
+};
+
+/// The minimum value of `oom_score_adj`, which prevents the process from being
+/// killed by the OOM killer.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/oom.h#L9>
+pub(crate) const OOM_SCORE_ADJ_MIN: i16 = -1000;
+/// The maximum value of `oom_score_adj`.
+pub(crate) const OOM_SCORE_ADJ_MAX: i16 = 1000;
+
+/// The last process killed by the OOM killer.
+static OOM_VICTIM: SpinLock<Weak<Process>> = SpinLock::new(Weak::new());
+
+/// Returns the OOM score of the process, as shown in `/proc/[pid]/oom_score`.
+///
+/// The score ranges from 0 to 2000. A process with a higher score is more
+/// likely to be killed by the OOM killer.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/proc/base.c#L562>
+pub(crate) fn oom_score(process: &Process) -> usize {
+    let Some(usage) = process.lock_vmar().as_ref().map(MemUsage::of) else {
+        return 0;
+    };
+
+    let total_pages = total_pages() as isize;
+    let Some(badness) = badness(process, &usage) else {
+        return 0;
+    };
+
+    ((1000 + badness * 1000 / total_pages) * 2 / 3).max(0) as usize
+}
+
+/// Kills a process to free memory for an allocation of `nr_pages` pages.
+///
+/// If `memcg` is specified, the victim is chosen among the processes in the
+/// memory cgroup.
+///
+/// Returns `true` if some memory is going to be freed by a killed process.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/mm/oom_kill.c#L1105>
+pub(super) fn out_of_memory(nr_pages: usize, memcg: Option<&MemCgroup>) -> bool {
+    // Do not kill another process until the last victim frees its memory.
+    if has_pending_victim() {
+        return true;
+    }
+
+    if let Some(memcg) = memcg {
+        memcg.record_event(MemoryEvent::Oom);
+    }
+
+    // The caller may hold arbitrary locks, so never block on the locks here.
+    let Some(pid_table) = pid_table::try_pid_table_mut() else {
+        return false;
+    };
+    let processes = pid_table.iter_processes().collect::<Vec<_>>();
+    drop(pid_table);
+
+    let candidates = processes
+        .into_iter()
+        .filter(|process| !process.status().is_zombie())
+        .filter(|process| memcg.is_none_or(|memcg| memcg.contains_process(process)))
+        .filter_map(|process| {
+            let usage = process.try_lock_vmar()?.as_ref().map(MemUsage::of)?;
+            Some((process, usage))
+        })
+        .collect::<Vec<_>>();
+
+    let Some((victim, victim_usage)) = candidates
+        .iter()
+        .filter_map(|(process, usage)| Some((badness(process, usage)?, process, usage)))
+        .max_by_key(|(badness, _, _)| *badness)
+        .map(|(_, process, usage)| (process, usage))
+    else {
+        error!("Out of memory and no killable processes");
+        return false;
+    };
+
+    dump_header(nr_pages, memcg, &candidates);
+
+    error!(
+        "{}: Killed process {} ({}) total-vm:{}kB, anon-rss:{}kB, file-rss:{}kB, UID:{} oom_score_adj:{}",
+        if memcg.is_some() {
+            "Memory cgroup out of memory"
+        } else {
+            "Out of memory"
+        },
+        victim.pid(),
+        process_name(victim),
+        victim_usage.total_vm * (PAGE_SIZE / 1024),
+        victim_usage.rss_anon * (PAGE_SIZE / 1024),
+        victim_usage.rss_file * (PAGE_SIZE / 1024),
+        process_uid(victim),
+        victim.oom_score_adj().load(Ordering::Relaxed),
+    );
+
+    victim.enqueue_signal(Box::new(KernelSignal::new(SIGKILL)));
+    *OOM_VICTIM.lock() = Arc::downgrade(victim);
+    if let Some(memcg) = memcg {
+        memcg.record_event(MemoryEvent::OomKill);
+    }
+
+    true
+}
+
+/// Returns whether the last process killed by the OOM killer has not exited yet.
+pub(super) fn has_pending_victim() -> bool {
+    let last_victim = OOM_VICTIM.lock().upgrade();
+    last_victim.is_some_and(|process| !process.status().is_zombie())
+}
+
+/// Prints the information about the OOM killing and the memory usage of the
+/// processes.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/mm/oom_kill.c#L462>
+fn dump_header(
+    nr_pages: usize,
+    memcg: Option<&MemCgroup>,
+    candidates: &[(Arc<Process>, MemUsage)],
+) {
+    let (name, oom_score_adj) = match Process::current() {
+        Some(process) => (
+            process_name(&process),
+            process.oom_score_adj().load(Ordering::Relaxed),
+        ),
+        None => (String::from("kernel"), 0),
+    };
+    warn!(
+        "{} invoked oom-killer: order={}, oom_score_adj={}",
+        name,
+        nr_pages.next_power_of_two().ilog2(),
+        oom_score_adj,
+    );
+
+    if let Some(memcg) = memcg {
+        info!(
+            "memory: usage {}kB, limit {}kB",
+            memcg.nr_used_pages() * (PAGE_SIZE / 1024),
+            memcg.nr_max_pages().saturating_mul(PAGE_SIZE / 1024),
+        );
+    }
+    info!(
+        "Mem-Info: free:{} cached:{} swap_total:{} swap_free:{}",
+        osdk_frame_allocator::load_total_free_size() / PAGE_SIZE,
+        super::page_cache::nr_cached_pages(),
+        swap::nr_total_pages(),
+        swap::nr_total_pages() - swap::nr_used_pages(),
+    );
+
+    info!("Tasks state (memory values in pages):");
+    info!("[  pid  ]   uid  tgid total_vm      rss rss_anon rss_file swapents oom_score_adj name");
+    for (process, usage) in candidates {
+        info!(
+            "[{:>7}] {:>5} {:>5} {:>8} {:>8} {:>8} {:>8} {:>8}         {:>5} {}",
+            process.pid(),
+            process_uid(process),
+            process.pid(),
+            usage.total_vm,
+            usage.rss_anon + usage.rss_file,
+            usage.rss_anon,
+            usage.rss_file,
+            usage.swap_ents,
+            process.oom_score_adj().load(Ordering::Relaxed),
+            process_name(process),
+        );
+    }
+}
+
+/// The memory usage of a process in pages.
+struct MemUsage {
+    total_vm: usize,
+    rss_anon: usize,
+    rss_file: usize,
+    swap_ents: usize,
+}
+
+impl MemUsage {
+    fn of(vmar: &Vmar) -> Self {
+        Self {
+            total_vm: vmar.get_mappings_total_size() / PAGE_SIZE,
+            rss_anon: vmar.get_rss_counter(RssType::Anon),
+            rss_file: vmar.get_rss_counter(RssType::File),
+            swap_ents: vmar.get_rss_counter(RssType::Swap),
+        }
+    }
+}
+
+/// Computes the badness of a process.
+///
+/// Returns `None` if the process must not be killed.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/mm/oom_kill.c#L202>
+fn badness(process: &Process, usage: &MemUsage) -> Option<isize> {
+    let oom_score_adj = process.oom_score_adj().load(Ordering::Relaxed);
+    if oom_score_adj == OOM_SCORE_ADJ_MIN || process.is_init_process() {
+        return None;
+    }
+
+    let points = (usage.rss_anon + usage.rss_file + usage.swap_ents) as isize;
+    // Each unit of `oom_score_adj` is worth 0.1% of the total memory.
+    let adj = oom_score_adj as isize * (total_pages() / 1000) as isize;
+
+    Some(points + adj)
+}
+
+/// Returns the total number of pages of the RAM and the swap areas.
+fn total_pages() -> usize {
+    super::mem_total() / PAGE_SIZE + swap::nr_total_pages()
+}
+
+fn process_name(process: &Process) -> String {
+    process
+        .main_thread()
+        .as_posix_thread()
+        .unwrap()
+        .thread_name()
+        .lock()
+        .as_cstr()
+        .to_string_lossy()
+        .into_owned()
+}
+
+fn process_uid(process: &Process) -> u32 {
+    let main_thread = process.main_thread();
+    let credentials = main_thread.as_posix_thread().unwrap().credentials();
+    u32::from(credentials.ruid())
+}
@@ -13,7 +13,11 @@ use ostd::{
     sync::WaitQueue,
 };
 
-use crate::prelude::*;
+use crate::{
+    fs::cgroupfs::{MemCgroup, MemoryCharge, MemoryKind, try_charge_memory},
+    prelude::*,
+    vm::reclaim,
+};
 
 /// The state of a page in the page cache.
 #[repr(u8)]
@@ -88,6 +92,8 @@ pub(crate) struct CachePageMeta {
     /// cleared without the page lock only from the BIO completion callback after
     /// the VMO writeback path has handed off the writeback state.
     is_writing_back: AtomicBool,
+    /// The charge to the memory cgroup, which is released when the page is freed.
+    charge: Option<MemoryCharge>,
     // TODO: Add a reverse mapping from the page to VMO for eviction.
 }
 
@@ -97,10 +103,20 @@ impl Default for CachePageMeta {
             state: AtomicPageState::new(PageState::Uninit),
             lock: AtomicBool::new(false),
             is_writing_back: AtomicBool::new(false),
+            charge: None,
         }
     }
 }
 
+impl CachePageMeta {
+    /// Returns whether the page is charged to the memory cgroup or one of its descendants.
+    pub(super) fn is_charged_to(&self, memcg: &MemCgroup) -> bool {
+        self.charge
+            .as_ref()
+            .is_some_and(|charge| charge.is_charged_to(memcg))
+    }
+}
+
 impl_untyped_frame_meta_for!(CachePageMeta);
 
 /// Convenience operations on a [`CachePage`] handle.
@@ -115,7 +131,6 @@ pub(crate) trait CachePageExt: Sized {
     fn wait_queue(&self) -> &'static WaitQueue;
 
     /// Tries to lock the cache page.
-    #[expect(dead_code)]
     fn try_lock(self) -> Option<LockedCachePage>;
 
     /// Tries to lock the cache page by reference.
@@ -152,8 +167,13 @@ pub(crate) trait CachePageExt: Sized {
     }
 
     /// Allocates a new cache page which content and state are uninitialized.
+    ///
+    /// This method may sleep to reclaim memory.
     fn alloc_uninit() -> Result<CachePage> {
-        let meta = CachePageMeta::default();
+        let meta = CachePageMeta {
+            charge: charge_memory()?,
+            ..Default::default()
+        };
         let page = FrameAllocOptions::new()
             .zeroed(false)
             .alloc_frame_with(meta)?;
@@ -161,9 +181,12 @@ pub(crate) trait CachePageExt: Sized {
     }
 
     /// Allocates a new zeroed cache page with the up-to-date state.
+    ///
+    /// This method may sleep to reclaim memory.
     fn alloc_zero() -> Result<CachePage> {
         let meta = CachePageMeta {
             state: AtomicPageState::new(PageState::UpToDate),
+            charge: charge_memory()?,
             ..Default::default()
         };
         let page = FrameAllocOptions::new()
@@ -384,3 +407,29 @@ fn lock_page(page: &CachePage) -> &'static WaitQueue {
     });
     wait_queue
 }
+
+/// Charges a new cache page to the memory cgroup of the current process.
+///
+/// If the memory cgroup reaches its `memory.max` limit, memory is reclaimed from the cgroup and
+/// the charge is retried. If no memory can be reclaimed, the OOM killer kills a process in the
+/// cgroup. So this function may sleep and must not be called in the atomic mode.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/mm/memcontrol.c>
+fn charge_memory() -> Result<Option<MemoryCharge>> {
+    /// The maximum number of retries after reclaiming memory.
+    ///
+    /// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/mm/internal.h>
+    const MAX_RECLAIM_RETRIES: usize = 16;
+
+    for _ in 0..MAX_RECLAIM_RETRIES {
+        if let Ok(charge) = try_charge_memory(MemoryKind::File) {
+            return Ok(charge);
+        }
+        if !reclaim::reclaim_memory_for_current(1) {
+            break;
+        }
+    }
+
+    try_charge_memory(MemoryKind::File)
+        .map_err(|_| Error::with_message(Errno::ENOMEM, "the memory cgroup reaches its limit"))
+}
@@ -91,9 +91,10 @@
 //!    [`PageCache`] operations do not yet take `&mut self`).
 //!
 //!  - In this table, `flush` and `evict` are assumed to be able to be called
-//!    without holding the inode lock (e.g., under memory pressure). But as we
-//!    have not implemented any related mechanisms, they can currently only be
-//!    called from the filesystem while holding at least the inode read lock.
+//!    without holding the inode lock (e.g., under memory pressure). The memory
+//!    reclaim (see [`reclaim_clean_pages`] and [`write_back_dirty_pages`])
+//!    does so. Other callers still call them from the filesystem while holding
+//!    at least the inode read lock.
 //!
 //!  - For interactions between page faults and page cache management, the
 //!    correct order of multiple locks is important to ensure that the cached
@@ -105,7 +106,7 @@
 
 use core::{
     ops::{Deref, Range},
-    sync::atomic::Ordering,
+    sync::atomic::{AtomicUsize, Ordering},
 };
 
 use align_ext::AlignExt;
@@ -113,7 +114,7 @@ use aster_block::bio::{BioCompleteFn, BioDirection, BioSegment, BioStatus};
 use io_util::batch::IoBatch;
 use ostd::mm::{Segment, VmIo, VmIoFill, io::util::HasVmReaderWriter};
 
-use crate::prelude::*;
+use crate::{fs::cgroupfs::MemCgroup, prelude::*};
 
 mod cache_page;
 #[cfg(ktest)]
@@ -164,7 +165,9 @@ impl PageCache {
         size: usize,
         backend: Weak<dyn PageCacheBackend>,
     ) -> Result<Self> {
-        Ok(Self(VmoOptions::new_page_cache(size, backend).alloc()?))
+        let vmo = VmoOptions::new_page_cache(size, backend).alloc()?;
+        register_backed_vmo(&vmo);
+        Ok(Self(vmo))
     }
 
     /// Creates an anonymous page cache with the specified initial capacity in
@@ -355,6 +358,89 @@ impl VmIo for PageCache {
     }
 }
 
+/// The VMOs of all page caches with backends.
+///
+/// Their pages can be written back and evicted to reclaim memory.
+static BACKED_VMOS: SpinLock<Vec<Weak<Vmo>>> = SpinLock::new(Vec::new());
+
+/// The index in [`BACKED_VMOS`] to start the next memory reclaim from.
+static NEXT_RECLAIM_IDX: AtomicUsize = AtomicUsize::new(0);
+
+fn register_backed_vmo(vmo: &Arc<Vmo>) {
+    let mut vmos = BACKED_VMOS.lock();
+    // Remove the VMOs of dropped page caches before the vector grows.
+    if vmos.len() == vmos.capacity() {
+        vmos.retain(|vmo| vmo.strong_count() > 0);
+    }
+    vmos.push(Arc::downgrade(vmo));
+}
+
+fn backed_vmos() -> Vec<Arc<Vmo>> {
+    BACKED_VMOS
+        .lock()
+        .iter()
+        .filter_map(Weak::upgrade)
+        .collect()
+}
+
+/// Evicts at most `max_pages` clean pages from all page caches with backends.
+///
+/// This method never blocks, so it can be called to reclaim memory regardless
+/// of the locks held by the caller. Page caches are visited in a round-robin
+/// fashion across calls.
+///
+/// If `memcg` is specified, only the pages charged to the memory cgroup (or
+/// its descendants) are evicted.
+///
+/// Returns the number of evicted pages.
+pub(crate) fn reclaim_clean_pages(max_pages: usize, memcg: Option<&MemCgroup>) -> usize {
+    let vmos = backed_vmos();
+    if vmos.is_empty() {
+        return 0;
+    }
+
+    let start = NEXT_RECLAIM_IDX.load(Ordering::Relaxed) % vmos.len();
+    let mut nr_evicted = 0;
+    for (i, vmo) in vmos.iter().enumerate().cycle().skip(start).take(vmos.len()) {
+        let Some(backed_vmo) = vmo.as_backed_vmo() else {
+            continue;
+        };
+        nr_evicted += backed_vmo.reclaim_up_to_date_pages(max_pages - nr_evicted, memcg);
+        if nr_evicted == max_pages {
+            NEXT_RECLAIM_IDX.store(i, Ordering::Relaxed);
+            break;
+        }
+    }
+
+    nr_evicted
+}
+
+/// Writes back the dirty pages in all page caches with backends.
+///
+/// After being written back, the pages become clean and can be evicted by
+/// [`reclaim_clean_pages`].
+///
+/// This method may block on the locks of the file systems. So it should not be
+/// called by a task that may hold such locks.
+pub(crate) fn write_back_dirty_pages() {
+    for vmo in backed_vmos() {
+        let Some(backed_vmo) = vmo.as_backed_vmo() else {
+            continue;
+        };
+        if let Err(err) = backed_vmo.flush_dirty_pages(&(0..backed_vmo.size())) {
+            debug!("failed to write back dirty pages: {:?}", err);
+        }
+    }
+}
+
+/// Returns the total number of pages in all page caches with backends.
+pub(crate) fn nr_cached_pages() -> usize {
+    backed_vmos()
+        .iter()
+        .map(|vmo| vmo.nr_committed_pages())
+        .sum()
+}
+
 /// A storage backend for a backed [`PageCache`].
 ///
 /// This trait is the high-level contract used by the page-cache layer to load
@@ -550,3 +550,34 @@ fn delayed_io_completion() {
     assert!(second_flush_result.lock().take().unwrap().is_ok());
     assert_eq!(backend.persisted_page_bytes(0), latest_dirty_pattern);
 }
+
+/// Reclaims a page cache with a dirty page and a clean page, ensuring that
+/// only the clean page is evicted and the dirty page stays cached.
+#[ktest]
+fn reclaim_clean_and_dirty_pages() {
+    let backend = MockPageCacheBackend::new(2);
+    let clean_pattern = vec![0x3c; PAGE_SIZE];
+    let dirty_pattern = vec![0xa5; PAGE_SIZE];
+    backend.set_persisted_page_bytes(1, &clean_pattern);
+
+    let page_cache = new_backend_page_cache(&backend, 2);
+    page_cache.write_bytes(0, &dirty_pattern).unwrap();
+    let mut read_buffer = vec![0; PAGE_SIZE];
+    page_cache.read_bytes(PAGE_SIZE, &mut read_buffer).unwrap();
+    assert_eq!(backend.read_count(1), 1);
+
+    let vmo = page_cache.as_vmo();
+    assert_eq!(vmo.nr_committed_pages(), 2);
+    let backed_vmo = vmo.as_backed_vmo().unwrap();
+    assert_eq!(backed_vmo.reclaim_up_to_date_pages(usize::MAX, None), 1);
+    assert_eq!(vmo.nr_committed_pages(), 1);
+
+    // The dirty page is still cached, while the clean page is read again from
+    // the backend.
+    page_cache.read_bytes(0, &mut read_buffer).unwrap();
+    assert_eq!(read_buffer, dirty_pattern);
+    assert_eq!(backend.read_count(0), 0);
+    page_cache.read_bytes(PAGE_SIZE, &mut read_buffer).unwrap();
+    assert_eq!(read_buffer, clean_pattern);
+    assert_eq!(backend.read_count(1), 2);
+}
@@ -30,6 +30,7 @@ use ostd::{
 use xarray::{Cursor, LockedXArray, XArray};
 
 use crate::{
+    fs::cgroupfs::MemCgroup,
     prelude::*,
     vm::{
         page_cache::{CachePage, CachePageExt, PageCacheBackend, cache_page::PageState},
@@ -307,19 +308,29 @@ impl Vmo {
     }
 
     fn commit_on_anonymous(&self, page_idx: usize) -> Result<CachePage> {
-        let mut locked_pages = self.pages.lock();
-        if page_idx >= self.size().div_ceil(PAGE_SIZE) {
-            return_errno_with_message!(Errno::EINVAL, "the offset is outside the VMO");
-        }
+        // Allocating a page may reclaim memory, which may sleep. So the page is allocated
+        // without holding the lock and we have to check again after acquiring the lock.
+        let mut new_page = None;
 
-        let mut cursor = locked_pages.cursor_mut(page_idx as u64);
-        if let Some(page) = cursor.load() {
-            return Ok(page.clone());
-        }
+        loop {
+            let mut locked_pages = self.pages.lock();
+            if page_idx >= self.size().div_ceil(PAGE_SIZE) {
+                return_errno_with_message!(Errno::EINVAL, "the offset is outside the VMO");
+            }
 
-        let new_page = CachePage::alloc_zero()?;
-        cursor.store(new_page.clone());
-        Ok(new_page)
+            let mut cursor = locked_pages.cursor_mut(page_idx as u64);
+            if let Some(page) = cursor.load() {
+                return Ok(page.clone());
+            }
+
+            if let Some(new_page) = new_page.take() {
+                cursor.store(new_page.clone());
+                return Ok(new_page);
+            }
+
+            drop(locked_pages);
+            new_page = Some(CachePage::alloc_zero()?);
+        }
     }
 
     /// Tries to commit the page covering byte `offset` without blocking on I/O.
@@ -458,6 +469,19 @@ impl Vmo {
         self.size.load(Ordering::Acquire)
     }
 
+    /// Returns the number of committed pages.
+    pub(crate) fn nr_committed_pages(&self) -> usize {
+        let locked_pages = self.pages.lock();
+        let mut cursor = locked_pages.cursor(0);
+
+        let mut nr_pages = usize::from(cursor.load().is_some());
+        while cursor.next_present().is_some() {
+            nr_pages += 1;
+        }
+
+        nr_pages
+    }
+
     /// Returns the status of writable mappings of the VMO.
     pub(crate) fn writable_mapping_status(&self) -> &WritableMappingStatus {
         // Currently, only VMOs used by `MemfdInode` (anonymous) track writable mapping status.
@@ -508,6 +532,60 @@ impl Vmo {
         Ok(())
     }
 
+    /// Makes the anonymous pages in `dst_offset..dst_offset + len` share the frames of the pages
+    /// in `src_offset..src_offset + len`.
+    ///
+    /// The source pages are committed if they are not present. This allows the same memory to be
+    /// mapped at different VMO offsets, e.g., the SQ ring and the CQ ring of io_uring.
+    ///
+    /// This method should only be called before the VMO is mapped.
+    pub(crate) fn alias_anon_pages(
+        &self,
+        src_offset: usize,
+        dst_offset: usize,
+        len: usize,
+    ) -> Result<()> {
+        self.share_anon_pages(self, src_offset, dst_offset, len)
+    }
+
+    /// Makes the anonymous pages in `dst_offset..dst_offset + len` share the frames of the pages
+    /// in `src_offset..src_offset + len` of the `src` VMO.
+    ///
+    /// The source pages are committed if they are not present. This allows the same memory to be
+    /// mapped through different VMOs, e.g., the vDSO VMOs of different time namespaces.
+    ///
+    /// This method should only be called before the VMO is mapped.
+    pub(crate) fn share_anon_pages(
+        &self,
+        src: &Vmo,
+        src_offset: usize,
+        dst_offset: usize,
+        len: usize,
+    ) -> Result<()> {
+        debug_assert!(!self.has_backend());
+        debug_assert!(!src.has_backend());
+        debug_assert!(src_offset.is_multiple_of(PAGE_SIZE));
+        debug_assert!(dst_offset.is_multiple_of(PAGE_SIZE));
+
+        if dst_offset
+            .checked_add(len)
+            .is_none_or(|end| end > self.size())
+        {
+            return_errno_with_message!(Errno::EINVAL, "the destination is outside the VMO");
+        }
+
+        for page_offset in (0..len).step_by(PAGE_SIZE) {
+            let page = src.commit_on_anonymous((src_offset + page_offset) / PAGE_SIZE)?;
+
+            let mut locked_pages = self.pages.lock();
+            let mut cursor =
+                locked_pages.cursor_mut(((dst_offset + page_offset) / PAGE_SIZE) as u64);
+            cursor.store(page);
+        }
+
+        Ok(())
+    }
+
     /// Converts this VMO to a backend VMO wrapper if it has a backend.
     ///
     /// Returns `None` if this is an anonymous VMO.
@@ -981,6 +1059,71 @@ impl<'a> BackedVmo<'a> {
         Ok(())
     }
 
+    /// Evicts at most `max_pages` up-to-date (clean) pages from the page cache
+    /// to reclaim memory.
+    ///
+    /// Unlike [`Self::evict_up_to_date_pages`], this method never blocks. The
+    /// caller may hold arbitrary locks (e.g., the allocation that triggers the
+    /// memory reclaim may happen with an inode lock held), so pages whose locks
+    /// cannot be acquired immediately are skipped.
+    ///
+    /// If `memcg` is specified, only the pages charged to the memory cgroup
+    /// (or its descendants) are evicted.
+    ///
+    /// Returns the number of pages removed from the page cache.
+    //
+    // TODO: Evict pages in the LRU order instead of the page index order.
+    pub(super) fn reclaim_up_to_date_pages(
+        &self,
+        max_pages: usize,
+        memcg: Option<&MemCgroup>,
+    ) -> usize {
+        let Some(mut locked_rmap) = self.rmap.try_lock() else {
+            return 0;
+        };
+
+        let page_idx_range = 0..self.size().div_ceil(PAGE_SIZE);
+        let locked_up_to_date_pages = self
+            .collect_pages_if(&page_idx_range, PageSelection::Evict)
+            .into_iter()
+            .filter_map(|(idx, page)| {
+                let locked_page = page.try_lock()?;
+                if !locked_page.state().is_up_to_date()
+                    || locked_page.is_writing_back()
+                    || memcg.is_some_and(|memcg| !locked_page.metadata().is_charged_to(memcg))
+                {
+                    return None;
+                }
+                locked_page.set_evicted();
+                Some((idx, locked_page))
+            })
+            .take(max_pages)
+            .collect::<Vec<_>>();
+
+        // See `evict_up_to_date_pages` for how this synchronizes with concurrent page faults.
+        for (idx, _) in locked_up_to_date_pages.iter() {
+            locked_rmap.unmap(idx * PAGE_SIZE..(idx + 1) * PAGE_SIZE);
+        }
+        drop(locked_rmap);
+
+        let mut nr_evicted = 0;
+        for (idx, page) in locked_up_to_date_pages {
+            let mut locked_pages = self.pages.lock();
+            let mut cursor_mut = locked_pages.cursor_mut(idx as u64);
+            // No file system lock is held, so the page may have been decommitted by a concurrent
+            // resize and the slot may have been filled with another page.
+            if cursor_mut
+                .load()
+                .is_some_and(|cached_page| cached_page.paddr() == page.paddr())
+            {
+                cursor_mut.remove();
+                nr_evicted += 1;
+            }
+        }
+
+        nr_evicted
+    }
+
     /// Decommits pages with a backend in the specified byte range.
     ///
     /// Dirty pages in the range are discarded without being written back. If a
@@ -1018,30 +1161,40 @@ impl<'a> BackedVmo<'a> {
 
     /// Commits a page at the given index for a VMO with a backend.
     fn commit_on_internal(&self, page_idx: usize, commit_mode: CommitMode) -> Result<CachePage> {
-        let mut locked_pages = self.pages.lock();
-        if page_idx >= self.size().div_ceil(PAGE_SIZE) {
-            return_errno_with_message!(Errno::EINVAL, "the offset is outside the VMO");
-        }
+        // Allocating a page may reclaim memory, which may sleep. So the page is allocated
+        // without holding the lock and we have to check again after acquiring the lock.
+        let mut new_page = None;
 
-        let mut cursor = locked_pages.cursor_mut(page_idx as u64);
+        let uninit_page = loop {
+            let mut locked_pages = self.pages.lock();
+            if page_idx >= self.size().div_ceil(PAGE_SIZE) {
+                return_errno_with_message!(Errno::EINVAL, "the offset is outside the VMO");
+            }
 
-        if let Some(page) = cursor.load() {
-            let page = page.clone();
-            drop(locked_pages);
+            let mut cursor = locked_pages.cursor_mut(page_idx as u64);
+
+            if let Some(page) = cursor.load() {
+                let page = page.clone();
+                drop(locked_pages);
+
+                if !commit_mode.skips_backend_read() {
+                    page.ensure_init(|locked_page| self.backend.read_page(page_idx, locked_page))?;
+                    return Ok(page);
+                }
 
-            if !commit_mode.skips_backend_read() {
-                page.ensure_init(|locked_page| self.backend.read_page(page_idx, locked_page))?;
                 return Ok(page);
+            };
+
+            // The page is within the file bounds - need to allocate a cache page.
+            if let Some(uninit_page) = new_page.take() {
+                cursor.store(uninit_page.clone());
+                break uninit_page;
             }
 
-            return Ok(page);
+            drop(locked_pages);
+            new_page = Some(CachePage::alloc_uninit()?);
         };
 
-        // The page is within the file bounds - need to allocate a cache page.
-        let uninit_page = CachePage::alloc_uninit()?;
-        cursor.store(uninit_page.clone());
-        drop(locked_pages);
-
         if commit_mode.skips_backend_read() {
             // The page will be completely overwritten, no need to read.
             Ok(uninit_page)
@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Memory reclaim.
+//!
+//! When the frame allocator is exhausted, OSTD calls the handler injected by
+//! this module (see [`ostd::mm::frame::allocator::inject_reclaim_handler`]).
+//! The handler frees memory in the following order:
+//!
+//! 1. Evicting clean pages from the page caches;
+//! 2. Swapping out private anonymous pages, if a swap area is turned on;
+//! 3. Writing back dirty pages in the page caches, and then evicting them;
+//! 4. Killing a process with the OOM killer (see [`super::oom`]).
+//!
+//! The allocating task may hold arbitrary locks, including the locks of file
+//! systems and VMARs. So memory reclaim never blocks on such locks: they are
+//! only acquired with try-locks, and dirty pages are written back by a work
+//! item, which the allocating task waits for with a timeout.
+//!
+//! Memory reclaim may still sleep, but never on the locks held by the
+//! allocating task:
+//!  - Memory reclaim is serialized by [`RECLAIM_LOCK`], so the allocating task
+//!    may sleep until a concurrent reclaim completes. See [`RECLAIM_LOCK`] for
+//!    the lock ordering that makes this safe.
+//!  - Swapping out pages writes them to the swap area synchronously. This only
+//!    waits for the block device, since swap areas do not go through file
+//!    systems.
+//!
+//! Memory can also be reclaimed from a memory cgroup that reaches its
+//! `memory.max` or `memory.high` limit. In that case, only the pages charged to
+//! the cgroup are reclaimed, and only the processes in the cgroup can be killed.
+
+use core::{
+    sync::atomic::{AtomicUsize, Ordering},
+    time::Duration,
+};
+
+use ostd::{sync::WaitQueue, task::Task};
+use spin::Once;
+
+use super::{oom, page_cache, swap};
+use crate::{
+    fs::cgroupfs::MemCgroup,
+    prelude::*,
+    process::{Process, pid_table},
+    thread::work_queue::{self, WorkPriority, work_item::WorkItem},
+    vm::vmar::RssType,
+};
+
+/// The minimum number of pages to reclaim at a time.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/linux/swap.h#L387>
+const SWAP_CLUSTER_MAX: usize = 32;
+
+/// The maximum time to wait for a round of writeback.
+const WRITEBACK_TIMEOUT: Duration = Duration::from_millis(100);
+
+/// The maximum time to wait for the victim of the OOM killer to exit.
+const OOM_VICTIM_TIMEOUT: Duration = Duration::from_millis(10);
+
+/// The lock that serializes memory reclaim.
+///
+/// This lock is acquired while the allocating task may hold any other lock, so
+/// it is ordered after all of them. While holding this lock, other locks can
+/// only be acquired with try-locks, unless they are never held across memory
+/// allocations.
+static RECLAIM_LOCK: Mutex<()> = Mutex::new(());
+
+/// The address of the task that is reclaiming memory, or zero if there is none.
+static RECLAIMER: AtomicUsize = AtomicUsize::new(0);
+
+/// The number of completed memory reclaims.
+static NR_RECLAIMS: AtomicUsize = AtomicUsize::new(0);
+
+/// The work item that writes back dirty pages in the page caches.
+static WRITEBACK_WORK: Once<Arc<WorkItem>> = Once::new();
+
+/// The number of completed rounds of writeback.
+static NR_WRITEBACKS: AtomicUsize = AtomicUsize::new(0);
+
+/// The wait queue to wait for the writeback or the victim of the OOM killer.
+static RECLAIM_WAIT_QUEUE: WaitQueue = WaitQueue::new();
+
+pub(super) fn init_in_first_kthread() {
+    WRITEBACK_WORK.call_once(|| {
+        WorkItem::new(Box::new(|| {
+            page_cache::write_back_dirty_pages();
+            NR_WRITEBACKS.fetch_add(1, Ordering::Release);
+            RECLAIM_WAIT_QUEUE.wake_all();
+        }))
+    });
+
+    ostd::mm::frame::allocator::inject_reclaim_handler(reclaim_memory);
+}
+
+/// Reclaims memory for an allocation of `nr_pages` pages.
+///
+/// Returns `true` if some memory may have been freed, so that the allocation
+/// is worth retrying.
+pub(crate) fn reclaim_memory(nr_pages: usize) -> bool {
+    reclaim(nr_pages, None, true)
+}
+
+/// Reclaims memory from the memory cgroup for a charge of `nr_pages` pages.
+///
+/// If `may_oom` is `true` and no memory can be reclaimed, a process in the
+/// memory cgroup will be killed by the OOM killer.
+///
+/// Returns `true` if some memory may have been freed, so that the charge is
+/// worth retrying.
+pub(crate) fn reclaim_cgroup_memory(memcg: &MemCgroup, nr_pages: usize, may_oom: bool) -> bool {
+    reclaim(nr_pages, Some(memcg), may_oom)
+}
+
+/// Reclaims memory for an allocation of `nr_pages` pages by the current
+/// process.
+///
+/// If the memory cgroup of the current process reaches its `memory.max` limit,
+/// memory is reclaimed from the cgroup. Otherwise, memory is reclaimed from
+/// the whole system.
+///
+/// Returns `true` if some memory may have been freed, so that the allocation
+/// is worth retrying.
+pub(crate) fn reclaim_memory_for_current(nr_pages: usize) -> bool {
+    let over_max_memcg = Process::current()
+        .and_then(|process| MemCgroup::of_process(&process))
+        .and_then(|memcg| memcg.find_over_max(nr_pages));
+
+    match over_max_memcg {
+        Some(memcg) => reclaim_cgroup_memory(&memcg, nr_pages, true),
+        None => reclaim_memory(nr_pages),
+    }
+}
+
+fn reclaim(nr_pages: usize, memcg: Option<&MemCgroup>, may_oom: bool) -> bool {
+    let Some(current) = Task::current() else {
+        return false;
+    };
+    let current_addr = &*current as *const Task as usize;
+
+    // Memory allocations during memory reclaim must not reclaim memory recursively.
+    if RECLAIMER.load(Ordering::Relaxed) == current_addr {
+        return false;
+    }
+
+    let nr_reclaims = NR_RECLAIMS.load(Ordering::Acquire);
+    let _guard = RECLAIM_LOCK.lock();
+    // Others have reclaimed memory while we were waiting for the lock.
+    if NR_RECLAIMS.load(Ordering::Acquire) != nr_reclaims {
+        return true;
+    }
+
+    RECLAIMER.store(current_addr, Ordering::Relaxed);
+    let res = do_reclaim(nr_pages, memcg, may_oom);
+    RECLAIMER.store(0, Ordering::Relaxed);
+    NR_RECLAIMS.fetch_add(1, Ordering::Release);
+
+    res
+}
+
+fn do_reclaim(nr_pages: usize, memcg: Option<&MemCgroup>, may_oom: bool) -> bool {
+    let nr_to_reclaim = nr_pages.max(SWAP_CLUSTER_MAX);
+
+    let mut nr_reclaimed = page_cache::reclaim_clean_pages(nr_to_reclaim, memcg);
+    if nr_reclaimed < nr_to_reclaim && swap::is_enabled() {
+        nr_reclaimed += swap_out_pages(nr_to_reclaim - nr_reclaimed, memcg);
+    }
+    if nr_reclaimed < nr_to_reclaim {
+        wait_for_writeback();
+        nr_reclaimed += page_cache::reclaim_clean_pages(nr_to_reclaim - nr_reclaimed, memcg);
+    }
+    if nr_reclaimed > 0 {
+        return true;
+    }
+
+    // The pages charged to the memory cgroup may have been freed by others.
+    if let Some(memcg) = memcg
+        && memcg.nr_used_pages() + nr_pages <= memcg.nr_max_pages()
+    {
+        return true;
+    }
+
+    if !may_oom || !oom::out_of_memory(nr_pages, memcg) {
+        return false;
+    }
+
+    // Give the victim a chance to exit and free its memory.
+    let _ = RECLAIM_WAIT_QUEUE.wait_until_or_timeout(
+        || (!oom::has_pending_victim()).then_some(()),
+        &OOM_VICTIM_TIMEOUT,
+    );
+    true
+}
+
+/// Swaps out at most `max_pages` pages from the processes.
+///
+/// Processes with more anonymous pages are chosen first. If `memcg` is
+/// specified, only the processes in the memory cgroup are chosen.
+fn swap_out_pages(max_pages: usize, memcg: Option<&MemCgroup>) -> usize {
+    let Some(pid_table) = pid_table::try_pid_table_mut() else {
+        return 0;
+    };
+    let processes = pid_table.iter_processes().collect::<Vec<_>>();
+    drop(pid_table);
+
+    let mut candidates = processes
+        .into_iter()
+        .filter(|process| memcg.is_none_or(|memcg| memcg.contains_process(process)))
+        .filter_map(|process| {
+            let nr_anon_pages = process
+                .try_lock_vmar()?
+                .as_ref()?
+                .get_rss_counter(RssType::Anon);
+            Some((process, nr_anon_pages))
+        })
+        .filter(|(_, nr_anon_pages)| *nr_anon_pages > 0)
+        .collect::<Vec<_>>();
+    candidates.sort_by_key(|(_, nr_anon_pages)| core::cmp::Reverse(*nr_anon_pages));
+
+    let mut nr_swapped = 0;
+    for (process, _) in candidates {
+        if nr_swapped == max_pages {
+            break;
+        }
+        let Some(vmar_guard) = process.try_lock_vmar() else {
+            continue;
+        };
+        if let Some(vmar) = vmar_guard.as_ref() {
+            nr_swapped += vmar.reclaim_pages(max_pages - nr_swapped);
+        }
+    }
+
+    nr_swapped
+}
+
+/// Starts writing back dirty pages and waits for a round of writeback to complete.
+fn wait_for_writeback() {
+    let Some(writeback_work) = WRITEBACK_WORK.get() else {
+        return;
+    };
+
+    let nr_writebacks = NR_WRITEBACKS.load(Ordering::Acquire);
+    work_queue::submit_work_item(writeback_work.clone(), WorkPriority::Normal);
+
+    // The writeback may need the locks held by the current task. So we cannot
+    // wait for it forever.
+    let _ = RECLAIM_WAIT_QUEUE.wait_until_or_timeout(
+        || (NR_WRITEBACKS.load(Ordering::Acquire) != nr_writebacks).then_some(()),
+        &WRITEBACK_TIMEOUT,
+    );
+}
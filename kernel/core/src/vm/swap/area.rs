@@ -0,0 +1,216 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use aster_block::BlockDevice;
+use device_id::DeviceId;
+use ostd::{
+    mm::{UFrame, VmIo, io::util::HasVmReaderWriter},
+    sync::LocalIrqDisabled,
+};
+
+use super::{SwapEntry, header::SwapHeader};
+use crate::{prelude::*, vm::anon_page::alloc_anon_page};
+
+/// The reference count that marks a slot as unusable.
+const BAD_SLOT: u32 = u32::MAX;
+
+/// A swap area on a block device.
+pub(super) struct SwapArea {
+    /// The index of the area in the global table.
+    index: usize,
+    /// The path name of the device when the area is turned on.
+    name: String,
+    device: Arc<dyn BlockDevice>,
+    priority: i16,
+    /// The number of usable slots.
+    nr_slots: usize,
+    slots: SpinLock<SwapSlots, LocalIrqDisabled>,
+}
+
+struct SwapSlots {
+    /// The reference counts of the slots, indexed by the page offset in the area.
+    ///
+    /// A free slot has a count of zero. Each swap entry in a page table holds
+    /// one reference.
+    counts: Vec<u32>,
+    /// The number of slots in use.
+    nr_used: usize,
+    /// The slot offset to start the next search from.
+    next: usize,
+    /// The swap cache.
+    ///
+    /// It holds the pages that are being written out, or that have been read
+    /// in but not mapped yet. A page in the cache always has the latest
+    /// content, so it is used instead of the content on the device.
+    cache: BTreeMap<usize, UFrame>,
+    /// Whether new slots can be allocated.
+    is_enabled: bool,
+}
+
+impl SwapArea {
+    /// Creates a swap area from a block device with a valid header.
+    pub(super) fn new(
+        index: usize,
+        name: String,
+        device: Arc<dyn BlockDevice>,
+        priority: i16,
+    ) -> Result<Self> {
+        let header = SwapHeader::read_from(device.as_ref())?;
+
+        // The offset must fit in a swap entry. Extra pages are left unused.
+        let last_page = header.last_page().min(SwapEntry::MAX_OFFSET);
+
+        let mut counts = vec![0; last_page + 1];
+        // The first page holds the header.
+        counts[0] = BAD_SLOT;
+        for &bad_page in header.bad_pages() {
+            if let Some(count) = counts.get_mut(bad_page) {
+                *count = BAD_SLOT;
+            }
+        }
+        let nr_slots = counts.iter().filter(|&&count| count == 0).count();
+        if nr_slots == 0 {
+            return_errno_with_message!(Errno::EINVAL, "the swap area has no usable pages");
+        }
+
+        let slots = SwapSlots {
+            counts,
+            nr_used: 0,
+            next: 1,
+            cache: BTreeMap::new(),
+            is_enabled: true,
+        };
+
+        Ok(Self {
+            index,
+            name,
+            device,
+            priority,
+            nr_slots,
+            slots: SpinLock::new(slots),
+        })
+    }
+
+    /// Returns the index of the area in the global table.
+    pub(super) fn index(&self) -> usize {
+        self.index
+    }
+
+    /// Returns the path name of the device when the area is turned on.
+    pub(super) fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the device ID of the underlying block device.
+    pub(super) fn device_id(&self) -> DeviceId {
+        self.device.id()
+    }
+
+    pub(super) fn priority(&self) -> i16 {
+        self.priority
+    }
+
+    /// Returns the number of usable slots.
+    pub(super) fn nr_slots(&self) -> usize {
+        self.nr_slots
+    }
+
+    /// Returns the number of slots in use.
+    pub(super) fn nr_used(&self) -> usize {
+        self.slots.lock().nr_used
+    }
+
+    /// Sets whether new slots can be allocated.
+    pub(super) fn set_enabled(&self, is_enabled: bool) {
+        self.slots.lock().is_enabled = is_enabled;
+    }
+
+    /// Allocates a free slot, which has one reference.
+    pub(super) fn alloc(&self) -> Option<SwapEntry> {
+        let mut slots = self.slots.lock();
+        if !slots.is_enabled || slots.nr_used == self.nr_slots {
+            return None;
+        }
+
+        let len = slots.counts.len();
+        let start = slots.next;
+        let offset = (start..len)
+            .chain(1..start)
+            .find(|&offset| slots.counts[offset] == 0)
+            .unwrap();
+
+        slots.counts[offset] = 1;
+        slots.nr_used += 1;
+        slots.next = if offset + 1 == len { 1 } else { offset + 1 };
+
+        Some(SwapEntry::new(self.index, offset))
+    }
+
+    /// Adds a reference to a slot in use.
+    pub(super) fn dup(&self, offset: usize) {
+        let mut slots = self.slots.lock();
+        let count = &mut slots.counts[offset];
+        debug_assert!(*count != 0 && *count < BAD_SLOT - 1);
+        *count += 1;
+    }
+
+    /// Removes a reference to a slot in use.
+    ///
+    /// The slot is freed when the last reference is removed.
+    pub(super) fn put(&self, offset: usize) {
+        let cached_frame = {
+            let mut slots = self.slots.lock();
+            let count = &mut slots.counts[offset];
+            debug_assert!(*count != 0 && *count != BAD_SLOT);
+            *count -= 1;
+            if *count != 0 {
+                return;
+            }
+
+            slots.nr_used -= 1;
+            slots.cache.remove(&offset)
+        };
+
+        // Drop the frame after releasing the lock.
+        drop(cached_frame);
+    }
+
+    /// Returns the number of references to a slot.
+    pub(super) fn count(&self, offset: usize) -> u32 {
+        self.slots.lock().counts[offset]
+    }
+
+    /// Looks up the page of a slot in the swap cache.
+    pub(super) fn lookup_cache(&self, offset: usize) -> Option<UFrame> {
+        self.slots.lock().cache.get(&offset).cloned()
+    }
+
+    /// Inserts the page of a slot into the swap cache.
+    ///
+    /// The caller must hold a reference to the slot.
+    pub(super) fn insert_cache(&self, offset: usize, frame: UFrame) {
+        let mut slots = self.slots.lock();
+        debug_assert!(slots.counts[offset] != 0);
+        slots.cache.entry(offset).or_insert(frame);
+    }
+
+    /// Removes the page of a slot from the swap cache.
+    ///
+    /// The caller must hold a reference to the slot.
+    pub(super) fn remove_cache(&self, offset: usize) {
+        let cached_frame = self.slots.lock().cache.remove(&offset);
+        drop(cached_frame);
+    }
+
+    /// Writes the page to a slot on the device.
+    pub(super) fn write_page(&self, offset: usize, frame: &UFrame) -> Result<()> {
+        self.device.write(offset * PAGE_SIZE, &mut frame.reader())?;
+        Ok(())
+    }
+
+    /// Reads the page of a slot from the device into a new frame.
+    pub(super) fn read_page(&self, offset: usize) -> Result<UFrame> {
+        let frame = alloc_anon_page(false)?;
+        self.device.read(offset * PAGE_SIZE, &mut frame.writer())?;
+        Ok(frame.into())
+    }
+}
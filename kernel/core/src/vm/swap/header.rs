@@ -0,0 +1,108 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! The on-disk header of swap areas.
+//!
+//! The header occupies the first page of a swap area and uses the same format
+//! as the one written by `mkswap`.
+//!
+//! Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/linux/swap.h#L128-L149>
+
+use aster_block::BlockDevice;
+use ostd::mm::VmIo;
+
+use crate::prelude::*;
+
+/// The magic of version 1 swap areas, which is located at the end of the first page.
+const SWAP_MAGIC: &[u8; 10] = b"SWAPSPACE2";
+
+/// The offset of [`SwapHeaderInfo`] in the first page.
+///
+/// The first 1024 bytes are reserved for boot loaders and disk labels.
+const SWAP_INFO_OFFSET: usize = 1024;
+
+/// The offset of the bad page list in the first page.
+const SWAP_BADPAGES_OFFSET: usize = SWAP_INFO_OFFSET + size_of::<SwapHeaderInfo>();
+
+/// The maximum number of bad pages that fit in the first page.
+const MAX_SWAP_BADPAGES: usize =
+    (PAGE_SIZE - SWAP_MAGIC.len() - SWAP_BADPAGES_OFFSET) / size_of::<u32>();
+
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct SwapHeaderInfo {
+    version: u32,
+    last_page: u32,
+    nr_badpages: u32,
+    uuid: [u8; 16],
+    volume_name: [u8; 16],
+    padding: [u32; 117],
+}
+
+/// The header of a swap area.
+#[derive(Debug)]
+pub(super) struct SwapHeader {
+    /// The index of the last usable page.
+    last_page: usize,
+    /// The indexes of pages that must not be used.
+    bad_pages: Vec<usize>,
+}
+
+impl SwapHeader {
+    /// Reads the header from the first page of the block device.
+    pub(super) fn read_from(device: &dyn BlockDevice) -> Result<Self> {
+        let nr_device_pages = device.metadata().nr_sectors * aster_block::SECTOR_SIZE / PAGE_SIZE;
+        if nr_device_pages < 2 {
+            return_errno_with_message!(Errno::EINVAL, "the device is too small for swapping");
+        }
+
+        let mut page = vec![0u8; PAGE_SIZE];
+        device.read_bytes(0, &mut page)?;
+
+        if &page[PAGE_SIZE - SWAP_MAGIC.len()..] != SWAP_MAGIC {
+            return_errno_with_message!(Errno::EINVAL, "the swap-space signature is not found");
+        }
+
+        let info = SwapHeaderInfo::from_first_bytes(&page[SWAP_INFO_OFFSET..]);
+        if info.version != 1 {
+            return_errno_with_message!(Errno::EINVAL, "the swap header version is not supported");
+        }
+
+        let nr_badpages = info.nr_badpages as usize;
+        if nr_badpages > MAX_SWAP_BADPAGES {
+            return_errno_with_message!(Errno::EINVAL, "the swap header has too many bad pages");
+        }
+
+        // Linux trusts the device size more than the header.
+        let last_page = (info.last_page as usize).min(nr_device_pages - 1);
+        if last_page == 0 {
+            return_errno_with_message!(Errno::EINVAL, "the swap area is empty");
+        }
+
+        let bad_pages = page[SWAP_BADPAGES_OFFSET..]
+            .chunks_exact(size_of::<u32>())
+            .take(nr_badpages)
+            .map(|bytes| u32::from_ne_bytes(bytes.try_into().unwrap()) as usize)
+            .collect::<Vec<_>>();
+        if bad_pages
+            .iter()
+            .any(|&bad_page| bad_page == 0 || bad_page > last_page)
+        {
+            return_errno_with_message!(Errno::EINVAL, "the swap header has invalid bad pages");
+        }
+
+        Ok(Self {
+            last_page,
+            bad_pages,
+        })
+    }
+
+    /// Returns the index of the last usable page.
+    pub(super) fn last_page(&self) -> usize {
+        self.last_page
+    }
+
+    /// Returns the indexes of pages that must not be used.
+    pub(super) fn bad_pages(&self) -> &[usize] {
+        &self.bad_pages
+    }
+}
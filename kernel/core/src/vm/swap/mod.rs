@@ -0,0 +1,313 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Swapping anonymous pages out to block devices.
+//!
+//! A swap area is a block device formatted by `mkswap`. Once it is turned on
+//! by the `swapon` system call, private anonymous pages can be written to free
+//! slots in the area and removed from the RAM. The page table entries of such
+//! pages are replaced by [`SwapEntry`]s, which locate the slots. Accessing a
+//! swapped-out page triggers a page fault, which reads the page back.
+//!
+//! Each swap entry in a page table holds a reference to its slot. Forking a
+//! process duplicates the references, and the slot is freed when the last
+//! reference is removed from page tables. The references are released by the
+//! handler injected into OSTD (see [`ostd::mm::vm_space::inject_swap_entry_drop_handler`]).
+//!
+//! Pages whose content is not on the device yet, or has just been read from
+//! the device, are kept in the swap cache of the area. The swap cache makes it
+//! possible to write a page out without blocking the page faults on it.
+
+mod area;
+mod header;
+
+use aster_block::BlockDevice;
+use device_id::DeviceId;
+use ostd::{
+    mm::{UFrame, vm_space::NR_SWAP_ENTRY_BITS},
+    sync::LocalIrqDisabled,
+};
+
+use self::area::SwapArea;
+use crate::{prelude::*, process::pid_table};
+
+pub(super) fn init() {
+    ostd::mm::vm_space::inject_swap_entry_drop_handler(|entry| {
+        free_entry(SwapEntry::from_raw(entry));
+    });
+}
+
+/// The maximum number of swap areas that can be turned on.
+///
+/// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/linux/swap.h#L72>
+pub(crate) const MAX_SWAP_AREAS: usize = 32;
+
+static SWAP_AREAS: SpinLock<[Option<Arc<SwapArea>>; MAX_SWAP_AREAS], LocalIrqDisabled> =
+    SpinLock::new([const { None }; MAX_SWAP_AREAS]);
+
+/// The priority of the next swap area turned on without a specified priority.
+///
+/// Such areas are given decreasing negative priorities, so the area turned on
+/// earlier is preferred.
+static NEXT_DEFAULT_PRIORITY: Mutex<i16> = Mutex::new(-2);
+
+/// A swap entry, which locates a page slot in a swap area.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) struct SwapEntry(usize);
+
+impl SwapEntry {
+    const AREA_BITS: u32 = MAX_SWAP_AREAS.ilog2();
+    const OFFSET_BITS: u32 = NR_SWAP_ENTRY_BITS - Self::AREA_BITS;
+    const MAX_OFFSET: usize = (1 << Self::OFFSET_BITS) - 1;
+
+    fn new(area_index: usize, offset: usize) -> Self {
+        debug_assert!(area_index < MAX_SWAP_AREAS && offset <= Self::MAX_OFFSET);
+        Self((area_index << Self::OFFSET_BITS) | offset)
+    }
+
+    /// Creates a swap entry from the raw value stored in page tables.
+    pub(crate) fn from_raw(raw: usize) -> Self {
+        Self(raw)
+    }
+
+    /// Returns the raw value stored in page tables.
+    pub(crate) fn into_raw(self) -> usize {
+        self.0
+    }
+
+    fn area_index(&self) -> usize {
+        self.0 >> Self::OFFSET_BITS
+    }
+
+    fn offset(&self) -> usize {
+        self.0 & Self::MAX_OFFSET
+    }
+
+    fn area(&self) -> Arc<SwapArea> {
+        SWAP_AREAS.lock()[self.area_index()]
+            .clone()
+            .expect("the swap area of a swap entry in use is turned off")
+    }
+}
+
+/// Turns on swapping to the block device.
+///
+/// If the priority is not specified, the area will be used after all the
+/// existing areas.
+pub(crate) fn swap_on(
+    name: String,
+    device: Arc<dyn BlockDevice>,
+    priority: Option<i16>,
+) -> Result<()> {
+    // Hold the lock during the whole process to assign priorities in order.
+    let mut next_default_priority = NEXT_DEFAULT_PRIORITY.lock();
+
+    let index = {
+        let areas = SWAP_AREAS.lock();
+        if areas
+            .iter()
+            .flatten()
+            .any(|area| area.device_id() == device.id())
+        {
+            return_errno_with_message!(Errno::EBUSY, "the device is already used for swapping");
+        }
+        areas
+            .iter()
+            .position(Option::is_none)
+            .ok_or_else(|| Error::with_message(Errno::EPERM, "too many swap areas"))?
+    };
+
+    let priority = priority.unwrap_or(*next_default_priority);
+    let area = SwapArea::new(index, name, device, priority)?;
+
+    let mut areas = SWAP_AREAS.lock();
+    if areas[index].is_some() {
+        return_errno_with_message!(Errno::EBUSY, "the swap area is turned on concurrently");
+    }
+    areas[index] = Some(Arc::new(area));
+    drop(areas);
+
+    if priority == *next_default_priority {
+        *next_default_priority = next_default_priority.saturating_sub(1);
+    }
+
+    Ok(())
+}
+
+/// Turns off swapping to the block device.
+///
+/// All pages in the swap area are read back into the RAM before the area is
+/// turned off.
+pub(crate) fn swap_off(device_id: DeviceId) -> Result<()> {
+    let area = SWAP_AREAS
+        .lock()
+        .iter()
+        .flatten()
+        .find(|area| area.device_id() == device_id)
+        .cloned()
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the device is not used for swapping"))?;
+
+    area.set_enabled(false);
+    if let Err(err) = swap_in_area(&area) {
+        area.set_enabled(true);
+        return Err(err);
+    }
+
+    let mut areas = SWAP_AREAS.lock();
+    // Some entries may be moved to other processes concurrently.
+    if area.nr_used() != 0 {
+        drop(areas);
+        area.set_enabled(true);
+        return_errno_with_message!(Errno::EBUSY, "the swap area is still in use");
+    }
+    areas[area.index()] = None;
+
+    Ok(())
+}
+
+/// Reads all pages in the swap area back into the RAM.
+fn swap_in_area(area: &SwapArea) -> Result<()> {
+    let processes = pid_table::pid_table_mut()
+        .iter_processes()
+        .collect::<Vec<_>>();
+
+    for process in processes {
+        let vmar = process.lock_vmar();
+        let Some(vmar) = vmar.as_ref() else {
+            continue;
+        };
+        vmar.swap_in_all(|entry| entry.area_index() == area.index())?;
+    }
+
+    Ok(())
+}
+
+/// Allocates a swap entry from the area with the highest priority.
+///
+/// The entry is returned with one reference held by the caller.
+pub(crate) fn alloc_entry() -> Option<SwapEntry> {
+    let mut areas = SWAP_AREAS
+        .lock()
+        .iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>();
+    areas.sort_by_key(|area| core::cmp::Reverse(area.priority()));
+
+    areas.iter().find_map(|area| area.alloc())
+}
+
+/// Adds a reference to the swap entry.
+///
+/// The caller must hold a reference to the entry.
+pub(crate) fn dup_entry(entry: SwapEntry) {
+    entry.area().dup(entry.offset());
+}
+
+/// Removes a reference to the swap entry.
+pub(crate) fn free_entry(entry: SwapEntry) {
+    entry.area().put(entry.offset());
+}
+
+/// Returns whether the page table entry is the only reference to the swap entry.
+pub(crate) fn is_exclusive(entry: SwapEntry) -> bool {
+    entry.area().count(entry.offset()) == 1
+}
+
+/// Writes the page out to the swap entry.
+///
+/// The page is kept in the swap cache until it is written, so page faults can
+/// find it before the write completes. If the write fails, the page stays in
+/// the swap cache until the entry is freed.
+///
+/// The caller must hold a reference to the entry and have mapped the entry in
+/// the page table.
+pub(crate) fn write_page(entry: SwapEntry, frame: &UFrame) -> Result<()> {
+    let area = entry.area();
+    area.write_page(entry.offset(), frame)?;
+    area.remove_cache(entry.offset());
+    Ok(())
+}
+
+/// Inserts the page of the swap entry into the swap cache.
+///
+/// The caller must hold a reference to the entry.
+pub(crate) fn insert_cache(entry: SwapEntry, frame: UFrame) {
+    entry.area().insert_cache(entry.offset(), frame);
+}
+
+/// Looks up the page of the swap entry in the swap cache.
+pub(crate) fn lookup_cache(entry: SwapEntry) -> Option<UFrame> {
+    entry.area().lookup_cache(entry.offset())
+}
+
+/// Reads the page of the swap entry into the swap cache.
+///
+/// The caller must hold a reference to the entry.
+pub(crate) fn read_into_cache(entry: SwapEntry) -> Result<()> {
+    let area = entry.area();
+    if area.lookup_cache(entry.offset()).is_some() {
+        return Ok(());
+    }
+
+    let frame = area.read_page(entry.offset())?;
+    area.insert_cache(entry.offset(), frame);
+    Ok(())
+}
+
+/// Returns whether any swap area is turned on.
+pub(crate) fn is_enabled() -> bool {
+    SWAP_AREAS.lock().iter().any(Option::is_some)
+}
+
+/// The statistics of a swap area.
+pub(crate) struct SwapAreaStat {
+    /// The path name of the device when the area is turned on.
+    pub(crate) name: String,
+    /// The number of usable pages.
+    pub(crate) nr_pages: usize,
+    /// The number of pages in use.
+    pub(crate) nr_used: usize,
+    pub(crate) priority: i16,
+}
+
+/// Returns the total number of usable pages in all swap areas that are turned on.
+pub(crate) fn nr_total_pages() -> usize {
+    SWAP_AREAS
+        .lock()
+        .iter()
+        .flatten()
+        .map(|area| area.nr_slots())
+        .sum()
+}
+
+/// Returns the number of pages in use in all swap areas that are turned on.
+pub(crate) fn nr_used_pages() -> usize {
+    let areas = SWAP_AREAS
+        .lock()
+        .iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    areas.iter().map(|area| area.nr_used()).sum()
+}
+
+/// Returns the statistics of all swap areas that are turned on.
+pub(crate) fn area_stats() -> Vec<SwapAreaStat> {
+    let areas = SWAP_AREAS
+        .lock()
+        .iter()
+        .flatten()
+        .cloned()
+        .collect::<Vec<_>>();
+
+    areas
+        .iter()
+        .map(|area| SwapAreaStat {
+            name: area.name().to_string(),
+            nr_pages: area.nr_slots(),
+            nr_used: area.nr_used(),
+            priority: area.priority(),
+        })
+        .collect()
+}
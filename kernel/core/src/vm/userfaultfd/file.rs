@@ -0,0 +1,623 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::{fmt::Display, ops::Range};
+
+use ostd::{mm::io::util::HasVmReaderWriter, task::Task};
+
+use super::{
+    PendingFault, UserfaultCtx, UserfaultEvent, UserfaultFeatures, UserfaultFlags, UserfaultMode,
+};
+use crate::{
+    context::current_userspace,
+    events::IoEvents,
+    fs::{
+        file::{
+            AccessMode, CreationFlags, FileCommon, FileLike, StatusFlags,
+            file_table::{FdFlags, FileDesc},
+        },
+        pseudofs::AnonInodeFs,
+    },
+    prelude::*,
+    process::{
+        UserNamespace,
+        credentials::capabilities::CapSet,
+        signal::{PollHandle, Pollable},
+    },
+    security::lsm::hooks as lsm_hooks,
+    util::ioctl::{RawIoctl, dispatch_ioctl},
+    vm::{
+        anon_page::alloc_anon_page,
+        vmar::{Vmar, is_userspace_vaddr_range},
+    },
+};
+
+/// `struct uffd_msg` in Linux.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct CUffdMsg {
+    event: u8,
+    reserved1: u8,
+    reserved2: u16,
+    reserved3: u32,
+    /// The arguments of the event, whose layout depends on the event type.
+    arg: [u64; 3],
+}
+
+impl CUffdMsg {
+    fn new(event: u8, arg: [u64; 3]) -> Self {
+        Self {
+            event,
+            reserved1: 0,
+            reserved2: 0,
+            reserved3: 0,
+            arg,
+        }
+    }
+}
+
+/// `struct uffdio_api` in Linux.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct CUffdioApi {
+    api: u64,
+    features: u64,
+    ioctls: u64,
+}
+
+/// `struct uffdio_range` in Linux.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct CUffdioRange {
+    start: u64,
+    len: u64,
+}
+
+/// `struct uffdio_register` in Linux.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct CUffdioRegister {
+    range: CUffdioRange,
+    mode: u64,
+    ioctls: u64,
+}
+
+/// `struct uffdio_copy` in Linux.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct CUffdioCopy {
+    dst: u64,
+    src: u64,
+    len: u64,
+    mode: u64,
+    /// The number of bytes copied, or the negated error number.
+    copy: i64,
+}
+
+/// `struct uffdio_zeropage` in Linux.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct CUffdioZeropage {
+    range: CUffdioRange,
+    mode: u64,
+    /// The number of bytes zeroed, or the negated error number.
+    zeropage: i64,
+}
+
+/// `struct uffdio_writeprotect` in Linux.
+#[repr(C)]
+#[derive(Clone, Copy, Debug, Pod)]
+struct CUffdioWriteprotect {
+    range: CUffdioRange,
+    mode: u64,
+}
+
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/userfaultfd.h>
+
+const UFFD_API: u64 = 0xAA;
+
+const UFFD_EVENT_PAGEFAULT: u8 = 0x12;
+const UFFD_EVENT_FORK: u8 = 0x13;
+const UFFD_EVENT_REMAP: u8 = 0x14;
+const UFFD_EVENT_UNMAP: u8 = 0x16;
+
+const UFFDIO_COPY_MODE_DONTWAKE: u64 = 1 << 0;
+const UFFDIO_COPY_MODE_WP: u64 = 1 << 1;
+const UFFDIO_ZEROPAGE_MODE_DONTWAKE: u64 = 1 << 0;
+const UFFDIO_WRITEPROTECT_MODE_WP: u64 = 1 << 0;
+const UFFDIO_WRITEPROTECT_MODE_DONTWAKE: u64 = 1 << 1;
+
+// The ioctl numbers, which are used to report the supported ioctls.
+const _UFFDIO_REGISTER: u64 = 0x00;
+const _UFFDIO_UNREGISTER: u64 = 0x01;
+const _UFFDIO_WAKE: u64 = 0x02;
+const _UFFDIO_COPY: u64 = 0x03;
+const _UFFDIO_ZEROPAGE: u64 = 0x04;
+const _UFFDIO_WRITEPROTECT: u64 = 0x06;
+const _UFFDIO_API: u64 = 0x3F;
+
+const UFFD_API_IOCTLS: u64 = 1 << _UFFDIO_REGISTER | 1 << _UFFDIO_UNREGISTER | 1 << _UFFDIO_API;
+const UFFD_API_RANGE_IOCTLS: u64 =
+    1 << _UFFDIO_WAKE | 1 << _UFFDIO_COPY | 1 << _UFFDIO_ZEROPAGE | 1 << _UFFDIO_WRITEPROTECT;
+
+mod ioctl_defs {
+    use super::{
+        CUffdioApi, CUffdioCopy, CUffdioRange, CUffdioRegister, CUffdioWriteprotect,
+        CUffdioZeropage,
+    };
+    use crate::util::ioctl::{InOutData, OutData, ioc};
+
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/userfaultfd.h>
+
+    // Note that the argument of `UFFDIO_UNREGISTER` and `UFFDIO_WAKE` is read by the kernel,
+    // which does not match the direction encoded in the ioctl commands.
+    pub(super) type Api          = ioc!(UFFDIO_API,          0xAA, 0x3F, InOutData<CUffdioApi>);
+    pub(super) type Register     = ioc!(UFFDIO_REGISTER,     0xAA, 0x00, InOutData<CUffdioRegister>);
+    pub(super) type Unregister   = ioc!(UFFDIO_UNREGISTER,   0xAA, 0x01, OutData<CUffdioRange>);
+    pub(super) type Wake         = ioc!(UFFDIO_WAKE,         0xAA, 0x02, OutData<CUffdioRange>);
+    pub(super) type CopyPages    = ioc!(UFFDIO_COPY,         0xAA, 0x03, InOutData<CUffdioCopy>);
+    pub(super) type ZeroPages    = ioc!(UFFDIO_ZEROPAGE,     0xAA, 0x04, InOutData<CUffdioZeropage>);
+    pub(super) type WriteProtect = ioc!(UFFDIO_WRITEPROTECT, 0xAA, 0x06, InOutData<CUffdioWriteprotect>);
+}
+
+/// Creates a userfaultfd for the address space of the current process, and installs it to the
+/// file table of the current thread.
+///
+/// The caller should check whether the current thread is allowed to create the userfaultfd.
+pub(crate) fn create_userfaultfd(flags: u32) -> Result<FileDesc> {
+    let flags = UserfaultFlags::from_bits(flags)
+        .ok_or_else(|| Error::with_message(Errno::EINVAL, "the flags are invalid"))?;
+
+    let current_task = Task::current().unwrap();
+    let thread_local = current_task.as_thread_local().unwrap();
+    let vmar = thread_local.vmar().borrow().as_ref().unwrap().clone_weak();
+
+    let file = UserfaultFile::from_ctx(UserfaultCtx::new(vmar, flags));
+    Ok(install_file(file))
+}
+
+/// Installs a userfaultfd to the file table of the current thread.
+fn install_file(file: Arc<UserfaultFile>) -> FileDesc {
+    let fd_flags = if file.ctx.flags.contains(UserfaultFlags::O_CLOEXEC) {
+        FdFlags::CLOEXEC
+    } else {
+        FdFlags::empty()
+    };
+
+    let current_task = Task::current().unwrap();
+    let thread_local = current_task.as_thread_local().unwrap();
+    let file_table = thread_local.borrow_file_table();
+    file_table.unwrap().write().insert(file, fd_flags)
+}
+
+/// A userfaultfd file.
+pub(crate) struct UserfaultFile {
+    ctx: Arc<UserfaultCtx>,
+    common: FileCommon,
+}
+
+impl UserfaultFile {
+    pub(super) fn from_ctx(ctx: Arc<UserfaultCtx>) -> Arc<Self> {
+        let status_flags = if ctx.flags.contains(UserfaultFlags::O_NONBLOCK) {
+            StatusFlags::O_NONBLOCK
+        } else {
+            StatusFlags::empty()
+        };
+        let pseudo_path = AnonInodeFs::new_path(|_| "anon_inode:[userfaultfd]".to_string());
+
+        Arc::new(Self {
+            ctx,
+            // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/userfaultfd.c>
+            common: FileCommon::new(pseudo_path, AccessMode::O_RDONLY, status_flags),
+        })
+    }
+
+    fn check_initialized(&self) -> Result<()> {
+        if self.ctx.state.lock().features.is_none() {
+            return_errno_with_message!(Errno::EINVAL, "the API handshake has not been done");
+        }
+        Ok(())
+    }
+
+    fn vmar(&self) -> Result<Arc<Vmar>> {
+        self.ctx
+            .vmar
+            .upgrade()
+            .ok_or_else(|| Error::with_message(Errno::ESRCH, "the address space has exited"))
+    }
+
+    /// Checks that the address space is not being changed.
+    ///
+    /// The address space is considered to be changing if there are unread events, so that the
+    /// faults can be resolved with the up-to-date knowledge of the address space.
+    fn check_not_changing(&self) -> Result<()> {
+        if !self.ctx.state.lock().events.is_empty() {
+            return_errno_with_message!(Errno::EAGAIN, "the address space is being changed");
+        }
+        Ok(())
+    }
+
+    fn api(&self, api: &CUffdioApi) -> Result<CUffdioApi> {
+        if api.api != UFFD_API {
+            return_errno_with_message!(Errno::EINVAL, "the API version is not supported");
+        }
+        let Some(features) = UserfaultFeatures::from_bits(api.features) else {
+            return_errno_with_message!(Errno::EINVAL, "the features are not supported");
+        };
+        if features.contains(UserfaultFeatures::EVENT_FORK) {
+            lsm_hooks::on_capable(lsm_hooks::CapableContext::new(
+                UserNamespace::get_init_singleton().as_ref(),
+                current_thread!().as_posix_thread().unwrap(),
+                CapSet::SYS_PTRACE,
+            ))?;
+        }
+
+        let mut state = self.ctx.state.lock();
+        if state.features.is_some() {
+            return_errno_with_message!(Errno::EINVAL, "the API handshake has been done");
+        }
+        state.features = Some(features);
+        drop(state);
+
+        Ok(CUffdioApi {
+            api: UFFD_API,
+            features: UserfaultFeatures::all().bits(),
+            ioctls: UFFD_API_IOCTLS,
+        })
+    }
+
+    fn register(&self, register: &CUffdioRegister) -> Result<u64> {
+        let Some(mode) = UserfaultMode::from_bits(register.mode) else {
+            return_errno_with_message!(Errno::EINVAL, "the mode is not supported");
+        };
+        if mode.is_empty() {
+            return_errno_with_message!(Errno::EINVAL, "no modes are specified");
+        }
+        let range = check_range(&register.range)?;
+
+        self.vmar()?.register_userfault(&self.ctx, range, mode)?;
+
+        let mut ioctls = UFFD_API_RANGE_IOCTLS;
+        if !mode.contains(UserfaultMode::WP) {
+            ioctls &= !(1 << _UFFDIO_WRITEPROTECT);
+        }
+        Ok(ioctls)
+    }
+
+    fn unregister(&self, range: &CUffdioRange) -> Result<()> {
+        let range = check_range(range)?;
+        self.vmar()?.unregister_userfault(&self.ctx, range)
+    }
+
+    fn wake(&self, range: &CUffdioRange) -> Result<()> {
+        let range = check_range(range)?;
+        self.ctx.wake_range(&range);
+        Ok(())
+    }
+
+    /// Fills the pages in the range, and returns the number of bytes filled.
+    ///
+    /// If `src` is `Some`, the pages are copied from the user space of the current process
+    /// starting at the address. Otherwise, the pages are zeroed.
+    ///
+    /// The pages are filled one by one. If a page cannot be filled, the number of bytes filled
+    /// before it is returned, or the error is returned if no pages are filled.
+    fn fill(
+        &self,
+        range: &Range<Vaddr>,
+        src: Option<Vaddr>,
+        is_write_protected: bool,
+    ) -> Result<usize> {
+        self.check_not_changing()?;
+        let vmar = self.vmar()?;
+
+        let mut filled = 0;
+        while filled < range.len() {
+            let res = (|| {
+                let page = alloc_anon_page(src.is_none())?;
+                if let Some(src) = src {
+                    let mut reader = current_userspace!().reader(src + filled, PAGE_SIZE)?;
+                    reader.read_fallible(&mut page.writer())?;
+                }
+                vmar.fill_userfault_page(
+                    &self.ctx,
+                    range.start + filled,
+                    page.into(),
+                    is_write_protected,
+                )
+            })();
+
+            match res {
+                Ok(()) => filled += PAGE_SIZE,
+                Err(err) if filled == 0 => return Err(err),
+                Err(_) => break,
+            }
+        }
+
+        Ok(filled)
+    }
+
+    /// Copies the pages and sets the number of bytes copied or the error in `copy.copy`.
+    fn copy(&self, copy: &mut CUffdioCopy) -> Result<()> {
+        if copy.mode & !(UFFDIO_COPY_MODE_DONTWAKE | UFFDIO_COPY_MODE_WP) != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the mode is invalid");
+        }
+        let range = check_range(&CUffdioRange {
+            start: copy.dst,
+            len: copy.len,
+        })?;
+        // Unlike the destination, the source does not have to be page-aligned.
+        if copy.src.checked_add(copy.len).is_none() {
+            return_errno_with_message!(Errno::EINVAL, "the source range overflows");
+        }
+
+        let res = self.fill(
+            &range,
+            Some(copy.src as Vaddr),
+            copy.mode & UFFDIO_COPY_MODE_WP != 0,
+        );
+        copy.copy = result_to_i64(&res);
+        let copied = res?;
+
+        if copy.mode & UFFDIO_COPY_MODE_DONTWAKE == 0 {
+            self.ctx.wake_range(&(range.start..range.start + copied));
+        }
+        if copied < range.len() {
+            return_errno_with_message!(Errno::EAGAIN, "the range is partially copied");
+        }
+        Ok(())
+    }
+
+    /// Zeroes the pages and sets the number of bytes zeroed or the error in
+    /// `zeropage.zeropage`.
+    fn zeropage(&self, zeropage: &mut CUffdioZeropage) -> Result<()> {
+        if zeropage.mode & !UFFDIO_ZEROPAGE_MODE_DONTWAKE != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the mode is invalid");
+        }
+        let range = check_range(&zeropage.range)?;
+
+        let res = self.fill(&range, None, false);
+        zeropage.zeropage = result_to_i64(&res);
+        let zeroed = res?;
+
+        if zeropage.mode & UFFDIO_ZEROPAGE_MODE_DONTWAKE == 0 {
+            self.ctx.wake_range(&(range.start..range.start + zeroed));
+        }
+        if zeroed < range.len() {
+            return_errno_with_message!(Errno::EAGAIN, "the range is partially zeroed");
+        }
+        Ok(())
+    }
+
+    fn write_protect(&self, write_protect: &CUffdioWriteprotect) -> Result<()> {
+        let mode = write_protect.mode;
+        if mode & !(UFFDIO_WRITEPROTECT_MODE_WP | UFFDIO_WRITEPROTECT_MODE_DONTWAKE) != 0 {
+            return_errno_with_message!(Errno::EINVAL, "the mode is invalid");
+        }
+        // Waking up the faulting threads makes no sense if the pages are still write-protected.
+        if mode & UFFDIO_WRITEPROTECT_MODE_WP != 0 && mode & UFFDIO_WRITEPROTECT_MODE_DONTWAKE != 0
+        {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "write-protecting pages cannot wake up the faulting threads"
+            );
+        }
+        let range = check_range(&write_protect.range)?;
+
+        self.check_not_changing()?;
+        let is_write_protected = mode & UFFDIO_WRITEPROTECT_MODE_WP != 0;
+        self.vmar()?
+            .write_protect_userfault(&self.ctx, range.clone(), is_write_protected)?;
+
+        if mode & (UFFDIO_WRITEPROTECT_MODE_WP | UFFDIO_WRITEPROTECT_MODE_DONTWAKE) == 0 {
+            self.ctx.wake_range(&range);
+        }
+        Ok(())
+    }
+
+    fn try_read_msg(&self) -> Result<CUffdMsg> {
+        let mut state = self.ctx.state.lock();
+
+        if let Some(fault) = state.faults.values_mut().find(|fault| !fault.is_read) {
+            fault.is_read = true;
+            let PendingFault { address, flags, .. } = *fault;
+            // TODO: Report the thread ID if `UFFD_FEATURE_THREAD_ID` is supported.
+            return Ok(CUffdMsg::new(
+                UFFD_EVENT_PAGEFAULT,
+                [flags, address as u64, 0],
+            ));
+        }
+
+        let Some(event) = state.events.pop_front() else {
+            return_errno_with_message!(Errno::EAGAIN, "no faults or events are pending");
+        };
+        drop(state);
+
+        let msg = match event {
+            UserfaultEvent::Fork(child_file) => {
+                let fd = install_file(child_file);
+                CUffdMsg::new(UFFD_EVENT_FORK, [u64::from(fd), 0, 0])
+            }
+            UserfaultEvent::Remap { from, to, len } => {
+                CUffdMsg::new(UFFD_EVENT_REMAP, [from as u64, to as u64, len as u64])
+            }
+            UserfaultEvent::Unmap { start, end } => {
+                CUffdMsg::new(UFFD_EVENT_UNMAP, [start as u64, end as u64, 0])
+            }
+        };
+        Ok(msg)
+    }
+}
+
+/// Converts the number of bytes filled or the error to the value reported to user space.
+fn result_to_i64(res: &Result<usize>) -> i64 {
+    match res {
+        Ok(len) => *len as i64,
+        Err(err) => -(err.error() as i64),
+    }
+}
+
+/// Checks that the range is page-aligned, non-empty, and in the user space.
+fn check_range(range: &CUffdioRange) -> Result<Range<Vaddr>> {
+    let (start, len) = (range.start as Vaddr, range.len as Vaddr);
+    if !start.is_multiple_of(PAGE_SIZE) || !len.is_multiple_of(PAGE_SIZE) {
+        return_errno_with_message!(Errno::EINVAL, "the range is not page-aligned");
+    }
+    if len == 0 {
+        return_errno_with_message!(Errno::EINVAL, "the range is empty");
+    }
+    if !is_userspace_vaddr_range(start, len) {
+        return_errno_with_message!(Errno::EINVAL, "the range is not in the user space");
+    }
+
+    Ok(start..start + len)
+}
+
+impl Drop for UserfaultFile {
+    fn drop(&mut self) {
+        self.ctx.release();
+    }
+}
+
+impl Pollable for UserfaultFile {
+    fn poll(&self, mask: IoEvents, poller: Option<&mut PollHandle>) -> IoEvents {
+        self.ctx
+            .pollee
+            .poll_with(mask, poller, || self.ctx.check_io_events())
+    }
+}
+
+impl FileLike for UserfaultFile {
+    fn read(&self, writer: &mut VmWriter) -> Result<usize> {
+        let msg_len = size_of::<CUffdMsg>();
+        if writer.avail() < msg_len {
+            return_errno_with_message!(Errno::EINVAL, "the message buffer is too small");
+        }
+        self.check_initialized()?;
+
+        // Only wait for the first message.
+        let msg = if self.common.is_nonblocking() {
+            self.try_read_msg()?
+        } else {
+            self.wait_events(IoEvents::IN, None, || self.try_read_msg())?
+        };
+        writer.write_val(&msg)?;
+
+        let mut read_len = msg_len;
+        while writer.avail() >= msg_len
+            && let Ok(msg) = self.try_read_msg()
+        {
+            writer.write_val(&msg)?;
+            read_len += msg_len;
+        }
+
+        Ok(read_len)
+    }
+
+    fn ioctl(&self, raw_ioctl: RawIoctl) -> Result<i32> {
+        use ioctl_defs::*;
+
+        if Api::try_from_raw(raw_ioctl).is_none() {
+            self.check_initialized()?;
+        }
+
+        dispatch_ioctl!(match raw_ioctl {
+            cmd @ Api => {
+                let api = cmd.read()?;
+                match self.api(&api) {
+                    Ok(api) => {
+                        cmd.write(&api)?;
+                        Ok(0)
+                    }
+                    Err(err) => {
+                        cmd.write(&CUffdioApi::new_zeroed())?;
+                        Err(err)
+                    }
+                }
+            }
+            cmd @ Register => {
+                let mut register = cmd.read()?;
+                register.ioctls = self.register(&register)?;
+                cmd.write(&register)?;
+                Ok(0)
+            }
+            _cmd @ Unregister => {
+                let range = current_userspace!().read_val::<CUffdioRange>(raw_ioctl.arg())?;
+                self.unregister(&range)?;
+                Ok(0)
+            }
+            _cmd @ Wake => {
+                let range = current_userspace!().read_val::<CUffdioRange>(raw_ioctl.arg())?;
+                self.wake(&range)?;
+                Ok(0)
+            }
+            cmd @ CopyPages => {
+                let mut copy = cmd.read()?;
+                let res = self.copy(&mut copy);
+                cmd.write(&copy)?;
+                res?;
+                Ok(0)
+            }
+            cmd @ ZeroPages => {
+                let mut zeropage = cmd.read()?;
+                let res = self.zeropage(&mut zeropage);
+                cmd.write(&zeropage)?;
+                res?;
+                Ok(0)
+            }
+            cmd @ WriteProtect => {
+                let write_protect = cmd.read()?;
+                self.write_protect(&write_protect)?;
+                Ok(0)
+            }
+            _ => return_errno_with_message!(Errno::ENOTTY, "the ioctl command is unknown"),
+        })
+    }
+
+    fn common(&self) -> &FileCommon {
+        &self.common
+    }
+
+    fn dump_proc_fdinfo(self: Arc<Self>, fd_flags: FdFlags) -> Box<dyn Display> {
+        struct FdInfo {
+            flags: u32,
+            num_pending: usize,
+            num_total: usize,
+            features: u64,
+        }
+
+        impl Display for FdInfo {
+            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+                writeln!(f, "pos:\t{}", 0)?;
+                writeln!(f, "flags:\t0{:o}", self.flags)?;
+                writeln!(f, "mnt_id:\t{}", AnonInodeFs::mount_node().id())?;
+                writeln!(f, "ino:\t{}", AnonInodeFs::shared_inode().ino())?;
+                // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/userfaultfd.c>
+                writeln!(f, "pending:\t{}", self.num_pending)?;
+                writeln!(f, "total:\t{}", self.num_total)?;
+                writeln!(
+                    f,
+                    "API:\t{:x}:{:x}:{:x}",
+                    UFFD_API, self.features, UFFD_API_IOCTLS
+                )
+            }
+        }
+
+        let mut flags = self.common.status_flags().bits() | self.common.access_mode() as u32;
+        if fd_flags.contains(FdFlags::CLOEXEC) {
+            flags |= CreationFlags::O_CLOEXEC.bits();
+        }
+
+        let state = self.ctx.state.lock();
+        let num_pending = state.faults.values().filter(|fault| fault.is_read).count();
+        let num_total = state.faults.len();
+        let features = state.features.map_or(0, |features| features.bits());
+        drop(state);
+
+        Box::new(FdInfo {
+            flags,
+            num_pending,
+            num_total,
+            features,
+        })
+    }
+}
@@ -0,0 +1,449 @@
+// SPDX-License-Identifier: MPL-2.0
+
+//! Userfaultfd, which lets user space handle page faults.
+//!
+//! A userfaultfd is created by the `userfaultfd` system call or by the `USERFAULTFD_IOC_NEW` ioctl
+//! of `/dev/userfaultfd`. After the `UFFDIO_API` handshake, ranges of private anonymous mappings
+//! can be registered with the userfaultfd in two modes:
+//!  - In the missing mode, accessing a page that is not mapped yet is reported to the userfaultfd.
+//!  - In the write-protect mode, writing to a page that is write-protected by
+//!    `UFFDIO_WRITEPROTECT` is reported to the userfaultfd.
+//!
+//! The faulting thread waits until the fault handler in user space resolves the fault (e.g., by
+//! `UFFDIO_COPY`) and wakes it up. If the handler asks for them, non-cooperative events (i.e.,
+//! `fork`, `mremap`, and `munmap` of registered mappings) are also reported to the userfaultfd.
+//!
+//! Unlike Linux, the thread that triggers an event does not wait until the event is read.
+//! Instead, the ioctls that resolve faults fail with `EAGAIN` as long as there are unread events,
+//! which is also what Linux does if the address space is being changed.
+
+use core::{
+    fmt::{Debug, Formatter, Result as FmtResult},
+    ops::Range,
+};
+
+use ostd::sync::WaitQueue;
+
+pub(crate) use self::file::create_userfaultfd;
+use self::{file::UserfaultFile, wp_ranges::WpRanges};
+use crate::{
+    events::IoEvents,
+    fs::file::{CreationFlags, StatusFlags},
+    prelude::*,
+    process::signal::Pollee,
+    vm::vmar::Vmar,
+};
+
+mod file;
+mod wp_ranges;
+
+bitflags! {
+    /// The flags to create a userfaultfd.
+    pub(crate) struct UserfaultFlags: u32 {
+        /// Only page faults from user mode are handled.
+        const USER_MODE_ONLY = 1 << 0;
+        const O_NONBLOCK     = StatusFlags::O_NONBLOCK.bits();
+        const O_CLOEXEC      = CreationFlags::O_CLOEXEC.bits();
+    }
+}
+
+bitflags! {
+    /// The features of a userfaultfd.
+    ///
+    /// Only the supported features are listed.
+    //
+    // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/userfaultfd.h>
+    pub(crate) struct UserfaultFeatures: u64 {
+        const PAGEFAULT_FLAG_WP = 1 << 0;
+        const EVENT_FORK        = 1 << 1;
+        const EVENT_REMAP       = 1 << 2;
+        const EVENT_UNMAP       = 1 << 6;
+    }
+}
+
+bitflags! {
+    /// The modes to register memory with a userfaultfd.
+    pub(crate) struct UserfaultMode: u64 {
+        /// Reports accesses to missing pages.
+        const MISSING = 1 << 0;
+        /// Reports writes to write-protected pages.
+        const WP      = 1 << 1;
+    }
+}
+
+// Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/include/uapi/linux/userfaultfd.h>
+const UFFD_PAGEFAULT_FLAG_WRITE: u64 = 1 << 0;
+const UFFD_PAGEFAULT_FLAG_WP: u64 = 1 << 1;
+
+/// The registration of a memory mapping with a userfaultfd.
+#[derive(Clone)]
+pub(crate) struct UserfaultRegistration {
+    ctx: Arc<UserfaultCtx>,
+    mode: UserfaultMode,
+}
+
+impl UserfaultRegistration {
+    pub(crate) fn new(ctx: Arc<UserfaultCtx>, mode: UserfaultMode) -> Self {
+        Self { ctx, mode }
+    }
+
+    /// Returns the userfaultfd context.
+    pub(crate) fn ctx(&self) -> &Arc<UserfaultCtx> {
+        &self.ctx
+    }
+
+    /// Returns the registration mode.
+    pub(crate) fn mode(&self) -> UserfaultMode {
+        self.mode
+    }
+}
+
+impl PartialEq for UserfaultRegistration {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.ctx, &other.ctx) && self.mode == other.mode
+    }
+}
+
+impl Debug for UserfaultRegistration {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.debug_struct("UserfaultRegistration")
+            .field("mode", &self.mode)
+            .finish_non_exhaustive()
+    }
+}
+
+/// The context of a userfaultfd.
+///
+/// A context is shared by the userfaultfd file and the mappings registered with it.
+pub(crate) struct UserfaultCtx {
+    /// The address space that the userfaultfd is created for.
+    vmar: Weak<Vmar>,
+    flags: UserfaultFlags,
+    state: SpinLock<UserfaultState>,
+    /// The wait queue for the threads waiting for faults to be resolved.
+    wait_queue: WaitQueue,
+    /// The pollee of the userfaultfd file.
+    pollee: Pollee,
+}
+
+struct UserfaultState {
+    /// The features, or `None` if the `UFFDIO_API` handshake has not been done.
+    features: Option<UserfaultFeatures>,
+    /// The pending faults, in the order that they occur.
+    faults: BTreeMap<u64, PendingFault>,
+    next_fault_id: u64,
+    /// The unread events.
+    events: VecDeque<UserfaultEvent>,
+    /// The write-protected pages.
+    //
+    // Linux records the write protection in a software bit of page table entries, which is not
+    // available in OSTD. Therefore, the write-protected pages are recorded here.
+    write_protected: WpRanges,
+    /// Whether the userfaultfd file has been closed.
+    is_released: bool,
+}
+
+struct PendingFault {
+    address: Vaddr,
+    flags: u64,
+    is_read: bool,
+}
+
+/// A non-cooperative event.
+enum UserfaultEvent {
+    /// The registered mappings are copied to a child process, which has its own userfaultfd.
+    Fork(Arc<UserfaultFile>),
+    /// The registered mapping is moved.
+    Remap { from: Vaddr, to: Vaddr, len: usize },
+    /// The registered mapping is unmapped.
+    Unmap { start: Vaddr, end: Vaddr },
+}
+
+impl UserfaultCtx {
+    fn new(vmar: Weak<Vmar>, flags: UserfaultFlags) -> Arc<Self> {
+        Arc::new(Self {
+            vmar,
+            flags,
+            state: SpinLock::new(UserfaultState {
+                features: None,
+                faults: BTreeMap::new(),
+                next_fault_id: 0,
+                events: VecDeque::new(),
+                write_protected: WpRanges::new(),
+                is_released: false,
+            }),
+            wait_queue: WaitQueue::new(),
+            pollee: Pollee::new(),
+        })
+    }
+
+    /// Creates the context for the child process that inherits the registered mappings.
+    ///
+    /// The child context has the same flags and features as this context.
+    pub(crate) fn new_fork(&self, vmar: Weak<Vmar>) -> Arc<Self> {
+        let ctx = Self::new(vmar, self.flags);
+        ctx.state.lock().features = self.state.lock().features;
+        ctx
+    }
+
+    /// Returns the features.
+    ///
+    /// If the `UFFDIO_API` handshake has not been done, no features are returned.
+    pub(crate) fn features(&self) -> UserfaultFeatures {
+        self.state
+            .lock()
+            .features
+            .unwrap_or(UserfaultFeatures::empty())
+    }
+
+    /// Reports a page fault at the page if the page fault should be handled by user space.
+    ///
+    /// The caller should hold the page table lock of the page, so that the page cannot be
+    /// populated or write-protected concurrently.
+    ///
+    /// If the page fault is reported, this method returns a [`FaultWaiter`] that should be used
+    /// to wait for the fault to be resolved after releasing the locks.
+    pub(crate) fn report_fault(
+        self: &Arc<Self>,
+        mode: UserfaultMode,
+        page_addr: Vaddr,
+        is_present: bool,
+        is_write: bool,
+        is_user: bool,
+    ) -> Result<Option<FaultWaiter>> {
+        let mut state = self.state.lock();
+        if state.is_released {
+            return Ok(None);
+        }
+
+        let flags = if !is_present && mode.contains(UserfaultMode::MISSING) {
+            if is_write {
+                UFFD_PAGEFAULT_FLAG_WRITE
+            } else {
+                0
+            }
+        } else if is_present
+            && is_write
+            && mode.contains(UserfaultMode::WP)
+            && state.write_protected.contains(page_addr)
+        {
+            UFFD_PAGEFAULT_FLAG_WRITE | UFFD_PAGEFAULT_FLAG_WP
+        } else {
+            return Ok(None);
+        };
+
+        if !is_user && self.flags.contains(UserfaultFlags::USER_MODE_ONLY) {
+            return_errno_with_message!(
+                Errno::EFAULT,
+                "the userfaultfd only handles page faults from user mode"
+            );
+        }
+
+        let id = state.next_fault_id;
+        state.next_fault_id += 1;
+        state.faults.insert(
+            id,
+            PendingFault {
+                address: page_addr,
+                flags,
+                is_read: false,
+            },
+        );
+
+        Ok(Some(FaultWaiter {
+            ctx: self.clone(),
+            id,
+        }))
+    }
+
+    /// Wakes up the threads waiting for the faults in the range.
+    pub(crate) fn wake_range(&self, range: &Range<Vaddr>) {
+        self.state
+            .lock()
+            .faults
+            .retain(|_, fault| !range.contains(&fault.address));
+        self.wait_queue.wake_all();
+    }
+
+    /// Returns whether the page is write-protected.
+    pub(crate) fn is_write_protected(&self, page_addr: Vaddr) -> bool {
+        self.state.lock().write_protected.contains(page_addr)
+    }
+
+    /// Sets whether the pages in the range are write-protected.
+    pub(crate) fn set_write_protected(&self, range: Range<Vaddr>, is_write_protected: bool) {
+        let mut state = self.state.lock();
+        if is_write_protected {
+            state.write_protected.insert(range);
+        } else {
+            state.write_protected.remove(&range);
+        }
+    }
+
+    /// Clears the write protection of the pages in the range.
+    pub(crate) fn clear_write_protected(&self, range: &Range<Vaddr>) {
+        self.state.lock().write_protected.remove(range);
+    }
+
+    /// Moves the write protection of the pages in the range to the pages starting at `new_start`.
+    pub(crate) fn move_write_protected(&self, range: &Range<Vaddr>, new_start: Vaddr) {
+        let mut state = self.state.lock();
+        let ranges = state.write_protected.intersections(range);
+        state.write_protected.remove(range);
+        for moved in ranges {
+            state
+                .write_protected
+                .insert(moved.start - range.start + new_start..moved.end - range.start + new_start);
+        }
+    }
+
+    /// Copies the write protection of the pages in the range from another context.
+    pub(crate) fn copy_write_protected(&self, from: &UserfaultCtx, range: &Range<Vaddr>) {
+        let ranges = from.state.lock().write_protected.intersections(range);
+        let mut state = self.state.lock();
+        for copied in ranges {
+            state.write_protected.insert(copied);
+        }
+    }
+
+    /// Reports that the registered mappings are copied to a child process.
+    ///
+    /// The event is reported only if the `EVENT_FORK` feature is enabled.
+    pub(crate) fn notify_fork(&self, child_ctx: Arc<UserfaultCtx>) {
+        if !self.features().contains(UserfaultFeatures::EVENT_FORK) {
+            return;
+        }
+
+        let child_file = UserfaultFile::from_ctx(child_ctx);
+        let mut state = self.state.lock();
+        if state.is_released {
+            drop(state);
+            // Closing the child userfaultfd unregisters the mappings in the child process.
+            drop(child_file);
+            return;
+        }
+        state.events.push_back(UserfaultEvent::Fork(child_file));
+        drop(state);
+
+        self.pollee.notify(IoEvents::IN);
+    }
+
+    /// Reports that a registered mapping is moved.
+    ///
+    /// The event is reported only if the `EVENT_REMAP` feature is enabled.
+    pub(crate) fn notify_remap(&self, from: Vaddr, to: Vaddr, len: usize) {
+        self.push_event(
+            UserfaultFeatures::EVENT_REMAP,
+            UserfaultEvent::Remap { from, to, len },
+        );
+    }
+
+    /// Reports that registered mappings in the range are unmapped.
+    ///
+    /// The event is reported only if the `EVENT_UNMAP` feature is enabled.
+    pub(crate) fn notify_unmap(&self, range: &Range<Vaddr>) {
+        self.push_event(
+            UserfaultFeatures::EVENT_UNMAP,
+            UserfaultEvent::Unmap {
+                start: range.start,
+                end: range.end,
+            },
+        );
+    }
+
+    fn push_event(&self, feature: UserfaultFeatures, event: UserfaultEvent) {
+        let mut state = self.state.lock();
+        if state.is_released
+            || !state
+                .features
+                .is_some_and(|features| features.contains(feature))
+        {
+            return;
+        }
+
+        // Unmapping a range that covers multiple registered mappings reports only one event.
+        if let (
+            UserfaultEvent::Unmap { start, end },
+            Some(UserfaultEvent::Unmap {
+                start: last_start,
+                end: last_end,
+            }),
+        ) = (&event, state.events.back())
+            && start == last_start
+            && end == last_end
+        {
+            return;
+        }
+
+        state.events.push_back(event);
+        drop(state);
+
+        self.pollee.notify(IoEvents::IN);
+    }
+
+    /// Releases the context when the userfaultfd file is closed.
+    ///
+    /// The waiting threads are woken up and the registered mappings are unregistered.
+    fn release(self: &Arc<Self>) {
+        let events = {
+            let mut state = self.state.lock();
+            state.is_released = true;
+            state.faults.clear();
+            state.write_protected.clear();
+            core::mem::take(&mut state.events)
+        };
+        self.wait_queue.wake_all();
+
+        // Dropping the events may close the userfaultfd files of child processes, which
+        // unregisters the mappings in the child processes.
+        drop(events);
+
+        if let Some(vmar) = self.vmar.upgrade() {
+            vmar.unregister_userfault_all(self);
+        }
+    }
+
+    fn check_io_events(&self) -> IoEvents {
+        let state = self.state.lock();
+
+        // Reference: <https://elixir.bootlin.com/linux/v6.16.5/source/fs/userfaultfd.c>
+        if state.features.is_none() {
+            return IoEvents::ERR;
+        }
+        if !state.events.is_empty() || state.faults.values().any(|fault| !fault.is_read) {
+            return IoEvents::IN;
+        }
+
+        IoEvents::empty()
+    }
+}
+
+/// A waiter for a page fault that is reported to a userfaultfd.
+#[must_use]
+pub(crate) struct FaultWaiter {
+    ctx: Arc<UserfaultCtx>,
+    id: u64,
+}
+
+impl FaultWaiter {
+    /// Notifies the userfaultfd reader and waits until the fault is resolved.
+    ///
+    /// The fault is also considered resolved if the userfaultfd file is closed.
+    ///
+    /// # Errors
+    ///
+    /// Returns `EINTR` if the wait is interrupted by a signal.
+    pub(crate) fn wait(self) -> Result<()> {
+        self.ctx.pollee.notify(IoEvents::IN);
+
+        let res = self.ctx.wait_queue.pause_until(|| {
+            let state = self.ctx.state.lock();
+            (state.is_released || !state.faults.contains_key(&self.id)).then_some(())
+        });
+        if res.is_err() {
+            self.ctx.state.lock().faults.remove(&self.id);
+        }
+
+        res
+    }
+}
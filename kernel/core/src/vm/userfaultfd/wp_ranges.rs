@@ -0,0 +1,96 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::ops::Range;
+
+use crate::prelude::*;
+
+/// The write-protected pages of a userfaultfd, recorded as disjoint address ranges.
+///
+/// Adjacent ranges are merged, so write-protecting a large populated range only takes a single
+/// entry.
+pub(super) struct WpRanges {
+    /// The ranges, keyed by their start addresses and mapped to their end addresses.
+    ranges: BTreeMap<Vaddr, Vaddr>,
+}
+
+impl WpRanges {
+    pub(super) const fn new() -> Self {
+        Self {
+            ranges: BTreeMap::new(),
+        }
+    }
+
+    /// Returns whether the address is in one of the ranges.
+    pub(super) fn contains(&self, addr: Vaddr) -> bool {
+        self.ranges
+            .range(..=addr)
+            .next_back()
+            .is_some_and(|(_, &end)| addr < end)
+    }
+
+    /// Adds the range, merging it with the overlapping and adjacent ranges.
+    pub(super) fn insert(&mut self, range: Range<Vaddr>) {
+        if range.is_empty() {
+            return;
+        }
+
+        let mut start = range.start;
+        let mut end = range.end;
+
+        if let Some((&prev_start, &prev_end)) = self.ranges.range(..=start).next_back()
+            && prev_end >= start
+        {
+            start = prev_start;
+            end = end.max(prev_end);
+        }
+        while let Some((&next_start, &next_end)) = self.ranges.range(start..=end).next() {
+            end = end.max(next_end);
+            self.ranges.remove(&next_start);
+        }
+
+        self.ranges.insert(start, end);
+    }
+
+    /// Removes the range, splitting the ranges that partially overlap with it.
+    pub(super) fn remove(&mut self, range: &Range<Vaddr>) {
+        if range.is_empty() {
+            return;
+        }
+
+        if let Some((&prev_start, &prev_end)) = self.ranges.range(..range.start).next_back()
+            && prev_end > range.start
+        {
+            self.ranges.insert(prev_start, range.start);
+            if prev_end > range.end {
+                self.ranges.insert(range.end, prev_end);
+                return;
+            }
+        }
+        while let Some((&next_start, &next_end)) = self.ranges.range(range.start..range.end).next()
+        {
+            self.ranges.remove(&next_start);
+            if next_end > range.end {
+                self.ranges.insert(range.end, next_end);
+            }
+        }
+    }
+
+    /// Returns the parts of the ranges that are within the specified range.
+    pub(super) fn intersections(&self, range: &Range<Vaddr>) -> Vec<Range<Vaddr>> {
+        let first = self
+            .ranges
+            .range(..range.start)
+            .next_back()
+            .filter(|(_, end)| **end > range.start);
+
+        first
+            .into_iter()
+            .chain(self.ranges.range(range.start..range.end))
+            .map(|(&start, &end)| start.max(range.start)..end.min(range.end))
+            .collect()
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.ranges.clear();
+    }
+}
@@ -15,6 +15,7 @@ use ostd::mm::Vaddr;
 pub(crate) use self::{
     handle::VmarHandle,
     rmap::{Rmap, RmapEntry},
+    vm_mapping::VmMapping,
     vmar_impls::{
         RssType, Vmar, map::VmarMapOffset, page_fault::PageFaultInfo, remap::RemapOldMappingAction,
     },
@@ -29,7 +30,7 @@ pub(crate) fn is_userspace_vaddr(vaddr: Vaddr) -> bool {
 }
 
 /// Returns whether `vaddr` and `len` specify a legal user space virtual address range.
-fn is_userspace_vaddr_range(vaddr: Vaddr, len: usize) -> bool {
+pub(crate) fn is_userspace_vaddr_range(vaddr: Vaddr, len: usize) -> bool {
     vaddr >= VMAR_LOWEST_ADDR
         && VMAR_CAP_ADDR
             .checked_sub(vaddr)
@@ -13,8 +13,10 @@ use aster_util::printer::VmPrinter;
 use ostd::{
     io::IoMem,
     mm::{
-        CachePolicy, Frame, FrameAllocOptions, PageFlags, PageProperty, UFrame, VmSpace,
-        io::util::HasVmReaderWriter, tlb::TlbFlushOp, vm_space::VmQueriedItem,
+        CachePolicy, PageFlags, PageProperty, UFrame, VmSpace,
+        io::util::HasVmReaderWriter,
+        tlb::TlbFlushOp,
+        vm_space::{CursorMut, VmQueriedItem},
     },
     task::disable_preempt,
 };
@@ -28,11 +30,15 @@ use crate::{
         file::FileLike,
         vfs::{inode::Inode, path::PathResolver},
     },
+    ipc::shm::ShmAttach,
     prelude::*,
     process::LockedHeap,
     vm::{
+        anon_page::{AnonPage, alloc_anon_page},
         page_cache::{CachePage, Vmo, VmoCommitError, VmoMapMode},
         perms::VmPerms,
+        swap::{self, SwapEntry},
+        userfaultfd::{UserfaultMode, UserfaultRegistration},
     },
 };
 
@@ -87,6 +93,14 @@ pub(crate) struct VmMapping {
     ///
     /// All pages within the same `VmMapping` have the same permissions.
     perms: VmPerms,
+    /// Whether the mapping is excluded from core dumps.
+    ///
+    /// This is set by `MADV_DONTDUMP` and is always set for device mappings.
+    dont_dump: bool,
+    /// The registration with a userfaultfd.
+    ///
+    /// Only private anonymous mappings can be registered.
+    userfault: Option<UserfaultRegistration>,
 }
 
 impl Debug for VmMapping {
@@ -99,6 +113,8 @@ impl Debug for VmMapping {
             .field("is_shared", &self.is_shared)
             .field("handle_page_faults_around", &self.handle_page_faults_around)
             .field("perms", &self.perms)
+            .field("dont_dump", &self.dont_dump)
+            .field("userfault", &self.userfault)
             .finish()
     }
 }
@@ -121,6 +137,9 @@ impl VmMapping {
         handle_page_faults_around: bool,
         perms: VmPerms,
     ) -> Self {
+        // Like Linux's `VM_IO` mappings, device mappings are never dumped.
+        let dont_dump = matches!(mapped_mem, MappedMemory::Device);
+
         Self {
             map_size,
             map_to_addr,
@@ -129,6 +148,8 @@ impl VmMapping {
             is_shared,
             handle_page_faults_around,
             perms,
+            dont_dump,
+            userfault: None,
         }
     }
 
@@ -136,6 +157,7 @@ impl VmMapping {
         VmMapping {
             mapped_mem: self.mapped_mem.dup(),
             file: self.file.clone(),
+            userfault: self.userfault.clone(),
             ..*self
         }
     }
@@ -153,6 +175,7 @@ impl VmMapping {
             map_to_addr: va,
             mapped_mem: self.mapped_mem.dup_at_offset(offset_in_mapping),
             file: self.file.clone(),
+            userfault: self.userfault.clone(),
             ..*self
         }
     }
@@ -177,11 +200,49 @@ impl VmMapping {
         self.perms
     }
 
+    /// Returns whether the mapping is shared.
+    pub(crate) fn is_shared(&self) -> bool {
+        self.is_shared
+    }
+
+    /// Returns whether the mapping is excluded from core dumps.
+    pub(crate) fn is_dont_dump(&self) -> bool {
+        self.dont_dump
+    }
+
+    /// Returns whether the mapping is anonymous.
+    pub(super) fn is_anonymous(&self) -> bool {
+        matches!(self.mapped_mem, MappedMemory::Anonymous)
+    }
+
+    /// Returns the registration with a userfaultfd.
+    pub(super) fn userfault(&self) -> Option<&UserfaultRegistration> {
+        self.userfault.as_ref()
+    }
+
+    /// Returns whether the page is write-protected by a userfaultfd.
+    fn is_userfault_write_protected(&self, page_addr: Vaddr) -> bool {
+        self.userfault.as_ref().is_some_and(|userfault| {
+            userfault.mode().contains(UserfaultMode::WP)
+                && userfault.ctx().is_write_protected(page_addr)
+        })
+    }
+
     /// Returns the inode of the file that backs the mapping.
     pub(crate) fn inode(&self) -> Option<&Arc<dyn Inode>> {
         self.file.as_ref().map(|file| file.path().inode())
     }
 
+    /// Returns the file that backs the mapping.
+    pub(crate) fn file(&self) -> Option<&Arc<dyn FileLike>> {
+        self.file.as_ref()
+    }
+
+    /// Returns the offset in the VMO where the mapping starts if this mapping is VMO-backed.
+    pub(crate) fn vmo_offset(&self) -> Option<usize> {
+        self.vmo().map(|vmo| vmo.offset())
+    }
+
     /// Returns a reference to the VMO if this mapping is VMO-backed.
     pub(super) fn vmo(&self) -> Option<&MappedVmo> {
         match &self.mapped_mem {
@@ -242,6 +303,11 @@ impl VmMapping {
         Ok(Some((Arc::downgrade(mapped_vmo.vmo()), offset)))
     }
 
+    /// Returns whether this mapping maps device memory.
+    pub(super) fn is_device(&self) -> bool {
+        matches!(self.mapped_mem, MappedMemory::Device)
+    }
+
     /// Returns whether this mapping can be expanded.
     ///
     /// Device mappings cannot be expanded as they represent fixed-size MMIO
@@ -338,13 +404,15 @@ impl VmMapping {
 
             #[cfg(any(target_arch = "x86_64", target_arch = "riscv64"))]
             if let Some(vmo) = self.vmo() {
-                use crate::vdso::{VDSO_VMO_LAYOUT, vdso_vmo};
+                use crate::vdso::VDSO_VMO_LAYOUT;
 
-                if let Some(vdso_vmo) = vdso_vmo()
+                if let Some(vdso_vmo) = process_vm.vdso_vmo()
                     && Arc::ptr_eq(vmo.vmo(), &vdso_vmo)
                 {
                     let offset = vmo.offset();
-                    if offset == VDSO_VMO_LAYOUT.data_segment_offset {
+                    if offset == VDSO_VMO_LAYOUT.data_segment_offset
+                        || offset == VDSO_VMO_LAYOUT.time_ns_data_segment_offset
+                    {
                         return Some(Cow::Borrowed("[vvar]"));
                     } else if offset == VDSO_VMO_LAYOUT.text_segment_offset {
                         return Some(Cow::Borrowed("[vdso]"));
@@ -436,7 +504,7 @@ impl VmMapping {
         )
     }
 
-    fn check_perms_for_page_fault(&self, page_fault_info: &PageFaultInfo) -> Result<()> {
+    pub(super) fn check_perms_for_page_fault(&self, page_fault_info: &PageFaultInfo) -> Result<()> {
         debug!(
             "self.perms {:?}, page_fault_info.required_perms {:?}, self.range {:?}",
             self.perms,
@@ -492,6 +560,12 @@ impl VmMapping {
                     }
                     assert!(is_write);
 
+                    // The page may be write-protected by a userfaultfd after the check in
+                    // `Vmar::handle_page_fault`. Retrying the access will report the fault.
+                    if self.is_userfault_write_protected(va.start) {
+                        return Ok(());
+                    }
+
                     // For shared mappings, ensure that the VMO allows the page to be written.
                     if self.is_shared
                         && let MappedMemory::Vmo(vmo) = &self.mapped_mem
@@ -542,6 +616,44 @@ impl VmMapping {
                         "device memory page faults cannot be resolved"
                     );
                 }
+                Some(VmQueriedItem::Swapped { entry }) => {
+                    let entry = SwapEntry::from_raw(entry);
+                    let Some(cached_frame) = swap::lookup_cache(entry) else {
+                        // Hold a reference to the entry, since the page may
+                        // be unmapped by others once the cursor is dropped.
+                        swap::dup_entry(entry);
+                        drop(cursor);
+                        drop(preempt_guard);
+                        let res = swap::read_into_cache(entry);
+                        swap::free_entry(entry);
+                        res?;
+                        continue 'retry;
+                    };
+
+                    // If the entry is shared with other processes (e.g., after
+                    // `fork`), the cached frame must be left for them.
+                    let frame = if swap::is_exclusive(entry) {
+                        cached_frame
+                    } else {
+                        duplicate_frame(&cached_frame)?.into()
+                    };
+
+                    let mut page_flags = PageFlags::from(self.perms) | PageFlags::ACCESSED;
+                    if is_write {
+                        page_flags |= PageFlags::DIRTY;
+                    }
+                    if self.is_userfault_write_protected(va.start) {
+                        page_flags -= PageFlags::W;
+                    }
+                    let map_prop = PageProperty::new_user(page_flags, CachePolicy::Writeback);
+
+                    // Unmapping the entry releases its reference.
+                    cursor.unmap(PAGE_SIZE);
+                    cursor.jump(va.start).unwrap();
+                    cursor.map(frame, map_prop);
+                    rss_delta.add(RssType::Anon, 1);
+                    rss_delta.add(RssType::Swap, -1);
+                }
                 None => {
                     // Map a new frame to the page fault address.
                     let (frame, is_readonly) = match self.prepare_page(page_aligned_addr, is_write)
@@ -595,7 +707,7 @@ impl VmMapping {
             MappedMemory::Vmo(vmo) => vmo,
             MappedMemory::Anonymous => {
                 // Anonymous mapping. Allocate a new frame.
-                return Ok((FrameAllocOptions::new().alloc_frame()?.into(), is_readonly));
+                return Ok((alloc_anon_page(true)?.into(), is_readonly));
             }
             MappedMemory::Device => {
                 // Device memory is populated when the memory mapping is created.
@@ -609,7 +721,7 @@ impl VmMapping {
         let page_offset = page_aligned_addr - self.map_to_addr;
         if !self.is_shared && page_offset >= vmo.valid_size() {
             // The page index is outside the VMO. This is only allowed in private mapping.
-            return Ok((FrameAllocOptions::new().alloc_frame()?.into(), is_readonly));
+            return Ok((alloc_anon_page(true)?.into(), is_readonly));
         }
 
         let (page, mode) =
@@ -746,6 +858,7 @@ impl VmMapping {
             map_to_addr: self.map_to_addr,
             mapped_mem: l_mapped_mem,
             file: self.file.clone(),
+            userfault: self.userfault.clone(),
             ..self
         };
         let right = Self {
@@ -834,18 +947,128 @@ impl VmMapping {
 /************************** VM Space operations ******************************/
 
 impl VmMapping {
-    /// Unmaps the mapping from the VM space,
-    /// and returns the number of unmapped pages.
-    pub(super) fn unmap(self, vm_space: &VmSpace) -> usize {
+    /// Unmaps the mapping from the VM space.
+    pub(super) fn unmap(self, vm_space: &VmSpace, rss_delta: &mut RssDelta) {
         let preempt_guard = disable_preempt();
         let range = self.range();
         let mut cursor = vm_space.cursor_mut(&preempt_guard, &range).unwrap();
 
-        let num_unmapped = cursor.unmap(range.len());
+        self.unmap_pages(&mut cursor, range.len(), rss_delta);
         cursor.flusher().dispatch_tlb_flush();
         cursor.flusher().sync_tlb_flush();
+    }
+
+    /// Unmaps the pages of the mapping from the cursor's current virtual
+    /// address for `len` bytes, and updates the RSS counters.
+    ///
+    /// This method will bring the cursor forward by `len` bytes.
+    pub(super) fn unmap_pages(
+        &self,
+        cursor: &mut CursorMut<'_>,
+        len: usize,
+        rss_delta: &mut RssDelta,
+    ) {
+        // Only private anonymous pages can be swapped out. If no swap area is
+        // turned on, there cannot be any swap entries.
+        if matches!(self.mapped_mem, MappedMemory::Anonymous) && swap::is_enabled() {
+            let num_swapped = unmap_swapped(cursor, len);
+            rss_delta.add(RssType::Swap, -(num_swapped as isize));
+        }
+
+        rss_delta.add(self.rss_type(), -(cursor.unmap(len) as isize));
+    }
+
+    /// Swaps out the pages of the mapping in the range, and returns the
+    /// number of pages swapped out.
+    ///
+    /// At most `max_pages` pages are swapped out. Only private anonymous pages
+    /// that are not shared with other processes are swapped out; other pages
+    /// are skipped.
+    pub(super) fn swap_out(
+        &self,
+        vm_space: &VmSpace,
+        range: Range<Vaddr>,
+        max_pages: usize,
+        rss_delta: &mut RssDelta,
+    ) -> Result<usize> {
+        debug_assert!(self.range().start <= range.start && range.end <= self.range().end);
+
+        if !matches!(self.mapped_mem, MappedMemory::Anonymous) || range.is_empty() {
+            return Ok(0);
+        }
+
+        let mut swapped_pages = Vec::new();
+        {
+            let preempt_guard = disable_preempt();
+            let mut cursor = vm_space.cursor_mut(&preempt_guard, &range)?;
+
+            while swapped_pages.len() < max_pages
+                && let Some(va) = cursor.find_next(range.end - cursor.virt_addr())
+            {
+                let frame = match cursor.query().unwrap() {
+                    // The frame may be shared with other processes after `fork`.
+                    (_, Some(VmQueriedItem::MappedRam { frame, .. }))
+                        if frame.reference_count() == 1 =>
+                    {
+                        Some((*frame).clone())
+                    }
+                    _ => None,
+                };
+
+                if let Some(frame) = frame {
+                    let Some(entry) = swap::alloc_entry() else {
+                        break;
+                    };
+
+                    // The page stays in the swap cache until it is written out.
+                    // One reference is held by the page table and the other is
+                    // held by us until then.
+                    swap::insert_cache(entry, frame.clone());
+                    swap::dup_entry(entry);
+                    cursor.unmap(PAGE_SIZE);
+                    cursor.jump(va).unwrap();
+                    cursor.map_swapped(entry.into_raw());
+                    swapped_pages.push((entry, frame));
+                }
+
+                let next_va = va + PAGE_SIZE;
+                if next_va == range.end {
+                    break;
+                }
+                cursor.jump(next_va).unwrap();
+            }
+
+            // The frames must no longer be accessed by the user space before
+            // they are written out.
+            cursor.flusher().dispatch_tlb_flush();
+            cursor.flusher().sync_tlb_flush();
+        }
+
+        let num_swapped = swapped_pages.len();
+        rss_delta.add(RssType::Anon, -(num_swapped as isize));
+        rss_delta.add(RssType::Swap, num_swapped as isize);
 
-        num_unmapped
+        for (entry, frame) in swapped_pages {
+            if let Err(err) = swap::write_page(entry, &frame) {
+                // The page is kept in the swap cache, so no data is lost.
+                warn!("failed to write a page out to the swap area: {:?}", err);
+            }
+            swap::free_entry(entry);
+        }
+
+        Ok(num_swapped)
+    }
+
+    /// Swaps in the page at the address if it is swapped out.
+    pub(super) fn swap_in(
+        &self,
+        vm_space: &VmSpace,
+        page_aligned_addr: Vaddr,
+        rss_delta: &mut RssDelta,
+    ) -> Result<()> {
+        // No permissions are required, so pages already in the RAM will be
+        // left untouched.
+        self.handle_single_page_fault(vm_space, page_aligned_addr, VmPerms::empty(), rss_delta)
     }
 
     /// Change the perms of the mapping.
@@ -874,6 +1097,16 @@ impl VmMapping {
 
         Self { perms, ..self }
     }
+
+    /// Changes whether the mapping is excluded from core dumps.
+    pub(super) fn set_dont_dump(self, dont_dump: bool) -> Self {
+        Self { dont_dump, ..self }
+    }
+
+    /// Changes the registration with a userfaultfd.
+    pub(super) fn set_userfault(self, userfault: Option<UserfaultRegistration>) -> Self {
+        Self { userfault, ..self }
+    }
 }
 
 /// Memory mapped by a [`VmMapping`].
@@ -938,11 +1171,19 @@ pub(super) struct MappedVmo {
     /// Whether the VMO's writable mappings need to be tracked, and the
     /// mapping is writable to the VMO.
     is_writable_tracked: bool,
+    /// The attach of the System V shared memory segment if the VMO belongs
+    /// to such a segment.
+    shm_attach: Option<ShmAttach>,
 }
 
 impl MappedVmo {
     /// Creates a `MappedVmo` used for the mapping.
-    pub(super) fn new(vmo: Arc<Vmo>, offset: usize, is_writable_tracked: bool) -> Result<Self> {
+    pub(super) fn new(
+        vmo: Arc<Vmo>,
+        offset: usize,
+        is_writable_tracked: bool,
+        shm_attach: Option<ShmAttach>,
+    ) -> Result<Self> {
         if is_writable_tracked {
             vmo.writable_mapping_status().map()?;
         }
@@ -951,6 +1192,7 @@ impl MappedVmo {
             vmo,
             offset,
             is_writable_tracked,
+            shm_attach,
         })
     }
 
@@ -1033,6 +1275,7 @@ impl MappedVmo {
             vmo: self.vmo.clone(),
             offset,
             is_writable_tracked: self.is_writable_tracked,
+            shm_attach: self.shm_attach.as_ref().map(ShmAttach::dup),
         }
     }
 }
@@ -1059,6 +1302,8 @@ fn try_merge(left: &VmMapping, right: &VmMapping) -> Option<VmMapping> {
     let is_type_equal = left.is_shared == right.is_shared
         && left.handle_page_faults_around == right.handle_page_faults_around
         && left.perms == right.perms
+        && left.dont_dump == right.dont_dump
+        && left.userfault == right.userfault
         && match (&left.file, &right.file) {
             (Some(left_file), Some(right_file)) => Arc::ptr_eq(left_file, right_file),
             (None, None) => true,
@@ -1099,12 +1344,39 @@ fn try_merge(left: &VmMapping, right: &VmMapping) -> Option<VmMapping> {
         map_size,
         mapped_mem,
         file: left.file.clone(),
+        userfault: left.userfault.clone(),
         ..*left
     })
 }
 
-fn duplicate_frame(src: &UFrame) -> Result<Frame<()>> {
-    let new_frame = FrameAllocOptions::new().zeroed(false).alloc_frame()?;
+/// Unmaps the swap entries from the cursor's current virtual address for
+/// `len` bytes, and returns the number of unmapped swap entries.
+///
+/// The cursor is moved back to where it starts after the operation.
+fn unmap_swapped(cursor: &mut CursorMut<'_>, len: usize) -> usize {
+    let start_va = cursor.virt_addr();
+    let end_va = start_va + len;
+
+    let mut num_unmapped = 0;
+    while let Some(va) = cursor.find_next(end_va - cursor.virt_addr()) {
+        if let (_, Some(VmQueriedItem::Swapped { .. })) = cursor.query().unwrap() {
+            cursor.unmap(PAGE_SIZE);
+            num_unmapped += 1;
+        }
+
+        let next_va = va + PAGE_SIZE;
+        if next_va == end_va {
+            break;
+        }
+        cursor.jump(next_va).unwrap();
+    }
+
+    cursor.jump(start_va).unwrap();
+    num_unmapped
+}
+
+fn duplicate_frame(src: &UFrame) -> Result<AnonPage> {
+    let new_frame = alloc_anon_page(false)?;
     new_frame.writer().write(&mut src.reader());
     Ok(new_frame)
 }
@@ -10,7 +10,7 @@ use ostd::{
     task::disable_preempt,
 };
 
-use super::{Vmar, is_userspace_vaddr};
+use super::{RssType, Vmar, is_userspace_vaddr};
 use crate::{
     prelude::*,
     vm::vmar::{PageFaultInfo, is_userspace_vaddr_range},
@@ -91,6 +91,48 @@ impl Vmar {
         self.access_alien(vaddr, len, PageFlags::W, write)
     }
 
+    /// Gets the page at `vaddr` for writing it to a core dump.
+    ///
+    /// This method returns `None` if the page can be omitted from the core dump, i.e., it reads as
+    /// zeros. This is the case if the page has never been touched in an anonymous mapping or if
+    /// the page cannot be brought in (e.g., it is beyond the end of the mapped file).
+    ///
+    /// This corresponds to `get_dump_page` in Linux.
+    pub(crate) fn get_dump_page(&self, vaddr: Vaddr) -> Option<UFrame> {
+        debug_assert!(is_userspace_vaddr(vaddr) && vaddr.is_multiple_of(PAGE_SIZE));
+
+        let vmspace = self.vm_space();
+
+        loop {
+            let preempt_guard = disable_preempt();
+            let mut cursor = vmspace
+                .cursor(&preempt_guard, &(vaddr..vaddr + PAGE_SIZE))
+                .ok()?;
+
+            let is_unpopulated = match cursor.query().ok()?.1 {
+                Some(VmQueriedItem::MappedRam { frame, .. }) => return Some((*frame).clone()),
+                Some(VmQueriedItem::MappedIoMem { .. }) => return None,
+                // Swapped-out pages are swapped in by the page fault handler.
+                Some(_) => false,
+                None => true,
+            };
+
+            drop(cursor);
+            drop(preempt_guard);
+
+            if is_unpopulated {
+                let inner = self.inner.read();
+                let vm_mapping = inner.vm_mappings.find_one(&vaddr)?;
+                if matches!(vm_mapping.rss_type(), RssType::Anon) {
+                    return None;
+                }
+            }
+
+            let page_fault_info = PageFaultInfo::new(vaddr, PageFlags::R.into()).force();
+            self.handle_page_fault(&page_fault_info).ok()?;
+        }
+    }
+
     /// Accesses memory at `vaddr..vaddr+len` in the context of an alien thread using `op`.
     ///
     /// The `VmSpace` of the process is not required to be activated on the current CPU.
@@ -156,17 +198,20 @@ impl Vmar {
             let mut cursor = vmspace.cursor(&preempt_guard, &(vaddr..vaddr + PAGE_SIZE))?;
 
             match cursor.query()?.1 {
-                Some(vm_item) if vm_item.prop().flags.contains(required_page_flags) => {
-                    match vm_item {
-                        VmQueriedItem::MappedRam { frame, .. } => return Ok((*frame).clone()),
-                        VmQueriedItem::MappedIoMem { .. } => {
-                            return_errno_with_message!(
-                                Errno::EOPNOTSUPP,
-                                "accessing alien MMIO memory is not supported currently"
-                            );
-                        }
-                    }
+                Some(VmQueriedItem::MappedRam { frame, prop })
+                    if prop.flags.contains(required_page_flags) =>
+                {
+                    return Ok((*frame).clone());
+                }
+                Some(VmQueriedItem::MappedIoMem { prop, .. })
+                    if prop.flags.contains(required_page_flags) =>
+                {
+                    return_errno_with_message!(
+                        Errno::EOPNOTSUPP,
+                        "accessing alien MMIO memory is not supported currently"
+                    );
                 }
+                // Swapped-out pages are swapped in by the page fault handler.
                 Some(_) | None => (),
             }
 
@@ -9,8 +9,15 @@ use ostd::{
     task::disable_preempt,
 };
 
-use super::{RssDelta, Vmar};
-use crate::{prelude::*, process::ProcessVm, vm::vmar::VmarHandle};
+use super::{RssDelta, RssType, Vmar, userfault::ForkedUserfaults};
+use crate::{
+    prelude::*,
+    process::ProcessVm,
+    vm::{
+        swap::{self, SwapEntry},
+        vmar::VmarHandle,
+    },
+};
 
 impl Vmar {
     /// Creates a new VMAR whose content is inherited from another
@@ -20,6 +27,7 @@ impl Vmar {
         let heap_guard = vmar.process_vm.heap().lock();
 
         let new_vmar = VmarHandle::new(ProcessVm::fork_from(&vmar.process_vm, &heap_guard));
+        let mut forked_userfaults = ForkedUserfaults::new(new_vmar.clone_weak());
 
         {
             let inner = vmar.inner.read();
@@ -38,7 +46,11 @@ impl Vmar {
                 let mut rmap = vm_mapping.lock_rmap();
 
                 // Clone the `VmMapping` to the new VMAR.
-                let new_mapping = vm_mapping.new_fork();
+                let mut new_mapping = vm_mapping.new_fork();
+                if let Some(userfault) = vm_mapping.userfault() {
+                    new_mapping =
+                        new_mapping.set_userfault(forked_userfaults.fork(userfault, &range));
+                }
                 new_inner.insert_without_try_merge(&new_vmar, new_mapping, rmap.as_deref_mut());
 
                 let preempt_guard = disable_preempt();
@@ -51,7 +63,7 @@ impl Vmar {
                 cur_cursor.jump(base).unwrap();
                 new_cursor.jump(base).unwrap();
 
-                let num_copied =
+                let (num_copied, num_swapped) =
                     cow_copy_pt(&mut cur_cursor, &mut new_cursor, vm_mapping.map_size());
 
                 // We need to ensure that no writes can be performed to COW
@@ -64,9 +76,13 @@ impl Vmar {
                 }
 
                 rss_delta.add(vm_mapping.rss_type(), num_copied as isize);
+                rss_delta.add(RssType::Swap, num_swapped as isize);
             }
         }
 
+        // Report the new userfaultfds after releasing the locks.
+        forked_userfaults.notify();
+
         Ok(new_vmar)
     }
 }
@@ -77,13 +93,15 @@ impl Vmar {
 /// The copied range starts from `src`'s current position with the given
 /// `size`. The destination range starts from `dst`'s current position.
 ///
-/// The number of physical frames copied is returned.
-fn cow_copy_pt(src: &mut CursorMut<'_>, dst: &mut CursorMut<'_>, size: usize) -> usize {
+/// The number of physical frames copied and the number of swap entries copied
+/// are returned.
+fn cow_copy_pt(src: &mut CursorMut<'_>, dst: &mut CursorMut<'_>, size: usize) -> (usize, usize) {
     let start_va = src.virt_addr();
     let end_va = start_va + size;
     let mut remain_size = size;
 
     let mut num_copied = 0;
+    let mut num_swapped = 0;
 
     let op = |flags: &mut PageFlags, _cache: &mut CachePolicy| {
         *flags -= PageFlags::W;
@@ -122,12 +140,28 @@ fn cow_copy_pt(src: &mut CursorMut<'_>, dst: &mut CursorMut<'_>, size: usize) ->
                 }
                 src.jump(next_va).unwrap();
             }
+            VmQueriedItem::Swapped { entry } => {
+                // The swap entry is shared, and the page will be copied when
+                // it is swapped in.
+                swap::dup_entry(SwapEntry::from_raw(entry));
+                dst.jump(mapped_va).unwrap();
+                dst.map_swapped(entry);
+
+                num_swapped += 1;
+
+                // Manually advance the source cursor, as in the `MappedIoMem` case.
+                let next_va = mapped_va + PAGE_SIZE;
+                if next_va == end_va {
+                    break;
+                }
+                src.jump(next_va).unwrap();
+            }
         }
 
         remain_size = end_va - src.virt_addr();
     }
 
-    num_copied
+    (num_copied, num_swapped)
 }
 
 #[cfg(ktest)]
@@ -169,7 +203,8 @@ mod test {
         {
             let mut child_cursor = child_space.cursor_mut(&preempt_guard, &cow_range).unwrap();
             let mut parent_cursor = vm_space.cursor_mut(&preempt_guard, &cow_range).unwrap();
-            let num_copied = cow_copy_pt(&mut parent_cursor, &mut child_cursor, cow_range.len());
+            let (num_copied, _) =
+                cow_copy_pt(&mut parent_cursor, &mut child_cursor, cow_range.len());
             assert_eq!(num_copied, 1); // Only one page should be copied
         };
 
@@ -214,7 +249,8 @@ mod test {
                 .cursor_mut(&preempt_guard, &cow_range)
                 .unwrap();
             let mut parent_cursor = vm_space.cursor_mut(&preempt_guard, &cow_range).unwrap();
-            let num_copied = cow_copy_pt(&mut parent_cursor, &mut sibling_cursor, cow_range.len());
+            let (num_copied, _) =
+                cow_copy_pt(&mut parent_cursor, &mut sibling_cursor, cow_range.len());
             assert_eq!(num_copied, 0); // No pages should be copied
         }
 
@@ -298,7 +334,8 @@ mod test {
         {
             let mut child_cursor = child_space.cursor_mut(&preempt_guard, &cow_range).unwrap();
             let mut parent_cursor = vm_space.cursor_mut(&preempt_guard, &cow_range).unwrap();
-            let num_copied = cow_copy_pt(&mut parent_cursor, &mut child_cursor, cow_range.len());
+            let (num_copied, _) =
+                cow_copy_pt(&mut parent_cursor, &mut child_cursor, cow_range.len());
             assert_eq!(num_copied, 0); // `IoMem` pages are not "copied" in the same sense as RAM pages.
         };
 
@@ -349,7 +386,8 @@ mod test {
                 .cursor_mut(&preempt_guard, &cow_range)
                 .unwrap();
             let mut parent_cursor = vm_space.cursor_mut(&preempt_guard, &cow_range).unwrap();
-            let num_copied = cow_copy_pt(&mut parent_cursor, &mut sibling_cursor, cow_range.len());
+            let (num_copied, _) =
+                cow_copy_pt(&mut parent_cursor, &mut sibling_cursor, cow_range.len());
             assert_eq!(num_copied, 0); // No pages should be copied
         }
 
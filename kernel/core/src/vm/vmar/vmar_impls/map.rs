@@ -2,12 +2,13 @@
 
 use core::num::NonZeroUsize;
 
-use super::{MappedMemory, MappedVmo, RssDelta, VmMapping, Vmar};
+use super::{MappedMemory, MappedVmo, RssDelta, VMAR_CAP_ADDR, VMAR_LOWEST_ADDR, VmMapping, Vmar};
 use crate::{
     fs::{
         file::{FileLike, Mappable},
         ramfs::memfd::MemfdInode,
     },
+    ipc::shm::ShmFile,
     prelude::*,
     vm::{page_cache::Vmo, perms::VmPerms},
 };
@@ -51,6 +52,46 @@ impl Vmar {
     pub(crate) fn new_map(&self, size: usize, perms: VmPerms) -> Result<VmarMapOptions<'_>> {
         Ok(VmarMapOptions::new(self, size, perms))
     }
+
+    /// Replaces the VMO of all the private mappings that map `old_vmo` with `new_vmo`.
+    ///
+    /// The new mappings keep the addresses, the VMO offsets, and the permissions of the old
+    /// mappings. Pages that have been copied on write are discarded.
+    pub(crate) fn replace_vmo_mappings(
+        &self,
+        old_vmo: &Arc<Vmo>,
+        new_vmo: &Arc<Vmo>,
+    ) -> Result<()> {
+        let old_mappings: Vec<_> = self
+            .query(VMAR_LOWEST_ADDR..VMAR_CAP_ADDR)
+            .iter()
+            .filter(|mapping| {
+                mapping
+                    .vmo()
+                    .is_some_and(|vmo| Arc::ptr_eq(vmo.vmo(), old_vmo))
+                    && mapping.vmo_for_rmap().is_none()
+            })
+            .map(|mapping| {
+                (
+                    mapping.map_to_addr(),
+                    mapping.map_size(),
+                    mapping.perms(),
+                    mapping.vmo_offset().unwrap(),
+                )
+            })
+            .collect();
+
+        for (map_to_addr, map_size, perms, vmo_offset) in old_mappings {
+            self.new_map(map_size, perms & VmPerms::ALL_PERMS)?
+                .may_perms(perms & VmPerms::ALL_MAY_PERMS)
+                .vmo(new_vmo.clone())
+                .vmo_offset(vmo_offset)
+                .offset(VmarMapOffset::FixedReplace(map_to_addr))
+                .build()?;
+        }
+
+        Ok(())
+    }
 }
 
 /// Options for creating a new mapping.
@@ -328,8 +369,11 @@ impl<'a> VmarMapOptions<'a> {
             Some(Mappable::Vmo(vmo)) => {
                 let path = file.as_ref().map(|file| file.path());
 
-                if let Some(path) = path {
-                    debug_assert!(Arc::ptr_eq(&vmo, &path.inode().page_cache().unwrap()));
+                // Files without a page cache (e.g., io_uring files) may also provide VMOs.
+                if let Some(path) = path
+                    && let Some(page_cache) = path.inode().page_cache()
+                {
+                    debug_assert!(Arc::ptr_eq(&vmo, &page_cache));
                 }
 
                 let is_writable_tracked = if let Some(path) = path
@@ -343,8 +387,17 @@ impl<'a> VmarMapOptions<'a> {
                     false
                 };
 
-                let mapped_mem =
-                    MappedMemory::Vmo(MappedVmo::new(vmo, vmo_offset, is_writable_tracked)?);
+                let shm_attach = file
+                    .as_ref()
+                    .and_then(|file| file.downcast_ref::<ShmFile>())
+                    .map(ShmFile::attach);
+
+                let mapped_mem = MappedMemory::Vmo(MappedVmo::new(
+                    vmo,
+                    vmo_offset,
+                    is_writable_tracked,
+                    shm_attach,
+                )?);
                 (mapped_mem, None)
             }
             Some(Mappable::IoMem(io_mem)) => (MappedMemory::Device, Some(io_mem)),
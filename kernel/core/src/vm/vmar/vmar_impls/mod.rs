@@ -7,7 +7,9 @@ pub(super) mod page_fault;
 mod protect;
 mod query;
 pub(super) mod remap;
+mod swap;
 mod unmap;
+mod userfault;
 
 use core::{
     array,
@@ -134,9 +136,14 @@ impl Vmar {
 pub(crate) enum RssType {
     File = 0,
     Anon = 1,
+    /// Anonymous pages that are swapped out.
+    ///
+    /// These pages are not resident in the RAM, but are counted in the same
+    /// way as Linux does.
+    Swap = 2,
 }
 
-const NUM_RSS_COUNTERS: usize = 2;
+const NUM_RSS_COUNTERS: usize = 3;
 
 pub(super) struct RssDelta<'a> {
     delta: [isize; NUM_RSS_COUNTERS],
@@ -407,7 +414,8 @@ impl VmarInner {
             // may attempt to access its reverse mappings in `Drop`.
             drop(rmap);
 
-            rss_delta.add(taken.rss_type(), -(taken.unmap(&vmar.vm_space) as isize));
+            userfault::unmap_userfault(&taken, &range);
+            taken.unmap(&vmar.vm_space, rss_delta);
         }
 
         Ok(offset..(offset + size))
@@ -3,24 +3,54 @@
 #![short_vis_path::add(vmar)]
 
 use super::{Interval, RssDelta, Vmar};
-use crate::{prelude::*, vm::perms::VmPerms};
+use crate::{
+    prelude::*,
+    vm::{perms::VmPerms, reclaim},
+};
 
 impl Vmar {
     pub(crate) fn handle_page_fault(&self, page_fault_info: &PageFaultInfo) -> Result<()> {
-        let inner = self.inner.read();
+        loop {
+            let inner = self.inner.read();
 
-        let address = page_fault_info.address;
-        if let Some(vm_mapping) = inner.vm_mappings.find_one(&address) {
+            let address = page_fault_info.address;
+            let Some(vm_mapping) = inner.vm_mappings.find_one(&address) else {
+                return_errno_with_message!(
+                    Errno::EACCES,
+                    "no VM mappings contain the page fault address"
+                );
+            };
             debug_assert!(vm_mapping.range().contains(&address));
 
+            // If the page fault is handled by user space, wait for it to be resolved and retry.
+            if let Some(waiter) = self.report_userfault(vm_mapping, page_fault_info)? {
+                drop(inner);
+                match waiter.wait() {
+                    Ok(()) => continue,
+                    // Return to user space to handle the signal. The faulting instruction will be
+                    // executed again afterward.
+                    Err(_) if page_fault_info.is_user_mode() => return Ok(()),
+                    Err(err) => return Err(err),
+                }
+            }
+
             let mut rss_delta = RssDelta::new(self);
-            return vm_mapping.handle_page_fault(&self.vm_space, page_fault_info, &mut rss_delta);
-        }
+            let res = vm_mapping.handle_page_fault(&self.vm_space, page_fault_info, &mut rss_delta);
+
+            // If we run out of memory, try to reclaim some memory and retry once.
+            if let Err(err) = &res
+                && err.error() == Errno::ENOMEM
+                && reclaim::reclaim_memory_for_current(1)
+            {
+                return vm_mapping.handle_page_fault(
+                    &self.vm_space,
+                    page_fault_info,
+                    &mut rss_delta,
+                );
+            }
 
-        return_errno_with_message!(
-            Errno::EACCES,
-            "no VM mappings contain the page fault address"
-        );
+            return res;
+        }
     }
 }
 
@@ -43,6 +73,9 @@ pub(crate) struct PageFaultInfo {
     /// Whether this page fault is forced (e.g., manually triggered by `ptrace`).
     /// A forced page fault may bypass some permission checks.
     is_forced: bool,
+
+    /// Whether this page fault is triggered by user-mode code.
+    is_user: bool,
 }
 
 impl PageFaultInfo {
@@ -52,6 +85,7 @@ impl PageFaultInfo {
             address,
             required_perms,
             is_forced: false,
+            is_user: false,
         }
     }
 
@@ -65,4 +99,15 @@ impl PageFaultInfo {
         self.is_forced = true;
         self
     }
+
+    /// Returns whether this page fault is triggered by user-mode code.
+    pub(in vmar) fn is_user_mode(&self) -> bool {
+        self.is_user
+    }
+
+    /// Marks this page fault as triggered by user-mode code.
+    pub(crate) fn in_user_mode(mut self) -> Self {
+        self.is_user = true;
+        self
+    }
 }
@@ -79,4 +79,78 @@ impl Vmar {
 
         Ok(())
     }
+
+    /// Changes whether the memory mappings in the specified range are excluded from core dumps.
+    ///
+    /// The range's start and end addresses must be page-aligned.
+    ///
+    /// If the range contains unmapped pages, an [`ENOMEM`] error will be returned. If the range
+    /// contains device mappings and `dont_dump` is false, an [`EINVAL`] error will be returned.
+    ///
+    /// [`ENOMEM`]: Errno::ENOMEM
+    /// [`EINVAL`]: Errno::EINVAL
+    pub(crate) fn set_dont_dump(&self, dont_dump: bool, range: Range<usize>) -> Result<()> {
+        debug_assert!(range.start.is_multiple_of(PAGE_SIZE));
+        debug_assert!(range.end.is_multiple_of(PAGE_SIZE));
+
+        let mut inner = self.inner.write();
+
+        let mut target_mappings = Vec::new();
+
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            // Like Linux, device mappings can never be dumped.
+            if !dont_dump && vm_mapping.is_device() {
+                return_errno_with_message!(Errno::EINVAL, "device mappings cannot be dumped");
+            }
+            target_mappings.push((vm_mapping.range(), vm_mapping.is_dont_dump()))
+        }
+
+        let mut last_mapping_end = range.start;
+        for (vm_mapping_range, vm_mapping_dont_dump) in target_mappings {
+            if last_mapping_end < vm_mapping_range.start {
+                return_errno_with_message!(
+                    Errno::ENOMEM,
+                    "the range contains pages that are not mapped"
+                );
+            }
+            last_mapping_end = vm_mapping_range.end;
+
+            if dont_dump == vm_mapping_dont_dump {
+                continue;
+            }
+
+            let Some((vm_mapping, mut rmap_to_remove)) = inner.remove(&vm_mapping_range.start)
+            else {
+                // This can happen only if the mapping is merged to the previous one (just
+                // changed before). We can skip this mapping because its property is already
+                // correct.
+                continue;
+            };
+            let mut rmap = rmap_to_remove.remove(self, vm_mapping_range.start);
+
+            let vm_mapping_range = vm_mapping.range();
+            let intersected_range = get_intersected_range(&range, &vm_mapping_range);
+
+            let (left, taken, right) = vm_mapping.split_range(&intersected_range);
+
+            if let Some(left) = left {
+                inner.insert_without_try_merge(self, left, rmap.as_deref_mut());
+            }
+            if let Some(right) = right {
+                inner.insert_without_try_merge(self, right, rmap.as_deref_mut());
+            }
+
+            let taken = taken.set_dont_dump(dont_dump);
+            inner.insert_try_merge(self, taken, rmap.as_deref_mut());
+        }
+
+        if last_mapping_end < range.end {
+            return_errno_with_message!(
+                Errno::ENOMEM,
+                "the range contains pages that are not mapped"
+            );
+        }
+
+        Ok(())
+    }
 }
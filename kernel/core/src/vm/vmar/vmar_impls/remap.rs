@@ -4,8 +4,14 @@ use core::ops::Range;
 
 use ostd::{mm::vm_space::VmQueriedItem, task::disable_preempt};
 
-use super::{RssDelta, Vmar, util::is_intersected};
-use crate::{prelude::*, vm::vmar::is_userspace_vaddr_range};
+use super::{RssDelta, Vmar, userfault::remap_userfault, util::is_intersected};
+use crate::{
+    prelude::*,
+    vm::{
+        swap::{self, SwapEntry},
+        vmar::is_userspace_vaddr_range,
+    },
+};
 
 /// Controls how the old mapping is handled during a `remap` operation.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -180,7 +186,11 @@ impl Vmar {
 
             // Create a new `VmMapping` at the target address.
             // Note that we have ensured that `new_size >= old_size` at the beginning.
-            let new_mapping = old_mapping.clone_for_remap_at(new_range.start);
+            let new_mapping = remap_userfault(
+                old_mapping.clone_for_remap_at(new_range.start),
+                &old_range,
+                true,
+            );
             inner.insert_try_merge(
                 self,
                 new_mapping.enlarge(new_size - old_size),
@@ -196,8 +206,12 @@ impl Vmar {
 
             // Create a new `VmMapping` at the target address.
             // Note that we have ensured that `new_size >= old_size` at the beginning.
-            let new_mapping = old_mapping
-                .clone_range_for_remap_at(old_addr..old_addr + old_size, new_range.start);
+            let new_mapping = remap_userfault(
+                old_mapping
+                    .clone_range_for_remap_at(old_addr..old_addr + old_size, new_range.start),
+                &old_range,
+                false,
+            );
             inner.insert_try_merge(
                 self,
                 new_mapping.enlarge(new_size - old_size),
@@ -256,6 +270,15 @@ impl Vmar {
                     let (iomem, offset) = cursor.find_iomem_by_paddr(paddr).unwrap();
                     cursor.map_iomem(iomem, prop, PAGE_SIZE, offset);
                 }
+                VmQueriedItem::Swapped { entry } => {
+                    // Unmapping the entry releases its reference, so hold
+                    // another one for the new location.
+                    swap::dup_entry(SwapEntry::from_raw(entry));
+                    cursor.unmap(PAGE_SIZE);
+                    cursor.jump(new_map_va).unwrap();
+
+                    cursor.map_swapped(entry);
+                }
             }
 
             current_offset = offset + PAGE_SIZE;
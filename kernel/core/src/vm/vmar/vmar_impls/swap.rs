@@ -0,0 +1,117 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::ops::Range;
+
+use ostd::{mm::vm_space::VmQueriedItem, task::disable_preempt};
+
+use super::{RssDelta, RssType, Vmar};
+use crate::{
+    prelude::*,
+    vm::{
+        swap::SwapEntry,
+        vmar::{interval_set::Interval, util::get_intersected_range},
+    },
+};
+
+impl Vmar {
+    /// Swaps out the pages in the mappings that fall within the specified
+    /// range in bytes.
+    ///
+    /// Only private anonymous pages that are not shared with other processes
+    /// are swapped out. Other pages are left untouched. If no swap area is
+    /// turned on, this method does nothing.
+    ///
+    /// The range's start and end addresses must be page-aligned.
+    pub(crate) fn page_out(&self, range: Range<usize>) -> Result<()> {
+        debug_assert!(range.start.is_multiple_of(PAGE_SIZE));
+        debug_assert!(range.end.is_multiple_of(PAGE_SIZE));
+
+        let inner = self.inner.read();
+        let mut rss_delta = RssDelta::new(self);
+
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            let intersected_range = get_intersected_range(&range, &vm_mapping.range());
+            vm_mapping.swap_out(
+                &self.vm_space,
+                intersected_range,
+                usize::MAX,
+                &mut rss_delta,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    /// Swaps out at most `max_pages` pages of the VMAR to reclaim memory.
+    ///
+    /// This method does nothing if the VMAR is being modified, so it can be
+    /// called regardless of the VMAR locks held by the caller.
+    ///
+    /// Returns the number of pages swapped out.
+    pub(crate) fn reclaim_pages(&self, max_pages: usize) -> usize {
+        let Some(inner) = self.inner.try_read() else {
+            return 0;
+        };
+        let mut rss_delta = RssDelta::new(self);
+        let mut num_reclaimed = 0;
+
+        for vm_mapping in inner.vm_mappings.iter() {
+            if num_reclaimed == max_pages {
+                break;
+            }
+            match vm_mapping.swap_out(
+                &self.vm_space,
+                vm_mapping.range(),
+                max_pages - num_reclaimed,
+                &mut rss_delta,
+            ) {
+                Ok(num_swapped) => num_reclaimed += num_swapped,
+                Err(_) => break,
+            }
+        }
+
+        num_reclaimed
+    }
+
+    /// Swaps in all pages whose swap entries satisfy the predicate.
+    pub(in crate::vm) fn swap_in_all<F>(&self, mut predicate: F) -> Result<()>
+    where
+        F: FnMut(SwapEntry) -> bool,
+    {
+        if self.get_rss_counter(RssType::Swap) == 0 {
+            return Ok(());
+        }
+
+        let inner = self.inner.read();
+        let mut rss_delta = RssDelta::new(self);
+
+        for vm_mapping in inner.vm_mappings.iter() {
+            let range = vm_mapping.range();
+
+            let mut addrs = Vec::new();
+            {
+                let preempt_guard = disable_preempt();
+                let mut cursor = self.vm_space.cursor(&preempt_guard, &range)?;
+                while let Some(va) = cursor.find_next(range.end - cursor.virt_addr()) {
+                    if let (_, Some(VmQueriedItem::Swapped { entry })) = cursor.query().unwrap()
+                        && predicate(SwapEntry::from_raw(entry))
+                    {
+                        addrs.push(va);
+                    }
+
+                    let next_va = va + PAGE_SIZE;
+                    if next_va == range.end {
+                        break;
+                    }
+                    cursor.jump(next_va).unwrap();
+                }
+            }
+
+            for addr in addrs {
+                vm_mapping.swap_in(&self.vm_space, addr, &mut rss_delta)?;
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -94,10 +94,10 @@ impl Vmar {
                 .cursor_mut(&preempt_guard, &intersected_range)
                 .unwrap();
 
-            rss_delta.add(
-                vm_mapping.rss_type(),
-                -(cursor.unmap(intersected_range.len()) as isize),
-            );
+            vm_mapping.unmap_pages(&mut cursor, intersected_range.len(), &mut rss_delta);
+            if let Some(userfault) = vm_mapping.userfault() {
+                userfault.ctx().clear_write_protected(&intersected_range);
+            }
             cursor.flusher().dispatch_tlb_flush();
             cursor.flusher().sync_tlb_flush();
         }
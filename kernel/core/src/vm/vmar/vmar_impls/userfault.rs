@@ -0,0 +1,491 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::ops::Range;
+
+use align_ext::AlignExt;
+use ostd::{
+    mm::{CachePolicy, PageFlags, PageProperty, UFrame, tlb::TlbFlushOp, vm_space::VmQueriedItem},
+    task::disable_preempt,
+};
+
+use super::{
+    Interval, RssDelta, VmMapping, Vmar, page_fault::PageFaultInfo, util::get_intersected_range,
+};
+use crate::{
+    prelude::*,
+    vm::{
+        perms::VmPerms,
+        userfaultfd::{
+            FaultWaiter, UserfaultCtx, UserfaultFeatures, UserfaultMode, UserfaultRegistration,
+        },
+    },
+};
+
+impl Vmar {
+    /// Registers the memory mappings in the specified range with a userfaultfd.
+    ///
+    /// The range's start and end addresses must be page-aligned. The range may contain unmapped
+    /// pages, but it must intersect with at least one mapping.
+    ///
+    /// All the mappings must be private anonymous mappings that may be written. Otherwise, no
+    /// mappings are registered.
+    pub(crate) fn register_userfault(
+        &self,
+        ctx: &Arc<UserfaultCtx>,
+        range: Range<Vaddr>,
+        mode: UserfaultMode,
+    ) -> Result<()> {
+        debug_assert!(range.start.is_multiple_of(PAGE_SIZE));
+        debug_assert!(range.end.is_multiple_of(PAGE_SIZE));
+
+        let mut inner = self.inner.write();
+
+        let mut target_mappings = Vec::new();
+
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            if !vm_mapping.is_anonymous() || vm_mapping.is_shared() {
+                return_errno_with_message!(
+                    Errno::EINVAL,
+                    "only private anonymous mappings can be registered"
+                );
+            }
+            if !vm_mapping.perms().contains(VmPerms::MAY_WRITE) {
+                return_errno_with_message!(
+                    Errno::EPERM,
+                    "mappings that cannot be written cannot be registered"
+                );
+            }
+            if let Some(userfault) = vm_mapping.userfault()
+                && !Arc::ptr_eq(userfault.ctx(), ctx)
+            {
+                return_errno_with_message!(
+                    Errno::EBUSY,
+                    "the mapping is registered with another userfaultfd"
+                );
+            }
+            target_mappings.push(vm_mapping.range());
+        }
+
+        if target_mappings.is_empty() {
+            return_errno_with_message!(Errno::EINVAL, "the range contains no mappings");
+        }
+
+        let registration = UserfaultRegistration::new(ctx.clone(), mode);
+
+        for vm_mapping_range in target_mappings {
+            let Some((vm_mapping, mut rmap_to_remove)) = inner.remove(&vm_mapping_range.start)
+            else {
+                // This can happen only if the mapping is merged to the previous one (just
+                // registered before). We can skip this mapping because its registration is
+                // already correct.
+                continue;
+            };
+            let mut rmap = rmap_to_remove.remove(self, vm_mapping_range.start);
+
+            let vm_mapping_range = vm_mapping.range();
+            let intersected_range = get_intersected_range(&range, &vm_mapping_range);
+
+            let (left, taken, right) = vm_mapping.split_range(&intersected_range);
+
+            if let Some(left) = left {
+                inner.insert_without_try_merge(self, left, rmap.as_deref_mut());
+            }
+            if let Some(right) = right {
+                inner.insert_without_try_merge(self, right, rmap.as_deref_mut());
+            }
+
+            let taken = taken.set_userfault(Some(registration.clone()));
+            inner.insert_try_merge(self, taken, rmap.as_deref_mut());
+        }
+
+        Ok(())
+    }
+
+    /// Unregisters the memory mappings in the specified range from a userfaultfd.
+    ///
+    /// The range's start and end addresses must be page-aligned. Mappings that are not registered
+    /// with the userfaultfd are left unchanged. The threads waiting for page faults in the range
+    /// are woken up.
+    pub(crate) fn unregister_userfault(
+        &self,
+        ctx: &Arc<UserfaultCtx>,
+        range: Range<Vaddr>,
+    ) -> Result<()> {
+        debug_assert!(range.start.is_multiple_of(PAGE_SIZE));
+        debug_assert!(range.end.is_multiple_of(PAGE_SIZE));
+
+        let mut inner = self.inner.write();
+
+        let mut has_mappings = false;
+        let mut target_mappings = Vec::new();
+
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            has_mappings = true;
+            if vm_mapping
+                .userfault()
+                .is_some_and(|userfault| Arc::ptr_eq(userfault.ctx(), ctx))
+            {
+                target_mappings.push(vm_mapping.range());
+            }
+        }
+
+        if !has_mappings {
+            return_errno_with_message!(Errno::EINVAL, "the range contains no mappings");
+        }
+
+        for vm_mapping_range in target_mappings {
+            let Some((vm_mapping, mut rmap_to_remove)) = inner.remove(&vm_mapping_range.start)
+            else {
+                // This can happen only if the mapping is merged to the previous one (just
+                // unregistered before).
+                continue;
+            };
+            let mut rmap = rmap_to_remove.remove(self, vm_mapping_range.start);
+
+            let vm_mapping_range = vm_mapping.range();
+            let intersected_range = get_intersected_range(&range, &vm_mapping_range);
+
+            let (left, taken, right) = vm_mapping.split_range(&intersected_range);
+
+            if let Some(left) = left {
+                inner.insert_without_try_merge(self, left, rmap.as_deref_mut());
+            }
+            if let Some(right) = right {
+                inner.insert_without_try_merge(self, right, rmap.as_deref_mut());
+            }
+
+            ctx.clear_write_protected(&intersected_range);
+            let taken = taken.set_userfault(None);
+            inner.insert_try_merge(self, taken, rmap.as_deref_mut());
+        }
+
+        drop(inner);
+        ctx.wake_range(&range);
+
+        Ok(())
+    }
+
+    /// Unregisters all the memory mappings from a userfaultfd.
+    ///
+    /// This method is called when the userfaultfd is released.
+    pub(crate) fn unregister_userfault_all(&self, ctx: &Arc<UserfaultCtx>) {
+        let mut inner = self.inner.write();
+
+        let target_mappings: Vec<_> = inner
+            .vm_mappings
+            .iter()
+            .filter(|vm_mapping| {
+                vm_mapping
+                    .userfault()
+                    .is_some_and(|userfault| Arc::ptr_eq(userfault.ctx(), ctx))
+            })
+            .map(|vm_mapping| vm_mapping.map_to_addr())
+            .collect();
+
+        for vm_mapping_addr in target_mappings {
+            let Some((vm_mapping, mut rmap_to_remove)) = inner.remove(&vm_mapping_addr) else {
+                // This can happen only if the mapping is merged to the previous one (just
+                // unregistered before).
+                continue;
+            };
+            let mut rmap = rmap_to_remove.remove(self, vm_mapping_addr);
+
+            let vm_mapping = vm_mapping.set_userfault(None);
+            inner.insert_try_merge(self, vm_mapping, rmap.as_deref_mut());
+        }
+    }
+
+    /// Fills a missing page that is registered with a userfaultfd.
+    ///
+    /// If `is_write_protected` is true, the page is mapped as write-protected, which requires the
+    /// mapping to be registered in the write-protect mode.
+    pub(crate) fn fill_userfault_page(
+        &self,
+        ctx: &Arc<UserfaultCtx>,
+        page_addr: Vaddr,
+        frame: UFrame,
+        is_write_protected: bool,
+    ) -> Result<()> {
+        debug_assert!(page_addr.is_multiple_of(PAGE_SIZE));
+
+        let inner = self.inner.read();
+
+        let Some(vm_mapping) = inner.vm_mappings.find_one(&page_addr) else {
+            return_errno_with_message!(Errno::ENOENT, "the page is not mapped");
+        };
+        let Some(userfault) = vm_mapping
+            .userfault()
+            .filter(|userfault| Arc::ptr_eq(userfault.ctx(), ctx))
+        else {
+            return_errno_with_message!(
+                Errno::ENOENT,
+                "the page is not registered with the userfaultfd"
+            );
+        };
+        if is_write_protected && !userfault.mode().contains(UserfaultMode::WP) {
+            return_errno_with_message!(
+                Errno::EINVAL,
+                "the page is not registered in the write-protect mode"
+            );
+        }
+
+        let preempt_guard = disable_preempt();
+        let mut cursor = self
+            .vm_space
+            .cursor_mut(&preempt_guard, &(page_addr..page_addr + PAGE_SIZE))?;
+        if let (_, Some(_)) = cursor.query().unwrap() {
+            return_errno_with_message!(Errno::EEXIST, "the page is already populated");
+        }
+
+        let mut page_flags =
+            PageFlags::from(vm_mapping.perms()) | PageFlags::ACCESSED | PageFlags::DIRTY;
+        if is_write_protected {
+            page_flags -= PageFlags::W;
+        }
+        cursor.map(
+            frame,
+            PageProperty::new_user(page_flags, CachePolicy::Writeback),
+        );
+        ctx.set_write_protected(page_addr..page_addr + PAGE_SIZE, is_write_protected);
+        drop(cursor);
+
+        RssDelta::new(self).add(vm_mapping.rss_type(), 1);
+
+        Ok(())
+    }
+
+    /// Write-protects or un-write-protects the populated pages in the specified range.
+    ///
+    /// The range's start and end addresses must be page-aligned. All the mappings in the range
+    /// must be registered with the userfaultfd in the write-protect mode.
+    pub(crate) fn write_protect_userfault(
+        &self,
+        ctx: &Arc<UserfaultCtx>,
+        range: Range<Vaddr>,
+        is_write_protected: bool,
+    ) -> Result<()> {
+        debug_assert!(range.start.is_multiple_of(PAGE_SIZE));
+        debug_assert!(range.end.is_multiple_of(PAGE_SIZE));
+
+        let inner = self.inner.read();
+
+        let mut has_mappings = false;
+        for vm_mapping in inner.vm_mappings.find(&range) {
+            if !vm_mapping.userfault().is_some_and(|userfault| {
+                Arc::ptr_eq(userfault.ctx(), ctx) && userfault.mode().contains(UserfaultMode::WP)
+            }) {
+                return_errno_with_message!(
+                    Errno::ENOENT,
+                    "the range is not registered with the userfaultfd in the write-protect mode"
+                );
+            }
+            has_mappings = true;
+        }
+
+        if !has_mappings {
+            return_errno_with_message!(Errno::ENOENT, "the range contains no mappings");
+        }
+
+        let preempt_guard = disable_preempt();
+        let mut cursor = self.vm_space.cursor_mut(&preempt_guard, &range)?;
+
+        // Un-write-protecting also clears the stale records of the pages that are no longer
+        // populated, so the whole range is cleared at once.
+        if !is_write_protected {
+            ctx.set_write_protected(range.clone(), false);
+        }
+
+        // The contiguous populated pages that are being write-protected.
+        let mut protected_run: Option<Range<Vaddr>> = None;
+
+        while let Some(mapped_va) = cursor.find_next(range.end - cursor.virt_addr()) {
+            let (va, Some(item)) = cursor.query().unwrap() else {
+                panic!("Found mapped page but query failed");
+            };
+            debug_assert_eq!(mapped_va, va.start);
+
+            // Swapped pages are write-protected when they are swapped in.
+            if let VmQueriedItem::MappedRam { frame, .. } = item {
+                if is_write_protected {
+                    cursor.protect_next(PAGE_SIZE, |flags, _cache| {
+                        *flags -= PageFlags::W;
+                    });
+                    cursor
+                        .flusher()
+                        .issue_tlb_flush(TlbFlushOp::for_range(va.clone()));
+                } else if inner
+                    .vm_mappings
+                    .find_one(&mapped_va)
+                    .is_some_and(|vm_mapping| vm_mapping.perms().contains(VmPerms::WRITE))
+                    && frame.reference_count() == 1
+                {
+                    // Restore the write access now instead of waiting for the next write
+                    // fault. Frames shared with other processes (e.g., after `fork`) are left
+                    // read-only, so that they are copied on write.
+                    cursor.protect_next(PAGE_SIZE, |flags, _cache| {
+                        *flags |= PageFlags::W;
+                    });
+                }
+            }
+
+            if is_write_protected {
+                match protected_run.as_mut() {
+                    Some(run) if run.end == mapped_va => run.end = va.end,
+                    _ => {
+                        if let Some(run) = protected_run.replace(va.clone()) {
+                            ctx.set_write_protected(run, true);
+                        }
+                    }
+                }
+            }
+
+            let next_va = mapped_va + PAGE_SIZE;
+            if next_va == range.end {
+                break;
+            }
+            cursor.jump(next_va).unwrap();
+        }
+
+        if let Some(run) = protected_run {
+            ctx.set_write_protected(run, true);
+        }
+
+        cursor.flusher().dispatch_tlb_flush();
+        cursor.flusher().sync_tlb_flush();
+
+        Ok(())
+    }
+
+    /// Reports the page fault to the userfaultfd that the mapping is registered with.
+    ///
+    /// If the page fault should be handled by user space, this method returns a [`FaultWaiter`]
+    /// that should be used to wait for the fault to be resolved after releasing the VMAR lock.
+    pub(super) fn report_userfault(
+        &self,
+        vm_mapping: &VmMapping,
+        page_fault_info: &PageFaultInfo,
+    ) -> Result<Option<FaultWaiter>> {
+        let Some(userfault) = vm_mapping.userfault() else {
+            return Ok(None);
+        };
+
+        // Faults that violate the permissions are never reported.
+        vm_mapping.check_perms_for_page_fault(page_fault_info)?;
+
+        let page_addr = page_fault_info.address.align_down(PAGE_SIZE);
+        let is_write = page_fault_info.required_perms.contains(VmPerms::WRITE);
+
+        // Hold the page table lock so that the page cannot be populated or write-protected
+        // concurrently.
+        let preempt_guard = disable_preempt();
+        let mut cursor = self
+            .vm_space
+            .cursor(&preempt_guard, &(page_addr..page_addr + PAGE_SIZE))?;
+        let (_, item) = cursor.query().unwrap();
+
+        userfault.ctx().report_fault(
+            userfault.mode(),
+            page_addr,
+            item.is_some(),
+            is_write,
+            page_fault_info.is_user_mode(),
+        )
+    }
+}
+
+/// The userfaultfds created for a child process during `fork`.
+///
+/// Mappings are registered with the new userfaultfds in the child process only if the
+/// userfaultfds in the parent process enable [`UserfaultFeatures::EVENT_FORK`].
+pub(super) struct ForkedUserfaults {
+    child_vmar: Weak<Vmar>,
+    /// The pairs of the parent and child userfaultfds.
+    ctxs: Vec<(Arc<UserfaultCtx>, Arc<UserfaultCtx>)>,
+}
+
+impl ForkedUserfaults {
+    pub(super) fn new(child_vmar: Weak<Vmar>) -> Self {
+        Self {
+            child_vmar,
+            ctxs: Vec::new(),
+        }
+    }
+
+    /// Returns the registration of the child mapping in the specified range.
+    pub(super) fn fork(
+        &mut self,
+        userfault: &UserfaultRegistration,
+        range: &Range<Vaddr>,
+    ) -> Option<UserfaultRegistration> {
+        let parent_ctx = userfault.ctx();
+        if !parent_ctx
+            .features()
+            .contains(UserfaultFeatures::EVENT_FORK)
+        {
+            return None;
+        }
+
+        let child_ctx = if let Some((_, child_ctx)) = self
+            .ctxs
+            .iter()
+            .find(|(ctx, _)| Arc::ptr_eq(ctx, parent_ctx))
+        {
+            child_ctx.clone()
+        } else {
+            let child_ctx = parent_ctx.new_fork(self.child_vmar.clone());
+            self.ctxs.push((parent_ctx.clone(), child_ctx.clone()));
+            child_ctx
+        };
+        child_ctx.copy_write_protected(parent_ctx, range);
+
+        Some(UserfaultRegistration::new(child_ctx, userfault.mode()))
+    }
+
+    /// Notifies the parent userfaultfds of the new userfaultfds.
+    ///
+    /// This method should be called after the VMAR locks are released.
+    pub(super) fn notify(self) {
+        for (parent_ctx, child_ctx) in self.ctxs {
+            parent_ctx.notify_fork(child_ctx);
+        }
+    }
+}
+
+/// Updates the registration of a mapping that is moved from the old range by `mremap`.
+///
+/// The registration is kept only if the userfaultfd enables [`UserfaultFeatures::EVENT_REMAP`].
+/// In that case, if the old range is unmapped, it is reported after the remapping, as Linux does.
+pub(super) fn remap_userfault(
+    new_mapping: VmMapping,
+    old_range: &Range<Vaddr>,
+    unmaps_old_range: bool,
+) -> VmMapping {
+    let Some(userfault) = new_mapping.userfault() else {
+        return new_mapping;
+    };
+    let ctx = userfault.ctx().clone();
+
+    if ctx.features().contains(UserfaultFeatures::EVENT_REMAP) {
+        let new_start = new_mapping.map_to_addr();
+        ctx.move_write_protected(old_range, new_start);
+        ctx.notify_remap(old_range.start, new_start, old_range.len());
+        if unmaps_old_range {
+            ctx.notify_unmap(old_range);
+        }
+        new_mapping
+    } else {
+        ctx.clear_write_protected(old_range);
+        new_mapping.set_userfault(None)
+    }
+}
+
+/// Updates the userfaultfd that a mapping is registered with when the pages in the range are
+/// unmapped.
+pub(super) fn unmap_userfault(vm_mapping: &VmMapping, range: &Range<Vaddr>) {
+    let Some(userfault) = vm_mapping.userfault() else {
+        return;
+    };
+
+    userfault.ctx().clear_write_protected(range);
+    userfault.ctx().notify_unmap(range);
+}
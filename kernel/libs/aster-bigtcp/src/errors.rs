@@ -140,3 +140,29 @@ pub mod udp {
         }
     }
 }
+
+pub mod raw {
+    /// An error returned by [`RawSocket::send`].
+    ///
+    /// [`RawSocket::send`]: crate::socket::RawSocket::send
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum SendError {
+        /// The destination address is in a different family from the bound address.
+        Unaddressable,
+        /// The packet is malformed (e.g., an ICMP socket sends something other than an echo
+        /// request).
+        InvalidPacket,
+        BufferFull,
+        /// The packet is too large.
+        TooLarge,
+    }
+
+    /// An error returned by [`RawSocket::recv`].
+    ///
+    /// [`RawSocket::recv`]: crate::socket::RawSocket::recv
+    #[derive(Clone, Copy, Debug, Eq, PartialEq)]
+    pub enum RecvError {
+        /// The receive buffer is empty.
+        Exhausted,
+    }
+}
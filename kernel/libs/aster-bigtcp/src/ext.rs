@@ -1,6 +1,9 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use crate::{iface::ScheduleNextPoll, socket::SocketEventObserver};
+use crate::{
+    iface::{FrameTap, PacketFilter, ScheduleNextPoll},
+    socket::SocketEventObserver,
+};
 
 /// Extension to be implemented by users of this crate.
 ///
@@ -19,4 +22,13 @@ pub trait Ext {
 
     /// The type for UDP sockets to observe events.
     type UdpEventObserver: SocketEventObserver;
+
+    /// The type for raw sockets to observe events.
+    type RawEventObserver: SocketEventObserver;
+
+    /// The type for ifaces to pass the frames that they receive or transmit.
+    type FrameTap: FrameTap;
+
+    /// The type for ifaces to filter, mangle, and forward the IP packets.
+    type PacketFilter: PacketFilter;
 }
@@ -1,7 +1,10 @@
 // SPDX-License-Identifier: MPL-2.0
 
 use alloc::{
-    collections::btree_map::{BTreeMap, Entry},
+    collections::{
+        btree_map::{BTreeMap, Entry},
+        vec_deque::VecDeque,
+    },
     sync::Arc,
     vec::Vec,
 };
@@ -17,11 +20,13 @@ use ostd::sync::{SpinLock, SpinLockGuard};
 use smoltcp::{
     iface::{Context, packet::Packet},
     phy::Device,
-    wire::{IpAddress, IpEndpoint, Ipv4Cidr, Ipv4Packet, Ipv6Address, Ipv6Cidr, Ipv6Packet},
+    wire::{
+        IpAddress, IpCidr, IpEndpoint, Ipv4Cidr, Ipv4Packet, Ipv6Address, Ipv6Cidr, Ipv6Packet,
+    },
 };
 
 use super::{
-    Iface, InterfaceName,
+    Iface, InterfaceName, RejectWith, ScheduleNextPoll,
     poll::{FnHelper, PollContext, SocketTableAction},
     poll_iface::PollableIface,
     port::BindPortConfig,
@@ -30,19 +35,26 @@ use super::{
 use crate::{
     errors::BindError,
     ext::Ext,
-    socket::{TcpListenerBg, UdpSocketBg},
+    socket::{RawSocketBg, TcpListenerBg, UdpSocketBg},
     socket_table::SocketTable,
 };
 
 pub struct IfaceCommon<E: Ext> {
-    index: u32,
+    id: u32,
+    index: AtomicU32,
     name: InterfaceName,
     type_: InterfaceType,
     flags: InterfaceFlags,
+    is_up: AtomicBool,
+    promiscuity: AtomicU32,
+    allmulti: AtomicU32,
 
     interface: SpinLock<PollableIface<E>, BottomHalfDisabled>,
     used_ports: SpinLock<PortTable, BottomHalfDisabled>,
     sockets: SpinLock<SocketTable<E>, BottomHalfDisabled>,
+    /// The forwarded packets that are waiting to be transmitted, or to be rejected if the
+    /// [`RejectWith`] is present.
+    forwarded: SpinLock<VecDeque<(Vec<u8>, Option<RejectWith>)>, BottomHalfDisabled>,
     sched_poll: E::ScheduleNextPoll,
 }
 
@@ -61,11 +73,8 @@ pub(super) enum IpPacket<'a> {
 /// to their IPv4 counterparts for binding purposes. This ensures that binding
 /// to `192.0.2.1:80` and `::ffff:192.0.2.1:80` are treated as the same.
 //
-// TODO: This type currently only handles port binding conflict detection. Full
-// dual-stack support is not yet implemented, including:
-// - Accepting IPv4 connections on IPv6 wildcard socket (binding to `::`).
-// - Returning IPv4-mapped addresses in `accept()` for IPv4 clients.
-// - Proper handling of `IPV6_V6ONLY` socket option.
+// TODO: Accept IPv4 connections on IPv6 wildcard sockets (i.e., sockets bound to `::`) once
+// wildcard addresses are supported.
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 struct NormalizedAddress(Ipv6Address);
 
@@ -86,22 +95,35 @@ impl<E: Ext> IfaceCommon<E> {
         interface: smoltcp::iface::Interface,
         sched_poll: E::ScheduleNextPoll,
     ) -> Self {
-        let index = INTERFACE_INDEX_ALLOCATOR.fetch_add(1, Ordering::Relaxed);
+        let id = INTERFACE_ID_ALLOCATOR.fetch_add(1, Ordering::Relaxed);
 
         Self {
-            index,
+            id,
+            index: AtomicU32::new(0),
             name,
             type_,
-            flags,
+            flags: flags - InterfaceFlags::UP,
+            is_up: AtomicBool::new(flags.contains(InterfaceFlags::UP)),
+            promiscuity: AtomicU32::new(0),
+            allmulti: AtomicU32::new(0),
             interface: SpinLock::new(PollableIface::new(interface)),
             used_ports: SpinLock::new(PortTable::new()),
             sockets: SpinLock::new(SocketTable::new()),
+            forwarded: SpinLock::new(VecDeque::new()),
             sched_poll,
         }
     }
 
+    pub(super) fn id(&self) -> u32 {
+        self.id
+    }
+
     pub(super) fn index(&self) -> u32 {
-        self.index
+        self.index.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn set_index(&self, index: u32) {
+        self.index.store(index, Ordering::Relaxed);
     }
 
     pub(super) fn name(&self) -> &InterfaceName {
@@ -113,7 +135,36 @@ impl<E: Ext> IfaceCommon<E> {
     }
 
     pub(super) fn flags(&self) -> InterfaceFlags {
-        self.flags
+        let mut flags = self.flags;
+        if self.is_up() {
+            flags |= InterfaceFlags::UP;
+        } else {
+            // The operational state cannot be up if the interface is administratively down.
+            flags -= InterfaceFlags::RUNNING;
+        }
+        if self.promiscuity.load(Ordering::Relaxed) != 0 {
+            flags |= InterfaceFlags::PROMISC;
+        }
+        if self.allmulti.load(Ordering::Relaxed) != 0 {
+            flags |= InterfaceFlags::ALLMULTI;
+        }
+        flags
+    }
+
+    pub(super) fn is_up(&self) -> bool {
+        self.is_up.load(Ordering::Relaxed)
+    }
+
+    pub(super) fn set_up(&self, is_up: bool) {
+        self.is_up.store(is_up, Ordering::Relaxed);
+    }
+
+    pub(super) fn update_promiscuity(&self, inc: bool) {
+        update_counter(&self.promiscuity, inc);
+    }
+
+    pub(super) fn update_allmulti(&self, inc: bool) {
+        update_counter(&self.allmulti, inc);
     }
 
     pub(super) fn ipv4_cidr(&self) -> Option<Ipv4Cidr> {
@@ -124,15 +175,81 @@ impl<E: Ext> IfaceCommon<E> {
         self.interface.lock().ipv6_cidr()
     }
 
+    pub(super) fn ipv4_cidrs(&self) -> Vec<Ipv4Cidr> {
+        self.interface.lock().ipv4_cidrs()
+    }
+
+    pub(super) fn ipv6_cidrs(&self) -> Vec<Ipv6Cidr> {
+        self.interface.lock().ipv6_cidrs()
+    }
+
+    pub(super) fn add_ip_cidr(&self, ip_cidr: IpCidr) -> bool {
+        self.interface.lock().add_ip_cidr(ip_cidr)
+    }
+
+    pub(super) fn remove_ip_cidr(&self, ip_cidr: IpCidr) -> bool {
+        self.interface.lock().remove_ip_cidr(ip_cidr)
+    }
+
+    pub(super) fn add_route(&self, cidr: IpCidr, via_router: IpAddress) -> bool {
+        self.interface.lock().add_route(cidr, via_router)
+    }
+
+    pub(super) fn remove_route(&self, cidr: IpCidr, via_router: IpAddress) -> bool {
+        self.interface.lock().remove_route(cidr, via_router)
+    }
+
     pub(super) fn sched_poll(&self) -> &E::ScheduleNextPoll {
         &self.sched_poll
     }
+
+    /// Schedules the next poll at the specific time.
+    ///
+    /// This should be called at the end of each poll instead of calling [`ScheduleNextPoll`]
+    /// directly, because a packet may be forwarded to the iface after the forwarded packets are
+    /// taken but before the next poll is scheduled, which may cancel the poll requested for the
+    /// packet.
+    pub(super) fn schedule_next_poll(&self, next_poll: Option<u64>) {
+        self.sched_poll.schedule_next_poll(next_poll);
+
+        if !self.forwarded.lock().is_empty() {
+            self.schedule_poll_now();
+        }
+    }
+
+    pub(super) fn send_forwarded(&self, packet: Vec<u8>, reject_with: Option<RejectWith>) -> bool {
+        {
+            let mut forwarded = self.forwarded.lock();
+            if forwarded.len() >= FORWARDED_QUEUE_LEN {
+                return false;
+            }
+            forwarded.push_back((packet, reject_with));
+        }
+
+        self.schedule_poll_now();
+        true
+    }
+
+    fn schedule_poll_now(&self) {
+        let now = get_network_timestamp().total_millis() as u64;
+        self.sched_poll.schedule_next_poll(Some(now));
+    }
 }
 
-/// An allocator that allocates a unique index for each interface.
-//
-// FIXME: This allocator is specific to each network namespace.
-static INTERFACE_INDEX_ALLOCATOR: AtomicU32 = AtomicU32::new(1);
+/// The maximum number of forwarded packets that can wait to be transmitted by an iface.
+const FORWARDED_QUEUE_LEN: usize = 1000;
+
+fn update_counter(counter: &AtomicU32, inc: bool) {
+    if inc {
+        counter.fetch_add(1, Ordering::Relaxed);
+    } else {
+        let old = counter.fetch_sub(1, Ordering::Relaxed);
+        debug_assert_ne!(old, 0);
+    }
+}
+
+/// An allocator that allocates a unique ID for each interface.
+static INTERFACE_ID_ALLOCATOR: AtomicU32 = AtomicU32::new(1);
 
 // Lock order: `interface` -> `sockets`
 impl<E: Ext> IfaceCommon<E> {
@@ -169,6 +286,30 @@ impl<E: Ext> IfaceCommon<E> {
             .map(BoundUdpPort)
     }
 
+    pub(super) fn bind_raw(
+        &self,
+        iface: Arc<dyn Iface<E>>,
+        addr: IpAddress,
+        protocol: u8,
+    ) -> Result<BoundRawPort<E>, BindError> {
+        debug_assert_ne!(protocol, 0);
+
+        // Like Linux, the IP protocol number is used as the port number. Multiple raw sockets can
+        // always be bound to the same protocol.
+        let config = BindPortConfig::new(IpEndpoint::new(addr, protocol as u16), true);
+        self.bind(iface, config, PortProtocol::Raw)
+            .map(BoundRawPort)
+    }
+
+    pub(super) fn bind_icmp(
+        &self,
+        iface: Arc<dyn Iface<E>>,
+        config: BindPortConfig,
+    ) -> Result<BoundRawPort<E>, BindError> {
+        self.bind(iface, config, PortProtocol::Icmp)
+            .map(BoundRawPort)
+    }
+
     fn bind(
         &self,
         iface: Arc<dyn Iface<E>>,
@@ -200,6 +341,11 @@ impl<E: Ext> IfaceCommon<E> {
         sockets.insert_udp_socket(socket);
     }
 
+    pub(crate) fn register_raw_socket(&self, socket: Arc<RawSocketBg<E>>) {
+        let mut sockets = self.sockets.lock();
+        sockets.insert_raw_socket(socket);
+    }
+
     pub(crate) fn remove_tcp_listener(&self, socket: &Arc<TcpListenerBg<E>>) {
         let mut sockets = self.sockets.lock();
         let removed = sockets.remove_listener(socket.listener_key());
@@ -211,6 +357,12 @@ impl<E: Ext> IfaceCommon<E> {
         let removed = sockets.remove_udp_socket(socket);
         debug_assert!(removed.is_some());
     }
+
+    pub(crate) fn remove_raw_socket(&self, socket: &Arc<RawSocketBg<E>>) {
+        let mut sockets = self.sockets.lock();
+        let removed = sockets.remove_raw_socket(socket);
+        debug_assert!(removed.is_some());
+    }
 }
 
 impl<E: Ext> IfaceCommon<E> {
@@ -230,14 +382,26 @@ impl<E: Ext> IfaceCommon<E> {
             >,
         Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
     {
+        // An interface that is administratively down neither receives nor transmits packets.
+        // The packets are kept in the device until the interface is brought up again, except for
+        // the forwarded packets, which are dropped like Linux.
+        if !self.is_up() {
+            self.forwarded.lock().clear();
+            return None;
+        }
+
         let mut interface = self.interface();
         interface.context_mut().now = get_network_timestamp();
 
         let mut sockets = self.sockets.lock();
         let mut socket_actions = Vec::new();
 
-        let mut context = PollContext::new(interface.as_mut(), &sockets, &mut socket_actions);
+        let forwarded = core::mem::take(&mut *self.forwarded.lock());
+
+        let mut context =
+            PollContext::new(self.id, interface.as_mut(), &sockets, &mut socket_actions);
         context.poll_ingress(device, &mut process_phy, &mut dispatch_phy);
+        context.poll_forwarded(device, forwarded, &mut dispatch_phy);
         context.poll_egress(device, &mut dispatch_phy);
 
         // Insert new connections and remove dead connections.
@@ -327,6 +491,11 @@ impl<E: Ext> Drop for BoundPort<E> {
 pub struct BoundTcpPort<E: Ext>(BoundPort<E>);
 /// A UDP port bound to an iface.
 pub struct BoundUdpPort<E: Ext>(BoundPort<E>);
+/// A raw or ICMP echo port bound to an iface.
+///
+/// The port number is the IP protocol number for raw sockets, or the echo identifier for ICMP
+/// echo sockets.
+pub struct BoundRawPort<E: Ext>(BoundPort<E>);
 
 impl<E: Ext> Deref for BoundTcpPort<E> {
     type Target = BoundPort<E>;
@@ -340,6 +509,12 @@ impl<E: Ext> Deref for BoundUdpPort<E> {
         &self.0
     }
 }
+impl<E: Ext> Deref for BoundRawPort<E> {
+    type Target = BoundPort<E>;
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
 
 #[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
 struct PortKey {
@@ -352,6 +527,8 @@ struct PortKey {
 enum PortProtocol {
     Tcp,
     Udp,
+    Raw,
+    Icmp,
 }
 
 struct PortState {
@@ -542,6 +719,9 @@ pub enum InterfaceType {
     LOOPBACK = 772,
     /// Localtalk device
     LOCALTALK = 773,
+
+    /// Zero header length
+    NONE = 0xFFFE,
     // TODO: This enum is not exhaustive
 }
 
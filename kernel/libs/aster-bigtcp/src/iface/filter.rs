@@ -0,0 +1,94 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use smoltcp::wire::Ipv4Address;
+
+/// The points in the IP layer where packets are passed to [`PacketFilter`].
+///
+/// These correspond to the netfilter hooks of the IPv4 and IPv6 families in Linux.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FilterHook {
+    /// A packet is received by an iface, before it is known whether the packet is destined for
+    /// a local address.
+    PreRouting,
+    /// A received packet is going to be delivered locally.
+    LocalIn,
+    /// A received packet is going to be forwarded to another iface.
+    Forward,
+    /// A locally generated packet is going to be transmitted.
+    LocalOut,
+    /// A packet is going to be transmitted by an iface, whether it is generated locally or
+    /// forwarded.
+    PostRouting,
+}
+
+/// The verdict on a packet made by [`PacketFilter`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FilterVerdict {
+    /// The packet continues to traverse the network stack.
+    Accept,
+    /// The packet is dropped silently.
+    Drop,
+    /// The packet is dropped, and the sender is notified with an error reply.
+    ///
+    /// Error replies can only be generated for received packets. Locally generated packets are
+    /// dropped silently.
+    Reject(RejectWith),
+}
+
+/// The error reply to a rejected packet.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RejectWith {
+    /// An ICMP destination unreachable message with the code.
+    IcmpUnreachable(u8),
+    /// A TCP RST segment, if the packet is a TCP segment without the RST flag.
+    TcpReset,
+}
+
+/// The iface at which a packet reaches a [`FilterHook`].
+#[derive(Clone, Copy, Debug)]
+pub struct HookIface {
+    /// The ID of the iface.
+    pub id: u32,
+    /// The IPv4 address of the iface, which can be used to translate the source addresses of
+    /// outgoing packets.
+    pub ipv4_addr: Option<Ipv4Address>,
+}
+
+/// A trait for filtering, mangling, and forwarding the IP packets that pass through ifaces.
+///
+/// This allows users of this crate to implement firewalls and network address translation (e.g.,
+/// netfilter in Linux), as well as packet forwarding between ifaces.
+///
+/// The methods are called while the iface is being polled, so they must not poll ifaces, lock
+/// ifaces (e.g., to query their addresses), or perform any blocking operations.
+pub trait PacketFilter {
+    /// Returns whether packets should be passed to [`Self::filter`].
+    ///
+    /// Ifaces skip the hooks entirely if this method returns `false`, so they don't need to copy
+    /// received packets to make them mutable.
+    fn is_active() -> bool;
+
+    /// Called when an IP packet reaches the hook at the iface.
+    ///
+    /// The iface is the receiving iface for [`FilterHook::PreRouting`] and
+    /// [`FilterHook::LocalIn`], and the transmitting iface for [`FilterHook::LocalOut`] and
+    /// [`FilterHook::PostRouting`]. [`FilterHook::Forward`] is never passed by ifaces, and the
+    /// forwarded packets do not pass through [`FilterHook::PostRouting`] at the transmitting
+    /// iface, because they are expected to be handled before they are sent by
+    /// `send_forwarded`.
+    ///
+    /// The packet starts with the IP header and may be mangled in place (e.g., to translate
+    /// addresses), in which case the checksums must be updated accordingly.
+    fn filter(hook: FilterHook, iface: &HookIface, packet: &mut [u8]) -> FilterVerdict;
+
+    /// Called when the iface with the given ID receives an IP packet that is not destined for
+    /// any of its addresses.
+    ///
+    /// If the packet can be forwarded, the implementation should take a copy of the packet and
+    /// return `true`. The packet can then be routed outside of the polling context and handed to
+    /// the transmitting iface with `send_forwarded`.
+    ///
+    /// If the packet cannot be forwarded (e.g., forwarding is disabled), the implementation should
+    /// return `false`, and the iface replies with an ICMP host unreachable message.
+    fn forward(iface_id: u32, packet: &[u8]) -> bool;
+}
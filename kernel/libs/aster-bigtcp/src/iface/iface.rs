@@ -1,11 +1,12 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::sync::Arc;
+use alloc::{sync::Arc, vec::Vec};
 
-use smoltcp::wire::{EthernetAddress, Ipv4Address, Ipv4Cidr, Ipv6Cidr};
+use smoltcp::wire::{EthernetAddress, IpAddress, IpCidr, Ipv4Address, Ipv4Cidr, Ipv6Cidr};
 
 use super::{
-    BindPortConfig, BoundTcpPort, BoundUdpPort, InterfaceFlags, InterfaceName, InterfaceType,
+    BindPortConfig, BoundRawPort, BoundTcpPort, BoundUdpPort, InterfaceFlags, InterfaceName,
+    InterfaceType, RejectWith,
 };
 use crate::{errors::BindError, ext::Ext};
 
@@ -24,6 +25,14 @@ pub trait Iface<E>: internal::IfaceInternal<E> + Send + Sync {
 
     /// Returns the maximum transmission unit.
     fn mtu(&self) -> usize;
+
+    /// Transmits a raw frame, which starts with the link-layer header if the iface has one.
+    ///
+    /// This method returns `false` if the device cannot transmit the frame now. Note that the
+    /// frame is not passed to [`FrameTap`], so the caller is responsible for doing so if needed.
+    ///
+    /// [`FrameTap`]: crate::iface::FrameTap
+    fn transmit_frame(&self, frame: &[u8]) -> bool;
 }
 
 impl<E: Ext> dyn Iface<E> {
@@ -55,11 +64,54 @@ impl<E: Ext> dyn Iface<E> {
         common.bind_udp(self.clone(), config)
     }
 
+    /// Binds a raw socket of the IP protocol to the address of the iface.
+    ///
+    /// The protocol must not be zero.
+    pub fn bind_raw(
+        self: &Arc<Self>,
+        addr: IpAddress,
+        protocol: u8,
+    ) -> Result<BoundRawPort<E>, BindError> {
+        let common = self.common();
+        common.bind_raw(self.clone(), addr, protocol)
+    }
+
+    /// Binds an ICMP echo identifier to the iface.
+    ///
+    /// If no specific identifier is given in [`BindPortConfig`], the iface will pick up an
+    /// ephemeral one.
+    pub fn bind_icmp(
+        self: &Arc<Self>,
+        config: BindPortConfig,
+    ) -> Result<BoundRawPort<E>, BindError> {
+        let common = self.common();
+        common.bind_icmp(self.clone(), config)
+    }
+
+    /// Returns the unique ID of the iface.
+    ///
+    /// Unlike the interface index, the ID is unique among all ifaces and never changes. It
+    /// identifies the iface in [`PacketFilter`] and [`FrameTap`].
+    ///
+    /// [`PacketFilter`]: super::PacketFilter
+    /// [`FrameTap`]: super::FrameTap
+    pub fn id(&self) -> u32 {
+        self.common().id()
+    }
+
     /// Returns the interface index.
+    ///
+    /// The index is unique among the ifaces that share the index space (e.g., a network
+    /// namespace). It is zero until it is set by [`Self::set_index`].
     pub fn index(&self) -> u32 {
         self.common().index()
     }
 
+    /// Sets the interface index.
+    pub fn set_index(&self, index: u32) {
+        self.common().set_index(index);
+    }
+
     /// Gets the name of the iface.
     ///
     /// In Linux, the name is usually the driver name followed by a unit number.
@@ -77,8 +129,37 @@ impl<E: Ext> dyn Iface<E> {
         self.common().flags()
     }
 
-    // FIXME: Linux and smoltcp allow multiple IP CIDRs per interface, while the
-    // address-related APIs below only account for the first CIDR of each family.
+    /// Brings the iface up or down.
+    ///
+    /// An iface that is down (i.e., [`InterfaceFlags::UP`] is not set) does not transmit or
+    /// receive any packets. Once the iface is brought up, it is polled immediately to process the
+    /// pending packets.
+    pub fn set_up(&self, is_up: bool) {
+        self.common().set_up(is_up);
+        if is_up {
+            self.poll();
+        }
+    }
+
+    /// Increments or decrements the promiscuity counter of the iface.
+    ///
+    /// The iface is in promiscuous mode (i.e., [`InterfaceFlags::PROMISC`] is set) as long as the
+    /// counter is not zero.
+    pub fn update_promiscuity(&self, inc: bool) {
+        self.common().update_promiscuity(inc);
+    }
+
+    /// Increments or decrements the all-multicast counter of the iface.
+    ///
+    /// The iface receives all multicast packets (i.e., [`InterfaceFlags::ALLMULTI`] is set) as
+    /// long as the counter is not zero.
+    pub fn update_allmulti(&self, inc: bool) {
+        self.common().update_allmulti(inc);
+    }
+
+    // FIXME: Linux and smoltcp allow multiple IP CIDRs per interface, while some of the
+    // address-related APIs below (e.g., `ipv4_cidr` and `broadcast_addr`) only account for the
+    // first CIDR of each family.
 
     /// Gets the IPv4 CIDR of the iface, if any.
     pub fn ipv4_cidr(&self) -> Option<Ipv4Cidr> {
@@ -90,6 +171,50 @@ impl<E: Ext> dyn Iface<E> {
         self.common().ipv6_cidr()
     }
 
+    /// Gets all the IPv4 CIDRs of the iface.
+    pub fn ipv4_cidrs(&self) -> Vec<Ipv4Cidr> {
+        self.common().ipv4_cidrs()
+    }
+
+    /// Gets all the IPv6 CIDRs of the iface.
+    ///
+    /// Besides the statically configured ones, the CIDRs include the link-local and global
+    /// addresses configured by IPv6 stateless address autoconfiguration (SLAAC), if enabled.
+    pub fn ipv6_cidrs(&self) -> Vec<Ipv6Cidr> {
+        self.common().ipv6_cidrs()
+    }
+
+    /// Adds an IP CIDR to the iface.
+    ///
+    /// This method returns `false` if the address already exists or the iface has too many
+    /// addresses.
+    pub fn add_ip_cidr(&self, ip_cidr: IpCidr) -> bool {
+        self.common().add_ip_cidr(ip_cidr)
+    }
+
+    /// Removes an IP CIDR from the iface.
+    ///
+    /// This method returns `false` if the CIDR does not exist.
+    pub fn remove_ip_cidr(&self, ip_cidr: IpCidr) -> bool {
+        self.common().remove_ip_cidr(ip_cidr)
+    }
+
+    /// Adds a route that forwards packets destined for `cidr` to `via_router`.
+    ///
+    /// Packets destined for the networks of the iface's own CIDRs are always delivered directly,
+    /// so only routes via routers need to be added. This method returns `false` if the iface has
+    /// too many routes.
+    pub fn add_route(&self, cidr: IpCidr, via_router: IpAddress) -> bool {
+        self.common().add_route(cidr, via_router)
+    }
+
+    /// Removes a route added by [`Self::add_route`].
+    ///
+    /// This method returns `false` if the route does not exist.
+    pub fn remove_route(&self, cidr: IpCidr, via_router: IpAddress) -> bool {
+        self.common().remove_route(cidr, via_router)
+    }
+
     /// Gets the IPv4 broadcast address of the iface, if any.
     ///
     /// IPv6 does not define broadcast addresses and uses multicast instead.
@@ -97,6 +222,28 @@ impl<E: Ext> dyn Iface<E> {
         self.common().ipv4_cidr()?.broadcast()
     }
 
+    /// Queues a forwarded IP packet to be transmitted by the iface.
+    ///
+    /// The packet starts with the IP header and will be sent without passing through the hooks
+    /// of [`PacketFilter`]. This method returns `false` if too many packets are waiting to be
+    /// transmitted, in which case the packet is dropped.
+    ///
+    /// [`PacketFilter`]: super::PacketFilter
+    pub fn send_forwarded(&self, packet: Vec<u8>) -> bool {
+        self.common().send_forwarded(packet, None)
+    }
+
+    /// Queues a received IP packet that cannot be forwarded to be rejected by the iface.
+    ///
+    /// The iface should be the one that received the packet. The sender will be notified with the
+    /// error reply as if the packet were rejected by [`PacketFilter::filter`]. This method
+    /// returns `false` if too many packets are waiting to be processed.
+    ///
+    /// [`PacketFilter::filter`]: super::PacketFilter::filter
+    pub fn reject_forwarded(&self, packet: Vec<u8>, reject_with: RejectWith) -> bool {
+        self.common().send_forwarded(packet, Some(reject_with))
+    }
+
     /// Returns a reference to the associated [`ScheduleNextPoll`].
     ///
     /// [`ScheduleNextPoll`]: crate::iface::sched::ScheduleNextPoll
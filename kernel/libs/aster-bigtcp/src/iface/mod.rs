@@ -1,6 +1,7 @@
 // SPDX-License-Identifier: MPL-2.0
 
 mod common;
+mod filter;
 #[expect(clippy::module_inception)]
 mod iface;
 mod phy;
@@ -8,14 +9,19 @@ mod poll;
 mod poll_iface;
 mod port;
 mod sched;
+mod tap;
 mod time;
 
 const IFNAMESIZE: usize = 16;
 pub type InterfaceName = aster_util::fixed_str::FixedCStr<IFNAMESIZE>;
 
-pub use common::{BoundPort, BoundTcpPort, BoundUdpPort, InterfaceFlags, InterfaceType};
+pub use common::{
+    BoundPort, BoundRawPort, BoundTcpPort, BoundUdpPort, InterfaceFlags, InterfaceType,
+};
+pub use filter::{FilterHook, FilterVerdict, HookIface, PacketFilter, RejectWith};
 pub use iface::Iface;
 pub use phy::{EtherIface, IpIface};
 pub(crate) use poll_iface::{PollKey, PollableIfaceMut};
 pub use port::BindPortConfig;
 pub use sched::ScheduleNextPoll;
+pub use tap::{FrameDirection, FrameTap};
@@ -1,15 +1,21 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{collections::btree_map::BTreeMap, sync::Arc};
+use alloc::{collections::btree_map::BTreeMap, sync::Arc, vec::Vec};
 
 use aster_softirq::BottomHalfDisabled;
 use ostd::sync::SpinLock;
 use smoltcp::{
-    iface::{Config, Context, packet::Packet},
+    iface::{
+        Config, Context,
+        packet::{IpPayload, Packet},
+    },
     phy::{Device, DeviceCapabilities, TxToken},
+    time::Duration,
     wire::{
         self, ArpOperation, ArpPacket, ArpRepr, EthernetAddress, EthernetFrame, EthernetProtocol,
-        EthernetRepr, IpAddress, Ipv4Address, Ipv4AddressExt, Ipv4Cidr, Ipv4Packet, Ipv6Packet,
+        EthernetRepr, Icmpv6Message, Icmpv6Packet, Icmpv6Repr, IpAddress, IpProtocol, IpRepr,
+        Ipv4Address, Ipv4AddressExt, Ipv4Cidr, Ipv4Packet, Ipv6Address, Ipv6Cidr, Ipv6Packet,
+        Ipv6Repr, NdiscNeighborFlags, NdiscPrefixInfoFlags, NdiscRepr, RawHardwareAddress,
     },
 };
 
@@ -20,8 +26,10 @@ use crate::{
         Iface, InterfaceFlags, InterfaceName, ScheduleNextPoll,
         common::{IfaceCommon, InterfaceType, IpPacket},
         iface::internal::IfaceInternal,
+        tap::TappedDevice,
         time::get_network_timestamp,
     },
+    socket::icmp_checksum,
 };
 
 pub struct EtherIface<D, E: Ext> {
@@ -29,14 +37,54 @@ pub struct EtherIface<D, E: Ext> {
     common: IfaceCommon<E>,
     ether_addr: EthernetAddress,
     arp_table: SpinLock<BTreeMap<Ipv4Address, EthernetAddress>, BottomHalfDisabled>,
+    ndisc: SpinLock<NdiscState, BottomHalfDisabled>,
+}
+
+/// The state of the IPv6 neighbor discovery protocol (NDISC).
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc4861>.
+struct NdiscState {
+    /// The mapping between the IPv6 addresses and the Ethernet addresses of the neighbors.
+    //
+    // TODO: Remove the mapping if it expires.
+    neighbors: BTreeMap<Ipv6Address, EthernetAddress>,
+    /// Whether IPv6 stateless address autoconfiguration (SLAAC) is enabled.
+    ///
+    /// Reference: <https://datatracker.ietf.org/doc/html/rfc4862>.
+    is_autoconf: bool,
+    /// Whether a router solicitation should be sent on the next poll.
+    needs_router_solicit: bool,
+    /// The default router learned from the router advertisements.
+    default_router: Option<Ipv6Address>,
+    /// The addresses configured from the router advertisements, which have not yet been added to
+    /// the interface.
+    ///
+    /// The addresses cannot be added when the interface is being polled, since the interface is
+    /// locked at that time.
+    pending_addrs: Vec<Ipv6Cidr>,
+}
+
+/// A packet that is generated by the neighbor discovery protocols.
+enum NeighborPacket {
+    /// An ARP packet for IPv4.
+    Arp(ArpRepr),
+    /// An NDISC message for IPv6.
+    Ndisc {
+        dst_ether: EthernetAddress,
+        ipv6_repr: Ipv6Repr,
+        ndisc_repr: NdiscRepr<'static>,
+    },
 }
 
 impl<D: WithDevice, E: Ext> EtherIface<D, E> {
+    /// Creates a new Ethernet interface.
+    ///
+    /// The interface may have no IPv4 address (e.g., a newly created virtual Ethernet device),
+    /// in which case `ip_cidr` should be `None`. Routes via gateways can be added later.
     pub fn new(
         driver: D,
         ether_addr: EthernetAddress,
-        ip_cidr: Ipv4Cidr,
-        gateway: Ipv4Address,
+        ip_cidr: Option<Ipv4Cidr>,
         name: InterfaceName,
         sched_poll: E::ScheduleNextPoll,
         flags: InterfaceFlags,
@@ -46,14 +94,12 @@ impl<D: WithDevice, E: Ext> EtherIface<D, E> {
             let now = get_network_timestamp();
 
             let mut interface = smoltcp::iface::Interface::new(config, device, now);
-            interface.update_ip_addrs(|ip_addrs| {
-                debug_assert!(ip_addrs.is_empty());
-                ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
-            });
-            interface
-                .routes_mut()
-                .add_default_ipv4_route(gateway)
-                .unwrap();
+            if let Some(ip_cidr) = ip_cidr {
+                interface.update_ip_addrs(|ip_addrs| {
+                    debug_assert!(ip_addrs.is_empty());
+                    ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
+                });
+            }
             interface
         });
 
@@ -64,8 +110,33 @@ impl<D: WithDevice, E: Ext> EtherIface<D, E> {
             common,
             ether_addr,
             arp_table: SpinLock::new(BTreeMap::new()),
+            ndisc: SpinLock::new(NdiscState {
+                neighbors: BTreeMap::new(),
+                is_autoconf: false,
+                needs_router_solicit: false,
+                default_router: None,
+                pending_addrs: Vec::new(),
+            }),
         })
     }
+
+    /// Enables IPv6 stateless address autoconfiguration (SLAAC).
+    ///
+    /// The link-local address is configured immediately. A router solicitation is sent on the
+    /// next poll, so that global addresses can be configured from the router advertisements.
+    //
+    // TODO: Perform duplicate address detection (DAD) before using the addresses.
+    pub fn enable_ipv6_autoconf(&self) {
+        let link_local_addr = slaac_addr(&IPV6_LINK_LOCAL_PREFIX, &self.ether_addr);
+        self.common.add_ip_cidr(wire::IpCidr::Ipv6(Ipv6Cidr::new(
+            link_local_addr,
+            SLAAC_PREFIX_LEN,
+        )));
+
+        let mut ndisc = self.ndisc.lock();
+        ndisc.is_autoconf = true;
+        ndisc.needs_router_solicit = true;
+    }
 }
 
 impl<D, E: Ext> IfaceInternal<E> for EtherIface<D, E> {
@@ -84,13 +155,16 @@ where
 
     fn poll(&self) {
         self.driver.with(|device| {
+            let mut tapped_device = TappedDevice::<_, E>::new(&mut *device, self.common.id());
             let next_poll = self.common.poll(
-                &mut *device,
+                &mut tapped_device,
                 |data, iface_cx, tx_token| self.process(data, iface_cx, tx_token),
                 |pkt, iface_cx, tx_token| self.dispatch(pkt, iface_cx, tx_token),
             );
+            self.solicit_routers(&mut tapped_device);
             device.notify_poll_end();
-            self.common.sched_poll().schedule_next_poll(next_poll);
+            self.add_pending_ipv6_addrs();
+            self.common.schedule_next_poll(next_poll);
         });
     }
 
@@ -98,6 +172,17 @@ where
         self.driver
             .with(|device| device.capabilities().max_transmission_unit)
     }
+
+    fn transmit_frame(&self, frame: &[u8]) -> bool {
+        self.driver.with(|device| {
+            let Some(tx_token) = device.transmit(get_network_timestamp()) else {
+                return false;
+            };
+            tx_token.consume(frame.len(), |buffer| buffer.copy_from_slice(frame));
+            device.notify_poll_end();
+            true
+        })
+    }
 }
 
 impl<D, E: Ext> EtherIface<D, E> {
@@ -107,27 +192,31 @@ impl<D, E: Ext> EtherIface<D, E> {
         iface_cx: &mut Context,
         tx_token: T,
     ) -> Option<(IpPacket<'pkt>, T)> {
-        match self.parse_ip_or_process_arp(data, iface_cx) {
+        match self.parse_ip_or_process_neighbor(data, iface_cx) {
             Ok(pkt) => Some((pkt, tx_token)),
-            Err(Some(arp)) => {
-                Self::emit_arp(&arp, tx_token);
+            Err(Some(neighbor_pkt)) => {
+                self.emit_neighbor_packet(&neighbor_pkt, &iface_cx.caps, tx_token);
                 None
             }
             Err(None) => None,
         }
     }
 
-    fn parse_ip_or_process_arp<'pkt>(
+    fn parse_ip_or_process_neighbor<'pkt>(
         &self,
         data: &'pkt [u8],
         iface_cx: &mut Context,
-    ) -> Result<IpPacket<'pkt>, Option<ArpRepr>> {
+    ) -> Result<IpPacket<'pkt>, Option<NeighborPacket>> {
         // Parse the Ethernet header. Ignore the packet if the header is ill-formed.
         let frame = EthernetFrame::new_checked(data).map_err(|_| None)?;
         let repr = EthernetRepr::parse(&frame).map_err(|_| None)?;
 
-        // Ignore the Ethernet frame if it is not sent to us.
-        if !repr.dst_addr.is_broadcast() && repr.dst_addr != self.ether_addr {
+        // Ignore the Ethernet frame if it is not sent to us. The IPv6 multicast frames are
+        // accepted here and will be filtered according to their IP addresses.
+        if !repr.dst_addr.is_broadcast()
+            && repr.dst_addr != self.ether_addr
+            && !is_ipv6_multicast_ether_addr(&repr.dst_addr)
+        {
             return Err(None);
         }
 
@@ -139,12 +228,20 @@ impl<D, E: Ext> EtherIface<D, E> {
             }
             EthernetProtocol::Ipv6 => {
                 let pkt = Ipv6Packet::new_checked(frame.payload()).map_err(|_| None)?;
+                if let Some((ipv6_repr, ndisc_repr)) = parse_ndisc(&pkt) {
+                    return Err(self.process_ndisc(
+                        &ipv6_repr,
+                        &ndisc_repr,
+                        &repr.src_addr,
+                        iface_cx,
+                    ));
+                }
                 Ok(IpPacket::Ipv6(pkt))
             }
             EthernetProtocol::Arp => {
                 let pkt = ArpPacket::new_checked(frame.payload()).map_err(|_| None)?;
                 let arp = ArpRepr::parse(&pkt).map_err(|_| None)?;
-                Err(self.process_arp(&arp, iface_cx))
+                Err(self.process_arp(&arp, iface_cx).map(NeighborPacket::Arp))
             }
             _ => Err(None),
         }
@@ -207,28 +304,76 @@ impl<D, E: Ext> EtherIface<D, E> {
     }
 
     fn dispatch<T: TxToken>(&self, pkt: &Packet, iface_cx: &mut Context, tx_token: T) {
-        match self.resolve_ether_or_generate_arp(pkt, iface_cx) {
+        match self.resolve_ether_or_generate_neighbor(pkt, iface_cx) {
             Ok(ether) => Self::emit_ip(&ether, pkt, &iface_cx.caps, tx_token),
-            Err(Some(arp)) => Self::emit_arp(&arp, tx_token),
+            Err(Some(neighbor_pkt)) => {
+                self.emit_neighbor_packet(&neighbor_pkt, &iface_cx.caps, tx_token)
+            }
             Err(None) => (),
         }
     }
 
+    fn resolve_ether_or_generate_neighbor(
+        &self,
+        pkt: &Packet,
+        iface_cx: &mut Context,
+    ) -> Result<EthernetRepr, Option<NeighborPacket>> {
+        let ipv6_repr = match pkt.ip_repr() {
+            IpRepr::Ipv4(_) => return self.resolve_ether_or_generate_arp(pkt, iface_cx),
+            IpRepr::Ipv6(ipv6_repr) => ipv6_repr,
+        };
+
+        let dst_addr = ipv6_repr.dst_addr;
+        let ndisc = self.ndisc.lock();
+
+        // Resolve the next-hop Ethernet address.
+        let next_hop_ether = if dst_addr.is_multicast() {
+            ipv6_multicast_ether_addr(&dst_addr)
+        } else {
+            // Resolve the next-hop IP address.
+            let next_hop_ip = if dst_addr.is_unicast_link_local()
+                || iface_cx.in_same_network(&IpAddress::Ipv6(dst_addr))
+            {
+                dst_addr
+            } else if let Some(default_router) = ndisc.default_router {
+                default_router
+            } else {
+                return Err(None);
+            };
+
+            if let Some(next_hop_ether) = ndisc.neighbors.get(&next_hop_ip) {
+                *next_hop_ether
+            } else {
+                // Like ARP, we drop the original packet and send a neighbor solicitation instead.
+                let solicited_node = solicited_node_addr(&next_hop_ip);
+                return Err(Some(self.new_ndisc(
+                    ipv6_repr.src_addr,
+                    solicited_node,
+                    ipv6_multicast_ether_addr(&solicited_node),
+                    NdiscRepr::NeighborSolicit {
+                        target_addr: next_hop_ip,
+                        lladdr: Some(self.raw_ether_addr()),
+                    },
+                )));
+            }
+        };
+
+        Ok(EthernetRepr {
+            src_addr: self.ether_addr,
+            dst_addr: next_hop_ether,
+            ethertype: EthernetProtocol::Ipv6,
+        })
+    }
+
     fn resolve_ether_or_generate_arp(
         &self,
         pkt: &Packet,
         iface_cx: &mut Context,
-    ) -> Result<EthernetRepr, Option<ArpRepr>> {
+    ) -> Result<EthernetRepr, Option<NeighborPacket>> {
         // Resolve the next-hop IP address.
         let next_hop_ip = match iface_cx.route(&pkt.ip_repr().dst_addr(), iface_cx.now()) {
             Some(IpAddress::Ipv4(next_hop_ip)) => next_hop_ip,
-            Some(IpAddress::Ipv6(_)) => {
-                // FIXME: Currently, we drop outbound IPv6 packets because neighbor discovery is not
-                // implemented and we have no way to resolve the next-hop link-layer address.
-                ostd::debug!("IPv6 neighbor discovery is not implemented for Ethernet interfaces");
-                return Err(None);
-            }
-            None => return Err(None),
+            Some(IpAddress::Ipv6(_)) | None => return Err(None),
         };
 
         // Resolve the next-hop Ethernet address.
@@ -240,13 +385,13 @@ impl<D, E: Ext> EtherIface<D, E> {
             // If the next-hop Ethernet address cannot be resolved, we drop the original packet and
             // send an ARP packet instead. The upper layer should be responsible for detecting the
             // packet loss and retrying later to see if the Ethernet address is ready.
-            return Err(Some(ArpRepr::EthernetIpv4 {
+            return Err(Some(NeighborPacket::Arp(ArpRepr::EthernetIpv4 {
                 operation: ArpOperation::Request,
                 source_hardware_addr: self.ether_addr,
                 source_protocol_addr: iface_cx.ipv4_addr().unwrap_or(Ipv4Address::UNSPECIFIED),
                 target_hardware_addr: EthernetAddress::BROADCAST,
                 target_protocol_addr: next_hop_ip,
-            }));
+            })));
         };
 
         Ok(EthernetRepr {
@@ -280,6 +425,34 @@ impl<D, E: Ext> EtherIface<D, E> {
         );
     }
 
+    /// Consumes the token and emits a packet of the neighbor discovery protocols.
+    fn emit_neighbor_packet<T: TxToken>(
+        &self,
+        neighbor_pkt: &NeighborPacket,
+        caps: &DeviceCapabilities,
+        tx_token: T,
+    ) {
+        match neighbor_pkt {
+            NeighborPacket::Arp(arp_repr) => Self::emit_arp(arp_repr, tx_token),
+            NeighborPacket::Ndisc {
+                dst_ether,
+                ipv6_repr,
+                ndisc_repr,
+            } => {
+                let ether_repr = EthernetRepr {
+                    src_addr: self.ether_addr,
+                    dst_addr: *dst_ether,
+                    ethertype: EthernetProtocol::Ipv6,
+                };
+                let ip_pkt = Packet::new(
+                    IpRepr::Ipv6(*ipv6_repr),
+                    IpPayload::Icmpv6(Icmpv6Repr::Ndisc(*ndisc_repr)),
+                );
+                Self::emit_ip(&ether_repr, &ip_pkt, caps, tx_token);
+            }
+        }
+    }
+
     /// Consumes the token and emits an ARP packet.
     fn emit_arp<T: TxToken>(arp_repr: &ArpRepr, tx_token: T) {
         let ether_repr = match arp_repr {
@@ -304,3 +477,266 @@ impl<D, E: Ext> EtherIface<D, E> {
         });
     }
 }
+
+impl<D, E: Ext> EtherIface<D, E> {
+    fn process_ndisc(
+        &self,
+        ipv6_repr: &Ipv6Repr,
+        ndisc_repr: &NdiscRepr,
+        src_ether: &EthernetAddress,
+        iface_cx: &mut Context,
+    ) -> Option<NeighborPacket> {
+        let src_addr = ipv6_repr.src_addr;
+
+        match ndisc_repr {
+            NdiscRepr::NeighborSolicit { target_addr, .. } => {
+                // Ignore the neighbor solicitation if we do not own the target address.
+                if !iface_cx.has_ip_addr(IpAddress::Ipv6(*target_addr)) {
+                    return None;
+                }
+
+                // If the source address is unspecified, the solicitation is for duplicate address
+                // detection and the advertisement should be sent to all nodes.
+                let (dst_addr, dst_ether, flags) = if src_addr.is_unspecified() {
+                    (
+                        IPV6_LINK_LOCAL_ALL_NODES,
+                        ipv6_multicast_ether_addr(&IPV6_LINK_LOCAL_ALL_NODES),
+                        NdiscNeighborFlags::OVERRIDE,
+                    )
+                } else {
+                    self.ndisc.lock().neighbors.insert(src_addr, *src_ether);
+                    (
+                        src_addr,
+                        *src_ether,
+                        NdiscNeighborFlags::SOLICITED | NdiscNeighborFlags::OVERRIDE,
+                    )
+                };
+
+                Some(self.new_ndisc(
+                    *target_addr,
+                    dst_addr,
+                    dst_ether,
+                    NdiscRepr::NeighborAdvert {
+                        flags,
+                        target_addr: *target_addr,
+                        lladdr: Some(self.raw_ether_addr()),
+                    },
+                ))
+            }
+            NdiscRepr::NeighborAdvert {
+                target_addr,
+                lladdr,
+                ..
+            } => {
+                let target_ether = lladdr.and_then(|lladdr| parse_raw_ether_addr(&lladdr))?;
+                if !target_addr.is_multicast() && target_ether.is_unicast() {
+                    self.ndisc
+                        .lock()
+                        .neighbors
+                        .insert(*target_addr, target_ether);
+                }
+                None
+            }
+            NdiscRepr::RouterAdvert {
+                router_lifetime,
+                lladdr,
+                prefix_info,
+                ..
+            } => {
+                // Routers must use their link-local addresses as the source addresses.
+                if !src_addr.is_unicast_link_local() {
+                    return None;
+                }
+
+                let mut ndisc = self.ndisc.lock();
+                if !ndisc.is_autoconf {
+                    return None;
+                }
+
+                if let Some(router_ether) = lladdr.and_then(|lladdr| parse_raw_ether_addr(&lladdr))
+                {
+                    ndisc.neighbors.insert(src_addr, router_ether);
+                }
+
+                // A zero router lifetime indicates that the router is not a default router.
+                if *router_lifetime != Duration::ZERO {
+                    ndisc.default_router = Some(src_addr);
+                } else if ndisc.default_router == Some(src_addr) {
+                    ndisc.default_router = None;
+                }
+
+                // TODO: Remove the address when its valid lifetime expires.
+                if let Some(prefix_info) = prefix_info
+                    && prefix_info.flags.contains(NdiscPrefixInfoFlags::ADDRCONF)
+                    && prefix_info.prefix_len == SLAAC_PREFIX_LEN
+                    && !prefix_info.prefix.is_unicast_link_local()
+                {
+                    let addr = slaac_addr(&prefix_info.prefix, &self.ether_addr);
+                    ndisc
+                        .pending_addrs
+                        .push(Ipv6Cidr::new(addr, SLAAC_PREFIX_LEN));
+                }
+
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Sends a router solicitation if it has been requested.
+    fn solicit_routers<Dev: Device + ?Sized>(&self, device: &mut Dev) {
+        let mut ndisc = self.ndisc.lock();
+        if !ndisc.needs_router_solicit {
+            return;
+        }
+        let caps = device.capabilities();
+        let Some(tx_token) = device.transmit(get_network_timestamp()) else {
+            return;
+        };
+        ndisc.needs_router_solicit = false;
+        drop(ndisc);
+
+        let src_addr = slaac_addr(&IPV6_LINK_LOCAL_PREFIX, &self.ether_addr);
+        let router_solicit = self.new_ndisc(
+            src_addr,
+            IPV6_LINK_LOCAL_ALL_ROUTERS,
+            ipv6_multicast_ether_addr(&IPV6_LINK_LOCAL_ALL_ROUTERS),
+            NdiscRepr::RouterSolicit {
+                lladdr: Some(self.raw_ether_addr()),
+            },
+        );
+        self.emit_neighbor_packet(&router_solicit, &caps, tx_token);
+    }
+
+    /// Adds the addresses configured from the router advertisements to the interface.
+    fn add_pending_ipv6_addrs(&self) {
+        let pending_addrs = core::mem::take(&mut self.ndisc.lock().pending_addrs);
+        for cidr in pending_addrs {
+            self.common.add_ip_cidr(wire::IpCidr::Ipv6(cidr));
+        }
+    }
+
+    fn new_ndisc(
+        &self,
+        src_addr: Ipv6Address,
+        dst_addr: Ipv6Address,
+        dst_ether: EthernetAddress,
+        ndisc_repr: NdiscRepr<'static>,
+    ) -> NeighborPacket {
+        NeighborPacket::Ndisc {
+            dst_ether,
+            ipv6_repr: Ipv6Repr {
+                src_addr,
+                dst_addr,
+                next_header: IpProtocol::Icmpv6,
+                payload_len: Icmpv6Repr::Ndisc(ndisc_repr).buffer_len(),
+                hop_limit: NDISC_HOP_LIMIT,
+            },
+            ndisc_repr,
+        }
+    }
+
+    fn raw_ether_addr(&self) -> RawHardwareAddress {
+        RawHardwareAddress::from_bytes(self.ether_addr.as_bytes())
+    }
+}
+
+/// The hop limit of NDISC messages, which ensures that the messages are not forwarded by routers.
+const NDISC_HOP_LIMIT: u8 = 255;
+
+const IPV6_LINK_LOCAL_PREFIX: Ipv6Address = Ipv6Address::new(0xfe80, 0, 0, 0, 0, 0, 0, 0);
+const IPV6_LINK_LOCAL_ALL_NODES: Ipv6Address = Ipv6Address::new(0xff02, 0, 0, 0, 0, 0, 0, 1);
+const IPV6_LINK_LOCAL_ALL_ROUTERS: Ipv6Address = Ipv6Address::new(0xff02, 0, 0, 0, 0, 0, 0, 2);
+
+/// The prefix length of the addresses configured by SLAAC.
+///
+/// The interface identifiers derived from the Ethernet addresses always have 64 bits.
+const SLAAC_PREFIX_LEN: u8 = 64;
+
+/// Parses the NDISC message in the IPv6 packet, if any.
+fn parse_ndisc<'pkt>(pkt: &Ipv6Packet<&'pkt [u8]>) -> Option<(Ipv6Repr, NdiscRepr<'pkt>)> {
+    let ipv6_repr = Ipv6Repr::parse(pkt).ok()?;
+    if ipv6_repr.next_header != IpProtocol::Icmpv6 || ipv6_repr.hop_limit != NDISC_HOP_LIMIT {
+        return None;
+    }
+
+    let icmp_payload = pkt.payload();
+    let icmp_pkt = Icmpv6Packet::new_checked(icmp_payload).ok()?;
+    if !matches!(
+        icmp_pkt.msg_type(),
+        Icmpv6Message::RouterSolicit
+            | Icmpv6Message::RouterAdvert
+            | Icmpv6Message::NeighborSolicit
+            | Icmpv6Message::NeighborAdvert
+            | Icmpv6Message::Redirect
+    ) {
+        return None;
+    }
+    if icmp_checksum(
+        icmp_payload,
+        Some((&ipv6_repr.src_addr, &ipv6_repr.dst_addr)),
+    ) != 0
+    {
+        return None;
+    }
+
+    let ndisc_repr = NdiscRepr::parse(&icmp_pkt).ok()?;
+    Some((ipv6_repr, ndisc_repr))
+}
+
+fn parse_raw_ether_addr(raw_addr: &RawHardwareAddress) -> Option<EthernetAddress> {
+    let bytes = raw_addr.as_bytes();
+    (bytes.len() == 6).then(|| EthernetAddress::from_bytes(bytes))
+}
+
+/// Returns the address whose interface identifier is derived from the Ethernet address.
+///
+/// The interface identifier is in the modified EUI-64 format.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc4291#appendix-A>.
+fn slaac_addr(prefix: &Ipv6Address, ether_addr: &EthernetAddress) -> Ipv6Address {
+    let mac = ether_addr.as_bytes();
+
+    let mut octets = prefix.octets();
+    octets[8..].copy_from_slice(&[
+        mac[0] ^ 0x02,
+        mac[1],
+        mac[2],
+        0xff,
+        0xfe,
+        mac[3],
+        mac[4],
+        mac[5],
+    ]);
+
+    Ipv6Address::from(octets)
+}
+
+/// Returns the solicited-node multicast address of the IPv6 address.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc4291#section-2.7.1>.
+fn solicited_node_addr(addr: &Ipv6Address) -> Ipv6Address {
+    let octets = addr.octets();
+    Ipv6Address::new(
+        0xff02,
+        0,
+        0,
+        0,
+        0,
+        1,
+        0xff00 | octets[13] as u16,
+        u16::from_be_bytes([octets[14], octets[15]]),
+    )
+}
+
+/// Returns the Ethernet address that the IPv6 multicast address is mapped to.
+///
+/// Reference: <https://datatracker.ietf.org/doc/html/rfc2464#section-7>.
+fn ipv6_multicast_ether_addr(addr: &Ipv6Address) -> EthernetAddress {
+    let octets = addr.octets();
+    EthernetAddress([0x33, 0x33, octets[12], octets[13], octets[14], octets[15]])
+}
+
+fn is_ipv6_multicast_ether_addr(ether_addr: &EthernetAddress) -> bool {
+    ether_addr.as_bytes().starts_with(&[0x33, 0x33])
+}
@@ -15,6 +15,7 @@ use crate::{
         Iface, InterfaceName, ScheduleNextPoll,
         common::{IfaceCommon, InterfaceFlags, InterfaceType, IpPacket},
         iface::internal::IfaceInternal,
+        tap::TappedDevice,
         time::get_network_timestamp,
     },
 };
@@ -25,10 +26,15 @@ pub struct IpIface<D, E: Ext> {
 }
 
 impl<D: WithDevice, E: Ext> IpIface<D, E> {
-    // TODO: Support interfaces with multiple IPv4/IPv6 addresses or without IPv4 addresses.
+    /// Creates a new IP interface.
+    ///
+    /// The interface may have no IPv4 address (e.g., a newly created TUN device), in which case
+    /// `ip_cidr` should be `None`.
+    //
+    // TODO: Support interfaces with multiple IPv4/IPv6 addresses.
     pub fn new(
         driver: D,
-        ip_cidr: Ipv4Cidr,
+        ip_cidr: Option<Ipv4Cidr>,
         ipv6_cidr: Option<Ipv6Cidr>,
         name: InterfaceName,
         sched_poll: E::ScheduleNextPoll,
@@ -42,7 +48,9 @@ impl<D: WithDevice, E: Ext> IpIface<D, E> {
             let mut interface = smoltcp::iface::Interface::new(config, device, now);
             interface.update_ip_addrs(|ip_addrs| {
                 debug_assert!(ip_addrs.is_empty());
-                ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
+                if let Some(ip_cidr) = ip_cidr {
+                    ip_addrs.push(wire::IpCidr::Ipv4(ip_cidr)).unwrap();
+                }
                 if let Some(ipv6_cidr) = ipv6_cidr {
                     ip_addrs.push(wire::IpCidr::Ipv6(ipv6_cidr)).unwrap();
                 }
@@ -70,7 +78,7 @@ impl<D: WithDevice + 'static, E: Ext> Iface<E> for IpIface<D, E> {
     fn poll(&self) {
         self.driver.with(|device| {
             let next_poll = self.common.poll(
-                device,
+                &mut TappedDevice::<_, E>::new(device, self.common.id()),
                 |data, _iface_cx, tx_token| {
                     if data.is_empty() {
                         return None;
@@ -99,7 +107,7 @@ impl<D: WithDevice + 'static, E: Ext> Iface<E> for IpIface<D, E> {
                     });
                 },
             );
-            self.common.sched_poll().schedule_next_poll(next_poll);
+            self.common.schedule_next_poll(next_poll);
         });
     }
 
@@ -107,4 +115,14 @@ impl<D: WithDevice + 'static, E: Ext> Iface<E> for IpIface<D, E> {
         self.driver
             .with(|device| device.capabilities().max_transmission_unit)
     }
+
+    fn transmit_frame(&self, frame: &[u8]) -> bool {
+        self.driver.with(|device| {
+            let Some(tx_token) = device.transmit(get_network_timestamp()) else {
+                return false;
+            };
+            tx_token.consume(frame.len(), |buffer| buffer.copy_from_slice(frame));
+            true
+        })
+    }
 }
@@ -1,6 +1,6 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{sync::Arc, vec, vec::Vec};
+use alloc::{collections::VecDeque, sync::Arc, vec, vec::Vec};
 
 use smoltcp::{
     iface::{
@@ -9,20 +9,25 @@ use smoltcp::{
     },
     phy::{ChecksumCapabilities, Device, RxToken, TxToken},
     wire::{
-        IPV4_HEADER_LEN, IPV4_MIN_MTU, Icmpv4DstUnreachable, Icmpv4Repr, IpAddress, IpProtocol,
-        IpRepr, Ipv4Address, Ipv4Packet, Ipv4Repr, Ipv6Packet, Ipv6Repr, TcpControl, TcpPacket,
-        TcpRepr, UdpPacket, UdpRepr,
+        IPV4_HEADER_LEN, IPV4_MIN_MTU, IPV6_HEADER_LEN, Icmpv4DstUnreachable, Icmpv4Packet,
+        Icmpv4Repr, Icmpv6Message, Icmpv6Packet, Icmpv6Repr, IpAddress, IpProtocol, IpRepr,
+        Ipv4Address, Ipv4Packet, Ipv4Repr, Ipv6Packet, Ipv6Repr, TcpControl, TcpPacket, TcpRepr,
+        UdpPacket, UdpRepr,
     },
 };
 
-use super::{common::IpPacket, poll_iface::PollableIfaceMut};
+use super::{
+    FilterHook, FilterVerdict, HookIface, PacketFilter, RejectWith, common::IpPacket,
+    poll_iface::PollableIfaceMut,
+};
 use crate::{
     ext::Ext,
-    socket::{TcpConnectionBg, TcpProcessResult},
+    socket::{RawSocketKind, TcpConnectionBg, TcpProcessResult, icmp_checksum},
     socket_table::{ConnectionKey, ListenerKey, SocketTable},
 };
 
 pub(super) struct PollContext<'a, E: Ext> {
+    iface_id: u32,
     iface: PollableIfaceMut<'a, E>,
     sockets: &'a SocketTable<E>,
     actions: &'a mut Vec<SocketTableAction<E>>,
@@ -39,11 +44,13 @@ pub(super) enum SocketTableAction<E: Ext> {
 
 impl<'a, E: Ext> PollContext<'a, E> {
     pub(super) fn new(
+        iface_id: u32,
         iface: PollableIfaceMut<'a, E>,
         sockets: &'a SocketTable<E>,
         actions: &'a mut Vec<SocketTableAction<E>>,
     ) -> Self {
         Self {
+            iface_id,
             iface,
             sockets,
             actions,
@@ -80,12 +87,22 @@ impl<E: Ext> PollContext<'_, E> {
                     return;
                 };
 
-                let reply = match ip_packet {
-                    IpPacket::Ipv4(p) => self.parse_and_process_ipv4(p),
-                    IpPacket::Ipv6(p) => self.parse_and_process_ipv6(p),
+                // The hooks may mangle the packet, so they need a mutable copy.
+                let mut filtered;
+                let reply = if E::PacketFilter::is_active() {
+                    filtered = match ip_packet {
+                        IpPacket::Ipv4(p) => p.into_inner().to_vec(),
+                        IpPacket::Ipv6(p) => p.into_inner().to_vec(),
+                    };
+                    self.filter_and_process_ip(&mut filtered)
+                } else {
+                    match ip_packet {
+                        IpPacket::Ipv4(p) => self.parse_and_process_ipv4(p),
+                        IpPacket::Ipv6(p) => self.parse_and_process_ipv6(p),
+                    }
                 };
                 let Some(reply) = reply else { return };
-                dispatch_phy(&reply, self.iface.context_mut(), tx_token);
+                self.dispatch_local(&reply, tx_token, dispatch_phy);
             });
         }
     }
@@ -98,6 +115,9 @@ impl<E: Ext> PollContext<'_, E> {
         let repr = Ipv4Repr::parse(&pkt, &self.iface.context().checksum_caps()).ok()?;
 
         if !repr.dst_addr.is_broadcast() && !self.is_unicast_local(IpAddress::Ipv4(repr.dst_addr)) {
+            if E::PacketFilter::forward(self.iface_id, &pkt.as_ref()[..pkt.total_len() as usize]) {
+                return None;
+            }
             return self.generate_icmp_unreachable(
                 &IpRepr::Ipv4(repr),
                 pkt.payload(),
@@ -105,6 +125,12 @@ impl<E: Ext> PollContext<'_, E> {
             );
         }
 
+        // IPv4 raw sockets receive the IP header as well.
+        self.process_raw(
+            &IpRepr::Ipv4(repr),
+            &pkt.as_ref()[..pkt.total_len() as usize],
+        );
+
         let checksum_caps = self.iface.context().checksum_caps();
         match repr.next_header {
             IpProtocol::Tcp => {
@@ -113,6 +139,7 @@ impl<E: Ext> PollContext<'_, E> {
             IpProtocol::Udp => {
                 self.parse_and_process_udp(&IpRepr::Ipv4(repr), pkt.payload(), &checksum_caps)
             }
+            IpProtocol::Icmp => self.parse_and_process_icmpv4(&repr, pkt.payload(), &checksum_caps),
             _ => None,
         }
     }
@@ -125,10 +152,15 @@ impl<E: Ext> PollContext<'_, E> {
         let repr = Ipv6Repr::parse(&pkt).ok()?;
 
         if !self.is_unicast_local(IpAddress::Ipv6(repr.dst_addr)) {
-            // TODO: Generate an IPv6 ICMP unreachable message.
+            let total_len = IPV6_HEADER_LEN + pkt.payload_len() as usize;
+            let _ = E::PacketFilter::forward(self.iface_id, &pkt.as_ref()[..total_len]);
+            // TODO: Generate an IPv6 ICMP unreachable message if the packet is not forwarded.
             return None;
         }
 
+        // IPv6 raw sockets never receive the IP header.
+        self.process_raw(&IpRepr::Ipv6(repr), pkt.payload());
+
         let checksum_caps = self.iface.context().checksum_caps();
         match repr.next_header {
             IpProtocol::Tcp => {
@@ -137,10 +169,49 @@ impl<E: Ext> PollContext<'_, E> {
             IpProtocol::Udp => {
                 self.parse_and_process_udp(&IpRepr::Ipv6(repr), pkt.payload(), &checksum_caps)
             }
+            IpProtocol::Icmpv6 => self.parse_and_process_icmpv6(&repr, pkt.payload()),
             _ => None,
         }
     }
 
+    fn parse_and_process_ip<'pkt>(&mut self, data: &'pkt [u8]) -> Option<Packet<'pkt>> {
+        match data.first()? >> 4 {
+            4 => self.parse_and_process_ipv4(Ipv4Packet::new_checked(data).ok()?),
+            6 => self.parse_and_process_ipv6(Ipv6Packet::new_checked(data).ok()?),
+            _ => None,
+        }
+    }
+
+    /// Passes a received IP packet through the input hooks, and processes it if it is accepted.
+    fn filter_and_process_ip<'pkt>(&mut self, data: &'pkt mut [u8]) -> Option<Packet<'pkt>> {
+        let hook_iface = self.hook_iface();
+
+        if E::PacketFilter::filter(FilterHook::PreRouting, &hook_iface, data)
+            != FilterVerdict::Accept
+        {
+            return None;
+        }
+
+        // The addresses may have been translated, so the destination is checked after
+        // `FilterHook::PreRouting`. Packets that are not delivered locally are forwarded in
+        // `parse_and_process_ip` and pass through `FilterHook::Forward` there.
+        let (ip_repr, _) = parse_ip(data)?;
+        let dst_addr = ip_repr.dst_addr();
+        if dst_addr.is_broadcast() || self.is_unicast_local(dst_addr) {
+            match E::PacketFilter::filter(FilterHook::LocalIn, &hook_iface, data) {
+                FilterVerdict::Accept => (),
+                FilterVerdict::Drop => return None,
+                FilterVerdict::Reject(reject_with) => {
+                    let data: &'pkt [u8] = data;
+                    let (ip_repr, ip_payload) = parse_ip(data)?;
+                    return self.generate_reject_reply(&ip_repr, ip_payload, reject_with);
+                }
+            }
+        }
+
+        self.parse_and_process_ip(data)
+    }
+
     fn parse_and_process_tcp<'pkt>(
         &mut self,
         ip_repr: &IpRepr,
@@ -176,7 +247,9 @@ impl<E: Ext> PollContext<'_, E> {
         let (mut ip_repr, mut tcp_repr) = self.process_tcp(ip_repr, tcp_repr)?;
 
         loop {
-            if !self.is_unicast_local(ip_repr.dst_addr()) {
+            // If the packet filter is active, the replies sent to local addresses must pass
+            // through the hooks, so they are returned and processed by `dispatch_local`.
+            if !self.is_unicast_local(ip_repr.dst_addr()) || E::PacketFilter::is_active() {
                 return Some((ip_repr, tcp_repr));
             }
 
@@ -318,6 +391,118 @@ impl<E: Ext> PollContext<'_, E> {
         processed
     }
 
+    fn parse_and_process_icmpv4<'pkt>(
+        &mut self,
+        ipv4_repr: &Ipv4Repr,
+        ip_payload: &'pkt [u8],
+        checksum_caps: &ChecksumCapabilities,
+    ) -> Option<Packet<'pkt>> {
+        // Parse the ICMP message. Ignore the packet if the message is ill-formed or unsupported.
+        let icmp_pkt = Icmpv4Packet::new_checked(ip_payload).ok()?;
+        let icmp_repr = Icmpv4Repr::parse(&icmp_pkt, checksum_caps).ok()?;
+
+        match icmp_repr {
+            Icmpv4Repr::EchoRequest {
+                ident,
+                seq_no,
+                data,
+            } => {
+                // Like Linux, ignore echo requests sent to broadcast addresses. See
+                // `icmp_echo_ignore_broadcasts` in
+                // <https://www.kernel.org/doc/Documentation/networking/ip-sysctl.txt>.
+                if !self.is_unicast_local(IpAddress::Ipv4(ipv4_repr.dst_addr)) {
+                    return None;
+                }
+
+                let icmp_reply = Icmpv4Repr::EchoReply {
+                    ident,
+                    seq_no,
+                    data,
+                };
+                Some(Packet::new_ipv4(
+                    Ipv4Repr {
+                        src_addr: ipv4_repr.dst_addr,
+                        dst_addr: ipv4_repr.src_addr,
+                        next_header: IpProtocol::Icmp,
+                        payload_len: icmp_reply.buffer_len(),
+                        hop_limit: 64,
+                    },
+                    IpPayload::Icmpv4(icmp_reply),
+                ))
+            }
+            Icmpv4Repr::EchoReply { ident, .. } => {
+                self.process_echo_reply(&IpRepr::Ipv4(*ipv4_repr), ident, ip_payload);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_and_process_icmpv6<'pkt>(
+        &mut self,
+        ipv6_repr: &Ipv6Repr,
+        ip_payload: &'pkt [u8],
+    ) -> Option<Packet<'pkt>> {
+        /// The length of the ICMPv6 echo header.
+        const ECHO_HEADER_LEN: usize = 8;
+
+        // Parse the ICMPv6 header. Ignore the packet if the header is ill-formed or the checksum
+        // is wrong.
+        let icmp_pkt = Icmpv6Packet::new_checked(ip_payload).ok()?;
+        if icmp_checksum(ip_payload, Some((&ipv6_repr.src_addr, &ipv6_repr.dst_addr))) != 0 {
+            return None;
+        }
+
+        // The neighbor discovery messages are handled by the Ethernet interfaces.
+        //
+        // TODO: Handle other ICMPv6 messages, such as the multicast listener discovery messages.
+        match icmp_pkt.msg_type() {
+            Icmpv6Message::EchoRequest if ip_payload.len() >= ECHO_HEADER_LEN => {
+                let icmp_reply = Icmpv6Repr::EchoReply {
+                    ident: icmp_pkt.echo_ident(),
+                    seq_no: icmp_pkt.echo_seq_no(),
+                    data: &ip_payload[ECHO_HEADER_LEN..],
+                };
+                Some(Packet::new(
+                    IpRepr::Ipv6(Ipv6Repr {
+                        src_addr: ipv6_repr.dst_addr,
+                        dst_addr: ipv6_repr.src_addr,
+                        next_header: IpProtocol::Icmpv6,
+                        payload_len: icmp_reply.buffer_len(),
+                        hop_limit: 64,
+                    }),
+                    IpPayload::Icmpv6(icmp_reply),
+                ))
+            }
+            Icmpv6Message::EchoReply if ip_payload.len() >= ECHO_HEADER_LEN => {
+                let ident = icmp_pkt.echo_ident();
+                self.process_echo_reply(&IpRepr::Ipv6(*ipv6_repr), ident, ip_payload);
+                None
+            }
+            _ => None,
+        }
+    }
+
+    /// Passes an ICMP echo reply to the ICMP echo sockets bound to the identifier.
+    fn process_echo_reply(&mut self, ip_repr: &IpRepr, ident: u16, icmp_message: &[u8]) {
+        for socket in self.sockets.raw_socket_iter() {
+            if socket.kind() == RawSocketKind::Ping && socket.can_process(ident) {
+                socket.process(ip_repr, icmp_message);
+            }
+        }
+    }
+
+    /// Passes a copy of an incoming packet to the raw sockets bound to the IP protocol.
+    fn process_raw(&mut self, ip_repr: &IpRepr, data: &[u8]) {
+        let protocol = u8::from(ip_repr.next_header()) as u16;
+
+        for socket in self.sockets.raw_socket_iter() {
+            if socket.kind() == RawSocketKind::Raw && socket.can_process(protocol) {
+                socket.process(ip_repr, data);
+            }
+        }
+    }
+
     fn generate_icmp_unreachable<'pkt>(
         &self,
         ip_repr: &IpRepr,
@@ -365,6 +550,49 @@ impl<E: Ext> PollContext<'_, E> {
         ))
     }
 
+    /// Generates the error reply to a rejected packet.
+    fn generate_reject_reply<'pkt>(
+        &self,
+        ip_repr: &IpRepr,
+        ip_payload: &'pkt [u8],
+        reject_with: RejectWith,
+    ) -> Option<Packet<'pkt>> {
+        match reject_with {
+            RejectWith::IcmpUnreachable(code) => {
+                self.generate_icmp_unreachable(ip_repr, ip_payload, code.into())
+            }
+            RejectWith::TcpReset => {
+                if ip_repr.next_header() != IpProtocol::Tcp {
+                    return None;
+                }
+
+                let tcp_pkt = TcpPacket::new_checked(ip_payload).ok()?;
+                let tcp_repr = TcpRepr::parse(
+                    &tcp_pkt,
+                    &ip_repr.src_addr(),
+                    &ip_repr.dst_addr(),
+                    &self.iface.context().checksum_caps(),
+                )
+                .ok()?;
+                if tcp_repr.control == TcpControl::Rst {
+                    return None;
+                }
+
+                let (ip_repr, tcp_repr) =
+                    smoltcp::socket::tcp::Socket::rst_reply(ip_repr, &tcp_repr);
+                Some(Packet::new(ip_repr, IpPayload::Tcp(tcp_repr)))
+            }
+        }
+    }
+
+    /// Returns the iface information passed to [`PacketFilter::filter`].
+    fn hook_iface(&self) -> HookIface {
+        HookIface {
+            id: self.iface_id,
+            ipv4_addr: self.iface.context().ipv4_addr(),
+        }
+    }
+
     /// Returns whether the destination address is the unicast address of a local interface.
     ///
     /// Note: "local" means that the IP address belongs to the local interface, not to be confused
@@ -376,11 +604,9 @@ impl<E: Ext> PollContext<'_, E> {
                 .context()
                 .ipv4_addr()
                 .is_some_and(|addr| addr == dst_addr),
-            IpAddress::Ipv6(dst_addr) => self
-                .iface
-                .context()
-                .ipv6_addr()
-                .is_some_and(|addr| addr == dst_addr),
+            // An interface may have multiple IPv6 addresses (e.g., a link-local address and a
+            // global address configured by SLAAC).
+            IpAddress::Ipv6(dst_addr) => self.iface.context().has_ip_addr(dst_addr),
         }
     }
 }
@@ -398,6 +624,39 @@ impl<E: Ext> PollContext<'_, E> {
         }
     }
 
+    pub(super) fn poll_forwarded<D, Q>(
+        &mut self,
+        device: &mut D,
+        forwarded: VecDeque<(Vec<u8>, Option<RejectWith>)>,
+        dispatch_phy: &mut Q,
+    ) where
+        D: Device + ?Sized,
+        Q: FnMut(&Packet, &mut Context, D::TxToken<'_>),
+    {
+        for (data, reject_with) in forwarded {
+            let Some((ip_repr, ip_payload)) = parse_ip(&data) else {
+                continue;
+            };
+
+            // Like Linux, the forwarded packets are dropped if the device cannot transmit them.
+            let Some(tx_token) = device.transmit(self.iface.context().now()) else {
+                break;
+            };
+
+            if let Some(reject_with) = reject_with {
+                if let Some(reply) = self.generate_reject_reply(&ip_repr, ip_payload, reject_with) {
+                    self.dispatch_local(&reply, tx_token, dispatch_phy);
+                }
+            } else {
+                dispatch_phy(
+                    &Packet::new(ip_repr, IpPayload::Raw(ip_payload)),
+                    self.iface.context_mut(),
+                    tx_token,
+                );
+            }
+        }
+    }
+
     fn dispatch_ip<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> bool
     where
         T: TxToken,
@@ -409,9 +668,15 @@ impl<E: Ext> PollContext<'_, E> {
             return did_something_tcp;
         };
 
-        let (did_something_udp, _tx_token) = self.dispatch_udp(tx_token, dispatch_phy);
+        let (did_something_udp, tx_token) = self.dispatch_udp(tx_token, dispatch_phy);
+
+        let Some(tx_token) = tx_token else {
+            return did_something_tcp || did_something_udp;
+        };
+
+        let (did_something_raw, _tx_token) = self.dispatch_raw(tx_token, dispatch_phy);
 
-        did_something_tcp || did_something_udp
+        did_something_tcp || did_something_udp || did_something_raw
     }
 
     fn dispatch_tcp<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
@@ -432,16 +697,27 @@ impl<E: Ext> PollContext<'_, E> {
             did_something = true;
 
             let mut deferred = None;
+            let mut deferred_ip = None;
 
             let (reply, became_dead) =
                 TcpConnectionBg::dispatch(&socket, &mut self.iface, |iface, ip_repr, tcp_repr| {
-                    let mut this = PollContext::new(iface, self.sockets, self.actions);
+                    let mut this =
+                        PollContext::new(self.iface_id, iface, self.sockets, self.actions);
 
                     if !this.is_unicast_local(ip_repr.dst_addr()) {
-                        dispatch_phy(
+                        this.dispatch_local(
                             &Packet::new(ip_repr.clone(), IpPayload::Tcp(*tcp_repr)),
-                            this.iface.context_mut(),
                             tx_token.take().unwrap(),
+                            dispatch_phy,
+                        );
+                        return None;
+                    }
+
+                    // The packet must pass through the hooks before it is processed. We will do
+                    // this after releasing the socket lock.
+                    if E::PacketFilter::is_active() {
+                        deferred_ip = Some(
+                            this.emit_ip(&Packet::new(ip_repr.clone(), IpPayload::Tcp(*tcp_repr))),
                         );
                         return None;
                     }
@@ -471,6 +747,10 @@ impl<E: Ext> PollContext<'_, E> {
                     .push(SocketTableAction::DelTcpConn(*socket.connection_key()));
             }
 
+            if let Some(data) = deferred_ip {
+                self.process_ip_until_outgoing(data, &mut tx_token, dispatch_phy);
+            }
+
             match (deferred, reply) {
                 (None, None) => (),
                 (Some((ip_repr, ip_payload)), None) => {
@@ -479,24 +759,24 @@ impl<E: Ext> PollContext<'_, E> {
                         &ip_payload,
                         &ChecksumCapabilities::ignored(),
                     ) {
-                        dispatch_phy(&reply, self.iface.context_mut(), tx_token.take().unwrap());
+                        self.dispatch_local(&reply, tx_token.take().unwrap(), dispatch_phy);
                     }
                 }
                 (None, Some((ip_repr, tcp_repr))) if !self.is_unicast_local(ip_repr.dst_addr()) => {
-                    dispatch_phy(
+                    self.dispatch_local(
                         &Packet::new(ip_repr, IpPayload::Tcp(tcp_repr)),
-                        self.iface.context_mut(),
                         tx_token.take().unwrap(),
+                        dispatch_phy,
                     );
                 }
                 (None, Some((ip_repr, tcp_repr))) => {
                     if let Some((new_ip_repr, new_tcp_repr)) =
                         self.process_tcp_until_outgoing(&ip_repr, &tcp_repr)
                     {
-                        dispatch_phy(
+                        self.dispatch_local(
                             &Packet::new(new_ip_repr, IpPayload::Tcp(new_tcp_repr)),
-                            self.iface.context_mut(),
                             tx_token.take().unwrap(),
+                            dispatch_phy,
                         );
                     }
                 }
@@ -531,23 +811,34 @@ impl<E: Ext> PollContext<'_, E> {
             did_something = true;
 
             let mut deferred = None;
+            let mut deferred_ip = None;
 
             let (cx, pending) = self.iface.inner_mut();
             socket.dispatch(cx, |cx, ip_repr, udp_repr, udp_payload| {
                 let iface = PollableIfaceMut::new(cx, pending);
-                let mut this = PollContext::new(iface, self.sockets, &mut actions);
+                let mut this = PollContext::new(self.iface_id, iface, self.sockets, &mut actions);
 
                 if ip_repr.dst_addr().is_broadcast() || !this.is_unicast_local(ip_repr.dst_addr()) {
-                    dispatch_phy(
+                    this.dispatch_local(
                         &Packet::new(ip_repr.clone(), IpPayload::Udp(*udp_repr, udp_payload)),
-                        this.iface.context_mut(),
                         tx_token.take().unwrap(),
+                        dispatch_phy,
                     );
                     if !ip_repr.dst_addr().is_broadcast() {
                         return;
                     }
                 }
 
+                // The packet must pass through the hooks before it is processed. We will do this
+                // after releasing the socket lock.
+                if E::PacketFilter::is_active() {
+                    deferred_ip = Some(this.emit_ip(&Packet::new(
+                        ip_repr.clone(),
+                        IpPayload::Udp(*udp_repr, udp_payload),
+                    )));
+                    return;
+                }
+
                 if !socket.can_process(udp_repr.dst_port) {
                     // TODO: Generate the ICMP message here once we're able to handle incoming ICMP
                     // messages.
@@ -571,6 +862,10 @@ impl<E: Ext> PollContext<'_, E> {
                 }));
             });
 
+            if let Some(data) = deferred_ip {
+                self.process_ip_until_outgoing(data, &mut tx_token, dispatch_phy);
+            }
+
             if let Some((ip_repr, ip_payload)) = deferred
                 && let Some(reply) = self.parse_and_process_udp(
                     &ip_repr,
@@ -578,7 +873,7 @@ impl<E: Ext> PollContext<'_, E> {
                     &ChecksumCapabilities::ignored(),
                 )
             {
-                dispatch_phy(&reply, self.iface.context_mut(), tx_token.take().unwrap());
+                self.dispatch_local(&reply, tx_token.take().unwrap(), dispatch_phy);
             }
 
             if tx_token.is_none() {
@@ -593,4 +888,163 @@ impl<E: Ext> PollContext<'_, E> {
 
         (did_something, tx_token)
     }
+
+    fn dispatch_raw<T, Q>(&mut self, tx_token: T, dispatch_phy: &mut Q) -> (bool, Option<T>)
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        let mut tx_token = Some(tx_token);
+        let mut did_something = false;
+
+        for socket in self.sockets.raw_socket_iter() {
+            if !socket.need_dispatch() {
+                continue;
+            }
+
+            // Unlike UDP sockets, the packet is moved out of the socket, so it is safe to process
+            // the packet locally without causing deadlocks.
+            let Some((ip_repr, ip_payload)) = socket.dispatch() else {
+                continue;
+            };
+            did_something = true;
+
+            let dst_addr = ip_repr.dst_addr();
+            let is_local = self.is_unicast_local(dst_addr);
+
+            if dst_addr.is_broadcast() || !is_local {
+                self.dispatch_local(
+                    &Packet::new(ip_repr.clone(), IpPayload::Raw(&ip_payload)),
+                    tx_token.take().unwrap(),
+                    dispatch_phy,
+                );
+            }
+
+            if dst_addr.is_broadcast() || is_local {
+                let data = self.emit_ip(&Packet::new(ip_repr, IpPayload::Raw(&ip_payload)));
+                self.process_ip_until_outgoing(data, &mut tx_token, dispatch_phy);
+            }
+
+            if tx_token.is_none() {
+                break;
+            }
+        }
+
+        (did_something, tx_token)
+    }
+
+    /// Processes a locally generated IP packet sent to a local address.
+    ///
+    /// If the packet filter is active, the packet passes through the output hooks and then the
+    /// input hooks, as if it were sent and received via a loopback device.
+    ///
+    /// If a reply is generated, the reply is processed in the same way until it is sent to a
+    /// non-local address, in which case it will be dispatched if `tx_token` is available.
+    fn process_ip_until_outgoing<T, Q>(
+        &mut self,
+        mut data: Vec<u8>,
+        tx_token: &mut Option<T>,
+        dispatch_phy: &mut Q,
+    ) where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        loop {
+            let reply = if E::PacketFilter::is_active() {
+                if !self.filter_output(&mut data) {
+                    return;
+                }
+                self.filter_and_process_ip(&mut data)
+            } else {
+                self.parse_and_process_ip(&data)
+            };
+            let Some(reply) = reply else {
+                return;
+            };
+
+            if !self.is_unicast_local(reply.ip_repr().dst_addr()) {
+                if let Some(tx_token) = tx_token.take() {
+                    self.dispatch_local(&reply, tx_token, dispatch_phy);
+                }
+                return;
+            }
+
+            data = self.emit_ip(&reply);
+        }
+    }
+
+    /// Dispatches a locally generated IP packet after passing it through the output hooks.
+    ///
+    /// If the packet filter is active, the packet may be sent to a local address (see
+    /// `process_tcp_until_outgoing`). In this case, it is processed locally instead.
+    fn dispatch_local<T, Q>(&mut self, packet: &Packet, tx_token: T, dispatch_phy: &mut Q)
+    where
+        T: TxToken,
+        Q: FnMut(&Packet, &mut Context, T),
+    {
+        if !E::PacketFilter::is_active() {
+            dispatch_phy(packet, self.iface.context_mut(), tx_token);
+            return;
+        }
+
+        let mut data = self.emit_ip(packet);
+        if self.is_unicast_local(packet.ip_repr().dst_addr()) {
+            self.process_ip_until_outgoing(data, &mut Some(tx_token), dispatch_phy);
+            return;
+        }
+
+        if !self.filter_output(&mut data) {
+            return;
+        }
+
+        let Some((ip_repr, ip_payload)) = parse_ip(&data) else {
+            return;
+        };
+        dispatch_phy(
+            &Packet::new(ip_repr, IpPayload::Raw(ip_payload)),
+            self.iface.context_mut(),
+            tx_token,
+        );
+    }
+
+    /// Passes a locally generated IP packet through the output hooks.
+    ///
+    /// Returns whether the packet is accepted.
+    fn filter_output(&self, data: &mut [u8]) -> bool {
+        let hook_iface = self.hook_iface();
+
+        // Error replies are never generated for locally generated packets.
+        [FilterHook::LocalOut, FilterHook::PostRouting]
+            .into_iter()
+            .all(|hook| E::PacketFilter::filter(hook, &hook_iface, data) == FilterVerdict::Accept)
+    }
+
+    /// Emits an IP packet into a buffer.
+    fn emit_ip(&self, packet: &Packet) -> Vec<u8> {
+        let context = self.iface.context();
+        let ip_repr = packet.ip_repr();
+
+        let mut data = vec![0; ip_repr.buffer_len()];
+        ip_repr.emit(&mut data[..], &context.checksum_caps());
+        packet.emit_payload(&ip_repr, &mut data[ip_repr.header_len()..], &context.caps);
+
+        data
+    }
+}
+
+/// Parses the IP header of a packet built or checked by the network stack.
+fn parse_ip(data: &[u8]) -> Option<(IpRepr, &[u8])> {
+    match data.first()? >> 4 {
+        4 => {
+            let pkt = Ipv4Packet::new_checked(data).ok()?;
+            let repr = Ipv4Repr::parse(&pkt, &ChecksumCapabilities::ignored()).ok()?;
+            Some((IpRepr::Ipv4(repr), pkt.payload()))
+        }
+        6 => {
+            let pkt = Ipv6Packet::new_checked(data).ok()?;
+            let repr = Ipv6Repr::parse(&pkt).ok()?;
+            Some((IpRepr::Ipv6(repr), pkt.payload()))
+        }
+        _ => None,
+    }
 }
@@ -1,11 +1,13 @@
 // SPDX-License-Identifier: MPL-2.0
 
-use alloc::{collections::btree_set::BTreeSet, sync::Arc};
+use alloc::{collections::btree_set::BTreeSet, sync::Arc, vec::Vec};
 use core::{
     borrow::Borrow,
     sync::atomic::{AtomicU64, Ordering},
 };
 
+use smoltcp::wire::IpAddress;
+
 use crate::{
     ext::Ext,
     socket::{NeedIfacePoll, TcpConnectionBg},
@@ -55,6 +57,112 @@ impl<E: Ext> PollableIface<E> {
         })
     }
 
+    pub(super) fn ipv4_cidrs(&self) -> Vec<smoltcp::wire::Ipv4Cidr> {
+        self.interface
+            .ip_addrs()
+            .iter()
+            .filter_map(|cidr| {
+                if let smoltcp::wire::IpCidr::Ipv4(ipv4_cidr) = cidr {
+                    Some(*ipv4_cidr)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    pub(super) fn ipv6_cidrs(&self) -> Vec<smoltcp::wire::Ipv6Cidr> {
+        self.interface
+            .ip_addrs()
+            .iter()
+            .filter_map(|cidr| {
+                if let smoltcp::wire::IpCidr::Ipv6(ipv6_cidr) = cidr {
+                    Some(*ipv6_cidr)
+                } else {
+                    None
+                }
+            })
+            .collect()
+    }
+
+    /// Adds an IP CIDR to the interface.
+    ///
+    /// This method returns `false` if the address already exists or there are too many addresses.
+    pub(super) fn add_ip_cidr(&mut self, ip_cidr: smoltcp::wire::IpCidr) -> bool {
+        let mut is_added = false;
+
+        self.interface.update_ip_addrs(|ip_addrs| {
+            if ip_addrs
+                .iter()
+                .any(|cidr| cidr.address() == ip_cidr.address())
+            {
+                return;
+            }
+            is_added = ip_addrs.push(ip_cidr).is_ok();
+        });
+
+        is_added
+    }
+
+    /// Removes an IP CIDR from the interface.
+    ///
+    /// This method returns `false` if the CIDR does not exist.
+    pub(super) fn remove_ip_cidr(&mut self, ip_cidr: smoltcp::wire::IpCidr) -> bool {
+        let mut is_removed = false;
+
+        self.interface.update_ip_addrs(|ip_addrs| {
+            if let Some(pos) = ip_addrs.iter().position(|cidr| *cidr == ip_cidr) {
+                ip_addrs.remove(pos);
+                is_removed = true;
+            }
+        });
+
+        is_removed
+    }
+
+    /// Adds a route that forwards packets to the destination CIDR via the router.
+    ///
+    /// This method returns `false` if there are too many routes.
+    pub(super) fn add_route(&mut self, cidr: smoltcp::wire::IpCidr, via_router: IpAddress) -> bool {
+        let mut is_added = false;
+
+        self.interface.routes_mut().update(|routes| {
+            is_added = routes
+                .push(smoltcp::iface::Route {
+                    cidr,
+                    via_router,
+                    preferred_until: None,
+                    expires_at: None,
+                })
+                .is_ok();
+        });
+
+        is_added
+    }
+
+    /// Removes a route added by [`Self::add_route`].
+    ///
+    /// This method returns `false` if the route does not exist.
+    pub(super) fn remove_route(
+        &mut self,
+        cidr: smoltcp::wire::IpCidr,
+        via_router: IpAddress,
+    ) -> bool {
+        let mut is_removed = false;
+
+        self.interface.routes_mut().update(|routes| {
+            if let Some(pos) = routes
+                .iter()
+                .position(|route| route.cidr == cidr && route.via_router == via_router)
+            {
+                routes.remove(pos);
+                is_removed = true;
+            }
+        });
+
+        is_removed
+    }
+
     /// Returns the next poll time.
     pub(super) fn next_poll_at_ms(&self) -> Option<u64> {
         self.pending_conns.next_poll_at_ms()
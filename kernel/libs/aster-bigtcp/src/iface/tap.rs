@@ -0,0 +1,135 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use core::marker::PhantomData;
+
+use smoltcp::{
+    phy::{Device, DeviceCapabilities, RxToken, TxToken},
+    time::Instant,
+};
+
+use crate::ext::Ext;
+
+/// The direction of a frame that passes through an iface.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum FrameDirection {
+    /// The frame is received by the iface.
+    Incoming,
+    /// The frame is transmitted by the iface.
+    Outgoing,
+}
+
+/// A trait for observing the raw frames that pass through ifaces.
+///
+/// This allows users of this crate to implement packet sockets (i.e., `AF_PACKET` sockets in
+/// Linux), which capture the frames before they are processed by the network stack.
+pub trait FrameTap {
+    /// Called when the iface with the given ID receives or transmits a frame.
+    ///
+    /// The frame starts with the link-layer header, if the iface has one.
+    ///
+    /// This method is called while the iface is being polled, so it must not poll ifaces or
+    /// perform any blocking operations.
+    fn on_frame(iface_id: u32, frame: &[u8], direction: FrameDirection);
+}
+
+/// A device that passes all the frames that it receives or transmits to [`FrameTap`].
+pub(super) struct TappedDevice<'a, D: ?Sized, E> {
+    inner: &'a mut D,
+    iface_id: u32,
+    phantom: PhantomData<E>,
+}
+
+impl<'a, D: ?Sized, E> TappedDevice<'a, D, E> {
+    pub(super) fn new(inner: &'a mut D, iface_id: u32) -> Self {
+        Self {
+            inner,
+            iface_id,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<D: Device + ?Sized, E: Ext> Device for TappedDevice<'_, D, E> {
+    type RxToken<'a>
+        = TappedRxToken<D::RxToken<'a>, E>
+    where
+        Self: 'a;
+    type TxToken<'a>
+        = TappedTxToken<D::TxToken<'a>, E>
+    where
+        Self: 'a;
+
+    fn receive(&mut self, timestamp: Instant) -> Option<(Self::RxToken<'_>, Self::TxToken<'_>)> {
+        let (rx_token, tx_token) = self.inner.receive(timestamp)?;
+        Some((
+            TappedRxToken::new(rx_token, self.iface_id),
+            TappedTxToken::new(tx_token, self.iface_id),
+        ))
+    }
+
+    fn transmit(&mut self, timestamp: Instant) -> Option<Self::TxToken<'_>> {
+        let tx_token = self.inner.transmit(timestamp)?;
+        Some(TappedTxToken::new(tx_token, self.iface_id))
+    }
+
+    fn capabilities(&self) -> DeviceCapabilities {
+        self.inner.capabilities()
+    }
+}
+
+pub(super) struct TappedRxToken<T, E> {
+    inner: T,
+    iface_id: u32,
+    phantom: PhantomData<E>,
+}
+
+impl<T, E> TappedRxToken<T, E> {
+    fn new(inner: T, iface_id: u32) -> Self {
+        Self {
+            inner,
+            iface_id,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: RxToken, E: Ext> RxToken for TappedRxToken<T, E> {
+    fn consume<R, F>(self, f: F) -> R
+    where
+        F: FnOnce(&[u8]) -> R,
+    {
+        self.inner.consume(|frame| {
+            E::FrameTap::on_frame(self.iface_id, frame, FrameDirection::Incoming);
+            f(frame)
+        })
+    }
+}
+
+pub(super) struct TappedTxToken<T, E> {
+    inner: T,
+    iface_id: u32,
+    phantom: PhantomData<E>,
+}
+
+impl<T, E> TappedTxToken<T, E> {
+    fn new(inner: T, iface_id: u32) -> Self {
+        Self {
+            inner,
+            iface_id,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: TxToken, E: Ext> TxToken for TappedTxToken<T, E> {
+    fn consume<R, F>(self, len: usize, f: F) -> R
+    where
+        F: FnOnce(&mut [u8]) -> R,
+    {
+        self.inner.consume(len, |frame| {
+            let res = f(frame);
+            E::FrameTap::on_frame(self.iface_id, frame, FrameDirection::Outgoing);
+            res
+        })
+    }
+}
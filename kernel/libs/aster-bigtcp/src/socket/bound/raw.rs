@@ -0,0 +1,394 @@
+// SPDX-License-Identifier: MPL-2.0
+
+use alloc::{collections::vec_deque::VecDeque, sync::Arc, vec::Vec};
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use aster_softirq::BottomHalfDisabled;
+use ostd::sync::SpinLock;
+use smoltcp::{
+    phy::ChecksumCapabilities,
+    wire::{IpAddress, IpProtocol, IpRepr, Ipv4Packet, Ipv4Repr, Ipv6Address},
+};
+
+use super::{
+    ReceiveBehavior,
+    common::{Inner, Socket, SocketBg},
+};
+use crate::{
+    errors::raw::{RecvError, SendError},
+    ext::Ext,
+    iface::BoundRawPort,
+    socket::{
+        event::SocketEvents,
+        unbound::{RAW_RECV_BUF_LEN, RAW_SEND_BUF_LEN},
+    },
+};
+
+pub type RawSocket<E> = Socket<RawSocketInner, E>;
+
+/// The kind of a [`RawSocket`].
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RawSocketKind {
+    /// A raw socket, which sends and receives packets of an IP protocol.
+    ///
+    /// The port number of the bound port is the IP protocol number.
+    Raw,
+    /// An ICMP echo socket (a.k.a. a ping socket), which sends echo requests and receives the
+    /// matching echo replies.
+    ///
+    /// The port number of the bound port is the echo identifier.
+    Ping,
+}
+
+/// States needed by [`RawSocketBg`].
+pub struct RawSocketInner {
+    kind: RawSocketKind,
+    recv_queue: SpinLock<PacketQueue<IpAddress>, BottomHalfDisabled>,
+    send_queue: SpinLock<PacketQueue<IpRepr>, BottomHalfDisabled>,
+    need_dispatch: AtomicBool,
+}
+
+impl<E: Ext> Inner<E> for RawSocketInner {
+    type BoundPort = BoundRawPort<E>;
+    type Observer = E::RawEventObserver;
+
+    fn on_drop(this: &Arc<SocketBg<Self, E>>) {
+        // A raw socket can be removed immediately.
+        this.bound.iface().common().remove_raw_socket(this);
+    }
+}
+
+pub(crate) type RawSocketBg<E> = SocketBg<RawSocketInner, E>;
+
+impl<E: Ext> RawSocketBg<E> {
+    pub(crate) fn kind(&self) -> RawSocketKind {
+        self.inner.kind
+    }
+
+    /// Processes an incoming packet if the packet is sent to the bound address.
+    ///
+    /// `data` is what the socket should receive. The caller is responsible for checking whether
+    /// the socket is interested in the packet (e.g., by checking the IP protocol).
+    pub(crate) fn process(&self, ip_repr: &IpRepr, data: &[u8]) {
+        let dst_addr = ip_repr.dst_addr();
+        let local_addr = *self.bound.addr();
+
+        let is_same_family = matches!(
+            (dst_addr, local_addr),
+            (IpAddress::Ipv4(_), IpAddress::Ipv4(_)) | (IpAddress::Ipv6(_), IpAddress::Ipv6(_))
+        );
+        if !is_same_family || (dst_addr.is_unicast() && dst_addr != local_addr) {
+            return;
+        }
+
+        // Copy the packet before locking the queue, which disables bottom halves.
+        let data = data.to_vec();
+        if !self.inner.recv_queue.lock().push(ip_repr.src_addr(), data) {
+            return;
+        }
+
+        self.notify_events(SocketEvents::CAN_RECV);
+    }
+
+    /// Dequeues an outgoing packet, which consists of the IP header and the IP payload.
+    pub(crate) fn dispatch(&self) -> Option<(IpRepr, Vec<u8>)> {
+        let mut send_queue = self.inner.send_queue.lock();
+        let packet = send_queue.pop();
+        self.inner
+            .need_dispatch
+            .store(!send_queue.is_empty(), Ordering::Relaxed);
+        drop(send_queue);
+
+        if packet.is_some() {
+            self.notify_events(SocketEvents::CAN_SEND);
+        }
+
+        packet
+    }
+
+    /// Returns whether the socket _may_ generate an outgoing packet.
+    ///
+    /// The check is intended to be lock-free and fast, but may have false positives.
+    pub(crate) fn need_dispatch(&self) -> bool {
+        self.inner.need_dispatch.load(Ordering::Relaxed)
+    }
+}
+
+impl<E: Ext> RawSocket<E> {
+    /// Binds to a specified port, whose meaning depends on `kind`.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn new_bind(
+        bound: BoundRawPort<E>,
+        kind: RawSocketKind,
+        observer: E::RawEventObserver,
+    ) -> Self {
+        let inner = RawSocketInner {
+            kind,
+            recv_queue: SpinLock::new(PacketQueue::new(RAW_RECV_BUF_LEN)),
+            send_queue: SpinLock::new(PacketQueue::new(RAW_SEND_BUF_LEN)),
+            need_dispatch: AtomicBool::new(false),
+        };
+
+        let socket = Self::new(bound, inner);
+        socket.init_observer(observer);
+        socket
+            .iface()
+            .common()
+            .register_raw_socket(socket.inner().clone());
+
+        socket
+    }
+
+    /// Sends a packet to `dst_addr`.
+    ///
+    /// For raw sockets, `data` is the IP payload, or the whole IPv4 packet if `hdrincl` is true.
+    /// For ICMP echo sockets, `data` is an ICMP echo request, whose identifier and checksum will
+    /// be filled in.
+    ///
+    /// Polling the iface is _always_ required after this method succeeds.
+    pub fn send(
+        &self,
+        data: &[u8],
+        dst_addr: IpAddress,
+        hop_limit: u8,
+        hdrincl: bool,
+    ) -> Result<(), SendError> {
+        let src_addr = *self.0.bound.addr();
+
+        let (ip_repr, payload) = match self.0.inner.kind {
+            RawSocketKind::Raw if hdrincl => build_hdrincl_packet(data, src_addr)?,
+            RawSocketKind::Raw => {
+                let protocol = IpProtocol::from(self.0.bound.port() as u8);
+                let mut payload = data.to_vec();
+                // Like Linux, compute the checksum for ICMPv6 messages, which is mandatory for
+                // ICMPv6 and depends on the IPv6 addresses. See
+                // <https://datatracker.ietf.org/doc/html/rfc3542#section-3.1>.
+                if let (IpProtocol::Icmpv6, IpAddress::Ipv6(src), IpAddress::Ipv6(dst)) =
+                    (protocol, src_addr, dst_addr)
+                    && payload.len() >= ICMP_CHECKSUM_END
+                {
+                    fill_icmp_checksum(&mut payload, Some((&src, &dst)));
+                }
+                let ip_repr = new_ip_repr(src_addr, dst_addr, protocol, payload.len(), hop_limit)?;
+                (ip_repr, payload)
+            }
+            RawSocketKind::Ping => {
+                let ident = self.0.bound.port();
+                let (protocol, payload) = build_echo_request(data, ident, src_addr, dst_addr)?;
+                let ip_repr = new_ip_repr(src_addr, dst_addr, protocol, payload.len(), hop_limit)?;
+                (ip_repr, payload)
+            }
+        };
+
+        if ip_repr.buffer_len() > u16::MAX as usize {
+            return Err(SendError::TooLarge);
+        }
+
+        let mut send_queue = self.0.inner.send_queue.lock();
+        if !send_queue.push(ip_repr, payload) {
+            return Err(SendError::BufferFull);
+        }
+        self.0.inner.need_dispatch.store(true, Ordering::Relaxed);
+
+        Ok(())
+    }
+
+    /// Receives a packet and its source address.
+    ///
+    /// Polling the iface is _not_ required after this method succeeds.
+    pub fn recv(&self, behavior: ReceiveBehavior) -> Result<(Vec<u8>, IpAddress), RecvError> {
+        let mut recv_queue = self.0.inner.recv_queue.lock();
+
+        let packet = match behavior {
+            ReceiveBehavior::Recv => recv_queue.pop(),
+            ReceiveBehavior::Peek => recv_queue
+                .front()
+                .map(|(src_addr, data)| (*src_addr, data.clone())),
+        };
+
+        packet
+            .map(|(src_addr, data)| (data, src_addr))
+            .ok_or(RecvError::Exhausted)
+    }
+
+    /// Returns whether there are packets in the receive buffer.
+    pub fn can_recv(&self) -> bool {
+        !self.0.inner.recv_queue.lock().is_empty()
+    }
+
+    /// Returns whether the send buffer has free space.
+    pub fn can_send(&self) -> bool {
+        !self.0.inner.send_queue.lock().is_full()
+    }
+}
+
+fn new_ip_repr(
+    src_addr: IpAddress,
+    dst_addr: IpAddress,
+    protocol: IpProtocol,
+    payload_len: usize,
+    hop_limit: u8,
+) -> Result<IpRepr, SendError> {
+    match (src_addr, dst_addr) {
+        (IpAddress::Ipv4(_), IpAddress::Ipv4(_)) | (IpAddress::Ipv6(_), IpAddress::Ipv6(_)) => Ok(
+            IpRepr::new(src_addr, dst_addr, protocol, payload_len, hop_limit),
+        ),
+        _ => Err(SendError::Unaddressable),
+    }
+}
+
+/// Builds an IPv4 packet from the IP header and the IP payload provided by the user.
+///
+/// Like Linux, the total length and the header checksum are always filled in, and the source
+/// address is filled in if it is unspecified. IP options are not supported and will be dropped.
+fn build_hdrincl_packet(data: &[u8], src_addr: IpAddress) -> Result<(IpRepr, Vec<u8>), SendError> {
+    let IpAddress::Ipv4(src_addr) = src_addr else {
+        return Err(SendError::Unaddressable);
+    };
+    if data.len() > u16::MAX as usize {
+        return Err(SendError::TooLarge);
+    }
+
+    let mut buffer = data.to_vec();
+    if buffer.len() < IPV4_TOTAL_LEN_END {
+        return Err(SendError::InvalidPacket);
+    }
+    Ipv4Packet::new_unchecked(buffer.as_mut_slice()).set_total_len(data.len() as u16);
+
+    let packet =
+        Ipv4Packet::new_checked(buffer.as_slice()).map_err(|_| SendError::InvalidPacket)?;
+    let mut ipv4_repr = Ipv4Repr::parse(&packet, &ChecksumCapabilities::ignored())
+        .map_err(|_| SendError::InvalidPacket)?;
+    if ipv4_repr.src_addr.is_unspecified() {
+        ipv4_repr.src_addr = src_addr;
+    }
+
+    Ok((IpRepr::Ipv4(ipv4_repr), packet.payload().to_vec()))
+}
+
+/// Builds an ICMP echo request from the message provided by the user.
+fn build_echo_request(
+    data: &[u8],
+    ident: u16,
+    src_addr: IpAddress,
+    dst_addr: IpAddress,
+) -> Result<(IpProtocol, Vec<u8>), SendError> {
+    // Reference: <https://www.iana.org/assignments/icmp-parameters/icmp-parameters.xhtml>
+    const ICMPV4_ECHO_REQUEST: u8 = 8;
+    // Reference: <https://www.iana.org/assignments/icmpv6-parameters/icmpv6-parameters.xhtml>
+    const ICMPV6_ECHO_REQUEST: u8 = 128;
+
+    let (protocol, echo_request_type, addrs) = match (src_addr, dst_addr) {
+        (IpAddress::Ipv4(_), IpAddress::Ipv4(_)) => (IpProtocol::Icmp, ICMPV4_ECHO_REQUEST, None),
+        (IpAddress::Ipv6(src), IpAddress::Ipv6(dst)) => {
+            (IpProtocol::Icmpv6, ICMPV6_ECHO_REQUEST, Some((src, dst)))
+        }
+        _ => return Err(SendError::Unaddressable),
+    };
+
+    // The type and code are the first two bytes of the ICMP header. The identifier follows the
+    // checksum.
+    if data.len() < ICMP_ECHO_HEADER_LEN || data[0] != echo_request_type || data[1] != 0 {
+        return Err(SendError::InvalidPacket);
+    }
+
+    let mut payload = data.to_vec();
+    payload[ICMP_CHECKSUM_END..ICMP_CHECKSUM_END + 2].copy_from_slice(&ident.to_be_bytes());
+    fill_icmp_checksum(&mut payload, addrs.as_ref().map(|(src, dst)| (src, dst)));
+
+    Ok((protocol, payload))
+}
+
+/// The length of the ICMP echo header, which consists of the type, the code, the checksum, the
+/// identifier, and the sequence number.
+const ICMP_ECHO_HEADER_LEN: usize = 8;
+/// The end of the ICMP checksum field.
+const ICMP_CHECKSUM_END: usize = 4;
+/// The end of the total length field in the IPv4 header.
+const IPV4_TOTAL_LEN_END: usize = 4;
+
+/// Fills in the checksum of an ICMP message.
+///
+/// For ICMPv6 messages, `ipv6_addrs` specifies the source and destination addresses that are
+/// covered by the checksum.
+fn fill_icmp_checksum(message: &mut [u8], ipv6_addrs: Option<(&Ipv6Address, &Ipv6Address)>) {
+    message[2..ICMP_CHECKSUM_END].fill(0);
+    let checksum = icmp_checksum(message, ipv6_addrs);
+    message[2..ICMP_CHECKSUM_END].copy_from_slice(&checksum.to_be_bytes());
+}
+
+/// Computes the checksum of an ICMP message.
+///
+/// The checksum of a valid message is zero if the checksum field is not cleared in advance.
+pub(crate) fn icmp_checksum(
+    message: &[u8],
+    ipv6_addrs: Option<(&Ipv6Address, &Ipv6Address)>,
+) -> u16 {
+    fn sum_words(bytes: &[u8]) -> u64 {
+        bytes
+            .chunks(2)
+            .map(|chunk| u16::from_be_bytes([chunk[0], chunk.get(1).copied().unwrap_or(0)]) as u64)
+            .sum()
+    }
+
+    let mut sum = sum_words(message);
+    if let Some((src_addr, dst_addr)) = ipv6_addrs {
+        // See <https://datatracker.ietf.org/doc/html/rfc8200#section-8.1> for the pseudo-header.
+        sum += sum_words(&src_addr.octets());
+        sum += sum_words(&dst_addr.octets());
+        sum += message.len() as u64;
+        sum += u8::from(IpProtocol::Icmpv6) as u64;
+    }
+
+    while sum >> 16 != 0 {
+        sum = (sum & 0xFFFF) + (sum >> 16);
+    }
+    !(sum as u16)
+}
+
+/// A queue of packets with a limited total length.
+struct PacketQueue<M> {
+    packets: VecDeque<(M, Vec<u8>)>,
+    total_len: usize,
+    capacity: usize,
+}
+
+impl<M> PacketQueue<M> {
+    const fn new(capacity: usize) -> Self {
+        Self {
+            packets: VecDeque::new(),
+            total_len: 0,
+            capacity,
+        }
+    }
+
+    /// Pushes a packet and returns whether the packet fits in the queue.
+    fn push(&mut self, meta: M, data: Vec<u8>) -> bool {
+        if self.capacity - self.total_len < data.len() {
+            return false;
+        }
+
+        self.total_len += data.len();
+        self.packets.push_back((meta, data));
+        true
+    }
+
+    fn pop(&mut self) -> Option<(M, Vec<u8>)> {
+        let packet = self.packets.pop_front()?;
+        self.total_len -= packet.1.len();
+        Some(packet)
+    }
+
+    fn front(&self) -> Option<&(M, Vec<u8>)> {
+        self.packets.front()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.packets.is_empty()
+    }
+
+    fn is_full(&self) -> bool {
+        self.total_len >= self.capacity
+    }
+}
@@ -6,12 +6,15 @@ mod option;
 mod unbound;
 
 pub use bound::{
-    ConnectState, NeedIfacePoll, RawTcpSocketExt, ReceiveBehavior, TcpConnection, TcpListener,
-    UdpSocket,
+    ConnectState, NeedIfacePoll, RawSocket, RawSocketKind, RawTcpSocketExt, ReceiveBehavior,
+    TcpConnection, TcpListener, UdpSocket,
+};
+pub(crate) use bound::{
+    RawSocketBg, TcpConnectionBg, TcpListenerBg, TcpProcessResult, UdpSocketBg, icmp_checksum,
 };
-pub(crate) use bound::{TcpConnectionBg, TcpListenerBg, TcpProcessResult, UdpSocketBg};
 pub use event::{SocketEventObserver, SocketEvents};
 pub use option::{RawTcpOption, RawTcpSetOption};
 pub use unbound::{
-    RawUdpSocket, TCP_RECV_BUF_LEN, TCP_SEND_BUF_LEN, UDP_RECV_PAYLOAD_LEN, UDP_SEND_PAYLOAD_LEN,
+    RAW_RECV_BUF_LEN, RAW_SEND_BUF_LEN, RawUdpSocket, TCP_RECV_BUF_LEN, TCP_SEND_BUF_LEN,
+    UDP_RECV_PAYLOAD_LEN, UDP_SEND_PAYLOAD_LEN,
 };
@@ -1,7 +1,8 @@
 // SPDX-License-Identifier: MPL-2.0
 
 pub use smoltcp::wire::{
-    EthernetAddress, IpAddress, IpCidr, IpEndpoint, Ipv4Address, Ipv4Cidr, Ipv6Address, Ipv6Cidr,
+    EthernetAddress, EthernetFrame, IpAddress, IpCidr, IpEndpoint, Ipv4Address, Ipv4Cidr,
+    Ipv6Address, Ipv6Cidr,
 };
 
 pub type PortNum = u16;
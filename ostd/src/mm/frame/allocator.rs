@@ -5,6 +5,7 @@
 use core::{alloc::Layout, ops::Range};
 
 use align_ext::AlignExt;
+use spin::Once;
 
 use super::{Frame, meta::AnyFrameMeta, segment::Segment};
 use crate::{
@@ -13,6 +14,7 @@ use crate::{
     impl_frame_meta_for,
     mm::{PAGE_SIZE, paddr_to_vaddr},
     prelude::*,
+    task::{Task, atomic_mode},
     util::ops::range_difference,
 };
 
@@ -53,8 +55,7 @@ impl FrameAllocOptions {
     /// Allocates a single frame with additional metadata.
     pub fn alloc_frame_with<M: AnyFrameMeta>(&self, metadata: M) -> Result<Frame<M>> {
         let single_layout = Layout::from_size_align(PAGE_SIZE, PAGE_SIZE).unwrap();
-        let frame = get_global_frame_allocator()
-            .alloc(single_layout)
+        let frame = alloc_or_reclaim(single_layout)
             .map(|paddr| Frame::from_unused(paddr, metadata).unwrap())
             .ok_or(Error::NoMemory)?;
 
@@ -88,8 +89,7 @@ impl FrameAllocOptions {
             return Err(Error::InvalidArgs);
         }
         let layout = Layout::from_size_align(nframes * PAGE_SIZE, PAGE_SIZE).unwrap();
-        let segment = get_global_frame_allocator()
-            .alloc(layout)
+        let segment = alloc_or_reclaim(layout)
             .map(|start| {
                 Segment::from_unused(start..start + nframes * PAGE_SIZE, metadata_fn).unwrap()
             })
@@ -105,6 +105,61 @@ impl FrameAllocOptions {
     }
 }
 
+/// A handler that reclaims memory when the global frame allocator is exhausted.
+///
+/// The handler is called with the number of frames requested by the failed
+/// allocation. It returns `true` if some memory may have been freed and the
+/// allocation is worth retrying, or `false` otherwise.
+pub type ReclaimHandler = fn(usize) -> bool;
+
+static RECLAIM_HANDLER: Once<ReclaimHandler> = Once::new();
+
+/// The maximum number of times to retry a failed allocation after reclaiming memory.
+const MAX_RECLAIM_RETRIES: usize = 16;
+
+/// Injects a handler that reclaims memory when the global frame allocator is
+/// exhausted.
+///
+/// The handler is only called for allocations outside [the atomic mode],
+/// so it can sleep (e.g., to write pages back to storage). Allocations in the
+/// atomic mode fail immediately if the allocator is exhausted.
+///
+/// The handler may allocate frames itself. It is the handler's responsibility
+/// to avoid infinite recursion.
+///
+/// The function may be called only once; subsequent calls take no effect.
+///
+/// [the atomic mode]: crate::task::atomic_mode
+pub fn inject_reclaim_handler(handler: ReclaimHandler) {
+    RECLAIM_HANDLER.call_once(|| handler);
+}
+
+/// Allocates frames from the global frame allocator, reclaiming memory if the
+/// allocator is exhausted and it is allowed to sleep.
+fn alloc_or_reclaim(layout: Layout) -> Option<Paddr> {
+    let allocator = get_global_frame_allocator();
+    if let Some(paddr) = allocator.alloc(layout) {
+        return Some(paddr);
+    }
+
+    let handler = RECLAIM_HANDLER.get()?;
+    if atomic_mode::is_in_atomic_mode() || Task::current().is_none() {
+        return None;
+    }
+
+    let nframes = layout.size() / PAGE_SIZE;
+    for _ in 0..MAX_RECLAIM_RETRIES {
+        if !handler(nframes) {
+            return None;
+        }
+        if let Some(paddr) = allocator.alloc(layout) {
+            return Some(paddr);
+        }
+    }
+
+    None
+}
+
 #[cfg(ktest)]
 #[ktest(expect_redundant_test_prefix)]
 fn test_alloc_dealloc() {
@@ -10,6 +10,8 @@
 
 use core::{ops::Range, sync::atomic::Ordering};
 
+use spin::Once;
+
 use super::{AnyUFrameMeta, PagingLevel, page_table::PageTableConfig};
 use crate::{
     Error,
@@ -32,6 +34,26 @@ use crate::{
     task::{DisabledPreemptGuard, atomic_mode::AsAtomicModeGuard, disable_preempt},
 };
 
+/// The number of low bits that a swap entry can occupy.
+///
+/// A swap entry mapped by [`CursorMut::map_swapped`] must be less than
+/// `1 << NR_SWAP_ENTRY_BITS`.
+pub const NR_SWAP_ENTRY_BITS: u32 = 32;
+
+static SWAP_ENTRY_DROP_HANDLER: Once<fn(usize)> = Once::new();
+
+/// Injects a handler that releases swap entries.
+///
+/// A [`VmSpace`] owns the swap entries mapped into it by
+/// [`CursorMut::map_swapped`]. The handler is called with the entry when the
+/// entry is removed from the page table, either by [`CursorMut::unmap`] or by
+/// dropping the page table. It may be called in the atomic mode.
+///
+/// The function may be called only once; subsequent calls take no effect.
+pub fn inject_swap_entry_drop_handler(handler: fn(usize)) {
+    SWAP_ENTRY_DROP_HANDLER.call_once(|| handler);
+}
+
 /// A virtual address space for user-mode tasks, enabling safe manipulation of user-space memory.
 ///
 /// The `VmSpace` type provides memory isolation guarantees between user-space and
@@ -376,6 +398,31 @@ impl<'a> CursorMut<'a> {
         unsafe { self.pt_cursor.map(item) };
     }
 
+    /// Maps a swap entry into the current slot.
+    ///
+    /// The slot becomes inaccessible to the user space, and the
+    /// [`VmQueriedItem::Swapped`] item will be returned by queries until the
+    /// entry is unmapped. The ownership of the entry is transferred to the VM
+    /// space, which releases it with the handler injected by
+    /// [`inject_swap_entry_drop_handler`].
+    ///
+    /// This method will bring the cursor to the next slot after the modification.
+    ///
+    /// # Panics
+    ///
+    /// Panics if
+    ///  - the entry does not fit in [`NR_SWAP_ENTRY_BITS`] bits;
+    ///  - the current virtual address is already mapped.
+    pub fn map_swapped(&mut self, entry: usize) {
+        assert!(entry < (1 << NR_SWAP_ENTRY_BITS));
+
+        let item = VmItem::new_swapped(entry);
+
+        // SAFETY: A swap entry is not accessible by the hardware, so it is
+        // safe to map it into the userspace.
+        unsafe { self.pt_cursor.map(item) };
+    }
+
     /// Maps a range of [`IoMem`] into the current slot.
     ///
     /// The memory region to be mapped is the [`IoMem`] range starting at
@@ -447,6 +494,10 @@ impl<'a> CursorMut<'a> {
     /// Clears the mapping starting from the current slot,
     /// and returns the number of unmapped pages.
     ///
+    /// Swap entries are released but not counted as unmapped pages. However,
+    /// if a whole page table node is removed, the swap entries inside it are
+    /// counted as well.
+    ///
     /// This method will bring the cursor forward by `len` bytes in the virtual
     /// address space after the modification.
     ///
@@ -508,6 +559,16 @@ impl<'a> CursorMut<'a> {
                             // handled here might be one segment of it.
                             self.flusher.issue_tlb_flush(TlbFlushOp::for_single(va));
                         }
+                        VmItem {
+                            mapped_item: MappedItem::Swapped(swapped),
+                            ..
+                        } => {
+                            panic_guard.forget();
+
+                            // Swap entries are never cached in the TLB, so
+                            // they can be released immediately.
+                            drop(swapped);
+                        }
                     }
                 }
                 PageTableFrag::StrayPageTable {
@@ -548,6 +609,8 @@ impl<'a> CursorMut<'a> {
     /// make the decision yourself on when and how to flush the TLB using
     /// [`Self::flusher`].
     ///
+    /// Swap entries are left untouched, since they never grant any access.
+    ///
     /// # Panics
     ///
     /// Panics if the length is longer than the remaining range of the cursor.
@@ -560,7 +623,9 @@ impl<'a> CursorMut<'a> {
         // in the userspace.
         unsafe {
             self.pt_cursor.protect_next(len, &mut |prop| {
-                op(&mut prop.flags, &mut prop.cache);
+                if !UserPtConfig::is_swapped(prop) {
+                    op(&mut prop.flags, &mut prop.cache);
+                }
             })
         }
     }
@@ -599,14 +664,22 @@ pub enum VmQueriedItem<'a> {
         /// The property of the slot.
         prop: PageProperty,
     },
+    /// The current slot is not accessible, the page within is swapped out.
+    Swapped {
+        /// The swap entry mapped by [`CursorMut::map_swapped`].
+        entry: usize,
+    },
 }
 
 impl VmQueriedItem<'_> {
     /// Returns the page property of the mapped item.
+    ///
+    /// A swapped-out page has no access permissions.
     pub fn prop(&self) -> &PageProperty {
         match self {
             Self::MappedRam { prop, .. } => prop,
             Self::MappedIoMem { prop, .. } => prop,
+            Self::Swapped { .. } => &UserPtConfig::SWAPPED_PROP,
         }
     }
 }
@@ -615,7 +688,7 @@ impl VmQueriedItem<'_> {
 ///
 /// This is kept private to ensure memory safety. The public interface
 /// should use `VmQueriedItem` for querying mapping information.
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Debug)]
 pub(crate) struct VmItem {
     prop: PageProperty,
     mapped_item: MappedItem,
@@ -628,16 +701,32 @@ pub(crate) struct VmItemRef<'a> {
     mapped_item: MappedItemRef<'a>,
 }
 
-#[derive(Clone, Debug, PartialEq)]
+#[derive(Debug)]
 enum MappedItem {
     TrackedFrame(UFrame),
     UntrackedIoMem { paddr: Paddr, level: PagingLevel },
+    Swapped(SwappedEntry),
 }
 
 #[derive(Debug)]
 enum MappedItemRef<'a> {
     TrackedFrame(FrameRef<'a, dyn AnyUFrameMeta>),
     UntrackedIoMem { paddr: Paddr, level: PagingLevel },
+    Swapped { entry: usize },
+}
+
+/// A swap entry owned by a page table.
+///
+/// The entry is released by the injected handler when dropped.
+#[derive(Debug)]
+struct SwappedEntry(usize);
+
+impl Drop for SwappedEntry {
+    fn drop(&mut self) {
+        if let Some(handler) = SWAP_ENTRY_DROP_HANDLER.get() {
+            handler(self.0);
+        }
+    }
 }
 
 impl VmItem {
@@ -656,6 +745,14 @@ impl VmItem {
             mapped_item: MappedItem::UntrackedIoMem { paddr, level: 1 },
         }
     }
+
+    /// Creates a new `VmItem` that holds a swap entry.
+    fn new_swapped(entry: usize) -> Self {
+        Self {
+            prop: UserPtConfig::SWAPPED_PROP,
+            mapped_item: MappedItem::Swapped(SwappedEntry(entry)),
+        }
+    }
 }
 
 impl<'a> From<VmItemRef<'a>> for VmQueriedItem<'a> {
@@ -672,6 +769,7 @@ impl<'a> From<VmItemRef<'a>> for VmQueriedItem<'a> {
                     prop: item.prop,
                 }
             }
+            MappedItemRef::Swapped { entry } => VmQueriedItem::Swapped { entry },
         }
     }
 }
@@ -679,6 +777,22 @@ impl<'a> From<VmItemRef<'a>> for VmQueriedItem<'a> {
 #[derive(Clone, Debug)]
 pub(crate) struct UserPtConfig {}
 
+impl UserPtConfig {
+    /// The page property of swap entries.
+    ///
+    /// Swap entries are encoded as inaccessible pages with `AVAIL2` set. The
+    /// physical address holds the swap entry shifted by the page size.
+    const SWAPPED_PROP: PageProperty = PageProperty {
+        flags: PageFlags::AVAIL2,
+        cache: CachePolicy::Writeback,
+        priv_flags: PrivilegedPageFlags::USER,
+    };
+
+    fn is_swapped(prop: &PageProperty) -> bool {
+        prop.flags.contains(PageFlags::AVAIL2)
+    }
+}
+
 // SAFETY: `item_raw_info`, `item_into_raw`, `item_from_raw`, and
 // `item_ref_from_raw` are correctly implemented with respect to the `Item` and
 // `ItemRef` types.
@@ -696,6 +810,7 @@ unsafe impl PageTableConfig for UserPtConfig {
             MappedItem::TrackedFrame(frame) => {
                 let mut prop = item.prop;
                 prop.priv_flags -= PrivilegedPageFlags::AVAIL1; // Clear AVAIL1 for tracked frames
+                prop.flags -= PageFlags::AVAIL2; // Clear AVAIL2 for mapped pages
                 let level = frame.map_level();
                 let paddr = frame.paddr();
                 (paddr, level, prop)
@@ -703,14 +818,19 @@ unsafe impl PageTableConfig for UserPtConfig {
             MappedItem::UntrackedIoMem { paddr, level } => {
                 let mut prop = item.prop;
                 prop.priv_flags |= PrivilegedPageFlags::AVAIL1; // Set AVAIL1 for I/O memory
+                prop.flags -= PageFlags::AVAIL2; // Clear AVAIL2 for mapped pages
                 (*paddr, *level, prop)
             }
+            MappedItem::Swapped(SwappedEntry(entry)) => (*entry * PAGE_SIZE, 1, item.prop),
         }
     }
 
     unsafe fn item_from_raw(paddr: Paddr, level: PagingLevel, prop: PageProperty) -> Self::Item {
         debug_assert_eq!(level, 1);
-        if prop.priv_flags.contains(PrivilegedPageFlags::AVAIL1) {
+        if Self::is_swapped(&prop) {
+            // `AVAIL2` is set, this is a swap entry.
+            VmItem::new_swapped(paddr / PAGE_SIZE)
+        } else if prop.priv_flags.contains(PrivilegedPageFlags::AVAIL1) {
             // `AVAIL1` is set, this is I/O memory.
             VmItem::new_untracked_io(paddr, prop)
         } else {
@@ -727,7 +847,15 @@ unsafe impl PageTableConfig for UserPtConfig {
         prop: PageProperty,
     ) -> Self::ItemRef<'a> {
         debug_assert_eq!(level, 1);
-        if prop.priv_flags.contains(PrivilegedPageFlags::AVAIL1) {
+        if Self::is_swapped(&prop) {
+            // `AVAIL2` is set, this is a swap entry.
+            VmItemRef {
+                prop,
+                mapped_item: MappedItemRef::Swapped {
+                    entry: paddr / PAGE_SIZE,
+                },
+            }
+        } else if prop.priv_flags.contains(PrivilegedPageFlags::AVAIL1) {
             // `AVAIL1` is set, this is I/O memory.
             VmItemRef {
                 prop,
@@ -34,11 +34,9 @@ use core::sync::atomic::Ordering;
 /// This function will panic if it is executed in atomic mode.
 #[track_caller]
 pub fn might_sleep() {
-    let preempt_count = super::preempt::cpu_local::get_guard_count();
-    let is_local_irq_enabled = crate::arch::irq::is_local_enabled();
-    if (preempt_count != 0 || !is_local_irq_enabled)
-        && !crate::IN_BOOTSTRAP_CONTEXT.load(Ordering::Relaxed)
-    {
+    if is_in_atomic_mode() && !crate::IN_BOOTSTRAP_CONTEXT.load(Ordering::Relaxed) {
+        let preempt_count = super::preempt::cpu_local::get_guard_count();
+        let is_local_irq_enabled = crate::arch::irq::is_local_enabled();
         panic!(
             "This function might break atomic mode (preempt_count = {}, is_local_irq_enabled = {})",
             preempt_count, is_local_irq_enabled
@@ -46,6 +44,11 @@ pub fn might_sleep() {
     }
 }
 
+/// Returns whether the current task is executing in atomic mode.
+pub(crate) fn is_in_atomic_mode() -> bool {
+    super::preempt::cpu_local::get_guard_count() != 0 || !crate::arch::irq::is_local_enabled()
+}
+
 /// A marker trait for guard types that enforce the atomic mode.
 ///
 /// Key kernel primitives such as `SpinLock` and `Rcu` rely on
@@ -0,0 +1,74 @@
+/* SPDX-License-Identifier: MPL-2.0 */
+
+#ifndef FILE_UTIL_H
+#define FILE_UTIL_H
+
+/*
+ * Utilities to read and write small files, such as those in procfs, sysfs, and
+ * cgroupfs.
+ *
+ * The content read by read_file() is stored in file_buf as a null-terminated
+ * string, which is overwritten by the next read.
+ */
+
+#include <fcntl.h>
+#include <stdlib.h>
+#include <string.h>
+#include <unistd.h>
+
+static char file_buf[4096];
+
+static inline ssize_t read_file(const char *path)
+{
+	int fd;
+	ssize_t len;
+
+	fd = open(path, O_RDONLY);
+	if (fd < 0)
+		return -1;
+	len = read(fd, file_buf, sizeof(file_buf) - 1);
+	close(fd);
+	if (len < 0)
+		return -1;
+	file_buf[len] = '\0';
+
+	return len;
+}
+
+static inline ssize_t write_file(const char *path, const char *str)
+{
+	int fd;
+	ssize_t len;
+
+	fd = open(path, O_WRONLY);
+	if (fd < 0)
+		return -1;
+	len = write(fd, str, strlen(str));
+	close(fd);
+
+	return len;
+}
+
+/*
+ * Returns the value of the "<key> <value>" line in the file, or -1 if the file
+ * cannot be read or there is no such line.
+ */
+static inline long read_key(const char *path, const char *key)
+{
+	size_t key_len = strlen(key);
+	char *line;
+
+	if (read_file(path) < 0)
+		return -1;
+
+	for (line = file_buf; line != NULL; line = strchr(line, '\n')) {
+		if (*line == '\n')
+			line++;
+		if (strncmp(line, key, key_len) == 0 && line[key_len] == ' ')
+			return strtol(line + key_len + 1, NULL, 10);
+	}
+
+	return -1;
+}
+
+#endif /* FILE_UTIL_H */
@@ -37,9 +37,11 @@ FN_TEST(open_fifo_via_proc_fd_after_setid)
 		CHECK(setresgid(-1, 65535, -1));
 		CHECK(setresuid(-1, 65535, -1));
 
+		CHECK_WITH(prctl(PR_GET_DUMPABLE), _ret != 1);
+
 		/*
-		 * FIXME: Asterinas does not support the "dumpable" attribute
-		 * yet. So procfs files are always owned by the user
+		 * FIXME: Asterinas procfs files do not respect the "dumpable"
+		 * attribute yet. So procfs files are always owned by the user
 		 * corresponding to the thread's effective UID.
 		 *
 		 * When resolving this FIXME, keep in mind that if the
@@ -50,9 +52,8 @@ FN_TEST(open_fifo_via_proc_fd_after_setid)
 		 */
 #ifdef __asterinas__
 		CHECK(prctl(PR_SET_DUMPABLE, 1));
-		CHECK_WITH(prctl(PR_GET_DUMPABLE), _ret != 1);
+		CHECK_WITH(prctl(PR_GET_DUMPABLE), _ret == 1);
 #else
-		CHECK_WITH(prctl(PR_GET_DUMPABLE), _ret != 1);
 		CHECK_WITH(stat("/proc/self/fd", &proc_dir_stat),
 			   proc_dir_stat.st_uid == 0 &&
 				   proc_dir_stat.st_gid == 0);
@@ -0,0 +1,262 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <stdio.h>
+#include <sys/stat.h>
+#include <sys/sysmacros.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "../../common/file_util.h"
+#include "../../common/test.h"
+#include "../../common/block_device.h"
+
+#define CGROUP_ROOT "/sys/fs/cgroup"
+#define TEST_CGROUP CGROUP_ROOT "/io-test"
+
+#define NR_READS 10
+#define READ_SIZE 4096
+#define NR_WEIGHTED_READS 1024
+
+static char disk[32];
+static int io_was_enabled;
+
+static ssize_t write_max(const char *limits)
+{
+	char str[128];
+
+	snprintf(str, sizeof(str), "%s %s", disk, limits);
+	return write_file(TEST_CGROUP "/io.max", str);
+}
+
+// Returns the value of `key` in the `io.stat` line of the test disk.
+static long read_disk_stat(const char *path, const char *key)
+{
+	size_t disk_len = strlen(disk);
+	char *line, *value;
+
+	if (read_file(path) < 0)
+		return -1;
+
+	for (line = file_buf; line != NULL; line = strchr(line, '\n')) {
+		if (*line == '\n')
+			line++;
+		if (strncmp(line, disk, disk_len) != 0 || line[disk_len] != ' ')
+			continue;
+
+		value = strstr(line, key);
+		if (value == NULL)
+			return -1;
+		return strtol(value + strlen(key), NULL, 10);
+	}
+
+	return 0;
+}
+
+FN_SETUP(create_cgroup)
+{
+	struct stat stat_buf;
+	int fd;
+
+	fd = CHECK(open_block_device(BLOCK_VDB_EXFAT));
+	CHECK(fstat(fd, &stat_buf));
+	CHECK(close_block_device(fd));
+	snprintf(disk, sizeof(disk), "%u:%u", major(stat_buf.st_rdev),
+		 minor(stat_buf.st_rdev));
+
+	CHECK(read_file(CGROUP_ROOT "/cgroup.subtree_control"));
+	io_was_enabled = strstr(file_buf, "io") != NULL;
+	if (!io_was_enabled)
+		CHECK(write_file(CGROUP_ROOT "/cgroup.subtree_control", "+io"));
+
+	CHECK(mkdir(TEST_CGROUP, 0755));
+}
+END_SETUP()
+
+FN_TEST(weight)
+{
+	TEST_RES(read_file(TEST_CGROUP "/io.weight"),
+		 strcmp(file_buf, "default 100\n") == 0);
+
+	TEST_RES(write_file(TEST_CGROUP "/io.weight", "200"), _ret == 3);
+	TEST_RES(read_file(TEST_CGROUP "/io.weight"),
+		 strcmp(file_buf, "default 200\n") == 0);
+	TEST_RES(write_file(TEST_CGROUP "/io.weight", "default 100"),
+		 _ret == 11);
+	TEST_RES(read_file(TEST_CGROUP "/io.weight"),
+		 strcmp(file_buf, "default 100\n") == 0);
+
+	TEST_ERRNO(write_file(TEST_CGROUP "/io.weight", "0"), EINVAL);
+	TEST_ERRNO(write_file(TEST_CGROUP "/io.weight", "10001"), EINVAL);
+
+	// The root cgroup has no weights or limits.
+	TEST_ERRNO(read_file(CGROUP_ROOT "/io.weight"), ENOENT);
+	TEST_ERRNO(read_file(CGROUP_ROOT "/io.max"), ENOENT);
+}
+END_TEST()
+
+FN_TEST(max)
+{
+	char expected[128];
+
+	TEST_RES(read_file(TEST_CGROUP "/io.max"), _ret == 0);
+
+	TEST_SUCC(write_max("rbps=1048576 wiops=100"));
+	snprintf(expected, sizeof(expected),
+		 "%s rbps=1048576 wbps=max riops=max wiops=100\n", disk);
+	TEST_RES(read_file(TEST_CGROUP "/io.max"), strcmp(file_buf, expected) == 0);
+
+	// The limits that are not specified are kept unchanged.
+	TEST_SUCC(write_max("rbps=max"));
+	snprintf(expected, sizeof(expected),
+		 "%s rbps=max wbps=max riops=max wiops=100\n", disk);
+	TEST_RES(read_file(TEST_CGROUP "/io.max"), strcmp(file_buf, expected) == 0);
+
+	TEST_SUCC(write_max("wiops=max"));
+	TEST_RES(read_file(TEST_CGROUP "/io.max"), _ret == 0);
+
+	TEST_ERRNO(write_max("rbps=0"), EINVAL);
+	TEST_ERRNO(write_max("xbps=1"), EINVAL);
+	TEST_ERRNO(write_max("rbps"), EINVAL);
+}
+END_TEST()
+
+FN_TEST(throttle_reads)
+{
+	struct timespec start, end;
+	char pid_text[32];
+	char read_buf[READ_SIZE];
+	long elapsed_ms;
+	int status, fd, i;
+	pid_t pid;
+
+	TEST_SUCC(write_max("riops=20"));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		snprintf(pid_text, sizeof(pid_text), "%d", getpid());
+		if (write_file(TEST_CGROUP "/cgroup.procs", pid_text) < 0)
+			_exit(1);
+
+		fd = open_block_device(BLOCK_VDB_EXFAT);
+		if (fd < 0)
+			_exit(2);
+
+		clock_gettime(CLOCK_MONOTONIC, &start);
+		for (i = 0; i < NR_READS; i++) {
+			if (pread(fd, read_buf, READ_SIZE,
+				  (off_t)i * 64 * READ_SIZE) != READ_SIZE)
+				_exit(3);
+		}
+		clock_gettime(CLOCK_MONOTONIC, &end);
+		close_block_device(fd);
+
+		// At 20 reads per second, the reads take at least 450ms.
+		elapsed_ms = (end.tv_sec - start.tv_sec) * 1000 +
+			     (end.tv_nsec - start.tv_nsec) / 1000000;
+		_exit(elapsed_ms >= 400 ? 0 : 4);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	TEST_RES(read_disk_stat(TEST_CGROUP "/io.stat", "rios="),
+		 _ret >= NR_READS);
+	TEST_RES(read_disk_stat(TEST_CGROUP "/io.stat", "rbytes="),
+		 _ret >= NR_READS * READ_SIZE);
+
+	TEST_SUCC(write_max("riops=max"));
+}
+END_TEST()
+
+static pid_t spawn_reader(const char *cgroup, off_t start, int ready_fd)
+{
+	char pid_text[32];
+	char read_buf[READ_SIZE];
+	int fd, i;
+	pid_t pid;
+	char c;
+
+	pid = CHECK(fork());
+	if (pid != 0)
+		return pid;
+
+	snprintf(pid_text, sizeof(pid_text), "%d", getpid());
+	if (write_file(cgroup, pid_text) < 0)
+		_exit(1);
+
+	fd = open_block_device(BLOCK_VDB_EXFAT);
+	if (fd < 0)
+		_exit(2);
+
+	// Wait until both readers are in their cgroups.
+	if (read(ready_fd, &c, 1) != 0)
+		_exit(3);
+
+	for (i = 0; i < NR_WEIGHTED_READS; i++) {
+		if (pread(fd, read_buf, READ_SIZE,
+			  start + (off_t)i * READ_SIZE) != READ_SIZE)
+			_exit(4);
+	}
+	close_block_device(fd);
+	_exit(0);
+}
+
+FN_TEST(weight_fairness)
+{
+	long light_rios, heavy_rios;
+	int ready_fds[2];
+	pid_t light_pid, heavy_pid;
+	int status;
+
+	TEST_RES(write_file(TEST_CGROUP "/cgroup.subtree_control", "+io"),
+		 _ret == 3);
+	TEST_SUCC(mkdir(TEST_CGROUP "/light", 0755));
+	TEST_SUCC(mkdir(TEST_CGROUP "/heavy", 0755));
+	TEST_RES(write_file(TEST_CGROUP "/light/io.weight", "100"),
+		 _ret == 3);
+	TEST_RES(write_file(TEST_CGROUP "/heavy/io.weight", "400"),
+		 _ret == 3);
+
+	// Read different areas of the disk, which are not in the page cache.
+	TEST_SUCC(pipe(ready_fds));
+	light_pid = spawn_reader(TEST_CGROUP "/light/cgroup.procs", 16 << 20,
+				 ready_fds[0]);
+	heavy_pid = spawn_reader(TEST_CGROUP "/heavy/cgroup.procs", 32 << 20,
+				 ready_fds[0]);
+	TEST_SUCC(close(ready_fds[0]));
+	TEST_SUCC(close(ready_fds[1]));
+
+	// When the heavy reader finishes, the light reader should have done
+	// about a quarter of its reads.
+	TEST_RES(waitpid(heavy_pid, &status, 0),
+		 _ret == heavy_pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == 0);
+	light_rios = TEST_RES(
+		read_disk_stat(TEST_CGROUP "/light/io.stat", "rios="),
+		_ret >= 0);
+	heavy_rios = TEST_RES(
+		read_disk_stat(TEST_CGROUP "/heavy/io.stat", "rios="),
+		_ret >= NR_WEIGHTED_READS);
+	TEST_RES(0, light_rios * 2 < heavy_rios);
+
+	TEST_RES(waitpid(light_pid, &status, 0),
+		 _ret == light_pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == 0);
+
+	TEST_SUCC(rmdir(TEST_CGROUP "/light"));
+	TEST_SUCC(rmdir(TEST_CGROUP "/heavy"));
+	TEST_RES(write_file(TEST_CGROUP "/cgroup.subtree_control", "-io"),
+		 _ret == 3);
+}
+END_TEST()
+
+FN_SETUP(remove_cgroup)
+{
+	CHECK(rmdir(TEST_CGROUP));
+
+	if (!io_was_enabled)
+		CHECK(write_file(CGROUP_ROOT "/cgroup.subtree_control", "-io"));
+}
+END_SETUP()
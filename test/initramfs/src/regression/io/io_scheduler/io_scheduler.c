@@ -0,0 +1,207 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <libgen.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../../common/file_util.h"
+#include "../../common/test.h"
+#include "../../common/block_device.h"
+
+#define IOPRIO_WHO_PROCESS 1
+
+#define IOPRIO_CLASS_NONE 0
+#define IOPRIO_CLASS_RT 1
+#define IOPRIO_CLASS_BE 2
+#define IOPRIO_CLASS_IDLE 3
+
+#define IOPRIO_PRIO_VALUE(class, level) (((class) << 13) | (level))
+
+#define NR_BLOCKS 64
+#define READ_SIZE 4096
+
+static char sched_path[128];
+static char orig_sched[32];
+static char expected[NR_BLOCKS * READ_SIZE];
+
+static int ioprio_set(int ioprio)
+{
+	return syscall(SYS_ioprio_set, IOPRIO_WHO_PROCESS, 0, ioprio);
+}
+
+static int ioprio_get(void)
+{
+	return syscall(SYS_ioprio_get, IOPRIO_WHO_PROCESS, 0);
+}
+
+// Returns whether the scheduler is listed in the scheduler file.
+static int has_scheduler(const char *name)
+{
+	char bracketed[40];
+	char *token, *saveptr;
+
+	if (read_file(sched_path) < 0)
+		return 0;
+
+	snprintf(bracketed, sizeof(bracketed), "[%s]", name);
+	for (token = strtok_r(file_buf, " \n", &saveptr); token != NULL;
+	     token = strtok_r(NULL, " \n", &saveptr)) {
+		if (strcmp(token, name) == 0 || strcmp(token, bracketed) == 0)
+			return 1;
+	}
+
+	return 0;
+}
+
+// Returns whether the scheduler is the active one.
+static int is_active(const char *name)
+{
+	char bracketed[40];
+
+	snprintf(bracketed, sizeof(bracketed), "[%s]", name);
+	return read_file(sched_path) >= 0 && strstr(file_buf, bracketed) != NULL;
+}
+
+// Reads the blocks in a scattered order and checks their contents.
+static int read_blocks(int fd)
+{
+	char block[READ_SIZE];
+	int i, index;
+
+	for (i = 0; i < NR_BLOCKS; i++) {
+		index = (i * 7) % NR_BLOCKS;
+		if (pread(fd, block, READ_SIZE, (off_t)index * READ_SIZE) !=
+		    READ_SIZE)
+			return -1;
+		if (memcmp(block, expected + index * READ_SIZE, READ_SIZE))
+			return -1;
+	}
+
+	return 0;
+}
+
+// Reads the blocks concurrently from processes of different I/O priorities.
+static int read_concurrently(void)
+{
+	static const int ioprios[] = {
+		IOPRIO_PRIO_VALUE(IOPRIO_CLASS_RT, 0),
+		IOPRIO_PRIO_VALUE(IOPRIO_CLASS_BE, 0),
+		IOPRIO_PRIO_VALUE(IOPRIO_CLASS_BE, 7),
+		IOPRIO_PRIO_VALUE(IOPRIO_CLASS_IDLE, 0),
+	};
+	pid_t pids[sizeof(ioprios) / sizeof(ioprios[0])];
+	int status, fd, result = 0;
+	size_t i;
+
+	for (i = 0; i < sizeof(ioprios) / sizeof(ioprios[0]); i++) {
+		pids[i] = fork();
+		if (pids[i] < 0)
+			return -1;
+		if (pids[i] == 0) {
+			if (ioprio_set(ioprios[i]) < 0)
+				_exit(1);
+			fd = open_block_device(BLOCK_VDB_EXFAT);
+			if (fd < 0)
+				_exit(2);
+			if (read_blocks(fd) < 0)
+				_exit(3);
+			close_block_device(fd);
+			_exit(0);
+		}
+	}
+
+	for (i = 0; i < sizeof(ioprios) / sizeof(ioprios[0]); i++) {
+		if (waitpid(pids[i], &status, 0) != pids[i] ||
+		    !WIFEXITED(status) || WEXITSTATUS(status) != 0)
+			result = -1;
+	}
+
+	return result;
+}
+
+FN_SETUP(find_device)
+{
+	char fd_path[32], dev_path[64];
+	char *start, *end;
+	ssize_t len;
+	int fd;
+
+	fd = CHECK(open_block_device(BLOCK_VDB_EXFAT));
+	snprintf(fd_path, sizeof(fd_path), "/proc/self/fd/%d", fd);
+	len = CHECK(readlink(fd_path, dev_path, sizeof(dev_path) - 1));
+	dev_path[len] = '\0';
+	CHECK_WITH(pread(fd, expected, sizeof(expected), 0),
+		   _ret == sizeof(expected));
+	CHECK(close_block_device(fd));
+
+	snprintf(sched_path, sizeof(sched_path), "/sys/block/%s/queue/scheduler",
+		 basename(dev_path));
+
+	CHECK(read_file(sched_path));
+	start = CHECK_WITH(strchr(file_buf, '['), _ret != NULL);
+	end = CHECK_WITH(strchr(start, ']'), _ret != NULL);
+	*end = '\0';
+	snprintf(orig_sched, sizeof(orig_sched), "%s", start + 1);
+}
+END_SETUP()
+
+FN_TEST(switch_scheduler)
+{
+	TEST_RES(has_scheduler("none"), _ret == 1);
+	TEST_RES(has_scheduler("mq-deadline"), _ret == 1);
+
+	TEST_RES(write_file(sched_path, "mq-deadline"), _ret == 11);
+	TEST_RES(is_active("mq-deadline"), _ret == 1);
+	TEST_RES(is_active("none"), _ret == 0);
+
+	TEST_RES(write_file(sched_path, "none\n"), _ret == 5);
+	TEST_RES(is_active("none"), _ret == 1);
+
+	TEST_ERRNO(write_file(sched_path, "cfq-foo"), EINVAL);
+	TEST_RES(is_active("none"), _ret == 1);
+}
+END_TEST()
+
+FN_TEST(ioprio)
+{
+	TEST_ERRNO(ioprio_set(IOPRIO_PRIO_VALUE(4, 0)), EINVAL);
+	TEST_ERRNO(ioprio_set(IOPRIO_PRIO_VALUE(IOPRIO_CLASS_NONE, 1)), EINVAL);
+
+	TEST_SUCC(ioprio_set(IOPRIO_PRIO_VALUE(IOPRIO_CLASS_BE, 7)));
+	TEST_RES(ioprio_get(), _ret == IOPRIO_PRIO_VALUE(IOPRIO_CLASS_BE, 7));
+	TEST_SUCC(ioprio_set(IOPRIO_PRIO_VALUE(IOPRIO_CLASS_IDLE, 0)));
+	TEST_RES(ioprio_get(),
+		 _ret == IOPRIO_PRIO_VALUE(IOPRIO_CLASS_IDLE, 0));
+	TEST_SUCC(ioprio_set(IOPRIO_PRIO_VALUE(IOPRIO_CLASS_RT, 0)));
+	TEST_RES(ioprio_get(), _ret == IOPRIO_PRIO_VALUE(IOPRIO_CLASS_RT, 0));
+
+	TEST_SUCC(ioprio_set(IOPRIO_PRIO_VALUE(IOPRIO_CLASS_BE, 4)));
+}
+END_TEST()
+
+FN_TEST(read_with_schedulers)
+{
+	static const char *const names[] = { "none", "mq-deadline", "bfq" };
+	size_t i;
+
+	for (i = 0; i < sizeof(names) / sizeof(names[0]); i++) {
+		if (!has_scheduler(names[i]))
+			continue;
+
+		TEST_RES(write_file(sched_path, names[i]),
+			 _ret == (ssize_t)strlen(names[i]));
+		TEST_SUCC(read_concurrently());
+	}
+}
+END_TEST()
+
+FN_SETUP(restore_scheduler)
+{
+	CHECK(write_file(sched_path, orig_sched));
+}
+END_SETUP()
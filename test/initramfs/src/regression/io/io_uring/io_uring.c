@@ -0,0 +1,285 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <linux/io_uring.h>
+#include <poll.h>
+#include <stdatomic.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#include "../../common/test.h"
+
+#define NR_ENTRIES 4
+#define FILE_PATH "/tmp/io_uring_test"
+
+struct ring {
+	int fd;
+	struct io_uring_params params;
+	void *rings;
+	size_t rings_size;
+	struct io_uring_sqe *sqes;
+	unsigned *sq_tail;
+	unsigned *sq_mask;
+	unsigned *sq_array;
+	unsigned *cq_head;
+	unsigned *cq_tail;
+	unsigned *cq_mask;
+	struct io_uring_cqe *cqes;
+};
+
+static struct ring ring;
+
+static int io_uring_setup(unsigned entries, struct io_uring_params *params)
+{
+	return syscall(SYS_io_uring_setup, entries, params);
+}
+
+static int io_uring_enter(int fd, unsigned to_submit, unsigned min_complete,
+			  unsigned flags)
+{
+	return syscall(SYS_io_uring_enter, fd, to_submit, min_complete, flags,
+		       NULL, 0);
+}
+
+static int io_uring_register(int fd, unsigned opcode, void *arg,
+			     unsigned nr_args)
+{
+	return syscall(SYS_io_uring_register, fd, opcode, arg, nr_args);
+}
+
+static struct io_uring_sqe *get_sqe(void)
+{
+	unsigned tail = *ring.sq_tail;
+	unsigned index = tail & *ring.sq_mask;
+
+	ring.sq_array[index] = index;
+	memset(&ring.sqes[index], 0, sizeof(struct io_uring_sqe));
+	atomic_store_explicit((_Atomic unsigned *)ring.sq_tail, tail + 1,
+			      memory_order_release);
+
+	return &ring.sqes[index];
+}
+
+static int pop_cqe(struct io_uring_cqe *cqe)
+{
+	unsigned head = *ring.cq_head;
+	unsigned tail = atomic_load_explicit((_Atomic unsigned *)ring.cq_tail,
+					     memory_order_acquire);
+
+	if (head == tail)
+		return -1;
+
+	*cqe = ring.cqes[head & *ring.cq_mask];
+	atomic_store_explicit((_Atomic unsigned *)ring.cq_head, head + 1,
+			      memory_order_release);
+
+	return 0;
+}
+
+FN_SETUP(init_ring)
+{
+	struct io_uring_params *params = &ring.params;
+	char *rings;
+
+	ring.fd = CHECK(io_uring_setup(NR_ENTRIES, params));
+
+	ring.rings_size = params->cq_off.cqes +
+			  params->cq_entries * sizeof(struct io_uring_cqe);
+	if (params->sq_off.array + params->sq_entries * sizeof(unsigned) >
+	    ring.rings_size)
+		ring.rings_size = params->sq_off.array +
+				  params->sq_entries * sizeof(unsigned);
+
+	rings = CHECK_WITH(mmap(NULL, ring.rings_size, PROT_READ | PROT_WRITE,
+				MAP_SHARED | MAP_POPULATE, ring.fd,
+				IORING_OFF_SQ_RING),
+			   _ret != MAP_FAILED);
+	ring.rings = rings;
+	ring.sqes = CHECK_WITH(mmap(NULL,
+				    params->sq_entries *
+					    sizeof(struct io_uring_sqe),
+				    PROT_READ | PROT_WRITE,
+				    MAP_SHARED | MAP_POPULATE, ring.fd,
+				    IORING_OFF_SQES),
+			       _ret != MAP_FAILED);
+
+	ring.sq_tail = (unsigned *)(rings + params->sq_off.tail);
+	ring.sq_mask = (unsigned *)(rings + params->sq_off.ring_mask);
+	ring.sq_array = (unsigned *)(rings + params->sq_off.array);
+	ring.cq_head = (unsigned *)(rings + params->cq_off.head);
+	ring.cq_tail = (unsigned *)(rings + params->cq_off.tail);
+	ring.cq_mask = (unsigned *)(rings + params->cq_off.ring_mask);
+	ring.cqes = (struct io_uring_cqe *)(rings + params->cq_off.cqes);
+}
+END_SETUP()
+
+FN_TEST(setup_params)
+{
+	struct io_uring_params params;
+
+	TEST_RES(ring.params.sq_entries,
+		 _ret == NR_ENTRIES &&
+			 ring.params.cq_entries == 2 * NR_ENTRIES &&
+			 (ring.params.features & IORING_FEAT_SINGLE_MMAP));
+	TEST_RES(fcntl(ring.fd, F_GETFD), _ret == FD_CLOEXEC);
+
+	memset(&params, 0, sizeof(params));
+	TEST_ERRNO(io_uring_setup(0, &params), EINVAL);
+
+	memset(&params, 0, sizeof(params));
+	params.flags = IORING_SETUP_SQPOLL;
+	TEST_ERRNO(io_uring_setup(NR_ENTRIES, &params), EINVAL);
+
+	memset(&params, 0, sizeof(params));
+	params.resv[0] = 1;
+	TEST_ERRNO(io_uring_setup(NR_ENTRIES, &params), EINVAL);
+}
+END_TEST()
+
+FN_TEST(enter_bad_fd)
+{
+	int fd = TEST_SUCC(open("/dev/null", O_RDONLY));
+
+	TEST_ERRNO(io_uring_enter(fd, 0, 0, 0), EOPNOTSUPP);
+	TEST_ERRNO(io_uring_register(fd, IORING_REGISTER_PROBE, NULL, 0),
+		   EOPNOTSUPP);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(nop)
+{
+	struct io_uring_sqe *sqe;
+	struct io_uring_cqe cqe;
+
+	sqe = get_sqe();
+	sqe->opcode = IORING_OP_NOP;
+	sqe->user_data = 42;
+
+	TEST_RES(io_uring_enter(ring.fd, 1, 1, IORING_ENTER_GETEVENTS),
+		 _ret == 1);
+	TEST_RES(pop_cqe(&cqe), cqe.user_data == 42 && cqe.res == 0);
+	TEST_ERRNO(pop_cqe(&cqe), 0);
+}
+END_TEST()
+
+FN_TEST(write_and_read)
+{
+	char buf[16] = { 0 };
+	struct io_uring_sqe *sqe;
+	struct io_uring_cqe cqe;
+	int fd;
+
+	fd = TEST_SUCC(open(FILE_PATH, O_CREAT | O_TRUNC | O_RDWR, 0600));
+
+	sqe = get_sqe();
+	sqe->opcode = IORING_OP_WRITE;
+	sqe->fd = fd;
+	sqe->addr = (unsigned long)"hello io_uring";
+	sqe->len = 15;
+	sqe->off = 0;
+	sqe->user_data = 1;
+
+	sqe = get_sqe();
+	sqe->opcode = IORING_OP_READ;
+	sqe->fd = fd;
+	sqe->addr = (unsigned long)buf;
+	sqe->len = sizeof(buf);
+	sqe->off = 6;
+	sqe->user_data = 2;
+
+	TEST_RES(io_uring_enter(ring.fd, 2, 2, IORING_ENTER_GETEVENTS),
+		 _ret == 2);
+	TEST_RES(pop_cqe(&cqe), cqe.user_data == 1 && cqe.res == 15);
+	TEST_RES(pop_cqe(&cqe), cqe.user_data == 2 && cqe.res == 9 &&
+					strcmp(buf, "io_uring") == 0);
+
+	TEST_SUCC(close(fd));
+	TEST_SUCC(unlink(FILE_PATH));
+}
+END_TEST()
+
+FN_TEST(bad_opcode)
+{
+	struct io_uring_sqe *sqe;
+	struct io_uring_cqe cqe;
+
+	sqe = get_sqe();
+	sqe->opcode = IORING_OP_LAST;
+	sqe->user_data = 3;
+
+	TEST_RES(io_uring_enter(ring.fd, 1, 1, IORING_ENTER_GETEVENTS),
+		 _ret == 1);
+	TEST_RES(pop_cqe(&cqe), cqe.user_data == 3 && cqe.res == -EINVAL);
+}
+END_TEST()
+
+FN_TEST(register_probe)
+{
+	size_t size = sizeof(struct io_uring_probe) +
+		      256 * sizeof(struct io_uring_probe_op);
+	struct io_uring_probe *probe = calloc(1, size);
+
+	TEST_SUCC(io_uring_register(ring.fd, IORING_REGISTER_PROBE, probe,
+				    256));
+	TEST_RES(probe->ops[IORING_OP_NOP].flags,
+		 (_ret & IO_URING_OP_SUPPORTED) && probe->last_op > 0);
+
+	free(probe);
+}
+END_TEST()
+
+FN_TEST(register_io_uring_files)
+{
+	int other_fd;
+	int fds[1];
+	struct io_uring_params params;
+
+	// The ring itself cannot be registered.
+	fds[0] = ring.fd;
+	TEST_ERRNO(io_uring_register(ring.fd, IORING_REGISTER_FILES, fds, 1),
+		   EBADF);
+
+	// Other rings cannot be registered either.
+	memset(&params, 0, sizeof(params));
+	other_fd = TEST_SUCC(io_uring_setup(NR_ENTRIES, &params));
+	fds[0] = other_fd;
+	TEST_ERRNO(io_uring_register(ring.fd, IORING_REGISTER_FILES, fds, 1),
+		   EBADF);
+
+	TEST_SUCC(close(other_fd));
+}
+END_TEST()
+
+#ifdef __asterinas__
+FN_TEST(poll_ring_itself)
+{
+	struct io_uring_sqe *sqe;
+	struct io_uring_cqe cqe;
+
+	sqe = get_sqe();
+	sqe->opcode = IORING_OP_POLL_ADD;
+	sqe->fd = ring.fd;
+	sqe->poll32_events = POLLIN;
+	sqe->user_data = 4;
+
+	TEST_RES(io_uring_enter(ring.fd, 1, 1, IORING_ENTER_GETEVENTS),
+		 _ret == 1);
+	TEST_RES(pop_cqe(&cqe), cqe.user_data == 4 && cqe.res == -EBADF);
+}
+END_TEST()
+#endif
+
+FN_SETUP(cleanup_ring)
+{
+	CHECK(munmap(ring.sqes,
+		     ring.params.sq_entries * sizeof(struct io_uring_sqe)));
+	CHECK(munmap(ring.rings, ring.rings_size));
+	CHECK(close(ring.fd));
+}
+END_SETUP()
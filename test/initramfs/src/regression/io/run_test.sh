@@ -4,6 +4,8 @@
 
 set -e
 
+./cgroup_io/cgroup_io
+
 ./epoll/epoll_err
 ./epoll/poll_err
 ./epoll/test_epoll_pwait.sh
@@ -18,3 +20,7 @@ set -e
 ./file_io/fcntl_status_flags
 ./file_io/file_err
 ./file_io/iovec_err
+
+./io_scheduler/io_scheduler
+
+./io_uring/io_uring
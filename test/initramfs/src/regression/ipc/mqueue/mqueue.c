@@ -0,0 +1,289 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <errno.h>
+#include <fcntl.h>
+#include <mqueue.h>
+#include <poll.h>
+#include <signal.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "../../common/test.h"
+
+#define QUEUE_NAME "/test_mqueue"
+#define QUEUE_PATH "/dev/mqueue/test_mqueue"
+
+#define MAX_MSGS 4
+#define MAX_MSG_SIZE 64
+
+#define SETTLE_MS 100
+
+static char msg_buf[MAX_MSG_SIZE];
+
+static void sleep_ms(long milliseconds)
+{
+	struct timespec request = {
+		.tv_sec = milliseconds / 1000,
+		.tv_nsec = (milliseconds % 1000) * 1000000L,
+	};
+
+	CHECK(nanosleep(&request, NULL));
+}
+
+static mqd_t create_queue(int flags)
+{
+	struct mq_attr attr = {
+		.mq_maxmsg = MAX_MSGS,
+		.mq_msgsize = MAX_MSG_SIZE,
+	};
+
+	return mq_open(QUEUE_NAME, O_CREAT | O_EXCL | flags, 0600, &attr);
+}
+
+static int send_str(mqd_t mqd, const char *text, unsigned int prio)
+{
+	return mq_send(mqd, text, strlen(text) + 1, prio);
+}
+
+static ssize_t recv_str(mqd_t mqd, unsigned int *prio)
+{
+	memset(msg_buf, 0, sizeof(msg_buf));
+	return mq_receive(mqd, msg_buf, sizeof(msg_buf), prio);
+}
+
+FN_TEST(open_and_unlink)
+{
+	struct mq_attr attr = { .mq_maxmsg = 0, .mq_msgsize = MAX_MSG_SIZE };
+	mqd_t mqd, mqd2;
+
+	TEST_ERRNO(mq_open(QUEUE_NAME, O_RDWR), ENOENT);
+	TEST_ERRNO(mq_open(QUEUE_NAME, O_RDWR | O_CREAT, 0600, &attr), EINVAL);
+
+	mqd = TEST_SUCC(create_queue(O_RDWR));
+	TEST_ERRNO(create_queue(O_RDWR), EEXIST);
+	mqd2 = TEST_SUCC(mq_open(QUEUE_NAME, O_RDONLY | O_CREAT, 0600, NULL));
+	TEST_RES(fcntl(mqd2, F_GETFD), _ret == FD_CLOEXEC);
+	TEST_SUCC(access(QUEUE_PATH, F_OK));
+
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+	TEST_ERRNO(mq_unlink(QUEUE_NAME), ENOENT);
+	TEST_ERRNO(access(QUEUE_PATH, F_OK), ENOENT);
+
+	// The queue remains usable until the last descriptor is closed.
+	TEST_SUCC(send_str(mqd, "unlinked", 0));
+	TEST_RES(recv_str(mqd2, NULL), _ret == 9);
+
+	TEST_SUCC(mq_close(mqd2));
+	TEST_SUCC(mq_close(mqd));
+}
+END_TEST()
+
+FN_TEST(priority_ordering)
+{
+	mqd_t mqd = TEST_SUCC(create_queue(O_RDWR));
+	unsigned int prio;
+
+	TEST_SUCC(send_str(mqd, "low", 1));
+	TEST_SUCC(send_str(mqd, "high", 10));
+	TEST_SUCC(send_str(mqd, "first mid", 5));
+	TEST_SUCC(send_str(mqd, "second mid", 5));
+
+	TEST_RES(recv_str(mqd, &prio),
+		 prio == 10 && strcmp(msg_buf, "high") == 0);
+	TEST_RES(recv_str(mqd, &prio),
+		 prio == 5 && strcmp(msg_buf, "first mid") == 0);
+	TEST_RES(recv_str(mqd, &prio),
+		 prio == 5 && strcmp(msg_buf, "second mid") == 0);
+	TEST_RES(recv_str(mqd, &prio),
+		 prio == 1 && strcmp(msg_buf, "low") == 0);
+
+	TEST_SUCC(mq_close(mqd));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+}
+END_TEST()
+
+FN_TEST(reject_bad_args)
+{
+	mqd_t mqd = TEST_SUCC(create_queue(O_WRONLY));
+	char large_buf[MAX_MSG_SIZE + 1] = { 0 };
+
+	TEST_ERRNO(mq_send(mqd, large_buf, sizeof(large_buf), 0), EMSGSIZE);
+	TEST_ERRNO(mq_send(mqd, "bad", 4, 32768), EINVAL);
+	TEST_ERRNO(mq_receive(mqd, msg_buf, sizeof(msg_buf), NULL), EBADF);
+	TEST_ERRNO(mq_send(STDIN_FILENO, "bad", 4, 0), EBADF);
+	TEST_SUCC(mq_close(mqd));
+
+	mqd = TEST_SUCC(mq_open(QUEUE_NAME, O_RDONLY));
+	TEST_ERRNO(mq_send(mqd, "bad", 4, 0), EBADF);
+	TEST_ERRNO(mq_receive(mqd, msg_buf, MAX_MSG_SIZE - 1, NULL), EMSGSIZE);
+	TEST_SUCC(mq_close(mqd));
+
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+}
+END_TEST()
+
+FN_TEST(nonblock_and_attributes)
+{
+	mqd_t mqd = TEST_SUCC(create_queue(O_RDWR | O_NONBLOCK));
+	struct mq_attr attr, new_attr = { .mq_flags = 0 };
+	int i;
+
+	TEST_ERRNO(recv_str(mqd, NULL), EAGAIN);
+	for (i = 0; i < MAX_MSGS; i++)
+		TEST_SUCC(send_str(mqd, "msg", 0));
+	TEST_ERRNO(send_str(mqd, "msg", 0), EAGAIN);
+
+	TEST_RES(mq_getattr(mqd, &attr),
+		 attr.mq_flags == O_NONBLOCK && attr.mq_maxmsg == MAX_MSGS &&
+			 attr.mq_msgsize == MAX_MSG_SIZE &&
+			 attr.mq_curmsgs == MAX_MSGS);
+
+	TEST_RES(mq_setattr(mqd, &new_attr, &attr),
+		 attr.mq_flags == O_NONBLOCK);
+	TEST_RES(mq_getattr(mqd, &attr), attr.mq_flags == 0);
+
+	TEST_SUCC(mq_close(mqd));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+}
+END_TEST()
+
+FN_TEST(timed_wait)
+{
+	mqd_t mqd = TEST_SUCC(create_queue(O_RDWR));
+	struct timespec timeout;
+	int i;
+
+	CHECK(clock_gettime(CLOCK_REALTIME, &timeout));
+	timeout.tv_nsec = (timeout.tv_nsec + SETTLE_MS * 1000000L) %
+			  1000000000L;
+	timeout.tv_sec += 1;
+
+	TEST_ERRNO(mq_timedreceive(mqd, msg_buf, sizeof(msg_buf), NULL,
+				   &timeout),
+		   ETIMEDOUT);
+
+	for (i = 0; i < MAX_MSGS; i++)
+		TEST_SUCC(send_str(mqd, "msg", 0));
+	TEST_ERRNO(mq_timedsend(mqd, "msg", 4, 0, &timeout), ETIMEDOUT);
+
+	timeout.tv_nsec = 1000000000L;
+	TEST_ERRNO(mq_timedsend(mqd, "msg", 4, 0, &timeout), EINVAL);
+
+	TEST_SUCC(mq_close(mqd));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+}
+END_TEST()
+
+FN_TEST(receive_wait_for_message)
+{
+	mqd_t mqd = TEST_SUCC(create_queue(O_RDWR));
+	struct pollfd pfd = { .fd = mqd, .events = POLLIN | POLLOUT };
+	int status;
+	pid_t pid;
+
+	TEST_RES(poll(&pfd, 1, 0), _ret == 1 && pfd.revents == POLLOUT);
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		sleep_ms(SETTLE_MS);
+		send_str(mqd, "late", 3);
+		_exit(0);
+	}
+
+	TEST_RES(recv_str(mqd, NULL),
+		 _ret == 5 && strcmp(msg_buf, "late") == 0);
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && WIFEXITED(status));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		sleep_ms(SETTLE_MS);
+		send_str(mqd, "polled", 3);
+		_exit(0);
+	}
+
+	pfd.events = POLLIN;
+	TEST_RES(poll(&pfd, 1, -1), _ret == 1 && pfd.revents == POLLIN);
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && WIFEXITED(status));
+
+	TEST_SUCC(mq_close(mqd));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+}
+END_TEST()
+
+FN_TEST(notify_by_signal)
+{
+	mqd_t mqd = TEST_SUCC(create_queue(O_RDWR));
+	struct sigevent sev = {
+		.sigev_notify = SIGEV_SIGNAL,
+		.sigev_signo = SIGUSR1,
+		.sigev_value.sival_int = 42,
+	};
+	struct timespec timeout = { .tv_sec = 1 };
+	siginfo_t info;
+	sigset_t set;
+	int status;
+	pid_t pid;
+
+	CHECK(sigemptyset(&set));
+	CHECK(sigaddset(&set, SIGUSR1));
+	CHECK(sigprocmask(SIG_BLOCK, &set, NULL));
+
+	TEST_SUCC(mq_notify(mqd, &sev));
+
+	// Only one process can be registered for notification.
+	pid = TEST_SUCC(fork());
+	if (pid == 0)
+		_exit(mq_notify(mqd, &sev) < 0 ? errno : 0);
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EBUSY);
+
+	TEST_SUCC(send_str(mqd, "notify", 0));
+	TEST_RES(sigtimedwait(&set, &info, &timeout),
+		 _ret == SIGUSR1 && info.si_code == SI_MESGQ &&
+			 info.si_pid == getpid() &&
+			 info.si_value.sival_int == 42);
+
+	// The registration is removed after the notification is delivered.
+	TEST_SUCC(send_str(mqd, "again", 0));
+	TEST_SUCC(recv_str(mqd, NULL));
+	TEST_SUCC(recv_str(mqd, NULL));
+	TEST_SUCC(send_str(mqd, "no notify", 0));
+	timeout.tv_sec = 0;
+	TEST_ERRNO(sigtimedwait(&set, &info, &timeout), EAGAIN);
+
+	TEST_SUCC(mq_notify(mqd, &sev));
+	TEST_SUCC(mq_notify(mqd, NULL));
+	sev.sigev_signo = 65;
+	TEST_ERRNO(mq_notify(mqd, &sev), EINVAL);
+
+	CHECK(sigprocmask(SIG_UNBLOCK, &set, NULL));
+	TEST_SUCC(mq_close(mqd));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+}
+END_TEST()
+
+FN_TEST(read_queue_file)
+{
+	mqd_t mqd = TEST_SUCC(create_queue(O_RDWR));
+	struct sigevent sev = { .sigev_notify = SIGEV_NONE };
+	char buf[128] = { 0 };
+	int fd;
+
+	TEST_SUCC(send_str(mqd, "abcd", 0));
+	TEST_SUCC(mq_notify(mqd, &sev));
+
+	fd = TEST_SUCC(open(QUEUE_PATH, O_RDONLY));
+	TEST_RES(read(fd, buf, sizeof(buf) - 1),
+		 strncmp(buf, "QSIZE:5 ", 8) == 0 &&
+			 strstr(buf, "NOTIFY:1 ") != NULL);
+	TEST_SUCC(close(fd));
+
+	TEST_SUCC(mq_close(mqd));
+	TEST_SUCC(mq_unlink(QUEUE_NAME));
+}
+END_TEST()
@@ -0,0 +1,333 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <errno.h>
+#include <fcntl.h>
+#include <signal.h>
+#include <string.h>
+#include <sys/ipc.h>
+#include <sys/msg.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "../../common/test.h"
+
+#define MSGMAX 8192
+#define MSGMNB 16384
+
+#define CUSTOM_KEY 0xbeefdead
+
+#define SETTLE_MS 100
+
+struct message {
+	long mtype;
+	char mtext[MSGMAX];
+};
+
+static struct message msg_buf;
+
+static void sleep_ms(long milliseconds)
+{
+	struct timespec request = {
+		.tv_sec = milliseconds / 1000,
+		.tv_nsec = (milliseconds % 1000) * 1000000L,
+	};
+
+	CHECK(nanosleep(&request, NULL));
+}
+
+static int create_msg_queue(void)
+{
+	return msgget(IPC_PRIVATE, IPC_CREAT | 0600);
+}
+
+static int send_msg(int msqid, long mtype, const char *text, int flags)
+{
+	msg_buf.mtype = mtype;
+	strcpy(msg_buf.mtext, text);
+
+	return msgsnd(msqid, &msg_buf, strlen(text) + 1, flags);
+}
+
+static long recv_msg_type(int msqid, long msgtyp, int flags)
+{
+	if (msgrcv(msqid, &msg_buf, sizeof(msg_buf.mtext), msgtyp,
+		   flags | IPC_NOWAIT) < 0)
+		return -1;
+	return msg_buf.mtype;
+}
+
+static int get_qnum(int msqid)
+{
+	struct msqid_ds ds;
+
+	if (msgctl(msqid, IPC_STAT, &ds) < 0)
+		return -1;
+	return ds.msg_qnum;
+}
+
+static void sigalrm_handler(int signum)
+{
+	(void)signum;
+}
+
+FN_SETUP(install_signal_handler)
+{
+	struct sigaction action = {
+		.sa_handler = sigalrm_handler,
+		.sa_flags = 0,
+	};
+
+	CHECK(sigemptyset(&action.sa_mask));
+	CHECK(sigaction(SIGALRM, &action, NULL));
+}
+END_SETUP()
+
+FN_TEST(msgget_lookup_by_key)
+{
+	int msqid = TEST_SUCC(msgget(CUSTOM_KEY, IPC_CREAT | 0600));
+
+	TEST_RES(msgget(CUSTOM_KEY, 0), _ret == msqid);
+	TEST_RES(msgget(CUSTOM_KEY, IPC_CREAT | 0600), _ret == msqid);
+	TEST_ERRNO(msgget(CUSTOM_KEY, IPC_CREAT | IPC_EXCL | 0600), EEXIST);
+	TEST_ERRNO(msgget(CUSTOM_KEY + 1, 0), ENOENT);
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+	TEST_ERRNO(msgget(CUSTOM_KEY, 0), ENOENT);
+	TEST_ERRNO(msgctl(msqid, IPC_RMID, NULL), EINVAL);
+}
+END_TEST()
+
+FN_TEST(msgsnd_reject_bad_args)
+{
+	int msqid = TEST_SUCC(create_msg_queue());
+
+	TEST_ERRNO(send_msg(msqid, 0, "bad", 0), EINVAL);
+	TEST_ERRNO(send_msg(msqid, -1, "bad", 0), EINVAL);
+	TEST_ERRNO(msgsnd(msqid, &msg_buf, MSGMAX + 1, 0), EINVAL);
+	TEST_ERRNO(send_msg(0, 1, "bad", 0), EINVAL);
+	TEST_ERRNO(msgsnd(msqid, NULL, 1, 0), EFAULT);
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(msgrcv_select_by_type)
+{
+	int msqid = TEST_SUCC(create_msg_queue());
+
+	TEST_SUCC(send_msg(msqid, 3, "three", 0));
+	TEST_SUCC(send_msg(msqid, 1, "one", 0));
+	TEST_SUCC(send_msg(msqid, 2, "two", 0));
+	TEST_SUCC(send_msg(msqid, 1, "another one", 0));
+	TEST_RES(get_qnum(msqid), _ret == 4);
+
+	TEST_ERRNO(recv_msg_type(msqid, 4, 0), ENOMSG);
+	TEST_RES(recv_msg_type(msqid, 2, 0),
+		 _ret == 2 && strcmp(msg_buf.mtext, "two") == 0);
+	TEST_RES(recv_msg_type(msqid, 1, MSG_EXCEPT),
+		 _ret == 3 && strcmp(msg_buf.mtext, "three") == 0);
+	TEST_RES(recv_msg_type(msqid, -3, 0),
+		 _ret == 1 && strcmp(msg_buf.mtext, "one") == 0);
+	TEST_RES(recv_msg_type(msqid, 0, 0),
+		 _ret == 1 && strcmp(msg_buf.mtext, "another one") == 0);
+	TEST_ERRNO(recv_msg_type(msqid, 0, 0), ENOMSG);
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(msgrcv_truncate)
+{
+	int msqid = TEST_SUCC(create_msg_queue());
+
+	TEST_SUCC(send_msg(msqid, 1, "hello", 0));
+	TEST_ERRNO(msgrcv(msqid, &msg_buf, 3, 0, IPC_NOWAIT), E2BIG);
+	TEST_RES(get_qnum(msqid), _ret == 1);
+	TEST_ERRNO(msgrcv(msqid, &msg_buf, -1, 0, IPC_NOWAIT), EINVAL);
+
+	memset(msg_buf.mtext, 0, sizeof(msg_buf.mtext));
+	TEST_RES(msgrcv(msqid, &msg_buf, 3, 0, IPC_NOWAIT | MSG_NOERROR),
+		 _ret == 3 && memcmp(msg_buf.mtext, "hel\0", 4) == 0);
+	TEST_RES(get_qnum(msqid), _ret == 0);
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(msgsnd_full_queue)
+{
+	int msqid = TEST_SUCC(create_msg_queue());
+	struct msqid_ds ds;
+
+	msg_buf.mtype = 1;
+	TEST_SUCC(msgsnd(msqid, &msg_buf, MSGMAX, IPC_NOWAIT));
+	TEST_SUCC(msgsnd(msqid, &msg_buf, MSGMNB - MSGMAX, IPC_NOWAIT));
+	TEST_ERRNO(msgsnd(msqid, &msg_buf, 1, IPC_NOWAIT), EAGAIN);
+	TEST_RES(msgctl(msqid, IPC_STAT, &ds),
+		 ds.msg_qnum == 2 && ds.msg_cbytes == MSGMNB &&
+			 ds.msg_qbytes == MSGMNB && ds.msg_lspid == getpid());
+
+	TEST_RES(recv_msg_type(msqid, 0, 0), _ret == 1);
+	TEST_SUCC(msgsnd(msqid, &msg_buf, 1, IPC_NOWAIT));
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+static int set_msg_qbytes_as(uid_t uid, int msqid, unsigned long qbytes)
+{
+	struct msqid_ds ds;
+
+	if (setuid(uid) < 0 || msgctl(msqid, IPC_STAT, &ds) < 0)
+		return -1;
+	ds.msg_qbytes = qbytes;
+	if (msgctl(msqid, IPC_SET, &ds) < 0)
+		return errno;
+	return 0;
+}
+
+FN_TEST(msgctl_set)
+{
+	int msqid = TEST_SUCC(create_msg_queue());
+	struct msqid_ds ds;
+	int status;
+	pid_t pid;
+
+	TEST_SUCC(msgctl(msqid, IPC_STAT, &ds));
+	ds.msg_perm.mode = 0640;
+	ds.msg_qbytes = 16;
+	TEST_SUCC(msgctl(msqid, IPC_SET, &ds));
+	TEST_RES(msgctl(msqid, IPC_STAT, &ds),
+		 _ret == 0 && ds.msg_perm.mode == 0640 && ds.msg_qbytes == 16);
+	TEST_SUCC(send_msg(msqid, 1, "fifteen bytes!", IPC_NOWAIT));
+	TEST_ERRNO(send_msg(msqid, 1, "x", IPC_NOWAIT), EAGAIN);
+	TEST_RES(recv_msg_type(msqid, 0, 0), _ret == 1);
+
+	ds.msg_perm.uid = -1;
+	TEST_ERRNO(msgctl(msqid, IPC_SET, &ds), EINVAL);
+
+	// Privileged threads can raise the limit above `MSGMNB`.
+	ds.msg_perm.uid = 65534;
+	ds.msg_qbytes = MSGMNB * 2;
+	TEST_SUCC(msgctl(msqid, IPC_SET, &ds));
+
+	// The owner can change the limit without exceeding `MSGMNB`.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (set_msg_qbytes_as(65534, msqid, MSGMNB * 4) != EPERM)
+			_exit(1);
+		_exit(set_msg_qbytes_as(65534, msqid, MSGMNB) != 0);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	TEST_RES(msgctl(msqid, IPC_STAT, &ds),
+		 _ret == 0 && ds.msg_qbytes == MSGMNB);
+
+	// Other threads cannot change the queue.
+	pid = TEST_SUCC(fork());
+	if (pid == 0)
+		_exit(set_msg_qbytes_as(65533, msqid, MSGMNB) != EPERM);
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(msgrcv_wait_for_message)
+{
+	int msqid = TEST_SUCC(create_msg_queue());
+	int status;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		sleep_ms(SETTLE_MS);
+		send_msg(msqid, 5, "late", 0);
+		_exit(0);
+	}
+
+	TEST_RES(msgrcv(msqid, &msg_buf, sizeof(msg_buf.mtext), 5, 0),
+		 _ret == 5 && msg_buf.mtype == 5 &&
+			 strcmp(msg_buf.mtext, "late") == 0);
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid && WIFEXITED(status));
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(msgrcv_interrupted_by_signal)
+{
+	int msqid = TEST_SUCC(create_msg_queue());
+
+	alarm(1);
+	TEST_ERRNO(msgrcv(msqid, &msg_buf, sizeof(msg_buf.mtext), 0, 0),
+		   EINTR);
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(msgrcv_queue_removed)
+{
+	int msqid = TEST_SUCC(create_msg_queue());
+	int status;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (msgrcv(msqid, &msg_buf, sizeof(msg_buf.mtext), 0, 0) < 0)
+			_exit(errno);
+		_exit(0);
+	}
+
+	sleep_ms(SETTLE_MS);
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EIDRM);
+}
+END_TEST()
+
+FN_TEST(msgctl_info)
+{
+	int msqid = TEST_SUCC(create_msg_queue());
+	struct msginfo info;
+	struct msqid_ds ds;
+
+	TEST_SUCC(send_msg(msqid, 1, "info", 0));
+
+	TEST_RES(msgctl(0, IPC_INFO, (struct msqid_ds *)&info),
+		 _ret >= msqid && info.msgmax == MSGMAX &&
+			 info.msgmnb == MSGMNB);
+	TEST_RES(msgctl(0, MSG_INFO, (struct msqid_ds *)&info),
+		 _ret >= msqid && info.msgpool >= 1 && info.msgmap >= 1);
+	TEST_RES(msgctl(msqid, MSG_STAT, &ds), _ret == msqid);
+
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(msgmax_sysctl)
+{
+	int msqid = TEST_SUCC(create_msg_queue());
+	char buf[16] = { 0 };
+	int fd;
+
+	fd = TEST_SUCC(open("/proc/sys/kernel/msgmax", O_RDWR));
+	TEST_RES(read(fd, buf, sizeof(buf)), strcmp(buf, "8192\n") == 0);
+
+	TEST_RES(pwrite(fd, "16", 2, 0), _ret == 2);
+	TEST_SUCC(send_msg(msqid, 1, "fifteen bytes!", IPC_NOWAIT));
+	TEST_ERRNO(send_msg(msqid, 1, "seventeen bytes!", IPC_NOWAIT), EINVAL);
+
+	TEST_ERRNO(pwrite(fd, "-1", 2, 0), EINVAL);
+	TEST_RES(pwrite(fd, "8192", 4, 0), _ret == 4);
+
+	TEST_SUCC(close(fd));
+	TEST_SUCC(msgctl(msqid, IPC_RMID, NULL));
+}
+END_TEST()
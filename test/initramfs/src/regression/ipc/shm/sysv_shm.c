@@ -0,0 +1,259 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <errno.h>
+#include <string.h>
+#include <sys/ipc.h>
+#include <sys/mman.h>
+#include <sys/resource.h>
+#include <sys/shm.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../../common/test.h"
+
+#define CUSTOM_KEY 0xdeadbeef
+#define SEG_SIZE 0x3000
+
+#define SHM_FAILED ((void *)-1)
+
+static int create_shm(size_t size)
+{
+	return shmget(IPC_PRIVATE, size, IPC_CREAT | 0600);
+}
+
+static int get_nattch(int shmid)
+{
+	struct shmid_ds ds;
+
+	if (shmctl(shmid, IPC_STAT, &ds) < 0)
+		return -1;
+	return ds.shm_nattch;
+}
+
+FN_TEST(shmget_reject_bad_size)
+{
+	TEST_ERRNO(create_shm(0), EINVAL);
+}
+END_TEST()
+
+FN_TEST(shmget_lookup_by_key)
+{
+	int shmid = TEST_SUCC(shmget(CUSTOM_KEY, SEG_SIZE, IPC_CREAT | 0600));
+
+	TEST_RES(shmget(CUSTOM_KEY, SEG_SIZE, 0), _ret == shmid);
+	TEST_RES(shmget(CUSTOM_KEY, 1, IPC_CREAT | 0600), _ret == shmid);
+	TEST_ERRNO(shmget(CUSTOM_KEY, SEG_SIZE, IPC_CREAT | IPC_EXCL | 0600),
+		   EEXIST);
+	TEST_ERRNO(shmget(CUSTOM_KEY, SEG_SIZE + 1, 0), EINVAL);
+	TEST_ERRNO(shmget(CUSTOM_KEY + 1, SEG_SIZE, 0), ENOENT);
+
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+	TEST_ERRNO(shmget(CUSTOM_KEY, SEG_SIZE, 0), ENOENT);
+}
+END_TEST()
+
+FN_TEST(shmat_share_memory)
+{
+	int shmid = TEST_SUCC(create_shm(SEG_SIZE));
+	char *addr1, *addr2;
+
+	addr1 = TEST_RES(shmat(shmid, NULL, 0), _ret != SHM_FAILED);
+	addr2 = TEST_RES(shmat(shmid, NULL, SHM_RDONLY), _ret != SHM_FAILED);
+	TEST_RES(get_nattch(shmid), _ret == 2);
+
+	strcpy(addr1 + SEG_SIZE - 0x10, "hello");
+	TEST_RES(strcmp(addr2 + SEG_SIZE - 0x10, "hello"), _ret == 0);
+
+	TEST_SUCC(shmdt(addr2));
+	TEST_RES(get_nattch(shmid), _ret == 1);
+	TEST_ERRNO(shmdt(addr2), EINVAL);
+	TEST_ERRNO(shmdt(addr1 + 1), EINVAL);
+
+	TEST_SUCC(shmdt(addr1));
+	TEST_RES(get_nattch(shmid), _ret == 0);
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(shmat_fixed_address)
+{
+	int shmid = TEST_SUCC(create_shm(SEG_SIZE));
+	char *addr;
+
+	addr = TEST_RES(shmat(shmid, NULL, 0), _ret != SHM_FAILED);
+	TEST_SUCC(shmdt(addr));
+
+	TEST_ERRNO(shmat(shmid, addr + 1, 0), EINVAL);
+	TEST_RES(shmat(shmid, addr + 1, SHM_RND), _ret == addr);
+	TEST_ERRNO(shmat(shmid, addr, 0), EINVAL);
+	TEST_RES(shmat(shmid, addr, SHM_REMAP), _ret == addr);
+	TEST_ERRNO(shmat(shmid, NULL, SHM_REMAP), EINVAL);
+
+	TEST_SUCC(shmdt(addr));
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(shmctl_rmid_attached)
+{
+	int shmid = TEST_SUCC(shmget(CUSTOM_KEY, SEG_SIZE, IPC_CREAT | 0600));
+	struct shmid_ds ds;
+	char *addr;
+
+	addr = TEST_RES(shmat(shmid, NULL, 0), _ret != SHM_FAILED);
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+
+	// The segment is still accessible by its ID, but not by its key.
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds),
+		 ds.shm_perm.__key == IPC_PRIVATE &&
+			 (ds.shm_perm.mode & SHM_DEST) != 0);
+	TEST_ERRNO(shmget(CUSTOM_KEY, SEG_SIZE, 0), ENOENT);
+	TEST_RES(shmat(shmid, NULL, 0), _ret != SHM_FAILED && shmdt(_ret) == 0);
+
+	addr[0] = 'a';
+	TEST_SUCC(shmdt(addr));
+	TEST_ERRNO(shmctl(shmid, IPC_STAT, &ds), EINVAL);
+}
+END_TEST()
+
+FN_TEST(shmat_fork)
+{
+	int shmid = TEST_SUCC(create_shm(SEG_SIZE));
+	int status;
+	char *addr;
+	pid_t pid;
+
+	addr = TEST_RES(shmat(shmid, NULL, 0), _ret != SHM_FAILED);
+	addr[0] = 0;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		// The mapping inherited by the child is also an attach.
+		addr[0] = 'x';
+		_exit(get_nattch(shmid) == 2 ? 0 : 1);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == 0 && addr[0] == 'x');
+	TEST_RES(get_nattch(shmid), _ret == 1);
+
+	TEST_SUCC(shmdt(addr));
+	TEST_RES(get_nattch(shmid), _ret == 0);
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(shmat_partial_munmap)
+{
+	int shmid = TEST_SUCC(create_shm(SEG_SIZE));
+	char *addr;
+
+	addr = TEST_RES(shmat(shmid, NULL, 0), _ret != SHM_FAILED);
+	TEST_RES(get_nattch(shmid), _ret == 1);
+
+	// Unmapping the middle page splits the mapping into two attaches.
+	TEST_SUCC(munmap(addr + 0x1000, 0x1000));
+	TEST_RES(get_nattch(shmid), _ret == 2);
+
+	TEST_SUCC(shmdt(addr));
+	TEST_RES(get_nattch(shmid), _ret == 0);
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(shmctl_set)
+{
+	int shmid = TEST_SUCC(create_shm(SEG_SIZE));
+	struct shmid_ds ds;
+	int status;
+	pid_t pid;
+
+	TEST_SUCC(shmctl(shmid, IPC_STAT, &ds));
+	ds.shm_perm.mode = SHM_DEST | 0640;
+	TEST_SUCC(shmctl(shmid, IPC_SET, &ds));
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds),
+		 _ret == 0 && ds.shm_perm.mode == 0640);
+
+	ds.shm_perm.uid = -1;
+	TEST_ERRNO(shmctl(shmid, IPC_SET, &ds), EINVAL);
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (setuid(65534) < 0)
+			_exit(1);
+		// Threads that neither own nor create the segment cannot change it.
+		ds.shm_perm.uid = 65534;
+		if (shmctl(shmid, IPC_SET, &ds) == 0 || errno != EPERM)
+			_exit(1);
+		_exit(0);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+}
+END_TEST()
+
+static int lock_with_small_rlimit(int big_shmid, int small_shmid)
+{
+	struct rlimit rlim = { .rlim_cur = 0x2000, .rlim_max = 0x2000 };
+
+	if (setrlimit(RLIMIT_MEMLOCK, &rlim) < 0)
+		return -1;
+	if (setuid(65534) < 0)
+		return -1;
+
+	// The segment size is checked against `RLIMIT_MEMLOCK`.
+	if (shmctl(big_shmid, SHM_LOCK, NULL) == 0 || errno != ENOMEM)
+		return -1;
+	if (shmctl(small_shmid, SHM_LOCK, NULL) < 0)
+		return -1;
+	return 0;
+}
+
+FN_TEST(shmctl_lock_rlimit)
+{
+	int big_shmid = TEST_SUCC(create_shm(SEG_SIZE));
+	int small_shmid = TEST_SUCC(create_shm(0x1000));
+	struct shmid_ds ds;
+	int status;
+	pid_t pid;
+
+	TEST_SUCC(shmctl(big_shmid, IPC_STAT, &ds));
+	ds.shm_perm.uid = 65534;
+	TEST_SUCC(shmctl(big_shmid, IPC_SET, &ds));
+	TEST_SUCC(shmctl(small_shmid, IPC_SET, &ds));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0)
+		_exit(lock_with_small_rlimit(big_shmid, small_shmid) < 0);
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	TEST_RES(shmctl(small_shmid, IPC_STAT, &ds),
+		 _ret == 0 && (ds.shm_perm.mode & SHM_LOCKED) != 0);
+
+	TEST_SUCC(shmctl(big_shmid, IPC_RMID, NULL));
+	TEST_SUCC(shmctl(small_shmid, IPC_RMID, NULL));
+}
+END_TEST()
+
+FN_TEST(shmctl_stat)
+{
+	int shmid = TEST_SUCC(create_shm(SEG_SIZE));
+	struct shminfo info;
+	struct shmid_ds ds;
+
+	TEST_RES(shmctl(shmid, IPC_STAT, &ds),
+		 _ret == 0 && ds.shm_segsz == SEG_SIZE &&
+			 ds.shm_cpid == getpid() &&
+			 (ds.shm_perm.mode & 0777) == 0600);
+	TEST_RES(shmctl(shmid, SHM_STAT, &ds), _ret == shmid);
+	TEST_RES(shmctl(0, IPC_INFO, (struct shmid_ds *)&info),
+		 _ret >= shmid && info.shmmni > 0);
+
+	TEST_SUCC(shmctl(shmid, IPC_RMID, NULL));
+	TEST_ERRNO(shmctl(shmid, IPC_RMID, NULL), EINVAL);
+}
+END_TEST()
@@ -0,0 +1,323 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <signal.h>
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../../common/file_util.h"
+#include "../../common/test.h"
+
+#define PAGE_SIZE 4096
+
+#define CGROUP_ROOT "/sys/fs/cgroup"
+#define TEST_CGROUP CGROUP_ROOT "/memcg-test"
+
+#define CACHE_FILE "/ext2/memcg_cache"
+
+static int memory_was_enabled;
+
+static long read_number(const char *path)
+{
+	if (read_file(path) < 0)
+		return -1;
+	return strtol(file_buf, NULL, 10);
+}
+
+static int join_test_cgroup(void)
+{
+	char pid_text[32];
+
+	snprintf(pid_text, sizeof(pid_text), "%d", getpid());
+	if (write_file(TEST_CGROUP "/cgroup.procs", pid_text) !=
+	    (ssize_t)strlen(pid_text))
+		return -1;
+
+	return 0;
+}
+
+static int touch_memory(size_t size)
+{
+	char *addr;
+	size_t i;
+
+	addr = mmap(NULL, size, PROT_READ | PROT_WRITE,
+		    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+	if (addr == MAP_FAILED)
+		return -1;
+	for (i = 0; i < size; i += PAGE_SIZE)
+		addr[i] = 1;
+
+	return 0;
+}
+
+// Writes `size` bytes to the file and syncs them so that the pages in the page
+// cache are clean and can be reclaimed.
+static int write_synced_file(const char *path, size_t size)
+{
+	static char chunk[256 << 10];
+	size_t written;
+	int fd;
+
+	fd = open(path, O_CREAT | O_TRUNC | O_WRONLY, 0644);
+	if (fd < 0)
+		return -1;
+
+	memset(chunk, 'a', sizeof(chunk));
+	for (written = 0; written < size; written += sizeof(chunk)) {
+		if (write(fd, chunk, sizeof(chunk)) != sizeof(chunk) ||
+		    fsync(fd) < 0) {
+			close(fd);
+			return -1;
+		}
+	}
+
+	return close(fd);
+}
+
+FN_SETUP(create_cgroup)
+{
+	CHECK(read_file(CGROUP_ROOT "/cgroup.subtree_control"));
+	memory_was_enabled = strstr(file_buf, "memory") != NULL;
+	if (!memory_was_enabled)
+		CHECK(write_file(CGROUP_ROOT "/cgroup.subtree_control",
+				 "+memory"));
+
+	CHECK(mkdir(TEST_CGROUP, 0755));
+}
+END_SETUP()
+
+FN_TEST(default_limits)
+{
+	TEST_RES(read_file(TEST_CGROUP "/memory.max"),
+		 strcmp(file_buf, "max\n") == 0);
+	TEST_RES(read_file(TEST_CGROUP "/memory.high"),
+		 strcmp(file_buf, "max\n") == 0);
+	TEST_RES(read_number(TEST_CGROUP "/memory.current"), _ret == 0);
+	TEST_RES(read_number(TEST_CGROUP "/memory.peak"), _ret == 0);
+	TEST_RES(read_key(TEST_CGROUP "/memory.events", "oom_kill"),
+		 _ret == 0);
+
+	// The root cgroup has no memory limits.
+	TEST_ERRNO(read_file(CGROUP_ROOT "/memory.max"), ENOENT);
+}
+END_TEST()
+
+FN_TEST(write_limits)
+{
+	TEST_RES(write_file(TEST_CGROUP "/memory.max", "8M"), _ret == 2);
+	TEST_RES(read_number(TEST_CGROUP "/memory.max"), _ret == 8 << 20);
+
+	// The limit is rounded down to the page size.
+	TEST_RES(write_file(TEST_CGROUP "/memory.high", "1000000"), _ret == 7);
+	TEST_RES(read_number(TEST_CGROUP "/memory.high"),
+		 _ret == 1000000 / PAGE_SIZE * PAGE_SIZE);
+
+	TEST_RES(write_file(TEST_CGROUP "/memory.max", "max"), _ret == 3);
+	TEST_RES(read_file(TEST_CGROUP "/memory.max"),
+		 strcmp(file_buf, "max\n") == 0);
+	TEST_RES(write_file(TEST_CGROUP "/memory.high", "max\n"), _ret == 4);
+	TEST_RES(read_file(TEST_CGROUP "/memory.high"),
+		 strcmp(file_buf, "max\n") == 0);
+
+	TEST_ERRNO(write_file(TEST_CGROUP "/memory.max", "invalid"), EINVAL);
+	TEST_ERRNO(write_file(TEST_CGROUP "/memory.max", "-1"), EINVAL);
+}
+END_TEST()
+
+FN_TEST(charge_anon_memory)
+{
+	int status;
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (join_test_cgroup() < 0)
+			_exit(1);
+		if (touch_memory(4 << 20) < 0)
+			_exit(2);
+		if (read_number(TEST_CGROUP "/memory.current") < 4 << 20)
+			_exit(3);
+		if (read_key(TEST_CGROUP "/memory.stat", "anon") < 4 << 20)
+			_exit(4);
+		_exit(0);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	// The memory is uncharged when the process exits, but the peak remains.
+	TEST_RES(read_number(TEST_CGROUP "/memory.current"), _ret < 4 << 20);
+	TEST_RES(read_number(TEST_CGROUP "/memory.peak"), _ret >= 4 << 20);
+}
+END_TEST()
+
+FN_TEST(oom_kill)
+{
+	int status;
+	pid_t pid;
+
+	TEST_RES(write_file(TEST_CGROUP "/memory.max", "8M"), _ret == 2);
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (join_test_cgroup() < 0)
+			_exit(1);
+		touch_memory(32 << 20);
+		_exit(2);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+
+	TEST_RES(read_key(TEST_CGROUP "/memory.events", "max"), _ret > 0);
+	TEST_RES(read_key(TEST_CGROUP "/memory.events", "oom"), _ret > 0);
+	TEST_RES(read_key(TEST_CGROUP "/memory.events", "oom_kill"),
+		 _ret == 1);
+	TEST_RES(read_number(TEST_CGROUP "/memory.peak"), _ret <= 8 << 20);
+
+	TEST_RES(write_file(TEST_CGROUP "/memory.max", "max"), _ret == 3);
+}
+END_TEST()
+
+FN_TEST(high_throttle)
+{
+	long nr_high_events;
+	int status;
+	pid_t pid;
+
+	nr_high_events =
+		TEST_SUCC(read_key(TEST_CGROUP "/memory.events", "high"));
+	TEST_RES(write_file(TEST_CGROUP "/memory.high", "8M"), _ret == 2);
+
+	// Exceeding `memory.high` throttles the process, but does not kill it.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (join_test_cgroup() < 0)
+			_exit(1);
+		if (touch_memory(9 << 20) < 0)
+			_exit(2);
+		if (read_number(TEST_CGROUP "/memory.current") <= 8 << 20)
+			_exit(3);
+		_exit(0);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	TEST_RES(read_key(TEST_CGROUP "/memory.events", "high"),
+		 _ret > nr_high_events);
+
+	TEST_RES(write_file(TEST_CGROUP "/memory.high", "max"), _ret == 3);
+}
+END_TEST()
+
+FN_TEST(high_reclaim_page_cache)
+{
+	int status;
+	pid_t pid;
+
+	TEST_RES(write_file(TEST_CGROUP "/memory.high", "4M"), _ret == 2);
+
+	// The page cache over `memory.high` is reclaimed before returning to
+	// the user space.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (join_test_cgroup() < 0)
+			_exit(1);
+		if (write_synced_file(CACHE_FILE, 16 << 20) < 0)
+			_exit(2);
+		if (read_number(TEST_CGROUP "/memory.current") > 5 << 20)
+			_exit(3);
+		_exit(0);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	TEST_RES(write_file(TEST_CGROUP "/memory.high", "max"), _ret == 3);
+	TEST_SUCC(unlink(CACHE_FILE));
+}
+END_TEST()
+
+FN_TEST(max_reclaim_page_cache)
+{
+	long nr_oom_kills;
+	int status;
+	pid_t pid;
+
+	nr_oom_kills = TEST_SUCC(
+		read_key(TEST_CGROUP "/memory.events", "oom_kill"));
+	TEST_RES(write_file(TEST_CGROUP "/memory.max", "8M"), _ret == 2);
+
+	// The page cache is reclaimed to charge new pages at `memory.max`,
+	// so the process is not killed.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (join_test_cgroup() < 0)
+			_exit(1);
+		if (write_synced_file(CACHE_FILE, 32 << 20) < 0)
+			_exit(2);
+		if (read_key(TEST_CGROUP "/memory.stat", "file") <= 0)
+			_exit(3);
+		_exit(0);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	TEST_RES(read_key(TEST_CGROUP "/memory.events", "max"), _ret > 0);
+	TEST_RES(read_key(TEST_CGROUP "/memory.events", "oom_kill"),
+		 _ret == nr_oom_kills);
+
+	TEST_RES(write_file(TEST_CGROUP "/memory.max", "max"), _ret == 3);
+	TEST_SUCC(unlink(CACHE_FILE));
+}
+END_TEST()
+
+FN_TEST(lower_max)
+{
+	int status, pipefd[2];
+	char byte;
+	pid_t pid;
+
+	TEST_SUCC(pipe(pipefd));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (join_test_cgroup() < 0)
+			_exit(1);
+		if (write_synced_file(CACHE_FILE, 8 << 20) < 0)
+			_exit(2);
+		if (write(pipefd[1], "x", 1) != 1)
+			_exit(3);
+		pause();
+		_exit(4);
+	}
+	TEST_RES(read(pipefd[0], &byte, 1), _ret == 1);
+	TEST_RES(read_number(TEST_CGROUP "/memory.current"), _ret >= 8 << 20);
+
+	// Lowering `memory.max` reclaims the page cache immediately.
+	TEST_RES(write_file(TEST_CGROUP "/memory.max", "2M"), _ret == 2);
+	TEST_RES(read_number(TEST_CGROUP "/memory.current"), _ret <= 2 << 20);
+	TEST_RES(waitpid(pid, &status, WNOHANG), _ret == 0);
+
+	TEST_SUCC(kill(pid, SIGKILL));
+	TEST_RES(waitpid(pid, &status, 0), _ret == pid);
+	TEST_RES(write_file(TEST_CGROUP "/memory.max", "max"), _ret == 3);
+	TEST_SUCC(close(pipefd[0]));
+	TEST_SUCC(close(pipefd[1]));
+	TEST_SUCC(unlink(CACHE_FILE));
+}
+END_TEST()
+
+FN_SETUP(remove_cgroup)
+{
+	CHECK(rmdir(TEST_CGROUP));
+
+	if (!memory_was_enabled)
+		CHECK(write_file(CGROUP_ROOT "/cgroup.subtree_control",
+				 "-memory"));
+}
+END_SETUP()
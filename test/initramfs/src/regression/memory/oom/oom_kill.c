@@ -0,0 +1,80 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <signal.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../../common/file_util.h"
+#include "../../common/test.h"
+
+#define CACHE_FILE "/ext2/oom_kill_cache"
+#define CACHE_SIZE (32 << 20)
+#define CHUNK_SIZE (64 << 20)
+
+static char chunk[1 << 20];
+
+FN_SETUP(create_cache_file)
+{
+	int fd, i;
+
+	// Create clean pages in the page cache, which can be reclaimed.
+	fd = CHECK(open(CACHE_FILE, O_CREAT | O_TRUNC | O_RDWR, 0644));
+	memset(chunk, 'a', sizeof(chunk));
+	for (i = 0; i < CACHE_SIZE / sizeof(chunk); i++)
+		CHECK_WITH(write(fd, chunk, sizeof(chunk)),
+			   _ret == sizeof(chunk));
+	CHECK(fsync(fd));
+	CHECK(close(fd));
+}
+END_SETUP()
+
+static void hog_memory(void)
+{
+	char *addr;
+
+	if (write_file("/proc/self/oom_score_adj", "1000") != 4)
+		_exit(1);
+
+	// Allocate memory until the OOM killer kills the process.
+	for (;;) {
+		addr = mmap(NULL, CHUNK_SIZE, PROT_READ | PROT_WRITE,
+			    MAP_PRIVATE | MAP_ANONYMOUS, -1, 0);
+		if (addr == MAP_FAILED)
+			_exit(2);
+		memset(addr, 1, CHUNK_SIZE);
+	}
+}
+
+FN_TEST(oom_kill)
+{
+	long cached;
+	int status;
+	pid_t pid;
+
+	cached = TEST_RES(read_key("/proc/meminfo", "Cached:"),
+			  _ret >= CACHE_SIZE / 1024);
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0)
+		hog_memory();
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+
+	// The clean pages in the page cache are reclaimed before any process
+	// is killed.
+	TEST_RES(read_key("/proc/meminfo", "Cached:"),
+		 _ret <= cached - CACHE_SIZE / 1024 / 2);
+}
+END_TEST()
+
+FN_SETUP(remove_cache_file)
+{
+	CHECK(unlink(CACHE_FILE));
+}
+END_SETUP()
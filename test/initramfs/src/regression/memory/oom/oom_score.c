@@ -0,0 +1,74 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../../common/file_util.h"
+#include "../../common/test.h"
+
+static long read_oom_score(const char *path)
+{
+	CHECK(read_file(path));
+	return strtol(file_buf, NULL, 10);
+}
+
+FN_TEST(oom_score_range)
+{
+	TEST_RES(read_oom_score("/proc/self/oom_score"),
+		 _ret >= 0 && _ret <= 2000);
+
+	// The init process can never be killed by the OOM killer.
+	TEST_RES(read_oom_score("/proc/1/oom_score"), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(oom_score_adj)
+{
+	int status;
+	pid_t pid;
+
+	// Run in a child process to keep the adjustment of the test process.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		if (write_file("/proc/self/oom_score_adj", "1000") != 4 ||
+		    read_oom_score("/proc/self/oom_score") <= 1000)
+			_exit(1);
+		if (write_file("/proc/self/oom_score_adj", "-1000") != 5 ||
+		    read_oom_score("/proc/self/oom_score") != 0)
+			_exit(2);
+		if (write_file("/proc/self/oom_score_adj", "0") != 1 ||
+		    read_oom_score("/proc/self/oom_score") >= 1000)
+			_exit(3);
+		_exit(0);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	TEST_ERRNO(write_file("/proc/self/oom_score_adj", "1001"), EINVAL);
+	TEST_ERRNO(write_file("/proc/self/oom_score_adj", "-1001"), EINVAL);
+}
+END_TEST()
+
+FN_TEST(meminfo)
+{
+	unsigned long free, available, cached;
+	char *line;
+
+	CHECK(read_file("/proc/meminfo"));
+
+	line = strstr(file_buf, "MemFree:");
+	TEST_RES(line != NULL &&
+			 sscanf(line, "MemFree: %lu kB\nMemAvailable: %lu kB",
+				&free, &available) == 2,
+		 _ret == 1 && free <= available);
+
+	line = strstr(file_buf, "\nCached:");
+	TEST_RES(line != NULL && sscanf(line, "\nCached: %lu kB", &cached) == 1,
+		 _ret == 1 && cached <= available);
+}
+END_TEST()
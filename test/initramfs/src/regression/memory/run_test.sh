@@ -4,6 +4,7 @@
 
 set -e
 
+./memcg/memcg
 ./mmap/mmap_and_fork
 ./mmap/mmap_and_mprotect
 ./mmap/mmap_and_mremap
@@ -15,3 +16,7 @@ set -e
 ./mmap/mmap_vmrss
 ./mmap/rev_map_ext2
 ./mmap/rev_map_tmpfs
+./oom/oom_kill
+./oom/oom_score
+./swap/swap
+./userfaultfd/userfaultfd
@@ -0,0 +1,129 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mman.h>
+#include <sys/swap.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../../common/file_util.h"
+#include "../../common/test.h"
+
+#ifndef MADV_PAGEOUT
+#define MADV_PAGEOUT 21
+#endif
+
+#define PAGE_SIZE 4096
+#define NR_PAGES 16
+
+#define NOT_SWAP_PATH "/tmp/not_swap"
+
+#define SWAPS_HEADER "Filename\t\t\t\tType\t\tSize\t\tUsed\t\tPriority\n"
+
+FN_SETUP(create_file)
+{
+	char page[PAGE_SIZE] = { 0 };
+	int fd;
+
+	fd = CHECK(open(NOT_SWAP_PATH, O_WRONLY | O_CREAT | O_TRUNC, 0600));
+	CHECK_WITH(write(fd, page, sizeof(page)), _ret == sizeof(page));
+	CHECK(close(fd));
+}
+END_SETUP()
+
+FN_TEST(swapon_bad_args)
+{
+	TEST_ERRNO(swapon("/nonexistent", 0), ENOENT);
+	TEST_ERRNO(swapon("/", 0), EINVAL);
+	// The file does not have the swap-space signature.
+	TEST_ERRNO(swapon(NOT_SWAP_PATH, 0), EINVAL);
+	TEST_ERRNO(swapon(NOT_SWAP_PATH, 0x80000), EINVAL);
+}
+END_TEST()
+
+FN_TEST(swapoff_bad_args)
+{
+	TEST_ERRNO(swapoff("/nonexistent"), ENOENT);
+	TEST_ERRNO(swapoff("/"), EINVAL);
+	TEST_ERRNO(swapoff(NOT_SWAP_PATH), EINVAL);
+}
+END_TEST()
+
+FN_TEST(proc_files)
+{
+	unsigned long total, free;
+	char *line;
+
+	TEST_RES(read_file("/proc/swaps"),
+		 strncmp(file_buf, SWAPS_HEADER, strlen(SWAPS_HEADER)) == 0);
+
+	CHECK(read_file("/proc/meminfo"));
+	line = strstr(file_buf, "SwapTotal:");
+	TEST_RES(line != NULL &&
+			 sscanf(line, "SwapTotal: %lu kB\nSwapFree: %lu kB",
+				&total, &free) == 2,
+		 _ret == 1 && free <= total);
+
+	TEST_RES(read_file("/proc/self/status"),
+		 strstr(file_buf, "\nVmSwap:") != NULL);
+}
+END_TEST()
+
+static int check_pages(const unsigned char *addr)
+{
+	int i;
+
+	for (i = 0; i < NR_PAGES; i++) {
+		if (addr[i * PAGE_SIZE] != i + 1 ||
+		    addr[i * PAGE_SIZE + PAGE_SIZE - 1] != i + 1)
+			return 0;
+	}
+
+	return 1;
+}
+
+FN_TEST(madvise_pageout)
+{
+	unsigned char *addr;
+	int i, status;
+	pid_t pid;
+
+	addr = TEST_RES(mmap(NULL, PAGE_SIZE * NR_PAGES, PROT_READ | PROT_WRITE,
+			     MAP_PRIVATE | MAP_ANONYMOUS, -1, 0),
+			_ret != MAP_FAILED);
+	for (i = 0; i < NR_PAGES; i++)
+		memset(addr + i * PAGE_SIZE, i + 1, PAGE_SIZE);
+
+	// The pages may or may not be swapped out, but the content must be kept.
+	TEST_SUCC(madvise(addr, PAGE_SIZE * NR_PAGES, MADV_PAGEOUT));
+	TEST_RES(check_pages(addr), _ret == 1);
+
+	// Pages shared with the child process must be kept for both processes.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		madvise(addr, PAGE_SIZE * NR_PAGES, MADV_PAGEOUT);
+		i = check_pages(addr);
+		addr[0] = 0;
+		_exit(i ? 0 : 1);
+	}
+	TEST_SUCC(madvise(addr, PAGE_SIZE * NR_PAGES, MADV_PAGEOUT));
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+	TEST_RES(check_pages(addr), _ret == 1);
+
+	TEST_SUCC(munmap(addr + PAGE_SIZE, PAGE_SIZE));
+	TEST_ERRNO(madvise(addr, PAGE_SIZE * NR_PAGES, MADV_PAGEOUT), ENOMEM);
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE * NR_PAGES));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(unlink(NOT_SWAP_PATH));
+}
+END_SETUP()
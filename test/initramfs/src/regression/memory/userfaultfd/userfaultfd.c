@@ -0,0 +1,602 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <linux/userfaultfd.h>
+#include <poll.h>
+#include <sys/ioctl.h>
+#include <sys/mman.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../../common/test.h"
+
+#define PAGE_SIZE 4096
+#define POLL_TIMEOUT_MS 10000
+
+static char src_page[PAGE_SIZE] __attribute__((aligned(PAGE_SIZE)));
+
+static int new_uffd(int flags, __u64 features)
+{
+	struct uffdio_api api = { .api = UFFD_API, .features = features };
+	int uffd;
+
+	uffd = syscall(SYS_userfaultfd, flags);
+	if (uffd < 0)
+		return -1;
+
+	if (ioctl(uffd, UFFDIO_API, &api) < 0) {
+		close(uffd);
+		return -1;
+	}
+
+	return uffd;
+}
+
+static int register_range(int uffd, void *addr, size_t len, __u64 mode,
+			  __u64 *ioctls)
+{
+	struct uffdio_register reg = {
+		.range = { .start = (unsigned long)addr, .len = len },
+		.mode = mode,
+	};
+	int ret;
+
+	ret = ioctl(uffd, UFFDIO_REGISTER, &reg);
+	if (ioctls != NULL)
+		*ioctls = reg.ioctls;
+	return ret;
+}
+
+static int unregister_range(int uffd, void *addr, size_t len)
+{
+	struct uffdio_range range = { .start = (unsigned long)addr,
+				      .len = len };
+
+	return ioctl(uffd, UFFDIO_UNREGISTER, &range);
+}
+
+static int copy_page(int uffd, void *dst, __u64 mode, __s64 *copied)
+{
+	struct uffdio_copy copy = {
+		.dst = (unsigned long)dst,
+		.src = (unsigned long)src_page,
+		.len = PAGE_SIZE,
+		.mode = mode,
+	};
+	int ret;
+
+	ret = ioctl(uffd, UFFDIO_COPY, &copy);
+	if (copied != NULL)
+		*copied = copy.copy;
+	return ret;
+}
+
+static int zero_page(int uffd, void *dst, __s64 *zeroed)
+{
+	struct uffdio_zeropage zeropage = {
+		.range = { .start = (unsigned long)dst, .len = PAGE_SIZE },
+	};
+	int ret;
+
+	ret = ioctl(uffd, UFFDIO_ZEROPAGE, &zeropage);
+	if (zeroed != NULL)
+		*zeroed = zeropage.zeropage;
+	return ret;
+}
+
+static int write_protect(int uffd, void *addr, size_t len, __u64 mode)
+{
+	struct uffdio_writeprotect wp = {
+		.range = { .start = (unsigned long)addr, .len = len },
+		.mode = mode,
+	};
+
+	return ioctl(uffd, UFFDIO_WRITEPROTECT, &wp);
+}
+
+static int wake(int uffd, void *addr, size_t len)
+{
+	struct uffdio_range range = { .start = (unsigned long)addr,
+				      .len = len };
+
+	return ioctl(uffd, UFFDIO_WAKE, &range);
+}
+
+static int read_msg(int uffd, struct uffd_msg *msg)
+{
+	struct pollfd pfd = { .fd = uffd, .events = POLLIN };
+
+	if (poll(&pfd, 1, POLL_TIMEOUT_MS) != 1) {
+		errno = ETIMEDOUT;
+		return -1;
+	}
+
+	return read(uffd, msg, sizeof(*msg));
+}
+
+#define IS_PAGEFAULT(msg, _addr, _flags)                          \
+	((msg).event == UFFD_EVENT_PAGEFAULT &&                   \
+	 (msg).arg.pagefault.address == (unsigned long)(_addr) && \
+	 (msg).arg.pagefault.flags == (_flags))
+
+struct access {
+	char *addr;
+	// The value to write, or zero to read the value.
+	char value;
+	char result;
+	volatile int done;
+};
+
+static void *access_thread(void *arg)
+{
+	struct access *access = arg;
+
+	if (access->value != 0)
+		*(volatile char *)access->addr = access->value;
+	else
+		access->result = *(volatile char *)access->addr;
+	access->done = 1;
+
+	return NULL;
+}
+
+static int start_access(pthread_t *thread, struct access *access, char *addr,
+			char value)
+{
+	access->addr = addr;
+	access->value = value;
+	access->result = 0;
+	access->done = 0;
+
+	return pthread_create(thread, NULL, access_thread, access);
+}
+
+FN_SETUP(src_page)
+{
+	memset(src_page, 'x', PAGE_SIZE);
+}
+END_SETUP()
+
+FN_TEST(create)
+{
+	struct uffdio_api api = { .api = UFFD_API };
+	struct uffd_msg msg;
+	struct pollfd pfd;
+	int uffd;
+
+	TEST_ERRNO(syscall(SYS_userfaultfd, 0x10), EINVAL);
+
+	uffd = TEST_SUCC(syscall(SYS_userfaultfd,
+				 O_CLOEXEC | O_NONBLOCK | UFFD_USER_MODE_ONLY));
+	TEST_RES(fcntl(uffd, F_GETFD), _ret == FD_CLOEXEC);
+	TEST_RES(fcntl(uffd, F_GETFL),
+		 (_ret & O_ACCMODE) == O_RDONLY && (_ret & O_NONBLOCK));
+
+	// The `UFFDIO_API` handshake has not been done.
+	pfd.fd = uffd;
+	pfd.events = POLLIN;
+	TEST_RES(poll(&pfd, 1, 0), _ret == 1 && pfd.revents == POLLERR);
+	TEST_ERRNO(read(uffd, &msg, sizeof(msg)), EINVAL);
+	TEST_ERRNO(register_range(uffd, src_page, PAGE_SIZE,
+				  UFFDIO_REGISTER_MODE_MISSING, NULL),
+		   EINVAL);
+
+	api.api = 0;
+	TEST_ERRNO(ioctl(uffd, UFFDIO_API, &api), EINVAL);
+
+	api.api = UFFD_API;
+	api.features = UFFD_FEATURE_PAGEFAULT_FLAG_WP |
+		       UFFD_FEATURE_EVENT_FORK | UFFD_FEATURE_EVENT_REMAP |
+		       UFFD_FEATURE_EVENT_UNMAP;
+	TEST_RES(ioctl(uffd, UFFDIO_API, &api),
+		 _ret == 0 && api.api == UFFD_API &&
+			 (api.features & UFFD_FEATURE_EVENT_UNMAP) &&
+			 (api.ioctls & UFFD_API_IOCTLS) == UFFD_API_IOCTLS);
+
+	// The handshake can only be done once.
+	api.features = 0;
+	TEST_ERRNO(ioctl(uffd, UFFDIO_API, &api), EINVAL);
+
+	TEST_RES(poll(&pfd, 1, 0), _ret == 0);
+	TEST_ERRNO(read(uffd, &msg, sizeof(msg) - 1), EINVAL);
+	TEST_ERRNO(read(uffd, &msg, sizeof(msg)), EAGAIN);
+
+	TEST_SUCC(close(uffd));
+}
+END_TEST()
+
+FN_TEST(dev_userfaultfd)
+{
+	struct uffdio_api api = { .api = UFFD_API };
+	char buf[1];
+	int dev_fd, uffd;
+
+	dev_fd = TEST_SUCC(open("/dev/userfaultfd", O_RDWR | O_CLOEXEC));
+	TEST_ERRNO(ioctl(dev_fd, USERFAULTFD_IOC_NEW, 0x10), EINVAL);
+	TEST_ERRNO(ioctl(dev_fd, _IO(USERFAULTFD_IOC, 0x01), 0), EINVAL);
+	TEST_ERRNO(read(dev_fd, buf, sizeof(buf)), EINVAL);
+
+	uffd = TEST_SUCC(ioctl(dev_fd, USERFAULTFD_IOC_NEW, O_CLOEXEC));
+	TEST_RES(fcntl(uffd, F_GETFD), _ret == FD_CLOEXEC);
+	TEST_SUCC(ioctl(uffd, UFFDIO_API, &api));
+
+	TEST_SUCC(close(uffd));
+	TEST_SUCC(close(dev_fd));
+}
+END_TEST()
+
+FN_TEST(register)
+{
+	__u64 ioctls;
+	__s64 copied;
+	char *addr;
+	int uffd, uffd2;
+
+	uffd = TEST_SUCC(new_uffd(O_NONBLOCK, 0));
+	uffd2 = TEST_SUCC(new_uffd(O_NONBLOCK, 0));
+
+	addr = TEST_SUCC(mmap(NULL, 4 * PAGE_SIZE, PROT_READ | PROT_WRITE,
+			      MAP_PRIVATE | MAP_ANONYMOUS, -1, 0));
+	TEST_SUCC(munmap(addr + 3 * PAGE_SIZE, PAGE_SIZE));
+
+	TEST_ERRNO(register_range(uffd, addr + 1, PAGE_SIZE,
+				  UFFDIO_REGISTER_MODE_MISSING, NULL),
+		   EINVAL);
+	TEST_ERRNO(register_range(uffd, addr, 0, UFFDIO_REGISTER_MODE_MISSING,
+				  NULL),
+		   EINVAL);
+	TEST_ERRNO(register_range(uffd, addr, PAGE_SIZE, 0, NULL), EINVAL);
+	TEST_ERRNO(register_range(uffd, addr, PAGE_SIZE, 1 << 3, NULL), EINVAL);
+
+	// The range contains no mappings.
+	TEST_ERRNO(register_range(uffd, addr + 3 * PAGE_SIZE, PAGE_SIZE,
+				  UFFDIO_REGISTER_MODE_MISSING, NULL),
+		   EINVAL);
+
+	// The range may contain holes.
+	TEST_RES(register_range(uffd, addr, 4 * PAGE_SIZE,
+				UFFDIO_REGISTER_MODE_MISSING, &ioctls),
+		 _ret == 0 && (ioctls & (1ULL << _UFFDIO_COPY)) &&
+			 (ioctls & (1ULL << _UFFDIO_ZEROPAGE)) &&
+			 (ioctls & (1ULL << _UFFDIO_WAKE)) &&
+			 !(ioctls & (1ULL << _UFFDIO_WRITEPROTECT)));
+	TEST_RES(register_range(uffd, addr, PAGE_SIZE,
+				UFFDIO_REGISTER_MODE_MISSING |
+					UFFDIO_REGISTER_MODE_WP,
+				&ioctls),
+		 _ret == 0 && (ioctls & (1ULL << _UFFDIO_WRITEPROTECT)));
+
+	// The mappings are registered with another userfaultfd.
+	TEST_ERRNO(register_range(uffd2, addr + PAGE_SIZE, PAGE_SIZE,
+				  UFFDIO_REGISTER_MODE_MISSING, NULL),
+		   EBUSY);
+
+	TEST_ERRNO(unregister_range(uffd, addr + 3 * PAGE_SIZE, PAGE_SIZE),
+		   EINVAL);
+	TEST_SUCC(unregister_range(uffd, addr, 4 * PAGE_SIZE));
+	TEST_SUCC(register_range(uffd2, addr + PAGE_SIZE, PAGE_SIZE,
+				 UFFDIO_REGISTER_MODE_MISSING, NULL));
+
+	// The page is not registered.
+	TEST(copy_page(uffd, addr, 0, &copied), ENOENT, copied == -ENOENT);
+
+	TEST_SUCC(close(uffd));
+	TEST_SUCC(close(uffd2));
+	TEST_SUCC(munmap(addr, 3 * PAGE_SIZE));
+}
+END_TEST()
+
+FN_TEST(missing)
+{
+	struct access access;
+	struct uffd_msg msg;
+	pthread_t thread;
+	__s64 copied;
+	char *addr;
+	int uffd;
+
+	uffd = TEST_SUCC(new_uffd(O_NONBLOCK, 0));
+	addr = TEST_SUCC(mmap(NULL, 2 * PAGE_SIZE, PROT_READ | PROT_WRITE,
+			      MAP_PRIVATE | MAP_ANONYMOUS, -1, 0));
+	TEST_SUCC(register_range(uffd, addr, 2 * PAGE_SIZE,
+				 UFFDIO_REGISTER_MODE_MISSING, NULL));
+
+	// Resolve a write fault with `UFFDIO_COPY`.
+	TEST_SUCC(start_access(&thread, &access, addr + 10, 'a'));
+	TEST_RES(read_msg(uffd, &msg),
+		 _ret == sizeof(msg) &&
+			 IS_PAGEFAULT(msg, addr, UFFD_PAGEFAULT_FLAG_WRITE));
+	TEST_ERRNO(read(uffd, &msg, sizeof(msg)), EAGAIN);
+
+	TEST_ERRNO(copy_page(uffd, addr + 1, 0, NULL), EINVAL);
+	TEST_ERRNO(copy_page(uffd, addr, 1 << 2, NULL), EINVAL);
+	TEST_RES(copy_page(uffd, addr, UFFDIO_COPY_MODE_DONTWAKE, &copied),
+		 _ret == 0 && copied == PAGE_SIZE);
+	TEST(copy_page(uffd, addr, 0, &copied), EEXIST, copied == -EEXIST);
+
+	// The faulting thread is not woken up yet.
+	usleep(100 * 1000);
+	TEST_RES(access.done, _ret == 0);
+	TEST_SUCC(wake(uffd, addr, PAGE_SIZE));
+	TEST_SUCC(pthread_join(thread, NULL));
+	TEST_RES(addr[10], _ret == 'a');
+	TEST_RES(addr[11], _ret == 'x');
+
+	// Resolve a read fault with `UFFDIO_ZEROPAGE`.
+	TEST_SUCC(start_access(&thread, &access, addr + PAGE_SIZE + 5, 0));
+	TEST_RES(read_msg(uffd, &msg),
+		 _ret == sizeof(msg) && IS_PAGEFAULT(msg, addr + PAGE_SIZE, 0));
+	TEST_RES(zero_page(uffd, addr + PAGE_SIZE, &copied),
+		 _ret == 0 && copied == PAGE_SIZE);
+	TEST_SUCC(pthread_join(thread, NULL));
+	TEST_RES(access.result, _ret == 0);
+
+	// The pages are populated and accessing them triggers no faults.
+	addr[PAGE_SIZE] = 'b';
+	TEST_RES(addr[PAGE_SIZE], _ret == 'b');
+
+	TEST_SUCC(close(uffd));
+	TEST_SUCC(munmap(addr, 2 * PAGE_SIZE));
+}
+END_TEST()
+
+FN_TEST(write_protect)
+{
+	struct access access;
+	struct uffd_msg msg;
+	pthread_t thread;
+	char *addr;
+	int uffd;
+
+	uffd = TEST_SUCC(new_uffd(O_NONBLOCK, UFFD_FEATURE_PAGEFAULT_FLAG_WP));
+	addr = TEST_SUCC(mmap(NULL, 3 * PAGE_SIZE, PROT_READ | PROT_WRITE,
+			      MAP_PRIVATE | MAP_ANONYMOUS, -1, 0));
+	addr[0] = 'a';
+	addr[PAGE_SIZE] = 'b';
+	TEST_SUCC(register_range(uffd, addr, 2 * PAGE_SIZE,
+				 UFFDIO_REGISTER_MODE_WP, NULL));
+	TEST_SUCC(register_range(uffd, addr + 2 * PAGE_SIZE, PAGE_SIZE,
+				 UFFDIO_REGISTER_MODE_MISSING, NULL));
+
+	TEST_ERRNO(write_protect(uffd, addr, 2 * PAGE_SIZE,
+				 UFFDIO_WRITEPROTECT_MODE_WP |
+					 UFFDIO_WRITEPROTECT_MODE_DONTWAKE),
+		   EINVAL);
+	TEST_ERRNO(write_protect(uffd, addr + 2 * PAGE_SIZE, PAGE_SIZE,
+				 UFFDIO_WRITEPROTECT_MODE_WP),
+		   ENOENT);
+	TEST_SUCC(write_protect(uffd, addr, 2 * PAGE_SIZE,
+				UFFDIO_WRITEPROTECT_MODE_WP));
+
+	// Reading write-protected pages triggers no faults.
+	TEST_RES(addr[0], _ret == 'a');
+
+	// Resolve a fault by un-write-protecting the page.
+	TEST_SUCC(start_access(&thread, &access, addr + 1, 'c'));
+	TEST_RES(read_msg(uffd, &msg),
+		 _ret == sizeof(msg) &&
+			 IS_PAGEFAULT(msg, addr,
+				      UFFD_PAGEFAULT_FLAG_WRITE |
+					      UFFD_PAGEFAULT_FLAG_WP));
+	TEST_SUCC(write_protect(uffd, addr, PAGE_SIZE, 0));
+	TEST_SUCC(pthread_join(thread, NULL));
+	TEST_RES(addr[1], _ret == 'c');
+
+	// Resolve a fault without waking up the thread, and then wake it up.
+	TEST_SUCC(start_access(&thread, &access, addr + PAGE_SIZE + 1, 'd'));
+	TEST_RES(read_msg(uffd, &msg),
+		 _ret == sizeof(msg) &&
+			 IS_PAGEFAULT(msg, addr + PAGE_SIZE,
+				      UFFD_PAGEFAULT_FLAG_WRITE |
+					      UFFD_PAGEFAULT_FLAG_WP));
+	TEST_SUCC(write_protect(uffd, addr + PAGE_SIZE, PAGE_SIZE,
+				UFFDIO_WRITEPROTECT_MODE_DONTWAKE));
+	TEST_SUCC(wake(uffd, addr + PAGE_SIZE, PAGE_SIZE));
+	TEST_SUCC(pthread_join(thread, NULL));
+	TEST_RES(addr[PAGE_SIZE], _ret == 'b');
+	TEST_RES(addr[PAGE_SIZE + 1], _ret == 'd');
+
+	// Pages copied in the write-protect mode are write-protected.
+	TEST_SUCC(register_range(uffd, addr + 2 * PAGE_SIZE, PAGE_SIZE,
+				 UFFDIO_REGISTER_MODE_MISSING |
+					 UFFDIO_REGISTER_MODE_WP,
+				 NULL));
+	TEST_SUCC(start_access(&thread, &access, addr + 2 * PAGE_SIZE, 'e'));
+	TEST_RES(read_msg(uffd, &msg),
+		 _ret == sizeof(msg) &&
+			 IS_PAGEFAULT(msg, addr + 2 * PAGE_SIZE,
+				      UFFD_PAGEFAULT_FLAG_WRITE));
+	TEST_SUCC(copy_page(uffd, addr + 2 * PAGE_SIZE, UFFDIO_COPY_MODE_WP,
+			    NULL));
+	TEST_RES(read_msg(uffd, &msg),
+		 _ret == sizeof(msg) &&
+			 IS_PAGEFAULT(msg, addr + 2 * PAGE_SIZE,
+				      UFFD_PAGEFAULT_FLAG_WRITE |
+					      UFFD_PAGEFAULT_FLAG_WP));
+	TEST_SUCC(write_protect(uffd, addr + 2 * PAGE_SIZE, PAGE_SIZE, 0));
+	TEST_SUCC(pthread_join(thread, NULL));
+	TEST_RES(addr[2 * PAGE_SIZE], _ret == 'e');
+	TEST_RES(addr[2 * PAGE_SIZE + 1], _ret == 'x');
+
+	TEST_SUCC(close(uffd));
+	TEST_SUCC(munmap(addr, 3 * PAGE_SIZE));
+}
+END_TEST()
+
+FN_TEST(release)
+{
+	struct access access;
+	struct uffd_msg msg;
+	pthread_t thread;
+	char *addr;
+	int uffd;
+
+	uffd = TEST_SUCC(new_uffd(O_NONBLOCK, 0));
+	addr = TEST_SUCC(mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE,
+			      MAP_PRIVATE | MAP_ANONYMOUS, -1, 0));
+	TEST_SUCC(register_range(uffd, addr, PAGE_SIZE,
+				 UFFDIO_REGISTER_MODE_MISSING, NULL));
+
+	// Closing the userfaultfd wakes up the faulting thread, and the fault
+	// is then handled by the kernel.
+	TEST_SUCC(start_access(&thread, &access, addr, 0));
+	TEST_RES(read_msg(uffd, &msg),
+		 _ret == sizeof(msg) && IS_PAGEFAULT(msg, addr, 0));
+	TEST_SUCC(close(uffd));
+	TEST_SUCC(pthread_join(thread, NULL));
+	TEST_RES(access.result, _ret == 0);
+
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+}
+END_TEST()
+
+FN_TEST(user_mode_only)
+{
+	int uffd, pipefd[2];
+	char *addr;
+
+	uffd = TEST_SUCC(new_uffd(O_NONBLOCK | UFFD_USER_MODE_ONLY, 0));
+	addr = TEST_SUCC(mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE,
+			      MAP_PRIVATE | MAP_ANONYMOUS, -1, 0));
+	TEST_SUCC(register_range(uffd, addr, PAGE_SIZE,
+				 UFFDIO_REGISTER_MODE_MISSING, NULL));
+
+	// Page faults from kernel mode cannot be handled by the userfaultfd.
+	TEST_SUCC(pipe(pipefd));
+	TEST_ERRNO(write(pipefd[1], addr, 1), EFAULT);
+
+	TEST_SUCC(close(pipefd[0]));
+	TEST_SUCC(close(pipefd[1]));
+	TEST_SUCC(close(uffd));
+	TEST_SUCC(munmap(addr, PAGE_SIZE));
+}
+END_TEST()
+
+// Linux blocks the thread that triggers an event until the event is read, so
+// the events are triggered in another thread.
+
+static char *event_addr;
+static char *remap_target;
+
+static void *unmap_and_remap_thread(void *arg)
+{
+	CHECK(munmap(event_addr, PAGE_SIZE));
+	CHECK_WITH(mremap(event_addr + PAGE_SIZE, PAGE_SIZE, PAGE_SIZE,
+			  MREMAP_MAYMOVE | MREMAP_FIXED, remap_target),
+		   _ret == remap_target);
+	return NULL;
+}
+
+FN_TEST(unmap_and_remap_events)
+{
+	struct access access;
+	struct uffd_msg msg;
+	struct pollfd pfd;
+	pthread_t thread;
+	__s64 copied;
+	int uffd;
+
+	uffd = TEST_SUCC(new_uffd(O_NONBLOCK, UFFD_FEATURE_EVENT_UNMAP |
+						  UFFD_FEATURE_EVENT_REMAP));
+	event_addr =
+		TEST_SUCC(mmap(NULL, 3 * PAGE_SIZE, PROT_READ | PROT_WRITE,
+			       MAP_PRIVATE | MAP_ANONYMOUS, -1, 0));
+	remap_target = TEST_SUCC(mmap(NULL, PAGE_SIZE, PROT_NONE,
+				      MAP_PRIVATE | MAP_ANONYMOUS, -1, 0));
+	TEST_SUCC(register_range(uffd, event_addr, 3 * PAGE_SIZE,
+				 UFFDIO_REGISTER_MODE_MISSING, NULL));
+
+	TEST_SUCC(pthread_create(&thread, NULL, unmap_and_remap_thread, NULL));
+
+	// Faults cannot be resolved while the events are not read.
+	pfd.fd = uffd;
+	pfd.events = POLLIN;
+	TEST_RES(poll(&pfd, 1, POLL_TIMEOUT_MS),
+		 _ret == 1 && pfd.revents == POLLIN);
+	TEST(copy_page(uffd, event_addr + 2 * PAGE_SIZE, 0, &copied), EAGAIN,
+	     copied == -EAGAIN);
+
+	TEST_RES(read_msg(uffd, &msg),
+		 _ret == sizeof(msg) && msg.event == UFFD_EVENT_UNMAP &&
+			 msg.arg.remove.start == (unsigned long)event_addr &&
+			 msg.arg.remove.end ==
+				 (unsigned long)event_addr + PAGE_SIZE);
+	TEST_RES(read_msg(uffd, &msg),
+		 _ret == sizeof(msg) && msg.event == UFFD_EVENT_REMAP &&
+			 msg.arg.remap.from ==
+				 (unsigned long)event_addr + PAGE_SIZE &&
+			 msg.arg.remap.to == (unsigned long)remap_target &&
+			 msg.arg.remap.len == PAGE_SIZE);
+	TEST_RES(read_msg(uffd, &msg),
+		 _ret == sizeof(msg) && msg.event == UFFD_EVENT_UNMAP &&
+			 msg.arg.remove.start ==
+				 (unsigned long)event_addr + PAGE_SIZE &&
+			 msg.arg.remove.end ==
+				 (unsigned long)event_addr + 2 * PAGE_SIZE);
+	TEST_SUCC(pthread_join(thread, NULL));
+
+	// The moved mapping is still registered.
+	TEST_SUCC(start_access(&thread, &access, remap_target, 0));
+	TEST_RES(read_msg(uffd, &msg),
+		 _ret == sizeof(msg) && IS_PAGEFAULT(msg, remap_target, 0));
+	TEST_SUCC(copy_page(uffd, remap_target, 0, NULL));
+	TEST_SUCC(pthread_join(thread, NULL));
+	TEST_RES(access.result, _ret == 'x');
+
+	TEST_SUCC(close(uffd));
+	TEST_SUCC(munmap(event_addr + 2 * PAGE_SIZE, PAGE_SIZE));
+	TEST_SUCC(munmap(remap_target, PAGE_SIZE));
+}
+END_TEST()
+
+static char *fork_addr;
+static int fork_status;
+
+static void *fork_thread(void *arg)
+{
+	pid_t pid;
+
+	pid = CHECK(fork());
+	if (pid == 0)
+		_exit(fork_addr[0] == 'x' ? EXIT_SUCCESS : EXIT_FAILURE);
+
+	CHECK_WITH(waitpid(pid, &fork_status, 0), _ret == pid);
+	return NULL;
+}
+
+FN_TEST(fork_event)
+{
+	struct uffd_msg msg;
+	pthread_t thread;
+	int uffd, child_uffd;
+
+	uffd = TEST_SUCC(new_uffd(O_NONBLOCK, UFFD_FEATURE_EVENT_FORK));
+	fork_addr = TEST_SUCC(mmap(NULL, PAGE_SIZE, PROT_READ | PROT_WRITE,
+				   MAP_PRIVATE | MAP_ANONYMOUS, -1, 0));
+	TEST_SUCC(register_range(uffd, fork_addr, PAGE_SIZE,
+				 UFFDIO_REGISTER_MODE_MISSING, NULL));
+
+	TEST_SUCC(pthread_create(&thread, NULL, fork_thread, NULL));
+
+	// The child process has its own userfaultfd.
+	TEST_RES(read_msg(uffd, &msg),
+		 _ret == sizeof(msg) && msg.event == UFFD_EVENT_FORK);
+	child_uffd = msg.arg.fork.ufd;
+	TEST_RES(read_msg(child_uffd, &msg),
+		 _ret == sizeof(msg) && IS_PAGEFAULT(msg, fork_addr, 0));
+	TEST_SUCC(copy_page(child_uffd, fork_addr, 0, NULL));
+	TEST_SUCC(pthread_join(thread, NULL));
+	TEST_RES(fork_status,
+		 WIFEXITED(_ret) && WEXITSTATUS(_ret) == EXIT_SUCCESS);
+
+	// The page in the parent process is still missing.
+	TEST_ERRNO(read(uffd, &msg, sizeof(msg)), EAGAIN);
+
+	TEST_SUCC(close(child_uffd));
+	TEST_SUCC(close(uffd));
+	TEST_SUCC(munmap(fork_addr, PAGE_SIZE));
+}
+END_TEST()
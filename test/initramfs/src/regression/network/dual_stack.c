@@ -0,0 +1,273 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <arpa/inet.h>
+#include <netinet/in.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#include "../common/test.h"
+
+#define UDP_PORT 0x2345
+#define UDP_V6ONLY_PORT 0x2346
+#define TCP_PORT 0x2347
+
+static struct sockaddr_in6 lo6_addr;
+static struct sockaddr_in6 mapped_addr;
+static struct sockaddr_in lo_addr;
+
+FN_SETUP(addrs)
+{
+	lo6_addr.sin6_family = AF_INET6;
+	lo6_addr.sin6_addr = in6addr_loopback;
+
+	mapped_addr.sin6_family = AF_INET6;
+	CHECK(inet_pton(AF_INET6, "::ffff:127.0.0.1", &mapped_addr.sin6_addr));
+
+	lo_addr.sin_family = AF_INET;
+	lo_addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+}
+END_SETUP()
+
+static int is_mapped_lo(const struct sockaddr_in6 *addr, socklen_t addrlen)
+{
+	return addrlen == sizeof(*addr) && addr->sin6_family == AF_INET6 &&
+	       memcmp(&addr->sin6_addr, &mapped_addr.sin6_addr,
+		      sizeof(addr->sin6_addr)) == 0;
+}
+
+static int is_lo6(const struct sockaddr_in6 *addr, socklen_t addrlen)
+{
+	return addrlen == sizeof(*addr) && addr->sin6_family == AF_INET6 &&
+	       IN6_IS_ADDR_LOOPBACK(&addr->sin6_addr);
+}
+
+FN_TEST(v6only_default)
+{
+	int sk_udp, sk_tcp, sk_udp4;
+	int v6only = -1;
+	socklen_t optlen = sizeof(v6only);
+
+	sk_udp = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+	sk_tcp = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	sk_udp4 = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+
+	TEST_RES(getsockopt(sk_udp, SOL_IPV6, IPV6_V6ONLY, &v6only, &optlen),
+		 optlen == sizeof(v6only) && v6only == 0);
+	TEST_RES(getsockopt(sk_tcp, SOL_IPV6, IPV6_V6ONLY, &v6only, &optlen),
+		 optlen == sizeof(v6only) && v6only == 0);
+
+	TEST_ERRNO(getsockopt(sk_udp4, SOL_IPV6, IPV6_V6ONLY, &v6only,
+			      &optlen),
+		   ENOPROTOOPT);
+	TEST_ERRNO(setsockopt(sk_udp4, SOL_IPV6, IPV6_V6ONLY, &v6only,
+			      sizeof(v6only)),
+		   ENOPROTOOPT);
+
+	TEST_SUCC(close(sk_udp));
+	TEST_SUCC(close(sk_tcp));
+	TEST_SUCC(close(sk_udp4));
+}
+END_TEST()
+
+FN_TEST(udp6_loopback)
+{
+	int sk_server, sk_client;
+	struct sockaddr_in6 addr;
+	socklen_t addrlen;
+	char buf[4];
+
+	sk_server = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+	sk_client = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+
+	lo6_addr.sin6_port = htons(UDP_PORT);
+	TEST_SUCC(bind(sk_server, (struct sockaddr *)&lo6_addr,
+		       sizeof(lo6_addr)));
+
+	TEST_RES(sendto(sk_client, "ping", 4, 0, (struct sockaddr *)&lo6_addr,
+			sizeof(lo6_addr)),
+		 _ret == 4);
+
+	addrlen = sizeof(addr);
+	TEST_RES(getsockname(sk_client, (struct sockaddr *)&addr, &addrlen),
+		 is_lo6(&addr, addrlen) && addr.sin6_port != 0);
+
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(sk_server, buf, sizeof(buf), 0,
+			  (struct sockaddr *)&addr, &addrlen),
+		 _ret == 4 && memcmp(buf, "ping", 4) == 0 &&
+			 is_lo6(&addr, addrlen));
+
+	TEST_RES(sendto(sk_server, "pong", 4, 0, (struct sockaddr *)&addr,
+			addrlen),
+		 _ret == 4);
+	TEST_RES(recv(sk_client, buf, sizeof(buf), 0),
+		 _ret == 4 && memcmp(buf, "pong", 4) == 0);
+
+	TEST_SUCC(close(sk_server));
+	TEST_SUCC(close(sk_client));
+}
+END_TEST()
+
+FN_TEST(udp6_v6only)
+{
+	int sk;
+	int v6only = 1;
+
+	sk = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+	TEST_SUCC(setsockopt(sk, SOL_IPV6, IPV6_V6ONLY, &v6only,
+			     sizeof(v6only)));
+
+	mapped_addr.sin6_port = htons(UDP_V6ONLY_PORT);
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&mapped_addr,
+			sizeof(mapped_addr)),
+		   EINVAL);
+	TEST_ERRNO(sendto(sk, "ping", 4, 0, (struct sockaddr *)&mapped_addr,
+			  sizeof(mapped_addr)),
+		   ENETUNREACH);
+	TEST_ERRNO(connect(sk, (struct sockaddr *)&mapped_addr,
+			   sizeof(mapped_addr)),
+		   ENETUNREACH);
+
+	lo6_addr.sin6_port = htons(UDP_V6ONLY_PORT);
+	TEST_SUCC(bind(sk, (struct sockaddr *)&lo6_addr, sizeof(lo6_addr)));
+
+	v6only = 0;
+	TEST_ERRNO(setsockopt(sk, SOL_IPV6, IPV6_V6ONLY, &v6only,
+			      sizeof(v6only)),
+		   EINVAL);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
+
+FN_TEST(udp6_mapped)
+{
+	int sk6, sk4;
+	struct sockaddr_in6 addr6;
+	struct sockaddr_in addr4;
+	socklen_t addrlen;
+	char buf[4];
+
+	sk6 = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, 0));
+	sk4 = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+
+	mapped_addr.sin6_port = htons(UDP_PORT);
+	TEST_SUCC(bind(sk6, (struct sockaddr *)&mapped_addr,
+		       sizeof(mapped_addr)));
+
+	addrlen = sizeof(addr6);
+	TEST_RES(getsockname(sk6, (struct sockaddr *)&addr6, &addrlen),
+		 is_mapped_lo(&addr6, addrlen) &&
+			 addr6.sin6_port == htons(UDP_PORT));
+
+	lo_addr.sin_port = htons(UDP_PORT);
+	TEST_RES(sendto(sk4, "ping", 4, 0, (struct sockaddr *)&lo_addr,
+			sizeof(lo_addr)),
+		 _ret == 4);
+
+	addrlen = sizeof(addr6);
+	TEST_RES(recvfrom(sk6, buf, sizeof(buf), 0, (struct sockaddr *)&addr6,
+			  &addrlen),
+		 _ret == 4 && memcmp(buf, "ping", 4) == 0 &&
+			 is_mapped_lo(&addr6, addrlen));
+
+	TEST_RES(sendto(sk6, "pong", 4, 0, (struct sockaddr *)&addr6,
+			addrlen),
+		 _ret == 4);
+
+	addrlen = sizeof(addr4);
+	TEST_RES(recvfrom(sk4, buf, sizeof(buf), 0, (struct sockaddr *)&addr4,
+			  &addrlen),
+		 _ret == 4 && memcmp(buf, "pong", 4) == 0 &&
+			 addrlen == sizeof(addr4) &&
+			 addr4.sin_family == AF_INET &&
+			 addr4.sin_addr.s_addr == htonl(INADDR_LOOPBACK) &&
+			 addr4.sin_port == htons(UDP_PORT));
+
+	TEST_SUCC(close(sk6));
+	TEST_SUCC(close(sk4));
+}
+END_TEST()
+
+FN_TEST(tcp6_mapped)
+{
+	int sk_listen, sk_client4, sk_client6, sk_accepted;
+	struct sockaddr_in6 addr6;
+	struct sockaddr_in addr4;
+	socklen_t addrlen;
+
+	sk_listen = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	sk_client4 = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	sk_client6 = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+
+	mapped_addr.sin6_port = htons(TCP_PORT);
+	TEST_SUCC(bind(sk_listen, (struct sockaddr *)&mapped_addr,
+		       sizeof(mapped_addr)));
+	TEST_SUCC(listen(sk_listen, 2));
+
+	lo_addr.sin_port = htons(TCP_PORT);
+	TEST_SUCC(connect(sk_client4, (struct sockaddr *)&lo_addr,
+			  sizeof(lo_addr)));
+
+	addrlen = sizeof(addr4);
+	TEST_SUCC(getsockname(sk_client4, (struct sockaddr *)&addr4,
+			      &addrlen));
+
+	addrlen = sizeof(addr6);
+	sk_accepted = TEST_RES(accept(sk_listen, (struct sockaddr *)&addr6,
+				      &addrlen),
+			       is_mapped_lo(&addr6, addrlen) &&
+				       addr6.sin6_port == addr4.sin_port);
+
+	addrlen = sizeof(addr6);
+	TEST_RES(getsockname(sk_accepted, (struct sockaddr *)&addr6, &addrlen),
+		 is_mapped_lo(&addr6, addrlen) &&
+			 addr6.sin6_port == htons(TCP_PORT));
+
+	addrlen = sizeof(addr6);
+	TEST_RES(getpeername(sk_accepted, (struct sockaddr *)&addr6, &addrlen),
+		 is_mapped_lo(&addr6, addrlen) &&
+			 addr6.sin6_port == addr4.sin_port);
+	TEST_SUCC(close(sk_accepted));
+
+	TEST_SUCC(connect(sk_client6, (struct sockaddr *)&mapped_addr,
+			  sizeof(mapped_addr)));
+
+	addrlen = sizeof(addr6);
+	TEST_RES(getpeername(sk_client6, (struct sockaddr *)&addr6, &addrlen),
+		 is_mapped_lo(&addr6, addrlen) &&
+			 addr6.sin6_port == htons(TCP_PORT));
+
+	addrlen = sizeof(addr6);
+	sk_accepted = TEST_RES(accept(sk_listen, (struct sockaddr *)&addr6,
+				      &addrlen),
+			       is_mapped_lo(&addr6, addrlen));
+	TEST_SUCC(close(sk_accepted));
+
+	TEST_SUCC(close(sk_listen));
+	TEST_SUCC(close(sk_client4));
+	TEST_SUCC(close(sk_client6));
+}
+END_TEST()
+
+FN_TEST(tcp6_v6only)
+{
+	int sk;
+	int v6only = 1;
+
+	sk = TEST_SUCC(socket(AF_INET6, SOCK_STREAM, 0));
+	TEST_SUCC(setsockopt(sk, SOL_IPV6, IPV6_V6ONLY, &v6only,
+			     sizeof(v6only)));
+
+	mapped_addr.sin6_port = htons(TCP_PORT);
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&mapped_addr,
+			sizeof(mapped_addr)),
+		   EINVAL);
+	TEST_ERRNO(connect(sk, (struct sockaddr *)&mapped_addr,
+			   sizeof(mapped_addr)),
+		   ENETUNREACH);
+
+	TEST_SUCC(close(sk));
+}
+END_TEST()
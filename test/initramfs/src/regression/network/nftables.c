@@ -0,0 +1,656 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <linux/netfilter.h>
+#include <linux/netfilter/nf_tables.h>
+#include <linux/netfilter/nfnetlink.h>
+#include <linux/netlink.h>
+#include <netinet/in.h>
+#include <poll.h>
+#include <sched.h>
+#include <stdint.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#include "../common/test.h"
+
+// `NFT_MSG_DESTROYTABLE` is missing in old headers.
+#define MSG_DESTROYTABLE 26
+
+#define TABLE_NAME "asterinas"
+
+#define PORT_DNAT_TO 9995
+#define PORT_DNAT 9996
+#define PORT_ACCEPT 9997
+#define PORT_REJECT 9998
+#define PORT_DROP 9999
+
+static int nft_fd;
+static unsigned int nft_seq;
+
+// The messages that will be sent together.
+static char batch[8192];
+static int batch_len;
+static int num_acks;
+static struct nlmsghdr *cur_msg;
+
+static int udp_fd;
+static int tcp_listen_fd;
+static struct sockaddr_in lo_addr = {
+	.sin_family = AF_INET,
+};
+
+FN_SETUP(nft)
+{
+	nft_fd = CHECK(socket(AF_NETLINK, SOCK_RAW, NETLINK_NETFILTER));
+
+	lo_addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+	udp_fd = CHECK(socket(AF_INET, SOCK_DGRAM, 0));
+	CHECK(bind(udp_fd, (struct sockaddr *)&lo_addr, sizeof(lo_addr)));
+
+	tcp_listen_fd = CHECK(socket(AF_INET, SOCK_STREAM, 0));
+	lo_addr.sin_port = htons(PORT_REJECT);
+	CHECK(bind(tcp_listen_fd, (struct sockaddr *)&lo_addr,
+		   sizeof(lo_addr)));
+	CHECK(listen(tcp_listen_fd, 2));
+}
+END_SETUP()
+
+static void reset_msgs(void)
+{
+	batch_len = 0;
+	num_acks = 0;
+}
+
+static void begin_msg(int type, int family, int flags, int res_id)
+{
+	struct nfgenmsg *nfg;
+
+	cur_msg = (struct nlmsghdr *)(batch + batch_len);
+	memset(cur_msg, 0, NLMSG_LENGTH(sizeof(*nfg)));
+	cur_msg->nlmsg_len = NLMSG_LENGTH(sizeof(*nfg));
+	cur_msg->nlmsg_type = type;
+	cur_msg->nlmsg_flags = NLM_F_REQUEST | flags;
+	cur_msg->nlmsg_seq = ++nft_seq;
+
+	nfg = NLMSG_DATA(cur_msg);
+	nfg->nfgen_family = family;
+	nfg->version = NFNETLINK_V0;
+	nfg->res_id = htons(res_id);
+
+	if (flags & NLM_F_ACK)
+		++num_acks;
+}
+
+static void begin_nft_msg(int type, int family, int flags)
+{
+	begin_msg((NFNL_SUBSYS_NFTABLES << 8) | type, family, flags, 0);
+}
+
+static void end_msg(void)
+{
+	batch_len += NLMSG_ALIGN(cur_msg->nlmsg_len);
+}
+
+static struct nlattr *add_attr(int type, const void *data, int len)
+{
+	struct nlattr *nla = (struct nlattr *)((char *)cur_msg +
+					       NLMSG_ALIGN(cur_msg->nlmsg_len));
+
+	nla->nla_type = type;
+	nla->nla_len = NLA_HDRLEN + len;
+	if (len > 0)
+		memcpy((char *)nla + NLA_HDRLEN, data, len);
+	cur_msg->nlmsg_len =
+		NLMSG_ALIGN(cur_msg->nlmsg_len) + NLA_ALIGN(nla->nla_len);
+
+	return nla;
+}
+
+static void add_str(int type, const char *str)
+{
+	add_attr(type, str, strlen(str) + 1);
+}
+
+static void add_be32(int type, uint32_t value)
+{
+	value = htonl(value);
+	add_attr(type, &value, sizeof(value));
+}
+
+static struct nlattr *begin_nest(int type)
+{
+	return add_attr(NLA_F_NESTED | type, NULL, 0);
+}
+
+static void end_nest(struct nlattr *nest)
+{
+	nest->nla_len = (char *)cur_msg + cur_msg->nlmsg_len - (char *)nest;
+}
+
+// Sends the messages and returns the first error in the acknowledgments.
+static int send_msgs(void)
+{
+	char buf[4096];
+	struct nlmsghdr *nh = (struct nlmsghdr *)buf;
+	struct nlmsgerr *err = NLMSG_DATA(nh);
+	int i, error = 0;
+
+	if (send(nft_fd, batch, batch_len, 0) < 0)
+		return -1;
+
+	for (i = 0; i < num_acks; ++i) {
+		if (recv(nft_fd, buf, sizeof(buf), 0) < 0)
+			return -1;
+		if (nh->nlmsg_type != NLMSG_ERROR) {
+			errno = EPROTO;
+			return -1;
+		}
+		if (err->error != 0 && error == 0)
+			error = -err->error;
+	}
+
+	if (error != 0) {
+		errno = error;
+		return -1;
+	}
+	return 0;
+}
+
+static void begin_batch(void)
+{
+	reset_msgs();
+	begin_msg(NFNL_MSG_BATCH_BEGIN, AF_UNSPEC, 0, NFNL_SUBSYS_NFTABLES);
+	end_msg();
+}
+
+static int end_batch(void)
+{
+	begin_msg(NFNL_MSG_BATCH_END, AF_UNSPEC, 0, NFNL_SUBSYS_NFTABLES);
+	end_msg();
+
+	return send_msgs();
+}
+
+static void table_msg(int type, int flags)
+{
+	begin_nft_msg(type, NFPROTO_INET, NLM_F_ACK | flags);
+	add_str(NFTA_TABLE_NAME, TABLE_NAME);
+	end_msg();
+}
+
+static void chain_msg(const char *table, const char *chain, int hook,
+		      int priority, const char *type)
+{
+	struct nlattr *nest;
+
+	begin_nft_msg(NFT_MSG_NEWCHAIN, NFPROTO_INET,
+		      NLM_F_ACK | NLM_F_CREATE | NLM_F_EXCL);
+	add_str(NFTA_CHAIN_TABLE, table);
+	add_str(NFTA_CHAIN_NAME, chain);
+	nest = begin_nest(NFTA_CHAIN_HOOK);
+	add_be32(NFTA_HOOK_HOOKNUM, hook);
+	add_be32(NFTA_HOOK_PRIORITY, priority);
+	end_nest(nest);
+	add_str(NFTA_CHAIN_TYPE, type);
+	end_msg();
+}
+
+// Adds an expression. The attributes of the expression are added between
+// `begin_expr` and `end_expr`.
+static struct nlattr *begin_expr(const char *name, struct nlattr **data)
+{
+	struct nlattr *elem = begin_nest(NFTA_LIST_ELEM);
+
+	add_str(NFTA_EXPR_NAME, name);
+	*data = begin_nest(NFTA_EXPR_DATA);
+
+	return elem;
+}
+
+static void end_expr(struct nlattr *elem, struct nlattr *data)
+{
+	end_nest(data);
+	end_nest(elem);
+}
+
+static void add_data_value(int type, const void *value, int len)
+{
+	struct nlattr *nest = begin_nest(type);
+
+	add_attr(NFTA_DATA_VALUE, value, len);
+	end_nest(nest);
+}
+
+// Adds the expressions of `meta l4proto <proto> th dport <port>`.
+static void add_match_port(uint8_t proto, uint16_t port)
+{
+	struct nlattr *elem, *data;
+
+	elem = begin_expr("meta", &data);
+	add_be32(NFTA_META_KEY, NFT_META_L4PROTO);
+	add_be32(NFTA_META_DREG, NFT_REG_1);
+	end_expr(elem, data);
+
+	elem = begin_expr("cmp", &data);
+	add_be32(NFTA_CMP_SREG, NFT_REG_1);
+	add_be32(NFTA_CMP_OP, NFT_CMP_EQ);
+	add_data_value(NFTA_CMP_DATA, &proto, sizeof(proto));
+	end_expr(elem, data);
+
+	elem = begin_expr("payload", &data);
+	add_be32(NFTA_PAYLOAD_DREG, NFT_REG_1);
+	add_be32(NFTA_PAYLOAD_BASE, NFT_PAYLOAD_TRANSPORT_HEADER);
+	add_be32(NFTA_PAYLOAD_OFFSET, 2);
+	add_be32(NFTA_PAYLOAD_LEN, 2);
+	end_expr(elem, data);
+
+	port = htons(port);
+	elem = begin_expr("cmp", &data);
+	add_be32(NFTA_CMP_SREG, NFT_REG_1);
+	add_be32(NFTA_CMP_OP, NFT_CMP_EQ);
+	add_data_value(NFTA_CMP_DATA, &port, sizeof(port));
+	end_expr(elem, data);
+}
+
+static void add_verdict(int code)
+{
+	struct nlattr *elem, *data, *imm_data, *verdict;
+
+	elem = begin_expr("immediate", &data);
+	add_be32(NFTA_IMMEDIATE_DREG, NFT_REG_VERDICT);
+	imm_data = begin_nest(NFTA_IMMEDIATE_DATA);
+	verdict = begin_nest(NFTA_DATA_VERDICT);
+	add_be32(NFTA_VERDICT_CODE, code);
+	end_nest(verdict);
+	end_nest(imm_data);
+	end_expr(elem, data);
+}
+
+static void add_reject_tcp(void)
+{
+	struct nlattr *elem, *data;
+
+	elem = begin_expr("reject", &data);
+	add_be32(NFTA_REJECT_TYPE, NFT_REJECT_TCP_RST);
+	end_expr(elem, data);
+}
+
+// Adds the expressions of `dnat ip to 127.0.0.1:<port>`.
+static void add_dnat(uint16_t port)
+{
+	struct nlattr *elem, *data;
+	uint32_t addr = htonl(INADDR_LOOPBACK);
+
+	port = htons(port);
+
+	elem = begin_expr("immediate", &data);
+	add_be32(NFTA_IMMEDIATE_DREG, NFT_REG_1);
+	add_data_value(NFTA_IMMEDIATE_DATA, &addr, sizeof(addr));
+	end_expr(elem, data);
+
+	elem = begin_expr("immediate", &data);
+	add_be32(NFTA_IMMEDIATE_DREG, NFT_REG_2);
+	add_data_value(NFTA_IMMEDIATE_DATA, &port, sizeof(port));
+	end_expr(elem, data);
+
+	elem = begin_expr("nat", &data);
+	add_be32(NFTA_NAT_TYPE, NFT_NAT_DNAT);
+	add_be32(NFTA_NAT_FAMILY, NFPROTO_IPV4);
+	add_be32(NFTA_NAT_REG_ADDR_MIN, NFT_REG_1);
+	add_be32(NFTA_NAT_REG_PROTO_MIN, NFT_REG_2);
+	end_expr(elem, data);
+}
+
+static struct nlattr *begin_rule(const char *chain)
+{
+	begin_nft_msg(NFT_MSG_NEWRULE, NFPROTO_INET,
+		      NLM_F_ACK | NLM_F_CREATE | NLM_F_APPEND);
+	add_str(NFTA_RULE_TABLE, TABLE_NAME);
+	add_str(NFTA_RULE_CHAIN, chain);
+
+	return begin_nest(NFTA_RULE_EXPRESSIONS);
+}
+
+static void end_rule(struct nlattr *exprs)
+{
+	end_nest(exprs);
+	end_msg();
+}
+
+static int get_gen(void)
+{
+	char buf[4096];
+	struct nlmsghdr *nh = (struct nlmsghdr *)buf;
+	struct nlattr *nla;
+	int len;
+
+	reset_msgs();
+	begin_nft_msg(NFT_MSG_GETGEN, AF_UNSPEC, 0);
+	end_msg();
+	if (send_msgs() < 0)
+		return -1;
+
+	len = recv(nft_fd, buf, sizeof(buf), 0);
+	if (len < 0)
+		return -1;
+	if (nh->nlmsg_type != ((NFNL_SUBSYS_NFTABLES << 8) | NFT_MSG_NEWGEN)) {
+		errno = EPROTO;
+		return -1;
+	}
+
+	nla = (struct nlattr *)((char *)NLMSG_DATA(nh) +
+				NLMSG_ALIGN(sizeof(struct nfgenmsg)));
+	if (nla->nla_type != NFTA_GEN_ID) {
+		errno = EPROTO;
+		return -1;
+	}
+
+	return ntohl(*(uint32_t *)((char *)nla + NLA_HDRLEN));
+}
+
+// Dumps the rules and returns the number of rules in the chain.
+static int count_rules(const char *chain)
+{
+	char buf[8192];
+	struct nlmsghdr *nh;
+	struct nlattr *nla;
+	int len, attr_len, count = 0;
+
+	reset_msgs();
+	begin_nft_msg(NFT_MSG_GETRULE, NFPROTO_INET, NLM_F_DUMP);
+	end_msg();
+	if (send_msgs() < 0)
+		return -1;
+
+	for (;;) {
+		len = recv(nft_fd, buf, sizeof(buf), 0);
+		if (len < 0)
+			return -1;
+
+		for (nh = (struct nlmsghdr *)buf; NLMSG_OK(nh, len);
+		     nh = NLMSG_NEXT(nh, len)) {
+			if (nh->nlmsg_type == NLMSG_DONE)
+				return count;
+			if (nh->nlmsg_type !=
+			    ((NFNL_SUBSYS_NFTABLES << 8) | NFT_MSG_NEWRULE))
+				continue;
+
+			attr_len = nh->nlmsg_len -
+				   NLMSG_LENGTH(sizeof(struct nfgenmsg));
+			for (nla = (struct nlattr *)((char *)NLMSG_DATA(nh) +
+						     NLMSG_ALIGN(sizeof(
+							     struct nfgenmsg)));
+			     attr_len >= NLA_HDRLEN &&
+			     nla->nla_len >= NLA_HDRLEN &&
+			     nla->nla_len <= attr_len;
+			     attr_len -= NLA_ALIGN(nla->nla_len),
+			    nla = (struct nlattr *)((char *)nla +
+						    NLA_ALIGN(nla->nla_len))) {
+				if (nla->nla_type == NFTA_RULE_CHAIN &&
+				    strcmp((char *)nla + NLA_HDRLEN, chain) ==
+					    0)
+					++count;
+			}
+		}
+	}
+}
+
+static int poll_in(int fd, int timeout)
+{
+	struct pollfd pfd = { .fd = fd, .events = POLLIN };
+
+	if (poll(&pfd, 1, timeout) < 0)
+		return -1;
+	return pfd.revents & POLLIN;
+}
+
+// Sends a datagram to the port and returns the port that receives it.
+static int udp_ping(int port)
+{
+	int fd, ret;
+	char buf[16];
+	struct sockaddr_in addr = lo_addr;
+	socklen_t addr_len = sizeof(addr);
+
+	fd = socket(AF_INET, SOCK_DGRAM, 0);
+	if (fd < 0)
+		return -1;
+
+	addr.sin_port = htons(port);
+	if (bind(fd, (struct sockaddr *)&addr, sizeof(addr)) < 0)
+		goto out;
+	if (sendto(udp_fd, "ping", 4, 0, (struct sockaddr *)&addr,
+		   sizeof(addr)) < 0)
+		goto out;
+
+	ret = poll_in(fd, 100);
+	if (ret <= 0) {
+		if (ret == 0)
+			errno = EAGAIN;
+		goto out;
+	}
+	if (recvfrom(fd, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+		     &addr_len) < 0)
+		goto out;
+
+	close(fd);
+	return port;
+
+out:
+	close(fd);
+	return -1;
+}
+
+static int tcp_connect(int port)
+{
+	int fd, ret;
+	struct sockaddr_in addr = lo_addr;
+
+	fd = socket(AF_INET, SOCK_STREAM, 0);
+	if (fd < 0)
+		return -1;
+
+	addr.sin_port = htons(port);
+	ret = connect(fd, (struct sockaddr *)&addr, sizeof(addr));
+	close(fd);
+	if (ret < 0)
+		return -1;
+
+	ret = accept(tcp_listen_fd, NULL, NULL);
+	if (ret < 0)
+		return -1;
+	close(ret);
+
+	return 0;
+}
+
+FN_TEST(get_gen)
+{
+	TEST_RES(get_gen(), _ret >= 0);
+}
+END_TEST()
+
+FN_TEST(not_in_batch)
+{
+	// Modifications must be sent in batches.
+	reset_msgs();
+	table_msg(NFT_MSG_NEWTABLE, NLM_F_CREATE);
+	TEST_ERRNO(send_msgs(), EINVAL);
+}
+END_TEST()
+
+FN_TEST(new_ruleset)
+{
+	struct nlattr *exprs;
+	int gen;
+
+	gen = TEST_RES(get_gen(), _ret >= 0);
+
+	begin_batch();
+	table_msg(NFT_MSG_NEWTABLE, NLM_F_CREATE | NLM_F_EXCL);
+	chain_msg(TABLE_NAME, "input", NF_INET_LOCAL_IN, 0, "filter");
+	chain_msg(TABLE_NAME, "output", NF_INET_LOCAL_OUT, -100, "nat");
+
+	exprs = begin_rule("input");
+	add_match_port(IPPROTO_UDP, PORT_DROP);
+	add_verdict(NF_DROP);
+	end_rule(exprs);
+
+	exprs = begin_rule("input");
+	add_match_port(IPPROTO_TCP, PORT_REJECT);
+	add_reject_tcp();
+	end_rule(exprs);
+
+	exprs = begin_rule("output");
+	add_match_port(IPPROTO_UDP, PORT_DNAT);
+	add_dnat(PORT_DNAT_TO);
+	end_rule(exprs);
+	TEST_SUCC(end_batch());
+
+	TEST_RES(get_gen(), _ret == gen + 1);
+	TEST_RES(count_rules("input"), _ret == 2);
+	TEST_RES(count_rules("output"), _ret == 1);
+
+	begin_batch();
+	table_msg(NFT_MSG_NEWTABLE, NLM_F_CREATE | NLM_F_EXCL);
+	TEST_ERRNO(end_batch(), EEXIST);
+}
+END_TEST()
+
+FN_TEST(failed_batch)
+{
+	struct nlattr *exprs, *elem, *data;
+	int gen;
+
+	gen = TEST_RES(get_gen(), _ret >= 0);
+
+	// The whole batch is discarded if one of the requests fails.
+	begin_batch();
+	exprs = begin_rule("input");
+	add_verdict(NF_DROP);
+	end_rule(exprs);
+	exprs = begin_rule("input");
+	elem = begin_expr("nonexistent", &data);
+	end_expr(elem, data);
+	end_rule(exprs);
+	TEST_ERRNO(end_batch(), ENOENT);
+
+	TEST_RES(get_gen(), _ret == gen);
+	TEST_RES(count_rules("input"), _ret == 2);
+
+	begin_batch();
+	chain_msg("nonexistent", "input", NF_INET_LOCAL_IN, 0, "filter");
+	TEST_ERRNO(end_batch(), ENOENT);
+
+	begin_batch();
+	chain_msg(TABLE_NAME, "forward", NF_INET_FORWARD, 0, "nonexistent");
+	TEST_ERRNO(end_batch(), ENOENT);
+}
+END_TEST()
+
+// The requests and the notifications are scoped to the network namespace
+// where the netlink socket is created.
+FN_TEST(other_net_ns)
+{
+	struct sockaddr_nl addr = {
+		.nl_family = AF_NETLINK,
+		.nl_groups = 1 << (NFNLGRP_NFTABLES - 1),
+	};
+	int init_ns_fd, init_fd, other_fd;
+	char buf[4096];
+
+	init_ns_fd = TEST_SUCC(open("/proc/self/ns/net", O_RDONLY));
+	TEST_SUCC(unshare(CLONE_NEWNET));
+	other_fd = TEST_SUCC(socket(AF_NETLINK, SOCK_RAW, NETLINK_NETFILTER));
+	TEST_SUCC(bind(other_fd, (struct sockaddr *)&addr, sizeof(addr)));
+	TEST_SUCC(setns(init_ns_fd, CLONE_NEWNET));
+
+	init_fd = nft_fd;
+	nft_fd = other_fd;
+	TEST_RES(count_rules("input"), _ret == 0);
+	nft_fd = init_fd;
+
+	begin_batch();
+	chain_msg(TABLE_NAME, "forward", NF_INET_FORWARD, 0, "filter");
+	TEST_SUCC(end_batch());
+	TEST_ERRNO(recv(other_fd, buf, sizeof(buf), MSG_DONTWAIT), EAGAIN);
+
+	TEST_SUCC(close(other_fd));
+	TEST_SUCC(close(init_ns_fd));
+}
+END_TEST()
+
+FN_TEST(filter)
+{
+	TEST_RES(udp_ping(PORT_ACCEPT), _ret == PORT_ACCEPT);
+	TEST_ERRNO(udp_ping(PORT_DROP), EAGAIN);
+
+	TEST_ERRNO(tcp_connect(PORT_REJECT), ECONNREFUSED);
+}
+END_TEST()
+
+FN_TEST(dnat)
+{
+	int fd;
+	char buf[16];
+	struct sockaddr_in addr = lo_addr;
+	socklen_t addr_len = sizeof(addr);
+
+	fd = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	addr.sin_port = htons(PORT_DNAT_TO);
+	TEST_SUCC(bind(fd, (struct sockaddr *)&addr, sizeof(addr)));
+
+	// The datagram sent to `PORT_DNAT` is received by `PORT_DNAT_TO`.
+	addr.sin_port = htons(PORT_DNAT);
+	TEST_RES(sendto(udp_fd, "ping", 4, 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == 4);
+	TEST_RES(poll_in(fd, 100), _ret != 0);
+	TEST_RES(recvfrom(fd, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			  &addr_len),
+		 _ret == 4);
+
+	// The reply comes from `PORT_DNAT`.
+	TEST_RES(sendto(fd, "pong", 4, 0, (struct sockaddr *)&addr,
+			sizeof(addr)),
+		 _ret == 4);
+	TEST_RES(poll_in(udp_fd, 100), _ret != 0);
+	TEST_RES(recvfrom(udp_fd, buf, sizeof(buf), 0,
+			  (struct sockaddr *)&addr, &addr_len),
+		 _ret == 4 && addr.sin_port == htons(PORT_DNAT));
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(del_table)
+{
+	begin_batch();
+	table_msg(NFT_MSG_DELTABLE, 0);
+	TEST_SUCC(end_batch());
+
+	begin_batch();
+	table_msg(NFT_MSG_DELTABLE, 0);
+	TEST_ERRNO(end_batch(), ENOENT);
+
+	begin_batch();
+	table_msg(MSG_DESTROYTABLE, 0);
+	TEST_SUCC(end_batch());
+
+	TEST_RES(count_rules("input"), _ret == 0);
+	TEST_RES(udp_ping(PORT_DROP), _ret == PORT_DROP);
+	TEST_SUCC(tcp_connect(PORT_REJECT));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(nft_fd));
+	CHECK(close(udp_fd));
+	CHECK(close(tcp_listen_fd));
+}
+END_SETUP()
@@ -0,0 +1,444 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <linux/filter.h>
+#include <linux/if_ether.h>
+#include <linux/if_packet.h>
+#include <linux/if_tun.h>
+#include <net/if.h>
+#include <net/if_arp.h>
+#include <poll.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <sys/ioctl.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#include "../common/test.h"
+
+#define TAP_NAME "ptest0"
+#define ETH_P_TEST 0x88b5
+
+static int tap_fd;
+static int tap_index;
+static uint8_t tap_addr[ETH_ALEN];
+static int ioctl_fd;
+
+static const uint8_t frame[] = {
+	// Destination address (not the address of the TAP device)
+	0x02, 0x00, 0x00, 0x00, 0x00, 0x01,
+	// Source address
+	0x02, 0x00, 0x00, 0x00, 0x00, 0x02,
+	// EtherType (local experimental)
+	0x88, 0xb5,
+	// Payload
+	'h', 'e', 'l', 'l', 'o'
+};
+
+FN_SETUP(tap)
+{
+	struct ifreq ifr;
+
+	tap_fd = CHECK(open("/dev/net/tun", O_RDWR | O_NONBLOCK));
+
+	memset(&ifr, 0, sizeof(ifr));
+	strcpy(ifr.ifr_name, TAP_NAME);
+	ifr.ifr_flags = IFF_TAP | IFF_NO_PI;
+	CHECK(ioctl(tap_fd, TUNSETIFF, &ifr));
+
+	tap_index = CHECK_WITH(if_nametoindex(TAP_NAME), _ret != 0);
+
+	ioctl_fd = CHECK(socket(AF_INET, SOCK_DGRAM, 0));
+	CHECK(ioctl(ioctl_fd, SIOCGIFHWADDR, &ifr));
+	memcpy(tap_addr, ifr.ifr_hwaddr.sa_data, ETH_ALEN);
+}
+END_SETUP()
+
+static int poll_in(int fd, int timeout)
+{
+	struct pollfd pfd = { .fd = fd, .events = POLLIN };
+
+	if (poll(&pfd, 1, timeout) < 0)
+		return -1;
+	return pfd.revents & POLLIN;
+}
+
+// Creates a packet socket that is bound to the TAP device.
+static int new_bound_socket(int type, int protocol)
+{
+	struct sockaddr_ll addr = {
+		.sll_family = AF_PACKET,
+		.sll_protocol = htons(protocol),
+		.sll_ifindex = tap_index,
+	};
+	int fd, err;
+
+	fd = socket(AF_PACKET, type | SOCK_NONBLOCK, htons(protocol));
+	if (fd < 0)
+		return -1;
+
+	if (bind(fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+		err = errno;
+		close(fd);
+		errno = err;
+		return -1;
+	}
+
+	return fd;
+}
+
+static int get_if_flags(void)
+{
+	struct ifreq ifr;
+
+	memset(&ifr, 0, sizeof(ifr));
+	strcpy(ifr.ifr_name, TAP_NAME);
+	if (ioctl(ioctl_fd, SIOCGIFFLAGS, &ifr) < 0)
+		return -1;
+	return ifr.ifr_flags;
+}
+
+FN_TEST(bind_and_getsockname)
+{
+	int fd;
+	struct sockaddr_ll addr;
+	socklen_t addrlen;
+
+	fd = TEST_SUCC(socket(AF_PACKET, SOCK_RAW, 0));
+
+	addrlen = sizeof(addr);
+	TEST_RES(getsockname(fd, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == offsetof(struct sockaddr_ll, sll_addr) &&
+			 addr.sll_family == AF_PACKET &&
+			 addr.sll_protocol == 0 && addr.sll_ifindex == 0 &&
+			 addr.sll_halen == 0);
+
+	memset(&addr, 0, sizeof(addr));
+	addr.sll_family = AF_PACKET;
+	addr.sll_ifindex = 0x7fffffff;
+	TEST_ERRNO(bind(fd, (struct sockaddr *)&addr, sizeof(addr)), ENODEV);
+	TEST_ERRNO(bind(fd, (struct sockaddr *)&addr, 8), EINVAL);
+
+	addr.sll_protocol = htons(ETH_P_TEST);
+	addr.sll_ifindex = tap_index;
+	TEST_SUCC(bind(fd, (struct sockaddr *)&addr, sizeof(addr)));
+
+	addrlen = sizeof(addr);
+	TEST_RES(getsockname(fd, (struct sockaddr *)&addr, &addrlen),
+		 addrlen == offsetof(struct sockaddr_ll, sll_addr) + ETH_ALEN &&
+			 addr.sll_protocol == htons(ETH_P_TEST) &&
+			 addr.sll_ifindex == tap_index &&
+			 addr.sll_hatype == ARPHRD_ETHER &&
+			 addr.sll_halen == ETH_ALEN &&
+			 memcmp(addr.sll_addr, tap_addr, ETH_ALEN) == 0);
+
+	// A zero protocol keeps the current protocol.
+	addr.sll_protocol = 0;
+	TEST_SUCC(bind(fd, (struct sockaddr *)&addr, sizeof(addr)));
+	addrlen = sizeof(addr);
+	TEST_RES(getsockname(fd, (struct sockaddr *)&addr, &addrlen),
+		 addr.sll_protocol == htons(ETH_P_TEST));
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(recv_frames)
+{
+	int raw_fd, dgram_fd, other_fd;
+	uint8_t buf[64];
+	struct sockaddr_ll addr;
+	socklen_t addrlen;
+	struct tpacket_stats stats;
+	socklen_t stats_len;
+
+	raw_fd = TEST_SUCC(new_bound_socket(SOCK_RAW, ETH_P_TEST));
+	dgram_fd = TEST_SUCC(new_bound_socket(SOCK_DGRAM, ETH_P_TEST));
+	other_fd = TEST_SUCC(new_bound_socket(SOCK_RAW, ETH_P_IP));
+
+	TEST_RES(write(tap_fd, frame, sizeof(frame)), _ret == sizeof(frame));
+
+	// SOCK_RAW sockets receive the frame with its link-layer header.
+	TEST_RES(poll_in(raw_fd, 1000), _ret != 0);
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(raw_fd, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			  &addrlen),
+		 _ret == sizeof(frame) &&
+			 memcmp(buf, frame, sizeof(frame)) == 0 &&
+			 addr.sll_family == AF_PACKET &&
+			 addr.sll_protocol == htons(ETH_P_TEST) &&
+			 addr.sll_ifindex == tap_index &&
+			 addr.sll_pkttype == PACKET_OTHERHOST &&
+			 addr.sll_halen == ETH_ALEN &&
+			 memcmp(addr.sll_addr, &frame[ETH_ALEN],
+				ETH_ALEN) == 0);
+
+	// SOCK_DGRAM sockets receive the frame without its link-layer header.
+	TEST_RES(poll_in(dgram_fd, 1000), _ret != 0);
+	TEST_RES(recv(dgram_fd, buf, sizeof(buf), 0),
+		 _ret == sizeof(frame) - ETH_HLEN &&
+			 memcmp(buf, &frame[ETH_HLEN], _ret) == 0);
+
+	// Sockets for other protocols do not receive the frame.
+	TEST_ERRNO(recv(other_fd, buf, sizeof(buf), 0), EAGAIN);
+
+	// The frame is truncated if the buffer is too small.
+	TEST_RES(write(tap_fd, frame, sizeof(frame)), _ret == sizeof(frame));
+	TEST_RES(poll_in(raw_fd, 1000), _ret != 0);
+	TEST_RES(recv(raw_fd, buf, 4, MSG_PEEK), _ret == 4);
+	TEST_RES(recv(raw_fd, buf, 4, MSG_TRUNC), _ret == sizeof(frame));
+	TEST_ERRNO(recv(raw_fd, buf, sizeof(buf), 0), EAGAIN);
+
+	stats_len = sizeof(stats);
+	TEST_RES(getsockopt(raw_fd, SOL_PACKET, PACKET_STATISTICS, &stats,
+			    &stats_len),
+		 stats_len == sizeof(stats) && stats.tp_packets == 2 &&
+			 stats.tp_drops == 0);
+	// The statistics are reset after being read.
+	TEST_RES(getsockopt(raw_fd, SOL_PACKET, PACKET_STATISTICS, &stats,
+			    &stats_len),
+		 stats.tp_packets == 0 && stats.tp_drops == 0);
+
+	TEST_SUCC(close(other_fd));
+	TEST_SUCC(close(dgram_fd));
+	TEST_SUCC(close(raw_fd));
+}
+END_TEST()
+
+FN_TEST(send_frames)
+{
+	int raw_fd, dgram_fd, all_fd;
+	uint8_t buf[64];
+	struct sockaddr_ll addr;
+	socklen_t addrlen;
+	// Accepts only the frames of the test protocol.
+	struct sock_filter insns[] = {
+		BPF_STMT(BPF_LD | BPF_H | BPF_ABS, 12),
+		BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, ETH_P_TEST, 0, 1),
+		BPF_STMT(BPF_RET | BPF_K, 0xffff),
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	struct sock_fprog fprog = { .len = 4, .filter = insns };
+
+	raw_fd = TEST_SUCC(new_bound_socket(SOCK_RAW, ETH_P_TEST));
+	dgram_fd = TEST_SUCC(new_bound_socket(SOCK_DGRAM, ETH_P_TEST));
+	all_fd = TEST_SUCC(new_bound_socket(SOCK_RAW, ETH_P_ALL));
+	TEST_SUCC(setsockopt(all_fd, SOL_SOCKET, SO_ATTACH_FILTER, &fprog,
+			     sizeof(fprog)));
+
+	// SOCK_RAW sockets send the frame as is.
+	TEST_RES(send(raw_fd, frame, sizeof(frame), 0), _ret == sizeof(frame));
+	TEST_RES(read(tap_fd, buf, sizeof(buf)),
+		 _ret == sizeof(frame) &&
+			 memcmp(buf, frame, sizeof(frame)) == 0);
+
+	// The sockets for all protocols see the outgoing frame.
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(all_fd, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			  &addrlen),
+		 _ret == sizeof(frame) &&
+			 addr.sll_pkttype == PACKET_OUTGOING);
+	// The sender itself and the sockets for a specific protocol do not.
+	TEST_ERRNO(recv(raw_fd, buf, sizeof(buf), 0), EAGAIN);
+	TEST_ERRNO(recv(dgram_fd, buf, sizeof(buf), 0), EAGAIN);
+
+	// SOCK_DGRAM sockets build the link-layer header.
+	memset(&addr, 0, sizeof(addr));
+	addr.sll_family = AF_PACKET;
+	addr.sll_protocol = htons(ETH_P_TEST);
+	addr.sll_ifindex = tap_index;
+	addr.sll_halen = ETH_ALEN;
+	memcpy(addr.sll_addr, frame, ETH_ALEN);
+	TEST_RES(sendto(dgram_fd, &frame[ETH_HLEN], sizeof(frame) - ETH_HLEN,
+			0, (struct sockaddr *)&addr, sizeof(addr)),
+		 _ret == sizeof(frame) - ETH_HLEN);
+	TEST_RES(read(tap_fd, buf, sizeof(buf)),
+		 _ret == sizeof(frame) && memcmp(buf, frame, ETH_ALEN) == 0 &&
+			 memcmp(&buf[ETH_ALEN], tap_addr, ETH_ALEN) == 0 &&
+			 memcmp(&buf[2 * ETH_ALEN], &frame[2 * ETH_ALEN],
+				sizeof(frame) - 2 * ETH_ALEN) == 0);
+	TEST_RES(recv(all_fd, buf, sizeof(buf), 0), _ret == sizeof(frame));
+
+	TEST_SUCC(close(all_fd));
+	TEST_SUCC(close(dgram_fd));
+	TEST_SUCC(close(raw_fd));
+}
+END_TEST()
+
+FN_TEST(send_errors)
+{
+	int fd;
+	uint8_t buf[2048];
+	struct sockaddr_ll addr;
+
+	fd = TEST_SUCC(socket(AF_PACKET, SOCK_RAW, htons(ETH_P_TEST)));
+
+	// The socket is not bound to any interface.
+	TEST_ERRNO(send(fd, frame, sizeof(frame), 0), ENXIO);
+
+	memset(&addr, 0, sizeof(addr));
+	addr.sll_family = AF_PACKET;
+	addr.sll_ifindex = 0x7fffffff;
+	TEST_ERRNO(sendto(fd, frame, sizeof(frame), 0, (struct sockaddr *)&addr,
+			  sizeof(addr)),
+		   ENXIO);
+
+	addr.sll_ifindex = tap_index;
+	TEST_ERRNO(sendto(fd, frame, ETH_HLEN - 1, 0, (struct sockaddr *)&addr,
+			  sizeof(addr)),
+		   EINVAL);
+	memset(buf, 0, sizeof(buf));
+	TEST_ERRNO(sendto(fd, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			  sizeof(addr)),
+		   EMSGSIZE);
+
+	TEST_SUCC(close(fd));
+
+	fd = TEST_SUCC(new_bound_socket(SOCK_DGRAM, ETH_P_TEST));
+
+	// The destination address is required to build the link-layer header.
+	TEST_ERRNO(send(fd, frame, sizeof(frame), 0), EINVAL);
+	TEST_ERRNO(sendto(fd, frame, sizeof(frame), 0, (struct sockaddr *)&addr,
+			  sizeof(addr)),
+		   EINVAL);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(socket_filter)
+{
+	int fd;
+	uint8_t buf[64];
+	struct sock_filter drop_all[] = {
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	struct sock_filter snap_six[] = {
+		BPF_STMT(BPF_RET | BPF_K, 6),
+	};
+	struct sock_filter no_ret[] = {
+		BPF_STMT(BPF_LD | BPF_W | BPF_LEN, 0),
+	};
+	struct sock_filter bad_jump[] = {
+		BPF_JUMP(BPF_JMP | BPF_JA, 1, 0, 0),
+		BPF_STMT(BPF_RET | BPF_K, 0),
+	};
+	struct sock_fprog fprog;
+
+	fd = TEST_SUCC(new_bound_socket(SOCK_RAW, ETH_P_TEST));
+
+	// Invalid filters are rejected.
+	fprog.len = 0;
+	fprog.filter = drop_all;
+	TEST_ERRNO(setsockopt(fd, SOL_SOCKET, SO_ATTACH_FILTER, &fprog,
+			      sizeof(fprog)),
+		   EINVAL);
+	fprog.len = 1;
+	fprog.filter = no_ret;
+	TEST_ERRNO(setsockopt(fd, SOL_SOCKET, SO_ATTACH_FILTER, &fprog,
+			      sizeof(fprog)),
+		   EINVAL);
+	fprog.len = 2;
+	fprog.filter = bad_jump;
+	TEST_ERRNO(setsockopt(fd, SOL_SOCKET, SO_ATTACH_FILTER, &fprog,
+			      sizeof(fprog)),
+		   EINVAL);
+	TEST_ERRNO(setsockopt(fd, SOL_SOCKET, SO_DETACH_FILTER, NULL, 0),
+		   ENOENT);
+
+	// A filter returning zero drops the frames.
+	fprog.len = 1;
+	fprog.filter = drop_all;
+	TEST_SUCC(setsockopt(fd, SOL_SOCKET, SO_ATTACH_FILTER, &fprog,
+			     sizeof(fprog)));
+	TEST_RES(write(tap_fd, frame, sizeof(frame)), _ret == sizeof(frame));
+	TEST_RES(poll_in(fd, 100), _ret == 0);
+
+	// A filter returning a non-zero value truncates the frames.
+	fprog.filter = snap_six;
+	TEST_SUCC(setsockopt(fd, SOL_SOCKET, SO_ATTACH_FILTER, &fprog,
+			     sizeof(fprog)));
+	TEST_RES(write(tap_fd, frame, sizeof(frame)), _ret == sizeof(frame));
+	TEST_RES(poll_in(fd, 1000), _ret != 0);
+	TEST_RES(recv(fd, buf, sizeof(buf), MSG_TRUNC),
+		 _ret == 6 && memcmp(buf, frame, 6) == 0);
+
+	TEST_SUCC(setsockopt(fd, SOL_SOCKET, SO_DETACH_FILTER, NULL, 0));
+	TEST_ERRNO(setsockopt(fd, SOL_SOCKET, SO_DETACH_FILTER, NULL, 0),
+		   ENOENT);
+	TEST_RES(write(tap_fd, frame, sizeof(frame)), _ret == sizeof(frame));
+	TEST_RES(poll_in(fd, 1000), _ret != 0);
+	TEST_RES(recv(fd, buf, sizeof(buf), 0), _ret == sizeof(frame));
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(membership)
+{
+	int fd;
+	struct packet_mreq mreq;
+
+	fd = TEST_SUCC(socket(AF_PACKET, SOCK_RAW, 0));
+	TEST_RES(get_if_flags(), (_ret & IFF_PROMISC) == 0);
+
+	memset(&mreq, 0, sizeof(mreq));
+	mreq.mr_ifindex = tap_index;
+	mreq.mr_type = PACKET_MR_PROMISC;
+
+	// The interface stays in promiscuous mode until all memberships are
+	// dropped.
+	TEST_SUCC(setsockopt(fd, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_SUCC(setsockopt(fd, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_RES(get_if_flags(), _ret & IFF_PROMISC);
+	TEST_SUCC(setsockopt(fd, SOL_PACKET, PACKET_DROP_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_RES(get_if_flags(), _ret & IFF_PROMISC);
+	TEST_SUCC(setsockopt(fd, SOL_PACKET, PACKET_DROP_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_RES(get_if_flags(), (_ret & IFF_PROMISC) == 0);
+	TEST_ERRNO(setsockopt(fd, SOL_PACKET, PACKET_DROP_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EADDRNOTAVAIL);
+
+	// Closing the socket drops its memberships.
+	TEST_SUCC(setsockopt(fd, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			     sizeof(mreq)));
+	TEST_RES(get_if_flags(), _ret & IFF_PROMISC);
+	TEST_SUCC(close(fd));
+	TEST_RES(get_if_flags(), (_ret & IFF_PROMISC) == 0);
+
+	fd = TEST_SUCC(socket(AF_PACKET, SOCK_RAW, 0));
+
+	mreq.mr_ifindex = 0x7fffffff;
+	TEST_ERRNO(setsockopt(fd, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   ENODEV);
+	mreq.mr_ifindex = tap_index;
+	mreq.mr_type = 0xff;
+	TEST_ERRNO(setsockopt(fd, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EINVAL);
+	mreq.mr_type = PACKET_MR_MULTICAST;
+	mreq.mr_alen = ETH_ALEN + 1;
+	TEST_ERRNO(setsockopt(fd, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq)),
+		   EINVAL);
+	TEST_ERRNO(setsockopt(fd, SOL_PACKET, PACKET_ADD_MEMBERSHIP, &mreq,
+			      sizeof(mreq) - 1),
+		   EINVAL);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(ioctl_fd));
+	CHECK(close(tap_fd));
+	CHECK_WITH(if_nametoindex(TAP_NAME), _ret == 0 && errno == ENODEV);
+}
+END_SETUP()
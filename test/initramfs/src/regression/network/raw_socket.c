@@ -0,0 +1,296 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <netinet/ip.h>
+#include <netinet/ip_icmp.h>
+#include <poll.h>
+#include <stdint.h>
+#include <string.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#include "../common/file_util.h"
+#include "../common/test.h"
+
+#define PING_GROUP_RANGE "/proc/sys/net/ipv4/ping_group_range"
+#define IPPROTO_TEST 253
+#define PING_IDENT 0x1234
+
+static struct sockaddr_in lo_addr;
+
+struct echo_msg {
+	struct icmphdr hdr;
+	char data[4];
+};
+
+FN_SETUP(lo_addr)
+{
+	lo_addr.sin_family = AF_INET;
+	lo_addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+}
+END_SETUP()
+
+static int poll_in(int fd, int timeout)
+{
+	struct pollfd pfd = { .fd = fd, .events = POLLIN };
+
+	if (poll(&pfd, 1, timeout) < 0)
+		return -1;
+	return pfd.revents & POLLIN;
+}
+
+static uint16_t checksum(const void *data, size_t len)
+{
+	const uint8_t *bytes = data;
+	uint32_t sum = 0;
+	size_t i;
+
+	for (i = 0; i + 1 < len; i += 2)
+		sum += (bytes[i] << 8) | bytes[i + 1];
+	if (len % 2 != 0)
+		sum += bytes[len - 1] << 8;
+	while (sum >> 16)
+		sum = (sum & 0xffff) + (sum >> 16);
+
+	return htons(~sum);
+}
+
+static struct echo_msg new_echo_msg(uint8_t type, uint16_t seq)
+{
+	struct echo_msg msg = {
+		.hdr = { .type = type, .code = 0 },
+		.data = { 'p', 'i', 'n', 'g' },
+	};
+
+	msg.hdr.un.echo.id = htons(PING_IDENT);
+	msg.hdr.un.echo.sequence = htons(seq);
+	msg.hdr.checksum = checksum(&msg, sizeof(msg));
+
+	return msg;
+}
+
+static int new_bound_socket(int type, int protocol, uint16_t port)
+{
+	struct sockaddr_in addr = lo_addr;
+	int fd, err;
+
+	fd = socket(AF_INET, type | SOCK_NONBLOCK, protocol);
+	if (fd < 0)
+		return -1;
+
+	addr.sin_port = htons(port);
+	if (bind(fd, (struct sockaddr *)&addr, sizeof(addr)) < 0) {
+		err = errno;
+		close(fd);
+		errno = err;
+		return -1;
+	}
+
+	return fd;
+}
+
+FN_TEST(raw_socket_protocols)
+{
+	int fd, val;
+	socklen_t len;
+
+	// Raw sockets cannot receive all IP protocols.
+	TEST_ERRNO(socket(AF_INET, SOCK_RAW, 0), EPROTONOSUPPORT);
+	TEST_ERRNO(socket(AF_INET6, SOCK_RAW, 0), EPROTONOSUPPORT);
+
+	fd = TEST_SUCC(socket(AF_INET, SOCK_RAW, IPPROTO_ICMP));
+	len = sizeof(val);
+	TEST_RES(getsockopt(fd, SOL_SOCKET, SO_TYPE, &val, &len),
+		 len == sizeof(val) && val == SOCK_RAW);
+	TEST_RES(getsockopt(fd, SOL_IP, IP_HDRINCL, &val, &len),
+		 len == sizeof(val) && val == 0);
+	TEST_SUCC(close(fd));
+
+	// `IPPROTO_RAW` implies `IP_HDRINCL`.
+	fd = TEST_SUCC(socket(AF_INET, SOCK_RAW, IPPROTO_RAW));
+	TEST_RES(getsockopt(fd, SOL_IP, IP_HDRINCL, &val, &len),
+		 len == sizeof(val) && val == 1);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(ping_group_range)
+{
+	int fd;
+
+	// By default, no groups can create ICMP echo sockets.
+	TEST_RES(read_file(PING_GROUP_RANGE), strcmp(file_buf, "1\t0\n") == 0);
+	TEST_ERRNO(socket(AF_INET, SOCK_DGRAM, IPPROTO_ICMP), EACCES);
+	TEST_ERRNO(socket(AF_INET6, SOCK_DGRAM, IPPROTO_ICMPV6), EACCES);
+
+	TEST_ERRNO(write_file(PING_GROUP_RANGE, "0"), EINVAL);
+	TEST_ERRNO(write_file(PING_GROUP_RANGE, "-1 0"), EINVAL);
+	TEST_ERRNO(write_file(PING_GROUP_RANGE, "0 2147483648"), EINVAL);
+	TEST_RES(read_file(PING_GROUP_RANGE), strcmp(file_buf, "1\t0\n") == 0);
+
+	// An inverted range is reset to the default range.
+	TEST_RES(write_file(PING_GROUP_RANGE, "10 5"), _ret == 4);
+	TEST_RES(read_file(PING_GROUP_RANGE), strcmp(file_buf, "1\t0\n") == 0);
+
+	TEST_RES(write_file(PING_GROUP_RANGE, "0 2147483647\n"), _ret == 13);
+	TEST_RES(read_file(PING_GROUP_RANGE),
+		 strcmp(file_buf, "0\t2147483647\n") == 0);
+	fd = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, IPPROTO_ICMP));
+	TEST_SUCC(close(fd));
+	fd = TEST_SUCC(socket(AF_INET6, SOCK_DGRAM, IPPROTO_ICMPV6));
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(ping_loopback)
+{
+	int fd, val;
+	struct echo_msg msg, reply;
+	struct sockaddr_in addr;
+	socklen_t addrlen;
+
+	fd = TEST_SUCC(new_bound_socket(SOCK_DGRAM, IPPROTO_ICMP, PING_IDENT));
+
+	val = 1;
+	TEST_ERRNO(setsockopt(fd, SOL_IP, IP_HDRINCL, &val, sizeof(val)),
+		   ENOPROTOOPT);
+
+	addrlen = sizeof(addr);
+	TEST_RES(getsockname(fd, (struct sockaddr *)&addr, &addrlen),
+		 addr.sin_port == htons(PING_IDENT) &&
+			 addr.sin_addr.s_addr == htonl(INADDR_LOOPBACK));
+
+	// Only echo requests with valid lengths can be sent.
+	msg = new_echo_msg(ICMP_ECHOREPLY, 1);
+	TEST_ERRNO(sendto(fd, &msg, sizeof(msg), 0, (struct sockaddr *)&lo_addr,
+			  sizeof(lo_addr)),
+		   EINVAL);
+	msg = new_echo_msg(ICMP_ECHO, 1);
+	TEST_ERRNO(sendto(fd, &msg, 4, 0, (struct sockaddr *)&lo_addr,
+			  sizeof(lo_addr)),
+		   EINVAL);
+
+	// The identifier and the checksum are filled in by the kernel.
+	msg.hdr.un.echo.id = 0;
+	msg.hdr.checksum = 0;
+	TEST_RES(sendto(fd, &msg, sizeof(msg), 0, (struct sockaddr *)&lo_addr,
+			sizeof(lo_addr)),
+		 _ret == sizeof(msg));
+
+	TEST_RES(poll_in(fd, 1000), _ret != 0);
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(fd, &reply, sizeof(reply), 0,
+			  (struct sockaddr *)&addr, &addrlen),
+		 _ret == sizeof(reply) && reply.hdr.type == ICMP_ECHOREPLY &&
+			 reply.hdr.code == 0 &&
+			 reply.hdr.un.echo.id == htons(PING_IDENT) &&
+			 reply.hdr.un.echo.sequence == htons(1) &&
+			 checksum(&reply, sizeof(reply)) == 0 &&
+			 memcmp(reply.data, msg.data, sizeof(msg.data)) == 0 &&
+			 addr.sin_family == AF_INET && addr.sin_port == 0 &&
+			 addr.sin_addr.s_addr == htonl(INADDR_LOOPBACK));
+	TEST_ERRNO(recv(fd, &reply, sizeof(reply), 0), EAGAIN);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(raw_icmp_loopback)
+{
+	int fd;
+	struct echo_msg msg;
+	uint8_t buf[64];
+	struct iphdr *ip = (struct iphdr *)buf;
+	struct icmphdr *icmp = (struct icmphdr *)&buf[sizeof(struct iphdr)];
+	struct sockaddr_in addr;
+	socklen_t addrlen;
+
+	fd = TEST_SUCC(new_bound_socket(SOCK_RAW, IPPROTO_ICMP, 0));
+
+	msg = new_echo_msg(ICMP_ECHO, 2);
+	TEST_RES(sendto(fd, &msg, sizeof(msg), 0, (struct sockaddr *)&lo_addr,
+			sizeof(lo_addr)),
+		 _ret == sizeof(msg));
+
+	// IPv4 raw sockets receive the packets with their IP headers, including
+	// the echo request sent by the socket itself and the echo reply.
+	TEST_RES(poll_in(fd, 1000), _ret != 0);
+	addrlen = sizeof(addr);
+	TEST_RES(recvfrom(fd, buf, sizeof(buf), 0, (struct sockaddr *)&addr,
+			  &addrlen),
+		 _ret == sizeof(struct iphdr) + sizeof(msg) && ip->ihl == 5 &&
+			 ip->protocol == IPPROTO_ICMP &&
+			 ntohs(ip->tot_len) == _ret &&
+			 icmp->type == ICMP_ECHO &&
+			 addr.sin_port == 0 &&
+			 addr.sin_addr.s_addr == htonl(INADDR_LOOPBACK));
+
+	TEST_RES(poll_in(fd, 1000), _ret != 0);
+	TEST_RES(recv(fd, buf, sizeof(buf), 0),
+		 _ret == sizeof(struct iphdr) + sizeof(msg) &&
+			 ip->protocol == IPPROTO_ICMP &&
+			 icmp->type == ICMP_ECHOREPLY &&
+			 icmp->un.echo.id == htons(PING_IDENT) &&
+			 icmp->un.echo.sequence == htons(2));
+	TEST_ERRNO(recv(fd, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(raw_hdrincl)
+{
+	int send_fd, recv_fd, other_fd;
+	uint8_t packet[sizeof(struct iphdr) + 5];
+	struct iphdr *ip = (struct iphdr *)packet;
+	uint8_t buf[64];
+	struct iphdr *recv_ip = (struct iphdr *)buf;
+
+	send_fd = TEST_SUCC(socket(AF_INET, SOCK_RAW, IPPROTO_RAW));
+	recv_fd = TEST_SUCC(new_bound_socket(SOCK_RAW, IPPROTO_TEST, 0));
+	other_fd = TEST_SUCC(new_bound_socket(SOCK_RAW, IPPROTO_UDP, 0));
+
+	// The total length and the header checksum are filled in by the kernel.
+	memset(packet, 0, sizeof(packet));
+	ip->version = 4;
+	ip->ihl = 5;
+	ip->ttl = 64;
+	ip->protocol = IPPROTO_TEST;
+	ip->saddr = htonl(INADDR_LOOPBACK);
+	ip->daddr = htonl(INADDR_LOOPBACK);
+	memcpy(&packet[sizeof(struct iphdr)], "hello", 5);
+
+	// The packet must contain a valid IP header.
+	TEST_ERRNO(sendto(send_fd, packet, 8, 0, (struct sockaddr *)&lo_addr,
+			  sizeof(lo_addr)),
+		   EINVAL);
+
+	TEST_RES(sendto(send_fd, packet, sizeof(packet), 0,
+			(struct sockaddr *)&lo_addr, sizeof(lo_addr)),
+		 _ret == sizeof(packet));
+
+	TEST_RES(poll_in(recv_fd, 1000), _ret != 0);
+	TEST_RES(recv(recv_fd, buf, sizeof(buf), 0),
+		 _ret == sizeof(packet) &&
+			 ntohs(recv_ip->tot_len) == sizeof(packet) &&
+			 recv_ip->protocol == IPPROTO_TEST &&
+			 checksum(buf, sizeof(struct iphdr)) == 0 &&
+			 memcmp(&buf[sizeof(struct iphdr)], "hello", 5) == 0);
+
+	// Raw sockets for other protocols do not receive the packet.
+	TEST_ERRNO(recv(other_fd, buf, sizeof(buf), 0), EAGAIN);
+
+	TEST_SUCC(close(send_fd));
+	TEST_SUCC(close(recv_fd));
+	TEST_SUCC(close(other_fd));
+}
+END_TEST()
+
+FN_TEST(ping_group_range_reset)
+{
+	TEST_RES(write_file(PING_GROUP_RANGE, "1 0"), _ret == 3);
+	TEST_RES(read_file(PING_GROUP_RANGE), strcmp(file_buf, "1\t0\n") == 0);
+}
+END_TEST()
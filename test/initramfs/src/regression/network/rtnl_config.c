@@ -0,0 +1,429 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <linux/if_link.h>
+#include <linux/rtnetlink.h>
+#include <linux/veth.h>
+#include <net/if.h>
+#include <sched.h>
+#include <stdint.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#include "../common/test.h"
+
+#define VETH_NAME "vcfg0"
+#define PEER_NAME "vcfg1"
+
+struct rtnl_req {
+	struct nlmsghdr nh;
+	union {
+		struct ifinfomsg ifi;
+		struct ifaddrmsg ifa;
+		struct rtmsg rtm;
+	};
+	char attrs[256];
+};
+
+static int rtnl_fd;
+static int monitor_fd;
+static unsigned int rtnl_seq;
+
+static int veth_index;
+static int peer_index;
+
+FN_SETUP(rtnl)
+{
+	struct sockaddr_nl addr = {
+		.nl_family = AF_NETLINK,
+		.nl_groups = RTMGRP_LINK | RTMGRP_IPV4_IFADDR |
+			     RTMGRP_IPV4_ROUTE,
+	};
+
+	rtnl_fd = CHECK(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+
+	monitor_fd = CHECK(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+	CHECK(bind(monitor_fd, (struct sockaddr *)&addr, sizeof(addr)));
+}
+END_SETUP()
+
+static struct rtattr *add_attr(struct nlmsghdr *nh, int type,
+			       const void *data, int len)
+{
+	struct rtattr *rta =
+		(struct rtattr *)((char *)nh + NLMSG_ALIGN(nh->nlmsg_len));
+
+	rta->rta_type = type;
+	rta->rta_len = RTA_LENGTH(len);
+	if (len > 0)
+		memcpy(RTA_DATA(rta), data, len);
+	nh->nlmsg_len = NLMSG_ALIGN(nh->nlmsg_len) + RTA_ALIGN(rta->rta_len);
+
+	return rta;
+}
+
+static void end_nest(struct nlmsghdr *nh, struct rtattr *nest)
+{
+	nest->rta_len = (char *)nh + nh->nlmsg_len - (char *)nest;
+}
+
+static void init_req(struct rtnl_req *req, int type, int flags, int body_len)
+{
+	memset(req, 0, sizeof(*req));
+	req->nh.nlmsg_len = NLMSG_LENGTH(body_len);
+	req->nh.nlmsg_type = type;
+	req->nh.nlmsg_flags = NLM_F_REQUEST | flags;
+	req->nh.nlmsg_seq = ++rtnl_seq;
+}
+
+// Sends the request and returns the error code in the acknowledgment.
+static int rtnl_talk(struct rtnl_req *req)
+{
+	char buf[4096];
+	struct nlmsghdr *nh = (struct nlmsghdr *)buf;
+	struct nlmsgerr *err = NLMSG_DATA(nh);
+
+	req->nh.nlmsg_flags |= NLM_F_ACK;
+
+	if (send(rtnl_fd, req, req->nh.nlmsg_len, 0) < 0)
+		return -1;
+	if (recv(rtnl_fd, buf, sizeof(buf), 0) < 0)
+		return -1;
+
+	if (nh->nlmsg_type != NLMSG_ERROR || nh->nlmsg_seq != rtnl_seq) {
+		errno = EPROTO;
+		return -1;
+	}
+	if (err->error != 0) {
+		errno = -err->error;
+		return -1;
+	}
+
+	return 0;
+}
+
+// Drains the notifications and returns whether one of them has the type and
+// the interface index. The index is ignored for route notifications.
+static int has_notification(int type, int index)
+{
+	char buf[4096];
+	struct nlmsghdr *nh;
+	int len, found = 0, msg_index;
+
+	while ((len = recv(monitor_fd, buf, sizeof(buf), MSG_DONTWAIT)) > 0) {
+		for (nh = (struct nlmsghdr *)buf; NLMSG_OK(nh, len);
+		     nh = NLMSG_NEXT(nh, len)) {
+			if (nh->nlmsg_type != type)
+				continue;
+
+			switch (type) {
+			case RTM_NEWLINK:
+			case RTM_DELLINK:
+				msg_index = ((struct ifinfomsg *)NLMSG_DATA(nh))
+						    ->ifi_index;
+				break;
+			case RTM_NEWADDR:
+			case RTM_DELADDR:
+				msg_index = ((struct ifaddrmsg *)NLMSG_DATA(nh))
+						    ->ifa_index;
+				break;
+			default:
+				msg_index = index;
+				break;
+			}
+			if (msg_index == index)
+				found = 1;
+		}
+	}
+
+	return found;
+}
+
+static int set_link_flags(int type, int index, unsigned int flags,
+			  unsigned int change)
+{
+	struct rtnl_req req;
+
+	init_req(&req, type, 0, sizeof(req.ifi));
+	req.ifi.ifi_family = AF_UNSPEC;
+	req.ifi.ifi_index = index;
+	req.ifi.ifi_flags = flags;
+	req.ifi.ifi_change = change;
+
+	return rtnl_talk(&req);
+}
+
+static int get_link_flags(int index)
+{
+	struct rtnl_req req;
+	char buf[4096];
+	struct nlmsghdr *nh = (struct nlmsghdr *)buf;
+
+	init_req(&req, RTM_GETLINK, 0, sizeof(req.ifi));
+	req.ifi.ifi_family = AF_UNSPEC;
+	req.ifi.ifi_index = index;
+
+	if (send(rtnl_fd, &req, req.nh.nlmsg_len, 0) < 0)
+		return -1;
+	if (recv(rtnl_fd, buf, sizeof(buf), 0) < 0)
+		return -1;
+
+	if (nh->nlmsg_type != RTM_NEWLINK) {
+		errno = EPROTO;
+		return -1;
+	}
+
+	return ((struct ifinfomsg *)NLMSG_DATA(nh))->ifi_flags;
+}
+
+static int modify_addr(int type, int flags, int index, const char *addr,
+		       int prefix_len)
+{
+	struct rtnl_req req;
+	struct in_addr in;
+
+	inet_pton(AF_INET, addr, &in);
+
+	init_req(&req, type, flags, sizeof(req.ifa));
+	req.ifa.ifa_family = AF_INET;
+	req.ifa.ifa_prefixlen = prefix_len;
+	req.ifa.ifa_index = index;
+	add_attr(&req.nh, IFA_LOCAL, &in, sizeof(in));
+	add_attr(&req.nh, IFA_ADDRESS, &in, sizeof(in));
+
+	return rtnl_talk(&req);
+}
+
+static int modify_route(int type, int flags, const char *dst, int dst_len,
+			const char *gateway)
+{
+	struct rtnl_req req;
+	struct in_addr in;
+
+	init_req(&req, type, flags, sizeof(req.rtm));
+	req.rtm.rtm_family = AF_INET;
+	req.rtm.rtm_dst_len = dst_len;
+	req.rtm.rtm_table = RT_TABLE_MAIN;
+	if (type == RTM_NEWROUTE) {
+		req.rtm.rtm_protocol = RTPROT_BOOT;
+		req.rtm.rtm_scope = RT_SCOPE_UNIVERSE;
+		req.rtm.rtm_type = RTN_UNICAST;
+	} else {
+		req.rtm.rtm_scope = RT_SCOPE_NOWHERE;
+	}
+
+	inet_pton(AF_INET, dst, &in);
+	add_attr(&req.nh, RTA_DST, &in, sizeof(in));
+	inet_pton(AF_INET, gateway, &in);
+	add_attr(&req.nh, RTA_GATEWAY, &in, sizeof(in));
+
+	return rtnl_talk(&req);
+}
+
+// Dumps the IPv4 routes and returns whether one of them matches. The gateway
+// can be `NULL` to match a route without a gateway.
+static int has_route(const char *dst, int dst_len, const char *gateway,
+		     int oif)
+{
+	struct rtnl_req req;
+	char buf[8192];
+	struct nlmsghdr *nh;
+	struct rtattr *rta;
+	struct in_addr dst_in, gateway_in = { 0 }, rta_dst, rta_gateway;
+	int len, attr_len, rta_oif, found = 0;
+
+	inet_pton(AF_INET, dst, &dst_in);
+	if (gateway != NULL)
+		inet_pton(AF_INET, gateway, &gateway_in);
+
+	init_req(&req, RTM_GETROUTE, NLM_F_DUMP, sizeof(req.rtm));
+	req.rtm.rtm_family = AF_INET;
+	if (send(rtnl_fd, &req, req.nh.nlmsg_len, 0) < 0)
+		return -1;
+
+	for (;;) {
+		len = recv(rtnl_fd, buf, sizeof(buf), 0);
+		if (len < 0)
+			return -1;
+
+		for (nh = (struct nlmsghdr *)buf; NLMSG_OK(nh, len);
+		     nh = NLMSG_NEXT(nh, len)) {
+			if (nh->nlmsg_type == NLMSG_DONE)
+				return found;
+			if (nh->nlmsg_type != RTM_NEWROUTE ||
+			    ((struct rtmsg *)NLMSG_DATA(nh))->rtm_dst_len !=
+				    dst_len)
+				continue;
+
+			rta_dst.s_addr = 0;
+			rta_gateway.s_addr = 0;
+			rta_oif = 0;
+			attr_len = RTM_PAYLOAD(nh);
+			for (rta = RTM_RTA(NLMSG_DATA(nh));
+			     RTA_OK(rta, attr_len);
+			     rta = RTA_NEXT(rta, attr_len)) {
+				if (rta->rta_type == RTA_DST)
+					memcpy(&rta_dst, RTA_DATA(rta), 4);
+				else if (rta->rta_type == RTA_GATEWAY)
+					memcpy(&rta_gateway, RTA_DATA(rta), 4);
+				else if (rta->rta_type == RTA_OIF)
+					rta_oif = *(int *)RTA_DATA(rta);
+			}
+
+			if (rta_dst.s_addr == dst_in.s_addr &&
+			    rta_gateway.s_addr == gateway_in.s_addr &&
+			    rta_oif == oif)
+				found = 1;
+		}
+	}
+}
+
+FN_TEST(new_veth_pair)
+{
+	struct rtnl_req req;
+	struct rtattr *link_info, *info_data, *peer;
+	struct ifinfomsg peer_ifi = { .ifi_family = AF_UNSPEC };
+
+	init_req(&req, RTM_NEWLINK, NLM_F_CREATE | NLM_F_EXCL,
+		 sizeof(req.ifi));
+	req.ifi.ifi_family = AF_UNSPEC;
+	add_attr(&req.nh, IFLA_IFNAME, VETH_NAME, sizeof(VETH_NAME));
+
+	link_info = add_attr(&req.nh, IFLA_LINKINFO, NULL, 0);
+	add_attr(&req.nh, IFLA_INFO_KIND, "veth", strlen("veth"));
+	info_data = add_attr(&req.nh, IFLA_INFO_DATA, NULL, 0);
+	peer = add_attr(&req.nh, VETH_INFO_PEER, &peer_ifi, sizeof(peer_ifi));
+	add_attr(&req.nh, IFLA_IFNAME, PEER_NAME, sizeof(PEER_NAME));
+	end_nest(&req.nh, peer);
+	end_nest(&req.nh, info_data);
+	end_nest(&req.nh, link_info);
+
+	TEST_SUCC(rtnl_talk(&req));
+
+	veth_index = TEST_RES(if_nametoindex(VETH_NAME), _ret != 0);
+	peer_index = TEST_RES(if_nametoindex(PEER_NAME), _ret != 0);
+	TEST_RES(has_notification(RTM_NEWLINK, veth_index), _ret == 1);
+}
+END_TEST()
+
+FN_TEST(link_up_down)
+{
+	// RTM_NEWLINK and RTM_SETLINK can both change the flags.
+	TEST_SUCC(set_link_flags(RTM_NEWLINK, veth_index, 0, IFF_UP));
+	TEST_RES(get_link_flags(veth_index), (_ret & IFF_UP) == 0);
+	TEST_RES(has_notification(RTM_NEWLINK, veth_index), _ret == 1);
+
+	TEST_SUCC(set_link_flags(RTM_SETLINK, veth_index, IFF_UP, IFF_UP));
+	TEST_RES(get_link_flags(veth_index), (_ret & IFF_UP) != 0);
+	TEST_RES(has_notification(RTM_NEWLINK, veth_index), _ret == 1);
+
+	// Flags that are not in `ifi_change` are not changed.
+	TEST_SUCC(set_link_flags(RTM_SETLINK, peer_index, IFF_UP, IFF_UP));
+	TEST_SUCC(set_link_flags(RTM_SETLINK, peer_index, 0, IFF_NOARP));
+	TEST_RES(get_link_flags(peer_index), (_ret & IFF_UP) != 0);
+
+	TEST_ERRNO(set_link_flags(RTM_SETLINK, 0x7fffffff, IFF_UP, IFF_UP),
+		   ENODEV);
+}
+END_TEST()
+
+FN_TEST(new_del_addr)
+{
+	TEST_SUCC(modify_addr(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL,
+			      veth_index, "10.7.0.1", 24));
+	TEST_RES(has_notification(RTM_NEWADDR, veth_index), _ret == 1);
+	TEST_ERRNO(modify_addr(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL,
+			       veth_index, "10.7.0.1", 24),
+		   EEXIST);
+	TEST_ERRNO(modify_addr(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL, 0,
+			       "10.7.0.1", 24),
+		   ENODEV);
+
+	TEST_SUCC(modify_addr(RTM_NEWADDR, NLM_F_CREATE | NLM_F_EXCL,
+			      veth_index, "10.7.0.3", 24));
+	TEST_SUCC(modify_addr(RTM_DELADDR, 0, veth_index, "10.7.0.3", 24));
+	TEST_RES(has_notification(RTM_DELADDR, veth_index), _ret == 1);
+	TEST_ERRNO(modify_addr(RTM_DELADDR, 0, veth_index, "10.7.0.3", 24),
+		   EADDRNOTAVAIL);
+}
+END_TEST()
+
+// The gateway 10.7.0.2 is not a local address, but it is reachable from the
+// network of 10.7.0.1/24.
+FN_TEST(new_del_route)
+{
+	TEST_RES(has_route("10.7.0.0", 24, NULL, veth_index), _ret == 1);
+	TEST_RES(has_route("10.8.0.0", 16, "10.7.0.2", veth_index), _ret == 0);
+
+	TEST_SUCC(modify_route(RTM_NEWROUTE, NLM_F_CREATE | NLM_F_EXCL,
+			       "10.8.0.0", 16, "10.7.0.2"));
+	TEST_RES(has_notification(RTM_NEWROUTE, 0), _ret == 1);
+	TEST_RES(has_route("10.8.0.0", 16, "10.7.0.2", veth_index), _ret == 1);
+
+	TEST_ERRNO(modify_route(RTM_NEWROUTE, NLM_F_CREATE | NLM_F_EXCL,
+				"10.8.0.0", 16, "10.7.0.2"),
+		   EEXIST);
+	TEST_ERRNO(modify_route(RTM_NEWROUTE, 0, "10.9.0.0", 16, "10.7.0.2"),
+		   ENOENT);
+	TEST_ERRNO(modify_route(RTM_NEWROUTE, NLM_F_CREATE | NLM_F_EXCL,
+				"10.9.0.0", 16, "10.10.0.1"),
+		   ENETUNREACH);
+
+	TEST_SUCC(modify_route(RTM_DELROUTE, 0, "10.8.0.0", 16, "10.7.0.2"));
+	TEST_RES(has_notification(RTM_DELROUTE, 0), _ret == 1);
+	TEST_RES(has_route("10.8.0.0", 16, "10.7.0.2", veth_index), _ret == 0);
+	TEST_ERRNO(modify_route(RTM_DELROUTE, 0, "10.8.0.0", 16, "10.7.0.2"),
+		   ESRCH);
+}
+END_TEST()
+
+// The notifications are delivered only to the sockets created in the network
+// namespace where the change happens.
+FN_TEST(notification_in_other_net_ns)
+{
+	struct sockaddr_nl addr = {
+		.nl_family = AF_NETLINK,
+		.nl_groups = RTMGRP_LINK,
+	};
+	int init_ns_fd, other_fd;
+	char buf[4096];
+
+	init_ns_fd = TEST_SUCC(open("/proc/self/ns/net", O_RDONLY));
+	TEST_SUCC(unshare(CLONE_NEWNET));
+	other_fd = TEST_SUCC(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+	TEST_SUCC(bind(other_fd, (struct sockaddr *)&addr, sizeof(addr)));
+	TEST_SUCC(setns(init_ns_fd, CLONE_NEWNET));
+
+	TEST_SUCC(set_link_flags(RTM_SETLINK, veth_index, 0, IFF_UP));
+	TEST_RES(has_notification(RTM_NEWLINK, veth_index), _ret == 1);
+	TEST_ERRNO(recv(other_fd, buf, sizeof(buf), MSG_DONTWAIT), EAGAIN);
+
+	TEST_SUCC(close(other_fd));
+	TEST_SUCC(close(init_ns_fd));
+}
+END_TEST()
+
+FN_TEST(del_veth_pair)
+{
+	struct rtnl_req req;
+
+	init_req(&req, RTM_DELLINK, 0, sizeof(req.ifi));
+	req.ifi.ifi_family = AF_UNSPEC;
+	req.ifi.ifi_index = veth_index;
+	TEST_SUCC(rtnl_talk(&req));
+	TEST_RES(has_notification(RTM_DELLINK, veth_index), _ret == 1);
+
+	TEST_ERRNO(if_nametoindex(VETH_NAME), ENODEV);
+	TEST_ERRNO(if_nametoindex(PEER_NAME), ENODEV);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(rtnl_fd));
+	CHECK(close(monitor_fd));
+}
+END_SETUP()
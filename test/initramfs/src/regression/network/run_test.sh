@@ -16,10 +16,12 @@ sleep 0.2
 sleep 0.2
 ./unix_client
 
+./dual_stack
 ./listen_backlog
 ./msg_peek
 ./msg_trunc
 ./privileged_ports
+./raw_socket
 ./send_buf_full
 ./sendmmsg
 ./socketpair
@@ -37,5 +39,10 @@ sleep 0.2
 ./unix_stream_err
 
 ./netlink_route
+./nftables
+./packet_socket
+./rtnl_config
 ./rtnl_err
+./tun_tap
 ./uevent_err
+./veth_bridge
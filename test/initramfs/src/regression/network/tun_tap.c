@@ -0,0 +1,343 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <linux/if_tun.h>
+#include <linux/rtnetlink.h>
+#include <net/if.h>
+#include <poll.h>
+#include <stdint.h>
+#include <sys/ioctl.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#include "../common/test.h"
+
+#define TUN_NAME "ttest0"
+#define TAP_NAME "ttest1"
+#define TAP2_NAME "ttest2"
+#define BRIDGE_NAME "btest0"
+
+static int set_iff(int fd, const char *name, int flags, char *new_name)
+{
+	struct ifreq ifr;
+
+	memset(&ifr, 0, sizeof(ifr));
+	strncpy(ifr.ifr_name, name, IFNAMSIZ - 1);
+	ifr.ifr_flags = flags;
+	if (ioctl(fd, TUNSETIFF, &ifr) < 0)
+		return -1;
+
+	if (new_name != NULL)
+		strcpy(new_name, ifr.ifr_name);
+	return 0;
+}
+
+// Opens `/dev/net/tun` and attaches the file to the device with the name.
+static int open_tun(const char *name, int flags)
+{
+	int fd, err;
+
+	fd = open("/dev/net/tun", O_RDWR);
+	if (fd < 0)
+		return -1;
+
+	if (set_iff(fd, name, flags, NULL) < 0) {
+		err = errno;
+		close(fd);
+		errno = err;
+		return -1;
+	}
+
+	return fd;
+}
+
+static int set_queue(int fd, int flags)
+{
+	struct ifreq ifr;
+
+	memset(&ifr, 0, sizeof(ifr));
+	ifr.ifr_flags = flags;
+	return ioctl(fd, TUNSETQUEUE, &ifr);
+}
+
+static int poll_events(int fd, int timeout)
+{
+	struct pollfd pfd = { .fd = fd, .events = POLLIN | POLLOUT };
+
+	if (poll(&pfd, 1, timeout) < 0)
+		return -1;
+	return pfd.revents;
+}
+
+FN_TEST(unattached)
+{
+	int fd;
+	unsigned int features;
+	char buf[64];
+	struct ifreq ifr;
+
+	fd = TEST_SUCC(open("/dev/net/tun", O_RDWR));
+
+	TEST_RES(ioctl(fd, TUNGETFEATURES, &features),
+		 (features & (IFF_TUN | IFF_TAP | IFF_NO_PI |
+			      IFF_MULTI_QUEUE)) ==
+			 (IFF_TUN | IFF_TAP | IFF_NO_PI | IFF_MULTI_QUEUE));
+
+	TEST_ERRNO(read(fd, buf, sizeof(buf)), EBADFD);
+	TEST_ERRNO(write(fd, buf, sizeof(buf)), EBADFD);
+	TEST_ERRNO(ioctl(fd, TUNGETIFF, &ifr), EBADFD);
+	TEST_ERRNO(ioctl(fd, TUNSETPERSIST, 1), EBADFD);
+	TEST_ERRNO(set_queue(fd, IFF_ATTACH_QUEUE), EINVAL);
+	TEST_RES(poll_events(fd, 0), _ret == POLLERR);
+
+	TEST_ERRNO(set_iff(fd, TUN_NAME, 0, NULL), EINVAL);
+	TEST_ERRNO(set_iff(fd, "lo", IFF_TUN, NULL), EINVAL);
+	TEST_ERRNO(set_iff(fd, "lo", IFF_TUN | IFF_TUN_EXCL, NULL), EBUSY);
+	TEST_ERRNO(set_iff(fd, "ttest/0", IFF_TUN, NULL), EINVAL);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(tun_device)
+{
+	int fd, fd2;
+	char name[IFNAMSIZ];
+	struct ifreq ifr;
+
+	fd = TEST_SUCC(open("/dev/net/tun", O_RDWR));
+	TEST_RES(set_iff(fd, "ttest%d", IFF_TUN | IFF_NO_PI, name),
+		 strcmp(name, TUN_NAME) == 0);
+	TEST_RES(if_nametoindex(TUN_NAME), _ret != 0);
+
+	TEST_RES(ioctl(fd, TUNGETIFF, &ifr),
+		 strcmp(ifr.ifr_name, TUN_NAME) == 0 &&
+			 ifr.ifr_flags == (IFF_TUN | IFF_NO_PI));
+	TEST_ERRNO(set_iff(fd, "ttest9", IFF_TUN, NULL), EEXIST);
+	TEST_RES(poll_events(fd, 0), _ret == POLLOUT);
+
+	fd2 = TEST_SUCC(open("/dev/net/tun", O_RDWR));
+	TEST_ERRNO(set_iff(fd2, TUN_NAME, IFF_TUN, NULL), EBUSY);
+	TEST_ERRNO(set_iff(fd2, TUN_NAME, IFF_TAP, NULL), EINVAL);
+	TEST_ERRNO(set_iff(fd2, TUN_NAME, IFF_TUN | IFF_MULTI_QUEUE, NULL),
+		   EINVAL);
+	TEST_ERRNO(set_iff(fd2, TUN_NAME, IFF_TUN | IFF_TUN_EXCL, NULL),
+		   EBUSY);
+	TEST_SUCC(close(fd2));
+
+	// Closing the last file deletes the device.
+	TEST_SUCC(close(fd));
+	TEST_ERRNO(if_nametoindex(TUN_NAME), ENODEV);
+}
+END_TEST()
+
+FN_TEST(persist)
+{
+	int fd;
+	struct ifreq ifr;
+
+	fd = TEST_SUCC(open_tun(TUN_NAME, IFF_TUN));
+	TEST_SUCC(ioctl(fd, TUNSETPERSIST, 1));
+	TEST_SUCC(close(fd));
+	TEST_RES(if_nametoindex(TUN_NAME), _ret != 0);
+
+	fd = TEST_SUCC(open_tun(TUN_NAME, IFF_TUN));
+	TEST_RES(ioctl(fd, TUNGETIFF, &ifr),
+		 ifr.ifr_flags == (IFF_TUN | IFF_PERSIST));
+	TEST_SUCC(ioctl(fd, TUNSETPERSIST, 0));
+	TEST_SUCC(close(fd));
+	TEST_ERRNO(if_nametoindex(TUN_NAME), ENODEV);
+}
+END_TEST()
+
+FN_TEST(multi_queue)
+{
+	int fd1, fd2;
+	char buf[64];
+
+	fd1 = TEST_SUCC(open_tun(TAP_NAME, IFF_TAP | IFF_MULTI_QUEUE));
+	fd2 = TEST_SUCC(open_tun(TAP_NAME, IFF_TAP | IFF_MULTI_QUEUE));
+	TEST_ERRNO(open_tun(TAP_NAME, IFF_TAP), EINVAL);
+
+	TEST_SUCC(set_queue(fd2, IFF_DETACH_QUEUE));
+	TEST_ERRNO(set_queue(fd2, IFF_DETACH_QUEUE), EINVAL);
+	TEST_ERRNO(read(fd2, buf, sizeof(buf)), EBADFD);
+	TEST_RES(poll_events(fd2, 0), _ret == POLLERR);
+	TEST_ERRNO(set_iff(fd2, TAP_NAME, IFF_TAP | IFF_MULTI_QUEUE, NULL),
+		   EINVAL);
+
+	TEST_SUCC(set_queue(fd2, IFF_ATTACH_QUEUE));
+	TEST_ERRNO(set_queue(fd2, IFF_ATTACH_QUEUE), EINVAL);
+	TEST_ERRNO(set_queue(fd2, 0), EINVAL);
+	TEST_RES(poll_events(fd2, 0), _ret == POLLOUT);
+
+	// The device is deleted after all its queues are closed.
+	TEST_SUCC(close(fd1));
+	TEST_RES(if_nametoindex(TAP_NAME), _ret != 0);
+	TEST_SUCC(close(fd2));
+	TEST_ERRNO(if_nametoindex(TAP_NAME), ENODEV);
+}
+END_TEST()
+
+FN_TEST(io_errors)
+{
+	int fd;
+	char buf[64] = { 0 };
+
+	fd = TEST_SUCC(open_tun(TAP_NAME, IFF_TAP));
+
+	TEST_ERRNO(write(fd, buf, sizeof(struct tun_pi) - 1), EINVAL);
+	TEST_ERRNO(write(fd, buf, sizeof(struct tun_pi) + 13), EINVAL);
+
+	TEST_SUCC(fcntl(fd, F_SETFL, O_NONBLOCK));
+	TEST_ERRNO(read(fd, buf, sizeof(buf)), EAGAIN);
+	TEST_RES(read(fd, buf, 0), _ret == 0);
+
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+struct link_req {
+	struct nlmsghdr nh;
+	struct ifinfomsg ifi;
+	char attrs[256];
+};
+
+static int rtnl_fd;
+
+FN_SETUP(rtnl)
+{
+	rtnl_fd = CHECK(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+}
+END_SETUP()
+
+static struct rtattr *add_attr(struct nlmsghdr *nh, int type,
+			       const void *data, int len)
+{
+	struct rtattr *rta =
+		(struct rtattr *)((char *)nh + NLMSG_ALIGN(nh->nlmsg_len));
+
+	rta->rta_type = type;
+	rta->rta_len = RTA_LENGTH(len);
+	if (len > 0)
+		memcpy(RTA_DATA(rta), data, len);
+	nh->nlmsg_len = NLMSG_ALIGN(nh->nlmsg_len) + RTA_ALIGN(rta->rta_len);
+
+	return rta;
+}
+
+// Sends a link request and returns the error code in the acknowledgment.
+//
+// A new bridge is created if `kind` is not NULL, and the link is attached to
+// `master` if `master` is not negative.
+static int link_request(int type, int index, const char *kind, int master)
+{
+	struct link_req req;
+	struct rtattr *link_info;
+	char buf[4096];
+	struct nlmsghdr *nh = (struct nlmsghdr *)buf;
+	struct nlmsgerr *err = NLMSG_DATA(nh);
+
+	memset(&req, 0, sizeof(req));
+	req.nh.nlmsg_len = NLMSG_LENGTH(sizeof(req.ifi));
+	req.nh.nlmsg_type = type;
+	req.nh.nlmsg_flags = NLM_F_REQUEST | NLM_F_ACK;
+	req.ifi.ifi_family = AF_UNSPEC;
+	req.ifi.ifi_index = index;
+
+	if (kind != NULL) {
+		req.nh.nlmsg_flags |= NLM_F_CREATE | NLM_F_EXCL;
+		add_attr(&req.nh, IFLA_IFNAME, BRIDGE_NAME,
+			 sizeof(BRIDGE_NAME));
+		link_info = add_attr(&req.nh, IFLA_LINKINFO, NULL, 0);
+		add_attr(&req.nh, IFLA_INFO_KIND, kind, strlen(kind));
+		link_info->rta_len =
+			(char *)&req.nh + req.nh.nlmsg_len - (char *)link_info;
+	}
+	if (master >= 0)
+		add_attr(&req.nh, IFLA_MASTER, &master, sizeof(master));
+
+	if (send(rtnl_fd, &req, req.nh.nlmsg_len, 0) < 0)
+		return -1;
+	if (recv(rtnl_fd, buf, sizeof(buf), 0) < 0)
+		return -1;
+
+	if (nh->nlmsg_type != NLMSG_ERROR) {
+		errno = EPROTO;
+		return -1;
+	}
+	if (err->error != 0) {
+		errno = -err->error;
+		return -1;
+	}
+
+	return 0;
+}
+
+static const uint8_t frame[] = {
+	// Destination address (unknown to the bridge)
+	0x02, 0x00, 0x00, 0x00, 0x00, 0x01,
+	// Source address
+	0x02, 0x00, 0x00, 0x00, 0x00, 0x02,
+	// EtherType (local experimental)
+	0x88, 0xb5,
+	// Payload
+	'h', 'e', 'l', 'l', 'o'
+};
+
+FN_TEST(tap_bridge)
+{
+	int tun_fd, tap_fd, tap2_fd;
+	int tun_index, tap_index, tap2_index, bridge_index;
+	struct {
+		struct tun_pi pi;
+		uint8_t data[64];
+	} buf;
+
+	tun_fd = TEST_SUCC(open_tun(TUN_NAME, IFF_TUN));
+	tap_fd = TEST_SUCC(open_tun(TAP_NAME, IFF_TAP | IFF_NO_PI));
+	tap2_fd = TEST_SUCC(open_tun(TAP2_NAME, IFF_TAP));
+	tun_index = TEST_RES(if_nametoindex(TUN_NAME), _ret != 0);
+	tap_index = TEST_RES(if_nametoindex(TAP_NAME), _ret != 0);
+	tap2_index = TEST_RES(if_nametoindex(TAP2_NAME), _ret != 0);
+
+	TEST_SUCC(link_request(RTM_NEWLINK, 0, "bridge", -1));
+	bridge_index = TEST_RES(if_nametoindex(BRIDGE_NAME), _ret != 0);
+	TEST_SUCC(link_request(RTM_NEWLINK, tap_index, NULL, bridge_index));
+	TEST_SUCC(link_request(RTM_NEWLINK, tap2_index, NULL, bridge_index));
+
+	// Only Ethernet devices can be attached to a bridge.
+	TEST_ERRNO(link_request(RTM_NEWLINK, tun_index, NULL, bridge_index),
+		   EINVAL);
+
+	// The frame written to one port is flooded to the other port.
+	TEST_RES(write(tap_fd, frame, sizeof(frame)), _ret == sizeof(frame));
+	TEST_RES(poll_events(tap2_fd, 1000), _ret & POLLIN);
+	TEST_RES(read(tap2_fd, &buf, sizeof(buf)),
+		 _ret == sizeof(buf.pi) + sizeof(frame) && buf.pi.flags == 0 &&
+			 buf.pi.proto == htons(0x88b5) &&
+			 memcmp(buf.data, frame, sizeof(frame)) == 0);
+
+	// The packet is truncated if the buffer is too small.
+	TEST_RES(write(tap_fd, frame, sizeof(frame)), _ret == sizeof(frame));
+	TEST_RES(poll_events(tap2_fd, 1000), _ret & POLLIN);
+	TEST_RES(read(tap2_fd, &buf, sizeof(buf.pi) + 6),
+		 _ret == sizeof(buf.pi) + sizeof(frame) &&
+			 buf.pi.flags == TUN_PKT_STRIP);
+
+	// Deleting the device detaches the file.
+	TEST_SUCC(link_request(RTM_DELLINK, tap2_index, NULL, -1));
+	TEST_ERRNO(if_nametoindex(TAP2_NAME), ENODEV);
+	TEST_ERRNO(read(tap2_fd, &buf, sizeof(buf)), EBADFD);
+	TEST_RES(poll_events(tap2_fd, 0), _ret == POLLERR);
+
+	TEST_SUCC(close(tap2_fd));
+	TEST_SUCC(close(tap_fd));
+	TEST_SUCC(close(tun_fd));
+	TEST_SUCC(link_request(RTM_DELLINK, bridge_index, NULL, -1));
+	TEST_ERRNO(if_nametoindex(TUN_NAME), ENODEV);
+	TEST_ERRNO(if_nametoindex(TAP_NAME), ENODEV);
+	TEST_ERRNO(if_nametoindex(BRIDGE_NAME), ENODEV);
+}
+END_TEST()
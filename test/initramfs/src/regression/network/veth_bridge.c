@@ -0,0 +1,275 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#include <linux/if_link.h>
+#include <linux/rtnetlink.h>
+#include <linux/veth.h>
+#include <net/if.h>
+#include <stdint.h>
+#include <sys/socket.h>
+#include <unistd.h>
+
+#include "../common/test.h"
+
+#define VETH_NAME "vtest0"
+#define PEER_NAME "vtest1"
+#define BRIDGE_NAME "btest0"
+
+struct link_req {
+	struct nlmsghdr nh;
+	struct ifinfomsg ifi;
+	char attrs[512];
+};
+
+static int rtnl_fd;
+static unsigned int rtnl_seq;
+
+FN_SETUP(rtnl)
+{
+	rtnl_fd = CHECK(socket(AF_NETLINK, SOCK_RAW, NETLINK_ROUTE));
+}
+END_SETUP()
+
+static struct rtattr *add_attr(struct nlmsghdr *nh, int type,
+			       const void *data, int len)
+{
+	struct rtattr *rta =
+		(struct rtattr *)((char *)nh + NLMSG_ALIGN(nh->nlmsg_len));
+
+	rta->rta_type = type;
+	rta->rta_len = RTA_LENGTH(len);
+	if (len > 0)
+		memcpy(RTA_DATA(rta), data, len);
+	nh->nlmsg_len = NLMSG_ALIGN(nh->nlmsg_len) + RTA_ALIGN(rta->rta_len);
+
+	return rta;
+}
+
+static void end_nest(struct nlmsghdr *nh, struct rtattr *nest)
+{
+	nest->rta_len = (char *)nh + nh->nlmsg_len - (char *)nest;
+}
+
+static void init_req(struct link_req *req, int type, int flags, int index)
+{
+	memset(req, 0, sizeof(*req));
+	req->nh.nlmsg_len = NLMSG_LENGTH(sizeof(req->ifi));
+	req->nh.nlmsg_type = type;
+	req->nh.nlmsg_flags = NLM_F_REQUEST | flags;
+	req->nh.nlmsg_seq = ++rtnl_seq;
+	req->ifi.ifi_family = AF_UNSPEC;
+	req->ifi.ifi_index = index;
+}
+
+// Sends the request and returns the error code in the acknowledgment.
+static int rtnl_talk(struct link_req *req)
+{
+	char buf[4096];
+	struct nlmsghdr *nh = (struct nlmsghdr *)buf;
+	struct nlmsgerr *err = NLMSG_DATA(nh);
+
+	req->nh.nlmsg_flags |= NLM_F_ACK;
+
+	if (send(rtnl_fd, req, req->nh.nlmsg_len, 0) < 0)
+		return -1;
+	if (recv(rtnl_fd, buf, sizeof(buf), 0) < 0)
+		return -1;
+
+	if (nh->nlmsg_type != NLMSG_ERROR || nh->nlmsg_seq != rtnl_seq) {
+		errno = EPROTO;
+		return -1;
+	}
+	if (err->error != 0) {
+		errno = -err->error;
+		return -1;
+	}
+
+	return 0;
+}
+
+static int new_link(const char *name, const char *kind, const char *peer_name,
+		    int flags)
+{
+	struct link_req req;
+	struct rtattr *link_info, *info_data, *peer;
+	struct ifinfomsg peer_ifi = { .ifi_family = AF_UNSPEC };
+
+	init_req(&req, RTM_NEWLINK, NLM_F_CREATE | flags, 0);
+	if (name != NULL)
+		add_attr(&req.nh, IFLA_IFNAME, name, strlen(name) + 1);
+
+	link_info = add_attr(&req.nh, IFLA_LINKINFO, NULL, 0);
+	add_attr(&req.nh, IFLA_INFO_KIND, kind, strlen(kind));
+	if (peer_name != NULL) {
+		info_data = add_attr(&req.nh, IFLA_INFO_DATA, NULL, 0);
+		peer = add_attr(&req.nh, VETH_INFO_PEER, &peer_ifi,
+				sizeof(peer_ifi));
+		add_attr(&req.nh, IFLA_IFNAME, peer_name,
+			 strlen(peer_name) + 1);
+		end_nest(&req.nh, peer);
+		end_nest(&req.nh, info_data);
+	}
+	end_nest(&req.nh, link_info);
+
+	return rtnl_talk(&req);
+}
+
+static int set_master(int index, int master)
+{
+	struct link_req req;
+
+	init_req(&req, RTM_NEWLINK, 0, index);
+	add_attr(&req.nh, IFLA_MASTER, &master, sizeof(master));
+
+	return rtnl_talk(&req);
+}
+
+static int del_link(int index)
+{
+	struct link_req req;
+
+	init_req(&req, RTM_DELLINK, 0, index);
+
+	return rtnl_talk(&req);
+}
+
+// Returns the value of the 32-bit link attribute, or -1 if it is absent.
+static int get_link_u32(int index, int type)
+{
+	struct link_req req;
+	char buf[4096];
+	struct nlmsghdr *nh = (struct nlmsghdr *)buf;
+	struct rtattr *rta;
+	int len;
+
+	init_req(&req, RTM_GETLINK, 0, index);
+	if (send(rtnl_fd, &req, req.nh.nlmsg_len, 0) < 0)
+		return -1;
+	if (recv(rtnl_fd, buf, sizeof(buf), 0) < 0)
+		return -1;
+
+	if (nh->nlmsg_type != RTM_NEWLINK) {
+		errno = EPROTO;
+		return -1;
+	}
+
+	len = nh->nlmsg_len - NLMSG_LENGTH(sizeof(struct ifinfomsg));
+	for (rta = IFLA_RTA(NLMSG_DATA(nh)); RTA_OK(rta, len);
+	     rta = RTA_NEXT(rta, len)) {
+		if (rta->rta_type == type)
+			return *(uint32_t *)RTA_DATA(rta);
+	}
+
+	errno = ENOENT;
+	return -1;
+}
+
+static int veth_index;
+static int peer_index;
+static int bridge_index;
+
+FN_TEST(new_veth_pair)
+{
+	TEST_SUCC(new_link(VETH_NAME, "veth", PEER_NAME, NLM_F_EXCL));
+
+	veth_index = TEST_RES(if_nametoindex(VETH_NAME), _ret != 0);
+	peer_index = TEST_RES(if_nametoindex(PEER_NAME), _ret != 0);
+
+	TEST_RES(get_link_u32(veth_index, IFLA_LINK), _ret == peer_index);
+	TEST_RES(get_link_u32(peer_index, IFLA_LINK), _ret == veth_index);
+
+	TEST_ERRNO(new_link(VETH_NAME, "veth", "vtest2", NLM_F_EXCL), EEXIST);
+	TEST_ERRNO(new_link("vtest2", "veth", PEER_NAME, NLM_F_EXCL), EEXIST);
+	TEST_ERRNO(new_link("vtest2", "veth", "vtest2", NLM_F_EXCL), EEXIST);
+	TEST_ERRNO(if_nametoindex("vtest2"), ENODEV);
+}
+END_TEST()
+
+FN_TEST(new_link_errors)
+{
+	struct link_req req;
+
+	TEST_ERRNO(new_link("vtest2", "no_such_kind", NULL, 0), EOPNOTSUPP);
+	TEST_ERRNO(new_link("vtest/2", "veth", NULL, 0), EINVAL);
+
+	init_req(&req, RTM_NEWLINK, 0, 0);
+	add_attr(&req.nh, IFLA_IFNAME, "vtest2", sizeof("vtest2"));
+	TEST_ERRNO(rtnl_talk(&req), ENODEV);
+}
+END_TEST()
+
+FN_TEST(name_template)
+{
+	int index;
+
+	TEST_SUCC(new_link("vtmpl%d", "veth", "vtmpl%d", NLM_F_EXCL));
+	TEST_RES(if_nametoindex("vtmpl0"), _ret != 0);
+	index = TEST_RES(if_nametoindex("vtmpl1"), _ret != 0);
+
+	TEST_SUCC(del_link(index));
+	TEST_ERRNO(if_nametoindex("vtmpl0"), ENODEV);
+	TEST_ERRNO(if_nametoindex("vtmpl1"), ENODEV);
+}
+END_TEST()
+
+FN_TEST(new_bridge)
+{
+	TEST_SUCC(new_link(BRIDGE_NAME, "bridge", NULL, NLM_F_EXCL));
+	bridge_index = TEST_RES(if_nametoindex(BRIDGE_NAME), _ret != 0);
+
+	TEST_ERRNO(new_link(BRIDGE_NAME, "bridge", NULL, NLM_F_EXCL), EEXIST);
+}
+END_TEST()
+
+FN_TEST(set_master)
+{
+	int bridge2_index;
+
+	TEST_ERRNO(get_link_u32(veth_index, IFLA_MASTER), ENOENT);
+
+	TEST_SUCC(set_master(veth_index, bridge_index));
+	TEST_RES(get_link_u32(veth_index, IFLA_MASTER), _ret == bridge_index);
+	TEST_ERRNO(get_link_u32(peer_index, IFLA_MASTER), ENOENT);
+
+	TEST_ERRNO(set_master(peer_index, veth_index), EOPNOTSUPP);
+	TEST_ERRNO(set_master(peer_index, 0x7fffffff), EINVAL);
+
+	TEST_SUCC(new_link("btest1", "bridge", NULL, NLM_F_EXCL));
+	bridge2_index = TEST_RES(if_nametoindex("btest1"), _ret != 0);
+	TEST_ERRNO(set_master(bridge2_index, bridge_index), ELOOP);
+
+	// Deleting the bridge releases its ports.
+	TEST_SUCC(set_master(peer_index, bridge2_index));
+	TEST_SUCC(del_link(bridge2_index));
+	TEST_ERRNO(get_link_u32(peer_index, IFLA_MASTER), ENOENT);
+
+	TEST_SUCC(set_master(veth_index, 0));
+	TEST_ERRNO(get_link_u32(veth_index, IFLA_MASTER), ENOENT);
+}
+END_TEST()
+
+FN_TEST(del_link_errors)
+{
+	struct link_req req;
+
+	TEST_ERRNO(del_link(if_nametoindex("lo")), EOPNOTSUPP);
+	TEST_ERRNO(del_link(0x7fffffff), ENODEV);
+
+	init_req(&req, RTM_DELLINK, 0, 0);
+	TEST_ERRNO(rtnl_talk(&req), EINVAL);
+}
+END_TEST()
+
+FN_TEST(del_link)
+{
+	TEST_SUCC(set_master(veth_index, bridge_index));
+
+	// Deleting one link of a veth pair also deletes its peer.
+	TEST_SUCC(del_link(peer_index));
+	TEST_ERRNO(if_nametoindex(VETH_NAME), ENODEV);
+	TEST_ERRNO(if_nametoindex(PEER_NAME), ENODEV);
+
+	TEST_SUCC(del_link(bridge_index));
+	TEST_ERRNO(if_nametoindex(BRIDGE_NAME), ENODEV);
+	TEST_ERRNO(del_link(bridge_index), ENODEV);
+}
+END_TEST()
@@ -195,7 +195,7 @@ verify "cpu.stat has burst_usec after enabling CPU sub-controller" \
     "grep '^burst_usec ' cpu.stat" \
     "burst_usec 0"
 
-log_step "2.2.1 Verify cpu.weight dummy read/write"
+log_step "2.2.1 Verify cpu.weight read/write"
 verify "cpu.weight defaults to 100" \
     "cat cpu.weight" \
     "100"
@@ -204,7 +204,7 @@ verify "cpu.weight accepts a valid weight" \
     "cat cpu.weight" \
     "250"
 
-log_step "2.2.2 Verify cpu.max dummy read/write"
+log_step "2.2.2 Verify cpu.max read/write"
 verify "cpu.max defaults to max with 100ms period" \
     "cat cpu.max" \
     "max 100000"
@@ -0,0 +1,445 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include "../../common/file_util.h"
+#include "../../common/test.h"
+
+#include <elf.h>
+#include <fcntl.h>
+#include <libgen.h>
+#include <limits.h>
+#include <signal.h>
+#include <sys/mman.h>
+#include <sys/prctl.h>
+#include <sys/procfs.h>
+#include <sys/resource.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#ifndef PAGE_SIZE
+#define PAGE_SIZE 4096
+#endif
+#define MAP_PAGES 4
+#define MAP_SIZE (MAP_PAGES * PAGE_SIZE)
+
+#define CORE_PATTERN "/proc/sys/kernel/core_pattern"
+#define COREDUMP_FILTER "/proc/self/coredump_filter"
+#define HELPER_NAME "coredump_helper"
+
+static char work_dir[] = "/tmp/coredump_test_XXXXXX";
+static char helper_path[PATH_MAX];
+static char saved_pattern[256];
+
+static char *mapping;
+
+FN_SETUP(work_dir)
+{
+	char self_path[PATH_MAX];
+	ssize_t len;
+
+	CHECK_WITH(mkdtemp(work_dir), _ret != NULL);
+	CHECK(chdir(work_dir));
+
+	len = CHECK(readlink("/proc/self/exe", self_path,
+			     sizeof(self_path) - 1));
+	self_path[len] = '\0';
+	CHECK_WITH(snprintf(helper_path, sizeof(helper_path), "%s/%s",
+			    dirname(self_path), HELPER_NAME),
+		   _ret > 0 && (size_t)_ret < sizeof(helper_path));
+
+	CHECK_WITH(read_file(CORE_PATTERN),
+		   _ret >= 0 && (size_t)_ret < sizeof(saved_pattern));
+	strcpy(saved_pattern, file_buf);
+	CHECK(write_file(CORE_PATTERN, "core.%p"));
+
+	mapping = CHECK_WITH(mmap(NULL, MAP_SIZE, PROT_READ | PROT_WRITE,
+				  MAP_PRIVATE | MAP_ANONYMOUS, -1, 0),
+			     _ret != MAP_FAILED);
+	memset(mapping, 0xab, MAP_SIZE);
+}
+END_SETUP()
+
+/*
+ * Forks a child that runs `prepare` and then kills itself with `SIGABRT`.
+ *
+ * Returns the wait status of the child and stores its PID in `pid_out`.
+ */
+static int crash_child(rlim_t core_limit, void (*prepare)(void), pid_t *pid_out)
+{
+	struct rlimit rlimit = { .rlim_cur = core_limit,
+				 .rlim_max = RLIM_INFINITY };
+	pid_t pid;
+	int status;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		CHECK(setrlimit(RLIMIT_CORE, &rlimit));
+		if (prepare)
+			prepare();
+		raise(SIGABRT);
+		exit(EXIT_FAILURE);
+	}
+
+	CHECK_WITH(waitpid(pid, &status, 0), _ret == pid);
+	if (pid_out)
+		*pid_out = pid;
+
+	return status;
+}
+
+static void core_path(char *buf, size_t len, pid_t pid)
+{
+	CHECK_WITH(snprintf(buf, len, "core.%d", pid),
+		   _ret > 0 && (size_t)_ret < len);
+}
+
+/*
+ * Reads the whole core file at `path` into a buffer, which should be freed by
+ * the caller.
+ */
+static char *read_core(const char *path, size_t *len_out)
+{
+	struct stat stat_buf;
+	char *core;
+	ssize_t len;
+	int fd;
+
+	fd = open(path, O_RDONLY);
+	if (fd < 0)
+		return NULL;
+	CHECK(fstat(fd, &stat_buf));
+
+	core = CHECK_WITH(malloc(stat_buf.st_size), _ret != NULL);
+	len = CHECK(pread(fd, core, stat_buf.st_size, 0));
+	close(fd);
+
+	*len_out = len;
+	return core;
+}
+
+static int is_valid_core(const char *core, size_t len)
+{
+	const Elf64_Ehdr *ehdr = (const Elf64_Ehdr *)core;
+
+	return len >= sizeof(*ehdr) &&
+	       memcmp(ehdr->e_ident, ELFMAG, SELFMAG) == 0 &&
+	       ehdr->e_ident[EI_CLASS] == ELFCLASS64 &&
+	       ehdr->e_type == ET_CORE &&
+	       ehdr->e_phentsize == sizeof(Elf64_Phdr) &&
+	       ehdr->e_phoff + ehdr->e_phnum * sizeof(Elf64_Phdr) <= len;
+}
+
+static const Elf64_Phdr *find_phdr(const char *core, unsigned long vaddr)
+{
+	const Elf64_Ehdr *ehdr = (const Elf64_Ehdr *)core;
+	const Elf64_Phdr *phdr = (const Elf64_Phdr *)(core + ehdr->e_phoff);
+	int i;
+
+	for (i = 0; i < ehdr->e_phnum; i++) {
+		if (phdr[i].p_type == PT_LOAD && phdr[i].p_vaddr <= vaddr &&
+		    vaddr < phdr[i].p_vaddr + phdr[i].p_memsz)
+			return &phdr[i];
+	}
+
+	return NULL;
+}
+
+/*
+ * Finds the first note of type `type` in the `PT_NOTE` segment and returns its
+ * descriptor.
+ */
+static const void *find_note(const char *core, Elf64_Word type)
+{
+	const Elf64_Ehdr *ehdr = (const Elf64_Ehdr *)core;
+	const Elf64_Phdr *phdr = (const Elf64_Phdr *)(core + ehdr->e_phoff);
+	const char *note, *end;
+
+	if (ehdr->e_phnum == 0 || phdr[0].p_type != PT_NOTE)
+		return NULL;
+
+	note = core + phdr[0].p_offset;
+	end = note + phdr[0].p_filesz;
+	while (note + sizeof(Elf64_Nhdr) <= end) {
+		const Elf64_Nhdr *nhdr = (const Elf64_Nhdr *)note;
+		const char *desc = note + sizeof(*nhdr) +
+				   ((nhdr->n_namesz + 3) & ~3);
+
+		if (nhdr->n_type == type)
+			return desc;
+		note = desc + ((nhdr->n_descsz + 3) & ~3);
+	}
+
+	return NULL;
+}
+
+/*
+ * Returns the number of bytes of the mapping that are dumped, or -1 if the
+ * mapping is not found in the core file.
+ */
+static long dumped_mapping_size(pid_t pid)
+{
+	char path[64];
+	const Elf64_Phdr *phdr;
+	size_t len;
+	char *core;
+	long size = -1;
+
+	core_path(path, sizeof(path), pid);
+	core = read_core(path, &len);
+	if (core == NULL)
+		return -1;
+
+	if (is_valid_core(core, len) &&
+	    (phdr = find_phdr(core, (unsigned long)mapping)) != NULL &&
+	    phdr->p_vaddr == (unsigned long)mapping &&
+	    phdr->p_memsz == MAP_SIZE)
+		size = phdr->p_filesz;
+
+	free(core);
+	unlink(path);
+	return size;
+}
+
+FN_TEST(wait_status)
+{
+	struct stat stat_buf;
+	char path[64];
+	pid_t pid;
+
+	// `RLIMIT_CORE` is zero, so no core file is produced.
+	TEST_RES(crash_child(0, NULL, &pid),
+		 WIFSIGNALED(_ret) && WTERMSIG(_ret) == SIGABRT &&
+			 !WCOREDUMP(_ret));
+	core_path(path, sizeof(path), pid);
+	TEST_ERRNO(access(path, F_OK), ENOENT);
+
+	// `RLIMIT_CORE` is smaller than a page, so no core file is produced.
+	TEST_RES(crash_child(PAGE_SIZE - 1, NULL, &pid),
+		 WIFSIGNALED(_ret) && WTERMSIG(_ret) == SIGABRT &&
+			 !WCOREDUMP(_ret));
+	core_path(path, sizeof(path), pid);
+	TEST_ERRNO(access(path, F_OK), ENOENT);
+
+	TEST_RES(crash_child(RLIM_INFINITY, NULL, &pid),
+		 WIFSIGNALED(_ret) && WTERMSIG(_ret) == SIGABRT &&
+			 WCOREDUMP(_ret));
+	core_path(path, sizeof(path), pid);
+	TEST_RES(stat(path, &stat_buf),
+		 S_ISREG(stat_buf.st_mode) &&
+			 (stat_buf.st_mode & 0777) == 0600);
+	TEST_SUCC(unlink(path));
+}
+END_TEST()
+
+FN_TEST(elf_layout)
+{
+	const Elf64_Ehdr *ehdr;
+	const Elf64_Phdr *phdr;
+	const struct elf_prstatus *prstatus;
+	const struct elf_prpsinfo *prpsinfo;
+	const siginfo_t *siginfo;
+	char path[64];
+	size_t len;
+	char *core;
+	pid_t pid;
+
+	TEST_RES(crash_child(RLIM_INFINITY, NULL, &pid), WCOREDUMP(_ret));
+	core_path(path, sizeof(path), pid);
+	core = CHECK_WITH(read_core(path, &len), _ret != NULL);
+
+	TEST_RES(is_valid_core(core, len), _ret);
+	ehdr = (const Elf64_Ehdr *)core;
+	phdr = (const Elf64_Phdr *)(core + ehdr->e_phoff);
+	TEST_RES(phdr[0].p_type, _ret == PT_NOTE);
+
+	// The contents of the mapping are dumped page-aligned.
+	phdr = find_phdr(core, (unsigned long)mapping);
+	TEST_RES(phdr != NULL && phdr->p_filesz == MAP_SIZE &&
+			 phdr->p_offset % PAGE_SIZE == 0 &&
+			 phdr->p_offset + MAP_SIZE <= len,
+		 _ret);
+	TEST_RES(memcmp(core + phdr->p_offset, mapping, MAP_SIZE), _ret == 0);
+
+	prstatus = find_note(core, NT_PRSTATUS);
+	TEST_RES(prstatus != NULL && prstatus->pr_pid == pid &&
+			 prstatus->pr_cursig == SIGABRT,
+		 _ret);
+
+	prpsinfo = find_note(core, NT_PRPSINFO);
+	TEST_RES(prpsinfo != NULL && prpsinfo->pr_pid == pid &&
+			 strcmp(prpsinfo->pr_fname, "coredump") == 0,
+		 _ret);
+
+	siginfo = find_note(core, NT_SIGINFO);
+	TEST_RES(siginfo != NULL && siginfo->si_signo == SIGABRT, _ret);
+
+	TEST_RES(find_note(core, NT_AUXV) != NULL, _ret);
+	TEST_RES(find_note(core, NT_FILE) != NULL, _ret);
+
+	free(core);
+	TEST_SUCC(unlink(path));
+}
+END_TEST()
+
+static void madvise_dontdump(void)
+{
+	CHECK(madvise(mapping, MAP_SIZE, MADV_DONTDUMP));
+}
+
+static void madvise_dontdump_then_dodump(void)
+{
+	CHECK(madvise(mapping, MAP_SIZE, MADV_DONTDUMP));
+	CHECK(madvise(mapping, MAP_SIZE, MADV_DODUMP));
+}
+
+FN_TEST(madvise_dump)
+{
+	pid_t pid;
+
+	TEST_RES(crash_child(RLIM_INFINITY, madvise_dontdump, &pid),
+		 WCOREDUMP(_ret));
+	TEST_RES(dumped_mapping_size(pid), _ret == 0);
+
+	TEST_RES(crash_child(RLIM_INFINITY, madvise_dontdump_then_dodump, &pid),
+		 WCOREDUMP(_ret));
+	TEST_RES(dumped_mapping_size(pid), _ret == MAP_SIZE);
+}
+END_TEST()
+
+static void set_not_dumpable(void)
+{
+	CHECK(prctl(PR_SET_DUMPABLE, 0, 0, 0, 0));
+}
+
+static void change_euid(void)
+{
+	// Setting the same IDs keeps the process dumpable.
+	CHECK(setresuid(-1, geteuid(), -1));
+	CHECK_WITH(prctl(PR_GET_DUMPABLE, 0, 0, 0, 0), _ret == 1);
+
+	CHECK(setresuid(-1, 65534, -1));
+	CHECK_WITH(prctl(PR_GET_DUMPABLE, 0, 0, 0, 0), _ret == 0);
+}
+
+FN_TEST(dumpable)
+{
+	char path[64];
+	pid_t pid;
+
+	TEST_RES(prctl(PR_GET_DUMPABLE, 0, 0, 0, 0), _ret == 1);
+	TEST_ERRNO(prctl(PR_SET_DUMPABLE, 2, 0, 0, 0), EINVAL);
+
+	TEST_RES(crash_child(RLIM_INFINITY, set_not_dumpable, &pid),
+		 WIFSIGNALED(_ret) && WTERMSIG(_ret) == SIGABRT &&
+			 !WCOREDUMP(_ret));
+	core_path(path, sizeof(path), pid);
+	TEST_ERRNO(access(path, F_OK), ENOENT);
+
+	// Changing the effective UID makes the process non-dumpable.
+	TEST_RES(crash_child(RLIM_INFINITY, change_euid, &pid),
+		 WIFSIGNALED(_ret) && WTERMSIG(_ret) == SIGABRT &&
+			 !WCOREDUMP(_ret));
+	core_path(path, sizeof(path), pid);
+	TEST_ERRNO(access(path, F_OK), ENOENT);
+}
+END_TEST()
+
+static void clear_coredump_filter(void)
+{
+	CHECK(write_file(COREDUMP_FILTER, "0"));
+}
+
+FN_TEST(coredump_filter)
+{
+	pid_t pid;
+
+	TEST_RES(read_file(COREDUMP_FILTER),
+		 strcmp(file_buf, "00000033\n") == 0);
+
+	TEST_RES(write_file(COREDUMP_FILTER, "0x1ff\n"), _ret == 6);
+	TEST_RES(read_file(COREDUMP_FILTER),
+		 strcmp(file_buf, "000001ff\n") == 0);
+
+	// Octal numbers are accepted, and unknown bits are ignored.
+	TEST_RES(write_file(COREDUMP_FILTER, "07023"), _ret == 5);
+	TEST_RES(read_file(COREDUMP_FILTER),
+		 strcmp(file_buf, "00000013\n") == 0);
+
+	TEST_ERRNO(write_file(COREDUMP_FILTER, "abc"), EINVAL);
+	TEST_ERRNO(write_file(COREDUMP_FILTER, "-1"), EINVAL);
+	TEST_RES(read_file(COREDUMP_FILTER),
+		 strcmp(file_buf, "00000013\n") == 0);
+
+	TEST_RES(write_file(COREDUMP_FILTER, "51"), _ret == 2);
+	TEST_RES(read_file(COREDUMP_FILTER),
+		 strcmp(file_buf, "00000033\n") == 0);
+
+	// Anonymous private mappings are excluded without bit 0.
+	TEST_RES(crash_child(RLIM_INFINITY, clear_coredump_filter, &pid),
+		 WCOREDUMP(_ret));
+	TEST_RES(dumped_mapping_size(pid), _ret == 0);
+}
+END_TEST()
+
+/*
+ * Waits for the core dump helper to write the core file at `path`.
+ */
+static int wait_for_file(const char *path)
+{
+	int i;
+
+	for (i = 0; i < 3000; i++) {
+		if (access(path, F_OK) == 0)
+			return 0;
+		usleep(10 * 1000);
+	}
+
+	errno = ENOENT;
+	return -1;
+}
+
+FN_TEST(core_pattern)
+{
+	char pattern[PATH_MAX + 64];
+	char path[64];
+	size_t len;
+	char *core;
+	pid_t pid;
+
+	TEST_RES(read_file(CORE_PATTERN),
+		 strcmp(file_buf, "core.%p\n") == 0);
+
+	// Specifiers are expanded, and unknown specifiers are dropped.
+	TEST_RES(write_file(CORE_PATTERN, "dump-%s-%p-%%-%z\n"), _ret == 17);
+	TEST_RES(read_file(CORE_PATTERN),
+		 strcmp(file_buf, "dump-%s-%p-%%-%z\n") == 0);
+	TEST_RES(crash_child(RLIM_INFINITY, NULL, &pid), WCOREDUMP(_ret));
+	CHECK_WITH(snprintf(path, sizeof(path), "dump-%d-%d-%%-", SIGABRT, pid),
+		   _ret > 0 && (size_t)_ret < sizeof(path));
+	TEST_SUCC(unlink(path));
+
+	// The core dump is piped to the helper, regardless of `RLIMIT_CORE`.
+	CHECK_WITH(snprintf(pattern, sizeof(pattern), "|%s %s/piped.%%s",
+			    helper_path, work_dir),
+		   _ret > 0 && (size_t)_ret < sizeof(pattern));
+	TEST_SUCC(write_file(CORE_PATTERN, pattern));
+	TEST_RES(crash_child(0, NULL, &pid), WCOREDUMP(_ret));
+	CHECK_WITH(snprintf(path, sizeof(path), "piped.%d", SIGABRT),
+		   _ret > 0 && (size_t)_ret < sizeof(path));
+	TEST_SUCC(wait_for_file(path));
+	core = CHECK_WITH(read_core(path, &len), _ret != NULL);
+	TEST_RES(is_valid_core(core, len), _ret);
+	free(core);
+	TEST_SUCC(unlink(path));
+
+	// The helper does not exist, so no core dump is produced.
+	TEST_SUCC(write_file(CORE_PATTERN, "|/nonexistent"));
+	TEST_RES(crash_child(RLIM_INFINITY, NULL, &pid), !WCOREDUMP(_ret));
+
+	TEST_SUCC(write_file(CORE_PATTERN, saved_pattern));
+	TEST_SUCC(chdir("/"));
+	TEST_SUCC(rmdir(work_dir));
+}
+END_TEST()
@@ -0,0 +1,41 @@
+// SPDX-License-Identifier: MPL-2.0
+
+// A core dump helper that copies the core dump from its standard input to
+// the file specified by the first argument.
+//
+// The core dump is first written to a temporary file, which is renamed when
+// the copy completes, so that the test can wait for the file to appear.
+
+#include <fcntl.h>
+#include <limits.h>
+#include <stdio.h>
+#include <unistd.h>
+
+int main(int argc, char *argv[])
+{
+	char tmp_path[PATH_MAX];
+	char buf[4096];
+	ssize_t len;
+	int fd;
+
+	if (argc != 2)
+		return 1;
+
+	if (snprintf(tmp_path, sizeof(tmp_path), "%s.tmp", argv[1]) >=
+	    (int)sizeof(tmp_path))
+		return 1;
+
+	fd = open(tmp_path, O_WRONLY | O_CREAT | O_TRUNC, 0600);
+	if (fd < 0)
+		return 1;
+
+	while ((len = read(STDIN_FILENO, buf, sizeof(buf))) > 0) {
+		if (write(fd, buf, len) != len)
+			return 1;
+	}
+	if (len < 0)
+		return 1;
+
+	close(fd);
+	return rename(tmp_path, argv[1]) < 0;
+}
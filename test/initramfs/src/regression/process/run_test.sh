@@ -15,6 +15,8 @@ fi
 ./clone3/clone_parent
 ./clone3/clone_process
 
+./coredump/coredump
+
 ./cpu_affinity/cpu_affinity
 
 ./execve/execve
@@ -53,6 +55,7 @@ if [ "$(uname -m)" = "x86_64" ]; then
     ./ptrace/read_write_regs
 fi
 
+./sched/cgroup_cpu
 ./sched/sched_attr_getset
 ./sched/sched_param_getset
 ./sched/sched_param_idle
@@ -0,0 +1,186 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <sched.h>
+#include <stdio.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "../../common/file_util.h"
+#include "../../common/test.h"
+
+#define CGROUP_ROOT "/sys/fs/cgroup"
+#define TEST_CGROUP CGROUP_ROOT "/cpu-test"
+
+static int cpu_was_enabled;
+
+static long elapsed_ms(const struct timespec *start)
+{
+	struct timespec now;
+
+	clock_gettime(CLOCK_MONOTONIC, &now);
+	return (now.tv_sec - start->tv_sec) * 1000 +
+	       (now.tv_nsec - start->tv_nsec) / 1000000;
+}
+
+FN_SETUP(create_cgroup)
+{
+	CHECK(read_file(CGROUP_ROOT "/cgroup.subtree_control"));
+	cpu_was_enabled = strstr(file_buf, "cpu ") != NULL ||
+			  strstr(file_buf, "cpu\n") != NULL;
+	if (!cpu_was_enabled)
+		CHECK(write_file(CGROUP_ROOT "/cgroup.subtree_control",
+				 "+cpu"));
+
+	CHECK(mkdir(TEST_CGROUP, 0755));
+}
+END_SETUP()
+
+FN_TEST(invalid_limits)
+{
+	TEST_ERRNO(write_file(TEST_CGROUP "/cpu.weight", "0"), EINVAL);
+	TEST_ERRNO(write_file(TEST_CGROUP "/cpu.weight", "10001"), EINVAL);
+	TEST_ERRNO(write_file(TEST_CGROUP "/cpu.max", "999 100000"), EINVAL);
+	TEST_ERRNO(write_file(TEST_CGROUP "/cpu.max", "10000 999"), EINVAL);
+
+	TEST_RES(read_file(TEST_CGROUP "/cpu.weight"),
+		 strcmp(file_buf, "100\n") == 0);
+	TEST_RES(read_file(TEST_CGROUP "/cpu.max"),
+		 strcmp(file_buf, "max 100000\n") == 0);
+}
+END_TEST()
+
+FN_TEST(unlimited_not_throttled)
+{
+	TEST_RES(read_key(TEST_CGROUP "/cpu.stat", "nr_periods"), _ret == 0);
+	TEST_RES(read_key(TEST_CGROUP "/cpu.stat", "nr_throttled"), _ret == 0);
+	TEST_RES(read_key(TEST_CGROUP "/cpu.stat", "throttled_usec"),
+		 _ret == 0);
+}
+END_TEST()
+
+FN_TEST(throttle_busy_loop)
+{
+	struct timespec start;
+	char pid_text[32];
+	int status;
+	pid_t pid;
+
+	// Allow 10ms of CPU time in every 50ms.
+	TEST_RES(write_file(TEST_CGROUP "/cpu.max", "10000 50000"), _ret == 11);
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		snprintf(pid_text, sizeof(pid_text), "%d", getpid());
+		if (write_file(TEST_CGROUP "/cgroup.procs", pid_text) < 0)
+			_exit(1);
+
+		clock_gettime(CLOCK_MONOTONIC, &start);
+		while (elapsed_ms(&start) < 500)
+			;
+		_exit(0);
+	}
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) && WEXITSTATUS(status) == 0);
+
+	TEST_RES(read_key(TEST_CGROUP "/cpu.stat", "nr_periods"), _ret > 1);
+	TEST_RES(read_key(TEST_CGROUP "/cpu.stat", "nr_throttled"), _ret > 1);
+	// The loop runs for at most 20% of the time, so it is throttled for
+	// most of the 500ms.
+	TEST_RES(read_key(TEST_CGROUP "/cpu.stat", "throttled_usec"),
+		 _ret > 200000);
+
+	TEST_RES(write_file(TEST_CGROUP "/cpu.max", "max"), _ret == 3);
+}
+END_TEST()
+
+static pid_t spawn_busy_loop(const char *cgroup, int ready_fd)
+{
+	struct timespec start;
+	char pid_text[32];
+	cpu_set_t cpus;
+	pid_t pid;
+	char c;
+
+	pid = CHECK(fork());
+	if (pid != 0)
+		return pid;
+
+	// Run on the same CPU so that the busy loops compete with each other.
+	CPU_ZERO(&cpus);
+	CPU_SET(0, &cpus);
+	if (sched_setaffinity(0, sizeof(cpus), &cpus) < 0)
+		_exit(1);
+
+	snprintf(pid_text, sizeof(pid_text), "%d", getpid());
+	if (write_file(cgroup, pid_text) < 0)
+		_exit(1);
+
+	// Wait until both busy loops are in their cgroups.
+	if (read(ready_fd, &c, 1) != 0)
+		_exit(1);
+
+	clock_gettime(CLOCK_MONOTONIC, &start);
+	while (elapsed_ms(&start) < 1000)
+		;
+	_exit(0);
+}
+
+FN_TEST(weight_fairness)
+{
+	long light_usage, heavy_usage;
+	int ready_fds[2];
+	pid_t pids[2];
+	int status, i;
+
+	TEST_RES(write_file(TEST_CGROUP "/cgroup.subtree_control", "+cpu"),
+		 _ret == 4);
+	TEST_SUCC(mkdir(TEST_CGROUP "/light", 0755));
+	TEST_SUCC(mkdir(TEST_CGROUP "/heavy", 0755));
+	TEST_RES(write_file(TEST_CGROUP "/light/cpu.weight", "100"),
+		 _ret == 3);
+	TEST_RES(write_file(TEST_CGROUP "/heavy/cpu.weight", "300"),
+		 _ret == 3);
+
+	TEST_SUCC(pipe(ready_fds));
+	pids[0] = spawn_busy_loop(TEST_CGROUP "/light/cgroup.procs",
+				  ready_fds[0]);
+	pids[1] = spawn_busy_loop(TEST_CGROUP "/heavy/cgroup.procs",
+				  ready_fds[0]);
+	TEST_SUCC(close(ready_fds[0]));
+	TEST_SUCC(close(ready_fds[1]));
+
+	for (i = 0; i < 2; i++)
+		TEST_RES(waitpid(pids[i], &status, 0),
+			 _ret == pids[i] && WIFEXITED(status) &&
+				 WEXITSTATUS(status) == 0);
+
+	// The heavy cgroup should get about three times as much CPU time as
+	// the light one.
+	light_usage = TEST_RES(
+		read_key(TEST_CGROUP "/light/cpu.stat", "usage_usec"),
+		_ret > 0);
+	heavy_usage = TEST_RES(
+		read_key(TEST_CGROUP "/heavy/cpu.stat", "usage_usec"),
+		_ret > 0);
+	TEST_RES(0, heavy_usage > light_usage * 2);
+
+	TEST_SUCC(rmdir(TEST_CGROUP "/light"));
+	TEST_SUCC(rmdir(TEST_CGROUP "/heavy"));
+	TEST_RES(write_file(TEST_CGROUP "/cgroup.subtree_control", "-cpu"),
+		 _ret == 4);
+}
+END_TEST()
+
+FN_SETUP(remove_cgroup)
+{
+	CHECK(rmdir(TEST_CGROUP));
+
+	if (!cpu_was_enabled)
+		CHECK(write_file(CGROUP_ROOT "/cgroup.subtree_control",
+				 "-cpu"));
+}
+END_SETUP()
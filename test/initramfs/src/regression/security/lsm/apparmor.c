@@ -0,0 +1,271 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <signal.h>
+#include <stdbool.h>
+#include <sys/mount.h>
+#include <sys/sendfile.h>
+#include <sys/socket.h>
+#include <sys/stat.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../../common/file_util.h"
+#include "../../common/test.h"
+
+#define APPARMOR_DIR "/sys/kernel/security/apparmor"
+#define LOAD_PATH APPARMOR_DIR "/.load"
+#define REPLACE_PATH APPARMOR_DIR "/.replace"
+#define REMOVE_PATH APPARMOR_DIR "/.remove"
+#define PROFILES_PATH APPARMOR_DIR "/profiles"
+
+#define TEST_DIR "/tmp/apparmor_test"
+#define CONFINED_PATH TEST_DIR "/confined"
+#define ALLOWED_PATH TEST_DIR "/allowed"
+#define DENIED_PATH TEST_DIR "/denied"
+#define NEW_PATH TEST_DIR "/new"
+#define MNT_PATH TEST_DIR "/mnt"
+
+// The environment variable that makes the test program perform one operation
+// and exit with the resulting error number.
+#define OP_ENV "APPARMOR_TEST_OP"
+
+#define PROFILE_BODY                                    \
+	" {\n"                                          \
+	"  /** mr,\n"                                   \
+	"  deny " DENIED_PATH " r,\n"                   \
+	"  " NEW_PATH " w,\n"                           \
+	"  network inet stream,\n"                      \
+	"  signal send set=(exists) peer=unconfined,\n" \
+	"  mount fstype=ramfs -> " MNT_PATH "/,\n"      \
+	"}\n"
+
+#define ENFORCE_POLICY                                      \
+	"# A profile that attaches to the test program.\n"  \
+	"profile apparmor_test " CONFINED_PATH PROFILE_BODY
+#define COMPLAIN_POLICY                                            \
+	"profile apparmor_test " CONFINED_PATH " flags=(complain)" \
+		PROFILE_BODY
+
+static int result_of(int ret)
+{
+	return ret < 0 ? errno : 0;
+}
+
+static int perform_op(const char *op)
+{
+	if (strcmp(op, "read_allowed") == 0)
+		return result_of(open(ALLOWED_PATH, O_RDONLY));
+	if (strcmp(op, "write_allowed") == 0)
+		return result_of(open(ALLOWED_PATH, O_WRONLY));
+	if (strcmp(op, "read_denied") == 0)
+		return result_of(open(DENIED_PATH, O_RDONLY));
+	if (strcmp(op, "create_new") == 0)
+		return result_of(open(NEW_PATH, O_WRONLY | O_CREAT, 0644));
+	if (strcmp(op, "mkdir") == 0)
+		return result_of(mkdir(TEST_DIR "/dir", 0755));
+	if (strcmp(op, "socket_stream") == 0)
+		return result_of(socket(AF_INET, SOCK_STREAM, 0));
+	if (strcmp(op, "socket_dgram") == 0)
+		return result_of(socket(AF_INET, SOCK_DGRAM, 0));
+	if (strcmp(op, "signal_exists") == 0)
+		return result_of(kill(getppid(), 0));
+	if (strcmp(op, "signal_usr1") == 0)
+		return result_of(kill(getppid(), SIGUSR1));
+	if (strcmp(op, "mount_ramfs") == 0)
+		return result_of(mount("none", MNT_PATH, "ramfs", 0, NULL));
+	if (strcmp(op, "mount_tmpfs") == 0)
+		return result_of(mount("none", MNT_PATH, "tmpfs", 0, NULL));
+	if (strcmp(op, "umount") == 0)
+		return result_of(umount(MNT_PATH));
+	if (strcmp(op, "exec") == 0) {
+		// The profile has no rules that allow executing programs.
+		setenv(OP_ENV, "read_allowed", 1);
+		execl(CONFINED_PATH, CONFINED_PATH, NULL);
+		return errno;
+	}
+
+	return EINVAL;
+}
+
+// This runs before the setup and test functions, so the copy of the test
+// program performs the requested operation without running the tests.
+__attribute__((constructor(101))) static void run_op(void)
+{
+	const char *op = getenv(OP_ENV);
+
+	if (op != NULL)
+		_exit(perform_op(op));
+}
+
+// Runs the confined copy of the test program to perform an operation and
+// returns the resulting error number.
+static int run_confined(const char *op)
+{
+	int status;
+	pid_t pid;
+
+	pid = CHECK(fork());
+	if (pid == 0) {
+		CHECK(setenv(OP_ENV, op, 1));
+		execl(CONFINED_PATH, CONFINED_PATH, NULL);
+		_exit(EXIT_FAILURE);
+	}
+
+	CHECK_WITH(waitpid(pid, &status, 0), _ret == pid);
+	CHECK_WITH(WIFEXITED(status), _ret);
+	return WEXITSTATUS(status);
+}
+
+static bool profiles_contain(const char *line)
+{
+	CHECK(read_file(PROFILES_PATH));
+
+	return strstr(file_buf, line) != NULL;
+}
+
+static void copy_file(const char *src, const char *dst)
+{
+	struct stat statbuf;
+	int src_fd, dst_fd;
+	off_t offset = 0;
+
+	src_fd = CHECK(open(src, O_RDONLY));
+	CHECK(fstat(src_fd, &statbuf));
+	dst_fd = CHECK(open(dst, O_WRONLY | O_CREAT | O_EXCL, 0755));
+	while (offset < statbuf.st_size)
+		CHECK(sendfile(dst_fd, src_fd, &offset,
+			       statbuf.st_size - offset));
+	CHECK(close(dst_fd));
+	CHECK(close(src_fd));
+}
+
+static bool apparmor_disabled;
+
+FN_SETUP(apparmor_enabled)
+{
+#ifdef __asterinas__
+	apparmor_disabled = access(APPARMOR_DIR, F_OK) < 0;
+#else
+	// Linux only loads policies compiled by `apparmor_parser`.
+	apparmor_disabled = true;
+#endif
+}
+END_SETUP()
+
+FN_SETUP(test_files)
+{
+	if (apparmor_disabled)
+		return;
+
+	CHECK(mkdir(TEST_DIR, 0755));
+	CHECK(mkdir(MNT_PATH, 0755));
+	CHECK(close(CHECK(open(ALLOWED_PATH, O_WRONLY | O_CREAT, 0644))));
+	CHECK(close(CHECK(open(DENIED_PATH, O_WRONLY | O_CREAT, 0644))));
+	copy_file("/proc/self/exe", CONFINED_PATH);
+
+	// A signal that the profile fails to deny should not kill the test.
+	CHECK_WITH(signal(SIGUSR1, SIG_IGN), _ret != SIG_ERR);
+}
+END_SETUP()
+
+FN_TEST(load_policy)
+{
+	SKIP_TEST_IF(apparmor_disabled);
+
+	TEST_ERRNO(write_file(LOAD_PATH, "profile bad {\n  /tmp rz,\n}\n"),
+		   EINVAL);
+	TEST_ERRNO(write_file(LOAD_PATH, "profile bad {\n"), EINVAL);
+	TEST_ERRNO(write_file(LOAD_PATH, "/tmp/a r,\n"), EINVAL);
+
+	TEST_RES(write_file(LOAD_PATH, ENFORCE_POLICY),
+		 _ret == strlen(ENFORCE_POLICY));
+	TEST_ERRNO(write_file(LOAD_PATH, ENFORCE_POLICY), EEXIST);
+	TEST_RES(write_file(REPLACE_PATH, ENFORCE_POLICY),
+		 _ret == strlen(ENFORCE_POLICY));
+
+	TEST_RES(profiles_contain("apparmor_test (enforce)\n"), _ret);
+}
+END_TEST()
+
+FN_TEST(enforce_mode)
+{
+	SKIP_TEST_IF(apparmor_disabled);
+
+	TEST_RES(run_confined("read_allowed"), _ret == 0);
+	TEST_RES(run_confined("write_allowed"), _ret == EACCES);
+	TEST_RES(run_confined("read_denied"), _ret == EACCES);
+	TEST_RES(run_confined("create_new"), _ret == 0);
+	TEST_RES(run_confined("mkdir"), _ret == EACCES);
+
+	TEST_RES(run_confined("socket_stream"), _ret == 0);
+	TEST_RES(run_confined("socket_dgram"), _ret == EACCES);
+
+	TEST_RES(run_confined("signal_exists"), _ret == 0);
+	TEST_RES(run_confined("signal_usr1"), _ret == EACCES);
+
+	TEST_RES(run_confined("mount_tmpfs"), _ret == EACCES);
+	TEST_RES(run_confined("mount_ramfs"), _ret == 0);
+	TEST_RES(run_confined("umount"), _ret == EACCES);
+	TEST_SUCC(umount(MNT_PATH));
+
+	TEST_RES(run_confined("exec"), _ret == EACCES);
+
+	// The test program itself is not confined.
+	TEST_SUCC(close(TEST_SUCC(open(DENIED_PATH, O_RDWR))));
+	TEST_SUCC(mkdir(TEST_DIR "/dir", 0755));
+	TEST_SUCC(rmdir(TEST_DIR "/dir"));
+	TEST_SUCC(unlink(NEW_PATH));
+}
+END_TEST()
+
+FN_TEST(complain_mode)
+{
+	SKIP_TEST_IF(apparmor_disabled);
+
+	TEST_RES(write_file(REPLACE_PATH, COMPLAIN_POLICY),
+		 _ret == strlen(COMPLAIN_POLICY));
+	TEST_RES(profiles_contain("apparmor_test (complain)\n"), _ret);
+
+	TEST_RES(run_confined("read_denied"), _ret == 0);
+	TEST_RES(run_confined("mkdir"), _ret == 0);
+	TEST_RES(run_confined("socket_dgram"), _ret == 0);
+	TEST_RES(run_confined("mount_tmpfs"), _ret == 0);
+	TEST_RES(run_confined("umount"), _ret == 0);
+
+	TEST_SUCC(rmdir(TEST_DIR "/dir"));
+}
+END_TEST()
+
+FN_TEST(remove_profile)
+{
+	SKIP_TEST_IF(apparmor_disabled);
+
+	TEST_RES(write_file(REPLACE_PATH, ENFORCE_POLICY),
+		 _ret == strlen(ENFORCE_POLICY));
+	TEST_RES(run_confined("read_denied"), _ret == EACCES);
+
+	TEST_ERRNO(write_file(REMOVE_PATH, "no_such_profile\n"), ENOENT);
+	TEST_RES(write_file(REMOVE_PATH, "apparmor_test\n"),
+		 _ret == strlen("apparmor_test\n"));
+	TEST_RES(profiles_contain("apparmor_test"), !_ret);
+
+	TEST_RES(run_confined("read_denied"), _ret == 0);
+	TEST_ERRNO(write_file(REMOVE_PATH, "apparmor_test\n"), ENOENT);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	if (apparmor_disabled)
+		return;
+
+	CHECK(unlink(CONFINED_PATH));
+	CHECK(unlink(DENIED_PATH));
+	CHECK(unlink(ALLOWED_PATH));
+	CHECK(rmdir(MNT_PATH));
+	CHECK(rmdir(TEST_DIR));
+}
+END_SETUP()
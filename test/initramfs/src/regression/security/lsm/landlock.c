@@ -0,0 +1,537 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <netinet/in.h>
+#include <stdint.h>
+#include <sys/prctl.h>
+#include <sys/socket.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../../common/capability.h"
+
+// The definitions below follow `include/uapi/linux/landlock.h` in Linux, which
+// may be missing or outdated in the C library headers.
+
+#define SYS_LANDLOCK_CREATE_RULESET 444
+#define SYS_LANDLOCK_ADD_RULE 445
+#define SYS_LANDLOCK_RESTRICT_SELF 446
+
+#define CREATE_RULESET_VERSION (1U << 0)
+
+#define RULE_PATH_BENEATH 1
+#define RULE_NET_PORT 2
+
+#define ACCESS_FS_EXECUTE (1ULL << 0)
+#define ACCESS_FS_WRITE_FILE (1ULL << 1)
+#define ACCESS_FS_READ_FILE (1ULL << 2)
+#define ACCESS_FS_READ_DIR (1ULL << 3)
+#define ACCESS_FS_REMOVE_DIR (1ULL << 4)
+#define ACCESS_FS_REMOVE_FILE (1ULL << 5)
+#define ACCESS_FS_MAKE_DIR (1ULL << 7)
+#define ACCESS_FS_MAKE_REG (1ULL << 8)
+#define ACCESS_FS_REFER (1ULL << 13)
+#define ACCESS_FS_TRUNCATE (1ULL << 14)
+
+#define ACCESS_NET_BIND_TCP (1ULL << 0)
+#define ACCESS_NET_CONNECT_TCP (1ULL << 1)
+
+struct ruleset_attr {
+	uint64_t handled_access_fs;
+	uint64_t handled_access_net;
+};
+
+struct path_beneath_attr {
+	uint64_t allowed_access;
+	int32_t parent_fd;
+} __attribute__((packed));
+
+struct net_port_attr {
+	uint64_t allowed_access;
+	uint64_t port;
+};
+
+#define TEST_DIR "/tmp/landlock_test"
+#define DIR_A TEST_DIR "/a"
+#define DIR_B TEST_DIR "/b"
+
+#define PORT_ALLOWED 40123
+#define PORT_DENIED 40124
+
+static int create_ruleset(uint64_t handled_fs, uint64_t handled_net)
+{
+	struct ruleset_attr attr = {
+		.handled_access_fs = handled_fs,
+		.handled_access_net = handled_net,
+	};
+
+	return syscall(SYS_LANDLOCK_CREATE_RULESET, &attr, sizeof(attr), 0);
+}
+
+static int add_path_rule(int ruleset_fd, uint64_t access, int parent_fd)
+{
+	struct path_beneath_attr attr = {
+		.allowed_access = access,
+		.parent_fd = parent_fd,
+	};
+
+	return syscall(SYS_LANDLOCK_ADD_RULE, ruleset_fd, RULE_PATH_BENEATH,
+		       &attr, 0);
+}
+
+static void allow_path(int ruleset_fd, uint64_t access, const char *path)
+{
+	int fd = CHECK(open(path, O_PATH | O_CLOEXEC));
+	CHECK(add_path_rule(ruleset_fd, access, fd));
+	CHECK(close(fd));
+}
+
+static int add_port_rule(int ruleset_fd, uint64_t access, uint64_t port)
+{
+	struct net_port_attr attr = {
+		.allowed_access = access,
+		.port = port,
+	};
+
+	return syscall(SYS_LANDLOCK_ADD_RULE, ruleset_fd, RULE_NET_PORT, &attr,
+		       0);
+}
+
+static int restrict_self(int ruleset_fd, unsigned int flags)
+{
+	return syscall(SYS_LANDLOCK_RESTRICT_SELF, ruleset_fd, flags);
+}
+
+static void enforce(int ruleset_fd)
+{
+	CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+	CHECK(restrict_self(ruleset_fd, 0));
+	CHECK(close(ruleset_fd));
+}
+
+static int wait_for_child(pid_t pid)
+{
+	int status;
+
+	CHECK_WITH(waitpid(pid, &status, 0), _ret == pid);
+	if (!WIFEXITED(status) || WEXITSTATUS(status) != EXIT_SUCCESS) {
+		errno = ECHILD;
+		return -1;
+	}
+	return 0;
+}
+
+static int create_file(const char *path)
+{
+	int fd = open(path, O_WRONLY | O_CREAT | O_EXCL, 0644);
+	if (fd < 0)
+		return -1;
+	return close(fd);
+}
+
+#define CHECK_ERRNO(func, err) CHECK_WITH(func, _ret < 0 && errno == (err))
+
+FN_SETUP(test_dirs)
+{
+	CHECK(mkdir(TEST_DIR, 0755));
+	CHECK(mkdir(DIR_A, 0755));
+	CHECK(mkdir(DIR_B, 0755));
+	CHECK(mkdir(DIR_A "/sub", 0755));
+	CHECK(mkdir(DIR_B "/sub", 0755));
+	CHECK(create_file(DIR_A "/file"));
+	CHECK(create_file(DIR_B "/file"));
+}
+END_SETUP()
+
+FN_TEST(abi_version)
+{
+	TEST_RES(syscall(SYS_LANDLOCK_CREATE_RULESET, NULL, 0,
+			 CREATE_RULESET_VERSION),
+		 _ret >= 4);
+
+	TEST_ERRNO(syscall(SYS_LANDLOCK_CREATE_RULESET, NULL, 0, 1U << 31),
+		   EINVAL);
+	TEST_ERRNO(syscall(SYS_LANDLOCK_CREATE_RULESET, NULL, 16,
+			   CREATE_RULESET_VERSION),
+		   EINVAL);
+}
+END_TEST()
+
+FN_TEST(create_ruleset)
+{
+	struct ruleset_attr attr = { .handled_access_fs = ACCESS_FS_READ_FILE };
+
+	TEST_ERRNO(syscall(SYS_LANDLOCK_CREATE_RULESET, NULL, sizeof(attr), 0),
+		   EFAULT);
+	TEST_ERRNO(syscall(SYS_LANDLOCK_CREATE_RULESET, &attr, 4, 0), EINVAL);
+	TEST_ERRNO(create_ruleset(0, 0), ENOMSG);
+	TEST_ERRNO(create_ruleset(1ULL << 63, 0), EINVAL);
+	TEST_ERRNO(create_ruleset(0, 1ULL << 63), EINVAL);
+
+	// Only the handled file system access rights are specified.
+	int fd = TEST_SUCC(
+		syscall(SYS_LANDLOCK_CREATE_RULESET, &attr, sizeof(uint64_t), 0));
+	TEST_RES(fcntl(fd, F_GETFD), _ret == FD_CLOEXEC);
+	TEST_SUCC(close(fd));
+}
+END_TEST()
+
+FN_TEST(add_rule)
+{
+	int ruleset_fd = TEST_SUCC(create_ruleset(
+		ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR, ACCESS_NET_BIND_TCP));
+	int dir_fd = TEST_SUCC(open(DIR_A, O_PATH));
+	int file_fd = TEST_SUCC(open(DIR_A "/file", O_RDONLY));
+	int pipe_fds[2];
+	TEST_SUCC(pipe(pipe_fds));
+
+	struct path_beneath_attr attr = {
+		.allowed_access = ACCESS_FS_READ_FILE,
+		.parent_fd = dir_fd,
+	};
+	TEST_ERRNO(syscall(SYS_LANDLOCK_ADD_RULE, ruleset_fd, RULE_PATH_BENEATH,
+			   &attr, 1),
+		   EINVAL);
+	TEST_ERRNO(syscall(SYS_LANDLOCK_ADD_RULE, ruleset_fd, 100, &attr, 0),
+		   EINVAL);
+
+	TEST_ERRNO(add_path_rule(-1, ACCESS_FS_READ_FILE, dir_fd), EBADF);
+	TEST_ERRNO(add_path_rule(dir_fd, ACCESS_FS_READ_FILE, dir_fd), EBADF);
+	TEST_ERRNO(add_path_rule(file_fd, ACCESS_FS_READ_FILE, dir_fd), EBADFD);
+
+	TEST_ERRNO(add_path_rule(ruleset_fd, 0, dir_fd), ENOMSG);
+	TEST_ERRNO(add_path_rule(ruleset_fd, ACCESS_FS_EXECUTE, dir_fd),
+		   EINVAL);
+	TEST_ERRNO(add_path_rule(ruleset_fd, ACCESS_FS_READ_FILE, -1), EBADF);
+	TEST_ERRNO(add_path_rule(ruleset_fd, ACCESS_FS_READ_FILE, pipe_fds[0]),
+		   EBADFD);
+	TEST_ERRNO(add_path_rule(ruleset_fd, ACCESS_FS_READ_FILE, ruleset_fd),
+		   EBADFD);
+	TEST_ERRNO(add_path_rule(ruleset_fd, ACCESS_FS_READ_DIR, file_fd),
+		   EINVAL);
+	TEST_SUCC(add_path_rule(ruleset_fd, ACCESS_FS_READ_FILE, file_fd));
+	TEST_SUCC(add_path_rule(ruleset_fd,
+				ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR,
+				dir_fd));
+
+	TEST_ERRNO(add_port_rule(ruleset_fd, 0, PORT_ALLOWED), ENOMSG);
+	TEST_ERRNO(add_port_rule(ruleset_fd, ACCESS_NET_CONNECT_TCP,
+				 PORT_ALLOWED),
+		   EINVAL);
+	TEST_ERRNO(add_port_rule(ruleset_fd, ACCESS_NET_BIND_TCP, 65536),
+		   EINVAL);
+	TEST_SUCC(add_port_rule(ruleset_fd, ACCESS_NET_BIND_TCP, PORT_ALLOWED));
+
+	TEST_SUCC(close(pipe_fds[0]));
+	TEST_SUCC(close(pipe_fds[1]));
+	TEST_SUCC(close(file_fd));
+	TEST_SUCC(close(dir_fd));
+	TEST_SUCC(close(ruleset_fd));
+}
+END_TEST()
+
+FN_TEST(restrict_self)
+{
+	int ruleset_fd = TEST_SUCC(create_ruleset(ACCESS_FS_READ_FILE, 0));
+	int dir_fd = TEST_SUCC(open(DIR_A, O_RDONLY | O_DIRECTORY));
+
+	pid_t pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		drop_capability(CAP_SYS_ADMIN);
+		CHECK_ERRNO(restrict_self(ruleset_fd, 0), EPERM);
+
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+		CHECK_ERRNO(restrict_self(ruleset_fd, 1U << 31), EINVAL);
+		CHECK_ERRNO(restrict_self(-1, 0), EBADF);
+		CHECK_ERRNO(restrict_self(dir_fd, 0), EBADFD);
+		CHECK(restrict_self(ruleset_fd, 0));
+		exit(EXIT_SUCCESS);
+	}
+	TEST_SUCC(wait_for_child(pid));
+
+	TEST_SUCC(close(dir_fd));
+	TEST_SUCC(close(ruleset_fd));
+}
+END_TEST()
+
+FN_TEST(too_many_layers)
+{
+	pid_t pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		int ruleset_fd = CHECK(create_ruleset(ACCESS_FS_READ_FILE, 0));
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+		for (int i = 0; i < 16; i++)
+			CHECK(restrict_self(ruleset_fd, 0));
+		CHECK_ERRNO(restrict_self(ruleset_fd, 0), E2BIG);
+		exit(EXIT_SUCCESS);
+	}
+	TEST_SUCC(wait_for_child(pid));
+}
+END_TEST()
+
+FN_TEST(read_write)
+{
+	int ruleset_fd = TEST_SUCC(create_ruleset(
+		ACCESS_FS_READ_FILE | ACCESS_FS_WRITE_FILE | ACCESS_FS_READ_DIR,
+		0));
+
+	pid_t pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		allow_path(ruleset_fd, ACCESS_FS_READ_FILE | ACCESS_FS_READ_DIR,
+			   DIR_A);
+		allow_path(ruleset_fd, ACCESS_FS_WRITE_FILE, DIR_A "/file");
+		enforce(ruleset_fd);
+
+		CHECK(close(CHECK(open(DIR_A "/file", O_RDWR))));
+		CHECK(close(CHECK(open(DIR_A "/sub", O_RDONLY | O_DIRECTORY))));
+		CHECK_ERRNO(open(DIR_B "/file", O_RDONLY), EACCES);
+		CHECK_ERRNO(open(DIR_B "/file", O_WRONLY), EACCES);
+		CHECK_ERRNO(open(DIR_B, O_RDONLY | O_DIRECTORY), EACCES);
+		CHECK_ERRNO(open(TEST_DIR, O_RDONLY | O_DIRECTORY), EACCES);
+
+		// `O_PATH` does not open the file for reading or writing.
+		CHECK(close(CHECK(open(DIR_B "/file", O_PATH))));
+		exit(EXIT_SUCCESS);
+	}
+	TEST_SUCC(wait_for_child(pid));
+
+	// The restrictions only apply to the child process.
+	TEST_SUCC(close(TEST_SUCC(open(DIR_B "/file", O_RDWR))));
+	TEST_SUCC(close(ruleset_fd));
+}
+END_TEST()
+
+FN_TEST(make_remove)
+{
+	int ruleset_fd = TEST_SUCC(create_ruleset(
+		ACCESS_FS_MAKE_REG | ACCESS_FS_MAKE_DIR | ACCESS_FS_REMOVE_FILE |
+			ACCESS_FS_REMOVE_DIR,
+		0));
+
+	pid_t pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		allow_path(ruleset_fd,
+			   ACCESS_FS_MAKE_REG | ACCESS_FS_MAKE_DIR |
+				   ACCESS_FS_REMOVE_FILE | ACCESS_FS_REMOVE_DIR,
+			   DIR_A);
+		enforce(ruleset_fd);
+
+		CHECK(create_file(DIR_A "/new_file"));
+		CHECK(unlink(DIR_A "/new_file"));
+		CHECK(mkdir(DIR_A "/new_dir", 0755));
+		CHECK(rmdir(DIR_A "/new_dir"));
+
+		CHECK_ERRNO(create_file(DIR_B "/new_file"), EACCES);
+		CHECK_ERRNO(mknod(DIR_B "/new_file", S_IFREG | 0644, 0), EACCES);
+		CHECK_ERRNO(mkdir(DIR_B "/new_dir", 0755), EACCES);
+		CHECK_ERRNO(unlink(DIR_B "/file"), EACCES);
+		CHECK_ERRNO(rmdir(DIR_B "/sub"), EACCES);
+		CHECK_ERRNO(rename(DIR_B "/file", DIR_B "/renamed"), EACCES);
+
+		// Opening an existing file with `O_CREAT` creates nothing.
+		CHECK(close(CHECK(open(DIR_B "/file", O_RDONLY | O_CREAT, 0644))));
+		exit(EXIT_SUCCESS);
+	}
+	TEST_SUCC(wait_for_child(pid));
+
+	TEST_SUCC(close(ruleset_fd));
+}
+END_TEST()
+
+FN_TEST(refer)
+{
+	TEST_SUCC(create_file(DIR_A "/refer_file"));
+	TEST_SUCC(create_file(DIR_B "/refer_file"));
+
+	int ruleset_fd = TEST_SUCC(create_ruleset(
+		ACCESS_FS_READ_FILE | ACCESS_FS_MAKE_REG |
+			ACCESS_FS_REMOVE_FILE | ACCESS_FS_REFER,
+		0));
+
+	pid_t pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		allow_path(ruleset_fd,
+			   ACCESS_FS_MAKE_REG | ACCESS_FS_REMOVE_FILE |
+				   ACCESS_FS_REFER,
+			   TEST_DIR);
+		allow_path(ruleset_fd, ACCESS_FS_READ_FILE, DIR_B);
+		enforce(ruleset_fd);
+
+		// Moving a file to `DIR_B` would allow reading it.
+		CHECK_ERRNO(rename(DIR_A "/refer_file", DIR_B "/moved"), EXDEV);
+		CHECK_ERRNO(link(DIR_A "/refer_file", DIR_B "/linked"), EXDEV);
+		CHECK_ERRNO(renameat2(AT_FDCWD, DIR_A "/refer_file", AT_FDCWD,
+				      DIR_B "/refer_file", RENAME_EXCHANGE),
+			    EXDEV);
+
+		// Moving a file out of `DIR_B` grants no more access rights.
+		CHECK(rename(DIR_B "/refer_file", DIR_A "/moved"));
+		CHECK(link(DIR_A "/moved", DIR_A "/linked"));
+		CHECK(unlink(DIR_A "/linked"));
+		CHECK(unlink(DIR_A "/moved"));
+		exit(EXIT_SUCCESS);
+	}
+	TEST_SUCC(wait_for_child(pid));
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		// Files cannot be moved between directories unless
+		// `ACCESS_FS_REFER` is handled and allowed.
+		int ruleset_fd = CHECK(create_ruleset(ACCESS_FS_READ_FILE, 0));
+		enforce(ruleset_fd);
+
+		CHECK(rename(DIR_A "/refer_file", DIR_A "/renamed"));
+		CHECK_ERRNO(rename(DIR_A "/renamed", DIR_B "/renamed"), EXDEV);
+		CHECK(rename(DIR_A "/renamed", DIR_A "/refer_file"));
+		exit(EXIT_SUCCESS);
+	}
+	TEST_SUCC(wait_for_child(pid));
+
+	TEST_SUCC(unlink(DIR_A "/refer_file"));
+	TEST_SUCC(close(ruleset_fd));
+}
+END_TEST()
+
+FN_TEST(truncate_and_execute)
+{
+	int ruleset_fd = TEST_SUCC(
+		create_ruleset(ACCESS_FS_TRUNCATE | ACCESS_FS_EXECUTE, 0));
+
+	pid_t pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		int fd_a, fd_b, fd_before;
+
+		// The right to truncate is decided when the file is opened.
+		fd_before = CHECK(open(DIR_B "/file", O_WRONLY));
+
+		allow_path(ruleset_fd, ACCESS_FS_TRUNCATE, DIR_A);
+		enforce(ruleset_fd);
+
+		CHECK(truncate(DIR_A "/file", 0));
+		CHECK(close(CHECK(open(DIR_A "/file", O_WRONLY | O_TRUNC))));
+		CHECK_ERRNO(truncate(DIR_B "/file", 0), EACCES);
+		CHECK_ERRNO(open(DIR_B "/file", O_WRONLY | O_TRUNC), EACCES);
+
+		fd_a = CHECK(open(DIR_A "/file", O_WRONLY));
+		fd_b = CHECK(open(DIR_B "/file", O_WRONLY));
+		CHECK(ftruncate(fd_a, 0));
+		CHECK_ERRNO(ftruncate(fd_b, 0), EACCES);
+		CHECK(ftruncate(fd_before, 0));
+		CHECK(close(fd_a));
+		CHECK(close(fd_b));
+		CHECK(close(fd_before));
+
+		CHECK_ERRNO(execl("/proc/self/exe", "landlock", NULL), EACCES);
+		exit(EXIT_SUCCESS);
+	}
+	TEST_SUCC(wait_for_child(pid));
+
+	TEST_SUCC(close(ruleset_fd));
+}
+END_TEST()
+
+FN_TEST(inheritance)
+{
+	int ruleset_fd = TEST_SUCC(create_ruleset(ACCESS_FS_READ_FILE, 0));
+
+	pid_t pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		allow_path(ruleset_fd, ACCESS_FS_READ_FILE, DIR_A);
+		enforce(ruleset_fd);
+
+		pid_t grandchild = CHECK(fork());
+		if (grandchild == 0) {
+			CHECK(close(CHECK(open(DIR_A "/file", O_RDONLY))));
+			CHECK_ERRNO(open(DIR_B "/file", O_RDONLY), EACCES);
+
+			// A new layer can only add restrictions.
+			int ruleset_fd =
+				CHECK(create_ruleset(ACCESS_FS_READ_FILE, 0));
+			allow_path(ruleset_fd, ACCESS_FS_READ_FILE, TEST_DIR);
+			enforce(ruleset_fd);
+			CHECK_ERRNO(open(DIR_B "/file", O_RDONLY), EACCES);
+			exit(EXIT_SUCCESS);
+		}
+		CHECK(wait_for_child(grandchild));
+		exit(EXIT_SUCCESS);
+	}
+	TEST_SUCC(wait_for_child(pid));
+
+	TEST_SUCC(close(ruleset_fd));
+}
+END_TEST()
+
+static int bind_port(int sock_fd, uint16_t port)
+{
+	struct sockaddr_in addr = {
+		.sin_family = AF_INET,
+		.sin_port = htons(port),
+		.sin_addr = { .s_addr = htonl(INADDR_LOOPBACK) },
+	};
+
+	return bind(sock_fd, (struct sockaddr *)&addr, sizeof(addr));
+}
+
+static int connect_port(int sock_fd, uint16_t port)
+{
+	struct sockaddr_in addr = {
+		.sin_family = AF_INET,
+		.sin_port = htons(port),
+		.sin_addr = { .s_addr = htonl(INADDR_LOOPBACK) },
+	};
+
+	return connect(sock_fd, (struct sockaddr *)&addr, sizeof(addr));
+}
+
+FN_TEST(tcp_port)
+{
+	int ruleset_fd = TEST_SUCC(create_ruleset(
+		0, ACCESS_NET_BIND_TCP | ACCESS_NET_CONNECT_TCP));
+
+	pid_t pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		CHECK(add_port_rule(ruleset_fd,
+				    ACCESS_NET_BIND_TCP |
+					    ACCESS_NET_CONNECT_TCP,
+				    PORT_ALLOWED));
+		enforce(ruleset_fd);
+
+		int listen_fd = CHECK(socket(AF_INET, SOCK_STREAM, 0));
+		CHECK(bind_port(listen_fd, PORT_ALLOWED));
+		CHECK(listen(listen_fd, 1));
+
+		int sock_fd = CHECK(socket(AF_INET, SOCK_STREAM, 0));
+		CHECK_ERRNO(bind_port(sock_fd, PORT_DENIED), EACCES);
+		CHECK_ERRNO(connect_port(sock_fd, PORT_DENIED), EACCES);
+		CHECK(connect_port(sock_fd, PORT_ALLOWED));
+		CHECK(close(sock_fd));
+		CHECK(close(listen_fd));
+
+		// UDP sockets are not restricted.
+		sock_fd = CHECK(socket(AF_INET, SOCK_DGRAM, 0));
+		CHECK(bind_port(sock_fd, PORT_DENIED));
+		CHECK(close(sock_fd));
+		exit(EXIT_SUCCESS);
+	}
+	TEST_SUCC(wait_for_child(pid));
+
+	TEST_SUCC(close(ruleset_fd));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(unlink(DIR_A "/file"));
+	CHECK(unlink(DIR_B "/file"));
+	CHECK(rmdir(DIR_A "/sub"));
+	CHECK(rmdir(DIR_B "/sub"));
+	CHECK(rmdir(DIR_A));
+	CHECK(rmdir(DIR_B));
+	CHECK(rmdir(TEST_DIR));
+}
+END_SETUP()
@@ -10,14 +10,19 @@
 #include <stdio.h>
 #include <string.h>
 #include <sys/stat.h>
+#include <sys/syscall.h>
 #include <unistd.h>
 
 #define CMDLINE_BUFFER_SIZE 4096
 
+#define SYS_LANDLOCK_CREATE_RULESET 444
+#define LANDLOCK_CREATE_RULESET_VERSION (1U << 0)
+
 static const char *CMDLINE_PATH = "/proc/cmdline";
 static const char *YAMA_DIR_PATH = "/proc/sys/kernel/yama";
 static const char *YAMA_PTRACE_SCOPE_PATH =
 	"/proc/sys/kernel/yama/ptrace_scope";
+static const char *APPARMOR_DIR_PATH = "/sys/kernel/security/apparmor";
 
 static void read_cmdline(char cmdline[CMDLINE_BUFFER_SIZE])
 {
@@ -85,7 +90,7 @@ static bool module_list_contains(const char *list, const char *module_name)
 	return false;
 }
 
-static bool expect_yama_enabled(void)
+static bool expect_module_enabled(const char *module_name)
 {
 	char lsm_param[CMDLINE_BUFFER_SIZE] = "";
 
@@ -93,12 +98,12 @@ static bool expect_yama_enabled(void)
 		return true;
 	}
 
-	return module_list_contains(lsm_param, "yama");
+	return module_list_contains(lsm_param, module_name);
 }
 
 FN_TEST(yama_procfs_visibility_follows_lsm_selection)
 {
-	bool expect_yama = expect_yama_enabled();
+	bool expect_yama = expect_module_enabled("yama");
 	struct stat statbuf;
 
 	if (expect_yama) {
@@ -112,3 +117,30 @@ FN_TEST(yama_procfs_visibility_follows_lsm_selection)
 	}
 }
 END_TEST()
+
+FN_TEST(landlock_syscalls_follow_lsm_selection)
+{
+	if (expect_module_enabled("landlock")) {
+		TEST_RES(syscall(SYS_LANDLOCK_CREATE_RULESET, NULL, 0,
+				 LANDLOCK_CREATE_RULESET_VERSION),
+			 _ret >= 1);
+	} else {
+		TEST_ERRNO(syscall(SYS_LANDLOCK_CREATE_RULESET, NULL, 0,
+				   LANDLOCK_CREATE_RULESET_VERSION),
+			   EOPNOTSUPP);
+	}
+}
+END_TEST()
+
+FN_TEST(apparmor_securityfs_visibility_follows_lsm_selection)
+{
+	struct stat statbuf;
+
+	if (expect_module_enabled("apparmor")) {
+		TEST_RES(stat(APPARMOR_DIR_PATH, &statbuf),
+			 S_ISDIR(statbuf.st_mode));
+	} else {
+		TEST_ERRNO(stat(APPARMOR_DIR_PATH, &statbuf), ENOENT);
+	}
+}
+END_TEST()
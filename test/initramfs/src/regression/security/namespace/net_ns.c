@@ -0,0 +1,281 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <arpa/inet.h>
+#include <fcntl.h>
+#include <net/if.h>
+#include <netinet/in.h>
+#include <sched.h>
+#include <stddef.h>
+#include <string.h>
+#include <sys/ioctl.h>
+#include <sys/socket.h>
+#include <sys/stat.h>
+#include <sys/un.h>
+#include <unistd.h>
+
+#include "../../common/test.h"
+
+#define TEST_PORT 0x1234
+#define ABSTRACT_NAME "\0net_ns_test"
+#define ABSTRACT_NAME_ONLY_IN_INIT_NS "\0net_ns_test_init"
+
+static int init_ns_fd;
+static ino_t init_ns_ino;
+
+static int sk_init_listen;
+static int sk_init_unix;
+static int sk_init_unix_only;
+static int sk_created_in_init_ns;
+static int sk_unix_created_in_new_ns;
+static int init_ns_iface_count;
+
+static struct sockaddr_in loopback_addr;
+
+static socklen_t abstract_addr(struct sockaddr_un *addr, const char *name,
+			       size_t len)
+{
+	memset(addr, 0, sizeof(*addr));
+	addr->sun_family = AF_UNIX;
+	memcpy(addr->sun_path, name, len);
+	return offsetof(struct sockaddr_un, sun_path) + len;
+}
+
+static int bind_abstract(const char *name, size_t len)
+{
+	struct sockaddr_un addr;
+	socklen_t addrlen = abstract_addr(&addr, name, len);
+	int sk;
+
+	sk = socket(AF_UNIX, SOCK_STREAM, 0);
+	if (sk < 0)
+		return -1;
+
+	if (bind(sk, (struct sockaddr *)&addr, addrlen) < 0 ||
+	    listen(sk, 1) < 0) {
+		close(sk);
+		return -1;
+	}
+
+	return sk;
+}
+
+static int connect_abstract(const char *name, size_t len)
+{
+	struct sockaddr_un addr;
+	socklen_t addrlen = abstract_addr(&addr, name, len);
+	int sk, ret;
+
+	sk = socket(AF_UNIX, SOCK_STREAM, 0);
+	if (sk < 0)
+		return -1;
+
+	ret = connect(sk, (struct sockaddr *)&addr, addrlen);
+	close(sk);
+	return ret;
+}
+
+/*
+ * Counts the interfaces in the network namespace of the socket.
+ */
+static int count_ifaces_of(int sk)
+{
+	struct ifreq ifreqs[8];
+	struct ifconf ifconf = {
+		.ifc_len = sizeof(ifreqs),
+		.ifc_req = ifreqs,
+	};
+	int count;
+
+	if (ioctl(sk, SIOCGIFCONF, &ifconf) < 0)
+		return -1;
+
+	count = ifconf.ifc_len / sizeof(struct ifreq);
+	if (count == 1 && strcmp(ifreqs[0].ifr_name, "lo") != 0)
+		return -1;
+
+	return count;
+}
+
+static int count_ifaces(void)
+{
+	int sk, count;
+
+	sk = socket(AF_INET, SOCK_DGRAM, 0);
+	if (sk < 0)
+		return -1;
+
+	count = count_ifaces_of(sk);
+	close(sk);
+
+	return count;
+}
+
+static ino_t current_ns_ino(void)
+{
+	struct stat st;
+
+	if (stat("/proc/self/ns/net", &st) < 0)
+		return 0;
+	return st.st_ino;
+}
+
+FN_SETUP(init_ns)
+{
+	struct stat st;
+
+	init_ns_fd = CHECK(open("/proc/self/ns/net", O_RDONLY));
+	CHECK(fstat(init_ns_fd, &st));
+	init_ns_ino = st.st_ino;
+
+	loopback_addr.sin_family = AF_INET;
+	loopback_addr.sin_port = htons(TEST_PORT);
+	loopback_addr.sin_addr.s_addr = htonl(INADDR_LOOPBACK);
+
+	sk_init_listen = CHECK(socket(AF_INET, SOCK_STREAM | SOCK_NONBLOCK, 0));
+	CHECK(bind(sk_init_listen, (struct sockaddr *)&loopback_addr,
+		   sizeof(loopback_addr)));
+	CHECK(listen(sk_init_listen, 1));
+
+	sk_init_unix = CHECK(
+		bind_abstract(ABSTRACT_NAME, sizeof(ABSTRACT_NAME) - 1));
+	sk_init_unix_only = CHECK(
+		bind_abstract(ABSTRACT_NAME_ONLY_IN_INIT_NS,
+			      sizeof(ABSTRACT_NAME_ONLY_IN_INIT_NS) - 1));
+
+	sk_created_in_init_ns = CHECK(socket(AF_INET, SOCK_STREAM, 0));
+	init_ns_iface_count = CHECK(count_ifaces());
+}
+END_SETUP()
+
+FN_TEST(unshare_net_ns)
+{
+	TEST_SUCC(unshare(CLONE_NEWNET));
+	TEST_RES(current_ns_ino(), _ret != 0 && _ret != init_ns_ino);
+
+#ifndef __asterinas__
+	// On Linux, the loopback interface of a new network namespace is down.
+	struct ifreq ifr;
+	int sk = TEST_SUCC(socket(AF_INET, SOCK_DGRAM, 0));
+	memset(&ifr, 0, sizeof(ifr));
+	strcpy(ifr.ifr_name, "lo");
+	TEST_SUCC(ioctl(sk, SIOCGIFFLAGS, &ifr));
+	ifr.ifr_flags |= IFF_UP;
+	TEST_SUCC(ioctl(sk, SIOCSIFFLAGS, &ifr));
+	TEST_SUCC(close(sk));
+#endif
+
+	// The new network namespace contains only the loopback interface.
+	TEST_RES(count_ifaces(), _ret == 1);
+
+	// Interface indexes are allocated per network namespace.
+	TEST_RES(if_nametoindex("lo"), _ret == 1);
+}
+END_TEST()
+
+FN_TEST(ports_are_isolated)
+{
+	int sk_listen, sk_connect, sk_accept;
+
+	// The port is in use in the initial network namespace,
+	// but not in the new one.
+	sk_listen = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_SUCC(bind(sk_listen, (struct sockaddr *)&loopback_addr,
+		       sizeof(loopback_addr)));
+	TEST_SUCC(listen(sk_listen, 1));
+
+	// The connection reaches the listener in the new network namespace.
+	sk_connect = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_SUCC(connect(sk_connect, (struct sockaddr *)&loopback_addr,
+			  sizeof(loopback_addr)));
+	sk_accept = TEST_SUCC(accept(sk_listen, NULL, NULL));
+	TEST_ERRNO(accept(sk_init_listen, NULL, NULL), EAGAIN);
+
+	TEST_SUCC(close(sk_accept));
+	TEST_SUCC(close(sk_connect));
+	TEST_SUCC(close(sk_listen));
+}
+END_TEST()
+
+FN_TEST(socket_keeps_its_net_ns)
+{
+	// The socket was created in the initial network namespace,
+	// where the port is still in use.
+	TEST_ERRNO(bind(sk_created_in_init_ns,
+			(struct sockaddr *)&loopback_addr,
+			sizeof(loopback_addr)),
+		   EADDRINUSE);
+
+	// The interface ioctls also use the network namespace of the socket.
+	TEST_RES(count_ifaces_of(sk_created_in_init_ns),
+		 _ret == init_ns_iface_count);
+}
+END_TEST()
+
+FN_TEST(abstract_names_are_isolated)
+{
+	int sk;
+
+	TEST_ERRNO(connect_abstract(ABSTRACT_NAME_ONLY_IN_INIT_NS,
+				    sizeof(ABSTRACT_NAME_ONLY_IN_INIT_NS) - 1),
+		   ECONNREFUSED);
+
+	sk = TEST_SUCC(bind_abstract(ABSTRACT_NAME, sizeof(ABSTRACT_NAME) - 1));
+	TEST_SUCC(connect_abstract(ABSTRACT_NAME, sizeof(ABSTRACT_NAME) - 1));
+	TEST_SUCC(close(sk));
+
+	sk_unix_created_in_new_ns = TEST_SUCC(socket(AF_UNIX, SOCK_STREAM, 0));
+}
+END_TEST()
+
+FN_TEST(setns_back_to_init_ns)
+{
+	int new_ns_fd;
+
+	new_ns_fd = TEST_SUCC(open("/proc/self/ns/net", O_RDONLY));
+
+	TEST_SUCC(setns(init_ns_fd, CLONE_NEWNET));
+	TEST_RES(current_ns_ino(), _ret == init_ns_ino);
+
+	// The port and the abstract names are in use again.
+	int sk = TEST_SUCC(socket(AF_INET, SOCK_STREAM, 0));
+	TEST_ERRNO(bind(sk, (struct sockaddr *)&loopback_addr,
+			sizeof(loopback_addr)),
+		   EADDRINUSE);
+	TEST_SUCC(close(sk));
+	TEST_ERRNO(bind_abstract(ABSTRACT_NAME, sizeof(ABSTRACT_NAME) - 1),
+		   EADDRINUSE);
+	TEST_SUCC(connect_abstract(ABSTRACT_NAME_ONLY_IN_INIT_NS,
+				   sizeof(ABSTRACT_NAME_ONLY_IN_INIT_NS) - 1));
+
+	// The socket created in the new network namespace still binds the
+	// abstract name there.
+	struct sockaddr_un addr;
+	socklen_t addrlen =
+		abstract_addr(&addr, ABSTRACT_NAME, sizeof(ABSTRACT_NAME) - 1);
+	TEST_SUCC(bind(sk_unix_created_in_new_ns, (struct sockaddr *)&addr,
+		       addrlen));
+	// It also sees only the loopback interface there.
+	TEST_RES(count_ifaces_of(sk_unix_created_in_new_ns), _ret == 1);
+	TEST_SUCC(close(sk_unix_created_in_new_ns));
+
+	// The flags must match the type of the namespace file.
+	TEST_ERRNO(setns(new_ns_fd, CLONE_NEWUTS), EINVAL);
+	TEST_SUCC(setns(new_ns_fd, 0));
+	TEST_RES(current_ns_ino(), _ret != init_ns_ino);
+	TEST_SUCC(setns(init_ns_fd, CLONE_NEWNET));
+
+	TEST_SUCC(close(new_ns_fd));
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(sk_created_in_init_ns));
+	CHECK(close(sk_init_unix_only));
+	CHECK(close(sk_init_unix));
+	CHECK(close(sk_init_listen));
+	CHECK(close(init_ns_fd));
+}
+END_SETUP()
@@ -0,0 +1,258 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <sched.h>
+#include <signal.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/mount.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <unistd.h>
+
+#include "../../common/test.h"
+
+#define LOCAL_PROC_DIR "/tmp/pid_ns_proc"
+
+static pid_t outer_pid;
+
+FN_SETUP(outer_pid)
+{
+	outer_pid = CHECK(getpid());
+}
+END_SETUP()
+
+static void wait_for_exit(pid_t pid, int exit_code)
+{
+	int status;
+
+	CHECK_WITH(waitpid(pid, &status, 0),
+		   _ret == pid && WIFEXITED(status) &&
+			   WEXITSTATUS(status) == exit_code);
+}
+
+static ino_t ns_ino(const char *path)
+{
+	struct stat st;
+
+	CHECK(stat(path, &st));
+	return st.st_ino;
+}
+
+/*
+ * Runs `init_fn` as the init process of a new PID namespace and returns the
+ * exit status.
+ *
+ * A helper process is forked to unshare the PID namespace, so that the PID
+ * namespace for the children of the test process is not changed.
+ */
+static int run_in_new_pid_ns(void (*init_fn)(void))
+{
+	pid_t helper = CHECK(fork());
+	int status;
+
+	if (helper == 0) {
+		CHECK(unshare(CLONE_NEWPID));
+
+		pid_t init = CHECK(fork());
+		if (init == 0) {
+			init_fn();
+			exit(EXIT_SUCCESS);
+		}
+
+		wait_for_exit(init, EXIT_SUCCESS);
+		exit(EXIT_SUCCESS);
+	}
+
+	CHECK_WITH(waitpid(helper, &status, 0), _ret == helper);
+	return status;
+}
+
+FN_TEST(unshare_pid_ns)
+{
+	pid_t pid = TEST_SUCC(fork());
+	int status;
+
+	if (pid == 0) {
+		pid_t self = CHECK(getpid());
+		ino_t ino = ns_ino("/proc/self/ns/pid");
+
+		CHECK_WITH(ns_ino("/proc/self/ns/pid_for_children"),
+			   _ret == ino);
+		CHECK(unshare(CLONE_NEWPID));
+
+		// The caller does not enter the new PID namespace.
+		CHECK_WITH(getpid(), _ret == self);
+		CHECK_WITH(ns_ino("/proc/self/ns/pid"), _ret == ino);
+		CHECK_WITH(ns_ino("/proc/self/ns/pid_for_children"),
+			   _ret != ino);
+
+		// The PID namespace for children cannot be changed again
+		// until it is entered.
+		CHECK_WITH(unshare(CLONE_NEWPID), _ret < 0 && errno == EINVAL);
+
+		// Going back to the PID namespace of the caller is allowed.
+		int fd = CHECK(open("/proc/self/ns/pid", O_RDONLY));
+		CHECK(setns(fd, CLONE_NEWPID));
+		CHECK(close(fd));
+		CHECK_WITH(ns_ino("/proc/self/ns/pid_for_children"),
+			   _ret == ino);
+
+		exit(EXIT_SUCCESS);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EXIT_SUCCESS);
+}
+END_TEST()
+
+static void check_local_pids(void)
+{
+	char link[16] = { 0 };
+	char path[64];
+
+	CHECK_WITH(getpid(), _ret == 1);
+	CHECK_WITH(syscall(SYS_gettid), _ret == 1);
+	CHECK_WITH(getppid(), _ret == 0);
+
+	// The procfs mounted outside the PID namespace shows the PIDs outside.
+	CHECK(readlink("/proc/self", link, sizeof(link) - 1));
+	CHECK_WITH(strcmp(link, "1"), _ret != 0);
+
+	// A procfs mounted inside the PID namespace shows the local PIDs.
+	CHECK(unshare(CLONE_NEWNS));
+	CHECK(mount(NULL, "/", NULL, MS_REC | MS_PRIVATE, NULL));
+	CHECK(mkdir(LOCAL_PROC_DIR, 0755));
+	CHECK(mount("proc", LOCAL_PROC_DIR, "proc", 0, NULL));
+	memset(link, 0, sizeof(link));
+	CHECK(readlink(LOCAL_PROC_DIR "/self", link, sizeof(link) - 1));
+	CHECK_WITH(strcmp(link, "1"), _ret == 0);
+	snprintf(path, sizeof(path), LOCAL_PROC_DIR "/%d", outer_pid);
+	CHECK_WITH(access(path, F_OK), _ret < 0 && errno == ENOENT);
+	CHECK(umount(LOCAL_PROC_DIR));
+	CHECK(rmdir(LOCAL_PROC_DIR));
+
+	// The processes outside the PID namespace are invisible.
+	CHECK_WITH(kill(outer_pid, 0), _ret < 0 && errno == ESRCH);
+
+	pid_t pid = CHECK(fork());
+	if (pid == 0) {
+		CHECK_WITH(getpid(), _ret == 2);
+		CHECK_WITH(getppid(), _ret == 1);
+		exit(EXIT_SUCCESS);
+	}
+
+	// The PIDs reported by `fork` and `wait` are local to the namespace.
+	CHECK_WITH(pid, _ret == 2);
+	wait_for_exit(2, EXIT_SUCCESS);
+}
+
+FN_TEST(local_pids)
+{
+	TEST_RES(run_in_new_pid_ns(check_local_pids),
+		 WIFEXITED(_ret) && WEXITSTATUS(_ret) == EXIT_SUCCESS);
+}
+END_TEST()
+
+static void check_orphan_reaping(void)
+{
+	pid_t pid = CHECK(fork());
+
+	if (pid == 0) {
+		pid_t orphan = CHECK(fork());
+
+		if (orphan == 0) {
+			// Wait until the parent exits and the orphan is
+			// reparented to the init process of the namespace.
+			while (getppid() != 1)
+				usleep(1000);
+			exit(EXIT_SUCCESS);
+		}
+
+		exit(EXIT_SUCCESS);
+	}
+
+	// The orphan is reaped by the init process of the namespace.
+	wait_for_exit(pid, EXIT_SUCCESS);
+	wait_for_exit(pid + 1, EXIT_SUCCESS);
+}
+
+FN_TEST(orphan_reaping)
+{
+	TEST_RES(run_in_new_pid_ns(check_orphan_reaping),
+		 WIFEXITED(_ret) && WEXITSTATUS(_ret) == EXIT_SUCCESS);
+}
+END_TEST()
+
+static void kill_init(void)
+{
+	int ready[2], alive[2];
+	int status;
+	char buf;
+
+	CHECK(pipe(ready));
+	CHECK(pipe(alive));
+
+	pid_t init = CHECK(fork());
+	if (init == 0) {
+		CHECK(close(ready[0]));
+		CHECK(close(alive[0]));
+
+		if (CHECK(fork()) == 0) {
+			// The process keeps the write end of the pipe open
+			// until it is killed.
+			CHECK(close(ready[1]));
+			pause();
+			exit(EXIT_FAILURE);
+		}
+		CHECK(close(alive[1]));
+
+		// Signals without handlers sent from inside the namespace are
+		// ignored by the init process.
+		CHECK(kill(1, SIGTERM));
+		CHECK(kill(1, SIGKILL));
+
+		CHECK_WITH(write(ready[1], "R", 1), _ret == 1);
+		pause();
+		exit(EXIT_FAILURE);
+	}
+
+	CHECK(close(ready[1]));
+	CHECK(close(alive[1]));
+	CHECK_WITH(read(ready[0], &buf, 1), _ret == 1 && buf == 'R');
+
+	// Killing the init process from the parent namespace tears down the
+	// whole namespace.
+	CHECK(kill(init, SIGKILL));
+	CHECK_WITH(waitpid(init, &status, 0),
+		   _ret == init && WIFSIGNALED(status) &&
+			   WTERMSIG(status) == SIGKILL);
+	CHECK_WITH(read(alive[0], &buf, 1), _ret == 0);
+
+	// No new processes can be created in the namespace.
+	CHECK_WITH(fork(), _ret < 0 && errno == ENOMEM);
+
+	CHECK(close(ready[0]));
+	CHECK(close(alive[0]));
+}
+
+FN_TEST(kill_init)
+{
+	pid_t pid = TEST_SUCC(fork());
+	int status;
+
+	if (pid == 0) {
+		CHECK(unshare(CLONE_NEWPID));
+		kill_init();
+		exit(EXIT_SUCCESS);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EXIT_SUCCESS);
+}
+END_TEST()
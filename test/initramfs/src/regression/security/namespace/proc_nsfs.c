@@ -34,14 +34,25 @@
  * `ns_names` lists the names as they appear in readlink(2) output.
  *   - For most namespaces these are identical to `ns_files`.
  *   - For "pid_for_children" and "time_for_children", readlink(2) shows
- *     "pid" and "time" respectively (not applicable to the entries below,
- *     but noted here for future reference).
+ *     "pid" and "time" respectively.
  * `clone_flags` lists the corresponding CLONE_NEW* flag for each entry.
  */
-static const char *ns_files[] = { "cgroup", "ipc", "mnt", "user", "uts" };
-static const char *ns_names[] = { "cgroup", "ipc", "mnt", "user", "uts" };
+static const char *ns_files[] = { "cgroup",
+				  "ipc",
+				  "mnt",
+				  "net",
+				  "pid",
+				  "pid_for_children",
+				  "time",
+				  "time_for_children",
+				  "user",
+				  "uts" };
+static const char *ns_names[] = { "cgroup", "ipc",  "mnt",  "net",  "pid",
+				  "pid",    "time", "time", "user", "uts" };
 static const int clone_flags[] = {
-	CLONE_NEWCGROUP, CLONE_NEWIPC, CLONE_NEWNS, CLONE_NEWUSER, CLONE_NEWUTS,
+	CLONE_NEWCGROUP, CLONE_NEWIPC,	CLONE_NEWNS,   CLONE_NEWNET,
+	CLONE_NEWPID,	 CLONE_NEWPID,	CLONE_NEWTIME, CLONE_NEWTIME,
+	CLONE_NEWUSER,	 CLONE_NEWUTS,
 };
 static const size_t ns_count = sizeof(ns_files) / sizeof(ns_files[0]);
 
@@ -197,6 +208,7 @@ FN_TEST(ioctl)
 		snprintf(path, sizeof(path), "%s/%s", NS_DIR, ns_files[i]);
 		int nsfd = TEST_SUCC(open(path, O_RDONLY));
 		int is_user_ns = (strcmp(ns_files[i], "user") == 0);
+		int is_pid_ns = (clone_flags[i] == CLONE_NEWPID);
 
 		/*
 		 * NS_GET_USERNS: returns the owning user namespace fd.
@@ -212,9 +224,9 @@ FN_TEST(ioctl)
 		/*
 		 * NS_GET_PARENT: returns the parent namespace fd.
 		 * Non-hierarchical namespaces return EINVAL;
-		 * the initial user namespace returns EPERM.
+		 * the initial user and PID namespaces return EPERM.
 		 */
-		if (!is_user_ns) {
+		if (!is_user_ns && !is_pid_ns) {
 			TEST_ERRNO(ioctl(nsfd, NS_GET_PARENT), EINVAL);
 		} else {
 			TEST_ERRNO(ioctl(nsfd, NS_GET_PARENT), EPERM);
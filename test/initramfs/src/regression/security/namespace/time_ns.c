@@ -0,0 +1,251 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <sched.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/stat.h>
+#include <sys/syscall.h>
+#include <sys/wait.h>
+#include <time.h>
+#include <unistd.h>
+
+#include "../../common/file_util.h"
+#include "../../common/test.h"
+
+#define TIMENS_OFFSETS "/proc/self/timens_offsets"
+
+#define MONOTONIC_OFFSET 1000
+#define BOOTTIME_OFFSET 2000
+
+// The maximum difference allowed between two clock readings in the test.
+#define SLACK_SECS 10
+
+static int init_ns_fd;
+
+FN_SETUP(init_ns)
+{
+	init_ns_fd = CHECK(open("/proc/self/ns/time", O_RDONLY));
+}
+END_SETUP()
+
+static void wait_for_exit(pid_t pid, int exit_code)
+{
+	int status;
+
+	CHECK_WITH(waitpid(pid, &status, 0),
+		   _ret == pid && WIFEXITED(status) &&
+			   WEXITSTATUS(status) == exit_code);
+}
+
+static ino_t ns_ino(const char *path)
+{
+	struct stat st;
+
+	CHECK(stat(path, &st));
+	return st.st_ino;
+}
+
+static time_t vdso_secs(clockid_t clock)
+{
+	struct timespec ts;
+
+	CHECK(clock_gettime(clock, &ts));
+	return ts.tv_sec;
+}
+
+static time_t syscall_secs(clockid_t clock)
+{
+	struct timespec ts;
+
+	CHECK(syscall(SYS_clock_gettime, clock, &ts));
+	return ts.tv_sec;
+}
+
+static const clockid_t clocks[] = { CLOCK_REALTIME, CLOCK_MONOTONIC,
+				     CLOCK_MONOTONIC_RAW,
+				     CLOCK_MONOTONIC_COARSE, CLOCK_BOOTTIME };
+static time_t base_secs[CLOCK_BOOTTIME + 1];
+
+// Records the time of the clocks in the current time namespace.
+static void save_base_secs(void)
+{
+	for (size_t i = 0; i < sizeof(clocks) / sizeof(clocks[0]); i++)
+		base_secs[clocks[i]] = vdso_secs(clocks[i]);
+}
+
+// Checks that the clock is ahead of the saved time by `offset` seconds.
+static void check_clock(clockid_t clock, time_t offset)
+{
+	time_t base = base_secs[clock];
+
+	CHECK_WITH(vdso_secs(clock),
+		   _ret >= base + offset && _ret < base + offset + SLACK_SECS);
+	CHECK_WITH(syscall_secs(clock),
+		   _ret >= base + offset && _ret < base + offset + SLACK_SECS);
+}
+
+/*
+ * Runs `fn` in a child process that has unshared the time namespace and
+ * returns the exit status.
+ */
+static int run_with_new_time_ns(void (*fn)(void))
+{
+	pid_t pid = CHECK(fork());
+	int status;
+
+	if (pid == 0) {
+		CHECK(unshare(CLONE_NEWTIME));
+		fn();
+		exit(EXIT_SUCCESS);
+	}
+
+	CHECK_WITH(waitpid(pid, &status, 0), _ret == pid);
+	return status;
+}
+
+static void check_unshare(void)
+{
+	ino_t ino = ns_ino("/proc/self/ns/time");
+
+	// The caller does not enter the new time namespace.
+	CHECK_WITH(ns_ino("/proc/self/ns/time_for_children"), _ret != ino);
+
+	// A new thread or a child sharing the address space must be in the
+	// same time namespace as the caller.
+	pid_t pid = vfork();
+	if (pid == 0)
+		_exit(EXIT_FAILURE);
+	CHECK_WITH(pid, _ret < 0 && errno == EINVAL);
+}
+
+FN_TEST(unshare_time_ns)
+{
+	ino_t ino = TEST_RES(ns_ino("/proc/self/ns/time"),
+			     _ret == ns_ino("/proc/self/ns/time_for_children"));
+
+	TEST_RES(run_with_new_time_ns(check_unshare),
+		 WIFEXITED(_ret) && WEXITSTATUS(_ret) == EXIT_SUCCESS);
+
+	// The time namespace of the test process is not changed.
+	TEST_RES(ns_ino("/proc/self/ns/time_for_children"), _ret == ino);
+}
+END_TEST()
+
+static void check_offsets_file(void)
+{
+	char buf[128] = { 0 };
+	int fd;
+
+	CHECK_WITH(write_file(TIMENS_OFFSETS, "realtime 1 0"),
+		   _ret < 0 && errno == EINVAL);
+	CHECK_WITH(write_file(TIMENS_OFFSETS, "2 1 0"),
+		   _ret < 0 && errno == EINVAL);
+	CHECK_WITH(write_file(TIMENS_OFFSETS, "monotonic 1 1000000000"),
+		   _ret < 0 && errno == EINVAL);
+	CHECK_WITH(write_file(TIMENS_OFFSETS,
+			      "monotonic 1 0\nboottime 1 0\nmonotonic 1 0"),
+		   _ret < 0 && errno == EINVAL);
+	CHECK_WITH(write_file(TIMENS_OFFSETS, "boottime -100000000000 0"),
+		   _ret < 0 && errno == ERANGE);
+
+	CHECK(write_file(TIMENS_OFFSETS, "monotonic 1 0\n7 -2 500"));
+
+	fd = CHECK(open("/proc/self/timens_offsets", O_RDONLY));
+	CHECK_WITH(read(fd, buf, sizeof(buf) - 1), _ret > 0);
+	CHECK(close(fd));
+	CHECK_WITH(strcmp(buf, "monotonic           1         0\n"
+			       "boottime           -2       500\n"),
+		   _ret == 0);
+}
+
+FN_TEST(offsets_file)
+{
+	TEST_RES(run_with_new_time_ns(check_offsets_file),
+		 WIFEXITED(_ret) && WEXITSTATUS(_ret) == EXIT_SUCCESS);
+}
+END_TEST()
+
+static void check_clock_offsets(void)
+{
+	char offsets[64];
+
+	snprintf(offsets, sizeof(offsets), "monotonic %d 0\nboottime %d 0",
+		 MONOTONIC_OFFSET, BOOTTIME_OFFSET);
+	CHECK(write_file(TIMENS_OFFSETS, offsets));
+	save_base_secs();
+
+	pid_t pid = CHECK(fork());
+	if (pid == 0) {
+		CHECK_WITH(ns_ino("/proc/self/ns/time"),
+			   _ret == ns_ino("/proc/self/ns/time_for_children"));
+
+		check_clock(CLOCK_MONOTONIC, MONOTONIC_OFFSET);
+		check_clock(CLOCK_MONOTONIC_RAW, MONOTONIC_OFFSET);
+		check_clock(CLOCK_MONOTONIC_COARSE, MONOTONIC_OFFSET);
+		check_clock(CLOCK_BOOTTIME, BOOTTIME_OFFSET);
+		check_clock(CLOCK_REALTIME, 0);
+
+		// Going back to the initial time namespace removes the
+		// offsets.
+		CHECK(setns(init_ns_fd, CLONE_NEWTIME));
+		check_clock(CLOCK_MONOTONIC, 0);
+		check_clock(CLOCK_BOOTTIME, 0);
+
+		exit(EXIT_SUCCESS);
+	}
+	wait_for_exit(pid, EXIT_SUCCESS);
+
+	// The offsets cannot be changed after a process enters the namespace.
+	CHECK_WITH(write_file(TIMENS_OFFSETS, "monotonic 0 0"),
+		   _ret < 0 && errno == EACCES);
+}
+
+FN_TEST(clock_offsets)
+{
+	TEST_RES(run_with_new_time_ns(check_clock_offsets),
+		 WIFEXITED(_ret) && WEXITSTATUS(_ret) == EXIT_SUCCESS);
+}
+END_TEST()
+
+static void check_setns(void)
+{
+	ino_t ino = ns_ino("/proc/self/ns/time_for_children");
+	char offsets[64];
+	int fd;
+
+	snprintf(offsets, sizeof(offsets), "monotonic %d 0", MONOTONIC_OFFSET);
+	CHECK(write_file(TIMENS_OFFSETS, offsets));
+	save_base_secs();
+	fd = CHECK(open("/proc/self/ns/time_for_children", O_RDONLY));
+
+	// Unlike `unshare`, `setns` moves the caller into the namespace.
+	CHECK(setns(fd, CLONE_NEWTIME));
+	CHECK_WITH(ns_ino("/proc/self/ns/time"), _ret == ino);
+	check_clock(CLOCK_MONOTONIC, MONOTONIC_OFFSET);
+	check_clock(CLOCK_BOOTTIME, 0);
+
+	// A child sharing the address space is allowed now.
+	pid_t pid = vfork();
+	if (pid == 0)
+		_exit(EXIT_SUCCESS);
+	CHECK_WITH(pid, _ret > 0);
+	wait_for_exit(pid, EXIT_SUCCESS);
+
+	CHECK(close(fd));
+}
+
+FN_TEST(setns_time_ns)
+{
+	TEST_RES(run_with_new_time_ns(check_setns),
+		 WIFEXITED(_ret) && WEXITSTATUS(_ret) == EXIT_SUCCESS);
+}
+END_TEST()
+
+FN_SETUP(cleanup)
+{
+	CHECK(close(init_ns_fd));
+}
+END_SETUP()
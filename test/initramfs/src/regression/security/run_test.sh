@@ -13,12 +13,21 @@ set -e
 ./capability/setgroups
 ./capability/trusted_xattr
 
+./lsm/apparmor
+./lsm/landlock
 ./lsm/module_selection
 ./lsm/yama
 
 ./namespace/cgroup_ns
 ./namespace/ipc_ns_sem
 ./namespace/mnt_ns
+./namespace/net_ns
+./namespace/pid_ns
 ./namespace/proc_nsfs
 ./namespace/setns
+./namespace/time_ns
 ./namespace/unshare
+
+./seccomp/filter
+./seccomp/strict
+./seccomp/user_notif
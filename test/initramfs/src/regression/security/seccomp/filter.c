@@ -0,0 +1,474 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <errno.h>
+#include <fcntl.h>
+#include <pthread.h>
+#include <signal.h>
+#include <stdbool.h>
+#include <stdio.h>
+#include <string.h>
+#include <sys/wait.h>
+
+#include "../../common/test.h"
+#include "seccomp_common.h"
+
+#define ERRNO_FILTER(err) (SECCOMP_RET_ERRNO | (err))
+
+// The `si_code` of `SIGSYS` sent by `SECCOMP_RET_TRAP`.
+#define SI_CODE_SYS_SECCOMP 1
+
+static int wait_exit_status(pid_t pid)
+{
+	int status;
+
+	CHECK_WITH(waitpid(pid, &status, 0), _ret == pid);
+	if (!WIFEXITED(status))
+		return -1;
+	return WEXITSTATUS(status);
+}
+
+static int wait_term_signal(pid_t pid)
+{
+	int status;
+
+	CHECK_WITH(waitpid(pid, &status, 0), _ret == pid);
+	if (!WIFSIGNALED(status))
+		return -1;
+	return WTERMSIG(status);
+}
+
+static bool status_contains(const char *path, const char *line)
+{
+	char buf[4096];
+	int fd = CHECK(open(path, O_RDONLY));
+	ssize_t len = CHECK(read(fd, buf, sizeof(buf) - 1));
+
+	CHECK(close(fd));
+	buf[len] = '\0';
+	return strstr(buf, line) != NULL;
+}
+
+FN_TEST(invalid_args)
+{
+	struct sock_filter oob_insns[] = {
+		BPF_STMT(BPF_LD | BPF_W | BPF_ABS,
+			 sizeof(struct seccomp_data)),
+		BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_ALLOW),
+	};
+	struct sock_filter unaligned_insns[] = {
+		BPF_STMT(BPF_LD | BPF_W | BPF_ABS, 1),
+		BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_ALLOW),
+	};
+	struct sock_fprog prog = { .len = 0, .filter = oob_insns };
+
+	TEST_ERRNO(seccomp(4096, 0, NULL), EINVAL);
+	TEST_ERRNO(seccomp(SECCOMP_SET_MODE_STRICT, 1, NULL), EINVAL);
+	TEST_ERRNO(seccomp(SECCOMP_SET_MODE_FILTER, 1 << 20, &prog), EINVAL);
+	TEST_ERRNO(seccomp(SECCOMP_SET_MODE_FILTER, 0, &prog), EINVAL);
+
+	prog.len = 2;
+	TEST_ERRNO(seccomp(SECCOMP_SET_MODE_FILTER, 0, &prog), EINVAL);
+	prog.filter = unaligned_insns;
+	TEST_ERRNO(seccomp(SECCOMP_SET_MODE_FILTER, 0, &prog), EINVAL);
+
+	TEST_ERRNO(seccomp(SECCOMP_SET_MODE_FILTER,
+			   SECCOMP_FILTER_FLAG_TSYNC |
+				   SECCOMP_FILTER_FLAG_NEW_LISTENER,
+			   &prog),
+		   EINVAL);
+	TEST_ERRNO(seccomp(SECCOMP_SET_MODE_FILTER,
+			   SECCOMP_FILTER_FLAG_WAIT_KILLABLE_RECV, &prog),
+		   EINVAL);
+
+	TEST_ERRNO(prctl(PR_SET_SECCOMP, SECCOMP_MODE_DISABLED, 0, 0, 0),
+		   EINVAL);
+	TEST_ERRNO(prctl(PR_SET_SECCOMP, 3, 0, 0, 0), EINVAL);
+	TEST_RES(prctl(PR_GET_SECCOMP, 0, 0, 0, 0), _ret == 0);
+}
+END_TEST()
+
+FN_TEST(get_action_avail)
+{
+	unsigned int action;
+
+	action = SECCOMP_RET_ALLOW;
+	TEST_SUCC(seccomp(SECCOMP_GET_ACTION_AVAIL, 0, &action));
+	action = SECCOMP_RET_KILL_PROCESS;
+	TEST_SUCC(seccomp(SECCOMP_GET_ACTION_AVAIL, 0, &action));
+	action = SECCOMP_RET_USER_NOTIF;
+	TEST_SUCC(seccomp(SECCOMP_GET_ACTION_AVAIL, 0, &action));
+	action = 0x12340000;
+	TEST_ERRNO(seccomp(SECCOMP_GET_ACTION_AVAIL, 0, &action), EOPNOTSUPP);
+	action = SECCOMP_RET_ALLOW;
+	TEST_ERRNO(seccomp(SECCOMP_GET_ACTION_AVAIL, 1, &action), EINVAL);
+}
+END_TEST()
+
+FN_TEST(get_notif_sizes)
+{
+	struct seccomp_notif_sizes sizes;
+
+	TEST_RES(seccomp(SECCOMP_GET_NOTIF_SIZES, 0, &sizes),
+		 sizes.seccomp_notif == sizeof(struct seccomp_notif) &&
+			 sizes.seccomp_notif_resp ==
+				 sizeof(struct seccomp_notif_resp) &&
+			 sizes.seccomp_data == sizeof(struct seccomp_data));
+}
+END_TEST()
+
+FN_TEST(ret_errno)
+{
+	pid_t pid = TEST_SUCC(fork());
+
+	if (pid == 0) {
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+		CHECK(install_filter(0, SYS_getppid, ERRNO_FILTER(EPERM)));
+
+		CHECK_WITH(syscall(SYS_getppid), _ret == -1 && errno == EPERM);
+		CHECK_WITH(prctl(PR_GET_SECCOMP, 0, 0, 0, 0), _ret == 2);
+		CHECK_WITH(status_contains("/proc/self/status",
+					   "Seccomp:\t2\n"),
+			   _ret);
+		CHECK_WITH(status_contains("/proc/self/status",
+					   "Seccomp_filters:\t1\n"),
+			   _ret);
+
+		// The strict mode cannot be enabled in the filter mode.
+		CHECK_WITH(prctl(PR_SET_SECCOMP, SECCOMP_MODE_STRICT, 0, 0, 0),
+			   _ret == -1 && errno == EINVAL);
+
+		// Error numbers are clamped to `MAX_ERRNO`.
+		CHECK(install_filter(0, SYS_getpgrp, ERRNO_FILTER(0xffff)));
+		CHECK_WITH(syscall(SYS_getpgrp), _ret == -1 && errno == 4095);
+
+		_exit(EXIT_SUCCESS);
+	}
+
+	TEST_RES(wait_exit_status(pid), _ret == EXIT_SUCCESS);
+}
+END_TEST()
+
+FN_TEST(prctl_set_seccomp)
+{
+	pid_t pid = TEST_SUCC(fork());
+
+	if (pid == 0) {
+		struct sock_filter insns[] = {
+			BPF_STMT(BPF_LD | BPF_W | BPF_ABS,
+				 offsetof(struct seccomp_data, nr)),
+			BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, SYS_getppid, 0, 1),
+			BPF_STMT(BPF_RET | BPF_K, ERRNO_FILTER(EACCES)),
+			BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_ALLOW),
+		};
+		struct sock_fprog prog = {
+			.len = sizeof(insns) / sizeof(insns[0]),
+			.filter = insns,
+		};
+
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+		CHECK(prctl(PR_SET_SECCOMP, SECCOMP_MODE_FILTER, &prog, 0, 0));
+		CHECK_WITH(syscall(SYS_getppid),
+			   _ret == -1 && errno == EACCES);
+
+		_exit(EXIT_SUCCESS);
+	}
+
+	TEST_RES(wait_exit_status(pid), _ret == EXIT_SUCCESS);
+}
+END_TEST()
+
+FN_TEST(stacked_filters)
+{
+	pid_t pid = TEST_SUCC(fork());
+
+	if (pid == 0) {
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+
+		// The most restrictive action wins.
+		CHECK(install_filter(0, SYS_getppid, ERRNO_FILTER(EPERM)));
+		CHECK(install_filter(0, SYS_getppid, SECCOMP_RET_ALLOW));
+		CHECK_WITH(syscall(SYS_getppid), _ret == -1 && errno == EPERM);
+
+		// The newest filter wins if the actions are the same.
+		CHECK(install_filter(0, SYS_getppid, ERRNO_FILTER(EACCES)));
+		CHECK_WITH(syscall(SYS_getppid),
+			   _ret == -1 && errno == EACCES);
+
+		CHECK_WITH(status_contains("/proc/self/status",
+					   "Seccomp_filters:\t3\n"),
+			   _ret);
+
+		_exit(EXIT_SUCCESS);
+	}
+
+	TEST_RES(wait_exit_status(pid), _ret == EXIT_SUCCESS);
+}
+END_TEST()
+
+static volatile siginfo_t sigsys_info;
+
+static void handle_sigsys(int signum, siginfo_t *info, void *ucontext)
+{
+	sigsys_info = *info;
+}
+
+FN_TEST(ret_trap)
+{
+	pid_t pid = TEST_SUCC(fork());
+
+	if (pid == 0) {
+		struct sigaction action = {
+			.sa_sigaction = handle_sigsys,
+			.sa_flags = SA_SIGINFO,
+		};
+
+		CHECK(sigaction(SIGSYS, &action, NULL));
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+		CHECK(install_filter(0, SYS_getppid, SECCOMP_RET_TRAP | 42));
+
+		syscall(SYS_getppid, 1, 2, 3);
+		CHECK_WITH(sigsys_info.si_signo, _ret == SIGSYS);
+		CHECK_WITH(sigsys_info.si_code, _ret == SI_CODE_SYS_SECCOMP);
+		CHECK_WITH(sigsys_info.si_errno, _ret == 42);
+		CHECK_WITH(sigsys_info.si_syscall, _ret == SYS_getppid);
+		CHECK_WITH(sigsys_info.si_arch, _ret == TEST_AUDIT_ARCH);
+
+		_exit(EXIT_SUCCESS);
+	}
+
+	TEST_RES(wait_exit_status(pid), _ret == EXIT_SUCCESS);
+
+	// `SIGSYS` is delivered even if it is blocked.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		sigset_t set;
+
+		sigemptyset(&set);
+		sigaddset(&set, SIGSYS);
+		CHECK(sigprocmask(SIG_BLOCK, &set, NULL));
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+		CHECK(install_filter(0, SYS_getppid, SECCOMP_RET_TRAP));
+		syscall(SYS_getppid);
+		_exit(EXIT_FAILURE);
+	}
+	TEST_RES(wait_term_signal(pid), _ret == SIGSYS);
+
+	// `SIGSYS` is delivered even if it is ignored.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		CHECK(signal(SIGSYS, SIG_IGN));
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+		CHECK(install_filter(0, SYS_getppid, SECCOMP_RET_TRAP));
+		syscall(SYS_getppid);
+		_exit(EXIT_FAILURE);
+	}
+	TEST_RES(wait_term_signal(pid), _ret == SIGSYS);
+}
+END_TEST()
+
+FN_TEST(ret_kill)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+		CHECK(install_filter(0, SYS_getppid, SECCOMP_RET_KILL_PROCESS));
+		syscall(SYS_getppid);
+		_exit(EXIT_FAILURE);
+	}
+	TEST_RES(wait_term_signal(pid), _ret == SIGSYS);
+
+	// Killing the only thread kills the process.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+		CHECK(install_filter(0, SYS_getppid, SECCOMP_RET_KILL_THREAD));
+		syscall(SYS_getppid);
+		_exit(EXIT_FAILURE);
+	}
+	TEST_RES(wait_term_signal(pid), _ret == SIGSYS);
+}
+END_TEST()
+
+FN_TEST(inherit_across_fork_and_execve)
+{
+	pid_t pid = TEST_SUCC(fork());
+
+	if (pid == 0) {
+		char buf[4096];
+		int pipefd[2];
+		ssize_t len;
+
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+		CHECK(install_filter(0, SYS_getppid, ERRNO_FILTER(EPERM)));
+
+		pid_t child = CHECK(fork());
+		if (child == 0) {
+			if (syscall(SYS_getppid) != -1 || errno != EPERM)
+				_exit(EXIT_FAILURE);
+			_exit(EXIT_SUCCESS);
+		}
+		CHECK_WITH(wait_exit_status(child), _ret == EXIT_SUCCESS);
+
+		CHECK(pipe(pipefd));
+		child = CHECK(fork());
+		if (child == 0) {
+			CHECK(dup2(pipefd[1], STDOUT_FILENO));
+			CHECK(execl("/bin/cat", "cat", "/proc/self/status",
+				    NULL));
+		}
+		CHECK(close(pipefd[1]));
+		len = CHECK(read(pipefd[0], buf, sizeof(buf) - 1));
+		buf[len] = '\0';
+		CHECK(close(pipefd[0]));
+		CHECK_WITH(wait_exit_status(child), _ret == EXIT_SUCCESS);
+
+		CHECK_WITH(strstr(buf, "Seccomp:\t2\n"), _ret != NULL);
+		CHECK_WITH(strstr(buf, "Seccomp_filters:\t1\n"), _ret != NULL);
+
+		_exit(EXIT_SUCCESS);
+	}
+
+	TEST_RES(wait_exit_status(pid), _ret == EXIT_SUCCESS);
+}
+END_TEST()
+
+static pthread_barrier_t barrier;
+static volatile pid_t thread_tid;
+static volatile int thread_errno;
+
+static void *tsync_thread(void *arg)
+{
+	bool has_own_filter = (bool)arg;
+
+	if (has_own_filter)
+		CHECK(install_filter(0, SYS_getpgrp, ERRNO_FILTER(EACCES)));
+	thread_tid = gettid();
+
+	// Wait for the main thread to install the filter.
+	pthread_barrier_wait(&barrier);
+	pthread_barrier_wait(&barrier);
+
+	errno = 0;
+	syscall(SYS_getppid);
+	thread_errno = errno;
+
+	return NULL;
+}
+
+FN_TEST(tsync)
+{
+	pid_t pid;
+
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		pthread_t thread;
+
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+		CHECK(pthread_barrier_init(&barrier, NULL, 2));
+		CHECK(pthread_create(&thread, NULL, tsync_thread,
+				     (void *)false));
+
+		pthread_barrier_wait(&barrier);
+		CHECK_WITH(install_filter(SECCOMP_FILTER_FLAG_TSYNC,
+					  SYS_getppid, ERRNO_FILTER(EPERM)),
+			   _ret == 0);
+		pthread_barrier_wait(&barrier);
+
+		CHECK(pthread_join(thread, NULL));
+		CHECK_WITH(thread_errno, _ret == EPERM);
+
+		_exit(EXIT_SUCCESS);
+	}
+	TEST_RES(wait_exit_status(pid), _ret == EXIT_SUCCESS);
+
+	// Threads with diverged filters cannot be synchronized.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		pthread_t thread;
+
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+		CHECK(pthread_barrier_init(&barrier, NULL, 2));
+		CHECK(pthread_create(&thread, NULL, tsync_thread,
+				     (void *)true));
+
+		pthread_barrier_wait(&barrier);
+		CHECK_WITH(install_filter(SECCOMP_FILTER_FLAG_TSYNC,
+					  SYS_getppid, ERRNO_FILTER(EPERM)),
+			   _ret == thread_tid);
+		unsigned int flags = SECCOMP_FILTER_FLAG_TSYNC |
+				     SECCOMP_FILTER_FLAG_TSYNC_ESRCH;
+		CHECK_WITH(install_filter(flags, SYS_getppid,
+					  ERRNO_FILTER(EPERM)),
+			   _ret == -1 && errno == ESRCH);
+		pthread_barrier_wait(&barrier);
+
+		CHECK(pthread_join(thread, NULL));
+		CHECK_WITH(thread_errno, _ret == 0);
+
+		_exit(EXIT_SUCCESS);
+	}
+	TEST_RES(wait_exit_status(pid), _ret == EXIT_SUCCESS);
+}
+END_TEST()
+
+static void *tsync_execve_thread(void *arg)
+{
+	int *pipefd = arg;
+
+	// Wait for the main thread to install the filter.
+	pthread_barrier_wait(&barrier);
+	pthread_barrier_wait(&barrier);
+
+	CHECK(dup2(pipefd[1], STDOUT_FILENO));
+	CHECK(execl("/bin/cat", "cat", "/proc/self/status", NULL));
+
+	return NULL;
+}
+
+FN_TEST(tsync_no_new_privs_across_execve)
+{
+	char buf[4096];
+	int pipefd[2];
+	ssize_t len;
+	pid_t pid;
+
+	TEST_SUCC(pipe(pipefd));
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		pthread_t thread;
+
+		CHECK(close(pipefd[0]));
+		CHECK(pthread_barrier_init(&barrier, NULL, 2));
+		CHECK(pthread_create(&thread, NULL, tsync_execve_thread,
+				     pipefd));
+
+		// Only the main thread sets `no_new_privs`. The other thread
+		// gets it when the filter is synchronized.
+		pthread_barrier_wait(&barrier);
+		CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+		CHECK_WITH(install_filter(SECCOMP_FILTER_FLAG_TSYNC,
+					  SYS_getppid, ERRNO_FILTER(EPERM)),
+			   _ret == 0);
+		pthread_barrier_wait(&barrier);
+
+		// The other thread executes a new program, which terminates
+		// this thread.
+		for (;;)
+			pause();
+	}
+	TEST_SUCC(close(pipefd[1]));
+	len = TEST_SUCC(read(pipefd[0], buf, sizeof(buf) - 1));
+	buf[len] = '\0';
+	TEST_SUCC(close(pipefd[0]));
+	TEST_RES(wait_exit_status(pid), _ret == EXIT_SUCCESS);
+
+	// Both `no_new_privs` and the filter are kept across `execve`.
+	TEST_RES(strstr(buf, "NoNewPrivs:\t1\n"), _ret != NULL);
+	TEST_RES(strstr(buf, "Seccomp:\t2\n"), _ret != NULL);
+	TEST_RES(strstr(buf, "Seccomp_filters:\t1\n"), _ret != NULL);
+}
+END_TEST()
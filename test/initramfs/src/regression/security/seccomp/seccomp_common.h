@@ -0,0 +1,54 @@
+/* SPDX-License-Identifier: MPL-2.0 */
+
+#ifndef SECCOMP_SECCOMP_COMMON_H
+#define SECCOMP_SECCOMP_COMMON_H
+
+#include <linux/audit.h>
+#include <linux/filter.h>
+#include <linux/seccomp.h>
+#include <stddef.h>
+#include <sys/prctl.h>
+#include <sys/syscall.h>
+#include <unistd.h>
+
+#if defined(__x86_64__)
+#define TEST_AUDIT_ARCH AUDIT_ARCH_X86_64
+#elif defined(__riscv) && __riscv_xlen == 64
+#define TEST_AUDIT_ARCH AUDIT_ARCH_RISCV64
+#elif defined(__loongarch64)
+#define TEST_AUDIT_ARCH AUDIT_ARCH_LOONGARCH64
+#else
+#error "unsupported architecture"
+#endif
+
+static inline int seccomp(unsigned int op, unsigned int flags, void *args)
+{
+	return syscall(SYS_seccomp, op, flags, args);
+}
+
+/*
+ * Installs a filter that returns `ret` for the syscall `nr` and allows all
+ * other syscalls. Syscalls from foreign architectures kill the process.
+ */
+static inline int install_filter(unsigned int flags, int nr, unsigned int ret)
+{
+	struct sock_filter insns[] = {
+		BPF_STMT(BPF_LD | BPF_W | BPF_ABS,
+			 offsetof(struct seccomp_data, arch)),
+		BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, TEST_AUDIT_ARCH, 1, 0),
+		BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_KILL_PROCESS),
+		BPF_STMT(BPF_LD | BPF_W | BPF_ABS,
+			 offsetof(struct seccomp_data, nr)),
+		BPF_JUMP(BPF_JMP | BPF_JEQ | BPF_K, nr, 0, 1),
+		BPF_STMT(BPF_RET | BPF_K, ret),
+		BPF_STMT(BPF_RET | BPF_K, SECCOMP_RET_ALLOW),
+	};
+	struct sock_fprog prog = {
+		.len = sizeof(insns) / sizeof(insns[0]),
+		.filter = insns,
+	};
+
+	return seccomp(SECCOMP_SET_MODE_FILTER, flags, &prog);
+}
+
+#endif
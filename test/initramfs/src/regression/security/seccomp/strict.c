@@ -0,0 +1,67 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <signal.h>
+#include <sys/wait.h>
+
+#include "../../common/test.h"
+#include "seccomp_common.h"
+
+FN_TEST(allowed_syscalls)
+{
+	int pipefd[2];
+	char buf[4];
+	int status;
+
+	TEST_SUCC(pipe(pipefd));
+
+	pid_t pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		CHECK(prctl(PR_SET_SECCOMP, SECCOMP_MODE_STRICT, 0, 0, 0));
+
+		// Only `read`, `write`, `exit`, and `rt_sigreturn` are allowed.
+		if (write(pipefd[1], "ok", 2) != 2)
+			syscall(SYS_exit, EXIT_FAILURE);
+		syscall(SYS_exit, EXIT_SUCCESS);
+	}
+
+	TEST_RES(read(pipefd[0], buf, sizeof(buf)),
+		 _ret == 2 && buf[0] == 'o' && buf[1] == 'k');
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFEXITED(status) &&
+			 WEXITSTATUS(status) == EXIT_SUCCESS);
+
+	TEST_SUCC(close(pipefd[0]));
+	TEST_SUCC(close(pipefd[1]));
+}
+END_TEST()
+
+FN_TEST(disallowed_syscalls)
+{
+	int status;
+
+	pid_t pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		CHECK(seccomp(SECCOMP_SET_MODE_STRICT, 0, NULL));
+		syscall(SYS_getpid);
+		syscall(SYS_exit, EXIT_FAILURE);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+
+	// `prctl` is not allowed either, so the mode cannot be queried.
+	pid = TEST_SUCC(fork());
+	if (pid == 0) {
+		CHECK(seccomp(SECCOMP_SET_MODE_STRICT, 0, NULL));
+		prctl(PR_GET_SECCOMP, 0, 0, 0, 0);
+		syscall(SYS_exit, EXIT_FAILURE);
+	}
+
+	TEST_RES(waitpid(pid, &status, 0),
+		 _ret == pid && WIFSIGNALED(status) &&
+			 WTERMSIG(status) == SIGKILL);
+}
+END_TEST()
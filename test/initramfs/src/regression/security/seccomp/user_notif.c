@@ -0,0 +1,171 @@
+// SPDX-License-Identifier: MPL-2.0
+
+#define _GNU_SOURCE
+
+#include <fcntl.h>
+#include <poll.h>
+#include <pthread.h>
+#include <sys/ioctl.h>
+
+#include "../../common/test.h"
+#include "seccomp_common.h"
+
+#define SETFD_TARGET 100
+
+static pthread_barrier_t barrier;
+static int listener = -1;
+static volatile pid_t target_tid;
+static volatile int busy_errno;
+static volatile long results[5];
+static volatile int errnos[5];
+
+static void *target_thread(void *arg)
+{
+	target_tid = gettid();
+	CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+	listener = CHECK(install_filter(SECCOMP_FILTER_FLAG_NEW_LISTENER,
+					SYS_getppid, SECCOMP_RET_USER_NOTIF));
+
+	// Only one filter of a thread can have a listener.
+	errno = 0;
+	install_filter(SECCOMP_FILTER_FLAG_NEW_LISTENER, SYS_getpgrp,
+		       SECCOMP_RET_USER_NOTIF);
+	busy_errno = errno;
+
+	pthread_barrier_wait(&barrier);
+
+	for (int i = 0; i < sizeof(results) / sizeof(results[0]); i++) {
+		errno = 0;
+		results[i] = syscall(SYS_getppid, i);
+		errnos[i] = errno;
+	}
+
+	return NULL;
+}
+
+static int recv_notif(struct seccomp_notif *notif)
+{
+	memset(notif, 0, sizeof(*notif));
+	return ioctl(listener, SECCOMP_IOCTL_NOTIF_RECV, notif);
+}
+
+static int send_resp(__u64 id, __s64 val, __s32 error, __u32 flags)
+{
+	struct seccomp_notif_resp resp = {
+		.id = id,
+		.val = val,
+		.error = error,
+		.flags = flags,
+	};
+
+	return ioctl(listener, SECCOMP_IOCTL_NOTIF_SEND, &resp);
+}
+
+FN_TEST(user_notif)
+{
+	struct seccomp_notif notif;
+	struct pollfd pfd;
+	pthread_t thread;
+	int fd;
+
+	TEST_SUCC(pthread_barrier_init(&barrier, NULL, 2));
+	TEST_SUCC(pthread_create(&thread, NULL, target_thread, NULL));
+	TEST_SUCC(pthread_barrier_wait(&barrier));
+	TEST_RES(busy_errno, _ret == EBUSY);
+	TEST_RES(fcntl(listener, F_GETFD), _ret == FD_CLOEXEC);
+
+	// Reply with a return value.
+	pfd.fd = listener;
+	pfd.events = POLLIN | POLLOUT;
+	TEST_RES(poll(&pfd, 1, -1), _ret == 1 && pfd.revents == POLLIN);
+	memset(&notif, 0xff, sizeof(notif));
+	TEST_ERRNO(ioctl(listener, SECCOMP_IOCTL_NOTIF_RECV, &notif), EINVAL);
+	TEST_RES(recv_notif(&notif),
+		 notif.pid == target_tid && notif.data.nr == SYS_getppid &&
+			 notif.data.arch == TEST_AUDIT_ARCH &&
+			 notif.data.args[0] == 0);
+	TEST_RES(poll(&pfd, 1, 0), _ret == 1 && pfd.revents == POLLOUT);
+	TEST_SUCC(ioctl(listener, SECCOMP_IOCTL_NOTIF_ID_VALID, &notif.id));
+	TEST_ERRNO(send_resp(notif.id + 1000, 0, 0, 0), ENOENT);
+	TEST_ERRNO(send_resp(notif.id, 1, 0, SECCOMP_USER_NOTIF_FLAG_CONTINUE),
+		   EINVAL);
+	TEST_SUCC(send_resp(notif.id, 42, 0, 0));
+	TEST_ERRNO(ioctl(listener, SECCOMP_IOCTL_NOTIF_ID_VALID, &notif.id),
+		   ENOENT);
+
+	// Reply with an error.
+	TEST_RES(recv_notif(&notif), notif.data.args[0] == 1);
+	TEST_SUCC(send_resp(notif.id, 0, -EPERM, 0));
+
+	// Continue the syscall.
+	TEST_RES(recv_notif(&notif), notif.data.args[0] == 2);
+	TEST_SUCC(send_resp(notif.id, 0, 0, SECCOMP_USER_NOTIF_FLAG_CONTINUE));
+
+	// Add a file and reply with its FD.
+	fd = TEST_SUCC(open("/dev/null", O_RDONLY));
+	TEST_RES(recv_notif(&notif), notif.data.args[0] == 3);
+	struct seccomp_notif_addfd addfd = {
+		.id = notif.id,
+		.flags = SECCOMP_ADDFD_FLAG_SEND,
+		.srcfd = fd,
+		.newfd_flags = O_CLOEXEC,
+	};
+	int sent_fd = TEST_SUCC(
+		ioctl(listener, SECCOMP_IOCTL_NOTIF_ADDFD, &addfd));
+	TEST_RES(fcntl(sent_fd, F_GETFD), _ret == FD_CLOEXEC);
+
+	// Add a file at a specified FD and then reply.
+	TEST_RES(recv_notif(&notif), notif.data.args[0] == 4);
+	addfd.id = notif.id;
+	addfd.flags = 0;
+	addfd.newfd = SETFD_TARGET;
+	TEST_ERRNO(ioctl(listener, SECCOMP_IOCTL_NOTIF_ADDFD, &addfd), EINVAL);
+	addfd.flags = SECCOMP_ADDFD_FLAG_SETFD;
+	addfd.newfd_flags = 0;
+	TEST_RES(ioctl(listener, SECCOMP_IOCTL_NOTIF_ADDFD, &addfd),
+		 _ret == SETFD_TARGET);
+	TEST_RES(fcntl(SETFD_TARGET, F_GETFD), _ret == 0);
+	TEST_SUCC(send_resp(notif.id, 0, 0, 0));
+
+	TEST_SUCC(pthread_join(thread, NULL));
+	TEST_RES(results[0], _ret == 42);
+	TEST_RES(results[1], _ret == -1 && errnos[1] == EPERM);
+	TEST_RES(results[2], _ret == getppid() && errnos[2] == 0);
+	TEST_RES(results[3], _ret == sent_fd && errnos[3] == 0);
+	TEST_RES(results[4], _ret == 0 && errnos[4] == 0);
+
+	// The filter is released after the target exits.
+	pfd.events = POLLIN;
+	TEST_RES(poll(&pfd, 1, 1000), _ret == 1 && pfd.revents == POLLHUP);
+
+	TEST_SUCC(close(SETFD_TARGET));
+	TEST_SUCC(close(sent_fd));
+	TEST_SUCC(close(fd));
+	TEST_SUCC(close(listener));
+	TEST_SUCC(pthread_barrier_destroy(&barrier));
+}
+END_TEST()
+
+static void *closed_listener_thread(void *arg)
+{
+	CHECK(prctl(PR_SET_NO_NEW_PRIVS, 1, 0, 0, 0));
+	listener = CHECK(install_filter(SECCOMP_FILTER_FLAG_NEW_LISTENER,
+					SYS_getppid, SECCOMP_RET_USER_NOTIF));
+	CHECK(close(listener));
+
+	errno = 0;
+	results[0] = syscall(SYS_getppid);
+	errnos[0] = errno;
+
+	return NULL;
+}
+
+FN_TEST(closed_listener)
+{
+	pthread_t thread;
+
+	TEST_SUCC(pthread_create(&thread, NULL, closed_listener_thread, NULL));
+	TEST_SUCC(pthread_join(thread, NULL));
+	TEST_RES(results[0], _ret == -1 && errnos[0] == ENOSYS);
+}
+END_TEST()